@@ -63,6 +63,9 @@ const libWallet = ffi.Library("./libtari_wallet_ffi.dylib", {
       fn,
       fn,
       fn,
+      fn,
+      fn,
+      fn,
       errPtr,
     ],
   ],
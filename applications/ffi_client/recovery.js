@@ -5,6 +5,7 @@ const ffi = require("ffi-napi");
 const i32 = ref.types.int32;
 const u8 = ref.types.uint8;
 const u64 = ref.types.uint64;
+const i64 = ref.types.int64;
 const bool = ref.types.bool;
 
 try {
@@ -112,6 +113,28 @@ try {
   const safsReceived = ffi.Callback("void", [], function () {
     console.log("safsReceived");
   });
+  // callback_balance_updated: unsafe extern "C" fn(u64, u64, u64, i64, u8),
+  const balanceUpdated = ffi.Callback(
+    "void",
+    [u64, u64, u64, i64, u8],
+    function (available, pendingIncoming, pendingOutgoing, delta, reason) {
+      console.log("balanceUpdated: ", {
+        available,
+        pendingIncoming,
+        pendingOutgoing,
+        delta,
+        reason,
+      });
+    }
+  );
+  // callback_connectivity_status: unsafe extern "C" fn(u8),
+  const connectivityStatus = ffi.Callback("void", [u8], function (status) {
+    console.log("connectivityStatus: ", status);
+  });
+  // callback_sync_progress: unsafe extern "C" fn(u64, u64),
+  const syncProgress = ffi.Callback("void", [u64, u64], function (current, total) {
+    console.log("syncProgress: ", current, total);
+  });
 
   const recovery = ffi.Callback("void", [u64, u64], function (current, total) {
     console.log("recovery scanning UTXOs: ", { current }, { total });
@@ -152,6 +175,9 @@ try {
     itxoValidation,
     txValidation,
     safsReceived,
+    balanceUpdated,
+    connectivityStatus,
+    syncProgress,
     err
   );
 
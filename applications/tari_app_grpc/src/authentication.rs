@@ -0,0 +1,257 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::fmt;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    Interceptor,
+    Request,
+    Status,
+};
+
+/// The metadata key clients use to send their token, in the form `Bearer <token>`
+pub const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// The permission level required to call a gRPC method. A token grants its own level and every level below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GrpcPermission {
+    /// Methods that only read node or wallet state
+    ReadOnly,
+    /// Methods that create transactions or otherwise spend funds
+    Spend,
+    /// Methods that change node state, e.g. rewinding the blockchain
+    Admin,
+}
+
+impl fmt::Display for GrpcPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrpcPermission::ReadOnly => write!(f, "read-only"),
+            GrpcPermission::Spend => write!(f, "spend"),
+            GrpcPermission::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Checks the bearer token of incoming gRPC requests against the configured tokens. If no tokens are configured, every
+/// request is rejected unless unauthenticated access has been explicitly allowed.
+#[derive(Clone, Default)]
+pub struct GrpcAuthenticator {
+    tokens: Vec<(String, GrpcPermission)>,
+    allow_unauthenticated: bool,
+}
+
+impl GrpcAuthenticator {
+    pub fn new(
+        read_only_token: Option<String>,
+        spend_token: Option<String>,
+        admin_token: Option<String>,
+        allow_unauthenticated: bool,
+    ) -> Self {
+        let tokens = vec![
+            (read_only_token, GrpcPermission::ReadOnly),
+            (spend_token, GrpcPermission::Spend),
+            (admin_token, GrpcPermission::Admin),
+        ]
+        .into_iter()
+        .filter_map(|(token, permission)| token.filter(|t| !t.is_empty()).map(|t| (t, permission)))
+        .collect();
+        Self {
+            tokens,
+            allow_unauthenticated,
+        }
+    }
+
+    /// Returns true if at least one token is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    /// Returns an `Unauthenticated` status if the request has no valid token, or `PermissionDenied` if the token does
+    /// not grant the required permission. If no tokens are configured, requests are only allowed if unauthenticated
+    /// access was explicitly allowed.
+    pub fn authorize<T>(&self, request: &Request<T>, required: GrpcPermission) -> Result<(), Status> {
+        if !self.is_enabled() {
+            if self.allow_unauthenticated {
+                return Ok(());
+            }
+            return Err(Status::unauthenticated(
+                "gRPC authentication is not configured. Set the grpc_*_token settings, or set \
+                 grpc_allow_unauthenticated to allow requests without a token.",
+            ));
+        }
+
+        let token = request
+            .metadata()
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+
+        // Every configured token is compared so that the time taken does not depend on which token matched
+        let granted = self
+            .tokens
+            .iter()
+            .filter(|(t, _)| constant_time_eq(t.as_bytes(), token.as_bytes()))
+            .map(|(_, permission)| *permission)
+            .max()
+            .ok_or_else(|| Status::unauthenticated("Invalid bearer token"))?;
+
+        if granted < required {
+            return Err(Status::permission_denied(format!(
+                "This method requires {} permission",
+                required
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns a client interceptor that sends the token, if any, as the bearer token of every request
+pub fn bearer_token_interceptor(token: Option<String>) -> Interceptor {
+    let header = bearer_token_header(token);
+    Interceptor::new(move |request| with_bearer_token(request, &header))
+}
+
+// The authorization header value for the token. The inner value is None if the token is not a valid header value.
+fn bearer_token_header(token: Option<String>) -> Option<Option<MetadataValue<Ascii>>> {
+    token
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("Bearer {}", t).parse().ok())
+}
+
+// Invalid tokens fail the request rather than being dropped, so that the cause is not hidden behind a missing token
+fn with_bearer_token(
+    mut request: Request<()>,
+    header: &Option<Option<MetadataValue<Ascii>>>,
+) -> Result<Request<()>, Status> {
+    match header {
+        Some(Some(value)) => {
+            request.metadata_mut().insert(AUTHORIZATION_HEADER, value.clone());
+        },
+        Some(None) => return Err(Status::invalid_argument("The gRPC token is not a valid header value")),
+        None => {},
+    }
+    Ok(request)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tonic::Code;
+
+    fn request_with_token(token: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(AUTHORIZATION_HEADER, format!("Bearer {}", token).parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn it_fails_closed_by_default() {
+        let auth = GrpcAuthenticator::default();
+        assert!(!auth.is_enabled());
+        let err = auth.authorize(&Request::new(()), GrpcPermission::Admin).unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        let err = auth
+            .authorize(&request_with_token("admin"), GrpcPermission::Admin)
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        let err = auth.authorize(&Request::new(()), GrpcPermission::ReadOnly).unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        // Empty tokens do not count as configured
+        let auth = GrpcAuthenticator::new(None, Some("".to_string()), None, false);
+        let err = auth.authorize(&Request::new(()), GrpcPermission::Admin).unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+
+    #[test]
+    fn it_allows_everything_when_unauthenticated_access_is_allowed() {
+        let auth = GrpcAuthenticator::new(None, None, None, true);
+        auth.authorize(&Request::new(()), GrpcPermission::Admin).unwrap();
+
+        // Configured tokens take precedence
+        let auth = GrpcAuthenticator::new(Some("read".to_string()), None, None, true);
+        let err = auth.authorize(&Request::new(()), GrpcPermission::ReadOnly).unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+
+    #[test]
+    fn it_sets_the_bearer_token_on_client_requests() {
+        let auth = GrpcAuthenticator::new(None, Some("spend".to_string()), None, false);
+        let header = bearer_token_header(Some("spend".to_string()));
+        let request = with_bearer_token(Request::new(()), &header).unwrap();
+        auth.authorize(&request, GrpcPermission::Spend).unwrap();
+
+        let request = with_bearer_token(Request::new(()), &bearer_token_header(None)).unwrap();
+        assert!(request.metadata().get(AUTHORIZATION_HEADER).is_none());
+
+        let header = bearer_token_header(Some("not\nvalid".to_string()));
+        let err = with_bearer_token(Request::new(()), &header).unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn it_checks_permission_levels() {
+        let auth = GrpcAuthenticator::new(
+            Some("read".to_string()),
+            Some("spend".to_string()),
+            Some("admin".to_string()),
+            false,
+        );
+
+        let err = auth.authorize(&Request::new(()), GrpcPermission::ReadOnly).unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        let err = auth
+            .authorize(&request_with_token("wrong"), GrpcPermission::ReadOnly)
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        auth.authorize(&request_with_token("read"), GrpcPermission::ReadOnly)
+            .unwrap();
+        let err = auth
+            .authorize(&request_with_token("read"), GrpcPermission::Spend)
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        auth.authorize(&request_with_token("spend"), GrpcPermission::Spend)
+            .unwrap();
+        let err = auth
+            .authorize(&request_with_token("spend"), GrpcPermission::Admin)
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        auth.authorize(&request_with_token("admin"), GrpcPermission::ReadOnly)
+            .unwrap();
+        auth.authorize(&request_with_token("admin"), GrpcPermission::Admin)
+            .unwrap();
+    }
+}
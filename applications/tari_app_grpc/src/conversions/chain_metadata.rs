@@ -31,6 +31,8 @@ impl From<ChainMetadata> for grpc::MetaData {
             best_block: meta.best_block().clone(),
             pruned_height: meta.pruned_height(),
             accumulated_difficulty: diff.to_be_bytes().to_vec(),
+            accumulated_monero_difficulty: meta.accumulated_monero_difficulty(),
+            accumulated_sha_difficulty: meta.accumulated_sha_difficulty(),
         }
     }
 }
@@ -22,7 +22,12 @@
 
 use crate::tari_rpc as grpc;
 use std::convert::TryFrom;
-use tari_core::transactions::transaction::{OutputFeatures, OutputFlags};
+use tari_core::transactions::{
+    encrypted_data::EncryptedData,
+    transaction::{OutputFeatures, OutputFlags},
+    types::PublicKey,
+};
+use tari_crypto::tari_utilities::ByteArray;
 
 impl TryFrom<grpc::OutputFeatures> for OutputFeatures {
     type Error = String;
@@ -32,6 +37,28 @@ impl TryFrom<grpc::OutputFeatures> for OutputFeatures {
             flags: OutputFlags::from_bits(features.flags as u8)
                 .ok_or_else(|| "Invalid or unrecognised output flags".to_string())?,
             maturity: features.maturity,
+            encrypted_data: EncryptedData::from_bytes(features.encrypted_data).map_err(|err| err.to_string())?,
+            parent_public_key: Some(features.parent_public_key)
+                .filter(|key| !key.is_empty())
+                .map(|key| PublicKey::from_bytes(&key))
+                .transpose()
+                .map_err(|err| format!("Invalid parent public key: {}", err))?,
+            unique_id: Some(features.unique_id).filter(|id| !id.is_empty()),
         })
     }
 }
+
+impl From<OutputFeatures> for grpc::OutputFeatures {
+    fn from(features: OutputFeatures) -> Self {
+        Self {
+            flags: features.flags.bits() as u32,
+            maturity: features.maturity,
+            encrypted_data: features.encrypted_data.as_bytes().to_vec(),
+            parent_public_key: features
+                .parent_public_key
+                .map(|key| key.as_bytes().to_vec())
+                .unwrap_or_default(),
+            unique_id: features.unique_id.unwrap_or_default(),
+        }
+    }
+}
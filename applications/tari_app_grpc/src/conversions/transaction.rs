@@ -75,6 +75,7 @@ impl From<models::TransactionDirection> for grpc::TransactionDirection {
             Unknown => grpc::TransactionDirection::Unknown,
             Inbound => grpc::TransactionDirection::Inbound,
             Outbound => grpc::TransactionDirection::Outbound,
+            Internal => grpc::TransactionDirection::Internal,
         }
     }
 }
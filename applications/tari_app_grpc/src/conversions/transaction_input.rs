@@ -23,6 +23,7 @@
 use crate::tari_rpc as grpc;
 use std::convert::{TryFrom, TryInto};
 use tari_core::transactions::{
+    covenant::Covenant,
     transaction::TransactionInput,
     types::{Commitment, PublicKey},
 };
@@ -53,6 +54,7 @@ impl TryFrom<grpc::TransactionInput> for TransactionInput {
             PublicKey::from_bytes(input.sender_offset_public_key.as_bytes()).map_err(|err| format!("{:?}", err))?;
         let script = TariScript::from_bytes(input.script.as_slice()).map_err(|err| format!("{:?}", err))?;
         let input_data = ExecutionStack::from_bytes(input.input_data.as_slice()).map_err(|err| format!("{:?}", err))?;
+        let covenant = Covenant::from_bytes(&input.covenant).map_err(|err| format!("Invalid covenant: {}", err))?;
 
         Ok(Self {
             features,
@@ -61,6 +63,7 @@ impl TryFrom<grpc::TransactionInput> for TransactionInput {
             input_data,
             script_signature,
             sender_offset_public_key,
+            covenant,
         })
     }
 }
@@ -69,10 +72,7 @@ impl From<TransactionInput> for grpc::TransactionInput {
     fn from(input: TransactionInput) -> Self {
         let hash = input.hash();
         Self {
-            features: Some(grpc::OutputFeatures {
-                flags: input.features.flags.bits() as u32,
-                maturity: input.features.maturity,
-            }),
+            features: Some(input.features.clone().into()),
             commitment: Vec::from(input.commitment.as_bytes()),
             hash,
             script: input.script.as_bytes(),
@@ -83,6 +83,7 @@ impl From<TransactionInput> for grpc::TransactionInput {
                 signature_v: Vec::from(input.script_signature.v().as_bytes()),
             }),
             sender_offset_public_key: input.sender_offset_public_key.as_bytes().to_vec(),
+            covenant: input.covenant.as_bytes(),
         }
     }
 }
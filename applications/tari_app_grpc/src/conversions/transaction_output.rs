@@ -24,6 +24,7 @@ use crate::tari_rpc as grpc;
 use std::convert::{TryFrom, TryInto};
 use tari_core::transactions::{
     bullet_rangeproofs::BulletRangeProof,
+    covenant::Covenant,
     transaction::TransactionOutput,
     types::{Commitment, PublicKey},
 };
@@ -55,6 +56,8 @@ impl TryFrom<grpc::TransactionOutput> for TransactionOutput {
             .try_into()
             .map_err(|_| "Metadata signature could not be converted".to_string())?;
 
+        let covenant = Covenant::from_bytes(&output.covenant).map_err(|err| format!("Invalid covenant: {}", err))?;
+
         Ok(Self {
             features,
             commitment,
@@ -62,6 +65,7 @@ impl TryFrom<grpc::TransactionOutput> for TransactionOutput {
             script,
             sender_offset_public_key,
             metadata_signature,
+            covenant,
         })
     }
 }
@@ -71,10 +75,7 @@ impl From<TransactionOutput> for grpc::TransactionOutput {
         let hash = output.hash();
         grpc::TransactionOutput {
             hash,
-            features: Some(grpc::OutputFeatures {
-                flags: output.features.flags.bits() as u32,
-                maturity: output.features.maturity,
-            }),
+            features: Some(output.features.clone().into()),
             commitment: Vec::from(output.commitment.as_bytes()),
             range_proof: Vec::from(output.proof.as_bytes()),
             script: output.script.as_bytes(),
@@ -84,6 +85,7 @@ impl From<TransactionOutput> for grpc::TransactionOutput {
                 signature_u: Vec::from(output.metadata_signature.u().as_bytes()),
                 signature_v: Vec::from(output.metadata_signature.v().as_bytes()),
             }),
+            covenant: output.covenant.as_bytes(),
         }
     }
 }
@@ -23,6 +23,7 @@
 use crate::tari_rpc as grpc;
 use std::convert::{TryFrom, TryInto};
 use tari_core::transactions::{
+    covenant::Covenant,
     tari_amount::MicroTari,
     transaction::UnblindedOutput,
     types::{PrivateKey, PublicKey},
@@ -37,10 +38,7 @@ impl From<UnblindedOutput> for grpc::UnblindedOutput {
         grpc::UnblindedOutput {
             value: u64::from(output.value),
             spending_key: output.spending_key.as_bytes().to_vec(),
-            features: Some(grpc::OutputFeatures {
-                flags: output.features.flags.bits() as u32,
-                maturity: output.features.maturity,
-            }),
+            features: Some(output.features.clone().into()),
             script: output.script.as_bytes(),
             input_data: output.input_data.as_bytes(),
             script_private_key: output.script_private_key.as_bytes().to_vec(),
@@ -50,6 +48,7 @@ impl From<UnblindedOutput> for grpc::UnblindedOutput {
                 signature_u: Vec::from(output.metadata_signature.u().as_bytes()),
                 signature_v: Vec::from(output.metadata_signature.v().as_bytes()),
             }),
+            covenant: output.covenant.as_bytes(),
         }
     }
 }
@@ -83,6 +82,8 @@ impl TryFrom<grpc::UnblindedOutput> for UnblindedOutput {
             .try_into()
             .map_err(|_| "Metadata signature could not be converted".to_string())?;
 
+        let covenant = Covenant::from_bytes(&output.covenant).map_err(|err| format!("covenant: {}", err))?;
+
         Ok(Self {
             value: MicroTari::from(output.value),
             spending_key,
@@ -92,6 +93,7 @@ impl TryFrom<grpc::UnblindedOutput> for UnblindedOutput {
             script_private_key,
             sender_offset_public_key,
             metadata_signature,
+            covenant,
         })
     }
 }
@@ -26,6 +26,7 @@
 #![deny(unused_must_use)]
 #![deny(unreachable_patterns)]
 #![deny(unknown_lints)]
+pub mod authentication;
 pub mod conversions;
 
 pub mod tari_rpc {
@@ -0,0 +1,243 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use log::*;
+use std::fmt;
+use tari_common::{GlobalConfig, LoggingError};
+
+const LOG_TARGET: &str = "tari::application::config_reload";
+
+/// The outcome of re-reading the configuration of a running application
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReloadReport {
+    /// Settings that were changed and are now in effect
+    pub applied: Vec<String>,
+    /// Settings that were changed but only take effect once the application is restarted
+    pub restart_required: Vec<String>,
+    /// Settings that could not be applied, with the reason
+    pub failed: Vec<String>,
+}
+
+impl ConfigReloadReport {
+    /// Sorts the changed settings into those that can be applied at runtime and those that need a restart
+    pub fn from_changes(changed: Vec<&'static str>, changeable: &[&str]) -> Self {
+        let (applied, restart_required): (Vec<_>, Vec<_>) = changed.into_iter().partition(|s| changeable.contains(s));
+        Self {
+            applied: applied.into_iter().map(String::from).collect(),
+            restart_required: restart_required.into_iter().map(String::from).collect(),
+            failed: Vec::new(),
+        }
+    }
+
+    /// Re-reads the logging configuration file, which is always reloaded along with the main configuration file
+    pub fn reload_logging(&mut self) {
+        match tari_common::reload_logging_config() {
+            Ok(_) => self.applied.push("log levels".to_string()),
+            Err(LoggingError::NotInitialized) => self.restart_required.push("log levels".to_string()),
+            Err(err) => self.failed.push(format!("log levels: {}", err)),
+        }
+    }
+
+    /// Logs the outcome of the reload
+    pub fn log(&self) {
+        info!(target: LOG_TARGET, "Configuration reloaded. {}", self);
+        if !self.restart_required.is_empty() {
+            warn!(
+                target: LOG_TARGET,
+                "Changed settings that require a restart: {}",
+                self.restart_required.join(", ")
+            );
+        }
+        for failure in &self.failed {
+            error!(target: LOG_TARGET, "Could not apply setting {}", failure);
+        }
+    }
+}
+
+impl fmt::Display for ConfigReloadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |settings: &[String]| {
+            if settings.is_empty() {
+                "none".to_string()
+            } else {
+                settings.join(", ")
+            }
+        };
+        write!(
+            f,
+            "Applied: {}. Requires restart: {}. Failed: {}.",
+            list(&self.applied),
+            list(&self.restart_required),
+            list(&self.failed)
+        )
+    }
+}
+
+macro_rules! changed_fields {
+    ($current:expr, $new:expr, [$($field:ident),* $(,)?]) => {{
+        let GlobalConfig { $($field: _,)* } = $current;
+        let mut changed = Vec::new();
+        $(
+            if format!("{:?}", $current.$field) != format!("{:?}", $new.$field) {
+                changed.push(stringify!($field));
+            }
+        )*
+        changed
+    }};
+}
+
+/// Returns the names of the settings that differ between the running configuration and a re-read one
+pub fn changed_settings(current: &GlobalConfig, new: &GlobalConfig) -> Vec<&'static str> {
+    // Every field is listed so that a new setting cannot be left out of reload reports
+    changed_fields!(current, new, [
+        autoupdate_check_interval,
+        autoupdate_dns_hosts,
+        autoupdate_hashes_url,
+        autoupdate_hashes_sig_url,
+        network,
+        comms_transport,
+        auxilary_tcp_listener_address,
+        allow_test_addresses,
+        listnener_liveness_max_sessions,
+        listener_liveness_allowlist_cidrs,
+        rpc_max_simultaneous_sessions,
+        rpc_max_in_flight_messages,
+        rpc_slow_consumer_timeout,
+        rpc_max_concurrent_requests,
+        rpc_max_concurrent_requests_per_peer,
+        data_dir,
+        db_type,
+        db_config,
+        orphan_storage_capacity,
+        orphan_db_clean_out_threshold,
+        pruning_horizon,
+        pruned_mode_cleanup_interval,
+        db_group_commit_max_operations,
+        db_group_commit_interval,
+        mempool_relay_max_script_size,
+        mempool_relay_min_fee_per_gram,
+        mempool_relay_max_outputs,
+        mempool_relay_min_fee_per_output,
+        mempool_max_txs_per_unique_id,
+        mempool_max_weight_per_sender,
+        watchdog_stale_tip_warning,
+        watchdog_stale_tip_critical,
+        watchdog_max_tip_divergence,
+        watchdog_webhook_url,
+        core_threads,
+        max_threads,
+        base_node_identity_file,
+        public_address,
+        grpc_enabled,
+        grpc_base_node_address,
+        grpc_console_wallet_address,
+        grpc_tls_cert_file,
+        grpc_tls_key_file,
+        grpc_read_only_token,
+        grpc_spend_token,
+        grpc_admin_token,
+        grpc_allow_unauthenticated,
+        metrics_server_address,
+        http_api_address,
+        peer_seeds,
+        dns_seeds,
+        dns_seeds_name_server,
+        dns_seeds_use_dnssec,
+        min_peer_minor_version,
+        peer_db_path,
+        enable_wallet,
+        num_mining_threads,
+        base_node_tor_identity_file,
+        wallet_db_file,
+        console_wallet_db_file,
+        console_wallet_identity_file,
+        console_wallet_tor_identity_file,
+        wallet_peer_db_path,
+        console_wallet_peer_db_path,
+        buffer_size_base_node,
+        buffer_size_base_node_wallet,
+        buffer_rate_limit_base_node,
+        buffer_rate_limit_base_node_wallet,
+        dedup_cache_capacity,
+        dedup_bloom_retention,
+        fetch_blocks_timeout,
+        fetch_utxos_timeout,
+        service_request_timeout,
+        base_node_query_timeout,
+        scan_for_utxo_interval,
+        saf_expiry_duration,
+        transaction_broadcast_monitoring_timeout,
+        transaction_chain_monitoring_timeout,
+        transaction_direct_send_timeout,
+        transaction_broadcast_send_timeout,
+        transaction_routing_mechanism,
+        transaction_num_confirmations_required,
+        console_wallet_password,
+        wallet_command_send_wait_stage,
+        wallet_command_send_wait_timeout,
+        wallet_base_node_service_peers,
+        wallet_base_node_service_refresh_interval,
+        wallet_base_node_service_request_max_age,
+        prevent_fee_gt_amount,
+        wallet_verify_utxo_proofs,
+        wallet_max_unconfirmed_change_depth,
+        wallet_one_sided_reclaim_lock_blocks,
+        monerod_url,
+        monerod_username,
+        monerod_password,
+        monerod_use_auth,
+        proxy_host_address,
+        transcoder_host_address,
+        proxy_submit_to_origin,
+        force_sync_peers,
+        wait_for_initial_sync_at_startup,
+        max_randomx_vms,
+        console_wallet_notify_file,
+        auto_ping_interval,
+        blocks_behind_before_considered_lagging,
+        flood_ban_max_msg_count,
+        mine_on_tip_only,
+        validate_tip_timeout_sec,
+        mining_pool_address,
+        mining_wallet_address,
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_separates_changeable_settings_from_those_that_require_a_restart() {
+        let report = ConfigReloadReport::from_changes(vec!["mempool_relay_max_outputs", "data_dir"], &[
+            "mempool_relay_max_outputs",
+            "mempool_relay_min_fee_per_gram",
+        ]);
+        assert_eq!(report.applied, vec!["mempool_relay_max_outputs".to_string()]);
+        assert_eq!(report.restart_required, vec!["data_dir".to_string()]);
+        assert!(report.failed.is_empty());
+        assert_eq!(
+            report.to_string(),
+            "Applied: mempool_relay_max_outputs. Requires restart: data_dir. Failed: none."
+        );
+    }
+}
@@ -23,21 +23,50 @@
 use crate::utilities::ExitCodes;
 use log::*;
 use rand::rngs::OsRng;
+use serde_json::Value;
 use std::{clone::Clone, fs, path::Path, string::ToString, sync::Arc};
-use tari_comms::{multiaddr::Multiaddr, peer_manager::PeerFeatures, NodeIdentity};
+use tari_comms::{
+    multiaddr::Multiaddr,
+    peer_manager::{Peer, PeerFeatures},
+    transports::DialProxy,
+    NodeIdentity,
+    PeerManager,
+};
 use tari_core::transactions::types::PrivateKey;
 use tari_crypto::{
     keys::SecretKey,
-    tari_utilities::{hex::Hex, message_format::MessageFormat},
+    tari_utilities::{
+        hex::{from_hex, Hex},
+        message_format::MessageFormat,
+    },
+};
+use tari_p2p::initialization::open_peer_database;
+use tari_wallet::util::encryption::{
+    cipher_from_passphrase,
+    decrypt_bytes_integral_nonce,
+    encrypt_bytes_integral_nonce,
+    generate_passphrase_salt,
 };
 pub const LOG_TARGET: &str = "tari_application";
 
-/// Loads the node identity, or creates a new one if the --create-id flag was specified
+/// The current version of the node identity file format. Identity files without a version field are version 0.
+pub const IDENTITY_FILE_VERSION: u64 = 1;
+/// The current version of the node export file format
+pub const NODE_EXPORT_VERSION: u64 = 2;
+/// The environment variable that the node identity passphrase is read from before prompting for it
+pub const TARI_IDENTITY_PASSPHRASE: &str = "TARI_IDENTITY_PASSPHRASE";
+/// The key derivation function used for encrypted identity files
+const IDENTITY_KDF: &str = "argon2id";
+
+/// Loads the node identity, or creates a new one if the identity file does not exist and the --create-id flag was
+/// specified. An existing identity file that cannot be loaded, e.g. because the passphrase is incorrect, is never
+/// replaced.
 /// ## Parameters
 /// `identity_file` - Reference to file path
 /// `public_address` - Network address of the base node
 /// `create_id` - Whether an identity needs to be created or not
 /// `peer_features` - Enables features of the base node
+/// `passphrase` - Passphrase used to decrypt the identity file, or to encrypt a newly created identity
 ///
 /// # Return
 /// A NodeIdentity wrapped in an atomic reference counter on success, the exit code indicating the reason on failure
@@ -46,10 +75,22 @@ pub fn setup_node_identity<P: AsRef<Path>>(
     public_address: &Multiaddr,
     create_id: bool,
     peer_features: PeerFeatures,
+    passphrase: Option<&str>,
 ) -> Result<Arc<NodeIdentity>, ExitCodes> {
-    match load_identity(&identity_file) {
+    if identity_file.as_ref().exists() && is_identity_file_encrypted(&identity_file) && passphrase.is_none() {
+        return Err(ExitCodes::NoPassword);
+    }
+
+    match load_identity(&identity_file, passphrase) {
         Ok(id) => Ok(Arc::new(id)),
         Err(e) => {
+            if identity_file.as_ref().exists() {
+                error!(target: LOG_TARGET, "Node identity file could not be loaded. {}", e);
+                return Err(ExitCodes::ConfigError(format!(
+                    "Node identity file could not be loaded. {}",
+                    e
+                )));
+            }
             if !create_id {
                 error!(
                     target: LOG_TARGET,
@@ -66,7 +107,7 @@ pub fn setup_node_identity<P: AsRef<Path>>(
 
             debug!(target: LOG_TARGET, "Node id not found. {}. Creating new ID", e);
 
-            match create_new_identity(&identity_file, public_address.clone(), peer_features) {
+            match create_new_identity(&identity_file, public_address.clone(), peer_features, passphrase) {
                 Ok(id) => {
                     info!(
                         target: LOG_TARGET,
@@ -93,10 +134,11 @@ pub fn setup_node_identity<P: AsRef<Path>>(
 /// missing fields from that information.
 /// ## Parameters
 /// `path` - Reference to a path
+/// `passphrase` - Passphrase used to decrypt the identity file. Ignored if the file is not encrypted.
 ///
 /// ## Returns
 /// Result containing a NodeIdentity on success, string indicates the reason on failure
-pub fn load_identity<P: AsRef<Path>>(path: P) -> Result<NodeIdentity, String> {
+pub fn load_identity<P: AsRef<Path>>(path: P, passphrase: Option<&str>) -> Result<NodeIdentity, String> {
     if !path.as_ref().exists() {
         return Err(format!(
             "Identity file, {}, does not exist.",
@@ -111,6 +153,8 @@ pub fn load_identity<P: AsRef<Path>>(path: P) -> Result<NodeIdentity, String> {
             e.to_string()
         )
     })?;
+    let (id_str, is_encrypted) = decrypt_identity_json(path.as_ref(), id_str, passphrase)?;
+    let id_str = upgrade_identity_file(path.as_ref(), id_str, passphrase.filter(|_| is_encrypted))?;
     let id = NodeIdentity::from_json(&id_str).map_err(|e| {
         format!(
             "The node identity file, {}, has an error. {}",
@@ -130,6 +174,7 @@ pub fn load_identity<P: AsRef<Path>>(path: P) -> Result<NodeIdentity, String> {
 /// `path` - Reference to path to save the file
 /// `public_addr` - Network address of the base node
 /// `peer_features` - The features enabled for the base node
+/// `passphrase` - If provided, the identity file is encrypted with this passphrase
 ///
 /// ## Returns
 /// Result containing the node identity, string will indicate reason on error
@@ -137,10 +182,11 @@ pub fn create_new_identity<P: AsRef<Path>>(
     path: P,
     public_addr: Multiaddr,
     features: PeerFeatures,
+    passphrase: Option<&str>,
 ) -> Result<NodeIdentity, String> {
     let private_key = PrivateKey::random(&mut OsRng);
     let node_identity = NodeIdentity::new(private_key, public_addr, features);
-    save_as_json(path, &node_identity)?;
+    save_identity(path, &node_identity, passphrase)?;
     Ok(node_identity)
 }
 
@@ -150,6 +196,7 @@ pub fn create_new_identity<P: AsRef<Path>>(
 /// `path` - Reference to path to save the file
 /// `public_addr` - Network address of the base node
 /// `peer_features` - The features enabled for the base node
+/// `passphrase` - If provided, the identity file is encrypted with this passphrase
 ///
 /// ## Returns
 /// A NodeIdentity wrapped in an atomic reference counter on success, the exit code indicating the reason on failure
@@ -158,12 +205,443 @@ pub fn recover_node_identity<P: AsRef<Path>>(
     path: P,
     public_addr: &Multiaddr,
     features: PeerFeatures,
+    passphrase: Option<&str>,
 ) -> Result<Arc<NodeIdentity>, ExitCodes> {
     let node_identity = NodeIdentity::new(private_key, public_addr.clone(), features);
-    save_as_json(path, &node_identity).map_err(ExitCodes::IOError)?;
+    save_identity(path, &node_identity, passphrase).map_err(ExitCodes::IOError)?;
     Ok(Arc::new(node_identity))
 }
 
+/// Upgrades the contents of an identity file to the current `IDENTITY_FILE_VERSION`. If an upgrade was required, the
+/// original file is backed up with a `.v<version>.bak` extension and the upgraded identity is written to `path`.
+/// ## Parameters
+/// `path` - Path of the identity file
+/// `id_str` - The (decrypted) identity json
+/// `passphrase` - If provided, the upgraded identity is encrypted with this passphrase
+///
+/// ## Returns
+/// Result containing the (possibly upgraded) identity json, string will indicate reason on error
+fn upgrade_identity_file(path: &Path, id_str: String, passphrase: Option<&str>) -> Result<String, String> {
+    let value = serde_json::from_str::<Value>(&id_str)
+        .map_err(|e| format!("The node identity file, {}, has an error. {}", path.display(), e))?;
+    let (value, from_version) = migrate_identity_json(value)?;
+    if from_version == IDENTITY_FILE_VERSION {
+        return Ok(id_str);
+    }
+
+    let backup_path = path.with_extension(format!("v{}.bak", from_version));
+    fs::copy(path, &backup_path).map_err(|e| {
+        format!(
+            "Could not back up the node identity file to {}. {}",
+            backup_path.display(),
+            e
+        )
+    })?;
+    let id_str = value.to_string();
+    let contents = match passphrase {
+        Some(passphrase) => encrypt_identity_json(&id_str, passphrase)?,
+        None => id_str.clone(),
+    };
+    fs::write(path, contents.as_bytes())
+        .map_err(|e| format!("Could not write upgraded node identity file {}. {}", path.display(), e))?;
+    info!(
+        target: LOG_TARGET,
+        "Node identity file upgraded from version {} to {}. A backup was saved to {}.",
+        from_version,
+        IDENTITY_FILE_VERSION,
+        backup_path.display()
+    );
+    Ok(id_str)
+}
+
+/// Applies identity file migrations to the given json value in order until it is at `IDENTITY_FILE_VERSION`.
+///
+/// ## Returns
+/// The migrated json value and the version it was migrated from
+fn migrate_identity_json(mut value: Value) -> Result<(Value, u64), String> {
+    let obj = value
+        .as_object_mut()
+        .ok_or_else(|| "The node identity file does not contain a json object".to_string())?;
+    let from_version = obj.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if from_version > IDENTITY_FILE_VERSION {
+        return Err(format!(
+            "The node identity file version ({}) is newer than the version supported by this application ({}).",
+            from_version, IDENTITY_FILE_VERSION
+        ));
+    }
+
+    let mut version = from_version;
+    while version < IDENTITY_FILE_VERSION {
+        match version {
+            0 => {
+                // v1: `control_service_address` was renamed to `public_address`
+                if let Some(address) = obj.remove("control_service_address") {
+                    obj.entry("public_address").or_insert(address);
+                }
+            },
+            _ => unreachable!("identity file version {} is not handled", version),
+        }
+        version += 1;
+        obj.insert("version".to_string(), version.into());
+    }
+
+    Ok((value, from_version))
+}
+
+/// Encrypts the identity json with a key derived from the passphrase using a new random salt. The returned json
+/// contains the salt and the ciphertext, which includes the nonce.
+fn encrypt_identity_json(id_str: &str, passphrase: &str) -> Result<String, String> {
+    let salt = generate_passphrase_salt();
+    let cipher =
+        cipher_from_passphrase(passphrase, &salt).map_err(|e| format!("Could not derive encryption key. {}", e))?;
+    let ciphertext = encrypt_bytes_integral_nonce(&cipher, id_str.as_bytes().to_vec())
+        .map_err(|e| format!("Could not encrypt the node identity. {}", e))?;
+    let value = serde_json::json!({
+        "version": IDENTITY_FILE_VERSION,
+        "encrypted": {
+            "kdf": IDENTITY_KDF,
+            "salt": salt.to_hex(),
+            "ciphertext": ciphertext.to_hex(),
+        },
+    });
+    Ok(value.to_string())
+}
+
+/// Decrypts the contents of an identity file if it is encrypted.
+///
+/// ## Returns
+/// The identity json and whether the file was encrypted, string will indicate reason on error
+fn decrypt_identity_json(path: &Path, contents: String, passphrase: Option<&str>) -> Result<(String, bool), String> {
+    let value = serde_json::from_str::<Value>(&contents)
+        .map_err(|e| format!("The node identity file, {}, has an error. {}", path.display(), e))?;
+    let encrypted = match value.get("encrypted") {
+        Some(encrypted) => encrypted,
+        None => return Ok((contents, false)),
+    };
+
+    let passphrase = passphrase.ok_or_else(|| {
+        format!(
+            "The node identity file, {}, is encrypted but no passphrase was provided.",
+            path.display()
+        )
+    })?;
+    let kdf = encrypted.get("kdf").and_then(Value::as_str).unwrap_or_default();
+    if kdf != IDENTITY_KDF {
+        return Err(format!(
+            "The node identity file, {}, uses an unsupported key derivation function '{}'.",
+            path.display(),
+            kdf
+        ));
+    }
+    let field = |name: &str| {
+        encrypted
+            .get(name)
+            .and_then(Value::as_str)
+            .and_then(|v| from_hex(v).ok())
+            .ok_or_else(|| {
+                format!(
+                    "The node identity file, {}, has an invalid '{}' field.",
+                    path.display(),
+                    name
+                )
+            })
+    };
+    let salt = field("salt")?;
+    let ciphertext = field("ciphertext")?;
+
+    let cipher =
+        cipher_from_passphrase(passphrase, &salt).map_err(|e| format!("Could not derive encryption key. {}", e))?;
+    let plaintext = decrypt_bytes_integral_nonce(&cipher, ciphertext).map_err(|_| {
+        format!(
+            "Could not decrypt the node identity file, {}. The passphrase is incorrect.",
+            path.display()
+        )
+    })?;
+    let id_str = String::from_utf8(plaintext)
+        .map_err(|e| format!("The node identity file, {}, has an error. {}", path.display(), e))?;
+    Ok((id_str, true))
+}
+
+/// Returns true if the identity file at the given path exists and is encrypted
+pub fn is_identity_file_encrypted<P: AsRef<Path>>(path: P) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+        .map(|value| value.get("encrypted").is_some())
+        .unwrap_or(false)
+}
+
+/// Changes the passphrase of the identity file at the given path. The identity is decrypted with `current_passphrase`
+/// (if it is encrypted) and saved encrypted with `new_passphrase`, or unencrypted if `new_passphrase` is `None`.
+/// ## Parameters
+/// `path` - Path of the identity file
+/// `current_passphrase` - The passphrase the file is currently encrypted with
+/// `new_passphrase` - The passphrase to encrypt the file with
+///
+/// ## Returns
+/// Result to check if successful or not, string will indicate reason on error
+pub fn change_identity_passphrase<P: AsRef<Path>>(
+    path: P,
+    current_passphrase: Option<&str>,
+    new_passphrase: Option<&str>,
+) -> Result<(), String> {
+    let node_identity = load_identity(path.as_ref(), current_passphrase)?;
+    save_identity(path.as_ref(), &node_identity, new_passphrase)?;
+    info!(
+        target: LOG_TARGET,
+        "Node identity file {} saved {}",
+        path.as_ref().display(),
+        if new_passphrase.is_some() {
+            "with the new passphrase"
+        } else {
+            "unencrypted"
+        }
+    );
+    Ok(())
+}
+
+/// Returns the passphrase for the identity file, read from the `TARI_IDENTITY_PASSPHRASE` environment variable. If
+/// the variable is not set and the identity file is encrypted, the user is prompted for it unless
+/// `non_interactive` is true.
+pub fn get_identity_passphrase<P: AsRef<Path>>(
+    identity_file: P,
+    non_interactive: bool,
+) -> Result<Option<String>, ExitCodes> {
+    if let Some(passphrase) = std::env::var_os(TARI_IDENTITY_PASSPHRASE) {
+        let passphrase = passphrase
+            .into_string()
+            .map_err(|_| ExitCodes::IOError("Failed to convert OsString into String".to_string()))?;
+        return Ok(Some(passphrase));
+    }
+
+    if !is_identity_file_encrypted(&identity_file) {
+        return Ok(None);
+    }
+    if non_interactive {
+        return Err(ExitCodes::NoPassword);
+    }
+    let passphrase = rpassword::prompt_password_stdout("Node identity passphrase: ")
+        .map_err(|e| ExitCodes::IOError(e.to_string()))?;
+    Ok(Some(passphrase))
+}
+
+/// Saves the node identity as versioned json at the given path, creating it if it does not already exist
+/// ## Parameters
+/// `path` - Path to save the file
+/// `node_identity` - The node identity to save
+/// `passphrase` - If provided, the identity is encrypted with a key derived from this passphrase
+///
+/// ## Returns
+/// Result to check if successful or not, string will indicate reason on error
+pub fn save_identity<P: AsRef<Path>>(
+    path: P,
+    node_identity: &NodeIdentity,
+    passphrase: Option<&str>,
+) -> Result<(), String> {
+    let mut value = serde_json::to_value(node_identity).map_err(|e| e.to_string())?;
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("version".to_string(), IDENTITY_FILE_VERSION.into());
+    }
+    let contents = match passphrase {
+        Some(passphrase) => encrypt_identity_json(&value.to_string(), passphrase)?,
+        None => value.to_string(),
+    };
+    if let Some(p) = path.as_ref().parent() {
+        if !p.exists() {
+            fs::create_dir_all(p).map_err(|e| format!("Could not save json to data folder. {}", e.to_string()))?;
+        }
+    }
+    fs::write(path.as_ref(), contents.as_bytes()).map_err(|e| {
+        format!(
+            "Error writing json file, {}. {}",
+            path.as_ref().to_str().unwrap_or("<invalid UTF-8>"),
+            e.to_string()
+        )
+    })?;
+
+    Ok(())
+}
+
+/// Exports the node identity and the peer database (including ban state and connection statistics) to a single json
+/// file so that a node can be moved to another machine.
+/// ## Parameters
+/// `identity_file` - Path of the node identity file
+/// `peer_db_path` - Path of the LMDB peer database
+/// `peer_db_name` - Name of the peer database
+/// `export_file` - Path to write the export to
+/// `passphrase` - Passphrase of the identity file. The exported identity is not encrypted.
+///
+/// ## Returns
+/// The number of peers exported on success, the exit code indicating the reason on failure
+pub async fn export_node_identity_and_peers<P: AsRef<Path>>(
+    identity_file: P,
+    peer_db_path: P,
+    peer_db_name: &str,
+    export_file: P,
+    passphrase: Option<&str>,
+) -> Result<usize, ExitCodes> {
+    let node_identity = load_identity(identity_file, passphrase).map_err(ExitCodes::ConfigError)?;
+    let peer_manager = open_peer_manager(peer_db_path.as_ref(), peer_db_name)?;
+    let peers = peer_manager
+        .all()
+        .await
+        .map_err(|e| ExitCodes::IOError(format!("Could not read the peer database. {}", e)))?;
+    let num_peers = peers.len();
+
+    let export = serde_json::json!({
+        "version": NODE_EXPORT_VERSION,
+        "identity": node_identity,
+        "peers": peers,
+    });
+    fs::write(export_file.as_ref(), export.to_string().as_bytes()).map_err(|e| {
+        ExitCodes::IOError(format!(
+            "Error writing export file, {}. {}",
+            export_file.as_ref().display(),
+            e
+        ))
+    })?;
+
+    info!(
+        target: LOG_TARGET,
+        "Exported node identity {} and {} peer(s) to {}",
+        node_identity.node_id(),
+        num_peers,
+        export_file.as_ref().display()
+    );
+    Ok(num_peers)
+}
+
+/// Imports a node identity and peers from a file created by `export_node_identity_and_peers`. The identity file is
+/// only written if it does not exist or already contains the same identity. Imported peers replace any existing peers
+/// with the same public key.
+/// ## Parameters
+/// `import_file` - Path of the export file
+/// `identity_file` - Path of the node identity file
+/// `peer_db_path` - Path of the LMDB peer database
+/// `peer_db_name` - Name of the peer database
+/// `passphrase` - Passphrase used to decrypt an existing identity file, or to encrypt the imported identity
+///
+/// ## Returns
+/// The number of peers imported on success, the exit code indicating the reason on failure
+pub async fn import_node_identity_and_peers<P: AsRef<Path>>(
+    import_file: P,
+    identity_file: P,
+    peer_db_path: P,
+    peer_db_name: &str,
+    passphrase: Option<&str>,
+) -> Result<usize, ExitCodes> {
+    let contents = fs::read_to_string(import_file.as_ref()).map_err(|e| {
+        ExitCodes::IOError(format!(
+            "Could not read import file, {}. {}",
+            import_file.as_ref().display(),
+            e
+        ))
+    })?;
+    let export = serde_json::from_str::<Value>(&contents)
+        .map_err(|e| ExitCodes::ConversionError(format!("Invalid import file. {}", e)))?;
+    let (mut export, from_version) = migrate_export_json(export).map_err(ExitCodes::ConversionError)?;
+    if from_version != NODE_EXPORT_VERSION {
+        info!(
+            target: LOG_TARGET,
+            "Import file upgraded from version {} to {}", from_version, NODE_EXPORT_VERSION
+        );
+    }
+
+    let identity = serde_json::from_value::<NodeIdentity>(export["identity"].take())
+        .map_err(|e| ExitCodes::ConversionError(format!("Invalid node identity in import file. {}", e)))?;
+    let peers = serde_json::from_value::<Vec<Peer>>(export["peers"].take())
+        .map_err(|e| ExitCodes::ConversionError(format!("Invalid peers in import file. {}", e)))?;
+
+    if identity_file.as_ref().exists() {
+        let existing = load_identity(identity_file.as_ref(), passphrase).map_err(ExitCodes::ConfigError)?;
+        if existing.public_key() != identity.public_key() {
+            return Err(ExitCodes::ConfigError(format!(
+                "A different node identity already exists at {}. Move it before importing.",
+                identity_file.as_ref().display()
+            )));
+        }
+    } else {
+        save_identity(identity_file.as_ref(), &identity, passphrase).map_err(ExitCodes::IOError)?;
+    }
+
+    let peer_manager = open_peer_manager(peer_db_path.as_ref(), peer_db_name)?;
+    let mut num_imported = 0;
+    for peer in peers {
+        if &peer.public_key == identity.public_key() {
+            continue;
+        }
+        peer_manager
+            .add_peer(peer)
+            .await
+            .map_err(|e| ExitCodes::IOError(format!("Could not write to the peer database. {}", e)))?;
+        num_imported += 1;
+    }
+
+    info!(
+        target: LOG_TARGET,
+        "Imported node identity {} and {} peer(s) from {}",
+        identity.node_id(),
+        num_imported,
+        import_file.as_ref().display()
+    );
+    Ok(num_imported)
+}
+
+/// Applies export file migrations to the given json value in order until it is at `NODE_EXPORT_VERSION`. The peers in
+/// an export file are stored in the peer database format of the exporting node, so peer fields added since then are
+/// filled in with the values the peer database migrations use.
+///
+/// ## Returns
+/// The migrated json value and the version it was migrated from
+fn migrate_export_json(mut value: Value) -> Result<(Value, u64), String> {
+    let obj = value
+        .as_object_mut()
+        .ok_or_else(|| "The import file does not contain a json object".to_string())?;
+    let from_version = obj.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if from_version == 0 || from_version > NODE_EXPORT_VERSION {
+        return Err(format!(
+            "Unsupported import file version {} (expected at most {})",
+            from_version, NODE_EXPORT_VERSION
+        ));
+    }
+
+    let mut version = from_version;
+    while version < NODE_EXPORT_VERSION {
+        match version {
+            1 => {
+                // v2: peers gained address `last_success` times, a `dial_proxy` and an `identity_signature`
+                let dial_proxy = serde_json::to_value(DialProxy::default()).map_err(|e| e.to_string())?;
+                let peers = obj.get_mut("peers").and_then(Value::as_array_mut);
+                for peer in peers.into_iter().flatten().filter_map(Value::as_object_mut) {
+                    let addresses = peer
+                        .get_mut("addresses")
+                        .and_then(|addresses| addresses.get_mut("addresses"))
+                        .and_then(Value::as_array_mut);
+                    for address in addresses.into_iter().flatten().filter_map(Value::as_object_mut) {
+                        address.entry("last_success").or_insert(Value::Null);
+                    }
+                    peer.entry("dial_proxy").or_insert_with(|| dial_proxy.clone());
+                    peer.entry("identity_signature").or_insert(Value::Null);
+                }
+            },
+            _ => unreachable!("export file version {} is not handled", version),
+        }
+        version += 1;
+        obj.insert("version".to_string(), version.into());
+    }
+
+    Ok((value, from_version))
+}
+
+fn open_peer_manager(peer_db_path: &Path, peer_db_name: &str) -> Result<PeerManager, ExitCodes> {
+    let peer_database = open_peer_database(peer_db_path, peer_db_name)
+        .map_err(|e| ExitCodes::IOError(format!("Could not open the peer database. {}", e)))?;
+    PeerManager::migrate_lmdb(&peer_database.inner())
+        .map_err(|e| ExitCodes::IOError(format!("Could not migrate the peer database. {}", e)))?;
+    PeerManager::new(peer_database, None)
+        .map_err(|e| ExitCodes::IOError(format!("Could not open the peer database. {}", e)))
+}
+
 /// Loads the node identity from json at the given path
 /// ## Parameters
 /// `path` - Path to file from which to load the node identity
@@ -207,3 +685,115 @@ pub fn save_as_json<P: AsRef<Path>, T: MessageFormat>(path: P, object: &T) -> Re
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn migrate_identity_json_from_v0() {
+        let value = serde_json::json!({
+            "node_id": "abc",
+            "control_service_address": "/ip4/127.0.0.1/tcp/18189",
+        });
+        let (value, from_version) = migrate_identity_json(value).unwrap();
+        assert_eq!(from_version, 0);
+        assert_eq!(value["version"], IDENTITY_FILE_VERSION);
+        assert_eq!(value["public_address"], "/ip4/127.0.0.1/tcp/18189");
+        assert!(value.get("control_service_address").is_none());
+    }
+
+    #[test]
+    fn migrate_identity_json_current_version() {
+        let value = serde_json::json!({
+            "version": IDENTITY_FILE_VERSION,
+            "public_address": "/ip4/127.0.0.1/tcp/18189",
+        });
+        let (migrated, from_version) = migrate_identity_json(value.clone()).unwrap();
+        assert_eq!(from_version, IDENTITY_FILE_VERSION);
+        assert_eq!(migrated, value);
+    }
+
+    #[test]
+    fn migrate_identity_json_rejects_newer_version() {
+        let value = serde_json::json!({ "version": IDENTITY_FILE_VERSION + 1 });
+        migrate_identity_json(value).unwrap_err();
+    }
+
+    #[test]
+    fn migrate_export_json_from_v1() {
+        let node_identity = NodeIdentity::random(
+            &mut OsRng,
+            "/ip4/127.0.0.1/tcp/18189".parse().unwrap(),
+            PeerFeatures::COMMUNICATION_NODE,
+        );
+        let mut peer = serde_json::to_value(node_identity.to_peer()).unwrap();
+        // Strip the fields that were added after version 1 of the export format
+        let obj = peer.as_object_mut().unwrap();
+        obj.remove("dial_proxy");
+        obj.remove("identity_signature");
+        for address in obj["addresses"]["addresses"].as_array_mut().unwrap() {
+            address.as_object_mut().unwrap().remove("last_success");
+        }
+        let value = serde_json::json!({ "version": 1, "peers": [peer] });
+        serde_json::from_value::<Vec<Peer>>(value["peers"].clone()).unwrap_err();
+
+        let (mut value, from_version) = migrate_export_json(value).unwrap();
+        assert_eq!(from_version, 1);
+        assert_eq!(value["version"], NODE_EXPORT_VERSION);
+        let peers = serde_json::from_value::<Vec<Peer>>(value["peers"].take()).unwrap();
+        assert_eq!(&peers[0].public_key, node_identity.public_key());
+        assert_eq!(peers[0].dial_proxy, DialProxy::Global);
+        assert!(peers[0].identity_signature.is_none());
+        assert_eq!(peers[0].addresses.len(), 1);
+    }
+
+    #[test]
+    fn migrate_export_json_rejects_unknown_versions() {
+        migrate_export_json(serde_json::json!({ "peers": [] })).unwrap_err();
+        migrate_export_json(serde_json::json!({ "version": NODE_EXPORT_VERSION + 1 })).unwrap_err();
+    }
+
+    #[test]
+    fn encrypted_identity_file_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node_id.json");
+        let node_identity = create_new_identity(
+            &path,
+            "/ip4/127.0.0.1/tcp/18189".parse().unwrap(),
+            PeerFeatures::COMMUNICATION_NODE,
+            Some("secret"),
+        )
+        .unwrap();
+        assert!(is_identity_file_encrypted(&path));
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(&node_identity.secret_key().to_hex()));
+
+        load_identity(&path, None).unwrap_err();
+        load_identity(&path, Some("wrong")).unwrap_err();
+        let loaded = load_identity(&path, Some("secret")).unwrap();
+        assert_eq!(loaded.public_key(), node_identity.public_key());
+
+        change_identity_passphrase(&path, Some("secret"), Some("new secret")).unwrap();
+        load_identity(&path, Some("secret")).unwrap_err();
+        load_identity(&path, Some("new secret")).unwrap();
+
+        change_identity_passphrase(&path, Some("new secret"), None).unwrap();
+        assert!(!is_identity_file_encrypted(&path));
+        let loaded = load_identity(&path, None).unwrap();
+        assert_eq!(loaded.public_key(), node_identity.public_key());
+    }
+
+    #[test]
+    fn setup_node_identity_does_not_replace_an_identity_that_cannot_be_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node_id.json");
+        let address = "/ip4/127.0.0.1/tcp/18189".parse().unwrap();
+        let node_identity =
+            setup_node_identity(&path, &address, true, PeerFeatures::COMMUNICATION_NODE, Some("secret")).unwrap();
+
+        setup_node_identity(&path, &address, true, PeerFeatures::COMMUNICATION_NODE, Some("wrong")).unwrap_err();
+        let loaded = load_identity(&path, Some("secret")).unwrap();
+        assert_eq!(loaded.public_key(), node_identity.public_key());
+    }
+}
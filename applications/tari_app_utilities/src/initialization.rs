@@ -30,6 +30,18 @@ pub fn init_configuration(
     Ok((bootstrap, global_config, cfg))
 }
 
+/// Re-reads the configuration file of a running application so that changed settings can be applied without a restart
+pub fn reload_configuration(
+    bootstrap: &ConfigBootstrap,
+    application_type: ApplicationType,
+) -> Result<GlobalConfig, ExitCodes> {
+    let cfg = bootstrap.load_configuration()?;
+    let mut global_config =
+        GlobalConfig::convert_from(application_type, cfg).map_err(|err| ExitCodes::ConfigError(err.to_string()))?;
+    check_file_paths(&mut global_config, bootstrap);
+    Ok(global_config)
+}
+
 fn check_file_paths(config: &mut GlobalConfig, bootstrap: &ConfigBootstrap) {
     let prepend = bootstrap.base_path.clone();
     if !config.data_dir.is_absolute() {
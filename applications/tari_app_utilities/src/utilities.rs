@@ -23,6 +23,7 @@
 use crate::identity_management::load_from_json;
 use futures::future::Either;
 use log::*;
+use std::{fs, net::SocketAddr};
 use tari_common::{CommsTransport, GlobalConfig, SocksAuthentication, TorControlAuthentication};
 use tari_comms::{
     connectivity::ConnectivityError,
@@ -44,6 +45,7 @@ use tari_wallet::{
 };
 use thiserror::Error;
 use tokio::{runtime, runtime::Runtime};
+use tonic::transport::{Identity, Server, ServerTlsConfig};
 
 pub const LOG_TARGET: &str = "tari::application";
 
@@ -78,6 +80,8 @@ pub enum ExitCodes {
     NoPassword,
     #[error("Tor connection is offline")]
     TorOffline,
+    #[error("The operation timed out: {0}")]
+    TimeoutError(String),
 }
 
 impl ExitCodes {
@@ -96,6 +100,7 @@ impl ExitCodes {
             Self::ConversionError(_) => 111,
             Self::IncorrectPassword | Self::NoPassword => 112,
             Self::TorOffline => 113,
+            Self::TimeoutError(_) => 114,
         }
     }
 }
@@ -167,6 +172,8 @@ pub fn create_transport_type(config: &GlobalConfig) -> TransportType {
             listener_address,
             tor_socks_address,
             tor_socks_auth,
+            outbound_socks_address,
+            outbound_socks_auth,
         } => TransportType::Tcp {
             listener_address,
             tor_socks_config: tor_socks_address.map(|proxy_address| SocksConfig {
@@ -174,6 +181,13 @@ pub fn create_transport_type(config: &GlobalConfig) -> TransportType {
                 authentication: tor_socks_auth.map(convert_socks_authentication).unwrap_or_default(),
                 proxy_bypass_addresses: vec![],
             }),
+            outbound_socks_config: outbound_socks_address.map(|proxy_address| SocksConfig {
+                proxy_address,
+                authentication: outbound_socks_auth
+                    .map(convert_socks_authentication)
+                    .unwrap_or_default(),
+                proxy_bypass_addresses: vec![],
+            }),
         },
         CommsTransport::TorHiddenService {
             control_server_address,
@@ -278,6 +292,47 @@ pub fn setup_runtime(config: &GlobalConfig) -> Result<Runtime, String> {
         .map_err(|e| format!("There was an error while building the node runtime. {}", e.to_string()))
 }
 
+/// Creates a gRPC server builder for the given listening address. TLS is enabled if a certificate and key are set in
+/// the configuration. A warning is logged if the server is exposed beyond localhost without TLS and authentication.
+pub fn create_grpc_server_builder(config: &GlobalConfig, address: &SocketAddr) -> Result<Server, ExitCodes> {
+    let mut builder = Server::builder();
+    let tls_enabled = match (&config.grpc_tls_cert_file, &config.grpc_tls_key_file) {
+        (Some(cert_file), Some(key_file)) => {
+            let cert = fs::read(cert_file).map_err(|e| {
+                ExitCodes::ConfigError(format!(
+                    "Could not read gRPC TLS certificate '{}': {}",
+                    cert_file.display(),
+                    e
+                ))
+            })?;
+            let key = fs::read(key_file).map_err(|e| {
+                ExitCodes::ConfigError(format!("Could not read gRPC TLS key '{}': {}", key_file.display(), e))
+            })?;
+            builder = builder.tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)));
+            true
+        },
+        _ => false,
+    };
+    // Empty tokens are ignored by the gRPC authenticator, so they do not count as authentication being configured
+    let auth_enabled = [
+        &config.grpc_read_only_token,
+        &config.grpc_spend_token,
+        &config.grpc_admin_token,
+    ]
+    .iter()
+    .any(|token| token.as_ref().map(|t| !t.is_empty()).unwrap_or(false));
+    let is_secured = tls_enabled && auth_enabled;
+    if !address.ip().is_loopback() && !is_secured {
+        warn!(
+            target: LOG_TARGET,
+            "gRPC server on {} is reachable from other hosts but TLS and/or token authentication is not configured. \
+             Set grpc_tls_cert_file, grpc_tls_key_file and the grpc_*_token settings to secure it.",
+            address
+        );
+    }
+    Ok(builder)
+}
+
 /// Returns a CommsPublicKey from either a emoji id or a public key
 pub fn parse_emoji_id_or_public_key(key: &str) -> Option<CommsPublicKey> {
     EmojiId::str_to_pubkey(&key.trim().replace('|', ""))
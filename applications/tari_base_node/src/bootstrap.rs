@@ -20,19 +20,30 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::PEER_DATABASE_NAME;
 use anyhow::anyhow;
 use log::*;
 use std::{cmp, fs, str::FromStr, sync::Arc, time::Duration};
 use tari_app_utilities::{consts, identity_management, utilities::create_transport_type};
 use tari_common::{configuration::bootstrap::ApplicationType, GlobalConfig};
-use tari_comms::{peer_manager::Peer, protocol::rpc::RpcServer, NodeIdentity, UnspawnedCommsNode};
+use tari_comms::{
+    peer_manager::Peer,
+    protocol::{
+        rpc::{NamedProtocolService, RpcQosConfig, RpcServer, SlowConsumerPolicy},
+        ProtocolId,
+    },
+    NodeIdentity,
+    UnspawnedCommsNode,
+};
 use tari_comms_dht::{DbConnectionUrl, Dht, DhtConfig};
 use tari_core::{
     base_node,
     base_node::{
         chain_metadata_service::ChainMetadataServiceInitializer,
+        fee_estimation_service::{FeeEstimationConfig, FeeEstimationHandle, FeeEstimationServiceInitializer},
         service::{BaseNodeServiceConfig, BaseNodeServiceInitializer},
         state_machine_service::{initializer::BaseNodeStateMachineInitializer, states::HorizonSyncConfig},
+        sync::rpc::BaseNodeSyncRpcClient,
         BaseNodeStateMachineConfig,
         BlockSyncConfig,
         StateMachineHandle,
@@ -58,7 +69,7 @@ use tari_p2p::{
     services::liveness::{LivenessConfig, LivenessInitializer},
 };
 use tari_service_framework::{ServiceHandles, StackBuilder};
-use tari_shutdown::ShutdownSignal;
+use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::runtime;
 
 const LOG_TARGET: &str = "c::bn::initialization";
@@ -72,7 +83,12 @@ pub struct BaseNodeBootstrapper<'a, B> {
     pub mempool: Mempool,
     pub rules: ConsensusManager,
     pub factories: CryptoFactories,
+    /// Signal to stop the base node services
     pub interrupt_signal: ShutdownSignal,
+    /// Triggered once the base node services have stopped
+    pub services_stopped: Shutdown,
+    /// Signal to stop comms. Comms is stopped after the services that use it.
+    pub comms_shutdown_signal: ShutdownSignal,
 }
 
 impl<B> BaseNodeBootstrapper<'_, B>
@@ -109,7 +125,10 @@ where B: BlockchainBackend + 'static
         let mempool_protocol = mempool_sync.get_protocol_extension();
 
         let mut handles = StackBuilder::new(self.interrupt_signal)
-            .add_initializer(P2pInitializer::new(comms_config, publisher))
+            .with_stopped_trigger(self.services_stopped)
+            .add_initializer(
+                P2pInitializer::new(comms_config, publisher).with_shutdown_signal(self.comms_shutdown_signal),
+            )
             .add_initializer(SoftwareUpdaterService::new(
                 ApplicationType::BaseNode,
                 consts::APP_VERSION_NUMBER
@@ -148,6 +167,10 @@ where B: BlockchainBackend + 'static
                 peer_message_subscriptions,
             ))
             .add_initializer(ChainMetadataServiceInitializer)
+            .add_initializer(FeeEstimationServiceInitializer::new(
+                FeeEstimationConfig::default(),
+                self.rules.clone(),
+            ))
             .add_initializer(BaseNodeStateMachineInitializer::new(
                 self.db.clone().into(),
                 BaseNodeStateMachineConfig {
@@ -210,6 +233,23 @@ where B: BlockchainBackend + 'static
                 builder.with_unlimited_simultaneous_sessions()
             },
         };
+        let builder = builder
+            .with_maximum_in_flight_messages(config.rpc_max_in_flight_messages)
+            .with_slow_consumer_policy(match config.rpc_slow_consumer_timeout {
+                Some(timeout) => SlowConsumerPolicy::DisconnectAfter(timeout),
+                None => SlowConsumerPolicy::AbortAfterDeadline,
+            })
+            .with_qos_config(
+                RpcQosConfig {
+                    max_concurrent_requests: config.rpc_max_concurrent_requests,
+                    max_concurrent_requests_per_peer: config.rpc_max_concurrent_requests_per_peer,
+                    ..Default::default()
+                }
+                .with_sync_critical_methods(
+                    ProtocolId::from_static(BaseNodeSyncRpcClient::PROTOCOL_NAME),
+                    base_node::sync::rpc::SYNC_CRITICAL_METHODS.iter().copied(),
+                ),
+            );
         let rpc_server = builder.finish();
         handles.register(rpc_server.get_handle());
 
@@ -224,6 +264,7 @@ where B: BlockchainBackend + 'static
                 db,
                 handles.expect_handle::<MempoolHandle>(),
                 handles.expect_handle::<StateMachineHandle>(),
+                handles.expect_handle::<FeeEstimationHandle>(),
             ));
 
         comms.add_protocol_extension(rpc_server)
@@ -236,7 +277,7 @@ where B: BlockchainBackend + 'static
             transport_type: create_transport_type(self.config),
             auxilary_tcp_listener_address: self.config.auxilary_tcp_listener_address.clone(),
             datastore_path: self.config.peer_db_path.clone(),
-            peer_database_name: "peers".to_string(),
+            peer_database_name: PEER_DATABASE_NAME.to_string(),
             max_concurrent_inbound_tasks: 100,
             outbound_buffer_size: 100,
             dht: DhtConfig {
@@ -246,6 +287,7 @@ where B: BlockchainBackend + 'static
                 flood_ban_max_msg_count: self.config.flood_ban_max_msg_count,
                 saf_msg_validity: self.config.saf_expiry_duration,
                 dedup_cache_capacity: self.config.dedup_cache_capacity,
+                dedup_bloom_retention: self.config.dedup_bloom_retention,
                 ..Default::default()
             },
             allow_test_addresses: self.config.allow_test_addresses,
@@ -263,6 +305,7 @@ where B: BlockchainBackend + 'static
             dns_seeds: self.config.dns_seeds.clone(),
             dns_seeds_name_server: self.config.dns_seeds_name_server,
             dns_seeds_use_dnssec: self.config.dns_seeds_use_dnssec,
+            min_peer_minor_version: self.config.min_peer_minor_version,
         }
     }
 }
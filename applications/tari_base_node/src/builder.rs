@@ -21,21 +21,36 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::bootstrap::BaseNodeBootstrapper;
+use futures::StreamExt;
 use log::*;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tari_common::{configuration::Network, DatabaseType, GlobalConfig};
 use tari_comms::{peer_manager::NodeIdentity, protocol::rpc::RpcServerHandle, CommsNode};
 use tari_comms_dht::Dht;
 use tari_core::{
-    base_node::{state_machine_service::states::StatusInfo, LocalNodeCommsInterface, StateMachineHandle},
-    chain_storage::{create_lmdb_database, BlockchainDatabase, BlockchainDatabaseConfig, LMDBDatabase, Validators},
+    base_node::{
+        chain_metadata_service::ChainMetadataHandle,
+        fee_estimation_service::FeeEstimationHandle,
+        state_machine_service::states::StatusInfo,
+        LocalNodeCommsInterface,
+        StateMachineHandle,
+    },
+    chain_storage::{
+        async_db::AsyncBlockchainDb,
+        create_lmdb_database,
+        BlockchainDatabase,
+        BlockchainDatabaseConfig,
+        LMDBDatabase,
+        Validators,
+    },
     consensus::ConsensusManager,
-    mempool::{service::LocalMempoolService, Mempool, MempoolConfig},
+    mempool::{service::LocalMempoolService, Mempool, MempoolConfig, RelayPolicyConfig, UnconfirmedPoolConfig},
     proof_of_work::randomx_factory::RandomXFactory,
     transactions::types::CryptoFactories,
     validation::{
         block_validators::{BodyOnlyValidator, OrphanBlockValidator},
         header_validator::HeaderValidator,
+        timestamp_validators::NetworkTimeOffset,
         transaction_validators::{
             MempoolValidator,
             TxConsensusValidator,
@@ -47,10 +62,22 @@ use tari_core::{
 };
 use tari_p2p::{auto_update::SoftwareUpdaterHandle, services::liveness::LivenessHandle};
 use tari_service_framework::ServiceHandles;
-use tari_shutdown::ShutdownSignal;
-use tokio::sync::watch;
+use tari_shutdown::{Shutdown, ShutdownOrchestrator, ShutdownSignal, ShutdownStage};
+use tokio::{sync::watch, task, time};
 
 const LOG_TARGET: &str = "c::bn::initialization";
+/// The time allowed for the base node services to stop
+const SERVICES_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+/// The time allowed for comms to stop once the services have stopped
+const COMMS_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+/// The time allowed to flush pending blockchain database writes to disk
+const STORAGE_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+/// How often the storage held by pruned blocks is reclaimed
+const PRUNING_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// The time allowed for an in-progress pruning maintenance batch to complete
+const PRUNING_MAINTENANCE_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the network time offset used to validate header timestamps is updated from the liveness service
+const NETWORK_TIME_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
 
 /// The base node context is a container for all the key structural pieces for the base node application, including the
 /// communications stack, the node state machine and handles to the various services that are registered
@@ -62,12 +89,14 @@ pub struct BaseNodeContext {
     base_node_comms: CommsNode,
     base_node_dht: Dht,
     base_node_handles: ServiceHandles,
+    mempool: Mempool,
+    comms_stopped: Shutdown,
 }
 
 impl BaseNodeContext {
     /// Starts the node container. This entails the base node state machine.
     /// This call consumes the NodeContainer instance.
-    pub async fn run(self) {
+    pub async fn run(mut self) {
         info!(target: LOG_TARGET, "Tari base node has STARTED");
 
         if let Err(e) = self.state_machine().shutdown_signal().await {
@@ -77,6 +106,7 @@ impl BaseNodeContext {
 
         self.base_node_comms.wait_until_shutdown().await;
         info!(target: LOG_TARGET, "Communications stack has shutdown");
+        let _ = self.comms_stopped.trigger();
     }
 
     /// Return the node config
@@ -94,6 +124,11 @@ impl BaseNodeContext {
         self.base_node_handles.expect_handle()
     }
 
+    /// Returns the Mempool
+    pub fn mempool(&self) -> Mempool {
+        self.mempool.clone()
+    }
+
     /// Returns the CommsNode.
     pub fn base_node_comms(&self) -> &CommsNode {
         &self.base_node_comms
@@ -104,6 +139,16 @@ impl BaseNodeContext {
         self.base_node_handles.expect_handle()
     }
 
+    /// Returns a handle to the chain metadata service, which reports the chain tips of peers
+    pub fn chain_metadata(&self) -> ChainMetadataHandle {
+        self.base_node_handles.expect_handle()
+    }
+
+    /// Returns a handle to the fee estimation service
+    pub fn fee_estimation(&self) -> FeeEstimationHandle {
+        self.base_node_handles.expect_handle()
+    }
+
     /// Returns the base node state machine
     pub fn state_machine(&self) -> StateMachineHandle {
         self.base_node_handles.expect_handle()
@@ -157,13 +202,13 @@ impl BaseNodeContext {
 /// `config` - The configuration for the base node
 /// `node_identity` - The node identity information of the base node
 /// `wallet_node_identity` - The node identity information of the base node's wallet
-/// `interrupt_signal` - The signal used to stop the application
+/// `shutdown` - The orchestrator with which the node's services are registered to be stopped
 /// ## Returns
 /// Result containing the NodeContainer, String will contain the reason on error
 pub async fn configure_and_initialize_node(
     config: Arc<GlobalConfig>,
     node_identity: Arc<NodeIdentity>,
-    interrupt_signal: ShutdownSignal,
+    shutdown: &mut ShutdownOrchestrator,
     cleanup_orphans_at_startup: bool,
 ) -> Result<BaseNodeContext, anyhow::Error> {
     let result = match &config.db_type {
@@ -182,14 +227,7 @@ pub async fn configure_and_initialize_node(
         },
         DatabaseType::LMDB(p) => {
             let backend = create_lmdb_database(&p, config.db_config.clone())?;
-            build_node_context(
-                backend,
-                node_identity,
-                config,
-                interrupt_signal,
-                cleanup_orphans_at_startup,
-            )
-            .await?
+            build_node_context(backend, node_identity, config, shutdown, cleanup_orphans_at_startup).await?
         },
     };
     Ok(result)
@@ -203,14 +241,14 @@ pub async fn configure_and_initialize_node(
 /// `base_node_identity` - The node identity information of the base node
 /// `wallet_node_identity` - The node identity information of the base node's wallet
 /// `config` - The configuration for the base node
-/// `interrupt_signal` - The signal used to stop the application
+/// `shutdown` - The orchestrator with which the node's services are registered to be stopped
 /// ## Returns
 /// Result containing the BaseNodeContext, String will contain the reason on error
 async fn build_node_context(
     backend: LMDBDatabase,
     base_node_identity: Arc<NodeIdentity>,
     config: Arc<GlobalConfig>,
-    interrupt_signal: ShutdownSignal,
+    shutdown: &mut ShutdownOrchestrator,
     cleanup_orphans_at_startup: bool,
 ) -> Result<BaseNodeContext, anyhow::Error> {
     //---------------------------------- Blockchain --------------------------------------------//
@@ -218,15 +256,18 @@ async fn build_node_context(
     let rules = ConsensusManager::builder(config.network).build();
     let factories = CryptoFactories::default();
     let randomx_factory = RandomXFactory::new(config.max_randomx_vms);
+    let network_time = NetworkTimeOffset::default();
     let validators = Validators::new(
         BodyOnlyValidator::default(),
-        HeaderValidator::new(rules.clone()),
+        HeaderValidator::with_network_time(rules.clone(), network_time.clone()),
         OrphanBlockValidator::new(rules.clone(), factories.clone()),
     );
     let db_config = BlockchainDatabaseConfig {
         orphan_storage_capacity: config.orphan_storage_capacity,
         pruning_horizon: config.pruning_horizon,
         pruning_interval: config.pruned_mode_cleanup_interval,
+        group_commit_max_operations: config.db_group_commit_max_operations,
+        group_commit_interval: config.db_group_commit_interval,
     };
     let blockchain_db = BlockchainDatabase::new(
         backend,
@@ -241,7 +282,51 @@ async fn build_node_context(
         Box::new(TxInputAndMaturityValidator::new(blockchain_db.clone())),
         Box::new(TxConsensusValidator::new(blockchain_db.clone())),
     ]);
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool_config = MempoolConfig {
+        unconfirmed_pool: unconfirmed_pool_config(&config),
+        relay_policy: relay_policy_config(&config),
+        ..Default::default()
+    };
+    let mempool = Mempool::new(mempool_config, rules.clone(), Arc::new(mempool_validator));
+
+    //---------------------------------- Shutdown  --------------------------------------------//
+    // Services are stopped before comms, and pending database writes are flushed once nothing else can write
+    let (interrupt_signal, services_stopped) = shutdown.register(
+        "Base node services",
+        ShutdownStage::Protocols,
+        SERVICES_SHUTDOWN_TIMEOUT,
+    );
+    let (comms_shutdown_signal, comms_stopped) = shutdown.register(
+        "Communications stack",
+        ShutdownStage::ConnectionManager,
+        COMMS_SHUTDOWN_TIMEOUT,
+    );
+    let db = AsyncBlockchainDb::from(blockchain_db.clone());
+    shutdown.register_task(
+        "Blockchain database",
+        ShutdownStage::Storage,
+        STORAGE_SHUTDOWN_TIMEOUT,
+        move || async move {
+            if let Err(err) = db.flush_write_queue().await {
+                error!(
+                    target: LOG_TARGET,
+                    "Failed to flush blockchain database writes: {}", err
+                );
+            }
+        },
+    );
+    if config.pruning_horizon > 0 {
+        let (shutdown_signal, stopped) = shutdown.register(
+            "Pruning maintenance",
+            ShutdownStage::Protocols,
+            PRUNING_MAINTENANCE_SHUTDOWN_TIMEOUT,
+        );
+        task::spawn(run_pruning_maintenance(
+            AsyncBlockchainDb::from(blockchain_db.clone()),
+            shutdown_signal,
+            stopped,
+        ));
+    }
 
     //---------------------------------- Base Node  --------------------------------------------//
     debug!(target: LOG_TARGET, "Creating base node state machine.");
@@ -250,10 +335,12 @@ async fn build_node_context(
         config: &config,
         node_identity: base_node_identity,
         db: blockchain_db.clone(),
-        mempool,
+        mempool: mempool.clone(),
         rules: rules.clone(),
         factories: factories.clone(),
-        interrupt_signal: interrupt_signal.clone(),
+        interrupt_signal,
+        services_stopped,
+        comms_shutdown_signal,
     }
     .bootstrap()
     .await?;
@@ -261,6 +348,18 @@ async fn build_node_context(
     let base_node_comms = base_node_handles.expect_handle::<CommsNode>();
     let base_node_dht = base_node_handles.expect_handle::<Dht>();
 
+    let (shutdown_signal, stopped) = shutdown.register(
+        "Network time updates",
+        ShutdownStage::Protocols,
+        SERVICES_SHUTDOWN_TIMEOUT,
+    );
+    task::spawn(run_network_time_updates(
+        base_node_handles.expect_handle::<LivenessHandle>(),
+        network_time,
+        shutdown_signal,
+        stopped,
+    ));
+
     Ok(BaseNodeContext {
         config,
         consensus_rules: rules,
@@ -268,5 +367,92 @@ async fn build_node_context(
         base_node_comms,
         base_node_dht,
         base_node_handles,
+        mempool,
+        comms_stopped,
     })
 }
+
+/// Periodically deletes the data of blocks below the pruned height that is no longer needed by a pruned node
+async fn run_pruning_maintenance(
+    db: AsyncBlockchainDb<LMDBDatabase>,
+    mut shutdown_signal: ShutdownSignal,
+    _stopped: Shutdown,
+) {
+    let mut interval = time::interval(PRUNING_MAINTENANCE_INTERVAL).fuse();
+    loop {
+        futures::select! {
+            _ = interval.select_next_some() => {
+                match db.reclaim_pruned_space().await {
+                    Ok(stats) => info!(target: LOG_TARGET, "Pruning maintenance complete: {}", stats),
+                    Err(err) => error!(target: LOG_TARGET, "Pruning maintenance failed: {}", err),
+                }
+            },
+            _ = shutdown_signal => {
+                debug!(target: LOG_TARGET, "Pruning maintenance stopped");
+                break;
+            }
+        }
+    }
+}
+
+/// Periodically updates the network time offset used to validate header timestamps from the median clock offset of
+/// peers reported by the liveness service
+async fn run_network_time_updates(
+    mut liveness: LivenessHandle,
+    network_time: NetworkTimeOffset,
+    mut shutdown_signal: ShutdownSignal,
+    _stopped: Shutdown,
+) {
+    let mut interval = time::interval(NETWORK_TIME_UPDATE_INTERVAL).fuse();
+    loop {
+        futures::select! {
+            _ = interval.select_next_some() => {
+                match liveness.get_network_time_estimate().await {
+                    Ok(Some(estimate)) => {
+                        network_time.set_offset_ms(estimate.offset_ms);
+                        debug!(
+                            target: LOG_TARGET,
+                            "Network time offset is {}ms ({} peer(s) reported {}ms)",
+                            network_time.offset_ms(),
+                            estimate.num_peers,
+                            estimate.offset_ms
+                        );
+                    },
+                    Ok(None) => {},
+                    Err(err) => warn!(target: LOG_TARGET, "Failed to get network time estimate: {}", err),
+                }
+            },
+            _ = shutdown_signal => break,
+        }
+    }
+}
+
+/// Applies the relay policy settings in the global config over the default relay policy
+pub fn relay_policy_config(config: &GlobalConfig) -> RelayPolicyConfig {
+    let mut relay_policy = RelayPolicyConfig::default();
+    if let Some(max_script_size) = config.mempool_relay_max_script_size {
+        relay_policy.max_script_size = max_script_size;
+    }
+    if let Some(min_fee_per_gram) = config.mempool_relay_min_fee_per_gram {
+        relay_policy.min_fee_per_gram = min_fee_per_gram.into();
+    }
+    if let Some(max_outputs) = config.mempool_relay_max_outputs {
+        relay_policy.max_outputs = max_outputs;
+    }
+    if let Some(min_fee_per_output) = config.mempool_relay_min_fee_per_output {
+        relay_policy.min_fee_per_output = min_fee_per_output.into();
+    }
+    relay_policy
+}
+
+/// Applies the mempool admission limits in the global config over the default unconfirmed pool config
+pub fn unconfirmed_pool_config(config: &GlobalConfig) -> UnconfirmedPoolConfig {
+    let mut unconfirmed_pool = UnconfirmedPoolConfig::default();
+    if let Some(max_txs_per_unique_id) = config.mempool_max_txs_per_unique_id {
+        unconfirmed_pool.max_txs_per_unique_id = max_txs_per_unique_id;
+    }
+    if let Some(max_weight_per_sender) = config.mempool_max_weight_per_sender {
+        unconfirmed_pool.max_weight_per_sender = max_weight_per_sender;
+    }
+    unconfirmed_pool
+}
@@ -21,7 +21,13 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::LOG_TARGET;
-use crate::{builder::BaseNodeContext, status_line::StatusLine, table::Table, utils::format_duration_basic};
+use crate::{
+    builder::BaseNodeContext,
+    status_line::StatusLine,
+    table::Table,
+    utils::format_duration_basic,
+    watchdog::{AlertLevel, WatchdogStatus},
+};
 use chrono::{DateTime, Utc};
 use log::*;
 use std::{
@@ -74,10 +80,15 @@ pub struct CommandHandler {
     mempool_service: LocalMempoolService,
     state_machine_info: watch::Receiver<StatusInfo>,
     software_updater: SoftwareUpdaterHandle,
+    watchdog_status: watch::Receiver<WatchdogStatus>,
 }
 
 impl CommandHandler {
-    pub fn new(executor: runtime::Handle, ctx: &BaseNodeContext) -> Self {
+    pub fn new(
+        executor: runtime::Handle,
+        ctx: &BaseNodeContext,
+        watchdog_status: watch::Receiver<WatchdogStatus>,
+    ) -> Self {
         CommandHandler {
             executor,
             config: ctx.config(),
@@ -92,6 +103,7 @@ impl CommandHandler {
             mempool_service: ctx.local_mempool(),
             state_machine_info: ctx.get_state_machine_info_channel(),
             software_updater: ctx.software_updater(),
+            watchdog_status,
         }
     }
 
@@ -104,6 +116,7 @@ impl CommandHandler {
         let mut metrics = self.dht_metrics_collector.clone();
         let mut rpc_server = self.rpc_server.clone();
         let config = self.config.clone();
+        let watchdog_status = self.watchdog_status.borrow().clone();
 
         self.executor.spawn(async move {
             let mut status_line = StatusLine::new();
@@ -171,6 +184,10 @@ impl CommandHandler {
                 ),
             );
 
+            if watchdog_status.level != AlertLevel::Ok {
+                status_line.add_field("Watchdog", watchdog_status);
+            }
+
             info!(target: "base_node::app::status", "{}", status_line);
             println!("{}", status_line);
         });
@@ -1039,11 +1056,154 @@ impl CommandHandler {
         let db = self.blockchain_db.clone();
         let local_node_comms_interface = self.node_service.clone();
         self.executor.spawn(async move {
+            println!("Rewinding blockchain to height {}...", new_height);
             let blocks = try_or_print!(db.rewind_to_height(new_height).await);
+            println!(
+                "Removed {} block(s). Chain tip is now at height {}",
+                blocks.len(),
+                new_height
+            );
             local_node_comms_interface.publish_block_event(BlockEvent::BlockSyncRewind(blocks));
         });
     }
 
+    pub fn invalidate_block(&self, hash: HashOutput) {
+        let db = self.blockchain_db.clone();
+        let local_node_comms_interface = self.node_service.clone();
+        self.executor.spawn(async move {
+            println!("Invalidating block {}...", hash.to_hex());
+            let blocks = try_or_print!(db.invalidate_block(hash).await);
+            let metadata = try_or_print!(db.get_chain_metadata().await);
+            println!(
+                "Removed {} block(s) from the main chain. Chain tip is now at height {}",
+                blocks.len(),
+                metadata.height_of_longest_chain()
+            );
+            if !blocks.is_empty() {
+                local_node_comms_interface.publish_block_event(BlockEvent::BlockSyncRewind(blocks));
+            }
+        });
+    }
+
+    pub fn reindex_db(&self) {
+        let db = self.blockchain_db.clone();
+        self.executor.spawn(async move {
+            println!("Rebuilding database indexes, this may take a while...");
+            let start = Instant::now();
+            try_or_print!(db.rebuild_secondary_indexes().await);
+            println!("Reindex completed in {:.2?}", start.elapsed());
+        });
+    }
+
+    pub fn get_db_stats(&self) {
+        const BYTES_PER_MB: usize = 1024 * 1024;
+        let db = self.blockchain_db.clone();
+        self.executor.spawn(async move {
+            let stats = try_or_print!(db.get_storage_stats().await);
+            let mut table = Table::new();
+            table.set_titles(vec![
+                "Name",
+                "Entries",
+                "Depth",
+                "Branch Pages",
+                "Leaf Pages",
+                "Overflow Pages",
+                "Size",
+            ]);
+            for db in stats.databases.iter() {
+                table.add_row(row![
+                    db.name,
+                    db.entries,
+                    db.depth,
+                    db.branch_pages,
+                    db.leaf_pages,
+                    db.overflow_pages,
+                    format!("{} MB", db.size_bytes / BYTES_PER_MB),
+                ]);
+            }
+            table.print_std();
+            println!();
+            println!(
+                "Map size: {} MB, used: {} MB ({:.1}%), free: {} MB ({} pages of {} bytes)",
+                stats.map_size_bytes / BYTES_PER_MB,
+                stats.used_bytes() / BYTES_PER_MB,
+                stats.map_usage_percent(),
+                stats.free_bytes() / BYTES_PER_MB,
+                stats.free_pages,
+                stats.page_size
+            );
+
+            let filter_stats = try_or_print!(db.get_existence_filter_stats().await);
+            println!();
+            let mut table = Table::new();
+            table.set_titles(vec![
+                "Existence Filter",
+                "Keys",
+                "Capacity",
+                "Lookups",
+                "Skipped",
+                "False Positives",
+                "FP Rate",
+                "Expected FP Rate",
+            ]);
+            for filter in filter_stats {
+                table.add_row(row![
+                    filter.name,
+                    filter.num_keys,
+                    filter.capacity,
+                    filter.lookups,
+                    filter.definitely_absent,
+                    filter.false_positives,
+                    format!("{:.2}%", filter.observed_false_positive_rate() * 100.0),
+                    format!("{:.2}%", filter.expected_false_positive_rate * 100.0),
+                ]);
+            }
+            table.print_std();
+
+            let pruning_stats = try_or_print!(db.get_pruning_stats().await);
+            if pruning_stats != Default::default() {
+                println!();
+                println!("Pruning: {}", pruning_stats);
+            }
+        });
+    }
+
+    pub fn compact_db(&self) {
+        let db = self.blockchain_db.clone();
+        self.executor.spawn(async move {
+            println!("Compacting the blockchain database, this may take a while...");
+            let start = Instant::now();
+            let result = try_or_print!(db.compact().await, "Compaction failed: {error}");
+            println!(
+                "Compaction completed in {:.2?}. Database size reduced from {} MB to {} MB",
+                start.elapsed(),
+                result.size_before_bytes / (1024 * 1024),
+                result.size_after_bytes / (1024 * 1024)
+            );
+        });
+    }
+
+    pub fn set_log_level(&self, target: &str, level: &str) {
+        try_or_print!(tari_common::set_log_level(target, level));
+        println!("Log level for `{}` set to {}", target, level);
+    }
+
+    pub fn reset_log_levels(&self) {
+        try_or_print!(tari_common::reset_log_levels());
+        println!("Log levels restored from the logging configuration file");
+    }
+
+    pub fn list_log_levels(&self) {
+        let overrides = tari_common::log_level_overrides();
+        if overrides.is_empty() {
+            println!("No log levels have been changed");
+            return;
+        }
+        for (target, level) in overrides {
+            println!("{}: {}", target, level);
+        }
+    }
+
     /// Function to process the whoami command
     pub fn whoami(&self) {
         println!("{}", self.base_node_identity);
@@ -0,0 +1,116 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::builder::relay_policy_config;
+use log::*;
+use std::sync::{Arc, Mutex};
+use tari_app_utilities::{
+    config_reload::{changed_settings, ConfigReloadReport},
+    initialization::reload_configuration,
+    utilities::ExitCodes,
+};
+use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap, GlobalConfig};
+use tari_core::mempool::Mempool;
+#[cfg(unix)]
+use tari_shutdown::{Shutdown, ShutdownSignal};
+
+const LOG_TARGET: &str = "base_node::app::config_watcher";
+
+/// The settings in the configuration file that are applied to a running base node. Changes to any other setting are
+/// reported as requiring a restart.
+const CHANGEABLE_SETTINGS: &[&str] = &[
+    "mempool_relay_max_script_size",
+    "mempool_relay_min_fee_per_gram",
+    "mempool_relay_max_outputs",
+    "mempool_relay_min_fee_per_output",
+];
+
+/// Re-reads the configuration file and applies the settings that can be changed at runtime. The log levels in the
+/// logging configuration file are reloaded at the same time.
+#[derive(Clone)]
+pub struct ConfigReloader {
+    bootstrap: Arc<ConfigBootstrap>,
+    /// The configuration that the node is running with, i.e. the startup configuration with any applied changes
+    running_config: Arc<Mutex<GlobalConfig>>,
+    mempool: Mempool,
+}
+
+impl ConfigReloader {
+    pub fn new(bootstrap: ConfigBootstrap, config: &GlobalConfig, mempool: Mempool) -> Self {
+        Self {
+            bootstrap: Arc::new(bootstrap),
+            running_config: Arc::new(Mutex::new(config.clone())),
+            mempool,
+        }
+    }
+
+    pub fn reload(&self) -> Result<ConfigReloadReport, ExitCodes> {
+        let new_config = reload_configuration(&self.bootstrap, ApplicationType::BaseNode)?;
+        let mut running_config = self.running_config.lock().expect("config reloader lock poisoned");
+        let mut report =
+            ConfigReloadReport::from_changes(changed_settings(&running_config, &new_config), CHANGEABLE_SETTINGS);
+
+        if !report.applied.is_empty() {
+            match self.mempool.set_relay_policy(relay_policy_config(&new_config)) {
+                Ok(_) => {
+                    running_config.mempool_relay_max_script_size = new_config.mempool_relay_max_script_size;
+                    running_config.mempool_relay_min_fee_per_gram = new_config.mempool_relay_min_fee_per_gram;
+                    running_config.mempool_relay_max_outputs = new_config.mempool_relay_max_outputs;
+                    running_config.mempool_relay_min_fee_per_output = new_config.mempool_relay_min_fee_per_output;
+                },
+                Err(err) => {
+                    let failed = report.applied.drain(..).map(|s| format!("{}: {}", s, err));
+                    report.failed.extend(failed);
+                },
+            }
+        }
+        report.reload_logging();
+        report.log();
+        Ok(report)
+    }
+}
+
+/// Reloads the configuration every time the process receives a SIGHUP
+#[cfg(unix)]
+pub async fn reload_on_hangup(reloader: ConfigReloader, mut shutdown_signal: ShutdownSignal, _stopped: Shutdown) {
+    use futures::StreamExt;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup.fuse(),
+        Err(err) => {
+            warn!(target: LOG_TARGET, "Unable to listen for SIGHUP: {}", err);
+            return;
+        },
+    };
+    loop {
+        futures::select! {
+            _ = hangup.select_next_some() => {
+                info!(target: LOG_TARGET, "SIGHUP received, reloading configuration");
+                if let Err(err) = reloader.reload() {
+                    error!(target: LOG_TARGET, "Could not reload configuration: {:?}", err);
+                }
+            },
+            _ = shutdown_signal => break,
+        }
+    }
+}
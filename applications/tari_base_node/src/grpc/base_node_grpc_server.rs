@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 use crate::{
     builder::BaseNodeContext,
+    config_watcher::ConfigReloader,
     grpc::{
         blocks::{block_fees, block_heights, block_size, GET_BLOCKS_MAX_HEIGHTS, GET_BLOCKS_PAGE_SIZE},
         helpers::{mean, median},
@@ -30,27 +31,34 @@ use log::*;
 use std::{
     cmp,
     convert::{TryFrom, TryInto},
+    sync::Arc,
 };
 use tari_app_grpc::{
+    authentication::{GrpcAuthenticator, GrpcPermission},
     tari_rpc,
     tari_rpc::{CalcType, Sorting},
 };
 use tari_app_utilities::consts;
+use tari_common::LoggingError;
 use tari_comms::{Bytes, CommsNode};
 use tari_core::{
     base_node::{
-        comms_interface::{Broadcast, CommsInterfaceError},
+        comms_interface::{BlockEvent, Broadcast, CommsInterfaceError},
+        fee_estimation_service::{FeeEstimationHandle, MAX_TARGET_BLOCKS},
         state_machine_service::states::BlockSyncInfo,
         LocalNodeCommsInterface,
         StateMachineHandle,
     },
     blocks::{Block, BlockHeader, NewBlockTemplate},
-    chain_storage::ChainStorageError,
+    chain_storage::{async_db::AsyncBlockchainDb, AssetOutput, ChainBlock, ChainStorageError, LMDBDatabase},
     consensus::{emission::Emission, ConsensusManager, NetworkConsensus},
     crypto::tari_utilities::{hex::Hex, ByteArray},
     mempool::{service::LocalMempoolService, TxStorageResponse},
     proof_of_work::PowAlgorithm,
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{
+        transaction::Transaction,
+        types::{PublicKey, Signature},
+    },
 };
 use tari_crypto::tari_utilities::{message_format::MessageFormat, Hashable};
 use tari_p2p::{auto_update::SoftwareUpdaterHandle, services::liveness::LivenessHandle};
@@ -73,9 +81,14 @@ const LIST_HEADERS_MAX_NUM_HEADERS: u64 = 10_000;
 const LIST_HEADERS_PAGE_SIZE: usize = 10;
 // The `num_headers` value if none is provided.
 const LIST_HEADERS_DEFAULT_NUM_HEADERS: u64 = 10;
+// The maximum number of outputs or kernels that MMR proofs can be requested for at once
+const GET_MMR_PROOFS_MAX_HASHES: usize = 1_000;
+// The maximum number of asset registrations or tokens returned in one page
+const GET_ASSETS_PAGE_SIZE: usize = 100;
 
 pub struct BaseNodeGrpcServer {
     node_service: LocalNodeCommsInterface,
+    blockchain_db: AsyncBlockchainDb<LMDBDatabase>,
     mempool_service: LocalMempoolService,
     network: NetworkConsensus,
     state_machine_handle: StateMachineHandle,
@@ -83,12 +96,17 @@ pub struct BaseNodeGrpcServer {
     software_updater: SoftwareUpdaterHandle,
     comms: CommsNode,
     liveness: LivenessHandle,
+    fee_estimation: FeeEstimationHandle,
+    authenticator: GrpcAuthenticator,
+    config_reloader: ConfigReloader,
 }
 
 impl BaseNodeGrpcServer {
-    pub fn from_base_node_context(ctx: &BaseNodeContext) -> Self {
+    pub fn from_base_node_context(ctx: &BaseNodeContext, config_reloader: ConfigReloader) -> Self {
+        let config = ctx.config();
         Self {
             node_service: ctx.local_node(),
+            blockchain_db: ctx.blockchain_db().into(),
             mempool_service: ctx.local_mempool(),
             network: ctx.network().into(),
             state_machine_handle: ctx.state_machine(),
@@ -96,6 +114,14 @@ impl BaseNodeGrpcServer {
             software_updater: ctx.software_updater(),
             comms: ctx.base_node_comms().clone(),
             liveness: ctx.liveness(),
+            fee_estimation: ctx.fee_estimation(),
+            authenticator: GrpcAuthenticator::new(
+                config.grpc_read_only_token.clone(),
+                config.grpc_spend_token.clone(),
+                config.grpc_admin_token.clone(),
+                config.grpc_allow_unauthenticated,
+            ),
+            config_reloader,
         }
     }
 }
@@ -122,6 +148,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::HeightRequest>,
     ) -> Result<Response<Self::GetNetworkDifficultyStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let request = request.into_inner();
         debug!(
             target: LOG_TARGET,
@@ -250,6 +277,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::GetMempoolTransactionsRequest>,
     ) -> Result<Response<Self::GetMempoolTransactionsStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let _request = request.into_inner();
         debug!(target: LOG_TARGET, "Incoming GRPC request for GetMempoolTransactions",);
 
@@ -296,6 +324,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::ListHeadersRequest>,
     ) -> Result<Response<Self::ListHeadersStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let request = request.into_inner();
         debug!(
             target: LOG_TARGET,
@@ -392,6 +421,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::NewBlockTemplateRequest>,
     ) -> Result<Response<tari_rpc::NewBlockTemplateResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let request = request.into_inner();
         debug!(target: LOG_TARGET, "Incoming GRPC request for get new block template");
         trace!(target: LOG_TARGET, "Request {:?}", request);
@@ -436,6 +466,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::NewBlockTemplate>,
     ) -> Result<Response<tari_rpc::GetNewBlockResult>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let request = request.into_inner();
         debug!(target: LOG_TARGET, "Incoming GRPC request for get new block");
         let block_template: NewBlockTemplate = request
@@ -474,6 +505,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::Block>,
     ) -> Result<Response<tari_rpc::SubmitBlockResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Spend)?;
         let request = request.into_inner();
         let block = Block::try_from(request)
             .map_err(|e| Status::invalid_argument(format!("Failed to convert arguments. Invalid block: {:?}", e)))?;
@@ -500,6 +532,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::SubmitTransactionRequest>,
     ) -> Result<Response<tari_rpc::SubmitTransactionResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Spend)?;
         let request = request.into_inner();
         let txn: Transaction = request
             .transaction
@@ -530,7 +563,10 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
             },
             TxStorageResponse::NotStored |
             TxStorageResponse::NotStoredOrphan |
-            TxStorageResponse::NotStoredTimeLocked => tari_rpc::SubmitTransactionResponse {
+            TxStorageResponse::NotStoredTimeLocked |
+            TxStorageResponse::NotStoredPolicyViolation(_) |
+            TxStorageResponse::NotStoredExpired |
+            TxStorageResponse::NotStoredConflict => tari_rpc::SubmitTransactionResponse {
                 result: tari_rpc::SubmitTransactionResult::Rejected.into(),
             },
         };
@@ -543,6 +579,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::TransactionStateRequest>,
     ) -> Result<Response<tari_rpc::TransactionStateResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let request = request.into_inner();
         let excess_sig: Signature = request
             .excess_sig
@@ -596,7 +633,10 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
             },
             TxStorageResponse::NotStored |
             TxStorageResponse::NotStoredOrphan |
-            TxStorageResponse::NotStoredTimeLocked => tari_rpc::TransactionStateResponse {
+            TxStorageResponse::NotStoredTimeLocked |
+            TxStorageResponse::NotStoredPolicyViolation(_) |
+            TxStorageResponse::NotStoredExpired |
+            TxStorageResponse::NotStoredConflict => tari_rpc::TransactionStateResponse {
                 result: tari_rpc::TransactionLocation::NotStored.into(),
             },
         };
@@ -607,8 +647,9 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
 
     async fn get_peers(
         &self,
-        _request: Request<tari_rpc::GetPeersRequest>,
+        request: Request<tari_rpc::GetPeersRequest>,
     ) -> Result<Response<Self::GetPeersStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(target: LOG_TARGET, "Incoming GRPC request for get all peers");
 
         let peers = self
@@ -646,6 +687,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::GetBlocksRequest>,
     ) -> Result<Response<Self::GetBlocksStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let request = request.into_inner();
         debug!(
             target: LOG_TARGET,
@@ -708,8 +750,9 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
 
     async fn get_tip_info(
         &self,
-        _request: Request<tari_rpc::Empty>,
+        request: Request<tari_rpc::Empty>,
     ) -> Result<Response<tari_rpc::TipInfoResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(target: LOG_TARGET, "Incoming GRPC request for BN tip data");
 
         let mut handler = self.node_service.clone();
@@ -734,6 +777,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::SearchKernelsRequest>,
     ) -> Result<Response<Self::SearchKernelsStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(target: LOG_TARGET, "Incoming GRPC request for SearchKernels");
         let request = request.into_inner();
 
@@ -787,6 +831,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::FetchMatchingUtxosRequest>,
     ) -> Result<Response<Self::FetchMatchingUtxosStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(target: LOG_TARGET, "Incoming GRPC request for FetchMatchingUtxos");
         let request = request.into_inner();
 
@@ -842,6 +887,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::HeightRequest>,
     ) -> Result<Response<tari_rpc::CalcTimingResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(
             target: LOG_TARGET,
             "Incoming GRPC request for deprecated GetCalcTiming. Forwarding to GetBlockTiming.",
@@ -857,6 +903,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::HeightRequest>,
     ) -> Result<Response<tari_rpc::BlockTimingResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let request = request.into_inner();
         debug!(
             target: LOG_TARGET,
@@ -885,8 +932,9 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
 
     async fn get_constants(
         &self,
-        _request: Request<tari_rpc::Empty>,
+        request: Request<tari_rpc::Empty>,
     ) -> Result<Response<tari_rpc::ConsensusConstants>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(target: LOG_TARGET, "Incoming GRPC request for GetConstants",);
         debug!(target: LOG_TARGET, "Sending GetConstants response to client");
         // TODO: Switch to request height
@@ -899,6 +947,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::BlockGroupRequest>,
     ) -> Result<Response<tari_rpc::BlockGroupResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         get_block_group(self.node_service.clone(), request, BlockGroupType::BlockSize).await
     }
 
@@ -906,17 +955,20 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::BlockGroupRequest>,
     ) -> Result<Response<tari_rpc::BlockGroupResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         get_block_group(self.node_service.clone(), request, BlockGroupType::BlockFees).await
     }
 
-    async fn get_version(&self, _request: Request<tari_rpc::Empty>) -> Result<Response<tari_rpc::StringValue>, Status> {
+    async fn get_version(&self, request: Request<tari_rpc::Empty>) -> Result<Response<tari_rpc::StringValue>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         Ok(Response::new(consts::APP_VERSION.to_string().into()))
     }
 
     async fn check_for_updates(
         &self,
-        _request: Request<tari_rpc::Empty>,
+        request: Request<tari_rpc::Empty>,
     ) -> Result<Response<tari_rpc::SoftwareUpdate>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let mut resp = tari_rpc::SoftwareUpdate::default();
 
         if let Some(ref update) = *self.software_updater.new_update_notifier().borrow() {
@@ -933,6 +985,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::GetBlocksRequest>,
     ) -> Result<Response<Self::GetTokensInCirculationStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(target: LOG_TARGET, "Incoming GRPC request for GetTokensInCirculation",);
         let request = request.into_inner();
         let mut heights = request.heights;
@@ -991,8 +1044,9 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
 
     async fn get_sync_info(
         &self,
-        _request: Request<tari_rpc::Empty>,
+        request: Request<tari_rpc::Empty>,
     ) -> Result<Response<tari_rpc::SyncInfoResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(target: LOG_TARGET, "Incoming GRPC request for BN sync data");
 
         let mut channel = self.state_machine_handle.get_status_info_watch();
@@ -1030,6 +1084,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         &self,
         request: Request<tari_rpc::GetHeaderByHashRequest>,
     ) -> Result<Response<tari_rpc::BlockHeaderResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let tari_rpc::GetHeaderByHashRequest { hash } = request.into_inner();
         let mut node_service = self.node_service.clone();
         let hash_hex = hash.to_hex();
@@ -1057,7 +1112,8 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         }
     }
 
-    async fn identify(&self, _: Request<tari_rpc::Empty>) -> Result<Response<tari_rpc::NodeIdentity>, Status> {
+    async fn identify(&self, request: Request<tari_rpc::Empty>) -> Result<Response<tari_rpc::NodeIdentity>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let identity = self.comms.node_identity_ref();
         Ok(Response::new(tari_rpc::NodeIdentity {
             public_key: identity.public_key().to_vec(),
@@ -1068,8 +1124,9 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
 
     async fn get_network_status(
         &self,
-        _: Request<tari_rpc::Empty>,
+        request: Request<tari_rpc::Empty>,
     ) -> Result<Response<tari_rpc::NetworkStatusResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let status = self
             .comms
             .connectivity()
@@ -1095,8 +1152,9 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
 
     async fn list_connected_peers(
         &self,
-        _: Request<tari_rpc::Empty>,
+        request: Request<tari_rpc::Empty>,
     ) -> Result<Response<tari_rpc::ListConnectedPeersResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let mut connectivity = self.comms.connectivity();
         let peer_manager = self.comms.peer_manager();
         let connected_peers = connectivity
@@ -1120,6 +1178,356 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
 
         Ok(Response::new(resp))
     }
+
+    async fn get_latency_stats(
+        &self,
+        request: Request<tari_rpc::Empty>,
+    ) -> Result<Response<tari_rpc::LatencyStatsResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
+        let mut liveness = self.liveness.clone();
+        let peers = liveness
+            .get_all_latency_stats()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let estimate = liveness
+            .get_network_time_estimate()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        let mut resp = tari_rpc::LatencyStatsResponse {
+            peers: peers
+                .into_iter()
+                .map(|(node_id, stats)| tari_rpc::PeerLatencyStats {
+                    node_id: node_id.to_vec(),
+                    num_samples: stats.num_samples as u32,
+                    avg_ms: stats.avg_ms,
+                    p50_ms: stats.p50_ms,
+                    p90_ms: stats.p90_ms,
+                    p99_ms: stats.p99_ms,
+                    max_ms: stats.max_ms,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        if let Some(estimate) = estimate {
+            resp.network_time_num_peers = estimate.num_peers as u32;
+            resp.network_time_offset_ms = estimate.offset_ms;
+            resp.network_adjusted_time_ms = estimate.adjusted_time().timestamp_millis() as u64;
+        }
+
+        Ok(Response::new(resp))
+    }
+
+    async fn estimate_fee_per_gram(
+        &self,
+        request: Request<tari_rpc::EstimateFeePerGramRequest>,
+    ) -> Result<Response<tari_rpc::EstimateFeePerGramResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
+        let target_blocks = request.into_inner().target_blocks;
+        if target_blocks == 0 || target_blocks > MAX_TARGET_BLOCKS {
+            return Err(Status::invalid_argument(format!(
+                "target_blocks must be between 1 and {}",
+                MAX_TARGET_BLOCKS
+            )));
+        }
+        let estimate = self
+            .fee_estimation
+            .clone()
+            .estimate_fee_per_gram(target_blocks)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(tari_rpc::EstimateFeePerGramResponse {
+            fee_per_gram: estimate.fee_per_gram.into(),
+            target_blocks: estimate.target_blocks,
+            mempool_weight: estimate.mempool_weight,
+            num_blocks_analysed: estimate.num_blocks_analysed as u64,
+        }))
+    }
+
+    async fn rewind_blockchain(
+        &self,
+        request: Request<tari_rpc::RewindBlockchainRequest>,
+    ) -> Result<Response<tari_rpc::RewindBlockchainResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Admin)?;
+        let request = request.into_inner();
+        if !request.confirm {
+            return Err(Status::failed_precondition(
+                "Rewind must be confirmed by setting `confirm`",
+            ));
+        }
+        warn!(
+            target: LOG_TARGET,
+            "Rewinding blockchain to height {} (requested via gRPC)", request.height
+        );
+        let blocks = self
+            .blockchain_db
+            .rewind_to_height(request.height)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        self.rewind_response(blocks).await
+    }
+
+    async fn invalidate_block(
+        &self,
+        request: Request<tari_rpc::InvalidateBlockRequest>,
+    ) -> Result<Response<tari_rpc::RewindBlockchainResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Admin)?;
+        let request = request.into_inner();
+        if !request.confirm {
+            return Err(Status::failed_precondition(
+                "Block invalidation must be confirmed by setting `confirm`",
+            ));
+        }
+        warn!(
+            target: LOG_TARGET,
+            "Invalidating block {} (requested via gRPC)",
+            request.hash.to_hex()
+        );
+        let blocks = self
+            .blockchain_db
+            .invalidate_block(request.hash)
+            .await
+            .map_err(|err| match err {
+                ChainStorageError::ValueNotFound { .. } => Status::not_found(err.to_string()),
+                ChainStorageError::InvalidOperation(_) => Status::invalid_argument(err.to_string()),
+                _ => Status::internal(err.to_string()),
+            })?;
+        self.rewind_response(blocks).await
+    }
+
+    async fn get_utxo_mmr_proofs(
+        &self,
+        request: Request<tari_rpc::GetMmrProofsRequest>,
+    ) -> Result<Response<tari_rpc::GetUtxoMmrProofsResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
+        let hashes = request.into_inner().hashes;
+        debug!(
+            target: LOG_TARGET,
+            "Incoming GRPC request for GetUtxoMmrProofs ({} outputs)",
+            hashes.len()
+        );
+        check_mmr_proof_hashes(&hashes)?;
+
+        let utxo_proofs = self
+            .blockchain_db
+            .fetch_utxo_mmr_proofs(hashes)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let proofs = utxo_proofs
+            .proofs
+            .into_iter()
+            .map(|proof| {
+                Ok(tari_rpc::UtxoMmrProof {
+                    output: Some(proof.output.into()),
+                    mmr_position: proof.mmr_position,
+                    merkle_proof: bincode::serialize(&proof.merkle_proof)?,
+                })
+            })
+            .collect::<Result<Vec<_>, bincode::Error>>()
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(Response::new(tari_rpc::GetUtxoMmrProofsResponse {
+            header: Some(utxo_proofs.header.into()),
+            output_mmr_root: utxo_proofs.output_mmr_root,
+            deleted_bitmap: utxo_proofs.deleted_bitmap,
+            proofs,
+        }))
+    }
+
+    async fn get_kernel_mmr_proofs(
+        &self,
+        request: Request<tari_rpc::GetMmrProofsRequest>,
+    ) -> Result<Response<tari_rpc::GetKernelMmrProofsResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
+        let hashes = request.into_inner().hashes;
+        debug!(
+            target: LOG_TARGET,
+            "Incoming GRPC request for GetKernelMmrProofs ({} kernels)",
+            hashes.len()
+        );
+        check_mmr_proof_hashes(&hashes)?;
+
+        let kernel_proofs = self
+            .blockchain_db
+            .fetch_kernel_mmr_proofs(hashes)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let proofs = kernel_proofs
+            .proofs
+            .into_iter()
+            .map(|proof| {
+                Ok(tari_rpc::KernelMmrProof {
+                    kernel: Some(proof.kernel.into()),
+                    mmr_position: proof.mmr_position,
+                    merkle_proof: bincode::serialize(&proof.merkle_proof)?,
+                })
+            })
+            .collect::<Result<Vec<_>, bincode::Error>>()
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(Response::new(tari_rpc::GetKernelMmrProofsResponse {
+            header: Some(kernel_proofs.header.into()),
+            proofs,
+        }))
+    }
+
+    async fn get_asset_registrations(
+        &self,
+        request: Request<tari_rpc::GetAssetRegistrationsRequest>,
+    ) -> Result<Response<tari_rpc::GetAssetRegistrationsResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
+        let request = request.into_inner();
+        debug!(
+            target: LOG_TARGET,
+            "Incoming GRPC request for GetAssetRegistrations from MMR position {}", request.start_mmr_position
+        );
+        let registrations = self
+            .blockchain_db
+            .fetch_asset_registrations(request.start_mmr_position, assets_page_size(request.limit))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(Response::new(tari_rpc::GetAssetRegistrationsResponse {
+            registrations: registrations.into_iter().map(to_grpc_asset_output).collect(),
+        }))
+    }
+
+    async fn get_asset_tokens(
+        &self,
+        request: Request<tari_rpc::GetAssetTokensRequest>,
+    ) -> Result<Response<tari_rpc::GetAssetTokensResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
+        let request = request.into_inner();
+        let asset_public_key = PublicKey::from_bytes(&request.asset_public_key)
+            .map_err(|err| Status::invalid_argument(format!("Invalid asset public key: {}", err)))?;
+        debug!(
+            target: LOG_TARGET,
+            "Incoming GRPC request for GetAssetTokens of asset {} from MMR position {}",
+            asset_public_key.to_hex(),
+            request.start_mmr_position
+        );
+        let (registration, tokens) = self
+            .blockchain_db
+            .fetch_asset_tokens(
+                asset_public_key,
+                request.start_mmr_position,
+                assets_page_size(request.limit),
+            )
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(Response::new(tari_rpc::GetAssetTokensResponse {
+            registration: registration.map(to_grpc_asset_output),
+            tokens: tokens.into_iter().map(to_grpc_asset_output).collect(),
+        }))
+    }
+
+    async fn reindex_database(
+        &self,
+        request: Request<tari_rpc::ReindexDatabaseRequest>,
+    ) -> Result<Response<tari_rpc::Empty>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Admin)?;
+        if !request.into_inner().confirm {
+            return Err(Status::failed_precondition(
+                "Reindex must be confirmed by setting `confirm`",
+            ));
+        }
+        info!(target: LOG_TARGET, "Rebuilding database indexes (requested via gRPC)");
+        self.blockchain_db
+            .rebuild_secondary_indexes()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(tari_rpc::Empty {}))
+    }
+
+    async fn set_log_level(
+        &self,
+        request: Request<tari_rpc::SetLogLevelRequest>,
+    ) -> Result<Response<tari_rpc::Empty>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Admin)?;
+        let tari_rpc::SetLogLevelRequest { target, level } = request.into_inner();
+        tari_common::set_log_level(&target, &level).map_err(|err| match err {
+            LoggingError::InvalidLevel(_) => Status::invalid_argument(err.to_string()),
+            _ => Status::internal(err.to_string()),
+        })?;
+        info!(
+            target: LOG_TARGET,
+            "Log level for `{}` set to {} (requested via gRPC)", target, level
+        );
+        Ok(Response::new(tari_rpc::Empty {}))
+    }
+
+    async fn reload_config(
+        &self,
+        request: Request<tari_rpc::Empty>,
+    ) -> Result<Response<tari_rpc::ReloadConfigResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Admin)?;
+        info!(target: LOG_TARGET, "Reloading configuration (requested via gRPC)");
+        let reloader = self.config_reloader.clone();
+        let report = task::spawn_blocking(move || reloader.reload())
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .map_err(|err| Status::failed_precondition(format!("Could not reload configuration: {:?}", err)))?;
+        Ok(Response::new(tari_rpc::ReloadConfigResponse {
+            applied: report.applied,
+            restart_required: report.restart_required,
+            failed: report.failed,
+        }))
+    }
+}
+
+impl BaseNodeGrpcServer {
+    async fn rewind_response(
+        &self,
+        blocks: Vec<Arc<ChainBlock>>,
+    ) -> Result<Response<tari_rpc::RewindBlockchainResponse>, Status> {
+        let num_blocks_removed = blocks.len() as u64;
+        if !blocks.is_empty() {
+            self.node_service
+                .publish_block_event(BlockEvent::BlockSyncRewind(blocks));
+        }
+        let metadata = self
+            .blockchain_db
+            .get_chain_metadata()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(tari_rpc::RewindBlockchainResponse {
+            num_blocks_removed,
+            height: metadata.height_of_longest_chain(),
+        }))
+    }
+}
+
+fn check_mmr_proof_hashes(hashes: &[Vec<u8>]) -> Result<(), Status> {
+    if hashes.is_empty() {
+        return Err(Status::invalid_argument("No hashes were provided"));
+    }
+    if hashes.len() > GET_MMR_PROOFS_MAX_HASHES {
+        return Err(Status::invalid_argument(format!(
+            "Cannot request MMR proofs for more than {} hashes",
+            GET_MMR_PROOFS_MAX_HASHES
+        )));
+    }
+    if hashes.iter().any(|hash| hash.len() != 32) {
+        return Err(Status::invalid_argument("Hashes must be 32 bytes"));
+    }
+    Ok(())
+}
+
+fn assets_page_size(limit: u32) -> usize {
+    match limit as usize {
+        0 => GET_ASSETS_PAGE_SIZE,
+        limit => cmp::min(limit, GET_ASSETS_PAGE_SIZE),
+    }
+}
+
+fn to_grpc_asset_output(asset_output: AssetOutput) -> tari_rpc::AssetOutput {
+    tari_rpc::AssetOutput {
+        output: Some(asset_output.output.into()),
+        mmr_position: asset_output.mmr_position,
+        mined_height: asset_output.mined_height,
+        header_hash: asset_output.header_hash,
+    }
 }
 
 enum BlockGroupType {
@@ -0,0 +1,64 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use hyper::StatusCode;
+use tari_core::{base_node::comms_interface::CommsInterfaceError, mempool::service::MempoolServiceError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HttpApiError {
+    #[error("Only GET requests are supported")]
+    MethodNotAllowed,
+    #[error("Unknown endpoint")]
+    UnknownEndpoint,
+    #[error("Invalid {0}")]
+    InvalidArgument(&'static str),
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    #[error("Node service error: {0}")]
+    CommsInterfaceError(#[from] CommsInterfaceError),
+    #[error("Mempool service error: {0}")]
+    MempoolServiceError(#[from] MempoolServiceError),
+    #[error("Could not serialize response: {0}")]
+    SerializationError(#[from] serde_json::Error),
+    #[error("Could not build response")]
+    ResponseBuilder,
+}
+
+impl HttpApiError {
+    pub fn status_code(&self) -> StatusCode {
+        use HttpApiError::*;
+        match self {
+            MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            UnknownEndpoint | NotFound(_) => StatusCode::NOT_FOUND,
+            InvalidArgument(_) => StatusCode::BAD_REQUEST,
+            CommsInterfaceError(_) | MempoolServiceError(_) | SerializationError(_) | ResponseBuilder => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+
+    /// Returns true if the error was caused by the node rather than the request
+    pub fn is_internal(&self) -> bool {
+        self.status_code().is_server_error()
+    }
+}
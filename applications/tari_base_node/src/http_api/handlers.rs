@@ -0,0 +1,172 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{HttpApi, HttpApiError};
+use serde::Serialize;
+use tari_core::{
+    blocks::Block,
+    chain_storage::HistoricalBlock,
+    crypto::tari_utilities::{hex::Hex, Hashable},
+    mempool::TxStorageResponse,
+    transactions::{
+        transaction::{Transaction, TransactionKernel},
+        types::{PrivateKey, PublicKey, Signature},
+    },
+};
+
+#[derive(Serialize)]
+struct TipResponse {
+    height_of_longest_chain: u64,
+    best_block: String,
+    pruning_horizon: u64,
+    pruned_height: u64,
+    /// A decimal string, as the value does not fit in a JSON number
+    accumulated_difficulty: String,
+    initial_sync_achieved: bool,
+}
+
+#[derive(Serialize)]
+struct BlockResponse<'a> {
+    hash: String,
+    confirmations: u64,
+    block: &'a Block,
+}
+
+impl<'a> From<&'a HistoricalBlock> for BlockResponse<'a> {
+    fn from(historical_block: &'a HistoricalBlock) -> Self {
+        Self {
+            hash: historical_block.block().hash().to_hex(),
+            confirmations: historical_block.confirmations(),
+            block: historical_block.block(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status")]
+enum KernelResponse<'a> {
+    Mined {
+        block_hash: String,
+        block_height: u64,
+        confirmations: u64,
+        kernel: &'a TransactionKernel,
+    },
+    Mempool {
+        storage: TxStorageResponse,
+    },
+}
+
+#[derive(Serialize)]
+struct MempoolResponse {
+    transactions: Vec<Transaction>,
+}
+
+impl HttpApi {
+    pub(super) async fn get_tip(&mut self) -> Result<Vec<u8>, HttpApiError> {
+        let metadata = self.node_service.get_metadata().await?;
+        let initial_sync_achieved = self.state_machine_handle.get_status_info_watch().borrow().bootstrapped;
+        to_json(&TipResponse {
+            height_of_longest_chain: metadata.height_of_longest_chain(),
+            best_block: metadata.best_block().to_hex(),
+            pruning_horizon: metadata.pruning_horizon(),
+            pruned_height: metadata.pruned_height(),
+            accumulated_difficulty: metadata.accumulated_difficulty().to_string(),
+            initial_sync_achieved,
+        })
+    }
+
+    pub(super) async fn get_block_by_height(&mut self, height: &str) -> Result<Vec<u8>, HttpApiError> {
+        let height = height
+            .parse::<u64>()
+            .map_err(|_| HttpApiError::InvalidArgument("block height"))?;
+        let blocks = self.node_service.get_blocks(vec![height]).await?;
+        let block = blocks.first().ok_or(HttpApiError::NotFound("Block"))?;
+        to_json(&BlockResponse::from(block))
+    }
+
+    pub(super) async fn get_block_by_hash(&mut self, hash: &str) -> Result<Vec<u8>, HttpApiError> {
+        let hash = Vec::<u8>::from_hex(hash).map_err(|_| HttpApiError::InvalidArgument("block hash"))?;
+        let block = self
+            .node_service
+            .get_block_by_hash(hash)
+            .await?
+            .ok_or(HttpApiError::NotFound("Block"))?;
+        to_json(&BlockResponse::from(&block))
+    }
+
+    pub(super) async fn get_kernel(&mut self, public_nonce: &str, signature: &str) -> Result<Vec<u8>, HttpApiError> {
+        let public_nonce =
+            PublicKey::from_hex(public_nonce).map_err(|_| HttpApiError::InvalidArgument("kernel public nonce"))?;
+        let signature =
+            PrivateKey::from_hex(signature).map_err(|_| HttpApiError::InvalidArgument("kernel signature"))?;
+        let excess_sig = Signature::new(public_nonce, signature);
+
+        let blocks = self
+            .node_service
+            .get_blocks_with_kernels(vec![excess_sig.clone()])
+            .await?;
+        let mined = blocks.first().and_then(|historical_block| {
+            historical_block
+                .block()
+                .body
+                .kernels()
+                .iter()
+                .find(|kernel| kernel.excess_sig == excess_sig)
+                .map(|kernel| (historical_block, kernel))
+        });
+        if let Some((historical_block, kernel)) = mined {
+            return to_json(&KernelResponse::Mined {
+                block_hash: historical_block.block().hash().to_hex(),
+                block_height: historical_block.header().height,
+                confirmations: historical_block.confirmations(),
+                kernel,
+            });
+        }
+
+        let storage = self
+            .mempool_service
+            .get_transaction_state_by_excess_sig(excess_sig)
+            .await?;
+        if !storage.is_stored() {
+            return Err(HttpApiError::NotFound("Kernel"));
+        }
+        to_json(&KernelResponse::Mempool { storage })
+    }
+
+    pub(super) async fn get_utxo(&mut self, hash: &str) -> Result<Vec<u8>, HttpApiError> {
+        let hash = Vec::<u8>::from_hex(hash).map_err(|_| HttpApiError::InvalidArgument("output hash"))?;
+        let outputs = self.node_service.fetch_matching_utxos(vec![hash]).await?;
+        let output = outputs.first().ok_or(HttpApiError::NotFound("Unspent output"))?;
+        to_json(output)
+    }
+
+    pub(super) async fn get_mempool(&mut self) -> Result<Vec<u8>, HttpApiError> {
+        let state = self.mempool_service.get_mempool_state().await?;
+        to_json(&MempoolResponse {
+            transactions: state.unconfirmed_pool,
+        })
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, HttpApiError> {
+    Ok(serde_json::to_vec(value)?)
+}
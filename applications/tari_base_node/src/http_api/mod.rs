@@ -0,0 +1,199 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A read-only HTTP JSON API for block explorers and other web clients that cannot use gRPC. The API is served from
+//! the same local node and mempool services as the gRPC server.
+//!
+//! Every endpoint responds to `GET` requests with a JSON body:
+//! - `/tip` - the chain metadata and whether the node has completed its initial sync
+//! - `/blocks/height/{height}` - the main chain block at the given height
+//! - `/blocks/hash/{hash}` - the block with the given hex encoded hash
+//! - `/kernels/{public_nonce}/{signature}` - the block that contains the kernel with the given hex encoded excess
+//!   signature or, if the kernel has not been mined, its transaction's state in the mempool
+//! - `/utxos/{hash}` - the unspent output with the given hex encoded hash
+//! - `/mempool` - the transactions in the mempool's unconfirmed pool
+//!
+//! Each response has an `ETag` computed from its body. A client that sends the tag back in `If-None-Match` receives an
+//! empty `304 Not Modified` response if the content has not changed.
+
+mod error;
+mod handlers;
+
+pub use error::HttpApiError;
+
+use crate::builder::BaseNodeContext;
+use futures::FutureExt;
+use hyper::{
+    header::{HeaderValue, CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+    service::{make_service_fn, service_fn},
+    Body,
+    Method,
+    Request,
+    Response,
+    Server,
+    StatusCode,
+};
+use log::*;
+use serde_json::json;
+use std::{
+    collections::hash_map::DefaultHasher,
+    convert::Infallible,
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+};
+use tari_core::{
+    base_node::{LocalNodeCommsInterface, StateMachineHandle},
+    mempool::service::LocalMempoolService,
+};
+use tari_shutdown::ShutdownSignal;
+
+const LOG_TARGET: &str = "base_node::http_api";
+
+/// The handles to the node services that the HTTP API reads from
+#[derive(Clone)]
+pub struct HttpApi {
+    node_service: LocalNodeCommsInterface,
+    mempool_service: LocalMempoolService,
+    state_machine_handle: StateMachineHandle,
+}
+
+impl HttpApi {
+    pub fn from_base_node_context(ctx: &BaseNodeContext) -> Self {
+        Self {
+            node_service: ctx.local_node(),
+            mempool_service: ctx.local_mempool(),
+            state_machine_handle: ctx.state_machine(),
+        }
+    }
+}
+
+/// Serves the HTTP API on `address` until the shutdown signal is triggered
+pub async fn start(address: SocketAddr, api: HttpApi, shutdown_signal: ShutdownSignal) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let api = api.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |request| handle_request(api.clone(), request))) }
+    });
+    let server = Server::try_bind(&address)?.serve(make_service);
+    info!(target: LOG_TARGET, "HTTP API listening on {}", address);
+    server.with_graceful_shutdown(shutdown_signal.map(|_| ())).await?;
+    info!(target: LOG_TARGET, "HTTP API has shut down");
+    Ok(())
+}
+
+async fn handle_request(mut api: HttpApi, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::GET {
+        return Ok(error_response(&HttpApiError::MethodNotAllowed));
+    }
+
+    let path = request.uri().path().trim_matches('/');
+    let segments = path.split('/').collect::<Vec<_>>();
+    debug!(target: LOG_TARGET, "Incoming HTTP API request for /{}", path);
+    let result = match segments.as_slice() {
+        ["tip"] => api.get_tip().await,
+        ["blocks", "height", height] => api.get_block_by_height(height).await,
+        ["blocks", "hash", hash] => api.get_block_by_hash(hash).await,
+        ["kernels", public_nonce, signature] => api.get_kernel(public_nonce, signature).await,
+        ["utxos", hash] => api.get_utxo(hash).await,
+        ["mempool"] => api.get_mempool().await,
+        _ => Err(HttpApiError::UnknownEndpoint),
+    };
+
+    let response = match result {
+        Ok(body) => json_response(body, request.headers().get(IF_NONE_MATCH)),
+        Err(err) => {
+            if err.is_internal() {
+                error!(target: LOG_TARGET, "HTTP API request for /{} failed: {}", path, err);
+            }
+            error_response(&err)
+        },
+    };
+    Ok(response)
+}
+
+fn json_response(body: Vec<u8>, if_none_match: Option<&HeaderValue>) -> Response<Body> {
+    let etag = etag_for(&body);
+    let not_modified = if_none_match
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| etag_matches(value, &etag));
+    let (status, body) = if not_modified {
+        (StatusCode::NOT_MODIFIED, Body::empty())
+    } else {
+        (StatusCode::OK, Body::from(body))
+    };
+
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .header(ETAG, etag)
+        // Clients may cache responses but must revalidate them, because most of the content changes as the chain grows
+        .header(CACHE_CONTROL, "no-cache")
+        .body(body)
+        .unwrap_or_else(|_| error_response(&HttpApiError::ResponseBuilder))
+}
+
+fn error_response(err: &HttpApiError) -> Response<Body> {
+    let body = json!({ "error": err.to_string() }).to_string();
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = err.status_code();
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// A strong entity tag for a response body
+fn etag_for(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Returns true if the `If-None-Match` header value matches the entity tag
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|tag| {
+        // Weak comparison is used for If-None-Match, so a weak tag matches its strong equivalent
+        tag == "*" || tag.trim_start_matches("W/") == etag
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn etag_depends_on_body() {
+        assert_eq!(etag_for(b"{}"), etag_for(b"{}"));
+        assert_ne!(etag_for(b"{}"), etag_for(b"[]"));
+        assert!(etag_for(b"{}").starts_with('"'));
+    }
+
+    #[test]
+    fn it_matches_if_none_match_values() {
+        let etag = etag_for(b"{}");
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("W/{}", etag), &etag));
+        assert!(etag_matches(&format!("\"abc\", {}", etag), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"abc\"", &etag));
+        assert!(!etag_matches("", &etag));
+    }
+}
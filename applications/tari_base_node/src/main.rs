@@ -89,16 +89,20 @@ mod bootstrap;
 mod builder;
 mod cli;
 mod command_handler;
+mod config_watcher;
 mod grpc;
+mod http_api;
 mod parser;
 mod recovery;
 mod status_line;
 mod utils;
+mod watchdog;
 
-use crate::command_handler::CommandHandler;
-use futures::{pin_mut, FutureExt};
+use crate::{command_handler::CommandHandler, config_watcher::ConfigReloader, watchdog::Watchdog};
+use futures::{future, pin_mut, FutureExt};
 use log::*;
 use parser::Parser;
+use rpassword::prompt_password_stdout;
 use rustyline::{config::OutputStreamType, error::ReadlineError, CompletionType, Config, EditMode, Editor};
 use std::{
     net::SocketAddr,
@@ -107,17 +111,34 @@ use std::{
     time::{Duration, Instant},
 };
 use tari_app_utilities::{
-    identity_management::setup_node_identity,
+    identity_management::{
+        change_identity_passphrase,
+        export_node_identity_and_peers,
+        get_identity_passphrase,
+        import_node_identity_and_peers,
+        setup_node_identity,
+    },
     initialization::init_configuration,
-    utilities::{setup_runtime, ExitCodes},
+    utilities::{create_grpc_server_builder, setup_runtime, ExitCodes},
+};
+use tari_common::{
+    configuration::bootstrap::ApplicationType,
+    set_global_log_field,
+    ConfigBootstrap,
+    DatabaseType,
+    GlobalConfig,
 };
-use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap, GlobalConfig};
 use tari_comms::{peer_manager::PeerFeatures, tor::HiddenServiceControllerError};
-use tari_shutdown::{Shutdown, ShutdownSignal};
+use tari_core::chain_storage::dry_run_lmdb_migrations;
+use tari_shutdown::{Shutdown, ShutdownOrchestrator, ShutdownSignal, ShutdownStage};
 use tokio::{runtime, task, time};
 use tonic::transport::Server;
 
 const LOG_TARGET: &str = "base_node::app";
+/// The name of the LMDB peer database
+pub const PEER_DATABASE_NAME: &str = "peers";
+/// The time allowed for the gRPC, metrics and HTTP API servers to stop
+const GRPC_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
 /// Application entry point
 fn main() {
     if let Err(exit_code) = main_inner() {
@@ -150,13 +171,39 @@ fn main_inner() -> Result<(), ExitCodes> {
 
 /// Sets up the base node and runs the cli_loop
 async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) -> Result<(), ExitCodes> {
+    if bootstrap.change_identity_passphrase {
+        return change_node_identity_passphrase(&node_config);
+    }
+
+    let identity_passphrase =
+        get_identity_passphrase(&node_config.base_node_identity_file, bootstrap.non_interactive_mode)?;
+
+    if let Some(ref import_file) = bootstrap.import_identity {
+        let num_peers = import_node_identity_and_peers(
+            import_file,
+            &node_config.base_node_identity_file,
+            &node_config.peer_db_path,
+            PEER_DATABASE_NAME,
+            identity_passphrase.as_deref(),
+        )
+        .await?;
+        println!(
+            "Imported node identity and {} peer(s) from '{}'. Done.",
+            num_peers,
+            import_file.to_string_lossy()
+        );
+        return Ok(());
+    }
+
     // Load or create the Node identity
     let node_identity = setup_node_identity(
         &node_config.base_node_identity_file,
         &node_config.public_address,
         bootstrap.create_id,
         PeerFeatures::COMMUNICATION_NODE,
+        identity_passphrase.as_deref(),
     )?;
+    set_global_log_field("node_id", Some(node_identity.node_id().to_string()));
 
     // Exit if create_id or init arguments were run
     if bootstrap.create_id {
@@ -167,8 +214,33 @@ async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) ->
         );
         return Ok(());
     }
-    // This is the main and only shutdown trigger for the system.
+
+    if let Some(ref export_file) = bootstrap.export_identity {
+        let num_peers = export_node_identity_and_peers(
+            &node_config.base_node_identity_file,
+            &node_config.peer_db_path,
+            PEER_DATABASE_NAME,
+            export_file,
+            identity_passphrase.as_deref(),
+        )
+        .await?;
+        println!(
+            "Exported node identity and {} peer(s) to '{}'. Done.",
+            num_peers,
+            export_file.to_string_lossy()
+        );
+        return Ok(());
+    }
+
+    // This is the main and only shutdown trigger for the system. Once triggered, the services registered with the
+    // shutdown orchestrator are stopped in dependency order.
     let shutdown = Shutdown::new();
+    let shutdown_signal = shutdown.to_signal();
+    let mut shutdown_orchestrator = ShutdownOrchestrator::new();
+
+    if bootstrap.migrate_db_dry_run {
+        return dry_run_db_migrations(&node_config);
+    }
 
     if bootstrap.rebuild_db {
         info!(target: LOG_TARGET, "Node is in recovery mode, entering recovery");
@@ -188,7 +260,7 @@ async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) ->
     let ctx = builder::configure_and_initialize_node(
         node_config.clone(),
         node_identity,
-        shutdown.to_signal(),
+        &mut shutdown_orchestrator,
         bootstrap.clean_orphans_db,
     )
     .await
@@ -214,17 +286,79 @@ async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) ->
         ExitCodes::UnknownError
     })?;
 
+    let config_reloader = ConfigReloader::new(bootstrap.clone(), &node_config, ctx.mempool());
+    #[cfg(unix)]
+    {
+        let (reload_shutdown_signal, reload_stopped) =
+            shutdown_orchestrator.register("Config watcher", ShutdownStage::RpcServers, GRPC_SHUTDOWN_TIMEOUT);
+        task::spawn(config_watcher::reload_on_hangup(
+            config_reloader.clone(),
+            reload_shutdown_signal,
+            reload_stopped,
+        ));
+    }
+
+    let (watchdog, watchdog_status) = Watchdog::new(&ctx);
+    let (watchdog_shutdown_signal, watchdog_stopped) =
+        shutdown_orchestrator.register("Watchdog", ShutdownStage::RpcServers, GRPC_SHUTDOWN_TIMEOUT);
+    task::spawn(watchdog.run(watchdog_shutdown_signal, watchdog_stopped));
+
     if node_config.grpc_enabled {
         // Go, GRPC, go go
-        let grpc = crate::grpc::base_node_grpc_server::BaseNodeGrpcServer::from_base_node_context(&ctx);
-        task::spawn(run_grpc(grpc, node_config.grpc_base_node_address, shutdown.to_signal()));
+        let grpc =
+            crate::grpc::base_node_grpc_server::BaseNodeGrpcServer::from_base_node_context(&ctx, config_reloader);
+        let grpc_server = create_grpc_server_builder(&node_config, &node_config.grpc_base_node_address)?;
+        let (grpc_shutdown_signal, grpc_stopped) =
+            shutdown_orchestrator.register("gRPC server", ShutdownStage::RpcServers, GRPC_SHUTDOWN_TIMEOUT);
+        let grpc_address = node_config.grpc_base_node_address;
+        task::spawn(async move {
+            let _ = run_grpc(grpc_server, grpc, grpc_address, grpc_shutdown_signal).await;
+            drop(grpc_stopped);
+        });
+    }
+
+    #[cfg(feature = "metrics")]
+    {
+        if let Some(address) = node_config.metrics_server_address {
+            info!(target: LOG_TARGET, "Starting metrics server on {}", address);
+            let (metrics_shutdown_signal, metrics_stopped) =
+                shutdown_orchestrator.register("Metrics server", ShutdownStage::RpcServers, GRPC_SHUTDOWN_TIMEOUT);
+            task::spawn(async move {
+                if let Err(err) = tari_metrics::server::start(address, metrics_shutdown_signal).await {
+                    error!(target: LOG_TARGET, "Metrics server failed: {}", err);
+                }
+                drop(metrics_stopped);
+            });
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    {
+        if node_config.metrics_server_address.is_some() {
+            warn!(
+                target: LOG_TARGET,
+                "metrics_server_address is set but the base node was not built with the `metrics` feature"
+            );
+        }
+    }
+
+    if let Some(address) = node_config.http_api_address {
+        info!(target: LOG_TARGET, "Starting HTTP API on {}", address);
+        let http_api = http_api::HttpApi::from_base_node_context(&ctx);
+        let (http_api_shutdown_signal, http_api_stopped) =
+            shutdown_orchestrator.register("HTTP API", ShutdownStage::RpcServers, GRPC_SHUTDOWN_TIMEOUT);
+        task::spawn(async move {
+            if let Err(err) = http_api::start(address, http_api, http_api_shutdown_signal).await {
+                error!(target: LOG_TARGET, "HTTP API failed: {}", err);
+            }
+            drop(http_api_stopped);
+        });
     }
 
     // Run, node, run!
     if bootstrap.non_interactive_mode {
         println!("Node started in non-interactive mode (pid = {})", process::id());
     } else {
-        let command_handler = Arc::new(CommandHandler::new(runtime::Handle::current(), &ctx));
+        let command_handler = Arc::new(CommandHandler::new(runtime::Handle::current(), &ctx, watchdog_status));
         let parser = Parser::new(command_handler);
         cli::print_banner(parser.get_commands(), 3);
 
@@ -236,21 +370,68 @@ async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) ->
         task::spawn(cli_loop(parser, shutdown));
     }
 
-    ctx.run().await;
+    let stop_node = async move {
+        let _ = shutdown_signal.await;
+        info!(target: LOG_TARGET, "Stopping base node services");
+        shutdown_orchestrator.shutdown().await
+    };
+    let (_, report) = future::join(ctx.run(), stop_node).await;
+    if report.is_clean() {
+        info!(target: LOG_TARGET, "{}", report);
+    } else {
+        warn!(target: LOG_TARGET, "{}", report);
+        println!("{}", report);
+    }
 
     println!("Goodbye!");
     Ok(())
 }
 
+/// Prints the migrations that would be run on the blockchain database the next time the node starts
+fn dry_run_db_migrations(node_config: &GlobalConfig) -> Result<(), ExitCodes> {
+    match node_config.db_type {
+        DatabaseType::LMDB(ref path) => {
+            let report = dry_run_lmdb_migrations(path, node_config.db_config.clone()).map_err(|e| {
+                error!(target: LOG_TARGET, "Could not check the database for migrations: {}", e);
+                ExitCodes::UnknownError
+            })?;
+            println!("{}", report);
+            Ok(())
+        },
+        _ => Err(ExitCodes::ConfigError(
+            "Migrations are only available for LMDB".to_string(),
+        )),
+    }
+}
+
+/// Prompts for the current and new passphrase of the node identity file and re-saves it. An empty new passphrase
+/// saves the identity unencrypted.
+fn change_node_identity_passphrase(node_config: &GlobalConfig) -> Result<(), ExitCodes> {
+    let identity_file = &node_config.base_node_identity_file;
+    let current = get_identity_passphrase(identity_file, false)?;
+    let prompt = |prompt: &str| prompt_password_stdout(prompt).map_err(|e| ExitCodes::IOError(e.to_string()));
+    let new_passphrase = prompt("New node identity passphrase (leave empty to remove encryption): ")?;
+    if new_passphrase != prompt("Confirm new passphrase: ")? {
+        return Err(ExitCodes::InputError("Passphrases don't match!".to_string()));
+    }
+
+    let new_passphrase = Some(new_passphrase).filter(|p| !p.is_empty());
+    change_identity_passphrase(identity_file, current.as_deref(), new_passphrase.as_deref())
+        .map_err(ExitCodes::ConfigError)?;
+    println!("Node identity passphrase changed successfully.");
+    Ok(())
+}
+
 /// Runs the gRPC server
 async fn run_grpc(
+    mut grpc_server: Server,
     grpc: crate::grpc::base_node_grpc_server::BaseNodeGrpcServer,
     grpc_address: SocketAddr,
     interrupt_signal: ShutdownSignal,
 ) -> Result<(), anyhow::Error> {
     info!(target: LOG_TARGET, "Starting GRPC on {}", grpc_address);
 
-    Server::builder()
+    grpc_server
         .add_service(tari_app_grpc::tari_rpc::base_node_server::BaseNodeServer::new(grpc))
         .serve_with_shutdown(grpc_address, interrupt_signal.map(|_| ()))
         .await
@@ -32,7 +32,13 @@ use rustyline::{
     Context,
 };
 use rustyline_derive::{Helper, Highlighter, Validator};
-use std::{str::FromStr, string::ToString, sync::Arc, time::Duration};
+use std::{
+    io::{self, Write},
+    str::FromStr,
+    string::ToString,
+    sync::Arc,
+    time::Duration,
+};
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 use tari_app_utilities::utilities::{
@@ -62,6 +68,12 @@ pub enum BaseNodeCommand {
     DialPeer,
     ResetOfflinePeers,
     RewindBlockchain,
+    InvalidateBlock,
+    ReindexDb,
+    GetDbStats,
+    CompactDb,
+    SetLogLevel,
+    ResetLogLevels,
     BanPeer,
     UnbanPeer,
     UnbanAllPeers,
@@ -206,6 +218,24 @@ impl Parser {
             RewindBlockchain => {
                 self.process_rewind_blockchain(args);
             },
+            InvalidateBlock => {
+                self.process_invalidate_block(args);
+            },
+            ReindexDb => {
+                self.process_reindex_db(args);
+            },
+            GetDbStats => {
+                self.command_handler.get_db_stats();
+            },
+            CompactDb => {
+                self.process_compact_db(args);
+            },
+            SetLogLevel => {
+                self.process_set_log_level(args);
+            },
+            ResetLogLevels => {
+                self.command_handler.reset_log_levels();
+            },
             CheckDb => {
                 self.command_handler.check_db();
             },
@@ -306,8 +336,41 @@ impl Parser {
             },
             RewindBlockchain => {
                 println!("Rewinds the blockchain to the given height.");
-                println!("Usage: {} [new_height]", command);
+                println!("Usage: {} [new_height] [--yes]", command);
                 println!("new_height must be less than the current height.");
+                println!("Pass --yes to skip the confirmation prompt.");
+            },
+            InvalidateBlock => {
+                println!("Removes a block and all blocks built on it from the main chain and the orphan pool.");
+                println!("Usage: {} [block_hash] [--yes]", command);
+                println!("If the block is in the main chain, the chain is first rewound to its parent.");
+                println!("Pass --yes to skip the confirmation prompt.");
+            },
+            ReindexDb => {
+                println!("Rebuilds the kernel and output lookup indexes from the blockchain database.");
+                println!("Usage: {} [--yes]", command);
+                println!("Pass --yes to skip the confirmation prompt.");
+            },
+            GetDbStats => {
+                println!(
+                    "Prints the size of each blockchain database table, the free space and the map usage, and the \
+                     false positive rates of the output and kernel existence filters."
+                );
+            },
+            CompactDb => {
+                println!("Compacts the blockchain database, returning free space to the file system.");
+                println!("Usage: {} [--yes]", command);
+                println!("The node continues to serve reads, but will not process blocks until it completes.");
+                println!("Pass --yes to skip the confirmation prompt.");
+            },
+            SetLogLevel => {
+                println!("Changes the log level of a log target until the node is restarted.");
+                println!("Usage: {} [target] [off|error|warn|info|debug|trace]", command);
+                println!("Use `root` as the target to change the default level.");
+                println!("Without arguments, lists the log levels that have been changed.");
+            },
+            ResetLogLevels => {
+                println!("Restores the log levels in the logging configuration file");
             },
             BanPeer => {
                 println!("Bans a peer");
@@ -672,6 +735,82 @@ impl Parser {
             .next()
             .ok_or("new_height argument required")
             .and_then(|s| u64::from_str(s).map_err(|_| "new_height must be an integer.")));
+        let message = format!(
+            "This will remove all blocks above height {} from the blockchain database.",
+            new_height
+        );
+        if !confirm(&message, args) {
+            return;
+        }
         self.command_handler.rewind_blockchain(new_height);
     }
+
+    fn process_invalidate_block<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let hash = try_or_print!(args
+            .next()
+            .ok_or("block_hash argument required")
+            .and_then(|s| from_hex(s).map_err(|_| "block_hash must be a valid hex string.")));
+        let message = format!(
+            "This will remove block {} and all blocks built on it from the blockchain database.",
+            hash.to_hex()
+        );
+        if !confirm(&message, args) {
+            return;
+        }
+        self.command_handler.invalidate_block(hash);
+    }
+
+    fn process_set_log_level<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let target = match args.next() {
+            Some(target) => target,
+            None => {
+                self.command_handler.list_log_levels();
+                return;
+            },
+        };
+        let level = try_or_print!(args.next().ok_or("level argument required"));
+        self.command_handler.set_log_level(target, level);
+    }
+
+    fn process_reindex_db<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
+        if !confirm(
+            "This will rebuild the kernel and output indexes. The node will not process blocks until it completes.",
+            args,
+        ) {
+            return;
+        }
+        self.command_handler.reindex_db();
+    }
+
+    fn process_compact_db<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
+        if !confirm(
+            "This will write a compacted copy of the blockchain database and replace the database with it. The node \
+             will not process blocks until it completes.",
+            args,
+        ) {
+            return;
+        }
+        self.command_handler.compact_db();
+    }
+}
+
+/// Asks the user to confirm a destructive command, unless `--yes` or `-y` was given as an argument
+fn confirm<'a, I: Iterator<Item = &'a str>>(message: &str, mut args: I) -> bool {
+    if args.any(|arg| arg == "--yes" || arg == "-y") {
+        return true;
+    }
+    println!("{}", message);
+    print!("Are you sure you want to continue? [y/N] ");
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    let confirmed = matches!(answer.trim().to_lowercase().as_str(), "y" | "yes");
+    if !confirmed {
+        println!("Aborted");
+    }
+    confirmed
 }
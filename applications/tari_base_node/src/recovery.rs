@@ -104,6 +104,8 @@ pub async fn run_recovery(node_config: &GlobalConfig) -> Result<(), anyhow::Erro
         orphan_storage_capacity: node_config.orphan_storage_capacity,
         pruning_horizon: node_config.pruning_horizon,
         pruning_interval: node_config.pruned_mode_cleanup_interval,
+        group_commit_max_operations: node_config.db_group_commit_max_operations,
+        group_commit_interval: node_config.db_group_commit_interval,
     };
     let db = BlockchainDatabase::new(
         main_db,
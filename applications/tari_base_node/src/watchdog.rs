@@ -0,0 +1,429 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Watches for a base node that has stopped receiving blocks or has ended up on a different chain to its peers. Alerts
+//! are logged, shown on the status line and, once critical, optionally posted to a webhook.
+
+use crate::{builder::BaseNodeContext, utils::format_duration_basic};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use log::*;
+use std::{
+    collections::HashMap,
+    fmt,
+    fmt::{Display, Formatter},
+    time::{Duration, Instant},
+};
+use tari_common::{configuration::Network, GlobalConfig};
+use tari_comms::peer_manager::NodeId;
+use tari_core::base_node::{
+    chain_metadata_service::{ChainMetadataEvent, ChainMetadataHandle, PeerChainMetadata},
+    comms_interface::CommsInterfaceError,
+    state_machine_service::states::{StateInfo, StatusInfo},
+    LocalNodeCommsInterface,
+};
+use tari_crypto::tari_utilities::Hashable;
+use tari_shutdown::{Shutdown, ShutdownSignal};
+use tokio::{sync::watch, task, time};
+
+const LOG_TARGET: &str = "base_node::app::watchdog";
+
+/// How often the chain tip is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Peers that have not reported their chain metadata for this long are left out of the divergence checks
+const PEER_TIP_MAX_AGE: Duration = Duration::from_secs(600);
+/// The fewest peers that must disagree with the local chain before the node is considered to be on a fork
+const MIN_PEERS_FOR_FORK: usize = 3;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AlertLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl Display for AlertLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertLevel::Ok => f.write_str("OK"),
+            AlertLevel::Warning => f.write_str("WARNING"),
+            AlertLevel::Critical => f.write_str("CRITICAL"),
+        }
+    }
+}
+
+/// The outcome of the latest watchdog check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchdogStatus {
+    pub level: AlertLevel,
+    pub reasons: Vec<String>,
+}
+
+impl WatchdogStatus {
+    pub fn ok() -> Self {
+        Self {
+            level: AlertLevel::Ok,
+            reasons: Vec::new(),
+        }
+    }
+
+    fn raise(&mut self, level: AlertLevel, reason: String) {
+        self.level = self.level.max(level);
+        self.reasons.push(reason);
+    }
+}
+
+impl Display for WatchdogStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.reasons.is_empty() {
+            write!(f, "{}", self.level)
+        } else {
+            write!(f, "{} ({})", self.level, self.reasons.join("; "))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    pub stale_tip_warning: Duration,
+    pub stale_tip_critical: Duration,
+    pub max_tip_divergence: u64,
+    pub webhook_url: Option<String>,
+}
+
+impl From<&GlobalConfig> for WatchdogConfig {
+    fn from(config: &GlobalConfig) -> Self {
+        Self {
+            stale_tip_warning: config.watchdog_stale_tip_warning,
+            stale_tip_critical: config.watchdog_stale_tip_critical,
+            max_tip_divergence: config.watchdog_max_tip_divergence,
+            webhook_url: config.watchdog_webhook_url.clone(),
+        }
+    }
+}
+
+/// What the watchdog knows about the local chain and the chains of its peers at the time of a check
+#[derive(Debug, Clone)]
+struct Observation {
+    /// A syncing node is expected to be behind its peers
+    is_syncing: bool,
+    /// The age of the tip block or, while syncing, the time since the local chain last grew
+    tip_age: Duration,
+    local_height: u64,
+    best_peer_height: Option<u64>,
+    /// The number of peers whose tip is at or below the local height
+    comparable_peers: usize,
+    /// The number of comparable peers whose tip is not on the local chain
+    diverging_peers: usize,
+}
+
+/// Decides how alarming an observation is. The reasons do not include the measured values, so that the status only
+/// changes when the alert does.
+fn assess(config: &WatchdogConfig, observation: &Observation) -> WatchdogStatus {
+    let mut status = WatchdogStatus::ok();
+    if observation.tip_age >= config.stale_tip_critical {
+        status.raise(
+            AlertLevel::Critical,
+            format!("no new block for {}", format_duration_basic(config.stale_tip_critical)),
+        );
+    } else if observation.tip_age >= config.stale_tip_warning {
+        status.raise(
+            AlertLevel::Warning,
+            format!("no new block for {}", format_duration_basic(config.stale_tip_warning)),
+        );
+    }
+
+    let blocks_behind = observation
+        .best_peer_height
+        .map(|height| height.saturating_sub(observation.local_height))
+        .unwrap_or(0);
+    if !observation.is_syncing && blocks_behind > config.max_tip_divergence {
+        status.raise(
+            AlertLevel::Warning,
+            format!(
+                "more than {} blocks behind peers but not syncing",
+                config.max_tip_divergence
+            ),
+        );
+    }
+
+    if observation.diverging_peers * 2 > observation.comparable_peers {
+        if observation.diverging_peers >= MIN_PEERS_FOR_FORK {
+            status.raise(AlertLevel::Critical, "most peers are on a different chain".to_string());
+        } else {
+            status.raise(AlertLevel::Warning, "peers report a different chain tip".to_string());
+        }
+    }
+    status
+}
+
+struct PeerTip {
+    height: u64,
+    best_block: Vec<u8>,
+    received_at: Instant,
+}
+
+/// Periodically checks the chain tip against the clock and the tips reported by peers
+pub struct Watchdog {
+    config: WatchdogConfig,
+    network: Network,
+    node_id: NodeId,
+    node: LocalNodeCommsInterface,
+    state_info: watch::Receiver<StatusInfo>,
+    chain_metadata: ChainMetadataHandle,
+    peer_tips: HashMap<NodeId, PeerTip>,
+    last_height: Option<u64>,
+    last_progress: Instant,
+    status: WatchdogStatus,
+    status_publisher: watch::Sender<WatchdogStatus>,
+}
+
+impl Watchdog {
+    /// Creates the watchdog and a receiver for its status, e.g. for the status line
+    pub fn new(ctx: &BaseNodeContext) -> (Self, watch::Receiver<WatchdogStatus>) {
+        let config = ctx.config();
+        let (status_publisher, status_receiver) = watch::channel(WatchdogStatus::ok());
+        let watchdog = Self {
+            config: WatchdogConfig::from(config.as_ref()),
+            network: config.network,
+            node_id: ctx.base_node_identity().node_id().clone(),
+            node: ctx.local_node(),
+            state_info: ctx.get_state_machine_info_channel(),
+            chain_metadata: ctx.chain_metadata(),
+            peer_tips: HashMap::new(),
+            last_height: None,
+            last_progress: Instant::now(),
+            status: WatchdogStatus::ok(),
+            status_publisher,
+        };
+        (watchdog, status_receiver)
+    }
+
+    pub async fn run(mut self, mut shutdown_signal: ShutdownSignal, _stopped: Shutdown) {
+        let mut interval = time::interval(CHECK_INTERVAL).fuse();
+        let mut chain_metadata_events = self.chain_metadata.get_event_stream().fuse();
+        loop {
+            futures::select! {
+                event = chain_metadata_events.select_next_some() => {
+                    if let Ok(event) = event {
+                        let ChainMetadataEvent::PeerChainMetadataReceived(peers) = &*event;
+                        self.record_peer_tips(peers);
+                    }
+                },
+                _ = interval.select_next_some() => self.check().await,
+                _ = shutdown_signal => break,
+            }
+        }
+    }
+
+    fn record_peer_tips(&mut self, peers: &[PeerChainMetadata]) {
+        let now = Instant::now();
+        for peer in peers {
+            self.peer_tips.insert(peer.node_id.clone(), PeerTip {
+                height: peer.chain_metadata.height_of_longest_chain(),
+                best_block: peer.chain_metadata.best_block().clone(),
+                received_at: now,
+            });
+        }
+    }
+
+    async fn check(&mut self) {
+        match self.observe().await {
+            Ok(observation) => {
+                let status = assess(&self.config, &observation);
+                self.update_status(status, observation.local_height);
+            },
+            Err(err) => warn!(target: LOG_TARGET, "Watchdog could not check the chain tip: {}", err),
+        }
+    }
+
+    async fn observe(&mut self) -> Result<Observation, CommsInterfaceError> {
+        let local_height = self.node.get_metadata().await?.height_of_longest_chain();
+        if self.last_height != Some(local_height) {
+            self.last_height = Some(local_height);
+            self.last_progress = Instant::now();
+        }
+
+        let is_syncing = !matches!(self.state_info.borrow().state_info, StateInfo::Listening(_));
+        let tip_age = if is_syncing {
+            self.last_progress.elapsed()
+        } else {
+            match self.node.get_headers(vec![local_height]).await?.pop() {
+                Some(tip) => (Utc::now() - DateTime::<Utc>::from(tip.timestamp))
+                    .to_std()
+                    .unwrap_or_default(),
+                None => self.last_progress.elapsed(),
+            }
+        };
+
+        self.peer_tips
+            .retain(|_, tip| tip.received_at.elapsed() < PEER_TIP_MAX_AGE);
+        let best_peer_height = self.peer_tips.values().map(|tip| tip.height).max();
+        let comparable = self
+            .peer_tips
+            .values()
+            .filter(|tip| tip.height <= local_height)
+            .collect::<Vec<_>>();
+        let mut heights = comparable.iter().map(|tip| tip.height).collect::<Vec<_>>();
+        heights.sort_unstable();
+        heights.dedup();
+        let local_hashes = if heights.is_empty() {
+            HashMap::new()
+        } else {
+            self.node
+                .get_headers(heights)
+                .await?
+                .into_iter()
+                .map(|header| (header.height, header.hash()))
+                .collect::<HashMap<_, _>>()
+        };
+        let diverging_peers = comparable
+            .iter()
+            .filter(|tip| {
+                local_hashes
+                    .get(&tip.height)
+                    .map(|hash| *hash != tip.best_block)
+                    .unwrap_or(false)
+            })
+            .count();
+
+        Ok(Observation {
+            is_syncing,
+            tip_age,
+            local_height,
+            best_peer_height,
+            comparable_peers: comparable.len(),
+            diverging_peers,
+        })
+    }
+
+    fn update_status(&mut self, status: WatchdogStatus, height: u64) {
+        if status == self.status {
+            return;
+        }
+        match status.level {
+            AlertLevel::Ok => info!(target: LOG_TARGET, "Watchdog alert cleared at height {}", height),
+            AlertLevel::Warning => warn!(target: LOG_TARGET, "Watchdog at height {}: {}", height, status),
+            AlertLevel::Critical => error!(target: LOG_TARGET, "Watchdog at height {}: {}", height, status),
+        }
+        let critical_changed = (status.level == AlertLevel::Critical) != (self.status.level == AlertLevel::Critical);
+        if critical_changed {
+            if let Some(url) = self.config.webhook_url.clone() {
+                let payload = serde_json::json!({
+                    "level": status.level.to_string().to_lowercase(),
+                    "reasons": status.reasons,
+                    "network": self.network.as_str(),
+                    "node_id": self.node_id.to_string(),
+                    "height": height,
+                    "timestamp": Utc::now().to_rfc3339(),
+                });
+                task::spawn(post_alert(url, payload));
+            }
+        }
+        let _ = self.status_publisher.broadcast(status.clone());
+        self.status = status;
+    }
+}
+
+async fn post_alert(url: String, payload: serde_json::Value) {
+    let result = reqwest::Client::new()
+        .post(&url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&payload)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(err) = result {
+        warn!(
+            target: LOG_TARGET,
+            "Could not post watchdog alert to '{}': {}", url, err
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> WatchdogConfig {
+        WatchdogConfig {
+            stale_tip_warning: Duration::from_secs(1200),
+            stale_tip_critical: Duration::from_secs(3600),
+            max_tip_divergence: 10,
+            webhook_url: None,
+        }
+    }
+
+    fn listening_at(local_height: u64, best_peer_height: u64) -> Observation {
+        Observation {
+            is_syncing: false,
+            tip_age: Duration::from_secs(120),
+            local_height,
+            best_peer_height: Some(best_peer_height),
+            comparable_peers: 0,
+            diverging_peers: 0,
+        }
+    }
+
+    #[test]
+    fn it_escalates_a_stale_tip() {
+        let mut observation = listening_at(100, 100);
+        assert_eq!(assess(&config(), &observation), WatchdogStatus::ok());
+
+        observation.tip_age = Duration::from_secs(1800);
+        let status = assess(&config(), &observation);
+        assert_eq!(status.level, AlertLevel::Warning);
+        assert_eq!(status.to_string(), "WARNING (no new block for 20m 0s)");
+
+        observation.tip_age = Duration::from_secs(3600);
+        assert_eq!(assess(&config(), &observation).level, AlertLevel::Critical);
+    }
+
+    #[test]
+    fn it_only_warns_about_a_lagging_tip_when_not_syncing() {
+        let mut observation = listening_at(100, 111);
+        assert_eq!(assess(&config(), &observation).level, AlertLevel::Warning);
+
+        observation.is_syncing = true;
+        assert_eq!(assess(&config(), &observation), WatchdogStatus::ok());
+
+        let observation = listening_at(100, 110);
+        assert_eq!(assess(&config(), &observation), WatchdogStatus::ok());
+    }
+
+    #[test]
+    fn it_raises_a_fork_alert_when_most_peers_disagree() {
+        let mut observation = listening_at(100, 100);
+        observation.comparable_peers = 8;
+        observation.diverging_peers = 1;
+        assert_eq!(assess(&config(), &observation), WatchdogStatus::ok());
+
+        observation.comparable_peers = 3;
+        observation.diverging_peers = 2;
+        assert_eq!(assess(&config(), &observation).level, AlertLevel::Warning);
+
+        observation.comparable_peers = 5;
+        observation.diverging_peers = 3;
+        assert_eq!(assess(&config(), &observation).level, AlertLevel::Critical);
+    }
+}
@@ -33,6 +33,7 @@ use tari_app_utilities::utilities::parse_emoji_id_or_public_key;
 use tari_comms::multiaddr::Multiaddr;
 
 use tari_core::transactions::{tari_amount::MicroTari, types::PublicKey};
+use tari_wallet::transaction_service::uri::TariUri;
 
 #[derive(Debug)]
 pub struct ParsedCommand {
@@ -47,6 +48,7 @@ impl Display for ParsedCommand {
             GetBalance => "get-balance",
             SendTari => "send-tari",
             SendOneSided => "send-one-sided",
+            ReclaimOneSided => "reclaim-one-sided",
             MakeItRain => "make-it-rain",
             CoinSplit => "coin-split",
             DiscoverPeer => "discover-peer",
@@ -57,6 +59,8 @@ impl Display for ParsedCommand {
             SetBaseNode => "set-base-node",
             SetCustomBaseNode => "set-custom-base-node",
             ClearCustomBaseNode => "clear-custom-base-node",
+            PayUri => "pay-uri",
+            RequestPayment => "request-payment",
         };
 
         let args = self
@@ -82,6 +86,7 @@ pub enum ParsedArgument {
     CSVFileName(String),
     Address(Multiaddr),
     Negotiated(bool),
+    Uri(TariUri),
 }
 
 impl Display for ParsedArgument {
@@ -98,6 +103,7 @@ impl Display for ParsedArgument {
             CSVFileName(v) => write!(f, "{}", v.to_string()),
             Address(v) => write!(f, "{}", v.to_string()),
             Negotiated(v) => write!(f, "{}", v.to_string()),
+            Uri(v) => write!(f, "{}", v.to_string()),
         }
     }
 }
@@ -114,6 +120,7 @@ pub fn parse_command(command: &str) -> Result<ParsedCommand, ParseError> {
         GetBalance => Vec::new(),
         SendTari => parse_send_tari(args)?,
         SendOneSided => parse_send_tari(args)?,
+        ReclaimOneSided => parse_tx_id(args)?,
         MakeItRain => parse_make_it_rain(args)?,
         CoinSplit => parse_coin_split(args)?,
         DiscoverPeer => parse_public_key(args)?,
@@ -124,6 +131,8 @@ pub fn parse_command(command: &str) -> Result<ParsedCommand, ParseError> {
         SetBaseNode => parse_public_key_and_address(args)?,
         SetCustomBaseNode => parse_public_key_and_address(args)?,
         ClearCustomBaseNode => Vec::new(),
+        PayUri => parse_pay_uri(args)?,
+        RequestPayment => parse_request_payment(args)?,
     };
 
     Ok(ParsedCommand { command, args })
@@ -275,6 +284,48 @@ fn parse_send_tari(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, Par
     Ok(parsed_args)
 }
 
+fn parse_tx_id(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let tx_id = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("transaction id".to_string()))?;
+    let tx_id = tx_id.parse::<u64>()?;
+    Ok(vec![ParsedArgument::Int(tx_id)])
+}
+
+fn parse_pay_uri(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // uri
+    let uri = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("tari:// URI".to_string()))?;
+    let uri = TariUri::from_str(uri)?;
+    parsed_args.push(ParsedArgument::Uri(uri));
+
+    // optional amount, used if the URI does not specify one
+    if let Some(amount) = args.next() {
+        let amount = MicroTari::from_str(amount)?;
+        parsed_args.push(ParsedArgument::Amount(amount));
+    }
+
+    Ok(parsed_args)
+}
+
+fn parse_request_payment(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
 fn parse_export_utxos(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
     let mut parsed_args = Vec::new();
 
@@ -349,7 +400,8 @@ mod test {
     use rand::rngs::OsRng;
     use std::str::FromStr;
     use tari_core::transactions::{tari_amount::MicroTari, types::PublicKey};
-    use tari_crypto::keys::PublicKey as PublicKeyTrait;
+    use tari_crypto::{keys::PublicKey as PublicKeyTrait, tari_utilities::hex::Hex};
+    use tari_wallet::transaction_service::uri::TariUri;
 
     #[test]
     fn test_parse_command() {
@@ -478,5 +530,34 @@ mod test {
                 _ => panic!("Expected parsing <transaction type> to return an error here"),
             },
         }
+
+        let uri = TariUri::payment("stibbons".to_string(), public_key.clone(), None, "msg text".to_string());
+        let command_str = format!("pay-uri {} 5T", uri);
+        let parsed = parse_command(&command_str).unwrap();
+
+        if let ParsedArgument::Uri(parsed_uri) = parsed.args[0].clone() {
+            assert_eq!(parsed_uri, uri);
+        } else {
+            panic!("Parsed URI is not the same as provided.");
+        }
+        if let ParsedArgument::Amount(amount) = parsed.args[1].clone() {
+            assert_eq!(amount, MicroTari::from_str("5T").unwrap());
+        } else {
+            panic!("Parsed MicroTari amount not the same as provided.");
+        }
+
+        let command_str = format!("pay-uri tari://stibbons/v1/pay/{}x", public_key.to_hex());
+        match parse_command(&command_str) {
+            Ok(_) => panic!("Invalid URI should not parse"),
+            Err(e) => assert!(matches!(e, ParseError::Uri(_))),
+        }
+
+        let parsed = parse_command("reclaim-one-sided 12345").unwrap();
+        if let ParsedArgument::Int(tx_id) = parsed.args[0] {
+            assert_eq!(tx_id, 12345);
+        } else {
+            panic!("Parsed transaction id not the same as provided.");
+        }
+        assert!(matches!(parse_command("reclaim-one-sided"), Err(ParseError::Empty(_))));
     }
 }
@@ -23,7 +23,10 @@
 use super::error::CommandError;
 use crate::{
     automation::command_parser::{ParsedArgument, ParsedCommand},
-    utils::db::{CUSTOM_BASE_NODE_ADDRESS_KEY, CUSTOM_BASE_NODE_PUBLIC_KEY_KEY},
+    utils::{
+        db::{CUSTOM_BASE_NODE_ADDRESS_KEY, CUSTOM_BASE_NODE_PUBLIC_KEY_KEY},
+        formatting::render_qr_code,
+    },
 };
 use chrono::{DateTime, Utc};
 use futures::{FutureExt, StreamExt};
@@ -45,15 +48,20 @@ use tari_comms_dht::{envelope::NodeDestination, DhtDiscoveryRequester};
 use tari_core::{
     tari_utilities::hex::Hex,
     transactions::{
-        tari_amount::{uT, MicroTari, Tari},
+        tari_amount::{MicroTari, Tari},
         transaction::UnblindedOutput,
         types::PublicKey,
     },
 };
 use tari_crypto::ristretto::pedersen::PedersenCommitmentFactory;
 use tari_wallet::{
+    base_node_service::handle::BaseNodeServiceHandle,
     output_manager_service::{handle::OutputManagerHandle, TxId},
-    transaction_service::handle::{TransactionEvent, TransactionServiceHandle},
+    transaction_service::{
+        handle::{TransactionEvent, TransactionServiceHandle},
+        uri::TariUri,
+    },
+    types::{DEFAULT_FEE_ESTIMATE_TARGET_BLOCKS, DEFAULT_FEE_PER_GRAM},
     util::emoji::EmojiId,
     WalletSqlite,
 };
@@ -71,6 +79,7 @@ pub enum WalletCommand {
     GetBalance,
     SendTari,
     SendOneSided,
+    ReclaimOneSided,
     MakeItRain,
     CoinSplit,
     DiscoverPeer,
@@ -81,6 +90,8 @@ pub enum WalletCommand {
     SetBaseNode,
     SetCustomBaseNode,
     ClearCustomBaseNode,
+    PayUri,
+    RequestPayment,
 }
 
 #[derive(Debug, EnumString, PartialEq, Clone)]
@@ -100,12 +111,30 @@ pub struct SentTransaction {
     stage: TransactionStage,
 }
 
-fn get_transaction_parameters(
-    args: Vec<ParsedArgument>,
-) -> Result<(MicroTari, MicroTari, PublicKey, String), CommandError> {
-    // TODO: Consolidate "fee per gram" in codebase
-    let fee_per_gram = 25 * uT;
+/// Asks the connected base node for a fee per gram estimate, falling back to the wallet default if the base node
+/// cannot provide one
+async fn estimate_fee_per_gram(mut base_node_service: BaseNodeServiceHandle) -> MicroTari {
+    match base_node_service
+        .estimate_fee_per_gram(DEFAULT_FEE_ESTIMATE_TARGET_BLOCKS)
+        .await
+    {
+        Ok(fee_per_gram) => {
+            debug!(target: LOG_TARGET, "Using estimated fee of {} per gram", fee_per_gram);
+            fee_per_gram
+        },
+        Err(e) => {
+            warn!(
+                target: LOG_TARGET,
+                "Could not get a fee estimate from the base node ({}). Using the default fee of {} per gram",
+                e,
+                DEFAULT_FEE_PER_GRAM
+            );
+            DEFAULT_FEE_PER_GRAM
+        },
+    }
+}
 
+fn get_transaction_parameters(args: Vec<ParsedArgument>) -> Result<(MicroTari, PublicKey, String), CommandError> {
     use ParsedArgument::*;
     let amount = match args[0].clone() {
         Amount(mtari) => Ok(mtari),
@@ -122,15 +151,16 @@ fn get_transaction_parameters(
         _ => Err(CommandError::Argument),
     }?;
 
-    Ok((fee_per_gram, amount, dest_pubkey, message))
+    Ok((amount, dest_pubkey, message))
 }
 
 /// Send a normal negotiated transaction to a recipient
 pub async fn send_tari(
     mut wallet_transaction_service: TransactionServiceHandle,
+    fee_per_gram: MicroTari,
     args: Vec<ParsedArgument>,
 ) -> Result<TxId, CommandError> {
-    let (fee_per_gram, amount, dest_pubkey, message) = get_transaction_parameters(args)?;
+    let (amount, dest_pubkey, message) = get_transaction_parameters(args)?;
     wallet_transaction_service
         .send_transaction(dest_pubkey, amount, fee_per_gram, message)
         .await
@@ -140,15 +170,61 @@ pub async fn send_tari(
 /// Send a one-sided transaction to a recipient
 pub async fn send_one_sided(
     mut wallet_transaction_service: TransactionServiceHandle,
+    fee_per_gram: MicroTari,
     args: Vec<ParsedArgument>,
 ) -> Result<TxId, CommandError> {
-    let (fee_per_gram, amount, dest_pubkey, message) = get_transaction_parameters(args)?;
+    let (amount, dest_pubkey, message) = get_transaction_parameters(args)?;
     wallet_transaction_service
         .send_one_sided_transaction(dest_pubkey, amount, fee_per_gram, message)
         .await
         .map_err(CommandError::TransactionServiceError)
 }
 
+/// Pay the payment request or one-sided payment request in a tari:// URI
+pub async fn pay_uri(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    fee_per_gram: MicroTari,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    use ParsedArgument::*;
+    let uri = match args[0].clone() {
+        Uri(uri) => Ok(uri),
+        _ => Err(CommandError::Argument),
+    }?;
+    let amount = match args.get(1) {
+        Some(Amount(amount)) => Some(*amount),
+        _ => None,
+    };
+
+    let tx_id = uri.pay(&mut wallet_transaction_service, amount, fee_per_gram).await?;
+    Ok(tx_id)
+}
+
+/// Create a tari:// URI asking for a payment to this wallet, and display it with its QR code
+pub fn request_payment(
+    public_key: CommsPublicKey,
+    config: &GlobalConfig,
+    args: Vec<ParsedArgument>,
+) -> Result<TariUri, CommandError> {
+    use ParsedArgument::*;
+    let amount = match args[0].clone() {
+        Amount(amount) => Ok(amount),
+        _ => Err(CommandError::Argument),
+    }?;
+    let message = match args[1].clone() {
+        Text(msg) => Ok(msg),
+        _ => Err(CommandError::Argument),
+    }?;
+
+    let uri = TariUri::payment(config.network.to_string(), public_key, Some(amount), message);
+    println!("Payment URI: {}", uri);
+    match render_qr_code(&uri.to_string()) {
+        Some(qr_code) => println!("{}", qr_code),
+        None => println!("The payment URI is too long to display as a QR code"),
+    }
+    Ok(uri)
+}
+
 pub async fn coin_split(
     args: &[ParsedArgument],
     output_service: &mut OutputManagerHandle,
@@ -249,6 +325,7 @@ pub async fn discover_peer(
 
 pub async fn make_it_rain(
     wallet_transaction_service: TransactionServiceHandle,
+    fee_per_gram: MicroTari,
     args: Vec<ParsedArgument>,
 ) -> Result<(), CommandError> {
     use ParsedArgument::*;
@@ -360,9 +437,9 @@ pub async fn make_it_rain(
                     let spawn_start = Instant::now();
                     // Send transaction
                     let tx_id = if negotiated {
-                        send_tari(tx_service, send_args).await
+                        send_tari(tx_service, fee_per_gram, send_args).await
                     } else {
-                        send_one_sided(tx_service, send_args).await
+                        send_one_sided(tx_service, fee_per_gram, send_args).await
                     };
                     let submit_time = Instant::now();
                     tokio::task::spawn(async move {
@@ -556,6 +633,7 @@ pub async fn command_runner(
 
     let transaction_service = wallet.transaction_service.clone();
     let mut output_service = wallet.output_manager_service.clone();
+    let base_node_service = wallet.base_node_service.clone();
     let dht_service = wallet.dht_service.discovery_service_requester().clone();
     let connectivity_requester = wallet.comms.connectivity();
     let mut online = false;
@@ -570,11 +648,9 @@ pub async fn command_runner(
         println!("\n{}. {}\n", idx + 1, parsed);
 
         match parsed.command {
-            GetBalance => match output_service.clone().get_balance().await {
-                Ok(balance) => {
-                    println!("{}", balance);
-                },
-                Err(e) => eprintln!("GetBalance error! {}", e),
+            GetBalance => {
+                let balance = output_service.clone().get_balance().await?;
+                println!("{}", balance);
             },
             DiscoverPeer => {
                 if !online {
@@ -583,17 +659,28 @@ pub async fn command_runner(
                 discover_peer(dht_service.clone(), parsed.args).await?
             },
             SendTari => {
-                let tx_id = send_tari(transaction_service.clone(), parsed.args).await?;
+                let fee_per_gram = estimate_fee_per_gram(base_node_service.clone()).await;
+                let tx_id = send_tari(transaction_service.clone(), fee_per_gram, parsed.args).await?;
                 debug!(target: LOG_TARGET, "send-tari tx_id {}", tx_id);
                 tx_ids.push(tx_id);
             },
             SendOneSided => {
-                let tx_id = send_one_sided(transaction_service.clone(), parsed.args).await?;
+                let fee_per_gram = estimate_fee_per_gram(base_node_service.clone()).await;
+                let tx_id = send_one_sided(transaction_service.clone(), fee_per_gram, parsed.args).await?;
                 debug!(target: LOG_TARGET, "send-one-sided tx_id {}", tx_id);
                 tx_ids.push(tx_id);
             },
+            ReclaimOneSided => {
+                let tx_id = match parsed.args[0] {
+                    ParsedArgument::Int(tx_id) => Ok(tx_id),
+                    _ => Err(CommandError::Argument),
+                }?;
+                let value = transaction_service.clone().reclaim_one_sided(tx_id).await?;
+                println!("Reclaimed {} from one-sided transaction {}", value, tx_id);
+            },
             MakeItRain => {
-                make_it_rain(transaction_service.clone(), parsed.args).await?;
+                let fee_per_gram = estimate_fee_per_gram(base_node_service.clone()).await;
+                make_it_rain(transaction_service.clone(), fee_per_gram, parsed.args).await?;
             },
             CoinSplit => {
                 let tx_id = coin_split(&parsed.args, &mut output_service, &mut transaction_service.clone()).await?;
@@ -683,6 +770,15 @@ pub async fn command_runner(
                     .await?;
                 println!("Custom base node peer cleared from wallet database.");
             },
+            PayUri => {
+                let fee_per_gram = estimate_fee_per_gram(base_node_service.clone()).await;
+                let tx_id = pay_uri(transaction_service.clone(), fee_per_gram, parsed.args).await?;
+                debug!(target: LOG_TARGET, "pay-uri tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
+            RequestPayment => {
+                request_payment(wallet.comms.node_identity().public_key().clone(), &config, parsed.args)?;
+            },
         }
     }
 
@@ -712,6 +808,10 @@ pub async fn command_runner(
                      the logs for more info.",
                     duration, wait_stage
                 );
+                return Err(CommandError::TransactionMonitorTimeout(
+                    duration,
+                    format!("{:?}", wait_stage),
+                ));
             },
         }
     } else {
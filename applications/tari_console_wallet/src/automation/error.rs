@@ -20,7 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::num::{ParseFloatError, ParseIntError};
+use std::{
+    num::{ParseFloatError, ParseIntError},
+    time::Duration,
+};
 
 use chrono_english::DateError;
 use log::*;
@@ -29,7 +32,7 @@ use tari_core::transactions::{tari_amount::MicroTariError, transaction::Transact
 use tari_wallet::{
     error::{WalletError, WalletStorageError},
     output_manager_service::error::OutputManagerError,
-    transaction_service::error::TransactionServiceError,
+    transaction_service::{error::TransactionServiceError, uri::TariUriError},
 };
 use thiserror::Error;
 use tokio::task::JoinError;
@@ -59,12 +62,25 @@ pub enum CommandError {
     WalletError(#[from] WalletError),
     #[error("Wallet storage error `{0}`")]
     WalletStorageError(#[from] WalletStorageError),
+    #[error("Timed out after {0:?} waiting for transactions to reach the {1} stage")]
+    TransactionMonitorTimeout(Duration, String),
+    #[error("Payment URI error `{0}`")]
+    Uri(#[from] TariUriError),
 }
 
 impl From<CommandError> for ExitCodes {
     fn from(err: CommandError) -> Self {
         error!(target: LOG_TARGET, "{}", err);
-        Self::CommandError(err.to_string())
+        // Map errors to distinct exit codes so that scripts can react to the cause of the failure
+        match err {
+            CommandError::Argument => Self::InputError(err.to_string()),
+            CommandError::Config(_) => Self::ConfigError(err.to_string()),
+            CommandError::Comms(_) => Self::NetworkError(err.to_string()),
+            CommandError::CSVFile(_) => Self::IOError(err.to_string()),
+            CommandError::TransactionMonitorTimeout(_, _) => Self::TimeoutError(err.to_string()),
+            CommandError::WalletError(_) | CommandError::WalletStorageError(_) => Self::WalletError(err.to_string()),
+            _ => Self::CommandError(err.to_string()),
+        }
     }
 }
 
@@ -86,6 +102,8 @@ pub enum ParseError {
     Date(#[from] DateError),
     #[error("Failed to parse a net address.")]
     Address,
+    #[error("Failed to parse a tari:// URI. {0}")]
+    Uri(#[from] TariUriError),
     #[error("Invalid combination of arguments ({0}).")]
     Invalid(String),
     #[error("Parsing not yet implemented for {0}.")]
@@ -0,0 +1,68 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use log::*;
+use tari_app_utilities::{
+    config_reload::{changed_settings, ConfigReloadReport},
+    initialization::reload_configuration,
+    utilities::ExitCodes,
+};
+use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap, GlobalConfig};
+use tari_shutdown::ShutdownSignal;
+
+const LOG_TARGET: &str = "wallet::console_wallet::config_watcher";
+
+/// Re-reads the configuration and reloads the log levels. None of the settings in the configuration file can be
+/// changed while the wallet is running, so any changes to them are reported as requiring a restart.
+pub fn reload_config(bootstrap: &ConfigBootstrap, config: &GlobalConfig) -> Result<ConfigReloadReport, ExitCodes> {
+    let new_config = reload_configuration(bootstrap, ApplicationType::ConsoleWallet)?;
+    let mut report = ConfigReloadReport::from_changes(changed_settings(config, &new_config), &[]);
+    report.reload_logging();
+    report.log();
+    Ok(report)
+}
+
+/// Reloads the configuration every time the process receives a SIGHUP
+#[cfg(unix)]
+pub async fn reload_on_hangup(bootstrap: ConfigBootstrap, config: GlobalConfig, mut shutdown_signal: ShutdownSignal) {
+    use futures::StreamExt;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup.fuse(),
+        Err(err) => {
+            warn!(target: LOG_TARGET, "Unable to listen for SIGHUP: {}", err);
+            return;
+        },
+    };
+    loop {
+        futures::select! {
+            _ = hangup.select_next_some() => {
+                info!(target: LOG_TARGET, "SIGHUP received, reloading configuration");
+                if let Err(err) = reload_config(&bootstrap, &config) {
+                    error!(target: LOG_TARGET, "Could not reload configuration: {:?}", err);
+                }
+            },
+            _ = shutdown_signal => break,
+        }
+    }
+}
@@ -2,6 +2,7 @@ use futures::future;
 use log::*;
 use std::convert::TryFrom;
 use tari_app_grpc::{
+    authentication::{GrpcAuthenticator, GrpcPermission},
     conversions::naive_datetime_to_timestamp,
     tari_rpc,
     tari_rpc::{
@@ -9,6 +10,8 @@ use tari_app_grpc::{
         wallet_server,
         CoinSplitRequest,
         CoinSplitResponse,
+        ExportUtxoRequest,
+        ExportUtxoResponse,
         GetBalanceRequest,
         GetBalanceResponse,
         GetCoinbaseRequest,
@@ -34,10 +37,16 @@ use tari_app_grpc::{
 use tari_comms::{types::CommsPublicKey, CommsNode};
 use tari_core::{
     tari_utilities::{hex::Hex, ByteArray},
-    transactions::{tari_amount::MicroTari, transaction::UnblindedOutput, types::Signature},
+    transactions::{
+        tari_amount::MicroTari,
+        transaction::UnblindedOutput,
+        types::{Commitment, Signature},
+        CoinbasePayout,
+    },
 };
 use tari_wallet::{
-    output_manager_service::handle::OutputManagerHandle,
+    error::WalletError,
+    output_manager_service::{handle::OutputManagerHandle, utxo_bundle::UtxoBundle},
     transaction_service::{handle::TransactionServiceHandle, storage::models},
     WalletSqlite,
 };
@@ -48,11 +57,12 @@ const LOG_TARGET: &str = "wallet::ui::grpc";
 
 pub struct WalletGrpcServer {
     wallet: WalletSqlite,
+    authenticator: GrpcAuthenticator,
 }
 
 impl WalletGrpcServer {
-    pub fn new(wallet: WalletSqlite) -> Self {
-        Self { wallet }
+    pub fn new(wallet: WalletSqlite, authenticator: GrpcAuthenticator) -> Self {
+        Self { wallet, authenticator }
     }
 
     fn get_transaction_service(&self) -> TransactionServiceHandle {
@@ -72,13 +82,15 @@ impl WalletGrpcServer {
 impl wallet_server::Wallet for WalletGrpcServer {
     type GetCompletedTransactionsStream = mpsc::Receiver<Result<GetCompletedTransactionsResponse, Status>>;
 
-    async fn get_version(&self, _: Request<GetVersionRequest>) -> Result<Response<GetVersionResponse>, Status> {
+    async fn get_version(&self, request: Request<GetVersionRequest>) -> Result<Response<GetVersionResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         Ok(Response::new(GetVersionResponse {
             version: env!("CARGO_PKG_VERSION").to_string(),
         }))
     }
 
-    async fn identify(&self, _: Request<GetIdentityRequest>) -> Result<Response<GetIdentityResponse>, Status> {
+    async fn identify(&self, request: Request<GetIdentityRequest>) -> Result<Response<GetIdentityResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let identity = self.wallet.comms.node_identity();
         Ok(Response::new(GetIdentityResponse {
             public_key: identity.public_key().to_string().as_bytes().to_vec(),
@@ -87,7 +99,8 @@ impl wallet_server::Wallet for WalletGrpcServer {
         }))
     }
 
-    async fn get_balance(&self, _request: Request<GetBalanceRequest>) -> Result<Response<GetBalanceResponse>, Status> {
+    async fn get_balance(&self, request: Request<GetBalanceRequest>) -> Result<Response<GetBalanceResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let mut output_service = self.get_output_manager_service();
         let balance;
         match output_service.get_balance().await {
@@ -105,11 +118,29 @@ impl wallet_server::Wallet for WalletGrpcServer {
         &self,
         request: Request<GetCoinbaseRequest>,
     ) -> Result<Response<GetCoinbaseResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Spend)?;
         let request = request.into_inner();
+        let payouts = request
+            .payouts
+            .into_iter()
+            .enumerate()
+            .map(|(idx, payout)| {
+                let destination = CommsPublicKey::from_hex(&payout.destination).map_err(|_| {
+                    Status::invalid_argument(format!("Payout destination at index {} is malformed", idx))
+                })?;
+                let percentage = u8::try_from(payout.percentage)
+                    .ok()
+                    .filter(|p| *p <= 100)
+                    .ok_or_else(|| {
+                        Status::invalid_argument(format!("Payout percentage at index {} is invalid", idx))
+                    })?;
+                Ok(CoinbasePayout::new(destination, percentage))
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
 
         let mut tx_service = self.get_transaction_service();
         let response = tx_service
-            .generate_coinbase_transaction(request.reward.into(), request.fee.into(), request.height)
+            .generate_split_coinbase_transaction(request.reward.into(), request.fee.into(), request.height, payouts)
             .await;
 
         match response {
@@ -121,6 +152,7 @@ impl wallet_server::Wallet for WalletGrpcServer {
     }
 
     async fn transfer(&self, request: Request<TransferRequest>) -> Result<Response<TransferResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Spend)?;
         let message = request.into_inner();
         let recipients = message
             .recipients
@@ -136,6 +168,7 @@ impl wallet_server::Wallet for WalletGrpcServer {
                     dest.fee_per_gram,
                     dest.message,
                     dest.payment_type,
+                    Some(dest.idempotency_key).filter(|k| !k.is_empty()),
                 ))
             })
             .collect::<Result<Vec<_>, _>>()
@@ -143,14 +176,20 @@ impl wallet_server::Wallet for WalletGrpcServer {
 
         let mut standard_transfers = Vec::new();
         let mut one_sided_transfers = Vec::new();
-        for (address, pk, amount, fee_per_gram, message, payment_type) in recipients.into_iter() {
+        for (address, pk, amount, fee_per_gram, message, payment_type, idempotency_key) in recipients.into_iter() {
             let mut transaction_service = self.get_transaction_service();
             if payment_type == PaymentType::StandardMimblewimble as i32 {
                 standard_transfers.push(async move {
                     (
                         address,
                         transaction_service
-                            .send_transaction(pk, amount.into(), fee_per_gram.into(), message)
+                            .send_transaction_with_idempotency_key(
+                                pk,
+                                amount.into(),
+                                fee_per_gram.into(),
+                                message,
+                                idempotency_key,
+                            )
                             .await,
                     )
                 });
@@ -159,7 +198,13 @@ impl wallet_server::Wallet for WalletGrpcServer {
                     (
                         address,
                         transaction_service
-                            .send_one_sided_transaction(pk, amount.into(), fee_per_gram.into(), message)
+                            .send_one_sided_transaction_with_idempotency_key(
+                                pk,
+                                amount.into(),
+                                fee_per_gram.into(),
+                                message,
+                                idempotency_key,
+                            )
                             .await,
                     )
                 });
@@ -201,6 +246,7 @@ impl wallet_server::Wallet for WalletGrpcServer {
         &self,
         request: Request<GetTransactionInfoRequest>,
     ) -> Result<Response<GetTransactionInfoResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let message = request.into_inner();
 
         let queries = message.transaction_ids.into_iter().map(|tx_id| {
@@ -232,8 +278,9 @@ impl wallet_server::Wallet for WalletGrpcServer {
 
     async fn get_completed_transactions(
         &self,
-        _request: Request<GetCompletedTransactionsRequest>,
+        request: Request<GetCompletedTransactionsRequest>,
     ) -> Result<Response<Self::GetCompletedTransactionsStream>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         debug!(
             target: LOG_TARGET,
             "Incoming GRPC request for GetAllCompletedTransactions"
@@ -288,6 +335,7 @@ impl wallet_server::Wallet for WalletGrpcServer {
     }
 
     async fn coin_split(&self, request: Request<CoinSplitRequest>) -> Result<Response<CoinSplitResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Spend)?;
         let message = request.into_inner();
 
         let lock_height = if message.lock_height == 0 {
@@ -316,6 +364,7 @@ impl wallet_server::Wallet for WalletGrpcServer {
         &self,
         request: Request<ImportUtxosRequest>,
     ) -> Result<Response<ImportUtxosResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Spend)?;
         let message = request.into_inner();
 
         let mut wallet = self.wallet.clone();
@@ -337,13 +386,49 @@ impl wallet_server::Wallet for WalletGrpcServer {
             );
         }
 
+        for bytes in message.bundles {
+            let bundle = UtxoBundle::from_bytes(&bytes).map_err(|e| Status::invalid_argument(e.to_string()))?;
+            tx_ids.push(
+                wallet
+                    .import_utxo_bundle(&bundle, &CommsPublicKey::default(), "Imported via gRPC".to_string())
+                    .await
+                    .map_err(|e| match e {
+                        WalletError::UtxoBundleError(e) => Status::failed_precondition(e.to_string()),
+                        e => Status::internal(format!("{:?}", e)),
+                    })?,
+            );
+        }
+
         Ok(Response::new(ImportUtxosResponse { tx_ids }))
     }
 
+    async fn export_utxo(&self, request: Request<ExportUtxoRequest>) -> Result<Response<ExportUtxoResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::Spend)?;
+        let message = request.into_inner();
+        let commitment =
+            Commitment::from_bytes(&message.commitment).map_err(|_| Status::invalid_argument("Invalid commitment"))?;
+        let recipient_public_key = CommsPublicKey::from_bytes(&message.recipient_public_key)
+            .map_err(|_| Status::invalid_argument("Invalid recipient public key"))?;
+
+        let mut wallet = self.wallet.clone();
+        let bundle = wallet
+            .export_utxo(&commitment, &recipient_public_key)
+            .await
+            .map_err(|e| match e {
+                WalletError::UtxoBundleError(e) => Status::not_found(e.to_string()),
+                e => Status::internal(format!("{:?}", e)),
+            })?;
+
+        Ok(Response::new(ExportUtxoResponse {
+            bundle: bundle.to_bytes(),
+        }))
+    }
+
     async fn get_network_status(
         &self,
-        _: Request<tari_rpc::Empty>,
+        request: Request<tari_rpc::Empty>,
     ) -> Result<Response<tari_rpc::NetworkStatusResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let status = self
             .comms()
             .connectivity()
@@ -368,8 +453,9 @@ impl wallet_server::Wallet for WalletGrpcServer {
 
     async fn list_connected_peers(
         &self,
-        _: Request<tari_rpc::Empty>,
+        request: Request<tari_rpc::Empty>,
     ) -> Result<Response<tari_rpc::ListConnectedPeersResponse>, Status> {
+        self.authenticator.authorize(&request, GrpcPermission::ReadOnly)?;
         let mut connectivity = self.comms().connectivity();
         let peer_manager = self.comms().peer_manager();
         let connected_peers = connectivity
@@ -47,7 +47,10 @@ use tari_shutdown::ShutdownSignal;
 use tari_wallet::{
     base_node_service::config::BaseNodeServiceConfig,
     error::{WalletError, WalletStorageError},
-    output_manager_service::{config::OutputManagerServiceConfig, TxoValidationType},
+    output_manager_service::{
+        config::{OutputManagerServiceConfig, TxoValidationMode, UnconfirmedChangePolicy},
+        TxoValidationType,
+    },
     storage::{database::WalletDatabase, sqlite_utilities::initialize_sqlite_database_backends},
     transaction_service::{
         config::{TransactionRoutingMechanism, TransactionServiceConfig},
@@ -282,7 +285,14 @@ pub async fn init_wallet(
             return Err(e.into());
         },
     };
-    let (wallet_backend, transaction_backend, output_manager_backend, contacts_backend) = backends;
+    let (
+        wallet_backend,
+        transaction_backend,
+        output_manager_backend,
+        contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
+    ) = backends;
     let wallet_db = WalletDatabase::new(wallet_backend);
 
     debug!(
@@ -333,6 +343,7 @@ pub async fn init_wallet(
             flood_ban_max_msg_count: config.flood_ban_max_msg_count,
             saf_msg_validity: config.saf_expiry_duration,
             dedup_cache_capacity: config.dedup_cache_capacity,
+            dedup_bloom_retention: config.dedup_bloom_retention,
             ..Default::default()
         },
         // TODO: This should be false unless testing locally - make this configurable
@@ -343,6 +354,7 @@ pub async fn init_wallet(
         peer_seeds: Default::default(),
         dns_seeds: Default::default(),
         dns_seeds_use_dnssec: true,
+        min_peer_minor_version: config.min_peer_minor_version,
     };
 
     let base_node_service_config = BaseNodeServiceConfig::new(
@@ -363,11 +375,18 @@ pub async fn init_wallet(
                 config.transaction_routing_mechanism.clone(),
             ),
             num_confirmations_required: config.transaction_num_confirmations_required,
+            one_sided_reclaim_lock_blocks: config.wallet_one_sided_reclaim_lock_blocks,
             ..Default::default()
         }),
         Some(OutputManagerServiceConfig {
             base_node_query_timeout: config.base_node_query_timeout,
             prevent_fee_gt_amount: config.prevent_fee_gt_amount,
+            txo_validation_mode: if config.wallet_verify_utxo_proofs {
+                TxoValidationMode::MerkleProof
+            } else {
+                TxoValidationMode::Trusted
+            },
+            unconfirmed_change_policy: UnconfirmedChangePolicy::from(config.wallet_max_unconfirmed_change_depth),
             ..Default::default()
         }),
         config.network.into(),
@@ -384,6 +403,8 @@ pub async fn init_wallet(
         transaction_backend,
         output_manager_backend,
         contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
         shutdown_signal,
         recovery_master_key.clone(),
     )
@@ -491,6 +512,27 @@ pub async fn start_wallet(
     Ok(())
 }
 
+/// Registers the configured base node service peers with the wallet so that it can switch between them automatically if
+/// the selected base node becomes unreachable or falls behind. A custom base node set by the user is never replaced.
+pub async fn add_base_node_candidates(wallet: &mut WalletSqlite, peer_config: &PeerConfig) -> Result<(), ExitCodes> {
+    if peer_config.base_node_peers.len() < 2 {
+        return Ok(());
+    }
+
+    for peer in &peer_config.base_node_peers {
+        let net_address = peer
+            .addresses
+            .first()
+            .ok_or_else(|| ExitCodes::ConfigError("Configured base node has no address!".to_string()))?
+            .to_string();
+        wallet
+            .add_base_node_candidate(peer.public_key.clone(), net_address)
+            .await
+            .map_err(|e| ExitCodes::WalletError(format!("Error adding base node candidate. {}", e)))?;
+    }
+    Ok(())
+}
+
 async fn validate_txos(wallet: &mut WalletSqlite) -> Result<(), ExitCodes> {
     debug!(target: LOG_TARGET, "Starting TXO validations.");
 
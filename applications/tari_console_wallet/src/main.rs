@@ -8,6 +8,7 @@
 #![recursion_limit = "1024"]
 use crate::{recovery::get_private_key_from_seed_words, wallet_modes::WalletModeConfig};
 use init::{
+    add_base_node_candidates,
     boot,
     change_password,
     get_base_node_peer_config,
@@ -30,6 +31,8 @@ use wallet_modes::{command_mode, grpc_mode, recovery_mode, script_mode, tui_mode
 pub const LOG_TARGET: &str = "wallet::console_wallet::main";
 
 mod automation;
+#[cfg(unix)]
+mod config_watcher;
 mod grpc;
 mod init;
 mod notifier;
@@ -125,6 +128,14 @@ fn main_inner() -> Result<(), ExitCodes> {
 
     // start wallet
     runtime.block_on(start_wallet(&mut wallet, &base_node_selected, &wallet_mode))?;
+    runtime.block_on(add_base_node_candidates(&mut wallet, &base_node_config))?;
+
+    #[cfg(unix)]
+    runtime.spawn(config_watcher::reload_on_hangup(
+        bootstrap.clone(),
+        global_config.clone(),
+        shutdown.to_signal(),
+    ));
 
     // optional path to notify script
     let notify_script = get_notify_script(&bootstrap, &global_config)?;
@@ -110,22 +110,25 @@ pub async fn wallet_recovery(wallet: &WalletSqlite, base_node_config: &PeerConfi
             Ok(UtxoScannerEvent::Progress {
                 current_block: current,
                 current_chain_height: total,
+                outputs_per_second,
             }) => {
                 let percentage_progress = ((current as f32) * 100f32 / (total as f32)).round() as u32;
                 debug!(
                     target: LOG_TARGET,
-                    "{}: Recovery process {}% complete ({} of {} utxos).",
+                    "{}: Recovery process {}% complete ({} of {} utxos, {} utxos/s).",
                     Local::now(),
                     percentage_progress,
                     current,
-                    total
+                    total,
+                    outputs_per_second
                 );
                 println!(
-                    "{}: Recovery process {}% complete ({} of {} utxos).",
+                    "{}: Recovery process {}% complete ({} of {} utxos, {} utxos/s).",
                     Local::now(),
                     percentage_progress,
                     current,
-                    total
+                    total,
+                    outputs_per_second
                 );
             },
             Ok(UtxoScannerEvent::ScanningRoundFailed {
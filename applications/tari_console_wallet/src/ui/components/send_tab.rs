@@ -8,7 +8,6 @@ use crate::{
     utils::formatting::display_compressed_string,
 };
 use tari_core::transactions::tari_amount::MicroTari;
-use tari_wallet::types::DEFAULT_FEE_PER_GRAM;
 use tokio::{runtime::Handle, sync::watch};
 use tui::{
     backend::Backend,
@@ -49,7 +48,7 @@ impl SendTab {
             show_edit_contact: false,
             to_field: "".to_string(),
             amount_field: "".to_string(),
-            fee_field: u64::from(DEFAULT_FEE_PER_GRAM).to_string(),
+            fee_field: "".to_string(),
             message_field: "".to_string(),
             alias_field: "".to_string(),
             public_key_field: "".to_string(),
@@ -61,7 +60,7 @@ impl SendTab {
         }
     }
 
-    fn draw_send_form<B>(&self, f: &mut Frame<B>, area: Rect, _app_state: &AppState)
+    fn draw_send_form<B>(&self, f: &mut Frame<B>, area: Rect, app_state: &AppState)
     where B: Backend {
         let block = Block::default().borders(Borders::ALL).title(Span::styled(
             "Send Transaction",
@@ -135,12 +134,17 @@ impl SendTab {
             .block(Block::default().borders(Borders::ALL).title("(A)mount (uT or T):"));
         f.render_widget(amount_input, amount_fee_layout[0]);
 
-        let fee_input = Paragraph::new(self.fee_field.as_ref())
-            .style(match self.send_input_mode {
-                SendInputMode::Fee => Style::default().fg(Color::Magenta),
-                _ => Style::default(),
-            })
-            .block(Block::default().borders(Borders::ALL).title("(F)ee-per-gram (uT):"));
+        // An empty fee field means the base node's estimate will be used
+        let fee_input = match self.send_input_mode {
+            SendInputMode::Fee => Paragraph::new(self.fee_field.as_ref()).style(Style::default().fg(Color::Magenta)),
+            _ if self.fee_field.is_empty() => Paragraph::new(format!(
+                "{} (estimated)",
+                u64::from(app_state.get_fee_per_gram_estimate())
+            ))
+            .style(Style::default().fg(Color::DarkGray)),
+            _ => Paragraph::new(self.fee_field.as_ref()),
+        }
+        .block(Block::default().borders(Borders::ALL).title("(F)ee-per-gram (uT):"));
         f.render_widget(fee_input, amount_fee_layout[1]);
 
         let message_input = Paragraph::new(self.message_field.as_ref())
@@ -324,7 +328,9 @@ impl SendTab {
                                 return KeyHandled::Handled;
                             };
 
-                            let fee_per_gram = if let Ok(v) = self.fee_field.parse::<u64>() {
+                            let fee_per_gram = if self.fee_field.is_empty() {
+                                u64::from(app_state.get_fee_per_gram_estimate())
+                            } else if let Ok(v) = self.fee_field.parse::<u64>() {
                                 v
                             } else {
                                 self.error_message =
@@ -371,7 +377,7 @@ impl SendTab {
                             if reset_fields {
                                 self.to_field = "".to_string();
                                 self.amount_field = "".to_string();
-                                self.fee_field = u64::from(DEFAULT_FEE_PER_GRAM).to_string();
+                                self.fee_field = "".to_string();
                                 self.message_field = "".to_string();
                                 self.send_input_mode = SendInputMode::None;
                                 self.send_result_watch = Some(rx);
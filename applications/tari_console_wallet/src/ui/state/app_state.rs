@@ -30,13 +30,15 @@ use crate::{
         UiContact,
         UiError,
     },
-    utils::db::{CUSTOM_BASE_NODE_ADDRESS_KEY, CUSTOM_BASE_NODE_PUBLIC_KEY_KEY},
+    utils::{
+        db::{CUSTOM_BASE_NODE_ADDRESS_KEY, CUSTOM_BASE_NODE_PUBLIC_KEY_KEY},
+        formatting::render_qr_code,
+    },
     wallet_modes::PeerConfig,
 };
 use bitflags::bitflags;
 use futures::{stream::Fuse, StreamExt};
 use log::*;
-use qrcode::{render::unicode, QrCode};
 use std::{
     collections::HashMap,
     sync::Arc,
@@ -63,8 +65,9 @@ use tari_wallet::{
     transaction_service::{
         handle::TransactionEventReceiver,
         storage::models::{CompletedTransaction, TransactionStatus},
+        uri::TariUri,
     },
-    types::ValidationRetryStrategy,
+    types::{ValidationRetryStrategy, DEFAULT_FEE_ESTIMATE_TARGET_BLOCKS, DEFAULT_FEE_PER_GRAM},
     util::emoji::EmojiId,
     WalletSqlite,
 };
@@ -159,7 +162,19 @@ impl AppState {
             },
         };
 
-        let contact = Contact { alias, public_key };
+        // Keep the notes of an existing contact, since they can't be edited here
+        let notes = inner
+            .wallet
+            .contacts_service
+            .get_contact(public_key.clone())
+            .await
+            .ok()
+            .and_then(|c| c.notes);
+        let contact = Contact {
+            alias,
+            public_key,
+            notes,
+        };
         inner.wallet.contacts_service.upsert_contact(contact).await?;
 
         inner.refresh_contacts_state().await?;
@@ -398,6 +413,10 @@ impl AppState {
         Ok(())
     }
 
+    pub fn get_fee_per_gram_estimate(&self) -> MicroTari {
+        self.cached_data.fee_per_gram_estimate
+    }
+
     pub fn get_required_confirmations(&self) -> u64 {
         (&self.node_config.transaction_num_confirmations_required).to_owned()
     }
@@ -620,12 +639,32 @@ impl AppStateInner {
     }
 
     pub async fn refresh_base_node_state(&mut self, state: BaseNodeState) -> Result<(), UiError> {
+        let tip_height = |s: &BaseNodeState| s.chain_metadata.as_ref().map(|m| m.height_of_longest_chain());
+        if tip_height(&state).is_some() && tip_height(&state) != tip_height(&self.data.base_node_state) {
+            self.refresh_fee_per_gram_estimate().await;
+        }
         self.data.base_node_state = state;
         self.updated = true;
 
         Ok(())
     }
 
+    /// Asks the base node for a new fee estimate, keeping the previous estimate if the base node cannot provide one
+    pub async fn refresh_fee_per_gram_estimate(&mut self) {
+        match self
+            .wallet
+            .base_node_service
+            .estimate_fee_per_gram(DEFAULT_FEE_ESTIMATE_TARGET_BLOCKS)
+            .await
+        {
+            Ok(fee_per_gram) => {
+                self.data.fee_per_gram_estimate = fee_per_gram;
+                self.updated = true;
+            },
+            Err(e) => debug!(target: LOG_TARGET, "Could not refresh the fee per gram estimate: {}", e),
+        }
+    }
+
     pub async fn refresh_base_node_peer(&mut self, peer: Peer) -> Result<(), UiError> {
         self.data.base_node_selected = peer;
         self.updated = true;
@@ -822,6 +861,7 @@ struct AppStateData {
     base_node_previous: Peer,
     base_node_list: Vec<(String, Peer)>,
     base_node_peer_custom: Option<Peer>,
+    fee_per_gram_estimate: MicroTari,
 }
 
 impl AppStateData {
@@ -832,22 +872,21 @@ impl AppStateData {
         base_node_config: PeerConfig,
     ) -> Self {
         let eid = EmojiId::from_pubkey(node_identity.public_key()).to_string();
-        let qr_link = format!("tari://{}/pubkey/{}", network, &node_identity.public_key().to_hex());
-        let code = QrCode::new(qr_link).unwrap();
-        let image = code
-            .render::<unicode::Dense1x2>()
-            .dark_color(unicode::Dense1x2::Dark)
-            .light_color(unicode::Dense1x2::Light)
-            .build()
-            .lines()
-            .skip(1)
-            .fold("".to_string(), |acc, l| format!("{}{}\n", acc, l));
+        let uri = TariUri::payment(
+            network.to_string(),
+            node_identity.public_key().clone(),
+            None,
+            String::new(),
+        );
+        let uri = uri.to_string();
+        let image = render_qr_code(&uri).unwrap_or_default();
 
         let identity = MyIdentity {
             public_key: node_identity.public_key().to_string(),
             public_address: node_identity.public_address().to_string(),
             emoji_id: eid,
             qr_code: image,
+            payment_uri: uri,
         };
         let base_node_previous = base_node_selected.clone();
 
@@ -885,6 +924,7 @@ impl AppStateData {
             base_node_previous,
             base_node_list,
             base_node_peer_custom: base_node_config.base_node_custom,
+            fee_per_gram_estimate: DEFAULT_FEE_PER_GRAM,
         }
     }
 }
@@ -895,6 +935,8 @@ pub struct MyIdentity {
     pub public_address: String,
     pub emoji_id: String,
     pub qr_code: String,
+    /// A tari:// URI that other wallets can use to pay this wallet
+    pub payment_uri: String,
 }
 
 #[derive(Clone)]
@@ -86,7 +86,8 @@ impl WalletEventMonitor {
                                     TransactionEvent::ReceivedTransaction(tx_id) |
                                     TransactionEvent::ReceivedTransactionReply(tx_id) |
                                     TransactionEvent::TransactionBroadcast(tx_id) |
-                                    TransactionEvent::TransactionMinedRequestTimedOut(tx_id) | TransactionEvent::TransactionImported(tx_id) => {
+                                    TransactionEvent::TransactionMinedRequestTimedOut(tx_id) | TransactionEvent::TransactionImported(tx_id) |
+                                    TransactionEvent::TransactionRejectedByMempool(tx_id, _) => {
                                         self.trigger_tx_state_refresh(tx_id).await;
                                     },
                                     TransactionEvent::TransactionDirectSendResult(tx_id, true) |
@@ -98,6 +99,13 @@ impl WalletEventMonitor {
                                     TransactionEvent::TransactionValidationSuccess(_) => {
                                         self.trigger_full_tx_state_refresh().await;
                                     },
+                                    TransactionEvent::ChainReorg(_) => {
+                                        self.trigger_full_tx_state_refresh().await;
+                                        self.trigger_balance_refresh().await;
+                                    },
+                                    TransactionEvent::OneSidedReclaimed(_) => {
+                                        self.trigger_balance_refresh().await;
+                                    },
                                     // Only the above variants trigger state refresh
                                     _ => (),
                                 }
@@ -115,7 +123,10 @@ impl WalletEventMonitor {
                                     ConnectivityEvent::PeerConnected(_) |
                                     ConnectivityEvent::PeerBanned(_) |
                                     ConnectivityEvent::PeerOffline(_) |
-                                    ConnectivityEvent::PeerConnectionWillClose(_, _) => {
+                                    ConnectivityEvent::PeerConnectionWillClose(_, _) |
+                                    ConnectivityEvent::ConnectivityStateOnline(_) |
+                                    ConnectivityEvent::ConnectivityStateDegraded(_) |
+                                    ConnectivityEvent::ConnectivityStateOffline => {
                                         self.trigger_peer_state_refresh().await;
                                     },
                                     // Only the above variants trigger state refresh
@@ -136,6 +147,9 @@ impl WalletEventMonitor {
                                     BaseNodeEvent::BaseNodePeerSet(peer) => {
                                         self.trigger_base_node_peer_refresh(*peer).await;
                                     }
+                                    // The wallet state affected by a reorg is refreshed on the Transaction Service's
+                                    // ChainReorg event
+                                    BaseNodeEvent::ChainReorg(_) => {}
                                 }
                             },
                             Err(_) => debug!(target: LOG_TARGET, "Lagging read on base node event broadcast channel"),
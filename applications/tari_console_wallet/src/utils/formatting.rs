@@ -20,6 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use qrcode::{render::unicode, QrCode};
 use tari_comms::peer_manager::Peer;
 use unicode_segmentation::UnicodeSegmentation;
 
@@ -50,6 +51,21 @@ pub fn display_address(peer: &Peer) -> String {
     }
 }
 
+/// Utility function to render data, e.g. a Tari URI, as a QR code made of unicode block characters. Returns None if the
+/// data is too long to fit in a QR code.
+pub fn render_qr_code(data: &str) -> Option<String> {
+    let code = QrCode::new(data).ok()?;
+    let image = code
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Dark)
+        .light_color(unicode::Dense1x2::Light)
+        .build()
+        .lines()
+        .skip(1)
+        .fold("".to_string(), |acc, l| format!("{}{}\n", acc, l));
+    Some(image)
+}
+
 #[cfg(test)]
 mod test {
     use crate::utils::formatting::display_compressed_string;
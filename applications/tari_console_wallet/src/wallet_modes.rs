@@ -31,7 +31,8 @@ use crate::{
 use log::*;
 use rand::{rngs::OsRng, seq::SliceRandom};
 use std::{fs, io::Stdout, net::SocketAddr, path::PathBuf};
-use tari_app_utilities::utilities::ExitCodes;
+use tari_app_grpc::authentication::GrpcAuthenticator;
+use tari_app_utilities::utilities::{create_grpc_server_builder, ExitCodes};
 use tari_common::{ConfigBootstrap, GlobalConfig};
 use tari_comms::peer_manager::Peer;
 use tari_wallet::WalletSqlite;
@@ -156,11 +157,21 @@ pub fn script_mode(config: WalletModeConfig, wallet: WalletSqlite, path: PathBuf
     let mut commands = Vec::new();
 
     println!("Parsing commands...");
-    for command in script.lines() {
+    for (line_num, command) in script.lines().enumerate() {
+        let command = command.trim();
         // skip empty lines and 'comments' starting with #
         if !command.is_empty() && !command.starts_with('#') {
             // parse the command
-            commands.push(parse_command(command)?);
+            let parsed = parse_command(command).map_err(|err| {
+                error!(target: LOG_TARGET, "Failed to parse line {}: {}", line_num + 1, err);
+                ExitCodes::InputError(format!(
+                    "Failed to parse line {} (`{}`) of the input file! {}",
+                    line_num + 1,
+                    command,
+                    err
+                ))
+            })?;
+            commands.push(parsed);
         }
     }
     println!("{} commands parsed successfully.", commands.len());
@@ -213,8 +224,9 @@ pub fn tui_mode(config: WalletModeConfig, mut wallet: WalletSqlite) -> Result<()
         notify_script,
         ..
     } = config;
-    let grpc = WalletGrpcServer::new(wallet.clone());
-    handle.spawn(run_grpc(grpc, global_config.grpc_console_wallet_address));
+    let grpc = WalletGrpcServer::new(wallet.clone(), grpc_authenticator(&global_config));
+    let grpc_server = create_grpc_server_builder(&global_config, &global_config.grpc_console_wallet_address)?;
+    handle.spawn(run_grpc(grpc_server, grpc, global_config.grpc_console_wallet_address));
 
     let notifier = Notifier::new(notify_script, handle.clone(), wallet.clone());
 
@@ -284,18 +296,32 @@ pub fn grpc_mode(config: WalletModeConfig, wallet: WalletSqlite) -> Result<(), E
         global_config, handle, ..
     } = config;
     println!("Starting grpc server");
-    let grpc = WalletGrpcServer::new(wallet);
+    let grpc = WalletGrpcServer::new(wallet, grpc_authenticator(&global_config));
+    let grpc_server = create_grpc_server_builder(&global_config, &global_config.grpc_console_wallet_address)?;
     handle
-        .block_on(run_grpc(grpc, global_config.grpc_console_wallet_address))
+        .block_on(run_grpc(grpc_server, grpc, global_config.grpc_console_wallet_address))
         .map_err(ExitCodes::GrpcError)?;
     println!("Shutting down");
     Ok(())
 }
 
-async fn run_grpc(grpc: WalletGrpcServer, grpc_console_wallet_address: SocketAddr) -> Result<(), String> {
+fn grpc_authenticator(config: &GlobalConfig) -> GrpcAuthenticator {
+    GrpcAuthenticator::new(
+        config.grpc_read_only_token.clone(),
+        config.grpc_spend_token.clone(),
+        config.grpc_admin_token.clone(),
+        config.grpc_allow_unauthenticated,
+    )
+}
+
+async fn run_grpc(
+    mut grpc_server: Server,
+    grpc: WalletGrpcServer,
+    grpc_console_wallet_address: SocketAddr,
+) -> Result<(), String> {
     info!(target: LOG_TARGET, "Starting GRPC on {}", grpc_console_wallet_address);
 
-    Server::builder()
+    grpc_server
         .add_service(tari_app_grpc::tari_rpc::wallet_server::WalletServer::new(grpc))
         .serve(grpc_console_wallet_address)
         .await
@@ -176,6 +176,7 @@ impl BlockTemplateProtocol<'_> {
                 reward: block_reward,
                 fee: total_fees,
                 height: tari_height,
+                payouts: vec![],
             })
             .await
             .map_err(|status| MmProxyError::GrpcRequestError {
@@ -41,10 +41,11 @@ use futures::future;
 use hyper::{service::make_service_fn, Server};
 use proxy::{MergeMiningProxyConfig, MergeMiningProxyService};
 use std::convert::Infallible;
-use tari_app_grpc::tari_rpc as grpc;
+use tari_app_grpc::{authentication::bearer_token_interceptor, tari_rpc as grpc};
 use tari_app_utilities::initialization::init_configuration;
 use tari_common::configuration::bootstrap::ApplicationType;
 use tokio::time::Duration;
+use tonic::transport::Endpoint;
 
 #[tokio_macros::main]
 async fn main() -> Result<(), anyhow::Error> {
@@ -59,11 +60,19 @@ async fn main() -> Result<(), anyhow::Error> {
         .build()
         .map_err(MmProxyError::ReqwestError)?;
     println!("Connecting to base node at {}", config.grpc_base_node_address);
-    let base_node_client =
-        grpc::base_node_client::BaseNodeClient::connect(format!("http://{}", config.grpc_base_node_address)).await?;
+    let base_node_client = grpc::base_node_client::BaseNodeClient::with_interceptor(
+        Endpoint::new(format!("http://{}", config.grpc_base_node_address))?
+            .connect()
+            .await?,
+        bearer_token_interceptor(config.grpc_token.clone()),
+    );
     println!("Connecting to wallet at {}", config.grpc_console_wallet_address);
-    let wallet_client =
-        grpc::wallet_client::WalletClient::connect(format!("http://{}", config.grpc_console_wallet_address)).await?;
+    let wallet_client = grpc::wallet_client::WalletClient::with_interceptor(
+        Endpoint::new(format!("http://{}", config.grpc_console_wallet_address))?
+            .connect()
+            .await?,
+        bearer_token_interceptor(config.grpc_token.clone()),
+    );
     let xmrig_service = MergeMiningProxyService::new(
         config,
         client,
@@ -65,6 +65,8 @@ pub struct MergeMiningProxyConfig {
     pub monerod_use_auth: bool,
     pub grpc_base_node_address: SocketAddr,
     pub grpc_console_wallet_address: SocketAddr,
+    /// The token sent to the base node and console wallet gRPC servers, which must grant the spend permission
+    pub grpc_token: Option<String>,
     pub proxy_host_address: SocketAddr,
     pub proxy_submit_to_origin: bool,
     pub wait_for_initial_sync_at_startup: bool,
@@ -80,6 +82,7 @@ impl From<GlobalConfig> for MergeMiningProxyConfig {
             monerod_use_auth: config.monerod_use_auth,
             grpc_base_node_address: config.grpc_base_node_address,
             grpc_console_wallet_address: config.grpc_console_wallet_address,
+            grpc_token: config.grpc_spend_token,
             proxy_host_address: config.proxy_host_address,
             proxy_submit_to_origin: config.proxy_submit_to_origin,
             wait_for_initial_sync_at_startup: config.wait_for_initial_sync_at_startup,
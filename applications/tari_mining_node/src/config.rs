@@ -33,12 +33,22 @@
 //! - mine_on_tip_only - will start mining only when node is reporting bootstrapped state
 //! - validate_tip_timeout_sec - will check tip with node every N seconds to validate that still
 //! mining on a tip
+//! - auto_tune_threads - benchmark the hash rate at startup and pick the number of mining threads
+//! automatically, ignoring num_mining_threads
+//! - auto_tune_benchmark_ms - time spent benchmarking each thread count when auto tuning
+//! - max_cpu_utilization - percentage of the time mining threads spend hashing
+//! - max_cpu_temperature - lower the utilization while the CPU is hotter than this (in degrees Celsius)
+//! - temperature_check_interval_sec - how often the CPU temperature is read when max_cpu_temperature is set
+//! - control_socket_address - local address on which the miner accepts runtime tuning commands
+//! - coinbase_payouts - shares of the coinbase paid directly to other parties, as a list of
+//! `<hex public key>:<percentage>` entries
 //! All miner options configured under `[mining_node]` section of
 //! Tari's `config.toml`.
 
+use crate::errors::MinerError;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
-use tari_app_grpc::tari_rpc::{pow_algo::PowAlgos, NewBlockTemplateRequest, PowAlgo};
+use tari_app_grpc::tari_rpc::{pow_algo::PowAlgos, CoinbasePayout, NewBlockTemplateRequest, PowAlgo};
 use tari_common::{GlobalConfig, NetworkConfigPath};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -51,6 +61,13 @@ pub struct MinerConfig {
     pub validate_tip_timeout_sec: u64,
     pub mining_pool_address: String,
     pub mining_wallet_address: String,
+    pub auto_tune_threads: bool,
+    pub auto_tune_benchmark_ms: u64,
+    pub max_cpu_utilization: u8,
+    pub max_cpu_temperature: Option<f64>,
+    pub temperature_check_interval_sec: u64,
+    pub control_socket_address: Option<String>,
+    pub coinbase_payouts: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -75,6 +92,13 @@ impl Default for MinerConfig {
             validate_tip_timeout_sec: 30,
             mining_pool_address: "".to_string(),
             mining_wallet_address: "".to_string(),
+            auto_tune_threads: false,
+            auto_tune_benchmark_ms: 1000,
+            max_cpu_utilization: 100,
+            max_cpu_temperature: None,
+            temperature_check_interval_sec: 5,
+            control_socket_address: None,
+            coinbase_payouts: vec![],
         }
     }
 }
@@ -109,4 +133,36 @@ impl MinerConfig {
     pub fn validate_tip_timeout_sec(&self) -> Duration {
         Duration::from_secs(self.validate_tip_timeout_sec)
     }
+
+    pub fn auto_tune_benchmark_duration(&self) -> Duration {
+        Duration::from_millis(self.auto_tune_benchmark_ms)
+    }
+
+    pub fn temperature_check_interval(&self) -> Duration {
+        Duration::from_secs(self.temperature_check_interval_sec)
+    }
+
+    /// Parse the configured coinbase payouts, each formatted as `<hex public key>:<percentage>`
+    pub fn coinbase_payouts(&self) -> Result<Vec<CoinbasePayout>, MinerError> {
+        self.coinbase_payouts
+            .iter()
+            .map(|payout| {
+                let (destination, percentage) = payout
+                    .rsplit_once(':')
+                    .ok_or_else(|| MinerError::InvalidPayout(payout.clone()))?;
+                let percentage = percentage
+                    .trim()
+                    .trim_end_matches('%')
+                    .parse::<u32>()
+                    .map_err(|_| MinerError::InvalidPayout(payout.clone()))?;
+                if percentage == 0 || percentage > 100 {
+                    return Err(MinerError::InvalidPayout(payout.clone()));
+                }
+                Ok(CoinbasePayout {
+                    destination: destination.trim().to_string(),
+                    percentage,
+                })
+            })
+            .collect()
+    }
 }
@@ -40,6 +40,10 @@ pub enum MinerError {
     BlockHeader(String),
     #[error("Conversion error: {0}")]
     Conversion(String),
+    #[error("Miner tuning error: {0}")]
+    Tuning(std::io::Error),
+    #[error("Invalid coinbase payout `{0}`, expected `<hex public key>:<percentage>`")]
+    InvalidPayout(String),
 }
 
 pub fn err_empty(name: &str) -> MinerError {
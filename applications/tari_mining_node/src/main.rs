@@ -23,12 +23,15 @@
 use config::MinerConfig;
 use futures::stream::StreamExt;
 use log::*;
-use tari_app_grpc::tari_rpc::{base_node_client::BaseNodeClient, wallet_client::WalletClient};
+use tari_app_grpc::{
+    authentication::bearer_token_interceptor,
+    tari_rpc::{base_node_client::BaseNodeClient, wallet_client::WalletClient},
+};
 use tari_app_utilities::{initialization::init_configuration, utilities::ExitCodes};
 use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap, DefaultConfigLoader, GlobalConfig};
 use tari_core::blocks::BlockHeader;
 use tokio::{runtime::Runtime, time::delay_for};
-use tonic::transport::Channel;
+use tonic::transport::{Channel, Endpoint};
 use utils::{coinbase_request, extract_outputs_and_kernels};
 
 mod config;
@@ -36,6 +39,7 @@ mod difficulty;
 mod errors;
 mod miner;
 mod stratum;
+mod tuning;
 mod utils;
 
 use crate::{
@@ -53,6 +57,7 @@ use std::{
     thread,
     time::Instant,
 };
+use tuning::Throttle;
 
 /// Application entry point
 fn main() {
@@ -75,6 +80,22 @@ async fn main_inner() -> Result<(), ExitCodes> {
     config.validate_tip_timeout_sec = global.validate_tip_timeout_sec;
     debug!("{:?}", bootstrap);
     debug!("{:?}", config);
+    config
+        .coinbase_payouts()
+        .map_err(|e| ExitCodes::ConfigError(e.to_string()))?;
+
+    let max_threads = if config.auto_tune_threads {
+        println!("Benchmarking mining threads, this may take a moment...");
+        config.num_mining_threads = tuning::autotune_threads(num_cpus::get(), config.auto_tune_benchmark_duration())
+            .map_err(|e| {
+                error!("Failed to tune mining threads: {}", e);
+                ExitCodes::IOError(e.to_string())
+            })?;
+        println!("Mining with {} threads", config.num_mining_threads);
+        num_cpus::get()
+    } else {
+        config.num_mining_threads
+    };
 
     if !config.mining_wallet_address.is_empty() && !config.mining_pool_address.is_empty() {
         let url = config.mining_pool_address.clone();
@@ -129,12 +150,29 @@ async fn main_inner() -> Result<(), ExitCodes> {
         config.mine_on_tip_only = global.mine_on_tip_only;
         debug!("mine_on_tip_only is {}", config.mine_on_tip_only);
 
+        let throttle = Arc::new(Throttle::new(
+            max_threads,
+            config.num_mining_threads,
+            config.max_cpu_utilization,
+            config.max_cpu_temperature,
+        ));
+        if config.max_cpu_temperature.is_some() {
+            tuning::spawn_temperature_monitor(throttle.clone(), config.temperature_check_interval())
+                .map_err(|e| ExitCodes::IOError(e.to_string()))?;
+        }
+        if let Some(address) = config.control_socket_address.as_ref() {
+            tuning::spawn_control_socket(address, throttle.clone()).map_err(|e| {
+                error!("Failed to start miner control socket on {}: {}", address, e);
+                ExitCodes::IOError(e.to_string())
+            })?;
+        }
+
         let (mut node_conn, mut wallet_conn) = connect(&config, &global).await.map_err(ExitCodes::grpc)?;
 
         let mut blocks_found: u64 = 0;
         loop {
             debug!("Starting new mining cycle");
-            match mining_cycle(&mut node_conn, &mut wallet_conn, &config, &bootstrap).await {
+            match mining_cycle(&mut node_conn, &mut wallet_conn, &config, &bootstrap, &throttle).await {
                 err @ Err(MinerError::GrpcConnection(_)) | err @ Err(MinerError::GrpcStatus(_)) => {
                     // Any GRPC error we will try to reconnect with a standard delay
                     error!("Connection error: {:?}", err);
@@ -187,10 +225,17 @@ async fn connect(
 ) -> Result<(BaseNodeClient<Channel>, WalletClient<Channel>), MinerError> {
     let base_node_addr = config.base_node_addr(&global);
     info!("Connecting to base node at {}", base_node_addr);
-    let node_conn = BaseNodeClient::connect(base_node_addr.clone()).await?;
+    // Submitting blocks and requesting coinbases requires the spend permission
+    let node_conn = BaseNodeClient::with_interceptor(
+        Endpoint::new(base_node_addr.clone())?.connect().await?,
+        bearer_token_interceptor(global.grpc_spend_token.clone()),
+    );
     let wallet_addr = config.wallet_addr(&global);
     info!("Connecting to wallet at {}", wallet_addr);
-    let wallet_conn = WalletClient::connect(wallet_addr.clone()).await?;
+    let wallet_conn = WalletClient::with_interceptor(
+        Endpoint::new(wallet_addr.clone())?.connect().await?,
+        bearer_token_interceptor(global.grpc_spend_token.clone()),
+    );
 
     Ok((node_conn, wallet_conn))
 }
@@ -200,6 +245,7 @@ async fn mining_cycle(
     wallet_conn: &mut WalletClient<Channel>,
     config: &MinerConfig,
     bootstrap: &ConfigBootstrap,
+    throttle: &Arc<Throttle>,
 ) -> Result<bool, MinerError> {
     // 1. Receive new block template
     let template = node_conn
@@ -222,14 +268,14 @@ async fn mining_cycle(
     }
 
     // 2. Get coinbase from wallet and add it to new block template body
-    let request = coinbase_request(&template)?;
+    let request = coinbase_request(&template, config.coinbase_payouts()?)?;
     let coinbase = wallet_conn.get_coinbase(request).await?.into_inner();
-    let (output, kernel) = extract_outputs_and_kernels(coinbase)?;
+    let (outputs, kernel) = extract_outputs_and_kernels(coinbase)?;
     let body = block_template
         .body
         .as_mut()
         .ok_or_else(|| err_empty("new_block_template.body"))?;
-    body.outputs.push(output);
+    body.outputs.extend(outputs);
     body.kernels.push(kernel);
     let target_difficulty = template
         .miner_data
@@ -242,7 +288,7 @@ async fn mining_cycle(
     let header = block.clone().header.ok_or_else(|| err_empty("block.header"))?;
 
     // 4. Initialize miner and start receiving mining statuses in the loop
-    let mut reports = Miner::init_mining(header.clone(), target_difficulty, config.num_mining_threads);
+    let mut reports = Miner::init_mining(header.clone(), target_difficulty, throttle.clone());
     let template_time = Instant::now();
     let mut reporting_timeout = Instant::now();
     let mut block_submitted = false;
@@ -281,10 +327,10 @@ async fn mining_cycle(
                 block_submitted = true;
                 break;
             } else {
-                display_report(&report, config, template_time).await;
+                display_report(&report, throttle, template_time).await;
             }
         } else {
-            display_report(&report, config, template_time).await;
+            display_report(&report, throttle, template_time).await;
         }
         if config.mine_on_tip_only && reporting_timeout.elapsed() > config.validate_tip_timeout_sec() {
             validate_tip(node_conn, report.height, bootstrap.mine_until_height).await?;
@@ -296,17 +342,18 @@ async fn mining_cycle(
     Ok(block_submitted)
 }
 
-async fn display_report(report: &MiningReport, config: &MinerConfig, template_time: Instant) {
+async fn display_report(report: &MiningReport, throttle: &Throttle, template_time: Instant) {
+    let num_threads = throttle.active_threads();
     let hashrate = report.hashes as f64 / report.elapsed.as_micros() as f64;
-    let estimated_time = report.target_difficulty as f64 / (hashrate * config.num_mining_threads as f64 * 1000000.0);
+    let estimated_time = report.target_difficulty as f64 / (hashrate * num_threads as f64 * 1000000.0);
     let remaining = estimated_time as i32 - template_time.elapsed().as_secs() as i32;
     debug!(
         "Miner {} reported {:.2}MH/s with total {:.2}MH/s over {} threads. Height: {}. Target: {}, Estimated block in \
          approx. {}m{}s (+/- Ave. {:.0}s)",
         report.miner,
         hashrate,
-        hashrate * config.num_mining_threads as f64,
-        config.num_mining_threads,
+        hashrate * num_threads as f64,
+        num_threads,
         report.height,
         report.target_difficulty,
         remaining / 60,
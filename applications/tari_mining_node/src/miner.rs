@@ -20,12 +20,16 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //
-use super::difficulty::BlockHeaderSha3;
+use super::{difficulty::BlockHeaderSha3, tuning::Throttle};
 use crossbeam::channel::{bounded, Select, Sender, TrySendError};
 use futures::Stream;
 use log::*;
 use std::{
     pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     task::{Context, Poll, Waker},
     thread,
     time::{Duration, Instant},
@@ -37,6 +41,9 @@ use thread::JoinHandle;
 // ~400_000 hashes per second
 const REPORTING_FREQUENCY: u64 = 3_000_000;
 
+// Identify how often mining thread is checking the throttle, ~6 times per second
+const THROTTLE_FREQUENCY: u64 = 1 << 16;
+
 // Thread's stack size, ideally we would fit all thread's data in the CPU L1 cache
 const STACK_SIZE: usize = 32_000;
 
@@ -62,16 +69,20 @@ pub struct Miner {
     num_threads: usize,
     header: BlockHeader,
     target_difficulty: u64,
+    throttle: Arc<Throttle>,
+    stopped: Arc<AtomicBool>,
 }
 
 impl Miner {
-    pub fn init_mining(header: BlockHeader, target_difficulty: u64, num_threads: usize) -> Self {
+    pub fn init_mining(header: BlockHeader, target_difficulty: u64, throttle: Arc<Throttle>) -> Self {
         Self {
             threads: vec![],
             channels: vec![],
             header,
-            num_threads,
+            num_threads: throttle.max_threads(),
             target_difficulty,
+            throttle,
+            stopped: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -91,8 +102,10 @@ impl Miner {
                 let header = self.header.clone();
                 let waker = ctx.waker().clone();
                 let difficulty = self.target_difficulty;
+                let throttle = self.throttle.clone();
+                let stopped = self.stopped.clone();
                 let handle = thread
-                    .spawn(move || mining_task(header, difficulty, tx, waker, i))
+                    .spawn(move || mining_task(header, difficulty, tx, waker, i, throttle, stopped))
                     .expect("Failed to create mining thread");
                 (handle, rx)
             });
@@ -103,6 +116,13 @@ impl Miner {
     }
 }
 
+impl Drop for Miner {
+    fn drop(&mut self) {
+        // Paused threads are not reporting, so they would not notice that the receivers were dropped
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
 impl Stream for Miner {
     type Item = MiningReport;
 
@@ -162,8 +182,11 @@ pub fn mining_task(
     sender: Sender<MiningReport>,
     waker: Waker,
     miner: usize,
+    throttle: Arc<Throttle>,
+    stopped: Arc<AtomicBool>,
 ) {
     let start = Instant::now();
+    let mut busy_since = Instant::now();
     let mut hasher = BlockHeaderSha3::new(header).unwrap();
     hasher.random_nonce();
     // We're mining over here!
@@ -211,6 +234,13 @@ pub fn mining_task(
             }
             hasher.set_timestamp(timestamp().seconds as u64);
         }
+        if hasher.nonce % THROTTLE_FREQUENCY == 0 {
+            if !throttle.throttle(miner, busy_since.elapsed(), &stopped) {
+                info!("Mining thread {} stopped", miner);
+                return;
+            }
+            busy_since = Instant::now();
+        }
         hasher.inc_nonce();
     }
 }
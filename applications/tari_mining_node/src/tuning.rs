@@ -0,0 +1,488 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Thread count autotuning and CPU throttling for the SHA3 miner
+//!
+//! - [autotune_threads] benchmarks the hash rate for an increasing number of threads at startup and picks the
+//! thread count after which adding threads stops paying off
+//! - [Throttle] is shared with the mining threads and controls how many of them are active and which share of the
+//! time they spend hashing
+//! - [spawn_temperature_monitor] lowers the utilization while the CPU is hotter than the configured maximum
+//! - [spawn_control_socket] allows adjusting all of the above at runtime over a local TCP socket
+
+use crate::{difficulty::BlockHeaderSha3, errors::MinerError};
+use log::*;
+use std::{
+    fmt,
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+        Arc,
+        RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Path under which the OS exposes thermal zones, each with a `temp` file in millidegrees Celsius
+const THERMAL_ZONES_PATH: &str = "/sys/class/thermal";
+/// The utilization will never be throttled below this percentage by the temperature monitor
+pub const MIN_UTILIZATION: u8 = 10;
+/// Percentage the utilization is lowered by for every sample above the maximum temperature
+const UTILIZATION_STEP_DOWN: u8 = 10;
+/// Percentage the utilization is raised by for every sample below the maximum temperature (minus hysteresis)
+const UTILIZATION_STEP_UP: u8 = 5;
+/// Degrees Celsius the temperature needs to drop below the maximum before utilization is raised again
+const TEMPERATURE_HYSTERESIS: f64 = 5.0;
+/// A thread count is only accepted while every thread still performs at least this fraction of a single thread
+const MIN_THREAD_EFFICIENCY: f64 = 0.75;
+/// How long paused mining threads sleep before checking whether they may resume
+const PAUSED_SLEEP: Duration = Duration::from_millis(200);
+
+/// Shared mining throttle. Mining threads call [Throttle::throttle] regularly, which will pause threads above the
+/// active thread count and sleep for long enough to keep to the configured utilization.
+#[derive(Debug)]
+pub struct Throttle {
+    max_threads: usize,
+    active_threads: AtomicUsize,
+    target_utilization: AtomicU8,
+    utilization: AtomicU8,
+    max_temperature: RwLock<Option<f64>>,
+    temperature: RwLock<Option<f64>>,
+}
+
+impl Throttle {
+    /// Create a throttle for up to `max_threads` mining threads, of which `active_threads` will be mining
+    pub fn new(max_threads: usize, active_threads: usize, utilization: u8, max_temperature: Option<f64>) -> Self {
+        let max_threads = max_threads.max(1);
+        let utilization = clamp_utilization(utilization);
+        Self {
+            max_threads,
+            active_threads: AtomicUsize::new(active_threads.max(1).min(max_threads)),
+            target_utilization: AtomicU8::new(utilization),
+            utilization: AtomicU8::new(utilization),
+            max_temperature: RwLock::new(max_temperature),
+            temperature: RwLock::new(None),
+        }
+    }
+
+    /// The number of mining threads that should be spawned
+    pub fn max_threads(&self) -> usize {
+        self.max_threads
+    }
+
+    pub fn active_threads(&self) -> usize {
+        self.active_threads.load(Ordering::Relaxed)
+    }
+
+    /// Set the number of active mining threads, clamped to `1..=max_threads`. Returns the value that was set.
+    pub fn set_active_threads(&self, threads: usize) -> usize {
+        let threads = threads.max(1).min(self.max_threads);
+        self.active_threads.store(threads, Ordering::Relaxed);
+        threads
+    }
+
+    /// The effective utilization percentage, which may be lower than the target while the CPU is too hot
+    pub fn utilization(&self) -> u8 {
+        self.utilization.load(Ordering::Relaxed)
+    }
+
+    pub fn target_utilization(&self) -> u8 {
+        self.target_utilization.load(Ordering::Relaxed)
+    }
+
+    /// Set the target utilization percentage, clamped to `MIN_UTILIZATION..=100`. Returns the value that was set.
+    pub fn set_target_utilization(&self, utilization: u8) -> u8 {
+        let utilization = clamp_utilization(utilization);
+        self.target_utilization.store(utilization, Ordering::Relaxed);
+        self.utilization.store(utilization, Ordering::Relaxed);
+        utilization
+    }
+
+    pub fn max_temperature(&self) -> Option<f64> {
+        *self.max_temperature.read().unwrap()
+    }
+
+    pub fn set_max_temperature(&self, max_temperature: Option<f64>) {
+        *self.max_temperature.write().unwrap() = max_temperature;
+        if max_temperature.is_none() {
+            self.utilization.store(self.target_utilization(), Ordering::Relaxed);
+        }
+    }
+
+    /// The last temperature read by the temperature monitor, if any
+    pub fn temperature(&self) -> Option<f64> {
+        *self.temperature.read().unwrap()
+    }
+
+    /// Record a temperature sample and adjust the effective utilization towards the target, or below it while the
+    /// temperature exceeds the maximum
+    pub fn update_temperature(&self, temperature: f64) {
+        *self.temperature.write().unwrap() = Some(temperature);
+        let max_temperature = match self.max_temperature() {
+            Some(t) => t,
+            None => return,
+        };
+        let utilization = self.utilization();
+        let target = self.target_utilization();
+        if temperature > max_temperature {
+            let throttled = utilization.saturating_sub(UTILIZATION_STEP_DOWN).max(MIN_UTILIZATION);
+            if throttled != utilization {
+                info!(
+                    "CPU temperature {:.1}°C above maximum {:.1}°C, lowering utilization to {}%",
+                    temperature, max_temperature, throttled
+                );
+            }
+            self.utilization.store(throttled, Ordering::Relaxed);
+        } else if temperature < max_temperature - TEMPERATURE_HYSTERESIS && utilization < target {
+            let raised = utilization.saturating_add(UTILIZATION_STEP_UP).min(target);
+            debug!(
+                "CPU temperature {:.1}°C, raising utilization to {}%",
+                temperature, raised
+            );
+            self.utilization.store(raised, Ordering::Relaxed);
+        }
+    }
+
+    /// Called by mining thread `miner` after it has been hashing for `busy`. Blocks while the thread is paused and
+    /// sleeps for long enough to keep to the effective utilization. Returns false if `stopped` was set while waiting.
+    pub fn throttle(&self, miner: usize, busy: Duration, stopped: &AtomicBool) -> bool {
+        let mut was_paused = false;
+        while miner >= self.active_threads() {
+            if !was_paused {
+                debug!("Mining thread {} paused", miner);
+                was_paused = true;
+            }
+            if stopped.load(Ordering::Relaxed) {
+                return false;
+            }
+            thread::sleep(PAUSED_SLEEP);
+        }
+        if was_paused {
+            debug!("Mining thread {} resumed", miner);
+        }
+        let utilization = u32::from(self.utilization());
+        if utilization < 100 {
+            thread::sleep(busy * (100 - utilization) / utilization);
+        }
+        !stopped.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Display for Throttle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "threads {}/{}, utilization {}% (target {}%)",
+            self.active_threads(),
+            self.max_threads,
+            self.utilization(),
+            self.target_utilization()
+        )?;
+        if let Some(t) = self.temperature() {
+            write!(f, ", temperature {:.1}°C", t)?;
+        }
+        match self.max_temperature() {
+            Some(t) => write!(f, ", max temperature {:.1}°C", t),
+            None => write!(f, ", max temperature off"),
+        }
+    }
+}
+
+fn clamp_utilization(utilization: u8) -> u8 {
+    utilization.max(MIN_UTILIZATION).min(100)
+}
+
+/// Measure the combined SHA3 hash rate (hashes per second) of `num_threads` threads hashing for `duration`
+pub fn benchmark_hash_rate(num_threads: usize, duration: Duration) -> Result<f64, MinerError> {
+    let header = benchmark_header();
+    let threads = (0..num_threads.max(1))
+        .map(|i| {
+            let mut hasher = BlockHeaderSha3::new(header.clone())?;
+            hasher.random_nonce();
+            thread::Builder::new()
+                .name(format!("cpu-benchmark-{}", i))
+                .spawn(move || {
+                    let start = Instant::now();
+                    // Checking the clock on every hash would skew the measurement
+                    while hasher.hashes % 1024 != 0 || start.elapsed() < duration {
+                        hasher.difficulty();
+                        hasher.inc_nonce();
+                    }
+                    hasher.hashes
+                })
+                .map_err(MinerError::Tuning)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let hashes: u64 = threads.into_iter().map(|t| t.join().unwrap_or(0)).sum();
+    Ok(hashes as f64 / duration.as_secs_f64())
+}
+
+/// Benchmark the hash rate for 1 up to `max_threads` threads, spending `duration` on each, and return the number of
+/// threads to mine with. Stops adding threads once the total hash rate no longer increases or the per-thread hash
+/// rate drops below `MIN_THREAD_EFFICIENCY` of a single thread.
+pub fn autotune_threads(max_threads: usize, duration: Duration) -> Result<usize, MinerError> {
+    let single = benchmark_hash_rate(1, duration)?;
+    info!("Benchmark: 1 thread {:.2} MH/s", single / 1_000_000.0);
+    let mut best = (1, single);
+    for num_threads in 2..=max_threads {
+        let rate = benchmark_hash_rate(num_threads, duration)?;
+        info!(
+            "Benchmark: {} threads {:.2} MH/s ({:.2} MH/s per thread)",
+            num_threads,
+            rate / 1_000_000.0,
+            rate / num_threads as f64 / 1_000_000.0
+        );
+        if rate <= best.1 || rate / (num_threads as f64) < single * MIN_THREAD_EFFICIENCY {
+            break;
+        }
+        best = (num_threads, rate);
+    }
+    info!(
+        "Selected {} mining threads with an expected {:.2} MH/s",
+        best.0,
+        best.1 / 1_000_000.0
+    );
+    Ok(best.0)
+}
+
+fn benchmark_header() -> tari_app_grpc::tari_rpc::BlockHeader {
+    let mut header = tari_core::blocks::BlockHeader::new(0);
+    header.pow.pow_algo = tari_core::proof_of_work::PowAlgorithm::Sha3;
+    header.into()
+}
+
+/// Read the highest temperature in degrees Celsius over all thermal zones exposed by the OS, if any
+pub fn read_cpu_temperature() -> Option<f64> {
+    fs::read_dir(THERMAL_ZONES_PATH)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("thermal_zone"))
+        .filter_map(|entry| fs::read_to_string(entry.path().join("temp")).ok())
+        .filter_map(|temp| temp.trim().parse::<i64>().ok())
+        .map(|millidegrees| millidegrees as f64 / 1000.0)
+        .fold(None, |max: Option<f64>, t| Some(max.map_or(t, |m| m.max(t))))
+}
+
+/// Spawn a thread reading the CPU temperature every `interval` and updating the throttle. The thread exits if the OS
+/// does not expose a temperature.
+pub fn spawn_temperature_monitor(throttle: Arc<Throttle>, interval: Duration) -> Result<(), MinerError> {
+    thread::Builder::new()
+        .name("temperature-monitor".to_string())
+        .spawn(move || loop {
+            match read_cpu_temperature() {
+                Some(temperature) => throttle.update_temperature(temperature),
+                None => {
+                    warn!("CPU temperature is not available on this system, temperature throttling is disabled");
+                    return;
+                },
+            }
+            thread::sleep(interval);
+        })
+        .map_err(MinerError::Tuning)?;
+    Ok(())
+}
+
+/// Commands accepted on the control socket, one per line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlCommand {
+    /// `status`
+    Status,
+    /// `threads <n>`
+    Threads(usize),
+    /// `utilization <percent>`
+    Utilization(u8),
+    /// `max_temperature <celsius|off>`
+    MaxTemperature(Option<f64>),
+}
+
+impl FromStr for ControlCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let command = parts.next().ok_or_else(|| "empty command".to_string())?;
+        let arg = parts.next();
+        if parts.next().is_some() {
+            return Err(format!("too many arguments for '{}'", command));
+        }
+        let missing = || format!("'{}' requires an argument", command);
+        match command.to_lowercase().as_str() {
+            "status" => Ok(ControlCommand::Status),
+            "threads" => arg
+                .ok_or_else(missing)?
+                .parse()
+                .map(ControlCommand::Threads)
+                .map_err(|e| format!("invalid thread count: {}", e)),
+            "utilization" => arg
+                .ok_or_else(missing)?
+                .trim_end_matches('%')
+                .parse()
+                .map(ControlCommand::Utilization)
+                .map_err(|e| format!("invalid utilization: {}", e)),
+            "max_temperature" => match arg.ok_or_else(missing)? {
+                "off" => Ok(ControlCommand::MaxTemperature(None)),
+                t => t
+                    .parse()
+                    .map(|t| ControlCommand::MaxTemperature(Some(t)))
+                    .map_err(|e| format!("invalid temperature: {}", e)),
+            },
+            _ => Err(format!("unknown command '{}'", command)),
+        }
+    }
+}
+
+impl ControlCommand {
+    /// Apply the command to the throttle and return the response line
+    pub fn apply(&self, throttle: &Throttle) -> String {
+        match self {
+            ControlCommand::Status => {},
+            ControlCommand::Threads(n) => {
+                throttle.set_active_threads(*n);
+            },
+            ControlCommand::Utilization(u) => {
+                throttle.set_target_utilization(*u);
+            },
+            ControlCommand::MaxTemperature(t) => throttle.set_max_temperature(*t),
+        }
+        throttle.to_string()
+    }
+}
+
+/// Listen on `address` (which should be a local address) for control connections adjusting the throttle at runtime
+pub fn spawn_control_socket(address: &str, throttle: Arc<Throttle>) -> Result<(), MinerError> {
+    let listener = TcpListener::bind(address).map_err(MinerError::Tuning)?;
+    info!("Miner control socket listening on {}", address);
+    thread::Builder::new()
+        .name("miner-control".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let throttle = throttle.clone();
+                        let _ = thread::Builder::new()
+                            .name("miner-control-conn".to_string())
+                            .spawn(move || handle_control_connection(stream, &throttle));
+                    },
+                    Err(e) => warn!("Control socket connection failed: {}", e),
+                }
+            }
+        })
+        .map_err(MinerError::Tuning)?;
+    Ok(())
+}
+
+fn handle_control_connection(stream: TcpStream, throttle: &Throttle) {
+    let mut writer = match stream.try_clone() {
+        Ok(w) => w,
+        Err(e) => {
+            warn!("Control socket connection failed: {}", e);
+            return;
+        },
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => return,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match line.parse::<ControlCommand>() {
+            Ok(command) => {
+                debug!("Control command: {:?}", command);
+                format!("ok: {}", command.apply(throttle))
+            },
+            Err(e) => format!("error: {}", e),
+        };
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_control_commands() {
+        assert_eq!("status".parse(), Ok(ControlCommand::Status));
+        assert_eq!(" threads 4 ".parse(), Ok(ControlCommand::Threads(4)));
+        assert_eq!("utilization 50%".parse(), Ok(ControlCommand::Utilization(50)));
+        assert_eq!(
+            "max_temperature 72.5".parse(),
+            Ok(ControlCommand::MaxTemperature(Some(72.5)))
+        );
+        assert_eq!("max_temperature off".parse(), Ok(ControlCommand::MaxTemperature(None)));
+        assert!("threads".parse::<ControlCommand>().is_err());
+        assert!("threads four".parse::<ControlCommand>().is_err());
+        assert!("threads 4 5".parse::<ControlCommand>().is_err());
+        assert!("overclock".parse::<ControlCommand>().is_err());
+        assert!("".parse::<ControlCommand>().is_err());
+    }
+
+    #[test]
+    fn throttle_limits() {
+        let throttle = Throttle::new(4, 8, 150, None);
+        assert_eq!(throttle.active_threads(), 4);
+        assert_eq!(throttle.utilization(), 100);
+        assert_eq!(throttle.set_active_threads(0), 1);
+        assert_eq!(throttle.set_target_utilization(1), MIN_UTILIZATION);
+        ControlCommand::Threads(3).apply(&throttle);
+        assert_eq!(throttle.active_threads(), 3);
+    }
+
+    #[test]
+    fn temperature_throttling() {
+        let throttle = Throttle::new(2, 2, 100, Some(80.0));
+        throttle.update_temperature(85.0);
+        assert_eq!(throttle.utilization(), 90);
+        for _ in 0..20 {
+            throttle.update_temperature(90.0);
+        }
+        assert_eq!(throttle.utilization(), MIN_UTILIZATION);
+        // Within the hysteresis band nothing changes
+        throttle.update_temperature(77.0);
+        assert_eq!(throttle.utilization(), MIN_UTILIZATION);
+        throttle.update_temperature(70.0);
+        assert_eq!(throttle.utilization(), MIN_UTILIZATION + UTILIZATION_STEP_UP);
+        for _ in 0..40 {
+            throttle.update_temperature(70.0);
+        }
+        assert_eq!(throttle.utilization(), 100);
+        assert_eq!(throttle.temperature(), Some(70.0));
+
+        throttle.update_temperature(95.0);
+        throttle.set_max_temperature(None);
+        assert_eq!(throttle.utilization(), 100);
+    }
+
+    #[test]
+    fn benchmark_hashes() {
+        let rate = benchmark_hash_rate(1, Duration::from_millis(50)).unwrap();
+        assert!(rate > 0.0);
+    }
+}
@@ -22,6 +22,7 @@
 //
 use crate::errors::{err_empty, MinerError};
 use tari_app_grpc::tari_rpc::{
+    CoinbasePayout,
     GetCoinbaseRequest,
     GetCoinbaseResponse,
     NewBlockTemplateResponse,
@@ -30,7 +31,10 @@ use tari_app_grpc::tari_rpc::{
 };
 
 /// Convert NewBlockTemplateResponse to GetCoinbaseRequest
-pub fn coinbase_request(template_response: &NewBlockTemplateResponse) -> Result<GetCoinbaseRequest, MinerError> {
+pub fn coinbase_request(
+    template_response: &NewBlockTemplateResponse,
+    payouts: Vec<CoinbasePayout>,
+) -> Result<GetCoinbaseRequest, MinerError> {
     let template = template_response
         .new_block_template
         .as_ref()
@@ -46,26 +50,30 @@ pub fn coinbase_request(template_response: &NewBlockTemplateResponse) -> Result<
         .as_ref()
         .ok_or_else(|| err_empty("template.header"))?
         .height;
-    Ok(GetCoinbaseRequest { reward, fee, height })
+    Ok(GetCoinbaseRequest {
+        reward,
+        fee,
+        height,
+        payouts,
+    })
 }
 
+/// Extract the coinbase outputs and kernel. A split coinbase has an output for every payout.
 pub fn extract_outputs_and_kernels(
     coinbase: GetCoinbaseResponse,
-) -> Result<(TransactionOutput, TransactionKernel), MinerError> {
+) -> Result<(Vec<TransactionOutput>, TransactionKernel), MinerError> {
     let transaction_body = coinbase
         .transaction
         .ok_or_else(|| err_empty("coinbase.transaction"))?
         .body
         .ok_or_else(|| err_empty("transaction.body"))?;
-    let output = transaction_body
-        .outputs
-        .get(0)
-        .cloned()
-        .ok_or_else(|| err_empty("transaction.body.outputs"))?;
+    if transaction_body.outputs.is_empty() {
+        return Err(err_empty("transaction.body.outputs"));
+    }
     let kernel = transaction_body
         .kernels
         .get(0)
         .cloned()
         .ok_or_else(|| err_empty("transaction.body.kernels"))?;
-    Ok((output, kernel))
+    Ok((transaction_body.outputs, kernel))
 }
@@ -37,9 +37,10 @@ use hyper::{service::make_service_fn, Server};
 use proxy::{StratumTranscoderProxyConfig, StratumTranscoderProxyService};
 use std::convert::Infallible;
 use structopt::StructOpt;
-use tari_app_grpc::tari_rpc as grpc;
+use tari_app_grpc::{authentication::bearer_token_interceptor, tari_rpc as grpc};
 use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap, GlobalConfig};
 use tokio::time::Duration;
+use tonic::transport::Endpoint;
 
 #[tokio_macros::main]
 async fn main() -> Result<(), StratumTranscoderProxyError> {
@@ -53,10 +54,18 @@ async fn main() -> Result<(), StratumTranscoderProxyError> {
         .pool_max_idle_per_host(25)
         .build()
         .map_err(StratumTranscoderProxyError::ReqwestError)?;
-    let base_node_client =
-        grpc::base_node_client::BaseNodeClient::connect(format!("http://{}", config.grpc_base_node_address)).await?;
-    let wallet_client =
-        grpc::wallet_client::WalletClient::connect(format!("http://{}", config.grpc_console_wallet_address)).await?;
+    let base_node_client = grpc::base_node_client::BaseNodeClient::with_interceptor(
+        Endpoint::new(format!("http://{}", config.grpc_base_node_address))?
+            .connect()
+            .await?,
+        bearer_token_interceptor(config.grpc_token.clone()),
+    );
+    let wallet_client = grpc::wallet_client::WalletClient::with_interceptor(
+        Endpoint::new(format!("http://{}", config.grpc_console_wallet_address))?
+            .connect()
+            .await?,
+        bearer_token_interceptor(config.grpc_token.clone()),
+    );
     let miningcore_service = StratumTranscoderProxyService::new(config, client, base_node_client, wallet_client);
     let service = make_service_fn(|_conn| future::ready(Result::<_, Infallible>::Ok(miningcore_service.clone())));
 
@@ -55,6 +55,8 @@ pub struct StratumTranscoderProxyConfig {
     pub network: Network,
     pub grpc_base_node_address: SocketAddr,
     pub grpc_console_wallet_address: SocketAddr,
+    /// The token sent to the base node and console wallet gRPC servers, which must grant the spend permission
+    pub grpc_token: Option<String>,
     pub transcoder_host_address: SocketAddr,
 }
 
@@ -64,6 +66,7 @@ impl From<GlobalConfig> for StratumTranscoderProxyConfig {
             network: config.network,
             grpc_base_node_address: config.grpc_base_node_address,
             grpc_console_wallet_address: config.grpc_console_wallet_address,
+            grpc_token: config.grpc_spend_token,
             transcoder_host_address: config.transcoder_host_address,
         }
     }
@@ -214,6 +217,7 @@ impl InnerService {
                 reward: block_reward,
                 fee: total_fees,
                 height: tari_height,
+                payouts: vec![],
             })
             .await
             .map_err(|status| StratumTranscoderProxyError::GrpcRequestError {
@@ -504,6 +508,7 @@ impl InnerService {
                 fee_per_gram: recipient["fee_per_gram"].as_u64().unwrap(),
                 message: recipient["message"].as_str().unwrap().to_string(),
                 payment_type: 1,
+                idempotency_key: recipient["idempotency_key"].as_str().unwrap_or_default().to_string(),
             });
         }
 
@@ -40,6 +40,13 @@ pub struct ChainMetadata {
     pruned_height: u64,
     /// The geometric mean of the proof of work of the longest chain, none if the chain is empty
     accumulated_difficulty: u128,
+    /// The accumulated difficulty of the Monero (RandomX) blocks of the longest chain. Not serialized, so that chain
+    /// metadata stored by earlier versions can still be read.
+    #[serde(skip)]
+    accumulated_monero_difficulty: u64,
+    /// The accumulated difficulty of the SHA3 blocks of the longest chain. Not serialized, see above.
+    #[serde(skip)]
+    accumulated_sha_difficulty: u64,
 }
 
 impl ChainMetadata {
@@ -56,9 +63,18 @@ impl ChainMetadata {
             pruning_horizon,
             pruned_height,
             accumulated_difficulty,
+            accumulated_monero_difficulty: 0,
+            accumulated_sha_difficulty: 0,
         }
     }
 
+    /// Sets the accumulated difficulty of each proof of work algorithm
+    pub fn with_pow_accumulated_difficulty(mut self, monero: u64, sha3: u64) -> Self {
+        self.accumulated_monero_difficulty = monero;
+        self.accumulated_sha_difficulty = sha3;
+        self
+    }
+
     pub fn empty() -> ChainMetadata {
         ChainMetadata {
             height_of_longest_chain: 0,
@@ -66,6 +82,8 @@ impl ChainMetadata {
             pruning_horizon: 0,
             pruned_height: 0,
             accumulated_difficulty: 0,
+            accumulated_monero_difficulty: 0,
+            accumulated_sha_difficulty: 0,
         }
     }
 
@@ -116,6 +134,16 @@ impl ChainMetadata {
         self.accumulated_difficulty
     }
 
+    /// The accumulated difficulty of the Monero (RandomX) blocks of the longest chain. Zero if it is not known.
+    pub fn accumulated_monero_difficulty(&self) -> u64 {
+        self.accumulated_monero_difficulty
+    }
+
+    /// The accumulated difficulty of the SHA3 blocks of the longest chain. Zero if it is not known.
+    pub fn accumulated_sha_difficulty(&self) -> u64 {
+        self.accumulated_sha_difficulty
+    }
+
     pub fn best_block(&self) -> &BlockHash {
         &self.best_block
     }
@@ -131,6 +159,10 @@ impl Display for ChainMetadata {
             "Geometric mean of longest chain : {}\n",
             accumulated_difficulty
         ))?;
+        fmt.write_str(&format!(
+            "Accumulated difficulty (Monero/SHA3) : {}/{}\n",
+            self.accumulated_monero_difficulty, self.accumulated_sha_difficulty
+        ))?;
         fmt.write_str(&format!("Best block : {}\n", best_block))?;
         fmt.write_str(&format!("Pruning horizon : {}\n", self.pruning_horizon))?;
         fmt.write_str(&format!("Effective pruned height : {}\n", self.pruned_height))?;
@@ -331,6 +331,8 @@ mod test {
             best_block: Some(vec![]),
             pruned_height: 0,
             accumulated_difficulty: diff.to_be_bytes().to_vec(),
+            accumulated_monero_difficulty: 0,
+            accumulated_sha_difficulty: 0,
         }
     }
 
@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    blocks::NewBlockTemplate,
+    blocks::{KernelShortId, NewBlockTemplate},
     chain_storage::MmrTree,
     proof_of_work::PowAlgorithm,
     transactions::types::{Commitment, HashOutput, Signature},
@@ -56,6 +56,7 @@ pub enum NodeCommsRequest {
     GetNewBlockTemplate(GetNewBlockTemplateRequest),
     GetNewBlock(NewBlockTemplate),
     FetchKernelByExcessSig(Signature),
+    FetchMempoolTransactionsByKernelShortIds(Vec<KernelShortId>),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -88,6 +89,9 @@ impl Display for NodeCommsRequest {
                 s.get_public_nonce().to_hex(),
                 s.get_signature().to_hex()
             ),
+            FetchMempoolTransactionsByKernelShortIds(v) => {
+                write!(f, "FetchMempoolTransactionsByKernelShortIds (n={})", v.len())
+            },
         }
     }
 }
@@ -25,7 +25,7 @@ use crate::{
     chain_storage::HistoricalBlock,
     proof_of_work::Difficulty,
     transactions::{
-        transaction::{TransactionKernel, TransactionOutput},
+        transaction::{Transaction, TransactionKernel, TransactionOutput},
         types::HashOutput,
     },
 };
@@ -52,6 +52,7 @@ pub enum NodeCommsResponse {
     TargetDifficulty(Difficulty),
     FetchHeadersAfterResponse(Vec<BlockHeader>),
     MmrNodes(Vec<HashOutput>, Vec<u8>),
+    Transactions(Vec<Transaction>),
 }
 
 impl Display for NodeCommsResponse {
@@ -79,6 +80,7 @@ impl Display for NodeCommsResponse {
             TargetDifficulty(_) => write!(f, "TargetDifficulty"),
             FetchHeadersAfterResponse(_) => write!(f, "FetchHeadersAfterResponse"),
             MmrNodes(_, _) => write!(f, "MmrNodes"),
+            Transactions(txs) => write!(f, "Transactions (n={})", txs.len()),
         }
     }
 }
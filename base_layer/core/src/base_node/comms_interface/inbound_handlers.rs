@@ -29,7 +29,7 @@ use crate::{
         },
         OutboundNodeCommsInterface,
     },
-    blocks::{block_header::BlockHeader, Block, NewBlock, NewBlockTemplate},
+    blocks::{block_header::BlockHeader, Block, CompactBlock, NewBlock, NewBlockTemplate},
     chain_storage::{async_db::AsyncBlockchainDb, BlockAddResult, BlockchainBackend, ChainBlock},
     consensus::{ConsensusConstants, ConsensusManager},
     mempool::{async_mempool, Mempool},
@@ -412,9 +412,25 @@ where T: BlockchainBackend + 'static
 
                 Ok(NodeCommsResponse::TransactionKernels(kernels))
             },
+            NodeCommsRequest::FetchMempoolTransactionsByKernelShortIds(short_ids) => {
+                let transactions = async_mempool::retrieve_by_kernel_short_ids(self.mempool.clone(), short_ids).await?;
+                debug!(
+                    target: LOG_TARGET,
+                    "Returning {} mempool transaction(s) for compact block reconstruction",
+                    transactions.len()
+                );
+                Ok(NodeCommsResponse::Transactions(
+                    transactions.into_iter().map(|tx| (*tx).clone()).collect(),
+                ))
+            },
         }
     }
 
+    /// Returns true if the block with the given hash has already been stored.
+    pub async fn is_block_stored(&self, block_hash: BlockHash) -> Result<bool, CommsInterfaceError> {
+        Ok(self.blockchain_db.block_exists(block_hash).await?)
+    }
+
     /// Handles a `NewBlock` message. Only a single `NewBlock` message can be handled at once to prevent extraneous
     /// requests for the full block.
     /// This may (asynchronously) block until the other request(s) complete or time out and so should typically be
@@ -424,13 +440,17 @@ where T: BlockchainBackend + 'static
         new_block: NewBlock,
         source_peer: NodeId,
     ) -> Result<(), CommsInterfaceError> {
-        let NewBlock { block_hash } = new_block;
+        let NewBlock {
+            block_hash,
+            compact_block,
+        } = new_block;
 
         // Only a single block request can complete at a time.
         // As multiple NewBlock requests arrive from propagation, this semaphore prevents multiple requests to nodes for
         // the same full block. The first request that succeeds will stop the node from requesting the block from any
         // other node (block_exists is true).
-        let _permit = self.new_block_request_semaphore.acquire().await;
+        let new_block_request_semaphore = self.new_block_request_semaphore.clone();
+        let _permit = new_block_request_semaphore.acquire().await;
 
         if self.blockchain_db.block_exists(block_hash.clone()).await? {
             debug!(
@@ -441,6 +461,42 @@ where T: BlockchainBackend + 'static
             return Ok(());
         }
 
+        if let Some(compact_block) = compact_block {
+            match self
+                .reconstruct_compact_block(&block_hash, &compact_block, &source_peer)
+                .await
+            {
+                Ok(block) => {
+                    // A reconstructed block that fails validation may have been built from the wrong transactions
+                    // (e.g. a short id collision), so the full block is requested before giving up on it.
+                    match self
+                        .handle_block(Arc::new(block), true.into(), Some(source_peer.clone()))
+                        .await
+                    {
+                        Ok(_) => return Ok(()),
+                        Err(CommsInterfaceError::ChainStorageError(err)) => {
+                            debug!(
+                                target: LOG_TARGET,
+                                "Reconstructed block `{}` was rejected ({}). Requesting the full block.",
+                                block_hash.to_hex(),
+                                err
+                            );
+                        },
+                        Err(err) => return Err(err),
+                    }
+                },
+                Err(err) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Could not reconstruct compact block `{}` from peer `{}`: {}. Requesting the full block.",
+                        block_hash.to_hex(),
+                        source_peer.short_str(),
+                        err
+                    );
+                },
+            }
+        }
+
         debug!(
             target: LOG_TARGET,
             "Block with hash `{}` is unknown. Requesting it from peer `{}`.",
@@ -473,6 +529,53 @@ where T: BlockchainBackend + 'static
         }
     }
 
+    /// Rebuilds a compact block from the transactions in the local mempool, requesting any missing transactions from
+    /// the peer that propagated the block.
+    #[allow(clippy::ptr_arg)]
+    async fn reconstruct_compact_block(
+        &mut self,
+        block_hash: &BlockHash,
+        compact_block: &CompactBlock,
+        source_peer: &NodeId,
+    ) -> Result<Block, CommsInterfaceError> {
+        if &compact_block.header.hash() != block_hash {
+            return Err(CommsInterfaceError::InvalidPeerResponse(format!(
+                "Compact block header from peer `{}` does not match the propagated block hash",
+                source_peer.short_str()
+            )));
+        }
+
+        let mut transactions =
+            async_mempool::retrieve_by_kernel_short_ids(self.mempool.clone(), compact_block.kernel_short_ids.clone())
+                .await?;
+        let missing = compact_block.missing_short_ids(&transactions);
+        if !missing.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "{} of {} transaction(s) for compact block `{}` are not in the mempool. Requesting them from peer \
+                 `{}`.",
+                missing.len(),
+                compact_block.kernel_short_ids.len(),
+                block_hash.to_hex(),
+                source_peer.short_str()
+            );
+            let missing_transactions = self
+                .outbound_nci
+                .request_mempool_transactions_by_kernel_short_ids(missing, source_peer.clone())
+                .await?;
+            transactions.extend(missing_transactions.into_iter().map(Arc::new));
+        }
+
+        let block = compact_block.reconstruct(&transactions)?;
+        debug!(
+            target: LOG_TARGET,
+            "Reconstructed compact block `{}` with {} kernel(s)",
+            block_hash.to_hex(),
+            compact_block.num_kernels()
+        );
+        Ok(block)
+    }
+
     /// Handle inbound blocks from remote nodes and local services.
     pub async fn handle_block(
         &self,
@@ -508,7 +611,7 @@ where T: BlockchainBackend + 'static
 
                 self.blockchain_db.cleanup_orphans().await?;
 
-                self.publish_block_event(BlockEvent::ValidBlockAdded(block, block_add_result, broadcast));
+                self.publish_block_event(BlockEvent::ValidBlockAdded(block.clone(), block_add_result, broadcast));
 
                 if should_propagate && broadcast.is_true() {
                     info!(
@@ -517,7 +620,7 @@ where T: BlockchainBackend + 'static
                         block_hash.to_hex()
                     );
                     let exclude_peers = source_peer.into_iter().collect();
-                    let new_block = NewBlock::new(block_hash.clone());
+                    let new_block = NewBlock::from(&*block);
                     self.outbound_nci.propagate_block(new_block, exclude_peers).await?;
                 }
                 Ok(block_hash)
@@ -22,9 +22,12 @@
 
 use crate::{
     base_node::comms_interface::{error::CommsInterfaceError, NodeCommsRequest, NodeCommsResponse},
-    blocks::{block_header::BlockHeader, NewBlock},
+    blocks::{block_header::BlockHeader, KernelShortId, NewBlock},
     chain_storage::HistoricalBlock,
-    transactions::{transaction::TransactionOutput, types::HashOutput},
+    transactions::{
+        transaction::{Transaction, TransactionOutput},
+        types::HashOutput,
+    },
 };
 use futures::channel::mpsc::UnboundedSender;
 use log::*;
@@ -228,6 +231,27 @@ impl OutboundNodeCommsInterface {
         }
     }
 
+    /// Fetch the mempool transactions containing kernels with the provided short ids from a specific base node. This is
+    /// used to fill in the transactions missing from the local mempool when reconstructing a compact block.
+    pub async fn request_mempool_transactions_by_kernel_short_ids(
+        &mut self,
+        short_ids: Vec<KernelShortId>,
+        node_id: NodeId,
+    ) -> Result<Vec<Transaction>, CommsInterfaceError> {
+        if let NodeCommsResponse::Transactions(transactions) = self
+            .request_sender
+            .call((
+                NodeCommsRequest::FetchMempoolTransactionsByKernelShortIds(short_ids),
+                Some(node_id),
+            ))
+            .await??
+        {
+            Ok(transactions)
+        } else {
+            Err(CommsInterfaceError::UnexpectedApiResponse)
+        }
+    }
+
     /// Transmit a block to remote base nodes, excluding the provided peers.
     pub async fn propagate_block(
         &self,
@@ -0,0 +1,305 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    blocks::Block,
+    transactions::{
+        tari_amount::MicroTari,
+        transaction::{KernelFeatures, OutputFlags, Transaction},
+        weight::TransactionWeight,
+    },
+};
+use std::{cmp, collections::VecDeque};
+
+/// The largest confirmation target that an estimate can be requested for
+pub const MAX_TARGET_BLOCKS: u64 = 100;
+/// Recent blocks are considered congested if at least this percentage of them were filled to this percentage of the
+/// maximum block weight
+const CONGESTED_BLOCKS_PERCENT: usize = 50;
+const FULL_BLOCK_WEIGHT_PERCENT: u64 = 90;
+
+/// The fee statistics of a mined block
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockFeeStats {
+    pub height: u64,
+    /// The weight of the block, not counting the coinbase
+    pub weight: u64,
+    /// The approximate fee per gram paid by each transaction in the block, in ascending order. Transactions are
+    /// aggregated in a block, so each kernel is taken to be a transaction of the average weight.
+    pub fees_per_gram: Vec<u64>,
+}
+
+impl BlockFeeStats {
+    pub fn from_block(block: &Block, weighting: &TransactionWeight) -> Self {
+        let kernels = block
+            .body
+            .kernels()
+            .iter()
+            .filter(|k| !k.features.contains(KernelFeatures::COINBASE_KERNEL))
+            .collect::<Vec<_>>();
+        let outputs = block
+            .body
+            .outputs()
+            .iter()
+            .filter(|o| !o.features.flags.contains(OutputFlags::COINBASE_OUTPUT))
+            .collect::<Vec<_>>();
+        let weight = weighting.calculate(
+            kernels.len(),
+            block.body.inputs().len(),
+            outputs.len(),
+            weighting.outputs_metadata_weight(outputs.iter().copied()),
+        );
+
+        let mut fees_per_gram = match weight / cmp::max(kernels.len() as u64, 1) {
+            0 => Vec::new(),
+            average_weight => kernels.iter().map(|k| k.fee.0 / average_weight).collect(),
+        };
+        fees_per_gram.sort_unstable();
+
+        Self {
+            height: block.header.height,
+            weight,
+            fees_per_gram,
+        }
+    }
+}
+
+/// The fee per gram and weight of a transaction waiting in the mempool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MempoolTxFee {
+    pub fee_per_gram: u64,
+    pub weight: u64,
+}
+
+impl MempoolTxFee {
+    pub fn from_transaction(tx: &Transaction, weighting: &TransactionWeight) -> Self {
+        Self {
+            fee_per_gram: tx.calculate_ave_fee_per_gram(weighting) as u64,
+            weight: tx.calculate_weight(weighting),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// The fee per gram expected to get a transaction mined within `target_blocks` blocks
+    pub fee_per_gram: MicroTari,
+    pub target_blocks: u64,
+    /// The total weight of the transactions waiting in the mempool
+    pub mempool_weight: u64,
+    /// The number of recent blocks that the estimate is based on
+    pub num_blocks_analysed: usize,
+}
+
+/// Estimates the fee per gram needed to have a transaction mined within a number of blocks.
+///
+/// The estimate is the highest of:
+/// 1. the minimum fee per gram that the mempool relays,
+/// 1. the fee per gram that outbids the mempool backlog that would otherwise fill the target number of blocks, and
+/// 1. if recent blocks were mostly full, a percentile of the fees per gram paid in those blocks. The shorter the
+///    target, the higher the percentile.
+#[derive(Debug, Clone)]
+pub struct FeeEstimator {
+    blocks: VecDeque<BlockFeeStats>,
+    max_blocks: usize,
+}
+
+impl FeeEstimator {
+    pub fn new(max_blocks: usize) -> Self {
+        Self {
+            blocks: VecDeque::with_capacity(max_blocks),
+            max_blocks,
+        }
+    }
+
+    /// Adds the stats of a block to the chain tip. Blocks at or above its height, which have been reorged out, are
+    /// removed.
+    pub fn add_block(&mut self, stats: BlockFeeStats) {
+        self.rewind_to(stats.height.saturating_sub(1));
+        self.blocks.push_back(stats);
+        while self.blocks.len() > self.max_blocks {
+            self.blocks.pop_front();
+        }
+    }
+
+    /// Removes the stats of blocks above `height`
+    pub fn rewind_to(&mut self, height: u64) {
+        while self.blocks.back().map(|b| b.height > height).unwrap_or(false) {
+            self.blocks.pop_back();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn estimate(
+        &self,
+        target_blocks: u64,
+        mempool: &[MempoolTxFee],
+        max_block_weight: u64,
+        min_fee_per_gram: MicroTari,
+    ) -> FeeEstimate {
+        let target_blocks = cmp::min(cmp::max(target_blocks, 1), MAX_TARGET_BLOCKS);
+        let fee_per_gram = cmp::max(
+            min_fee_per_gram.0,
+            cmp::max(
+                self.mempool_fee_per_gram(target_blocks, mempool, max_block_weight),
+                self.historical_fee_per_gram(target_blocks, max_block_weight),
+            ),
+        );
+
+        FeeEstimate {
+            fee_per_gram: MicroTari(fee_per_gram),
+            target_blocks,
+            mempool_weight: mempool.iter().map(|tx| tx.weight).sum(),
+            num_blocks_analysed: self.blocks.len(),
+        }
+    }
+
+    /// The fee per gram that places a transaction in the first `target_blocks` blocks' worth of the mempool, or 0 if
+    /// the whole mempool fits in those blocks
+    fn mempool_fee_per_gram(&self, target_blocks: u64, mempool: &[MempoolTxFee], max_block_weight: u64) -> u64 {
+        let mut mempool = mempool.to_vec();
+        mempool.sort_unstable_by(|a, b| b.fee_per_gram.cmp(&a.fee_per_gram));
+        let capacity = max_block_weight.saturating_mul(target_blocks);
+        let mut total_weight = 0u64;
+        for tx in mempool {
+            total_weight = total_weight.saturating_add(tx.weight);
+            if total_weight > capacity {
+                return tx.fee_per_gram + 1;
+            }
+        }
+        0
+    }
+
+    /// A percentile of the fees per gram paid in recent blocks if those blocks were congested, otherwise 0
+    fn historical_fee_per_gram(&self, target_blocks: u64, max_block_weight: u64) -> u64 {
+        let full_weight = max_block_weight * FULL_BLOCK_WEIGHT_PERCENT / 100;
+        let num_full = self.blocks.iter().filter(|b| b.weight >= full_weight).count();
+        if self.blocks.is_empty() || num_full * 100 < self.blocks.len() * CONGESTED_BLOCKS_PERCENT {
+            return 0;
+        }
+
+        let mut fees_per_gram = self
+            .blocks
+            .iter()
+            .flat_map(|b| b.fees_per_gram.iter().copied())
+            .collect::<Vec<_>>();
+        if fees_per_gram.is_empty() {
+            return 0;
+        }
+        fees_per_gram.sort_unstable();
+        let percentile = match target_blocks {
+            1 => 50,
+            2..=3 => 25,
+            _ => 10,
+        };
+        fees_per_gram[(fees_per_gram.len() - 1) * percentile / 100]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAX_BLOCK_WEIGHT: u64 = 1000;
+
+    fn block(height: u64, weight: u64, fees_per_gram: Vec<u64>) -> BlockFeeStats {
+        BlockFeeStats {
+            height,
+            weight,
+            fees_per_gram,
+        }
+    }
+
+    fn tx(fee_per_gram: u64, weight: u64) -> MempoolTxFee {
+        MempoolTxFee { fee_per_gram, weight }
+    }
+
+    #[test]
+    fn it_returns_the_minimum_fee_when_there_is_no_demand() {
+        let mut estimator = FeeEstimator::new(10);
+        estimator.add_block(block(1, 100, vec![50]));
+        let estimate = estimator.estimate(1, &[tx(20, 100)], MAX_BLOCK_WEIGHT, MicroTari(1));
+        assert_eq!(estimate.fee_per_gram, MicroTari(1));
+        assert_eq!(estimate.mempool_weight, 100);
+        assert_eq!(estimate.num_blocks_analysed, 1);
+    }
+
+    #[test]
+    fn it_outbids_the_mempool_backlog() {
+        let estimator = FeeEstimator::new(10);
+        let mempool = (1..=30).map(|fee| tx(fee, 100)).collect::<Vec<_>>();
+        // The 10 highest paying transactions fill the next block
+        let estimate = estimator.estimate(1, &mempool, MAX_BLOCK_WEIGHT, MicroTari(1));
+        assert_eq!(estimate.fee_per_gram, MicroTari(21));
+        // ...and the 20 highest paying the next two
+        let estimate = estimator.estimate(2, &mempool, MAX_BLOCK_WEIGHT, MicroTari(1));
+        assert_eq!(estimate.fee_per_gram, MicroTari(11));
+        let estimate = estimator.estimate(3, &mempool, MAX_BLOCK_WEIGHT, MicroTari(1));
+        assert_eq!(estimate.fee_per_gram, MicroTari(1));
+    }
+
+    #[test]
+    fn it_uses_recent_fees_when_blocks_are_full() {
+        let mut estimator = FeeEstimator::new(10);
+        for height in 1..=4 {
+            estimator.add_block(block(height, MAX_BLOCK_WEIGHT, (1..=25).map(|f| f * 4).collect()));
+        }
+        assert_eq!(
+            estimator.estimate(1, &[], MAX_BLOCK_WEIGHT, MicroTari(1)).fee_per_gram,
+            MicroTari(52)
+        );
+        assert_eq!(
+            estimator.estimate(2, &[], MAX_BLOCK_WEIGHT, MicroTari(1)).fee_per_gram,
+            MicroTari(28)
+        );
+        assert_eq!(
+            estimator.estimate(10, &[], MAX_BLOCK_WEIGHT, MicroTari(1)).fee_per_gram,
+            MicroTari(12)
+        );
+    }
+
+    #[test]
+    fn it_replaces_reorged_blocks_and_limits_the_window() {
+        let mut estimator = FeeEstimator::new(3);
+        for height in 1..=5 {
+            estimator.add_block(block(height, MAX_BLOCK_WEIGHT, vec![100]));
+        }
+        assert_eq!(estimator.num_blocks(), 3);
+
+        // A reorg to a chain of empty blocks from height 4
+        estimator.add_block(block(4, 0, vec![]));
+        assert_eq!(estimator.num_blocks(), 2);
+        estimator.add_block(block(5, 0, vec![]));
+        assert_eq!(estimator.num_blocks(), 3);
+        assert_eq!(
+            estimator.estimate(1, &[], MAX_BLOCK_WEIGHT, MicroTari(1)).fee_per_gram,
+            MicroTari(1)
+        );
+    }
+}
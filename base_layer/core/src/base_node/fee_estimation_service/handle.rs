@@ -0,0 +1,56 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{error::FeeEstimationError, estimator::FeeEstimate};
+use tari_service_framework::{reply_channel::SenderService, Service};
+
+#[derive(Debug)]
+pub enum FeeEstimationRequest {
+    /// Estimate the fee per gram needed to be mined within the given number of blocks
+    EstimateFeePerGram(u64),
+}
+
+#[derive(Debug)]
+pub enum FeeEstimationResponse {
+    FeeEstimate(FeeEstimate),
+}
+
+#[derive(Clone)]
+pub struct FeeEstimationHandle {
+    handle: SenderService<FeeEstimationRequest, Result<FeeEstimationResponse, FeeEstimationError>>,
+}
+
+impl FeeEstimationHandle {
+    pub fn new(handle: SenderService<FeeEstimationRequest, Result<FeeEstimationResponse, FeeEstimationError>>) -> Self {
+        Self { handle }
+    }
+
+    pub async fn estimate_fee_per_gram(&mut self, target_blocks: u64) -> Result<FeeEstimate, FeeEstimationError> {
+        match self
+            .handle
+            .call(FeeEstimationRequest::EstimateFeePerGram(target_blocks))
+            .await??
+        {
+            FeeEstimationResponse::FeeEstimate(estimate) => Ok(estimate),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{handle::FeeEstimationHandle, service::FeeEstimationService, LOG_TARGET};
+use crate::{base_node::LocalNodeCommsInterface, consensus::ConsensusManager, mempool::service::MempoolHandle};
+use futures::{future, pin_mut};
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tari_common::configuration::seconds;
+use tari_service_framework::{
+    async_trait,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+    ServiceInitializerContext,
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FeeEstimationConfig {
+    /// The number of recent blocks whose fees are taken into account
+    pub num_blocks: usize,
+    /// How long a copy of the mempool's fees is used for estimates before it is fetched again. The copy is always
+    /// fetched again after a block is added.
+    #[serde(with = "seconds")]
+    pub mempool_refresh_interval: Duration,
+}
+
+impl Default for FeeEstimationConfig {
+    fn default() -> Self {
+        Self {
+            num_blocks: 100,
+            mempool_refresh_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+pub struct FeeEstimationServiceInitializer {
+    config: FeeEstimationConfig,
+    consensus_manager: ConsensusManager,
+}
+
+impl FeeEstimationServiceInitializer {
+    pub fn new(config: FeeEstimationConfig, consensus_manager: ConsensusManager) -> Self {
+        Self {
+            config,
+            consensus_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl ServiceInitializer for FeeEstimationServiceInitializer {
+    async fn initialize(&mut self, context: ServiceInitializerContext) -> Result<(), ServiceInitializationError> {
+        let (sender, receiver) = reply_channel::unbounded();
+        context.register_handle(FeeEstimationHandle::new(sender));
+
+        let config = self.config;
+        let consensus_manager = self.consensus_manager.clone();
+        context.spawn_when_ready(move |handles| async move {
+            let base_node = handles.expect_handle::<LocalNodeCommsInterface>();
+            let mempool = handles.expect_handle::<MempoolHandle>();
+
+            let service_run = FeeEstimationService::new(config, base_node, mempool, consensus_manager).run(receiver);
+            pin_mut!(service_run);
+            future::select(service_run, handles.get_shutdown_signal()).await;
+            info!(target: LOG_TARGET, "FeeEstimationService has shut down");
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{
+    error::FeeEstimationError,
+    estimator::{BlockFeeStats, FeeEstimator, MempoolTxFee},
+    handle::{FeeEstimationRequest, FeeEstimationResponse},
+    initializer::FeeEstimationConfig,
+    LOG_TARGET,
+};
+use crate::{
+    base_node::comms_interface::{BlockEvent, LocalNodeCommsInterface},
+    blocks::Block,
+    chain_storage::BlockAddResult,
+    consensus::ConsensusManager,
+    mempool::{service::MempoolHandle, RelayPolicyConfig},
+};
+use futures::stream::StreamExt;
+use log::*;
+use std::{cmp, time::Instant};
+use tari_common::log_if_error;
+use tari_service_framework::reply_channel::Receiver;
+
+pub(super) struct FeeEstimationService {
+    config: FeeEstimationConfig,
+    estimator: FeeEstimator,
+    base_node: LocalNodeCommsInterface,
+    mempool: MempoolHandle,
+    consensus_manager: ConsensusManager,
+    tip_height: u64,
+    mempool_backlog: Option<(Instant, Vec<MempoolTxFee>)>,
+}
+
+impl FeeEstimationService {
+    pub fn new(
+        config: FeeEstimationConfig,
+        base_node: LocalNodeCommsInterface,
+        mempool: MempoolHandle,
+        consensus_manager: ConsensusManager,
+    ) -> Self {
+        Self {
+            estimator: FeeEstimator::new(config.num_blocks),
+            config,
+            base_node,
+            mempool,
+            consensus_manager,
+            tip_height: 0,
+            mempool_backlog: None,
+        }
+    }
+
+    pub async fn run(
+        mut self,
+        request_stream: Receiver<FeeEstimationRequest, Result<FeeEstimationResponse, FeeEstimationError>>,
+    ) {
+        let mut block_event_stream = self.base_node.get_block_event_stream().fuse();
+        let mut request_stream = request_stream.fuse();
+
+        log_if_error!(
+            target: LOG_TARGET,
+            "Failed to load recent blocks because '{}'",
+            self.load_recent_blocks().await
+        );
+
+        loop {
+            futures::select! {
+                block_event = block_event_stream.select_next_some() => {
+                    if let Ok(block_event) = block_event {
+                        log_if_error!(
+                            level: debug,
+                            target: LOG_TARGET,
+                            "Failed to handle block event because '{}'",
+                            self.handle_block_event(&block_event).await
+                        );
+                    }
+                },
+
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(self.handle_request(request).await);
+                },
+
+                complete => {
+                    info!(target: LOG_TARGET, "FeeEstimationService is exiting because all tasks have completed");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn handle_request(
+        &mut self,
+        request: FeeEstimationRequest,
+    ) -> Result<FeeEstimationResponse, FeeEstimationError> {
+        match request {
+            FeeEstimationRequest::EstimateFeePerGram(target_blocks) => {
+                let mempool_backlog = self.mempool_backlog().await?;
+                let constants = self.consensus_manager.consensus_constants(self.tip_height + 1);
+                let estimate = self.estimator.estimate(
+                    target_blocks,
+                    &mempool_backlog,
+                    constants.get_max_block_weight_excluding_coinbase(),
+                    RelayPolicyConfig::default().min_fee_per_gram,
+                );
+                debug!(
+                    target: LOG_TARGET,
+                    "Estimated {} per gram to be mined within {} block(s)",
+                    estimate.fee_per_gram,
+                    estimate.target_blocks
+                );
+                Ok(FeeEstimationResponse::FeeEstimate(estimate))
+            },
+        }
+    }
+
+    async fn handle_block_event(&mut self, event: &BlockEvent) -> Result<(), FeeEstimationError> {
+        match event {
+            BlockEvent::ValidBlockAdded(block, BlockAddResult::Ok(_), _) => {
+                self.add_block(block);
+            },
+            BlockEvent::ValidBlockAdded(_, BlockAddResult::ChainReorg { added, .. }, _) => {
+                let mut added = added.iter().map(|b| b.block()).collect::<Vec<_>>();
+                added.sort_by_key(|b| b.header.height);
+                for block in added {
+                    self.add_block(block);
+                }
+            },
+            BlockEvent::BlockSyncComplete(_) | BlockEvent::BlockSyncRewind(_) => {
+                self.load_recent_blocks().await?;
+            },
+            _ => {},
+        }
+        // The mempool changes with every block
+        self.mempool_backlog = None;
+        Ok(())
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        let height = block.header.height;
+        let weighting = self.consensus_manager.consensus_constants(height).transaction_weight();
+        self.estimator.add_block(BlockFeeStats::from_block(block, weighting));
+        self.tip_height = height;
+    }
+
+    /// Replaces the block stats with the stats of the most recent blocks in the database
+    async fn load_recent_blocks(&mut self) -> Result<(), FeeEstimationError> {
+        let tip_height = self.base_node.get_metadata().await?.height_of_longest_chain();
+        let num_blocks = cmp::max(self.config.num_blocks as u64, 1);
+        let heights = (tip_height.saturating_sub(num_blocks - 1)..=tip_height).collect::<Vec<_>>();
+        let blocks = self.base_node.get_blocks(heights).await?;
+
+        self.estimator.clear();
+        self.tip_height = tip_height;
+        for block in &blocks {
+            self.add_block(block.block());
+        }
+        debug!(
+            target: LOG_TARGET,
+            "Loaded the fee stats of {} block(s) up to height {}",
+            self.estimator.num_blocks(),
+            tip_height
+        );
+        Ok(())
+    }
+
+    /// The fees of the transactions in the mempool. Fetching them copies the mempool, so they are only refreshed when
+    /// a block is added or the refresh interval has elapsed.
+    async fn mempool_backlog(&mut self) -> Result<Vec<MempoolTxFee>, FeeEstimationError> {
+        if let Some((fetched_at, backlog)) = &self.mempool_backlog {
+            if fetched_at.elapsed() < self.config.mempool_refresh_interval {
+                return Ok(backlog.clone());
+            }
+        }
+
+        let weighting = *self
+            .consensus_manager
+            .consensus_constants(self.tip_height + 1)
+            .transaction_weight();
+        let backlog = self
+            .mempool
+            .get_state()
+            .await?
+            .unconfirmed_pool
+            .iter()
+            .map(|tx| MempoolTxFee::from_transaction(tx, &weighting))
+            .collect::<Vec<_>>();
+        self.mempool_backlog = Some((Instant::now(), backlog.clone()));
+        Ok(backlog)
+    }
+}
@@ -0,0 +1,392 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A bounded, staged pipeline for processing blocks and transactions received from peers.
+//!
+//! Inbound messages pass through the following stages:
+//! 1. _Decode_: messages are decoded as they are read from the comms subscription. Messages that fail to decode never
+//!    enter the pipeline.
+//! 2. _Preliminary checks_: cheap checks that discard messages that do not need full validation, e.g. the node is
+//!    still syncing or the item has already been seen.
+//! 3. _Full validation and storage_: the expensive stage. Blocks and transactions are validated as they are written
+//!    to the blockchain database and mempool respectively, so validation and storage share a stage.
+//!
+//! Each stage is fed by a bounded queue and runs a limited number of concurrent tasks. A stage that is at its
+//! concurrency limit stops reading from its queue, which pushes back on the previous stage, so messages are only ever
+//! dropped on entry to the pipeline. A message is dropped on entry if the pipeline is full or if its source peer
+//! already has `max_in_flight_per_peer` messages in the pipeline. Peers that keep sending while over their quota are
+//! reported for penalization.
+
+use futures::{channel::mpsc, Future, SinkExt, StreamExt};
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tari_common::configuration::seconds;
+use tari_comms::{bounded_executor::BoundedExecutor, peer_manager::NodeId};
+use tokio::task;
+
+const LOG_TARGET: &str = "c::bn::inbound_pipeline";
+
+/// Configuration for an [InboundPipeline](self::InboundPipeline).
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct InboundPipelineConfig {
+    /// The number of messages that may wait for preliminary checks. Messages received while this queue is full are
+    /// dropped.
+    pub queue_size: usize,
+    /// The maximum number of preliminary checks that run concurrently
+    pub max_concurrent_preliminary_checks: usize,
+    /// The number of messages that have passed preliminary checks and are waiting for full validation
+    pub validation_queue_size: usize,
+    /// The maximum number of messages that are fully validated and stored concurrently
+    pub max_concurrent_validations: usize,
+    /// The maximum number of messages from a single peer that may be queued or processed at once
+    pub max_in_flight_per_peer: usize,
+    /// A peer is penalized once this many of its messages have been dropped for exceeding `max_in_flight_per_peer`.
+    /// If None, peers are never penalized.
+    pub penalize_after_dropped: Option<usize>,
+    /// The length of the ban given to a penalized peer
+    #[serde(with = "seconds")]
+    pub penalty_ban_duration: Duration,
+}
+
+impl Default for InboundPipelineConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 1000,
+            max_concurrent_preliminary_checks: 16,
+            validation_queue_size: 200,
+            max_concurrent_validations: 8,
+            max_in_flight_per_peer: 100,
+            penalize_after_dropped: Some(200),
+            penalty_ban_duration: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+/// The result of submitting a message to an [InboundPipeline](self::InboundPipeline).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitOutcome {
+    /// The message was queued for processing
+    Accepted,
+    /// The message was dropped because the pipeline is full
+    DroppedQueueFull,
+    /// The message was dropped because the source peer has too many messages in the pipeline. If `penalize` is true
+    /// the peer has exceeded its quota often enough to be penalized.
+    DroppedPeerQuota { penalize: bool },
+    /// The message was dropped because the pipeline has shut down
+    Closed,
+}
+
+/// The bounded processing pipeline for a single type of inbound message. The pipeline's tasks shut down once the
+/// pipeline is dropped and all queued messages have been processed.
+pub struct InboundPipeline<T> {
+    name: &'static str,
+    sender: mpsc::Sender<Tracked<T>>,
+    peers: Arc<Mutex<HashMap<NodeId, PeerQuota>>>,
+    config: InboundPipelineConfig,
+}
+
+impl<T> InboundPipeline<T>
+where T: Send + 'static
+{
+    /// Spawn the pipeline's stage tasks. `preliminary_check` returns the message if it should go on to
+    /// `validate_and_store`, or None if it should be discarded. This must be called from within a tokio runtime.
+    pub fn spawn<P, PFut, V, VFut>(
+        name: &'static str,
+        config: InboundPipelineConfig,
+        preliminary_check: P,
+        validate_and_store: V,
+    ) -> Self
+    where
+        P: Fn(T) -> PFut + Clone + Send + 'static,
+        PFut: Future<Output = Option<T>> + Send + 'static,
+        V: Fn(T) -> VFut + Clone + Send + 'static,
+        VFut: Future<Output = ()> + Send + 'static,
+    {
+        let (sender, mut receiver) = mpsc::channel::<Tracked<T>>(config.queue_size);
+        let (validation_sender, mut validation_receiver) = mpsc::channel::<Tracked<T>>(config.validation_queue_size);
+
+        let executor = BoundedExecutor::from_current(config.max_concurrent_preliminary_checks);
+        task::spawn(async move {
+            while let Some(Tracked { item, in_flight }) = receiver.next().await {
+                let preliminary_check = preliminary_check.clone();
+                let mut validation_sender = validation_sender.clone();
+                executor
+                    .spawn(async move {
+                        let check = preliminary_check(item);
+                        if let Some(item) = check.await {
+                            // Waiting for space in the validation queue holds this task's permit, which applies back
+                            // pressure to the preliminary check stage
+                            let _ = validation_sender.send(Tracked { item, in_flight }).await;
+                        }
+                    })
+                    .await;
+            }
+            debug!(
+                target: LOG_TARGET,
+                "{} pipeline preliminary check stage shut down", name
+            );
+        });
+
+        let executor = BoundedExecutor::from_current(config.max_concurrent_validations);
+        task::spawn(async move {
+            while let Some(Tracked { item, in_flight }) = validation_receiver.next().await {
+                let validate_and_store = validate_and_store.clone();
+                executor
+                    .spawn(async move {
+                        let validation = validate_and_store(item);
+                        validation.await;
+                        drop(in_flight);
+                    })
+                    .await;
+            }
+            debug!(target: LOG_TARGET, "{} pipeline validation stage shut down", name);
+        });
+
+        Self {
+            name,
+            sender,
+            peers: Default::default(),
+            config,
+        }
+    }
+
+    /// Submit a message from `source_peer` to the pipeline. This never waits: if the message cannot be queued
+    /// immediately it is dropped and the reason is returned.
+    pub fn submit(&mut self, source_peer: NodeId, item: T) -> SubmitOutcome {
+        let in_flight = match self.reserve(source_peer) {
+            Ok(in_flight) => in_flight,
+            Err(outcome) => return outcome,
+        };
+        match self.sender.try_send(Tracked { item, in_flight }) {
+            Ok(_) => SubmitOutcome::Accepted,
+            Err(err) if err.is_full() => {
+                debug!(target: LOG_TARGET, "{} pipeline is full, dropping message", self.name);
+                SubmitOutcome::DroppedQueueFull
+            },
+            Err(_) => SubmitOutcome::Closed,
+        }
+    }
+
+    fn reserve(&self, source_peer: NodeId) -> Result<InFlight, SubmitOutcome> {
+        let mut peers = self.peers.lock().unwrap();
+        let quota = peers.entry(source_peer.clone()).or_default();
+        if quota.in_flight >= self.config.max_in_flight_per_peer {
+            quota.dropped += 1;
+            let penalize = self
+                .config
+                .penalize_after_dropped
+                .map_or(false, |max_dropped| quota.dropped >= max_dropped);
+            if penalize {
+                quota.dropped = 0;
+            }
+            debug!(
+                target: LOG_TARGET,
+                "Peer `{}` has too many messages in the {} pipeline, dropping message",
+                source_peer.short_str(),
+                self.name
+            );
+            return Err(SubmitOutcome::DroppedPeerQuota { penalize });
+        }
+        quota.in_flight += 1;
+        Ok(InFlight {
+            node_id: source_peer,
+            peers: self.peers.clone(),
+        })
+    }
+}
+
+struct Tracked<T> {
+    item: T,
+    in_flight: InFlight,
+}
+
+#[derive(Default)]
+struct PeerQuota {
+    in_flight: usize,
+    dropped: usize,
+}
+
+/// Releases a peer's in-flight slot when the message leaves the pipeline, whichever stage that happens in.
+struct InFlight {
+    node_id: NodeId,
+    peers: Arc<Mutex<HashMap<NodeId, PeerQuota>>>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut peers = self.peers.lock().unwrap();
+        if let Some(quota) = peers.get_mut(&self.node_id) {
+            quota.in_flight = quota.in_flight.saturating_sub(1);
+            // Once a peer has nothing in flight it is no longer flooding the pipeline, so its drop count is forgotten
+            if quota.in_flight == 0 {
+                peers.remove(&self.node_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::time::delay_for;
+
+    fn config() -> InboundPipelineConfig {
+        InboundPipelineConfig {
+            queue_size: 10,
+            max_concurrent_preliminary_checks: 2,
+            validation_queue_size: 10,
+            max_concurrent_validations: 2,
+            max_in_flight_per_peer: 10,
+            penalize_after_dropped: None,
+            penalty_ban_duration: Duration::from_secs(1),
+        }
+    }
+
+    async fn wait_for(counter: &AtomicUsize, n: usize) {
+        for _ in 0..100 {
+            if counter.load(Ordering::SeqCst) >= n {
+                return;
+            }
+            delay_for(Duration::from_millis(10)).await;
+        }
+        panic!("Timed out waiting for {} messages to be processed", n);
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_drops_messages_when_the_queue_is_full() {
+        let mut pipeline = InboundPipeline::spawn(
+            "test",
+            InboundPipelineConfig {
+                queue_size: 2,
+                ..config()
+            },
+            |n: usize| async move { Some(n) },
+            |_| async {},
+        );
+        // The stage tasks do not run until this task yields, so only the queue (plus the sender's slot) is available
+        let outcomes = (0..4).map(|n| pipeline.submit(NodeId::new(), n)).collect::<Vec<_>>();
+        assert_eq!(outcomes, vec![
+            SubmitOutcome::Accepted,
+            SubmitOutcome::Accepted,
+            SubmitOutcome::Accepted,
+            SubmitOutcome::DroppedQueueFull
+        ]);
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_limits_messages_per_peer() {
+        let mut pipeline = InboundPipeline::spawn(
+            "test",
+            InboundPipelineConfig {
+                max_in_flight_per_peer: 2,
+                penalize_after_dropped: Some(2),
+                ..config()
+            },
+            |n: usize| async move { Some(n) },
+            |_| async {},
+        );
+        let flooder = NodeId::new();
+        assert_eq!(pipeline.submit(flooder.clone(), 0), SubmitOutcome::Accepted);
+        assert_eq!(pipeline.submit(flooder.clone(), 1), SubmitOutcome::Accepted);
+        assert_eq!(pipeline.submit(flooder.clone(), 2), SubmitOutcome::DroppedPeerQuota {
+            penalize: false
+        });
+        assert_eq!(pipeline.submit(flooder, 3), SubmitOutcome::DroppedPeerQuota {
+            penalize: true
+        });
+        assert_eq!(pipeline.submit(NodeId::new(), 4), SubmitOutcome::Accepted);
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_processes_messages_through_each_stage() {
+        let validated = Arc::new(Mutex::new(Vec::new()));
+        let num_validating = Arc::new(AtomicUsize::new(0));
+        let max_validating = Arc::new(AtomicUsize::new(0));
+        let num_done = Arc::new(AtomicUsize::new(0));
+        let num_checked = Arc::new(AtomicUsize::new(0));
+
+        let mut pipeline = InboundPipeline::spawn(
+            "test",
+            InboundPipelineConfig {
+                max_in_flight_per_peer: 1,
+                ..config()
+            },
+            {
+                let num_checked = num_checked.clone();
+                move |n: usize| {
+                    num_checked.fetch_add(1, Ordering::SeqCst);
+                    // Odd messages fail the preliminary check
+                    async move {
+                        if n % 2 == 0 {
+                            Some(n)
+                        } else {
+                            None
+                        }
+                    }
+                }
+            },
+            {
+                let validated = validated.clone();
+                let num_validating = num_validating.clone();
+                let max_validating = max_validating.clone();
+                let num_done = num_done.clone();
+                move |n| {
+                    let validated = validated.clone();
+                    let num_validating = num_validating.clone();
+                    let max_validating = max_validating.clone();
+                    let num_done = num_done.clone();
+                    async move {
+                        let current = num_validating.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_validating.fetch_max(current, Ordering::SeqCst);
+                        delay_for(Duration::from_millis(10)).await;
+                        validated.lock().unwrap().push(n);
+                        num_validating.fetch_sub(1, Ordering::SeqCst);
+                        num_done.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            },
+        );
+
+        let peer = NodeId::new();
+        for n in 0..8 {
+            assert_eq!(pipeline.submit(NodeId::new(), n), SubmitOutcome::Accepted);
+        }
+        wait_for(&num_done, 4).await;
+        wait_for(&num_checked, 8).await;
+        let mut validated_msgs = validated.lock().unwrap().clone();
+        validated_msgs.sort_unstable();
+        assert_eq!(validated_msgs, vec![0, 2, 4, 6]);
+        assert!(max_validating.load(Ordering::SeqCst) <= 2);
+
+        // A peer's in-flight slot is released once its message has been processed
+        assert_eq!(pipeline.submit(peer.clone(), 8), SubmitOutcome::Accepted);
+        wait_for(&num_done, 5).await;
+        assert_eq!(pipeline.submit(peer, 10), SubmitOutcome::Accepted);
+        wait_for(&num_done, 6).await;
+    }
+}
@@ -40,6 +40,12 @@ pub mod comms_interface;
 #[cfg(feature = "base_node")]
 pub use comms_interface::{LocalNodeCommsInterface, OutboundNodeCommsInterface};
 
+#[cfg(feature = "base_node")]
+pub mod fee_estimation_service;
+
+#[cfg(feature = "base_node")]
+pub mod inbound_pipeline;
+
 #[cfg(feature = "base_node")]
 pub mod service;
 
@@ -55,6 +55,10 @@ impl TryFrom<proto::ChainMetadata> for ChainMetadata {
             pruning_horizon,
             metadata.pruned_height,
             accumulated_difficulty,
+        )
+        .with_pow_accumulated_difficulty(
+            metadata.accumulated_monero_difficulty,
+            metadata.accumulated_sha_difficulty,
         ))
     }
 }
@@ -67,6 +71,8 @@ impl From<ChainMetadata> for proto::ChainMetadata {
             best_block: Some(metadata.best_block().clone()),
             pruned_height: metadata.pruned_height(),
             accumulated_difficulty,
+            accumulated_monero_difficulty: metadata.accumulated_monero_difficulty(),
+            accumulated_sha_difficulty: metadata.accumulated_sha_difficulty(),
         }
     }
 }
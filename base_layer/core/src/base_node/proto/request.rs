@@ -82,6 +82,9 @@ impl TryInto<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             FetchKernelByExcessSig(sig) => ci::NodeCommsRequest::FetchKernelByExcessSig(
                 Signature::try_from(sig).map_err(|err: ByteArrayError| err.to_string())?,
             ),
+            FetchMempoolTransactionsByKernelShortIds(short_ids) => {
+                ci::NodeCommsRequest::FetchMempoolTransactionsByKernelShortIds(short_ids.short_ids)
+            },
         };
         Ok(request)
     }
@@ -119,6 +122,9 @@ impl From<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             },
             GetNewBlock(block_template) => ProtoNodeCommsRequest::GetNewBlock(block_template.into()),
             FetchKernelByExcessSig(signature) => ProtoNodeCommsRequest::FetchKernelByExcessSig(signature.into()),
+            FetchMempoolTransactionsByKernelShortIds(short_ids) => {
+                ProtoNodeCommsRequest::FetchMempoolTransactionsByKernelShortIds(proto::KernelShortIds { short_ids })
+            },
         }
     }
 }
@@ -86,6 +86,10 @@ impl TryInto<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
             },
             TargetDifficulty(difficulty) => ci::NodeCommsResponse::TargetDifficulty(Difficulty::from(difficulty)),
             MmrNodes(response) => ci::NodeCommsResponse::MmrNodes(response.added, response.deleted),
+            Transactions(transactions) => {
+                let transactions = try_convert_all(transactions.transactions)?;
+                ci::NodeCommsResponse::Transactions(transactions)
+            },
         };
 
         Ok(response)
@@ -127,6 +131,10 @@ impl From<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
             }),
             TargetDifficulty(difficulty) => ProtoNodeCommsResponse::TargetDifficulty(difficulty.as_u64()),
             MmrNodes(added, deleted) => ProtoNodeCommsResponse::MmrNodes(ProtoMmrNodes { added, deleted }),
+            Transactions(transactions) => {
+                let transactions = transactions.into_iter().map(Into::into).collect();
+                ProtoNodeCommsResponse::Transactions(base_node_proto::Transactions { transactions })
+            },
         }
     }
 }
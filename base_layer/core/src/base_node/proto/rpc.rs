@@ -21,6 +21,8 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{blocks::Block, chain_storage::PrunedOutput, proto::base_node as proto, tari_utilities::Hashable};
+use proto::block_body_part::Part;
+use std::cmp;
 
 impl From<Block> for proto::BlockBodyResponse {
     fn from(block: Block) -> Self {
@@ -31,6 +33,40 @@ impl From<Block> for proto::BlockBodyResponse {
     }
 }
 
+impl proto::BlockBodyPart {
+    /// Splits a block body into its hash followed by its kernels, outputs and inputs, with at most `max_items` of each
+    /// in a part
+    pub fn split(block: proto::BlockBodyResponse, max_items: usize) -> Vec<Self> {
+        let body = block.body.unwrap_or_default();
+        let kernels = into_chunks(body.kernels, max_items)
+            .into_iter()
+            .map(|kernels| Part::Kernels(proto::BlockKernels { kernels }));
+        let outputs = into_chunks(body.outputs, max_items)
+            .into_iter()
+            .map(|outputs| Part::Outputs(proto::BlockOutputs { outputs }));
+        let inputs = into_chunks(body.inputs, max_items)
+            .into_iter()
+            .map(|inputs| Part::Inputs(proto::BlockInputs { inputs }));
+        Some(Part::Hash(block.hash))
+            .into_iter()
+            .chain(kernels)
+            .chain(outputs)
+            .chain(inputs)
+            .map(|part| Self { part: Some(part) })
+            .collect()
+    }
+}
+
+fn into_chunks<T>(items: Vec<T>, size: usize) -> Vec<Vec<T>> {
+    let size = cmp::max(size, 1);
+    let mut items = items.into_iter().peekable();
+    let mut chunks = Vec::new();
+    while items.peek().is_some() {
+        chunks.push(items.by_ref().take(size).collect());
+    }
+    chunks
+}
+
 impl From<PrunedOutput> for proto::SyncUtxo {
     fn from(output: PrunedOutput) -> Self {
         match output {
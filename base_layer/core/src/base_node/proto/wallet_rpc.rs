@@ -48,6 +48,13 @@ pub enum TxSubmissionRejectionReason {
     Orphan,
     TimeLocked,
     ValidationFailed,
+    ScriptTooLarge,
+    FeePerGramTooLow,
+    TooManyOutputs,
+    DustOutput,
+    Expired,
+    TooManyUnconfirmedForUniqueId,
+    SenderWeightLimitExceeded,
 }
 
 impl Display for TxSubmissionRejectionReason {
@@ -58,6 +65,13 @@ impl Display for TxSubmissionRejectionReason {
             TxSubmissionRejectionReason::Orphan => "Orphan",
             TxSubmissionRejectionReason::TimeLocked => "Time Locked",
             TxSubmissionRejectionReason::ValidationFailed => "Validation Failed",
+            TxSubmissionRejectionReason::ScriptTooLarge => "Output Script Too Large",
+            TxSubmissionRejectionReason::FeePerGramTooLow => "Fee Per Gram Too Low",
+            TxSubmissionRejectionReason::TooManyOutputs => "Too Many Outputs",
+            TxSubmissionRejectionReason::DustOutput => "Dust Output",
+            TxSubmissionRejectionReason::Expired => "Expired",
+            TxSubmissionRejectionReason::TooManyUnconfirmedForUniqueId => "Too Many Unconfirmed For Unique Id",
+            TxSubmissionRejectionReason::SenderWeightLimitExceeded => "Sender Weight Limit Exceeded",
             TxSubmissionRejectionReason::None => "None",
         };
         fmt.write_str(&response)
@@ -76,6 +90,13 @@ impl TryFrom<proto::TxSubmissionRejectionReason> for TxSubmissionRejectionReason
             Orphan => TxSubmissionRejectionReason::Orphan,
             TimeLocked => TxSubmissionRejectionReason::TimeLocked,
             ValidationFailed => TxSubmissionRejectionReason::ValidationFailed,
+            ScriptTooLarge => TxSubmissionRejectionReason::ScriptTooLarge,
+            FeePerGramTooLow => TxSubmissionRejectionReason::FeePerGramTooLow,
+            TooManyOutputs => TxSubmissionRejectionReason::TooManyOutputs,
+            DustOutput => TxSubmissionRejectionReason::DustOutput,
+            Expired => TxSubmissionRejectionReason::Expired,
+            TooManyUnconfirmedForUniqueId => TxSubmissionRejectionReason::TooManyUnconfirmedForUniqueId,
+            SenderWeightLimitExceeded => TxSubmissionRejectionReason::SenderWeightLimitExceeded,
         })
     }
 }
@@ -90,6 +111,13 @@ impl From<TxSubmissionRejectionReason> for proto::TxSubmissionRejectionReason {
             Orphan => proto::TxSubmissionRejectionReason::Orphan,
             TimeLocked => proto::TxSubmissionRejectionReason::TimeLocked,
             ValidationFailed => proto::TxSubmissionRejectionReason::ValidationFailed,
+            ScriptTooLarge => proto::TxSubmissionRejectionReason::ScriptTooLarge,
+            FeePerGramTooLow => proto::TxSubmissionRejectionReason::FeePerGramTooLow,
+            TooManyOutputs => proto::TxSubmissionRejectionReason::TooManyOutputs,
+            DustOutput => proto::TxSubmissionRejectionReason::DustOutput,
+            Expired => proto::TxSubmissionRejectionReason::Expired,
+            TooManyUnconfirmedForUniqueId => proto::TxSubmissionRejectionReason::TooManyUnconfirmedForUniqueId,
+            SenderWeightLimitExceeded => proto::TxSubmissionRejectionReason::SenderWeightLimitExceeded,
         }
     }
 }
@@ -126,6 +154,7 @@ pub struct TxQueryResponse {
     pub confirmations: u64,
     pub is_synced: bool,
     pub height_of_longest_chain: u64,
+    pub rejection_reason: TxSubmissionRejectionReason,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -134,6 +163,15 @@ pub struct TxQueryBatchResponse {
     pub location: TxLocation,
     pub block_hash: Option<BlockHash>,
     pub confirmations: u64,
+    pub block_height: u64,
+    pub rejection_reason: TxSubmissionRejectionReason,
+}
+
+fn rejection_reason_from_i32(value: i32) -> Result<TxSubmissionRejectionReason, String> {
+    TxSubmissionRejectionReason::try_from(
+        proto::TxSubmissionRejectionReason::from_i32(value)
+            .ok_or_else(|| "Invalid or unrecognised `TxSubmissionRejectionReason` enum".to_string())?,
+    )
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -192,6 +230,7 @@ impl TryFrom<proto::TxQueryResponse> for TxQueryResponse {
             confirmations: proto_response.confirmations,
             is_synced: proto_response.is_synced,
             height_of_longest_chain: proto_response.height_of_longest_chain,
+            rejection_reason: rejection_reason_from_i32(proto_response.rejection_reason)?,
         })
     }
 }
@@ -204,6 +243,7 @@ impl From<TxQueryResponse> for proto::TxQueryResponse {
             confirmations: response.confirmations,
             is_synced: response.is_synced,
             height_of_longest_chain: response.height_of_longest_chain,
+            rejection_reason: proto::TxSubmissionRejectionReason::from(response.rejection_reason) as i32,
         }
     }
 }
@@ -225,6 +265,8 @@ impl TryFrom<proto::TxQueryBatchResponse> for TxQueryBatchResponse {
             )?,
             block_hash: proto_response.block_hash,
             confirmations: proto_response.confirmations,
+            block_height: proto_response.block_height,
+            rejection_reason: rejection_reason_from_i32(proto_response.rejection_reason)?,
         })
     }
 }
@@ -26,8 +26,16 @@ mod service;
 use crate::base_node::StateMachineHandle;
 use crate::proto::{
     base_node::{
+        FeePerGramEstimate,
         FetchMatchingUtxos,
+        FetchMmrProof,
+        FetchMmrProofResponse,
         FetchUtxosResponse,
+        QueryOutputMaturity,
+        QueryOutputMaturityResponse,
+        QueryUtxosByFeatures,
+        QueryUtxosByScriptHash,
+        QueryUtxosResponse,
         Signatures,
         TipInfoResponse,
         TxQueryBatchResponses,
@@ -38,6 +46,7 @@ use crate::proto::{
 };
 #[cfg(feature = "base_node")]
 use crate::{
+    base_node::fee_estimation_service::FeeEstimationHandle,
     chain_storage::{async_db::AsyncBlockchainDb, BlockchainBackend},
     mempool::service::MempoolHandle,
 };
@@ -72,6 +81,41 @@ pub trait BaseNodeWalletService: Send + Sync + 'static {
 
     #[rpc(method = 5)]
     async fn get_tip_info(&self, request: Request<()>) -> Result<Response<TipInfoResponse>, RpcStatus>;
+
+    /// Returns a page of unspent outputs with the given script hash, ordered by output MMR position
+    #[rpc(method = 6)]
+    async fn query_utxos_by_script_hash(
+        &self,
+        request: Request<QueryUtxosByScriptHash>,
+    ) -> Result<Response<QueryUtxosResponse>, RpcStatus>;
+
+    /// Returns a page of unspent outputs with the given output feature flags, ordered by output MMR position
+    #[rpc(method = 7)]
+    async fn query_utxos_by_features(
+        &self,
+        request: Request<QueryUtxosByFeatures>,
+    ) -> Result<Response<QueryUtxosResponse>, RpcStatus>;
+
+    /// Returns MMR inclusion proofs for those of the given outputs that are unspent, made against the output MMR of
+    /// the tip header. This allows a client to check its outputs against a header rather than trusting the node.
+    #[rpc(method = 8)]
+    async fn fetch_mmr_proof(
+        &self,
+        request: Request<FetchMmrProof>,
+    ) -> Result<Response<FetchMmrProofResponse>, RpcStatus>;
+
+    /// Returns the maturity height and spendability of each of the given outputs, and the tip height they were
+    /// determined at, so that a wallet can tell which of its outputs are time-locked without tracking the chain height
+    #[rpc(method = 9)]
+    async fn query_output_maturity(
+        &self,
+        request: Request<QueryOutputMaturity>,
+    ) -> Result<Response<QueryOutputMaturityResponse>, RpcStatus>;
+
+    /// Returns the fee per gram that a transaction is expected to need to be mined within the requested number of
+    /// blocks
+    #[rpc(method = 10)]
+    async fn estimate_fee_per_gram(&self, request: Request<u64>) -> Result<Response<FeePerGramEstimate>, RpcStatus>;
 }
 
 #[cfg(feature = "base_node")]
@@ -79,6 +123,12 @@ pub fn create_base_node_wallet_rpc_service<B: BlockchainBackend + 'static>(
     db: AsyncBlockchainDb<B>,
     mempool: MempoolHandle,
     state_machine: StateMachineHandle,
+    fee_estimation: FeeEstimationHandle,
 ) -> BaseNodeWalletRpcServer<BaseNodeWalletRpcService<B>> {
-    BaseNodeWalletRpcServer::new(BaseNodeWalletRpcService::new(db, mempool, state_machine))
+    BaseNodeWalletRpcServer::new(BaseNodeWalletRpcService::new(
+        db,
+        mempool,
+        state_machine,
+        fee_estimation,
+    ))
 }
@@ -21,13 +21,28 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    base_node::{rpc::BaseNodeWalletService, state_machine_service::states::StateInfo, StateMachineHandle},
+    base_node::{
+        fee_estimation_service::{FeeEstimationHandle, MAX_TARGET_BLOCKS},
+        rpc::BaseNodeWalletService,
+        state_machine_service::states::StateInfo,
+        StateMachineHandle,
+    },
     chain_storage::{async_db::AsyncBlockchainDb, BlockchainBackend},
-    mempool::{service::MempoolHandle, TxStorageResponse},
+    mempool::{service::MempoolHandle, RelayPolicyViolation, TxStorageResponse},
     proto::{
         base_node::{
+            FeePerGramEstimate,
             FetchMatchingUtxos,
+            FetchMmrProof,
+            FetchMmrProofResponse,
             FetchUtxosResponse,
+            IndexedUtxo,
+            OutputMaturity,
+            QueryOutputMaturity,
+            QueryOutputMaturityResponse,
+            QueryUtxosByFeatures,
+            QueryUtxosByScriptHash,
+            QueryUtxosResponse,
             Signatures as SignaturesProto,
             TipInfoResponse,
             TxLocation,
@@ -36,28 +51,43 @@ use crate::{
             TxQueryResponse,
             TxSubmissionRejectionReason,
             TxSubmissionResponse,
+            UtxoMmrProof as UtxoMmrProofProto,
         },
         types::{Signature as SignatureProto, Transaction as TransactionProto},
     },
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{
+        transaction::{OutputFlags, Transaction, TransactionOutput},
+        types::{Commitment, Signature},
+    },
 };
-use std::convert::TryFrom;
+use std::{cmp, convert::TryFrom};
+use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::protocol::rpc::{Request, Response, RpcStatus};
+use tari_crypto::tari_utilities::ByteArray;
 
 const LOG_TARGET: &str = "c::base_node::rpc";
+/// The maximum number of outputs returned by a single UTXO query
+const MAX_UTXO_QUERY_PAGE_SIZE: usize = 1000;
 
 pub struct BaseNodeWalletRpcService<B> {
     db: AsyncBlockchainDb<B>,
     mempool: MempoolHandle,
     state_machine: StateMachineHandle,
+    fee_estimation: FeeEstimationHandle,
 }
 
 impl<B: BlockchainBackend + 'static> BaseNodeWalletRpcService<B> {
-    pub fn new(db: AsyncBlockchainDb<B>, mempool: MempoolHandle, state_machine: StateMachineHandle) -> Self {
+    pub fn new(
+        db: AsyncBlockchainDb<B>,
+        mempool: MempoolHandle,
+        state_machine: StateMachineHandle,
+        fee_estimation: FeeEstimationHandle,
+    ) -> Self {
         Self {
             db,
             mempool,
             state_machine,
+            fee_estimation,
         }
     }
 
@@ -76,12 +106,17 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletRpcService<B> {
         self.state_machine.clone()
     }
 
-    async fn fetch_kernel(&self, signature: Signature) -> Result<TxQueryResponse, RpcStatus> {
+    #[inline]
+    pub fn fee_estimation(&self) -> FeeEstimationHandle {
+        self.fee_estimation.clone()
+    }
+
+    async fn fetch_kernel(
+        &self,
+        signature: Signature,
+        chain_metadata: &ChainMetadata,
+    ) -> Result<TxQueryResponse, RpcStatus> {
         let db = self.db();
-        let chain_metadata = db
-            .get_chain_metadata()
-            .await
-            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
         match db
             .fetch_kernel_by_excess_sig(signature.clone())
             .await
@@ -103,6 +138,7 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletRpcService<B> {
                             confirmations,
                             is_synced: true,
                             height_of_longest_chain: chain_metadata.height_of_longest_chain(),
+                            rejection_reason: TxSubmissionRejectionReason::None.into(),
                         };
                         return Ok(response);
                     },
@@ -123,17 +159,23 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletRpcService<B> {
                 confirmations: 0,
                 is_synced: true,
                 height_of_longest_chain: chain_metadata.height_of_longest_chain(),
+                rejection_reason: TxSubmissionRejectionReason::None.into(),
             },
-            TxStorageResponse::ReorgPool |
-            TxStorageResponse::NotStoredOrphan |
-            TxStorageResponse::NotStoredTimeLocked |
-            TxStorageResponse::NotStoredAlreadySpent |
-            TxStorageResponse::NotStored => TxQueryResponse {
-                location: TxLocation::NotStored as i32,
-                block_hash: None,
-                confirmations: 0,
-                is_synced: true,
-                height_of_longest_chain: chain_metadata.height_of_longest_chain(),
+            state => {
+                // Let the wallet know if the transaction was removed from the mempool rather than never seen
+                let rejection_reason = match state {
+                    TxStorageResponse::NotStoredExpired => TxSubmissionRejectionReason::Expired,
+                    TxStorageResponse::NotStoredConflict => TxSubmissionRejectionReason::DoubleSpend,
+                    _ => TxSubmissionRejectionReason::None,
+                };
+                TxQueryResponse {
+                    location: TxLocation::NotStored as i32,
+                    block_hash: None,
+                    confirmations: 0,
+                    is_synced: true,
+                    height_of_longest_chain: chain_metadata.height_of_longest_chain(),
+                    rejection_reason: rejection_reason.into(),
+                }
             },
         };
         Ok(mempool_response)
@@ -181,11 +223,39 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletService for BaseNodeWalletRpc
                 is_synced,
             },
 
+            TxStorageResponse::NotStoredPolicyViolation(violation) => TxSubmissionResponse {
+                accepted: false,
+                rejection_reason: match violation {
+                    RelayPolicyViolation::ScriptTooLarge => TxSubmissionRejectionReason::ScriptTooLarge,
+                    RelayPolicyViolation::FeePerGramTooLow => TxSubmissionRejectionReason::FeePerGramTooLow,
+                    RelayPolicyViolation::TooManyOutputs => TxSubmissionRejectionReason::TooManyOutputs,
+                    RelayPolicyViolation::DustOutput => TxSubmissionRejectionReason::DustOutput,
+                    RelayPolicyViolation::TooManyUnconfirmedForUniqueId => {
+                        TxSubmissionRejectionReason::TooManyUnconfirmedForUniqueId
+                    },
+                    RelayPolicyViolation::SenderWeightLimitExceeded => {
+                        TxSubmissionRejectionReason::SenderWeightLimitExceeded
+                    },
+                }
+                .into(),
+                is_synced,
+            },
+
             TxStorageResponse::NotStored => TxSubmissionResponse {
                 accepted: false,
                 rejection_reason: TxSubmissionRejectionReason::ValidationFailed.into(),
                 is_synced,
             },
+            TxStorageResponse::NotStoredExpired => TxSubmissionResponse {
+                accepted: false,
+                rejection_reason: TxSubmissionRejectionReason::Expired.into(),
+                is_synced,
+            },
+            TxStorageResponse::NotStoredConflict => TxSubmissionResponse {
+                accepted: false,
+                rejection_reason: TxSubmissionRejectionReason::DoubleSpend.into(),
+                is_synced,
+            },
             TxStorageResponse::NotStoredAlreadySpent | TxStorageResponse::ReorgPool => {
                 // Is this transaction a double spend or has this transaction been mined?
                 match transaction.first_kernel_excess_sig() {
@@ -237,7 +307,12 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletService for BaseNodeWalletRpc
         let message = request.into_message();
         let signature = Signature::try_from(message).map_err(|_| RpcStatus::bad_request("Signature was invalid"))?;
 
-        let mut response = self.fetch_kernel(signature).await?;
+        let chain_metadata = self
+            .db()
+            .get_chain_metadata()
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+        let mut response = self.fetch_kernel(signature, &chain_metadata).await?;
         response.is_synced = is_synced;
         Ok(Response::new(response))
     }
@@ -257,19 +332,38 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletService for BaseNodeWalletRpc
 
         let message = request.into_message();
 
-        let mut responses: Vec<TxQueryBatchResponse> = Vec::new();
+        // All responses in the batch are relative to the same chain tip
+        let chain_metadata = self
+            .db()
+            .get_chain_metadata()
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+        let height_of_longest_chain = chain_metadata.height_of_longest_chain();
+
+        let mut responses: Vec<TxQueryBatchResponse> = Vec::with_capacity(message.sigs.len());
 
         for sig in message.sigs {
             let signature = Signature::try_from(sig).map_err(|_| RpcStatus::bad_request("Signature was invalid"))?;
-            let response: TxQueryResponse = self.fetch_kernel(signature.clone()).await?;
+            let response: TxQueryResponse = self.fetch_kernel(signature.clone(), &chain_metadata).await?;
+            let block_height = if response.location == TxLocation::Mined as i32 {
+                height_of_longest_chain.saturating_sub(response.confirmations)
+            } else {
+                0
+            };
             responses.push(TxQueryBatchResponse {
                 signature: Some(SignatureProto::from(signature)),
                 location: response.location,
                 block_hash: response.block_hash,
                 confirmations: response.confirmations,
+                block_height,
+                rejection_reason: response.rejection_reason,
             });
         }
-        Ok(Response::new(TxQueryBatchResponses { responses, is_synced }))
+        Ok(Response::new(TxQueryBatchResponses {
+            responses,
+            is_synced,
+            height_of_longest_chain,
+        }))
     }
 
     async fn fetch_matching_utxos(
@@ -325,4 +419,216 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletService for BaseNodeWalletRpc
             is_synced,
         }))
     }
+
+    async fn query_utxos_by_script_hash(
+        &self,
+        request: Request<QueryUtxosByScriptHash>,
+    ) -> Result<Response<QueryUtxosResponse>, RpcStatus> {
+        let message = request.into_message();
+        if message.script_hash.is_empty() {
+            return Err(RpcStatus::bad_request("script_hash must not be empty"));
+        }
+
+        let state_machine = self.state_machine();
+        let status_watch = state_machine.get_status_info_watch();
+        let is_synced = match (*status_watch.borrow()).state_info {
+            StateInfo::Listening(li) => li.is_synced(),
+            _ => false,
+        };
+
+        let outputs = self
+            .db()
+            .fetch_utxos_by_script_hash(
+                message.script_hash,
+                message.start_mmr_position,
+                utxo_query_page_size(message.limit),
+            )
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+
+        Ok(Response::new(to_query_utxos_response(outputs, is_synced)))
+    }
+
+    async fn query_utxos_by_features(
+        &self,
+        request: Request<QueryUtxosByFeatures>,
+    ) -> Result<Response<QueryUtxosResponse>, RpcStatus> {
+        let message = request.into_message();
+        let flags = u8::try_from(message.flags)
+            .ok()
+            .and_then(OutputFlags::from_bits)
+            .filter(|flags| !flags.is_empty())
+            .ok_or_else(|| RpcStatus::bad_request(format!("Invalid output feature flags {}", message.flags)))?;
+
+        let state_machine = self.state_machine();
+        let status_watch = state_machine.get_status_info_watch();
+        let is_synced = match (*status_watch.borrow()).state_info {
+            StateInfo::Listening(li) => li.is_synced(),
+            _ => false,
+        };
+
+        let outputs = self
+            .db()
+            .fetch_utxos_by_features(flags, message.start_mmr_position, utxo_query_page_size(message.limit))
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+
+        Ok(Response::new(to_query_utxos_response(outputs, is_synced)))
+    }
+
+    async fn fetch_mmr_proof(
+        &self,
+        request: Request<FetchMmrProof>,
+    ) -> Result<Response<FetchMmrProofResponse>, RpcStatus> {
+        let message = request.into_message();
+        if message.output_hashes.len() > MAX_UTXO_QUERY_PAGE_SIZE {
+            return Err(RpcStatus::bad_request(format!(
+                "Cannot request MMR proofs for more than {} outputs",
+                MAX_UTXO_QUERY_PAGE_SIZE
+            )));
+        }
+
+        let state_machine = self.state_machine();
+        let status_watch = state_machine.get_status_info_watch();
+        let is_synced = match (*status_watch.borrow()).state_info {
+            StateInfo::Listening(li) => li.is_synced(),
+            _ => false,
+        };
+
+        let utxo_proofs = self
+            .db()
+            .fetch_utxo_mmr_proofs(message.output_hashes)
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+
+        let proofs = utxo_proofs
+            .proofs
+            .into_iter()
+            .map(|proof| {
+                Ok(UtxoMmrProofProto {
+                    output: Some(proof.output.into()),
+                    mmr_position: proof.mmr_position,
+                    merkle_proof: bincode::serialize(&proof.merkle_proof)?,
+                })
+            })
+            .collect::<Result<Vec<_>, bincode::Error>>()
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+
+        Ok(Response::new(FetchMmrProofResponse {
+            header: Some(utxo_proofs.header.into()),
+            output_mmr_root: utxo_proofs.output_mmr_root,
+            deleted_bitmap: utxo_proofs.deleted_bitmap,
+            proofs,
+            is_synced,
+        }))
+    }
+
+    async fn query_output_maturity(
+        &self,
+        request: Request<QueryOutputMaturity>,
+    ) -> Result<Response<QueryOutputMaturityResponse>, RpcStatus> {
+        let message = request.into_message();
+        if message.commitments.len() > MAX_UTXO_QUERY_PAGE_SIZE {
+            return Err(RpcStatus::bad_request(format!(
+                "Cannot query the maturity of more than {} outputs",
+                MAX_UTXO_QUERY_PAGE_SIZE
+            )));
+        }
+        let commitments = message
+            .commitments
+            .iter()
+            .map(|bytes| Commitment::from_bytes(bytes))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| RpcStatus::bad_request("Invalid commitment"))?;
+
+        let state_machine = self.state_machine();
+        let status_watch = state_machine.get_status_info_watch();
+        let is_synced = match (*status_watch.borrow()).state_info {
+            StateInfo::Listening(li) => li.is_synced(),
+            _ => false,
+        };
+
+        let (outputs, tip_height) = self
+            .db()
+            .fetch_unspent_outputs_by_commitment(commitments)
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+
+        let outputs = message
+            .commitments
+            .into_iter()
+            .zip(outputs)
+            .map(|(commitment, output)| match output {
+                Some(output) => OutputMaturity {
+                    commitment,
+                    is_unspent: true,
+                    maturity_height: output.features.maturity,
+                    // An input is valid in a block if its maturity is not greater than the block's height
+                    is_spendable: output.features.maturity <= tip_height + 1,
+                    is_coinbase: output.features.flags.contains(OutputFlags::COINBASE_OUTPUT),
+                },
+                None => OutputMaturity {
+                    commitment,
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        Ok(Response::new(QueryOutputMaturityResponse {
+            outputs,
+            tip_height,
+            is_synced,
+        }))
+    }
+
+    async fn estimate_fee_per_gram(&self, request: Request<u64>) -> Result<Response<FeePerGramEstimate>, RpcStatus> {
+        let target_blocks = request.into_message();
+        if target_blocks == 0 || target_blocks > MAX_TARGET_BLOCKS {
+            return Err(RpcStatus::bad_request(format!(
+                "Target blocks must be between 1 and {}",
+                MAX_TARGET_BLOCKS
+            )));
+        }
+
+        let state_machine = self.state_machine();
+        let status_watch = state_machine.get_status_info_watch();
+        let is_synced = match (*status_watch.borrow()).state_info {
+            StateInfo::Listening(li) => li.is_synced(),
+            _ => false,
+        };
+
+        let estimate = self
+            .fee_estimation()
+            .estimate_fee_per_gram(target_blocks)
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+
+        Ok(Response::new(FeePerGramEstimate {
+            fee_per_gram: estimate.fee_per_gram.into(),
+            target_blocks: estimate.target_blocks,
+            mempool_weight: estimate.mempool_weight,
+            num_blocks_analysed: estimate.num_blocks_analysed as u64,
+            is_synced,
+        }))
+    }
+}
+
+fn utxo_query_page_size(limit: u32) -> usize {
+    match limit as usize {
+        0 => MAX_UTXO_QUERY_PAGE_SIZE,
+        limit => cmp::min(limit, MAX_UTXO_QUERY_PAGE_SIZE),
+    }
+}
+
+fn to_query_utxos_response(outputs: Vec<(TransactionOutput, u32)>, is_synced: bool) -> QueryUtxosResponse {
+    QueryUtxosResponse {
+        outputs: outputs
+            .into_iter()
+            .map(|(output, mmr_position)| IndexedUtxo {
+                output: Some(output.into()),
+                mmr_position,
+            })
+            .collect(),
+        is_synced,
+    }
 }
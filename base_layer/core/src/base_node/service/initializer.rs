@@ -36,6 +36,7 @@ use crate::{
 use futures::{channel::mpsc, future, Stream, StreamExt};
 use log::*;
 use std::{convert::TryFrom, sync::Arc};
+use tari_comms::connectivity::ConnectivityRequester;
 use tari_comms_dht::Dht;
 use tari_p2p::{
     comms_connector::{PeerMessage, SubscriptionFactory},
@@ -180,6 +181,7 @@ where T: BlockchainBackend + 'static
             let outbound_message_service = dht.outbound_requester();
 
             let state_machine = handles.expect_handle::<StateMachineHandle>();
+            let connectivity = handles.expect_handle::<ConnectivityRequester>();
 
             let streams = BaseNodeStreams {
                 outbound_request_stream,
@@ -190,8 +192,14 @@ where T: BlockchainBackend + 'static
                 local_request_stream,
                 local_block_stream,
             };
-            let service =
-                BaseNodeService::new(outbound_message_service, inbound_nch, config, state_machine).start(streams);
+            let service = BaseNodeService::new(
+                outbound_message_service,
+                inbound_nch,
+                config,
+                state_machine,
+                connectivity,
+            )
+            .start(streams);
             futures::pin_mut!(service);
             future::select(service, handles.get_shutdown_signal()).await;
             info!(target: LOG_TARGET, "Base Node Service shutdown");
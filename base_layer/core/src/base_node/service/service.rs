@@ -29,6 +29,7 @@ use crate::{
             NodeCommsRequest,
             NodeCommsResponse,
         },
+        inbound_pipeline::{InboundPipeline, InboundPipelineConfig, SubmitOutcome},
         service::error::BaseNodeServiceError,
         state_machine_service::states::StateInfo,
         StateMachineHandle,
@@ -55,7 +56,7 @@ use tari_common_types::{
     types::BlockHash,
     waiting_requests::{generate_request_key, RequestKey, WaitingRequests},
 };
-use tari_comms::peer_manager::NodeId;
+use tari_comms::{connectivity::ConnectivityRequester, peer_manager::NodeId};
 use tari_comms_dht::{
     domain_message::OutboundDomainMessage,
     envelope::NodeDestination,
@@ -79,6 +80,8 @@ pub struct BaseNodeServiceConfig {
     pub fetch_utxos_timeout: Duration,
     /// The fraction of responses that need to be received for a corresponding service request to be finalize.
     pub desired_response_fraction: f32,
+    /// Queue and concurrency limits for processing blocks propagated by peers
+    pub inbound_block_pipeline: InboundPipelineConfig,
 }
 
 impl Default for BaseNodeServiceConfig {
@@ -88,6 +91,15 @@ impl Default for BaseNodeServiceConfig {
             fetch_blocks_timeout: Duration::from_secs(150),
             fetch_utxos_timeout: Duration::from_secs(600),
             desired_response_fraction: 0.6,
+            inbound_block_pipeline: InboundPipelineConfig {
+                queue_size: 100,
+                max_concurrent_preliminary_checks: 4,
+                validation_queue_size: 10,
+                max_concurrent_validations: 2,
+                max_in_flight_per_peer: 5,
+                penalize_after_dropped: Some(20),
+                penalty_ban_duration: Duration::from_secs(30 * 60),
+            },
         }
     }
 }
@@ -123,6 +135,7 @@ pub(super) struct BaseNodeService<B> {
     timeout_receiver_stream: Option<Receiver<RequestKey>>,
     config: BaseNodeServiceConfig,
     state_machine_handle: StateMachineHandle,
+    connectivity: ConnectivityRequester,
 }
 
 impl<B> BaseNodeService<B>
@@ -133,6 +146,7 @@ where B: BlockchainBackend + 'static
         inbound_nch: InboundNodeCommsHandlers<B>,
         config: BaseNodeServiceConfig,
         state_machine_handle: StateMachineHandle,
+        connectivity: ConnectivityRequester,
     ) -> Self {
         let (timeout_sender, timeout_receiver) = channel(100);
         Self {
@@ -143,6 +157,7 @@ where B: BlockchainBackend + 'static
             timeout_receiver_stream: Some(timeout_receiver),
             config,
             state_machine_handle,
+            connectivity,
         }
     }
 
@@ -180,6 +195,7 @@ where B: BlockchainBackend + 'static
             .expect("Base Node Service initialized without timeout_receiver_stream")
             .fuse();
         pin_mut!(timeout_receiver_stream);
+        let mut block_pipeline = self.spawn_inbound_block_pipeline();
         loop {
             futures::select! {
                 // Outbound request messages from the OutboundNodeCommsInterface
@@ -209,7 +225,7 @@ where B: BlockchainBackend + 'static
 
                 // Incoming block messages from the Comms layer
                 block_msg = inbound_block_stream.select_next_some() => {
-                    self.spawn_handle_incoming_block(block_msg).await;
+                    self.submit_incoming_block(&mut block_pipeline, block_msg).await;
                 }
 
                 // Incoming local request messages from the LocalNodeCommsInterface and other local services
@@ -311,27 +327,55 @@ where B: BlockchainBackend + 'static
         });
     }
 
-    async fn spawn_handle_incoming_block(&self, new_block: DomainMessage<NewBlock>) {
-        // Determine if we are bootstrapped
-        let status_watch = self.state_machine_handle.get_status_info_watch();
+    fn spawn_inbound_block_pipeline(&self) -> InboundPipeline<DomainMessage<NewBlock>> {
+        let state_machine_handle = self.state_machine_handle.clone();
+        let inbound_nch = self.inbound_nch.clone();
+        let preliminary_check = move |new_block| {
+            let bootstrapped = state_machine_handle.get_status_info_watch().borrow().bootstrapped;
+            preliminary_block_checks(inbound_nch.clone(), bootstrapped, new_block)
+        };
 
-        if !(*status_watch.borrow()).bootstrapped {
-            debug!(
-                target: LOG_TARGET,
-                "Propagated block `{}` from peer `{}` not processed while busy with initial sync.",
-                new_block.inner.block_hash.to_hex(),
-                new_block.source_peer.node_id.short_str(),
-            );
-            return;
-        }
         let inbound_nch = self.inbound_nch.clone();
-        task::spawn(async move {
-            let result = handle_incoming_block(inbound_nch, new_block).await;
+        let validate_and_store = move |new_block| {
+            let inbound_nch = inbound_nch.clone();
+            async move {
+                let result = handle_incoming_block(inbound_nch, new_block).await;
 
-            if let Err(e) = result {
-                error!(target: LOG_TARGET, "Failed to handle incoming block message: {:?}", e);
+                if let Err(e) = result {
+                    error!(target: LOG_TARGET, "Failed to handle incoming block message: {:?}", e);
+                }
             }
-        });
+        };
+
+        InboundPipeline::spawn(
+            "Block",
+            self.config.inbound_block_pipeline,
+            preliminary_check,
+            validate_and_store,
+        )
+    }
+
+    async fn submit_incoming_block(
+        &mut self,
+        pipeline: &mut InboundPipeline<DomainMessage<NewBlock>>,
+        new_block: DomainMessage<NewBlock>,
+    ) {
+        let source_peer = new_block.source_peer.node_id.clone();
+        if let SubmitOutcome::DroppedPeerQuota { penalize: true } = pipeline.submit(source_peer.clone(), new_block) {
+            warn!(
+                target: LOG_TARGET,
+                "Banning peer `{}` for flooding the node with blocks",
+                source_peer.short_str()
+            );
+            let ban_duration = self.config.inbound_block_pipeline.penalty_ban_duration;
+            if let Err(e) = self
+                .connectivity
+                .ban_peer_until(source_peer, ban_duration, "Flooded the node with blocks".to_string())
+                .await
+            {
+                error!(target: LOG_TARGET, "Failed to ban peer: {:?}", e);
+            }
+        }
     }
 
     fn spawn_handle_local_request(
@@ -653,6 +697,45 @@ fn spawn_request_timeout(mut timeout_sender: Sender<RequestKey>, request_key: Re
     });
 }
 
+/// Discards propagated blocks that do not need full validation. Returns the block if it should be validated.
+async fn preliminary_block_checks<B: BlockchainBackend + 'static>(
+    inbound_nch: InboundNodeCommsHandlers<B>,
+    bootstrapped: bool,
+    new_block: DomainMessage<NewBlock>,
+) -> Option<DomainMessage<NewBlock>> {
+    if !bootstrapped {
+        debug!(
+            target: LOG_TARGET,
+            "Propagated block `{}` from peer `{}` not processed while busy with initial sync.",
+            new_block.inner.block_hash.to_hex(),
+            new_block.source_peer.node_id.short_str(),
+        );
+        return None;
+    }
+
+    match inbound_nch.is_block_stored(new_block.inner.block_hash.clone()).await {
+        Ok(true) => {
+            debug!(
+                target: LOG_TARGET,
+                "Propagated block `{}` already stored",
+                new_block.inner.block_hash.to_hex()
+            );
+            None
+        },
+        Ok(false) => Some(new_block),
+        Err(e) => {
+            // The full validation stage performs the same lookup, so let it decide what to do with the block
+            warn!(
+                target: LOG_TARGET,
+                "Could not check if propagated block `{}` is stored: {:?}",
+                new_block.inner.block_hash.to_hex(),
+                e
+            );
+            Some(new_block)
+        },
+    }
+}
+
 async fn handle_incoming_block<B: BlockchainBackend + 'static>(
     mut inbound_nch: InboundNodeCommsHandlers<B>,
     domain_block_msg: DomainMessage<NewBlock>,
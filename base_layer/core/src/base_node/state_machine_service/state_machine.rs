@@ -133,7 +133,12 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
             (HeaderSync(s), HeaderSyncFailed) => Waiting(s.into()),
             (HeaderSync(s), NetworkSilence) => Listening(s.into()),
             (HorizonStateSync(s), HorizonStateSynchronized) => BlockSync(s.into()),
+            (HorizonStateSync(s), HorizonStateVerified) => BlockSync(s.into()),
             (HorizonStateSync(s), HorizonStateSyncFailure) => Waiting(s.into()),
+            // The committed horizon state is invalid, so the node must not continue to sync or start listening
+            (HorizonStateSync(_), HorizonStateVerificationFailed(reason)) => Shutdown(states::Shutdown::with_reason(
+                format!("Horizon state verification failed: {}", reason),
+            )),
             (BlockSync(s), BlocksSynchronized) => Listening(s.into()),
             (BlockSync(s), BlockSyncFailed) => Waiting(s.into()),
             (Listening(_), FallenBehind(Lagging(_, sync_peers))) => HeaderSync(sync_peers.into()),
@@ -58,6 +58,8 @@ pub enum StateEvent {
     HeaderSyncFailed,
     HorizonStateSynchronized,
     HorizonStateSyncFailure,
+    HorizonStateVerified,
+    HorizonStateVerificationFailed(String),
     BlocksSynchronized,
     BlockSyncFailed,
     FallenBehind(SyncStatus),
@@ -130,6 +132,8 @@ impl Display for StateEvent {
             HeaderSyncFailed => f.write_str("Header Synchronization Failed"),
             HorizonStateSynchronized => f.write_str("Horizon State Synchronized"),
             HorizonStateSyncFailure => f.write_str("Horizon State Synchronization Failed"),
+            HorizonStateVerified => f.write_str("Horizon State Verified"),
+            HorizonStateVerificationFailed(e) => write!(f, "Horizon State Verification Failed - {}", e),
             BlockSyncFailed => f.write_str("Block Synchronization Failed"),
             FallenBehind(s) => write!(f, "Fallen behind main chain - {}", s),
             NetworkSilence => f.write_str("Network Silence"),
@@ -191,6 +195,7 @@ impl StateInfo {
                     current as f64 / total as f64 * 100.0
                 ),
                 HorizonSyncStatus::Finalizing => "Finalizing horizon sync".to_string(),
+                HorizonSyncStatus::Verifying => "Verifying horizon state".to_string(),
             },
             Self::BlockSync(info) => format!(
                 "Syncing blocks: {}/{} ({:.0}%)",
@@ -316,6 +321,7 @@ impl Display for HorizonSyncInfo {
                 fmt.write_str(&format!("Horizon syncing outputs: {}/{}\n", current, total))
             },
             HorizonSyncStatus::Finalizing => fmt.write_str("Finalizing horizon state synchronization"),
+            HorizonSyncStatus::Verifying => fmt.write_str("Verifying horizon state"),
         }
     }
 }
@@ -325,4 +331,5 @@ pub enum HorizonSyncStatus {
     Kernels(u64, u64),
     Outputs(u64, u64),
     Finalizing,
+    Verifying,
 }
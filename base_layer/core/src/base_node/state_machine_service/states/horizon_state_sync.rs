@@ -37,6 +37,10 @@ mod horizon_state_synchronization;
 
 use horizon_state_synchronization::HorizonStateSynchronization;
 
+mod horizon_state_verification;
+
+use horizon_state_verification::HorizonStateVerification;
+
 use super::{
     events_and_states::{HorizonSyncInfo, HorizonSyncStatus},
     StateEvent,
@@ -89,14 +93,28 @@ impl HorizonStateSync {
         let mut horizon_state =
             HorizonStateSynchronization::new(shared, self.sync_peer.clone(), horizon_sync_height, &prover);
 
-        match horizon_state.synchronize().await {
-            Ok(()) => {
-                info!(target: LOG_TARGET, "Horizon state has synchronized.");
-                StateEvent::HorizonStateSynchronized
-            },
+        if let Err(err) = horizon_state.synchronize().await {
+            warn!(target: LOG_TARGET, "Synchronizing horizon state has failed. {}", err);
+            return StateEvent::HorizonStateSyncFailure;
+        }
+        info!(target: LOG_TARGET, "Horizon state has synchronized.");
+
+        let info = HorizonSyncInfo::new(
+            vec![self.sync_peer.peer_node_id().clone()],
+            HorizonSyncStatus::Verifying,
+        );
+        shared.set_state_info(StateInfo::HorizonSync(info));
+
+        let verification = HorizonStateVerification::new(
+            shared.db.clone().into_inner(),
+            shared.sync_validators.final_horizon_state.clone(),
+            horizon_sync_height,
+        );
+        match verification.run().await {
+            Ok(()) => StateEvent::HorizonStateVerified,
             Err(err) => {
-                warn!(target: LOG_TARGET, "Synchronizing horizon state has failed. {}", err);
-                StateEvent::HorizonStateSyncFailure
+                error!(target: LOG_TARGET, "Horizon state verification failed. {}", err);
+                StateEvent::HorizonStateVerificationFailed(err.to_string())
             },
         }
     }
@@ -44,6 +44,8 @@ pub enum HorizonSyncError {
     CommsInterfaceError(#[from] CommsInterfaceError),
     #[error("Final state validation failed: {0}")]
     FinalStateValidationFailed(ValidationError),
+    #[error("Horizon state verification failed: {0}")]
+    HorizonStateVerificationFailed(String),
     #[error("Join error: {0}")]
     JoinError(#[from] task::JoinError),
     #[error("Invalid kernel signature: {0}")]
@@ -622,6 +622,8 @@ impl<'a, B: BlockchainBackend + 'static> HorizonStateSynchronization<'a, B> {
                 header.height(),
                 header.hash().clone(),
                 header.accumulated_data().total_accumulated_difficulty,
+                header.accumulated_data().accumulated_monero_difficulty,
+                header.accumulated_data().accumulated_sha_difficulty,
             )
             .set_pruned_height(header.height(), pruned_kernel_sum, pruned_utxo_sum)
             .commit()
@@ -0,0 +1,154 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::error::HorizonSyncError;
+use crate::{
+    chain_storage::{BlockchainBackend, BlockchainDatabase, ChainHeader, MmrTree, PrunedOutput},
+    transactions::types::{Commitment, HashDigest},
+    validation::FinalHorizonStateValidation,
+};
+use log::*;
+use std::sync::Arc;
+use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+use tari_mmr::MerkleMountainRange;
+use tokio::task;
+
+const LOG_TARGET: &str = "c::bn::state_machine_service::states::horizon_state_sync::verification";
+
+/// Verifies the horizon state after it has been committed by horizon sync. This re-reads the stored state rather than
+/// trusting the sums calculated while syncing, checking that:
+/// - the unspent outputs balance against the expected emission at the horizon height, and
+/// - every kernel MMR leaf has a stored kernel, and together they produce the kernel MMR root of the horizon header.
+pub struct HorizonStateVerification<B> {
+    db: BlockchainDatabase<B>,
+    validator: Arc<dyn FinalHorizonStateValidation<B>>,
+    horizon_height: u64,
+}
+
+impl<B: BlockchainBackend + 'static> HorizonStateVerification<B> {
+    pub fn new(
+        db: BlockchainDatabase<B>,
+        validator: Arc<dyn FinalHorizonStateValidation<B>>,
+        horizon_height: u64,
+    ) -> Self {
+        Self {
+            db,
+            validator,
+            horizon_height,
+        }
+    }
+
+    /// Runs the verification on a blocking thread so that the runtime is not held up while the UTXO and kernel sets
+    /// are read.
+    pub async fn run(self) -> Result<(), HorizonSyncError> {
+        task::spawn_blocking(move || self.verify()).await?
+    }
+
+    fn verify(&self) -> Result<(), HorizonSyncError> {
+        let header = self.db.fetch_chain_header(self.horizon_height)?;
+        debug!(
+            target: LOG_TARGET,
+            "Verifying horizon state at height {}",
+            header.height()
+        );
+
+        let kernel_sum = self.verify_kernels(&header)?;
+        let utxo_sum = self.sum_unspent_outputs(&header)?;
+
+        if let Some(horizon_data) = self.db.fetch_horizon_data()? {
+            if horizon_data.kernel_sum() != &kernel_sum || horizon_data.utxo_sum() != &utxo_sum {
+                return Err(HorizonSyncError::HorizonStateVerificationFailed(
+                    "Stored horizon sums do not match the stored kernels and outputs".to_string(),
+                ));
+            }
+        }
+
+        let backend = self.db.db_read_access()?;
+        self.validator
+            .validate(header.height(), &utxo_sum, &kernel_sum, &*backend)
+            .map_err(HorizonSyncError::FinalStateValidationFailed)?;
+
+        info!(
+            target: LOG_TARGET,
+            "Horizon state at height {} verified",
+            header.height()
+        );
+        Ok(())
+    }
+
+    /// Returns the sum of the kernel excesses
+    fn verify_kernels(&self, header: &ChainHeader) -> Result<Commitment, HorizonSyncError> {
+        let num_kernels = header.header().kernel_mmr_size;
+        let kernels = if num_kernels == 0 {
+            Vec::new()
+        } else {
+            self.db.fetch_kernels_by_mmr_position(0, num_kernels - 1)?
+        };
+        if kernels.len() as u64 != num_kernels {
+            return Err(HorizonSyncError::HorizonStateVerificationFailed(format!(
+                "Expected {} kernels but {} are stored",
+                num_kernels,
+                kernels.len()
+            )));
+        }
+
+        let mut kernel_mmr = MerkleMountainRange::<HashDigest, _>::new(Vec::new());
+        let mut kernel_sum = Commitment::default();
+        for kernel in kernels {
+            kernel_mmr.push(kernel.hash())?;
+            kernel_sum = &kernel.excess + &kernel_sum;
+        }
+
+        let kernel_mr = kernel_mmr.get_merkle_root()?;
+        if kernel_mr != header.header().kernel_mr {
+            return Err(HorizonSyncError::InvalidMmrRoot {
+                mmr_tree: MmrTree::Kernel,
+                at_height: header.height(),
+                expected_hex: header.header().kernel_mr.to_hex(),
+                actual_hex: kernel_mr.to_hex(),
+            });
+        }
+
+        Ok(kernel_sum)
+    }
+
+    fn sum_unspent_outputs(&self, header: &ChainHeader) -> Result<Commitment, HorizonSyncError> {
+        let num_outputs = header.header().output_mmr_size;
+        if num_outputs == 0 {
+            return Ok(Commitment::default());
+        }
+
+        let deleted = Arc::new(
+            self.db
+                .fetch_complete_deleted_bitmap_at(header.hash().clone())?
+                .into_bitmap(),
+        );
+        let (outputs, _) = self.db.fetch_utxos_by_mmr_position(0, num_outputs - 1, deleted)?;
+        let utxo_sum = outputs
+            .into_iter()
+            .fold(Commitment::default(), |sum, output| match output {
+                PrunedOutput::NotPruned { output } => &output.commitment + &sum,
+                _ => sum,
+            });
+        Ok(utxo_sum)
+    }
+}
@@ -31,15 +31,18 @@ use crate::{
     },
     chain_storage::BlockchainBackend,
 };
-use futures::StreamExt;
+use futures::{stream::Fuse, FutureExt, StreamExt};
 use log::*;
 use num_format::{Locale, ToFormattedString};
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp::Ordering,
     fmt::{Display, Formatter},
     ops::Deref,
+    sync::Arc,
 };
 use tari_common_types::chain_metadata::ChainMetadata;
+use tari_comms::connectivity::{ConnectivityEvent, ConnectivityEventRx};
 use tari_crypto::tari_utilities::epoch_time::EpochTime;
 use tokio::sync::broadcast;
 
@@ -118,7 +121,8 @@ impl Listening {
 
         info!(target: LOG_TARGET, "Listening for chain metadata updates");
         shared.set_state_info(StateInfo::Listening(ListeningInfo::new(self.is_synced)));
-        while let Some(metadata_event) = shared.metadata_event_stream.next().await {
+        let mut connectivity_events = shared.connectivity.get_event_subscription().fuse();
+        while let Some(metadata_event) = self.next_metadata_event(shared, &mut connectivity_events).await {
             match metadata_event.as_ref().map(|v| v.deref()) {
                 Ok(ChainMetadataEvent::PeerChainMetadataReceived(peer_metadata_list)) => {
                     let mut peer_metadata_list = peer_metadata_list.clone();
@@ -218,6 +222,36 @@ impl Listening {
         );
         StateEvent::UserQuit
     }
+
+    /// Waits for the next chain metadata event. A node that goes offline can no longer tell whether it is synced, so
+    /// it is marked as not synced until chain metadata is received from its peers again.
+    async fn next_metadata_event<B: BlockchainBackend + 'static>(
+        &mut self,
+        shared: &mut BaseNodeStateMachine<B>,
+        connectivity_events: &mut Fuse<ConnectivityEventRx>,
+    ) -> Option<Result<Arc<ChainMetadataEvent>, broadcast::RecvError>> {
+        loop {
+            let connectivity_event = {
+                let metadata_event = shared.metadata_event_stream.next().fuse();
+                futures::pin_mut!(metadata_event);
+                futures::select! {
+                    event = metadata_event => return event,
+                    event = connectivity_events.select_next_some() => event,
+                }
+            };
+
+            if let Ok(ConnectivityEvent::ConnectivityStateOffline) = connectivity_event.as_ref().map(|e| e.deref()) {
+                if self.is_synced {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Node is offline and is no longer considered synced until chain metadata is received"
+                    );
+                    self.is_synced = false;
+                    shared.set_state_info(StateInfo::Listening(ListeningInfo::new(self.is_synced)));
+                }
+            }
+        }
+    }
 }
 
 impl From<Waiting> for Listening {
@@ -263,17 +297,26 @@ fn select_sync_peers(
 /// Determine the best metadata from a set of metadata received from the network.
 fn best_metadata(metadata_list: &[PeerChainMetadata]) -> Option<&ChainMetadata> {
     // TODO: Use heuristics to weed out outliers / dishonest nodes.
-    metadata_list.iter().fold(None, |best, current| {
-        if current.chain_metadata.accumulated_difficulty() >=
-            best.as_ref().map(|cm| cm.accumulated_difficulty()).unwrap_or(0)
-        {
-            Some(&current.chain_metadata)
-        } else {
-            best
-        }
+    metadata_list.iter().fold(None, |best, current| match best {
+        Some(best) if compare_chain_strength(&current.chain_metadata, best) == Ordering::Less => Some(best),
+        _ => Some(&current.chain_metadata),
     })
 }
 
+/// Compares the strength of the chains described by two sets of chain metadata in the same order as the default chain
+/// strength comparer: by total accumulated difficulty, then height, then the accumulated Monero and SHA3 difficulty.
+/// Peers that do not report the accumulated difficulty of each proof of work algorithm are compared as if it were zero.
+fn compare_chain_strength(a: &ChainMetadata, b: &ChainMetadata) -> Ordering {
+    a.accumulated_difficulty()
+        .cmp(&b.accumulated_difficulty())
+        .then_with(|| a.height_of_longest_chain().cmp(&b.height_of_longest_chain()))
+        .then_with(|| {
+            a.accumulated_monero_difficulty()
+                .cmp(&b.accumulated_monero_difficulty())
+        })
+        .then_with(|| a.accumulated_sha_difficulty().cmp(&b.accumulated_sha_difficulty()))
+}
+
 /// Given a local and the network chain state respectively, figure out what synchronisation state we should be in.
 fn determine_sync_mode(
     blocks_behind_before_considered_lagging: u64,
@@ -284,7 +327,7 @@ fn determine_sync_mode(
     use SyncStatus::*;
     let network_tip_accum_difficulty = network.accumulated_difficulty();
     let local_tip_accum_difficulty = local.accumulated_difficulty();
-    if local_tip_accum_difficulty < network_tip_accum_difficulty {
+    if compare_chain_strength(local, &network) == Ordering::Less {
         let local_tip_height = local.height_of_longest_chain();
         let network_tip_height = network.height_of_longest_chain();
         info!(
@@ -473,4 +516,24 @@ mod test {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn chain_strength_tie_breaks_on_pow_accumulated_difficulty() {
+        let local = ChainMetadata::new(10, vec![1], 0, 0, 500_000).with_pow_accumulated_difficulty(300, 200);
+        let network = ChainMetadata::new(10, vec![2], 0, 0, 500_000).with_pow_accumulated_difficulty(301, 199);
+        match determine_sync_mode(0, &local, network.clone(), vec![]) {
+            SyncStatus::Lagging(n, _) => assert_eq!(n, network),
+            _ => panic!(),
+        }
+        match determine_sync_mode(0, &network, local.clone(), vec![]) {
+            SyncStatus::UpToDate => {},
+            _ => panic!(),
+        }
+
+        let peers = vec![
+            PeerChainMetadata::new(random_node_id(), network.clone()),
+            PeerChainMetadata::new(random_node_id(), local),
+        ];
+        assert_eq!(best_metadata(&peers), Some(&network));
+    }
 }
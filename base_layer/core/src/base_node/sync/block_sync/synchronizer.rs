@@ -23,29 +23,36 @@
 use super::error::BlockSyncError;
 use crate::{
     base_node::sync::{hooks::Hooks, rpc},
-    chain_storage::{async_db::AsyncBlockchainDb, BlockchainBackend, ChainBlock},
-    proto::base_node::SyncBlocksRequest,
-    tari_utilities::{hex::Hex, Hashable},
-    transactions::aggregated_body::AggregateBody,
-    validation::CandidateBlockBodyValidation,
+    chain_storage::{async_db::AsyncBlockchainDb, BlockchainBackend, ChainBlock, ChainHeader},
+    proto::base_node::{block_body_part::Part, BlockBodyPart, SyncBlocksRequest},
+    tari_utilities::{convert::try_convert_all, hex::Hex, Hashable},
+    transactions::{
+        aggregated_body::AggregateBody,
+        transaction::{TransactionInput, TransactionKernel, TransactionOutput},
+    },
+    validation::{BlockBodyPartValidation, CandidateBlockBodyValidation},
 };
-use futures::StreamExt;
+use futures::{stream, stream::BoxStream, StreamExt, TryStreamExt};
 use log::*;
 use num_format::{Locale, ToFormattedString};
 use std::{
-    convert::TryFrom,
     sync::Arc,
     time::{Duration, Instant},
 };
 use tari_comms::{
     connectivity::{ConnectivityRequester, ConnectivitySelection},
     peer_manager::NodeId,
+    protocol::rpc::{RpcStatus, RpcStatusCode},
     PeerConnection,
 };
 use tokio::task;
 
 const LOG_TARGET: &str = "c::bn::block_sync";
 
+/// The maximum number of kernels, outputs or inputs in each part when splitting whole block bodies received from peers
+/// that do not support `sync_block_parts`
+const MAX_ITEMS_PER_PART: usize = 100;
+
 pub struct BlockSynchronizer<B> {
     db: AsyncBlockchainDb<B>,
     connectivity: ConnectivityRequester,
@@ -153,21 +160,33 @@ impl<B: BlockchainBackend + 'static> BlockSynchronizer<B> {
             end_hash: tip_hash.clone(),
         };
 
-        let mut block_stream = client.sync_blocks(request).await?;
+        let mut parts = Self::request_block_parts(client, request).await?;
         let mut prev_hash = best_full_block_hash;
+        let mut pending = None;
         let mut current_block = None;
-        while let Some(block) = block_stream.next().await {
-            let block = block?;
+        while let Some(part) = parts.next().await {
+            let part = part?
+                .part
+                .ok_or_else(|| BlockSyncError::ReceivedInvalidBlockBody("Block body part was empty".to_string()))?;
+
+            let hash = match part {
+                Part::Hash(hash) => hash,
+                part => {
+                    let block = pending.take().ok_or_else(|| {
+                        BlockSyncError::ReceivedInvalidBlockBody("Peer sent a block body part before its hash".into())
+                    })?;
+                    pending = Some(self.validate_part(block, part).await?);
+                    continue;
+                },
+            };
 
-            let header = self
-                .db
-                .fetch_chain_header_by_block_hash(block.hash.clone())
-                .await?
-                .ok_or_else(|| {
-                    BlockSyncError::ReceivedInvalidBlockBody("Peer sent hash for block header we do not have".into())
-                })?;
+            if let Some(block) = pending.take() {
+                current_block = Some(self.store_block(peer, tip_height, block).await?);
+            }
 
-            let header_hash = header.hash().clone();
+            let header = self.db.fetch_chain_header_by_block_hash(hash).await?.ok_or_else(|| {
+                BlockSyncError::ReceivedInvalidBlockBody("Peer sent hash for block header we do not have".into())
+            })?;
 
             if header.header().prev_hash != prev_hash {
                 return Err(BlockSyncError::PeerSentBlockThatDidNotFormAChain {
@@ -176,63 +195,19 @@ impl<B: BlockchainBackend + 'static> BlockSynchronizer<B> {
                 });
             }
 
-            prev_hash = header_hash.clone();
-
-            let body = block
-                .body
-                .map(AggregateBody::try_from)
-                .ok_or_else(|| BlockSyncError::ReceivedInvalidBlockBody("Block body was empty".to_string()))?
-                .map_err(BlockSyncError::ReceivedInvalidBlockBody)?;
+            prev_hash = header.hash().clone();
 
             debug!(
                 target: LOG_TARGET,
-                "Validating block body #{} (PoW = {}, {})",
+                "Validating block body #{} (PoW = {})",
                 header.height(),
                 header.header().pow_algo(),
-                body.to_counts_string(),
             );
+            pending = Some(self.begin_block(header).await?);
+        }
 
-            let timer = Instant::now();
-            let block = Arc::new(header.upgrade_to_chain_block(body));
-            self.validate_block(block.clone()).await?;
-
-            debug!(
-                target: LOG_TARGET,
-                "Validated in {:.0?}. Storing block body #{} (PoW = {}, {})",
-                timer.elapsed(),
-                block.header().height,
-                block.header().pow_algo(),
-                block.block().body.to_counts_string(),
-            );
-
-            let timer = Instant::now();
-            self.db
-                .write_transaction()
-                .insert_block_body(block.clone())
-                .set_best_block(
-                    block.height(),
-                    header_hash,
-                    block.accumulated_data().total_accumulated_difficulty,
-                )
-                .commit()
-                .await?;
-
-            self.hooks
-                .call_on_progress_block_hooks(block.clone(), tip_height, &[peer.clone()]);
-
-            debug!(
-                target: LOG_TARGET,
-                "Block body #{} added in {:.0?}, Tot_acc_diff {}, Monero {}, SHA3 {}",
-                block.height(),
-                timer.elapsed(),
-                block
-                    .accumulated_data()
-                    .total_accumulated_difficulty
-                    .to_formatted_string(&Locale::en),
-                block.accumulated_data().accumulated_monero_difficulty,
-                block.accumulated_data().accumulated_sha_difficulty,
-            );
-            current_block = Some(block);
+        if let Some(block) = pending.take() {
+            current_block = Some(self.store_block(peer, tip_height, block).await?);
         }
 
         if let Some(block) = current_block {
@@ -240,10 +215,16 @@ impl<B: BlockchainBackend + 'static> BlockSynchronizer<B> {
             let header = &block.header();
             let height = header.height;
             let best_block = header.hash();
-            let accumulated_difficulty = block.accumulated_data().total_accumulated_difficulty;
+            let accumulated_data = block.accumulated_data();
             self.db
                 .write_transaction()
-                .set_best_block(height, best_block.to_vec(), accumulated_difficulty)
+                .set_best_block(
+                    height,
+                    best_block.to_vec(),
+                    accumulated_data.total_accumulated_difficulty,
+                    accumulated_data.accumulated_monero_difficulty,
+                    accumulated_data.accumulated_sha_difficulty,
+                )
                 .commit()
                 .await?;
 
@@ -255,16 +236,150 @@ impl<B: BlockchainBackend + 'static> BlockSynchronizer<B> {
         Ok(())
     }
 
-    async fn validate_block(&self, block: Arc<ChainBlock>) -> Result<(), BlockSyncError> {
+    /// Requests the block bodies a part at a time, falling back to whole block bodies for peers that do not support
+    /// `sync_block_parts`
+    async fn request_block_parts(
+        client: &mut rpc::BaseNodeSyncRpcClient,
+        request: SyncBlocksRequest,
+    ) -> Result<BoxStream<'static, Result<BlockBodyPart, RpcStatus>>, BlockSyncError> {
+        let mut parts = client.sync_block_parts(request.clone()).await?;
+        match parts.next().await {
+            Some(Err(status)) if status.status_code() == RpcStatusCode::UnsupportedMethod => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Peer does not support syncing block body parts, requesting whole block bodies"
+                );
+                let blocks = client.sync_blocks(request).await?;
+                Ok(blocks
+                    .map_ok(|block| stream::iter(BlockBodyPart::split(block, MAX_ITEMS_PER_PART).into_iter().map(Ok)))
+                    .try_flatten()
+                    .boxed())
+            },
+            first => Ok(stream::iter(first).chain(parts).boxed()),
+        }
+    }
+
+    async fn begin_block(&self, header: ChainHeader) -> Result<PendingBlock<B>, BlockSyncError> {
         let validator = self.block_validator.clone();
+        let db = self.db.clone();
+        task::spawn_blocking(move || {
+            let db = db.inner().db_read_access()?;
+            let body_validator = validator.begin_body(header.header(), &*db)?;
+            Ok(PendingBlock {
+                header,
+                validator: body_validator,
+                kernels: Vec::new(),
+                outputs: Vec::new(),
+                inputs: Vec::new(),
+                timer: Instant::now(),
+            })
+        })
+        .await
+        .expect("block validator panicked")
+    }
 
+    /// Validates a part of the body of `block` and adds it to the block
+    async fn validate_part(&self, block: PendingBlock<B>, part: Part) -> Result<PendingBlock<B>, BlockSyncError> {
         let db = self.db.clone();
         task::spawn_blocking(move || {
+            let mut block = block;
             let db = db.inner().db_read_access()?;
-            validator.validate_body(block.block(), &*db)?;
-            Result::<_, BlockSyncError>::Ok(())
+            match part {
+                Part::Kernels(kernels) => {
+                    let kernels = try_convert_all(kernels.kernels).map_err(BlockSyncError::ReceivedInvalidBlockBody)?;
+                    block.validator.validate_kernels(&kernels)?;
+                    block.kernels.extend(kernels);
+                },
+                Part::Outputs(outputs) => {
+                    let outputs = try_convert_all(outputs.outputs).map_err(BlockSyncError::ReceivedInvalidBlockBody)?;
+                    block.validator.validate_outputs(&outputs, &*db)?;
+                    block.outputs.extend(outputs);
+                },
+                Part::Inputs(inputs) => {
+                    let inputs = try_convert_all(inputs.inputs).map_err(BlockSyncError::ReceivedInvalidBlockBody)?;
+                    block.validator.validate_inputs(&inputs, &block.outputs, &*db)?;
+                    block.inputs.extend(inputs);
+                },
+                Part::Hash(_) => unreachable!("block hashes are handled by the caller"),
+            }
+            Ok(block)
         })
         .await
         .expect("block validator panicked")
     }
+
+    /// Completes the validation of `block` and stores it
+    async fn store_block(
+        &mut self,
+        peer: &NodeId,
+        tip_height: u64,
+        block: PendingBlock<B>,
+    ) -> Result<Arc<ChainBlock>, BlockSyncError> {
+        let PendingBlock {
+            header,
+            validator,
+            kernels,
+            outputs,
+            inputs,
+            timer,
+        } = block;
+        task::spawn_blocking(move || validator.finalize())
+            .await
+            .expect("block validator panicked")?;
+
+        // The validator has checked that the inputs and outputs were received in order, so this does not reorder them
+        let mut body = AggregateBody::new(inputs, outputs, kernels);
+        body.sort();
+        let block = Arc::new(header.upgrade_to_chain_block(body));
+        debug!(
+            target: LOG_TARGET,
+            "Validated in {:.0?}. Storing block body #{} (PoW = {}, {})",
+            timer.elapsed(),
+            block.header().height,
+            block.header().pow_algo(),
+            block.block().body.to_counts_string(),
+        );
+
+        let timer = Instant::now();
+        self.db
+            .write_transaction()
+            .insert_block_body(block.clone())
+            .set_best_block_if_equal(
+                block.header().prev_hash.clone(),
+                block.height(),
+                block.hash().clone(),
+                block.accumulated_data().total_accumulated_difficulty,
+                block.accumulated_data().accumulated_monero_difficulty,
+                block.accumulated_data().accumulated_sha_difficulty,
+            )
+            .commit()
+            .await?;
+
+        self.hooks
+            .call_on_progress_block_hooks(block.clone(), tip_height, &[peer.clone()]);
+
+        debug!(
+            target: LOG_TARGET,
+            "Block body #{} added in {:.0?}, Tot_acc_diff {}, Monero {}, SHA3 {}",
+            block.height(),
+            timer.elapsed(),
+            block
+                .accumulated_data()
+                .total_accumulated_difficulty
+                .to_formatted_string(&Locale::en),
+            block.accumulated_data().accumulated_monero_difficulty,
+            block.accumulated_data().accumulated_sha_difficulty,
+        );
+        Ok(block)
+    }
+}
+
+/// A block whose body is being received. Each part of the body is validated as it is received.
+struct PendingBlock<B> {
+    header: ChainHeader,
+    validator: Box<dyn BlockBodyPartValidation<B>>,
+    kernels: Vec<TransactionKernel>,
+    outputs: Vec<TransactionOutput>,
+    inputs: Vec<TransactionInput>,
+    timer: Instant,
 }
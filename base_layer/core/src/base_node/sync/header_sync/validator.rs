@@ -36,15 +36,15 @@ use crate::{
     proof_of_work::{randomx_factory::RandomXFactory, PowAlgorithm},
     tari_utilities::{epoch_time::EpochTime, hash::Hashable, hex::Hex},
     transactions::types::HashOutput,
-    validation::helpers::{
-        check_header_timestamp_greater_than_median,
-        check_pow_data,
-        check_target_difficulty,
-        check_timestamp_ftl,
+    validation::{
+        helpers::{check_pow_data, check_target_difficulty},
+        timestamp_validators::{default_timestamp_validators, NetworkTimeOffset},
+        HeaderTimestampValidation,
+        ValidationError,
     },
 };
 use log::*;
-use std::cmp::Ordering;
+use std::{cmp::Ordering, sync::Arc};
 
 const LOG_TARGET: &str = "c::bn::header_sync";
 
@@ -54,6 +54,7 @@ pub struct BlockHeaderSyncValidator<B> {
     state: Option<State>,
     consensus_rules: ConsensusManager,
     randomx_factory: RandomXFactory,
+    timestamp_validators: Vec<Arc<dyn HeaderTimestampValidation>>,
 }
 
 #[derive(Debug, Clone)]
@@ -70,6 +71,7 @@ impl<B: BlockchainBackend + 'static> BlockHeaderSyncValidator<B> {
         Self {
             db,
             state: None,
+            timestamp_validators: default_timestamp_validators(consensus_rules.clone(), NetworkTimeOffset::default()),
             consensus_rules,
             randomx_factory,
         }
@@ -130,9 +132,16 @@ impl<B: BlockchainBackend + 'static> BlockHeaderSyncValidator<B> {
                 expected: state.previous_accum.hash.to_hex(),
             });
         }
-        check_timestamp_ftl(&header, &self.consensus_rules)?;
-
-        check_header_timestamp_greater_than_median(&header, &state.timestamps)?;
+        let header_hash = header.hash();
+        if self.db.inner().bad_block_exists(header_hash.clone())? {
+            return Err(ValidationError::BadBlockFound {
+                hash: header_hash.to_hex(),
+            }
+            .into());
+        }
+        for validator in &self.timestamp_validators {
+            validator.validate_timestamp(&header, &state.timestamps)?;
+        }
 
         let constants = self.consensus_rules.consensus_constants(header.height);
         let target_difficulty = state.target_difficulties.get(header.pow_algo()).calculate(
@@ -148,14 +157,8 @@ impl<B: BlockchainBackend + 'static> BlockHeaderSyncValidator<B> {
         // nothing to do with locking or concurrency.
         let state = self.state_mut();
 
-        // Ensure that timestamps are inserted in sorted order
-        let maybe_index = state.timestamps.iter().position(|ts| ts >= &header.timestamp());
-        match maybe_index {
-            Some(pos) => {
-                state.timestamps.insert(pos, header.timestamp());
-            },
-            None => state.timestamps.push(header.timestamp()),
-        }
+        // Timestamps are kept in chain order so that the oldest is the one that leaves the median window
+        state.timestamps.push(header.timestamp());
 
         state.current_height = header.height;
         // Add a "more recent" datapoint onto the target difficulty
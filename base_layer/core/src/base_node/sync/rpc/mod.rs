@@ -49,6 +49,11 @@ use crate::{
 use tari_comms::protocol::rpc::{Request, Response, RpcStatus, Streaming};
 use tari_comms_rpc_macros::tari_rpc;
 
+/// The methods of the sync RPC service that a syncing peer needs in order to make progress (sync_blocks, sync_headers,
+/// find_chain_split, sync_kernels, sync_utxos and sync_block_parts). Requests for these methods from peers that are
+/// actively syncing are handled before other queued RPC requests.
+pub const SYNC_CRITICAL_METHODS: &[u32] = &[1, 2, 4, 6, 8, 9];
+
 #[tari_rpc(protocol_name = b"t/blksync/1", server_struct = BaseNodeSyncRpcServer, client_struct = BaseNodeSyncRpcClient)]
 pub trait BaseNodeSyncService: Send + Sync + 'static {
     #[rpc(method = 1)]
@@ -89,6 +94,14 @@ pub trait BaseNodeSyncService: Send + Sync + 'static {
 
     #[rpc(method = 8)]
     async fn sync_utxos(&self, request: Request<SyncUtxosRequest>) -> Result<Streaming<SyncUtxosResponse>, RpcStatus>;
+
+    /// Streams the requested block bodies a part at a time, so that the receiver can validate each part as it arrives
+    /// without holding the whole serialized block
+    #[rpc(method = 9)]
+    async fn sync_block_parts(
+        &self,
+        request: Request<SyncBlocksRequest>,
+    ) -> Result<Streaming<proto::base_node::BlockBodyPart>, RpcStatus>;
 }
 
 #[cfg(feature = "base_node")]
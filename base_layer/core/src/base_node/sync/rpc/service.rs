@@ -22,7 +22,13 @@
 
 use crate::{
     base_node::sync::rpc::BaseNodeSyncService,
-    chain_storage::{async_db::AsyncBlockchainDb, BlockchainBackend, OrNotFound},
+    chain_storage::{
+        async_db::AsyncBlockchainDb,
+        BlockchainBackend,
+        BlockchainSnapshot,
+        ChainStorageError,
+        OrNotFound,
+    },
     crypto::tari_utilities::Hashable,
     iterators::NonOverlappingIntegerPairIter,
     proto,
@@ -59,17 +65,10 @@ impl<B: BlockchainBackend + 'static> BaseNodeSyncRpcService<B> {
     fn db(&self) -> AsyncBlockchainDb<B> {
         self.db.clone()
     }
-}
-
-#[tari_comms::async_trait]
-impl<B: BlockchainBackend + 'static> BaseNodeSyncService for BaseNodeSyncRpcService<B> {
-    async fn sync_blocks(
-        &self,
-        request: Request<SyncBlocksRequest>,
-    ) -> Result<Streaming<proto::base_node::BlockBodyResponse>, RpcStatus> {
-        let peer_node_id = request.context().peer_node_id().clone();
-        let message = request.into_message();
 
+    /// Returns the heights of the first and last blocks requested by a block sync, or None if this node does not have
+    /// any of the blocks yet
+    async fn block_sync_range(&self, message: SyncBlocksRequest) -> Result<Option<(u64, u64)>, RpcStatus> {
         let db = self.db();
         let start_header = db
             .fetch_header_by_block_hash(message.start_hash)
@@ -92,7 +91,7 @@ impl<B: BlockchainBackend + 'static> BaseNodeSyncService for BaseNodeSyncRpcServ
         }
 
         if start > metadata.height_of_longest_chain() {
-            return Ok(Streaming::empty());
+            return Ok(None);
         }
 
         let end_header = db
@@ -109,11 +108,28 @@ impl<B: BlockchainBackend + 'static> BaseNodeSyncService for BaseNodeSyncRpcServ
             )));
         }
 
+        Ok(Some((start, end)))
+    }
+}
+
+#[tari_comms::async_trait]
+impl<B: BlockchainBackend + 'static> BaseNodeSyncService for BaseNodeSyncRpcService<B> {
+    async fn sync_blocks(
+        &self,
+        request: Request<SyncBlocksRequest>,
+    ) -> Result<Streaming<proto::base_node::BlockBodyResponse>, RpcStatus> {
+        let peer_node_id = request.context().peer_node_id().clone();
+        let (start, end) = match self.block_sync_range(request.into_message()).await? {
+            Some(range) => range,
+            None => return Ok(Streaming::empty()),
+        };
+
         debug!(
             target: LOG_TARGET,
             "Initiating block sync with peer `{}` from height {} to {}", peer_node_id, start, end,
         );
 
+        let db = self.db();
         // Number of blocks to load and push to the stream before loading the next batch
         const BATCH_SIZE: usize = 4;
         let (mut tx, rx) = mpsc::channel(BATCH_SIZE);
@@ -168,24 +184,94 @@ impl<B: BlockchainBackend + 'static> BaseNodeSyncService for BaseNodeSyncRpcServ
         Ok(Streaming::new(rx))
     }
 
+    async fn sync_block_parts(
+        &self,
+        request: Request<SyncBlocksRequest>,
+    ) -> Result<Streaming<proto::base_node::BlockBodyPart>, RpcStatus> {
+        let peer_node_id = request.context().peer_node_id().clone();
+        let (start, end) = match self.block_sync_range(request.into_message()).await? {
+            Some(range) => range,
+            None => return Ok(Streaming::empty()),
+        };
+
+        debug!(
+            target: LOG_TARGET,
+            "Initiating block part sync with peer `{}` from height {} to {}", peer_node_id, start, end,
+        );
+
+        let db = self.db();
+        // Maximum number of kernels, outputs or inputs in each part
+        const MAX_ITEMS_PER_PART: usize = 100;
+        // Number of parts to push to the stream before waiting for the peer to read them
+        const BUFFER_SIZE: usize = 10;
+        let (mut tx, rx) = mpsc::channel(BUFFER_SIZE);
+
+        task::spawn(async move {
+            for height in start..=end {
+                if tx.is_closed() {
+                    break;
+                }
+
+                trace!(target: LOG_TARGET, "Sending block #{}", height);
+                let block = db
+                    .fetch_block(height)
+                    .await
+                    .map_err(RpcStatus::log_internal_error(LOG_TARGET))
+                    .and_then(|hb| hb.try_into_block().map_err(RpcStatus::log_internal_error(LOG_TARGET)));
+
+                match block {
+                    Ok(block) => {
+                        let mut parts = stream::iter(
+                            proto::base_node::BlockBodyPart::split(block.into(), MAX_ITEMS_PER_PART)
+                                .into_iter()
+                                .map(Ok)
+                                .map(Ok),
+                        );
+
+                        // Ensure task stops if the peer prematurely stops their RPC session
+                        if tx.send_all(&mut parts).await.is_err() {
+                            break;
+                        }
+                    },
+                    Err(err) => {
+                        let _ = tx.send(Err(err)).await;
+                        break;
+                    },
+                }
+            }
+
+            debug!(
+                target: LOG_TARGET,
+                "Block part sync round complete for peer `{}`.", peer_node_id,
+            );
+        });
+
+        Ok(Streaming::new(rx))
+    }
+
     async fn sync_headers(
         &self,
         request: Request<SyncHeadersRequest>,
     ) -> Result<Streaming<proto::core::BlockHeader>, RpcStatus> {
-        let db = self.db();
         let peer_node_id = request.context().peer_node_id().clone();
         let message = request.into_message();
 
-        let start_header = db
-            .fetch_header_by_block_hash(message.start_hash)
+        // All headers are read from a single snapshot so that the stream cannot straddle a reorg
+        let snapshot = self
+            .db()
+            .open_snapshot()
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+
+        let start_hash = message.start_hash;
+        let start_header = read_snapshot(&snapshot, move |s| s.fetch_header_by_block_hash(&start_hash))
             .await
             .map_err(RpcStatus::log_internal_error(LOG_TARGET))?
             .ok_or_else(|| RpcStatus::not_found("Header not found with given hash"))?;
 
         let mut count = message.count;
         if count == 0 {
-            let tip_header = db
-                .fetch_tip_header()
+            let tip_header = read_snapshot(&snapshot, |s| s.fetch_tip_header())
                 .await
                 .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
             count = tip_header.height().saturating_sub(start_header.height);
@@ -216,10 +302,17 @@ impl<B: BlockchainBackend + 'static> BaseNodeSyncService for BaseNodeSyncRpcServ
                     break;
                 }
                 debug!(target: LOG_TARGET, "Sending headers #{} - #{}", start, end);
-                let headers = db
-                    .fetch_headers(start..=end)
-                    .await
-                    .map_err(RpcStatus::log_internal_error(LOG_TARGET));
+                let headers = match read_snapshot(&snapshot, move |s| s.fetch_headers(start, end)).await {
+                    Err(err @ ChainStorageError::SnapshotExpired(_)) => {
+                        warn!(
+                            target: LOG_TARGET,
+                            "Header sync with peer `{}` stopped at height {}: {}", peer_node_id, start, err
+                        );
+                        // The peer can resume from the last header it received
+                        Err(RpcStatus::general(err))
+                    },
+                    res => res.map_err(RpcStatus::log_internal_error(LOG_TARGET)),
+                };
 
                 match headers {
                     Ok(headers) if headers.is_empty() => {
@@ -592,3 +685,13 @@ impl<B: BlockchainBackend + 'static> BaseNodeSyncService for BaseNodeSyncRpcServ
         Ok(Streaming::new(rx))
     }
 }
+
+/// Runs a read against the snapshot on the blocking thread pool
+async fn read_snapshot<F, T>(snapshot: &BlockchainSnapshot, f: F) -> Result<T, ChainStorageError>
+where
+    F: FnOnce(&BlockchainSnapshot) -> Result<T, ChainStorageError> + Send + 'static,
+    T: Send + 'static,
+{
+    let snapshot = snapshot.clone();
+    task::spawn_blocking(move || f(&snapshot)).await?
+}
@@ -24,7 +24,7 @@
 // Version 2.0, available at http://www.apache.org/licenses/LICENSE-2.0.
 
 use crate::{
-    blocks::BlockHeader,
+    blocks::{BlockHeader, CompactBlock},
     chain_storage::MmrTree,
     consensus::ConsensusConstants,
     proof_of_work::ProofOfWork,
@@ -88,18 +88,19 @@ impl Block {
     }
 
     /// Run through the outputs of the block and check that
-    /// 1. There is exactly ONE coinbase output
-    /// 1. The output's maturity is correctly set
-    /// 1. The amount is correct.
+    /// 1. There is at least one and at most `max_coinbase_outputs` coinbase outputs
+    /// 1. The outputs' maturity is correctly set
+    /// 1. The amounts add up to the reward.
     pub fn check_coinbase_output(
         &self,
         reward: MicroTari,
         consensus_constants: &ConsensusConstants,
         factories: &CryptoFactories,
     ) -> Result<(), BlockValidationError> {
-        self.body.check_coinbase_output(
+        self.body.check_coinbase_outputs(
             reward,
             consensus_constants.coinbase_lock_height(),
+            consensus_constants.max_coinbase_outputs(),
             factories,
             self.header.height,
         )?;
@@ -247,11 +248,16 @@ impl Hashable for Block {
 //---------------------------------- NewBlock --------------------------------------------//
 pub struct NewBlock {
     pub block_hash: BlockHash,
+    /// The compact block, if the propagating node provided one
+    pub compact_block: Option<CompactBlock>,
 }
 
 impl NewBlock {
     pub fn new(block_hash: BlockHash) -> Self {
-        Self { block_hash }
+        Self {
+            block_hash,
+            compact_block: None,
+        }
     }
 }
 
@@ -259,6 +265,7 @@ impl From<&Block> for NewBlock {
     fn from(block: &Block) -> Self {
         Self {
             block_hash: block.hash(),
+            compact_block: Some(CompactBlock::from_block(block)),
         }
     }
 }
@@ -0,0 +1,245 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    blocks::{Block, BlockHeader},
+    transactions::{
+        aggregated_body::AggregateBody,
+        transaction::{KernelFeatures, OutputFlags, Transaction, TransactionKernel, TransactionOutput},
+        types::{HashDigest, Signature},
+    },
+};
+use digest::Digest;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    sync::Arc,
+};
+use tari_crypto::tari_utilities::ByteArray;
+use thiserror::Error;
+
+/// A short identifier for a kernel, used in place of the full transaction when relaying compact blocks
+pub type KernelShortId = u64;
+
+/// Returns the short id of the kernel with the given excess signature. This is the first 8 bytes of the hash of the
+/// signature, which is enough to identify a kernel in the mempool without sending the full transaction.
+pub fn kernel_short_id(excess_sig: &Signature) -> KernelShortId {
+    let hash = HashDigest::new()
+        .chain(excess_sig.get_public_nonce().as_bytes())
+        .chain(excess_sig.get_signature().as_bytes())
+        .finalize();
+    u64::from_le_bytes(hash[..8].try_into().expect("hash is at least 8 bytes"))
+}
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum CompactBlockError {
+    #[error("{0} transaction(s) are missing from the mempool")]
+    MissingTransactions(usize),
+    #[error("Transaction contains kernel(s) that are not in the compact block")]
+    UnexpectedKernel,
+    #[error("Reconstructed block does not match the compact block: {0}")]
+    Mismatch(String),
+}
+
+/// A block that is relayed as its header, coinbase and the short ids of its kernels. The receiving node rebuilds
+/// the full block from the transactions in its mempool, only requesting the transactions that it does not have.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactBlock {
+    pub header: BlockHeader,
+    pub coinbase_outputs: Vec<TransactionOutput>,
+    pub coinbase_kernels: Vec<TransactionKernel>,
+    pub kernel_short_ids: Vec<KernelShortId>,
+}
+
+impl CompactBlock {
+    pub fn from_block(block: &Block) -> Self {
+        let coinbase_outputs = block
+            .body
+            .outputs()
+            .iter()
+            .filter(|o| o.features.flags.contains(OutputFlags::COINBASE_OUTPUT))
+            .cloned()
+            .collect();
+        let (coinbase_kernels, kernels) = block
+            .body
+            .kernels()
+            .iter()
+            .partition::<Vec<_>, _>(|k| k.features.contains(KernelFeatures::COINBASE_KERNEL));
+        Self {
+            header: block.header.clone(),
+            coinbase_outputs,
+            coinbase_kernels: coinbase_kernels.into_iter().cloned().collect(),
+            kernel_short_ids: kernels.iter().map(|k| kernel_short_id(&k.excess_sig)).collect(),
+        }
+    }
+
+    /// The number of transaction kernels (including coinbase kernels) in the block
+    pub fn num_kernels(&self) -> usize {
+        self.coinbase_kernels.len() + self.kernel_short_ids.len()
+    }
+
+    /// Returns the short ids that are not matched by any kernel in the given transactions
+    pub fn missing_short_ids(&self, transactions: &[Arc<Transaction>]) -> Vec<KernelShortId> {
+        let known = transactions
+            .iter()
+            .flat_map(|tx| tx.body.kernels().iter().map(|k| kernel_short_id(&k.excess_sig)))
+            .collect::<HashSet<_>>();
+        self.kernel_short_ids
+            .iter()
+            .filter(|id| !known.contains(id))
+            .copied()
+            .collect()
+    }
+
+    /// Rebuilds the full block from the coinbase and the given transactions. Transactions that have no kernels in the
+    /// block are ignored, so the full set of candidate transactions from the mempool can be passed in. Every kernel of
+    /// a matched transaction must be in the block.
+    pub fn reconstruct(&self, transactions: &[Arc<Transaction>]) -> Result<Block, CompactBlockError> {
+        let short_ids = self.kernel_short_ids.iter().copied().collect::<HashSet<_>>();
+        if short_ids.len() != self.kernel_short_ids.len() {
+            return Err(CompactBlockError::Mismatch("duplicate kernel short ids".to_string()));
+        }
+
+        let mut matched = HashMap::new();
+        for tx in transactions {
+            let tx_short_ids = tx
+                .body
+                .kernels()
+                .iter()
+                .map(|k| kernel_short_id(&k.excess_sig))
+                .collect::<Vec<_>>();
+            if !tx_short_ids.iter().any(|id| short_ids.contains(id)) {
+                continue;
+            }
+            if !tx_short_ids.iter().all(|id| short_ids.contains(id)) {
+                return Err(CompactBlockError::UnexpectedKernel);
+            }
+            for id in tx_short_ids {
+                matched.insert(id, tx.clone());
+            }
+        }
+
+        let num_missing = short_ids.iter().filter(|id| !matched.contains_key(id)).count();
+        if num_missing > 0 {
+            return Err(CompactBlockError::MissingTransactions(num_missing));
+        }
+
+        let mut body = AggregateBody::new(vec![], self.coinbase_outputs.clone(), self.coinbase_kernels.clone());
+        let mut added = HashSet::new();
+        for tx in matched.values() {
+            let key = tx.body.kernels()[0].excess_sig.clone();
+            if !added.insert(key) {
+                continue;
+            }
+            body.add_inputs(&mut tx.body.inputs().clone());
+            body.add_outputs(&mut tx.body.outputs().clone());
+            body.add_kernels(&mut tx.body.kernels().clone());
+        }
+        body.sort();
+
+        if body.kernels().len() != self.num_kernels() {
+            return Err(CompactBlockError::Mismatch(format!(
+                "expected {} kernels but reconstructed {}",
+                self.num_kernels(),
+                body.kernels().len()
+            )));
+        }
+
+        Ok(Block::new(self.header.clone(), body))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        blocks::BlockBuilder,
+        transactions::{
+            helpers::{create_test_kernel, create_utxo},
+            tari_amount::{uT, MicroTari},
+            transaction::{KernelBuilder, OutputFeatures},
+            types::CryptoFactories,
+        },
+        tx,
+    };
+    use tari_crypto::script::TariScript;
+
+    fn create_block(txs: Vec<Transaction>) -> Block {
+        let factories = CryptoFactories::default();
+        let (coinbase, _, _) = create_utxo(
+            MicroTari(5000),
+            &factories,
+            Some(OutputFeatures::create_coinbase(10)),
+            &TariScript::default(),
+        );
+        let kernel = create_test_kernel(0.into(), 0);
+        let coinbase_kernel = KernelBuilder::new()
+            .with_features(KernelFeatures::COINBASE_KERNEL)
+            .with_excess(&kernel.excess)
+            .with_signature(&kernel.excess_sig)
+            .build()
+            .unwrap();
+        BlockBuilder::new(1)
+            .with_transactions(txs)
+            .with_coinbase_utxo(coinbase, coinbase_kernel)
+            .build()
+    }
+
+    #[test]
+    fn it_reconstructs_a_block() {
+        let (tx1, _, _) = tx!(MicroTari(10_000), fee: 20*uT, inputs: 2, outputs: 2);
+        let (tx2, _, _) = tx!(MicroTari(10_000), fee: 20*uT, inputs: 1, outputs: 1);
+        let (tx3, _, _) = tx!(MicroTari(10_000), fee: 20*uT, inputs: 1, outputs: 1);
+        let block = create_block(vec![tx1.clone(), tx2.clone()]);
+        let compact = CompactBlock::from_block(&block);
+        assert_eq!(compact.coinbase_outputs.len(), 1);
+        assert_eq!(compact.coinbase_kernels.len(), 1);
+        assert_eq!(compact.kernel_short_ids.len(), 2);
+
+        let mempool = vec![Arc::new(tx3), Arc::new(tx2.clone())];
+        assert_eq!(compact.missing_short_ids(&mempool), vec![kernel_short_id(
+            &tx1.body.kernels()[0].excess_sig
+        )]);
+        assert_eq!(
+            compact.reconstruct(&mempool).unwrap_err(),
+            CompactBlockError::MissingTransactions(1)
+        );
+
+        let mempool = vec![Arc::new(tx2), Arc::new(tx1)];
+        assert!(compact.missing_short_ids(&mempool).is_empty());
+        let reconstructed = compact.reconstruct(&mempool).unwrap();
+        assert_eq!(reconstructed, block);
+    }
+
+    #[test]
+    fn it_rejects_transactions_with_extra_kernels() {
+        let (tx1, _, _) = tx!(MicroTari(10_000), fee: 20*uT, inputs: 1, outputs: 1);
+        let (mut tx2, _, _) = tx!(MicroTari(10_000), fee: 20*uT, inputs: 1, outputs: 1);
+        let block = create_block(vec![tx1.clone()]);
+        let compact = CompactBlock::from_block(&block);
+
+        tx2.body.add_kernel(tx1.body.kernels()[0].clone());
+        let err = compact.reconstruct(&[Arc::new(tx2)]).unwrap_err();
+        assert_eq!(err, CompactBlockError::UnexpectedKernel);
+    }
+}
@@ -137,6 +137,7 @@ pub fn get_stibbons_genesis_block_raw() -> Block {
             features: OutputFeatures {
                 flags: OutputFlags::COINBASE_OUTPUT,
                 maturity: 60,
+                ..Default::default()
             },
             commitment: Commitment::from_hex(
                 "fadafb12de96d90042dcbf839985aadb7ae88baa3446d5c6a17937ef2b36783e",
@@ -149,6 +150,7 @@ pub fn get_stibbons_genesis_block_raw() -> Block {
             sender_offset_public_key: Default::default(),
             // For genesis block: Metadata signature will never be checked
             metadata_signature: Default::default(),
+            covenant: Default::default(),
         }],
         vec![TransactionKernel {
             features: KernelFeatures::COINBASE_KERNEL,
@@ -204,6 +206,7 @@ pub fn get_weatherwax_genesis_block_raw() -> Block {
             features: OutputFeatures {
                 flags: OutputFlags::COINBASE_OUTPUT,
                 maturity: 60,
+                ..Default::default()
             },
             commitment: Commitment::from_hex(
                 "fadafb12de96d90042dcbf839985aadb7ae88baa3446d5c6a17937ef2b36783e",
@@ -216,6 +219,7 @@ pub fn get_weatherwax_genesis_block_raw() -> Block {
             sender_offset_public_key: Default::default(),
             // For genesis block: Metadata signature will never be checked
             metadata_signature: Default::default(),
+            covenant: Default::default(),
         }],
         vec![TransactionKernel {
             features: KernelFeatures::COINBASE_KERNEL,
@@ -314,6 +318,7 @@ pub fn get_ridcully_genesis_block_raw() -> Block {
             features: OutputFeatures {
                 flags: OutputFlags::COINBASE_OUTPUT,
                 maturity: 60,
+                ..Default::default()
             },
             commitment: Commitment::from_hex(
                 "fadafb12de96d90042dcbf839985aadb7ae88baa3446d5c6a17937ef2b36783e",
@@ -325,7 +330,8 @@ pub fn get_ridcully_genesis_block_raw() -> Block {
             // Script offset never checked for coinbase, thus can use default
             sender_offset_public_key: Default::default(),
             // For genesis block: Metadata signature will never be checked
-            metadata_signature: Default::default()
+            metadata_signature: Default::default(),
+            covenant: Default::default(),
         }],
         vec![TransactionKernel {
             features: KernelFeatures::COINBASE_KERNEL,
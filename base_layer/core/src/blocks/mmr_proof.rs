@@ -0,0 +1,97 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Verification of header-anchored MMR proofs. These functions let a client that only trusts block headers, such as a
+//! light client or a bridge, check proofs of output and kernel inclusion provided by an untrusted base node.
+
+use crate::{blocks::BlockHeader, transactions::types::HashDigest};
+use croaring::Bitmap;
+use digest::Digest;
+use tari_crypto::tari_utilities::hex::to_hex;
+use tari_mmr::{MerkleProof, MerkleProofError};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MmrProofError {
+    #[error("The output MMR root and deleted bitmap are not committed to by header #{0}")]
+    OutputMmrCommitmentMismatch(u64),
+    #[error("The deleted bitmap could not be deserialized")]
+    InvalidDeletedBitmap,
+    #[error("Output {0} is spent")]
+    OutputSpent(String),
+    #[error("Invalid Merkle proof for {hash}: {source}")]
+    InvalidMerkleProof { hash: String, source: MerkleProofError },
+}
+
+/// Checks that `output_mmr_root` and `deleted_bitmap` are the values that `header.output_mr` commits to, and returns
+/// the deserialized deleted bitmap
+pub fn verify_output_mmr_commitment(
+    header: &BlockHeader,
+    output_mmr_root: &[u8],
+    deleted_bitmap: &[u8],
+) -> Result<Bitmap, MmrProofError> {
+    let output_mr = HashDigest::new()
+        .chain(output_mmr_root)
+        .chain(deleted_bitmap)
+        .finalize()
+        .to_vec();
+    if output_mr != header.output_mr {
+        return Err(MmrProofError::OutputMmrCommitmentMismatch(header.height));
+    }
+    Bitmap::try_deserialize(deleted_bitmap).ok_or(MmrProofError::InvalidDeletedBitmap)
+}
+
+/// Checks that the output with the given hash is at `mmr_position` in the output MMR with root `output_mmr_root`, and
+/// that it is not spent. `output_mmr_root` and `deleted` must have been checked against a trusted header with
+/// [verify_output_mmr_commitment].
+pub fn verify_utxo_proof(
+    output_mmr_root: &[u8],
+    deleted: &Bitmap,
+    output_hash: &[u8],
+    mmr_position: u32,
+    merkle_proof: &MerkleProof,
+) -> Result<(), MmrProofError> {
+    if deleted.contains(mmr_position) {
+        return Err(MmrProofError::OutputSpent(to_hex(output_hash)));
+    }
+    merkle_proof
+        .verify_leaf::<HashDigest>(output_mmr_root, output_hash, mmr_position as usize)
+        .map_err(|source| MmrProofError::InvalidMerkleProof {
+            hash: to_hex(output_hash),
+            source,
+        })
+}
+
+/// Checks that the kernel with the given hash is at `mmr_position` in the kernel MMR of `header`
+pub fn verify_kernel_proof(
+    header: &BlockHeader,
+    kernel_hash: &[u8],
+    mmr_position: u32,
+    merkle_proof: &MerkleProof,
+) -> Result<(), MmrProofError> {
+    merkle_proof
+        .verify_leaf::<HashDigest>(&header.kernel_mr, kernel_hash, mmr_position as usize)
+        .map_err(|source| MmrProofError::InvalidMerkleProof {
+            hash: to_hex(kernel_hash),
+            source,
+        })
+}
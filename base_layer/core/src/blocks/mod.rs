@@ -25,6 +25,11 @@ mod block;
 #[cfg(any(feature = "base_node", feature = "base_node_proto"))]
 pub mod block_header;
 
+#[cfg(feature = "base_node")]
+mod compact_block;
+#[cfg(feature = "base_node")]
+pub use compact_block::{kernel_short_id, CompactBlock, CompactBlockError, KernelShortId};
+
 #[cfg(feature = "base_node")]
 pub mod genesis_block;
 #[cfg(feature = "base_node")]
@@ -39,3 +44,8 @@ pub use block_header::{BlockHeader, BlockHeaderValidationError};
 pub use new_block_template::NewBlockTemplate;
 #[cfg(feature = "base_node")]
 pub use new_blockheader_template::NewBlockHeaderTemplate;
+
+#[cfg(all(feature = "tari_mmr", feature = "croaring"))]
+mod mmr_proof;
+#[cfg(all(feature = "tari_mmr", feature = "croaring"))]
+pub use mmr_proof::{verify_kernel_proof, verify_output_mmr_commitment, verify_utxo_proof, MmrProofError};
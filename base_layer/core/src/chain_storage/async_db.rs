@@ -24,27 +24,35 @@ use crate::{
     blocks::{Block, BlockHeader, NewBlockTemplate},
     chain_storage::{
         accumulated_data::BlockHeaderAccumulatedData,
+        AssetOutput,
         BlockAccumulatedData,
         BlockAddResult,
         BlockchainBackend,
         BlockchainDatabase,
+        BlockchainSnapshot,
         ChainBlock,
         ChainHeader,
         ChainStorageError,
+        CompactionResult,
         CompleteDeletedBitmap,
         DbTransaction,
+        ExistenceFilterStats,
         HistoricalBlock,
         HorizonData,
+        KernelMmrProofs,
+        LMDBDatabase,
         MmrTree,
         PrunedOutput,
+        PruningStats,
         TargetDifficulties,
+        UtxoMmrProofs,
     },
     common::rolling_vec::RollingVec,
-    proof_of_work::{PowAlgorithm, TargetDifficultyWindow},
+    proof_of_work::{Difficulty, PowAlgorithm, TargetDifficultyWindow},
     tari_utilities::epoch_time::EpochTime,
     transactions::{
-        transaction::{TransactionKernel, TransactionOutput},
-        types::{Commitment, HashOutput, Signature},
+        transaction::{OutputFlags, TransactionKernel, TransactionOutput},
+        types::{Commitment, HashOutput, PublicKey, Signature},
     },
 };
 use croaring::Bitmap;
@@ -53,6 +61,7 @@ use rand::{rngs::OsRng, RngCore};
 use std::{mem, ops::RangeBounds, sync::Arc, time::Instant};
 use tari_common_types::{chain_metadata::ChainMetadata, types::BlockHash};
 use tari_mmr::pruned_hashset::PrunedHashSet;
+use tari_storage::lmdb_store::LMDBStoreStats;
 
 const LOG_TARGET: &str = "c::bn::async_db";
 
@@ -133,6 +142,10 @@ impl<B: BlockchainBackend + 'static> AsyncBlockchainDb<B> {
 impl<B: BlockchainBackend + 'static> AsyncBlockchainDb<B> {
     make_async_fn!(write(transaction: DbTransaction) -> (), "write");
 
+    make_async_fn!(queue_write(transaction: DbTransaction) -> (), "queue_write");
+
+    make_async_fn!(flush_write_queue() -> (), "flush_write_queue");
+
     //---------------------------------- Metadata --------------------------------------------//
     make_async_fn!(get_chain_metadata() -> ChainMetadata, "get_chain_metadata");
 
@@ -143,6 +156,20 @@ impl<B: BlockchainBackend + 'static> AsyncBlockchainDb<B> {
 
     make_async_fn!(fetch_utxos(hashes: Vec<HashOutput>) -> Vec<Option<(TransactionOutput, bool)>>, "fetch_utxos");
 
+    make_async_fn!(fetch_utxos_by_script_hash(script_hash: Vec<u8>, start_mmr_position: u32, limit: usize) -> Vec<(TransactionOutput, u32)>, "fetch_utxos_by_script_hash");
+
+    make_async_fn!(fetch_utxos_by_features(flags: OutputFlags, start_mmr_position: u32, limit: usize) -> Vec<(TransactionOutput, u32)>, "fetch_utxos_by_features");
+
+    make_async_fn!(fetch_asset_registrations(start_mmr_position: u32, limit: usize) -> Vec<AssetOutput>, "fetch_asset_registrations");
+
+    make_async_fn!(fetch_asset_tokens(asset_public_key: PublicKey, start_mmr_position: u32, limit: usize) -> (Option<AssetOutput>, Vec<AssetOutput>), "fetch_asset_tokens");
+
+    make_async_fn!(fetch_unspent_outputs_by_commitment(commitments: Vec<Commitment>) -> (Vec<Option<TransactionOutput>>, u64), "fetch_unspent_outputs_by_commitment");
+
+    make_async_fn!(fetch_utxo_mmr_proofs(hashes: Vec<HashOutput>) -> UtxoMmrProofs, "fetch_utxo_mmr_proofs");
+
+    make_async_fn!(fetch_kernel_mmr_proofs(hashes: Vec<HashOutput>) -> KernelMmrProofs, "fetch_kernel_mmr_proofs");
+
     make_async_fn!(fetch_utxos_by_mmr_position(start: u64, end: u64, deleted: Arc<Bitmap>) -> (Vec<PrunedOutput>, Bitmap), "fetch_utxos_by_mmr_position");
 
     //---------------------------------- Kernel --------------------------------------------//
@@ -159,6 +186,12 @@ impl<B: BlockchainBackend + 'static> AsyncBlockchainDb<B> {
 
     make_async_fn!(rewind_to_hash(hash: BlockHash) -> Vec<Arc<ChainBlock>>, "rewind_to_hash");
 
+    make_async_fn!(invalidate_block(hash: BlockHash) -> Vec<Arc<ChainBlock>>, "invalidate_block");
+
+    make_async_fn!(bad_block_exists(hash: BlockHash) -> bool, "bad_block_exists");
+
+    make_async_fn!(rebuild_secondary_indexes() -> (), "rebuild_secondary_indexes");
+
     //---------------------------------- Headers --------------------------------------------//
     make_async_fn!(fetch_header(height: u64) -> Option<BlockHeader>, "fetch_header");
 
@@ -188,6 +221,8 @@ impl<B: BlockchainBackend + 'static> AsyncBlockchainDb<B> {
 
     make_async_fn!(fetch_tip_header() -> ChainHeader, "fetch_tip_header");
 
+    make_async_fn!(open_snapshot() -> BlockchainSnapshot, "open_snapshot");
+
     make_async_fn!(insert_valid_headers(headers: Vec<ChainHeader>) -> (), "insert_valid_headers");
 
     //---------------------------------- Block --------------------------------------------//
@@ -228,6 +263,18 @@ impl<B: BlockchainBackend + 'static> AsyncBlockchainDb<B> {
     make_async_fn!(fetch_complete_deleted_bitmap_at(hash: HashOutput) -> CompleteDeletedBitmap, "fetch_deleted_bitmap");
 }
 
+impl AsyncBlockchainDb<LMDBDatabase> {
+    make_async_fn!(get_storage_stats() -> LMDBStoreStats, "get_storage_stats");
+
+    make_async_fn!(compact() -> CompactionResult, "compact");
+
+    make_async_fn!(get_existence_filter_stats() -> Vec<ExistenceFilterStats>, "get_existence_filter_stats");
+
+    make_async_fn!(reclaim_pruned_space() -> PruningStats, "reclaim_pruned_space");
+
+    make_async_fn!(get_pruning_stats() -> PruningStats, "get_pruning_stats");
+}
+
 impl<B: BlockchainBackend + 'static> From<BlockchainDatabase<B>> for AsyncBlockchainDb<B> {
     fn from(db: BlockchainDatabase<B>) -> Self {
         Self::new(db)
@@ -253,8 +300,41 @@ impl<'a, B: BlockchainBackend + 'static> AsyncDbTransaction<'a, B> {
         }
     }
 
-    pub fn set_best_block(&mut self, height: u64, hash: HashOutput, accumulated_data: u128) -> &mut Self {
-        self.transaction.set_best_block(height, hash, accumulated_data);
+    pub fn set_best_block(
+        &mut self,
+        height: u64,
+        hash: HashOutput,
+        accumulated_difficulty: u128,
+        accumulated_monero_difficulty: Difficulty,
+        accumulated_sha_difficulty: Difficulty,
+    ) -> &mut Self {
+        self.transaction.set_best_block(
+            height,
+            hash,
+            accumulated_difficulty,
+            accumulated_monero_difficulty,
+            accumulated_sha_difficulty,
+        );
+        self
+    }
+
+    pub fn set_best_block_if_equal(
+        &mut self,
+        expected_hash: HashOutput,
+        height: u64,
+        hash: HashOutput,
+        accumulated_difficulty: u128,
+        accumulated_monero_difficulty: Difficulty,
+        accumulated_sha_difficulty: Difficulty,
+    ) -> &mut Self {
+        self.transaction.set_best_block_if_equal(
+            expected_hash,
+            height,
+            hash,
+            accumulated_difficulty,
+            accumulated_monero_difficulty,
+            accumulated_sha_difficulty,
+        );
         self
     }
 
@@ -338,4 +418,11 @@ impl<'a, B: BlockchainBackend + 'static> AsyncDbTransaction<'a, B> {
         let transaction = mem::take(&mut self.transaction);
         self.db.write(transaction).await
     }
+
+    /// Adds the transaction to the write queue, to be committed together with other queued transactions. See
+    /// [BlockchainDatabase::queue_write].
+    pub async fn queue(&mut self) -> Result<(), ChainStorageError> {
+        let transaction = mem::take(&mut self.transaction);
+        self.db.queue_write(transaction).await
+    }
 }
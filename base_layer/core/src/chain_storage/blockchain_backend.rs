@@ -3,10 +3,12 @@ use crate::{
     chain_storage::{
         accumulated_data::DeletedBitmap,
         pruned_output::PrunedOutput,
+        AssetOutput,
         BlockAccumulatedData,
         BlockHeaderAccumulatedData,
         ChainBlock,
         ChainHeader,
+        ChainReadSnapshot,
         ChainStorageError,
         DbKey,
         DbTransaction,
@@ -15,8 +17,8 @@ use crate::{
         MmrTree,
     },
     transactions::{
-        transaction::{TransactionInput, TransactionKernel, TransactionOutput},
-        types::{HashOutput, Signature},
+        transaction::{OutputFlags, TransactionInput, TransactionKernel, TransactionOutput, UniqueAssetId},
+        types::{Commitment, HashOutput, PublicKey, Signature},
     },
 };
 use croaring::Bitmap;
@@ -102,6 +104,68 @@ pub trait BlockchainBackend: Send + Sync {
         deleted: &Bitmap,
     ) -> Result<(Vec<PrunedOutput>, Bitmap), ChainStorageError>;
 
+    /// Fetch up to `limit` unspent outputs with the given script hash, starting at `start_mmr_position` (inclusive).
+    /// Returns each output with its leaf index in the output MMR. Pruned outputs are not returned.
+    fn fetch_utxos_by_script_hash(
+        &self,
+        script_hash: &[u8],
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError>;
+
+    /// Fetch up to `limit` unspent outputs with exactly the given (non-empty) output feature flags, starting at
+    /// `start_mmr_position` (inclusive). Returns each output with its leaf index in the output MMR.
+    fn fetch_utxos_by_features(
+        &self,
+        flags: OutputFlags,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError>;
+
+    /// Fetch the unspent output with the given commitment, if any. Returns the output and its leaf index in the output
+    /// MMR.
+    fn fetch_unspent_output_by_commitment(
+        &self,
+        commitment: &Commitment,
+        deleted: &Bitmap,
+    ) -> Result<Option<(TransactionOutput, u32)>, ChainStorageError>;
+
+    /// Fetch the unspent output that carries the given unique asset token, if any. Returns the output and its leaf
+    /// index in the output MMR.
+    fn fetch_unspent_output_by_unique_id(
+        &self,
+        id: &UniqueAssetId,
+        deleted: &Bitmap,
+    ) -> Result<Option<(TransactionOutput, u32)>, ChainStorageError>;
+
+    /// Fetch up to `limit` unspent asset registrations, starting at `start_mmr_position` (inclusive). Registrations are
+    /// outputs with exactly the `ASSET_REGISTRATION` output flag.
+    fn fetch_asset_registrations(
+        &self,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<AssetOutput>, ChainStorageError>;
+
+    /// Fetch the unspent output that registers the asset with the given public key, if any
+    fn fetch_asset_registration(
+        &self,
+        asset_public_key: &PublicKey,
+        deleted: &Bitmap,
+    ) -> Result<Option<AssetOutput>, ChainStorageError>;
+
+    /// Fetch up to `limit` unspent tokens issued by the asset with the given public key, starting at
+    /// `start_mmr_position` (inclusive)
+    fn fetch_asset_tokens(
+        &self,
+        asset_public_key: &PublicKey,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<AssetOutput>, ChainStorageError>;
+
     /// Fetch a specific output. Returns the output and the leaf index in the output MMR
     fn fetch_output(
         &self,
@@ -154,5 +218,11 @@ pub trait BlockchainBackend: Send + Sync {
     /// This gets the monero seed_height. This will return 0, if the seed is unkown
     fn fetch_monero_seed_first_seen_height(&self, seed: &[u8]) -> Result<u64, ChainStorageError>;
 
+    /// Returns true if the block hash was previously marked as invalid
+    fn bad_block_exists(&self, block_hash: HashOutput) -> Result<bool, ChainStorageError>;
+
     fn fetch_horizon_data(&self) -> Result<Option<HorizonData>, ChainStorageError>;
+
+    /// Opens a read-only view of the chain that is unaffected by writes made after it was opened
+    fn open_read_snapshot(&self) -> Result<Box<dyn ChainReadSnapshot>, ChainStorageError>;
 }
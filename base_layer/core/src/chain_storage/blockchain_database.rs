@@ -19,54 +19,71 @@
 // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+#[cfg(feature = "metrics")]
+use crate::chain_storage::metrics;
 use crate::{
     blocks::{Block, BlockHeader, NewBlockTemplate},
     chain_storage::{
         accumulated_data::{BlockAccumulatedData, BlockHeaderAccumulatedData, CompleteDeletedBitmap},
         consts::{
+            BLOCKCHAIN_DATABASE_GROUP_COMMIT_INTERVAL,
+            BLOCKCHAIN_DATABASE_GROUP_COMMIT_MAX_OPERATIONS,
             BLOCKCHAIN_DATABASE_ORPHAN_STORAGE_CAPACITY,
             BLOCKCHAIN_DATABASE_PRUNED_MODE_PRUNING_INTERVAL,
             BLOCKCHAIN_DATABASE_PRUNING_HORIZON,
+            BLOCKCHAIN_DATABASE_SNAPSHOT_MAX_AGE,
         },
         db_transaction::{DbKey, DbTransaction, DbValue},
         error::ChainStorageError,
         pruned_output::PrunedOutput,
+        AssetOutput,
         BlockAddResult,
         BlockchainBackend,
+        BlockchainSnapshot,
         ChainBlock,
         ChainHeader,
+        CompactionResult,
+        ExistenceFilterStats,
         HistoricalBlock,
         HorizonData,
+        KernelMmrProof,
+        KernelMmrProofs,
+        LMDBDatabase,
         MmrTree,
         Optional,
         OrNotFound,
+        PruningStats,
         TargetDifficulties,
+        UtxoMmrProof,
+        UtxoMmrProofs,
     },
     common::rolling_vec::RollingVec,
     consensus::{chain_strength_comparer::ChainStrengthComparer, ConsensusConstants, ConsensusManager},
     proof_of_work::{monero_rx::MoneroPowData, PowAlgorithm, TargetDifficultyWindow},
     tari_utilities::epoch_time::EpochTime,
     transactions::{
-        transaction::{TransactionKernel, TransactionOutput},
-        types::{Commitment, HashDigest, HashOutput, Signature},
+        transaction::{OutputFlags, TransactionInput, TransactionKernel, TransactionOutput},
+        types::{Commitment, HashDigest, HashOutput, PublicKey, Signature},
     },
     validation::{DifficultyCalculator, HeaderValidation, OrphanValidation, PostOrphanBodyValidation, ValidationError},
 };
 use croaring::Bitmap;
+use digest::Digest;
 use log::*;
 use std::{
     cmp,
     cmp::Ordering,
-    collections::VecDeque,
+    collections::{HashSet, VecDeque},
     convert::TryFrom,
     mem,
     ops::Bound,
-    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
-    time::Instant,
+    sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, Instant},
 };
 use tari_common_types::{chain_metadata::ChainMetadata, types::BlockHash};
 use tari_crypto::tari_utilities::{hex::Hex, ByteArray, Hashable};
-use tari_mmr::{MerkleMountainRange, MutableMmr};
+use tari_mmr::{pruned_hashset::PrunedHashSet, MerkleMountainRange, MerkleProof, MutableMmr};
+use tari_storage::lmdb_store::LMDBStoreStats;
 use uint::static_assertions::_core::ops::RangeBounds;
 
 const LOG_TARGET: &str = "c::cs::database";
@@ -77,6 +94,10 @@ pub struct BlockchainDatabaseConfig {
     pub orphan_storage_capacity: usize,
     pub pruning_horizon: u64,
     pub pruning_interval: u64,
+    /// The number of queued write operations at which the write queue is committed
+    pub group_commit_max_operations: usize,
+    /// The maximum time a write is held in the write queue before the queue is committed
+    pub group_commit_interval: Duration,
 }
 
 impl Default for BlockchainDatabaseConfig {
@@ -85,10 +106,33 @@ impl Default for BlockchainDatabaseConfig {
             orphan_storage_capacity: BLOCKCHAIN_DATABASE_ORPHAN_STORAGE_CAPACITY,
             pruning_horizon: BLOCKCHAIN_DATABASE_PRUNING_HORIZON,
             pruning_interval: BLOCKCHAIN_DATABASE_PRUNED_MODE_PRUNING_INTERVAL,
+            group_commit_max_operations: BLOCKCHAIN_DATABASE_GROUP_COMMIT_MAX_OPERATIONS,
+            group_commit_interval: BLOCKCHAIN_DATABASE_GROUP_COMMIT_INTERVAL,
         }
     }
 }
 
+/// Writes that have been queued with [BlockchainDatabase::queue_write] and not yet committed to the backend
+#[derive(Default)]
+struct WriteQueue {
+    pending: DbTransaction,
+    num_transactions: usize,
+    oldest: Option<Instant>,
+}
+
+impl WriteQueue {
+    fn is_empty(&self) -> bool {
+        self.pending.operations().is_empty()
+    }
+
+    fn is_due(&self, config: &BlockchainDatabaseConfig) -> bool {
+        self.pending.operations().len() >= config.group_commit_max_operations ||
+            self.oldest
+                .map(|t| t.elapsed() >= config.group_commit_interval)
+                .unwrap_or(false)
+    }
+}
+
 /// A placeholder struct that contains the two validators that the database uses to decide whether or not a block is
 /// eligible to be added to the database. The `block` validator should perform a full consensus check. The `orphan`
 /// validator needs to check that the block is internally consistent, but can't know whether the PoW is sufficient,
@@ -162,6 +206,7 @@ macro_rules! try_fetch {
 /// provide it with the backend it is going to use; for example, for a memory-backed DB:
 pub struct BlockchainDatabase<B> {
     db: Arc<RwLock<B>>,
+    write_queue: Arc<Mutex<WriteQueue>>,
     validators: Validators<B>,
     config: BlockchainDatabaseConfig,
     consensus_manager: ConsensusManager,
@@ -185,6 +230,7 @@ where B: BlockchainBackend
         let is_empty = db.is_empty()?;
         let blockchain_db = BlockchainDatabase {
             db: Arc::new(RwLock::new(db)),
+            write_queue: Default::default(),
             validators,
             config,
             consensus_manager,
@@ -227,6 +273,7 @@ where B: BlockchainBackend
     // Be careful about making this method public. Rather use `db_and_metadata_read_access`
     // so that metadata and db are read in the correct order so that deadlocks don't occur
     pub fn db_read_access(&self) -> Result<RwLockReadGuard<B>, ChainStorageError> {
+        self.flush_write_queue()?;
         self.db.read().map_err(|e| {
             error!(
                 target: LOG_TARGET,
@@ -248,6 +295,7 @@ where B: BlockchainBackend
     }
 
     fn db_write_access(&self) -> Result<RwLockWriteGuard<B>, ChainStorageError> {
+        self.flush_write_queue()?;
         self.db.write().map_err(|e| {
             error!(
                 target: LOG_TARGET,
@@ -262,6 +310,72 @@ where B: BlockchainBackend
         db.write(transaction)
     }
 
+    /// Adds the transaction to the write queue. Queued transactions are committed to the backend together in a
+    /// single backend transaction (group commit) once `group_commit_max_operations` operations are queued or the
+    /// oldest has been queued for `group_commit_interval`. This reduces the number of commits, and so disk syncs,
+    /// when many small transactions are written.
+    ///
+    /// The queue is also committed before any other read or write, so queued writes are always visible to later
+    /// reads and are applied in the order they were made. As the queue is committed atomically, a crash loses either
+    /// all or none of the queued transactions, never part of one. An error committing the queue is returned to the
+    /// call that triggered the commit, and the queued transactions are discarded.
+    pub fn queue_write(&self, transaction: DbTransaction) -> Result<(), ChainStorageError> {
+        let mut queue = self.write_queue_access()?;
+        queue.pending.append(transaction);
+        queue.num_transactions += 1;
+        if queue.oldest.is_none() {
+            queue.oldest = Some(Instant::now());
+        }
+        if queue.is_due(&self.config) {
+            self.commit_write_queue(&mut queue)?;
+        }
+        Ok(())
+    }
+
+    /// Commits any queued writes to the backend
+    pub fn flush_write_queue(&self) -> Result<(), ChainStorageError> {
+        let mut queue = self.write_queue_access()?;
+        if queue.is_empty() {
+            return Ok(());
+        }
+        self.commit_write_queue(&mut queue)
+    }
+
+    fn write_queue_access(&self) -> Result<MutexGuard<'_, WriteQueue>, ChainStorageError> {
+        self.write_queue.lock().map_err(|e| {
+            error!(target: LOG_TARGET, "An attempt to lock the write queue failed. {:?}", e);
+            ChainStorageError::AccessError("Lock on write queue failed".into())
+        })
+    }
+
+    /// Commits the write queue. The queue lock is held until the commit completes so that a concurrent flush cannot
+    /// overtake it.
+    fn commit_write_queue(&self, queue: &mut WriteQueue) -> Result<(), ChainStorageError> {
+        let WriteQueue {
+            pending,
+            num_transactions,
+            ..
+        } = mem::take(queue);
+        let timer = Instant::now();
+        let num_operations = pending.operations().len();
+        let mut db = self.db.write().map_err(|e| {
+            error!(
+                target: LOG_TARGET,
+                "An attempt to get a write lock on the blockchain backend failed. {:?}", e
+            );
+            ChainStorageError::AccessError("Write lock on blockchain backend failed".into())
+        })?;
+        db.write(pending)?;
+        debug!(
+            target: LOG_TARGET,
+            "Committed {} queued transaction(s) ({} operation(s)) in {:.2?}",
+            num_transactions,
+            num_operations,
+            timer.elapsed()
+        );
+        Ok(())
+    }
+
     /// Returns the height of the current longest chain. This method will only fail if there's a fairly serious
     /// synchronisation problem on the database. You can try calling [BlockchainDatabase::try_recover_metadata] in
     /// that case to re-sync the metadata; or else just exit the program.
@@ -307,6 +421,180 @@ where B: BlockchainBackend
         Ok(result)
     }
 
+    /// Returns up to `limit` unspent outputs with the given script hash, starting at `start_mmr_position`, along with
+    /// their output MMR positions
+    pub fn fetch_utxos_by_script_hash(
+        &self,
+        script_hash: Vec<u8>,
+        start_mmr_position: u32,
+        limit: usize,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        let deleted = db.fetch_deleted_bitmap()?;
+        db.fetch_utxos_by_script_hash(&script_hash, start_mmr_position, limit, deleted.bitmap())
+    }
+
+    /// Returns up to `limit` unspent outputs with exactly the given output feature flags, starting at
+    /// `start_mmr_position`, along with their output MMR positions
+    pub fn fetch_utxos_by_features(
+        &self,
+        flags: OutputFlags,
+        start_mmr_position: u32,
+        limit: usize,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        let deleted = db.fetch_deleted_bitmap()?;
+        db.fetch_utxos_by_features(flags, start_mmr_position, limit, deleted.bitmap())
+    }
+
+    /// Returns up to `limit` unspent asset registrations, starting at `start_mmr_position`
+    pub fn fetch_asset_registrations(
+        &self,
+        start_mmr_position: u32,
+        limit: usize,
+    ) -> Result<Vec<AssetOutput>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        let deleted = db.fetch_deleted_bitmap()?;
+        db.fetch_asset_registrations(start_mmr_position, limit, deleted.bitmap())
+    }
+
+    /// Returns the unspent output that registers the asset with the given public key, if any, along with up to `limit`
+    /// unspent tokens issued by the asset, starting at `start_mmr_position`
+    pub fn fetch_asset_tokens(
+        &self,
+        asset_public_key: PublicKey,
+        start_mmr_position: u32,
+        limit: usize,
+    ) -> Result<(Option<AssetOutput>, Vec<AssetOutput>), ChainStorageError> {
+        let db = self.db_read_access()?;
+        let deleted = db.fetch_deleted_bitmap()?;
+        let registration = db.fetch_asset_registration(&asset_public_key, deleted.bitmap())?;
+        let tokens = db.fetch_asset_tokens(&asset_public_key, start_mmr_position, limit, deleted.bitmap())?;
+        Ok((registration, tokens))
+    }
+
+    /// Returns the unspent outputs with the given commitments, or None for commitments that are spent or unknown, along
+    /// with the height of the tip that they were fetched at
+    pub fn fetch_unspent_outputs_by_commitment(
+        &self,
+        commitments: Vec<Commitment>,
+    ) -> Result<(Vec<Option<TransactionOutput>>, u64), ChainStorageError> {
+        let db = self.db_read_access()?;
+        let deleted = db.fetch_deleted_bitmap()?;
+        let tip_height = db.fetch_chain_metadata()?.height_of_longest_chain();
+        let outputs = commitments
+            .iter()
+            .map(|commitment| {
+                db.fetch_unspent_output_by_commitment(commitment, deleted.bitmap())
+                    .map(|output| output.map(|(output, _)| output))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((outputs, tip_height))
+    }
+
+    /// Returns MMR inclusion proofs for those of the given outputs that are unspent, made against the output MMR of the
+    /// tip block header. Outputs that are spent or not found are omitted.
+    ///
+    /// The output MMR is rebuilt from every output hash to create the proofs, so callers should request proofs for
+    /// many outputs at once rather than one at a time.
+    pub fn fetch_utxo_mmr_proofs(&self, hashes: Vec<HashOutput>) -> Result<UtxoMmrProofs, ChainStorageError> {
+        let db = self.db_read_access()?;
+        let metadata = db.fetch_chain_metadata()?;
+        let header = fetch_header(&*db, metadata.height_of_longest_chain())?;
+        let mut deleted = db.fetch_deleted_bitmap()?.into_bitmap();
+        // The header commits to the optimized form of the bitmap, see `calculate_mmr_roots`
+        deleted.run_optimize();
+
+        let mut output_mmr = MerkleMountainRange::<HashDigest, _>::new(Vec::new());
+        if header.output_mmr_size > 0 {
+            let (outputs, _) = db.fetch_utxos_by_mmr_position(0, header.output_mmr_size - 1, &deleted)?;
+            for output in outputs {
+                let hash = match output {
+                    PrunedOutput::Pruned { output_hash, .. } => output_hash,
+                    PrunedOutput::NotPruned { output } => output.hash(),
+                };
+                output_mmr.push(hash)?;
+            }
+        }
+
+        let output_mmr_root = output_mmr.get_merkle_root()?;
+        let deleted_bitmap = deleted.serialize();
+        let mut output_mr = HashDigest::new();
+        output_mr.update(&output_mmr_root);
+        output_mr.update(&deleted_bitmap);
+        if output_mr.finalize().to_vec() != header.output_mr {
+            return Err(ChainStorageError::MismatchedMmrRoot(MmrTree::Utxo));
+        }
+
+        let mut proofs = Vec::with_capacity(hashes.len());
+        for hash in hashes {
+            // Spent outputs may have been pruned, so the deleted bitmap is checked before the output is fetched. A
+            // proof for a spent output is never served, even if the output has not been pruned yet.
+            match db.fetch_mmr_leaf_index(MmrTree::Utxo, &hash)? {
+                Some(mmr_position) if !deleted.contains(mmr_position) => {},
+                _ => continue,
+            }
+            if let Some((output, mmr_position, _)) = db.fetch_output(&hash)? {
+                let merkle_proof = MerkleProof::for_leaf_node(&output_mmr, mmr_position as usize)?;
+                proofs.push(UtxoMmrProof {
+                    output,
+                    mmr_position,
+                    merkle_proof,
+                });
+            }
+        }
+
+        Ok(UtxoMmrProofs {
+            header,
+            output_mmr_root,
+            deleted_bitmap,
+            proofs,
+        })
+    }
+
+    /// Returns MMR inclusion proofs for those of the given kernels that are in the chain, made against the kernel MMR
+    /// of the tip block header. Kernels that are not found are omitted.
+    ///
+    /// As with [fetch_utxo_mmr_proofs](Self::fetch_utxo_mmr_proofs), the kernel MMR is rebuilt from every kernel to
+    /// find the positions of the kernels and create the proofs.
+    pub fn fetch_kernel_mmr_proofs(&self, hashes: Vec<HashOutput>) -> Result<KernelMmrProofs, ChainStorageError> {
+        let db = self.db_read_access()?;
+        let metadata = db.fetch_chain_metadata()?;
+        let header = fetch_header(&*db, metadata.height_of_longest_chain())?;
+        let requested = hashes.into_iter().collect::<HashSet<_>>();
+
+        let mut kernel_mmr = MerkleMountainRange::<HashDigest, _>::new(Vec::new());
+        let mut found = Vec::with_capacity(requested.len());
+        if header.kernel_mmr_size > 0 {
+            let kernels = db.fetch_kernels_by_mmr_position(0, header.kernel_mmr_size - 1)?;
+            for (mmr_position, kernel) in kernels.into_iter().enumerate() {
+                let hash = kernel.hash();
+                if requested.contains(&hash) {
+                    found.push((kernel, mmr_position as u32));
+                }
+                kernel_mmr.push(hash)?;
+            }
+        }
+
+        if kernel_mmr.get_merkle_root()? != header.kernel_mr {
+            return Err(ChainStorageError::MismatchedMmrRoot(MmrTree::Kernel));
+        }
+
+        let proofs = found
+            .into_iter()
+            .map(|(kernel, mmr_position)| {
+                let merkle_proof = MerkleProof::for_leaf_node(&kernel_mmr, mmr_position as usize)?;
+                Ok(KernelMmrProof {
+                    kernel,
+                    mmr_position,
+                    merkle_proof,
+                })
+            })
+            .collect::<Result<Vec<_>, ChainStorageError>>()?;
+
+        Ok(KernelMmrProofs { header, proofs })
+    }
+
     pub fn fetch_kernel_by_excess(
         &self,
         excess: &[u8],
@@ -531,6 +819,14 @@ where B: BlockchainBackend
         db.fetch_last_header()
     }
 
+    /// Opens a snapshot of the chain for queries that must see a consistent view across many reads. Queued writes are
+    /// committed first so that the snapshot includes them.
+    pub fn open_snapshot(&self) -> Result<BlockchainSnapshot, ChainStorageError> {
+        let db = self.db_read_access()?;
+        let snapshot = db.open_read_snapshot()?;
+        Ok(BlockchainSnapshot::new(snapshot, BLOCKCHAIN_DATABASE_SNAPSHOT_MAX_AGE))
+    }
+
     /// Returns the sum of all kernels
     pub fn fetch_kernel_commitment_sum(&self, at_hash: &HashOutput) -> Result<Commitment, ChainStorageError> {
         Ok(self.fetch_block_accumulated_data(at_hash.clone())?.kernel_sum)
@@ -725,6 +1021,7 @@ where B: BlockchainBackend
             // If blocks were added and the node is in pruned mode, perform pruning
             prune_database_if_needed(&mut *db, self.config.pruning_horizon, self.config.pruning_interval)?
         }
+        update_block_add_metrics(&block_add_result);
 
         info!(
             target: LOG_TARGET,
@@ -842,6 +1139,12 @@ where B: BlockchainBackend
         Ok(db.contains(&DbKey::BlockHash(hash.clone()))? || db.contains(&DbKey::OrphanBlock(hash))?)
     }
 
+    /// Returns true if this block was previously marked as invalid
+    pub fn bad_block_exists(&self, hash: BlockHash) -> Result<bool, ChainStorageError> {
+        let db = self.db_read_access()?;
+        db.bad_block_exists(hash)
+    }
+
     /// Atomically commit the provided transaction to the database backend. This function does not update the metadata.
     pub fn commit(&self, txn: DbTransaction) -> Result<(), ChainStorageError> {
         let mut db = self.db_write_access()?;
@@ -868,6 +1171,27 @@ where B: BlockchainBackend
         rewind_to_hash(&mut *db, hash)
     }
 
+    /// Remove the block with the given hash, and every block that builds on it, from the main chain and the orphan
+    /// pool. If the block is part of the main chain, the chain is first rewound to its parent. The removed blocks are
+    /// marked as bad so that they are rejected if they are received again. Returns the main chain blocks that were
+    /// removed.
+    ///
+    /// The operation will fail if
+    /// * The block hash does not exist in the main chain or the orphan pool
+    /// * The block is the genesis block
+    pub fn invalidate_block(&self, hash: BlockHash) -> Result<Vec<Arc<ChainBlock>>, ChainStorageError> {
+        let mut db = self.db_write_access()?;
+        invalidate_block(&mut *db, hash)
+    }
+
+    /// Drop and rebuild the kernel and output secondary indexes from the kernel and output tables.
+    pub fn rebuild_secondary_indexes(&self) -> Result<(), ChainStorageError> {
+        let mut db = self.db_write_access()?;
+        let mut txn = DbTransaction::new();
+        txn.rebuild_secondary_indexes();
+        db.write(txn)
+    }
+
     pub fn fetch_horizon_data(&self) -> Result<Option<HorizonData>, ChainStorageError> {
         let db = self.db_read_access()?;
         db.fetch_horizon_data()
@@ -918,52 +1242,93 @@ pub struct MmrRoots {
 }
 
 pub fn calculate_mmr_roots<T: BlockchainBackend>(db: &T, block: &Block) -> Result<MmrRoots, ChainStorageError> {
-    let header = &block.header;
     let body = &block.body;
+    let mut calculator = MmrRootsCalculator::new(db, &block.header)?;
 
-    let metadata = db.fetch_chain_metadata()?;
-    if header.prev_hash != *metadata.best_block() {
-        return Err(ChainStorageError::CannotCalculateNonTipMmr(format!(
-            "Block (#{}) previous hash is {} but the current tip is #{} {}",
-            header.height,
-            header.prev_hash.to_hex(),
-            metadata.height_of_longest_chain(),
-            metadata.best_block().to_hex()
-        )));
+    for kernel in body.kernels().iter() {
+        calculator.add_kernel(kernel)?;
     }
 
-    let deleted = db.fetch_deleted_bitmap()?;
-    let deleted = deleted.into_bitmap();
-
-    let BlockAccumulatedData {
-        kernels,
-        outputs,
-        range_proofs,
-        ..
-    } = db
-        .fetch_block_accumulated_data(&header.prev_hash)?
-        .ok_or_else(|| ChainStorageError::ValueNotFound {
-            entity: "BlockAccumulatedData".to_string(),
-            field: "header_hash".to_string(),
-            value: header.prev_hash.to_hex(),
-        })?;
+    for output in body.outputs().iter() {
+        calculator.add_output(output)?;
+    }
 
-    let mut kernel_mmr = MerkleMountainRange::<HashDigest, _>::new(kernels);
-    let mut output_mmr = MutableMmr::<HashDigest, _>::new(outputs, deleted)?;
-    let mut witness_mmr = MerkleMountainRange::<HashDigest, _>::new(range_proofs);
-    let mut input_mmr = MutableMmr::<HashDigest, _>::new(Vec::new(), Bitmap::create())?;
+    for input in body.inputs().iter() {
+        calculator.add_input(db, input)?;
+    }
 
-    for kernel in body.kernels().iter() {
-        kernel_mmr.push(kernel.hash())?;
+    calculator.finalize()
+}
+
+/// Calculates the MMR roots of a block on top of the current tip incrementally, one kernel, output or input at a
+/// time. This allows the roots to be calculated while the block body is being validated or received, without
+/// requiring an additional pass over the body.
+///
+/// All outputs must be added before any inputs, so that inputs that spend outputs in the same block (zero-conf
+/// spends) can be found.
+pub struct MmrRootsCalculator {
+    kernel_mmr: MerkleMountainRange<HashDigest, PrunedHashSet>,
+    output_mmr: MutableMmr<HashDigest, PrunedHashSet>,
+    witness_mmr: MerkleMountainRange<HashDigest, PrunedHashSet>,
+    input_mmr: MutableMmr<HashDigest, Vec<HashOutput>>,
+}
+
+impl MmrRootsCalculator {
+    /// Loads the MMR state of the current tip. Returns an error if `header` does not build on the current tip.
+    pub fn new<T: BlockchainBackend>(db: &T, header: &BlockHeader) -> Result<Self, ChainStorageError> {
+        let metadata = db.fetch_chain_metadata()?;
+        if header.prev_hash != *metadata.best_block() {
+            return Err(ChainStorageError::CannotCalculateNonTipMmr(format!(
+                "Block (#{}) previous hash is {} but the current tip is #{} {}",
+                header.height,
+                header.prev_hash.to_hex(),
+                metadata.height_of_longest_chain(),
+                metadata.best_block().to_hex()
+            )));
+        }
+
+        let deleted = db.fetch_deleted_bitmap()?;
+        let deleted = deleted.into_bitmap();
+
+        let BlockAccumulatedData {
+            kernels,
+            outputs,
+            range_proofs,
+            ..
+        } = db
+            .fetch_block_accumulated_data(&header.prev_hash)?
+            .ok_or_else(|| ChainStorageError::ValueNotFound {
+                entity: "BlockAccumulatedData".to_string(),
+                field: "header_hash".to_string(),
+                value: header.prev_hash.to_hex(),
+            })?;
+
+        Ok(Self {
+            kernel_mmr: MerkleMountainRange::new(kernels),
+            output_mmr: MutableMmr::new(outputs, deleted)?,
+            witness_mmr: MerkleMountainRange::new(range_proofs),
+            input_mmr: MutableMmr::new(Vec::new(), Bitmap::create())?,
+        })
     }
 
-    for output in body.outputs().iter() {
-        output_mmr.push(output.hash())?;
-        witness_mmr.push(output.witness_hash())?;
+    pub fn add_kernel(&mut self, kernel: &TransactionKernel) -> Result<(), ChainStorageError> {
+        self.kernel_mmr.push(kernel.hash())?;
+        Ok(())
     }
 
-    for input in body.inputs().iter() {
-        input_mmr.push(input.hash())?;
+    pub fn add_output(&mut self, output: &TransactionOutput) -> Result<(), ChainStorageError> {
+        self.output_mmr.push(output.hash())?;
+        self.witness_mmr.push(output.witness_hash())?;
+        Ok(())
+    }
+
+    /// Adds the input to the input MMR and marks the output it spends as deleted in the output MMR
+    pub fn add_input<T: BlockchainBackend>(
+        &mut self,
+        db: &T,
+        input: &TransactionInput,
+    ) -> Result<(), ChainStorageError> {
+        self.input_mmr.push(input.hash())?;
 
         // Search the DB for the output leaf index so that it can be marked as spent/deleted.
         // If the output hash is not found, check the current output_mmr. This allows zero-conf transactions
@@ -972,7 +1337,7 @@ pub fn calculate_mmr_roots<T: BlockchainBackend>(db: &T, block: &Block) -> Resul
             Some(index) => index,
             None => {
                 let index =
-                    output_mmr
+                    self.output_mmr
                         .find_leaf_index(&output_hash)?
                         .ok_or_else(|| ChainStorageError::ValueNotFound {
                             entity: "UTXO".to_string(),
@@ -989,10 +1354,10 @@ pub fn calculate_mmr_roots<T: BlockchainBackend>(db: &T, block: &Block) -> Resul
             },
         };
 
-        if !output_mmr.delete(index) {
-            let num_leaves = u32::try_from(output_mmr.get_leaf_count())
+        if !self.output_mmr.delete(index) {
+            let num_leaves = u32::try_from(self.output_mmr.get_leaf_count())
                 .map_err(|_| ChainStorageError::CriticalError("UTXO MMR leaf count overflows u32".to_string()))?;
-            if index < num_leaves && output_mmr.deleted().contains(index) {
+            if index < num_leaves && self.output_mmr.deleted().contains(index) {
                 return Err(ChainStorageError::InvalidOperation(format!(
                     "UTXO {} was already marked as deleted.",
                     output_hash.to_hex()
@@ -1004,19 +1369,22 @@ pub fn calculate_mmr_roots<T: BlockchainBackend>(db: &T, block: &Block) -> Resul
                 index, num_leaves
             )));
         }
+
+        Ok(())
     }
 
-    output_mmr.compress();
+    pub fn finalize(mut self) -> Result<MmrRoots, ChainStorageError> {
+        self.output_mmr.compress();
 
-    let mmr_roots = MmrRoots {
-        kernel_mr: kernel_mmr.get_merkle_root()?,
-        kernel_mmr_size: kernel_mmr.get_leaf_count()? as u64,
-        input_mr: input_mmr.get_merkle_root()?,
-        output_mr: output_mmr.get_merkle_root()?,
-        output_mmr_size: output_mmr.get_leaf_count() as u64,
-        witness_mr: witness_mmr.get_merkle_root()?,
-    };
-    Ok(mmr_roots)
+        Ok(MmrRoots {
+            kernel_mr: self.kernel_mmr.get_merkle_root()?,
+            kernel_mmr_size: self.kernel_mmr.get_leaf_count()? as u64,
+            input_mr: self.input_mmr.get_merkle_root()?,
+            output_mr: self.output_mmr.get_merkle_root()?,
+            output_mmr_size: self.output_mmr.get_leaf_count() as u64,
+            witness_mr: self.witness_mmr.get_merkle_root()?,
+        })
+    }
 }
 
 pub fn fetch_header<T: BlockchainBackend>(db: &T, block_num: u64) -> Result<BlockHeader, ChainStorageError> {
@@ -1097,9 +1465,16 @@ fn add_block<T: BlockchainBackend>(
     block: Arc<Block>,
 ) -> Result<BlockAddResult, ChainStorageError> {
     let block_hash = block.hash();
-    if db.contains(&DbKey::BlockHash(block_hash))? {
+    if db.contains(&DbKey::BlockHash(block_hash.clone()))? {
         return Ok(BlockAddResult::BlockExists);
     }
+    if db.bad_block_exists(block_hash.clone())? {
+        return Err(ChainStorageError::ValidationError {
+            source: ValidationError::BadBlockFound {
+                hash: block_hash.to_hex(),
+            },
+        });
+    }
     handle_possible_reorg(
         db,
         block_validator,
@@ -1128,9 +1503,29 @@ fn insert_block(txn: &mut DbTransaction, block: Arc<ChainBlock>) -> Result<(), C
 
     let height = block.height();
     let accumulated_difficulty = block.accumulated_data().total_accumulated_difficulty;
+    let accumulated_monero_difficulty = block.accumulated_data().accumulated_monero_difficulty;
+    let accumulated_sha_difficulty = block.accumulated_data().accumulated_sha_difficulty;
+    let prev_hash = block.header().prev_hash.clone();
     txn.insert_chain_header(block.to_chain_header())
-        .insert_block_body(block)
-        .set_best_block(height, block_hash, accumulated_difficulty);
+        .insert_block_body(block);
+    if height == 0 {
+        txn.set_best_block(
+            height,
+            block_hash,
+            accumulated_difficulty,
+            accumulated_monero_difficulty,
+            accumulated_sha_difficulty,
+        );
+    } else {
+        txn.set_best_block_if_equal(
+            prev_hash,
+            height,
+            block_hash,
+            accumulated_difficulty,
+            accumulated_monero_difficulty,
+            accumulated_sha_difficulty,
+        );
+    }
 
     Ok(())
 }
@@ -1409,12 +1804,71 @@ fn rewind_to_height<T: BlockchainBackend>(
         chain_header.height(),
         chain_header.accumulated_data().hash.clone(),
         chain_header.accumulated_data().total_accumulated_difficulty,
+        chain_header.accumulated_data().accumulated_monero_difficulty,
+        chain_header.accumulated_data().accumulated_sha_difficulty,
     );
     db.write(txn)?;
 
     Ok(removed_blocks)
 }
 
+fn invalidate_block<T: BlockchainBackend>(
+    db: &mut T,
+    hash: BlockHash,
+) -> Result<Vec<Arc<ChainBlock>>, ChainStorageError> {
+    let mut removed_blocks = Vec::new();
+    let height;
+    if let Some(DbValue::BlockHash(header)) = db.fetch(&DbKey::BlockHash(hash.clone()))? {
+        if header.height == 0 {
+            return Err(ChainStorageError::InvalidOperation(
+                "Cannot invalidate the genesis block".to_string(),
+            ));
+        }
+        info!(
+            target: LOG_TARGET,
+            "Invalidating main chain block #{} ({})",
+            header.height,
+            hash.to_hex()
+        );
+        height = header.height;
+        // Rewinding moves the removed blocks into the orphan pool, where they are removed below
+        removed_blocks = rewind_to_height(db, header.height - 1)?;
+    } else if let Some(DbValue::OrphanBlock(block)) = db.fetch(&DbKey::OrphanBlock(hash.clone()))? {
+        height = block.header.height;
+    } else {
+        return Err(ChainStorageError::ValueNotFound {
+            entity: "Block".to_string(),
+            field: "hash".to_string(),
+            value: hash.to_hex(),
+        });
+    }
+
+    // Collect the invalid block and all of its orphan descendants, which are invalid because they build on it
+    let mut to_delete = vec![(hash, height)];
+    let mut i = 0;
+    while i < to_delete.len() {
+        let children = db.fetch_orphan_children_of(to_delete[i].0.clone())?;
+        to_delete.extend(children.into_iter().map(|b| (b.hash(), b.header.height)));
+        i += 1;
+    }
+
+    let mut txn = DbTransaction::new();
+    // Delete from the leaves back towards the invalid block so that the chain tips are kept consistent
+    for (hash, height) in to_delete.into_iter().rev() {
+        debug!(
+            target: LOG_TARGET,
+            "Removing invalidated block #{} {} from orphan pool",
+            height,
+            hash.to_hex()
+        );
+        txn.delete_orphan(hash.clone());
+        txn.insert_bad_block(hash, height);
+    }
+    db.write(txn)?;
+
+    Ok(removed_blocks)
+}
+
 fn rewind_to_hash<T: BlockchainBackend>(
     db: &mut T,
     block_hash: BlockHash,
@@ -1431,6 +1885,26 @@ fn rewind_to_hash<T: BlockchainBackend>(
 
 // Checks whether we should add the block as an orphan. If it is the case, the orphan block is added and the chain
 // is reorganised if necessary.
+#[cfg(feature = "metrics")]
+fn update_block_add_metrics(result: &BlockAddResult) {
+    match result {
+        BlockAddResult::Ok(block) => metrics::tip_height().set(block.height() as i64),
+        BlockAddResult::ChainReorg { added, removed } => {
+            if let Some(tip) = added.last() {
+                metrics::tip_height().set(tip.height() as i64);
+            }
+            if !removed.is_empty() {
+                metrics::reorgs().inc();
+                metrics::reorg_depth().observe(removed.len() as f64);
+            }
+        },
+        BlockAddResult::BlockExists | BlockAddResult::OrphanBlock => {},
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+fn update_block_add_metrics(_: &BlockAddResult) {}
+
 fn handle_possible_reorg<T: BlockchainBackend>(
     db: &mut T,
     block_validator: &dyn PostOrphanBodyValidation<T>,
@@ -1608,9 +2082,13 @@ fn reorganize_chain<T: BlockchainBackend>(
         txn.delete_orphan(block.accumulated_data().hash.clone());
         let chain_metadata = backend.fetch_chain_metadata()?;
         let deleted_bitmap = backend.fetch_deleted_bitmap()?;
-        if let Err(e) =
-            block_validator.validate_body_for_valid_orphan(&block, backend, &chain_metadata, &deleted_bitmap)
-        {
+        #[cfg(feature = "metrics")]
+        let timer = metrics::block_validation_seconds().start_timer();
+        let validation_result =
+            block_validator.validate_body_for_valid_orphan(&block, backend, &chain_metadata, &deleted_bitmap);
+        #[cfg(feature = "metrics")]
+        timer.observe_duration();
+        if let Err(e) = validation_result {
             warn!(
                 target: LOG_TARGET,
                 "Orphan block {} ({}) failed validation during chain reorg: {:?}",
@@ -1720,9 +2198,7 @@ fn insert_orphan_and_find_new_tips<T: BlockchainBackend>(
                     hash.to_hex()
                 );
 
-                if !db.contains(&DbKey::OrphanBlock(hash))? {
-                    txn.insert_orphan(block);
-                }
+                txn.insert_orphan_if_not_exists(block);
                 db.write(txn)?;
                 return Ok(vec![]);
             },
@@ -1938,10 +2414,51 @@ fn log_error<T>(req: DbKey, err: ChainStorageError) -> Result<T, ChainStorageErr
     Err(err)
 }
 
+impl BlockchainDatabase<LMDBDatabase> {
+    /// Returns the map usage of the LMDB environment, the number of free pages and the size of each database.
+    pub fn get_storage_stats(&self) -> Result<LMDBStoreStats, ChainStorageError> {
+        let db = self.db_read_access()?;
+        db.get_storage_stats()
+    }
+
+    /// Compacts the LMDB database, returning the space held by free pages to the file system. The compacted copy is
+    /// made while holding a read lock, so the database continues to serve reads while blocks are held back. The
+    /// write lock is only held to swap in the copy. If a write is made between the two, the copy is discarded and
+    /// the operation fails; it can be retried.
+    pub fn compact(&self) -> Result<CompactionResult, ChainStorageError> {
+        let copy = {
+            let db = self.db_read_access()?;
+            db.create_compacted_copy()?
+        };
+        let mut db = self.db_write_access()?;
+        db.swap_compacted_copy(copy)
+    }
+
+    /// Returns the usage statistics, including false positive rates, of the output and kernel existence filters
+    pub fn get_existence_filter_stats(&self) -> Result<Vec<ExistenceFilterStats>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        Ok(db.get_existence_filter_stats())
+    }
+
+    /// Deletes the inputs of blocks below the pruned height, returning the updated pruning stats. LMDB serializes
+    /// write transactions, so only a read lock is held and blocks can continue to be added between batches.
+    pub fn reclaim_pruned_space(&self) -> Result<PruningStats, ChainStorageError> {
+        let db = self.db_read_access()?;
+        db.reclaim_pruned_blocks()
+    }
+
+    /// Returns the number of pruned outputs and inputs and the storage reclaimed by pruning
+    pub fn get_pruning_stats(&self) -> Result<PruningStats, ChainStorageError> {
+        let db = self.db_read_access()?;
+        db.get_pruning_stats()
+    }
+}
+
 impl<T> Clone for BlockchainDatabase<T> {
     fn clone(&self) -> Self {
         BlockchainDatabase {
             db: self.db.clone(),
+            write_queue: self.write_queue.clone(),
             validators: self.validators.clone(),
             config: self.config,
             consensus_manager: self.consensus_manager.clone(),
@@ -2175,6 +2692,98 @@ mod test {
         }
     }
 
+    mod invalidate_block {
+        use super::*;
+
+        #[test]
+        fn it_removes_the_block_and_its_descendants_from_the_main_chain() {
+            let db = create_new_blockchain();
+            let (_, main_chain) = create_main_chain(&db, &[("A->GB", 1, 120), ("B->A", 1, 120), ("C->B", 1, 120)]);
+            let block_b = main_chain.get("B").unwrap().clone();
+            let block_c = main_chain.get("C").unwrap().clone();
+
+            let removed = db.invalidate_block(block_b.hash().clone()).unwrap();
+            assert_eq!(removed.len(), 2);
+            assert_eq!(db.get_height().unwrap(), 1);
+            assert!(!db.block_exists(block_b.hash().clone()).unwrap());
+            assert!(!db.block_exists(block_c.hash().clone()).unwrap());
+        }
+
+        #[test]
+        fn it_rejects_an_invalidated_block_when_it_is_added_again() {
+            let db = create_new_blockchain();
+            let (_, main_chain) = create_main_chain(&db, &[("A->GB", 1, 120), ("B->A", 1, 120), ("C->B", 1, 120)]);
+            let block_b = main_chain.get("B").unwrap().clone();
+            let block_c = main_chain.get("C").unwrap().clone();
+
+            db.invalidate_block(block_b.hash().clone()).unwrap();
+            assert!(db.bad_block_exists(block_b.hash().clone()).unwrap());
+            assert!(db.bad_block_exists(block_c.hash().clone()).unwrap());
+
+            let err = db.add_block(block_b.to_arc_block()).unwrap_err();
+            assert!(matches!(err, ChainStorageError::ValidationError {
+                source: ValidationError::BadBlockFound { .. }
+            }));
+            assert_eq!(db.get_height().unwrap(), 1);
+        }
+
+        #[test]
+        fn it_errors_for_the_genesis_block() {
+            let db = create_new_blockchain();
+            let genesis_hash = db.fetch_block(0).unwrap().block().hash();
+            let err = db.invalidate_block(genesis_hash).unwrap_err();
+            assert!(matches!(err, ChainStorageError::InvalidOperation(_)));
+        }
+
+        #[test]
+        fn it_errors_for_an_unknown_block() {
+            let db = create_new_blockchain();
+            let err = db.invalidate_block(vec![0u8; 32]).unwrap_err();
+            assert!(matches!(err, ChainStorageError::ValueNotFound { .. }));
+        }
+    }
+
+    mod queue_write {
+        use super::*;
+
+        fn seed_height_txn(seed: &[u8], height: u64) -> DbTransaction {
+            let mut txn = DbTransaction::new();
+            txn.insert_monero_seed_height(seed.to_vec(), height);
+            txn
+        }
+
+        #[test]
+        fn it_commits_queued_writes_before_a_read() {
+            let db = create_test_blockchain_db();
+            db.queue_write(seed_height_txn(b"seed1", 5)).unwrap();
+            db.queue_write(seed_height_txn(b"seed2", 6)).unwrap();
+            assert_eq!(db.write_queue.lock().unwrap().num_transactions, 2);
+
+            let db_read = db.db_read_access().unwrap();
+            assert!(db.write_queue.lock().unwrap().is_empty());
+            assert_eq!(db_read.fetch_monero_seed_first_seen_height(b"seed1").unwrap(), 5);
+            assert_eq!(db_read.fetch_monero_seed_first_seen_height(b"seed2").unwrap(), 6);
+        }
+
+        #[test]
+        fn it_commits_when_the_queue_is_full() {
+            let mut db = create_test_blockchain_db();
+            db.config.group_commit_max_operations = 2;
+            db.queue_write(seed_height_txn(b"seed1", 5)).unwrap();
+            assert_eq!(db.write_queue.lock().unwrap().num_transactions, 1);
+            db.queue_write(seed_height_txn(b"seed2", 6)).unwrap();
+            assert!(db.write_queue.lock().unwrap().is_empty());
+        }
+
+        #[test]
+        fn it_commits_when_the_interval_has_elapsed() {
+            let mut db = create_test_blockchain_db();
+            db.config.group_commit_interval = Duration::from_millis(0);
+            db.queue_write(seed_height_txn(b"seed1", 5)).unwrap();
+            assert!(db.write_queue.lock().unwrap().is_empty());
+        }
+    }
+
     #[test]
     fn test_handle_possible_reorg_case1() {
         // Normal chain
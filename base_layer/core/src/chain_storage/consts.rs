@@ -20,9 +20,17 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::time::Duration;
+
 /// The maximum number of orphans that can be stored in the Orphan block pool.
 pub const BLOCKCHAIN_DATABASE_ORPHAN_STORAGE_CAPACITY: usize = 720;
 /// The pruning horizon that is set for a default configuration of the blockchain db.
 pub const BLOCKCHAIN_DATABASE_PRUNING_HORIZON: u64 = 0;
 /// The chain height interval used to determine when a pruned node should perform pruning.
 pub const BLOCKCHAIN_DATABASE_PRUNED_MODE_PRUNING_INTERVAL: u64 = 50;
+/// The number of queued write operations at which the write queue is committed to the backend.
+pub const BLOCKCHAIN_DATABASE_GROUP_COMMIT_MAX_OPERATIONS: usize = 1000;
+/// The maximum time that a queued write is held before the write queue is committed to the backend.
+pub const BLOCKCHAIN_DATABASE_GROUP_COMMIT_INTERVAL: Duration = Duration::from_millis(500);
+/// The maximum time that a read snapshot may be held open before it refuses further reads.
+pub const BLOCKCHAIN_DATABASE_SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(5 * 60);
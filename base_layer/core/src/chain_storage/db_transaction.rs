@@ -22,6 +22,7 @@
 use crate::{
     blocks::{Block, BlockHeader},
     chain_storage::{error::ChainStorageError, ChainBlock, ChainHeader, MmrTree},
+    proof_of_work::Difficulty,
     transactions::{
         transaction::{TransactionInput, TransactionKernel, TransactionOutput},
         types::{Commitment, HashOutput},
@@ -87,6 +88,13 @@ impl DbTransaction {
         self
     }
 
+    /// Drop and rebuild the secondary lookup indexes (kernel excess, kernel signature and output hash) from the
+    /// kernel and output tables.
+    pub fn rebuild_secondary_indexes(&mut self) -> &mut Self {
+        self.operations.push(WriteOperation::RebuildSecondaryIndexes);
+        self
+    }
+
     /// Inserts a transaction kernel into the current transaction.
     pub fn insert_kernel(
         &mut self,
@@ -204,6 +212,14 @@ impl DbTransaction {
         self
     }
 
+    /// Stores an orphan block if it does not already exist. Unlike `insert_orphan`, an existing orphan is not an
+    /// error, so the check and the insert happen atomically in the same write transaction.
+    pub fn insert_orphan_if_not_exists(&mut self, orphan: Arc<Block>) -> &mut Self {
+        self.operations
+            .push(WriteOperation::InsertOrphanBlockIfNotExists(orphan));
+        self
+    }
+
     /// Insert a "chained" orphan block.
     /// The transaction will rollback and write will return an error if the orphan already exists.
     pub fn insert_chained_orphan(&mut self, orphan: Arc<ChainBlock>) -> &mut Self {
@@ -232,11 +248,44 @@ impl DbTransaction {
         self
     }
 
-    pub fn set_best_block(&mut self, height: u64, hash: HashOutput, accumulated_difficulty: u128) -> &mut Self {
+    /// Sets the best block along with the total accumulated difficulty and the accumulated difficulty of each proof
+    /// of work algorithm of the chain that it is the tip of
+    pub fn set_best_block(
+        &mut self,
+        height: u64,
+        hash: HashOutput,
+        accumulated_difficulty: u128,
+        accumulated_monero_difficulty: Difficulty,
+        accumulated_sha_difficulty: Difficulty,
+    ) -> &mut Self {
         self.operations.push(WriteOperation::SetBestBlock {
             height,
             hash,
             accumulated_difficulty,
+            accumulated_monero_difficulty,
+            accumulated_sha_difficulty,
+        });
+        self
+    }
+
+    /// Sets the best block only if the current best block is `expected_hash`. The transaction will rollback and write
+    /// will return a `ConditionalWriteFailed` error if the best block has been changed by another writer.
+    pub fn set_best_block_if_equal(
+        &mut self,
+        expected_hash: HashOutput,
+        height: u64,
+        hash: HashOutput,
+        accumulated_difficulty: u128,
+        accumulated_monero_difficulty: Difficulty,
+        accumulated_sha_difficulty: Difficulty,
+    ) -> &mut Self {
+        self.operations.push(WriteOperation::SetBestBlockIfEqual {
+            expected_hash,
+            height,
+            hash,
+            accumulated_difficulty,
+            accumulated_monero_difficulty,
+            accumulated_sha_difficulty,
         });
         self
     }
@@ -260,6 +309,12 @@ impl DbTransaction {
         &self.operations
     }
 
+    /// Appends the operations of `other` to this transaction
+    pub(crate) fn append(&mut self, other: DbTransaction) -> &mut Self {
+        self.operations.extend(other.operations);
+        self
+    }
+
     pub(crate) fn into_operations(self) -> Vec<WriteOperation> {
         self.operations
     }
@@ -270,12 +325,19 @@ impl DbTransaction {
         self.operations
             .push(WriteOperation::InsertMoneroSeedHeight(monero_seed, height));
     }
+
+    /// Marks the block hash as invalid so that the block is rejected if it is received again
+    pub fn insert_bad_block(&mut self, hash: HashOutput, height: u64) -> &mut Self {
+        self.operations.push(WriteOperation::InsertBadBlock { hash, height });
+        self
+    }
 }
 
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum WriteOperation {
     InsertOrphanBlock(Arc<Block>),
+    InsertOrphanBlockIfNotExists(Arc<Block>),
     InsertChainOrphanBlock(Arc<ChainBlock>),
     InsertChainHeader {
         header: Box<ChainHeader>,
@@ -309,9 +371,14 @@ pub enum WriteOperation {
     DeleteHeader(u64),
     DeleteOrphan(HashOutput),
     DeleteBlock(HashOutput),
+    RebuildSecondaryIndexes,
     DeleteOrphanChainTip(HashOutput),
     InsertOrphanChainTip(HashOutput),
     InsertMoneroSeedHeight(Vec<u8>, u64),
+    InsertBadBlock {
+        hash: HashOutput,
+        height: u64,
+    },
     UpdatePrunedHashSet {
         mmr_tree: MmrTree,
         header_hash: HashOutput,
@@ -337,6 +404,16 @@ pub enum WriteOperation {
         height: u64,
         hash: HashOutput,
         accumulated_difficulty: u128,
+        accumulated_monero_difficulty: Difficulty,
+        accumulated_sha_difficulty: Difficulty,
+    },
+    SetBestBlockIfEqual {
+        expected_hash: HashOutput,
+        height: u64,
+        hash: HashOutput,
+        accumulated_difficulty: u128,
+        accumulated_monero_difficulty: Difficulty,
+        accumulated_sha_difficulty: Difficulty,
     },
     SetPruningHorizonConfig(u64),
     SetPrunedHeight {
@@ -356,6 +433,9 @@ impl fmt::Display for WriteOperation {
                 block.hash().to_hex(),
                 block.body.to_counts_string()
             ),
+            InsertOrphanBlockIfNotExists(block) => {
+                write!(f, "InsertOrphanBlockIfNotExists({})", block.hash().to_hex())
+            },
             InsertChainHeader { header } => {
                 write!(f, "InsertChainHeader(#{} {})", header.height(), header.hash().to_hex())
             },
@@ -446,6 +526,7 @@ impl fmt::Display for WriteOperation {
                 height,
                 hash,
                 accumulated_difficulty,
+                ..
             } => write!(
                 f,
                 "Update best block to height:{} ({}) with difficulty: {}",
@@ -453,10 +534,24 @@ impl fmt::Display for WriteOperation {
                 hash.to_hex(),
                 accumulated_difficulty
             ),
+            SetBestBlockIfEqual {
+                expected_hash,
+                height,
+                hash,
+                ..
+            } => write!(
+                f,
+                "Update best block to height:{} ({}) if the best block is {}",
+                height,
+                hash.to_hex(),
+                expected_hash.to_hex()
+            ),
             SetPruningHorizonConfig(pruning_horizon) => write!(f, "Set config: pruning horizon to {}", pruning_horizon),
             SetPrunedHeight { height, .. } => write!(f, "Set pruned height to {}", height),
             DeleteHeader(height) => write!(f, "Delete header at height: {}", height),
             DeleteOrphan(hash) => write!(f, "Delete orphan with hash: {}", hash.to_hex()),
+            RebuildSecondaryIndexes => write!(f, "Rebuild secondary indexes"),
+            InsertBadBlock { hash, height } => write!(f, "Insert bad block #{} {}", height, hash.to_hex()),
         }
     }
 }
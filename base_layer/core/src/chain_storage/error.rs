@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{chain_storage::MmrTree, proof_of_work::PowError, validation::ValidationError};
+use std::time::Duration;
 use tari_mmr::{error::MerkleMountainRangeError, MerkleProofError};
 use tari_storage::lmdb_store::LMDBError;
 use thiserror::Error;
@@ -107,6 +108,12 @@ pub enum ChainStorageError {
     IoError(#[from] std::io::Error),
     #[error("Cannot calculate MMR roots for block that does not form a chain with the current tip. {0}")]
     CannotCalculateNonTipMmr(String),
+    #[error("Conditional write failed: {0}")]
+    ConditionalWriteFailed(String),
+    #[error("The snapshot has been open longer than its maximum age of {0:.0?} and must be reopened")]
+    SnapshotExpired(Duration),
+    #[error("The database schema version {found} is newer than version {supported} supported by this node")]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
 }
 
 impl ChainStorageError {
@@ -0,0 +1,128 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    chain_storage::{
+        error::ChainStorageError,
+        lmdb_db::lmdb::{lmdb_for_each_key, lmdb_len},
+    },
+    common::bloom_filter::BloomFilter,
+};
+use lmdb_zero::{ConstTransaction, Database};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The false positive rate of an existence filter when it is filled to capacity
+const FALSE_POSITIVE_RATE: f64 = 0.01;
+/// The minimum number of keys an existence filter is sized for
+const MIN_CAPACITY: usize = 100_000;
+
+/// A bloom filter over the keys of an LMDB index, used to skip lookups for keys that are definitely not in the index.
+/// It is sized for twice the number of keys in the index when it is built.
+pub(super) struct ExistenceFilter {
+    name: &'static str,
+    filter: BloomFilter,
+    lookups: AtomicU64,
+    definitely_absent: AtomicU64,
+    false_positives: AtomicU64,
+}
+
+impl ExistenceFilter {
+    /// Builds a filter containing every key in the index
+    pub fn build(name: &'static str, txn: &ConstTransaction<'_>, db: &Database) -> Result<Self, ChainStorageError> {
+        let capacity = (lmdb_len(txn, db)? * 2).max(MIN_CAPACITY);
+        let filter = Self::new(name, capacity);
+        lmdb_for_each_key(txn, db, |key| filter.insert(key))?;
+        Ok(filter)
+    }
+
+    pub fn new(name: &'static str, capacity: usize) -> Self {
+        Self {
+            name,
+            filter: BloomFilter::new(capacity, FALSE_POSITIVE_RATE),
+            lookups: AtomicU64::new(0),
+            definitely_absent: AtomicU64::new(0),
+            false_positives: AtomicU64::new(0),
+        }
+    }
+
+    pub fn insert(&self, key: &[u8]) {
+        self.filter.insert(key);
+    }
+
+    /// Returns false if the key is definitely not in the index
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        let may_contain = self.filter.may_contain(key);
+        if !may_contain {
+            self.definitely_absent.fetch_add(1, Ordering::Relaxed);
+        }
+        may_contain
+    }
+
+    /// Records that a key the filter reported as possibly present was not in the index
+    pub fn record_false_positive(&self) {
+        self.false_positives.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns true if more keys have been inserted than the filter was sized for
+    pub fn is_full(&self) -> bool {
+        self.filter.len() > self.filter.capacity()
+    }
+
+    pub fn stats(&self) -> ExistenceFilterStats {
+        ExistenceFilterStats {
+            name: self.name,
+            num_keys: self.filter.len(),
+            capacity: self.filter.capacity(),
+            lookups: self.lookups.load(Ordering::Relaxed),
+            definitely_absent: self.definitely_absent.load(Ordering::Relaxed),
+            false_positives: self.false_positives.load(Ordering::Relaxed),
+            expected_false_positive_rate: self.filter.expected_false_positive_rate(),
+        }
+    }
+}
+
+/// Usage statistics for a chain storage existence filter
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExistenceFilterStats {
+    /// The name of the index the filter covers
+    pub name: &'static str,
+    pub num_keys: usize,
+    pub capacity: usize,
+    pub lookups: u64,
+    /// The number of lookups that were answered by the filter without reading the index
+    pub definitely_absent: u64,
+    /// The number of lookups the filter could not rule out that were not in the index
+    pub false_positives: u64,
+    pub expected_false_positive_rate: f64,
+}
+
+impl ExistenceFilterStats {
+    /// The fraction of lookups for absent keys that the filter failed to rule out
+    pub fn observed_false_positive_rate(&self) -> f64 {
+        let absent = self.definitely_absent + self.false_positives;
+        if absent == 0 {
+            return 0.0;
+        }
+        self.false_positives as f64 / absent as f64
+    }
+}
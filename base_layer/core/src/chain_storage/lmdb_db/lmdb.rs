@@ -137,6 +137,12 @@ where K: AsLmdbBytes + ?Sized {
     Ok(())
 }
 
+/// Removes all entries from the given database, leaving the database itself in place
+pub fn lmdb_clear(txn: &WriteTransaction<'_>, db: &Database) -> Result<(), ChainStorageError> {
+    txn.access().clear_db(&db)?;
+    Ok(())
+}
+
 pub fn lmdb_delete_key_value<K, V>(
     txn: &WriteTransaction<'_>,
     db: &Database,
@@ -280,6 +286,57 @@ pub fn lmdb_len(txn: &ConstTransaction<'_>, db: &Database) -> Result<usize, Chai
     Ok(stats.entries)
 }
 
+/// Calls `f` with every key in the database, without deserializing the values
+pub fn lmdb_for_each_key<F>(txn: &ConstTransaction<'_>, db: &Database, mut f: F) -> Result<(), ChainStorageError>
+where F: FnMut(&[u8]) {
+    let access = txn.access();
+    let mut cursor = txn.cursor(db).map_err(|e| {
+        error!(target: LOG_TARGET, "Could not get read cursor from lmdb: {:?}", e);
+        ChainStorageError::AccessError(e.to_string())
+    })?;
+    let iter = CursorIter::new(
+        MaybeOwned::Borrowed(&mut cursor),
+        &access,
+        |c, a| c.first(a),
+        Cursor::next::<[u8], [u8]>,
+    )?;
+    for row in iter {
+        f(row?.0);
+    }
+    Ok(())
+}
+
+/// Calls `f` with the key and the serialized value of every row in the database and collects the results that are not
+/// `None`
+pub fn lmdb_filter_map_rows<F, R>(
+    txn: &ConstTransaction<'_>,
+    db: &Database,
+    mut f: F,
+) -> Result<Vec<R>, ChainStorageError>
+where
+    F: FnMut(&[u8], &[u8]) -> Result<Option<R>, ChainStorageError>,
+{
+    let access = txn.access();
+    let mut cursor = txn.cursor(db).map_err(|e| {
+        error!(target: LOG_TARGET, "Could not get read cursor from lmdb: {:?}", e);
+        ChainStorageError::AccessError(e.to_string())
+    })?;
+    let iter = CursorIter::new(
+        MaybeOwned::Borrowed(&mut cursor),
+        &access,
+        |c, a| c.first(a),
+        Cursor::next::<[u8], [u8]>,
+    )?;
+    let mut result = vec![];
+    for row in iter {
+        let (key, val) = row?;
+        if let Some(r) = f(key, val)? {
+            result.push(r);
+        }
+    }
+    Ok(result)
+}
+
 pub fn lmdb_fetch_keys_starting_with<V>(
     key: &str,
     txn: &ConstTransaction<'_>,
@@ -313,6 +370,42 @@ where
     Ok(result)
 }
 
+/// Calls `f` with the values of keys that start with `prefix`, in key order, beginning at the first key that is greater
+/// than or equal to `start`. Iteration stops when `f` returns false.
+pub fn lmdb_visit_prefix_from<V, F>(
+    txn: &ConstTransaction<'_>,
+    db: &Database,
+    prefix: &[u8],
+    start: &[u8],
+    mut f: F,
+) -> Result<(), ChainStorageError>
+where
+    V: DeserializeOwned,
+    F: FnMut(V) -> Result<bool, ChainStorageError>,
+{
+    let access = txn.access();
+    let mut cursor = txn.cursor(db).map_err(|e| {
+        error!(target: LOG_TARGET, "Could not get read cursor from lmdb: {:?}", e);
+        ChainStorageError::AccessError(e.to_string())
+    })?;
+
+    let mut row = match cursor.seek_range_k::<[u8], [u8]>(&access, start) {
+        Ok(r) => r,
+        Err(_) => return Ok(()),
+    };
+    while row.0.starts_with(prefix) {
+        let val = deserialize::<V>(row.1)?;
+        if !f(val)? {
+            break;
+        }
+        row = match cursor.next(&access) {
+            Ok(r) => r,
+            Err(_) => break,
+        }
+    }
+    Ok(())
+}
+
 pub fn lmdb_first_after<K, V>(
     txn: &ConstTransaction<'_>,
     db: &Database,
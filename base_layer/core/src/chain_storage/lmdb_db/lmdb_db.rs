@@ -23,15 +23,19 @@ use crate::{
     blocks::{block_header::BlockHeader, Block},
     chain_storage::{
         accumulated_data::{BlockAccumulatedData, BlockHeaderAccumulatedData, DeletedBitmap},
+        asset_output::AssetOutput,
         db_transaction::{DbKey, DbTransaction, DbValue, WriteOperation},
         error::{ChainStorageError, OrNotFound},
         lmdb_db::{
+            existence_filter::{ExistenceFilter, ExistenceFilterStats},
             lmdb::{
+                lmdb_clear,
                 lmdb_delete,
                 lmdb_delete_key_value,
                 lmdb_delete_keys_starting_with,
                 lmdb_exists,
                 lmdb_fetch_keys_starting_with,
+                lmdb_filter_map_rows,
                 lmdb_filter_map_values,
                 lmdb_first_after,
                 lmdb_get,
@@ -41,10 +45,25 @@ use crate::{
                 lmdb_last,
                 lmdb_len,
                 lmdb_replace,
+                lmdb_visit_prefix_from,
+            },
+            migrations::{pending_migrations, MigrationReport, MigrationStepReport, LMDB_DB_SCHEMA_VERSION},
+            row_layouts::{
+                deserialize_exact,
+                BlockWithoutCovenant,
+                BlockWithoutEncryptedData,
+                BlockWithoutUniqueId,
+                InputRowWithoutCovenant,
+                InputRowWithoutEncryptedData,
+                InputRowWithoutUniqueId,
+                OutputRowWithoutCovenant,
+                OutputRowWithoutEncryptedData,
+                OutputRowWithoutUniqueId,
             },
             TransactionInputRowData,
             TransactionKernelRowData,
             TransactionOutputRowData,
+            LMDB_DB_BAD_BLOCK_LIST,
             LMDB_DB_BLOCK_ACCUMULATED_DATA,
             LMDB_DB_BLOCK_HASHES,
             LMDB_DB_HEADERS,
@@ -62,40 +81,81 @@ use crate::{
             LMDB_DB_ORPHAN_PARENT_MAP_INDEX,
             LMDB_DB_TXOS_HASH_TO_INDEX,
             LMDB_DB_UTXOS,
+            LMDB_DB_UTXO_ASSET_TOKEN_INDEX,
+            LMDB_DB_UTXO_COMMITMENT_INDEX,
+            LMDB_DB_UTXO_FEATURES_INDEX,
             LMDB_DB_UTXO_MMR_SIZE_INDEX,
+            LMDB_DB_UTXO_SCRIPT_HASH_INDEX,
+            LMDB_DB_UTXO_UNIQUE_ID_INDEX,
         },
         BlockchainBackend,
         ChainBlock,
         ChainHeader,
+        ChainReadSnapshot,
         HorizonData,
         MmrTree,
         PrunedOutput,
+        PruningStats,
     },
     crypto::tari_utilities::hex::to_hex,
+    proof_of_work::Difficulty,
     transactions::{
         aggregated_body::AggregateBody,
-        transaction::{TransactionInput, TransactionKernel, TransactionOutput},
-        types::{Commitment, HashDigest, HashOutput, Signature},
+        transaction::{OutputFlags, TransactionInput, TransactionKernel, TransactionOutput, UniqueAssetId},
+        types::{Commitment, HashDigest, HashOutput, PublicKey, Signature},
     },
 };
 use croaring::Bitmap;
 use fs2::FileExt;
 use lmdb_zero::{ConstTransaction, Database, Environment, ReadTransaction, WriteTransaction};
 use log::*;
-use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, fmt, fs, fs::File, ops::Deref, path::Path, sync::Arc, time::Instant};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    cmp,
+    convert::TryFrom,
+    fmt,
+    fs,
+    fs::File,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 use tari_common_types::{
     chain_metadata::ChainMetadata,
     types::{BlockHash, BLOCK_HASH_LENGTH},
 };
 use tari_crypto::tari_utilities::{hash::Hashable, hex::Hex, ByteArray};
 use tari_mmr::{pruned_hashset::PrunedHashSet, Hash, MerkleMountainRange, MutableMmr};
-use tari_storage::lmdb_store::{db, LMDBBuilder, LMDBConfig, LMDBStore};
+use tari_storage::lmdb_store::{db, LMDBBuilder, LMDBConfig, LMDBStore, LMDBStoreStats};
 
 type DatabaseRef = Arc<Database<'static>>;
 
 pub const LOG_TARGET: &str = "c::cs::lmdb_db::lmdb_db";
 
+const BYTES_PER_MB: u64 = 1024 * 1024;
+const LMDB_DATA_FILE: &str = "data.mdb";
+const LMDB_LOCK_FILE: &str = "lock.mdb";
+const COMPACTION_DIR: &str = "compaction";
+const COMPACTION_BACKUP_DIR: &str = "compaction_backup";
+const MIGRATION_BACKUP_DIR: &str = "migration_backup";
+/// The number of blocks whose inputs are deleted in each write transaction by `reclaim_pruned_blocks`
+const RECLAIM_BATCH_SIZE: u64 = 1000;
+
+/// A compacted copy of the database that has not yet replaced the live database files
+#[derive(Debug)]
+pub struct CompactedCopy {
+    path: PathBuf,
+    last_txn_id: usize,
+}
+
+/// The data file sizes before and after compaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionResult {
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+}
+
 struct OutputKey {
     header_hash: HashOutput,
     mmr_position: u32,
@@ -136,14 +196,40 @@ pub struct LMDBDatabase {
     orphan_header_accumulated_data_db: DatabaseRef,
     orphan_chain_tips_db: DatabaseRef,
     orphan_parent_map_index: DatabaseRef,
+    utxo_script_hash_index: DatabaseRef,
+    utxo_features_index: DatabaseRef,
+    utxo_commitment_index: DatabaseRef,
+    utxo_unique_id_index: DatabaseRef,
+    utxo_asset_token_index: DatabaseRef,
+    bad_blocks: DatabaseRef,
+    path: PathBuf,
+    output_filter: ExistenceFilter,
+    kernel_excess_sig_filter: ExistenceFilter,
     _file_lock: Arc<File>,
 }
 
 impl LMDBDatabase {
     pub fn new(store: LMDBStore, file_lock: File) -> Result<Self, ChainStorageError> {
+        Self::with_file_lock(store, Arc::new(file_lock))
+    }
+
+    fn with_file_lock(store: LMDBStore, file_lock: Arc<File>) -> Result<Self, ChainStorageError> {
+        let mut res = Self::open(store, file_lock)?;
+        res.migrate()?;
+        res.rebuild_existence_filters()?;
+        Ok(res)
+    }
+
+    /// Opens the database without running migrations or building the existence filters
+    fn open(store: LMDBStore, file_lock: Arc<File>) -> Result<Self, ChainStorageError> {
         let env = store.env();
+        let path = env
+            .path()?
+            .to_str()
+            .map(PathBuf::from)
+            .map_err(|e| ChainStorageError::CriticalError(format!("LMDB path is not valid UTF-8: {}", e)))?;
 
-        let res = Self {
+        Ok(Self {
             metadata_db: get_database(&store, LMDB_DB_METADATA)?,
             headers_db: get_database(&store, LMDB_DB_HEADERS)?,
             header_accumulated_data_db: get_database(&store, LMDB_DB_HEADER_ACCUMULATED_DATA)?,
@@ -162,12 +248,503 @@ impl LMDBDatabase {
             monero_seed_height_db: get_database(&store, LMDB_DB_MONERO_SEED_HEIGHT)?,
             orphan_chain_tips_db: get_database(&store, LMDB_DB_ORPHAN_CHAIN_TIPS)?,
             orphan_parent_map_index: get_database(&store, LMDB_DB_ORPHAN_PARENT_MAP_INDEX)?,
+            utxo_script_hash_index: get_database(&store, LMDB_DB_UTXO_SCRIPT_HASH_INDEX)?,
+            utxo_features_index: get_database(&store, LMDB_DB_UTXO_FEATURES_INDEX)?,
+            utxo_commitment_index: get_database(&store, LMDB_DB_UTXO_COMMITMENT_INDEX)?,
+            utxo_unique_id_index: get_database(&store, LMDB_DB_UTXO_UNIQUE_ID_INDEX)?,
+            utxo_asset_token_index: get_database(&store, LMDB_DB_UTXO_ASSET_TOKEN_INDEX)?,
+            bad_blocks: get_database(&store, LMDB_DB_BAD_BLOCK_LIST)?,
             env,
             env_config: store.env_config(),
-            _file_lock: Arc::new(file_lock),
+            path,
+            output_filter: ExistenceFilter::new(LMDB_DB_TXOS_HASH_TO_INDEX, 0),
+            kernel_excess_sig_filter: ExistenceFilter::new(LMDB_DB_KERNEL_EXCESS_SIG_INDEX, 0),
+            _file_lock: file_lock,
+        })
+    }
+
+    /// Returns the migration steps required to bring the database up to `LMDB_DB_SCHEMA_VERSION` and the number of
+    /// records each would change. The database is not modified.
+    pub fn plan_migrations(&self) -> Result<MigrationReport, ChainStorageError> {
+        let txn = self.read_transaction()?;
+        let from_version = self.fetch_schema_version(&txn)?;
+        let mut report = MigrationReport::new(from_version, true);
+        for migration in pending_migrations(from_version) {
+            report.steps.push(MigrationStepReport {
+                version: migration.version,
+                description: migration.description.to_string(),
+                num_changes: (migration.count_changes)(self, &txn)?,
+            });
+            report.to_version = migration.version;
+        }
+        Ok(report)
+    }
+
+    /// Runs the pending migration steps. A copy of the database is written to a backup directory first if any of the
+    /// steps change records. Each step is committed in its own write transaction together with its schema version, so
+    /// an interrupted migration resumes from the step that did not complete.
+    fn migrate(&self) -> Result<MigrationReport, ChainStorageError> {
+        let mut report = self.plan_migrations()?;
+        report.is_dry_run = false;
+        if report.is_up_to_date() {
+            let txn = self.write_transaction()?;
+            if lmdb_get::<_, MetadataValue>(&txn, &self.metadata_db, &MetadataKey::SchemaVersion.as_u32())?.is_none() {
+                self.set_metadata(
+                    &txn,
+                    MetadataKey::SchemaVersion,
+                    MetadataValue::SchemaVersion(report.from_version),
+                )?;
+                txn.commit()
+                    .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+            }
+            return Ok(report);
+        }
+
+        if report.has_changes() {
+            report.backup_path = Some(self.create_migration_backup(report.from_version)?);
+        }
+        for (migration, step) in pending_migrations(report.from_version).zip(report.steps.iter_mut()) {
+            let timer = Instant::now();
+            let txn = self.write_transaction()?;
+            step.num_changes = (migration.run)(self, &txn)?;
+            self.set_metadata(
+                &txn,
+                MetadataKey::SchemaVersion,
+                MetadataValue::SchemaVersion(migration.version),
+            )?;
+            txn.commit()
+                .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+            info!(
+                target: LOG_TARGET,
+                "Migrated blockchain database to schema version {} ({}): {} record(s) changed in {:.2?}",
+                migration.version,
+                migration.description,
+                step.num_changes,
+                timer.elapsed()
+            );
+        }
+        info!(target: LOG_TARGET, "{}", report);
+        Ok(report)
+    }
+
+    /// Writes a compacted copy of the database to a backup directory for the schema version it is being migrated
+    /// from. A backup left by an earlier attempt to migrate from the same version is replaced.
+    fn create_migration_backup(&self, from_version: u32) -> Result<PathBuf, ChainStorageError> {
+        let path = self.path.join(format!("{}_v{}", MIGRATION_BACKUP_DIR, from_version));
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        fs::create_dir_all(&path)?;
+        let timer = Instant::now();
+        LMDBStore::copy_compacted(&self.env, &path)?;
+        info!(
+            target: LOG_TARGET,
+            "Blockchain database backed up to {} before migrating in {:.2?}",
+            path.display(),
+            timer.elapsed()
+        );
+        Ok(path)
+    }
+
+    /// Returns the schema version of the database. Databases written before the schema version was recorded are
+    /// version 0, unless they are empty, in which case there is nothing to migrate.
+    fn fetch_schema_version(&self, txn: &ConstTransaction<'_>) -> Result<u32, ChainStorageError> {
+        let version = match lmdb_get(txn, &self.metadata_db, &MetadataKey::SchemaVersion.as_u32())? {
+            Some(MetadataValue::SchemaVersion(version)) => version,
+            Some(_) => {
+                return Err(ChainStorageError::DataInconsistencyDetected {
+                    function: "fetch_schema_version",
+                    details: "Schema version metadata has an unexpected value".to_string(),
+                })
+            },
+            None if lmdb_len(txn, &self.headers_db)? == 0 => LMDB_DB_SCHEMA_VERSION,
+            None => 0,
         };
+        if version > LMDB_DB_SCHEMA_VERSION {
+            return Err(ChainStorageError::UnsupportedSchemaVersion {
+                found: version,
+                supported: LMDB_DB_SCHEMA_VERSION,
+            });
+        }
+        Ok(version)
+    }
 
-        Ok(res)
+    /// Rebuilds the bloom filters that are used to skip output and kernel lookups for keys that are definitely not in
+    /// the database
+    fn rebuild_existence_filters(&mut self) -> Result<(), ChainStorageError> {
+        let timer = Instant::now();
+        let txn = self.read_transaction()?;
+        let output_filter = ExistenceFilter::build(LMDB_DB_TXOS_HASH_TO_INDEX, &txn, &self.txos_hash_to_index_db)?;
+        let kernel_excess_sig_filter =
+            ExistenceFilter::build(LMDB_DB_KERNEL_EXCESS_SIG_INDEX, &txn, &self.kernel_excess_sig_index)?;
+        drop(txn);
+        self.output_filter = output_filter;
+        self.kernel_excess_sig_filter = kernel_excess_sig_filter;
+        debug!(
+            target: LOG_TARGET,
+            "Existence filters built for {} output(s) and {} kernel(s) in {:.2?}",
+            self.output_filter.stats().num_keys,
+            self.kernel_excess_sig_filter.stats().num_keys,
+            timer.elapsed()
+        );
+        Ok(())
+    }
+
+    /// Returns the usage statistics of the output and kernel existence filters
+    pub fn get_existence_filter_stats(&self) -> Vec<ExistenceFilterStats> {
+        vec![self.output_filter.stats(), self.kernel_excess_sig_filter.stats()]
+    }
+
+    /// Returns the running totals of the storage reclaimed by pruning
+    pub fn get_pruning_stats(&self) -> Result<PruningStats, ChainStorageError> {
+        let txn = self.read_transaction()?;
+        fetch_pruning_stats(&txn, &self.metadata_db)
+    }
+
+    /// Deletes the inputs of the blocks below the pruned height. The outputs they spend have already been pruned and
+    /// the node can neither rewind to nor serve these blocks, so the inputs are never read again. Blocks are processed
+    /// in batches of `RECLAIM_BATCH_SIZE`, each in its own write transaction, so that writers are not held up for
+    /// long when a node first switches to pruned mode.
+    pub fn reclaim_pruned_blocks(&self) -> Result<PruningStats, ChainStorageError> {
+        let timer = Instant::now();
+        loop {
+            let txn = self.write_transaction()?;
+            let mut stats = fetch_pruning_stats(&txn, &self.metadata_db)?;
+            let pruned_height = fetch_pruned_height(&txn, &self.metadata_db)?;
+            let start = stats.reclaimed_height + 1;
+            let end = pruned_height.min(start + RECLAIM_BATCH_SIZE);
+            if start >= end {
+                debug!(
+                    target: LOG_TARGET,
+                    "Reclaimed pruned blocks in {:.2?}: {}",
+                    timer.elapsed(),
+                    stats
+                );
+                return Ok(stats);
+            }
+
+            for height in start..end {
+                let header: BlockHeader = lmdb_get(&txn, &self.headers_db, &height).or_not_found(
+                    "BlockHeader",
+                    "height",
+                    height.to_string(),
+                )?;
+                let inputs = lmdb_delete_keys_starting_with::<TransactionInputRowData>(
+                    &txn,
+                    &self.inputs_db,
+                    header.hash().to_hex().as_str(),
+                )?;
+                for input in &inputs {
+                    stats.bytes_reclaimed += serialized_size(input)?;
+                }
+                stats.inputs_pruned += inputs.len() as u64;
+                stats.reclaimed_height = height;
+            }
+            self.set_metadata(&txn, MetadataKey::PruningStats, MetadataValue::PruningStats(stats))?;
+            txn.commit()
+                .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+        }
+    }
+
+    /// Returns the number of outputs that are missing from the output script hash, features and commitment indexes
+    pub(super) fn count_unindexed_outputs(&self, txn: &ConstTransaction<'_>) -> Result<u64, ChainStorageError> {
+        let is_indexed =
+            lmdb_len(txn, &self.utxo_script_hash_index)? > 0 && lmdb_len(txn, &self.utxo_commitment_index)? > 0;
+        if is_indexed {
+            return Ok(0);
+        }
+        Ok(lmdb_len(txn, &self.utxos_db)? as u64)
+    }
+
+    /// Builds the output script hash, features and commitment indexes for a database that was created before they
+    /// existed
+    pub(super) fn build_output_indexes(&self, txn: &WriteTransaction<'_>) -> Result<u64, ChainStorageError> {
+        if self.count_unindexed_outputs(txn)? == 0 {
+            return Ok(0);
+        }
+        self.rebuild_output_indexes(txn)
+    }
+
+    /// Returns the number of asset tokens that are missing from the asset token index
+    pub(super) fn count_unindexed_asset_tokens(&self, txn: &ConstTransaction<'_>) -> Result<u64, ChainStorageError> {
+        if lmdb_len(txn, &self.utxo_asset_token_index)? > 0 {
+            return Ok(0);
+        }
+        let tokens = lmdb_filter_map_rows(txn, &self.utxos_db, |_, val| {
+            let row = TransactionOutputRowData::from_stored_bytes(val)?;
+            let is_token = row.output.as_ref().and_then(asset_token_public_key).is_some();
+            Ok(if is_token { Some(()) } else { None })
+        })?;
+        Ok(tokens.len() as u64)
+    }
+
+    /// Builds the asset token index for a database that was created before it existed
+    pub(super) fn build_asset_token_index(&self, txn: &WriteTransaction<'_>) -> Result<u64, ChainStorageError> {
+        if self.count_unindexed_asset_tokens(txn)? == 0 {
+            return Ok(0);
+        }
+        let tokens = lmdb_filter_map_rows(txn, &self.utxos_db, |_, val| {
+            let row = TransactionOutputRowData::from_stored_bytes(val)?;
+            let key = OutputKey::new(row.header_hash.clone(), row.mmr_position).get_key();
+            let mmr_position = row.mmr_position;
+            Ok(row
+                .output
+                .as_ref()
+                .and_then(asset_token_public_key)
+                .map(|asset_public_key| (output_index_key(asset_public_key.as_bytes(), mmr_position), key)))
+        })?;
+        for (index_key, key) in &tokens {
+            lmdb_replace(txn, &self.utxo_asset_token_index, index_key.as_slice(), key)?;
+        }
+        info!(target: LOG_TARGET, "Indexed {} asset token(s)", tokens.len());
+        Ok(tokens.len() as u64)
+    }
+
+    /// Returns 1 if the accumulated difficulty of each proof of work algorithm has not been recorded for the chain tip
+    pub(super) fn count_missing_accumulated_pow_work(
+        &self,
+        txn: &ConstTransaction<'_>,
+    ) -> Result<u64, ChainStorageError> {
+        let is_recorded =
+            lmdb_get::<_, MetadataValue>(txn, &self.metadata_db, &MetadataKey::AccumulatedPowWork.as_u32())?.is_some();
+        if is_recorded || lmdb_len(txn, &self.headers_db)? == 0 {
+            return Ok(0);
+        }
+        Ok(1)
+    }
+
+    /// Records the accumulated difficulty of each proof of work algorithm of the chain tip for a database that was
+    /// created before it was part of the chain metadata
+    pub(super) fn record_accumulated_pow_work(&self, txn: &WriteTransaction<'_>) -> Result<u64, ChainStorageError> {
+        if self.count_missing_accumulated_pow_work(txn)? == 0 {
+            return Ok(0);
+        }
+        let height = fetch_chain_height(txn, &self.metadata_db)?;
+        let accumulated_data: BlockHeaderAccumulatedData = lmdb_get(txn, &self.header_accumulated_data_db, &height)?
+            .ok_or_else(|| ChainStorageError::ValueNotFound {
+                entity: "BlockHeaderAccumulatedData".to_string(),
+                field: "height".to_string(),
+                value: height.to_string(),
+            })?;
+        self.set_metadata(
+            txn,
+            MetadataKey::AccumulatedPowWork,
+            MetadataValue::AccumulatedPowWork(
+                accumulated_data.accumulated_monero_difficulty,
+                accumulated_data.accumulated_sha_difficulty,
+            ),
+        )?;
+        Ok(1)
+    }
+
+    /// Returns the number of outputs, inputs and orphan blocks that were stored before covenants were added
+    pub(super) fn count_rows_without_covenants(&self, txn: &ConstTransaction<'_>) -> Result<u64, ChainStorageError> {
+        let num_outputs =
+            count_rows_in_layout::<TransactionOutputRowData, OutputRowWithoutCovenant>(txn, &self.utxos_db)?;
+        let num_inputs =
+            count_rows_in_layout::<TransactionInputRowData, InputRowWithoutCovenant>(txn, &self.inputs_db)?;
+        let num_orphans = count_rows_in_layout::<Block, BlockWithoutCovenant>(txn, &self.orphans_db)?;
+        Ok(num_outputs + num_inputs + num_orphans)
+    }
+
+    /// Rewrites the outputs, inputs and orphan blocks that were stored before covenants were added, with empty
+    /// covenants
+    pub(super) fn add_empty_covenants(&self, txn: &WriteTransaction<'_>) -> Result<u64, ChainStorageError> {
+        let num_outputs =
+            rewrite_rows_in_layout::<TransactionOutputRowData, OutputRowWithoutCovenant>(txn, &self.utxos_db)?;
+        let num_inputs =
+            rewrite_rows_in_layout::<TransactionInputRowData, InputRowWithoutCovenant>(txn, &self.inputs_db)?;
+        let num_orphans = rewrite_rows_in_layout::<Block, BlockWithoutCovenant>(txn, &self.orphans_db)?;
+        info!(
+            target: LOG_TARGET,
+            "Added empty covenants to {} output(s), {} input(s) and {} orphan block(s)",
+            num_outputs,
+            num_inputs,
+            num_orphans
+        );
+        Ok(num_outputs + num_inputs + num_orphans)
+    }
+
+    /// Returns the number of outputs, inputs and orphan blocks that were stored before encrypted data was added to
+    /// output features
+    pub(super) fn count_rows_without_encrypted_data(
+        &self,
+        txn: &ConstTransaction<'_>,
+    ) -> Result<u64, ChainStorageError> {
+        let num_outputs =
+            count_rows_in_layout::<TransactionOutputRowData, OutputRowWithoutEncryptedData>(txn, &self.utxos_db)?;
+        let num_inputs =
+            count_rows_in_layout::<TransactionInputRowData, InputRowWithoutEncryptedData>(txn, &self.inputs_db)?;
+        let num_orphans = count_rows_in_layout::<Block, BlockWithoutEncryptedData>(txn, &self.orphans_db)?;
+        Ok(num_outputs + num_inputs + num_orphans)
+    }
+
+    /// Rewrites the outputs, inputs and orphan blocks that were stored before encrypted data was added to output
+    /// features, with empty encrypted data
+    pub(super) fn add_empty_encrypted_data(&self, txn: &WriteTransaction<'_>) -> Result<u64, ChainStorageError> {
+        let num_outputs =
+            rewrite_rows_in_layout::<TransactionOutputRowData, OutputRowWithoutEncryptedData>(txn, &self.utxos_db)?;
+        let num_inputs =
+            rewrite_rows_in_layout::<TransactionInputRowData, InputRowWithoutEncryptedData>(txn, &self.inputs_db)?;
+        let num_orphans = rewrite_rows_in_layout::<Block, BlockWithoutEncryptedData>(txn, &self.orphans_db)?;
+        info!(
+            target: LOG_TARGET,
+            "Added empty encrypted data to {} output(s), {} input(s) and {} orphan block(s)",
+            num_outputs,
+            num_inputs,
+            num_orphans
+        );
+        Ok(num_outputs + num_inputs + num_orphans)
+    }
+
+    /// Returns the number of outputs, inputs and orphan blocks that were stored before the parent public key and
+    /// unique id were added to output features
+    pub(super) fn count_rows_without_unique_ids(&self, txn: &ConstTransaction<'_>) -> Result<u64, ChainStorageError> {
+        let num_outputs =
+            count_rows_in_layout::<TransactionOutputRowData, OutputRowWithoutUniqueId>(txn, &self.utxos_db)?;
+        let num_inputs =
+            count_rows_in_layout::<TransactionInputRowData, InputRowWithoutUniqueId>(txn, &self.inputs_db)?;
+        let num_orphans = count_rows_in_layout::<Block, BlockWithoutUniqueId>(txn, &self.orphans_db)?;
+        Ok(num_outputs + num_inputs + num_orphans)
+    }
+
+    /// Rewrites the outputs, inputs and orphan blocks that were stored before the parent public key and unique id were
+    /// added to output features, without a parent public key or unique id
+    pub(super) fn add_empty_unique_ids(&self, txn: &WriteTransaction<'_>) -> Result<u64, ChainStorageError> {
+        let num_outputs =
+            rewrite_rows_in_layout::<TransactionOutputRowData, OutputRowWithoutUniqueId>(txn, &self.utxos_db)?;
+        let num_inputs =
+            rewrite_rows_in_layout::<TransactionInputRowData, InputRowWithoutUniqueId>(txn, &self.inputs_db)?;
+        let num_orphans = rewrite_rows_in_layout::<Block, BlockWithoutUniqueId>(txn, &self.orphans_db)?;
+        info!(
+            target: LOG_TARGET,
+            "Added empty unique ids to {} output(s), {} input(s) and {} orphan block(s)",
+            num_outputs,
+            num_inputs,
+            num_orphans
+        );
+        Ok(num_outputs + num_inputs + num_orphans)
+    }
+
+    fn all_dbs(&self) -> [(&'static str, &DatabaseRef); 24] {
+        [
+            (LMDB_DB_METADATA, &self.metadata_db),
+            (LMDB_DB_HEADERS, &self.headers_db),
+            (LMDB_DB_HEADER_ACCUMULATED_DATA, &self.header_accumulated_data_db),
+            (LMDB_DB_BLOCK_ACCUMULATED_DATA, &self.block_accumulated_data_db),
+            (LMDB_DB_BLOCK_HASHES, &self.block_hashes_db),
+            (LMDB_DB_UTXOS, &self.utxos_db),
+            (LMDB_DB_INPUTS, &self.inputs_db),
+            (LMDB_DB_TXOS_HASH_TO_INDEX, &self.txos_hash_to_index_db),
+            (LMDB_DB_KERNELS, &self.kernels_db),
+            (LMDB_DB_KERNEL_EXCESS_INDEX, &self.kernel_excess_index),
+            (LMDB_DB_KERNEL_EXCESS_SIG_INDEX, &self.kernel_excess_sig_index),
+            (LMDB_DB_KERNEL_MMR_SIZE_INDEX, &self.kernel_mmr_size_index),
+            (LMDB_DB_UTXO_MMR_SIZE_INDEX, &self.output_mmr_size_index),
+            (LMDB_DB_ORPHANS, &self.orphans_db),
+            (LMDB_DB_MONERO_SEED_HEIGHT, &self.monero_seed_height_db),
+            (
+                LMDB_DB_ORPHAN_HEADER_ACCUMULATED_DATA,
+                &self.orphan_header_accumulated_data_db,
+            ),
+            (LMDB_DB_ORPHAN_CHAIN_TIPS, &self.orphan_chain_tips_db),
+            (LMDB_DB_ORPHAN_PARENT_MAP_INDEX, &self.orphan_parent_map_index),
+            (LMDB_DB_UTXO_SCRIPT_HASH_INDEX, &self.utxo_script_hash_index),
+            (LMDB_DB_UTXO_FEATURES_INDEX, &self.utxo_features_index),
+            (LMDB_DB_UTXO_COMMITMENT_INDEX, &self.utxo_commitment_index),
+            (LMDB_DB_UTXO_UNIQUE_ID_INDEX, &self.utxo_unique_id_index),
+            (LMDB_DB_UTXO_ASSET_TOKEN_INDEX, &self.utxo_asset_token_index),
+            (LMDB_DB_BAD_BLOCK_LIST, &self.bad_blocks),
+        ]
+    }
+
+    /// Returns the map usage of the LMDB environment, the number of free pages and the size of each database.
+    pub fn get_storage_stats(&self) -> Result<LMDBStoreStats, ChainStorageError> {
+        let stats = LMDBStore::get_env_stats(&self.env, self.all_dbs().iter().map(|(name, db)| (*name, &***db)))?;
+        Ok(stats)
+    }
+
+    /// Writes a compacted copy of the database alongside the live database files. Only a read transaction is used,
+    /// so the database can be read from while the copy is made. The copy replaces the live database when passed to
+    /// [swap_compacted_copy](#method.swap_compacted_copy).
+    pub fn create_compacted_copy(&self) -> Result<CompactedCopy, ChainStorageError> {
+        let path = self.path.join(COMPACTION_DIR);
+        // Remove the remains of a previous compaction that did not complete
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        fs::create_dir_all(&path)?;
+
+        let timer = Instant::now();
+        let last_txn_id = self.env.info()?.last_txnid;
+        LMDBStore::copy_compacted(&self.env, &path)?;
+        info!(
+            target: LOG_TARGET,
+            "Compacted copy of the blockchain database written to {} in {:.2?}",
+            path.display(),
+            timer.elapsed()
+        );
+        Ok(CompactedCopy { path, last_txn_id })
+    }
+
+    /// Atomically replaces the database files with the compacted copy and reopens the environment. The copy is
+    /// discarded and an error returned if the database has been written to since the copy was made, as those writes
+    /// would otherwise be lost.
+    pub fn swap_compacted_copy(&mut self, copy: CompactedCopy) -> Result<CompactionResult, ChainStorageError> {
+        if self.env.info()?.last_txnid != copy.last_txn_id {
+            let _ = fs::remove_dir_all(&copy.path);
+            return Err(ChainStorageError::InvalidOperation(
+                "The blockchain database was written to during compaction".to_string(),
+            ));
+        }
+
+        let data_file = self.path.join(LMDB_DATA_FILE);
+        let lock_file = self.path.join(LMDB_LOCK_FILE);
+        let backup_path = self.path.join(COMPACTION_BACKUP_DIR);
+        let backup_data_file = backup_path.join(LMDB_DATA_FILE);
+        let backup_lock_file = backup_path.join(LMDB_LOCK_FILE);
+        let size_before_bytes = fs::metadata(&data_file)?.len();
+        let size_after_bytes = fs::metadata(copy.path.join(LMDB_DATA_FILE))?.len();
+
+        // The open environment keeps its handles to the moved files, so it remains valid until it is replaced. Both
+        // files are moved so that the new environment does not share a lock file with the old one.
+        fs::create_dir_all(&backup_path)?;
+        fs::rename(&data_file, &backup_data_file)?;
+        fs::rename(&lock_file, &backup_lock_file)?;
+        let reopened = fs::rename(copy.path.join(LMDB_DATA_FILE), &data_file)
+            .map_err(ChainStorageError::from)
+            .and_then(|_| open_lmdb_store(&self.path, self.env_config.clone()))
+            .and_then(|store| Self::with_file_lock(store, self._file_lock.clone()));
+
+        match reopened {
+            Ok(db) => {
+                *self = db;
+                let _ = fs::remove_dir_all(&backup_path);
+                let _ = fs::remove_dir_all(&copy.path);
+                info!(
+                    target: LOG_TARGET,
+                    "Blockchain database compacted from {} MB to {} MB",
+                    size_before_bytes / BYTES_PER_MB,
+                    size_after_bytes / BYTES_PER_MB
+                );
+                Ok(CompactionResult {
+                    size_before_bytes,
+                    size_after_bytes,
+                })
+            },
+            Err(err) => {
+                error!(
+                    target: LOG_TARGET,
+                    "Could not open the compacted blockchain database, restoring the original: {}", err
+                );
+                let _ = fs::remove_file(&lock_file);
+                fs::rename(&backup_data_file, &data_file)?;
+                fs::rename(&backup_lock_file, &lock_file)?;
+                let _ = fs::remove_dir_all(&backup_path);
+                let _ = fs::remove_dir_all(&copy.path);
+                Err(err)
+            },
+        }
     }
 
     /// Try to establish a read lock on the LMDB database. If an exclusive write lock has been previously acquired, this
@@ -189,6 +766,11 @@ impl LMDBDatabase {
             trace!(target: LOG_TARGET, "[apply_db_transaction] WriteOperation: {}", op);
             match op {
                 InsertOrphanBlock(block) => self.insert_orphan_block(&write_txn, &block)?,
+                InsertOrphanBlockIfNotExists(block) => {
+                    if !lmdb_exists(&write_txn, &self.orphans_db, block.hash().as_slice())? {
+                        self.insert_orphan_block(&write_txn, &block)?;
+                    }
+                },
                 InsertChainHeader { header } => {
                     self.insert_header(&write_txn, header.header(), header.accumulated_data())?;
                 },
@@ -254,9 +836,15 @@ impl LMDBDatabase {
                 DeleteBlock(hash) => {
                     self.delete_block_body(&write_txn, hash)?;
                 },
+                RebuildSecondaryIndexes => {
+                    self.rebuild_secondary_indexes(&write_txn)?;
+                },
                 InsertMoneroSeedHeight(data, height) => {
                     self.insert_monero_seed_height(&write_txn, &data, height)?;
                 },
+                InsertBadBlock { hash, height } => {
+                    lmdb_replace(&write_txn, &self.bad_blocks, &hash, &height)?;
+                },
                 SetAccumulatedDataForOrphan(chain_header) => {
                     self.set_accumulated_data_for_orphan(
                         &write_txn,
@@ -303,7 +891,39 @@ impl LMDBDatabase {
                     height,
                     hash,
                     accumulated_difficulty,
+                    accumulated_monero_difficulty,
+                    accumulated_sha_difficulty,
+                } => {
+                    self.set_metadata(&write_txn, MetadataKey::ChainHeight, MetadataValue::ChainHeight(height))?;
+                    self.set_metadata(&write_txn, MetadataKey::BestBlock, MetadataValue::BestBlock(hash))?;
+                    self.set_metadata(
+                        &write_txn,
+                        MetadataKey::AccumulatedWork,
+                        MetadataValue::AccumulatedWork(accumulated_difficulty),
+                    )?;
+                    self.set_metadata(
+                        &write_txn,
+                        MetadataKey::AccumulatedPowWork,
+                        MetadataValue::AccumulatedPowWork(accumulated_monero_difficulty, accumulated_sha_difficulty),
+                    )?;
+                },
+                SetBestBlockIfEqual {
+                    expected_hash,
+                    height,
+                    hash,
+                    accumulated_difficulty,
+                    accumulated_monero_difficulty,
+                    accumulated_sha_difficulty,
                 } => {
+                    // Read within the write transaction so that no other writer can change the tip in between
+                    let best_block = fetch_best_block(&write_txn, &self.metadata_db)?;
+                    if best_block != expected_hash {
+                        return Err(ChainStorageError::ConditionalWriteFailed(format!(
+                            "Expected best block {} but it is {}",
+                            expected_hash.to_hex(),
+                            best_block.to_hex()
+                        )));
+                    }
                     self.set_metadata(&write_txn, MetadataKey::ChainHeight, MetadataValue::ChainHeight(height))?;
                     self.set_metadata(&write_txn, MetadataKey::BestBlock, MetadataValue::BestBlock(hash))?;
                     self.set_metadata(
@@ -311,6 +931,11 @@ impl LMDBDatabase {
                         MetadataKey::AccumulatedWork,
                         MetadataValue::AccumulatedWork(accumulated_difficulty),
                     )?;
+                    self.set_metadata(
+                        &write_txn,
+                        MetadataKey::AccumulatedPowWork,
+                        MetadataValue::AccumulatedPowWork(accumulated_monero_difficulty, accumulated_sha_difficulty),
+                    )?;
                 },
                 SetPruningHorizonConfig(pruning_horizon) => {
                     self.set_metadata(
@@ -358,6 +983,9 @@ impl LMDBDatabase {
         let result = output.output.take();
         // output.output is None
         lmdb_replace(txn, &self.utxos_db, key_string, &output)?;
+        if let Some(ref pruned) = result {
+            self.delete_output_indexes(txn, pruned, output.mmr_position)?;
+        }
         Ok(result)
     }
 
@@ -382,6 +1010,8 @@ impl LMDBDatabase {
             &(mmr_position, key_string.clone()),
             "txos_hash_to_index_db",
         )?;
+        self.output_filter.insert(output_hash.as_slice());
+        self.insert_output_indexes(txn, &output, mmr_position, &key_string)?;
         lmdb_insert(
             txn,
             &*self.utxos_db,
@@ -422,6 +1052,7 @@ impl LMDBDatabase {
             &(mmr_position, key_string.clone()),
             "txos_hash_to_index_db",
         )?;
+        self.output_filter.insert(output_hash.as_slice());
         lmdb_insert(
             txn,
             &*self.utxos_db,
@@ -466,6 +1097,7 @@ impl LMDBDatabase {
             &(header_hash.clone(), mmr_position, hash.clone()),
             "kernel_excess_sig_index",
         )?;
+        self.kernel_excess_sig_filter.insert(excess_sig_key.as_slice());
 
         lmdb_insert(
             txn,
@@ -709,6 +1341,9 @@ impl LMDBDatabase {
         for utxo in rows {
             trace!(target: LOG_TARGET, "Deleting UTXO `{}`", to_hex(&utxo.hash));
             lmdb_delete(&write_txn, &self.txos_hash_to_index_db, utxo.hash.as_slice())?;
+            if let Some(ref output) = utxo.output {
+                self.delete_output_indexes(&write_txn, output, utxo.mmr_position)?;
+            }
         }
         debug!(target: LOG_TARGET, "Deleting kernels...");
         let kernels =
@@ -735,6 +1370,222 @@ impl LMDBDatabase {
         Ok(())
     }
 
+    fn rebuild_secondary_indexes(&self, txn: &WriteTransaction<'_>) -> Result<(), ChainStorageError> {
+        info!(target: LOG_TARGET, "Rebuilding secondary indexes");
+        lmdb_clear(txn, &self.kernel_excess_index)?;
+        lmdb_clear(txn, &self.kernel_excess_sig_index)?;
+        lmdb_clear(txn, &self.txos_hash_to_index_db)?;
+
+        let kernels = lmdb_filter_map_values(txn, &self.kernels_db, |row: TransactionKernelRowData| Ok(Some(row)))?;
+        let num_kernels = kernels.len();
+        for (i, row) in kernels.into_iter().enumerate() {
+            let index_value = (row.header_hash.clone(), row.mmr_position, row.hash.clone());
+            lmdb_replace(
+                txn,
+                &self.kernel_excess_index,
+                row.kernel.excess.as_bytes(),
+                &index_value,
+            )?;
+            let mut excess_sig_key = Vec::<u8>::new();
+            excess_sig_key.extend(row.kernel.excess_sig.get_public_nonce().as_bytes());
+            excess_sig_key.extend(row.kernel.excess_sig.get_signature().as_bytes());
+            lmdb_replace(
+                txn,
+                &self.kernel_excess_sig_index,
+                excess_sig_key.as_slice(),
+                &index_value,
+            )?;
+            self.kernel_excess_sig_filter.insert(excess_sig_key.as_slice());
+            if (i + 1) % 10_000 == 0 {
+                info!(target: LOG_TARGET, "Reindexed {}/{} kernels", i + 1, num_kernels);
+            }
+        }
+        info!(target: LOG_TARGET, "Reindexed {} kernel(s)", num_kernels);
+
+        let outputs = lmdb_filter_map_values(txn, &self.utxos_db, |row: TransactionOutputRowData| {
+            let key = OutputKey::new(row.header_hash.clone(), row.mmr_position).get_key();
+            Ok(Some((row.hash, row.mmr_position, key)))
+        })?;
+        let num_outputs = outputs.len();
+        for (i, (hash, mmr_position, key)) in outputs.into_iter().enumerate() {
+            lmdb_replace(txn, &self.txos_hash_to_index_db, hash.as_slice(), &(mmr_position, key))?;
+            self.output_filter.insert(hash.as_slice());
+            if (i + 1) % 10_000 == 0 {
+                info!(target: LOG_TARGET, "Reindexed {}/{} outputs", i + 1, num_outputs);
+            }
+        }
+        info!(target: LOG_TARGET, "Reindexed {} output(s)", num_outputs);
+        self.rebuild_output_indexes(txn)?;
+        Ok(())
+    }
+
+    fn insert_output_indexes(
+        &self,
+        txn: &WriteTransaction<'_>,
+        output: &TransactionOutput,
+        mmr_position: u32,
+        key_string: &str,
+    ) -> Result<(), ChainStorageError> {
+        let script_hash = output_script_hash(output)?;
+        lmdb_replace(
+            txn,
+            &self.utxo_script_hash_index,
+            output_index_key(script_hash.as_slice(), mmr_position).as_slice(),
+            &key_string.to_string(),
+        )?;
+        lmdb_replace(
+            txn,
+            &self.utxo_commitment_index,
+            output_index_key(output.commitment.as_bytes(), mmr_position).as_slice(),
+            &key_string.to_string(),
+        )?;
+        // Outputs without flags make up most of the set and are not indexed
+        if !output.features.flags.is_empty() {
+            lmdb_replace(
+                txn,
+                &self.utxo_features_index,
+                output_index_key(&[output.features.flags.bits()], mmr_position).as_slice(),
+                &key_string.to_string(),
+            )?;
+        }
+        if let Some(id) = output.features.unique_asset_id() {
+            lmdb_replace(
+                txn,
+                &self.utxo_unique_id_index,
+                output_index_key(&unique_id_index_prefix(&id), mmr_position).as_slice(),
+                &key_string.to_string(),
+            )?;
+        }
+        if let Some(asset_public_key) = asset_token_public_key(output) {
+            lmdb_replace(
+                txn,
+                &self.utxo_asset_token_index,
+                output_index_key(asset_public_key.as_bytes(), mmr_position).as_slice(),
+                &key_string.to_string(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn delete_output_indexes(
+        &self,
+        txn: &WriteTransaction<'_>,
+        output: &TransactionOutput,
+        mmr_position: u32,
+    ) -> Result<(), ChainStorageError> {
+        let script_hash = output_script_hash(output)?;
+        lmdb_delete(
+            txn,
+            &self.utxo_script_hash_index,
+            output_index_key(script_hash.as_slice(), mmr_position).as_slice(),
+        )?;
+        lmdb_delete(
+            txn,
+            &self.utxo_commitment_index,
+            output_index_key(output.commitment.as_bytes(), mmr_position).as_slice(),
+        )?;
+        if !output.features.flags.is_empty() {
+            lmdb_delete(
+                txn,
+                &self.utxo_features_index,
+                output_index_key(&[output.features.flags.bits()], mmr_position).as_slice(),
+            )?;
+        }
+        if let Some(id) = output.features.unique_asset_id() {
+            lmdb_delete(
+                txn,
+                &self.utxo_unique_id_index,
+                output_index_key(&unique_id_index_prefix(&id), mmr_position).as_slice(),
+            )?;
+        }
+        if let Some(asset_public_key) = asset_token_public_key(output) {
+            lmdb_delete(
+                txn,
+                &self.utxo_asset_token_index,
+                output_index_key(asset_public_key.as_bytes(), mmr_position).as_slice(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the output script hash, features, commitment, unique id and asset token indexes from the outputs that
+    /// have not been pruned
+    fn rebuild_output_indexes(&self, txn: &WriteTransaction<'_>) -> Result<u64, ChainStorageError> {
+        lmdb_clear(txn, &self.utxo_script_hash_index)?;
+        lmdb_clear(txn, &self.utxo_features_index)?;
+        lmdb_clear(txn, &self.utxo_commitment_index)?;
+        lmdb_clear(txn, &self.utxo_unique_id_index)?;
+        lmdb_clear(txn, &self.utxo_asset_token_index)?;
+        // The indexes are built by the first migration step, before the outputs are rewritten in the current layout
+        let outputs = lmdb_filter_map_rows(txn, &self.utxos_db, |_, val| {
+            let row = TransactionOutputRowData::from_stored_bytes(val)?;
+            let key = OutputKey::new(row.header_hash.clone(), row.mmr_position).get_key();
+            let mmr_position = row.mmr_position;
+            Ok(row.output.map(|output| (output, mmr_position, key)))
+        })?;
+        let num_outputs = outputs.len();
+        for (output, mmr_position, key) in outputs {
+            self.insert_output_indexes(txn, &output, mmr_position, &key)?;
+        }
+        info!(
+            target: LOG_TARGET,
+            "Rebuilt script hash, features, commitment, unique id and asset token indexes for {} output(s)",
+            num_outputs
+        );
+        Ok(num_outputs as u64)
+    }
+
+    /// Fetches up to `limit` unspent outputs with index keys that start with `prefix`, from `start_mmr_position`
+    fn fetch_indexed_utxos(
+        &self,
+        index: &DatabaseRef,
+        prefix: &[u8],
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError> {
+        let rows = self.fetch_indexed_utxo_rows(index, prefix, start_mmr_position, limit, deleted)?;
+        Ok(rows.into_iter().map(|row| (row.output, row.mmr_position)).collect())
+    }
+
+    /// As for `fetch_indexed_utxos`, but returns where each output was mined along with the output
+    fn fetch_indexed_utxo_rows(
+        &self,
+        index: &DatabaseRef,
+        prefix: &[u8],
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<AssetOutput>, ChainStorageError> {
+        let txn = self.read_transaction()?;
+        let mut result = Vec::new();
+        if limit == 0 {
+            return Ok(result);
+        }
+        let start = output_index_key(prefix, start_mmr_position);
+        lmdb_visit_prefix_from(&txn, index, prefix, &start, |key: String| {
+            let row: TransactionOutputRowData = lmdb_get(&txn, &self.utxos_db, key.as_str())?.ok_or_else(|| {
+                ChainStorageError::DataInconsistencyDetected {
+                    function: "fetch_indexed_utxos",
+                    details: format!("Indexed output {} does not exist", key),
+                }
+            })?;
+            if deleted.contains(row.mmr_position) {
+                return Ok(true);
+            }
+            if let Some(output) = row.output {
+                result.push(AssetOutput {
+                    output,
+                    mmr_position: row.mmr_position,
+                    mined_height: row.mined_height,
+                    header_hash: row.header_hash,
+                });
+            }
+            Ok(result.len() < limit)
+        })?;
+        Ok(result)
+    }
+
     fn delete_orphan(&self, txn: &WriteTransaction<'_>, hash: HashOutput) -> Result<(), ChainStorageError> {
         if let Some(orphan) = lmdb_get::<_, Block>(&txn, &self.orphans_db, hash.as_slice())? {
             let parent_hash = orphan.header.prev_hash;
@@ -1019,6 +1870,7 @@ impl LMDBDatabase {
         output_positions: Vec<u32>,
         horizon: u64,
     ) -> Result<(), ChainStorageError> {
+        let mut stats = fetch_pruning_stats(&write_txn, &self.metadata_db)?;
         for pos in output_positions {
             let (_height, hash) = lmdb_first_after::<_, (u64, Vec<u8>)>(
                 &write_txn,
@@ -1028,8 +1880,16 @@ impl LMDBDatabase {
             .or_not_found("BlockHeader", "mmr_position", pos.to_string())?;
             let key = OutputKey::new(hash, pos);
             debug!(target: LOG_TARGET, "Pruning output: {}", key.get_key());
-            self.prune_output(&write_txn, &key)?;
+            if let Some(output) = self.prune_output(&write_txn, &key)? {
+                stats.outputs_pruned += 1;
+                stats.bytes_reclaimed += serialized_size(&output)?;
+            }
         }
+        self.set_metadata(
+            &write_txn,
+            MetadataKey::PruningStats,
+            MetadataValue::PruningStats(stats),
+        )?;
 
         self.set_metadata(
             &write_txn,
@@ -1093,15 +1953,78 @@ impl LMDBDatabase {
 }
 
 pub fn create_lmdb_database<P: AsRef<Path>>(path: P, config: LMDBConfig) -> Result<LMDBDatabase, ChainStorageError> {
-    let flags = db::CREATE;
     let _ = std::fs::create_dir_all(&path);
 
     let file_lock = acquire_exclusive_file_lock(&path.as_ref().to_path_buf())?;
 
-    let lmdb_store = LMDBBuilder::new()
+    let lmdb_store = open_lmdb_store(path, config)?;
+    LMDBDatabase::new(lmdb_store, file_lock)
+}
+
+/// Opens the database at `path` and reports the migration steps that would be run when it is next opened with
+/// `create_lmdb_database`, without running them
+pub fn dry_run_lmdb_migrations<P: AsRef<Path>>(
+    path: P,
+    config: LMDBConfig,
+) -> Result<MigrationReport, ChainStorageError> {
+    let _ = std::fs::create_dir_all(&path);
+
+    let file_lock = acquire_exclusive_file_lock(&path.as_ref().to_path_buf())?;
+
+    let lmdb_store = open_lmdb_store(path, config)?;
+    LMDBDatabase::open(lmdb_store, Arc::new(file_lock))?.plan_migrations()
+}
+
+fn output_script_hash(output: &TransactionOutput) -> Result<Vec<u8>, ChainStorageError> {
+    output
+        .script
+        .as_hash::<HashDigest>()
+        .map(|hash| hash.to_vec())
+        .map_err(|e| ChainStorageError::InvalidOperation(format!("Could not hash output script: {}", e)))
+}
+
+/// The public key of the asset that issued the token carried by `output`, if the output carries a token. Tokens are
+/// indexed by this key in the asset token index.
+fn asset_token_public_key(output: &TransactionOutput) -> Option<&PublicKey> {
+    let features = &output.features;
+    if features.is_asset_registration() || features.unique_id.is_none() {
+        return None;
+    }
+    features.parent_public_key.as_ref()
+}
+
+/// The indexed value of an output in the unique id index. The parent public key comes first, so that the tokens of an
+/// asset share a prefix, followed by the length prefixed unique id, so that no unique id is a prefix of another.
+fn unique_id_index_prefix(id: &UniqueAssetId) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(34 + id.unique_id.len());
+    match id.parent_public_key {
+        Some(ref parent_public_key) => {
+            prefix.push(1);
+            prefix.extend_from_slice(parent_public_key.as_bytes());
+        },
+        None => prefix.push(0),
+    }
+    // Unique ids are limited to MAX_UNIQUE_ID_BYTES by consensus
+    prefix.push(id.unique_id.len() as u8);
+    prefix.extend_from_slice(&id.unique_id);
+    prefix
+}
+
+/// Keys in the output script hash, features, commitment and unique id indexes are the indexed value followed by the
+/// big-endian MMR position, so that outputs with the same value are ordered by MMR position
+fn output_index_key(value: &[u8], mmr_position: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(value.len() + 4);
+    key.extend_from_slice(value);
+    key.extend_from_slice(&mmr_position.to_be_bytes());
+    key
+}
+
+fn open_lmdb_store<P: AsRef<Path>>(path: P, config: LMDBConfig) -> Result<LMDBStore, ChainStorageError> {
+    let flags = db::CREATE;
+    LMDBBuilder::new()
         .set_path(path)
         .set_env_config(config)
-        .set_max_number_of_databases(20)
+        .set_max_number_of_databases(25)
         .add_database(LMDB_DB_METADATA, flags | db::INTEGERKEY)
         .add_database(LMDB_DB_HEADERS, flags | db::INTEGERKEY)
         .add_database(LMDB_DB_HEADER_ACCUMULATED_DATA, flags | db::INTEGERKEY)
@@ -1120,9 +2043,14 @@ pub fn create_lmdb_database<P: AsRef<Path>>(path: P, config: LMDBConfig) -> Resu
         .add_database(LMDB_DB_MONERO_SEED_HEIGHT, flags)
         .add_database(LMDB_DB_ORPHAN_CHAIN_TIPS, flags)
         .add_database(LMDB_DB_ORPHAN_PARENT_MAP_INDEX, flags | db::DUPSORT)
+        .add_database(LMDB_DB_UTXO_SCRIPT_HASH_INDEX, flags)
+        .add_database(LMDB_DB_UTXO_FEATURES_INDEX, flags)
+        .add_database(LMDB_DB_UTXO_COMMITMENT_INDEX, flags)
+        .add_database(LMDB_DB_UTXO_UNIQUE_ID_INDEX, flags)
+        .add_database(LMDB_DB_UTXO_ASSET_TOKEN_INDEX, flags)
+        .add_database(LMDB_DB_BAD_BLOCK_LIST, flags)
         .build()
-        .map_err(|err| ChainStorageError::CriticalError(format!("Could not create LMDB store:{}", err)))?;
-    LMDBDatabase::new(lmdb_store, file_lock)
+        .map_err(|err| ChainStorageError::CriticalError(format!("Could not create LMDB store:{}", err)))
 }
 
 pub fn create_recovery_lmdb_database<P: AsRef<Path>>(path: P) -> Result<(), ChainStorageError> {
@@ -1175,6 +2103,10 @@ impl BlockchainBackend for LMDBDatabase {
                     num_operations,
                     mark.elapsed()
                 );
+                if self.output_filter.is_full() || self.kernel_excess_sig_filter.is_full() {
+                    // The false positive rate rises quickly beyond the capacity the filters were sized for
+                    self.rebuild_existence_filters()?;
+                }
                 Ok(())
             },
             Err(e) => {
@@ -1442,10 +2374,13 @@ impl BlockchainBackend for LMDBDatabase {
         &self,
         excess_sig: &Signature,
     ) -> Result<Option<(TransactionKernel, HashOutput)>, ChainStorageError> {
-        let txn = self.read_transaction()?;
         let mut key = Vec::<u8>::new();
         key.extend(excess_sig.get_public_nonce().as_bytes());
         key.extend(excess_sig.get_signature().as_bytes());
+        if !self.kernel_excess_sig_filter.may_contain(key.as_slice()) {
+            return Ok(None);
+        }
+        let txn = self.read_transaction()?;
         if let Some((header_hash, mmr_position, hash)) =
             lmdb_get::<_, (HashOutput, u32, HashOutput)>(&txn, &self.kernel_excess_sig_index, key.as_slice())?
         {
@@ -1453,6 +2388,7 @@ impl BlockchainBackend for LMDBDatabase {
             Ok(lmdb_get(&txn, &self.kernels_db, key.as_str())?
                 .map(|kernel: TransactionKernelRowData| (kernel.kernel, header_hash)))
         } else {
+            self.kernel_excess_sig_filter.record_false_positive();
             Ok(None)
         }
     }
@@ -1616,11 +2552,121 @@ impl BlockchainBackend for LMDBDatabase {
         Ok((result, difference_bitmap))
     }
 
+    fn fetch_utxos_by_script_hash(
+        &self,
+        script_hash: &[u8],
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError> {
+        self.fetch_indexed_utxos(
+            &self.utxo_script_hash_index,
+            script_hash,
+            start_mmr_position,
+            limit,
+            deleted,
+        )
+    }
+
+    fn fetch_utxos_by_features(
+        &self,
+        flags: OutputFlags,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError> {
+        if flags.is_empty() {
+            return Err(ChainStorageError::InvalidQuery(
+                "Outputs without flags are not indexed".to_string(),
+            ));
+        }
+        self.fetch_indexed_utxos(
+            &self.utxo_features_index,
+            &[flags.bits()],
+            start_mmr_position,
+            limit,
+            deleted,
+        )
+    }
+
+    fn fetch_unspent_output_by_commitment(
+        &self,
+        commitment: &Commitment,
+        deleted: &Bitmap,
+    ) -> Result<Option<(TransactionOutput, u32)>, ChainStorageError> {
+        let mut outputs =
+            self.fetch_indexed_utxos(&self.utxo_commitment_index, commitment.as_bytes(), 0, 1, deleted)?;
+        Ok(outputs.pop())
+    }
+
+    fn fetch_unspent_output_by_unique_id(
+        &self,
+        id: &UniqueAssetId,
+        deleted: &Bitmap,
+    ) -> Result<Option<(TransactionOutput, u32)>, ChainStorageError> {
+        let mut outputs =
+            self.fetch_indexed_utxos(&self.utxo_unique_id_index, &unique_id_index_prefix(id), 0, 1, deleted)?;
+        Ok(outputs.pop())
+    }
+
+    fn fetch_asset_registrations(
+        &self,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<AssetOutput>, ChainStorageError> {
+        self.fetch_indexed_utxo_rows(
+            &self.utxo_features_index,
+            &[OutputFlags::ASSET_REGISTRATION.bits()],
+            start_mmr_position,
+            limit,
+            deleted,
+        )
+    }
+
+    fn fetch_asset_registration(
+        &self,
+        asset_public_key: &PublicKey,
+        deleted: &Bitmap,
+    ) -> Result<Option<AssetOutput>, ChainStorageError> {
+        let id = UniqueAssetId {
+            parent_public_key: Some(asset_public_key.clone()),
+            unique_id: Vec::new(),
+        };
+        let mut outputs =
+            self.fetch_indexed_utxo_rows(&self.utxo_unique_id_index, &unique_id_index_prefix(&id), 0, 1, deleted)?;
+        Ok(outputs.pop())
+    }
+
+    fn fetch_asset_tokens(
+        &self,
+        asset_public_key: &PublicKey,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<AssetOutput>, ChainStorageError> {
+        self.fetch_indexed_utxo_rows(
+            &self.utxo_asset_token_index,
+            asset_public_key.as_bytes(),
+            start_mmr_position,
+            limit,
+            deleted,
+        )
+    }
+
     fn fetch_output(
         &self,
         output_hash: &HashOutput,
     ) -> Result<Option<(TransactionOutput, u32, u64)>, ChainStorageError> {
         debug!(target: LOG_TARGET, "Fetch output: {}", output_hash.to_hex());
+        if !self.output_filter.may_contain(output_hash.as_slice()) {
+            debug!(
+                target: LOG_TARGET,
+                "Fetch output: {} NOT found in existence filter",
+                output_hash.to_hex()
+            );
+            return Ok(None);
+        }
         let txn = self.read_transaction()?;
         if let Some((index, key)) =
             lmdb_get::<_, (u32, String)>(&txn, &self.txos_hash_to_index_db, output_hash.as_slice())?
@@ -1653,6 +2699,7 @@ impl BlockchainBackend for LMDBDatabase {
                 "Fetch output: {} NOT found in index",
                 output_hash.to_hex()
             );
+            self.output_filter.record_false_positive();
             Ok(None)
         }
     }
@@ -1895,23 +2942,129 @@ impl BlockchainBackend for LMDBDatabase {
         Ok(lmdb_get(&txn, &self.monero_seed_height_db, seed)?.unwrap_or(0))
     }
 
+    fn bad_block_exists(&self, block_hash: HashOutput) -> Result<bool, ChainStorageError> {
+        let txn = self.read_transaction()?;
+        lmdb_exists(&txn, &self.bad_blocks, &block_hash)
+    }
+
     fn fetch_horizon_data(&self) -> Result<Option<HorizonData>, ChainStorageError> {
         let txn = self.read_transaction()?;
         fetch_horizon_data(&txn, &self.metadata_db)
     }
+
+    fn open_read_snapshot(&self) -> Result<Box<dyn ChainReadSnapshot>, ChainStorageError> {
+        Ok(Box::new(LMDBReadSnapshot {
+            txn: ReadTransaction::new(self.env.clone())?,
+            metadata_db: self.metadata_db.clone(),
+            headers_db: self.headers_db.clone(),
+            header_accumulated_data_db: self.header_accumulated_data_db.clone(),
+            block_hashes_db: self.block_hashes_db.clone(),
+        }))
+    }
+}
+
+/// A read transaction held open for the lifetime of a [BlockchainSnapshot](crate::chain_storage::BlockchainSnapshot).
+/// LMDB read transactions see the database as it was when the transaction began, regardless of later writes.
+struct LMDBReadSnapshot {
+    txn: ReadTransaction<'static>,
+    metadata_db: DatabaseRef,
+    headers_db: DatabaseRef,
+    header_accumulated_data_db: DatabaseRef,
+    block_hashes_db: DatabaseRef,
+}
+
+// The environment is opened with MDB_NOTLS, so a read transaction is not tied to the thread that began it and may be
+// used from another thread. The snapshot is only ever used by one thread at a time, behind the `BlockchainSnapshot`
+// mutex.
+unsafe impl Send for LMDBReadSnapshot {}
+
+impl ChainReadSnapshot for LMDBReadSnapshot {
+    fn fetch_tip_header(&self) -> Result<ChainHeader, ChainStorageError> {
+        let height = fetch_chain_height(&self.txn, &self.metadata_db)?;
+        let header =
+            lmdb_get(&self.txn, &self.headers_db, &height)?.ok_or_else(|| ChainStorageError::ValueNotFound {
+                entity: "Header".to_string(),
+                field: "height".to_string(),
+                value: height.to_string(),
+            })?;
+        let accumulated_data = lmdb_get(&self.txn, &self.header_accumulated_data_db, &height)?.ok_or_else(|| {
+            ChainStorageError::ValueNotFound {
+                entity: "BlockHeaderAccumulatedData".to_string(),
+                field: "height".to_string(),
+                value: height.to_string(),
+            }
+        })?;
+        ChainHeader::try_construct(header, accumulated_data).ok_or_else(|| {
+            ChainStorageError::DataInconsistencyDetected {
+                function: "LMDBReadSnapshot::fetch_tip_header",
+                details: format!("Accumulated data mismatch at height #{}", height),
+            }
+        })
+    }
+
+    fn fetch_header_by_block_hash(&self, hash: &HashOutput) -> Result<Option<BlockHeader>, ChainStorageError> {
+        if hash.len() != BLOCK_HASH_LENGTH {
+            return Err(ChainStorageError::InvalidQuery(format!(
+                "Invalid block hash length. Expected length: {} Got: {}",
+                BLOCK_HASH_LENGTH,
+                hash.len()
+            )));
+        }
+        let height: Option<u64> = lmdb_get(&self.txn, &self.block_hashes_db, hash.as_slice())?;
+        match height {
+            Some(height) => lmdb_get(&self.txn, &self.headers_db, &height),
+            None => Ok(None),
+        }
+    }
+
+    fn fetch_headers(&self, start: u64, end_inclusive: u64) -> Result<Vec<BlockHeader>, ChainStorageError> {
+        let tip_height = fetch_chain_height(&self.txn, &self.metadata_db)?;
+        let end_inclusive = cmp::min(end_inclusive, tip_height);
+        if start > end_inclusive {
+            return Ok(Vec::new());
+        }
+        (start..=end_inclusive)
+            .map(|height| {
+                lmdb_get(&self.txn, &self.headers_db, &height)?.ok_or_else(|| ChainStorageError::ValueNotFound {
+                    entity: "BlockHeader".to_string(),
+                    field: "height".to_string(),
+                    value: height.to_string(),
+                })
+            })
+            .collect()
+    }
 }
 
 // Fetch the chain metadata
 fn fetch_metadata(txn: &ConstTransaction<'_>, db: &Database) -> Result<ChainMetadata, ChainStorageError> {
+    let (accumulated_monero_difficulty, accumulated_sha_difficulty) = fetch_accumulated_pow_work(&txn, &db)?;
     Ok(ChainMetadata::new(
         fetch_chain_height(&txn, &db)?,
         fetch_best_block(&txn, &db)?,
         fetch_pruning_horizon(&txn, &db)?,
         fetch_pruned_height(&txn, &db)?,
         fetch_accumulated_work(&txn, &db)?,
+    )
+    .with_pow_accumulated_difficulty(
+        accumulated_monero_difficulty.as_u64(),
+        accumulated_sha_difficulty.as_u64(),
     ))
 }
 
+// Fetches the accumulated difficulty of each proof of work algorithm (Monero, SHA3) from the provided metadata db.
+// Zero is returned for databases that have not recorded it yet.
+fn fetch_accumulated_pow_work(
+    txn: &ConstTransaction<'_>,
+    db: &Database,
+) -> Result<(Difficulty, Difficulty), ChainStorageError> {
+    let k = MetadataKey::AccumulatedPowWork;
+    let val: Option<MetadataValue> = lmdb_get(&txn, &db, &k.as_u32())?;
+    match val {
+        Some(MetadataValue::AccumulatedPowWork(monero, sha3)) => Ok((monero, sha3)),
+        _ => Ok((Difficulty::from(0), Difficulty::from(0))),
+    }
+}
+
 // Fetches the chain height from the provided metadata db.
 fn fetch_chain_height(txn: &ConstTransaction<'_>, db: &Database) -> Result<u64, ChainStorageError> {
     let k = MetadataKey::ChainHeight;
@@ -1992,6 +3145,55 @@ fn fetch_deleted_bitmap(txn: &ConstTransaction<'_>, db: &Database) -> Result<Del
     }
 }
 
+// Fetches the pruning stats from the provided metadata db. Databases that have never been pruned have no stats.
+fn fetch_pruning_stats(txn: &ConstTransaction<'_>, db: &Database) -> Result<PruningStats, ChainStorageError> {
+    let k = MetadataKey::PruningStats;
+    let val: Option<MetadataValue> = lmdb_get(&txn, &db, &k.as_u32())?;
+    match val {
+        Some(MetadataValue::PruningStats(stats)) => Ok(stats),
+        _ => Ok(PruningStats::default()),
+    }
+}
+
+/// Returns the number of rows of `db` that are stored in the layout `L` of an earlier schema version rather than in the
+/// layout of `T`
+fn count_rows_in_layout<T, L>(txn: &ConstTransaction<'_>, db: &Database) -> Result<u64, ChainStorageError>
+where
+    T: DeserializeOwned,
+    L: DeserializeOwned,
+{
+    let rows = lmdb_filter_map_rows(txn, db, |_, val| {
+        let is_in_layout = deserialize_exact::<T>(val).is_err() && deserialize_exact::<L>(val).is_ok();
+        Ok(if is_in_layout { Some(()) } else { None })
+    })?;
+    Ok(rows.len() as u64)
+}
+
+/// Rewrites the rows of `db` that are stored in the layout `L` of an earlier schema version in the layout of `T`.
+/// Rows in any other layout are left unchanged. Returns the number of rows that were rewritten.
+fn rewrite_rows_in_layout<T, L>(txn: &WriteTransaction<'_>, db: &Database) -> Result<u64, ChainStorageError>
+where
+    T: Serialize + DeserializeOwned,
+    L: DeserializeOwned + Into<T>,
+{
+    let rows = lmdb_filter_map_rows(txn, db, |key, val| {
+        if deserialize_exact::<T>(val).is_ok() {
+            return Ok(None);
+        }
+        Ok(deserialize_exact::<L>(val)
+            .ok()
+            .map(|row| (key.to_vec(), Into::<T>::into(row))))
+    })?;
+    for (key, row) in &rows {
+        lmdb_replace::<_, T>(txn, db, key.as_slice(), row)?;
+    }
+    Ok(rows.len() as u64)
+}
+
+fn serialized_size<T: Serialize>(value: &T) -> Result<u64, ChainStorageError> {
+    bincode::serialized_size(value).map_err(|e| ChainStorageError::AccessError(e.to_string()))
+}
+
 // Fetches the pruning horizon from the provided metadata db.
 fn fetch_pruning_horizon(txn: &ConstTransaction<'_>, db: &Database) -> Result<u64, ChainStorageError> {
     let k = MetadataKey::PruningHorizon;
@@ -2018,6 +3220,9 @@ enum MetadataKey {
     PrunedHeight,
     HorizonData,
     DeletedBitmap,
+    PruningStats,
+    SchemaVersion,
+    AccumulatedPowWork,
 }
 
 impl MetadataKey {
@@ -2037,6 +3242,9 @@ impl fmt::Display for MetadataKey {
             MetadataKey::BestBlock => f.write_str("Chain tip block hash"),
             MetadataKey::HorizonData => f.write_str("Database info"),
             MetadataKey::DeletedBitmap => f.write_str("Deleted bitmap"),
+            MetadataKey::PruningStats => f.write_str("Pruning stats"),
+            MetadataKey::SchemaVersion => f.write_str("Schema version"),
+            MetadataKey::AccumulatedPowWork => f.write_str("Accumulated work per proof of work algorithm"),
         }
     }
 }
@@ -2051,6 +3259,10 @@ enum MetadataValue {
     PrunedHeight(u64),
     HorizonData(HorizonData),
     DeletedBitmap(DeletedBitmap),
+    PruningStats(PruningStats),
+    SchemaVersion(u32),
+    /// The accumulated difficulty of the Monero and SHA3 blocks of the chain respectively
+    AccumulatedPowWork(Difficulty, Difficulty),
 }
 
 impl fmt::Display for MetadataValue {
@@ -2065,6 +3277,11 @@ impl fmt::Display for MetadataValue {
             MetadataValue::DeletedBitmap(deleted) => {
                 write!(f, "Deleted Bitmap ({} indexes)", deleted.bitmap().cardinality())
             },
+            MetadataValue::PruningStats(stats) => write!(f, "Pruning stats: {}", stats),
+            MetadataValue::SchemaVersion(version) => write!(f, "Schema version is {}", version),
+            MetadataValue::AccumulatedPowWork(monero, sha3) => {
+                write!(f, "Accumulated work is {} (Monero) and {} (SHA3)", monero, sha3)
+            },
         }
     }
 }
@@ -0,0 +1,160 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::chain_storage::{error::ChainStorageError, lmdb_db::LMDBDatabase};
+use lmdb_zero::{ConstTransaction, WriteTransaction};
+use std::{fmt, path::PathBuf};
+
+/// The schema version of databases written by this version of the node. When the layout of the database changes, this
+/// is incremented and a step that converts the previous layout is appended to `MIGRATIONS`.
+pub const LMDB_DB_SCHEMA_VERSION: u32 = 6;
+
+/// A step that converts the database from the previous schema version to `version`
+pub(super) struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    /// Returns the number of records the step would change, without changing them
+    pub count_changes: fn(&LMDBDatabase, &ConstTransaction<'_>) -> Result<u64, ChainStorageError>,
+    /// Runs the step and returns the number of records that were changed
+    pub run: fn(&LMDBDatabase, &WriteTransaction<'_>) -> Result<u64, ChainStorageError>,
+}
+
+/// The migration steps in the order that they must be run
+pub(super) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Build the output script hash, features and commitment indexes",
+        count_changes: LMDBDatabase::count_unindexed_outputs,
+        run: LMDBDatabase::build_output_indexes,
+    },
+    Migration {
+        version: 2,
+        description: "Record the accumulated difficulty of each proof of work algorithm in the chain metadata",
+        count_changes: LMDBDatabase::count_missing_accumulated_pow_work,
+        run: LMDBDatabase::record_accumulated_pow_work,
+    },
+    Migration {
+        version: 3,
+        description: "Add empty covenants to the stored outputs, inputs and orphan blocks",
+        count_changes: LMDBDatabase::count_rows_without_covenants,
+        run: LMDBDatabase::add_empty_covenants,
+    },
+    Migration {
+        version: 4,
+        description: "Add empty encrypted data to the features of stored outputs, inputs and orphan blocks",
+        count_changes: LMDBDatabase::count_rows_without_encrypted_data,
+        run: LMDBDatabase::add_empty_encrypted_data,
+    },
+    Migration {
+        version: 5,
+        description: "Add empty unique ids to the features of stored outputs, inputs and orphan blocks",
+        count_changes: LMDBDatabase::count_rows_without_unique_ids,
+        run: LMDBDatabase::add_empty_unique_ids,
+    },
+    Migration {
+        version: 6,
+        description: "Build the asset token index",
+        count_changes: LMDBDatabase::count_unindexed_asset_tokens,
+        run: LMDBDatabase::build_asset_token_index,
+    },
+];
+
+/// Returns the steps that must be run to bring a database at `from_version` up to `LMDB_DB_SCHEMA_VERSION`
+pub(super) fn pending_migrations(from_version: u32) -> impl Iterator<Item = &'static Migration> {
+    MIGRATIONS.iter().filter(move |m| m.version > from_version)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStepReport {
+    pub version: u32,
+    pub description: String,
+    pub num_changes: u64,
+}
+
+/// The migration steps that were run, or in a dry run would be run, when the database was opened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub steps: Vec<MigrationStepReport>,
+    /// The directory containing a copy of the database taken before the migration, if one was required
+    pub backup_path: Option<PathBuf>,
+    pub is_dry_run: bool,
+}
+
+impl MigrationReport {
+    pub(super) fn new(from_version: u32, is_dry_run: bool) -> Self {
+        Self {
+            from_version,
+            to_version: from_version,
+            steps: Vec::new(),
+            backup_path: None,
+            is_dry_run,
+        }
+    }
+
+    pub fn is_up_to_date(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns true if any of the steps change records in the database. Steps that change nothing only update the
+    /// schema version.
+    pub fn has_changes(&self) -> bool {
+        self.steps.iter().any(|step| step.num_changes > 0)
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_up_to_date() {
+            return write!(f, "Database schema is up to date at version {}", self.from_version);
+        }
+        writeln!(
+            f,
+            "Database schema {} from version {} to version {}:",
+            if self.is_dry_run {
+                "would be migrated"
+            } else {
+                "migrated"
+            },
+            self.from_version,
+            self.to_version
+        )?;
+        for step in &self.steps {
+            writeln!(
+                f,
+                "  v{}: {} ({} record(s) {})",
+                step.version,
+                step.description,
+                step.num_changes,
+                if self.is_dry_run { "to change" } else { "changed" }
+            )?;
+        }
+        match self.backup_path {
+            Some(ref path) => write!(f, "Backup written to {}", path.display()),
+            None if self.is_dry_run && self.has_changes() => {
+                write!(f, "A backup of the database will be written before migrating")
+            },
+            None => write!(f, "No backup required"),
+        }
+    }
+}
@@ -20,15 +20,27 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+mod existence_filter;
 mod lmdb;
 #[allow(clippy::module_inception)]
 mod lmdb_db;
+mod migrations;
+mod row_layouts;
 
 use crate::transactions::{
     transaction::{TransactionInput, TransactionKernel, TransactionOutput},
     types::HashOutput,
 };
-pub use lmdb_db::{create_lmdb_database, create_recovery_lmdb_database, LMDBDatabase};
+pub use existence_filter::ExistenceFilterStats;
+pub use lmdb_db::{
+    create_lmdb_database,
+    create_recovery_lmdb_database,
+    dry_run_lmdb_migrations,
+    CompactedCopy,
+    CompactionResult,
+    LMDBDatabase,
+};
+pub use migrations::{MigrationReport, MigrationStepReport, LMDB_DB_SCHEMA_VERSION};
 use serde::{Deserialize, Serialize};
 
 pub const LMDB_DB_METADATA: &str = "metadata";
@@ -49,6 +61,12 @@ pub const LMDB_DB_MONERO_SEED_HEIGHT: &str = "monero_seed_height";
 pub const LMDB_DB_ORPHAN_HEADER_ACCUMULATED_DATA: &str = "orphan_accumulated_data";
 pub const LMDB_DB_ORPHAN_CHAIN_TIPS: &str = "orphan_chain_tips";
 pub const LMDB_DB_ORPHAN_PARENT_MAP_INDEX: &str = "orphan_parent_map_index";
+pub const LMDB_DB_UTXO_SCRIPT_HASH_INDEX: &str = "utxo_script_hash_index";
+pub const LMDB_DB_UTXO_FEATURES_INDEX: &str = "utxo_features_index";
+pub const LMDB_DB_UTXO_COMMITMENT_INDEX: &str = "utxo_commitment_index";
+pub const LMDB_DB_UTXO_UNIQUE_ID_INDEX: &str = "utxo_unique_id_index";
+pub const LMDB_DB_UTXO_ASSET_TOKEN_INDEX: &str = "utxo_asset_token_index";
+pub const LMDB_DB_BAD_BLOCK_LIST: &str = "bad_blocks";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct TransactionOutputRowData {
@@ -0,0 +1,403 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! The layouts that rows of the outputs, inputs and orphans databases were written in by earlier schema versions.
+//! The rows are serialized with bincode, which does not encode field names or lengths of structs, so adding a field to
+//! a stored struct means rows written before the field existed can no longer be read. The layouts are generic over
+//! the added fields, with the unit type standing in for a field that did not exist yet, since bincode encodes it in
+//! zero bytes.
+
+use crate::{
+    blocks::{Block, BlockHeader},
+    chain_storage::{
+        error::ChainStorageError,
+        lmdb_db::{TransactionInputRowData, TransactionOutputRowData},
+    },
+    transactions::{
+        aggregated_body::AggregateBody,
+        covenant::Covenant,
+        encrypted_data::EncryptedData,
+        transaction::{OutputFeatures, OutputFlags, TransactionInput, TransactionKernel, TransactionOutput},
+        types::{ComSignature, Commitment, HashOutput, PublicKey, RangeProof},
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tari_crypto::script::{ExecutionStack, TariScript};
+
+/// Rows written before covenants were added to outputs and inputs (schema version 2 and earlier)
+pub(super) type OutputRowWithoutCovenant = TransactionOutputRowLayout<(), (), ()>;
+pub(super) type InputRowWithoutCovenant = TransactionInputRowLayout<(), (), ()>;
+pub(super) type BlockWithoutCovenant = BlockLayout<(), (), ()>;
+
+/// Rows written before encrypted data was added to output features (schema version 3)
+pub(super) type OutputRowWithoutEncryptedData = TransactionOutputRowLayout<(), Covenant, ()>;
+pub(super) type InputRowWithoutEncryptedData = TransactionInputRowLayout<(), Covenant, ()>;
+pub(super) type BlockWithoutEncryptedData = BlockLayout<(), Covenant, ()>;
+
+/// Rows written before the parent public key and unique id were added to output features (schema version 4)
+pub(super) type OutputRowWithoutUniqueId = TransactionOutputRowLayout<EncryptedData, Covenant, ()>;
+pub(super) type InputRowWithoutUniqueId = TransactionInputRowLayout<EncryptedData, Covenant, ()>;
+pub(super) type BlockWithoutUniqueId = BlockLayout<EncryptedData, Covenant, ()>;
+
+/// The parent public key and unique id of output features, in the order they are stored
+pub(super) type UniqueIdFields = (Option<PublicKey>, Option<Vec<u8>>);
+
+/// A field of a stored row. Layouts written before the field was added use the unit type, which is read as the
+/// default value of the field.
+pub(super) trait StoredField<T> {
+    fn into_field(self) -> T;
+}
+
+impl StoredField<EncryptedData> for () {
+    fn into_field(self) -> EncryptedData {
+        EncryptedData::default()
+    }
+}
+
+impl StoredField<Covenant> for () {
+    fn into_field(self) -> Covenant {
+        Covenant::default()
+    }
+}
+
+impl StoredField<UniqueIdFields> for () {
+    fn into_field(self) -> UniqueIdFields {
+        (None, None)
+    }
+}
+
+impl StoredField<EncryptedData> for EncryptedData {
+    fn into_field(self) -> EncryptedData {
+        self
+    }
+}
+
+impl StoredField<Covenant> for Covenant {
+    fn into_field(self) -> Covenant {
+        self
+    }
+}
+
+impl StoredField<UniqueIdFields> for UniqueIdFields {
+    fn into_field(self) -> UniqueIdFields {
+        self
+    }
+}
+
+/// Deserializes `bytes` as `T`, failing if any bytes are left over. A row of one layout is very unlikely to be read as
+/// another layout without an error and without bytes left over, which is used to tell the layouts apart.
+pub(super) fn deserialize_exact<T: DeserializeOwned>(mut bytes: &[u8]) -> Result<T, ChainStorageError> {
+    let val = bincode::deserialize_from(&mut bytes).map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+    if !bytes.is_empty() {
+        return Err(ChainStorageError::AccessError(format!(
+            "{} byte(s) left over after deserializing row",
+            bytes.len()
+        )));
+    }
+    Ok(val)
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct OutputFeaturesLayout<E, U> {
+    flags: OutputFlags,
+    maturity: u64,
+    encrypted_data: E,
+    unique_id_fields: U,
+}
+
+impl<E: StoredField<EncryptedData>, U: StoredField<UniqueIdFields>> From<OutputFeaturesLayout<E, U>>
+    for OutputFeatures
+{
+    fn from(features: OutputFeaturesLayout<E, U>) -> Self {
+        let (parent_public_key, unique_id) = features.unique_id_fields.into_field();
+        OutputFeatures {
+            flags: features.flags,
+            maturity: features.maturity,
+            encrypted_data: features.encrypted_data.into_field(),
+            parent_public_key,
+            unique_id,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TransactionOutputLayout<E, C, U> {
+    features: OutputFeaturesLayout<E, U>,
+    commitment: Commitment,
+    proof: RangeProof,
+    script: TariScript,
+    sender_offset_public_key: PublicKey,
+    metadata_signature: ComSignature,
+    covenant: C,
+}
+
+impl<E: StoredField<EncryptedData>, C: StoredField<Covenant>, U: StoredField<UniqueIdFields>>
+    From<TransactionOutputLayout<E, C, U>> for TransactionOutput
+{
+    fn from(output: TransactionOutputLayout<E, C, U>) -> Self {
+        TransactionOutput {
+            features: output.features.into(),
+            commitment: output.commitment,
+            proof: output.proof,
+            script: output.script,
+            sender_offset_public_key: output.sender_offset_public_key,
+            metadata_signature: output.metadata_signature,
+            covenant: output.covenant.into_field(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TransactionInputLayout<E, C, U> {
+    features: OutputFeaturesLayout<E, U>,
+    commitment: Commitment,
+    script: TariScript,
+    input_data: ExecutionStack,
+    script_signature: ComSignature,
+    sender_offset_public_key: PublicKey,
+    covenant: C,
+}
+
+impl<E: StoredField<EncryptedData>, C: StoredField<Covenant>, U: StoredField<UniqueIdFields>>
+    From<TransactionInputLayout<E, C, U>> for TransactionInput
+{
+    fn from(input: TransactionInputLayout<E, C, U>) -> Self {
+        TransactionInput {
+            features: input.features.into(),
+            commitment: input.commitment,
+            script: input.script,
+            input_data: input.input_data,
+            script_signature: input.script_signature,
+            sender_offset_public_key: input.sender_offset_public_key,
+            covenant: input.covenant.into_field(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TransactionOutputRowLayout<E, C, U> {
+    output: Option<TransactionOutputLayout<E, C, U>>,
+    header_hash: HashOutput,
+    mmr_position: u32,
+    hash: HashOutput,
+    witness_hash: HashOutput,
+    mined_height: u64,
+}
+
+impl<E: StoredField<EncryptedData>, C: StoredField<Covenant>, U: StoredField<UniqueIdFields>>
+    From<TransactionOutputRowLayout<E, C, U>> for TransactionOutputRowData
+{
+    fn from(row: TransactionOutputRowLayout<E, C, U>) -> Self {
+        TransactionOutputRowData {
+            output: row.output.map(Into::into),
+            header_hash: row.header_hash,
+            mmr_position: row.mmr_position,
+            hash: row.hash,
+            witness_hash: row.witness_hash,
+            mined_height: row.mined_height,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TransactionInputRowLayout<E, C, U> {
+    input: TransactionInputLayout<E, C, U>,
+    header_hash: HashOutput,
+    mmr_position: u32,
+    hash: HashOutput,
+}
+
+impl<E: StoredField<EncryptedData>, C: StoredField<Covenant>, U: StoredField<UniqueIdFields>>
+    From<TransactionInputRowLayout<E, C, U>> for TransactionInputRowData
+{
+    fn from(row: TransactionInputRowLayout<E, C, U>) -> Self {
+        TransactionInputRowData {
+            input: row.input.into(),
+            header_hash: row.header_hash,
+            mmr_position: row.mmr_position,
+            hash: row.hash,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct AggregateBodyLayout<E, C, U> {
+    sorted: bool,
+    inputs: Vec<TransactionInputLayout<E, C, U>>,
+    outputs: Vec<TransactionOutputLayout<E, C, U>>,
+    kernels: Vec<TransactionKernel>,
+}
+
+impl<E: StoredField<EncryptedData>, C: StoredField<Covenant>, U: StoredField<UniqueIdFields>>
+    From<AggregateBodyLayout<E, C, U>> for AggregateBody
+{
+    fn from(body: AggregateBodyLayout<E, C, U>) -> Self {
+        let mut result = AggregateBody::new(
+            body.inputs.into_iter().map(Into::into).collect(),
+            body.outputs.into_iter().map(Into::into).collect(),
+            body.kernels,
+        );
+        if body.sorted {
+            result.sort();
+        }
+        result
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct BlockLayout<E, C, U> {
+    header: BlockHeader,
+    body: AggregateBodyLayout<E, C, U>,
+}
+
+impl<E: StoredField<EncryptedData>, C: StoredField<Covenant>, U: StoredField<UniqueIdFields>> From<BlockLayout<E, C, U>>
+    for Block
+{
+    fn from(block: BlockLayout<E, C, U>) -> Self {
+        Block::new(block.header, block.body.into())
+    }
+}
+
+impl TransactionOutputRowData {
+    /// Reads a row of the outputs database, which may have been written in the layout of an earlier schema version
+    pub(super) fn from_stored_bytes(bytes: &[u8]) -> Result<Self, ChainStorageError> {
+        deserialize_exact::<Self>(bytes)
+            .or_else(|_| deserialize_exact::<OutputRowWithoutUniqueId>(bytes).map(Into::into))
+            .or_else(|_| deserialize_exact::<OutputRowWithoutEncryptedData>(bytes).map(Into::into))
+            .or_else(|_| deserialize_exact::<OutputRowWithoutCovenant>(bytes).map(Into::into))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        chain_storage::lmdb_db::lmdb::serialize,
+        transactions::{helpers::create_test_input, tari_amount::MicroTari, types::CryptoFactories},
+    };
+
+    /// A row for `output` without unique ids, and with `encrypted_data` and `covenant` standing in for its encrypted
+    /// data and covenant
+    fn stored_row<E, C>(
+        output: &TransactionOutput,
+        encrypted_data: E,
+        covenant: C,
+    ) -> TransactionOutputRowLayout<E, C, ()> {
+        TransactionOutputRowLayout {
+            output: Some(TransactionOutputLayout {
+                features: OutputFeaturesLayout {
+                    flags: output.features.flags,
+                    maturity: output.features.maturity,
+                    encrypted_data,
+                    unique_id_fields: (),
+                },
+                commitment: output.commitment.clone(),
+                proof: output.proof.clone(),
+                script: output.script.clone(),
+                sender_offset_public_key: output.sender_offset_public_key.clone(),
+                metadata_signature: output.metadata_signature.clone(),
+                covenant,
+            }),
+            header_hash: vec![1; 32],
+            mmr_position: 7,
+            hash: vec![2; 32],
+            witness_hash: vec![3; 32],
+            mined_height: 10,
+        }
+    }
+
+    #[test]
+    fn it_reads_output_rows_written_without_covenants() {
+        let factories = CryptoFactories::default();
+        let (_, unblinded) = create_test_input(MicroTari(1000), 5, &factories.commitment);
+        let output = unblinded.as_transaction_output(&factories).unwrap();
+        let bytes = serialize(&stored_row(&output, (), ())).unwrap();
+
+        assert!(deserialize_exact::<TransactionOutputRowData>(&bytes).is_err());
+        let row = TransactionOutputRowData::from_stored_bytes(&bytes).unwrap();
+        assert_eq!(row.output.unwrap(), output);
+        assert_eq!(row.mmr_position, 7);
+        assert_eq!(row.mined_height, 10);
+
+        let current = TransactionOutputRowData {
+            output: Some(output.clone()),
+            header_hash: vec![1; 32],
+            mmr_position: 7,
+            hash: vec![2; 32],
+            witness_hash: vec![3; 32],
+            mined_height: 10,
+        };
+        let bytes = serialize(&current).unwrap();
+        assert!(deserialize_exact::<OutputRowWithoutCovenant>(&bytes).is_err());
+        let row = TransactionOutputRowData::from_stored_bytes(&bytes).unwrap();
+        assert_eq!(row.output.unwrap(), output);
+    }
+
+    #[test]
+    fn it_reads_output_rows_written_without_encrypted_data() {
+        let factories = CryptoFactories::default();
+        let (_, unblinded) = create_test_input(MicroTari(1000), 5, &factories.commitment);
+        let output = unblinded.as_transaction_output(&factories).unwrap();
+        let bytes = serialize(&stored_row(&output, (), output.covenant.clone())).unwrap();
+
+        assert!(deserialize_exact::<TransactionOutputRowData>(&bytes).is_err());
+        assert!(deserialize_exact::<OutputRowWithoutCovenant>(&bytes).is_err());
+        let row = TransactionOutputRowData::from_stored_bytes(&bytes).unwrap();
+        assert_eq!(row.output.unwrap(), output);
+        assert_eq!(row.mmr_position, 7);
+    }
+
+    #[test]
+    fn it_reads_output_rows_written_without_unique_ids() {
+        let factories = CryptoFactories::default();
+        let (_, unblinded) = create_test_input(MicroTari(1000), 5, &factories.commitment);
+        let mut output = unblinded.as_transaction_output(&factories).unwrap();
+        output.features.encrypted_data = EncryptedData::from_bytes(vec![1, 2, 3]).unwrap();
+        let bytes = serialize(&stored_row(
+            &output,
+            output.features.encrypted_data.clone(),
+            output.covenant.clone(),
+        ))
+        .unwrap();
+
+        assert!(deserialize_exact::<TransactionOutputRowData>(&bytes).is_err());
+        assert!(deserialize_exact::<OutputRowWithoutEncryptedData>(&bytes).is_err());
+        let row = TransactionOutputRowData::from_stored_bytes(&bytes).unwrap();
+        let read = row.output.unwrap();
+        assert_eq!(read, output);
+        assert_eq!(read.features.unique_id, None);
+
+        output.features.unique_id = Some(vec![4; 8]);
+        let current = TransactionOutputRowData {
+            output: Some(output),
+            header_hash: vec![1; 32],
+            mmr_position: 7,
+            hash: vec![2; 32],
+            witness_hash: vec![3; 32],
+            mined_height: 10,
+        };
+        let bytes = serialize(&current).unwrap();
+        assert!(deserialize_exact::<OutputRowWithoutUniqueId>(&bytes).is_err());
+        let row = TransactionOutputRowData::from_stored_bytes(&bytes).unwrap();
+        assert_eq!(row.output.unwrap().features.unique_id, Some(vec![4; 8]));
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use lazy_static::lazy_static;
+use tari_metrics::{Histogram, IntCounter, IntGauge};
+
+lazy_static! {
+    static ref TIP_HEIGHT: IntGauge =
+        tari_metrics::register_int_gauge("base_node_tip_height", "The height of the chain tip").unwrap();
+    static ref REORGS: IntCounter =
+        tari_metrics::register_int_counter("base_node_reorgs", "The number of chain reorgs").unwrap();
+    static ref REORG_DEPTH: Histogram = tari_metrics::register_histogram(
+        "base_node_reorg_depth",
+        "The number of blocks removed from the main chain by each reorg",
+        Some(vec![1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 50.0, 100.0, 1000.0]),
+    )
+    .unwrap();
+    static ref BLOCK_VALIDATION_SECONDS: Histogram = tari_metrics::register_histogram(
+        "base_node_block_validation_seconds",
+        "The time taken to validate the body of a block being added to the main chain",
+        None,
+    )
+    .unwrap();
+}
+
+pub fn tip_height() -> &'static IntGauge {
+    &TIP_HEIGHT
+}
+
+pub fn reorgs() -> &'static IntCounter {
+    &REORGS
+}
+
+pub fn reorg_depth() -> &'static Histogram {
+    &REORG_DEPTH
+}
+
+pub fn block_validation_seconds() -> &'static Histogram {
+    &BLOCK_VALIDATION_SECONDS
+}
@@ -0,0 +1,102 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    blocks::{verify_kernel_proof, verify_output_mmr_commitment, verify_utxo_proof, BlockHeader, MmrProofError},
+    transactions::{
+        transaction::{TransactionKernel, TransactionOutput},
+        types::HashOutput,
+    },
+};
+use tari_crypto::tari_utilities::hash::Hashable;
+use tari_mmr::MerkleProof;
+
+/// MMR inclusion proofs for a set of unspent outputs, made against the output MMR of `header`.
+///
+/// The header's `output_mr` commits to `output_mmr_root` and `deleted_bitmap`, so a client that trusts the header can
+/// check that each output is in the output MMR and that it has not been spent, without trusting the node that
+/// produced the proofs.
+#[derive(Debug, Clone)]
+pub struct UtxoMmrProofs {
+    pub header: BlockHeader,
+    /// The root of the output MMR, excluding the deleted bitmap
+    pub output_mmr_root: HashOutput,
+    /// The serialized deleted bitmap, exactly as it was hashed into `output_mr`
+    pub deleted_bitmap: Vec<u8>,
+    /// A proof for each of the requested outputs that is unspent as of `header`
+    pub proofs: Vec<UtxoMmrProof>,
+}
+
+impl UtxoMmrProofs {
+    /// Verifies every proof against `header`. The caller is responsible for checking that the header is part of the
+    /// chain it trusts.
+    pub fn verify(&self) -> Result<(), MmrProofError> {
+        let deleted = verify_output_mmr_commitment(&self.header, &self.output_mmr_root, &self.deleted_bitmap)?;
+        self.proofs.iter().try_for_each(|proof| {
+            verify_utxo_proof(
+                &self.output_mmr_root,
+                &deleted,
+                &proof.output.hash(),
+                proof.mmr_position,
+                &proof.merkle_proof,
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UtxoMmrProof {
+    pub output: TransactionOutput,
+    pub mmr_position: u32,
+    pub merkle_proof: MerkleProof,
+}
+
+/// MMR inclusion proofs for a set of kernels, made against the kernel MMR of `header`. The header's `kernel_mr` is the
+/// root of the kernel MMR, so no further data is needed to verify the proofs.
+#[derive(Debug, Clone)]
+pub struct KernelMmrProofs {
+    pub header: BlockHeader,
+    /// A proof for each of the requested kernels that is in the chain as of `header`
+    pub proofs: Vec<KernelMmrProof>,
+}
+
+impl KernelMmrProofs {
+    /// Verifies every proof against `header`. The caller is responsible for checking that the header is part of the
+    /// chain it trusts.
+    pub fn verify(&self) -> Result<(), MmrProofError> {
+        self.proofs.iter().try_for_each(|proof| {
+            verify_kernel_proof(
+                &self.header,
+                &proof.kernel.hash(),
+                proof.mmr_position,
+                &proof.merkle_proof,
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KernelMmrProof {
+    pub kernel: TransactionKernel,
+    pub mmr_position: u32,
+    pub merkle_proof: MerkleProof,
+}
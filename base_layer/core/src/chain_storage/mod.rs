@@ -42,6 +42,9 @@ pub use accumulated_data::{
 
 pub mod async_db;
 
+mod asset_output;
+pub use asset_output::AssetOutput;
+
 mod block_add_result;
 pub use block_add_result::BlockAddResult;
 mod blockchain_database;
@@ -52,6 +55,8 @@ pub use blockchain_database::{
     fetch_target_difficulty_for_next_block,
     BlockchainDatabase,
     BlockchainDatabaseConfig,
+    MmrRoots,
+    MmrRootsCalculator,
     Validators,
 };
 
@@ -78,19 +83,38 @@ pub use horizon_data::HorizonData;
 mod pruned_output;
 pub use pruned_output::PrunedOutput;
 
+mod pruning_stats;
+pub use pruning_stats::PruningStats;
+
+mod snapshot;
+pub use snapshot::{BlockchainSnapshot, ChainReadSnapshot};
+
+#[cfg(feature = "metrics")]
+mod metrics;
+
 mod lmdb_db;
 pub use lmdb_db::{
     create_lmdb_database,
     create_recovery_lmdb_database,
+    dry_run_lmdb_migrations,
+    CompactedCopy,
+    CompactionResult,
+    ExistenceFilterStats,
     LMDBDatabase,
+    MigrationReport,
+    MigrationStepReport,
     LMDB_DB_BLOCK_HASHES,
     LMDB_DB_HEADERS,
     LMDB_DB_KERNELS,
     LMDB_DB_METADATA,
     LMDB_DB_MONERO_SEED_HEIGHT,
     LMDB_DB_ORPHANS,
+    LMDB_DB_SCHEMA_VERSION,
     LMDB_DB_UTXOS,
 };
 
 mod target_difficulties;
 pub use target_difficulties::TargetDifficulties;
+
+mod mmr_proof;
+pub use mmr_proof::{KernelMmrProof, KernelMmrProofs, UtxoMmrProof, UtxoMmrProofs};
@@ -0,0 +1,106 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    blocks::BlockHeader,
+    chain_storage::{ChainHeader, ChainStorageError},
+    transactions::types::HashOutput,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A read-only view of the chain, fixed at the time it was opened. Writes committed after the snapshot was opened,
+/// including reorgs, are not visible to it.
+pub trait ChainReadSnapshot: Send {
+    /// The tip of the longest chain as at the time the snapshot was opened
+    fn fetch_tip_header(&self) -> Result<ChainHeader, ChainStorageError>;
+
+    /// Fetches the main chain header with the given hash, if it exists in the snapshot
+    #[allow(clippy::ptr_arg)]
+    fn fetch_header_by_block_hash(&self, hash: &HashOutput) -> Result<Option<BlockHeader>, ChainStorageError>;
+
+    /// Fetches the main chain headers from `start` to `end_inclusive`. Headers beyond the snapshot tip are omitted.
+    fn fetch_headers(&self, start: u64, end_inclusive: u64) -> Result<Vec<BlockHeader>, ChainStorageError>;
+}
+
+/// A handle to a consistent view of the chain for queries that span many reads, such as streaming headers to a
+/// syncing peer. Reads made through the snapshot cannot straddle a reorg.
+///
+/// The backend has to retain the data the snapshot refers to for as long as it is open (for LMDB, pages freed by
+/// later writes cannot be reused), so a snapshot refuses reads once it is older than its maximum age. Callers should
+/// open a new snapshot and continue from where they left off.
+#[derive(Clone)]
+pub struct BlockchainSnapshot {
+    inner: Arc<Mutex<Box<dyn ChainReadSnapshot>>>,
+    opened_at: Instant,
+    max_age: Duration,
+}
+
+impl BlockchainSnapshot {
+    pub fn new(inner: Box<dyn ChainReadSnapshot>, max_age: Duration) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            opened_at: Instant::now(),
+            max_age,
+        }
+    }
+
+    /// Returns true if the snapshot is older than its maximum age and will refuse further reads
+    pub fn is_stale(&self) -> bool {
+        self.opened_at.elapsed() > self.max_age
+    }
+
+    /// The time since the snapshot was opened
+    pub fn age(&self) -> Duration {
+        self.opened_at.elapsed()
+    }
+
+    pub fn fetch_tip_header(&self) -> Result<ChainHeader, ChainStorageError> {
+        self.with_snapshot(|snapshot| snapshot.fetch_tip_header())
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn fetch_header_by_block_hash(&self, hash: &HashOutput) -> Result<Option<BlockHeader>, ChainStorageError> {
+        self.with_snapshot(|snapshot| snapshot.fetch_header_by_block_hash(hash))
+    }
+
+    pub fn fetch_headers(&self, start: u64, end_inclusive: u64) -> Result<Vec<BlockHeader>, ChainStorageError> {
+        if start > end_inclusive {
+            return Ok(Vec::new());
+        }
+        self.with_snapshot(|snapshot| snapshot.fetch_headers(start, end_inclusive))
+    }
+
+    fn with_snapshot<F, T>(&self, f: F) -> Result<T, ChainStorageError>
+    where F: FnOnce(&dyn ChainReadSnapshot) -> Result<T, ChainStorageError> {
+        if self.is_stale() {
+            return Err(ChainStorageError::SnapshotExpired(self.max_age));
+        }
+        let snapshot = self
+            .inner
+            .lock()
+            .map_err(|_| ChainStorageError::AccessError("Lock on blockchain snapshot failed".into()))?;
+        f(snapshot.as_ref())
+    }
+}
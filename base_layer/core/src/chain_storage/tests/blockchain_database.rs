@@ -321,3 +321,286 @@ mod fetch_block_hashes_from_header_tip {
         assert_eq!(&hashes[5], genesis.hash());
     }
 }
+
+mod fetch_utxos_by_index {
+    use super::*;
+    use crate::transactions::{
+        transaction::OutputFlags,
+        types::{Commitment, HashDigest},
+    };
+
+    #[test]
+    fn it_pages_through_outputs_by_features() {
+        let db = setup();
+        let blocks = add_many_chained_blocks(3, &db);
+        let all = db
+            .fetch_utxos_by_features(OutputFlags::COINBASE_OUTPUT, 0, 100)
+            .unwrap();
+        for block in &blocks {
+            let hash = block.body.outputs()[0].hash();
+            assert!(all.iter().any(|(output, _)| output.hash() == hash));
+        }
+        assert!(all.windows(2).all(|w| w[0].1 < w[1].1));
+
+        let page = db.fetch_utxos_by_features(OutputFlags::COINBASE_OUTPUT, 0, 2).unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[1].1, all[1].1);
+        let next_page = db
+            .fetch_utxos_by_features(OutputFlags::COINBASE_OUTPUT, page[1].1 + 1, 2)
+            .unwrap();
+        assert_eq!(next_page[0].1, all[2].1);
+    }
+
+    #[test]
+    fn it_returns_outputs_by_script_hash() {
+        let db = setup();
+        let blocks = add_many_chained_blocks(2, &db);
+        let output = &blocks[1].body.outputs()[0];
+        let script_hash = output.script.as_hash::<HashDigest>().unwrap().to_vec();
+        let outputs = db.fetch_utxos_by_script_hash(script_hash, 0, 100).unwrap();
+        assert!(outputs.iter().any(|(o, _)| o.hash() == output.hash()));
+
+        let outputs = db.fetch_utxos_by_script_hash(vec![0u8; 32], 0, 100).unwrap();
+        assert!(outputs.is_empty());
+    }
+
+    #[test]
+    fn it_returns_unspent_outputs_by_commitment() {
+        let db = setup();
+        let blocks = add_many_chained_blocks(2, &db);
+        let output = &blocks[1].body.outputs()[0];
+        let (outputs, tip_height) = db
+            .fetch_unspent_outputs_by_commitment(vec![output.commitment.clone(), Commitment::default()])
+            .unwrap();
+        assert_eq!(tip_height, 2);
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].as_ref().unwrap().hash(), output.hash());
+        assert!(outputs[1].is_none());
+    }
+}
+
+mod fetch_mmr_proofs {
+    use super::*;
+    use crate::{
+        blocks::{verify_kernel_proof, MmrProofError},
+        transactions::types::HashDigest,
+    };
+
+    fn add_blocks_with_mmr_roots(size: usize, db: &BlockchainDatabase<TempDatabase>) -> Vec<Arc<Block>> {
+        let mut prev_block = Arc::new(db.fetch_block(0).unwrap().try_into_block().unwrap());
+        let mut blocks = Vec::with_capacity(size);
+        for i in 1..=size as u64 {
+            let mut block = create_block(1, i, vec![]);
+            block.header.prev_hash = prev_block.hash().clone();
+            let roots = db.calculate_mmr_roots(&block).unwrap();
+            block.header.kernel_mr = roots.kernel_mr;
+            block.header.kernel_mmr_size = roots.kernel_mmr_size;
+            block.header.input_mr = roots.input_mr;
+            block.header.output_mr = roots.output_mr;
+            block.header.witness_mr = roots.witness_mr;
+            block.header.output_mmr_size = roots.output_mmr_size;
+            let block = Arc::new(block);
+            prev_block = block.clone();
+            db.add_block(block.clone()).unwrap().assert_added();
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    #[test]
+    fn it_proves_unspent_outputs_against_the_tip_header() {
+        let db = setup();
+        let blocks = add_blocks_with_mmr_roots(3, &db);
+        let output_hash = blocks[1].body.outputs()[0].hash();
+
+        let utxo_proofs = db
+            .fetch_utxo_mmr_proofs(vec![output_hash.clone(), vec![0u8; 32]])
+            .unwrap();
+        assert_eq!(utxo_proofs.header.hash(), blocks[2].hash());
+        assert_eq!(utxo_proofs.proofs.len(), 1);
+
+        let proof = &utxo_proofs.proofs[0];
+        assert_eq!(proof.output.hash(), output_hash);
+        proof
+            .merkle_proof
+            .verify_leaf::<HashDigest>(&utxo_proofs.output_mmr_root, &output_hash, proof.mmr_position as usize)
+            .unwrap();
+        assert!(proof
+            .merkle_proof
+            .verify_leaf::<HashDigest>(&utxo_proofs.output_mmr_root, &[0u8; 32], proof.mmr_position as usize)
+            .is_err());
+        utxo_proofs.verify().unwrap();
+    }
+
+    #[test]
+    fn it_fails_verification_if_the_header_does_not_commit_to_the_proofs() {
+        let db = setup();
+        let blocks = add_blocks_with_mmr_roots(2, &db);
+        let output_hash = blocks[1].body.outputs()[0].hash();
+
+        let mut utxo_proofs = db.fetch_utxo_mmr_proofs(vec![output_hash]).unwrap();
+        utxo_proofs.header = blocks[0].header.clone();
+        let err = utxo_proofs.verify().unwrap_err();
+        assert_eq!(err, MmrProofError::OutputMmrCommitmentMismatch(1));
+    }
+
+    #[test]
+    fn it_proves_kernels_against_the_tip_header() {
+        let db = setup();
+        let blocks = add_blocks_with_mmr_roots(3, &db);
+        let kernel_hash = blocks[1].body.kernels()[0].hash();
+
+        let kernel_proofs = db
+            .fetch_kernel_mmr_proofs(vec![kernel_hash.clone(), vec![0u8; 32]])
+            .unwrap();
+        assert_eq!(kernel_proofs.header.hash(), blocks[2].hash());
+        assert_eq!(kernel_proofs.proofs.len(), 1);
+        let proof = &kernel_proofs.proofs[0];
+        assert_eq!(proof.kernel.hash(), kernel_hash);
+        kernel_proofs.verify().unwrap();
+
+        assert!(verify_kernel_proof(
+            &kernel_proofs.header,
+            &[0u8; 32],
+            proof.mmr_position,
+            &proof.merkle_proof
+        )
+        .is_err());
+    }
+}
+
+mod chain_metadata {
+    use super::*;
+
+    #[test]
+    fn it_tracks_the_accumulated_difficulty_of_each_pow_algorithm() {
+        let db = setup();
+        add_many_chained_blocks(3, &db);
+        let tip = db.fetch_tip_header().unwrap();
+        let metadata = db.get_chain_metadata().unwrap();
+        assert_eq!(metadata.height_of_longest_chain(), 3);
+        assert_eq!(
+            metadata.accumulated_monero_difficulty(),
+            tip.accumulated_data().accumulated_monero_difficulty.as_u64()
+        );
+        assert_eq!(
+            metadata.accumulated_sha_difficulty(),
+            tip.accumulated_data().accumulated_sha_difficulty.as_u64()
+        );
+        assert!(metadata.accumulated_sha_difficulty() > 0);
+    }
+}
+
+mod conditional_writes {
+    use super::*;
+    use crate::chain_storage::{ChainStorageError, DbTransaction};
+
+    #[test]
+    fn it_only_sets_the_best_block_if_it_is_unchanged() {
+        let db = setup();
+        let blocks = add_many_chained_blocks(2, &db);
+        let tip_hash = blocks[1].hash();
+
+        let mut txn = DbTransaction::new();
+        txn.set_best_block_if_equal(blocks[0].hash(), 1, blocks[0].hash(), 1, 1.into(), 1.into());
+        let err = db.write(txn).unwrap_err();
+        unpack_enum!(ChainStorageError::ConditionalWriteFailed(_s) = err);
+        let metadata = db.get_chain_metadata().unwrap();
+        assert_eq!(metadata.height_of_longest_chain(), 2);
+        assert_eq!(*metadata.best_block(), tip_hash);
+
+        let mut txn = DbTransaction::new();
+        txn.set_best_block_if_equal(tip_hash, 1, blocks[0].hash(), 1, 1.into(), 1.into());
+        db.write(txn).unwrap();
+        let metadata = db.get_chain_metadata().unwrap();
+        assert_eq!(metadata.height_of_longest_chain(), 1);
+        assert_eq!(*metadata.best_block(), blocks[0].hash());
+    }
+
+    #[test]
+    fn it_inserts_an_orphan_if_it_does_not_exist() {
+        let db = setup();
+        let orphan = Arc::new(create_block(1, 10, vec![]));
+
+        let mut txn = DbTransaction::new();
+        txn.insert_orphan_if_not_exists(orphan.clone());
+        db.write(txn).unwrap();
+        assert_eq!(db.fetch_orphan(orphan.hash()).unwrap().hash(), orphan.hash());
+
+        let mut txn = DbTransaction::new();
+        txn.insert_orphan_if_not_exists(orphan.clone());
+        db.write(txn).unwrap();
+
+        let mut txn = DbTransaction::new();
+        txn.insert_orphan(orphan);
+        assert!(db.write(txn).is_err());
+    }
+}
+
+mod snapshot {
+    use super::*;
+    use crate::chain_storage::{BlockchainBackend, BlockchainSnapshot, ChainStorageError};
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn it_does_not_see_later_writes() {
+        let db = setup();
+        let blocks = add_many_chained_blocks(2, &db);
+        let snapshot = db.open_snapshot().unwrap();
+
+        let new_blocks = add_many_chained_blocks_from(2, &blocks[1], &db);
+        assert_eq!(db.fetch_tip_header().unwrap().height(), 4);
+
+        assert_eq!(snapshot.fetch_tip_header().unwrap().height(), 2);
+        let headers = snapshot.fetch_headers(0, 10).unwrap();
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers[2].hash(), blocks[1].hash());
+        assert!(snapshot
+            .fetch_header_by_block_hash(&new_blocks[0].hash())
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            snapshot
+                .fetch_header_by_block_hash(&blocks[0].hash())
+                .unwrap()
+                .unwrap()
+                .height,
+            1
+        );
+
+        let snapshot = db.open_snapshot().unwrap();
+        assert_eq!(snapshot.fetch_tip_header().unwrap().height(), 4);
+    }
+
+    #[test]
+    fn it_refuses_reads_once_stale() {
+        let db = setup();
+        let inner = db.db_read_access().unwrap().open_read_snapshot().unwrap();
+        let snapshot = BlockchainSnapshot::new(inner, Duration::from_millis(10));
+        assert!(snapshot.fetch_tip_header().is_ok());
+        thread::sleep(Duration::from_millis(20));
+        assert!(snapshot.is_stale());
+        let err = snapshot.fetch_headers(0, 1).unwrap_err();
+        unpack_enum!(ChainStorageError::SnapshotExpired(_max_age) = err);
+    }
+
+    fn add_many_chained_blocks_from(
+        size: usize,
+        prev_block: &Arc<Block>,
+        db: &BlockchainDatabase<TempDatabase>,
+    ) -> Vec<Arc<Block>> {
+        let mut prev_block = prev_block.clone();
+        let mut blocks = Vec::with_capacity(size);
+        for _ in 0..size {
+            let mut block = create_block(1, prev_block.header.height + 1, vec![]);
+            block.header.prev_hash = prev_block.hash();
+            block.header.output_mmr_size = prev_block.header.output_mmr_size + block.body.outputs().len() as u64;
+            block.header.kernel_mmr_size = prev_block.header.kernel_mmr_size + block.body.kernels().len() as u64;
+            let block = Arc::new(block);
+            prev_block = block.clone();
+            db.add_block(block.clone()).unwrap().assert_added();
+            blocks.push(block);
+        }
+        blocks
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::transactions::types::HashDigest;
+use digest::Digest;
+use std::{
+    convert::TryInto,
+    f64::consts::LN_2,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+/// A fixed size bloom filter for fast, definite absence checks. Bits are set atomically, so keys can be inserted and
+/// queried through a shared reference. Keys cannot be removed; a removed key is reported as a possible member until
+/// the filter is rebuilt.
+pub struct BloomFilter {
+    bits: Vec<AtomicU64>,
+    num_bits: u64,
+    num_hashes: u32,
+    capacity: usize,
+    num_inserted: AtomicUsize,
+}
+
+impl BloomFilter {
+    /// Creates a filter that has the given false positive rate once `capacity` keys have been inserted
+    pub fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1);
+        let num_bits = (-(capacity as f64) * false_positive_rate.ln() / (LN_2 * LN_2))
+            .ceil()
+            .max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / capacity as f64) * LN_2).round().max(1.0) as u32;
+        let num_words = ((num_bits + 63) / 64) as usize;
+        Self {
+            bits: (0..num_words).map(|_| AtomicU64::new(0)).collect(),
+            num_bits,
+            num_hashes,
+            capacity,
+            num_inserted: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn insert(&self, key: &[u8]) {
+        for index in self.bit_indexes(key) {
+            self.bits[(index / 64) as usize].fetch_or(1 << (index % 64), Ordering::Relaxed);
+        }
+        self.num_inserted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns false if the key has definitely not been inserted, otherwise true
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        self.bit_indexes(key)
+            .all(|index| self.bits[(index / 64) as usize].load(Ordering::Relaxed) & (1 << (index % 64)) != 0)
+    }
+
+    /// The number of keys that have been inserted, including duplicates
+    pub fn len(&self) -> usize {
+        self.num_inserted.load(Ordering::Relaxed)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The expected false positive rate for the number of keys that have been inserted
+    pub fn expected_false_positive_rate(&self) -> f64 {
+        let k = self.num_hashes as f64;
+        (1.0 - (-k * self.len() as f64 / self.num_bits as f64).exp()).powf(k)
+    }
+
+    /// Derives the bit indexes for a key from a single hash using double hashing
+    fn bit_indexes(&self, key: &[u8]) -> impl Iterator<Item = u64> {
+        let hash = HashDigest::digest(key);
+        let h1 = u64::from_le_bytes(hash[0..8].try_into().expect("hash is 32 bytes"));
+        let h2 = u64::from_le_bytes(hash[8..16].try_into().expect("hash is 32 bytes")) | 1;
+        let num_bits = self.num_bits;
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_has_no_false_negatives() {
+        let filter = BloomFilter::new(1000, 0.01);
+        for i in 0u32..1000 {
+            filter.insert(&i.to_le_bytes());
+        }
+        assert_eq!(filter.len(), 1000);
+        assert!((0u32..1000).all(|i| filter.may_contain(&i.to_le_bytes())));
+    }
+
+    #[test]
+    fn it_has_about_the_expected_false_positive_rate() {
+        let filter = BloomFilter::new(1000, 0.01);
+        assert!(!filter.may_contain(b"key"));
+        for i in 0u32..1000 {
+            filter.insert(&i.to_le_bytes());
+        }
+        let false_positives = (1000u32..11000)
+            .filter(|i| filter.may_contain(&i.to_le_bytes()))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+        let expected = filter.expected_false_positive_rate();
+        assert!(expected > 0.005 && expected < 0.02, "expected rate {}", expected);
+    }
+}
@@ -21,9 +21,12 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    consensus::{network::NetworkConsensus, KERNEL_WEIGHT, WEIGHT_PER_OUTPUT},
+    consensus::network::NetworkConsensus,
     proof_of_work::{Difficulty, PowAlgorithm},
-    transactions::tari_amount::{uT, MicroTari, T},
+    transactions::{
+        tari_amount::{uT, MicroTari, T},
+        weight::TransactionWeight,
+    },
 };
 use chrono::{DateTime, Duration, Utc};
 use std::{collections::HashMap, ops::Add};
@@ -62,6 +65,10 @@ pub struct ConsensusConstants {
     proof_of_work: HashMap<PowAlgorithm, PowAlgorithmConstants>,
     /// This is to keep track of the value inside of the genesis block
     faucet_value: MicroTari,
+    /// The model used to calculate transaction and block weights
+    transaction_weight: TransactionWeight,
+    /// The maximum number of outputs the coinbase may be split into
+    max_coinbase_outputs: usize,
 }
 
 /// This is just a convenience  wrapper to put all the info into a hashmap per diff algo
@@ -94,11 +101,23 @@ impl ConsensusConstants {
         self.coinbase_lock_height
     }
 
+    /// The maximum number of outputs the coinbase may be split into. A block must contain between one and this many
+    /// coinbase outputs.
+    pub fn max_coinbase_outputs(&self) -> usize {
+        self.max_coinbase_outputs
+    }
+
     /// Current version of the blockchain.
     pub fn blockchain_version(&self) -> u16 {
         self.blockchain_version
     }
 
+    /// The Future Time Limit (FTL) in seconds. Any block with a timestamp more than this far ahead of the current
+    /// time is rejected.
+    pub fn get_future_time_limit(&self) -> u64 {
+        self.future_time_limit
+    }
+
     /// This returns the FTL(Future Time Limit) for blocks
     /// Any block with a timestamp greater than this is rejected.
     pub fn ftl(&self) -> EpochTime {
@@ -127,7 +146,12 @@ impl ConsensusConstants {
 
     /// Maximum transaction weight used for the construction of new blocks. It leaves place for 1 kernel and 1 output
     pub fn get_max_block_weight_excluding_coinbase(&self) -> u64 {
-        self.max_block_transaction_weight - WEIGHT_PER_OUTPUT - KERNEL_WEIGHT
+        self.max_block_transaction_weight - self.transaction_weight.calculate(1, 0, 1, 0)
+    }
+
+    /// The model used to calculate transaction and block weights
+    pub fn transaction_weight(&self) -> &TransactionWeight {
+        &self.transaction_weight
     }
 
     /// The amount of PoW algorithms used by the Tari chain.
@@ -212,6 +236,8 @@ impl ConsensusConstants {
             max_randomx_seed_height: std::u64::MAX,
             proof_of_work: algos,
             faucet_value: (5000 * 4000) * T,
+            transaction_weight: TransactionWeight::V2,
+            max_coinbase_outputs: 16,
         }]
     }
 
@@ -245,6 +271,8 @@ impl ConsensusConstants {
             max_randomx_seed_height: std::u64::MAX,
             proof_of_work: algos,
             faucet_value: (5000 * 4000) * T,
+            transaction_weight: TransactionWeight::V1,
+            max_coinbase_outputs: 1,
         }]
     }
 
@@ -305,6 +333,8 @@ impl ConsensusConstants {
                 max_randomx_seed_height: std::u64::MAX,
                 proof_of_work: algos,
                 faucet_value: (5000 * 4000) * T,
+                transaction_weight: TransactionWeight::V1,
+                max_coinbase_outputs: 1,
             },
             ConsensusConstants {
                 effective_from_height: 1400,
@@ -320,6 +350,8 @@ impl ConsensusConstants {
                 max_randomx_seed_height: std::u64::MAX,
                 proof_of_work: algos2,
                 faucet_value: (5000 * 4000) * T,
+                transaction_weight: TransactionWeight::V1,
+                max_coinbase_outputs: 1,
             },
         ]
     }
@@ -353,6 +385,8 @@ impl ConsensusConstants {
             max_randomx_seed_height: std::u64::MAX,
             proof_of_work: algos,
             faucet_value: (5000 * 4000) * T,
+            transaction_weight: TransactionWeight::V1,
+            max_coinbase_outputs: 1,
         }]
     }
 
@@ -386,6 +420,8 @@ impl ConsensusConstants {
             max_randomx_seed_height: std::u64::MAX,
             proof_of_work: algos,
             faucet_value: MicroTari::from(0),
+            transaction_weight: TransactionWeight::V1,
+            max_coinbase_outputs: 16,
         }]
     }
 }
@@ -438,6 +474,16 @@ impl ConsensusConstantsBuilder {
         self
     }
 
+    pub fn with_transaction_weight(mut self, weighting: TransactionWeight) -> Self {
+        self.consensus.transaction_weight = weighting;
+        self
+    }
+
+    pub fn with_max_coinbase_outputs(mut self, max_outputs: usize) -> Self {
+        self.consensus.max_coinbase_outputs = max_outputs;
+        self
+    }
+
     pub fn with_faucet_value(mut self, value: MicroTari) -> Self {
         self.consensus.faucet_value = value;
         self
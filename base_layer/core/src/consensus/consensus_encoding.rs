@@ -0,0 +1,634 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Canonical encodings of the consensus-critical and wire types.
+//!
+//! Every byte produced here is either hashed into a block or sent to peers, so an encoding must never change once
+//! released. Unlike the serde derives on these types, which follow whatever the struct definitions (and their
+//! `#[serde(default)]` attributes) happen to be, these encodings spell out the field order and width explicitly:
+//!
+//! * Integers are fixed width, little endian.
+//! * Keys, commitments and scalars are their 32 byte canonical representation.
+//! * Variable length fields (scripts, covenants, range proofs, merkle roots) are prefixed with their length as an
+//!   unsigned LEB128 varint.
+//! * Optional fields are a `0` byte when absent, or a `1` byte followed by the value.
+//!
+//! The golden vectors in the tests below pin both these encodings and the protobuf messages exchanged with peers. If
+//! one of those tests fails, the change being made would break consensus or compatibility with older nodes.
+
+#[cfg(any(feature = "base_node", feature = "base_node_proto"))]
+use crate::blocks::BlockHeader;
+use crate::{
+    proof_of_work::ProofOfWork,
+    transactions::{
+        covenant::Covenant,
+        tari_amount::MicroTari,
+        transaction::{KernelFeatures, OutputFeatures, TransactionInput, TransactionKernel, TransactionOutput},
+        types::{ComSignature, Commitment, PrivateKey, PublicKey, RangeProof, Signature},
+    },
+};
+use std::io::{self, Write};
+use tari_crypto::{
+    script::{ExecutionStack, TariScript},
+    tari_utilities::ByteArray,
+};
+
+/// A type with a canonical, consensus-critical byte encoding
+pub trait ConsensusEncoding {
+    /// Write the canonical encoding of `self` to `writer`, returning the number of bytes written
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error>;
+}
+
+pub trait ToConsensusBytes {
+    fn to_consensus_bytes(&self) -> Vec<u8>;
+}
+
+impl<T: ConsensusEncoding + ?Sized> ToConsensusBytes for T {
+    fn to_consensus_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.consensus_encode(&mut buf)
+            .expect("writing to a Vec<u8> cannot fail");
+        buf
+    }
+}
+
+/// Writes `n` as an unsigned LEB128 varint
+fn write_varint<W: Write>(writer: &mut W, mut n: u64) -> Result<usize, io::Error> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    writer.write_all(&buf[..len])?;
+    Ok(len)
+}
+
+/// Writes a length-prefixed byte string
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<usize, io::Error> {
+    let len = write_varint(writer, bytes.len() as u64)?;
+    writer.write_all(bytes)?;
+    Ok(len + bytes.len())
+}
+
+impl ConsensusEncoding for u8 {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        writer.write_all(&[*self])?;
+        Ok(1)
+    }
+}
+
+impl ConsensusEncoding for u16 {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        writer.write_all(&self.to_le_bytes())?;
+        Ok(2)
+    }
+}
+
+impl ConsensusEncoding for u64 {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        writer.write_all(&self.to_le_bytes())?;
+        Ok(8)
+    }
+}
+
+impl ConsensusEncoding for [u8] {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        write_bytes(writer, self)
+    }
+}
+
+impl ConsensusEncoding for Vec<u8> {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        write_bytes(writer, self)
+    }
+}
+
+impl<T: ConsensusEncoding> ConsensusEncoding for Option<T> {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        match self {
+            Some(value) => Ok(1u8.consensus_encode(writer)? + value.consensus_encode(writer)?),
+            None => 0u8.consensus_encode(writer),
+        }
+    }
+}
+
+/// Fixed size byte array types are written as is, without a length prefix
+macro_rules! impl_fixed_size_encoding {
+    ($($ty:ty),+) => {
+        $(
+            impl ConsensusEncoding for $ty {
+                fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+                    writer.write_all(self.as_bytes())?;
+                    Ok(self.as_bytes().len())
+                }
+            }
+        )+
+    };
+}
+
+impl_fixed_size_encoding!(PublicKey, PrivateKey, Commitment);
+
+impl ConsensusEncoding for Signature {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut written = self.get_public_nonce().consensus_encode(writer)?;
+        written += self.get_signature().consensus_encode(writer)?;
+        Ok(written)
+    }
+}
+
+impl ConsensusEncoding for ComSignature {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut written = self.public_nonce().consensus_encode(writer)?;
+        written += self.u().consensus_encode(writer)?;
+        written += self.v().consensus_encode(writer)?;
+        Ok(written)
+    }
+}
+
+impl ConsensusEncoding for RangeProof {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        write_bytes(writer, self.as_bytes())
+    }
+}
+
+impl ConsensusEncoding for TariScript {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        write_bytes(writer, &self.as_bytes())
+    }
+}
+
+impl ConsensusEncoding for ExecutionStack {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        write_bytes(writer, &self.as_bytes())
+    }
+}
+
+impl ConsensusEncoding for Covenant {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        write_bytes(writer, &self.as_bytes())
+    }
+}
+
+impl ConsensusEncoding for MicroTari {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        u64::from(*self).consensus_encode(writer)
+    }
+}
+
+impl ConsensusEncoding for KernelFeatures {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        self.bits().consensus_encode(writer)
+    }
+}
+
+/// Without encrypted data or a unique id, this is byte-for-byte what the bincode serialisation of `OutputFeatures`
+/// produced, which input and output hashes were originally built on. The later fields are only appended when present,
+/// so the hashes of existing outputs do not change. The encrypted data is length prefixed, so features with a unique id
+/// (which always include the encrypted data, even if empty) cannot encode to the same bytes as features without one.
+impl ConsensusEncoding for OutputFeatures {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut written = self.flags.bits().consensus_encode(writer)?;
+        written += self.maturity.consensus_encode(writer)?;
+        let has_unique_id = self.parent_public_key.is_some() || self.unique_id.is_some();
+        if !self.encrypted_data.is_empty() || has_unique_id {
+            written += self.encrypted_data.as_bytes().consensus_encode(writer)?;
+        }
+        if has_unique_id {
+            written += self.parent_public_key.consensus_encode(writer)?;
+            written += self.unique_id.consensus_encode(writer)?;
+        }
+        Ok(written)
+    }
+}
+
+impl ConsensusEncoding for TransactionKernel {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut written = self.features.consensus_encode(writer)?;
+        written += self.fee.consensus_encode(writer)?;
+        written += self.lock_height.consensus_encode(writer)?;
+        written += self.excess.consensus_encode(writer)?;
+        written += self.excess_sig.consensus_encode(writer)?;
+        Ok(written)
+    }
+}
+
+impl ConsensusEncoding for TransactionInput {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut written = self.features.consensus_encode(writer)?;
+        written += self.commitment.consensus_encode(writer)?;
+        written += self.script.consensus_encode(writer)?;
+        written += self.input_data.consensus_encode(writer)?;
+        written += self.script_signature.consensus_encode(writer)?;
+        written += self.sender_offset_public_key.consensus_encode(writer)?;
+        written += self.covenant.consensus_encode(writer)?;
+        Ok(written)
+    }
+}
+
+impl ConsensusEncoding for TransactionOutput {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut written = self.features.consensus_encode(writer)?;
+        written += self.commitment.consensus_encode(writer)?;
+        written += self.proof.consensus_encode(writer)?;
+        written += self.script.consensus_encode(writer)?;
+        written += self.sender_offset_public_key.consensus_encode(writer)?;
+        written += self.metadata_signature.consensus_encode(writer)?;
+        written += self.covenant.consensus_encode(writer)?;
+        Ok(written)
+    }
+}
+
+impl ConsensusEncoding for ProofOfWork {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut written = (self.pow_algo as u8).consensus_encode(writer)?;
+        written += self.pow_data.consensus_encode(writer)?;
+        Ok(written)
+    }
+}
+
+#[cfg(any(feature = "base_node", feature = "base_node_proto"))]
+impl ConsensusEncoding for BlockHeader {
+    fn consensus_encode<W: Write>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut written = self.version.consensus_encode(writer)?;
+        written += self.height.consensus_encode(writer)?;
+        written += self.prev_hash.consensus_encode(writer)?;
+        written += self.timestamp.as_u64().consensus_encode(writer)?;
+        written += self.output_mr.consensus_encode(writer)?;
+        written += self.witness_mr.consensus_encode(writer)?;
+        written += self.output_mmr_size.consensus_encode(writer)?;
+        written += self.kernel_mr.consensus_encode(writer)?;
+        written += self.kernel_mmr_size.consensus_encode(writer)?;
+        written += self.input_mr.consensus_encode(writer)?;
+        written += self.total_kernel_offset.consensus_encode(writer)?;
+        written += self.total_script_offset.consensus_encode(writer)?;
+        written += self.nonce.consensus_encode(writer)?;
+        written += self.pow.consensus_encode(writer)?;
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        proof_of_work::PowAlgorithm,
+        proto,
+        transactions::{
+            covenant::CovenantFilter,
+            encrypted_data::EncryptedData,
+            transaction::OutputFlags,
+            types::HashDigest,
+        },
+    };
+    use blake2::Digest;
+    use prost::Message;
+    use std::convert::TryFrom;
+    use tari_crypto::tari_utilities::{
+        epoch_time::EpochTime,
+        hex::{from_hex, to_hex},
+        Hashable,
+    };
+
+    // Canonical encodings of G, 2G and 3G, where G is the Ristretto base point
+    const POINT_G: &str = "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76";
+    const POINT_2G: &str = "6a493210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919";
+    const POINT_3G: &str = "94741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d0259";
+
+    fn public_key(hex: &str) -> PublicKey {
+        PublicKey::from_bytes(&from_hex(hex).unwrap()).unwrap()
+    }
+
+    fn commitment(hex: &str) -> Commitment {
+        Commitment::from_bytes(&from_hex(hex).unwrap()).unwrap()
+    }
+
+    fn scalar(n: u8) -> PrivateKey {
+        let mut bytes = [0u8; 32];
+        bytes[0] = n;
+        PrivateKey::from_bytes(&bytes).unwrap()
+    }
+
+    fn encode_proto<T: Message>(msg: &T) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(msg.encoded_len());
+        msg.encode(&mut buf).unwrap();
+        buf
+    }
+
+    fn sample_kernel() -> TransactionKernel {
+        TransactionKernel {
+            features: KernelFeatures::COINBASE_KERNEL,
+            fee: MicroTari::from(250),
+            lock_height: 7,
+            excess: commitment(POINT_G),
+            excess_sig: Signature::new(public_key(POINT_2G), scalar(3)),
+        }
+    }
+
+    fn sample_output() -> TransactionOutput {
+        TransactionOutput::new(
+            OutputFeatures::create_coinbase(10),
+            commitment(POINT_3G),
+            RangeProof::from_bytes(&[0xaa; 4]).unwrap(),
+            TariScript::new(vec![]),
+            public_key(POINT_2G),
+            ComSignature::new(commitment(POINT_G), scalar(1), scalar(2)),
+            Covenant::new(vec![CovenantFilter::AbsoluteHeight(1000)]),
+        )
+    }
+
+    fn sample_input() -> TransactionInput {
+        TransactionInput::new(
+            OutputFeatures::default(),
+            commitment(POINT_3G),
+            TariScript::new(vec![]),
+            ExecutionStack::new(vec![]),
+            ComSignature::new(commitment(POINT_2G), scalar(4), scalar(5)),
+            public_key(POINT_G),
+            Covenant::default(),
+        )
+    }
+
+    fn sample_header() -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            height: 42,
+            prev_hash: vec![0x11; 32],
+            timestamp: EpochTime::from(1_600_000_000),
+            output_mr: vec![0x22; 32],
+            witness_mr: vec![0x33; 32],
+            output_mmr_size: 100,
+            kernel_mr: vec![0x44; 32],
+            kernel_mmr_size: 50,
+            input_mr: vec![0x55; 32],
+            total_kernel_offset: scalar(6),
+            total_script_offset: scalar(7),
+            nonce: 0xdead_beef,
+            pow: ProofOfWork {
+                pow_algo: PowAlgorithm::Sha3,
+                pow_data: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn varint_encoding() {
+        let cases: &[(u64, &[u8])] = &[
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (300, &[0xac, 0x02]),
+            (u64::MAX, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        ];
+        for (n, expected) in cases {
+            let mut buf = Vec::new();
+            let written = write_varint(&mut buf, *n).unwrap();
+            assert_eq!(written, expected.len());
+            assert_eq!(&buf, expected);
+        }
+    }
+
+    #[test]
+    fn output_features_encoding_matches_bincode() {
+        let cases = vec![
+            OutputFeatures::default(),
+            OutputFeatures::create_coinbase(10),
+            OutputFeatures::with_maturity(u64::MAX),
+            OutputFeatures {
+                flags: OutputFlags::all(),
+                maturity: 1,
+                ..Default::default()
+            },
+        ];
+        for features in cases {
+            // Bincode of the fields that existed before encrypted data and unique ids were added
+            let legacy = bincode::serialize(&(features.flags, features.maturity)).unwrap();
+            assert_eq!(features.to_consensus_bytes(), legacy);
+            assert_eq!(features.to_bytes(), features.to_consensus_bytes());
+        }
+        assert_eq!(
+            to_hex(&OutputFeatures::create_coinbase(10).to_consensus_bytes()),
+            "010a00000000000000"
+        );
+    }
+
+    #[test]
+    fn output_features_encrypted_data_is_appended() {
+        let features = OutputFeatures {
+            flags: OutputFlags::COINBASE_OUTPUT,
+            maturity: 10,
+            encrypted_data: EncryptedData::from_bytes(vec![0xaa, 0xbb]).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(to_hex(&features.to_consensus_bytes()), "010a0000000000000002aabb");
+    }
+
+    #[test]
+    fn output_features_unique_id_is_appended() {
+        let features = OutputFeatures::with_unique_id(None, vec![0x01, 0x02]);
+        assert_eq!(to_hex(&features.to_consensus_bytes()), "000000000000000000000001020102");
+
+        let features = OutputFeatures::with_unique_id(Some(PublicKey::default()), vec![0x01, 0x02]);
+        assert_eq!(
+            to_hex(&features.to_consensus_bytes()),
+            format!("0000000000000000000001{}01020102", "00".repeat(32))
+        );
+
+        // Encrypted data that happens to look like an empty unique id section does not encode the same
+        let features = OutputFeatures::with_encrypted_data(EncryptedData::from_bytes(vec![0x00, 0x01]).unwrap());
+        assert_ne!(
+            features.to_consensus_bytes(),
+            OutputFeatures::with_unique_id(None, vec![]).to_consensus_bytes()
+        );
+    }
+
+    #[test]
+    fn kernel_golden_vectors() {
+        let kernel = sample_kernel();
+        let bytes = kernel.to_consensus_bytes();
+        assert_eq!(
+            to_hex(&bytes),
+            "01fa000000000000000700000000000000e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d766a49321\
+             0f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b91903000000000000000000000000000000000000000000000\
+             00000000000000000"
+        );
+        // The kernel hash is taken over exactly the canonical encoding
+        assert_eq!(kernel.hash(), HashDigest::digest(&bytes).to_vec());
+        assert_eq!(
+            to_hex(&kernel.hash()),
+            "08c65c1c3c28ce24f13fa7ad781817676b2cdf2d48baefc2342e724650a918ff"
+        );
+    }
+
+    #[test]
+    fn output_golden_vectors() {
+        let output = sample_output();
+        assert_eq!(
+            to_hex(&output.to_consensus_bytes()),
+            "010a0000000000000094741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d025904aaaaaaaa006a493210f\
+             7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6\
+             a65945e08d2d76010000000000000000000000000000000000000000000000000000000000000002000000000000000000000000\
+             000000000000000000000000000000000000000901e803000000000000"
+        );
+        assert_eq!(
+            to_hex(&output.hash()),
+            "0f46f85174df505ccdfe4795733921ff4927e29d3cd4237342d91ac1e79aaa01"
+        );
+    }
+
+    #[test]
+    fn output_hash_without_covenant_is_unchanged() {
+        let mut output = sample_output();
+        output.covenant = Covenant::default();
+        // Outputs from before covenants existed, and so the output MMR roots of existing blocks, keep their hash
+        let expected = HashDigest::new()
+            .chain(output.features.to_bytes())
+            .chain(output.commitment.as_bytes())
+            .chain(output.script.as_bytes())
+            .finalize();
+        assert_eq!(output.hash(), expected.to_vec());
+        assert_eq!(
+            to_hex(&output.hash()),
+            "99e7cc2f447fb9ffc5a697c665de047bea4c63ca15f642d2260a1ee995252381"
+        );
+    }
+
+    #[test]
+    fn input_golden_vectors() {
+        let input = sample_input();
+        assert_eq!(
+            to_hex(&input.to_consensus_bytes()),
+            "00000000000000000094741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d025900006a493210f7499cd17f\
+             ecb510ae0cea23a110e8d5b901f8acadd3095c73a3b9190400000000000000000000000000000000000000000000000000000000\
+             0000000500000000000000000000000000000000000000000000000000000000000000e2f2ae0a6abc4e71a884a961c500515f58\
+             e30b6aa582dd8db6a65945e08d2d7600"
+        );
+        assert_eq!(
+            to_hex(&input.hash()),
+            "4088bf72950951bf55b803f686c43a1a27a76296ebc2dfc3b8c5b726adcd066b"
+        );
+    }
+
+    #[test]
+    fn header_golden_vectors() {
+        let header = sample_header();
+        assert_eq!(
+            to_hex(&header.to_consensus_bytes()),
+            "01002a0000000000000020111111111111111111111111111111111111111111111111111111111111111100105e5f0000000020\
+             22222222222222222222222222222222222222222222222222222222222222222033333333333333333333333333333333333333\
+             33333333333333333333333333640000000000000020444444444444444444444444444444444444444444444444444444444444\
+             44443200000000000000205555555555555555555555555555555555555555555555555555555555555555060000000000000000\
+             00000000000000000000000000000000000000000000000700000000000000000000000000000000000000000000000000000000\
+             000000efbeadde000000000100"
+        );
+        assert_eq!(
+            to_hex(&header.merged_mining_hash()),
+            "b8caf211eff002c7785f7eaf9455679f2ed424174c63d2376081d990636aa6b4"
+        );
+        assert_eq!(
+            to_hex(&header.hash()),
+            "f9390957ab1ba0ae6846bb5e176fcd9e543b9711bb9993e1e62b65a30b92a197"
+        );
+    }
+
+    #[test]
+    fn kernel_protobuf_wire_compatibility() {
+        let kernel = sample_kernel();
+        let msg = proto::types::TransactionKernel::from(kernel.clone());
+        let bytes = encode_proto(&msg);
+        assert_eq!(
+            to_hex(&bytes),
+            "080110fa01180732220a20e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d763a440a206a493210f7\
+             499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919122003000000000000000000000000000000000000000000000\
+             00000000000000000"
+        );
+        let decoded = proto::types::TransactionKernel::decode(bytes.as_slice()).unwrap();
+        let kernel2 = TransactionKernel::try_from(decoded).unwrap();
+        assert_eq!(kernel2, kernel);
+        assert_eq!(kernel2.to_consensus_bytes(), kernel.to_consensus_bytes());
+    }
+
+    #[test]
+    fn output_protobuf_wire_compatibility() {
+        let output = sample_output();
+        let msg = proto::types::TransactionOutput::from(output.clone());
+        let bytes = encode_proto(&msg);
+        assert_eq!(
+            to_hex(&bytes),
+            "0a040801100a12220a2094741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d02591a04aaaaaaaa2a206a49\
+             3210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b91932660a20e2f2ae0a6abc4e71a884a961c500515f58e3\
+             0b6aa582dd8db6a65945e08d2d76122001000000000000000000000000000000000000000000000000000000000000001a200200\
+             0000000000000000000000000000000000000000000000000000000000003a0901e803000000000000"
+        );
+        let decoded = proto::types::TransactionOutput::decode(bytes.as_slice()).unwrap();
+        let output2 = TransactionOutput::try_from(decoded).unwrap();
+        assert_eq!(output2.to_consensus_bytes(), output.to_consensus_bytes());
+        assert_eq!(output2.hash(), output.hash());
+    }
+
+    #[test]
+    fn input_protobuf_round_trip() {
+        let input = sample_input();
+        let bytes = encode_proto(&proto::types::TransactionInput::from(input.clone()));
+        let decoded = proto::types::TransactionInput::decode(bytes.as_slice()).unwrap();
+        let input2 = TransactionInput::try_from(decoded).unwrap();
+        assert_eq!(input2.to_consensus_bytes(), input.to_consensus_bytes());
+        assert_eq!(input2.hash(), input.hash());
+    }
+
+    #[test]
+    fn header_protobuf_wire_compatibility() {
+        let header = sample_header();
+        let msg = proto::core::BlockHeader::from(header.clone());
+        let bytes = encode_proto(&msg);
+        assert_eq!(
+            to_hex(&bytes),
+            "0801102a222011111111111111111111111111111111111111111111111111111111111111112a060880a0f8fa05322022222222\
+             222222222222222222222222222222222222222222222222222222223a2033333333333333333333333333333333333333333333\
+             33333333333333333333422044444444444444444444444444444444444444444444444444444444444444444a20555555555555\
+             55555555555555555555555555555555555555555555555555555220060000000000000000000000000000000000000000000000\
+             000000000000000058effdb6f50d62020801683270647a2007000000000000000000000000000000000000000000000000000000\
+             00000000"
+        );
+        let decoded = proto::core::BlockHeader::decode(bytes.as_slice()).unwrap();
+        let header2 = BlockHeader::try_from(decoded).unwrap();
+        assert_eq!(header2.to_consensus_bytes(), header.to_consensus_bytes());
+        assert_eq!(header2.hash(), header.hash());
+    }
+
+    #[test]
+    fn empty_protobuf_messages_are_rejected() {
+        // Proto3 drops default values from the wire, so a missing nested message must not silently decode into a
+        // default value.
+        assert!(TransactionKernel::try_from(proto::types::TransactionKernel::default()).is_err());
+        assert!(TransactionOutput::try_from(proto::types::TransactionOutput::default()).is_err());
+        assert!(BlockHeader::try_from(proto::core::BlockHeader::default()).is_err());
+    }
+}
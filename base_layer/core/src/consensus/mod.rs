@@ -24,6 +24,8 @@
 pub(crate) mod chain_strength_comparer;
 #[cfg(any(feature = "base_node", feature = "transactions"))]
 pub mod consensus_constants;
+#[cfg(any(feature = "base_node", feature = "transactions"))]
+pub mod consensus_encoding;
 #[cfg(feature = "base_node")]
 mod consensus_manager;
 #[cfg(any(feature = "base_node", feature = "transactions"))]
@@ -31,15 +33,10 @@ pub mod emission;
 #[cfg(any(feature = "base_node", feature = "transactions"))]
 mod network;
 
-#[cfg(any(feature = "base_node", feature = "transactions"))]
-pub const WEIGHT_PER_INPUT: u64 = 1;
-#[cfg(any(feature = "base_node", feature = "transactions"))]
-pub const WEIGHT_PER_OUTPUT: u64 = 13;
-#[cfg(any(feature = "base_node", feature = "transactions"))]
-pub const KERNEL_WEIGHT: u64 = 3; // Constant weight per transaction; covers kernel and part of header.
-
 #[cfg(any(feature = "base_node", feature = "transactions"))]
 pub use consensus_constants::{ConsensusConstants, ConsensusConstantsBuilder};
+#[cfg(any(feature = "base_node", feature = "transactions"))]
+pub use consensus_encoding::{ConsensusEncoding, ToConsensusBytes};
 #[cfg(feature = "base_node")]
 pub use consensus_manager::{ConsensusManager, ConsensusManagerBuilder, ConsensusManagerError};
 #[cfg(any(feature = "base_node", feature = "transactions"))]
@@ -20,7 +20,15 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mempool::{consts, reorg_pool::ReorgPoolConfig, unconfirmed_pool::UnconfirmedPoolConfig};
+use crate::{
+    base_node::inbound_pipeline::InboundPipelineConfig,
+    mempool::{
+        consts,
+        relay_policy::RelayPolicyConfig,
+        reorg_pool::ReorgPoolConfig,
+        unconfirmed_pool::UnconfirmedPoolConfig,
+    },
+};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tari_common::{configuration::seconds, NetworkConfigPath};
@@ -30,6 +38,8 @@ use tari_common::{configuration::seconds, NetworkConfigPath};
 pub struct MempoolConfig {
     pub unconfirmed_pool: UnconfirmedPoolConfig,
     pub reorg_pool: ReorgPoolConfig,
+    #[serde(default)]
+    pub relay_policy: RelayPolicyConfig,
 }
 
 impl Default for MempoolConfig {
@@ -37,6 +47,7 @@ impl Default for MempoolConfig {
         Self {
             unconfirmed_pool: UnconfirmedPoolConfig::default(),
             reorg_pool: ReorgPoolConfig::default(),
+            relay_policy: RelayPolicyConfig::default(),
         }
     }
 }
@@ -58,6 +69,9 @@ pub struct MempoolServiceConfig {
     pub initial_sync_num_peers: usize,
     /// The maximum number of transactions to sync in a single sync session Default: 10_000
     pub initial_sync_max_transactions: usize,
+    /// Queue and concurrency limits for processing transactions propagated by peers
+    #[serde(default)]
+    pub inbound_transaction_pipeline: InboundPipelineConfig,
 }
 
 impl Default for MempoolServiceConfig {
@@ -66,6 +80,7 @@ impl Default for MempoolServiceConfig {
             request_timeout: consts::MEMPOOL_SERVICE_REQUEST_TIMEOUT,
             initial_sync_num_peers: 2,
             initial_sync_max_transactions: 10_000,
+            inbound_transaction_pipeline: InboundPipelineConfig::default(),
         }
     }
 }
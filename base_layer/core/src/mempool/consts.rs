@@ -20,6 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::transactions::tari_amount::MicroTari;
 use std::time::Duration;
 
 /// The maximum number of transactions that can be stored in the Unconfirmed Transaction pool
@@ -28,10 +29,30 @@ pub const MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY: usize = 40_000;
 /// skipping over large transactions are performed in an attempt to fit more transactions into the remaining space.
 pub const MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT: usize = 20;
 
+/// The time-to-live duration used for transactions stored in the UnconfirmedPool
+pub const MEMPOOL_UNCONFIRMED_POOL_TX_TTL: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+/// The maximum number of transactions in the UnconfirmedPool that may create outputs carrying the same unique id
+pub const MEMPOOL_UNCONFIRMED_POOL_MAX_TXS_PER_UNIQUE_ID: usize = 4;
+/// The maximum total weight of the transactions in the UnconfirmedPool that create outputs with the same sender offset
+/// public key, which is five full blocks
+pub const MEMPOOL_UNCONFIRMED_POOL_MAX_WEIGHT_PER_SENDER: u64 = 5 * 19_500;
+
 /// The maximum number of transactions that can be stored in the Reorg pool
 pub const MEMPOOL_REORG_POOL_STORAGE_CAPACITY: usize = 5_000;
 /// The time-to-live duration used for transactions stored in the ReorgPool
 pub const MEMPOOL_REORG_POOL_CACHE_TTL: Duration = Duration::from_secs(300);
 
+/// The maximum number of recently removed transactions whose removal reason (expired or conflicted) is remembered
+pub const MEMPOOL_REMOVED_TX_CACHE_CAPACITY: usize = 10_000;
+/// The time that the removal reason of a transaction is remembered, so that wallets can query why it was removed
+pub const MEMPOOL_REMOVED_TX_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The default maximum size in bytes of an output script that the mempool will accept and relay
+pub const MEMPOOL_RELAY_MAX_SCRIPT_SIZE: usize = 512;
+/// The default minimum average fee per gram that the mempool will accept and relay
+pub const MEMPOOL_RELAY_MIN_FEE_PER_GRAM: MicroTari = MicroTari(1);
+/// The default maximum number of outputs in a transaction that the mempool will accept and relay
+pub const MEMPOOL_RELAY_MAX_OUTPUTS: usize = 500;
+
 /// The allocated waiting time for a request waiting for service responses from the mempools of remote base nodes.
 pub const MEMPOOL_SERVICE_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
@@ -21,11 +21,13 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    blocks::Block,
+    blocks::{Block, KernelShortId},
+    consensus::ConsensusManager,
     mempool::{
         error::MempoolError,
         mempool_storage::MempoolStorage,
         MempoolConfig,
+        RelayPolicyConfig,
         StateResponse,
         StatsResponse,
         TxStorageResponse,
@@ -45,9 +47,13 @@ pub struct Mempool {
 
 impl Mempool {
     /// Create a new Mempool with an UnconfirmedPool, OrphanPool, PendingPool and ReOrgPool.
-    pub fn new(config: MempoolConfig, validator: Arc<dyn MempoolTransactionValidation>) -> Self {
+    pub fn new(
+        config: MempoolConfig,
+        rules: ConsensusManager,
+        validator: Arc<dyn MempoolTransactionValidation>,
+    ) -> Self {
         Self {
-            pool_storage: Arc::new(RwLock::new(MempoolStorage::new(config, validator))),
+            pool_storage: Arc::new(RwLock::new(MempoolStorage::new(config, rules, validator))),
         }
     }
 
@@ -60,6 +66,16 @@ impl Mempool {
             .insert(tx)
     }
 
+    /// Replace the relay policy that new transactions must satisfy. Transactions that are already in the Mempool are
+    /// not affected.
+    pub fn set_relay_policy(&self, config: RelayPolicyConfig) -> Result<(), MempoolError> {
+        self.pool_storage
+            .write()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .set_relay_policy(config);
+        Ok(())
+    }
+
     /// Update the Mempool based on the received published block.
     pub fn process_published_block(&self, published_block: Arc<Block>) -> Result<(), MempoolError> {
         self.pool_storage
@@ -99,6 +115,17 @@ impl Mempool {
             .retrieve(total_weight)
     }
 
+    /// Returns the transactions in the Mempool that contain kernels with the given short ids.
+    pub fn retrieve_by_kernel_short_ids(
+        &self,
+        short_ids: Vec<KernelShortId>,
+    ) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        self.pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .retrieve_by_kernel_short_ids(&short_ids)
+    }
+
     /// Check if the specified transaction is stored in the Mempool.
     pub fn has_tx_with_excess_sig(&self, excess_sig: Signature) -> Result<TxStorageResponse, MempoolError> {
         self.pool_storage
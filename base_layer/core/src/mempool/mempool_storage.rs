@@ -20,10 +20,15 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[cfg(feature = "metrics")]
+use crate::mempool::metrics;
 use crate::{
-    blocks::Block,
+    blocks::{kernel_short_id, Block, KernelShortId},
+    consensus::ConsensusManager,
     mempool::{
+        consts::{MEMPOOL_REMOVED_TX_CACHE_CAPACITY, MEMPOOL_REMOVED_TX_CACHE_TTL},
         error::MempoolError,
+        relay_policy::{RelayPolicy, RelayPolicyConfig},
         reorg_pool::ReorgPool,
         unconfirmed_pool::UnconfirmedPool,
         MempoolConfig,
@@ -31,12 +36,13 @@ use crate::{
         StatsResponse,
         TxStorageResponse,
     },
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{transaction::Transaction, types::Signature, weight::TransactionWeight},
     validation::{MempoolTransactionValidation, ValidationError},
 };
 use log::*;
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+use ttl_cache::TtlCache;
 
 pub const LOG_TARGET: &str = "c::mp::mempool_storage";
 
@@ -46,22 +52,71 @@ pub const LOG_TARGET: &str = "c::mp::mempool_storage";
 pub struct MempoolStorage {
     unconfirmed_pool: UnconfirmedPool,
     reorg_pool: ReorgPool,
+    relay_policy: RelayPolicy,
     validator: Arc<dyn MempoolTransactionValidation>,
+    /// The reason that recently expired or conflicted transactions were removed from the unconfirmed pool
+    removed_txs: TtlCache<Signature, TxStorageResponse>,
+    rules: ConsensusManager,
+    /// The height of the most recent block the mempool has processed
+    last_seen_height: u64,
 }
 
 impl MempoolStorage {
     /// Create a new Mempool with an UnconfirmedPool and ReOrgPool.
-    pub fn new(config: MempoolConfig, validators: Arc<dyn MempoolTransactionValidation>) -> Self {
+    pub fn new(
+        config: MempoolConfig,
+        rules: ConsensusManager,
+        validators: Arc<dyn MempoolTransactionValidation>,
+    ) -> Self {
         Self {
             unconfirmed_pool: UnconfirmedPool::new(config.unconfirmed_pool),
             reorg_pool: ReorgPool::new(config.reorg_pool),
+            relay_policy: RelayPolicy::new(config.relay_policy),
             validator: validators,
+            removed_txs: TtlCache::new(MEMPOOL_REMOVED_TX_CACHE_CAPACITY),
+            rules,
+            last_seen_height: 0,
         }
     }
 
+    /// The weighting of the consensus rules for the next block, which is the block that transactions stored now are
+    /// candidates for
+    fn get_transaction_weighting(&self) -> TransactionWeight {
+        *self
+            .rules
+            .consensus_constants(self.last_seen_height + 1)
+            .transaction_weight()
+    }
+
+    /// Replace the relay policy that new transactions must satisfy. Transactions that are already stored are kept.
+    pub fn set_relay_policy(&mut self, config: RelayPolicyConfig) {
+        self.relay_policy = RelayPolicy::new(config);
+    }
+
     /// Insert an unconfirmed transaction into the Mempool. The transaction *MUST* have passed through the validation
-    /// pipeline already and will thus always be internally consistent by this stage
+    /// pipeline already and will thus always be internally consistent by this stage. Transactions that break the
+    /// node's relay policy or would exceed the admission limits of the unconfirmed pool are not stored.
     pub fn insert(&mut self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
+        let weighting = self.get_transaction_weighting();
+        if let Err(violation) = self
+            .relay_policy
+            .check(&tx, &weighting)
+            .and_then(|_| self.unconfirmed_pool.check_admission_limits(&tx, &weighting))
+        {
+            warn!(
+                target: LOG_TARGET,
+                "Transaction rejected by relay policy: {}", violation
+            );
+            return Ok(TxStorageResponse::NotStoredPolicyViolation(violation));
+        }
+        let result = self.validate_and_insert(tx);
+        self.update_metrics();
+        result
+    }
+
+    // Validate and insert a transaction without applying the relay policy. Transactions that were already accepted,
+    // e.g. those re-submitted after a reorg, are not checked against the relay policy again.
+    fn validate_and_insert(&mut self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
         debug!(
             target: LOG_TARGET,
             "Inserting tx into mempool: {}",
@@ -71,14 +126,15 @@ impl MempoolStorage {
                 .map(|k| k.excess_sig.get_signature().to_hex())
                 .unwrap_or_else(|| "None".into())
         );
+        let weighting = self.get_transaction_weighting();
         match self.validator.validate(&tx) {
             Ok(()) => {
-                self.unconfirmed_pool.insert(tx, None)?;
+                self.unconfirmed_pool.insert(tx, None, &weighting)?;
                 Ok(TxStorageResponse::UnconfirmedPool)
             },
             Err(ValidationError::UnknownInputs(dependent_outputs)) => {
                 if self.unconfirmed_pool.verify_outputs_exist(&dependent_outputs) {
-                    self.unconfirmed_pool.insert(tx, Some(dependent_outputs))?;
+                    self.unconfirmed_pool.insert(tx, Some(dependent_outputs), &weighting)?;
                     Ok(TxStorageResponse::UnconfirmedPool)
                 } else {
                     warn!(target: LOG_TARGET, "Validation failed due to unknown inputs");
@@ -103,7 +159,7 @@ impl MempoolStorage {
     // Insert a set of new transactions into the UTxPool.
     fn insert_txs(&mut self, txs: Vec<Arc<Transaction>>) -> Result<(), MempoolError> {
         for tx in txs {
-            self.insert(tx)?;
+            self.validate_and_insert(tx)?;
         }
         Ok(())
     }
@@ -111,15 +167,35 @@ impl MempoolStorage {
     /// Update the Mempool based on the received published block.
     pub fn process_published_block(&mut self, published_block: Arc<Block>) -> Result<(), MempoolError> {
         trace!(target: LOG_TARGET, "Mempool processing new block: {}", published_block);
+        self.last_seen_height = published_block.header.height;
         // Move published txs to ReOrgPool and discard double spends
-        self.reorg_pool.insert_txs(
-            self.unconfirmed_pool
-                .remove_published_and_discard_deprecated_transactions(&published_block),
-        )?;
+        let mut results = self
+            .unconfirmed_pool
+            .remove_published_and_discard_deprecated_transactions(&published_block);
+        self.record_removed_txs(&results.deprecated_transactions, TxStorageResponse::NotStoredConflict);
+        results
+            .published_transactions
+            .append(&mut results.deprecated_transactions);
+        self.reorg_pool.insert_txs(results.published_transactions)?;
+
+        // New blocks also drive the expiry sweep of transactions that have been waiting for too long
+        let expired_txs = self.unconfirmed_pool.remove_expired_transactions();
+        self.record_removed_txs(&expired_txs, TxStorageResponse::NotStoredExpired);
+        self.update_metrics();
 
         Ok(())
     }
 
+    // Remember why transactions were removed from the unconfirmed pool so that wallets can be told
+    fn record_removed_txs(&mut self, txs: &[Arc<Transaction>], reason: TxStorageResponse) {
+        for tx in txs {
+            if let Some(excess_sig) = tx.first_kernel_excess_sig() {
+                self.removed_txs
+                    .insert(excess_sig.clone(), reason.clone(), MEMPOOL_REMOVED_TX_CACHE_TTL);
+            }
+        }
+    }
+
     /// In the event of a ReOrg, resubmit all ReOrged transactions into the Mempool and process each newly introduced
     /// block from the latest longest chain.
     pub fn process_reorg(
@@ -147,6 +223,10 @@ impl MempoolStorage {
 
         let previous_tip = removed_blocks.last().map(|block| block.header.height);
         let new_tip = new_blocks.last().map(|block| block.header.height);
+        // Re-submitted transactions are weighed for the block after the new tip
+        if let Some(height) = new_tip {
+            self.last_seen_height = height;
+        }
 
         // Clear out all transactions from the unconfirmed pool and re-submit them to the unconfirmed mempool for
         // validation. This is important as invalid transactions that have not been mined yet may remain in the mempool
@@ -183,6 +263,7 @@ impl MempoolStorage {
                 );
             }
         }
+        self.update_metrics();
 
         Ok(())
     }
@@ -199,13 +280,35 @@ impl MempoolStorage {
     pub fn retrieve(&mut self, total_weight: u64) -> Result<Vec<Arc<Transaction>>, MempoolError> {
         let results = self.unconfirmed_pool.highest_priority_txs(total_weight)?;
         self.insert_txs(results.transactions_to_insert)?;
+        self.update_metrics();
         Ok(results.retrieved_transactions)
     }
 
-    /// Check if the specified transaction is stored in the Mempool.
+    /// Returns the transactions in the unconfirmed and reorg pools that contain kernels with the given short ids. The
+    /// reorg pool is included so that blocks on a competing chain can be reconstructed.
+    pub fn retrieve_by_kernel_short_ids(
+        &self,
+        short_ids: &[KernelShortId],
+    ) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        let mut txs = self.unconfirmed_pool.retrieve_by_kernel_short_ids(short_ids);
+        let short_ids = short_ids.iter().collect::<HashSet<_>>();
+        let reorg_txs = self.reorg_pool.snapshot()?.into_iter().filter(|tx| {
+            tx.body
+                .kernels()
+                .iter()
+                .any(|k| short_ids.contains(&kernel_short_id(&k.excess_sig)))
+        });
+        txs.extend(reorg_txs);
+        Ok(txs)
+    }
+
+    /// Check if the specified transaction is stored in the Mempool. If it was recently removed from the unconfirmed
+    /// pool because it expired or conflicts with a mined transaction, the reason is returned.
     pub fn has_tx_with_excess_sig(&self, excess_sig: Signature) -> Result<TxStorageResponse, MempoolError> {
         if self.unconfirmed_pool.has_tx_with_excess_sig(&excess_sig) {
             Ok(TxStorageResponse::UnconfirmedPool)
+        } else if let Some(reason) = self.removed_txs.get(&excess_sig) {
+            Ok(reason.clone())
         } else if self.reorg_pool.has_tx_with_excess_sig(&excess_sig)? {
             Ok(TxStorageResponse::ReorgPool)
         } else {
@@ -220,7 +323,8 @@ impl MempoolStorage {
 
     // Returns the total weight of all transactions stored in the Mempool.
     fn calculate_weight(&self) -> Result<u64, MempoolError> {
-        Ok(self.unconfirmed_pool.calculate_weight() + self.reorg_pool.calculate_weight()?)
+        let weighting = self.get_transaction_weighting();
+        Ok(self.unconfirmed_pool.calculate_weight(&weighting) + self.reorg_pool.calculate_weight(&weighting)?)
     }
 
     /// Gathers and returns the stats of the Mempool.
@@ -233,6 +337,18 @@ impl MempoolStorage {
         })
     }
 
+    #[cfg(feature = "metrics")]
+    fn update_metrics(&self) {
+        if let Ok(stats) = self.stats() {
+            metrics::unconfirmed_txs().set(stats.unconfirmed_txs as i64);
+            metrics::reorg_txs().set(stats.reorg_txs as i64);
+            metrics::total_weight().set(stats.total_weight as i64);
+        }
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn update_metrics(&self) {}
+
     /// Gathers and returns a breakdown of all the transaction in the Mempool.
     pub fn state(&self) -> Result<StateResponse, MempoolError> {
         let unconfirmed_pool = self
@@ -0,0 +1,51 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use lazy_static::lazy_static;
+use tari_metrics::IntGauge;
+
+lazy_static! {
+    static ref UNCONFIRMED_TXS: IntGauge = tari_metrics::register_int_gauge(
+        "mempool_unconfirmed_txs",
+        "The number of transactions in the unconfirmed pool"
+    )
+    .unwrap();
+    static ref REORG_TXS: IntGauge =
+        tari_metrics::register_int_gauge("mempool_reorg_txs", "The number of transactions in the reorg pool").unwrap();
+    static ref TOTAL_WEIGHT: IntGauge = tari_metrics::register_int_gauge(
+        "mempool_total_weight",
+        "The total weight of the transactions in the mempool"
+    )
+    .unwrap();
+}
+
+pub fn unconfirmed_txs() -> &'static IntGauge {
+    &UNCONFIRMED_TXS
+}
+
+pub fn reorg_txs() -> &'static IntGauge {
+    &REORG_TXS
+}
+
+pub fn total_weight() -> &'static IntGauge {
+    &TOTAL_WEIGHT
+}
@@ -34,9 +34,15 @@ mod error;
 mod mempool;
 #[cfg(feature = "base_node")]
 mod mempool_storage;
+#[cfg(all(feature = "base_node", feature = "metrics"))]
+mod metrics;
 #[cfg(feature = "base_node")]
 mod priority;
 #[cfg(feature = "base_node")]
+mod relay_policy;
+#[cfg(feature = "base_node")]
+pub use relay_policy::{RelayPolicy, RelayPolicyConfig};
+#[cfg(feature = "base_node")]
 mod reorg_pool;
 #[cfg(feature = "base_node")]
 mod rpc;
@@ -46,6 +52,8 @@ pub use rpc::create_mempool_rpc_service;
 pub use rpc::{MempoolRpcClient, MempoolRpcServer, MempoolRpcService, MempoolService};
 #[cfg(feature = "base_node")]
 mod unconfirmed_pool;
+#[cfg(feature = "base_node")]
+pub use unconfirmed_pool::UnconfirmedPoolConfig;
 
 // public modules
 #[cfg(feature = "base_node")]
@@ -132,6 +140,11 @@ pub enum TxStorageResponse {
     NotStoredOrphan,
     NotStoredTimeLocked,
     NotStoredAlreadySpent,
+    NotStoredPolicyViolation(RelayPolicyViolation),
+    /// The transaction was removed from the unconfirmed pool because it was not mined within the Time-to-live
+    NotStoredExpired,
+    /// The transaction was removed from the unconfirmed pool because it conflicts with a mined transaction
+    NotStoredConflict,
     NotStored,
 }
 
@@ -149,12 +162,44 @@ impl Display for TxStorageResponse {
             TxStorageResponse::NotStoredOrphan => "Not stored orphan transaction",
             TxStorageResponse::NotStoredTimeLocked => "Not stored time locked transaction",
             TxStorageResponse::NotStoredAlreadySpent => "Not stored output already spent",
+            TxStorageResponse::NotStoredPolicyViolation(violation) => {
+                return write!(fmt, "Not stored relay policy violation ({})", violation);
+            },
+            TxStorageResponse::NotStoredExpired => "Not stored expired transaction",
+            TxStorageResponse::NotStoredConflict => "Not stored conflicting transaction",
             TxStorageResponse::NotStored => "Not stored",
         };
         fmt.write_str(&storage)
     }
 }
 
+/// The relay policy rule that a transaction broke. The relay policy is local to each node and is not part of consensus.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RelayPolicyViolation {
+    ScriptTooLarge,
+    FeePerGramTooLow,
+    TooManyOutputs,
+    DustOutput,
+    /// Too many unconfirmed transactions already create an output carrying the same unique id
+    TooManyUnconfirmedForUniqueId,
+    /// The unconfirmed transactions from the same sender already weigh too much
+    SenderWeightLimitExceeded,
+}
+
+impl Display for RelayPolicyViolation {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        let violation = match self {
+            RelayPolicyViolation::ScriptTooLarge => "Output script too large",
+            RelayPolicyViolation::FeePerGramTooLow => "Fee per gram too low",
+            RelayPolicyViolation::TooManyOutputs => "Too many outputs",
+            RelayPolicyViolation::DustOutput => "Fee too low for the number of outputs",
+            RelayPolicyViolation::TooManyUnconfirmedForUniqueId => "Too many unconfirmed transactions for a unique id",
+            RelayPolicyViolation::SenderWeightLimitExceeded => "Unconfirmed weight limit for the sender exceeded",
+        };
+        fmt.write_str(violation)
+    }
+}
+
 /// Events that can be published on state changes of the Mempool
 #[derive(Debug, Clone)]
 pub enum MempoolStateEvent {
@@ -22,9 +22,7 @@
 
 mod error;
 mod prioritized_transaction;
-mod timelocked_transaction;
 
 // Public re-exports
 pub use error::PriorityError;
 pub use prioritized_transaction::{FeePriority, PrioritizedTransaction};
-pub use timelocked_transaction::{TimelockPriority, TimelockedTransaction};
@@ -22,9 +22,9 @@
 
 use crate::{
     mempool::priority::PriorityError,
-    transactions::{transaction::Transaction, types::HashOutput},
+    transactions::{transaction::Transaction, types::HashOutput, weight::TransactionWeight},
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 use tari_crypto::tari_utilities::message_format::MessageFormat;
 
 /// Create a unique unspent transaction priority based on the transaction fee, maturity of the oldest input UTXO and the
@@ -34,9 +34,15 @@ use tari_crypto::tari_utilities::message_format::MessageFormat;
 pub struct FeePriority(Vec<u8>);
 
 impl FeePriority {
-    pub fn try_from(transaction: &Transaction) -> Result<Self, PriorityError> {
+    pub fn try_from(transaction: &Transaction, weighting: &TransactionWeight) -> Result<Self, PriorityError> {
         // The weights have been normalised, so the fee priority is now equal to the fee per gram ± a few pct points
-        let fee_per_byte = (transaction.calculate_ave_fee_per_gram() * 1000.0) as usize; // Include 3 decimal places before flooring
+        Self::try_from_fee_per_gram(transaction, transaction.calculate_ave_fee_per_gram(weighting))
+    }
+
+    /// Create the priority of a transaction using the given fee per gram rather than the transaction's own, e.g. the
+    /// fee per gram of a transaction together with its unconfirmed parents.
+    pub fn try_from_fee_per_gram(transaction: &Transaction, fee_per_gram: f64) -> Result<Self, PriorityError> {
+        let fee_per_byte = (fee_per_gram * 1000.0) as usize; // Include 3 decimal places before flooring
         let mut fee_priority = fee_per_byte.to_binary()?;
         fee_priority.reverse(); // Requires Big-endian for BtreeMap sorting
 
@@ -63,22 +69,26 @@ pub struct PrioritizedTransaction {
     pub priority: FeePriority,
     pub weight: u64,
     pub depended_output_hashes: Vec<HashOutput>,
+    /// The time the transaction was added to the pool, used to expire transactions that are not mined
+    pub inserted_at: Instant,
 }
 
 impl PrioritizedTransaction {
     pub fn convert_from_transaction(
         transaction: Transaction,
         dependent_outputs: Option<Vec<HashOutput>>,
+        weighting: &TransactionWeight,
     ) -> Result<PrioritizedTransaction, PriorityError> {
         let depended_output_hashes = match dependent_outputs {
             Some(v) => v,
             None => Vec::new(),
         };
         Ok(Self {
-            priority: FeePriority::try_from(&transaction)?,
-            weight: transaction.calculate_weight(),
+            priority: FeePriority::try_from(&transaction, weighting)?,
+            weight: transaction.calculate_weight(weighting),
             transaction: Arc::new(transaction),
             depended_output_hashes,
+            inserted_at: Instant::now(),
         })
     }
 }
@@ -47,6 +47,9 @@ impl From<TxStorageResponse> for proto::TxStorageResponse {
             NotStoredOrphan => proto::TxStorageResponse::NotStored,
             NotStoredTimeLocked => proto::TxStorageResponse::NotStored,
             NotStoredAlreadySpent => proto::TxStorageResponse::NotStored,
+            NotStoredPolicyViolation(_) => proto::TxStorageResponse::NotStored,
+            NotStoredExpired => proto::TxStorageResponse::NotStored,
+            NotStoredConflict => proto::TxStorageResponse::NotStored,
         }
     }
 }
@@ -0,0 +1,136 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    mempool::{
+        consts::{MEMPOOL_RELAY_MAX_OUTPUTS, MEMPOOL_RELAY_MAX_SCRIPT_SIZE, MEMPOOL_RELAY_MIN_FEE_PER_GRAM},
+        RelayPolicyViolation,
+    },
+    transactions::{tari_amount::MicroTari, transaction::Transaction, weight::TransactionWeight},
+};
+use serde::{Deserialize, Serialize};
+
+/// Configuration for the RelayPolicy. These are local standardness rules that decide which transactions this node will
+/// store and relay. They are not consensus rules: a block containing transactions that break them is still valid.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RelayPolicyConfig {
+    /// The maximum size in bytes of the script of any output in the transaction
+    pub max_script_size: usize,
+    /// The minimum average fee per gram that the transaction must pay
+    pub min_fee_per_gram: MicroTari,
+    /// The maximum number of outputs the transaction may create
+    pub max_outputs: usize,
+    /// The minimum fee that must be paid for each output the transaction creates. Output values are hidden, so dust
+    /// outputs cannot be detected directly; this makes creating them uneconomical instead. Zero disables the check.
+    pub min_fee_per_output: MicroTari,
+}
+
+impl Default for RelayPolicyConfig {
+    fn default() -> Self {
+        Self {
+            max_script_size: MEMPOOL_RELAY_MAX_SCRIPT_SIZE,
+            min_fee_per_gram: MEMPOOL_RELAY_MIN_FEE_PER_GRAM,
+            max_outputs: MEMPOOL_RELAY_MAX_OUTPUTS,
+            min_fee_per_output: MicroTari(0),
+        }
+    }
+}
+
+/// Checks transactions against the node's RelayPolicyConfig before they are validated and stored in the mempool
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RelayPolicy {
+    config: RelayPolicyConfig,
+}
+
+impl RelayPolicy {
+    pub fn new(config: RelayPolicyConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns the first rule that the transaction breaks, if any. The fee per gram is calculated using the given
+    /// weighting, which should be the one that applies to the next block.
+    pub fn check(&self, tx: &Transaction, weighting: &TransactionWeight) -> Result<(), RelayPolicyViolation> {
+        let outputs = tx.body.outputs();
+        if outputs.len() > self.config.max_outputs {
+            return Err(RelayPolicyViolation::TooManyOutputs);
+        }
+        if outputs
+            .iter()
+            .any(|o| o.script.as_bytes().len() > self.config.max_script_size)
+        {
+            return Err(RelayPolicyViolation::ScriptTooLarge);
+        }
+        if tx.calculate_ave_fee_per_gram(weighting) < self.config.min_fee_per_gram.0 as f64 {
+            return Err(RelayPolicyViolation::FeePerGramTooLow);
+        }
+        if tx.body.get_total_fee() < outputs.len() as u64 * self.config.min_fee_per_output {
+            return Err(RelayPolicyViolation::DustOutput);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transactions::helpers::create_tx;
+
+    const WEIGHTING: TransactionWeight = TransactionWeight::latest();
+
+    #[test]
+    fn it_enforces_the_configured_rules() {
+        let (tx, _, _) = create_tx(5000.into(), 20.into(), 1, 2, 1, 4);
+        let policy = RelayPolicy::default();
+        policy.check(&tx, &WEIGHTING).unwrap();
+
+        let policy = RelayPolicy::new(RelayPolicyConfig {
+            max_outputs: 3,
+            ..Default::default()
+        });
+        assert_eq!(policy.check(&tx, &WEIGHTING), Err(RelayPolicyViolation::TooManyOutputs));
+
+        let policy = RelayPolicy::new(RelayPolicyConfig {
+            min_fee_per_gram: 1000.into(),
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.check(&tx, &WEIGHTING),
+            Err(RelayPolicyViolation::FeePerGramTooLow)
+        );
+
+        let policy = RelayPolicy::new(RelayPolicyConfig {
+            min_fee_per_output: tx.body.get_total_fee(),
+            ..Default::default()
+        });
+        assert_eq!(policy.check(&tx, &WEIGHTING), Err(RelayPolicyViolation::DustOutput));
+    }
+
+    #[test]
+    fn it_rejects_large_scripts() {
+        let (tx, _, _) = create_tx(5000.into(), 20.into(), 1, 2, 1, 1);
+        let policy = RelayPolicy::new(RelayPolicyConfig {
+            max_script_size: 0,
+            ..Default::default()
+        });
+        assert_eq!(policy.check(&tx, &WEIGHTING), Err(RelayPolicyViolation::ScriptTooLarge));
+    }
+}
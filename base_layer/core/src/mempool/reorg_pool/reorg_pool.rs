@@ -26,7 +26,7 @@ use crate::{
         consts::{MEMPOOL_REORG_POOL_CACHE_TTL, MEMPOOL_REORG_POOL_STORAGE_CAPACITY},
         reorg_pool::{ReorgPoolError, ReorgPoolStorage},
     },
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{transaction::Transaction, types::Signature, weight::TransactionWeight},
 };
 use serde::{Deserialize, Serialize};
 use std::{
@@ -133,12 +133,12 @@ impl ReorgPool {
     }
 
     /// Returns the total weight of all transactions stored in the pool.
-    pub fn calculate_weight(&self) -> Result<u64, ReorgPoolError> {
+    pub fn calculate_weight(&self, weighting: &TransactionWeight) -> Result<u64, ReorgPoolError> {
         Ok(self
             .pool_storage
             .write()
             .map_err(|e| ReorgPoolError::BackendError(e.to_string()))?
-            .calculate_weight())
+            .calculate_weight(weighting))
     }
 }
 
@@ -23,7 +23,7 @@
 use crate::{
     blocks::Block,
     mempool::reorg_pool::reorg_pool::ReorgPoolConfig,
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{transaction::Transaction, types::Signature, weight::TransactionWeight},
 };
 use log::*;
 use std::sync::Arc;
@@ -136,9 +136,9 @@ impl ReorgPoolStorage {
     }
 
     /// Returns the total weight of all transactions stored in the pool.
-    pub fn calculate_weight(&mut self) -> u64 {
+    pub fn calculate_weight(&mut self, weighting: &TransactionWeight) -> u64 {
         self.txs_by_signature
             .iter()
-            .fold(0, |weight, (_, tx)| weight + tx.calculate_weight())
+            .fold(0, |weight, (_, tx)| weight + tx.calculate_weight(weighting))
     }
 }
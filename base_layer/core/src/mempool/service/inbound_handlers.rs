@@ -30,7 +30,7 @@ use crate::{
         MempoolStateEvent,
         TxStorageResponse,
     },
-    transactions::transaction::Transaction,
+    transactions::{transaction::Transaction, types::Signature},
 };
 use log::*;
 use std::sync::Arc;
@@ -88,6 +88,12 @@ impl MempoolInboundHandlers {
         }
     }
 
+    /// Returns true if the mempool already holds the transaction with the given kernel excess signature.
+    pub async fn is_transaction_stored(&self, excess_sig: Signature) -> Result<bool, MempoolServiceError> {
+        let tx_storage = async_mempool::has_tx_with_excess_sig(self.mempool.clone(), excess_sig).await?;
+        Ok(tx_storage.is_stored())
+    }
+
     /// Handle inbound transactions from remote wallets and local services.
     pub async fn handle_transaction(
         &mut self,
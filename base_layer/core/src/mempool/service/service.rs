@@ -23,6 +23,7 @@
 use crate::{
     base_node::{
         comms_interface::{BlockEvent, BlockEventReceiver},
+        inbound_pipeline::{InboundPipeline, SubmitOutcome},
         StateMachineHandle,
     },
     mempool::{
@@ -49,7 +50,7 @@ use log::*;
 use rand::rngs::OsRng;
 use std::{convert::TryInto, sync::Arc, time::Duration};
 use tari_common_types::waiting_requests::{generate_request_key, RequestKey, WaitingRequests};
-use tari_comms::peer_manager::NodeId;
+use tari_comms::{connectivity::ConnectivityRequester, peer_manager::NodeId};
 use tari_comms_dht::{
     domain_message::OutboundDomainMessage,
     envelope::NodeDestination,
@@ -84,6 +85,7 @@ pub struct MempoolService {
     timeout_receiver_stream: Option<mpsc::Receiver<RequestKey>>,
     config: MempoolServiceConfig,
     state_machine: StateMachineHandle,
+    connectivity: ConnectivityRequester,
 }
 
 impl MempoolService {
@@ -92,6 +94,7 @@ impl MempoolService {
         inbound_handlers: MempoolInboundHandlers,
         config: MempoolServiceConfig,
         state_machine: StateMachineHandle,
+        connectivity: ConnectivityRequester,
     ) -> Self {
         let (timeout_sender, timeout_receiver) = mpsc::channel(100);
         Self {
@@ -102,6 +105,7 @@ impl MempoolService {
             timeout_receiver_stream: Some(timeout_receiver),
             config,
             state_machine,
+            connectivity,
         }
     }
 
@@ -134,6 +138,7 @@ impl MempoolService {
             .expect("Mempool Service initialized without timeout_receiver_stream")
             .fuse();
         let mut request_receiver = streams.request_receiver;
+        let mut transaction_pipeline = self.spawn_inbound_transaction_pipeline();
 
         loop {
             futures::select! {
@@ -165,7 +170,7 @@ impl MempoolService {
 
                 // Incoming transaction messages from the Comms layer
                 transaction_msg = inbound_transaction_stream.select_next_some() => {
-                    self.spawn_handle_incoming_tx(transaction_msg).await;
+                    self.submit_incoming_tx(&mut transaction_pipeline, transaction_msg).await;
                 }
 
                 // Incoming local request messages from the LocalMempoolServiceInterface and other local services
@@ -261,29 +266,61 @@ impl MempoolService {
         });
     }
 
-    async fn spawn_handle_incoming_tx(&self, tx_msg: DomainMessage<Transaction>) {
-        // Determine if we are bootstrapped
-        let status_watch = self.state_machine.get_status_info_watch();
+    fn spawn_inbound_transaction_pipeline(&self) -> InboundPipeline<DomainMessage<Transaction>> {
+        let state_machine = self.state_machine.clone();
+        let inbound_handlers = self.inbound_handlers.clone();
+        let preliminary_check = move |tx_msg| {
+            let bootstrapped = state_machine.get_status_info_watch().borrow().bootstrapped;
+            preliminary_tx_checks(inbound_handlers.clone(), bootstrapped, tx_msg)
+        };
 
-        if !(*status_watch.borrow()).bootstrapped {
-            debug!(
+        let inbound_handlers = self.inbound_handlers.clone();
+        let validate_and_store = move |tx_msg| {
+            let inbound_handlers = inbound_handlers.clone();
+            async move {
+                let result = handle_incoming_tx(inbound_handlers, tx_msg).await;
+                if let Err(e) = result {
+                    error!(
+                        target: LOG_TARGET,
+                        "Failed to handle incoming transaction message: {:?}", e
+                    );
+                }
+            }
+        };
+
+        InboundPipeline::spawn(
+            "Transaction",
+            self.config.inbound_transaction_pipeline,
+            preliminary_check,
+            validate_and_store,
+        )
+    }
+
+    async fn submit_incoming_tx(
+        &mut self,
+        pipeline: &mut InboundPipeline<DomainMessage<Transaction>>,
+        tx_msg: DomainMessage<Transaction>,
+    ) {
+        let source_peer = tx_msg.source_peer.node_id.clone();
+        if let SubmitOutcome::DroppedPeerQuota { penalize: true } = pipeline.submit(source_peer.clone(), tx_msg) {
+            warn!(
                 target: LOG_TARGET,
-                "Transaction with Message {} from peer `{}` not processed while busy with initial sync.",
-                tx_msg.dht_header.message_tag,
-                tx_msg.source_peer.node_id.short_str(),
+                "Banning peer `{}` for flooding the node with transactions",
+                source_peer.short_str()
             );
-            return;
-        }
-        let inbound_handlers = self.inbound_handlers.clone();
-        task::spawn(async move {
-            let result = handle_incoming_tx(inbound_handlers, tx_msg).await;
-            if let Err(e) = result {
-                error!(
-                    target: LOG_TARGET,
-                    "Failed to handle incoming transaction message: {:?}", e
-                );
+            let ban_duration = self.config.inbound_transaction_pipeline.penalty_ban_duration;
+            if let Err(e) = self
+                .connectivity
+                .ban_peer_until(
+                    source_peer,
+                    ban_duration,
+                    "Flooded the node with transactions".to_string(),
+                )
+                .await
+            {
+                error!(target: LOG_TARGET, "Failed to ban peer: {:?}", e);
             }
-        });
+        }
     }
 
     fn spawn_handle_local_request(
@@ -435,6 +472,56 @@ async fn handle_outbound_request(
     }
 }
 
+/// Discards propagated transactions that do not need full validation. Returns the transaction if it should be
+/// validated.
+async fn preliminary_tx_checks(
+    inbound_handlers: MempoolInboundHandlers,
+    bootstrapped: bool,
+    tx_msg: DomainMessage<Transaction>,
+) -> Option<DomainMessage<Transaction>> {
+    if !bootstrapped {
+        debug!(
+            target: LOG_TARGET,
+            "Transaction with Message {} from peer `{}` not processed while busy with initial sync.",
+            tx_msg.dht_header.message_tag,
+            tx_msg.source_peer.node_id.short_str(),
+        );
+        return None;
+    }
+
+    let excess_sig = match tx_msg.inner.body.kernels().first() {
+        Some(kernel) => kernel.excess_sig.clone(),
+        None => {
+            warn!(
+                target: LOG_TARGET,
+                "Transaction with Message {} from peer `{}` has no kernels",
+                tx_msg.dht_header.message_tag,
+                tx_msg.source_peer.node_id.short_str(),
+            );
+            return None;
+        },
+    };
+
+    match inbound_handlers.is_transaction_stored(excess_sig).await {
+        Ok(true) => {
+            debug!(
+                target: LOG_TARGET,
+                "Mempool already has transaction with Message {}", tx_msg.dht_header.message_tag,
+            );
+            None
+        },
+        Ok(false) => Some(tx_msg),
+        Err(e) => {
+            // The full validation stage performs the same lookup, so let it decide what to do with the transaction
+            warn!(
+                target: LOG_TARGET,
+                "Could not check if transaction with Message {} is stored: {:?}", tx_msg.dht_header.message_tag, e
+            );
+            Some(tx_msg)
+        },
+    }
+}
+
 async fn handle_incoming_tx(
     mut inbound_handlers: MempoolInboundHandlers,
     domain_transaction_msg: DomainMessage<Transaction>,
@@ -21,6 +21,7 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    consensus::ConsensusManager,
     mempool::{
         async_mempool,
         proto,
@@ -32,6 +33,7 @@ use crate::{
 };
 use futures::{channel::mpsc, Sink, SinkExt, Stream, StreamExt};
 use std::{fmt, io, iter::repeat_with, sync::Arc};
+use tari_common::configuration::Network;
 use tari_comms::{
     connectivity::{ConnectivityEvent, ConnectivityEventTx},
     framing,
@@ -56,7 +58,11 @@ pub fn create_transactions(n: usize) -> Vec<Transaction> {
 }
 
 fn new_mempool_with_transactions(n: usize) -> (Mempool, Vec<Transaction>) {
-    let mempool = Mempool::new(Default::default(), Arc::new(MockValidator::new(true)));
+    let mempool = Mempool::new(
+        Default::default(),
+        ConsensusManager::builder(Network::LocalNet).build(),
+        Arc::new(MockValidator::new(true)),
+    );
 
     let transactions = create_transactions(n);
     for txn in &transactions {
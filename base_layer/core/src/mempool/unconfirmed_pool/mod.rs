@@ -26,4 +26,4 @@ mod unconfirmed_pool;
 
 // Public re-exports
 pub use error::UnconfirmedPoolError;
-pub use unconfirmed_pool::{UnconfirmedPool, UnconfirmedPoolConfig};
+pub use unconfirmed_pool::{PublishedBlockResults, UnconfirmedPool, UnconfirmedPoolConfig};
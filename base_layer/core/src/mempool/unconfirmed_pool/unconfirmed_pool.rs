@@ -21,23 +21,33 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    blocks::Block,
+    blocks::{kernel_short_id, Block, KernelShortId},
     mempool::{
-        consts::{MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY, MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT},
+        consts::{
+            MEMPOOL_UNCONFIRMED_POOL_MAX_TXS_PER_UNIQUE_ID,
+            MEMPOOL_UNCONFIRMED_POOL_MAX_WEIGHT_PER_SENDER,
+            MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY,
+            MEMPOOL_UNCONFIRMED_POOL_TX_TTL,
+            MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT,
+        },
         priority::{FeePriority, PrioritizedTransaction},
         unconfirmed_pool::UnconfirmedPoolError,
+        RelayPolicyViolation,
     },
     transactions::{
-        transaction::Transaction,
-        types::{HashOutput, Signature},
+        transaction::{Transaction, UniqueAssetId},
+        types::{HashOutput, PublicKey, Signature},
+        weight::TransactionWeight,
     },
 };
 use log::*;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
+use tari_common::configuration::seconds;
 use tari_crypto::tari_utilities::{hex::Hex, Hashable};
 
 pub const LOG_TARGET: &str = "c::mp::unconfirmed_pool::unconfirmed_pool_storage";
@@ -50,6 +60,15 @@ pub struct UnconfirmedPoolConfig {
     /// The maximum number of transactions that can be skipped when compiling a set of highest priority transactions,
     /// skipping over large transactions are performed in an attempt to fit more transactions into the remaining space.
     pub weight_tx_skip_count: usize,
+    /// The Time-to-live for each stored transaction. Transactions that have not been mined within this time are
+    /// removed from the pool.
+    #[serde(with = "seconds")]
+    pub tx_ttl: Duration,
+    /// The maximum number of stored transactions that may create outputs carrying the same unique id. Only one of them
+    /// can be mined, so this bounds the work that competing transfers of a single token can cause.
+    pub max_txs_per_unique_id: usize,
+    /// The maximum total weight of the stored transactions that create outputs with the same sender offset public key
+    pub max_weight_per_sender: u64,
 }
 
 impl Default for UnconfirmedPoolConfig {
@@ -57,6 +76,9 @@ impl Default for UnconfirmedPoolConfig {
         Self {
             storage_capacity: MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY,
             weight_tx_skip_count: MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT,
+            tx_ttl: MEMPOOL_UNCONFIRMED_POOL_TX_TTL,
+            max_txs_per_unique_id: MEMPOOL_UNCONFIRMED_POOL_MAX_TXS_PER_UNIQUE_ID,
+            max_weight_per_sender: MEMPOOL_UNCONFIRMED_POOL_MAX_WEIGHT_PER_SENDER,
         }
     }
 }
@@ -68,12 +90,17 @@ impl Default for UnconfirmedPoolConfig {
 /// transactions in the pool according to TXPriority, it allows transactions to be inserted in sorted order by their
 /// priority. The txs_by_priority BTreeMap makes it easier to select the set of highest priority transactions that can
 /// be included in a block. The excess_sig of a transaction is used a key to uniquely identify a specific transaction in
-/// these containers.
+/// these containers. The txs_by_kernel_short_id HashMap maps the short id of every kernel to the transaction containing
+/// it, so that compact blocks can be reconstructed from the pool. The txs_by_unique_id and weight_by_sender HashMaps
+/// track the transactions creating each unique id and the weight stored for each sender, to enforce admission limits.
 pub struct UnconfirmedPool {
     config: UnconfirmedPoolConfig,
     txs_by_signature: HashMap<Signature, PrioritizedTransaction>,
     txs_by_priority: BTreeMap<FeePriority, Signature>,
     txs_by_output: HashMap<HashOutput, Vec<Signature>>,
+    txs_by_kernel_short_id: HashMap<KernelShortId, Signature>,
+    txs_by_unique_id: HashMap<UniqueAssetId, Vec<Signature>>,
+    weight_by_sender: HashMap<PublicKey, u64>,
 }
 
 // helper class to reduce type complexity
@@ -82,6 +109,14 @@ pub struct RetrieveResults {
     pub transactions_to_insert: Vec<Arc<Transaction>>,
 }
 
+/// The transactions removed from the pool when a block is published
+pub struct PublishedBlockResults {
+    /// Transactions that were included in the block
+    pub published_transactions: Vec<Arc<Transaction>>,
+    /// Transactions that conflict with the block, i.e. spend the same inputs or create the same outputs
+    pub deprecated_transactions: Vec<Arc<Transaction>>,
+}
+
 impl UnconfirmedPool {
     /// Create a new UnconfirmedPool with the specified configuration
     pub fn new(config: UnconfirmedPoolConfig) -> Self {
@@ -90,7 +125,37 @@ impl UnconfirmedPool {
             txs_by_signature: HashMap::new(),
             txs_by_priority: BTreeMap::new(),
             txs_by_output: HashMap::new(),
+            txs_by_kernel_short_id: HashMap::new(),
+            txs_by_unique_id: HashMap::new(),
+            weight_by_sender: HashMap::new(),
+        }
+    }
+
+    /// Checks that storing the transaction would not exceed the limits on the number of transactions creating the same
+    /// unique id or the weight stored for any of its senders. Transactions that are already stored are accepted.
+    pub fn check_admission_limits(
+        &self,
+        tx: &Transaction,
+        weighting: &TransactionWeight,
+    ) -> Result<(), RelayPolicyViolation> {
+        if tx
+            .first_kernel_excess_sig()
+            .map_or(false, |sig| self.txs_by_signature.contains_key(sig))
+        {
+            return Ok(());
+        }
+        if unique_asset_ids(tx).iter().any(|id| {
+            self.txs_by_unique_id.get(id).map_or(0, |signatures| signatures.len()) >= self.config.max_txs_per_unique_id
+        }) {
+            return Err(RelayPolicyViolation::TooManyUnconfirmedForUniqueId);
         }
+        let weight = tx.calculate_weight(weighting);
+        if sender_offset_public_keys(tx).iter().any(|sender| {
+            self.weight_by_sender.get(sender).copied().unwrap_or(0) + weight > self.config.max_weight_per_sender
+        }) {
+            return Err(RelayPolicyViolation::SenderWeightLimitExceeded);
+        }
+        Ok(())
     }
 
     fn lowest_priority(&self) -> &FeePriority {
@@ -98,9 +163,8 @@ impl UnconfirmedPool {
     }
 
     fn remove_lowest_priority_tx(&mut self) {
-        if let Some((priority, sig)) = self.txs_by_priority.iter().next().map(|(p, s)| (p.clone(), s.clone())) {
-            self.txs_by_signature.remove(&sig);
-            self.txs_by_priority.remove(&priority);
+        if let Some(sig) = self.txs_by_priority.values().next().cloned() {
+            self.delete_transaction(&sig);
         }
     }
 
@@ -112,12 +176,17 @@ impl UnconfirmedPool {
         &mut self,
         tx: Arc<Transaction>,
         dependent_outputs: Option<Vec<HashOutput>>,
+        weighting: &TransactionWeight,
     ) -> Result<(), UnconfirmedPoolError> {
         let tx_key = tx
             .first_kernel_excess_sig()
             .ok_or(UnconfirmedPoolError::TransactionNoKernels)?;
         if !self.txs_by_signature.contains_key(tx_key) {
-            let prioritized_tx = PrioritizedTransaction::convert_from_transaction((*tx).clone(), dependent_outputs)?;
+            let mut prioritized_tx =
+                PrioritizedTransaction::convert_from_transaction((*tx).clone(), dependent_outputs, weighting)?;
+            if !prioritized_tx.depended_output_hashes.is_empty() {
+                prioritized_tx.priority = self.calculate_package_priority(&prioritized_tx)?;
+            }
             if self.txs_by_signature.len() >= self.config.storage_capacity {
                 if prioritized_tx.priority < *self.lowest_priority() {
                     return Ok(());
@@ -126,6 +195,7 @@ impl UnconfirmedPool {
             }
             self.txs_by_priority
                 .insert(prioritized_tx.priority.clone(), tx_key.clone());
+            let weight = prioritized_tx.weight;
             self.txs_by_signature.insert(tx_key.clone(), prioritized_tx);
             for output in tx.body.outputs().clone() {
                 self.txs_by_output
@@ -133,6 +203,16 @@ impl UnconfirmedPool {
                     .or_default()
                     .push(tx_key.clone());
             }
+            for kernel in tx.body.kernels() {
+                self.txs_by_kernel_short_id
+                    .insert(kernel_short_id(&kernel.excess_sig), tx_key.clone());
+            }
+            for id in unique_asset_ids(&tx) {
+                self.txs_by_unique_id.entry(id).or_default().push(tx_key.clone());
+            }
+            for sender in sender_offset_public_keys(&tx) {
+                *self.weight_by_sender.entry(sender).or_default() += weight;
+            }
             debug!(
                 target: LOG_TARGET,
                 "Inserted transaction with signature {} into unconfirmed pool:",
@@ -144,6 +224,42 @@ impl UnconfirmedPool {
         Ok(())
     }
 
+    /// A transaction that spends unconfirmed outputs can only be mined together with the transactions that create them,
+    /// so it is prioritised by the fee per gram of the whole package. This allows a child transaction to pay for a
+    /// parent that was submitted with a low fee (child-pays-for-parent).
+    fn calculate_package_priority(
+        &self,
+        transaction: &PrioritizedTransaction,
+    ) -> Result<FeePriority, UnconfirmedPoolError> {
+        let mut ancestors = HashMap::new();
+        let mut dependent_outputs = transaction.depended_output_hashes.clone();
+        while let Some(output_hash) = dependent_outputs.pop() {
+            if let Some(signatures) = self.txs_by_output.get(&output_hash) {
+                let signature = self.find_highest_priority_transaction(signatures)?;
+                if let Entry::Vacant(entry) = ancestors.entry(signature) {
+                    let ancestor = self
+                        .txs_by_signature
+                        .get(entry.key())
+                        .ok_or(UnconfirmedPoolError::StorageOutofSync)?;
+                    dependent_outputs.extend(ancestor.depended_output_hashes.iter().cloned());
+                    entry.insert(ancestor);
+                }
+            }
+        }
+
+        let (fee, weight) = ancestors.values().fold(
+            (transaction.transaction.body.get_total_fee().0, transaction.weight),
+            |(fee, weight), ancestor| {
+                (
+                    fee + ancestor.transaction.body.get_total_fee().0,
+                    weight + ancestor.weight,
+                )
+            },
+        );
+        let priority = FeePriority::try_from_fee_per_gram(&transaction.transaction, fee as f64 / weight as f64)?;
+        Ok(priority)
+    }
+
     /// TThis will search the unconfirmed pool for the set of outputs and return true if all of them are found
     pub fn verify_outputs_exist(&mut self, outputs: &[HashOutput]) -> bool {
         for hash in outputs {
@@ -156,9 +272,13 @@ impl UnconfirmedPool {
 
     /// Insert a set of new transactions into the UnconfirmedPool
     #[cfg(test)]
-    pub fn insert_txs(&mut self, txs: Vec<Arc<Transaction>>) -> Result<(), UnconfirmedPoolError> {
+    pub fn insert_txs(
+        &mut self,
+        txs: Vec<Arc<Transaction>>,
+        weighting: &TransactionWeight,
+    ) -> Result<(), UnconfirmedPoolError> {
         for tx in txs.into_iter() {
-            self.insert(tx, None)?;
+            self.insert(tx, None, weighting)?;
         }
         Ok(())
     }
@@ -168,6 +288,18 @@ impl UnconfirmedPool {
         self.txs_by_signature.contains_key(excess_sig)
     }
 
+    /// Returns the transactions containing kernels with the given short ids. Short ids that are not found are ignored.
+    pub fn retrieve_by_kernel_short_ids(&self, short_ids: &[KernelShortId]) -> Vec<Arc<Transaction>> {
+        let keys = short_ids
+            .iter()
+            .filter_map(|id| self.txs_by_kernel_short_id.get(id))
+            .collect::<HashSet<_>>();
+        keys.into_iter()
+            .filter_map(|key| self.txs_by_signature.get(key))
+            .map(|ptx| ptx.transaction.clone())
+            .collect()
+    }
+
     /// Returns a set of the highest priority unconfirmed transactions, that can be included in a block
     pub fn highest_priority_txs(&mut self, total_weight: u64) -> Result<RetrieveResults, UnconfirmedPoolError> {
         let mut selected_txs = HashMap::new();
@@ -196,7 +328,9 @@ impl UnconfirmedPool {
             if curr_weight + total_transaction_weight <= total_weight &&
                 potential_transactions_to_remove_and_recheck.is_empty()
             {
-                if !UnconfirmedPool::find_duplicate_input(&selected_txs, &potential_transactions_to_insert) {
+                if !UnconfirmedPool::find_duplicate_input(&selected_txs, &potential_transactions_to_insert) &&
+                    !UnconfirmedPool::find_duplicate_unique_id(&selected_txs, &potential_transactions_to_insert)
+                {
                     curr_weight += total_transaction_weight;
                     for (key, transaction) in potential_transactions_to_insert {
                         selected_txs.insert((key).clone(), transaction.transaction.clone());
@@ -319,6 +453,40 @@ impl UnconfirmedPool {
         false
     }
 
+    // This will check whether inserting the transactions would leave more than one unspent output carrying the same
+    // unique id in the set of transactions
+    fn find_duplicate_unique_id(
+        current_transactions: &HashMap<Signature, Arc<Transaction>>,
+        transactions_to_insert: &HashMap<Signature, PrioritizedTransaction>,
+    ) -> bool {
+        let has_unique_ids = transactions_to_insert.values().any(|tx| {
+            tx.transaction
+                .body
+                .outputs()
+                .iter()
+                .any(|output| output.features.unique_asset_id().is_some())
+        });
+        if !has_unique_ids {
+            return false;
+        }
+        let transactions = current_transactions
+            .values()
+            .chain(transactions_to_insert.values().map(|tx| &tx.transaction));
+        let spent = transactions
+            .clone()
+            .flat_map(|tx| tx.body.inputs().iter().map(|input| input.output_hash()))
+            .collect::<HashSet<_>>();
+        let mut unique_ids = HashSet::new();
+        for output in transactions.flat_map(|tx| tx.body.outputs()) {
+            if let Some(id) = output.features.unique_asset_id() {
+                if !spent.contains(&output.hash()) && !unique_ids.insert(id) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     /// Remove all current mempool transactions from the UnconfirmedPoolStorage, returning that which have been removed
     pub fn drain_all_mempool_transactions(&mut self) -> Vec<Arc<Transaction>> {
         let mempool_txs: Vec<Arc<Transaction>> = self
@@ -328,6 +496,7 @@ impl UnconfirmedPool {
             .collect();
         self.txs_by_priority.clear();
         self.txs_by_output.clear();
+        self.txs_by_kernel_short_id.clear();
 
         mempool_txs
     }
@@ -336,7 +505,7 @@ impl UnconfirmedPool {
     pub fn remove_published_and_discard_deprecated_transactions(
         &mut self,
         published_block: &Block,
-    ) -> Vec<Arc<Transaction>> {
+    ) -> PublishedBlockResults {
         trace!(
             target: LOG_TARGET,
             "Searching for transactions to remove from unconfirmed pool in block {} ({})",
@@ -348,11 +517,14 @@ impl UnconfirmedPool {
         published_block.body.kernels().iter().for_each(|kernel| {
             transactions_to_remove.push(kernel.excess_sig.clone());
         });
-        let mut removed_transactions = self.delete_transactions(&transactions_to_remove);
+        let published_transactions = self.delete_transactions(&transactions_to_remove);
 
         // Remove all other deprecated transactions that cannot be valid anymore
-        removed_transactions.append(&mut self.remove_deprecated_transactions(published_block));
-        removed_transactions
+        let deprecated_transactions = self.remove_deprecated_transactions(published_block);
+        PublishedBlockResults {
+            published_transactions,
+            deprecated_transactions,
+        }
     }
 
     // Remove all deprecated transactions from the UnconfirmedPool by scanning inputs and outputs.
@@ -417,6 +589,28 @@ impl UnconfirmedPool {
                     }
                 }
             }
+            for kernel in prioritized_transaction.transaction.body.kernels() {
+                let short_id = kernel_short_id(&kernel.excess_sig);
+                if self.txs_by_kernel_short_id.get(&short_id) == Some(signature) {
+                    self.txs_by_kernel_short_id.remove(&short_id);
+                }
+            }
+            for id in unique_asset_ids(&prioritized_transaction.transaction) {
+                if let Some(signatures) = self.txs_by_unique_id.get_mut(&id) {
+                    signatures.retain(|x| x != signature);
+                    if signatures.is_empty() {
+                        self.txs_by_unique_id.remove(&id);
+                    }
+                }
+            }
+            for sender in sender_offset_public_keys(&prioritized_transaction.transaction) {
+                if let Entry::Occupied(mut entry) = self.weight_by_sender.entry(sender) {
+                    *entry.get_mut() = entry.get().saturating_sub(prioritized_transaction.weight);
+                    if *entry.get() == 0 {
+                        entry.remove();
+                    }
+                }
+            }
             trace!(
                 target: LOG_TARGET,
                 "Deleted transaction: {}",
@@ -440,6 +634,24 @@ impl UnconfirmedPool {
         self.delete_transactions(&removed_tx_keys)
     }
 
+    /// Remove all transactions that have been in the pool for longer than the configured Time-to-live
+    pub fn remove_expired_transactions(&mut self) -> Vec<Arc<Transaction>> {
+        let expired_tx_keys = self
+            .txs_by_signature
+            .iter()
+            .filter(|(_, ptx)| ptx.inserted_at.elapsed() > self.config.tx_ttl)
+            .map(|(tx_key, _)| tx_key.clone())
+            .collect::<Vec<_>>();
+        if !expired_tx_keys.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "Removing {} expired transaction(s) from unconfirmed pool",
+                expired_tx_keys.len()
+            );
+        }
+        self.delete_transactions(&expired_tx_keys)
+    }
+
     /// Returns the total number of unconfirmed transactions stored in the UnconfirmedPool.
     pub fn len(&self) -> usize {
         self.txs_by_signature.len()
@@ -454,10 +666,10 @@ impl UnconfirmedPool {
     }
 
     /// Returns the total weight of all transactions stored in the pool.
-    pub fn calculate_weight(&self) -> u64 {
-        self.txs_by_signature
-            .iter()
-            .fold(0, |weight, (_, ptx)| weight + ptx.transaction.calculate_weight())
+    pub fn calculate_weight(&self, weighting: &TransactionWeight) -> u64 {
+        self.txs_by_signature.iter().fold(0, |weight, (_, ptx)| {
+            weight + ptx.transaction.calculate_weight(weighting)
+        })
     }
 
     #[cfg(test)]
@@ -472,6 +684,24 @@ impl UnconfirmedPool {
     }
 }
 
+// The distinct unique ids carried by the outputs of the transaction
+fn unique_asset_ids(tx: &Transaction) -> HashSet<UniqueAssetId> {
+    tx.body
+        .outputs()
+        .iter()
+        .filter_map(|output| output.features.unique_asset_id())
+        .collect()
+}
+
+// The distinct sender offset public keys of the outputs of the transaction
+fn sender_offset_public_keys(tx: &Transaction) -> HashSet<PublicKey> {
+    tx.body
+        .outputs()
+        .iter()
+        .map(|output| output.sender_offset_public_key.clone())
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -490,6 +720,8 @@ mod test {
     };
     use tari_common::configuration::Network;
 
+    const WEIGHTING: TransactionWeight = TransactionWeight::latest();
+
     #[test]
     fn test_find_duplicate_input() {
         let tx1 = Arc::new(tx!(MicroTari(5000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
@@ -500,11 +732,11 @@ mod test {
         tx_pool.insert(tx1.first_kernel_excess_sig().unwrap().clone(), tx1.clone());
         tx1_pool.insert(
             tx1.first_kernel_excess_sig().unwrap().clone(),
-            PrioritizedTransaction::convert_from_transaction((*tx1).clone(), None).unwrap(),
+            PrioritizedTransaction::convert_from_transaction((*tx1).clone(), None, &WEIGHTING).unwrap(),
         );
         tx2_pool.insert(
             tx2.first_kernel_excess_sig().unwrap().clone(),
-            PrioritizedTransaction::convert_from_transaction((*tx2).clone(), None).unwrap(),
+            PrioritizedTransaction::convert_from_transaction((*tx2).clone(), None, &WEIGHTING).unwrap(),
         );
         assert!(
             UnconfirmedPool::find_duplicate_input(&tx_pool, &tx1_pool),
@@ -516,6 +748,44 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_find_duplicate_unique_id() {
+        let with_unique_id = |id: u8| {
+            let mut tx = tx!(MicroTari(5000), fee: MicroTari(50), inputs: 1, outputs: 1).0;
+            tx.body.outputs_mut()[0].features.unique_id = Some(vec![id]);
+            PrioritizedTransaction::convert_from_transaction(tx, None, &WEIGHTING).unwrap()
+        };
+        let tx1 = with_unique_id(1);
+        let mut tx_pool = HashMap::new();
+        tx_pool.insert(
+            tx1.transaction.first_kernel_excess_sig().unwrap().clone(),
+            tx1.transaction.clone(),
+        );
+
+        let mut to_insert = HashMap::new();
+        let tx2 = with_unique_id(2);
+        to_insert.insert(tx2.transaction.first_kernel_excess_sig().unwrap().clone(), tx2);
+        assert!(!UnconfirmedPool::find_duplicate_unique_id(&tx_pool, &to_insert));
+
+        let tx3 = with_unique_id(1);
+        to_insert.insert(tx3.transaction.first_kernel_excess_sig().unwrap().clone(), tx3);
+        assert!(UnconfirmedPool::find_duplicate_unique_id(&tx_pool, &to_insert));
+
+        // A transaction that spends the output carrying the unique id may carry it on
+        let mut tx4 = tx!(MicroTari(5000), fee: MicroTari(50), inputs: 1, outputs: 1).0;
+        tx4.body.outputs_mut()[0].features.unique_id = Some(vec![1]);
+        let spent = &tx1.transaction.body.outputs()[0];
+        let input = &mut tx4.body.inputs_mut()[0];
+        input.features = spent.features.clone();
+        input.commitment = spent.commitment.clone();
+        input.script = spent.script.clone();
+        input.covenant = spent.covenant.clone();
+        let tx4 = PrioritizedTransaction::convert_from_transaction(tx4, None, &WEIGHTING).unwrap();
+        let mut to_insert = HashMap::new();
+        to_insert.insert(tx4.transaction.first_kernel_excess_sig().unwrap().clone(), tx4);
+        assert!(!UnconfirmedPool::find_duplicate_unique_id(&tx_pool, &to_insert));
+    }
+
     #[test]
     fn test_insert_and_retrieve_highest_priority_txs() {
         let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
@@ -527,9 +797,14 @@ mod test {
         let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
             storage_capacity: 4,
             weight_tx_skip_count: 3,
+            tx_ttl: MEMPOOL_UNCONFIRMED_POOL_TX_TTL,
+            ..Default::default()
         });
         unconfirmed_pool
-            .insert_txs(vec![tx1.clone(), tx2.clone(), tx3.clone(), tx4.clone(), tx5.clone()])
+            .insert_txs(
+                vec![tx1.clone(), tx2.clone(), tx3.clone(), tx4.clone(), tx5.clone()],
+                &WEIGHTING,
+            )
             .unwrap();
         // Check that lowest priority tx was removed to make room for new incoming transactions
         assert!(unconfirmed_pool.has_tx_with_excess_sig(&tx1.body.kernels()[0].excess_sig),);
@@ -538,7 +813,8 @@ mod test {
         assert!(unconfirmed_pool.has_tx_with_excess_sig(&tx4.body.kernels()[0].excess_sig),);
         assert!(unconfirmed_pool.has_tx_with_excess_sig(&tx5.body.kernels()[0].excess_sig),);
         // Retrieve the set of highest priority unspent transactions
-        let desired_weight = tx1.calculate_weight() + tx3.calculate_weight() + tx5.calculate_weight();
+        let desired_weight =
+            tx1.calculate_weight(&WEIGHTING) + tx3.calculate_weight(&WEIGHTING) + tx5.calculate_weight(&WEIGHTING);
         let results = unconfirmed_pool.highest_priority_txs(desired_weight).unwrap();
         assert_eq!(results.retrieved_transactions.len(), 3);
         assert!(results.retrieved_transactions.contains(&tx1));
@@ -594,14 +870,19 @@ mod test {
         let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
             storage_capacity: 4,
             weight_tx_skip_count: 3,
+            tx_ttl: MEMPOOL_UNCONFIRMED_POOL_TX_TTL,
+            ..Default::default()
         });
 
         unconfirmed_pool
-            .insert_txs(vec![tx1.clone(), tx2.clone(), tx3.clone()])
+            .insert_txs(vec![tx1.clone(), tx2.clone(), tx3.clone()], &WEIGHTING)
             .unwrap();
         assert_eq!(unconfirmed_pool.len(), 3);
 
-        let desired_weight = tx1.calculate_weight() + tx2.calculate_weight() + tx3.calculate_weight() + 1000;
+        let desired_weight = tx1.calculate_weight(&WEIGHTING) +
+            tx2.calculate_weight(&WEIGHTING) +
+            tx3.calculate_weight(&WEIGHTING) +
+            1000;
         let results = unconfirmed_pool.highest_priority_txs(desired_weight).unwrap();
         assert!(results.retrieved_transactions.contains(&tx1));
         // Whether tx2 or tx3 is selected is non-deterministic
@@ -623,9 +904,14 @@ mod test {
         let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
             storage_capacity: 10,
             weight_tx_skip_count: 3,
+            tx_ttl: MEMPOOL_UNCONFIRMED_POOL_TX_TTL,
+            ..Default::default()
         });
         unconfirmed_pool
-            .insert_txs(vec![tx1.clone(), tx2.clone(), tx3.clone(), tx4.clone(), tx5.clone()])
+            .insert_txs(
+                vec![tx1.clone(), tx2.clone(), tx3.clone(), tx4.clone(), tx5.clone()],
+                &WEIGHTING,
+            )
             .unwrap();
         // utx6 should not be added to unconfirmed_pool as it is an unknown transactions that was included in the block
         // by another node
@@ -670,16 +956,21 @@ mod test {
         let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
             storage_capacity: 10,
             weight_tx_skip_count: 3,
+            tx_ttl: MEMPOOL_UNCONFIRMED_POOL_TX_TTL,
+            ..Default::default()
         });
         unconfirmed_pool
-            .insert_txs(vec![
-                tx1.clone(),
-                tx2.clone(),
-                tx3.clone(),
-                tx4.clone(),
-                tx5.clone(),
-                tx6.clone(),
-            ])
+            .insert_txs(
+                vec![
+                    tx1.clone(),
+                    tx2.clone(),
+                    tx3.clone(),
+                    tx4.clone(),
+                    tx5.clone(),
+                    tx6.clone(),
+                ],
+                &WEIGHTING,
+            )
             .unwrap();
 
         // The publishing of tx1 and tx3 will be double-spends and orphan tx5 and tx6
@@ -714,6 +1005,8 @@ mod test {
         let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
             storage_capacity: 10,
             weight_tx_skip_count: 3,
+            tx_ttl: MEMPOOL_UNCONFIRMED_POOL_TX_TTL,
+            ..Default::default()
         });
         let txns = vec![
             Arc::new(tx1.clone()),
@@ -722,7 +1015,7 @@ mod test {
             Arc::new(tx3.clone()),
             Arc::new(tx4.clone()),
         ];
-        unconfirmed_pool.insert_txs(txns.clone()).unwrap();
+        unconfirmed_pool.insert_txs(txns.clone(), &WEIGHTING).unwrap();
 
         for txn in txns {
             for output in txn.as_ref().body.outputs() {
@@ -762,4 +1055,111 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_retrieve_by_kernel_short_ids() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 2, outputs: 1).0);
+        let tx3 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(30), inputs: 2, outputs: 1).0);
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            storage_capacity: 2,
+            weight_tx_skip_count: 3,
+            tx_ttl: MEMPOOL_UNCONFIRMED_POOL_TX_TTL,
+            ..Default::default()
+        });
+        unconfirmed_pool
+            .insert_txs(vec![tx1.clone(), tx2.clone()], &WEIGHTING)
+            .unwrap();
+
+        let short_id = |tx: &Transaction| kernel_short_id(&tx.body.kernels()[0].excess_sig);
+        let txs = unconfirmed_pool.retrieve_by_kernel_short_ids(&[short_id(&tx1), short_id(&tx3), short_id(&tx1)]);
+        assert_eq!(txs.len(), 1);
+        assert_eq!(*txs[0], *tx1);
+
+        // tx2 has the lowest priority and is removed to make space for tx3
+        unconfirmed_pool.insert(tx3.clone(), None, &WEIGHTING).unwrap();
+        assert!(unconfirmed_pool
+            .retrieve_by_kernel_short_ids(&[short_id(&tx2)])
+            .is_empty());
+        assert_eq!(
+            unconfirmed_pool.retrieve_by_kernel_short_ids(&[short_id(&tx3)]).len(),
+            1
+        );
+        assert_eq!(unconfirmed_pool.txs_by_kernel_short_id.len(), 2);
+        assert!(unconfirmed_pool.check_status());
+    }
+
+    #[test]
+    fn test_remove_expired_transactions() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 2, outputs: 1).0);
+
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            storage_capacity: 4,
+            weight_tx_skip_count: 3,
+            tx_ttl: Duration::from_millis(100),
+            ..Default::default()
+        });
+        unconfirmed_pool.insert_txs(vec![tx1.clone()], &WEIGHTING).unwrap();
+        assert!(unconfirmed_pool.remove_expired_transactions().is_empty());
+
+        std::thread::sleep(Duration::from_millis(150));
+        unconfirmed_pool.insert_txs(vec![tx2.clone()], &WEIGHTING).unwrap();
+        let expired = unconfirmed_pool.remove_expired_transactions();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0], tx1);
+        assert!(!unconfirmed_pool.has_tx_with_excess_sig(&tx1.body.kernels()[0].excess_sig));
+        assert!(unconfirmed_pool.has_tx_with_excess_sig(&tx2.body.kernels()[0].excess_sig));
+    }
+
+    #[test]
+    fn test_admission_limits() {
+        let with_unique_id = |fee: u64| {
+            let mut tx = tx!(MicroTari(5_000), fee: MicroTari(fee), inputs: 2, outputs: 1).0;
+            tx.body.outputs_mut()[0].features.unique_id = Some(vec![1, 2, 3]);
+            tx
+        };
+        let tx1 = Arc::new(with_unique_id(50));
+        let tx2 = Arc::new(with_unique_id(40));
+        let tx3 = Arc::new(with_unique_id(30));
+
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            max_txs_per_unique_id: 2,
+            ..Default::default()
+        });
+        unconfirmed_pool.insert_txs(vec![tx1.clone(), tx2], &WEIGHTING).unwrap();
+        assert_eq!(
+            unconfirmed_pool.check_admission_limits(&tx3, &WEIGHTING),
+            Err(RelayPolicyViolation::TooManyUnconfirmedForUniqueId)
+        );
+        // Transactions that are already stored are not limited again
+        assert_eq!(unconfirmed_pool.check_admission_limits(&tx1, &WEIGHTING), Ok(()));
+        unconfirmed_pool.delete_transaction(tx1.first_kernel_excess_sig().unwrap());
+        assert_eq!(unconfirmed_pool.check_admission_limits(&tx3, &WEIGHTING), Ok(()));
+
+        let from_sender = |fee: u64| {
+            let mut tx = tx!(MicroTari(5_000), fee: MicroTari(fee), inputs: 2, outputs: 2).0;
+            for output in tx.body.outputs_mut() {
+                output.sender_offset_public_key = PublicKey::default();
+            }
+            tx
+        };
+        let tx4 = Arc::new(from_sender(50));
+        let tx5 = Arc::new(from_sender(40));
+        let weight = tx4.calculate_weight(&WEIGHTING);
+
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            max_weight_per_sender: weight + tx5.calculate_weight(&WEIGHTING) - 1,
+            ..Default::default()
+        });
+        assert_eq!(unconfirmed_pool.check_admission_limits(&tx4, &WEIGHTING), Ok(()));
+        unconfirmed_pool.insert_txs(vec![tx4.clone()], &WEIGHTING).unwrap();
+        assert_eq!(
+            unconfirmed_pool.check_admission_limits(&tx5, &WEIGHTING),
+            Err(RelayPolicyViolation::SenderWeightLimitExceeded)
+        );
+        unconfirmed_pool.delete_transaction(tx4.first_kernel_excess_sig().unwrap());
+        assert!(unconfirmed_pool.weight_by_sender.is_empty());
+        assert_eq!(unconfirmed_pool.check_admission_limits(&tx5, &WEIGHTING), Ok(()));
+    }
 }
@@ -22,9 +22,10 @@
 
 use super::core as proto;
 use crate::{
-    blocks::{Block, NewBlock, NewBlockHeaderTemplate, NewBlockTemplate},
+    blocks::{Block, CompactBlock, NewBlock, NewBlockHeaderTemplate, NewBlockTemplate},
     chain_storage::{BlockHeaderAccumulatedData, HistoricalBlock},
     proof_of_work::ProofOfWork,
+    tari_utilities::convert::try_convert_all,
     transactions::types::BlindingFactor,
 };
 use std::convert::{TryFrom, TryInto};
@@ -234,7 +235,12 @@ impl TryFrom<proto::NewBlock> for NewBlock {
             ));
         }
 
-        Ok(Self { block_hash })
+        let compact_block = new_block.compact_block.map(TryInto::try_into).transpose()?;
+
+        Ok(Self {
+            block_hash,
+            compact_block,
+        })
     }
 }
 
@@ -242,6 +248,38 @@ impl From<NewBlock> for proto::NewBlock {
     fn from(new_block: NewBlock) -> Self {
         Self {
             block_hash: new_block.block_hash,
+            compact_block: new_block.compact_block.map(Into::into),
+        }
+    }
+}
+
+//---------------------------------- CompactBlock --------------------------------------------//
+
+impl TryFrom<proto::CompactBlock> for CompactBlock {
+    type Error = String;
+
+    fn try_from(compact_block: proto::CompactBlock) -> Result<Self, Self::Error> {
+        let header = compact_block
+            .header
+            .map(TryInto::try_into)
+            .ok_or_else(|| "Compact block header not provided".to_string())??;
+
+        Ok(Self {
+            header,
+            coinbase_outputs: try_convert_all(compact_block.coinbase_outputs)?,
+            coinbase_kernels: try_convert_all(compact_block.coinbase_kernels)?,
+            kernel_short_ids: compact_block.kernel_short_ids,
+        })
+    }
+}
+
+impl From<CompactBlock> for proto::CompactBlock {
+    fn from(compact_block: CompactBlock) -> Self {
+        Self {
+            header: Some(compact_block.header.into()),
+            coinbase_outputs: compact_block.coinbase_outputs.into_iter().map(Into::into).collect(),
+            coinbase_kernels: compact_block.coinbase_kernels.into_iter().map(Into::into).collect(),
+            kernel_short_ids: compact_block.kernel_short_ids,
         }
     }
 }
@@ -28,6 +28,8 @@ use crate::{
     transactions::{
         aggregated_body::AggregateBody,
         bullet_rangeproofs::BulletRangeProof,
+        covenant::Covenant,
+        encrypted_data::EncryptedData,
         tari_amount::MicroTari,
         transaction::{
             KernelFeatures,
@@ -123,6 +125,7 @@ impl TryFrom<proto::types::TransactionInput> for TransactionInput {
             input_data: ExecutionStack::from_bytes(input.input_data.as_slice()).map_err(|err| format!("{:?}", err))?,
             script_signature,
             sender_offset_public_key,
+            covenant: Covenant::from_bytes(&input.covenant).map_err(|err| err.to_string())?,
         })
     }
 }
@@ -136,6 +139,7 @@ impl From<TransactionInput> for proto::types::TransactionInput {
             input_data: input.input_data.as_bytes(),
             script_signature: Some(input.script_signature.into()),
             sender_offset_public_key: input.sender_offset_public_key.as_bytes().to_vec(),
+            covenant: input.covenant.as_bytes(),
         }
     }
 }
@@ -175,6 +179,7 @@ impl TryFrom<proto::types::TransactionOutput> for TransactionOutput {
             script,
             sender_offset_public_key,
             metadata_signature,
+            covenant: Covenant::from_bytes(&output.covenant).map_err(|err| err.to_string())?,
         })
     }
 }
@@ -188,6 +193,7 @@ impl From<TransactionOutput> for proto::types::TransactionOutput {
             script: output.script.as_bytes(),
             sender_offset_public_key: output.sender_offset_public_key.as_bytes().to_vec(),
             metadata_signature: Some(output.metadata_signature.into()),
+            covenant: output.covenant.as_bytes(),
         }
     }
 }
@@ -202,6 +208,13 @@ impl TryFrom<proto::types::OutputFeatures> for OutputFeatures {
             flags: OutputFlags::from_bits(features.flags as u8)
                 .ok_or_else(|| "Invalid or unrecognised output flags".to_string())?,
             maturity: features.maturity,
+            encrypted_data: EncryptedData::from_bytes(features.encrypted_data).map_err(|err| err.to_string())?,
+            parent_public_key: Some(features.parent_public_key)
+                .filter(|key| !key.is_empty())
+                .map(|key| PublicKey::from_bytes(&key))
+                .transpose()
+                .map_err(|err| format!("Invalid parent public key: {}", err))?,
+            unique_id: Some(features.unique_id).filter(|id| !id.is_empty()),
         })
     }
 }
@@ -211,6 +224,12 @@ impl From<OutputFeatures> for proto::types::OutputFeatures {
         Self {
             flags: features.flags.bits() as u32,
             maturity: features.maturity,
+            encrypted_data: features.encrypted_data.as_bytes().to_vec(),
+            parent_public_key: features
+                .parent_public_key
+                .map(|key| key.as_bytes().to_vec())
+                .unwrap_or_default(),
+            unique_id: features.unique_id.unwrap_or_default(),
         }
     }
 }
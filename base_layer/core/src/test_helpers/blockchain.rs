@@ -24,6 +24,7 @@ use crate::{
     blocks::{genesis_block::get_weatherwax_genesis_block, Block, BlockHeader},
     chain_storage::{
         create_lmdb_database,
+        AssetOutput,
         BlockAccumulatedData,
         BlockHeaderAccumulatedData,
         BlockchainBackend,
@@ -31,6 +32,7 @@ use crate::{
         BlockchainDatabaseConfig,
         ChainBlock,
         ChainHeader,
+        ChainReadSnapshot,
         ChainStorageError,
         DbKey,
         DbTransaction,
@@ -44,8 +46,8 @@ use crate::{
     },
     consensus::{chain_strength_comparer::ChainStrengthComparerBuilder, ConsensusConstantsBuilder, ConsensusManager},
     transactions::{
-        transaction::{TransactionInput, TransactionKernel, TransactionOutput},
-        types::{CryptoFactories, HashOutput, Signature},
+        transaction::{OutputFlags, TransactionInput, TransactionKernel, TransactionOutput, UniqueAssetId},
+        types::{Commitment, CryptoFactories, HashOutput, PublicKey, Signature},
     },
     validation::{
         block_validators::{BodyOnlyValidator, OrphanBlockValidator},
@@ -244,6 +246,72 @@ impl BlockchainBackend for TempDatabase {
         self.db.fetch_utxos_by_mmr_position(start, end, deleted)
     }
 
+    fn fetch_utxos_by_script_hash(
+        &self,
+        script_hash: &[u8],
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError> {
+        self.db
+            .fetch_utxos_by_script_hash(script_hash, start_mmr_position, limit, deleted)
+    }
+
+    fn fetch_utxos_by_features(
+        &self,
+        flags: OutputFlags,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<(TransactionOutput, u32)>, ChainStorageError> {
+        self.db
+            .fetch_utxos_by_features(flags, start_mmr_position, limit, deleted)
+    }
+
+    fn fetch_unspent_output_by_commitment(
+        &self,
+        commitment: &Commitment,
+        deleted: &Bitmap,
+    ) -> Result<Option<(TransactionOutput, u32)>, ChainStorageError> {
+        self.db.fetch_unspent_output_by_commitment(commitment, deleted)
+    }
+
+    fn fetch_unspent_output_by_unique_id(
+        &self,
+        id: &UniqueAssetId,
+        deleted: &Bitmap,
+    ) -> Result<Option<(TransactionOutput, u32)>, ChainStorageError> {
+        self.db.fetch_unspent_output_by_unique_id(id, deleted)
+    }
+
+    fn fetch_asset_registrations(
+        &self,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<AssetOutput>, ChainStorageError> {
+        self.db.fetch_asset_registrations(start_mmr_position, limit, deleted)
+    }
+
+    fn fetch_asset_registration(
+        &self,
+        asset_public_key: &PublicKey,
+        deleted: &Bitmap,
+    ) -> Result<Option<AssetOutput>, ChainStorageError> {
+        self.db.fetch_asset_registration(asset_public_key, deleted)
+    }
+
+    fn fetch_asset_tokens(
+        &self,
+        asset_public_key: &PublicKey,
+        start_mmr_position: u32,
+        limit: usize,
+        deleted: &Bitmap,
+    ) -> Result<Vec<AssetOutput>, ChainStorageError> {
+        self.db
+            .fetch_asset_tokens(asset_public_key, start_mmr_position, limit, deleted)
+    }
+
     fn fetch_output(
         &self,
         output_hash: &HashOutput,
@@ -319,7 +387,15 @@ impl BlockchainBackend for TempDatabase {
         self.db.fetch_monero_seed_first_seen_height(&seed)
     }
 
+    fn bad_block_exists(&self, block_hash: HashOutput) -> Result<bool, ChainStorageError> {
+        self.db.bad_block_exists(block_hash)
+    }
+
     fn fetch_horizon_data(&self) -> Result<Option<HorizonData>, ChainStorageError> {
         self.db.fetch_horizon_data()
     }
+
+    fn open_read_snapshot(&self) -> Result<Box<dyn ChainReadSnapshot>, ChainStorageError> {
+        self.db.open_read_snapshot()
+    }
 }
@@ -20,19 +20,24 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 use crate::transactions::{
-    fee::Fee,
+    covenant::{CovenantError, MAX_COVENANT_BYTES},
+    encrypted_data::{EncryptedDataError, MAX_ENCRYPTED_DATA_BYTES},
     tari_amount::*,
     transaction::*,
     types::{BlindingFactor, Commitment, CommitmentFactory, CryptoFactories, PrivateKey, PublicKey, RangeProofService},
+    weight::TransactionWeight,
 };
 use log::*;
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Error, Formatter};
+use std::{
+    collections::HashSet,
+    fmt::{Display, Error, Formatter},
+};
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
     keys::PublicKey as PublicKeyTrait,
     ristretto::pedersen::PedersenCommitment,
-    tari_utilities::hex::Hex,
+    tari_utilities::{hex::Hex, Hashable},
 };
 
 pub const LOG_TARGET: &str = "c::tx::aggregated_body";
@@ -180,14 +185,15 @@ impl AggregateBody {
         false
     }
 
-    /// Sort the component lists of the aggregate body
+    /// Sort the component lists of the aggregate body. Lists that are already in order, such as those of a block
+    /// received from a peer, are left as is to avoid the scratch allocation made by the sort.
     pub fn sort(&mut self) {
         if self.sorted {
             return;
         }
-        self.inputs.sort();
-        self.outputs.sort();
-        self.kernels.sort();
+        sort_if_unsorted(&mut self.inputs);
+        sort_if_unsorted(&mut self.outputs);
+        sort_if_unsorted(&mut self.kernels);
         self.sorted = true;
     }
 
@@ -234,23 +240,38 @@ impl AggregateBody {
         factories: &CryptoFactories,
         height: u64,
     ) -> Result<(), TransactionError> {
-        let mut coinbase_utxo = None;
+        self.check_coinbase_outputs(reward, coinbase_lock_height, 1, factories, height)
+    }
+
+    /// Run through the outputs of the block and check that
+    /// 1. There are between one and `max_coinbase_outputs` coinbase outputs
+    /// 1. The outputs' maturity is correctly set
+    /// 1. The amounts of the coinbase outputs add up to the reward.
+    pub fn check_coinbase_outputs(
+        &self,
+        reward: MicroTari,
+        coinbase_lock_height: u64,
+        max_coinbase_outputs: usize,
+        factories: &CryptoFactories,
+        height: u64,
+    ) -> Result<(), TransactionError> {
+        let mut coinbase_commitments = Vec::new();
         let mut coinbase_kernel = None;
-        let mut coinbase_counter = 0; // there should be exactly 1 coinbase
         for utxo in self.outputs() {
             if utxo.features.flags.contains(OutputFlags::COINBASE_OUTPUT) {
-                coinbase_counter += 1;
                 if utxo.features.maturity < (height + coinbase_lock_height) {
                     warn!(target: LOG_TARGET, "Coinbase {} found with maturity set too low", utxo);
                     return Err(TransactionError::InvalidCoinbaseMaturity);
                 }
-                coinbase_utxo = Some(utxo.clone());
+                coinbase_commitments.push(&utxo.commitment);
             }
         }
-        if coinbase_counter != 1 {
+        if coinbase_commitments.is_empty() || coinbase_commitments.len() > max_coinbase_outputs {
             warn!(
                 target: LOG_TARGET,
-                "{} coinbases found in body. Only a single coinbase is permitted.", coinbase_counter,
+                "{} coinbases found in body. Between 1 and {} coinbases are permitted.",
+                coinbase_commitments.len(),
+                max_coinbase_outputs,
             );
             return Err(TransactionError::MoreThanOneCoinbase);
         }
@@ -269,19 +290,22 @@ impl AggregateBody {
             );
             return Err(TransactionError::MoreThanOneCoinbase);
         }
-        // Unwrap used here are fine as they should have an amount in them by here. If the coinbase's are missing the
-        // counters should be 0 and the fn should have returned an error by now.
-        let utxo = coinbase_utxo.unwrap();
+        // Unwrap used here is fine as it should have an amount in it by here. If the coinbase kernel is missing the
+        // counter should be 0 and the fn should have returned an error by now.
         let rhs =
             &coinbase_kernel.unwrap().excess + &factories.commitment.commit_value(&BlindingFactor::default(), reward.0);
-        if rhs != utxo.commitment {
-            warn!(target: LOG_TARGET, "Coinbase {} amount validation failed", utxo);
+        let coinbase_sum = coinbase_commitments.into_iter().sum::<Commitment>();
+        if rhs != coinbase_sum {
+            warn!(
+                target: LOG_TARGET,
+                "Coinbase amount validation failed for block {}", height
+            );
             return Err(TransactionError::InvalidCoinbase);
         }
         Ok(())
     }
 
-    /// This function will check all stxo to ensure that feature flags where followed
+    /// This function will check all stxo to ensure that feature flags and covenants where followed
     pub fn check_stxo_rules(&self, height: u64) -> Result<(), TransactionError> {
         for input in self.inputs() {
             if input.features.maturity > height {
@@ -292,6 +316,70 @@ impl AggregateBody {
                 return Err(TransactionError::InputMaturity);
             }
         }
+        self.check_covenants(height)
+    }
+
+    /// Checks that the outputs of this body satisfy the covenants of the outputs being spent at the given height, and
+    /// that the covenants of the new outputs are not too large. For a block body, the outputs of every transaction in
+    /// the block are considered.
+    pub fn check_covenants(&self, height: u64) -> Result<(), TransactionError> {
+        for output in self.outputs() {
+            let size = output.covenant.byte_size();
+            if size > MAX_COVENANT_BYTES {
+                return Err(CovenantError::TooLarge(size).into());
+            }
+        }
+        for input in self.inputs() {
+            if let Err(err) = input.covenant.execute(height, input, self.outputs()) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Input found that does not satisfy its covenant: {} ({})", input, err
+                );
+                return Err(err.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the encrypted data of the outputs of this body is not too large
+    pub fn check_encrypted_data_sizes(&self) -> Result<(), TransactionError> {
+        for output in self.outputs() {
+            let size = output.features.encrypted_data.len();
+            if size > MAX_ENCRYPTED_DATA_BYTES {
+                return Err(EncryptedDataError::TooLarge(size).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The unique asset tokens carried by the outputs of this body that are not spent by an input of the same body
+    pub fn unspent_unique_asset_ids(&self) -> Vec<UniqueAssetId> {
+        self.outputs()
+            .iter()
+            .filter_map(|output| {
+                let id = output.features.unique_asset_id()?;
+                let hash = output.hash();
+                if self.inputs().iter().any(|input| input.output_hash() == hash) {
+                    None
+                } else {
+                    Some(id)
+                }
+            })
+            .collect()
+    }
+
+    /// Checks that the unique ids of the outputs of this body are valid, and that no two outputs that are left unspent
+    /// by this body carry the same unique asset token
+    pub fn check_unique_ids(&self) -> Result<(), TransactionError> {
+        for output in self.outputs() {
+            output.features.check_unique_id()?;
+        }
+        let mut seen = HashSet::new();
+        for id in self.unspent_unique_asset_ids() {
+            if !seen.insert(id.clone()) {
+                return Err(TransactionError::DuplicateUniqueId(id.to_string()));
+            }
+        }
         Ok(())
     }
 
@@ -299,6 +387,8 @@ impl AggregateBody {
     /// 1. The sum of inputs, outputs and fees equal the (public excess value + offset)
     /// 1. The signature signs the canonical message with the private excess
     /// 1. Range proofs of the outputs are valid
+    /// 1. The encrypted data of the outputs is within the consensus size limit
+    /// 1. No two unspent outputs carry the same unique id
     ///
     /// This function does NOT check that inputs come from the UTXO set
     /// The reward is the total amount of Tari rewarded for this block (block reward + total fees), this should be 0
@@ -313,6 +403,8 @@ impl AggregateBody {
         let total_offset = factories.commitment.commit_value(&tx_offset, total_reward.0);
         let script_offset_g = PublicKey::from_secret_key(&script_offset);
 
+        self.check_encrypted_data_sizes()?;
+        self.check_unique_ids()?;
         self.verify_kernel_signatures()?;
         self.validate_kernel_sum(total_offset, &factories.commitment)?;
 
@@ -426,8 +518,8 @@ impl AggregateBody {
     }
 
     /// Returns the byte size or weight of a body
-    pub fn calculate_weight(&self) -> u64 {
-        Fee::calculate_weight(self.kernels().len(), self.inputs().len(), self.outputs().len())
+    pub fn calculate_weight(&self, weighting: &TransactionWeight) -> u64 {
+        weighting.calculate_body(self)
     }
 
     pub fn is_sorted(&self) -> bool {
@@ -451,6 +543,12 @@ impl From<Transaction> for AggregateBody {
     }
 }
 
+fn sort_if_unsorted<T: Ord>(items: &mut Vec<T>) {
+    if items.windows(2).any(|pair| pair[0] > pair[1]) {
+        items.sort();
+    }
+}
+
 impl Display for AggregateBody {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         if !self.is_sorted() {
@@ -27,6 +27,7 @@ use crate::{
         ConsensusConstants,
     },
     transactions::{
+        covenant::Covenant,
         tari_amount::{uT, MicroTari},
         transaction::{
             KernelBuilder,
@@ -38,16 +39,18 @@ use crate::{
             UnblindedOutput,
         },
         transaction_protocol::{build_challenge, RewindData, TransactionMetadata},
-        types::{BlindingFactor, CryptoFactories, PrivateKey, PublicKey, Signature},
+        types::{BlindingFactor, CryptoFactories, HashDigest, PrivateKey, PublicKey, Signature},
     },
 };
+use digest::Digest;
 use rand::rngs::OsRng;
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
     inputs,
-    keys::{PublicKey as PK, SecretKey},
+    keys::{DiffieHellmanSharedSecret, PublicKey as PK, SecretKey},
     script,
-    script::TariScript,
+    script::{ExecutionStack, TariScript},
+    tari_utilities::ByteArray,
 };
 use thiserror::Error;
 
@@ -67,6 +70,40 @@ pub enum CoinbaseBuildError {
     BuildError(String),
     #[error("Some inconsistent data was given to the builder. This transaction is not valid")]
     InvalidTransaction,
+    #[error("Coinbase payouts must each be between 1% and 100% and add up to at most 100%, but they add up to {0}%")]
+    InvalidPayoutPercentage(u32),
+    #[error("The coinbase would have {found} outputs, but at most {max} are permitted")]
+    TooManyOutputs { found: usize, max: usize },
+}
+
+/// A share of the coinbase that is paid directly to another party, such as a mining pool or a development fund. The
+/// payout is created as a one-sided output to `destination`, which can be recovered by its wallet in the same way as
+/// any other one-sided payment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoinbasePayout {
+    pub destination: PublicKey,
+    pub percentage: u8,
+}
+
+impl CoinbasePayout {
+    pub fn new(destination: PublicKey, percentage: u8) -> Self {
+        Self {
+            destination,
+            percentage,
+        }
+    }
+
+    /// The value of this payout out of the total coinbase value (block reward plus fees), rounded down
+    pub fn value(&self, total_reward: MicroTari) -> MicroTari {
+        MicroTari::from((u128::from(total_reward.0) * u128::from(self.percentage) / 100) as u64)
+    }
+}
+
+/// The value left for the miner once all payouts have been taken out of the total coinbase value
+pub fn miner_coinbase_value(total_reward: MicroTari, payouts: &[CoinbasePayout]) -> MicroTari {
+    payouts.iter().fold(total_reward, |remaining, payout| {
+        remaining.saturating_sub(payout.value(total_reward))
+    })
 }
 
 pub struct CoinbaseBuilder {
@@ -78,6 +115,7 @@ pub struct CoinbaseBuilder {
     script: Option<TariScript>,
     private_nonce: Option<PrivateKey>,
     rewind_data: Option<RewindData>,
+    payouts: Vec<CoinbasePayout>,
 }
 
 impl CoinbaseBuilder {
@@ -93,6 +131,7 @@ impl CoinbaseBuilder {
             script: None,
             private_nonce: None,
             rewind_data: None,
+            payouts: Vec::new(),
         }
     }
 
@@ -139,6 +178,13 @@ impl CoinbaseBuilder {
         self
     }
 
+    /// Split the coinbase, paying the given shares directly to other parties. The miner's output receives whatever is
+    /// left over.
+    pub fn with_payouts(mut self, payouts: Vec<CoinbasePayout>) -> Self {
+        self.payouts = payouts;
+        self
+    }
+
     /// Try and construct a Coinbase Transaction. The block reward is taken from the emission curve for the current
     /// block height. The other parameters (keys, nonces etc.) are provided by the caller. Other data is
     /// automatically set: Coinbase transactions have an offset of zero, no fees, the `COINBASE_OUTPUT` flags are set
@@ -178,27 +224,51 @@ impl CoinbaseBuilder {
         let script_private_key = self.script_key.unwrap_or_else(|| spending_key.clone());
         let script = self.script.unwrap_or_else(|| script!(Nop));
         let output_features = OutputFeatures::create_coinbase(height + constants.coinbase_lock_height());
-        let excess = self.factories.commitment.commit_value(&spending_key, 0);
+
+        let num_outputs = self.payouts.len() + 1;
+        if num_outputs > constants.max_coinbase_outputs() {
+            return Err(CoinbaseBuildError::TooManyOutputs {
+                found: num_outputs,
+                max: constants.max_coinbase_outputs(),
+            });
+        }
+        let total_percentage = self.payouts.iter().map(|p| u32::from(p.percentage)).sum::<u32>();
+        if total_percentage > 100 || self.payouts.iter().any(|p| p.percentage == 0) {
+            return Err(CoinbaseBuildError::InvalidPayoutPercentage(total_percentage));
+        }
+        let miner_value = miner_coinbase_value(total_reward, &self.payouts);
+        let mut payout_outputs = Vec::with_capacity(self.payouts.len());
+        // The kernel has to commit to the sum of the blinding factors of all the coinbase outputs
+        let mut kernel_key = spending_key.clone();
+        for payout in &self.payouts {
+            let (output, payout_spending_key) =
+                build_payout_output(&self.factories, payout, total_reward, &output_features)?;
+            kernel_key = kernel_key + payout_spending_key;
+            payout_outputs.push(output);
+        }
+
+        let excess = self.factories.commitment.commit_value(&kernel_key, 0);
         let kernel_features = KernelFeatures::create_coinbase();
         let metadata = TransactionMetadata::default();
         let challenge = build_challenge(&public_nonce, &metadata);
-        let sig = Signature::sign(spending_key.clone(), nonce, &challenge)
+        let sig = Signature::sign(kernel_key, nonce, &challenge)
             .map_err(|_| CoinbaseBuildError::BuildError("Challenge could not be represented as a scalar".into()))?;
 
         let sender_offset_private_key = PrivateKey::random(&mut OsRng);
         let sender_offset_public_key = PublicKey::from_secret_key(&sender_offset_private_key);
 
         let metadata_sig = TransactionOutput::create_final_metadata_signature(
-            &total_reward,
+            &miner_value,
             &spending_key,
             &script,
             &output_features,
+            &Covenant::default(),
             &sender_offset_private_key,
         )
         .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
 
         let unblinded_output = UnblindedOutput::new(
-            total_reward,
+            miner_value,
             spending_key,
             Some(output_features),
             script,
@@ -206,6 +276,7 @@ impl CoinbaseBuilder {
             script_private_key,
             sender_offset_public_key,
             metadata_sig,
+            Covenant::default(),
         );
         let output = if let Some(rewind_data) = self.rewind_data.as_ref() {
             unblinded_output
@@ -226,6 +297,9 @@ impl CoinbaseBuilder {
             .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
 
         let mut builder = TransactionBuilder::new();
+        for payout_output in payout_outputs {
+            builder.add_output(payout_output);
+        }
         builder
             .add_output(output)
             .add_offset(BlindingFactor::default())
@@ -239,6 +313,63 @@ impl CoinbaseBuilder {
     }
 }
 
+/// Build a one-sided coinbase output paying `payout` its share of `total_reward`. The spending key is the
+/// Diffie-Hellman shared secret between a random sender offset key and the destination, so only the destination can
+/// spend (and rewind) the output. Returns the output along with its spending key, which the kernel has to account for.
+fn build_payout_output(
+    factories: &CryptoFactories,
+    payout: &CoinbasePayout,
+    total_reward: MicroTari,
+    features: &OutputFeatures,
+) -> Result<(TransactionOutput, PrivateKey), CoinbaseBuildError> {
+    let value = payout.value(total_reward);
+    let sender_offset_private_key = PrivateKey::random(&mut OsRng);
+    let sender_offset_public_key = PublicKey::from_secret_key(&sender_offset_private_key);
+    let spending_key =
+        PrivateKey::from_bytes(PublicKey::shared_secret(&sender_offset_private_key, &payout.destination).as_bytes())
+            .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
+    let rewind_key = PrivateKey::from_bytes(&hash_secret_key(&spending_key))
+        .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
+    let rewind_blinding_key = PrivateKey::from_bytes(&hash_secret_key(&rewind_key))
+        .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
+    let rewind_data = RewindData {
+        rewind_key,
+        rewind_blinding_key,
+        proof_message: [0u8; 21],
+    };
+    let script = script!(PushPubKey(Box::new(payout.destination.clone())));
+
+    let metadata_sig = TransactionOutput::create_final_metadata_signature(
+        &value,
+        &spending_key,
+        &script,
+        features,
+        &Covenant::default(),
+        &sender_offset_private_key,
+    )
+    .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
+
+    // The script key belongs to the destination, it is not needed to build the transaction output
+    let output = UnblindedOutput::new(
+        value,
+        spending_key.clone(),
+        Some(features.clone()),
+        script,
+        ExecutionStack::default(),
+        PrivateKey::default(),
+        sender_offset_public_key,
+        metadata_sig,
+        Covenant::default(),
+    )
+    .as_rewindable_transaction_output(factories, &rewind_data)
+    .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
+    Ok((output, spending_key))
+}
+
+fn hash_secret_key(key: &PrivateKey) -> Vec<u8> {
+    HashDigest::new().chain(key.as_bytes()).finalize().to_vec()
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -249,13 +380,19 @@ mod test {
             tari_amount::uT,
             transaction::{KernelFeatures, OutputFeatures, OutputFlags, TransactionError},
             transaction_protocol::RewindData,
-            types::{BlindingFactor, CryptoFactories, PrivateKey},
+            types::{BlindingFactor, CryptoFactories, PrivateKey, PublicKey},
             CoinbaseBuilder,
+            CoinbasePayout,
         },
     };
     use rand::rngs::OsRng;
     use tari_common::configuration::Network;
-    use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey as SecretKeyTrait};
+    use tari_crypto::{
+        commitment::HomomorphicCommitmentFactory,
+        keys::{DiffieHellmanSharedSecret, PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait},
+        script,
+        tari_utilities::ByteArray,
+    };
 
     fn get_builder() -> (CoinbaseBuilder, ConsensusManager, CryptoFactories) {
         let network = Network::LocalNet;
@@ -526,4 +663,101 @@ mod test {
             Ok(())
         );
     }
+
+    #[test]
+    fn valid_split_coinbase() {
+        let p = TestParams::new();
+        let (builder, rules, factories) = get_builder();
+        let pool_key = PrivateKey::random(&mut OsRng);
+        let fund_key = PrivateKey::random(&mut OsRng);
+        let payouts = vec![
+            CoinbasePayout::new(PublicKey::from_secret_key(&pool_key), 20),
+            CoinbasePayout::new(PublicKey::from_secret_key(&fund_key), 1),
+        ];
+        let builder = builder
+            .with_block_height(42)
+            .with_fees(145 * uT)
+            .with_nonce(p.nonce.clone())
+            .with_spend_key(p.spend_key)
+            .with_payouts(payouts.clone());
+        let (tx, miner_output) = builder
+            .build(rules.consensus_constants(42), rules.emission_schedule())
+            .unwrap();
+        let block_reward = rules.emission_schedule().block_reward(42) + 145 * uT;
+        assert_eq!(tx.body.outputs().len(), 3);
+        assert_eq!(tx.body.kernels().len(), 1);
+        assert_eq!(
+            miner_output.value,
+            block_reward - payouts[0].value(block_reward) - payouts[1].value(block_reward)
+        );
+
+        let constants = rules.consensus_constants(42);
+        assert_eq!(
+            tx.body.check_coinbase_outputs(
+                block_reward,
+                constants.coinbase_lock_height(),
+                constants.max_coinbase_outputs(),
+                &factories,
+                42
+            ),
+            Ok(())
+        );
+        // Consensus rules that only allow a single coinbase output reject the split
+        assert_eq!(
+            tx.body
+                .check_coinbase_output(block_reward, constants.coinbase_lock_height(), &factories, 42),
+            Err(TransactionError::MoreThanOneCoinbase)
+        );
+
+        // The pool can recover its output like any other one-sided payment
+        let pool_output = tx
+            .body
+            .outputs()
+            .iter()
+            .find(|o| o.script == script!(PushPubKey(Box::new(payouts[0].destination.clone()))))
+            .unwrap();
+        let spending_key = PrivateKey::from_bytes(
+            PublicKey::shared_secret(&pool_key, &pool_output.sender_offset_public_key).as_bytes(),
+        )
+        .unwrap();
+        assert!(factories.commitment.open_value(
+            &spending_key,
+            payouts[0].value(block_reward).into(),
+            &pool_output.commitment
+        ));
+    }
+
+    #[test]
+    fn invalid_split_coinbase() {
+        let p = TestParams::new();
+        let (builder, rules, _) = get_builder();
+        let destination = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+        let err = builder
+            .with_block_height(42)
+            .with_fees(0.into())
+            .with_nonce(p.nonce.clone())
+            .with_spend_key(p.spend_key.clone())
+            .with_payouts(vec![
+                CoinbasePayout::new(destination.clone(), 60),
+                CoinbasePayout::new(destination.clone(), 41),
+            ])
+            .build(rules.consensus_constants(42), rules.emission_schedule())
+            .unwrap_err();
+        assert_eq!(err, CoinbaseBuildError::InvalidPayoutPercentage(101));
+
+        let max_outputs = rules.consensus_constants(42).max_coinbase_outputs();
+        let (builder, _, _) = get_builder();
+        let err = builder
+            .with_block_height(42)
+            .with_fees(0.into())
+            .with_nonce(p.nonce)
+            .with_spend_key(p.spend_key)
+            .with_payouts(vec![CoinbasePayout::new(destination, 1); max_outputs])
+            .build(rules.consensus_constants(42), rules.emission_schedule())
+            .unwrap_err();
+        assert_eq!(err, CoinbaseBuildError::TooManyOutputs {
+            found: max_outputs + 1,
+            max: max_outputs
+        });
+    }
 }
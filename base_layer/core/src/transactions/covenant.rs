@@ -0,0 +1,363 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Covenants constrain the transaction that spends an output.
+//!
+//! A script decides _who_ may spend an output, a covenant decides _how_ it may be spent. A [Covenant] is a list of
+//! filters. When an output with a covenant is spent, the filters are applied in turn to the outputs of the spending
+//! transaction and the covenant passes if at least one output is left. An empty covenant always passes, so outputs
+//! without a covenant behave exactly as before.
+//!
+//! For example, `FieldsPreserved([Features, Script])` requires the spending transaction to create an output with the
+//! same features and script as the output being spent, which lets an asset move between owners without losing its
+//! identity.
+
+use crate::transactions::{
+    transaction::{TransactionInput, TransactionOutput},
+    types::HashOutput,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Error, Formatter},
+};
+use tari_crypto::tari_utilities::{hex::to_hex, Hashable};
+use thiserror::Error;
+
+/// The largest encoded covenant that consensus accepts
+pub const MAX_COVENANT_BYTES: usize = 1024;
+
+const FILTER_ABSOLUTE_HEIGHT: u8 = 0x01;
+const FILTER_OUTPUT_HASH_EQ: u8 = 0x02;
+const FILTER_FIELDS_PRESERVED: u8 = 0x03;
+
+#[derive(Debug, Clone, Error, PartialEq, Deserialize, Serialize)]
+pub enum CovenantError {
+    #[error("Covenant is {0} bytes, the maximum is {}", MAX_COVENANT_BYTES)]
+    TooLarge(usize),
+    #[error("Covenant encoding ended unexpectedly")]
+    UnexpectedEndOfBytes,
+    #[error("Unknown covenant filter {0:#04x}")]
+    UnknownFilter(u8),
+    #[error("Unknown output field {0:#04x}")]
+    UnknownOutputField(u8),
+    #[error("Covenant filter `{0}` did not leave any outputs of the spending transaction")]
+    NoMatchingOutputs(CovenantFilter),
+}
+
+/// A field of an output that is also known for the input that spends it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum OutputField {
+    Features = 0x00,
+    Script = 0x01,
+    SenderOffsetPublicKey = 0x02,
+    Covenant = 0x03,
+}
+
+impl OutputField {
+    fn is_preserved(self, input: &TransactionInput, output: &TransactionOutput) -> bool {
+        match self {
+            OutputField::Features => input.features == output.features,
+            OutputField::Script => input.script == output.script,
+            OutputField::SenderOffsetPublicKey => input.sender_offset_public_key == output.sender_offset_public_key,
+            OutputField::Covenant => input.covenant == output.covenant,
+        }
+    }
+}
+
+impl TryFrom<u8> for OutputField {
+    type Error = CovenantError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(OutputField::Features),
+            0x01 => Ok(OutputField::Script),
+            0x02 => Ok(OutputField::SenderOffsetPublicKey),
+            0x03 => Ok(OutputField::Covenant),
+            v => Err(CovenantError::UnknownOutputField(v)),
+        }
+    }
+}
+
+/// A filter over the outputs of the transaction that spends an output with a covenant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CovenantFilter {
+    /// Keeps all outputs if the spending transaction is mined at or above the given height, otherwise none
+    AbsoluteHeight(u64),
+    /// Keeps the output with the given hash
+    OutputHashEq(HashOutput),
+    /// Keeps outputs for which every given field is equal to the field of the output being spent
+    FieldsPreserved(Vec<OutputField>),
+}
+
+impl CovenantFilter {
+    fn apply<'a>(
+        &self,
+        block_height: u64,
+        input: &TransactionInput,
+        outputs: Vec<&'a TransactionOutput>,
+    ) -> Vec<&'a TransactionOutput> {
+        match self {
+            CovenantFilter::AbsoluteHeight(height) => {
+                if block_height >= *height {
+                    outputs
+                } else {
+                    Vec::new()
+                }
+            },
+            CovenantFilter::OutputHashEq(hash) => outputs.into_iter().filter(|o| o.hash() == *hash).collect(),
+            CovenantFilter::FieldsPreserved(fields) => outputs
+                .into_iter()
+                .filter(|o| fields.iter().all(|f| f.is_preserved(input, o)))
+                .collect(),
+        }
+    }
+
+    fn write_to(&self, buf: &mut Vec<u8>) {
+        match self {
+            CovenantFilter::AbsoluteHeight(height) => {
+                buf.push(FILTER_ABSOLUTE_HEIGHT);
+                buf.extend_from_slice(&height.to_le_bytes());
+            },
+            CovenantFilter::OutputHashEq(hash) => {
+                buf.push(FILTER_OUTPUT_HASH_EQ);
+                buf.push(hash.len() as u8);
+                buf.extend_from_slice(hash);
+            },
+            CovenantFilter::FieldsPreserved(fields) => {
+                buf.push(FILTER_FIELDS_PRESERVED);
+                buf.push(fields.len() as u8);
+                buf.extend(fields.iter().map(|f| *f as u8));
+            },
+        }
+    }
+
+    fn read_from(reader: &mut ByteReader<'_>) -> Result<Self, CovenantError> {
+        match reader.read_u8()? {
+            FILTER_ABSOLUTE_HEIGHT => {
+                let mut height = [0u8; 8];
+                height.copy_from_slice(reader.read_slice(8)?);
+                Ok(CovenantFilter::AbsoluteHeight(u64::from_le_bytes(height)))
+            },
+            FILTER_OUTPUT_HASH_EQ => {
+                let len = reader.read_u8()? as usize;
+                Ok(CovenantFilter::OutputHashEq(reader.read_slice(len)?.to_vec()))
+            },
+            FILTER_FIELDS_PRESERVED => {
+                let len = reader.read_u8()? as usize;
+                let fields = reader
+                    .read_slice(len)?
+                    .iter()
+                    .map(|f| OutputField::try_from(*f))
+                    .collect::<Result<_, _>>()?;
+                Ok(CovenantFilter::FieldsPreserved(fields))
+            },
+            v => Err(CovenantError::UnknownFilter(v)),
+        }
+    }
+}
+
+impl Display for CovenantFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            CovenantFilter::AbsoluteHeight(height) => write!(f, "AbsoluteHeight({})", height),
+            CovenantFilter::OutputHashEq(hash) => write!(f, "OutputHashEq({})", to_hex(hash)),
+            CovenantFilter::FieldsPreserved(fields) => write!(f, "FieldsPreserved({:?})", fields),
+        }
+    }
+}
+
+/// Constraints on the transaction that spends an output. See the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Covenant {
+    filters: Vec<CovenantFilter>,
+}
+
+impl Covenant {
+    pub fn new(filters: Vec<CovenantFilter>) -> Self {
+        Self { filters }
+    }
+
+    pub fn filters(&self) -> &[CovenantFilter] {
+        &self.filters
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Checks that `outputs`, the outputs of a transaction mined at `block_height`, satisfy the covenant of the output
+    /// spent by `input`
+    pub fn execute(
+        &self,
+        block_height: u64,
+        input: &TransactionInput,
+        outputs: &[TransactionOutput],
+    ) -> Result<(), CovenantError> {
+        let mut remaining = outputs.iter().collect::<Vec<_>>();
+        for filter in &self.filters {
+            remaining = filter.apply(block_height, input, remaining);
+            if remaining.is_empty() {
+                return Err(CovenantError::NoMatchingOutputs(filter.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The canonical encoding of the covenant. An empty covenant encodes to zero bytes, so adding it to a hash does
+    /// not change the hash.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for filter in &self.filters {
+            filter.write_to(&mut buf);
+        }
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CovenantError> {
+        if bytes.len() > MAX_COVENANT_BYTES {
+            return Err(CovenantError::TooLarge(bytes.len()));
+        }
+        let mut reader = ByteReader { bytes };
+        let mut filters = Vec::new();
+        while !reader.bytes.is_empty() {
+            filters.push(CovenantFilter::read_from(&mut reader)?);
+        }
+        Ok(Self { filters })
+    }
+
+    /// The size of the encoded covenant, which is taken into account by the transaction weight
+    pub fn byte_size(&self) -> usize {
+        self.as_bytes().len()
+    }
+}
+
+impl Display for Covenant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let filters = self.filters.iter().map(ToString::to_string).collect::<Vec<_>>();
+        write!(f, "[{}]", filters.join(", "))
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn read_u8(&mut self) -> Result<u8, CovenantError> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8], CovenantError> {
+        if self.bytes.len() < len {
+            return Err(CovenantError::UnexpectedEndOfBytes);
+        }
+        let (slice, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(slice)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transactions::{
+        transaction::OutputFeatures,
+        types::{ComSignature, CommitmentFactory, PublicKey},
+    };
+    use tari_crypto::{commitment::HomomorphicCommitmentFactory, script, script::ExecutionStack};
+
+    fn input_and_outputs() -> (TransactionInput, Vec<TransactionOutput>) {
+        let input = TransactionInput::new(
+            OutputFeatures::with_maturity(5),
+            CommitmentFactory::default().zero(),
+            script!(Nop),
+            ExecutionStack::default(),
+            ComSignature::default(),
+            PublicKey::default(),
+            Covenant::default(),
+        );
+        let same = TransactionOutput {
+            features: OutputFeatures::with_maturity(5),
+            script: script!(Nop),
+            ..Default::default()
+        };
+        let different = TransactionOutput {
+            features: OutputFeatures::default(),
+            script: script!(Nop Nop),
+            ..Default::default()
+        };
+        (input, vec![different, same])
+    }
+
+    #[test]
+    fn it_round_trips_the_encoding() {
+        let covenant = Covenant::new(vec![
+            CovenantFilter::AbsoluteHeight(123),
+            CovenantFilter::OutputHashEq(vec![1u8; 32]),
+            CovenantFilter::FieldsPreserved(vec![OutputField::Features, OutputField::Covenant]),
+        ]);
+        assert_eq!(Covenant::from_bytes(&covenant.as_bytes()).unwrap(), covenant);
+        assert!(Covenant::default().as_bytes().is_empty());
+
+        let mut bytes = covenant.as_bytes();
+        bytes.pop();
+        assert_eq!(
+            Covenant::from_bytes(&bytes).unwrap_err(),
+            CovenantError::UnexpectedEndOfBytes
+        );
+        assert_eq!(
+            Covenant::from_bytes(&[0xff]).unwrap_err(),
+            CovenantError::UnknownFilter(0xff)
+        );
+    }
+
+    #[test]
+    fn it_filters_the_spending_outputs() {
+        let (input, outputs) = input_and_outputs();
+        Covenant::default().execute(0, &input, &outputs).unwrap();
+
+        let covenant = Covenant::new(vec![CovenantFilter::FieldsPreserved(vec![
+            OutputField::Features,
+            OutputField::Script,
+        ])]);
+        covenant.execute(0, &input, &outputs).unwrap();
+        covenant.execute(0, &input, &outputs[..1]).unwrap_err();
+
+        let covenant = Covenant::new(vec![CovenantFilter::OutputHashEq(outputs[0].hash())]);
+        covenant.execute(0, &input, &outputs).unwrap();
+        covenant.execute(0, &input, &outputs[1..]).unwrap_err();
+    }
+
+    #[test]
+    fn it_checks_the_height() {
+        let (input, outputs) = input_and_outputs();
+        let covenant = Covenant::new(vec![CovenantFilter::AbsoluteHeight(10)]);
+        let err = covenant.execute(9, &input, &outputs).unwrap_err();
+        assert_eq!(
+            err,
+            CovenantError::NoMatchingOutputs(CovenantFilter::AbsoluteHeight(10))
+        );
+        covenant.execute(10, &input, &outputs).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Value and payment metadata encrypted to the recipient of an output.
+//!
+//! The recipient of a one-sided payment knows the Diffie-Hellman secret `k_recipient * K_sender_offset`, from which
+//! the spending key of the output is derived. Encrypting the value to the same secret lets the recipient recover the
+//! output without rewinding the range proof, and so without the rewind keys of the sender's wallet having been used
+//! to build the proof.
+//!
+//! The encryption key is bound to the commitment of the output, so the data cannot be copied to another output. The
+//! encoding is `nonce || ChaCha20-Poly1305(value || message)`, with the value as a little endian `u64`.
+
+use crate::transactions::{
+    tari_amount::MicroTari,
+    types::{Commitment, PublicKey},
+};
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305,
+    Key,
+    Nonce,
+};
+use digest::Digest;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryInto,
+    fmt::{Display, Error as FmtError, Formatter},
+    mem::size_of,
+};
+use tari_crypto::{
+    common::Blake256,
+    tari_utilities::{hex::to_hex, ByteArray},
+};
+use thiserror::Error;
+
+/// The largest encrypted data field that consensus accepts
+pub const MAX_ENCRYPTED_DATA_BYTES: usize = 256;
+
+const ENCRYPTED_DATA_KEY_DOMAIN: &[u8] = b"com.tari.base_layer.core.transactions.encrypted_data";
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+/// The longest message that can be encrypted alongside the value
+pub const MAX_ENCRYPTED_MESSAGE_BYTES: usize = MAX_ENCRYPTED_DATA_BYTES - NONCE_SIZE - TAG_SIZE - size_of::<u64>();
+
+#[derive(Debug, Clone, Error, PartialEq, Deserialize, Serialize)]
+pub enum EncryptedDataError {
+    #[error("Encrypted data is {0} bytes, the maximum is {}", MAX_ENCRYPTED_DATA_BYTES)]
+    TooLarge(usize),
+    #[error("Encrypted data is too short to contain a value")]
+    TooShort,
+    #[error("Encrypted data could not be decrypted with the given key")]
+    DecryptionFailed,
+}
+
+/// The encrypted value and payment metadata of an output. Outputs without encrypted data have an empty field.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EncryptedData(Vec<u8>);
+
+impl EncryptedData {
+    /// Encrypts `value` and `message` to the holder of `shared_secret` for the output with the given commitment
+    pub fn encrypt(
+        shared_secret: &PublicKey,
+        commitment: &Commitment,
+        value: MicroTari,
+        message: &[u8],
+    ) -> Result<Self, EncryptedDataError> {
+        let size = NONCE_SIZE + TAG_SIZE + size_of::<u64>() + message.len();
+        if size > MAX_ENCRYPTED_DATA_BYTES {
+            return Err(EncryptedDataError::TooLarge(size));
+        }
+        let mut plaintext = Vec::with_capacity(size_of::<u64>() + message.len());
+        plaintext.extend_from_slice(&u64::from(value).to_le_bytes());
+        plaintext.extend_from_slice(message);
+
+        let mut nonce = [0u8; NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = cipher(shared_secret, commitment)
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .expect("encrypting a buffer in memory cannot fail");
+
+        let mut bytes = Vec::with_capacity(size);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        Ok(Self(bytes))
+    }
+
+    /// Decrypts the value and message. This fails if the data was not encrypted to `shared_secret` for the output
+    /// with the given commitment.
+    pub fn decrypt(
+        &self,
+        shared_secret: &PublicKey,
+        commitment: &Commitment,
+    ) -> Result<(MicroTari, Vec<u8>), EncryptedDataError> {
+        if self.0.len() < NONCE_SIZE + TAG_SIZE + size_of::<u64>() {
+            return Err(EncryptedDataError::TooShort);
+        }
+        let (nonce, ciphertext) = self.0.split_at(NONCE_SIZE);
+        let mut plaintext = cipher(shared_secret, commitment)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| EncryptedDataError::DecryptionFailed)?;
+        let message = plaintext.split_off(size_of::<u64>());
+        let value = u64::from_le_bytes(plaintext.as_slice().try_into().expect("plaintext is 8 bytes"));
+        Ok((MicroTari(value), message))
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, EncryptedDataError> {
+        if bytes.len() > MAX_ENCRYPTED_DATA_BYTES {
+            return Err(EncryptedDataError::TooLarge(bytes.len()));
+        }
+        Ok(Self(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Display for EncryptedData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        write!(f, "{}", to_hex(&self.0))
+    }
+}
+
+fn cipher(shared_secret: &PublicKey, commitment: &Commitment) -> ChaCha20Poly1305 {
+    let key = Blake256::new()
+        .chain(ENCRYPTED_DATA_KEY_DOMAIN)
+        .chain(shared_secret.as_bytes())
+        .chain(commitment.as_bytes())
+        .finalize();
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transactions::types::{CommitmentFactory, PrivateKey};
+    use tari_crypto::{
+        commitment::HomomorphicCommitmentFactory,
+        keys::{DiffieHellmanSharedSecret, PublicKey as PublicKeyTrait, SecretKey},
+    };
+
+    fn random_public_key() -> PublicKey {
+        PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng))
+    }
+
+    fn random_commitment() -> Commitment {
+        CommitmentFactory::default().commit_value(&PrivateKey::random(&mut OsRng), 1234)
+    }
+
+    #[test]
+    fn it_round_trips_the_value_and_message() {
+        let recipient_key = PrivateKey::random(&mut OsRng);
+        let sender_offset_key = PrivateKey::random(&mut OsRng);
+        let commitment = random_commitment();
+
+        let sender_secret = PublicKey::shared_secret(&sender_offset_key, &PublicKey::from_secret_key(&recipient_key));
+        let data = EncryptedData::encrypt(&sender_secret, &commitment, MicroTari(1234), b"for the coffee").unwrap();
+        assert_eq!(data.len(), NONCE_SIZE + TAG_SIZE + 8 + 14);
+
+        let recipient_secret =
+            PublicKey::shared_secret(&recipient_key, &PublicKey::from_secret_key(&sender_offset_key));
+        let (value, message) = data.decrypt(&recipient_secret, &commitment).unwrap();
+        assert_eq!(value, MicroTari(1234));
+        assert_eq!(message, b"for the coffee");
+    }
+
+    #[test]
+    fn it_does_not_decrypt_with_another_key_or_commitment() {
+        let shared_secret = random_public_key();
+        let commitment = random_commitment();
+        let data = EncryptedData::encrypt(&shared_secret, &commitment, MicroTari(1), b"").unwrap();
+
+        assert_eq!(
+            data.decrypt(&random_public_key(), &commitment),
+            Err(EncryptedDataError::DecryptionFailed)
+        );
+        assert_eq!(
+            data.decrypt(&shared_secret, &random_commitment()),
+            Err(EncryptedDataError::DecryptionFailed)
+        );
+        assert_eq!(
+            EncryptedData::default().decrypt(&shared_secret, &commitment),
+            Err(EncryptedDataError::TooShort)
+        );
+    }
+
+    #[test]
+    fn it_limits_the_size() {
+        let shared_secret = random_public_key();
+        let commitment = random_commitment();
+        let message = vec![0u8; MAX_ENCRYPTED_MESSAGE_BYTES];
+        let data = EncryptedData::encrypt(&shared_secret, &commitment, MicroTari(1), &message).unwrap();
+        assert_eq!(data.len(), MAX_ENCRYPTED_DATA_BYTES);
+
+        let message = vec![0u8; MAX_ENCRYPTED_MESSAGE_BYTES + 1];
+        assert_eq!(
+            EncryptedData::encrypt(&shared_secret, &commitment, MicroTari(1), &message),
+            Err(EncryptedDataError::TooLarge(MAX_ENCRYPTED_DATA_BYTES + 1))
+        );
+        assert!(EncryptedData::from_bytes(vec![0u8; MAX_ENCRYPTED_DATA_BYTES + 1]).is_err());
+    }
+}
@@ -20,17 +20,24 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{
-    consensus::{KERNEL_WEIGHT, WEIGHT_PER_INPUT, WEIGHT_PER_OUTPUT},
-    transactions::{tari_amount::*, transaction::MINIMUM_TRANSACTION_FEE},
-};
+use crate::transactions::{tari_amount::*, transaction::MINIMUM_TRANSACTION_FEE, weight::TransactionWeight};
 
 pub struct Fee {}
 
 impl Fee {
-    /// Computes the absolute transaction fee given the fee-per-gram, and the size of the transaction
+    /// Computes the absolute transaction fee given the fee-per-gram, and the size of the transaction. Outputs are
+    /// assumed to have standard-size features and scripts; use `calculate_for_weight` with
+    /// `TransactionWeight::calculate` when they may not.
     pub fn calculate(fee_per_gram: MicroTari, num_kernels: usize, num_inputs: usize, num_outputs: usize) -> MicroTari {
-        (Fee::calculate_weight(num_kernels, num_inputs, num_outputs) * u64::from(fee_per_gram)).into()
+        Fee::calculate_for_weight(
+            fee_per_gram,
+            TransactionWeight::latest().calculate(num_kernels, num_inputs, num_outputs, 0),
+        )
+    }
+
+    /// Computes the absolute transaction fee given the fee-per-gram and the weight of the transaction
+    pub fn calculate_for_weight(fee_per_gram: MicroTari, weight: u64) -> MicroTari {
+        (weight * u64::from(fee_per_gram)).into()
     }
 
     /// Computes the absolute transaction fee using `calculate`, but the resulting fee will always be at least the
@@ -48,11 +55,4 @@ impl Fee {
             fee
         }
     }
-
-    /// Calculate the weight of a transaction based on the number of inputs and outputs
-    pub fn calculate_weight(num_kernels: usize, num_inputs: usize, num_outputs: usize) -> u64 {
-        KERNEL_WEIGHT * num_kernels as u64 +
-            WEIGHT_PER_INPUT * num_inputs as u64 +
-            WEIGHT_PER_OUTPUT * num_outputs as u64
-    }
 }
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::transactions::{
+    covenant::Covenant,
     fee::Fee,
     tari_amount::MicroTari,
     transaction::{
@@ -86,6 +87,7 @@ pub struct UtxoTestParams {
     pub value: MicroTari,
     pub script: TariScript,
     pub output_features: OutputFeatures,
+    pub covenant: Covenant,
     pub input_data: Option<ExecutionStack>,
 }
 
@@ -95,6 +97,7 @@ impl Default for UtxoTestParams {
             value: 10.into(),
             script: script![Nop],
             output_features: Default::default(),
+            covenant: Default::default(),
             input_data: None,
         }
     }
@@ -129,6 +132,7 @@ impl TestParams {
             &self.spend_key,
             &params.script,
             &params.output_features,
+            &params.covenant,
             &self.sender_offset_private_key,
         )
         .unwrap();
@@ -144,6 +148,7 @@ impl TestParams {
             self.script_private_key.clone(),
             self.sender_offset_public_key.clone(),
             metadata_signature,
+            params.covenant,
         )
     }
 
@@ -452,6 +457,7 @@ pub fn spend_utxos(schema: TransactionSchema) -> (Transaction, Vec<UnblindedOutp
             output_features: schema.features.clone(),
             script: schema.script.clone(),
             input_data: schema.input_data.clone(),
+            covenant: Covenant::default(),
         });
         outputs.push(utxo.clone());
         stx_builder
@@ -470,6 +476,7 @@ pub fn spend_utxos(schema: TransactionSchema) -> (Transaction, Vec<UnblindedOutp
         &test_params_change_and_txn.change_spend_key,
         &script,
         &schema.features,
+        &Covenant::default(),
         &test_params_change_and_txn.sender_offset_private_key,
     )
     .unwrap();
@@ -485,6 +492,7 @@ pub fn spend_utxos(schema: TransactionSchema) -> (Transaction, Vec<UnblindedOutp
         test_params_change_and_txn.script_private_key.clone(),
         change_sender_offset_public_key,
         metadata_sig,
+        Covenant::default(),
     );
     outputs.push(change_output);
     match stx_protocol.finalize(KernelFeatures::empty(), &factories) {
@@ -519,9 +527,16 @@ pub fn create_utxo(
     let features = features.unwrap_or_default();
     let commitment = factories.commitment.commit_value(&keys.k, value.into());
     let proof = factories.range_proof.construct_proof(&keys.k, value.into()).unwrap();
-    let metadata_sig =
-        TransactionOutput::create_final_metadata_signature(&value, &keys.k, &script, &features, &offset_keys.k)
-            .unwrap();
+    let covenant = Covenant::default();
+    let metadata_sig = TransactionOutput::create_final_metadata_signature(
+        &value,
+        &keys.k,
+        &script,
+        &features,
+        &covenant,
+        &offset_keys.k,
+    )
+    .unwrap();
 
     let utxo = TransactionOutput::new(
         features,
@@ -530,6 +545,7 @@ pub fn create_utxo(
         script.clone(),
         offset_keys.pk,
         metadata_sig,
+        covenant,
     );
     (utxo, keys.k, offset_keys.k)
 }
@@ -1,11 +1,16 @@
 pub mod aggregated_body;
 pub mod bullet_rangeproofs;
+pub mod covenant;
+pub mod encrypted_data;
 pub mod fee;
+pub mod one_sided;
+pub mod script_analysis;
 pub mod tari_amount;
 pub mod transaction;
 #[allow(clippy::op_ref)]
 pub mod transaction_protocol;
 pub mod types;
+pub mod weight;
 // Re-export commonly used structs
 pub use transaction_protocol::{recipient::ReceiverTransactionProtocol, sender::SenderTransactionProtocol};
 
@@ -18,3 +23,5 @@ mod coinbase_builder;
 pub use crate::transactions::coinbase_builder::CoinbaseBuildError;
 #[cfg(any(feature = "base_node", feature = "transactions"))]
 pub use crate::transactions::coinbase_builder::CoinbaseBuilder;
+#[cfg(any(feature = "base_node", feature = "transactions"))]
+pub use crate::transactions::coinbase_builder::{miner_coinbase_value, CoinbasePayout};
@@ -0,0 +1,137 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Reclaimable one-sided payments.
+//!
+//! A one-sided payment is normally locked with `PushPubKey(K_recipient)`, so a payment sent to a mistyped address can
+//! never be spent again. A reclaimable payment is locked with
+//!
+//! ```text
+//! CheckHeight(lock_height) GeZero IfThen PushPubKey(K_refund) Else PushPubKey(K_recipient) EndIf
+//! ```
+//!
+//! which leaves `K_recipient` on the stack until the chain reaches `lock_height` and `K_refund` from then on. The
+//! recipient has to claim the payment before the lock height, after which only the sender can spend it.
+
+use crate::transactions::types::PublicKey;
+use tari_crypto::script::{Opcode, TariScript};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReclaimableOneSidedScript {
+    pub recipient: PublicKey,
+    pub refund_key: PublicKey,
+    pub lock_height: u64,
+}
+
+impl ReclaimableOneSidedScript {
+    pub fn new(recipient: PublicKey, refund_key: PublicKey, lock_height: u64) -> Self {
+        Self {
+            recipient,
+            refund_key,
+            lock_height,
+        }
+    }
+
+    /// Parses `script`, returning `None` if it is not a reclaimable one-sided payment script
+    pub fn from_script(script: &TariScript) -> Option<Self> {
+        let opcodes = Opcode::parse(&script.as_bytes()).ok()?;
+        match opcodes.as_slice() {
+            [Opcode::CheckHeight(lock_height), Opcode::GeZero, Opcode::IfThen, Opcode::PushPubKey(refund_key), Opcode::Else, Opcode::PushPubKey(recipient), Opcode::EndIf] => {
+                Some(Self::new((**recipient).clone(), (**refund_key).clone(), *lock_height))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn to_script(&self) -> TariScript {
+        TariScript::new(vec![
+            Opcode::CheckHeight(self.lock_height),
+            Opcode::GeZero,
+            Opcode::IfThen,
+            Opcode::PushPubKey(Box::new(self.refund_key.clone())),
+            Opcode::Else,
+            Opcode::PushPubKey(Box::new(self.recipient.clone())),
+            Opcode::EndIf,
+        ])
+    }
+
+    /// The script of a plain one-sided payment to the same recipient, which is what the recipient's wallet knows to
+    /// look for
+    pub fn recipient_script(&self) -> TariScript {
+        TariScript::new(vec![Opcode::PushPubKey(Box::new(self.recipient.clone()))])
+    }
+
+    /// Returns true if the sender can spend the output at `height`
+    pub fn is_reclaimable_at(&self, height: u64) -> bool {
+        height >= self.lock_height
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transactions::{script_analysis::analyze_script, types::PrivateKey};
+    use rand::rngs::OsRng;
+    use tari_crypto::{
+        keys::{PublicKey as PublicKeyTrait, SecretKey},
+        script,
+    };
+
+    fn random_public_key() -> PublicKey {
+        PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng))
+    }
+
+    #[test]
+    fn it_round_trips_through_the_script() {
+        let reclaimable = ReclaimableOneSidedScript::new(random_public_key(), random_public_key(), 1234);
+        let script = reclaimable.to_script();
+        assert_eq!(ReclaimableOneSidedScript::from_script(&script), Some(reclaimable));
+    }
+
+    #[test]
+    fn it_does_not_parse_other_scripts() {
+        let recipient = random_public_key();
+        assert!(ReclaimableOneSidedScript::from_script(&script!(PushPubKey(Box::new(recipient)))).is_none());
+        assert!(ReclaimableOneSidedScript::from_script(&script!(Nop)).is_none());
+    }
+
+    #[test]
+    fn it_is_a_standard_script() {
+        let reclaimable = ReclaimableOneSidedScript::new(random_public_key(), random_public_key(), 1234);
+        let analysis = analyze_script(&reclaimable.to_script()).unwrap();
+        assert!(analysis.check_standard().is_ok());
+        assert_eq!(analysis.min_input_items, 0);
+        assert_eq!(analysis.min_stack_delta, 1);
+        assert_eq!(analysis.max_stack_delta, 1);
+    }
+
+    #[test]
+    fn it_is_reclaimable_from_the_lock_height() {
+        let reclaimable = ReclaimableOneSidedScript::new(random_public_key(), random_public_key(), 1234);
+        assert!(!reclaimable.is_reclaimable_at(1233));
+        assert!(reclaimable.is_reclaimable_at(1234));
+        assert_eq!(
+            reclaimable.recipient_script(),
+            script!(PushPubKey(Box::new(reclaimable.recipient)))
+        );
+    }
+}
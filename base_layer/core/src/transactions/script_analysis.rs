@@ -0,0 +1,303 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Static analysis of TariScripts.
+//!
+//! [analyze_script] walks a script's opcodes without executing them and reports the number of input stack items the
+//! script consumes, how deep the stack can grow, an upper bound on the cost of executing it and its serialized size.
+//! Both branches of every `IfThen`/`Else` are followed, so the bounds hold for any input. Wallets use the result to
+//! avoid creating outputs that base nodes would not relay or that could never be spent.
+
+use tari_crypto::script::{Opcode, ScriptError, TariScript};
+use thiserror::Error;
+
+/// The largest serialized script that is considered standard
+pub const MAX_STANDARD_SCRIPT_SIZE: usize = 512;
+/// The maximum stack depth, beyond the input stack, of a standard script
+pub const MAX_STANDARD_STACK_DEPTH: usize = 64;
+/// The maximum execution cost of a standard script
+pub const MAX_STANDARD_EXECUTION_COST: u64 = 1_000;
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum ScriptAnalysisError {
+    #[error("The script could not be parsed: {0}")]
+    ParseError(String),
+    #[error("IfThen, Else and EndIf opcodes are not balanced")]
+    UnbalancedConditional,
+}
+
+impl From<ScriptError> for ScriptAnalysisError {
+    fn from(err: ScriptError) -> Self {
+        ScriptAnalysisError::ParseError(err.to_string())
+    }
+}
+
+/// Reasons a script is not standard
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum NonStandardScript {
+    #[error("Script is {0} bytes, the maximum is {}", MAX_STANDARD_SCRIPT_SIZE)]
+    TooLarge(usize),
+    #[error(
+        "Script can grow the stack by {0} items, the maximum is {}",
+        MAX_STANDARD_STACK_DEPTH
+    )]
+    StackTooDeep(usize),
+    #[error("Script execution cost is {0}, the maximum is {}", MAX_STANDARD_EXECUTION_COST)]
+    TooExpensive(u64),
+    #[error("Script contains discouraged opcodes: {0}")]
+    DiscouragedOpcodes(String),
+    #[error("Script always fails, so the output can never be spent")]
+    Unspendable,
+}
+
+/// The result of statically analysing a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptAnalysis {
+    /// The minimum number of items the input stack must contain for the script to execute
+    pub min_input_items: usize,
+    /// The maximum number of items the script can add to the stack on top of the input stack
+    pub max_stack_depth: usize,
+    /// The net change in the size of the stack, taking the branch that leaves the fewest items
+    pub min_stack_delta: isize,
+    /// The net change in the size of the stack, taking the branch that leaves the most items
+    pub max_stack_delta: isize,
+    /// An upper bound on the cost of executing the script
+    pub max_execution_cost: u64,
+    /// The size of the script when serialized
+    pub serialized_size: usize,
+    /// Opcodes that standard scripts should not use, in the order they appear
+    pub discouraged_opcodes: Vec<String>,
+    /// True if every execution path ends in `Return`
+    pub always_fails: bool,
+}
+
+impl ScriptAnalysis {
+    /// Checks the analysis against the standardness limits. A standard script is small and cheap enough for base
+    /// nodes to relay and must be possible to spend.
+    pub fn check_standard(&self) -> Result<(), NonStandardScript> {
+        if self.always_fails {
+            return Err(NonStandardScript::Unspendable);
+        }
+        if self.serialized_size > MAX_STANDARD_SCRIPT_SIZE {
+            return Err(NonStandardScript::TooLarge(self.serialized_size));
+        }
+        if self.max_stack_depth > MAX_STANDARD_STACK_DEPTH {
+            return Err(NonStandardScript::StackTooDeep(self.max_stack_depth));
+        }
+        if self.max_execution_cost > MAX_STANDARD_EXECUTION_COST {
+            return Err(NonStandardScript::TooExpensive(self.max_execution_cost));
+        }
+        if !self.discouraged_opcodes.is_empty() {
+            return Err(NonStandardScript::DiscouragedOpcodes(
+                self.discouraged_opcodes.join(", "),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_standard(&self) -> bool {
+        self.check_standard().is_ok()
+    }
+}
+
+/// The possible stack states along the paths through the script analysed so far
+#[derive(Debug, Clone, Copy)]
+struct PathState {
+    /// Lowest and highest stack size relative to the input stack
+    min_delta: isize,
+    max_delta: isize,
+    cost: u64,
+    /// All paths have hit `Return`
+    failed: bool,
+}
+
+impl PathState {
+    fn merge(self, other: PathState) -> PathState {
+        // A path that always fails does not contribute to the stack of the paths that continue
+        match (self.failed, other.failed) {
+            (true, false) => PathState {
+                cost: self.cost.max(other.cost),
+                ..other
+            },
+            (false, true) => PathState {
+                cost: self.cost.max(other.cost),
+                ..self
+            },
+            _ => PathState {
+                min_delta: self.min_delta.min(other.min_delta),
+                max_delta: self.max_delta.max(other.max_delta),
+                cost: self.cost.max(other.cost),
+                failed: self.failed && other.failed,
+            },
+        }
+    }
+}
+
+/// The number of items an opcode requires on the stack, the number it leaves in their place and its cost, or `None`
+/// if the analyser does not recognise the opcode
+fn stack_effect(opcode: &Opcode) -> Option<(usize, usize, u64)> {
+    use Opcode::*;
+    let effect = match opcode {
+        Nop | CheckHeightVerify(_) | Return | Else | EndIf => (0, 0, 1),
+        CheckHeight(_) | PushZero | PushOne | PushHash(_) | PushInt(_) | PushPubKey(_) => (0, 1, 1),
+        CompareHeightVerify | Drop | IfThen => (1, 0, 1),
+        CompareHeight | GeZero | GtZero | LeZero | LtZero => (1, 1, 1),
+        Dup => (1, 2, 1),
+        RevRot => (3, 3, 1),
+        Add | Sub | Equal => (2, 1, 1),
+        EqualVerify => (2, 0, 1),
+        Or(n) => (*n as usize + 1, 1, 1 + *n as u64),
+        OrVerify(n) => (*n as usize + 1, 0, 1 + *n as u64),
+        HashBlake256 | HashSha256 | HashSha3 => (1, 1, 10),
+        CheckSig(_) => (2, 1, 100),
+        CheckSigVerify(_) => (2, 0, 100),
+        #[allow(unreachable_patterns)]
+        _ => return None,
+    };
+    Some(effect)
+}
+
+/// Statically analyse `script`. See the [module documentation](self) for details.
+pub fn analyze_script(script: &TariScript) -> Result<ScriptAnalysis, ScriptAnalysisError> {
+    let bytes = script.as_bytes();
+    let opcodes = Opcode::parse(&bytes)?;
+
+    let mut min_input_items = 0usize;
+    let mut max_stack_depth = 0usize;
+    let mut discouraged_opcodes = Vec::new();
+    let mut state = PathState {
+        min_delta: 0,
+        max_delta: 0,
+        cost: 0,
+        failed: false,
+    };
+    // For each open IfThen: the state after the condition is popped and, once Else is reached, the state at the end
+    // of the `then` branch
+    let mut branches: Vec<(PathState, Option<PathState>)> = Vec::new();
+
+    for opcode in &opcodes {
+        // Return is allowed inside a branch, e.g. to fail a path, but a standard script should use a verify opcode
+        let effect = stack_effect(opcode);
+        if effect.is_none() || matches!(opcode, Opcode::Return) {
+            discouraged_opcodes.push(format!("{:?}", opcode));
+        }
+        let (required, produced, cost) = effect.unwrap_or((0, 0, 1));
+
+        // Opcodes after a Return are only reachable if a branch is closed
+        if !state.failed {
+            min_input_items = min_input_items.max((required as isize - state.min_delta).max(0) as usize);
+            state.min_delta += produced as isize - required as isize;
+            state.max_delta += produced as isize - required as isize;
+            state.cost += cost;
+        }
+
+        match opcode {
+            Opcode::IfThen => branches.push((state, None)),
+            Opcode::Else => {
+                let (before, then_state) = branches.last_mut().ok_or(ScriptAnalysisError::UnbalancedConditional)?;
+                if then_state.is_some() {
+                    return Err(ScriptAnalysisError::UnbalancedConditional);
+                }
+                *then_state = Some(state);
+                state = *before;
+            },
+            Opcode::EndIf => {
+                let (before, then_state) = branches.pop().ok_or(ScriptAnalysisError::UnbalancedConditional)?;
+                // Without an Else, the condition being false skips straight to EndIf
+                state = state.merge(then_state.unwrap_or(before));
+            },
+            Opcode::Return => state.failed = true,
+            _ => {},
+        }
+        max_stack_depth = max_stack_depth.max(state.max_delta.max(0) as usize);
+    }
+
+    if !branches.is_empty() {
+        return Err(ScriptAnalysisError::UnbalancedConditional);
+    }
+
+    Ok(ScriptAnalysis {
+        min_input_items,
+        max_stack_depth,
+        min_stack_delta: state.min_delta,
+        max_stack_delta: state.max_delta,
+        max_execution_cost: state.cost,
+        serialized_size: bytes.len(),
+        discouraged_opcodes,
+        always_fails: state.failed,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_crypto::script;
+
+    #[test]
+    fn it_analyzes_straight_line_scripts() {
+        let analysis = analyze_script(&script!(Nop)).unwrap();
+        assert_eq!(analysis.min_input_items, 0);
+        assert_eq!(analysis.max_stack_depth, 0);
+        assert_eq!(analysis.max_execution_cost, 1);
+        assert!(analysis.is_standard());
+
+        let analysis = analyze_script(&script!(Drop Drop PushOne PushZero PushOne Add)).unwrap();
+        assert_eq!(analysis.min_input_items, 2);
+        assert_eq!(analysis.max_stack_depth, 1);
+        assert_eq!(analysis.min_stack_delta, 0);
+        assert_eq!(analysis.max_stack_delta, 0);
+        assert_eq!(analysis.serialized_size, 6);
+    }
+
+    #[test]
+    fn it_follows_both_branches() {
+        let analysis = analyze_script(&script!(IfThen PushOne Else PushZero PushZero HashBlake256 EndIf)).unwrap();
+        assert_eq!(analysis.min_input_items, 1);
+        assert_eq!(analysis.min_stack_delta, 0);
+        assert_eq!(analysis.max_stack_delta, 1);
+        assert_eq!(analysis.max_stack_depth, 1);
+        // IfThen, the more expensive else branch and EndIf
+        assert_eq!(analysis.max_execution_cost, 1 + 1 + 1 + 10 + 1);
+        assert!(analysis.is_standard());
+
+        let err = analyze_script(&script!(IfThen Nop)).unwrap_err();
+        assert_eq!(err, ScriptAnalysisError::UnbalancedConditional);
+        let err = analyze_script(&script!(Nop EndIf)).unwrap_err();
+        assert_eq!(err, ScriptAnalysisError::UnbalancedConditional);
+    }
+
+    #[test]
+    fn it_detects_unspendable_scripts() {
+        let analysis = analyze_script(&script!(Return)).unwrap();
+        assert!(analysis.always_fails);
+        assert_eq!(analysis.check_standard(), Err(NonStandardScript::Unspendable));
+
+        // Only one branch fails
+        let analysis = analyze_script(&script!(IfThen Return Else PushOne EndIf)).unwrap();
+        assert!(!analysis.always_fails);
+        assert_eq!(analysis.min_stack_delta, 0);
+        assert!(matches!(
+            analysis.check_standard(),
+            Err(NonStandardScript::DiscouragedOpcodes(_))
+        ));
+    }
+}
@@ -104,6 +104,183 @@ impl MicroTari {
     pub fn formatted(self) -> FormattedMicroTari {
         self.into()
     }
+
+    /// Formats the amount for display using the given format. Tari amounts are rounded half up to the format's
+    /// precision.
+    pub fn format_with(self, format: &AmountFormat) -> String {
+        let unit = match format.unit {
+            AmountUnit::Auto if self < T => AmountUnit::MicroTari,
+            AmountUnit::Auto => AmountUnit::Tari,
+            unit => unit,
+        };
+
+        match unit {
+            AmountUnit::Tari => {
+                let precision = format.precision.min(MAX_TARI_DECIMALS) as u32;
+                let scale = 10u64.pow(MAX_TARI_DECIMALS as u32 - precision);
+                let mut rounded = self.0 / scale;
+                if scale > 1 && self.0 % scale >= scale / 2 {
+                    rounded += 1;
+                }
+                let divisor = 10u64.pow(precision);
+                let whole = group_digits(rounded / divisor, format.thousands_separator);
+                if precision == 0 {
+                    format!("{} T", whole)
+                } else {
+                    format!(
+                        "{}{}{:0width$} T",
+                        whole,
+                        format.decimal_separator,
+                        rounded % divisor,
+                        width = precision as usize
+                    )
+                }
+            },
+            _ => format!("{} µT", group_digits(self.0, format.thousands_separator)),
+        }
+    }
+
+    /// Parses a user-entered amount such as "1,234.56 T", "1234560 uT" or "1234560". Amounts without a unit are
+    /// taken to be in µT. The separators of the given format are used; the unit and precision are ignored. Parsing is
+    /// exact, amounts with more decimal places than a µT can represent are rejected rather than rounded.
+    pub fn parse_with(s: &str, format: &AmountFormat) -> Result<MicroTari, MicroTariError> {
+        if format.thousands_separator == Some(format.decimal_separator) {
+            return Err(MicroTariError::ParseError(
+                "thousands and decimal separators must differ".to_string(),
+            ));
+        }
+
+        let processed = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let (number, is_micro_tari) =
+            if let Some(n) = processed.strip_suffix("ut").or_else(|| processed.strip_suffix("µt")) {
+                (n, true)
+            } else if let Some(n) = processed.strip_suffix('t') {
+                (n, false)
+            } else {
+                (processed.as_str(), true)
+            };
+
+        if number.starts_with('-') {
+            return Err(MicroTariError::ParseError("value cannot be negative".to_string()));
+        }
+        let number = match format.thousands_separator {
+            Some(sep) => number.replace(sep, ""),
+            None => number.to_string(),
+        };
+        let mut parts = number.splitn(2, format.decimal_separator);
+        let whole = parts.next().unwrap_or("");
+        let fraction = parts.next().unwrap_or("");
+        let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+            return Err(MicroTariError::ParseError(format!("'{}' is not a valid amount", s)));
+        }
+
+        let overflow = || MicroTariError::ParseError("value is too large".to_string());
+        let whole = if whole.is_empty() {
+            0
+        } else {
+            whole.parse::<u64>().map_err(|_| overflow())?
+        };
+        if is_micro_tari {
+            if fraction.chars().any(|c| c != '0') {
+                return Err(MicroTariError::ParseError(
+                    "µT amounts cannot have a fractional part".to_string(),
+                ));
+            }
+            return Ok(MicroTari(whole));
+        }
+
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > MAX_TARI_DECIMALS {
+            return Err(MicroTariError::ParseError(format!(
+                "Tari amounts cannot have more than {} decimal places",
+                MAX_TARI_DECIMALS
+            )));
+        }
+        let fraction = if fraction.is_empty() {
+            0
+        } else {
+            format!("{:0<width$}", fraction, width = MAX_TARI_DECIMALS)
+                .parse::<u64>()
+                .map_err(|e| MicroTariError::ParseError(e.to_string()))?
+        };
+        whole
+            .checked_mul(T.0)
+            .and_then(|v| v.checked_add(fraction))
+            .map(MicroTari)
+            .ok_or_else(overflow)
+    }
+}
+
+/// The number of decimal places in a Tari amount that can be represented in µT
+const MAX_TARI_DECIMALS: usize = 6;
+
+fn group_digits(value: u64, separator: Option<char>) -> String {
+    let digits = value.to_string();
+    let separator = match separator {
+        Some(sep) => sep,
+        None => return digits,
+    };
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+/// The unit that an amount is displayed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountUnit {
+    Tari,
+    MicroTari,
+    /// Tari for amounts of 1 T or more, otherwise µT
+    Auto,
+}
+
+/// Options for formatting amounts for display and parsing amounts entered by users, e.g. to match the user's locale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountFormat {
+    pub unit: AmountUnit,
+    /// The number of decimal places shown for Tari amounts, at most 6
+    pub precision: usize,
+    pub thousands_separator: Option<char>,
+    pub decimal_separator: char,
+}
+
+impl AmountFormat {
+    pub fn with_unit(mut self, unit: AmountUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn with_separators(mut self, thousands_separator: Option<char>, decimal_separator: char) -> Self {
+        self.thousands_separator = thousands_separator;
+        self.decimal_separator = decimal_separator;
+        self
+    }
+}
+
+impl Default for AmountFormat {
+    fn default() -> Self {
+        Self {
+            unit: AmountUnit::Auto,
+            precision: MAX_TARI_DECIMALS,
+            thousands_separator: Some(','),
+            decimal_separator: '.',
+        }
+    }
 }
 
 #[allow(clippy::identity_op)]
@@ -127,33 +304,7 @@ impl std::str::FromStr for MicroTari {
     type Err = MicroTariError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Is this Tari or MicroTari
-        let processed = s.replace(",", "").replace(" ", "").to_ascii_lowercase();
-        let is_micro_tari = if processed.ends_with("ut") || processed.ends_with("µt") {
-            true
-        } else {
-            !processed.ends_with('t')
-        };
-
-        // Avoid using f64 if we an
-        let processed = processed.replace("ut", "").replace("µt", "").replace("t", "");
-        if is_micro_tari {
-            processed
-                .parse::<u64>()
-                .map(|v| MicroTari::from(v.max(0)))
-                .map_err(|e| MicroTariError::ParseError(e.to_string()))
-        } else {
-            processed
-                .parse::<f64>()
-                .map_err(|e| MicroTariError::ParseError(e.to_string()))
-                .map(|v| {
-                    if v < 0.0 {
-                        Err(MicroTariError::ParseError("value cannot be negative".to_string()))
-                    } else {
-                        Ok(MicroTari::from(Tari::from(v.max(0.0))))
-                    }
-                })?
-        }
+        MicroTari::parse_with(s, &AmountFormat::default())
     }
 }
 
@@ -263,7 +414,8 @@ impl From<MicroTari> for Tari {
 
 #[cfg(test)]
 mod test {
-    use super::{MicroTari, Tari};
+    use super::*;
+    use rand::{rngs::OsRng, Rng};
     use std::str::FromStr;
     #[test]
     fn micro_tari_arithmetic() {
@@ -351,4 +503,103 @@ mod test {
         let s = format!("{}", Tari::from(99999.100).formatted());
         assert_eq!(s, "99,999.10 T");
     }
+
+    #[test]
+    fn parse_exact_amounts() {
+        let format = AmountFormat::default();
+        assert_eq!(
+            MicroTari::parse_with("1,234.56 T", &format).unwrap(),
+            MicroTari(1_234_560_000)
+        );
+        assert_eq!(
+            MicroTari::parse_with("1234560 uT", &format).unwrap(),
+            MicroTari(1_234_560)
+        );
+        assert_eq!(
+            MicroTari::parse_with("1 234 560 µT", &format).unwrap(),
+            MicroTari(1_234_560)
+        );
+        assert_eq!(MicroTari::parse_with(".5T", &format).unwrap(), MicroTari(500_000));
+        assert_eq!(MicroTari::parse_with("0.000001 t", &format).unwrap(), MicroTari(1));
+        assert_eq!(
+            MicroTari::parse_with("2.500000000 T", &format).unwrap(),
+            MicroTari(2_500_000)
+        );
+        assert_eq!(
+            MicroTari::parse_with("18446744073709.551615 T", &format).unwrap(),
+            MicroTari(u64::MAX)
+        );
+        assert!(MicroTari::parse_with("18446744073709.551616 T", &format).is_err());
+        assert!(MicroTari::parse_with("0.0000001 T", &format).is_err());
+        assert!(MicroTari::parse_with("1.5 uT", &format).is_err());
+        assert!(MicroTari::parse_with("1.2.3 T", &format).is_err());
+        assert!(MicroTari::parse_with(". T", &format).is_err());
+        assert!(MicroTari::parse_with("", &format).is_err());
+        assert!(MicroTari::parse_with("1e6", &format).is_err());
+
+        let european = format.with_separators(Some('.'), ',');
+        assert_eq!(
+            MicroTari::parse_with("1.234,56 T", &european).unwrap(),
+            MicroTari(1_234_560_000)
+        );
+        let invalid = AmountFormat::default().with_separators(Some('.'), '.');
+        assert!(MicroTari::parse_with("1 T", &invalid).is_err());
+    }
+
+    #[test]
+    fn format_with_options() {
+        let format = AmountFormat::default();
+        assert_eq!(MicroTari(1_234_560_000).format_with(&format), "1,234.560000 T");
+        assert_eq!(MicroTari(999_999).format_with(&format), "999,999 µT");
+        let format = format.with_precision(2);
+        assert_eq!(MicroTari(1_234_565_000).format_with(&format), "1,234.57 T");
+        assert_eq!(MicroTari(1_234_564_999).format_with(&format), "1,234.56 T");
+        assert_eq!(MicroTari(999_995_000).format_with(&format), "1,000.00 T");
+        let format = format.with_precision(0).with_unit(AmountUnit::Tari);
+        assert_eq!(MicroTari(499_999).format_with(&format), "0 T");
+        assert_eq!(MicroTari(u64::MAX).format_with(&format), "18,446,744,073,710 T");
+        let format = format.with_unit(AmountUnit::MicroTari).with_separators(None, '.');
+        assert_eq!(MicroTari(1_234_560_000).format_with(&format), "1234560000 µT");
+        let format = AmountFormat::default()
+            .with_precision(3)
+            .with_separators(Some(' '), ',')
+            .with_unit(AmountUnit::Tari);
+        assert_eq!(MicroTari(1_234_567_890).format_with(&format), "1 234,568 T");
+    }
+
+    #[test]
+    fn format_and_parse_round_trip() {
+        let formats = vec![
+            AmountFormat::default(),
+            AmountFormat::default().with_unit(AmountUnit::Tari),
+            AmountFormat::default().with_unit(AmountUnit::MicroTari),
+            AmountFormat::default().with_separators(None, '.'),
+            AmountFormat::default().with_separators(Some('.'), ','),
+            AmountFormat::default().with_separators(Some(' '), ','),
+            AmountFormat::default().with_separators(Some('\''), '.'),
+        ];
+        let mut values = vec![0, 1, 999_999, 1_000_000, 1_000_001, u64::MAX];
+        values.extend((0..1000).map(|_| OsRng.gen::<u64>()));
+        values.extend((0..1000).map(|_| OsRng.gen_range(0..1_000_000_000_000)));
+
+        for format in &formats {
+            for &v in &values {
+                let s = MicroTari(v).format_with(format);
+                assert_eq!(MicroTari::parse_with(&s, format).unwrap(), MicroTari(v), "{}", s);
+            }
+        }
+
+        // Rounding to fewer decimal places is never off by more than half of the last place shown
+        for precision in 0..MAX_TARI_DECIMALS {
+            let format = AmountFormat::default()
+                .with_unit(AmountUnit::Tari)
+                .with_precision(precision);
+            let half = 10u64.pow((MAX_TARI_DECIMALS - precision) as u32) / 2;
+            for &v in values.iter().filter(|v| **v < u64::MAX - half) {
+                let parsed = MicroTari::parse_with(&MicroTari(v).format_with(&format), &format).unwrap();
+                let diff = if parsed.0 > v { parsed.0 - v } else { v - parsed.0 };
+                assert!(diff <= half, "{} {}", v, parsed);
+            }
+        }
+    }
 }
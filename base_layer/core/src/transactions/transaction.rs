@@ -23,24 +23,30 @@
 // Portions of this file were originally copyrighted (c) 2018 The Grin Developers, issued under the Apache License,
 // Version 2.0, available at http://www.apache.org/licenses/LICENSE-2.0.
 
-use crate::transactions::{
-    aggregated_body::AggregateBody,
-    tari_amount::{uT, MicroTari},
-    transaction_protocol::{build_challenge, RewindData, TransactionMetadata},
-    types::{
-        BlindingFactor,
-        Challenge,
-        ComSignature,
-        Commitment,
-        CommitmentFactory,
-        CryptoFactories,
-        HashDigest,
-        MessageHash,
-        PrivateKey,
-        PublicKey,
-        RangeProof,
-        RangeProofService,
-        Signature,
+use crate::{
+    consensus::ToConsensusBytes,
+    transactions::{
+        aggregated_body::AggregateBody,
+        covenant::{Covenant, CovenantError},
+        encrypted_data::{EncryptedData, EncryptedDataError},
+        tari_amount::{uT, MicroTari},
+        transaction_protocol::{build_challenge, RewindData, TransactionMetadata},
+        types::{
+            BlindingFactor,
+            Challenge,
+            ComSignature,
+            Commitment,
+            CommitmentFactory,
+            CryptoFactories,
+            HashDigest,
+            MessageHash,
+            PrivateKey,
+            PublicKey,
+            RangeProof,
+            RangeProofService,
+            Signature,
+        },
+        weight::{output_metadata_size, TransactionWeight},
     },
 };
 use blake2::Digest;
@@ -75,6 +81,12 @@ pub const MAX_TRANSACTION_INPUTS: usize = 12_500;
 pub const MAX_TRANSACTION_OUTPUTS: usize = 500;
 pub const MAX_TRANSACTION_RECIPIENTS: usize = 15;
 pub const MINIMUM_TRANSACTION_FEE: MicroTari = MicroTari(100);
+/// The maximum size of the unique id of an output. Unique ids are used as keys in the chain database.
+pub const MAX_UNIQUE_ID_BYTES: usize = 64;
+
+/// Prefixes the hash preimage of inputs and outputs that carry a covenant. The preimage of every other input and output
+/// starts with its output flags, which are never `t`, so the two forms cannot collide.
+const COVENANT_HASH_DOMAIN: &[u8] = b"tari.transactions.covenant_hash";
 
 //--------------------------------------        Output features   --------------------------------------------------//
 
@@ -102,19 +114,31 @@ pub struct OutputFeatures {
     /// the maturity of the specific UTXO. This is the min lock height at which an UTXO can be spent. Coinbase UTXO
     /// require a min maturity of the Coinbase_lock_height, this should be checked on receiving new blocks.
     pub maturity: u64,
+    /// The value and payment metadata encrypted to the recipient, used to recover one-sided payments. Empty for
+    /// outputs without encrypted data.
+    #[serde(default)]
+    pub encrypted_data: EncryptedData,
+    /// The public key of the asset that issued the unique id of this output
+    #[serde(default)]
+    pub parent_public_key: Option<PublicKey>,
+    /// Identifies the unique asset token that this output carries. At most one unspent output may carry a given unique
+    /// id under the same parent public key.
+    #[serde(default)]
+    pub unique_id: Option<Vec<u8>>,
 }
 
 impl OutputFeatures {
+    /// The canonical encoding of the features, used in input and output hashes. This must not depend on the serde
+    /// derive, as adding a field to the struct would otherwise silently change every hash.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::new();
-        bincode::serialize_into(&mut buf, self).unwrap(); // this should not fail
-        buf
+        self.to_consensus_bytes()
     }
 
     pub fn create_coinbase(maturity_height: u64) -> OutputFeatures {
         OutputFeatures {
             flags: OutputFlags::COINBASE_OUTPUT,
             maturity: maturity_height,
+            ..OutputFeatures::default()
         }
     }
 
@@ -125,6 +149,73 @@ impl OutputFeatures {
             ..OutputFeatures::default()
         }
     }
+
+    /// Create an `OutputFeatures` carrying the given encrypted data and all other values at their default setting
+    pub fn with_encrypted_data(encrypted_data: EncryptedData) -> OutputFeatures {
+        OutputFeatures {
+            encrypted_data,
+            ..OutputFeatures::default()
+        }
+    }
+
+    /// Create an `OutputFeatures` carrying the given unique id and all other values at their default setting
+    pub fn with_unique_id(parent_public_key: Option<PublicKey>, unique_id: Vec<u8>) -> OutputFeatures {
+        OutputFeatures {
+            parent_public_key,
+            unique_id: Some(unique_id),
+            ..OutputFeatures::default()
+        }
+    }
+
+    /// Create an `OutputFeatures` that registers the asset with the given public key, with all other values at their
+    /// default setting
+    pub fn for_asset_registration(asset_public_key: PublicKey) -> OutputFeatures {
+        OutputFeatures {
+            flags: OutputFlags::ASSET_REGISTRATION,
+            parent_public_key: Some(asset_public_key),
+            ..OutputFeatures::default()
+        }
+    }
+
+    pub fn is_asset_registration(&self) -> bool {
+        self.flags.contains(OutputFlags::ASSET_REGISTRATION)
+    }
+
+    /// The unique asset token carried by the output, if any. An asset registration carries the empty unique id under
+    /// the asset public key, so that each asset is registered by at most one unspent output.
+    pub fn unique_asset_id(&self) -> Option<UniqueAssetId> {
+        if self.is_asset_registration() {
+            return self.parent_public_key.as_ref().map(|parent_public_key| UniqueAssetId {
+                parent_public_key: Some(parent_public_key.clone()),
+                unique_id: Vec::new(),
+            });
+        }
+        self.unique_id.as_ref().map(|unique_id| UniqueAssetId {
+            parent_public_key: self.parent_public_key.clone(),
+            unique_id: unique_id.clone(),
+        })
+    }
+
+    /// Checks that the unique id is not empty and not larger than [MAX_UNIQUE_ID_BYTES], and that an asset
+    /// registration carries the asset public key and no unique id
+    pub fn check_unique_id(&self) -> Result<(), TransactionError> {
+        if self.is_asset_registration() {
+            if self.parent_public_key.is_none() || self.unique_id.is_some() {
+                return Err(TransactionError::InvalidUniqueId(
+                    "An asset registration must carry the asset public key and no unique id".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+        match self.unique_id.as_ref().map(Vec::len) {
+            Some(0) => Err(TransactionError::InvalidUniqueId("Unique id is empty".to_string())),
+            Some(len) if len > MAX_UNIQUE_ID_BYTES => Err(TransactionError::InvalidUniqueId(format!(
+                "Unique id is {} bytes, the maximum is {}",
+                len, MAX_UNIQUE_ID_BYTES
+            ))),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Default for OutputFeatures {
@@ -132,6 +223,9 @@ impl Default for OutputFeatures {
         OutputFeatures {
             flags: OutputFlags::empty(),
             maturity: 0,
+            encrypted_data: EncryptedData::default(),
+            parent_public_key: None,
+            unique_id: None,
         }
     }
 }
@@ -158,11 +252,29 @@ impl Display for OutputFeatures {
     }
 }
 
+/// A unique asset token: the unique id of an output, scoped by the public key of the asset that issued it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UniqueAssetId {
+    pub parent_public_key: Option<PublicKey>,
+    pub unique_id: Vec<u8>,
+}
+
+impl Display for UniqueAssetId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.parent_public_key {
+            Some(ref parent_public_key) => write!(f, "{}/{}", parent_public_key.to_hex(), self.unique_id.to_hex()),
+            None => write!(f, "{}", self.unique_id.to_hex()),
+        }
+    }
+}
+
 bitflags! {
     #[derive(Deserialize, Serialize)]
     pub struct OutputFlags: u8 {
         /// Output is a coinbase output, must not be spent until maturity
         const COINBASE_OUTPUT = 0b0000_0001;
+        /// Output registers the asset whose public key is the parent public key of the output
+        const ASSET_REGISTRATION = 0b0000_0010;
     }
 }
 
@@ -200,6 +312,14 @@ pub enum TransactionError {
     ScriptOffset,
     #[error("Error executing script: {0}")]
     ScriptExecutionError(String),
+    #[error("Covenant error: {0}")]
+    CovenantError(#[from] CovenantError),
+    #[error("Encrypted data error: {0}")]
+    EncryptedDataError(#[from] EncryptedDataError),
+    #[error("Invalid unique id: {0}")]
+    InvalidUniqueId(String),
+    #[error("More than one unspent output carries the unique id {0}")]
+    DuplicateUniqueId(String),
 }
 
 //-----------------------------------------     UnblindedOutput   ----------------------------------------------------//
@@ -217,6 +337,8 @@ pub struct UnblindedOutput {
     pub script_private_key: PrivateKey,
     pub sender_offset_public_key: PublicKey,
     pub metadata_signature: ComSignature,
+    #[serde(default)]
+    pub covenant: Covenant,
 }
 
 impl UnblindedOutput {
@@ -231,6 +353,7 @@ impl UnblindedOutput {
         script_private_key: PrivateKey,
         sender_offset_public_key: PublicKey,
         metadata_signature: ComSignature,
+        covenant: Covenant,
     ) -> UnblindedOutput {
         UnblindedOutput {
             value,
@@ -241,6 +364,7 @@ impl UnblindedOutput {
             script_private_key,
             sender_offset_public_key,
             metadata_signature,
+            covenant,
         }
     }
 
@@ -275,6 +399,7 @@ impl UnblindedOutput {
             input_data: self.input_data.clone(),
             script_signature,
             sender_offset_public_key: self.sender_offset_public_key.clone(),
+            covenant: self.covenant.clone(),
         })
     }
 
@@ -292,6 +417,7 @@ impl UnblindedOutput {
             script: self.script.clone(),
             sender_offset_public_key: self.sender_offset_public_key.clone(),
             metadata_signature: self.metadata_signature.clone(),
+            covenant: self.covenant.clone(),
         };
         // A range proof can be constructed for an invalid value so we should confirm that the proof can be verified.
         if !output.verify_range_proof(&factories.range_proof)? {
@@ -327,6 +453,7 @@ impl UnblindedOutput {
             script: self.script.clone(),
             sender_offset_public_key: self.sender_offset_public_key.clone(),
             metadata_signature: self.metadata_signature.clone(),
+            covenant: self.covenant.clone(),
         };
         // A range proof can be constructed for an invalid value so we should confirm that the proof can be verified.
         if !output.verify_range_proof(&factories.range_proof)? {
@@ -384,6 +511,9 @@ pub struct TransactionInput {
     pub script_signature: ComSignature,
     /// The offset public key, K_O
     pub sender_offset_public_key: PublicKey,
+    /// The covenant of the output being spent
+    #[serde(default)]
+    pub covenant: Covenant,
 }
 
 /// An input for a transaction that spends an existing output
@@ -396,6 +526,7 @@ impl TransactionInput {
         input_data: ExecutionStack,
         script_signature: ComSignature,
         sender_offset_public_key: PublicKey,
+        covenant: Covenant,
     ) -> TransactionInput {
         TransactionInput {
             features,
@@ -404,6 +535,7 @@ impl TransactionInput {
             input_data,
             script_signature,
             sender_offset_public_key,
+            covenant,
         }
     }
 
@@ -486,18 +618,30 @@ impl TransactionInput {
     /// Returns the hash of the output data contained in this input.
     /// This hash matches the hash of a transaction output that this input spends.
     pub fn output_hash(&self) -> Vec<u8> {
-        HashDigest::new()
-            .chain(self.features.to_bytes())
-            .chain(self.commitment.as_bytes())
-            .chain(self.script.as_bytes())
-            .finalize()
-            .to_vec()
+        hash_output_data(&self.features, &self.commitment, &self.script, &self.covenant)
     }
 }
 
-/// Implement the canonical hashing function for TransactionInput for use in ordering
+/// Implement the canonical hashing function for TransactionInput for use in ordering.
+///
+/// Inputs without a covenant are hashed as they were before covenants existed. Otherwise every variable length field is
+/// length prefixed using its consensus encoding, so bytes cannot be moved between the script, input data and covenant
+/// without changing the hash.
 impl Hashable for TransactionInput {
     fn hash(&self) -> Vec<u8> {
+        if !self.covenant.is_empty() {
+            return HashDigest::new()
+                .chain(COVENANT_HASH_DOMAIN)
+                .chain(self.features.to_consensus_bytes())
+                .chain(self.commitment.as_bytes())
+                .chain(self.script.to_consensus_bytes())
+                .chain(self.sender_offset_public_key.as_bytes())
+                .chain(self.script_signature.to_consensus_bytes())
+                .chain(self.input_data.to_consensus_bytes())
+                .chain(self.covenant.to_consensus_bytes())
+                .finalize()
+                .to_vec();
+        }
         HashDigest::new()
             .chain(self.features.to_bytes())
             .chain(self.commitment.as_bytes())
@@ -516,10 +660,11 @@ impl Display for TransactionInput {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         write!(
             fmt,
-            "{} [{:?}], Script hash: ({}), Offset_Pubkey: ({})",
+            "{} [{:?}], Script hash: ({}), Covenant: {}, Offset_Pubkey: ({})",
             self.commitment.to_hex(),
             self.features,
             self.script,
+            self.covenant,
             self.sender_offset_public_key.to_hex()
         )
     }
@@ -556,10 +701,19 @@ pub struct TransactionOutput {
     pub sender_offset_public_key: PublicKey,
     /// UTXO signature with the script offset private key, k_O
     pub metadata_signature: ComSignature,
+    /// Constraints on the transaction that spends this output
+    #[serde(default)]
+    pub covenant: Covenant,
 }
 
 /// An output for a transaction, includes a range proof and Tari script metadata
 impl TransactionOutput {
+    /// The serialized size of the output features, script and covenant, which is taken into account by the
+    /// transaction weight
+    pub fn metadata_byte_size(&self) -> usize {
+        output_metadata_size(&self.features, &self.script, &self.covenant)
+    }
+
     /// Create new Transaction Output
     pub fn new(
         features: OutputFeatures,
@@ -568,6 +722,7 @@ impl TransactionOutput {
         script: TariScript,
         sender_offset_public_key: PublicKey,
         metadata_signature: ComSignature,
+        covenant: Covenant,
     ) -> TransactionOutput {
         TransactionOutput {
             features,
@@ -576,6 +731,7 @@ impl TransactionOutput {
             script,
             sender_offset_public_key,
             metadata_signature,
+            covenant,
         }
     }
 
@@ -599,6 +755,7 @@ impl TransactionOutput {
         let challenge = TransactionOutput::build_metadata_signature_challenge(
             &self.script,
             &self.features,
+            &self.covenant,
             &self.sender_offset_public_key,
             &self.metadata_signature.public_nonce(),
             &self.commitment,
@@ -665,6 +822,7 @@ impl TransactionOutput {
         TransactionOutput::build_metadata_signature_challenge(
             &self.script,
             &self.features,
+            &self.covenant,
             &self.sender_offset_public_key,
             &nonce_commitment,
             &self.commitment,
@@ -675,6 +833,7 @@ impl TransactionOutput {
     pub fn build_metadata_signature_challenge(
         script: &TariScript,
         features: &OutputFeatures,
+        covenant: &Covenant,
         sender_offset_public_key: &PublicKey,
         public_commitment_nonce: &Commitment,
         commitment: &Commitment,
@@ -683,6 +842,7 @@ impl TransactionOutput {
             .chain(public_commitment_nonce.as_bytes())
             .chain(script.as_bytes())
             .chain(features.to_bytes())
+            .chain(covenant.as_bytes())
             .chain(sender_offset_public_key.as_bytes())
             .chain(commitment.as_bytes())
             .finalize()
@@ -690,11 +850,13 @@ impl TransactionOutput {
     }
 
     // Create commitment signature for the metadata
+    #[allow(clippy::too_many_arguments)]
     fn create_metadata_signature(
         value: &MicroTari,
         spending_key: &BlindingFactor,
         script: &TariScript,
         output_features: &OutputFeatures,
+        covenant: &Covenant,
         sender_offset_public_key: &PublicKey,
         partial_commitment_nonce: Option<&PublicKey>,
         sender_offset_private_key: Option<&PrivateKey>,
@@ -711,6 +873,7 @@ impl TransactionOutput {
         let e = TransactionOutput::build_metadata_signature_challenge(
             &script,
             &output_features,
+            &covenant,
             &sender_offset_public_key,
             &nonce_commitment,
             &commitment,
@@ -735,6 +898,7 @@ impl TransactionOutput {
         spending_key: &BlindingFactor,
         script: &TariScript,
         output_features: &OutputFeatures,
+        covenant: &Covenant,
         sender_offset_public_key: &PublicKey,
         partial_commitment_nonce: &PublicKey,
     ) -> Result<ComSignature, TransactionError> {
@@ -743,6 +907,7 @@ impl TransactionOutput {
             spending_key,
             script,
             output_features,
+            covenant,
             &sender_offset_public_key,
             Some(partial_commitment_nonce),
             None,
@@ -755,6 +920,7 @@ impl TransactionOutput {
         spending_key: &BlindingFactor,
         script: &TariScript,
         output_features: &OutputFeatures,
+        covenant: &Covenant,
         sender_offset_private_key: &PrivateKey,
     ) -> Result<ComSignature, TransactionError> {
         let sender_offset_public_key = PublicKey::from_secret_key(sender_offset_private_key);
@@ -763,6 +929,7 @@ impl TransactionOutput {
             spending_key,
             script,
             output_features,
+            covenant,
             &sender_offset_public_key,
             None,
             Some(sender_offset_private_key),
@@ -788,14 +955,36 @@ impl TransactionOutput {
 /// c) TransactionInputs will now have the same hash as UTXOs, which makes locating STXOs easier when doing reorgs
 impl Hashable for TransactionOutput {
     fn hash(&self) -> Vec<u8> {
-        HashDigest::new()
-            .chain(self.features.to_bytes())
-            .chain(self.commitment.as_bytes())
+        hash_output_data(&self.features, &self.commitment, &self.script, &self.covenant)
+    }
+}
+
+/// Outputs without a covenant are hashed as they were before covenants existed, which keeps the output MMR roots of
+/// existing blocks valid. Otherwise the script and covenant are length prefixed using their consensus encoding.
+fn hash_output_data(
+    features: &OutputFeatures,
+    commitment: &Commitment,
+    script: &TariScript,
+    covenant: &Covenant,
+) -> Vec<u8> {
+    if !covenant.is_empty() {
+        return HashDigest::new()
+            .chain(COVENANT_HASH_DOMAIN)
+            .chain(features.to_consensus_bytes())
+            .chain(commitment.as_bytes())
             // .chain(range proof) // See docs as to why we exclude this
-            .chain(self.script.as_bytes())
+            .chain(script.to_consensus_bytes())
+            .chain(covenant.to_consensus_bytes())
             .finalize()
-            .to_vec()
-    }
+            .to_vec();
+    }
+    HashDigest::new()
+        .chain(features.to_bytes())
+        .chain(commitment.as_bytes())
+        // .chain(range proof) // See docs as to why we exclude this
+        .chain(script.as_bytes())
+        .finalize()
+        .to_vec()
 }
 
 impl Default for TransactionOutput {
@@ -807,6 +996,7 @@ impl Default for TransactionOutput {
             TariScript::default(),
             PublicKey::default(),
             ComSignature::default(),
+            Covenant::default(),
         )
     }
 }
@@ -825,10 +1015,11 @@ impl Display for TransactionOutput {
         };
         write!(
             fmt,
-            "{} [{:?}], Script: ({}), Offset Pubkey: ({}), Metadata Signature: ({}, {}, {}), Proof: {}",
+            "{} [{:?}], Script: ({}), Covenant: {}, Offset Pubkey: ({}), Metadata Signature: ({}, {}, {}), Proof: {}",
             self.commitment.to_hex(),
             self.features,
             self.script,
+            self.covenant,
             self.sender_offset_public_key.to_hex(),
             self.metadata_signature.u().to_hex(),
             self.metadata_signature.v().to_hex(),
@@ -1122,13 +1313,13 @@ impl Transaction {
     }
 
     /// Returns the byte size or weight of a transaction
-    pub fn calculate_weight(&self) -> u64 {
-        self.body.calculate_weight()
+    pub fn calculate_weight(&self, weighting: &TransactionWeight) -> u64 {
+        self.body.calculate_weight(weighting)
     }
 
     /// Returns the total fee allocated to each byte of the transaction
-    pub fn calculate_ave_fee_per_gram(&self) -> f64 {
-        (self.body.get_total_fee().0 as f64) / self.calculate_weight() as f64
+    pub fn calculate_ave_fee_per_gram(&self, weighting: &TransactionWeight) -> f64 {
+        (self.body.get_total_fee().0 as f64) / self.calculate_weight(weighting) as f64
     }
 
     /// Returns the minimum maturity of the input UTXOs
@@ -1388,9 +1579,11 @@ mod test {
                 &test_params_2.spend_key,
                 &script,
                 &output_features,
+                &Covenant::default(),
                 &test_params_2.sender_offset_private_key,
             )
             .unwrap(),
+            Covenant::default(),
         );
         assert!(!tx_output3.verify_range_proof(&factories.range_proof).unwrap());
     }
@@ -1473,6 +1666,7 @@ mod test {
             input_data,
             script_signature,
             offset_pub_key,
+            Covenant::default(),
         );
 
         let mut kernel = helpers::create_test_kernel(0.into(), 0);
@@ -1583,6 +1777,63 @@ mod test {
         assert!(broken_tx_2.body.contains_duplicated_outputs());
     }
 
+    #[test]
+    fn check_unique_ids() {
+        let (mut tx, _, _) = helpers::create_tx(50000000.into(), 15.into(), 1, 2, 1, 2);
+        assert_eq!(tx.body.check_unique_ids(), Ok(()));
+
+        tx.body.outputs_mut()[0].features.unique_id = Some(vec![1, 2]);
+        assert_eq!(tx.body.check_unique_ids(), Ok(()));
+        assert_eq!(tx.body.unspent_unique_asset_ids().len(), 1);
+
+        tx.body.outputs_mut()[1].features.unique_id = Some(vec![1, 2]);
+        assert_eq!(
+            tx.body.check_unique_ids(),
+            Err(TransactionError::DuplicateUniqueId("0102".to_string()))
+        );
+
+        // The same unique id under a different parent public key is a different token
+        tx.body.outputs_mut()[1].features.parent_public_key = Some(PublicKey::default());
+        assert_eq!(tx.body.check_unique_ids(), Ok(()));
+
+        tx.body.outputs_mut()[1].features.unique_id = Some(vec![]);
+        assert!(matches!(
+            tx.body.check_unique_ids(),
+            Err(TransactionError::InvalidUniqueId(_))
+        ));
+        tx.body.outputs_mut()[1].features.unique_id = Some(vec![0; MAX_UNIQUE_ID_BYTES + 1]);
+        assert!(matches!(
+            tx.body.check_unique_ids(),
+            Err(TransactionError::InvalidUniqueId(_))
+        ));
+    }
+
+    #[test]
+    fn check_asset_registrations() {
+        let asset_public_key = PublicKey::default();
+        let mut features = OutputFeatures::for_asset_registration(asset_public_key.clone());
+        assert!(features.check_unique_id().is_ok());
+        assert_eq!(
+            features.unique_asset_id(),
+            Some(UniqueAssetId {
+                parent_public_key: Some(asset_public_key),
+                unique_id: vec![],
+            })
+        );
+
+        features.unique_id = Some(vec![1]);
+        assert!(matches!(
+            features.check_unique_id(),
+            Err(TransactionError::InvalidUniqueId(_))
+        ));
+        features.unique_id = None;
+        features.parent_public_key = None;
+        assert!(matches!(
+            features.check_unique_id(),
+            Err(TransactionError::InvalidUniqueId(_))
+        ));
+    }
+
     #[test]
     fn inputs_not_malleable() {
         let (mut inputs, outputs) = helpers::create_unblinded_txos(5000.into(), 1, 1, 2, 15.into());
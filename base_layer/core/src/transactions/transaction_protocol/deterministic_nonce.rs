@@ -0,0 +1,88 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Deterministic nonces for the partial signatures of the sender and receiver, for signers that do not have a good
+//! source of entropy. See [tari_key_manager::deterministic_nonce] for the derivation.
+//!
+//! Each nonce is derived from the signer's secret key and a message that commits to everything the signer knows about
+//! the signature challenge. The receiver knows the whole challenge, since it replies to the sender's public nonce.
+//!
+//! The sender commits to its public nonce before the receiver's public nonce and excess are known, so the sender's
+//! nonce cannot commit to the whole challenge. Signing the same nonce for two different replies would reveal the
+//! sender's private excess. A sender using deterministic nonces must therefore:
+//! * use an offset that has never been used before, since the nonce is derived from the excess less the offset; and
+//! * sign at most once with a given [SenderTransactionProtocol](super::sender::SenderTransactionProtocol). A protocol
+//!   instance only accepts one reply, but a copy that has been stored before the reply arrived must be discarded once
+//!   it has been signed, even when finalizing fails.
+
+use crate::transactions::{
+    tari_amount::MicroTari,
+    transaction::{TransactionInput, TransactionOutput},
+    transaction_protocol::{sender::SingleRoundSenderData, TransactionMetadata},
+    types::{PrivateKey, PublicKey},
+};
+use sha2::Sha256;
+use tari_crypto::tari_utilities::ByteArray;
+use tari_key_manager::deterministic_nonce::derive_nonce;
+
+const SENDER_NONCE_DOMAIN: &[u8] = b"tari.transaction_protocol.sender_nonce";
+const RECEIVER_NONCE_DOMAIN: &[u8] = b"tari.transaction_protocol.receiver_nonce";
+
+/// Derives the sender's signing nonce from the sender's private excess and the transaction being built. See the
+/// [module documentation](self) for the conditions under which this nonce is safe to use.
+pub fn sender_nonce(
+    private_excess: &PrivateKey,
+    public_excess: &PublicKey,
+    metadata: &TransactionMetadata,
+    amounts: &[MicroTari],
+    inputs: &[TransactionInput],
+    outputs: &[TransactionOutput],
+) -> PrivateKey {
+    let mut message = SENDER_NONCE_DOMAIN.to_vec();
+    message.extend_from_slice(public_excess.as_bytes());
+    message.extend_from_slice(&u64::from(metadata.fee).to_le_bytes());
+    message.extend_from_slice(&metadata.lock_height.to_le_bytes());
+    for amount in amounts {
+        message.extend_from_slice(&u64::from(*amount).to_le_bytes());
+    }
+    for input in inputs {
+        message.extend_from_slice(input.commitment.as_bytes());
+    }
+    for output in outputs {
+        message.extend_from_slice(output.commitment.as_bytes());
+    }
+    derive_nonce::<Sha256>(private_excess, &message)
+}
+
+/// Derives the receiver's signing nonce from the receiver's spending key and the sender's message
+pub fn receiver_nonce(spending_key: &PrivateKey, sender_data: &SingleRoundSenderData) -> PrivateKey {
+    let mut message = RECEIVER_NONCE_DOMAIN.to_vec();
+    message.extend_from_slice(&sender_data.tx_id.to_le_bytes());
+    message.extend_from_slice(&u64::from(sender_data.amount).to_le_bytes());
+    message.extend_from_slice(sender_data.public_excess.as_bytes());
+    message.extend_from_slice(sender_data.public_nonce.as_bytes());
+    message.extend_from_slice(&u64::from(sender_data.metadata.fee).to_le_bytes());
+    message.extend_from_slice(&sender_data.metadata.lock_height.to_le_bytes());
+    message.extend_from_slice(sender_data.sender_offset_public_key.as_bytes());
+    message.extend_from_slice(sender_data.public_commitment_nonce.as_bytes());
+    derive_nonce::<Sha256>(spending_key, &message)
+}
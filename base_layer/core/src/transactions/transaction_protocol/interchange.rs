@@ -0,0 +1,184 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A stable, versioned encoding of partially signed transactions for exchange with third-party tooling such as
+//! hardware wallets and escrow services.
+//!
+//! The encoding is `magic (4 bytes) | version (1 byte) | protobuf body | checksum (4 bytes)`, where the body is a
+//! `PartiallySignedTransaction` protobuf message and the checksum is the first four bytes of the Blake256 hash of
+//! everything before it. [PartiallySignedTransaction::to_base58] wraps this in base58 for copying between tools.
+
+use crate::transactions::{
+    transaction::Transaction,
+    transaction_protocol::{
+        proto::protocol as proto,
+        recipient::RecipientSignedMessage,
+        sender::SingleRoundSenderData,
+    },
+    types::HashDigest,
+};
+use digest::Digest;
+use prost::Message;
+use std::convert::TryFrom;
+use thiserror::Error;
+
+/// Identifies the encoding, "TPST" (Tari Partially Signed Transaction)
+pub const INTERCHANGE_MAGIC: [u8; 4] = *b"TPST";
+/// The current version of the encoding
+pub const INTERCHANGE_VERSION: u8 = 1;
+const CHECKSUM_LENGTH: usize = 4;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum InterchangeError {
+    #[error("Invalid base58 encoding: {0}")]
+    InvalidBase58(String),
+    #[error("Encoded transaction is too short")]
+    TooShort,
+    #[error("Not a partially signed transaction")]
+    InvalidMagic,
+    #[error("Unsupported encoding version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Checksum does not match")]
+    ChecksumMismatch,
+    #[error("Failed to decode protobuf body: {0}")]
+    DecodeError(String),
+    #[error("Conversion error: {0}")]
+    ConversionError(String),
+}
+
+/// A transaction at one of the stages of the single-round transaction protocol
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartiallySignedTransaction {
+    /// The sender's initial message, to be signed by the recipient
+    SenderMessage(Box<SingleRoundSenderData>),
+    /// The recipient's signed reply, to be finalized by the sender
+    RecipientReply(Box<RecipientSignedMessage>),
+    /// A finalized transaction, ready to be broadcast
+    Finalized(Box<Transaction>),
+}
+
+impl PartiallySignedTransaction {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let body = proto::PartiallySignedTransaction::from(self.clone());
+        let mut buf = Vec::with_capacity(INTERCHANGE_MAGIC.len() + 1 + body.encoded_len() + CHECKSUM_LENGTH);
+        buf.extend_from_slice(&INTERCHANGE_MAGIC);
+        buf.push(INTERCHANGE_VERSION);
+        body.encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed so encoding cannot fail");
+        let checksum = checksum(&buf);
+        buf.extend_from_slice(&checksum);
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InterchangeError> {
+        let header_len = INTERCHANGE_MAGIC.len() + 1;
+        if bytes.len() < header_len + CHECKSUM_LENGTH {
+            return Err(InterchangeError::TooShort);
+        }
+        if bytes[..INTERCHANGE_MAGIC.len()] != INTERCHANGE_MAGIC {
+            return Err(InterchangeError::InvalidMagic);
+        }
+        let version = bytes[INTERCHANGE_MAGIC.len()];
+        if version != INTERCHANGE_VERSION {
+            return Err(InterchangeError::UnsupportedVersion(version));
+        }
+        let (payload, expected_checksum) = bytes.split_at(bytes.len() - CHECKSUM_LENGTH);
+        if checksum(payload) != expected_checksum {
+            return Err(InterchangeError::ChecksumMismatch);
+        }
+
+        let body = proto::PartiallySignedTransaction::decode(&payload[header_len..])
+            .map_err(|err| InterchangeError::DecodeError(err.to_string()))?;
+        Self::try_from(body).map_err(InterchangeError::ConversionError)
+    }
+
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.to_bytes()).into_string()
+    }
+
+    pub fn from_base58(s: &str) -> Result<Self, InterchangeError> {
+        let bytes = bs58::decode(s.trim())
+            .into_vec()
+            .map_err(|err| InterchangeError::InvalidBase58(err.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LENGTH] {
+    let hash = HashDigest::new().chain(payload).finalize();
+    let mut checksum = [0u8; CHECKSUM_LENGTH];
+    checksum.copy_from_slice(&hash[..CHECKSUM_LENGTH]);
+    checksum
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transactions::{helpers::create_tx, tari_amount::MicroTari};
+
+    #[test]
+    fn it_round_trips_each_stage() {
+        let sender_message = PartiallySignedTransaction::SenderMessage(Box::new(SingleRoundSenderData {
+            tx_id: 123,
+            amount: MicroTari(5000),
+            message: "for the pizza".to_string(),
+            ..Default::default()
+        }));
+        let decoded = PartiallySignedTransaction::from_base58(&sender_message.to_base58()).unwrap();
+        assert_eq!(decoded, sender_message);
+
+        let (tx, _, _) = create_tx(MicroTari(5000), MicroTari(15), 0, 1, 0, 2);
+        let finalized = PartiallySignedTransaction::Finalized(Box::new(tx));
+        let decoded = PartiallySignedTransaction::from_bytes(&finalized.to_bytes()).unwrap();
+        assert_eq!(decoded, finalized);
+    }
+
+    #[test]
+    fn it_rejects_invalid_encodings() {
+        let psbt = PartiallySignedTransaction::SenderMessage(Box::new(Default::default()));
+        let bytes = psbt.to_bytes();
+
+        let mut corrupted = bytes.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xff;
+        assert_eq!(
+            PartiallySignedTransaction::from_bytes(&corrupted).unwrap_err(),
+            InterchangeError::ChecksumMismatch
+        );
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[INTERCHANGE_MAGIC.len()] = INTERCHANGE_VERSION + 1;
+        assert_eq!(
+            PartiallySignedTransaction::from_bytes(&wrong_version).unwrap_err(),
+            InterchangeError::UnsupportedVersion(INTERCHANGE_VERSION + 1)
+        );
+
+        assert_eq!(
+            PartiallySignedTransaction::from_bytes(&bytes[1..]).unwrap_err(),
+            InterchangeError::InvalidMagic
+        );
+        assert!(matches!(
+            PartiallySignedTransaction::from_base58("0OIl").unwrap_err(),
+            InterchangeError::InvalidBase58(_)
+        ));
+    }
+}
@@ -80,6 +80,8 @@
 //!   end
 //! </div>
 
+pub mod deterministic_nonce;
+pub mod interchange;
 pub mod proto;
 pub mod recipient;
 pub mod sender;
@@ -22,6 +22,7 @@
 
 pub use crate::proto::transaction_protocol as protocol;
 
+pub mod partially_signed_transaction;
 pub mod recipient_signed_message;
 pub mod transaction_metadata;
 pub mod transaction_sender;
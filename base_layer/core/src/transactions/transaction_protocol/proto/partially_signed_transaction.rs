@@ -0,0 +1,58 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::protocol as proto;
+use crate::transactions::transaction_protocol::interchange::PartiallySignedTransaction;
+use proto::partially_signed_transaction::Stage as ProtoStage;
+use std::convert::{TryFrom, TryInto};
+
+impl TryFrom<proto::PartiallySignedTransaction> for PartiallySignedTransaction {
+    type Error = String;
+
+    fn try_from(value: proto::PartiallySignedTransaction) -> Result<Self, Self::Error> {
+        let stage = value
+            .stage
+            .ok_or_else(|| "PartiallySignedTransaction.stage not provided".to_string())?;
+
+        match stage {
+            ProtoStage::SenderMessage(data) => {
+                Ok(PartiallySignedTransaction::SenderMessage(Box::new(data.try_into()?)))
+            },
+            ProtoStage::RecipientReply(message) => Ok(PartiallySignedTransaction::RecipientReply(Box::new(
+                message.try_into()?,
+            ))),
+            ProtoStage::Finalized(tx) => Ok(PartiallySignedTransaction::Finalized(Box::new(tx.try_into()?))),
+        }
+    }
+}
+
+impl From<PartiallySignedTransaction> for proto::PartiallySignedTransaction {
+    fn from(value: PartiallySignedTransaction) -> Self {
+        let stage = match value {
+            PartiallySignedTransaction::SenderMessage(data) => ProtoStage::SenderMessage((*data).into()),
+            PartiallySignedTransaction::RecipientReply(message) => ProtoStage::RecipientReply((*message).into()),
+            PartiallySignedTransaction::Finalized(tx) => ProtoStage::Finalized((*tx).into()),
+        };
+
+        Self { stage: Some(stage) }
+    }
+}
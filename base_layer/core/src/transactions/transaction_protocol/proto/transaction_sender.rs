@@ -28,7 +28,7 @@ use std::convert::{TryFrom, TryInto};
 use tari_crypto::tari_utilities::ByteArray;
 
 // The generated _oneof_ enum
-use crate::transactions::types::PublicKey;
+use crate::transactions::{covenant::Covenant, types::PublicKey};
 use proto::transaction_sender_message::Message as ProtoTxnSenderMessage;
 use tari_crypto::script::TariScript;
 
@@ -117,6 +117,7 @@ impl TryFrom<proto::SingleRoundSenderData> for SingleRoundSenderData {
             script: TariScript::from_bytes(&data.script).map_err(|err| err.to_string())?,
             sender_offset_public_key,
             public_commitment_nonce,
+            covenant: Covenant::from_bytes(&data.covenant).map_err(|err| err.to_string())?,
         })
     }
 }
@@ -136,6 +137,7 @@ impl From<SingleRoundSenderData> for proto::SingleRoundSenderData {
             script: sender_data.script.as_bytes(),
             sender_offset_public_key: sender_data.sender_offset_public_key.to_vec(),
             public_commitment_nonce: sender_data.public_commitment_nonce.to_vec(),
+            covenant: sender_data.covenant.as_bytes(),
         }
     }
 }
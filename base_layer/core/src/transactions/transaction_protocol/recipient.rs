@@ -21,8 +21,10 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::transactions::{
+    covenant::Covenant,
     transaction::{OutputFeatures, TransactionOutput},
     transaction_protocol::{
+        deterministic_nonce,
         sender::{SingleRoundSenderData as SD, TransactionSenderMessage},
         single_receiver::SingleReceiverTransactionProtocol,
         RewindData,
@@ -30,8 +32,10 @@ use crate::transactions::{
     },
     types::{CryptoFactories, MessageHash, PrivateKey, PublicKey, Signature},
 };
+use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt};
+use tari_crypto::{keys::SecretKey, script::TariScript};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[allow(clippy::large_enum_variant)]
@@ -176,7 +180,32 @@ impl ReceiverTransactionProtocol {
         factories: &CryptoFactories,
         rewind_data: Option<&RewindData>,
     ) -> RecipientState {
-        let signer = SingleReceiverTransactionProtocol::create(data, nonce, key, features, factories, rewind_data);
+        let script = data.script.clone();
+        let covenant = data.covenant.clone();
+        Self::single_round_with_script(nonce, key, features, script, covenant, data, factories, rewind_data)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn single_round_with_script(
+        nonce: PrivateKey,
+        key: PrivateKey,
+        features: OutputFeatures,
+        script: TariScript,
+        covenant: Covenant,
+        data: &SD,
+        factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
+    ) -> RecipientState {
+        let signer = SingleReceiverTransactionProtocol::create_with_script(
+            data,
+            nonce,
+            key,
+            features,
+            script,
+            covenant,
+            factories,
+            rewind_data,
+        );
         match signer {
             Ok(signed_data) => RecipientState::Finalized(Box::new(signed_data)),
             Err(e) => RecipientState::Failed(e),
@@ -200,6 +229,102 @@ impl ReceiverTransactionProtocol {
     }
 }
 
+/// Builds a [ReceiverTransactionProtocol], allowing the recipient to customise the output it receives into.
+///
+/// By default the output uses the script, features and covenant proposed by the sender and a random nonce. The
+/// recipient can instead lock the funds with its own script (e.g. a multisig or time-locked script), choose its own
+/// output features and covenant and make the range proof rewindable.
+pub struct ReceiverTransactionProtocolBuilder {
+    sender_message: TransactionSenderMessage,
+    spending_key: PrivateKey,
+    nonce: Option<PrivateKey>,
+    deterministic_nonce: bool,
+    features: Option<OutputFeatures>,
+    script: Option<TariScript>,
+    covenant: Option<Covenant>,
+    rewind_data: Option<RewindData>,
+}
+
+impl ReceiverTransactionProtocolBuilder {
+    pub fn new(sender_message: TransactionSenderMessage, spending_key: PrivateKey) -> Self {
+        Self {
+            sender_message,
+            spending_key,
+            nonce: None,
+            deterministic_nonce: false,
+            features: None,
+            script: None,
+            covenant: None,
+            rewind_data: None,
+        }
+    }
+
+    /// Sets the private nonce used for the partial signature. If this is not called, a random nonce is used.
+    pub fn with_nonce(&mut self, nonce: PrivateKey) -> &mut Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Derives the private nonce for the partial signature from the spending key and the sender's message instead of
+    /// choosing it at random. Ignored if a nonce is set with [with_nonce](Self::with_nonce).
+    pub fn with_deterministic_nonce(&mut self) -> &mut Self {
+        self.deterministic_nonce = true;
+        self
+    }
+
+    /// Sets the features of the received output, instead of the features proposed by the sender
+    pub fn with_output_features(&mut self, features: OutputFeatures) -> &mut Self {
+        self.features = Some(features);
+        self
+    }
+
+    /// Locks the received output with this script, instead of the script proposed by the sender
+    pub fn with_script(&mut self, script: TariScript) -> &mut Self {
+        self.script = Some(script);
+        self
+    }
+
+    /// Sets the covenant of the received output, instead of the covenant proposed by the sender
+    pub fn with_output_covenant(&mut self, covenant: Covenant) -> &mut Self {
+        self.covenant = Some(covenant);
+        self
+    }
+
+    /// Makes the range proof of the received output rewindable with the given keys
+    pub fn with_rewind_data(&mut self, rewind_data: RewindData) -> &mut Self {
+        self.rewind_data = Some(rewind_data);
+        self
+    }
+
+    pub fn build(self, factories: &CryptoFactories) -> ReceiverTransactionProtocol {
+        let state = match self.sender_message {
+            TransactionSenderMessage::None => RecipientState::Failed(TransactionProtocolError::InvalidStateError),
+            TransactionSenderMessage::Single(data) => {
+                let nonce = match self.nonce {
+                    Some(nonce) => nonce,
+                    None if self.deterministic_nonce => deterministic_nonce::receiver_nonce(&self.spending_key, &data),
+                    None => PrivateKey::random(&mut OsRng),
+                };
+                let features = self.features.unwrap_or_else(|| data.features.clone());
+                let script = self.script.unwrap_or_else(|| data.script.clone());
+                let covenant = self.covenant.unwrap_or_else(|| data.covenant.clone());
+                ReceiverTransactionProtocol::single_round_with_script(
+                    nonce,
+                    self.spending_key,
+                    features,
+                    script,
+                    covenant,
+                    &data,
+                    factories,
+                    self.rewind_data.as_ref(),
+                )
+            },
+            TransactionSenderMessage::Multiple => ReceiverTransactionProtocol::multi_round(),
+        };
+        ReceiverTransactionProtocol { state }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -210,6 +335,7 @@ mod test {
             transaction::OutputFeatures,
             transaction_protocol::{
                 build_challenge,
+                recipient::ReceiverTransactionProtocolBuilder,
                 sender::{SingleRoundSenderData, TransactionSenderMessage},
                 RewindData,
                 TransactionMetadata,
@@ -222,6 +348,7 @@ mod test {
     use tari_crypto::{
         commitment::HomomorphicCommitmentFactory,
         keys::{PublicKey as PK, SecretKey as SecretKeyTrait},
+        script,
     };
 
     #[test]
@@ -246,6 +373,7 @@ mod test {
             script,
             sender_offset_public_key: p.sender_offset_public_key,
             public_commitment_nonce: p.sender_public_commitment_nonce,
+            covenant: Default::default(),
         };
         let sender_info = TransactionSenderMessage::Single(Box::new(msg.clone()));
         let pubkey = PublicKey::from_secret_key(&p.spend_key);
@@ -293,6 +421,7 @@ mod test {
             script,
             sender_offset_public_key: p.sender_offset_public_key,
             public_commitment_nonce: p.sender_public_commitment_nonce,
+            covenant: Default::default(),
         };
         let sender_info = TransactionSenderMessage::Single(Box::new(msg));
         let rewind_data = RewindData {
@@ -325,4 +454,90 @@ mod test {
         assert_eq!(&full_rewind_result.proof_message, message);
         assert_eq!(full_rewind_result.blinding_factor, p.spend_key);
     }
+
+    #[test]
+    fn single_round_recipient_with_custom_output() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let msg = SingleRoundSenderData {
+            tx_id: 15,
+            amount: MicroTari(500),
+            public_excess: PublicKey::from_secret_key(&p.spend_key),
+            public_nonce: PublicKey::from_secret_key(&p.change_spend_key),
+            metadata: TransactionMetadata {
+                fee: MicroTari(125),
+                lock_height: 0,
+            },
+            message: "".to_string(),
+            features: OutputFeatures::default(),
+            script: TariScript::default(),
+            sender_offset_public_key: p.sender_offset_public_key,
+            public_commitment_nonce: p.sender_public_commitment_nonce,
+            covenant: Default::default(),
+        };
+        let sender_info = TransactionSenderMessage::Single(Box::new(msg));
+        let script = script!(Drop Nop);
+        let features = OutputFeatures::with_maturity(100);
+
+        let mut builder = ReceiverTransactionProtocolBuilder::new(sender_info, p.spend_key.clone());
+        builder
+            .with_nonce(p.nonce.clone())
+            .with_script(script.clone())
+            .with_output_features(features.clone());
+        let receiver = builder.build(&factories);
+        assert!(receiver.is_finalized());
+        let data = receiver.get_signed_data().unwrap();
+        assert_eq!(data.output.script, script);
+        assert_eq!(data.output.features, features);
+        assert!(factories
+            .commitment
+            .open_value(&p.spend_key, 500, &data.output.commitment));
+    }
+
+    #[test]
+    fn single_round_recipient_with_deterministic_nonce() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let msg = SingleRoundSenderData {
+            tx_id: 15,
+            amount: MicroTari(500),
+            public_excess: PublicKey::from_secret_key(&p.spend_key),
+            public_nonce: PublicKey::from_secret_key(&p.change_spend_key),
+            metadata: TransactionMetadata {
+                fee: MicroTari(125),
+                lock_height: 0,
+            },
+            message: "".to_string(),
+            features: OutputFeatures::default(),
+            script: TariScript::default(),
+            sender_offset_public_key: p.sender_offset_public_key.clone(),
+            public_commitment_nonce: p.sender_public_commitment_nonce.clone(),
+            covenant: Default::default(),
+        };
+        let receive = |msg: SingleRoundSenderData| {
+            let mut builder = ReceiverTransactionProtocolBuilder::new(
+                TransactionSenderMessage::Single(Box::new(msg)),
+                p.spend_key.clone(),
+            );
+            builder.with_deterministic_nonce();
+            let receiver = builder.build(&factories);
+            assert!(receiver.is_finalized());
+            receiver.get_signed_data().unwrap().partial_signature.clone()
+        };
+
+        let signature = receive(msg.clone());
+        assert_eq!(receive(msg.clone()), signature);
+        let e = build_challenge(&(&msg.public_nonce + signature.get_public_nonce()), &msg.metadata);
+        assert!(signature.verify_challenge(&PublicKey::from_secret_key(&p.spend_key), &e));
+
+        let other_msg = SingleRoundSenderData {
+            public_nonce: PublicKey::from_secret_key(&p.nonce),
+            ..msg
+        };
+        assert_ne!(
+            receive(other_msg).get_public_nonce(),
+            signature.get_public_nonce(),
+            "A different sender nonce must result in a different receiver nonce"
+        );
+    }
 }
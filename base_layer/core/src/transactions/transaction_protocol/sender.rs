@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::transactions::{
+    covenant::Covenant,
     tari_amount::*,
     transaction::{
         KernelBuilder,
@@ -69,6 +70,8 @@ pub(super) struct RawTransactionInfo {
     pub amounts: Vec<MicroTari>,
     pub recipient_scripts: Vec<TariScript>,
     pub recipient_output_features: Vec<OutputFeatures>,
+    #[serde(default)]
+    pub recipient_covenants: Vec<Covenant>,
     pub recipient_sender_offset_private_keys: Vec<PrivateKey>,
     // The sender's portion of the public commitment nonce
     pub private_commitment_nonces: Vec<PrivateKey>,
@@ -118,6 +121,9 @@ pub struct SingleRoundSenderData {
     pub sender_offset_public_key: PublicKey,
     /// The sender's portion of the public commitment nonce
     pub public_commitment_nonce: PublicKey,
+    /// The covenant that the recipient's output must have
+    #[serde(default)]
+    pub covenant: Covenant,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -367,6 +373,7 @@ impl SenderTransactionProtocol {
                     script: recipient_script,
                     sender_offset_public_key: PublicKey::from_secret_key(recipient_script_offset_secret_key),
                     public_commitment_nonce: PublicKey::from_secret_key(&private_commitment_nonce),
+                    covenant: info.recipient_covenants.first().cloned().unwrap_or_default(),
                 })
             },
             _ => Err(TPE::InvalidStateError),
@@ -386,6 +393,14 @@ impl SenderTransactionProtocol {
                         "Recipient output range proof failed to verify".into(),
                     ));
                 }
+                let features = info.recipient_output_features.first().ok_or_else(|| {
+                    TPE::IncompleteStateError("The recipient output features should be available".to_string())
+                })?;
+                if rec.output.features.unique_asset_id() != features.unique_asset_id() {
+                    return Err(TPE::ValidationError(
+                        "Recipient output does not carry the unique id of the transaction".into(),
+                    ));
+                }
                 // Consolidate transaction info
                 info.outputs.push(rec.output.clone());
 
@@ -706,6 +721,7 @@ impl fmt::Display for SenderState {
 #[cfg(test)]
 mod test {
     use crate::transactions::{
+        covenant::Covenant,
         fee::Fee,
         helpers::{create_test_input, create_unblinded_output, TestParams},
         tari_amount::*,
@@ -763,6 +779,7 @@ mod test {
             &spending_key,
             &script,
             &output_features,
+            &Covenant::default(),
             &sender_offset_public_key,
             &sender_public_commitment_nonce,
         )
@@ -775,6 +792,7 @@ mod test {
             script,
             sender_offset_public_key,
             partial_metadata_signature.clone(),
+            Covenant::default(),
         );
         assert!(!output.verify_metadata_signature().is_ok());
         assert!(partial_metadata_signature.verify_challenge(
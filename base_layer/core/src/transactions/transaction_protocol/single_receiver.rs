@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::transactions::{
+    covenant::Covenant,
     transaction::{OutputFeatures, TransactionOutput},
     transaction_protocol::{
         build_challenge,
@@ -35,6 +36,7 @@ use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
     keys::PublicKey as PK,
     range_proof::{RangeProofError, RangeProofService as RPS},
+    script::TariScript,
     tari_utilities::byte_array::ByteArray,
 };
 
@@ -54,12 +56,46 @@ impl SingleReceiverTransactionProtocol {
         features: OutputFeatures,
         factories: &CryptoFactories,
         rewind_data: Option<&RewindData>,
+    ) -> Result<RD, TPE> {
+        let script = sender_info.script.clone();
+        let covenant = sender_info.covenant.clone();
+        Self::create_with_script(
+            sender_info,
+            nonce,
+            spending_key,
+            features,
+            script,
+            covenant,
+            factories,
+            rewind_data,
+        )
+    }
+
+    /// As for `create`, but the output is locked with the given script and covenant instead of those proposed by the
+    /// sender. The parent public key and unique id of the output are always those proposed by the sender.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_with_script(
+        sender_info: &SD,
+        nonce: SK,
+        spending_key: SK,
+        features: OutputFeatures,
+        script: TariScript,
+        covenant: Covenant,
+        factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
     ) -> Result<RD, TPE> {
         SingleReceiverTransactionProtocol::validate_sender_data(sender_info)?;
+        let features = OutputFeatures {
+            parent_public_key: sender_info.features.parent_public_key.clone(),
+            unique_id: sender_info.features.unique_id.clone(),
+            ..features
+        };
         let output = SingleReceiverTransactionProtocol::build_output(
             sender_info,
             &spending_key,
             features,
+            script,
+            covenant,
             factories,
             rewind_data,
         )?;
@@ -88,6 +124,8 @@ impl SingleReceiverTransactionProtocol {
         sender_info: &SD,
         spending_key: &SK,
         features: OutputFeatures,
+        script: TariScript,
+        covenant: Covenant,
         factories: &CryptoFactories,
         rewind_data: Option<&RewindData>,
     ) -> Result<TransactionOutput, TPE> {
@@ -109,11 +147,14 @@ impl SingleReceiverTransactionProtocol {
                 .construct_proof(&spending_key, sender_info.amount.into())?
         };
 
+        // The metadata signature commits to the script, features and covenant chosen by the receiver. The sender
+        // completes it using the values in the returned output.
         let partial_metadata_signature = TransactionOutput::create_partial_metadata_signature(
             &sender_info.amount,
             &spending_key.clone(),
-            &sender_info.script,
-            &sender_info.features,
+            &script,
+            &features,
+            &covenant,
             &sender_info.sender_offset_public_key,
             &sender_info.public_commitment_nonce,
         )?;
@@ -123,9 +164,10 @@ impl SingleReceiverTransactionProtocol {
             commitment,
             RangeProof::from_bytes(&proof)
                 .map_err(|_| TPE::RangeProofError(RangeProofError::ProofConstructionError))?,
-            sender_info.script.clone(),
+            script,
             sender_info.sender_offset_public_key.clone(),
             partial_metadata_signature,
+            covenant,
         );
         Ok(output)
     }
@@ -199,6 +241,7 @@ mod test {
             script,
             sender_offset_public_key,
             public_commitment_nonce,
+            covenant: Default::default(),
         };
         let prot = SingleReceiverTransactionProtocol::create(&info, r, k.clone(), of, &factories, None).unwrap();
         assert_eq!(prot.tx_id, 500, "tx_id is incorrect");
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::transactions::{
+    covenant::Covenant,
     fee::Fee,
     tari_amount::*,
     transaction::{
@@ -33,19 +34,21 @@ use crate::transactions::{
         MINIMUM_TRANSACTION_FEE,
     },
     transaction_protocol::{
+        deterministic_nonce,
         recipient::RecipientInfo,
         sender::{calculate_tx_id, RawTransactionInfo, SenderState, SenderTransactionProtocol},
         RewindData,
         TransactionMetadata,
     },
     types::{BlindingFactor, CryptoFactories, PrivateKey, PublicKey},
+    weight::TransactionWeight,
 };
 use digest::Digest;
 use log::*;
 use rand::rngs::OsRng;
 use std::{
     cmp::max,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{Debug, Error, Formatter},
 };
 use tari_crypto::{
@@ -85,17 +88,36 @@ pub struct SenderTransactionInitializer {
     offset: Option<BlindingFactor>,
     excess_blinding_factor: BlindingFactor,
     private_nonce: Option<PrivateKey>,
+    deterministic_nonce: bool,
     message: Option<String>,
     prevent_fee_gt_amount: bool,
     recipient_output_features: FixedSet<OutputFeatures>,
     recipient_scripts: FixedSet<TariScript>,
+    recipient_covenants: Vec<Covenant>,
     recipient_sender_offset_private_keys: FixedSet<PrivateKey>,
     private_commitment_nonces: FixedSet<PrivateKey>,
+    transaction_weight: TransactionWeight,
+    validate_input_scripts: bool,
 }
 
 pub struct BuildError {
     pub builder: SenderTransactionInitializer,
     pub message: String,
+    /// Set if the build failed because an input's script would not be accepted by consensus
+    pub script_error: Option<InputScriptError>,
+}
+
+/// Reasons an input's script failed the pre-build validation enabled with
+/// [with_input_script_validation](SenderTransactionInitializer::with_input_script_validation). `index` is the position
+/// of the input in the order it was added to the builder.
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum InputScriptError {
+    #[error("The script of input {index} failed to execute: {reason}")]
+    ExecutionFailed { index: usize, reason: String },
+    #[error("The script of input {index} did not return the public key of the input's script private key")]
+    ScriptKeyMismatch { index: usize },
+    #[error("The script signature of input {index} is invalid: {reason}")]
+    InvalidScriptSignature { index: usize, reason: String },
 }
 
 impl Debug for BuildError {
@@ -123,13 +145,17 @@ impl SenderTransactionInitializer {
             rewind_data: None,
             offset: None,
             private_nonce: None,
+            deterministic_nonce: false,
             excess_blinding_factor: BlindingFactor::default(),
             message: None,
             prevent_fee_gt_amount: true,
             recipient_output_features: FixedSet::new(num_recipients),
             recipient_scripts: FixedSet::new(num_recipients),
+            recipient_covenants: vec![Covenant::default(); num_recipients],
             recipient_sender_offset_private_keys: FixedSet::new(num_recipients),
             private_commitment_nonces: FixedSet::new(num_recipients),
+            transaction_weight: TransactionWeight::latest(),
+            validate_input_scripts: false,
         }
     }
 
@@ -166,6 +192,30 @@ impl SenderTransactionInitializer {
         self
     }
 
+    /// Set the covenant of the ith recipient's output, which constrains how the recipient may spend it. Recipient
+    /// outputs have an empty covenant unless this is called. This method will silently fail if `receiver_index` >=
+    /// num_receivers.
+    pub fn with_output_covenant(&mut self, receiver_index: usize, covenant: Covenant) -> &mut Self {
+        if let Some(c) = self.recipient_covenants.get_mut(receiver_index) {
+            *c = covenant;
+        }
+        self
+    }
+
+    /// Sets the weight model used to calculate the fee. If this is not called, the latest model is used.
+    pub fn with_transaction_weight(&mut self, weighting: TransactionWeight) -> &mut Self {
+        self.transaction_weight = weighting;
+        self
+    }
+
+    /// When enabled, `build()` executes each input's script against its input data and checks that the resulting key
+    /// matches the input's script private key before building the transaction. This catches inputs that consensus
+    /// would reject before the transaction is sent anywhere. Disabled by default.
+    pub fn with_input_script_validation(&mut self, enabled: bool) -> &mut Self {
+        self.validate_input_scripts = enabled;
+        self
+    }
+
     /// Sets the minimum block height that this transaction will be mined.
     pub fn with_lock_height(&mut self, lock_height: u64) -> &mut Self {
         self.lock_height = Some(lock_height);
@@ -199,6 +249,7 @@ impl SenderTransactionInitializer {
         let e = TransactionOutput::build_metadata_signature_challenge(
             &output.script,
             &output.features,
+            &output.covenant,
             &output.sender_offset_public_key,
             &output.metadata_signature.public_nonce(),
             &commitment,
@@ -251,6 +302,14 @@ impl SenderTransactionInitializer {
         self
     }
 
+    /// Derive the private nonce for the sender's partial signature from the sender's excess and the transaction data
+    /// instead of providing it with [with_private_nonce](Self::with_private_nonce). See
+    /// [deterministic_nonce](super::deterministic_nonce) for when this is safe to use.
+    pub fn with_deterministic_nonce(&mut self) -> &mut Self {
+        self.deterministic_nonce = true;
+        self
+    }
+
     /// Provide a text message for receiver
     pub fn with_message(&mut self, message: String) -> &mut Self {
         self.message = Some(message);
@@ -274,8 +333,22 @@ impl SenderTransactionInitializer {
         let total_to_self = self.sender_custom_outputs.iter().map(|o| o.value).sum::<MicroTari>();
         let total_amount = self.amounts.sum().ok_or("Not all amounts have been provided")?;
         let fee_per_gram = self.fee_per_gram.ok_or("Fee per gram was not provided")?;
-        let fee_without_change = Fee::calculate(fee_per_gram, 1, num_inputs, num_outputs);
-        let fee_with_change = Fee::calculate(fee_per_gram, 1, num_inputs, num_outputs + 1);
+        let weighting = self.transaction_weight;
+        let metadata_weight = self.outputs_metadata_weight();
+        let change_metadata_weight = match self.change_script.as_ref() {
+            Some(script) => {
+                weighting.output_metadata_weight_of(&OutputFeatures::default(), script, &Covenant::default())
+            },
+            None => 0,
+        };
+        let fee_without_change = Fee::calculate_for_weight(
+            fee_per_gram,
+            weighting.calculate(1, num_inputs, num_outputs, metadata_weight),
+        );
+        let fee_with_change = Fee::calculate_for_weight(
+            fee_per_gram,
+            weighting.calculate(1, num_inputs, num_outputs + 1, metadata_weight + change_metadata_weight),
+        );
         let extra_fee = fee_with_change - fee_without_change;
         // Subtract with a check on going negative
         let change_amount = total_being_spent.checked_sub(total_to_self + total_amount + fee_without_change);
@@ -308,6 +381,7 @@ impl SenderTransactionInitializer {
                             &change_key.clone(),
                             &script,
                             &output_features,
+                            &Covenant::default(),
                             &change_sender_offset_private_key,
                         )
                         .map_err(|e| e.to_string())?;
@@ -326,6 +400,7 @@ impl SenderTransactionInitializer {
                                 .clone(),
                             PublicKey::from_secret_key(&change_sender_offset_private_key),
                             metadata_signature,
+                            Covenant::default(),
                         );
                         Ok((fee_with_change, v, Some(change_unblinded_output)))
                     },
@@ -334,6 +409,29 @@ impl SenderTransactionInitializer {
         }
     }
 
+    /// The additional weight of the recipient and custom outputs' features, scripts and covenants
+    fn outputs_metadata_weight(&self) -> u64 {
+        let weighting = self.transaction_weight;
+        let recipients = self
+            .recipient_covenants
+            .iter()
+            .enumerate()
+            .map(|(i, covenant)| {
+                let default_features = OutputFeatures::default();
+                let features = self.recipient_output_features.get_item(i).unwrap_or(&default_features);
+                let default_script = TariScript::default();
+                let script = self.recipient_scripts.get_item(i).unwrap_or(&default_script);
+                weighting.output_metadata_weight_of(features, script, covenant)
+            })
+            .sum::<u64>();
+        let custom_outputs = self
+            .sender_custom_outputs
+            .iter()
+            .map(|o| weighting.output_metadata_weight_of(&o.features, &o.script, &o.covenant))
+            .sum::<u64>();
+        recipients + custom_outputs
+    }
+
     fn check_value<T>(name: &str, val: &Option<T>, vec: &mut Vec<String>) {
         if val.is_none() {
             vec.push(name.to_string());
@@ -344,9 +442,64 @@ impl SenderTransactionInitializer {
         Err(BuildError {
             builder: self,
             message: msg.to_string(),
+            script_error: None,
+        })
+    }
+
+    fn script_err<T>(self, err: InputScriptError) -> Result<T, BuildError> {
+        Err(BuildError {
+            builder: self,
+            message: err.to_string(),
+            script_error: Some(err),
         })
     }
 
+    /// Check that no two outputs carry the same unique id, and that the unique id of each input is carried on by one of
+    /// the recipient or custom outputs. The change output never carries a unique id.
+    fn check_unique_ids(&self) -> Result<(), String> {
+        let features = (0..self.num_recipients)
+            .filter_map(|i| self.recipient_output_features.get_item(i))
+            .chain(self.sender_custom_outputs.iter().map(|o| &o.features));
+        let mut carried = HashSet::new();
+        for f in features {
+            f.check_unique_id().map_err(|e| e.to_string())?;
+            if let Some(id) = f.unique_asset_id() {
+                if !carried.insert(id.clone()) {
+                    return Err(format!("More than one output carries the unique id {}", id));
+                }
+            }
+        }
+        for input in &self.inputs {
+            if let Some(id) = input.features.unique_asset_id() {
+                if !carried.contains(&id) {
+                    return Err(format!("The unique id {} of an input is not carried by any output", id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run each input's script and check the result against the script private key that will be used in the script
+    /// offset, as well as the input's script signature
+    fn check_input_scripts(&self, factory: &PedersenCommitmentFactory) -> Result<(), InputScriptError> {
+        for (index, (input, unblinded)) in self.inputs.iter().zip(&self.unblinded_inputs).enumerate() {
+            let script_key = input.run_script().map_err(|e| InputScriptError::ExecutionFailed {
+                index,
+                reason: e.to_string(),
+            })?;
+            if script_key != PublicKey::from_secret_key(&unblinded.script_private_key) {
+                return Err(InputScriptError::ScriptKeyMismatch { index });
+            }
+            input.validate_script_signature(&script_key, factory).map_err(|e| {
+                InputScriptError::InvalidScriptSignature {
+                    index,
+                    reason: e.to_string(),
+                }
+            })?;
+        }
+        Ok(())
+    }
+
     fn calculate_amount_to_others(&self) -> MicroTari {
         self.amounts.clone().into_vec().iter().sum()
     }
@@ -362,13 +515,18 @@ impl SenderTransactionInitializer {
         Self::check_value("Missing Lock Height", &self.lock_height, &mut message);
         Self::check_value("Missing Fee per gram", &self.fee_per_gram, &mut message);
         Self::check_value("Missing Offset", &self.offset, &mut message);
-        Self::check_value("Change script", &self.private_nonce, &mut message);
-        Self::check_value("Change input data", &self.private_nonce, &mut message);
-        Self::check_value("Change script private key", &self.private_nonce, &mut message);
+        if !self.deterministic_nonce {
+            Self::check_value("Change script", &self.private_nonce, &mut message);
+            Self::check_value("Change input data", &self.private_nonce, &mut message);
+            Self::check_value("Change script private key", &self.private_nonce, &mut message);
+        }
 
         if !message.is_empty() {
             return self.build_err(&message.join(","));
         }
+        if self.deterministic_nonce && self.private_nonce.is_some() {
+            return self.build_err("A private nonce cannot be provided when the nonce is derived deterministically");
+        }
         if !self.amounts.is_full() {
             let size = self.amounts.size();
             return self.build_err(&*format!("Missing all {} amounts", size));
@@ -392,6 +550,14 @@ impl SenderTransactionInitializer {
         if self.inputs.len() > MAX_TRANSACTION_INPUTS {
             return self.build_err("Too many inputs in transaction");
         }
+        if self.validate_input_scripts {
+            if let Err(e) = self.check_input_scripts(&factories.commitment) {
+                return self.script_err(e);
+            }
+        }
+        if let Err(e) = self.check_unique_ids() {
+            return self.build_err(&e);
+        }
         // Calculate the fee based on whether we need to add a residual change output or not
         let (total_fee, change, change_output) = match self.add_change_if_required() {
             Ok((fee, change, output)) => (fee, change, output),
@@ -475,12 +641,26 @@ impl SenderTransactionInitializer {
             gamma = gamma - sender_offset_private_key.clone();
         }
 
-        let nonce = self.private_nonce.clone().unwrap();
-        let public_nonce = PublicKey::from_secret_key(&nonce);
         let offset = self.offset.clone().unwrap();
         let excess_blinding_factor = self.excess_blinding_factor.clone();
         let offset_blinding_factor = &excess_blinding_factor - &offset;
         let excess = PublicKey::from_secret_key(&offset_blinding_factor);
+        let metadata = TransactionMetadata {
+            fee: total_fee,
+            lock_height: self.lock_height.unwrap(),
+        };
+        let nonce = match self.private_nonce.clone() {
+            Some(nonce) => nonce,
+            None => deterministic_nonce::sender_nonce(
+                &offset_blinding_factor,
+                &excess,
+                &metadata,
+                &self.amounts.clone().into_vec(),
+                &self.inputs,
+                &outputs,
+            ),
+        };
+        let public_nonce = PublicKey::from_secret_key(&nonce);
         let amount_to_self = self
             .sender_custom_outputs
             .iter()
@@ -527,6 +707,7 @@ impl SenderTransactionInitializer {
             amounts: self.amounts.into_vec(),
             recipient_output_features: self.recipient_output_features.into_vec(),
             recipient_scripts: self.recipient_scripts.into_vec(),
+            recipient_covenants: self.recipient_covenants,
             recipient_sender_offset_private_keys: self.recipient_sender_offset_private_keys.into_vec(),
             private_commitment_nonces: self.private_commitment_nonces.into_vec(),
             change,
@@ -535,10 +716,7 @@ impl SenderTransactionInitializer {
             change_sender_offset_public_key: self
                 .change_sender_offset_private_key
                 .map(|pk| PublicKey::from_secret_key(&pk)),
-            metadata: TransactionMetadata {
-                fee: total_fee,
-                lock_height: self.lock_height.unwrap(),
-            },
+            metadata,
             inputs: self.inputs,
             outputs,
             offset,
@@ -565,25 +743,24 @@ impl SenderTransactionInitializer {
 
 #[cfg(test)]
 mod test {
-    use crate::{
-        consensus::{KERNEL_WEIGHT, WEIGHT_PER_INPUT, WEIGHT_PER_OUTPUT},
-        transactions::{
-            fee::Fee,
-            helpers::{create_test_input, create_unblinded_output, TestParams, UtxoTestParams},
-            tari_amount::*,
-            transaction::{OutputFeatures, MAX_TRANSACTION_INPUTS},
-            transaction_protocol::{
-                sender::SenderState,
-                transaction_initializer::SenderTransactionInitializer,
-                TransactionProtocolError,
-            },
-            types::{CryptoFactories, PrivateKey},
+    use crate::transactions::{
+        fee::Fee,
+        helpers::{create_test_input, create_unblinded_output, TestParams, UtxoTestParams},
+        tari_amount::*,
+        transaction::{OutputFeatures, MAX_TRANSACTION_INPUTS},
+        transaction_protocol::{
+            sender::SenderState,
+            transaction_initializer::{InputScriptError, SenderTransactionInitializer},
+            TransactionProtocolError,
         },
+        types::{CryptoFactories, PrivateKey, PublicKey},
+        weight::TransactionWeight,
     };
     use rand::rngs::OsRng;
     use tari_crypto::{
         common::Blake256,
-        keys::SecretKey,
+        inputs,
+        keys::{PublicKey as PublicKeyTrait, SecretKey},
         script,
         script::{ExecutionStack, TariScript},
     };
@@ -704,7 +881,9 @@ mod test {
         let factories = CryptoFactories::default();
         let p = TestParams::new();
         let (utxo, input) = create_test_input(MicroTari(500), 0, &factories.commitment);
-        let expected_fee = MicroTari::from((KERNEL_WEIGHT + WEIGHT_PER_INPUT + 1 * WEIGHT_PER_OUTPUT) * 20);
+        let weights = TransactionWeight::latest().params();
+        let expected_fee =
+            MicroTari::from((weights.kernel_weight + weights.input_weight + 1 * weights.output_weight) * 20);
         // fee == 340, output = 80
 
         // Pay out so that I should get change, but not enough to pay for the output
@@ -739,6 +918,38 @@ mod test {
         }
     }
 
+    #[test]
+    fn unique_ids_must_be_carried_once() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (mut utxo, input) = create_test_input(MicroTari(5000), 0, &factories.commitment);
+        utxo.features.unique_id = Some(vec![1]);
+        let token_features = OutputFeatures::with_unique_id(None, vec![1]);
+        let build = |outputs: Vec<OutputFeatures>| {
+            let mut builder = SenderTransactionInitializer::new(0);
+            builder
+                .with_lock_height(0)
+                .with_offset(p.offset.clone())
+                .with_private_nonce(p.nonce.clone())
+                .with_input(utxo.clone(), input.clone())
+                .with_fee_per_gram(MicroTari(1))
+                .with_prevent_fee_gt_amount(false);
+            for features in outputs {
+                let output = create_unblinded_output(TariScript::default(), features, p.clone(), MicroTari(1000));
+                builder
+                    .with_output(output, p.sender_offset_private_key.clone())
+                    .unwrap();
+            }
+            builder.build::<Blake256>(&factories)
+        };
+
+        let err = build(vec![OutputFeatures::default()]).unwrap_err();
+        assert_eq!(err.message, "The unique id 01 of an input is not carried by any output");
+        let err = build(vec![token_features.clone(), token_features.clone()]).unwrap_err();
+        assert_eq!(err.message, "More than one output carries the unique id 01");
+        assert!(build(vec![token_features, OutputFeatures::default()]).is_ok());
+    }
+
     #[test]
     fn too_many_inputs() {
         // Create some inputs
@@ -929,6 +1140,112 @@ mod test {
         }
     }
 
+    #[test]
+    fn deterministic_nonce() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (utxo, input) = create_test_input(MicroTari(5000), 0, &factories.commitment);
+        let recipient_offset_key = PrivateKey::random(&mut OsRng);
+        let recipient_commitment_nonce = PrivateKey::random(&mut OsRng);
+        let builder = |offset: PrivateKey| {
+            let mut builder = SenderTransactionInitializer::new(1);
+            builder
+                .with_lock_height(0)
+                .with_offset(offset)
+                .with_deterministic_nonce()
+                .with_input(utxo.clone(), input.clone())
+                .with_amount(0, MicroTari(2500))
+                .with_change_secret(p.change_spend_key.clone())
+                .with_fee_per_gram(MicroTari(20))
+                .with_recipient_data(
+                    0,
+                    script!(Nop),
+                    recipient_offset_key.clone(),
+                    Default::default(),
+                    recipient_commitment_nonce.clone(),
+                )
+                .with_change_script(script!(Nop), ExecutionStack::default(), PrivateKey::default());
+            builder
+        };
+
+        let tx_id = builder(p.offset.clone())
+            .build::<Blake256>(&factories)
+            .unwrap()
+            .get_tx_id()
+            .unwrap();
+        let same_tx_id = builder(p.offset.clone())
+            .build::<Blake256>(&factories)
+            .unwrap()
+            .get_tx_id()
+            .unwrap();
+        assert_eq!(tx_id, same_tx_id, "The nonce, and so the tx_id, should be the same");
+        let other_tx_id = builder(PrivateKey::random(&mut OsRng))
+            .build::<Blake256>(&factories)
+            .unwrap()
+            .get_tx_id()
+            .unwrap();
+        assert_ne!(
+            tx_id, other_tx_id,
+            "A different excess should result in a different nonce"
+        );
+
+        let mut with_nonce = builder(p.offset.clone());
+        with_nonce.with_private_nonce(p.nonce.clone());
+        let err = with_nonce.build::<Blake256>(&factories).unwrap_err();
+        assert_eq!(
+            err.message,
+            "A private nonce cannot be provided when the nonce is derived deterministically"
+        );
+    }
+
+    #[test]
+    fn input_script_validation() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let fee = Fee::calculate(MicroTari(20), 1, 1, 1);
+        let output = create_unblinded_output(
+            script!(Nop),
+            OutputFeatures::default(),
+            p.clone(),
+            MicroTari(5_000) - fee,
+        );
+        let build = |input_data: Option<ExecutionStack>| {
+            let (utxo, input) = TestParams::new().create_input(UtxoTestParams {
+                value: MicroTari(5_000),
+                input_data,
+                ..Default::default()
+            });
+            let mut builder = SenderTransactionInitializer::new(0);
+            builder
+                .with_lock_height(0)
+                .with_offset(p.offset.clone())
+                .with_private_nonce(p.nonce.clone())
+                .with_output(output.clone(), p.sender_offset_private_key.clone())
+                .unwrap()
+                .with_input(utxo, input)
+                .with_fee_per_gram(MicroTari(20))
+                .with_prevent_fee_gt_amount(false)
+                .with_input_script_validation(true);
+            builder.build::<Blake256>(&factories)
+        };
+
+        assert!(build(None).is_ok());
+
+        // The script leaves a key on the stack that does not belong to the input's script private key
+        let err = build(Some(inputs!(PublicKey::from_secret_key(&PrivateKey::random(
+            &mut OsRng
+        )))))
+        .unwrap_err();
+        assert_eq!(err.script_error, Some(InputScriptError::ScriptKeyMismatch { index: 0 }));
+
+        // The script fails to execute because the stack is empty
+        let err = build(Some(ExecutionStack::default())).unwrap_err();
+        assert!(matches!(
+            err.script_error,
+            Some(InputScriptError::ExecutionFailed { index: 0, .. })
+        ));
+    }
+
     #[test]
     fn fail_range_proof() {
         // Create some inputs
@@ -0,0 +1,167 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::transactions::{
+    aggregated_body::AggregateBody,
+    covenant::Covenant,
+    transaction::{OutputFeatures, TransactionOutput},
+};
+use tari_crypto::script::TariScript;
+
+/// The parameters of a transaction weight model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeightParams {
+    /// Weight of each kernel; covers the kernel and part of the header
+    pub kernel_weight: u64,
+    /// Weight of each input
+    pub input_weight: u64,
+    /// Weight of each output, including `output_metadata_allowance` bytes of features, script and covenant
+    pub output_weight: u64,
+    /// The number of output features, script and covenant bytes covered by `output_weight`. Only used if
+    /// `output_metadata_bytes_per_gram` is set.
+    pub output_metadata_allowance: usize,
+    /// The number of output features, script and covenant bytes, beyond the allowance, that add one gram of weight.
+    /// If `None`, the size of the metadata is not taken into account.
+    pub output_metadata_bytes_per_gram: Option<usize>,
+}
+
+/// The consensus-versioned model used to calculate the weight, and hence the fee, of a transaction. The model in use
+/// at a given height is given by `ConsensusConstants::transaction_weight`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionWeight {
+    /// Fixed weights per kernel, input and output
+    V1,
+    /// As for V1, but outputs with features and scripts larger than a standard output pay per byte for the excess.
+    /// V1 and V2 agree on the weight of transactions that only contain standard outputs.
+    V2,
+}
+
+impl TransactionWeight {
+    /// The most recent weight model
+    pub const fn latest() -> Self {
+        TransactionWeight::V2
+    }
+
+    pub const fn params(&self) -> WeightParams {
+        match self {
+            TransactionWeight::V1 => WeightParams {
+                kernel_weight: 3,
+                input_weight: 1,
+                output_weight: 13,
+                output_metadata_allowance: 0,
+                output_metadata_bytes_per_gram: None,
+            },
+            TransactionWeight::V2 => WeightParams {
+                kernel_weight: 3,
+                input_weight: 1,
+                output_weight: 13,
+                output_metadata_allowance: 64,
+                output_metadata_bytes_per_gram: Some(16),
+            },
+        }
+    }
+
+    /// Calculates the weight of a transaction from the number of kernels, inputs and outputs, plus the additional
+    /// weight of the outputs' metadata (see `output_metadata_weight`).
+    pub fn calculate(
+        &self,
+        num_kernels: usize,
+        num_inputs: usize,
+        num_outputs: usize,
+        output_metadata_weight: u64,
+    ) -> u64 {
+        let params = self.params();
+        params.kernel_weight * num_kernels as u64 +
+            params.input_weight * num_inputs as u64 +
+            params.output_weight * num_outputs as u64 +
+            output_metadata_weight
+    }
+
+    /// The additional weight of an output whose features and script are `metadata_size` bytes when serialized
+    pub fn output_metadata_weight(&self, metadata_size: usize) -> u64 {
+        match self.params().output_metadata_bytes_per_gram {
+            Some(bytes_per_gram) => {
+                let excess = metadata_size.saturating_sub(self.params().output_metadata_allowance);
+                ((excess + bytes_per_gram - 1) / bytes_per_gram) as u64
+            },
+            None => 0,
+        }
+    }
+
+    /// The additional weight of an output with the given features, script and covenant
+    pub fn output_metadata_weight_of(
+        &self,
+        features: &OutputFeatures,
+        script: &TariScript,
+        covenant: &Covenant,
+    ) -> u64 {
+        self.output_metadata_weight(output_metadata_size(features, script, covenant))
+    }
+
+    /// Sum of the additional metadata weight of the given outputs
+    pub fn outputs_metadata_weight<'a, I: IntoIterator<Item = &'a TransactionOutput>>(&self, outputs: I) -> u64 {
+        outputs
+            .into_iter()
+            .map(|o| self.output_metadata_weight(o.metadata_byte_size()))
+            .sum()
+    }
+
+    /// Calculates the weight of a transaction body
+    pub fn calculate_body(&self, body: &AggregateBody) -> u64 {
+        self.calculate(
+            body.kernels().len(),
+            body.inputs().len(),
+            body.outputs().len(),
+            self.outputs_metadata_weight(body.outputs()),
+        )
+    }
+}
+
+/// The serialized size of output features, script and covenant, as accounted for by the transaction weight
+pub fn output_metadata_size(features: &OutputFeatures, script: &TariScript, covenant: &Covenant) -> usize {
+    features.to_bytes().len() + script.as_bytes().len() + covenant.byte_size()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_agrees_with_v1_for_standard_outputs() {
+        assert_eq!(
+            TransactionWeight::V1.calculate(1, 2, 3, 0),
+            TransactionWeight::V2.calculate(1, 2, 3, 0)
+        );
+        assert_eq!(TransactionWeight::V1.calculate(1, 2, 3, 0), 3 + 2 + 39);
+        assert_eq!(TransactionWeight::V2.output_metadata_weight(10), 0);
+        assert_eq!(TransactionWeight::V2.output_metadata_weight(64), 0);
+    }
+
+    #[test]
+    fn it_charges_for_large_metadata_in_v2() {
+        assert_eq!(TransactionWeight::V1.output_metadata_weight(1000), 0);
+        assert_eq!(TransactionWeight::V2.output_metadata_weight(65), 1);
+        assert_eq!(TransactionWeight::V2.output_metadata_weight(80), 1);
+        assert_eq!(TransactionWeight::V2.output_metadata_weight(81), 2);
+        assert_eq!(TransactionWeight::V2.output_metadata_weight(64 + 1600), 100);
+    }
+}
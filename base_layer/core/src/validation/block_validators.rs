@@ -20,28 +20,48 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 use crate::{
-    blocks::{Block, BlockValidationError},
+    blocks::{Block, BlockHeader, BlockValidationError},
     chain_storage,
-    chain_storage::{BlockchainBackend, ChainBlock, DeletedBitmap, MmrTree},
-    consensus::ConsensusManager,
+    chain_storage::{BlockchainBackend, ChainBlock, DeletedBitmap, MmrRoots, MmrRootsCalculator, MmrTree},
+    consensus::{emission::Emission, ConsensusManager},
     transactions::{
         aggregated_body::AggregateBody,
-        transaction::{KernelFeatures, OutputFlags, TransactionError},
-        types::CryptoFactories,
+        covenant::{CovenantError, MAX_COVENANT_BYTES},
+        encrypted_data::{EncryptedDataError, MAX_ENCRYPTED_DATA_BYTES},
+        tari_amount::MicroTari,
+        transaction::{
+            KernelFeatures,
+            TransactionError,
+            TransactionInput,
+            TransactionKernel,
+            TransactionOutput,
+            UniqueAssetId,
+        },
+        types::{Commitment, CryptoFactories, HashOutput, PublicKey},
+        weight::TransactionWeight,
     },
     validation::{
-        helpers::{check_accounting_balance, check_block_weight, check_coinbase_output, is_all_unique_and_sorted},
+        helpers::{
+            check_accounting_balance,
+            check_block_weight,
+            check_coinbase_output,
+            check_unique_ids_are_unspent,
+            is_all_unique_and_sorted,
+        },
         traits::PostOrphanBodyValidation,
+        BlockBodyPartValidation,
         CandidateBlockBodyValidation,
         OrphanValidation,
         ValidationError,
     },
 };
+use croaring::Bitmap;
 use log::*;
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData};
 use tari_common_types::chain_metadata::ChainMetadata;
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
+    keys::PublicKey as PublicKeyTrait,
     tari_utilities::{hash::Hashable, hex::Hex},
 };
 
@@ -124,6 +144,7 @@ impl<B: BlockchainBackend> PostOrphanBodyValidation<B> for BodyOnlyValidator {
     /// 1. Does the block satisfy the stateless checks?
     /// 1. Are all inputs currently in the UTXO set?
     /// 1. Are all inputs and outputs not in the STXO set?
+    /// 1. Are the unique ids of the outputs not carried by another unspent output?
     /// 1. Are the block header MMR roots valid?
     fn validate_body_for_valid_orphan(
         &self,
@@ -148,6 +169,7 @@ impl<B: BlockchainBackend> PostOrphanBodyValidation<B> for BodyOnlyValidator {
         let block_id = format!("block #{} ({})", block.header().height, block.hash().to_hex());
         check_inputs_are_utxos(block.block(), backend, deleted_bitmap)?;
         check_not_duplicate_txos(block.block(), backend)?;
+        check_unique_ids_are_unspent(&block.block().body, backend, deleted_bitmap.bitmap())?;
         trace!(
             target: LOG_TARGET,
             "Block validation: All inputs and outputs are valid for {}",
@@ -227,12 +249,15 @@ fn check_not_duplicate_txos<B: BlockchainBackend>(block: &Block, db: &B) -> Resu
 
 fn check_mmr_roots<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), ValidationError> {
     let mmr_roots = chain_storage::calculate_mmr_roots(db, &block)?;
-    let header = &block.header;
+    compare_mmr_roots(&block.header, &mmr_roots)
+}
+
+fn compare_mmr_roots(header: &BlockHeader, mmr_roots: &MmrRoots) -> Result<(), ValidationError> {
     if header.input_mr != mmr_roots.input_mr {
         warn!(
             target: LOG_TARGET,
             "Block header input merkle root in {} do not match calculated root. Expected: {}, Actual:{}",
-            block.hash().to_hex(),
+            header.hash().to_hex(),
             header.input_mr.to_hex(),
             mmr_roots.input_mr.to_hex()
         );
@@ -242,7 +267,7 @@ fn check_mmr_roots<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), Va
         warn!(
             target: LOG_TARGET,
             "Block header kernel MMR roots in {} do not match calculated roots. Expected: {}, Actual:{}",
-            block.hash().to_hex(),
+            header.hash().to_hex(),
             header.kernel_mr.to_hex(),
             mmr_roots.kernel_mr.to_hex()
         );
@@ -252,7 +277,7 @@ fn check_mmr_roots<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), Va
         warn!(
             target: LOG_TARGET,
             "Block header kernel MMR size in {} does not match. Expected: {}, Actual:{}",
-            block.hash().to_hex(),
+            header.hash().to_hex(),
             header.kernel_mmr_size,
             mmr_roots.kernel_mmr_size
         );
@@ -266,7 +291,7 @@ fn check_mmr_roots<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), Va
         warn!(
             target: LOG_TARGET,
             "Block header output MMR roots in {} do not match calculated roots. Expected: {}, Actual:{}",
-            block.hash().to_hex(),
+            header.hash().to_hex(),
             header.output_mr.to_hex(),
             mmr_roots.output_mr.to_hex()
         );
@@ -276,7 +301,7 @@ fn check_mmr_roots<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), Va
         warn!(
             target: LOG_TARGET,
             "Block header witness MMR roots in {} do not match calculated roots",
-            block.hash().to_hex()
+            header.hash().to_hex()
         );
         return Err(ValidationError::BlockError(BlockValidationError::MismatchedMmrRoots));
     };
@@ -284,7 +309,7 @@ fn check_mmr_roots<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), Va
         warn!(
             target: LOG_TARGET,
             "Block header output MMR size in {} does not match. Expected: {}, Actual:{}",
-            block.hash().to_hex(),
+            header.hash().to_hex(),
             header.output_mmr_size,
             mmr_roots.output_mmr_size
         );
@@ -300,6 +325,11 @@ fn check_mmr_roots<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), Va
 /// This validator checks whether a block satisfies consensus rules.
 /// It implements two validators: one for the `BlockHeader` and one for `Block`. The `Block` validator ONLY validates
 /// the block body using the header. It is assumed that the `BlockHeader` has already been validated.
+///
+/// The body is validated in a single pass over the kernels, outputs and inputs, which can be made as the body is
+/// received (see [BlockBodyValidator]). Only running totals are kept and the MMR roots are calculated as each item is
+/// checked, so that validating a large block does not require any intermediate collections proportional to the size of
+/// the block.
 pub struct BlockValidator<B: BlockchainBackend> {
     rules: ConsensusManager,
     factories: CryptoFactories,
@@ -314,119 +344,380 @@ impl<B: BlockchainBackend> BlockValidator<B> {
             phantom_data: Default::default(),
         }
     }
+}
 
-    /// This function checks that all inputs in the blocks are valid UTXO's to be spend
-    fn check_inputs(&self, block: &Block) -> Result<(), ValidationError> {
-        let inputs = block.body.inputs();
-        for (i, input) in inputs.iter().enumerate() {
-            // Check for duplicates and/or incorrect sorting
-            if i > 0 && input <= &inputs[i - 1] {
-                return Err(ValidationError::UnsortedOrDuplicateInput);
-            }
+impl<B: BlockchainBackend> CandidateBlockBodyValidation<B> for BlockValidator<B> {
+    /// The following consensus checks are done:
+    /// 1. Does the block satisfy the stateless checks?
+    /// 1. Are the block header MMR roots valid?
+    fn validate_body(&self, block: &Block, backend: &B) -> Result<(), ValidationError> {
+        let mut validator = self.begin_body(&block.header, backend)?;
+        validator.validate_kernels(block.body.kernels())?;
+        validator.validate_outputs(block.body.outputs(), backend)?;
+        validator.validate_inputs(block.body.inputs(), block.body.outputs(), backend)?;
+        validator.finalize()
+    }
+
+    fn begin_body(
+        &self,
+        header: &BlockHeader,
+        backend: &B,
+    ) -> Result<Box<dyn BlockBodyPartValidation<B>>, ValidationError> {
+        trace!(target: LOG_TARGET, "Validating block #{}", header.height);
+        let constants = self.rules.consensus_constants(header.height);
+        Ok(Box::new(BlockBodyValidator {
+            header: header.clone(),
+            factories: self.factories.clone(),
+            reward: self.rules.emission_schedule().block_reward(header.height),
+            max_coinbase_outputs: constants.max_coinbase_outputs(),
+            transaction_weight: *constants.transaction_weight(),
+            // The genesis block has a larger weight than other blocks may have
+            max_weight: Some(constants.get_max_block_transaction_weight()).filter(|_| header.height > 0),
+            mmr_roots: MmrRootsCalculator::new(backend, header)?,
+            totals: Default::default(),
+            part: BodyPart::Kernels,
+            prev_output: None,
+            prev_input: None,
+            unique_ids: HashMap::new(),
+            deleted: None,
+        }))
+    }
+}
 
-            // Check maturity
-            if input.features.maturity > block.header.height {
+/// The parts of a block body, in the order that they are validated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum BodyPart {
+    Kernels,
+    Outputs,
+    Inputs,
+}
+
+/// The running totals of a block body that are needed to check the accounting balance and coinbase once every item
+/// has been seen
+#[derive(Default)]
+struct BodyTotals {
+    weight: u64,
+    total_fees: MicroTari,
+    kernel_excess_sum: Commitment,
+    coinbase_kernel_excess: Option<Commitment>,
+    num_coinbase_outputs: usize,
+    coinbase_output_sum: Commitment,
+    output_commitment_sum: Commitment,
+    input_commitment_sum: Commitment,
+    output_sender_offset_keys: PublicKey,
+    input_script_keys: PublicKey,
+}
+
+/// Validates the body of a block for the [BlockValidator] as the body is received. Only the last output and input are
+/// kept, to check the sorting of the next ones.
+pub struct BlockBodyValidator {
+    header: BlockHeader,
+    factories: CryptoFactories,
+    reward: MicroTari,
+    max_coinbase_outputs: usize,
+    transaction_weight: TransactionWeight,
+    max_weight: Option<u64>,
+    mmr_roots: MmrRootsCalculator,
+    totals: BodyTotals,
+    part: BodyPart,
+    prev_output: Option<TransactionOutput>,
+    prev_input: Option<TransactionInput>,
+    /// The hashes of the unspent outputs of the chain and of the block that carry each unique id seen in the block
+    unique_ids: HashMap<UniqueAssetId, Vec<HashOutput>>,
+    /// The deleted bitmap of the tip, fetched the first time a unique id is seen
+    deleted: Option<Bitmap>,
+}
+
+impl BlockBodyValidator {
+    /// Checks that the parts of the body are received in order and moves on to `part`
+    fn start_part(&mut self, part: BodyPart) -> Result<(), ValidationError> {
+        if part < self.part {
+            return Err(ValidationError::custom_error(format!(
+                "Block #{} {:?} received after {:?}",
+                self.header.height, part, self.part
+            )));
+        }
+        self.part = part;
+        Ok(())
+    }
+
+    /// Adds `weight` to the weight of the body and checks that the body is not too large
+    fn add_weight(&mut self, weight: u64) -> Result<(), ValidationError> {
+        self.totals.weight += weight;
+        match self.max_weight {
+            Some(max_weight) if self.totals.weight > max_weight => {
                 warn!(
                     target: LOG_TARGET,
-                    "Input found that has not yet matured to spending height: {}", input
+                    "Block #{} failed to validate: weight exceeds {}", self.header.height, max_weight
                 );
-                return Err(TransactionError::InputMaturity.into());
-            }
+                Err(BlockValidationError::BlockTooLarge.into())
+            },
+            _ => Ok(()),
         }
-        Ok(())
     }
 
-    fn check_outputs(&self, block: &Block) -> Result<(), ValidationError> {
-        let outputs = block.body.outputs();
-        let mut coinbase_output = None;
-        for (j, output) in outputs.iter().enumerate() {
-            if output.features.flags.contains(OutputFlags::COINBASE_OUTPUT) {
-                if coinbase_output.is_some() {
-                    return Err(ValidationError::TransactionError(TransactionError::MoreThanOneCoinbase));
-                }
-                coinbase_output = Some(output);
-            }
-
-            if j > 0 && output <= &outputs[j - 1] {
-                return Err(ValidationError::UnsortedOrDuplicateOutput);
+    /// Records that `output` carries `id`, along with the unspent output of the chain that carries it, if any
+    fn add_unique_id<B: BlockchainBackend>(
+        &mut self,
+        id: UniqueAssetId,
+        output: &TransactionOutput,
+        backend: &B,
+    ) -> Result<(), ValidationError> {
+        if !self.unique_ids.contains_key(&id) {
+            if self.deleted.is_none() {
+                self.deleted = Some(backend.fetch_deleted_bitmap()?.into_bitmap());
             }
+            let deleted = self.deleted.as_ref().expect("deleted bitmap was just fetched");
+            let carriers = backend
+                .fetch_unspent_output_by_unique_id(&id, deleted)?
+                .map(|(output, _)| vec![output.hash()])
+                .unwrap_or_default();
+            self.unique_ids.insert(id.clone(), carriers);
         }
+        self.unique_ids
+            .get_mut(&id)
+            .expect("entry was just added")
+            .push(output.hash());
+        Ok(())
+    }
 
-        let coinbase_output = match coinbase_output {
-            Some(output) => output,
-            // No coinbase found
-            None => {
+    /// Checks that each unique id seen in the block is carried by at most one unspent output once the inputs of the
+    /// block have been spent
+    fn check_unique_ids(&self) -> Result<(), ValidationError> {
+        match self.unique_ids.iter().find(|(_, carriers)| carriers.len() > 1) {
+            Some((id, _)) => {
                 warn!(
                     target: LOG_TARGET,
-                    "Block #{} failed to validate: no coinbase UTXO", block.header.height
+                    "Block #{} failed to validate: more than one unspent output carries unique id {}",
+                    self.header.height,
+                    id
                 );
-                return Err(ValidationError::TransactionError(TransactionError::NoCoinbase));
+                Err(TransactionError::DuplicateUniqueId(id.to_string()).into())
             },
-        };
-
-        let mut coinbase_kernel = None;
-        for kernel in block.body.kernels() {
-            if kernel.features.contains(KernelFeatures::COINBASE_KERNEL) {
-                if coinbase_kernel.is_some() {
-                    return Err(ValidationError::TransactionError(TransactionError::MoreThanOneCoinbase));
-                }
-                coinbase_kernel = Some(kernel);
-            }
+            None => Ok(()),
         }
+    }
 
-        let coinbase_kernel = match coinbase_kernel {
-            Some(kernel) => kernel,
+    /// Checks the coinbase amount, that the kernels balance the inputs and outputs and that the script offset is
+    /// correct using the totals gathered from the body
+    fn check_balance(&self) -> Result<(), ValidationError> {
+        let header = &self.header;
+        let totals = &self.totals;
+        if totals.num_coinbase_outputs == 0 {
+            warn!(
+                target: LOG_TARGET,
+                "Block #{} failed to validate: no coinbase UTXO", header.height
+            );
+            return Err(ValidationError::TransactionError(TransactionError::NoCoinbase));
+        }
+        let coinbase_kernel_excess = match totals.coinbase_kernel_excess {
+            Some(ref excess) => excess,
             // No coinbase found
             None => {
                 warn!(
                     target: LOG_TARGET,
-                    "Block #{} failed to validate: no coinbase kernel", block.header.height
+                    "Block #{} failed to validate: no coinbase kernel", header.height
                 );
                 return Err(ValidationError::TransactionError(TransactionError::NoCoinbase));
             },
         };
 
-        let reward = self.rules.calculate_coinbase_and_fees(block);
-        let rhs = &coinbase_kernel.excess +
-            &self
-                .factories
-                .commitment
-                .commit_value(&Default::default(), reward.into());
-        if rhs != coinbase_output.commitment {
+        let commitment_factory = &self.factories.commitment;
+        let reward = self.reward + totals.total_fees;
+        let rhs = coinbase_kernel_excess + &commitment_factory.commit_value(&Default::default(), reward.into());
+        if rhs != totals.coinbase_output_sum {
             warn!(
                 target: LOG_TARGET,
-                "Coinbase {} amount validation failed", coinbase_output
+                "Block #{} failed to validate: coinbase amount validation failed", header.height
             );
             return Err(ValidationError::TransactionError(TransactionError::InvalidCoinbase));
         }
 
+        if header.height == 0 {
+            // Gen block does not need to be checked for this.
+            return Ok(());
+        }
+
+        let total_offset = commitment_factory.commit_value(&header.total_kernel_offset, reward.into());
+        let excess = &total_offset + &totals.kernel_excess_sum;
+        let sum_io = &totals.output_commitment_sum - &totals.input_commitment_sum;
+        let fees = commitment_factory.commit_value(&Default::default(), totals.total_fees.into());
+        if excess != &sum_io + &fees {
+            warn!(
+                target: LOG_TARGET,
+                "Internal validation failed on block:{}: sum of inputs and outputs did not equal sum of kernels with \
+                 fees",
+                header.hash().to_hex(),
+            );
+            return Err(ValidationError::TransactionError(TransactionError::ValidationError(
+                "Sum of inputs and outputs did not equal sum of kernels with fees".into(),
+            )));
+        }
+
+        let script_offset = PublicKey::from_secret_key(&header.total_script_offset);
+        if totals.input_script_keys.clone() - totals.output_sender_offset_keys.clone() != script_offset {
+            warn!(
+                target: LOG_TARGET,
+                "Internal validation failed on block:{}: script offset is invalid",
+                header.hash().to_hex(),
+            );
+            return Err(ValidationError::TransactionError(TransactionError::ScriptOffset));
+        }
+
         Ok(())
     }
 }
 
-impl<B: BlockchainBackend> CandidateBlockBodyValidation<B> for BlockValidator<B> {
-    /// The following consensus checks are done:
-    /// 1. Does the block satisfy the stateless checks?
-    /// 1. Are the block header MMR roots valid?
-    fn validate_body(&self, block: &Block, backend: &B) -> Result<(), ValidationError> {
-        let block_id = format!("block #{}", block.header.height);
-        trace!(target: LOG_TARGET, "Validating {}", block_id);
+impl<B: BlockchainBackend> BlockBodyPartValidation<B> for BlockBodyValidator {
+    /// Checks the kernel signatures and that there is at most one coinbase kernel
+    fn validate_kernels(&mut self, kernels: &[TransactionKernel]) -> Result<(), ValidationError> {
+        self.start_part(BodyPart::Kernels)?;
+        for kernel in kernels {
+            self.add_weight(self.transaction_weight.calculate(1, 0, 0, 0))?;
+            if kernel.features.contains(KernelFeatures::COINBASE_KERNEL) {
+                if self.totals.coinbase_kernel_excess.is_some() {
+                    return Err(ValidationError::TransactionError(TransactionError::MoreThanOneCoinbase));
+                }
+                self.totals.coinbase_kernel_excess = Some(kernel.excess.clone());
+            }
 
-        let constants = self.rules.consensus_constants(block.header.height);
-        check_block_weight(block, &constants)?;
-        trace!(target: LOG_TARGET, "SV - Block weight is ok for {} ", &block_id);
+            kernel.verify_signature().map_err(|err| {
+                warn!(target: LOG_TARGET, "Kernel ({}) signature failed {:?}.", kernel, err);
+                err
+            })?;
 
-        self.check_inputs(block)?;
-        self.check_outputs(block)?;
+            self.totals.total_fees += kernel.fee;
+            self.totals.kernel_excess_sum = &self.totals.kernel_excess_sum + &kernel.excess;
+            self.mmr_roots.add_kernel(kernel)?;
+        }
+        Ok(())
+    }
 
-        check_accounting_balance(block, &self.rules, &self.factories)?;
+    /// Checks the sorting, coinbase count, covenant and encrypted data sizes, unique id, range proof and metadata
+    /// signature of each output
+    fn validate_outputs(&mut self, outputs: &[TransactionOutput], backend: &B) -> Result<(), ValidationError> {
+        self.start_part(BodyPart::Outputs)?;
+        let mut prev_output = self.prev_output.take();
+        for output in outputs {
+            if prev_output.as_ref().map(|prev| output <= prev).unwrap_or(false) {
+                return Err(ValidationError::UnsortedOrDuplicateOutput);
+            }
+            self.add_weight(
+                self.transaction_weight.calculate(
+                    0,
+                    0,
+                    1,
+                    self.transaction_weight
+                        .output_metadata_weight(output.metadata_byte_size()),
+                ),
+            )?;
+
+            if output.is_coinbase() {
+                if self.totals.num_coinbase_outputs >= self.max_coinbase_outputs {
+                    return Err(ValidationError::TransactionError(TransactionError::MoreThanOneCoinbase));
+                }
+                self.totals.num_coinbase_outputs += 1;
+                self.totals.coinbase_output_sum = &self.totals.coinbase_output_sum + &output.commitment;
+            } else {
+                // Coinbase outputs are not counted towards the script offset
+                self.totals.output_sender_offset_keys =
+                    self.totals.output_sender_offset_keys.clone() + output.sender_offset_public_key.clone();
+            }
+
+            let size = output.covenant.byte_size();
+            if size > MAX_COVENANT_BYTES {
+                return Err(TransactionError::from(CovenantError::TooLarge(size)).into());
+            }
+            let size = output.features.encrypted_data.len();
+            if size > MAX_ENCRYPTED_DATA_BYTES {
+                return Err(TransactionError::from(EncryptedDataError::TooLarge(size)).into());
+            }
+            output.features.check_unique_id()?;
+            if let Some(id) = output.features.unique_asset_id() {
+                self.add_unique_id(id, output, backend)?;
+            }
+            if !output.verify_range_proof(&self.factories.range_proof)? {
+                return Err(TransactionError::ValidationError("Range proof could not be verified".into()).into());
+            }
+            output.verify_metadata_signature()?;
+
+            self.totals.output_commitment_sum = &self.totals.output_commitment_sum + &output.commitment;
+            self.mmr_roots.add_output(output)?;
+            prev_output = Some(output.clone());
+        }
+        self.prev_output = prev_output;
+        Ok(())
+    }
+
+    /// Checks the sorting and maturity of each input, runs the input scripts and checks that the outputs of the block
+    /// satisfy the covenant of each input
+    fn validate_inputs(
+        &mut self,
+        inputs: &[TransactionInput],
+        outputs: &[TransactionOutput],
+        backend: &B,
+    ) -> Result<(), ValidationError> {
+        self.start_part(BodyPart::Inputs)?;
+        let height = self.header.height;
+        let mut prev_input = self.prev_input.take();
+        for input in inputs {
+            if prev_input.as_ref().map(|prev| input <= prev).unwrap_or(false) {
+                return Err(ValidationError::UnsortedOrDuplicateInput);
+            }
+            self.add_weight(self.transaction_weight.calculate(0, 1, 0, 0))?;
+
+            if input.features.maturity > height {
+                warn!(
+                    target: LOG_TARGET,
+                    "Input found that has not yet matured to spending height: {}", input
+                );
+                return Err(TransactionError::InputMaturity.into());
+            }
+            if let Err(err) = input.covenant.execute(height, input, outputs) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Input found that does not satisfy its covenant: {} ({})", input, err
+                );
+                return Err(TransactionError::from(err).into());
+            }
+
+            self.totals.input_script_keys =
+                self.totals.input_script_keys.clone() + input.run_and_verify_script(&self.factories.commitment)?;
+            self.totals.input_commitment_sum = &self.totals.input_commitment_sum + &input.commitment;
+            if let Some(carriers) = input
+                .features
+                .unique_asset_id()
+                .and_then(|id| self.unique_ids.get_mut(&id))
+            {
+                let hash = input.output_hash();
+                carriers.retain(|carrier| *carrier != hash);
+            }
+            // Inputs are only received once all outputs have been added, so that zero-conf spends can be found
+            self.mmr_roots.add_input(backend, input)?;
+            prev_input = Some(input.clone());
+        }
+        self.prev_input = prev_input;
+        Ok(())
+    }
+
+    fn finalize(self: Box<Self>) -> Result<(), ValidationError> {
+        let block_id = format!("block #{}", self.header.height);
+        trace!(
+            target: LOG_TARGET,
+            "SV - Block weight {} is ok for {}",
+            self.totals.weight,
+            block_id
+        );
+        self.check_balance()?;
         trace!(target: LOG_TARGET, "SV - accounting balance correct for {}", &block_id);
+        self.check_unique_ids()?;
+        trace!(target: LOG_TARGET, "SV - unique ids are unspent for {}", &block_id);
         debug!(
             target: LOG_TARGET,
             "{} has PASSED stateless VALIDATION check.", &block_id
         );
 
-        check_mmr_roots(&block, backend)?;
+        compare_mmr_roots(&self.header, &self.mmr_roots.finalize()?)?;
         trace!(
             target: LOG_TARGET,
             "Block validation: MMR roots are valid for {}",
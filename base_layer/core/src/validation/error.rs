@@ -77,6 +77,8 @@ pub enum ValidationError {
     IncorrectNextTipHeight { expected: u64, block_height: u64 },
     #[error("Expected block previous hash to be {expected}, but was {block_hash}")]
     IncorrectPreviousHash { expected: String, block_hash: String },
+    #[error("Bad block with hash {hash} found")]
+    BadBlockFound { hash: String },
 }
 
 // ChainStorageError has a ValidationError variant, so to prevent a cyclic dependency we use a string representation in
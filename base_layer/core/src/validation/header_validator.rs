@@ -4,34 +4,58 @@ use crate::{
     consensus::ConsensusManager,
     proof_of_work::AchievedTargetDifficulty,
     validation::{
-        helpers::{check_header_timestamp_greater_than_median, check_pow_data, check_timestamp_ftl},
+        helpers::check_pow_data,
+        timestamp_validators::{default_timestamp_validators, NetworkTimeOffset},
         DifficultyCalculator,
+        HeaderTimestampValidation,
         HeaderValidation,
         ValidationError,
     },
 };
 use log::*;
-use tari_crypto::tari_utilities::{hash::Hashable, hex::Hex};
+use std::sync::Arc;
+use tari_crypto::tari_utilities::{epoch_time::EpochTime, hash::Hashable, hex::Hex};
 
 pub const LOG_TARGET: &str = "c::val::header_validators";
 
 pub struct HeaderValidator {
     rules: ConsensusManager,
+    timestamp_validators: Vec<Arc<dyn HeaderTimestampValidation>>,
 }
 
 impl HeaderValidator {
+    /// Creates a header validator with the default timestamp rules, measuring the future time limit from the local
+    /// clock
     pub fn new(rules: ConsensusManager) -> Self {
-        Self { rules }
+        Self::with_network_time(rules, NetworkTimeOffset::default())
     }
 
-    /// This function tests that the block timestamp is greater than the median timestamp at the specified height.
-    fn check_median_timestamp<B: BlockchainBackend>(
+    /// Creates a header validator with the default timestamp rules, measuring the future time limit from
+    /// network-adjusted time
+    pub fn with_network_time(rules: ConsensusManager, network_time: NetworkTimeOffset) -> Self {
+        let timestamp_validators = default_timestamp_validators(rules.clone(), network_time);
+        Self::with_timestamp_validators(rules, timestamp_validators)
+    }
+
+    /// Creates a header validator that checks header timestamps with the given validators
+    pub fn with_timestamp_validators(
+        rules: ConsensusManager,
+        timestamp_validators: Vec<Arc<dyn HeaderTimestampValidation>>,
+    ) -> Self {
+        Self {
+            rules,
+            timestamp_validators,
+        }
+    }
+
+    /// Fetches the timestamps of the headers in the median timestamp window before the given header
+    fn fetch_prev_timestamps<B: BlockchainBackend>(
         &self,
         db: &B,
         block_header: &BlockHeader,
-    ) -> Result<(), ValidationError> {
+    ) -> Result<Vec<EpochTime>, ValidationError> {
         if block_header.height == 0 {
-            return Ok(()); // Its the genesis block, so we dont have to check median
+            return Ok(Vec::new());
         }
 
         let height = block_header.height - 1;
@@ -43,17 +67,16 @@ impl HeaderValidator {
         let timestamps = fetch_headers(db, min_height, height)?
             .iter()
             .map(|h| h.timestamp)
-            .collect::<Vec<_>>();
-
-        check_header_timestamp_greater_than_median(block_header, &timestamps)?;
-
-        Ok(())
+            .collect();
+        Ok(timestamps)
     }
 }
 
 impl<TBackend: BlockchainBackend> HeaderValidation<TBackend> for HeaderValidator {
     /// The consensus checks that are done (in order of cheapest to verify to most expensive):
-    /// 1. Is the block timestamp within the Future Time Limit (FTL)?
+    /// 1. Was the block previously marked as invalid?
+    /// 1. Does the block timestamp pass the timestamp validators, by default the Future Time Limit (FTL) and median
+    ///    timestamp rules?
     /// 1. Is the Proof of Work valid?
     /// 1. Is the achieved difficulty of this block >= the target difficulty for this block?
 
@@ -63,17 +86,20 @@ impl<TBackend: BlockchainBackend> HeaderValidation<TBackend> for HeaderValidator
         header: &BlockHeader,
         difficulty_calculator: &DifficultyCalculator,
     ) -> Result<AchievedTargetDifficulty, ValidationError> {
-        check_timestamp_ftl(&header, &self.rules)?;
-        let header_id = format!("header #{} ({})", header.height, header.hash().to_hex());
-        trace!(
-            target: LOG_TARGET,
-            "BlockHeader validation: FTL timestamp is ok for {} ",
-            header_id
-        );
-        self.check_median_timestamp(backend, header)?;
+        let header_hash = header.hash();
+        let header_id = format!("header #{} ({})", header.height, header_hash.to_hex());
+        if backend.bad_block_exists(header_hash.clone())? {
+            return Err(ValidationError::BadBlockFound {
+                hash: header_hash.to_hex(),
+            });
+        }
+        let prev_timestamps = self.fetch_prev_timestamps(backend, header)?;
+        for validator in &self.timestamp_validators {
+            validator.validate_timestamp(header, &prev_timestamps)?;
+        }
         trace!(
             target: LOG_TARGET,
-            "BlockHeader validation: Median timestamp is ok for {} ",
+            "BlockHeader validation: Timestamp is ok for {} ",
             header_id
         );
         check_pow_data(header, &self.rules, backend)?;
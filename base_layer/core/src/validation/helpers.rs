@@ -38,33 +38,16 @@ use crate::{
         PowAlgorithm,
         PowError,
     },
-    transactions::types::CryptoFactories,
+    transactions::{aggregated_body::AggregateBody, transaction::TransactionError, types::CryptoFactories},
     validation::ValidationError,
 };
+use croaring::Bitmap;
 use log::*;
 use tari_crypto::tari_utilities::{epoch_time::EpochTime, hash::Hashable, hex::Hex};
 
 pub const LOG_TARGET: &str = "c::val::helpers";
 
-/// This function tests that the block timestamp is less than the FTL
-pub fn check_timestamp_ftl(
-    block_header: &BlockHeader,
-    consensus_manager: &ConsensusManager,
-) -> Result<(), ValidationError> {
-    if block_header.timestamp > consensus_manager.consensus_constants(block_header.height).ftl() {
-        warn!(
-            target: LOG_TARGET,
-            "Invalid Future Time Limit on block:{}",
-            block_header.hash().to_hex()
-        );
-        return Err(ValidationError::BlockHeaderError(
-            BlockHeaderValidationError::InvalidTimestampFutureTimeLimit,
-        ));
-    }
-    Ok(())
-}
-
-/// Returns the median timestamp for the provided timestamps.
+/// Returns the median timestamp for the provided timestamps, which must be in ascending order.
 pub fn calc_median_timestamp(timestamps: &[EpochTime]) -> EpochTime {
     assert!(
         !timestamps.is_empty(),
@@ -86,36 +69,6 @@ pub fn calc_median_timestamp(timestamps: &[EpochTime]) -> EpochTime {
     median_timestamp
 }
 
-pub fn check_header_timestamp_greater_than_median(
-    block_header: &BlockHeader,
-    timestamps: &[EpochTime],
-) -> Result<(), ValidationError> {
-    if timestamps.is_empty() {
-        return Err(ValidationError::BlockHeaderError(
-            BlockHeaderValidationError::InvalidTimestamp("The timestamp is empty".to_string()),
-        ));
-    }
-
-    let median_timestamp = calc_median_timestamp(timestamps);
-    if block_header.timestamp < median_timestamp {
-        warn!(
-            target: LOG_TARGET,
-            "Block header timestamp {} is less than median timestamp: {} for block:{}",
-            block_header.timestamp,
-            median_timestamp,
-            block_header.hash().to_hex()
-        );
-        return Err(ValidationError::BlockHeaderError(
-            BlockHeaderValidationError::InvalidTimestamp(format!(
-                "The timestamp `{}` was less than the median timestamp `{}`",
-                block_header.timestamp, median_timestamp
-            )),
-        ));
-    }
-
-    Ok(())
-}
-
 /// Check the PoW data in the BlockHeader. This currently only applies to blocks merged mined with Monero.
 pub fn check_pow_data<B: BlockchainBackend>(
     block_header: &BlockHeader,
@@ -180,7 +133,7 @@ pub fn check_target_difficulty(
 
 pub fn check_block_weight(block: &Block, consensus_constants: &ConsensusConstants) -> Result<(), ValidationError> {
     // The genesis block has a larger weight than other blocks may have so we have to exclude it here
-    let block_weight = block.body.calculate_weight();
+    let block_weight = block.body.calculate_weight(consensus_constants.transaction_weight());
     if block_weight <= consensus_constants.get_max_block_transaction_weight() || block.header.height == 0 {
         trace!(
             target: LOG_TARGET,
@@ -222,6 +175,30 @@ pub fn check_accounting_balance(
         })
 }
 
+/// Checks that the unique asset tokens left unspent by `body` are not carried by an unspent output of the chain, unless
+/// `body` spends that output
+pub fn check_unique_ids_are_unspent<B: BlockchainBackend>(
+    body: &AggregateBody,
+    db: &B,
+    deleted: &Bitmap,
+) -> Result<(), ValidationError> {
+    for id in body.unspent_unique_asset_ids() {
+        if let Some((output, _)) = db.fetch_unspent_output_by_unique_id(&id, deleted)? {
+            let hash = output.hash();
+            if !body.inputs().iter().any(|input| input.output_hash() == hash) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Validation failed because unique id {} is already carried by unspent output {}",
+                    id,
+                    hash.to_hex()
+                );
+                return Err(TransactionError::DuplicateUniqueId(id.to_string()).into());
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn check_coinbase_output(
     block: &Block,
     rules: &ConsensusManager,
@@ -24,9 +24,13 @@ use crate::{
     blocks::{Block, BlockHeader},
     chain_storage::{BlockchainBackend, ChainBlock, DeletedBitmap},
     proof_of_work::{sha3_difficulty, AchievedTargetDifficulty, Difficulty, PowAlgorithm},
-    transactions::{transaction::Transaction, types::Commitment},
+    transactions::{
+        transaction::{Transaction, TransactionInput, TransactionKernel, TransactionOutput},
+        types::Commitment,
+    },
     validation::{
         error::ValidationError,
+        BlockBodyPartValidation,
         CandidateBlockBodyValidation,
         DifficultyCalculator,
         FinalHorizonStateValidation,
@@ -77,6 +81,39 @@ impl<B: BlockchainBackend> CandidateBlockBodyValidation<B> for MockValidator {
             ))
         }
     }
+
+    fn begin_body(&self, _: &BlockHeader, _: &B) -> Result<Box<dyn BlockBodyPartValidation<B>>, ValidationError> {
+        Ok(Box::new(self.clone()))
+    }
+}
+
+impl<B: BlockchainBackend> BlockBodyPartValidation<B> for MockValidator {
+    fn validate_kernels(&mut self, _: &[TransactionKernel]) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    fn validate_outputs(&mut self, _: &[TransactionOutput], _: &B) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    fn validate_inputs(
+        &mut self,
+        _: &[TransactionInput],
+        _: &[TransactionOutput],
+        _: &B,
+    ) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    fn finalize(self: Box<Self>) -> Result<(), ValidationError> {
+        if self.is_valid.load(Ordering::SeqCst) {
+            Ok(())
+        } else {
+            Err(ValidationError::custom_error(
+                "This mock validator always returns an error",
+            ))
+        }
+    }
 }
 
 impl<B: BlockchainBackend> PostOrphanBodyValidation<B> for MockValidator {
@@ -34,8 +34,10 @@ pub(crate) mod helpers;
 
 mod traits;
 pub use traits::{
+    BlockBodyPartValidation,
     CandidateBlockBodyValidation,
     FinalHorizonStateValidation,
+    HeaderTimestampValidation,
     HeaderValidation,
     MempoolTransactionValidation,
     OrphanValidation,
@@ -47,6 +49,7 @@ mod difficulty_calculator;
 pub use difficulty_calculator::*;
 pub mod header_validator;
 pub mod mocks;
+pub mod timestamp_validators;
 pub mod transaction_validators;
 // pub mod header_validator;
 
@@ -0,0 +1,285 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Validators for the timestamp of a block header. Each rule is a separate [HeaderTimestampValidation] so that the
+//! set of rules, and their parameters, can be chosen per network without changing the header validators that use
+//! them.
+
+use crate::{
+    blocks::{block_header::BlockHeaderValidationError, BlockHeader},
+    consensus::ConsensusManager,
+    validation::{helpers::calc_median_timestamp, HeaderTimestampValidation, ValidationError},
+};
+use log::*;
+use std::{
+    cmp,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+};
+use tari_crypto::tari_utilities::{epoch_time::EpochTime, hash::Hashable, hex::Hex};
+
+pub const LOG_TARGET: &str = "c::val::timestamp_validators";
+
+/// The largest network time offset that is applied to the local clock. Peers that agree on a larger offset are
+/// more likely to be attacking the node than to be correct.
+pub const MAX_NETWORK_TIME_OFFSET_MS: i64 = 5 * 60 * 1000;
+
+/// The offset of network-adjusted time from the local clock. Clones share the same offset, so that it can be updated
+/// from the liveness service's network time estimate while validators are in use.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkTimeOffset {
+    offset_ms: Arc<AtomicI64>,
+}
+
+impl NetworkTimeOffset {
+    /// Sets the offset in milliseconds, limited to [MAX_NETWORK_TIME_OFFSET_MS] in either direction
+    pub fn set_offset_ms(&self, offset_ms: i64) {
+        let offset_ms = cmp::max(
+            cmp::min(offset_ms, MAX_NETWORK_TIME_OFFSET_MS),
+            -MAX_NETWORK_TIME_OFFSET_MS,
+        );
+        self.offset_ms.store(offset_ms, Ordering::Relaxed);
+    }
+
+    pub fn offset_ms(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// Returns the current network-adjusted time
+    pub fn now(&self) -> EpochTime {
+        let now_ms = EpochTime::now().as_u64() as i64 * 1000 + self.offset_ms();
+        (cmp::max(now_ms, 0) as u64 / 1000).into()
+    }
+}
+
+/// Returns the median-time-past and future-time-limit validators, which are the timestamp rules of every network
+pub fn default_timestamp_validators(
+    rules: ConsensusManager,
+    network_time: NetworkTimeOffset,
+) -> Vec<Arc<dyn HeaderTimestampValidation>> {
+    vec![
+        Arc::new(MedianTimePastValidator::new(rules.clone())),
+        Arc::new(FutureTimeLimitValidator::new(rules, network_time)),
+    ]
+}
+
+/// Checks that the header timestamp is not less than the median timestamp of the headers before it. The number of
+/// headers is the median timestamp window of the consensus constants in effect at the header's height.
+#[derive(Clone)]
+pub struct MedianTimePastValidator {
+    rules: ConsensusManager,
+}
+
+impl MedianTimePastValidator {
+    pub fn new(rules: ConsensusManager) -> Self {
+        Self { rules }
+    }
+}
+
+impl HeaderTimestampValidation for MedianTimePastValidator {
+    fn validate_timestamp(&self, header: &BlockHeader, prev_timestamps: &[EpochTime]) -> Result<(), ValidationError> {
+        if header.height == 0 {
+            // The genesis block has no previous headers
+            return Ok(());
+        }
+        if prev_timestamps.is_empty() {
+            return Err(ValidationError::BlockHeaderError(
+                BlockHeaderValidationError::InvalidTimestamp("The timestamp is empty".to_string()),
+            ));
+        }
+
+        let window = self
+            .rules
+            .consensus_constants(header.height)
+            .get_median_timestamp_count();
+        // Timestamps are not necessarily in ascending order, e.g. while the difficulty oscillates, so the window is
+        // taken from the end and sorted before the median is calculated
+        let mut timestamps = prev_timestamps[prev_timestamps.len().saturating_sub(window)..].to_vec();
+        timestamps.sort_unstable();
+        let median_timestamp = calc_median_timestamp(&timestamps);
+        if header.timestamp < median_timestamp {
+            warn!(
+                target: LOG_TARGET,
+                "Block header timestamp {} is less than median timestamp: {} for block:{}",
+                header.timestamp,
+                median_timestamp,
+                header.hash().to_hex()
+            );
+            return Err(ValidationError::BlockHeaderError(
+                BlockHeaderValidationError::InvalidTimestamp(format!(
+                    "The timestamp `{}` was less than the median timestamp `{}`",
+                    header.timestamp, median_timestamp
+                )),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that the header timestamp is no further in the future than the future time limit (FTL) of the consensus
+/// constants in effect at the header's height. The limit is measured from network-adjusted time.
+#[derive(Clone)]
+pub struct FutureTimeLimitValidator {
+    rules: ConsensusManager,
+    network_time: NetworkTimeOffset,
+}
+
+impl FutureTimeLimitValidator {
+    pub fn new(rules: ConsensusManager, network_time: NetworkTimeOffset) -> Self {
+        Self { rules, network_time }
+    }
+
+    fn check_at(&self, header: &BlockHeader, now: EpochTime) -> Result<(), ValidationError> {
+        let future_time_limit = self.rules.consensus_constants(header.height).get_future_time_limit();
+        let ftl = EpochTime::from(now.as_u64() + future_time_limit);
+        if header.timestamp > ftl {
+            warn!(
+                target: LOG_TARGET,
+                "Invalid Future Time Limit on block:{} (timestamp: {}, FTL: {}, network time offset: {}ms)",
+                header.hash().to_hex(),
+                header.timestamp,
+                ftl,
+                self.network_time.offset_ms()
+            );
+            return Err(ValidationError::BlockHeaderError(
+                BlockHeaderValidationError::InvalidTimestampFutureTimeLimit,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl HeaderTimestampValidation for FutureTimeLimitValidator {
+    fn validate_timestamp(&self, header: &BlockHeader, _: &[EpochTime]) -> Result<(), ValidationError> {
+        self.check_at(header, self.network_time.now())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::consensus::ConsensusManagerBuilder;
+    use tari_common::configuration::Network;
+
+    fn header_at(height: u64, timestamp: u64) -> BlockHeader {
+        let mut header = BlockHeader::new(0);
+        header.height = height;
+        header.timestamp = timestamp.into();
+        header
+    }
+
+    fn timestamps(values: &[u64]) -> Vec<EpochTime> {
+        values.iter().map(|v| EpochTime::from(*v)).collect()
+    }
+
+    mod median_time_past {
+        use super::*;
+
+        #[test]
+        fn it_accepts_a_timestamp_equal_to_the_median() {
+            let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+            let validator = MedianTimePastValidator::new(rules);
+            let prev = timestamps(&[100, 110, 120, 130, 140]);
+            validator.validate_timestamp(&header_at(6, 120), &prev).unwrap();
+            let err = validator.validate_timestamp(&header_at(6, 119), &prev).unwrap_err();
+            assert!(matches!(
+                err,
+                ValidationError::BlockHeaderError(BlockHeaderValidationError::InvalidTimestamp(_))
+            ));
+        }
+
+        #[test]
+        fn it_uses_the_median_of_unordered_timestamps() {
+            let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+            let validator = MedianTimePastValidator::new(rules);
+            // Oscillating difficulty leads to blocks that are alternately early and late. The median of these is 150,
+            // whereas the middle element in height order is 300.
+            let prev = timestamps(&[100, 400, 120, 300, 150, 500, 130]);
+            validator.validate_timestamp(&header_at(8, 150), &prev).unwrap();
+            validator.validate_timestamp(&header_at(8, 149), &prev).unwrap_err();
+        }
+
+        #[test]
+        fn it_only_considers_the_window() {
+            let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+            let window = rules.consensus_constants(0).get_median_timestamp_count();
+            let validator = MedianTimePastValidator::new(rules);
+            // The old timestamps outside of the window would pull the median down if they were included
+            let mut prev = timestamps(&vec![0; window]);
+            prev.extend(timestamps(&vec![1000; window]));
+            validator.validate_timestamp(&header_at(100, 1000), &prev).unwrap();
+            validator.validate_timestamp(&header_at(100, 999), &prev).unwrap_err();
+        }
+
+        #[test]
+        fn it_ignores_genesis_and_rejects_missing_timestamps() {
+            let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+            let validator = MedianTimePastValidator::new(rules);
+            validator.validate_timestamp(&header_at(0, 0), &[]).unwrap();
+            validator.validate_timestamp(&header_at(1, 0), &[]).unwrap_err();
+        }
+    }
+
+    mod future_time_limit {
+        use super::*;
+
+        #[test]
+        fn it_accepts_a_timestamp_at_the_limit() {
+            let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+            let ftl = rules.consensus_constants(1).get_future_time_limit();
+            let validator = FutureTimeLimitValidator::new(rules, NetworkTimeOffset::default());
+            let now = EpochTime::from(1_000_000);
+            validator.check_at(&header_at(1, 1_000_000 + ftl), now).unwrap();
+            let err = validator.check_at(&header_at(1, 1_000_001 + ftl), now).unwrap_err();
+            assert!(matches!(
+                err,
+                ValidationError::BlockHeaderError(BlockHeaderValidationError::InvalidTimestampFutureTimeLimit)
+            ));
+        }
+
+        #[test]
+        fn it_applies_the_network_time_offset() {
+            let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+            let ftl = rules.consensus_constants(1).get_future_time_limit();
+            let network_time = NetworkTimeOffset::default();
+            let validator = FutureTimeLimitValidator::new(rules, network_time.clone());
+            let header = header_at(1, EpochTime::now().as_u64() + ftl + 60);
+            validator.validate_timestamp(&header, &[]).unwrap_err();
+
+            // The peers' clocks are two minutes ahead of ours
+            network_time.set_offset_ms(120_000);
+            validator.validate_timestamp(&header, &[]).unwrap();
+        }
+
+        #[test]
+        fn it_limits_the_network_time_offset() {
+            let network_time = NetworkTimeOffset::default();
+            network_time.set_offset_ms(i64::MAX);
+            assert_eq!(network_time.offset_ms(), MAX_NETWORK_TIME_OFFSET_MS);
+            network_time.set_offset_ms(i64::MIN);
+            assert_eq!(network_time.offset_ms(), -MAX_NETWORK_TIME_OFFSET_MS);
+        }
+    }
+}
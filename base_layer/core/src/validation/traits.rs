@@ -24,15 +24,47 @@ use crate::{
     blocks::{Block, BlockHeader},
     chain_storage::{BlockchainBackend, ChainBlock, DeletedBitmap},
     proof_of_work::AchievedTargetDifficulty,
-    transactions::{transaction::Transaction, types::Commitment},
+    transactions::{
+        transaction::{Transaction, TransactionInput, TransactionKernel, TransactionOutput},
+        types::Commitment,
+    },
     validation::{error::ValidationError, DifficultyCalculator},
 };
 use tari_common_types::chain_metadata::ChainMetadata;
+use tari_crypto::tari_utilities::epoch_time::EpochTime;
 
 /// A validator that determines if a block body is valid, assuming that the header has already been
 /// validated
 pub trait CandidateBlockBodyValidation<B: BlockchainBackend>: Send + Sync {
     fn validate_body(&self, block: &Block, backend: &B) -> Result<(), ValidationError>;
+
+    /// Starts validating the body of the block with the given header as the body is received, a part at a time. The
+    /// header must build on the current tip.
+    fn begin_body(
+        &self,
+        header: &BlockHeader,
+        backend: &B,
+    ) -> Result<Box<dyn BlockBodyPartValidation<B>>, ValidationError>;
+}
+
+/// Validates the body of a single block as it is received. The kernels are validated first, then the outputs and then
+/// the inputs, each in the order that they appear in the body.
+pub trait BlockBodyPartValidation<B: BlockchainBackend>: Send {
+    fn validate_kernels(&mut self, kernels: &[TransactionKernel]) -> Result<(), ValidationError>;
+
+    /// `backend` is used to look up the unspent outputs of the chain that carry the unique ids of `outputs`
+    fn validate_outputs(&mut self, outputs: &[TransactionOutput], backend: &B) -> Result<(), ValidationError>;
+
+    /// `outputs` are all of the outputs of the block, which the covenants of the inputs are checked against
+    fn validate_inputs(
+        &mut self,
+        inputs: &[TransactionInput],
+        outputs: &[TransactionOutput],
+        backend: &B,
+    ) -> Result<(), ValidationError>;
+
+    /// Completes the validation once the whole body has been validated
+    fn finalize(self: Box<Self>) -> Result<(), ValidationError>;
 }
 
 /// A validator that validates a body after it has been determined to be a valid orphan
@@ -63,6 +95,12 @@ pub trait HeaderValidation<TBackend: BlockchainBackend>: Send + Sync {
     ) -> Result<AchievedTargetDifficulty, ValidationError>;
 }
 
+/// A validator for the timestamp of a block header. `prev_timestamps` are the timestamps of the headers preceding it,
+/// oldest first, and contain at least the median timestamp window of the header's height if there are enough headers.
+pub trait HeaderTimestampValidation: Send + Sync {
+    fn validate_timestamp(&self, header: &BlockHeader, prev_timestamps: &[EpochTime]) -> Result<(), ValidationError>;
+}
+
 pub trait FinalHorizonStateValidation<B>: Send + Sync {
     fn validate(
         &self,
@@ -21,10 +21,13 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    chain_storage::{BlockchainBackend, BlockchainDatabase, MmrTree},
+    chain_storage::{BlockchainBackend, BlockchainDatabase, DeletedBitmap, MmrTree},
     crypto::tari_utilities::Hashable,
-    transactions::{transaction::Transaction, types::CryptoFactories},
-    validation::{MempoolTransactionValidation, ValidationError},
+    transactions::{
+        transaction::Transaction,
+        types::{CryptoFactories, HashOutput},
+    },
+    validation::{helpers::check_unique_ids_are_unspent, MempoolTransactionValidation, ValidationError},
 };
 use log::*;
 
@@ -73,7 +76,9 @@ impl<B: BlockchainBackend> MempoolTransactionValidation for TxConsensusValidator
     fn validate(&self, tx: &Transaction) -> Result<(), ValidationError> {
         let consensus_constants = self.db.consensus_constants()?;
         // validate maximum tx weight
-        if tx.calculate_weight() > consensus_constants.get_max_block_weight_excluding_coinbase() {
+        if tx.calculate_weight(consensus_constants.transaction_weight()) >
+            consensus_constants.get_max_block_weight_excluding_coinbase()
+        {
             return Err(ValidationError::MaxTransactionWeightExceeded);
         }
 
@@ -82,7 +87,8 @@ impl<B: BlockchainBackend> MempoolTransactionValidation for TxConsensusValidator
 }
 
 /// This validator assumes that the transaction was already validated and it will skip this step. It will only check, in
-/// order,: All inputs exist in the backend, All timelocks (kernel lock heights and output maturities) have passed
+/// order,: All inputs exist in the backend, All timelocks (kernel lock heights and output maturities) have passed, No
+/// unique id of the outputs is carried by an unspent output that the transaction does not spend
 #[derive(Clone)]
 pub struct TxInputAndMaturityValidator<B> {
     db: BlockchainDatabase<B>,
@@ -97,12 +103,21 @@ impl<B: BlockchainBackend> TxInputAndMaturityValidator<B> {
 impl<B: BlockchainBackend> MempoolTransactionValidation for TxInputAndMaturityValidator<B> {
     fn validate(&self, tx: &Transaction) -> Result<(), ValidationError> {
         let db = self.db.db_read_access()?;
-        verify_not_stxos(tx, &*db)?;
+        let deleted = db.fetch_deleted_bitmap()?;
+        let unknown_inputs = verify_not_stxos(tx, &*db, &deleted)?;
         check_not_duplicate_txos(tx, &*db)?;
+        check_unique_ids_are_unspent(&tx.body, &*db, deleted.bitmap())?;
 
         let tip_height = db.fetch_chain_metadata()?.height_of_longest_chain();
         verify_timelocks(tx, tip_height)?;
+        verify_covenants(tx, tip_height)?;
         verify_no_duplicated_inputs_outputs(tx)?;
+
+        // Unknown inputs are reported last so that a transaction spending unconfirmed outputs has passed every other
+        // check before the mempool looks for its parents
+        if !unknown_inputs.is_empty() {
+            return Err(ValidationError::UnknownInputs(unknown_inputs));
+        }
         Ok(())
     }
 }
@@ -116,9 +131,20 @@ fn verify_timelocks(tx: &Transaction, current_height: u64) -> Result<(), Validat
     Ok(())
 }
 
-// This function checks that the inputs exists in the UTXO set but do not exist in the STXO set.
-fn verify_not_stxos<B: BlockchainBackend>(tx: &Transaction, db: &B) -> Result<(), ValidationError> {
-    let deleted = db.fetch_deleted_bitmap()?;
+// This function checks that the transaction satisfies the covenants of its inputs if it is mined in the next block
+fn verify_covenants(tx: &Transaction, current_height: u64) -> Result<(), ValidationError> {
+    tx.body
+        .check_covenants(current_height + 1)
+        .map_err(ValidationError::TransactionError)
+}
+
+// This function checks that the inputs exists in the UTXO set but do not exist in the STXO set. The hashes of inputs
+// that are not found are returned.
+fn verify_not_stxos<B: BlockchainBackend>(
+    tx: &Transaction,
+    db: &B,
+    deleted: &DeletedBitmap,
+) -> Result<Vec<HashOutput>, ValidationError> {
     let mut not_found_input = Vec::new();
     for input in tx.body.inputs() {
         if let Some((_, index, _height)) = db.fetch_output(&input.output_hash())? {
@@ -142,11 +168,7 @@ fn verify_not_stxos<B: BlockchainBackend>(tx: &Transaction, db: &B) -> Result<()
             not_found_input.push(input.output_hash());
         }
     }
-    if !not_found_input.is_empty() {
-        return Err(ValidationError::UnknownInputs(not_found_input));
-    }
-
-    Ok(())
+    Ok(not_found_input)
 }
 
 // This function checks that the inputs and outputs do not exist in the STxO set.
@@ -188,9 +210,19 @@ impl MempoolValidator {
 
 impl MempoolTransactionValidation for MempoolValidator {
     fn validate(&self, transaction: &Transaction) -> Result<(), ValidationError> {
+        let mut unknown_inputs = None;
         for v in &self.validators {
-            v.validate(transaction)?;
+            match v.validate(transaction) {
+                Ok(()) => {},
+                // A transaction spending the outputs of unconfirmed transactions may still be accepted into the
+                // mempool with its parents, so the remaining validators are run before reporting the unknown inputs
+                Err(ValidationError::UnknownInputs(inputs)) => unknown_inputs = Some(inputs),
+                Err(err) => return Err(err),
+            }
+        }
+        match unknown_inputs {
+            Some(inputs) => Err(ValidationError::UnknownInputs(inputs)),
+            None => Ok(()),
         }
-        Ok(())
     }
 }
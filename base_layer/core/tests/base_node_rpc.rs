@@ -50,10 +50,11 @@ use crate::helpers::{
 };
 use std::convert::TryFrom;
 use tari_common::configuration::Network;
-use tari_comms::protocol::rpc::mock::RpcRequestMock;
+use tari_comms::protocol::rpc::{mock::RpcRequestMock, RpcStatusCode};
 use tari_core::{
     base_node::{
         comms_interface::Broadcast,
+        fee_estimation_service::MAX_TARGET_BLOCKS,
         proto::wallet_rpc::{
             TxLocation,
             TxQueryBatchResponse,
@@ -67,6 +68,7 @@ use tari_core::{
     chain_storage::ChainBlock,
     consensus::{ConsensusManager, ConsensusManagerBuilder, NetworkConsensus},
     crypto::tari_utilities::Hashable,
+    mempool::RelayPolicyConfig,
     proto::{
         base_node::{FetchMatchingUtxos, Signatures as SignaturesProto},
         types::{Signature as SignatureProto, Transaction as TransactionProto},
@@ -118,6 +120,7 @@ fn setup() -> (
         base_node.blockchain_db.clone().into(),
         base_node.mempool_handle.clone(),
         base_node.state_machine_handle.clone(),
+        base_node.fee_estimation_handle.clone(),
     );
     (
         service,
@@ -276,11 +279,15 @@ fn test_base_node_wallet_rpc() {
         .unwrap()
         .into_message();
 
+    assert_eq!(response.responses.len(), 2);
+    assert_eq!(response.height_of_longest_chain, 2);
     for r in response.responses {
         let response = TxQueryBatchResponse::try_from(r).unwrap();
 
         if response.signature == tx1_sig {
             assert_eq!(response.location, TxLocation::Mined);
+            assert_eq!(response.confirmations, 1);
+            assert_eq!(response.block_height, 1);
         } else {
             assert_eq!(response.location, TxLocation::InMempool);
         }
@@ -313,3 +320,30 @@ fn test_base_node_wallet_rpc() {
             .any(|u| u.as_transaction_output(&factories).unwrap().commitment == output.commitment));
     }
 }
+
+#[test]
+fn test_estimate_fee_per_gram() {
+    let (service, _base_node, request_mock, _consensus_manager, _block0, _utxo0, mut runtime, _temp_dir) = setup();
+
+    let req = request_mock.request_with_context(Default::default(), 0u64);
+    let err = runtime.block_on(service.estimate_fee_per_gram(req)).unwrap_err();
+    assert_eq!(err.status_code(), RpcStatusCode::BadRequest);
+
+    let req = request_mock.request_with_context(Default::default(), MAX_TARGET_BLOCKS + 1);
+    let err = runtime.block_on(service.estimate_fee_per_gram(req)).unwrap_err();
+    assert_eq!(err.status_code(), RpcStatusCode::BadRequest);
+
+    // With an empty mempool and no full blocks the estimate is the minimum relay fee
+    let req = request_mock.request_with_context(Default::default(), 3u64);
+    let resp = runtime
+        .block_on(service.estimate_fee_per_gram(req))
+        .unwrap()
+        .into_message();
+    assert_eq!(resp.target_blocks, 3);
+    assert_eq!(resp.mempool_weight, 0);
+    assert_eq!(
+        resp.fee_per_gram,
+        RelayPolicyConfig::default().min_fee_per_gram.as_u64()
+    );
+    assert!(resp.is_synced);
+}
@@ -209,6 +209,7 @@ fn inputs_are_not_malleable() {
         script: spent_output.script.clone(),
         input_data: Some(inputs![malicious_script_public_key]),
         output_features: spent_output.features,
+        covenant: spent_output.covenant,
     });
 
     let input_mut = block.body.inputs_mut().get_mut(0).unwrap();
@@ -227,3 +228,80 @@ fn inputs_are_not_malleable() {
         ValidationError::BlockError(BlockValidationError::MismatchedMmrRoots)
     ));
 }
+
+#[test]
+fn block_validator_checks_body_in_a_single_pass() {
+    let mut blockchain = TestBlockchain::with_genesis("GB");
+    let blocks = blockchain.builder();
+
+    let (_, output) = blockchain.add_block(blocks.new_block("A1").child_of("GB").difficulty(1));
+
+    let (txs, _) = schema_to_transaction(&[txn_schema!(from: vec![output], to: vec![50 * T])]);
+    let txs = txs.into_iter().map(|tx| Clone::clone(&*tx)).collect();
+    blockchain.add_block(
+        blocks
+            .new_block("A2")
+            .child_of("A1")
+            .difficulty(1)
+            .with_transactions(txs),
+    );
+    let mut block = blockchain.get_block("A2").cloned().unwrap().block.block().clone();
+    blockchain.store().rewind_to_height(block.header.height - 1).unwrap();
+
+    let validator = BlockValidator::new(blockchain.consensus_manager().clone(), CryptoFactories::default());
+    validator
+        .validate_body(&block, &*blockchain.store().db_read_access().unwrap())
+        .unwrap();
+
+    assert!(block.body.outputs().len() > 1);
+    block.body.outputs_mut().swap(0, 1);
+    let err = validator
+        .validate_body(&block, &*blockchain.store().db_read_access().unwrap())
+        .unwrap_err();
+    assert!(matches!(err, ValidationError::UnsortedOrDuplicateOutput));
+}
+
+#[test]
+fn block_validator_checks_body_a_part_at_a_time() {
+    let mut blockchain = TestBlockchain::with_genesis("GB");
+    let blocks = blockchain.builder();
+
+    let (_, output) = blockchain.add_block(blocks.new_block("A1").child_of("GB").difficulty(1));
+
+    let (txs, _) = schema_to_transaction(&[txn_schema!(from: vec![output], to: vec![50 * T])]);
+    let txs = txs.into_iter().map(|tx| Clone::clone(&*tx)).collect();
+    blockchain.add_block(
+        blocks
+            .new_block("A2")
+            .child_of("A1")
+            .difficulty(1)
+            .with_transactions(txs),
+    );
+    let block = blockchain.get_block("A2").cloned().unwrap().block.block().clone();
+    blockchain.store().rewind_to_height(block.header.height - 1).unwrap();
+
+    let validator = BlockValidator::new(blockchain.consensus_manager().clone(), CryptoFactories::default());
+    let db = blockchain.store().db_read_access().unwrap();
+    let mut parts = validator.begin_body(&block.header, &*db).unwrap();
+    parts.validate_kernels(block.body.kernels()).unwrap();
+    assert!(block.body.outputs().len() > 1);
+    for output in block.body.outputs() {
+        parts.validate_outputs(std::slice::from_ref(output), &*db).unwrap();
+    }
+    parts
+        .validate_inputs(block.body.inputs(), block.body.outputs(), &*db)
+        .unwrap();
+    parts.finalize().unwrap();
+
+    // Kernels may not be sent after the outputs
+    let mut parts = validator.begin_body(&block.header, &*db).unwrap();
+    parts.validate_outputs(block.body.outputs(), &*db).unwrap();
+    assert!(parts.validate_kernels(block.body.kernels()).is_err());
+
+    // Outputs must be sorted across parts
+    let mut parts = validator.begin_body(&block.header, &*db).unwrap();
+    parts.validate_kernels(block.body.kernels()).unwrap();
+    parts.validate_outputs(&block.body.outputs()[1..2], &*db).unwrap();
+    let err = parts.validate_outputs(&block.body.outputs()[0..1], &*db).unwrap_err();
+    assert!(matches!(err, ValidationError::UnsortedOrDuplicateOutput));
+}
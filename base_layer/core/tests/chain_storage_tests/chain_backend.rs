@@ -23,7 +23,17 @@
 use crate::helpers::database::create_orphan_block;
 use tari_common::configuration::Network;
 use tari_core::{
-    chain_storage::{create_lmdb_database, BlockchainBackend, ChainStorageError, DbKey, DbTransaction, DbValue},
+    chain_storage::{
+        create_lmdb_database,
+        dry_run_lmdb_migrations,
+        BlockchainBackend,
+        ChainStorageError,
+        DbKey,
+        DbTransaction,
+        DbValue,
+        LMDB_DB_ORPHANS,
+        LMDB_DB_SCHEMA_VERSION,
+    },
     consensus::ConsensusManagerBuilder,
     test_helpers::blockchain::create_test_db,
     tx,
@@ -320,3 +330,87 @@ fn lmdb_file_lock() {
         }
     }
 }
+
+#[test]
+fn lmdb_compaction() {
+    let temp_path = create_temporary_data_path();
+    {
+        let network = Network::LocalNet;
+        let consensus = ConsensusManagerBuilder::new(network).build();
+        let mut db = create_lmdb_database(&temp_path, LMDBConfig::default()).unwrap();
+        let orphans = (0..20)
+            .map(|i| {
+                let txs = vec![(tx!(1000.into(), fee: 20.into(), inputs: 2, outputs: 1)).0];
+                create_orphan_block(i, txs, &consensus)
+            })
+            .collect::<Vec<_>>();
+        let mut txn = DbTransaction::new();
+        for orphan in &orphans {
+            txn.insert_orphan(orphan.clone().into());
+        }
+        db.write(txn).unwrap();
+        let mut txn = DbTransaction::new();
+        for orphan in &orphans[1..] {
+            txn.delete_orphan(orphan.hash());
+        }
+        db.write(txn).unwrap();
+
+        let stats = db.get_storage_stats().unwrap();
+        assert!(stats.free_pages > 0);
+        let orphans_stats = stats.databases.iter().find(|db| db.name == LMDB_DB_ORPHANS).unwrap();
+        assert_eq!(orphans_stats.entries, 1);
+
+        let copy = db.create_compacted_copy().unwrap();
+        let result = db.swap_compacted_copy(copy).unwrap();
+        assert!(result.size_after_bytes < result.size_before_bytes);
+        assert!(db.contains(&DbKey::OrphanBlock(orphans[0].hash())).unwrap());
+        assert!(!db.contains(&DbKey::OrphanBlock(orphans[1].hash())).unwrap());
+
+        // A copy is discarded if the database is written to before it is swapped in
+        let copy = db.create_compacted_copy().unwrap();
+        let mut txn = DbTransaction::new();
+        txn.delete_orphan(orphans[0].hash());
+        db.write(txn).unwrap();
+        assert!(db.swap_compacted_copy(copy).is_err());
+        assert!(!db.contains(&DbKey::OrphanBlock(orphans[0].hash())).unwrap());
+    }
+
+    if std::path::Path::new(&temp_path).exists() {
+        if let Err(e) = std::fs::remove_dir_all(&temp_path) {
+            println!("\n{:?}\n", e)
+        }
+    }
+}
+
+#[test]
+fn lmdb_migrations() {
+    let temp_path = create_temporary_data_path();
+    {
+        let db = create_lmdb_database(&temp_path, LMDBConfig::default()).unwrap();
+        let report = db.plan_migrations().unwrap();
+        assert!(report.is_up_to_date());
+        assert_eq!(report.from_version, LMDB_DB_SCHEMA_VERSION);
+
+        // A dry run needs the exclusive file lock
+        match dry_run_lmdb_migrations(&temp_path, LMDBConfig::default()) {
+            Err(ChainStorageError::CannotAcquireFileLock) => {},
+            _ => panic!("Should not be able to dry run migrations on an open database"),
+        }
+        drop(db);
+
+        let report = dry_run_lmdb_migrations(&temp_path, LMDBConfig::default()).unwrap();
+        assert!(report.is_dry_run);
+        assert!(report.is_up_to_date());
+        assert!(report.backup_path.is_none());
+        assert_eq!(report.to_version, LMDB_DB_SCHEMA_VERSION);
+
+        let db = create_lmdb_database(&temp_path, LMDBConfig::default()).unwrap();
+        assert!(db.plan_migrations().unwrap().is_up_to_date());
+    }
+
+    if std::path::Path::new(&temp_path).exists() {
+        if let Err(e) = std::fs::remove_dir_all(&temp_path) {
+            println!("\n{:?}\n", e)
+        }
+    }
+}
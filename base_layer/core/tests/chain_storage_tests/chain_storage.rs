@@ -142,6 +142,28 @@ fn store_and_retrieve_block() {
     assert_eq!(blocks[0].block(), &block0);
 }
 
+#[test]
+fn existence_filters_skip_absent_keys() {
+    let (db, blocks, _, _) = create_new_blockchain(Network::LocalNet);
+    let output = blocks[0].block().body.outputs()[0].clone();
+    let kernel = blocks[0].block().body.kernels()[0].clone();
+    let stats_before = db.db_read_access().unwrap().get_existence_filter_stats();
+
+    assert!(db.fetch_utxo(output.hash()).unwrap().is_some());
+    assert!(db.fetch_utxo(vec![0u8; 32]).unwrap().is_none());
+    assert!(db.fetch_kernel_by_excess_sig(kernel.excess_sig).unwrap().is_some());
+
+    let stats = db.db_read_access().unwrap().get_existence_filter_stats();
+    let (outputs_before, outputs) = (&stats_before[0], &stats[0]);
+    assert!(outputs.num_keys >= 1);
+    assert_eq!(outputs.lookups - outputs_before.lookups, 2);
+    let absent_before = outputs_before.definitely_absent + outputs_before.false_positives;
+    assert_eq!(outputs.definitely_absent + outputs.false_positives - absent_before, 1);
+    let (kernels_before, kernels) = (&stats_before[1], &stats[1]);
+    assert_eq!(kernels.lookups - kernels_before.lookups, 1);
+    assert_eq!(kernels.definitely_absent, kernels_before.definitely_absent);
+}
+
 #[test]
 fn add_multiple_blocks() {
     // Create new database with genesis block
@@ -253,6 +275,7 @@ fn rewind_past_horizon_height() {
         orphan_storage_capacity: 3,
         pruning_horizon: 2,
         pruning_interval: 2,
+        ..Default::default()
     };
     let store = BlockchainDatabase::new(
         db,
@@ -1287,6 +1310,7 @@ fn orphan_cleanup_on_block_add() {
         orphan_storage_capacity: 3,
         pruning_horizon: 0,
         pruning_interval: 50,
+        ..Default::default()
     };
     let store = BlockchainDatabase::new(
         db,
@@ -1356,6 +1380,7 @@ fn horizon_height_orphan_cleanup() {
         orphan_storage_capacity: 3,
         pruning_horizon: 2,
         pruning_interval: 50,
+        ..Default::default()
     };
     let store = BlockchainDatabase::new(
         db,
@@ -1421,6 +1446,7 @@ fn orphan_cleanup_on_reorg() {
         orphan_storage_capacity: 3,
         pruning_horizon: 0,
         pruning_interval: 50,
+        ..Default::default()
     };
     let mut store = BlockchainDatabase::new(
         db,
@@ -1559,6 +1585,7 @@ fn orphan_cleanup_delete_all_orphans() {
         orphan_storage_capacity: 5,
         pruning_horizon: 0,
         pruning_interval: 50,
+        ..Default::default()
     };
     // Test cleanup during runtime
     {
@@ -1672,6 +1699,7 @@ fn fails_validation() {
         orphan_storage_capacity: 3,
         pruning_horizon: 0,
         pruning_interval: 50,
+        ..Default::default()
     };
     let mut store = BlockchainDatabase::new(
         db,
@@ -1717,6 +1745,7 @@ fn pruned_mode_cleanup_and_fetch_block() {
         orphan_storage_capacity: 3,
         pruning_horizon: 3,
         pruning_interval: 1,
+        ..Default::default()
     };
     let store = BlockchainDatabase::new(
         db,
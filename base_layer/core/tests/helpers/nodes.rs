@@ -35,6 +35,7 @@ use tari_comms_dht::{outbound::OutboundMessageRequester, Dht};
 use tari_core::{
     base_node::{
         chain_metadata_service::{ChainMetadataHandle, ChainMetadataServiceInitializer},
+        fee_estimation_service::{FeeEstimationConfig, FeeEstimationHandle, FeeEstimationServiceInitializer},
         service::{BaseNodeServiceConfig, BaseNodeServiceInitializer},
         LocalNodeCommsInterface,
         OutboundNodeCommsInterface,
@@ -80,6 +81,7 @@ pub struct NodeInterfaces {
     pub mempool_handle: MempoolHandle,
     pub local_mp_interface: LocalMempoolService,
     pub chain_metadata_handle: ChainMetadataHandle,
+    pub fee_estimation_handle: FeeEstimationHandle,
     pub liveness_handle: LivenessHandle,
     pub comms: CommsNode,
     pub mock_base_node_state_machine: MockBaseNodeStateMachine,
@@ -196,7 +198,11 @@ impl BaseNodeBuilder {
             .unwrap_or_else(|| ConsensusManagerBuilder::new(network).build());
         let blockchain_db = create_store_with_consensus_and_validators(consensus_manager.clone(), validators);
         let mempool_validator = TxInputAndMaturityValidator::new(blockchain_db.clone());
-        let mempool = Mempool::new(self.mempool_config.unwrap_or_default(), Arc::new(mempool_validator));
+        let mempool = Mempool::new(
+            self.mempool_config.unwrap_or_default(),
+            consensus_manager.clone(),
+            Arc::new(mempool_validator),
+        );
         let node_identity = self.node_identity.unwrap_or_else(|| random_node_identity());
         let node_interfaces = setup_base_node_services(
             runtime,
@@ -423,7 +429,7 @@ fn setup_base_node_services(
             subscription_factory.clone(),
             blockchain_db.clone().into(),
             mempool.clone(),
-            consensus_manager,
+            consensus_manager.clone(),
             base_node_service_config,
         ))
         .add_initializer(MempoolServiceInitializer::new(
@@ -433,6 +439,10 @@ fn setup_base_node_services(
         ))
         .add_initializer(mock_state_machine.get_initializer())
         .add_initializer(ChainMetadataServiceInitializer)
+        .add_initializer(FeeEstimationServiceInitializer::new(
+            FeeEstimationConfig::default(),
+            consensus_manager,
+        ))
         .build();
 
     let handles = runtime.block_on(fut).expect("Service initialization failed");
@@ -444,6 +454,7 @@ fn setup_base_node_services(
     let mempool_handle = handles.expect_handle::<MempoolHandle>();
     let outbound_message_service = handles.expect_handle::<Dht>().outbound_requester();
     let chain_metadata_handle = handles.expect_handle::<ChainMetadataHandle>();
+    let fee_estimation_handle = handles.expect_handle::<FeeEstimationHandle>();
     let liveness_handle = handles.expect_handle::<LivenessHandle>();
     let state_machine_handle = handles.expect_handle::<StateMachineHandle>();
 
@@ -458,6 +469,7 @@ fn setup_base_node_services(
         local_mp_interface,
         mempool_handle,
         chain_metadata_handle,
+        fee_estimation_handle,
         liveness_handle,
         comms,
         messaging_events,
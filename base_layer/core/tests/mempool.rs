@@ -47,20 +47,32 @@ use tari_core::{
         state_machine_service::states::{ListeningInfo, StateInfo, StatusInfo},
     },
     consensus::{ConsensusConstantsBuilder, ConsensusManager, NetworkConsensus},
-    mempool::{Mempool, MempoolConfig, MempoolServiceConfig, MempoolServiceError, TxStorageResponse},
+    mempool::{
+        Mempool,
+        MempoolConfig,
+        MempoolServiceConfig,
+        MempoolServiceError,
+        RelayPolicyConfig,
+        RelayPolicyViolation,
+        TxStorageResponse,
+    },
     proof_of_work::Difficulty,
     proto,
     transactions::{
         fee::Fee,
-        helpers::{create_unblinded_output, schema_to_transaction, spend_utxos, TestParams},
+        helpers::{create_tx, create_unblinded_output, schema_to_transaction, spend_utxos, TestParams},
         tari_amount::{uT, MicroTari, T},
         transaction::{KernelBuilder, OutputFeatures, Transaction, TransactionOutput},
         transaction_protocol::{build_challenge, TransactionMetadata},
         types::{Commitment, CryptoFactories, PrivateKey, PublicKey, Signature},
+        weight::TransactionWeight,
     },
     tx,
     txn_schema,
-    validation::transaction_validators::{TxConsensusValidator, TxInputAndMaturityValidator},
+    validation::{
+        mocks::MockValidator,
+        transaction_validators::{TxConsensusValidator, TxInputAndMaturityValidator},
+    },
 };
 use tari_crypto::script;
 use tari_p2p::{services::liveness::LivenessConfig, tari_message::TariMessageType};
@@ -74,7 +86,11 @@ fn test_insert_and_process_published_block() {
     let network = Network::LocalNet;
     let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
     let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager.clone(),
+        Arc::new(mempool_validator),
+    );
     // Create a block with 4 outputs
     let txs = vec![txn_schema!(
         from: vec![outputs[0][0].clone()],
@@ -201,13 +217,77 @@ fn test_insert_and_process_published_block() {
     assert_eq!(stats.total_weight, 30);
 }
 
+#[test]
+#[allow(clippy::identity_op)]
+fn test_relay_policy() {
+    let network = Network::LocalNet;
+    let (store, _, outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store);
+    let config = MempoolConfig {
+        relay_policy: RelayPolicyConfig {
+            min_fee_per_gram: 25 * uT,
+            max_outputs: 2,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mempool = Mempool::new(config, consensus_manager, Arc::new(mempool_validator));
+
+    let tx1 = txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T], fee: 5 * uT, lock: 0, features: OutputFeatures::default());
+    let tx1 = Arc::new(spend_utxos(tx1).0);
+    assert_eq!(
+        mempool.insert(tx1).unwrap(),
+        TxStorageResponse::NotStoredPolicyViolation(RelayPolicyViolation::FeePerGramTooLow)
+    );
+
+    let tx2 = txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T, 1 * T], fee: 30 * uT, lock: 0, features: OutputFeatures::default());
+    let tx2 = Arc::new(spend_utxos(tx2).0);
+    assert_eq!(
+        mempool.insert(tx2).unwrap(),
+        TxStorageResponse::NotStoredPolicyViolation(RelayPolicyViolation::TooManyOutputs)
+    );
+
+    let tx3 = txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T], fee: 30 * uT, lock: 0, features: OutputFeatures::default());
+    let tx3 = Arc::new(spend_utxos(tx3).0);
+    assert_eq!(mempool.insert(tx3).unwrap(), TxStorageResponse::UnconfirmedPool);
+}
+
+#[test]
+#[allow(clippy::identity_op)]
+fn test_relay_policy_update() {
+    let network = Network::LocalNet;
+    let (store, _, outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store);
+    let mempool = Mempool::new(MempoolConfig::default(), consensus_manager, Arc::new(mempool_validator));
+
+    let tx1 = txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T, 1 * T], fee: 30 * uT, lock: 0, features: OutputFeatures::default());
+    let tx1 = Arc::new(spend_utxos(tx1).0);
+    mempool
+        .set_relay_policy(RelayPolicyConfig {
+            max_outputs: 2,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(
+        mempool.insert(tx1.clone()).unwrap(),
+        TxStorageResponse::NotStoredPolicyViolation(RelayPolicyViolation::TooManyOutputs)
+    );
+
+    mempool.set_relay_policy(RelayPolicyConfig::default()).unwrap();
+    assert_eq!(mempool.insert(tx1).unwrap(), TxStorageResponse::UnconfirmedPool);
+}
+
 #[test]
 #[allow(clippy::identity_op)]
 fn test_time_locked() {
     let network = Network::LocalNet;
     let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
     let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager.clone(),
+        Arc::new(mempool_validator),
+    );
     // Create a block with 4 outputs
     let txs = vec![txn_schema!(
         from: vec![outputs[0][0].clone()],
@@ -251,7 +331,11 @@ fn test_retrieve() {
     let network = Network::LocalNet;
     let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
     let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager.clone(),
+        Arc::new(mempool_validator),
+    );
     let txs = vec![txn_schema!(
         from: vec![outputs[0][0].clone()],
         to: vec![1 * T, 1 * T, 1 * T, 1 * T, 1 * T, 1 * T, 1 * T]
@@ -279,7 +363,9 @@ fn test_retrieve() {
         mempool.insert(t.clone()).unwrap();
     });
     // 1-block, 8 UTXOs, 8 txs in mempool
-    let weight = tx[6].calculate_weight() + tx[2].calculate_weight() + tx[3].calculate_weight();
+    let weight = tx[6].calculate_weight(&TransactionWeight::latest()) +
+        tx[2].calculate_weight(&TransactionWeight::latest()) +
+        tx[3].calculate_weight(&TransactionWeight::latest());
     let retrieved_txs = mempool.retrieve(weight).unwrap();
     assert_eq!(retrieved_txs.len(), 3);
     assert!(retrieved_txs.contains(&tx[6]));
@@ -319,7 +405,8 @@ fn test_retrieve() {
     // 2 blocks, 3 unconfirmed txs in mempool, 2 time locked
 
     // Top 2 txs are tx[3] (fee/g = 50) and tx2[1] (fee/g = 40). tx2[0] (fee/g = 80) is still not matured.
-    let weight = tx[3].calculate_weight() + tx2[1].calculate_weight();
+    let weight =
+        tx[3].calculate_weight(&TransactionWeight::latest()) + tx2[1].calculate_weight(&TransactionWeight::latest());
     let retrieved_txs = mempool.retrieve(weight).unwrap();
     let stats = mempool.stats().unwrap();
 
@@ -331,13 +418,93 @@ fn test_retrieve() {
     assert!(retrieved_txs.contains(&tx2[1]));
 }
 
+#[test]
+#[allow(clippy::identity_op)]
+fn test_child_pays_for_parent() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager.clone(),
+        Arc::new(mempool_validator),
+    );
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![10 * T, 10 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // The parent was sent with a very low fee and the child spends its output with a high fee
+    let (parent, parent_out, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][0].clone()],
+        to: vec![5 * T],
+        fee: 1*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (other, _, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][1].clone()],
+        to: vec![5 * T],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let mut locked_child = txn_schema!(
+        from: vec![parent_out[0].clone()],
+        to: vec![1 * T],
+        fee: 100*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    );
+    locked_child.lock_height = 100;
+    let (locked_child, _, _) = spend_utxos(locked_child);
+    let (child, _, _) = spend_utxos(txn_schema!(
+        from: vec![parent_out[0].clone()],
+        to: vec![1 * T],
+        fee: 100*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let parent = Arc::new(parent);
+    let child = Arc::new(child);
+
+    assert_eq!(
+        mempool.insert(parent.clone()).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    assert_eq!(
+        mempool.insert(Arc::new(other)).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    // The child is still fully validated even though its input is not in the blockchain
+    assert_eq!(
+        mempool.insert(Arc::new(locked_child)).unwrap(),
+        TxStorageResponse::NotStoredTimeLocked
+    );
+    assert_eq!(
+        mempool.insert(child.clone()).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+
+    // The package has a higher fee per gram than the other transaction, so it is selected first
+    let weight =
+        parent.calculate_weight(&TransactionWeight::latest()) + child.calculate_weight(&TransactionWeight::latest());
+    let retrieved_txs = mempool.retrieve(weight).unwrap();
+    assert_eq!(retrieved_txs.len(), 2);
+    assert!(retrieved_txs.contains(&parent));
+    assert!(retrieved_txs.contains(&child));
+}
+
 #[test]
 #[allow(clippy::identity_op)]
 fn test_zero_conf() {
     let network = Network::LocalNet;
     let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
     let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager.clone(),
+        Arc::new(mempool_validator),
+    );
     let txs = vec![txn_schema!(
         from: vec![outputs[0][0].clone()],
         to: vec![21 * T, 11 * T, 11 * T, 16 * T]
@@ -637,7 +804,11 @@ fn test_reorg() {
     let network = Network::LocalNet;
     let (mut db, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
     let mempool_validator = TxInputAndMaturityValidator::new(db.clone());
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager.clone(),
+        Arc::new(mempool_validator),
+    );
 
     // "Mine" Block 1
     let txs = vec![
@@ -951,7 +1122,11 @@ fn consensus_validation_large_tx() {
     let (mut store, mut blocks, mut outputs, consensus_manager) =
         create_new_blockchain_with_constants(network, consensus_constants);
     let mempool_validator = TxConsensusValidator::new(store.clone());
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager.clone(),
+        Arc::new(mempool_validator),
+    );
     // Create a block with 1 output
     let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![5 * T])];
     generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
@@ -1030,7 +1205,7 @@ fn consensus_validation_large_tx() {
     // make sure the tx was correctly made and is valid
     let factories = CryptoFactories::default();
     assert!(tx.validate_internal_consistency(&factories, None).is_ok());
-    let weight = tx.calculate_weight();
+    let weight = tx.calculate_weight(&TransactionWeight::latest());
 
     let height = blocks.len() as u64;
     let constants = consensus_manager.consensus_constants(height);
@@ -1042,6 +1217,31 @@ fn consensus_validation_large_tx() {
     assert!(matches!(response, TxStorageResponse::NotStored));
 }
 
+#[test]
+fn test_mempool_weight_follows_consensus_rules() {
+    // Ridcully still weighs transactions with the V1 rules
+    let consensus_manager = ConsensusManager::builder(Network::Ridcully).build();
+    let weighting = *consensus_manager.consensus_constants(1).transaction_weight();
+    assert_eq!(weighting, TransactionWeight::V1);
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager,
+        Arc::new(MockValidator::new(true)),
+    );
+
+    let (tx, _, _) = create_tx(5000 * uT, 15 * uT, 1, 2, 1, 4);
+    let v1_weight = tx.calculate_weight(&TransactionWeight::V1);
+    assert_ne!(v1_weight, tx.calculate_weight(&TransactionWeight::V2));
+    assert_eq!(
+        mempool.insert(Arc::new(tx)).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+
+    let stats = mempool.stats().unwrap();
+    assert_eq!(stats.unconfirmed_txs, 1);
+    assert_eq!(stats.total_weight, v1_weight);
+}
+
 #[test]
 fn service_request_timeout() {
     let mut runtime = Runtime::new().unwrap();
@@ -566,6 +566,7 @@ fn service_request_timeout() {
         fetch_blocks_timeout: Default::default(),
         fetch_utxos_timeout: Default::default(),
         desired_response_fraction: Default::default(),
+        ..Default::default()
     };
     let temp_dir = tempdir().unwrap();
     let (mut alice_node, bob_node, _consensus_manager) = create_network_with_2_base_nodes_with_config(
@@ -0,0 +1,205 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Deterministic derivation of signing nonces, for signers that do not have a good source of entropy.
+//!
+//! Nonces are generated with the HMAC-DRBG construction of [RFC 6979](https://tools.ietf.org/html/rfc6979) section
+//! 3.2, using the order of the Ristretto group as `q`. The nonce is a function of the secret key and the message only,
+//! so the message MUST commit to everything that the signature challenge commits to. Signing two different challenges
+//! with the same nonce reveals the secret key.
+
+use digest::{generic_array::typenum::Unsigned, BlockInput, Digest};
+use tari_crypto::{ristretto::RistrettoSecretKey, tari_utilities::ByteArray};
+
+/// The order of the Ristretto group, big-endian
+const GROUP_ORDER: [u8; 32] = [
+    0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x14, 0xde, 0xf9,
+    0xde, 0xa2, 0xf7, 0x9c, 0xd6, 0x58, 0x12, 0x63, 0x1a, 0x5c, 0xf5, 0xd3, 0xed,
+];
+/// The bit length of the group order (`qlen` in RFC 6979)
+const GROUP_ORDER_BITS: usize = 253;
+/// The byte length of scalars (`rlen / 8` in RFC 6979)
+const SCALAR_BYTES: usize = 32;
+
+/// Derives the nonce for signing `message` with `secret_key`, following RFC 6979 with the digest `D` (e.g. SHA-256).
+/// The same key and message always result in the same nonce.
+pub fn derive_nonce<D: Digest + BlockInput>(secret_key: &RistrettoSecretKey, message: &[u8]) -> RistrettoSecretKey {
+    let x = to_big_endian(secret_key.as_bytes());
+    let h1 = bits2octets(&D::digest(message));
+
+    let mut v = vec![0x01; D::output_size()];
+    let mut k = vec![0x00; D::output_size()];
+    k = hmac::<D>(&k, &[&v, &[0x00], &x, &h1]);
+    v = hmac::<D>(&k, &[&v]);
+    k = hmac::<D>(&k, &[&v, &[0x01], &x, &h1]);
+    v = hmac::<D>(&k, &[&v]);
+
+    loop {
+        let mut t = Vec::with_capacity(SCALAR_BYTES);
+        while t.len() * 8 < GROUP_ORDER_BITS {
+            v = hmac::<D>(&k, &[&v]);
+            t.extend_from_slice(&v);
+        }
+        let candidate = bits2int(&t);
+        if candidate != [0u8; SCALAR_BYTES] && candidate < GROUP_ORDER {
+            return RistrettoSecretKey::from_bytes(&to_big_endian(&candidate))
+                .expect("A 32 byte value less than the group order is a valid secret key");
+        }
+        k = hmac::<D>(&k, &[&v, &[0x00]]);
+        v = hmac::<D>(&k, &[&v]);
+    }
+}
+
+/// HMAC (RFC 2104) of the concatenated `parts`
+fn hmac<D: Digest + BlockInput>(key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let block_size = D::BlockSize::to_usize();
+    let mut padded_key = if key.len() > block_size {
+        D::digest(key).to_vec()
+    } else {
+        key.to_vec()
+    };
+    padded_key.resize(block_size, 0);
+
+    let mut inner = D::new();
+    inner.update(padded_key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    for part in parts {
+        inner.update(part);
+    }
+    let mut outer = D::new();
+    outer.update(padded_key.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+    outer.update(inner.finalize());
+    outer.finalize().to_vec()
+}
+
+/// The integer value of the leftmost `qlen` bits of `bytes`, as a big-endian scalar
+fn bits2int(bytes: &[u8]) -> [u8; SCALAR_BYTES] {
+    let mut result = [0u8; SCALAR_BYTES];
+    if bytes.len() < SCALAR_BYTES {
+        result[SCALAR_BYTES - bytes.len()..].copy_from_slice(bytes);
+        return result;
+    }
+    let shift = SCALAR_BYTES * 8 - GROUP_ORDER_BITS;
+    let mut carry = 0u8;
+    for (r, b) in result.iter_mut().zip(&bytes[..SCALAR_BYTES]) {
+        *r = (b >> shift) | carry;
+        carry = b << (8 - shift);
+    }
+    result
+}
+
+/// `bits2int` of the message hash, reduced modulo the group order
+fn bits2octets(hash: &[u8]) -> [u8; SCALAR_BYTES] {
+    let mut z = bits2int(hash);
+    // z < 2^qlen < 2q, so a single subtraction reduces it
+    if z >= GROUP_ORDER {
+        let mut borrow = 0i16;
+        for i in (0..SCALAR_BYTES).rev() {
+            let diff = i16::from(z[i]) - i16::from(GROUP_ORDER[i]) - borrow;
+            borrow = if diff < 0 { 1 } else { 0 };
+            z[i] = (diff + (borrow << 8)) as u8;
+        }
+    }
+    z
+}
+
+/// Secret keys are stored little-endian while RFC 6979 works with big-endian integers
+fn to_big_endian(bytes: &[u8]) -> [u8; SCALAR_BYTES] {
+    let mut result = [0u8; SCALAR_BYTES];
+    result.copy_from_slice(bytes);
+    result.reverse();
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sha2::{Sha256, Sha512};
+    use tari_crypto::tari_utilities::hex::Hex;
+
+    /// These vectors were generated with an independent implementation of RFC 6979 section 3.2 over the Ristretto
+    /// group order. Keys and nonces are little-endian hex, as in `RistrettoSecretKey::to_hex`.
+    const TEST_VECTORS: &[(&str, &str, &str, &str)] = &[
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "sample",
+            "543bb323959fd2cc0985acd9d5e342d2ac1705fe19d8bd5f98014d150b3c870e",
+            "35639f112ebe4fa6ffcb9bf80dec8d94ad5fc145b265f17cbfde7e0356a5dc06",
+        ),
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "test",
+            "eea3d85f0aca6059a49224bc3f3fb520a465414259e1e1a0bb773ebeaf8e0c03",
+            "cc5bafb909d85064b03d7b5f62c6b60afaddd5fabdd2905d7f136c8995715c00",
+        ),
+        (
+            "a7b3c1d2e4f5061728394a5b6c7d8e9f00112233445566778899aabbccddee0f",
+            "sample",
+            "580eb11ea081a8d4c2358b964a98281799694fe4d00d51cb151593c10cfc690a",
+            "3121958bcc1f29537b2bbca7b49f06fff28b99905c011bcc837900ccd30f8d04",
+        ),
+        (
+            "a7b3c1d2e4f5061728394a5b6c7d8e9f00112233445566778899aabbccddee0f",
+            "",
+            "66dc9432df116f59157a8af3fd800cbab6282714a2e729d12475a431ac812e06",
+            "497f3c0a83097e36515401c3d52922ed3bd9be938a04bed0eec01b3224dfa006",
+        ),
+    ];
+
+    #[test]
+    fn it_matches_the_test_vectors() {
+        for (key, message, sha256_nonce, sha512_nonce) in TEST_VECTORS {
+            let key = RistrettoSecretKey::from_hex(key).unwrap();
+            assert_eq!(
+                derive_nonce::<Sha256>(&key, message.as_bytes()).to_hex(),
+                *sha256_nonce,
+                "SHA-256 nonce for message '{}'",
+                message
+            );
+            assert_eq!(
+                derive_nonce::<Sha512>(&key, message.as_bytes()).to_hex(),
+                *sha512_nonce,
+                "SHA-512 nonce for message '{}'",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn it_computes_hmac() {
+        // RFC 4231 test case 2
+        let mac = hmac::<Sha256>(b"Jefe", &[b"what do ya want ", b"for nothing?"]);
+        assert_eq!(
+            mac.to_hex(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn it_reduces_the_message_hash() {
+        // (2^256 - 1) >> 3 = 2^253 - 1, which is greater than the group order
+        let reduced = bits2octets(&[0xff; 32]);
+        assert_eq!(
+            reduced.to_vec().to_hex(),
+            "0fffffffffffffffffffffffffffffffeb2106215d086329a7ed9ce5a30a2c12"
+        );
+    }
+}
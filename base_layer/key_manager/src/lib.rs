@@ -5,6 +5,7 @@
 #![deny(unused_must_use)]
 #![deny(unreachable_patterns)]
 #![deny(unknown_lints)]
+pub mod deterministic_nonce;
 pub mod diacritics;
 pub mod file_backup;
 pub mod key_manager;
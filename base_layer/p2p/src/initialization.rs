@@ -28,7 +28,7 @@ use crate::{
     MINOR_NETWORK_VERSION,
 };
 use fs2::FileExt;
-use futures::{channel::mpsc, future, Sink};
+use futures::{channel::mpsc, future, AsyncRead, AsyncWrite, Sink};
 use log::*;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use std::{
@@ -42,6 +42,8 @@ use std::{
     time::{Duration, Instant},
 };
 use tari_common::configuration::Network;
+#[cfg(feature = "chaos")]
+use tari_comms::pipeline::fault_injection::FaultInjector;
 use tari_comms::{
     backoff::ConstantBackoff,
     multiaddr::Multiaddr,
@@ -52,10 +54,12 @@ use tari_comms::{
         messaging::{MessagingEventSender, MessagingProtocolExtension},
         rpc::RpcServer,
         NodeNetworkInfo,
+        ProtocolExtensions,
     },
     tor,
     tor::HiddenServiceControllerError,
-    transports::{MemoryTransport, SocksTransport, TcpWithTorTransport},
+    transports::{MemoryTransport, OutboundProxyTransport, SocksTransport, TcpWithTorTransport, Transport},
+    types::CommsDatabase,
     utils::cidr::parse_cidrs,
     CommsBuilder,
     CommsBuilderError,
@@ -94,6 +98,8 @@ pub enum CommsInitializationError {
     CannotAcquireFileLock,
     #[error("IO Error: `{0}`")]
     IoError(#[from] std::io::Error),
+    #[error("Failed to open peer database: `{0}`")]
+    PeerDatabaseError(String),
 }
 
 impl CommsInitializationError {
@@ -151,6 +157,9 @@ pub struct CommsConfig {
     pub dns_seeds_name_server: SocketAddr,
     /// All DNS seed records must pass DNSSEC validation
     pub dns_seeds_use_dnssec: bool,
+    /// Peers advertising a minor protocol version below this value are refused.
+    /// Default: 0
+    pub min_peer_minor_version: u32,
     /// The address to bind on using the TCP transport _in addition to_ the primary transport. This is typically useful
     /// for direct comms between a wallet and base node. If this is set to None, no listener will be bound.
     /// Default: None
@@ -169,6 +178,100 @@ pub async fn initialize_local_test_comms<TSink>(
 where
     TSink: Sink<Arc<PeerMessage>> + Unpin + Clone + Send + Sync + 'static,
     TSink::Error: Error + Send + Sync,
+{
+    initialize_local_test_comms_with_pipeline(
+        pipeline::Builder::new(),
+        MemoryTransport,
+        ProtocolExtensions::new(),
+        node_identity,
+        connector,
+        data_path,
+        discovery_request_timeout,
+        seed_peers,
+        shutdown_signal,
+    )
+    .await
+}
+
+/// Initialize Tari Comms configured for tests, injecting faults into all messages sent and received by the node
+#[cfg(feature = "chaos")]
+pub async fn initialize_local_test_comms_with_fault_injection<TSink>(
+    node_identity: Arc<NodeIdentity>,
+    connector: InboundDomainConnector<TSink>,
+    data_path: &str,
+    discovery_request_timeout: Duration,
+    seed_peers: Vec<Peer>,
+    fault_injector: FaultInjector,
+    shutdown_signal: ShutdownSignal,
+) -> Result<(CommsNode, Dht, MessagingEventSender), CommsInitializationError>
+where
+    TSink: Sink<Arc<PeerMessage>> + Unpin + Clone + Send + Sync + 'static,
+    TSink::Error: Error + Send + Sync,
+{
+    initialize_local_test_comms_with_pipeline(
+        pipeline::Builder::new().with_fault_injection(fault_injector),
+        MemoryTransport,
+        ProtocolExtensions::new(),
+        node_identity,
+        connector,
+        data_path,
+        discovery_request_timeout,
+        seed_peers,
+        shutdown_signal,
+    )
+    .await
+}
+
+/// Initialize Tari Comms configured for tests using the given transport, e.g. a `SimulatedTransport`, and installing
+/// the given protocol extensions, e.g. an RPC server.
+#[allow(clippy::too_many_arguments)]
+pub async fn initialize_local_test_comms_with_transport<TSink, TTransport>(
+    node_identity: Arc<NodeIdentity>,
+    connector: InboundDomainConnector<TSink>,
+    data_path: &str,
+    discovery_request_timeout: Duration,
+    seed_peers: Vec<Peer>,
+    transport: TTransport,
+    protocol_extensions: ProtocolExtensions,
+    shutdown_signal: ShutdownSignal,
+) -> Result<(CommsNode, Dht, MessagingEventSender), CommsInitializationError>
+where
+    TSink: Sink<Arc<PeerMessage>> + Unpin + Clone + Send + Sync + 'static,
+    TSink::Error: Error + Send + Sync,
+    TTransport: Transport + Unpin + Send + Sync + Clone + 'static,
+    TTransport::Output: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+{
+    initialize_local_test_comms_with_pipeline(
+        pipeline::Builder::new(),
+        transport,
+        protocol_extensions,
+        node_identity,
+        connector,
+        data_path,
+        discovery_request_timeout,
+        seed_peers,
+        shutdown_signal,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn initialize_local_test_comms_with_pipeline<TSink, TTransport>(
+    pipeline_builder: pipeline::Builder<(), (), ()>,
+    transport: TTransport,
+    protocol_extensions: ProtocolExtensions,
+    node_identity: Arc<NodeIdentity>,
+    connector: InboundDomainConnector<TSink>,
+    data_path: &str,
+    discovery_request_timeout: Duration,
+    seed_peers: Vec<Peer>,
+    shutdown_signal: ShutdownSignal,
+) -> Result<(CommsNode, Dht, MessagingEventSender), CommsInitializationError>
+where
+    TSink: Sink<Arc<PeerMessage>> + Unpin + Clone + Send + Sync + 'static,
+    TSink::Error: Error + Send + Sync,
+    TTransport: Transport + Unpin + Send + Sync + Clone + 'static,
+    TTransport::Output: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
 {
     let peer_database_name = {
         let mut rng = thread_rng();
@@ -221,7 +324,7 @@ where
 
     let dht_outbound_layer = dht.outbound_middleware_layer();
     let (event_sender, _) = broadcast::channel(100);
-    let pipeline = pipeline::Builder::new()
+    let pipeline = pipeline_builder
         .outbound_buffer_size(10)
         .with_outbound_pipeline(outbound_rx, |sink| {
             ServiceBuilder::new().layer(dht_outbound_layer).service(sink)
@@ -236,7 +339,8 @@ where
 
     let comms = comms
         .add_protocol_extension(MessagingProtocolExtension::new(event_sender.clone(), pipeline))
-        .spawn_with_transport(MemoryTransport)
+        .add_protocol_extensions(protocol_extensions)
+        .spawn_with_transport(transport)
         .await?;
 
     Ok((comms, dht, event_sender))
@@ -257,15 +361,24 @@ pub async fn spawn_comms_using_transport(
         TransportType::Tcp {
             listener_address,
             tor_socks_config,
+            outbound_socks_config,
         } => {
             debug!(
                 target: LOG_TARGET,
-                "Building TCP comms stack{}",
-                tor_socks_config.as_ref().map(|_| " with Tor support").unwrap_or("")
+                "Building TCP comms stack{}{}",
+                tor_socks_config.as_ref().map(|_| " with Tor support").unwrap_or(""),
+                outbound_socks_config
+                    .as_ref()
+                    .map(|_| " with an outbound SOCKS proxy")
+                    .unwrap_or("")
             );
-            let mut transport = TcpWithTorTransport::new();
+            let mut tcp_transport = TcpWithTorTransport::new();
             if let Some(config) = tor_socks_config {
-                transport.set_tor_socks_proxy(config);
+                tcp_transport.set_tor_socks_proxy(config);
+            }
+            let mut transport = OutboundProxyTransport::new(tcp_transport);
+            if let Some(config) = outbound_socks_config {
+                transport.set_outbound_proxy(config);
             }
             comms
                 .with_listener_address(listener_address)
@@ -276,7 +389,7 @@ pub async fn spawn_comms_using_transport(
             debug!(target: LOG_TARGET, "Building TOR comms stack ({})", tor_config);
             let mut hidden_service_ctl = initialize_hidden_service(tor_config).await?;
             // Set the listener address to be the address (usually local) to which tor will forward all traffic
-            let transport = hidden_service_ctl.initialize_transport().await?;
+            let transport = OutboundProxyTransport::new(hidden_service_ctl.initialize_transport().await?);
             debug!(target: LOG_TARGET, "Comms and DHT configured");
             comms
                 .with_listener_address(hidden_service_ctl.proxied_address())
@@ -289,7 +402,7 @@ pub async fn spawn_comms_using_transport(
             listener_address,
         } => {
             debug!(target: LOG_TARGET, "Building SOCKS5 comms stack");
-            let transport = SocksTransport::new(socks_config);
+            let transport = OutboundProxyTransport::new(SocksTransport::new(socks_config));
             comms
                 .with_listener_address(listener_address)
                 .spawn_with_transport(transport)
@@ -330,15 +443,7 @@ where
 {
     let file_lock = acquire_exclusive_file_lock(&config.datastore_path)?;
 
-    let datastore = LMDBBuilder::new()
-        .set_path(&config.datastore_path)
-        .set_env_config(LMDBConfig::default())
-        .set_max_number_of_databases(1)
-        .add_database(&config.peer_database_name, lmdb_zero::db::CREATE)
-        .build()
-        .unwrap();
-    let peer_database = datastore.get_handle(&config.peer_database_name).unwrap();
-    let peer_database = LMDBWrapper::new(Arc::new(peer_database));
+    let peer_database = open_peer_database(&config.datastore_path, &config.peer_database_name)?;
 
     let listener_liveness_allowlist_cidrs = parse_cidrs(&config.listener_liveness_allowlist_cidrs)
         .map_err(CommsInitializationError::InvalidLivenessCidrs)?;
@@ -346,6 +451,7 @@ where
     let builder = builder
         .with_listener_liveness_max_sessions(config.listener_liveness_max_sessions)
         .with_listener_liveness_allowlist_cidrs(listener_liveness_allowlist_cidrs)
+        .with_min_peer_minor_version(config.min_peer_minor_version)
         .with_dial_backoff(ConstantBackoff::new(Duration::from_millis(500)))
         .with_peer_storage(peer_database, Some(file_lock));
 
@@ -403,6 +509,30 @@ where
     Ok((comms, dht))
 }
 
+/// Open (creating if necessary) the LMDB peer database with the given name at `datastore_path`
+/// ## Parameters
+/// `datastore_path` - Path of the LMDB data files
+/// `peer_database_name` - Name of the peer database
+///
+/// ## Returns
+/// The peer database, ready to be used for peer storage
+pub fn open_peer_database(
+    datastore_path: &Path,
+    peer_database_name: &str,
+) -> Result<CommsDatabase, CommsInitializationError> {
+    let datastore = LMDBBuilder::new()
+        .set_path(datastore_path)
+        .set_env_config(LMDBConfig::default())
+        .set_max_number_of_databases(1)
+        .add_database(peer_database_name, lmdb_zero::db::CREATE)
+        .build()
+        .map_err(|err| CommsInitializationError::PeerDatabaseError(err.to_string()))?;
+    let peer_database = datastore
+        .get_handle(peer_database_name)
+        .ok_or_else(|| CommsInitializationError::PeerDatabaseError(format!("{} not found", peer_database_name)))?;
+    Ok(LMDBWrapper::new(Arc::new(peer_database)))
+}
+
 /// Acquire an exclusive OS level write lock on a file in the provided path. This is used to check if another instance
 /// of this database has already been initialized in order to prevent two process from using it simultaneously
 /// ## Parameters
@@ -460,6 +590,7 @@ async fn add_all_peers(
 pub struct P2pInitializer {
     config: CommsConfig,
     connector: Option<PubsubDomainConnector>,
+    shutdown_signal: Option<ShutdownSignal>,
 }
 
 impl P2pInitializer {
@@ -467,9 +598,17 @@ impl P2pInitializer {
         Self {
             config,
             connector: Some(connector),
+            shutdown_signal: None,
         }
     }
 
+    /// Stop comms using the given signal instead of the service stack's shutdown signal. This allows comms to be
+    /// stopped after the services that depend on it.
+    pub fn with_shutdown_signal(mut self, shutdown_signal: ShutdownSignal) -> Self {
+        self.shutdown_signal = Some(shutdown_signal);
+        self
+    }
+
     // Following are inlined due to Rust ICE: https://github.com/rust-lang/rust/issues/73537
     #[inline(always)]
     fn try_parse_seed_peers(peer_seeds_str: &[String]) -> Result<Vec<Peer>, ServiceInitializationError> {
@@ -546,8 +685,12 @@ impl ServiceInitializer for P2pInitializer {
         let config = self.config.clone();
         let connector = self.connector.take().expect("P2pInitializer called more than once");
 
+        let shutdown_signal = self
+            .shutdown_signal
+            .clone()
+            .unwrap_or_else(|| context.get_shutdown_signal());
         let mut builder = CommsBuilder::new()
-            .with_shutdown_signal(context.get_shutdown_signal())
+            .with_shutdown_signal(shutdown_signal)
             .with_node_identity(config.node_identity.clone())
             .with_node_info(NodeNetworkInfo {
                 major_version: MAJOR_NETWORK_VERSION,
@@ -20,7 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::{error::LivenessError, state::Metadata};
+use super::{
+    error::LivenessError,
+    state::{LatencyStats, Metadata, NetworkTimeEstimate},
+};
 use crate::proto::liveness::MetadataKey;
 use std::sync::Arc;
 use tari_comms::peer_manager::NodeId;
@@ -41,6 +44,12 @@ pub enum LivenessRequest {
     GetAvgLatency(NodeId),
     /// Get average latency for all connected nodes
     GetNetworkAvgLatency,
+    /// Get round trip time statistics for node ID
+    GetLatencyStats(NodeId),
+    /// Get round trip time statistics for all nodes that have responded to a ping
+    GetAllLatencyStats,
+    /// Get the network-adjusted time estimate
+    GetNetworkTimeEstimate,
     /// Set the metadata attached to each ping/pong message
     SetMetadataEntry(MetadataKey, Vec<u8>),
 }
@@ -56,6 +65,12 @@ pub enum LivenessResponse {
     AvgLatency(Option<u32>),
     /// The number of active neighbouring peers
     NumActiveNeighbours(usize),
+    /// Response for GetLatencyStats
+    LatencyStats(Option<LatencyStats>),
+    /// Response for GetAllLatencyStats
+    AllLatencyStats(Vec<(NodeId, LatencyStats)>),
+    /// Response for GetNetworkTimeEstimate
+    NetworkTimeEstimate(Option<NetworkTimeEstimate>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -166,4 +181,29 @@ impl LivenessHandle {
             _ => Err(LivenessError::UnexpectedApiResponse),
         }
     }
+
+    /// Retrieve round trip time statistics for a given node
+    pub async fn get_latency_stats(&mut self, node_id: NodeId) -> Result<Option<LatencyStats>, LivenessError> {
+        match self.handle.call(LivenessRequest::GetLatencyStats(node_id)).await?? {
+            LivenessResponse::LatencyStats(v) => Ok(v),
+            _ => Err(LivenessError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Retrieve round trip time statistics for all nodes that have responded to a ping
+    pub async fn get_all_latency_stats(&mut self) -> Result<Vec<(NodeId, LatencyStats)>, LivenessError> {
+        match self.handle.call(LivenessRequest::GetAllLatencyStats).await?? {
+            LivenessResponse::AllLatencyStats(v) => Ok(v),
+            _ => Err(LivenessError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Retrieve the network-adjusted time estimate, based on the clock offsets of peers that have responded to a
+    /// ping. Returns None if no peers have reported their time.
+    pub async fn get_network_time_estimate(&mut self) -> Result<Option<NetworkTimeEstimate>, LivenessError> {
+        match self.handle.call(LivenessRequest::GetNetworkTimeEstimate).await?? {
+            LivenessResponse::NetworkTimeEstimate(v) => Ok(v),
+            _ => Err(LivenessError::UnexpectedApiResponse),
+        }
+    }
 }
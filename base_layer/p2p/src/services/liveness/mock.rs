@@ -137,6 +137,15 @@ impl LivenessMock {
             GetNetworkAvgLatency => {
                 reply.send(Ok(LivenessResponse::AvgLatency(None))).unwrap();
             },
+            GetLatencyStats(_) => {
+                reply.send(Ok(LivenessResponse::LatencyStats(None))).unwrap();
+            },
+            GetAllLatencyStats => {
+                reply.send(Ok(LivenessResponse::AllLatencyStats(Vec::new()))).unwrap();
+            },
+            GetNetworkTimeEstimate => {
+                reply.send(Ok(LivenessResponse::NetworkTimeEstimate(None))).unwrap();
+            },
             SetMetadataEntry(_, _) => {
                 reply.send(Ok(LivenessResponse::Ok)).unwrap();
             },
@@ -54,7 +54,7 @@ mod message;
 mod service;
 
 mod state;
-pub use state::Metadata;
+pub use state::{LatencyStats, Metadata, NetworkTimeEstimate};
 
 #[cfg(feature = "test-mocks")]
 pub mod mock;
@@ -176,7 +176,7 @@ where
                     return Ok(());
                 }
 
-                let maybe_latency = self.state.record_pong(ping_pong_msg.nonce);
+                let maybe_latency = self.state.record_pong(ping_pong_msg.nonce, ping_pong_msg.timestamp);
                 debug!(
                     target: LOG_TARGET,
                     "Received pong from peer '{}' with useragent '{}'. {}",
@@ -238,6 +238,18 @@ where
                 let latency = self.state.get_network_avg_latency();
                 Ok(LivenessResponse::AvgLatency(latency))
             },
+            GetLatencyStats(node_id) => {
+                let stats = self.state.get_latency_stats(&node_id);
+                Ok(LivenessResponse::LatencyStats(stats))
+            },
+            GetAllLatencyStats => {
+                let stats = self.state.get_all_latency_stats();
+                Ok(LivenessResponse::AllLatencyStats(stats))
+            },
+            GetNetworkTimeEstimate => {
+                let estimate = self.state.get_network_time_estimate();
+                Ok(LivenessResponse::NetworkTimeEstimate(estimate))
+            },
             SetMetadataEntry(key, value) => {
                 self.state.set_metadata_entry(key, value);
                 Ok(LivenessResponse::Ok)
@@ -21,15 +21,17 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::proto::liveness::MetadataKey;
-use chrono::{NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use std::{
     collections::{hash_map::RandomState, HashMap},
     time::Duration,
 };
 use tari_comms::peer_manager::NodeId;
 
-const LATENCY_SAMPLE_WINDOW_SIZE: usize = 25;
+const LATENCY_SAMPLE_WINDOW_SIZE: usize = 100;
 const MAX_INFLIGHT_TTL: Duration = Duration::from_secs(20);
+/// Clock offsets older than this are not used to estimate network time
+const MAX_CLOCK_OFFSET_AGE: Duration = Duration::from_secs(60 * 60);
 
 /// Represents metadata in a ping/pong message.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -68,6 +70,7 @@ impl From<Metadata> for HashMap<i32, Vec<u8>, RandomState> {
 pub struct LivenessState {
     inflight_pings: HashMap<u64, (NodeId, NaiveDateTime)>,
     peer_latency: HashMap<NodeId, AverageLatency>,
+    peer_clock_offsets: HashMap<NodeId, (i64, NaiveDateTime)>,
 
     pings_received: usize,
     pongs_received: usize,
@@ -149,16 +152,19 @@ impl LivenessState {
     }
 
     /// Records a pong. Specifically, the pong counter is incremented and
-    /// a latency sample is added and calculated.
-    pub fn record_pong(&mut self, nonce: u64) -> Option<u32> {
+    /// a latency sample is added and calculated. If the peer included its time in the pong (`peer_timestamp_ms` is
+    /// non-zero), the peer's clock offset is recorded.
+    pub fn record_pong(&mut self, nonce: u64, peer_timestamp_ms: u64) -> Option<u32> {
         self.inc_pongs_received();
 
         match self.inflight_pings.remove_entry(&nonce) {
             Some((_, (node_id, sent_time))) => {
                 let now = Utc::now().naive_utc();
-                let latency = self
-                    .add_latency_sample(node_id, convert_to_std_duration(now - sent_time))
-                    .calc_average();
+                let round_trip = convert_to_std_duration(now - sent_time);
+                if peer_timestamp_ms > 0 {
+                    self.record_clock_offset(node_id.clone(), peer_timestamp_ms, round_trip.as_millis() as u32);
+                }
+                let latency = self.add_latency_sample(node_id, round_trip).calc_average();
                 Some(latency)
             },
             None => None,
@@ -175,6 +181,53 @@ impl LivenessState {
         latency
     }
 
+    /// Records the clock offset of a peer from the timestamp in its pong. The peer's clock is assumed to have been read
+    /// half way through the round trip.
+    pub fn record_clock_offset(&mut self, node_id: NodeId, peer_timestamp_ms: u64, latency_ms: u32) {
+        let now = Utc::now().naive_utc();
+        let offset = peer_timestamp_ms as i64 + i64::from(latency_ms / 2) - now.timestamp_millis();
+        self.peer_clock_offsets.insert(node_id, (offset, now));
+    }
+
+    /// Estimates network-adjusted time from the median clock offset of peers that have recently responded to a ping.
+    /// Returns None if no peers have reported their time.
+    pub fn get_network_time_estimate(&self) -> Option<NetworkTimeEstimate> {
+        let now = Utc::now().naive_utc();
+        let mut offsets = self
+            .peer_clock_offsets
+            .values()
+            .filter(|(_, recorded_at)| convert_to_std_duration(now - *recorded_at) <= MAX_CLOCK_OFFSET_AGE)
+            .map(|(offset, _)| *offset)
+            .collect::<Vec<_>>();
+        if offsets.is_empty() {
+            return None;
+        }
+
+        offsets.sort_unstable();
+        let mid = offsets.len() / 2;
+        let offset_ms = if offsets.len() % 2 == 0 {
+            (offsets[mid - 1] + offsets[mid]) / 2
+        } else {
+            offsets[mid]
+        };
+
+        Some(NetworkTimeEstimate {
+            offset_ms,
+            num_peers: offsets.len(),
+        })
+    }
+
+    pub fn get_latency_stats(&self, node_id: &NodeId) -> Option<LatencyStats> {
+        self.peer_latency.get(node_id).map(|latency| latency.calc_stats())
+    }
+
+    pub fn get_all_latency_stats(&self) -> Vec<(NodeId, LatencyStats)> {
+        self.peer_latency
+            .iter()
+            .map(|(node_id, latency)| (node_id.clone(), latency.calc_stats()))
+            .collect()
+    }
+
     pub fn get_avg_latency_ms(&self, node_id: &NodeId) -> Option<u32> {
         self.peer_latency.get(node_id).map(|latency| latency.calc_average())
     }
@@ -198,6 +251,33 @@ pub(super) fn convert_to_std_duration(old_duration: chrono::Duration) -> Duratio
     Duration::from_millis(old_duration.num_milliseconds() as u64)
 }
 
+/// Round trip time statistics for a peer, calculated over the most recent samples
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub num_samples: usize,
+    pub avg_ms: u32,
+    pub p50_ms: u32,
+    pub p90_ms: u32,
+    pub p99_ms: u32,
+    pub max_ms: u32,
+}
+
+/// Network-adjusted time, being the local time adjusted by the median clock offset of peers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NetworkTimeEstimate {
+    /// The median offset of peer clocks from the local clock in milliseconds. Positive if peers are ahead.
+    pub offset_ms: i64,
+    /// The number of peers that the estimate is based on
+    pub num_peers: usize,
+}
+
+impl NetworkTimeEstimate {
+    /// Returns the current network-adjusted time
+    pub fn adjusted_time(&self) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::milliseconds(self.offset_ms)
+    }
+}
+
 /// A very simple implementation for calculating average latency. Samples are added in milliseconds and the mean average
 /// is calculated for those samples. If more than [LATENCY_SAMPLE_WINDOW_SIZE](self::LATENCY_SAMPLE_WINDOW_SIZE) samples
 /// are added the oldest sample is discarded.
@@ -231,11 +311,35 @@ impl AverageLatency {
 
         samples.iter().fold(0, |sum, x| sum + *x) / samples.len() as u32
     }
+
+    /// Calculate the average, percentiles and maximum of the recorded samples
+    pub fn calc_stats(&self) -> LatencyStats {
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        LatencyStats {
+            num_samples: sorted.len(),
+            avg_ms: self.calc_average(),
+            p50_ms: percentile(&sorted, 50),
+            p90_ms: percentile(&sorted, 90),
+            p99_ms: percentile(&sorted, 99),
+            max_ms: sorted.last().copied().unwrap_or(0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[u32], percentile: usize) -> u32 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (percentile * sorted.len() + 99) / 100;
+    sorted[rank.max(1) - 1]
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use tari_crypto::tari_utilities::ByteArray;
 
     #[test]
     fn new() {
@@ -295,10 +399,41 @@ mod test {
         let node_id = NodeId::default();
         state.add_inflight_ping(123, node_id);
 
-        let latency = state.record_pong(123).unwrap();
+        let latency = state.record_pong(123, 0).unwrap();
         assert!(latency < 50);
     }
 
+    #[test]
+    fn calc_stats() {
+        let mut latency = AverageLatency::new(100);
+        assert_eq!(latency.calc_stats(), LatencyStats::default());
+        for ms in (1..=100).rev() {
+            latency.add_sample(Duration::from_millis(ms));
+        }
+        let stats = latency.calc_stats();
+        assert_eq!(stats.num_samples, 100);
+        assert_eq!(stats.avg_ms, 50);
+        assert_eq!(stats.p50_ms, 50);
+        assert_eq!(stats.p90_ms, 90);
+        assert_eq!(stats.p99_ms, 99);
+        assert_eq!(stats.max_ms, 100);
+    }
+
+    #[test]
+    fn network_time_estimate() {
+        let mut state = LivenessState::new();
+        assert!(state.get_network_time_estimate().is_none());
+
+        let now = Utc::now().timestamp_millis() as u64;
+        state.record_clock_offset(NodeId::from_bytes(&[1u8; 13]).unwrap(), now + 10_000, 0);
+        state.record_clock_offset(NodeId::from_bytes(&[2u8; 13]).unwrap(), now + 20_000, 0);
+        state.record_clock_offset(NodeId::from_bytes(&[3u8; 13]).unwrap(), now + 1_000_000, 0);
+        let estimate = state.get_network_time_estimate().unwrap();
+        assert_eq!(estimate.num_peers, 3);
+        // The median is not affected by the outlier. Allow some slack for the time taken by the test.
+        assert!(estimate.offset_ms <= 20_000 && estimate.offset_ms > 19_000);
+    }
+
     #[test]
     fn set_metadata_entry() {
         let mut state = LivenessState::new();
@@ -32,6 +32,8 @@ pub enum TransportType {
         listener_address: Multiaddr,
         /// The optional SOCKS proxy to use when connecting to Tor onion addresses
         tor_socks_config: Option<SocksConfig>,
+        /// The optional SOCKS proxy used to dial all outbound connections. This can be overridden per-peer.
+        outbound_socks_config: Option<SocksConfig>,
     },
     /// This does not directly map to a transport, but will configure comms to run over a tor hidden service using the
     /// Tor proxy. This transport can connect to TCP/IP, onion v2, onion v3 and DNS addresses.
@@ -35,10 +35,16 @@ use tokio::task;
 ///
 /// The `Notifier::notify` method will notify all cloned `ServiceHandlesFuture`s
 /// and which will resolve with the collected `ServiceHandles`.
-pub(crate) fn create_context_notifier_pair(shutdown_signal: ShutdownSignal) -> (Shutdown, ServiceInitializerContext) {
+pub(crate) fn create_context_notifier_pair(
+    shutdown_signal: ShutdownSignal,
+    stopped_trigger: Option<Shutdown>,
+) -> (Shutdown, ServiceInitializerContext) {
     let trigger = Shutdown::new();
     let trigger_signal = trigger.to_signal();
-    (trigger, ServiceInitializerContext::new(shutdown_signal, trigger_signal))
+    (
+        trigger,
+        ServiceInitializerContext::new(shutdown_signal, trigger_signal, stopped_trigger.map(Arc::new)),
+    )
 }
 
 /// Contains context for service initialization.
@@ -49,6 +55,8 @@ pub(crate) fn create_context_notifier_pair(shutdown_signal: ShutdownSignal) -> (
 pub struct ServiceInitializerContext {
     inner: ServiceHandles,
     ready_signal: ShutdownSignal,
+    /// Held by every task spawned from this context. The trigger fires once the last task has completed.
+    stopped_trigger: Option<Arc<Shutdown>>,
 }
 
 impl ServiceInitializerContext {
@@ -58,10 +66,16 @@ impl ServiceInitializerContext {
     /// `shutdown_signal` - signal that is provided to services. If this signal is triggered, services should terminate.
     /// `ready_signal` - indicates that all services are ready. This should be triggered by the `StackBuilder` once all
     ///                  initializers have run.
-    pub(crate) fn new(shutdown_signal: ShutdownSignal, ready_signal: ShutdownSignal) -> Self {
+    /// `stopped_trigger` - triggered once all tasks spawned from this context have completed
+    pub(crate) fn new(
+        shutdown_signal: ShutdownSignal,
+        ready_signal: ShutdownSignal,
+        stopped_trigger: Option<Arc<Shutdown>>,
+    ) -> Self {
         Self {
             inner: ServiceHandles::new(shutdown_signal),
             ready_signal,
+            stopped_trigger,
         }
     }
 
@@ -84,7 +98,11 @@ impl ServiceInitializerContext {
         Fut: Future + Send + 'static,
         Fut::Output: Send,
     {
-        task::spawn(self.wait_ready().then(f))
+        let stopped_guard = self.stopped_trigger.clone();
+        task::spawn(self.wait_ready().then(f).map(move |output| {
+            drop(stopped_guard);
+            output
+        }))
     }
 
     /// Spawn a task once handles are ready. The resolved handles are passed into this closure.
@@ -96,6 +114,7 @@ impl ServiceInitializerContext {
         Fut::Output: Send + 'static,
     {
         task::spawn(async move {
+            let _stopped_guard = self.stopped_trigger.clone();
             let shutdown_signal = self.get_shutdown_signal();
             let _ = self.ready_signal.await;
             let fut = f(self.inner);
@@ -225,7 +244,7 @@ mod test {
         #[derive(Clone)]
         struct TestHandle;
         let trigger = Shutdown::new();
-        let context = ServiceInitializerContext::new(trigger.to_signal(), trigger.to_signal());
+        let context = ServiceInitializerContext::new(trigger.to_signal(), trigger.to_signal(), None);
         context.register_handle(TestHandle);
         context.inner.expect_handle::<TestHandle>();
         assert!(context.inner.get_handle::<()>().is_none());
@@ -26,7 +26,7 @@ use crate::{
     ServiceInitializerContext,
 };
 use futures::future;
-use tari_shutdown::ShutdownSignal;
+use tari_shutdown::{Shutdown, ShutdownSignal};
 
 /// Responsible for building and collecting handles and (usually long-running) service futures.
 /// `finish` is an async function which resolves once all the services are initialized, or returns
@@ -34,6 +34,7 @@ use tari_shutdown::ShutdownSignal;
 pub struct StackBuilder {
     initializers: Vec<Box<dyn ServiceInitializer + Send>>,
     shutdown_signal: ShutdownSignal,
+    stopped_trigger: Option<Shutdown>,
 }
 
 impl StackBuilder {
@@ -41,8 +42,16 @@ impl StackBuilder {
         Self {
             initializers: Vec::new(),
             shutdown_signal,
+            stopped_trigger: None,
         }
     }
+
+    /// Set a trigger that fires once every task spawned by the services in this stack has completed. Combined with the
+    /// shutdown signal, this allows a caller to wait for the stack to stop.
+    pub fn with_stopped_trigger(mut self, stopped_trigger: Shutdown) -> Self {
+        self.stopped_trigger = Some(stopped_trigger);
+        self
+    }
 }
 
 impl StackBuilder {
@@ -71,9 +80,10 @@ impl StackBuilder {
         let StackBuilder {
             shutdown_signal,
             mut initializers,
+            stopped_trigger,
         } = self;
 
-        let (mut notifier, context) = create_context_notifier_pair(shutdown_signal);
+        let (mut notifier, context) = create_context_notifier_pair(shutdown_signal, stopped_trigger);
 
         // Collect all the initialization futures
         let init_futures = initializers.iter_mut().map(|init| init.initialize(context.clone()));
@@ -95,7 +105,7 @@ mod test {
     use super::*;
     use crate::{initializer::ServiceInitializer, ServiceInitializerContext};
     use async_trait::async_trait;
-    use futures::{executor::block_on, future};
+    use futures::{executor::block_on, future, FutureExt};
     use std::sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
@@ -173,4 +183,28 @@ mod test {
 
         assert_eq!(shared_state.load(Ordering::SeqCst), 1);
     }
+
+    #[tokio_macros::test]
+    async fn stopped_trigger() {
+        let mut shutdown = Shutdown::new();
+        let stopped = Shutdown::new();
+        let stopped_signal = stopped.to_signal();
+
+        let _handles = StackBuilder::new(shutdown.to_signal())
+            .with_stopped_trigger(stopped)
+            .add_initializer(|context: ServiceInitializerContext| {
+                context.spawn_when_ready(|handles| async move {
+                    let _ = handles.get_shutdown_signal().await;
+                });
+                Ok(())
+            })
+            .build()
+            .await
+            .unwrap();
+
+        // The service task is still running
+        assert!(stopped_signal.clone().now_or_never().is_none());
+        shutdown.trigger().unwrap();
+        stopped_signal.await.unwrap();
+    }
 }
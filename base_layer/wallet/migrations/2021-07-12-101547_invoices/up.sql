@@ -0,0 +1,11 @@
+CREATE TABLE invoices (
+    invoice_id INTEGER PRIMARY KEY NOT NULL,
+    counterparty_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    memo TEXT NOT NULL,
+    expires_at DATETIME NOT NULL,
+    direction INTEGER NOT NULL,
+    status INTEGER NOT NULL,
+    tx_id INTEGER NULL,
+    timestamp DATETIME NOT NULL
+);
@@ -0,0 +1,16 @@
+CREATE TABLE recurring_payments (
+    id INTEGER PRIMARY KEY NOT NULL,
+    destination_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    fee_per_gram INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    interval_secs INTEGER NOT NULL,
+    next_payment_at DATETIME NOT NULL,
+    max_payments INTEGER NULL,
+    end_at DATETIME NULL,
+    payments_made INTEGER NOT NULL,
+    failed_attempts INTEGER NOT NULL,
+    status INTEGER NOT NULL,
+    last_tx_id INTEGER NULL,
+    timestamp DATETIME NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE outputs
+    ADD COLUMN covenant BLOB NOT NULL DEFAULT x'';
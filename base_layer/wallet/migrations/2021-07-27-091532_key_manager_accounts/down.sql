@@ -0,0 +1,4 @@
+DROP TABLE key_manager_accounts;
+
+ALTER TABLE outputs
+    DROP COLUMN account;
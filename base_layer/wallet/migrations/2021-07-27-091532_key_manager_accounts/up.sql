@@ -0,0 +1,7 @@
+CREATE TABLE key_manager_accounts (
+    name TEXT PRIMARY KEY NOT NULL,
+    key_index BIGINT NOT NULL
+);
+
+ALTER TABLE outputs
+    ADD COLUMN account TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,5 @@
+CREATE TABLE transaction_idempotency_keys (
+    idempotency_key TEXT PRIMARY KEY NOT NULL,
+    tx_id BIGINT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE outputs
+    DROP COLUMN received_in_tx_id;
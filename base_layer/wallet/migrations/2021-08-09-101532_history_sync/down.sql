@@ -0,0 +1,3 @@
+DROP TABLE synced_transactions;
+DROP TABLE history_sync_state;
+DROP TABLE history_sync_companions;
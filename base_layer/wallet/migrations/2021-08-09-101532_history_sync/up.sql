@@ -0,0 +1,30 @@
+CREATE TABLE history_sync_companions (
+    public_key BLOB PRIMARY KEY NOT NULL,
+    timestamp DATETIME NOT NULL
+);
+
+CREATE TABLE history_sync_state (
+    primary_public_key BLOB PRIMARY KEY NOT NULL,
+    cursor_epoch INTEGER NULL,
+    cursor_sequence INTEGER NULL,
+    available_balance INTEGER NULL,
+    pending_incoming_balance INTEGER NULL,
+    pending_outgoing_balance INTEGER NULL,
+    time_locked_balance INTEGER NULL,
+    last_synced_at DATETIME NULL
+);
+
+CREATE TABLE synced_transactions (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    source_public_key BLOB NOT NULL,
+    destination_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    fee INTEGER NOT NULL,
+    status INTEGER NOT NULL,
+    direction INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    timestamp DATETIME NOT NULL,
+    cancelled INTEGER NOT NULL,
+    mined_height INTEGER NULL,
+    confirmations INTEGER NULL
+);
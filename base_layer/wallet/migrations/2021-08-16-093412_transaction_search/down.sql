@@ -0,0 +1,19 @@
+DROP TRIGGER completed_transactions_search_insert;
+DROP TRIGGER completed_transactions_search_update;
+DROP TRIGGER completed_transactions_search_delete;
+DROP TRIGGER inbound_transactions_search_insert;
+DROP TRIGGER inbound_transactions_search_update;
+DROP TRIGGER inbound_transactions_search_delete;
+DROP TRIGGER outbound_transactions_search_insert;
+DROP TRIGGER outbound_transactions_search_update;
+DROP TRIGGER outbound_transactions_search_delete;
+DROP TRIGGER contacts_search_insert;
+DROP TRIGGER contacts_search_update;
+DROP TRIGGER contacts_search_delete;
+DROP TRIGGER invoices_search_insert;
+DROP TRIGGER invoices_search_update;
+DROP TRIGGER invoices_search_delete;
+
+DROP TABLE invoice_memo_search;
+DROP TABLE contact_alias_search;
+DROP TABLE transaction_message_search;
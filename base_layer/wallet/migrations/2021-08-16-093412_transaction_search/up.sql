@@ -0,0 +1,80 @@
+-- Full-text indexes used to search the transaction history. Pending and completed transactions share their tx_id,
+-- so the message index is keyed by tx_id and only loses a message once no table holds the transaction anymore.
+CREATE VIRTUAL TABLE transaction_message_search USING fts5(message, tokenize = 'unicode61 remove_diacritics 2');
+CREATE VIRTUAL TABLE contact_alias_search USING fts5(alias, tokenize = 'unicode61 remove_diacritics 2');
+CREATE VIRTUAL TABLE invoice_memo_search USING fts5(memo, tokenize = 'unicode61 remove_diacritics 2');
+
+CREATE TRIGGER completed_transactions_search_insert AFTER INSERT ON completed_transactions BEGIN
+    INSERT OR REPLACE INTO transaction_message_search (rowid, message) VALUES (new.tx_id, new.message);
+END;
+
+CREATE TRIGGER completed_transactions_search_update AFTER UPDATE OF message ON completed_transactions BEGIN
+    INSERT OR REPLACE INTO transaction_message_search (rowid, message) VALUES (new.tx_id, new.message);
+END;
+
+CREATE TRIGGER completed_transactions_search_delete AFTER DELETE ON completed_transactions
+WHEN NOT EXISTS (SELECT 1 FROM inbound_transactions WHERE tx_id = old.tx_id)
+    AND NOT EXISTS (SELECT 1 FROM outbound_transactions WHERE tx_id = old.tx_id)
+BEGIN
+    DELETE FROM transaction_message_search WHERE rowid = old.tx_id;
+END;
+
+CREATE TRIGGER inbound_transactions_search_insert AFTER INSERT ON inbound_transactions BEGIN
+    INSERT OR REPLACE INTO transaction_message_search (rowid, message) VALUES (new.tx_id, new.message);
+END;
+
+CREATE TRIGGER inbound_transactions_search_update AFTER UPDATE OF message ON inbound_transactions BEGIN
+    INSERT OR REPLACE INTO transaction_message_search (rowid, message) VALUES (new.tx_id, new.message);
+END;
+
+CREATE TRIGGER inbound_transactions_search_delete AFTER DELETE ON inbound_transactions
+WHEN NOT EXISTS (SELECT 1 FROM completed_transactions WHERE tx_id = old.tx_id)
+    AND NOT EXISTS (SELECT 1 FROM outbound_transactions WHERE tx_id = old.tx_id)
+BEGIN
+    DELETE FROM transaction_message_search WHERE rowid = old.tx_id;
+END;
+
+CREATE TRIGGER outbound_transactions_search_insert AFTER INSERT ON outbound_transactions BEGIN
+    INSERT OR REPLACE INTO transaction_message_search (rowid, message) VALUES (new.tx_id, new.message);
+END;
+
+CREATE TRIGGER outbound_transactions_search_update AFTER UPDATE OF message ON outbound_transactions BEGIN
+    INSERT OR REPLACE INTO transaction_message_search (rowid, message) VALUES (new.tx_id, new.message);
+END;
+
+CREATE TRIGGER outbound_transactions_search_delete AFTER DELETE ON outbound_transactions
+WHEN NOT EXISTS (SELECT 1 FROM completed_transactions WHERE tx_id = old.tx_id)
+    AND NOT EXISTS (SELECT 1 FROM inbound_transactions WHERE tx_id = old.tx_id)
+BEGIN
+    DELETE FROM transaction_message_search WHERE rowid = old.tx_id;
+END;
+
+CREATE TRIGGER contacts_search_insert AFTER INSERT ON contacts BEGIN
+    INSERT INTO contact_alias_search (rowid, alias) VALUES (new.rowid, new.alias);
+END;
+
+CREATE TRIGGER contacts_search_update AFTER UPDATE OF alias ON contacts BEGIN
+    UPDATE contact_alias_search SET alias = new.alias WHERE rowid = new.rowid;
+END;
+
+CREATE TRIGGER contacts_search_delete AFTER DELETE ON contacts BEGIN
+    DELETE FROM contact_alias_search WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER invoices_search_insert AFTER INSERT ON invoices BEGIN
+    INSERT INTO invoice_memo_search (rowid, memo) VALUES (new.invoice_id, new.memo);
+END;
+
+CREATE TRIGGER invoices_search_update AFTER UPDATE OF memo ON invoices BEGIN
+    UPDATE invoice_memo_search SET memo = new.memo WHERE rowid = new.invoice_id;
+END;
+
+CREATE TRIGGER invoices_search_delete AFTER DELETE ON invoices BEGIN
+    DELETE FROM invoice_memo_search WHERE rowid = old.invoice_id;
+END;
+
+INSERT OR REPLACE INTO transaction_message_search (rowid, message) SELECT tx_id, message FROM outbound_transactions;
+INSERT OR REPLACE INTO transaction_message_search (rowid, message) SELECT tx_id, message FROM inbound_transactions;
+INSERT OR REPLACE INTO transaction_message_search (rowid, message) SELECT tx_id, message FROM completed_transactions;
+INSERT INTO contact_alias_search (rowid, alias) SELECT rowid, alias FROM contacts;
+INSERT INTO invoice_memo_search (rowid, memo) SELECT invoice_id, memo FROM invoices;
@@ -0,0 +1,2 @@
+DROP TABLE key_index_reservations;
+DROP TABLE key_manager_branches;
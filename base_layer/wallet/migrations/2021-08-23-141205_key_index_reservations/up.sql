@@ -0,0 +1,21 @@
+CREATE TABLE key_manager_branches (
+    branch TEXT PRIMARY KEY NOT NULL,
+    key_index BIGINT NOT NULL,
+    tracked_from BIGINT NOT NULL
+);
+
+CREATE TABLE key_index_reservations (
+    branch TEXT NOT NULL,
+    key_index BIGINT NOT NULL,
+    consumer TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    timestamp DATETIME NOT NULL,
+    PRIMARY KEY (branch, key_index)
+);
+
+-- Indexes handed out before reservations were tracked cannot be audited, so tracking starts at the current index
+INSERT INTO key_manager_branches (branch, key_index, tracked_from)
+    SELECT 'primary', primary_key_index, primary_key_index FROM key_manager_states LIMIT 1;
+
+INSERT INTO key_manager_branches (branch, key_index, tracked_from)
+    SELECT 'account_' || name, key_index, key_index FROM key_manager_accounts;
@@ -0,0 +1,2 @@
+ALTER TABLE outputs
+    DROP COLUMN features_encrypted_data;
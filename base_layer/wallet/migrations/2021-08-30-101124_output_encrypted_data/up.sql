@@ -0,0 +1,2 @@
+ALTER TABLE outputs
+    ADD COLUMN features_encrypted_data BLOB NOT NULL DEFAULT x'';
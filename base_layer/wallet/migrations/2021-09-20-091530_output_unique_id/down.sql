@@ -0,0 +1,4 @@
+ALTER TABLE outputs
+    DROP COLUMN features_parent_public_key;
+ALTER TABLE outputs
+    DROP COLUMN features_unique_id;
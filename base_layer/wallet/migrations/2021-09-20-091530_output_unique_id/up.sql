@@ -0,0 +1,4 @@
+ALTER TABLE outputs
+    ADD COLUMN features_parent_public_key BLOB NULL;
+ALTER TABLE outputs
+    ADD COLUMN features_unique_id BLOB NULL;
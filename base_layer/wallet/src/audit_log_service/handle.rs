@@ -0,0 +1,122 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::audit_log_service::{
+    error::AuditLogServiceError,
+    storage::database::{AuditAction, AuditLogEntry},
+};
+use chrono::{NaiveDateTime, Utc};
+use futures::channel::mpsc;
+use log::*;
+use tari_service_framework::reply_channel::SenderService;
+use tower::Service;
+
+const LOG_TARGET: &str = "wallet::audit_log_service::handle";
+
+#[derive(Debug)]
+pub enum AuditLogRequest {
+    GetEntries { from_sequence: u64, limit: usize },
+    VerifyChain,
+    ExportJson,
+}
+
+#[derive(Debug)]
+pub enum AuditLogResponse {
+    Entries(Vec<AuditLogEntry>),
+    ChainVerified(u64),
+    ExportedJson(String),
+}
+
+/// An operation that is waiting to be appended to the audit log
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub action: AuditAction,
+    pub details: String,
+    /// The time at which the operation was performed
+    pub timestamp: NaiveDateTime,
+}
+
+/// The audit log keeps an append-only, hash-chained record of the key usage, signing operations and destructive
+/// actions of the wallet
+#[derive(Clone)]
+pub struct AuditLogHandle {
+    handle: SenderService<AuditLogRequest, Result<AuditLogResponse, AuditLogServiceError>>,
+    record_sender: mpsc::UnboundedSender<AuditRecord>,
+}
+
+impl AuditLogHandle {
+    pub fn new(
+        handle: SenderService<AuditLogRequest, Result<AuditLogResponse, AuditLogServiceError>>,
+        record_sender: mpsc::UnboundedSender<AuditRecord>,
+    ) -> Self {
+        Self { handle, record_sender }
+    }
+
+    /// Queue an operation to be appended to the audit log. Recording does not wait for the entry to be stored, so that
+    /// it never delays or fails the operation being audited.
+    pub fn record<S: Into<String>>(&self, action: AuditAction, details: S) {
+        let record = AuditRecord {
+            action,
+            details: details.into(),
+            timestamp: Utc::now().naive_utc(),
+        };
+        if let Err(e) = self.record_sender.unbounded_send(record) {
+            error!(
+                target: LOG_TARGET,
+                "Could not record {} in the audit log because the audit log service is not running",
+                e.into_inner().action
+            );
+        }
+    }
+
+    /// Return up to `limit` entries, starting at the entry with the given sequence number. Sequence numbers start at 1.
+    pub async fn get_entries(
+        &mut self,
+        from_sequence: u64,
+        limit: usize,
+    ) -> Result<Vec<AuditLogEntry>, AuditLogServiceError> {
+        match self
+            .handle
+            .call(AuditLogRequest::GetEntries { from_sequence, limit })
+            .await??
+        {
+            AuditLogResponse::Entries(entries) => Ok(entries),
+            _ => Err(AuditLogServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Check the hash chain of the whole audit log and return the number of entries in it
+    pub async fn verify_chain(&mut self) -> Result<u64, AuditLogServiceError> {
+        match self.handle.call(AuditLogRequest::VerifyChain).await?? {
+            AuditLogResponse::ChainVerified(num_entries) => Ok(num_entries),
+            _ => Err(AuditLogServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Export the whole audit log as JSON for a compliance review. The hash chain is verified before it is exported.
+    pub async fn export_json(&mut self) -> Result<String, AuditLogServiceError> {
+        match self.handle.call(AuditLogRequest::ExportJson).await?? {
+            AuditLogResponse::ExportedJson(json) => Ok(json),
+            _ => Err(AuditLogServiceError::UnexpectedApiResponse),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Audit log
+//! An append-only log of every signing operation, key derivation, encryption change and destructive action (cancel,
+//! delete, reclaim) of the wallet. Each entry commits to the hash of the entry before it, so that the log can be
+//! verified for tampering before it is exported for a compliance review. The entries are not encrypted when wallet
+//! encryption is applied, since they only contain identifiers and must remain verifiable without the passphrase.
+
+pub mod error;
+pub mod handle;
+pub mod service;
+pub mod storage;
+
+use crate::audit_log_service::{
+    handle::AuditLogHandle,
+    service::AuditLogService,
+    storage::database::{AuditLogBackend, AuditLogDatabase},
+};
+use futures::channel::mpsc;
+use log::*;
+use tari_service_framework::{
+    async_trait,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+    ServiceInitializerContext,
+};
+
+const LOG_TARGET: &str = "wallet::audit_log_service::initializer";
+
+pub struct AuditLogServiceInitializer<T>
+where T: AuditLogBackend
+{
+    backend: Option<T>,
+}
+
+impl<T> AuditLogServiceInitializer<T>
+where T: AuditLogBackend
+{
+    pub fn new(backend: T) -> Self {
+        Self { backend: Some(backend) }
+    }
+}
+
+#[async_trait]
+impl<T> ServiceInitializer for AuditLogServiceInitializer<T>
+where T: AuditLogBackend + 'static
+{
+    async fn initialize(&mut self, context: ServiceInitializerContext) -> Result<(), ServiceInitializationError> {
+        let (sender, receiver) = reply_channel::unbounded();
+        let (record_sender, record_receiver) = mpsc::unbounded();
+
+        let handle = AuditLogHandle::new(sender, record_sender);
+
+        // Register handle before waiting for handles to be ready
+        context.register_handle(handle);
+
+        let backend = self
+            .backend
+            .take()
+            .expect("Cannot start Audit Log Service without setting a storage backend");
+
+        context.spawn_when_ready(move |handles| async move {
+            let service = AuditLogService::new(
+                receiver,
+                record_receiver,
+                AuditLogDatabase::new(backend),
+                handles.get_shutdown_signal(),
+            )
+            .start();
+            // The service stops itself on shutdown, once the operations that were already recorded are stored
+            if let Err(e) = service.await {
+                error!(target: LOG_TARGET, "Audit log service failed: {:?}", e);
+            }
+            info!(target: LOG_TARGET, "Audit log service shutdown");
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::audit_log_service::{
+    error::AuditLogServiceError,
+    handle::{AuditLogRequest, AuditLogResponse, AuditRecord},
+    storage::database::{AuditLogBackend, AuditLogDatabase},
+};
+use futures::{channel::mpsc, pin_mut, StreamExt};
+use log::*;
+use tari_service_framework::reply_channel;
+use tari_shutdown::ShutdownSignal;
+
+const LOG_TARGET: &str = "wallet::audit_log_service";
+
+/// Appends the records sent by the audit log handles to the audit log, one at a time, so that every entry is chained
+/// to the entry before it
+pub struct AuditLogService<T>
+where T: AuditLogBackend + 'static
+{
+    db: AuditLogDatabase<T>,
+    request_stream: Option<reply_channel::Receiver<AuditLogRequest, Result<AuditLogResponse, AuditLogServiceError>>>,
+    record_stream: Option<mpsc::UnboundedReceiver<AuditRecord>>,
+    shutdown_signal: Option<ShutdownSignal>,
+}
+
+impl<T> AuditLogService<T>
+where T: AuditLogBackend + 'static
+{
+    pub fn new(
+        request_stream: reply_channel::Receiver<AuditLogRequest, Result<AuditLogResponse, AuditLogServiceError>>,
+        record_stream: mpsc::UnboundedReceiver<AuditRecord>,
+        db: AuditLogDatabase<T>,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self {
+        Self {
+            db,
+            request_stream: Some(request_stream),
+            record_stream: Some(record_stream),
+            shutdown_signal: Some(shutdown_signal),
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), AuditLogServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Audit Log Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+
+        let mut record_stream = self
+            .record_stream
+            .take()
+            .expect("Audit Log Service initialized without record_stream");
+
+        let shutdown = self
+            .shutdown_signal
+            .take()
+            .expect("Audit Log Service initialized without shutdown signal");
+        pin_mut!(shutdown);
+
+        info!(target: LOG_TARGET, "Audit Log Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    // Store the operations that were recorded before the request so that the response includes them
+                    while let Ok(Some(record)) = record_stream.try_next() {
+                        self.append_record(record).await;
+                    }
+                    let (request, reply_tx) = request_context.split();
+                    let response = self.handle_request(request).await.map_err(|e| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", e);
+                        e
+                    });
+                    let _ = reply_tx.send(response).map_err(|e| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        e
+                    });
+                },
+                record = record_stream.select_next_some() => {
+                    self.append_record(record).await;
+                },
+                _ = shutdown => {
+                    info!(target: LOG_TARGET, "Audit log service shutting down because it received the shutdown signal");
+                    break;
+                }
+                complete => {
+                    info!(target: LOG_TARGET, "Audit log service shutting down");
+                    break;
+                }
+            }
+        }
+        // Do not lose the operations that were recorded just before shutdown
+        while let Ok(Some(record)) = record_stream.try_next() {
+            self.append_record(record).await;
+        }
+        info!(target: LOG_TARGET, "Audit Log Service ended");
+        Ok(())
+    }
+
+    async fn append_record(&self, record: AuditRecord) {
+        let AuditRecord {
+            action,
+            details,
+            timestamp,
+        } = record;
+        match self.db.append_entry(action, details, timestamp).await {
+            Ok(entry) => trace!(
+                target: LOG_TARGET,
+                "Audit log entry {} appended: {} {}",
+                entry.sequence,
+                entry.action,
+                entry.details
+            ),
+            Err(e) => error!(
+                target: LOG_TARGET,
+                "Could not append {} to the audit log: {:?}", action, e
+            ),
+        }
+    }
+
+    async fn handle_request(&mut self, request: AuditLogRequest) -> Result<AuditLogResponse, AuditLogServiceError> {
+        trace!(target: LOG_TARGET, "Handling Service Request: {:?}", request);
+        match request {
+            AuditLogRequest::GetEntries { from_sequence, limit } => Ok(AuditLogResponse::Entries(
+                self.db.get_entries(from_sequence, limit).await?,
+            )),
+            AuditLogRequest::VerifyChain => Ok(AuditLogResponse::ChainVerified(self.db.verify_chain().await?)),
+            AuditLogRequest::ExportJson => Ok(AuditLogResponse::ExportedJson(self.db.export_json().await?)),
+        }
+    }
+}
@@ -0,0 +1,325 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::audit_log_service::error::AuditLogStorageError;
+use chrono::NaiveDateTime;
+use digest::Digest;
+use serde::Serialize;
+use std::{convert::TryFrom, fmt, sync::Arc};
+use tari_crypto::{common::Blake256, tari_utilities::hex::Hex};
+
+/// The number of entries that are loaded at a time when the whole audit log is verified or exported
+const AUDIT_LOG_PAGE_SIZE: usize = 1000;
+
+/// This trait defines the functionality that a database backend needs to provide for the Audit Log Service. The audit
+/// log is append-only, so there are no methods to change or remove entries.
+pub trait AuditLogBackend: Send + Sync + Clone {
+    /// Append an entry that is chained to the current last entry. This must be atomic with respect to other appends so
+    /// that every entry has a unique sequence number and the hash of its predecessor.
+    fn append_audit_entry(
+        &self,
+        action: AuditAction,
+        details: &str,
+        timestamp: NaiveDateTime,
+    ) -> Result<AuditLogEntry, AuditLogStorageError>;
+    /// Retrieve up to `limit` entries, starting at the entry with the given sequence number, ordered by sequence number
+    fn fetch_audit_entries(&self, from_sequence: u64, limit: usize)
+        -> Result<Vec<AuditLogEntry>, AuditLogStorageError>;
+}
+
+/// The operations that are recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// A key index was reserved, from which a new key is derived
+    KeyDerived,
+    /// The wallet signed its part of a transaction
+    TransactionSigned,
+    /// A message was signed with a wallet key
+    MessageSigned,
+    EncryptionApplied,
+    EncryptionRemoved,
+    TransactionCancelled,
+    ContactRemoved,
+    /// The output of a one-sided payment was reclaimed by the sender
+    OneSidedReclaimed,
+}
+
+impl TryFrom<i32> for AuditAction {
+    type Error = AuditLogStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AuditAction::KeyDerived),
+            1 => Ok(AuditAction::TransactionSigned),
+            2 => Ok(AuditAction::MessageSigned),
+            3 => Ok(AuditAction::EncryptionApplied),
+            4 => Ok(AuditAction::EncryptionRemoved),
+            5 => Ok(AuditAction::TransactionCancelled),
+            6 => Ok(AuditAction::ContactRemoved),
+            7 => Ok(AuditAction::OneSidedReclaimed),
+            _ => Err(AuditLogStorageError::ConversionError),
+        }
+    }
+}
+
+impl From<AuditAction> for i32 {
+    fn from(action: AuditAction) -> Self {
+        match action {
+            AuditAction::KeyDerived => 0,
+            AuditAction::TransactionSigned => 1,
+            AuditAction::MessageSigned => 2,
+            AuditAction::EncryptionApplied => 3,
+            AuditAction::EncryptionRemoved => 4,
+            AuditAction::TransactionCancelled => 5,
+            AuditAction::ContactRemoved => 6,
+            AuditAction::OneSidedReclaimed => 7,
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditAction::KeyDerived => write!(f, "KeyDerived"),
+            AuditAction::TransactionSigned => write!(f, "TransactionSigned"),
+            AuditAction::MessageSigned => write!(f, "MessageSigned"),
+            AuditAction::EncryptionApplied => write!(f, "EncryptionApplied"),
+            AuditAction::EncryptionRemoved => write!(f, "EncryptionRemoved"),
+            AuditAction::TransactionCancelled => write!(f, "TransactionCancelled"),
+            AuditAction::ContactRemoved => write!(f, "ContactRemoved"),
+            AuditAction::OneSidedReclaimed => write!(f, "OneSidedReclaimed"),
+        }
+    }
+}
+
+/// An entry of the audit log. Every entry commits to the hash of the entry before it, so that an entry cannot be
+/// changed or removed without breaking the hash chain of all the entries after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditLogEntry {
+    /// The position of the entry in the log, starting at 1
+    pub sequence: u64,
+    pub timestamp: NaiveDateTime,
+    pub action: AuditAction,
+    pub details: String,
+    /// The hash of the previous entry, which is empty for the first entry
+    pub prev_hash: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+impl AuditLogEntry {
+    /// Create the entry that follows `prev`, or the first entry of the log if `prev` is `None`
+    pub fn new(prev: Option<&AuditLogEntry>, action: AuditAction, details: String, timestamp: NaiveDateTime) -> Self {
+        let sequence = prev.map(|p| p.sequence + 1).unwrap_or(1);
+        let prev_hash = prev.map(|p| p.hash.clone()).unwrap_or_default();
+        let hash = Self::calculate_hash(sequence, &timestamp, action, &details, &prev_hash);
+        Self {
+            sequence,
+            timestamp,
+            action,
+            details,
+            prev_hash,
+            hash,
+        }
+    }
+
+    fn calculate_hash(
+        sequence: u64,
+        timestamp: &NaiveDateTime,
+        action: AuditAction,
+        details: &str,
+        prev_hash: &[u8],
+    ) -> Vec<u8> {
+        Blake256::new()
+            .chain(prev_hash)
+            .chain(sequence.to_le_bytes())
+            .chain(timestamp.timestamp_millis().to_le_bytes())
+            .chain(i32::from(action).to_le_bytes())
+            .chain(details.as_bytes())
+            .finalize()
+            .to_vec()
+    }
+
+    /// Returns true if the hash of the entry is correct and the entry directly follows `prev`
+    pub fn is_valid_successor_of(&self, prev: Option<&AuditLogEntry>) -> bool {
+        let (sequence, prev_hash) = match prev {
+            Some(prev) => (prev.sequence + 1, prev.hash.as_slice()),
+            None => (1, &[][..]),
+        };
+        self.sequence == sequence &&
+            self.prev_hash == prev_hash &&
+            self.hash ==
+                Self::calculate_hash(
+                    self.sequence,
+                    &self.timestamp,
+                    self.action,
+                    &self.details,
+                    &self.prev_hash,
+                )
+    }
+}
+
+/// The representation of an entry in an exported audit log
+#[derive(Serialize)]
+struct ExportedAuditLogEntry {
+    sequence: u64,
+    timestamp: NaiveDateTime,
+    action: String,
+    details: String,
+    prev_hash: String,
+    hash: String,
+}
+
+impl From<AuditLogEntry> for ExportedAuditLogEntry {
+    fn from(entry: AuditLogEntry) -> Self {
+        Self {
+            sequence: entry.sequence,
+            timestamp: entry.timestamp,
+            action: entry.action.to_string(),
+            details: entry.details,
+            prev_hash: entry.prev_hash.to_hex(),
+            hash: entry.hash.to_hex(),
+        }
+    }
+}
+
+pub struct AuditLogDatabase<T>
+where T: AuditLogBackend
+{
+    db: Arc<T>,
+}
+
+impl<T> AuditLogDatabase<T>
+where T: AuditLogBackend + 'static
+{
+    pub fn new(db: T) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    pub async fn append_entry(
+        &self,
+        action: AuditAction,
+        details: String,
+        timestamp: NaiveDateTime,
+    ) -> Result<AuditLogEntry, AuditLogStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.append_audit_entry(action, &details, timestamp))
+            .await
+            .map_err(|err| AuditLogStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn get_entries(
+        &self,
+        from_sequence: u64,
+        limit: usize,
+    ) -> Result<Vec<AuditLogEntry>, AuditLogStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.fetch_audit_entries(from_sequence, limit))
+            .await
+            .map_err(|err| AuditLogStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    /// Check the hash chain of the whole log, one page at a time. Returns the number of entries in the log.
+    pub async fn verify_chain(&self) -> Result<u64, AuditLogStorageError> {
+        let mut last_entry = None;
+        loop {
+            let from_sequence = last_entry.as_ref().map(|e: &AuditLogEntry| e.sequence + 1).unwrap_or(1);
+            let entries = self.get_entries(from_sequence, AUDIT_LOG_PAGE_SIZE).await?;
+            if entries.is_empty() {
+                break;
+            }
+            for entry in entries {
+                if !entry.is_valid_successor_of(last_entry.as_ref()) {
+                    return Err(AuditLogStorageError::BrokenHashChain {
+                        sequence: entry.sequence,
+                    });
+                }
+                last_entry = Some(entry);
+            }
+        }
+        Ok(last_entry.map(|e| e.sequence).unwrap_or(0))
+    }
+
+    /// Export the whole log as a JSON array, after checking its hash chain
+    pub async fn export_json(&self) -> Result<String, AuditLogStorageError> {
+        self.verify_chain().await?;
+        let mut exported = Vec::new();
+        loop {
+            let entries = self.get_entries(exported.len() as u64 + 1, AUDIT_LOG_PAGE_SIZE).await?;
+            if entries.is_empty() {
+                break;
+            }
+            exported.extend(entries.into_iter().map(ExportedAuditLogEntry::from));
+        }
+        Ok(serde_json::to_string_pretty(&exported)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn it_chains_entries() {
+        let first = AuditLogEntry::new(
+            None,
+            AuditAction::KeyDerived,
+            "index 1".to_string(),
+            Utc::now().naive_utc(),
+        );
+        let second = AuditLogEntry::new(
+            Some(&first),
+            AuditAction::TransactionSigned,
+            "tx 123".to_string(),
+            Utc::now().naive_utc(),
+        );
+        assert_eq!(first.sequence, 1);
+        assert!(first.prev_hash.is_empty());
+        assert_eq!(second.sequence, 2);
+        assert_eq!(second.prev_hash, first.hash);
+        assert!(first.is_valid_successor_of(None));
+        assert!(second.is_valid_successor_of(Some(&first)));
+        assert!(!second.is_valid_successor_of(None));
+    }
+
+    #[test]
+    fn it_detects_tampered_entries() {
+        let first = AuditLogEntry::new(
+            None,
+            AuditAction::KeyDerived,
+            "index 1".to_string(),
+            Utc::now().naive_utc(),
+        );
+        let mut second = AuditLogEntry::new(
+            Some(&first),
+            AuditAction::TransactionCancelled,
+            "tx 123".to_string(),
+            Utc::now().naive_utc(),
+        );
+        second.details = "tx 456".to_string();
+        assert!(!second.is_valid_successor_of(Some(&first)));
+
+        let mut tampered_first = first;
+        tampered_first.action = AuditAction::MessageSigned;
+        assert!(!tampered_first.is_valid_successor_of(None));
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+pub struct BaseNodeSelectionServiceConfig {
+    /// How often every candidate base node is probed
+    pub probe_interval: Duration,
+    /// How long to wait for a candidate to respond to a probe before it is considered unreachable
+    pub probe_timeout: Duration,
+    /// How many blocks a candidate may be behind the highest candidate and still be selected
+    pub max_height_lag: u64,
+    /// How much lower the latency of a candidate must be than that of the current base node before switching to it
+    pub min_latency_improvement: Duration,
+    /// How many probes in a row the current base node must fail before switching away from it
+    pub max_probe_failures: u32,
+    /// If false, candidates are probed but the base node is never switched automatically
+    pub auto_switch: bool,
+}
+
+impl Default for BaseNodeSelectionServiceConfig {
+    fn default() -> Self {
+        Self {
+            probe_interval: Duration::from_secs(60),
+            probe_timeout: Duration::from_secs(20),
+            max_height_lag: 3,
+            min_latency_improvement: Duration::from_millis(500),
+            max_probe_failures: 2,
+            auto_switch: true,
+        }
+    }
+}
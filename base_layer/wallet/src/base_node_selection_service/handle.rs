@@ -0,0 +1,191 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::base_node_selection_service::error::BaseNodeSelectionServiceError;
+use chrono::NaiveDateTime;
+use futures::{stream::Fuse, StreamExt};
+use std::{fmt, sync::Arc, time::Duration};
+use tari_comms::{peer_manager::Peer, types::CommsPublicKey};
+use tari_service_framework::reply_channel::SenderService;
+use tokio::sync::broadcast;
+use tower::Service;
+
+/// A base node that the wallet may connect to, along with the result of the last time it was probed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseNodeCandidate {
+    pub peer: Peer,
+    pub latency: Option<Duration>,
+    pub height: Option<u64>,
+    pub is_synced: Option<bool>,
+    pub last_probed: Option<NaiveDateTime>,
+    /// The number of probes in a row that have failed. Zero if the last probe succeeded.
+    pub consecutive_failures: u32,
+}
+
+impl BaseNodeCandidate {
+    pub fn new(peer: Peer) -> Self {
+        Self {
+            peer,
+            latency: None,
+            height: None,
+            is_synced: None,
+            last_probed: None,
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Returns true if the last probe succeeded
+    pub fn is_reachable(&self) -> bool {
+        self.last_probed.is_some() && self.consecutive_failures == 0
+    }
+}
+
+/// Why the wallet switched to a different base node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchReason {
+    /// No base node was set
+    NoBaseNode,
+    /// The current base node failed too many probes in a row
+    Unreachable,
+    /// The current base node is not synced or is too far behind the other candidates
+    Behind,
+    /// Another candidate responds significantly faster than the current base node
+    LowerLatency,
+}
+
+impl fmt::Display for SwitchReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwitchReason::NoBaseNode => write!(f, "no base node set"),
+            SwitchReason::Unreachable => write!(f, "base node unreachable"),
+            SwitchReason::Behind => write!(f, "base node behind the network"),
+            SwitchReason::LowerLatency => write!(f, "lower latency"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BaseNodeSelectionServiceRequest {
+    AddCandidate(Box<Peer>),
+    RemoveCandidate(CommsPublicKey),
+    GetCandidates,
+    ProbeCandidates,
+}
+
+#[derive(Debug)]
+pub enum BaseNodeSelectionServiceResponse {
+    CandidateAdded,
+    CandidateRemoved,
+    Candidates(Vec<BaseNodeCandidate>),
+    /// The public key of the base node that was switched to, if any
+    CandidatesProbed(Option<CommsPublicKey>),
+}
+
+/// Events that can be published on the Base Node Selection Service Event Stream
+#[derive(Clone, Debug, PartialEq)]
+pub enum BaseNodeSelectionEvent {
+    BaseNodeSwitched {
+        previous: Option<CommsPublicKey>,
+        selected: CommsPublicKey,
+        reason: SwitchReason,
+    },
+    /// The current base node should be replaced but none of the candidates are suitable
+    NoSuitableBaseNode,
+}
+
+pub type BaseNodeSelectionEventSender = broadcast::Sender<Arc<BaseNodeSelectionEvent>>;
+pub type BaseNodeSelectionEventReceiver = broadcast::Receiver<Arc<BaseNodeSelectionEvent>>;
+
+#[derive(Clone)]
+pub struct BaseNodeSelectionServiceHandle {
+    handle: SenderService<
+        BaseNodeSelectionServiceRequest,
+        Result<BaseNodeSelectionServiceResponse, BaseNodeSelectionServiceError>,
+    >,
+    event_stream_sender: BaseNodeSelectionEventSender,
+}
+
+impl BaseNodeSelectionServiceHandle {
+    pub fn new(
+        handle: SenderService<
+            BaseNodeSelectionServiceRequest,
+            Result<BaseNodeSelectionServiceResponse, BaseNodeSelectionServiceError>,
+        >,
+        event_stream_sender: BaseNodeSelectionEventSender,
+    ) -> Self {
+        Self {
+            handle,
+            event_stream_sender,
+        }
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<BaseNodeSelectionEventReceiver> {
+        self.event_stream_sender.subscribe().fuse()
+    }
+
+    /// Add a base node that may be selected. The peer must already be known to the peer manager.
+    pub async fn add_candidate(&mut self, peer: Peer) -> Result<(), BaseNodeSelectionServiceError> {
+        match self
+            .handle
+            .call(BaseNodeSelectionServiceRequest::AddCandidate(Box::new(peer)))
+            .await??
+        {
+            BaseNodeSelectionServiceResponse::CandidateAdded => Ok(()),
+            _ => Err(BaseNodeSelectionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn remove_candidate(&mut self, public_key: CommsPublicKey) -> Result<(), BaseNodeSelectionServiceError> {
+        match self
+            .handle
+            .call(BaseNodeSelectionServiceRequest::RemoveCandidate(public_key))
+            .await??
+        {
+            BaseNodeSelectionServiceResponse::CandidateRemoved => Ok(()),
+            _ => Err(BaseNodeSelectionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_candidates(&mut self) -> Result<Vec<BaseNodeCandidate>, BaseNodeSelectionServiceError> {
+        match self
+            .handle
+            .call(BaseNodeSelectionServiceRequest::GetCandidates)
+            .await??
+        {
+            BaseNodeSelectionServiceResponse::Candidates(candidates) => Ok(candidates),
+            _ => Err(BaseNodeSelectionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Probe every candidate now instead of waiting for the next probe interval. Returns the public key of the base
+    /// node that was switched to, if the probe resulted in a switch.
+    pub async fn probe_candidates(&mut self) -> Result<Option<CommsPublicKey>, BaseNodeSelectionServiceError> {
+        match self
+            .handle
+            .call(BaseNodeSelectionServiceRequest::ProbeCandidates)
+            .await??
+        {
+            BaseNodeSelectionServiceResponse::CandidatesProbed(selected) => Ok(selected),
+            _ => Err(BaseNodeSelectionServiceError::UnexpectedApiResponse),
+        }
+    }
+}
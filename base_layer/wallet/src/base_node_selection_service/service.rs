@@ -0,0 +1,527 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    base_node_selection_service::{
+        config::BaseNodeSelectionServiceConfig,
+        error::BaseNodeSelectionServiceError,
+        handle::{
+            BaseNodeCandidate,
+            BaseNodeSelectionEvent,
+            BaseNodeSelectionEventSender,
+            BaseNodeSelectionServiceRequest,
+            BaseNodeSelectionServiceResponse,
+            SwitchReason,
+        },
+    },
+    base_node_service::handle::BaseNodeServiceHandle,
+    output_manager_service::handle::OutputManagerHandle,
+    transaction_service::handle::TransactionServiceHandle,
+    utxo_scanner_service::handle::UtxoScannerHandle,
+};
+use chrono::Utc;
+use futures::{future, pin_mut, StreamExt};
+use log::*;
+use std::{
+    convert::TryFrom,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tari_common_types::chain_metadata::ChainMetadata;
+use tari_comms::{
+    connectivity::{ConnectivityError, ConnectivityRequester},
+    peer_manager::{NodeId, Peer},
+    protocol::rpc::RpcError,
+    types::CommsPublicKey,
+};
+use tari_core::base_node::rpc::BaseNodeWalletRpcClient;
+use tari_service_framework::reply_channel;
+use tari_shutdown::ShutdownSignal;
+use tokio::time;
+
+const LOG_TARGET: &str = "wallet::base_node_selection_service";
+
+/// The Base Node Selection Service probes a list of candidate base nodes every `probe_interval` and switches the wallet
+/// to the best one. A candidate is suitable if it responded to the last probe, is synced and is no more than
+/// `max_height_lag` blocks behind the highest candidate. Of the suitable candidates the one with the lowest latency is
+/// the best.
+///
+/// The current base node is replaced if it has failed `max_probe_failures` probes in a row, if it is not suitable, or
+/// if the best candidate's latency is lower by at least `min_latency_improvement`. A base node that was set manually
+/// and is not one of the candidates is never replaced.
+pub struct BaseNodeSelectionService {
+    config: BaseNodeSelectionServiceConfig,
+    candidates: Vec<BaseNodeCandidate>,
+    request_stream: Option<
+        reply_channel::Receiver<
+            BaseNodeSelectionServiceRequest,
+            Result<BaseNodeSelectionServiceResponse, BaseNodeSelectionServiceError>,
+        >,
+    >,
+    connectivity_manager: ConnectivityRequester,
+    base_node_service: BaseNodeServiceHandle,
+    transaction_service: TransactionServiceHandle,
+    output_manager_service: OutputManagerHandle,
+    utxo_scanner_service: UtxoScannerHandle,
+    event_publisher: BaseNodeSelectionEventSender,
+    shutdown_signal: Option<ShutdownSignal>,
+}
+
+impl BaseNodeSelectionService {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: BaseNodeSelectionServiceConfig,
+        request_stream: reply_channel::Receiver<
+            BaseNodeSelectionServiceRequest,
+            Result<BaseNodeSelectionServiceResponse, BaseNodeSelectionServiceError>,
+        >,
+        connectivity_manager: ConnectivityRequester,
+        base_node_service: BaseNodeServiceHandle,
+        transaction_service: TransactionServiceHandle,
+        output_manager_service: OutputManagerHandle,
+        utxo_scanner_service: UtxoScannerHandle,
+        event_publisher: BaseNodeSelectionEventSender,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self {
+        Self {
+            config,
+            candidates: Vec::new(),
+            request_stream: Some(request_stream),
+            connectivity_manager,
+            base_node_service,
+            transaction_service,
+            output_manager_service,
+            utxo_scanner_service,
+            event_publisher,
+            shutdown_signal: Some(shutdown_signal),
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), BaseNodeSelectionServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Base Node Selection Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+
+        let shutdown = self
+            .shutdown_signal
+            .take()
+            .expect("Base Node Selection Service initialized without shutdown signal");
+        pin_mut!(shutdown);
+
+        let mut probe_interval = time::interval(self.config.probe_interval).fuse();
+
+        info!(target: LOG_TARGET, "Base Node Selection Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let response = self.handle_request(request).await.map_err(|e| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", e);
+                        e
+                    });
+                    let _ = reply_tx.send(response).map_err(|e| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        e
+                    });
+                },
+                _ = probe_interval.select_next_some() => {
+                    if let Err(e) = self.probe_and_select().await {
+                        error!(target: LOG_TARGET, "Error selecting base node: {:?}", e);
+                    }
+                },
+                _ = shutdown => {
+                    info!(target: LOG_TARGET, "Base Node Selection service shutting down because it received the shutdown signal");
+                    break;
+                }
+                complete => {
+                    info!(target: LOG_TARGET, "Base Node Selection service shutting down");
+                    break;
+                }
+            }
+        }
+        info!(target: LOG_TARGET, "Base Node Selection Service ended");
+        Ok(())
+    }
+
+    async fn handle_request(
+        &mut self,
+        request: BaseNodeSelectionServiceRequest,
+    ) -> Result<BaseNodeSelectionServiceResponse, BaseNodeSelectionServiceError> {
+        match request {
+            BaseNodeSelectionServiceRequest::AddCandidate(peer) => {
+                self.add_candidate(*peer);
+                Ok(BaseNodeSelectionServiceResponse::CandidateAdded)
+            },
+            BaseNodeSelectionServiceRequest::RemoveCandidate(public_key) => self
+                .remove_candidate(&public_key)
+                .map(|_| BaseNodeSelectionServiceResponse::CandidateRemoved),
+            BaseNodeSelectionServiceRequest::GetCandidates => {
+                Ok(BaseNodeSelectionServiceResponse::Candidates(self.candidates.clone()))
+            },
+            BaseNodeSelectionServiceRequest::ProbeCandidates => self
+                .probe_and_select()
+                .await
+                .map(BaseNodeSelectionServiceResponse::CandidatesProbed),
+        }
+    }
+
+    fn add_candidate(&mut self, peer: Peer) {
+        match self
+            .candidates
+            .iter_mut()
+            .find(|c| c.peer.public_key == peer.public_key)
+        {
+            Some(candidate) => candidate.peer = peer,
+            None => {
+                info!(target: LOG_TARGET, "Base node candidate {} added", peer.public_key);
+                self.candidates.push(BaseNodeCandidate::new(peer));
+            },
+        }
+    }
+
+    fn remove_candidate(&mut self, public_key: &CommsPublicKey) -> Result<(), BaseNodeSelectionServiceError> {
+        let index = self
+            .candidates
+            .iter()
+            .position(|c| &c.peer.public_key == public_key)
+            .ok_or_else(|| BaseNodeSelectionServiceError::CandidateNotFound(public_key.clone()))?;
+        self.candidates.remove(index);
+        info!(target: LOG_TARGET, "Base node candidate {} removed", public_key);
+        Ok(())
+    }
+
+    /// Probe every candidate and switch to the best one if the current base node should be replaced
+    async fn probe_and_select(&mut self) -> Result<Option<CommsPublicKey>, BaseNodeSelectionServiceError> {
+        if self.candidates.is_empty() {
+            return Ok(None);
+        }
+        self.probe_candidates().await;
+        if !self.config.auto_switch {
+            return Ok(None);
+        }
+
+        let current = self.base_node_service.get_base_node_peer().await?.map(|p| p.public_key);
+        if let Some(current) = current.as_ref() {
+            if !self.candidates.iter().any(|c| &c.peer.public_key == current) {
+                trace!(
+                    target: LOG_TARGET,
+                    "Current base node {} is not a candidate, it will not be replaced",
+                    current
+                );
+                return Ok(None);
+            }
+        }
+
+        match select_base_node(&self.candidates, current.as_ref(), &self.config) {
+            Selection::Keep => Ok(None),
+            Selection::NoneSuitable => {
+                warn!(
+                    target: LOG_TARGET,
+                    "The current base node should be replaced but none of the {} candidates are suitable",
+                    self.candidates.len()
+                );
+                self.publish_event(BaseNodeSelectionEvent::NoSuitableBaseNode);
+                Ok(None)
+            },
+            Selection::Switch(index, reason) => {
+                let peer = self.candidates[index].peer.clone();
+                self.switch_to(peer, current, reason).await.map(Some)
+            },
+        }
+    }
+
+    async fn probe_candidates(&mut self) {
+        let probes = self.candidates.iter().map(|candidate| {
+            let connectivity_manager = self.connectivity_manager.clone();
+            let node_id = candidate.peer.node_id.clone();
+            let probe_timeout = self.config.probe_timeout;
+            async move {
+                time::timeout(probe_timeout, probe(connectivity_manager, node_id))
+                    .await
+                    .unwrap_or(Err(ProbeError::Timeout))
+            }
+        });
+        let results = future::join_all(probes).await;
+
+        let now = Utc::now().naive_utc();
+        for (candidate, result) in self.candidates.iter_mut().zip(results) {
+            candidate.last_probed = Some(now);
+            match result {
+                Ok(probe) => {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Base node candidate {} is at height {} (synced: {}) with latency {:.2?}",
+                        candidate.peer.public_key,
+                        probe.height,
+                        probe.is_synced,
+                        probe.latency
+                    );
+                    candidate.latency = Some(probe.latency);
+                    candidate.height = Some(probe.height);
+                    candidate.is_synced = Some(probe.is_synced);
+                    candidate.consecutive_failures = 0;
+                },
+                Err(e) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Failed to probe base node candidate {}: {}", candidate.peer.public_key, e
+                    );
+                    candidate.latency = None;
+                    candidate.consecutive_failures += 1;
+                },
+            }
+        }
+    }
+
+    async fn switch_to(
+        &mut self,
+        peer: Peer,
+        previous: Option<CommsPublicKey>,
+        reason: SwitchReason,
+    ) -> Result<CommsPublicKey, BaseNodeSelectionServiceError> {
+        info!(
+            target: LOG_TARGET,
+            "Switching base node to {} ({})", peer.public_key, reason
+        );
+        let selected = peer.public_key.clone();
+
+        self.connectivity_manager
+            .add_managed_peers(vec![peer.node_id.clone()])
+            .await?;
+        self.transaction_service
+            .set_base_node_public_key(selected.clone())
+            .await?;
+        self.output_manager_service
+            .set_base_node_public_key(selected.clone())
+            .await?;
+        self.utxo_scanner_service
+            .set_base_node_public_key(selected.clone())
+            .await?;
+        self.base_node_service.set_base_node_peer(peer).await?;
+
+        self.publish_event(BaseNodeSelectionEvent::BaseNodeSwitched {
+            previous,
+            selected: selected.clone(),
+            reason,
+        });
+        Ok(selected)
+    }
+
+    fn publish_event(&self, event: BaseNodeSelectionEvent) {
+        let _ = self.event_publisher.send(Arc::new(event)).map_err(|e| {
+            trace!(
+                target: LOG_TARGET,
+                "Error sending event, usually because there are no subscribers: {:?}",
+                e
+            );
+            e
+        });
+    }
+}
+
+struct ProbeResult {
+    latency: Duration,
+    height: u64,
+    is_synced: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum ProbeError {
+    #[error("Failed to dial base node: {0}")]
+    DialFailed(#[from] ConnectivityError),
+    #[error("Rpc error: {0}")]
+    RpcFailed(#[from] RpcError),
+    #[error("Invalid base node response: {0}")]
+    InvalidBaseNodeResponse(String),
+    #[error("Probe timed out")]
+    Timeout,
+}
+
+async fn probe(mut connectivity_manager: ConnectivityRequester, node_id: NodeId) -> Result<ProbeResult, ProbeError> {
+    let mut connection = connectivity_manager.dial_peer(node_id).await?;
+    let mut client = connection.connect_rpc::<BaseNodeWalletRpcClient>().await?;
+
+    let timer = Instant::now();
+    let tip_info = client.get_tip_info().await?;
+    let latency = client
+        .get_last_request_latency()
+        .await?
+        .unwrap_or_else(|| timer.elapsed());
+
+    let chain_metadata = tip_info
+        .metadata
+        .ok_or_else(|| ProbeError::InvalidBaseNodeResponse("Tip info no metadata".to_string()))
+        .and_then(|metadata| ChainMetadata::try_from(metadata).map_err(ProbeError::InvalidBaseNodeResponse))?;
+
+    Ok(ProbeResult {
+        latency,
+        height: chain_metadata.height_of_longest_chain(),
+        is_synced: tip_info.is_synced,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+enum Selection {
+    Keep,
+    Switch(usize, SwitchReason),
+    NoneSuitable,
+}
+
+fn select_base_node(
+    candidates: &[BaseNodeCandidate],
+    current: Option<&CommsPublicKey>,
+    config: &BaseNodeSelectionServiceConfig,
+) -> Selection {
+    let best_height = candidates
+        .iter()
+        .filter(|c| c.is_reachable())
+        .filter_map(|c| c.height)
+        .max()
+        .unwrap_or(0);
+    let is_suitable = |c: &BaseNodeCandidate| {
+        c.is_reachable() &&
+            c.is_synced == Some(true) &&
+            c.height
+                .map(|h| h.saturating_add(config.max_height_lag) >= best_height)
+                .unwrap_or(false)
+    };
+    let best = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| is_suitable(c))
+        .min_by_key(|(_, c)| c.latency);
+
+    let current = current.and_then(|key| candidates.iter().position(|c| &c.peer.public_key == key));
+    let reason = match current {
+        None => SwitchReason::NoBaseNode,
+        Some(index) => {
+            let candidate = &candidates[index];
+            if candidate.consecutive_failures >= config.max_probe_failures {
+                SwitchReason::Unreachable
+            } else if !candidate.is_reachable() {
+                // Give the current base node another chance before switching away from it
+                return Selection::Keep;
+            } else if !is_suitable(candidate) {
+                SwitchReason::Behind
+            } else {
+                return match (best, candidate.latency) {
+                    (Some((best_index, best_candidate)), Some(current_latency))
+                        if best_index != index &&
+                            best_candidate.latency.unwrap_or_default() + config.min_latency_improvement <
+                                current_latency =>
+                    {
+                        Selection::Switch(best_index, SwitchReason::LowerLatency)
+                    },
+                    _ => Selection::Keep,
+                };
+            }
+        },
+    };
+
+    match best {
+        Some((index, _)) => Selection::Switch(index, reason),
+        None => Selection::NoneSuitable,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_comms::peer_manager::{PeerFeatures, PeerFlags};
+    use tari_crypto::keys::PublicKey;
+
+    fn candidate(latency_ms: u64, height: u64, is_synced: bool) -> BaseNodeCandidate {
+        let (_, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let peer = Peer::new(
+            public_key.clone(),
+            NodeId::from_key(&public_key),
+            Default::default(),
+            PeerFlags::empty(),
+            PeerFeatures::COMMUNICATION_NODE,
+            Default::default(),
+            String::new(),
+        );
+        BaseNodeCandidate {
+            latency: Some(Duration::from_millis(latency_ms)),
+            height: Some(height),
+            is_synced: Some(is_synced),
+            last_probed: Some(Utc::now().naive_utc()),
+            ..BaseNodeCandidate::new(peer)
+        }
+    }
+
+    #[test]
+    fn it_selects_the_fastest_fresh_candidate() {
+        let config = BaseNodeSelectionServiceConfig::default();
+        // The fastest candidate is not synced and the second fastest is too far behind
+        let candidates = vec![
+            candidate(500, 100, true),
+            candidate(50, 100, false),
+            candidate(100, 90, true),
+            candidate(300, 99, true),
+        ];
+        assert_eq!(
+            select_base_node(&candidates, None, &config),
+            Selection::Switch(3, SwitchReason::NoBaseNode)
+        );
+
+        let unsuitable = vec![candidate(50, 100, false)];
+        assert_eq!(select_base_node(&unsuitable, None, &config), Selection::NoneSuitable);
+    }
+
+    #[test]
+    fn it_only_replaces_the_current_base_node_when_needed() {
+        let config = BaseNodeSelectionServiceConfig::default();
+        let mut candidates = vec![candidate(1000, 100, true), candidate(800, 100, true)];
+        let current = candidates[0].peer.public_key.clone();
+
+        // Not enough of an improvement to switch
+        assert_eq!(select_base_node(&candidates, Some(&current), &config), Selection::Keep);
+
+        candidates[1].latency = Some(Duration::from_millis(100));
+        assert_eq!(
+            select_base_node(&candidates, Some(&current), &config),
+            Selection::Switch(1, SwitchReason::LowerLatency)
+        );
+
+        candidates[1].latency = Some(Duration::from_millis(800));
+        candidates[0].height = Some(90);
+        assert_eq!(
+            select_base_node(&candidates, Some(&current), &config),
+            Selection::Switch(1, SwitchReason::Behind)
+        );
+
+        // A single failed probe is tolerated
+        candidates[0].height = Some(100);
+        candidates[0].consecutive_failures = 1;
+        assert_eq!(select_base_node(&candidates, Some(&current), &config), Selection::Keep);
+        candidates[0].consecutive_failures = config.max_probe_failures;
+        assert_eq!(
+            select_base_node(&candidates, Some(&current), &config),
+            Selection::Switch(1, SwitchReason::Unreachable)
+        );
+    }
+}
@@ -27,6 +27,7 @@ use tari_comms::peer_manager::Peer;
 
 use std::time::Duration;
 use tari_common_types::chain_metadata::ChainMetadata;
+use tari_core::transactions::tari_amount::MicroTari;
 use tari_service_framework::reply_channel::SenderService;
 use tokio::sync::broadcast;
 use tower::Service;
@@ -40,6 +41,7 @@ pub enum BaseNodeServiceRequest {
     SetBaseNodePeer(Box<Peer>),
     GetBaseNodePeer,
     GetBaseNodeLatency,
+    EstimateFeePerGram(u64),
 }
 /// API Response enum
 #[derive(Debug)]
@@ -48,11 +50,25 @@ pub enum BaseNodeServiceResponse {
     BaseNodePeerSet,
     BaseNodePeer(Option<Box<Peer>>),
     Latency(Option<Duration>),
+    FeePerGramEstimate(MicroTari),
 }
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum BaseNodeEvent {
     BaseNodeStateChanged(BaseNodeState),
     BaseNodePeerSet(Box<Peer>),
+    ChainReorg(ChainReorg),
+}
+
+/// Describes a reorg observed on the base node's chain
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ChainReorg {
+    /// The height of the newest block we had seen that is still part of the chain. Every block above this height may
+    /// have been replaced, and is 0 when the split is older than the tips that were tracked.
+    pub fork_height: u64,
+    /// The tip height before the reorg
+    pub orphaned_tip_height: u64,
+    /// The tip height after the reorg
+    pub new_tip_height: u64,
 }
 
 /// The Base Node Service Handle is a struct that contains the interfaces used to communicate with a running
@@ -109,4 +125,17 @@ impl BaseNodeServiceHandle {
             _ => Err(BaseNodeServiceError::UnexpectedApiResponse),
         }
     }
+
+    /// Asks the connected base node for the fee per gram required for a transaction to be mined within
+    /// `target_blocks` blocks
+    pub async fn estimate_fee_per_gram(&mut self, target_blocks: u64) -> Result<MicroTari, BaseNodeServiceError> {
+        match self
+            .handle
+            .call(BaseNodeServiceRequest::EstimateFeePerGram(target_blocks))
+            .await??
+        {
+            BaseNodeServiceResponse::FeePerGramEstimate(fee_per_gram) => Ok(fee_per_gram),
+            _ => Err(BaseNodeServiceError::UnexpectedApiResponse),
+        }
+    }
 }
@@ -20,10 +20,13 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::base_node_service::{
-    error::BaseNodeServiceError,
-    handle::{BaseNodeServiceRequest, BaseNodeServiceResponse},
-    service::{BaseNodeState, OnlineState},
+use crate::{
+    base_node_service::{
+        error::BaseNodeServiceError,
+        handle::{BaseNodeServiceRequest, BaseNodeServiceResponse},
+        service::{BaseNodeState, OnlineState},
+    },
+    types::DEFAULT_FEE_PER_GRAM,
 };
 use futures::StreamExt;
 use tari_common_types::chain_metadata::ChainMetadata;
@@ -129,6 +132,9 @@ impl MockBaseNodeService {
                 self.state.chain_metadata.clone(),
             )),
             BaseNodeServiceRequest::GetBaseNodeLatency => Ok(BaseNodeServiceResponse::Latency(None)),
+            BaseNodeServiceRequest::EstimateFeePerGram(_) => {
+                Ok(BaseNodeServiceResponse::FeePerGramEstimate(DEFAULT_FEE_PER_GRAM))
+            },
         }
     }
 }
@@ -22,7 +22,7 @@
 
 use crate::{
     base_node_service::{
-        handle::{BaseNodeEvent, BaseNodeEventSender},
+        handle::{BaseNodeEvent, BaseNodeEventSender, ChainReorg},
         service::{BaseNodeState, OnlineState},
     },
     error::WalletStorageError,
@@ -31,15 +31,18 @@ use crate::{
 use chrono::Utc;
 use futures::{future, future::Either};
 use log::*;
-use std::{convert::TryFrom, sync::Arc, time::Duration};
-use tari_common_types::chain_metadata::ChainMetadata;
+use std::{collections::VecDeque, convert::TryFrom, sync::Arc, time::Duration};
+use tari_common_types::{chain_metadata::ChainMetadata, types::BlockHash};
 use tari_comms::{
     connectivity::{ConnectivityError, ConnectivityRequester},
     peer_manager::NodeId,
     protocol::rpc::RpcError,
     PeerConnection,
 };
-use tari_core::base_node::rpc::BaseNodeWalletRpcClient;
+use tari_core::{
+    base_node::{rpc::BaseNodeWalletRpcClient, sync::rpc::BaseNodeSyncRpcClient},
+    proto::base_node::FindChainSplitRequest,
+};
 use tari_shutdown::ShutdownSignal;
 use tokio::{
     stream::StreamExt,
@@ -48,6 +51,8 @@ use tokio::{
 };
 
 const LOG_TARGET: &str = "wallet::base_node_service::chain_metadata_monitor";
+/// The number of previously seen tips that are sent to the base node to find where its chain split from ours
+const MAX_TRACKED_TIPS: usize = 500;
 
 pub struct BaseNodeMonitor<T> {
     interval: Duration,
@@ -56,6 +61,7 @@ pub struct BaseNodeMonitor<T> {
     connectivity_manager: ConnectivityRequester,
     event_publisher: BaseNodeEventSender,
     shutdown_signal: ShutdownSignal,
+    recent_tips: VecDeque<(u64, BlockHash)>,
 }
 
 impl<T: WalletBackend + 'static> BaseNodeMonitor<T> {
@@ -74,6 +80,7 @@ impl<T: WalletBackend + 'static> BaseNodeMonitor<T> {
             connectivity_manager,
             event_publisher,
             shutdown_signal,
+            recent_tips: VecDeque::new(),
         }
     }
 
@@ -135,9 +142,11 @@ impl<T: WalletBackend + 'static> BaseNodeMonitor<T> {
             target: LOG_TARGET,
             "Base node connected. Establishing RPC connection...",
         );
-        let client = self.connect_client(connection).await?;
+        let client = self.connect_client(connection.clone()).await?;
         debug!(target: LOG_TARGET, "RPC established",);
-        self.monitor_node(peer, client).await?;
+        // Tips seen on another base node tell us nothing about reorgs on this one
+        self.recent_tips.clear();
+        self.monitor_node(peer, connection, client).await?;
         Ok(())
     }
 
@@ -187,8 +196,9 @@ impl<T: WalletBackend + 'static> BaseNodeMonitor<T> {
     }
 
     async fn monitor_node(
-        &self,
+        &mut self,
         peer_node_id: NodeId,
+        mut connection: PeerConnection,
         mut client: BaseNodeWalletRpcClient,
     ) -> Result<(), BaseNodeMonitorError> {
         loop {
@@ -210,6 +220,7 @@ impl<T: WalletBackend + 'static> BaseNodeMonitor<T> {
                 })?;
 
             self.db.set_chain_metadata(chain_metadata.clone()).await?;
+            self.check_for_reorg(&mut connection, &chain_metadata).await?;
 
             self.map_state(move |state| BaseNodeState {
                 chain_metadata: Some(chain_metadata),
@@ -230,6 +241,58 @@ impl<T: WalletBackend + 'static> BaseNodeMonitor<T> {
         Ok(())
     }
 
+    /// Compares the base node's tip with the tips seen in previous rounds and publishes a `ChainReorg` event when the
+    /// last tip we saw is no longer part of the base node's chain.
+    async fn check_for_reorg(
+        &mut self,
+        connection: &mut PeerConnection,
+        chain_metadata: &ChainMetadata,
+    ) -> Result<(), BaseNodeMonitorError> {
+        let new_tip_height = chain_metadata.height_of_longest_chain();
+        let new_tip_hash = chain_metadata.best_block().clone();
+        if let Some((orphaned_tip_height, last_tip_hash)) = self.recent_tips.front().cloned() {
+            if last_tip_hash == new_tip_hash {
+                return Ok(());
+            }
+            let num_orphaned = self.count_orphaned_tips(connection).await?;
+            if num_orphaned > 0 {
+                self.recent_tips.drain(..num_orphaned);
+                // If none of the tracked tips are still on the chain, the split is deeper than we can tell
+                let fork_height = self.recent_tips.front().map(|(height, _)| *height).unwrap_or(0);
+                info!(
+                    target: LOG_TARGET,
+                    "Base node reorged from height {} to height {} (fork at height {})",
+                    orphaned_tip_height,
+                    new_tip_height,
+                    fork_height
+                );
+                self.publish_event(BaseNodeEvent::ChainReorg(ChainReorg {
+                    fork_height,
+                    orphaned_tip_height,
+                    new_tip_height,
+                }));
+            }
+        }
+
+        self.recent_tips.push_front((new_tip_height, new_tip_hash));
+        self.recent_tips.truncate(MAX_TRACKED_TIPS);
+        Ok(())
+    }
+
+    /// Returns the number of tracked tips, newest first, that are no longer part of the base node's chain
+    async fn count_orphaned_tips(&self, connection: &mut PeerConnection) -> Result<usize, BaseNodeMonitorError> {
+        let mut client = connection.connect_rpc::<BaseNodeSyncRpcClient>().await?;
+        let request = FindChainSplitRequest {
+            block_hashes: self.recent_tips.iter().map(|(_, hash)| hash.clone()).collect(),
+            header_count: 1,
+        };
+        match client.find_chain_split(request).await {
+            Ok(resp) => Ok(resp.fork_hash_index as usize),
+            Err(RpcError::RequestFailed(err)) if err.status_code().is_not_found() => Ok(self.recent_tips.len()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     async fn check_if_base_node_changed(&self, peer_node_id: &NodeId) -> Result<(), BaseNodeMonitorError> {
         // Check if the base node peer is no longer set or has changed
         if self
@@ -35,6 +35,7 @@ use log::*;
 use std::{sync::Arc, time::Duration};
 use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::{connectivity::ConnectivityRequester, peer_manager::Peer};
+use tari_core::{base_node::rpc::BaseNodeWalletRpcClient, transactions::tari_amount::MicroTari};
 use tari_service_framework::reply_channel::Receiver;
 use tari_shutdown::ShutdownSignal;
 use tokio::sync::RwLock;
@@ -173,6 +174,30 @@ where T: WalletBackend + 'static
         self.publish_event(BaseNodeEvent::BaseNodePeerSet(Box::new(peer)));
     }
 
+    async fn estimate_fee_per_gram(&mut self, target_blocks: u64) -> Result<MicroTari, BaseNodeServiceError> {
+        let peer = self
+            .get_state()
+            .await
+            .base_node_peer
+            .ok_or(BaseNodeServiceError::NoBaseNodePeer)?;
+        let mut conn = self.connectivity_manager.dial_peer(peer.node_id).await?;
+        let mut client = conn.connect_rpc::<BaseNodeWalletRpcClient>().await?;
+        let estimate = client.estimate_fee_per_gram(target_blocks).await?;
+        debug!(
+            target: LOG_TARGET,
+            "Base node estimated {} µT per gram for {} block(s) (is_synced = {})",
+            estimate.fee_per_gram,
+            estimate.target_blocks,
+            estimate.is_synced
+        );
+        if !estimate.is_synced {
+            return Err(BaseNodeServiceError::InvalidBaseNodeResponse(
+                "Base node is not synced and cannot estimate fees".to_string(),
+            ));
+        }
+        Ok(estimate.fee_per_gram.into())
+    }
+
     /// This handler is called when requests arrive from the various streams
     async fn handle_request(
         &mut self,
@@ -202,6 +227,10 @@ where T: WalletBackend + 'static
             BaseNodeServiceRequest::GetBaseNodeLatency => {
                 Ok(BaseNodeServiceResponse::Latency(self.state.read().await.latency))
             },
+            BaseNodeServiceRequest::EstimateFeePerGram(target_blocks) => {
+                let fee_per_gram = self.estimate_fee_per_gram(target_blocks).await?;
+                Ok(BaseNodeServiceResponse::FeePerGramEstimate(fee_per_gram))
+            },
         }
     }
 
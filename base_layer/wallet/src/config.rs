@@ -21,8 +21,12 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    base_node_selection_service::config::BaseNodeSelectionServiceConfig,
     base_node_service::config::BaseNodeServiceConfig,
+    contacts_service::config::ContactsServiceConfig,
+    history_sync_service::config::HistorySyncServiceConfig,
     output_manager_service::config::OutputManagerServiceConfig,
+    recurring_payment_service::config::RecurringPaymentServiceConfig,
     transaction_service::config::TransactionServiceConfig,
 };
 use std::time::Duration;
@@ -41,6 +45,10 @@ pub struct WalletConfig {
     pub rate_limit: usize,
     pub network: NetworkConsensus,
     pub base_node_service_config: BaseNodeServiceConfig,
+    pub recurring_payment_service_config: RecurringPaymentServiceConfig,
+    pub base_node_selection_service_config: BaseNodeSelectionServiceConfig,
+    pub contacts_service_config: ContactsServiceConfig,
+    pub history_sync_service_config: HistorySyncServiceConfig,
     pub scan_for_utxo_interval: Duration,
 }
 
@@ -66,6 +74,10 @@ impl WalletConfig {
             rate_limit: rate_limit.unwrap_or(50),
             network,
             base_node_service_config: base_node_service_config.unwrap_or_default(),
+            recurring_payment_service_config: Default::default(),
+            base_node_selection_service_config: Default::default(),
+            contacts_service_config: Default::default(),
+            history_sync_service_config: Default::default(),
             scan_for_utxo_interval: scan_for_utxo_interval.unwrap_or_else(|| Duration::from_secs(43200)),
         }
     }
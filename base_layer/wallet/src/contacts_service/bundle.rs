@@ -0,0 +1,378 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Contact bundles
+//!
+//! Contacts are exported and imported as a JSON contact bundle:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "contacts": [
+//!     {
+//!       "public_key": "<hex encoded public key>",
+//!       "alias": "Alice",
+//!       "notes": "Optional notes about Alice",
+//!       "emoji_id": "<optional emoji ID of the public key>"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `public_key` may be left out of a contact if its `emoji_id` is given. If both are given, they must refer to the same
+//! public key.
+//!
+//! A bundle can be encrypted with a passphrase to move contacts between devices. An encrypted bundle file contains
+//! `{"version": 1, "salt": "<hex>", "ciphertext": "<hex>"}`, where `ciphertext` is the AES-256-GCM encrypted JSON
+//! bundle and the key is derived from the passphrase and salt using Argon2id.
+
+use crate::{
+    contacts_service::{error::ContactsServiceError, storage::database::Contact},
+    util::{
+        emoji::EmojiId,
+        encryption::{
+            cipher_from_passphrase,
+            decrypt_bytes_integral_nonce,
+            encrypt_bytes_integral_nonce,
+            generate_passphrase_salt,
+        },
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    fs,
+    path::Path,
+};
+use tari_comms::types::CommsPublicKey;
+use tari_crypto::tari_utilities::hex::{from_hex, to_hex, Hex};
+
+/// The current version of the contact bundle format
+pub const CONTACT_BUNDLE_VERSION: u32 = 1;
+
+/// A single contact in a contact bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContactRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    pub alias: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji_id: Option<String>,
+}
+
+impl From<&Contact> for ContactRecord {
+    fn from(contact: &Contact) -> Self {
+        Self {
+            public_key: Some(contact.public_key.to_hex()),
+            alias: contact.alias.clone(),
+            notes: contact.notes.clone(),
+            emoji_id: Some(EmojiId::from_pubkey(&contact.public_key).to_string()),
+        }
+    }
+}
+
+impl TryFrom<ContactRecord> for Contact {
+    type Error = ContactsServiceError;
+
+    fn try_from(record: ContactRecord) -> Result<Self, Self::Error> {
+        let invalid = |msg: &str| ContactsServiceError::InvalidContactBundle(format!("{} '{}'", msg, record.alias));
+        let from_hex = record
+            .public_key
+            .as_deref()
+            .map(CommsPublicKey::from_hex)
+            .transpose()
+            .map_err(|_| invalid("Invalid public key for contact"))?;
+        let from_emoji_id = record
+            .emoji_id
+            .as_deref()
+            .map(EmojiId::str_to_pubkey)
+            .transpose()
+            .map_err(|_| invalid("Invalid emoji ID for contact"))?;
+
+        let public_key = match (from_hex, from_emoji_id) {
+            (Some(pk), Some(emoji_pk)) if pk != emoji_pk => {
+                return Err(invalid("Public key and emoji ID do not match for contact"))
+            },
+            (Some(pk), _) | (None, Some(pk)) => pk,
+            (None, None) => return Err(invalid("No public key or emoji ID for contact")),
+        };
+
+        Ok(Contact {
+            alias: record.alias,
+            public_key,
+            notes: record.notes,
+        })
+    }
+}
+
+/// A versioned collection of contacts that can be exported from one wallet and imported into another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContactBundle {
+    pub version: u32,
+    pub contacts: Vec<ContactRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedContactBundle {
+    version: u32,
+    salt: String,
+    ciphertext: String,
+}
+
+/// The contents of a contact bundle file, which may or may not be encrypted
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ContactBundleFile {
+    Encrypted(EncryptedContactBundle),
+    Plain(ContactBundle),
+}
+
+impl ContactBundle {
+    pub fn new(contacts: &[Contact]) -> Self {
+        Self {
+            version: CONTACT_BUNDLE_VERSION,
+            contacts: contacts.iter().map(ContactRecord::from).collect(),
+        }
+    }
+
+    /// Validates and converts the records in this bundle into contacts. An error is returned if any record is
+    /// invalid, so that a partially valid bundle is never imported.
+    pub fn to_contacts(&self) -> Result<Vec<Contact>, ContactsServiceError> {
+        if self.version > CONTACT_BUNDLE_VERSION {
+            return Err(ContactsServiceError::InvalidContactBundle(format!(
+                "Unsupported contact bundle version {}",
+                self.version
+            )));
+        }
+        self.contacts.iter().cloned().map(Contact::try_from).collect()
+    }
+
+    pub fn to_json(&self) -> Result<String, ContactsServiceError> {
+        serde_json::to_string_pretty(self).map_err(|e| ContactsServiceError::InvalidContactBundle(e.to_string()))
+    }
+
+    /// Serializes the bundle and encrypts it with a key derived from `passphrase`
+    pub fn to_encrypted_json(&self, passphrase: &str) -> Result<String, ContactsServiceError> {
+        let salt = generate_passphrase_salt();
+        let cipher = cipher_from_passphrase(passphrase, &salt)
+            .map_err(|_| ContactsServiceError::ContactBundleEncryptionFailed)?;
+        let ciphertext = encrypt_bytes_integral_nonce(&cipher, self.to_json()?.into_bytes())
+            .map_err(|_| ContactsServiceError::ContactBundleEncryptionFailed)?;
+        let encrypted = EncryptedContactBundle {
+            version: CONTACT_BUNDLE_VERSION,
+            salt: to_hex(&salt),
+            ciphertext: to_hex(&ciphertext),
+        };
+        serde_json::to_string_pretty(&encrypted).map_err(|e| ContactsServiceError::InvalidContactBundle(e.to_string()))
+    }
+
+    /// Parses a plain or encrypted contact bundle. A passphrase is required if the bundle is encrypted and ignored if
+    /// it is not.
+    pub fn from_json(json: &str, passphrase: Option<&str>) -> Result<Self, ContactsServiceError> {
+        let file = serde_json::from_str::<ContactBundleFile>(json)
+            .map_err(|e| ContactsServiceError::InvalidContactBundle(e.to_string()))?;
+        match file {
+            ContactBundleFile::Plain(bundle) => Ok(bundle),
+            ContactBundleFile::Encrypted(encrypted) => {
+                let passphrase = passphrase.ok_or_else(|| {
+                    ContactsServiceError::InvalidContactBundle(
+                        "The contact bundle is encrypted but no passphrase was given".to_string(),
+                    )
+                })?;
+                let salt = from_hex(&encrypted.salt)
+                    .map_err(|_| ContactsServiceError::InvalidContactBundle("Invalid salt".to_string()))?;
+                let ciphertext = from_hex(&encrypted.ciphertext)
+                    .map_err(|_| ContactsServiceError::InvalidContactBundle("Invalid ciphertext".to_string()))?;
+                let cipher = cipher_from_passphrase(passphrase, &salt)
+                    .map_err(|_| ContactsServiceError::ContactBundleDecryptionFailed)?;
+                let plaintext = decrypt_bytes_integral_nonce(&cipher, ciphertext)
+                    .map_err(|_| ContactsServiceError::ContactBundleDecryptionFailed)?;
+                serde_json::from_slice(&plaintext)
+                    .map_err(|e| ContactsServiceError::InvalidContactBundle(e.to_string()))
+            },
+        }
+    }
+
+    /// Writes the bundle to a file, encrypting it if a passphrase is given
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P, passphrase: Option<&str>) -> Result<(), ContactsServiceError> {
+        let json = match passphrase {
+            Some(passphrase) => self.to_encrypted_json(passphrase)?,
+            None => self.to_json()?,
+        };
+        fs::write(path, json).map_err(|e| ContactsServiceError::ContactBundleIoError(e.to_string()))
+    }
+
+    /// Reads a plain or encrypted bundle from a file
+    pub fn read_from_file<P: AsRef<Path>>(path: P, passphrase: Option<&str>) -> Result<Self, ContactsServiceError> {
+        let json = fs::read_to_string(path).map_err(|e| ContactsServiceError::ContactBundleIoError(e.to_string()))?;
+        Self::from_json(&json, passphrase)
+    }
+}
+
+/// The outcome of importing a contact bundle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContactsImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+/// Merges imported contacts into the existing address book and returns the contacts that have to be saved.
+///
+/// - Contacts that are not in the address book are added.
+/// - Existing contacts keep their alias, so that an import never undoes a local rename.
+/// - Imported notes are only added to existing contacts that do not have notes.
+/// - If a public key appears more than once in the import, the first record is used.
+pub fn merge_contacts(existing: &[Contact], imported: Vec<Contact>) -> (Vec<Contact>, ContactsImportSummary) {
+    let existing = existing
+        .iter()
+        .map(|c| (c.public_key.clone(), c))
+        .collect::<HashMap<_, _>>();
+    let mut seen = HashSet::new();
+    let mut summary = ContactsImportSummary::default();
+    let mut to_save = Vec::new();
+
+    for contact in imported {
+        if !seen.insert(contact.public_key.clone()) {
+            continue;
+        }
+        match existing.get(&contact.public_key) {
+            None => {
+                summary.added += 1;
+                to_save.push(contact);
+            },
+            Some(current) if current.notes.is_none() && contact.notes.is_some() => {
+                summary.updated += 1;
+                to_save.push(Contact {
+                    notes: contact.notes,
+                    ..(*current).clone()
+                });
+            },
+            Some(_) => {
+                summary.unchanged += 1;
+            },
+        }
+    }
+
+    (to_save, summary)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::PublicKey;
+
+    fn random_contact(alias: &str, notes: Option<&str>) -> Contact {
+        Contact {
+            alias: alias.to_string(),
+            public_key: CommsPublicKey::random_keypair(&mut OsRng).1,
+            notes: notes.map(ToString::to_string),
+        }
+    }
+
+    #[test]
+    fn it_round_trips_plain_and_encrypted_bundles() {
+        let contacts = vec![random_contact("Alice", Some("From work")), random_contact("Bob", None)];
+        let bundle = ContactBundle::new(&contacts);
+
+        let json = bundle.to_json().unwrap();
+        let parsed = ContactBundle::from_json(&json, None).unwrap();
+        assert_eq!(parsed.to_contacts().unwrap(), contacts);
+
+        let encrypted = bundle.to_encrypted_json("correct horse").unwrap();
+        assert!(!encrypted.contains("Alice"));
+        assert!(ContactBundle::from_json(&encrypted, None).is_err());
+        assert_eq!(
+            ContactBundle::from_json(&encrypted, Some("wrong horse")).unwrap_err(),
+            ContactsServiceError::ContactBundleDecryptionFailed
+        );
+        let parsed = ContactBundle::from_json(&encrypted, Some("correct horse")).unwrap();
+        assert_eq!(parsed.to_contacts().unwrap(), contacts);
+    }
+
+    #[test]
+    fn it_accepts_either_a_public_key_or_an_emoji_id() {
+        let contact = random_contact("Carol", None);
+        let emoji_id = EmojiId::from_pubkey(&contact.public_key).to_string();
+        let json = format!(
+            r#"{{"version": 1, "contacts": [{{"alias": "Carol", "emoji_id": "{}"}}]}}"#,
+            emoji_id
+        );
+        let bundle = ContactBundle::from_json(&json, None).unwrap();
+        assert_eq!(bundle.to_contacts().unwrap(), vec![contact]);
+
+        let other = random_contact("Dave", None);
+        let json = format!(
+            r#"{{"version": 1, "contacts": [{{"alias": "Carol", "public_key": "{}", "emoji_id": "{}"}}]}}"#,
+            other.public_key.to_hex(),
+            emoji_id
+        );
+        let bundle = ContactBundle::from_json(&json, None).unwrap();
+        assert!(bundle.to_contacts().is_err());
+
+        let bundle = ContactBundle::from_json(r#"{"version": 1, "contacts": [{"alias": "Eve"}]}"#, None).unwrap();
+        assert!(bundle.to_contacts().is_err());
+    }
+
+    #[test]
+    fn it_merges_imported_contacts() {
+        let alice = random_contact("Alice", None);
+        let bob = random_contact("Bob", Some("Local notes"));
+        let existing = vec![alice.clone(), bob.clone()];
+
+        let carol = random_contact("Carol", None);
+        let imported = vec![
+            Contact {
+                alias: "Alice Renamed".to_string(),
+                notes: Some("Imported notes".to_string()),
+                ..alice.clone()
+            },
+            Contact {
+                notes: Some("Imported notes".to_string()),
+                ..bob
+            },
+            carol.clone(),
+            Contact {
+                alias: "Carol Again".to_string(),
+                ..carol.clone()
+            },
+        ];
+
+        let (to_save, summary) = merge_contacts(&existing, imported);
+        assert_eq!(summary, ContactsImportSummary {
+            added: 1,
+            updated: 1,
+            unchanged: 1,
+        });
+        assert_eq!(to_save, vec![
+            Contact {
+                notes: Some("Imported notes".to_string()),
+                ..alice
+            },
+            carol
+        ]);
+    }
+}
@@ -36,6 +36,14 @@ pub enum ContactsServiceError {
     ContactsServiceStorageError(#[from] ContactsServiceStorageError),
     #[error("Transport channel error: `{0}`")]
     TransportChannelError(#[from] TransportChannelError),
+    #[error("Invalid contact bundle: `{0}`")]
+    InvalidContactBundle(String),
+    #[error("Could not encrypt the contact bundle")]
+    ContactBundleEncryptionFailed,
+    #[error("Could not decrypt the contact bundle. The passphrase may be incorrect.")]
+    ContactBundleDecryptionFailed,
+    #[error("Contact bundle IO error: `{0}`")]
+    ContactBundleIoError(String),
 }
 
 #[derive(Debug, Error, PartialEq)]
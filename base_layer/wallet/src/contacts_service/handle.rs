@@ -20,17 +20,71 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::contacts_service::{error::ContactsServiceError, storage::database::Contact};
-use tari_comms::types::CommsPublicKey;
+use crate::contacts_service::{
+    bundle::{ContactBundle, ContactsImportSummary},
+    error::ContactsServiceError,
+    storage::database::Contact,
+};
+use chrono::NaiveDateTime;
+use futures::{stream::Fuse, StreamExt};
+use std::{fmt, sync::Arc};
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
 use tari_service_framework::reply_channel::SenderService;
+use tokio::sync::broadcast;
 use tower::Service;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactOnlineStatus {
+    Online,
+    Offline,
+    /// The contact has not responded to a ping since the wallet started
+    NeverSeen,
+}
+
+impl fmt::Display for ContactOnlineStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContactOnlineStatus::Online => write!(f, "Online"),
+            ContactOnlineStatus::Offline => write!(f, "Offline"),
+            ContactOnlineStatus::NeverSeen => write!(f, "Never seen"),
+        }
+    }
+}
+
+/// The online status of a contact, as determined by pinging it using the liveness service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactLivenessData {
+    pub public_key: CommsPublicKey,
+    pub node_id: NodeId,
+    /// The latency of the last ping round trip in milliseconds, if known
+    pub latency: Option<u32>,
+    pub last_seen: Option<NaiveDateTime>,
+    pub online_status: ContactOnlineStatus,
+}
+
+impl ContactLivenessData {
+    pub fn new(public_key: CommsPublicKey) -> Self {
+        Self {
+            node_id: NodeId::from_public_key(&public_key),
+            public_key,
+            latency: None,
+            last_seen: None,
+            online_status: ContactOnlineStatus::NeverSeen,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ContactsServiceRequest {
     GetContact(CommsPublicKey),
     UpsertContact(Contact),
     RemoveContact(CommsPublicKey),
     GetContacts,
+    SearchContacts(String),
+    GetContactLiveness(CommsPublicKey),
+    GetContactsLiveness,
+    ExportContacts,
+    ImportContacts(Box<ContactBundle>),
 }
 
 #[derive(Debug)]
@@ -39,17 +93,63 @@ pub enum ContactsServiceResponse {
     ContactRemoved(Contact),
     Contact(Contact),
     Contacts(Vec<Contact>),
+    ContactLiveness(Box<ContactLivenessData>),
+    ContactsLiveness(Vec<ContactLivenessData>),
+    ContactBundle(Box<ContactBundle>),
+    ContactsImported(ContactsImportSummary),
+}
+
+/// Events that can be published on the Contacts Service Event Stream
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContactsLivenessEvent {
+    /// A contact responded to a ping or its online status changed
+    StatusUpdated(Box<ContactLivenessData>),
 }
 
+pub type ContactsLivenessEventSender = broadcast::Sender<Arc<ContactsLivenessEvent>>;
+pub type ContactsLivenessEventReceiver = broadcast::Receiver<Arc<ContactsLivenessEvent>>;
+
 #[derive(Clone)]
 pub struct ContactsServiceHandle {
     handle: SenderService<ContactsServiceRequest, Result<ContactsServiceResponse, ContactsServiceError>>,
+    liveness_events: ContactsLivenessEventSender,
 }
 impl ContactsServiceHandle {
     pub fn new(
         handle: SenderService<ContactsServiceRequest, Result<ContactsServiceResponse, ContactsServiceError>>,
+        liveness_events: ContactsLivenessEventSender,
     ) -> Self {
-        Self { handle }
+        Self {
+            handle,
+            liveness_events,
+        }
+    }
+
+    pub fn get_contacts_liveness_event_stream_fused(&self) -> Fuse<ContactsLivenessEventReceiver> {
+        self.liveness_events.subscribe().fuse()
+    }
+
+    /// Returns the online status of a contact
+    pub async fn get_contact_liveness(
+        &mut self,
+        pub_key: CommsPublicKey,
+    ) -> Result<ContactLivenessData, ContactsServiceError> {
+        match self
+            .handle
+            .call(ContactsServiceRequest::GetContactLiveness(pub_key))
+            .await??
+        {
+            ContactsServiceResponse::ContactLiveness(data) => Ok(*data),
+            _ => Err(ContactsServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the online status of every contact
+    pub async fn get_contacts_liveness(&mut self) -> Result<Vec<ContactLivenessData>, ContactsServiceError> {
+        match self.handle.call(ContactsServiceRequest::GetContactsLiveness).await?? {
+            ContactsServiceResponse::ContactsLiveness(data) => Ok(data),
+            _ => Err(ContactsServiceError::UnexpectedApiResponse),
+        }
     }
 
     pub async fn get_contact(&mut self, pub_key: CommsPublicKey) -> Result<Contact, ContactsServiceError> {
@@ -66,6 +166,19 @@ impl ContactsServiceHandle {
         }
     }
 
+    /// Returns the contacts matching `query`, best match first. The query can be part of an alias, part of an emoji ID
+    /// or hex public key, or a full emoji ID containing up to `MAX_SEARCH_EMOJI_DISTANCE` typos.
+    pub async fn search_contacts(&mut self, query: String) -> Result<Vec<Contact>, ContactsServiceError> {
+        match self
+            .handle
+            .call(ContactsServiceRequest::SearchContacts(query))
+            .await??
+        {
+            ContactsServiceResponse::Contacts(c) => Ok(c),
+            _ => Err(ContactsServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn upsert_contact(&mut self, contact: Contact) -> Result<(), ContactsServiceError> {
         match self
             .handle
@@ -87,4 +200,27 @@ impl ContactsServiceHandle {
             _ => Err(ContactsServiceError::UnexpectedApiResponse),
         }
     }
+
+    /// Returns a bundle of all contacts that can be imported into another wallet
+    pub async fn export_contacts(&mut self) -> Result<ContactBundle, ContactsServiceError> {
+        match self.handle.call(ContactsServiceRequest::ExportContacts).await?? {
+            ContactsServiceResponse::ContactBundle(bundle) => Ok(*bundle),
+            _ => Err(ContactsServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Merges the contacts in the bundle into the address book. See `bundle::merge_contacts` for the merge rules.
+    pub async fn import_contacts(
+        &mut self,
+        bundle: ContactBundle,
+    ) -> Result<ContactsImportSummary, ContactsServiceError> {
+        match self
+            .handle
+            .call(ContactsServiceRequest::ImportContacts(Box::new(bundle)))
+            .await??
+        {
+            ContactsServiceResponse::ContactsImported(summary) => Ok(summary),
+            _ => Err(ContactsServiceError::UnexpectedApiResponse),
+        }
+    }
 }
@@ -20,18 +20,25 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+pub mod bundle;
+pub mod config;
 pub mod error;
 pub mod handle;
 pub mod service;
 pub mod storage;
 
-use crate::contacts_service::{
-    handle::ContactsServiceHandle,
-    service::ContactsService,
-    storage::database::{ContactsBackend, ContactsDatabase},
+use crate::{
+    audit_log_service::handle::AuditLogHandle,
+    contacts_service::{
+        config::ContactsServiceConfig,
+        handle::ContactsServiceHandle,
+        service::ContactsService,
+        storage::database::{ContactsBackend, ContactsDatabase},
+    },
 };
 use futures::future;
 use log::*;
+use tari_p2p::services::liveness::LivenessHandle;
 use tari_service_framework::{
     async_trait,
     reply_channel,
@@ -39,20 +46,25 @@ use tari_service_framework::{
     ServiceInitializer,
     ServiceInitializerContext,
 };
+use tokio::sync::broadcast;
 
 const LOG_TARGET: &str = "wallet::contacts_service::initializer";
 
 pub struct ContactsServiceInitializer<T>
 where T: ContactsBackend
 {
+    config: ContactsServiceConfig,
     backend: Option<T>,
 }
 
 impl<T> ContactsServiceInitializer<T>
 where T: ContactsBackend
 {
-    pub fn new(backend: T) -> Self {
-        Self { backend: Some(backend) }
+    pub fn new(config: ContactsServiceConfig, backend: T) -> Self {
+        Self {
+            config,
+            backend: Some(backend),
+        }
     }
 }
 
@@ -62,8 +74,9 @@ where T: ContactsBackend + 'static
 {
     async fn initialize(&mut self, context: ServiceInitializerContext) -> Result<(), ServiceInitializationError> {
         let (sender, receiver) = reply_channel::unbounded();
+        let (publisher, _) = broadcast::channel(200);
 
-        let contacts_handle = ContactsServiceHandle::new(sender);
+        let contacts_handle = ContactsServiceHandle::new(sender, publisher.clone());
 
         // Register handle before waiting for handles to be ready
         context.register_handle(contacts_handle);
@@ -74,10 +87,21 @@ where T: ContactsBackend + 'static
             .expect("Cannot start Contacts Service without setting a storage backend");
 
         let shutdown_signal = context.get_shutdown_signal();
+        let config = self.config.clone();
 
         context.spawn_when_ready(move |handles| async move {
-            let service =
-                ContactsService::new(receiver, ContactsDatabase::new(backend), handles.get_shutdown_signal()).start();
+            // Contact liveness is only tracked if the liveness service is part of the stack, and contact removals are
+            // only audited if the audit log service is
+            let service = ContactsService::new(
+                config,
+                receiver,
+                ContactsDatabase::new(backend),
+                handles.get_shutdown_signal(),
+                handles.get_handle::<LivenessHandle>(),
+                publisher,
+                handles.get_handle::<AuditLogHandle>(),
+            )
+            .start();
             futures::pin_mut!(service);
             future::select(service, shutdown_signal).await;
             info!(target: LOG_TARGET, "Contacts service shutdown");
@@ -20,31 +20,58 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::contacts_service::{
-    error::ContactsServiceError,
-    handle::{ContactsServiceRequest, ContactsServiceResponse},
-    storage::database::{ContactsBackend, ContactsDatabase},
+use crate::{
+    audit_log_service::{handle::AuditLogHandle, storage::database::AuditAction},
+    contacts_service::{
+        bundle::{merge_contacts, ContactBundle},
+        config::ContactsServiceConfig,
+        error::ContactsServiceError,
+        handle::{
+            ContactLivenessData,
+            ContactOnlineStatus,
+            ContactsLivenessEvent,
+            ContactsLivenessEventSender,
+            ContactsServiceRequest,
+            ContactsServiceResponse,
+        },
+        storage::database::{Contact, ContactsBackend, ContactsDatabase},
+    },
+    util::emoji::{emoji_distance, EmojiId, EMOJI_ID_LENGTH},
 };
-use futures::{pin_mut, StreamExt};
+use chrono::Utc;
+use futures::{pin_mut, stream, StreamExt};
 use log::*;
+use std::{collections::HashMap, sync::Arc};
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_p2p::services::liveness::{LivenessEvent, LivenessHandle};
 use tari_service_framework::reply_channel;
 use tari_shutdown::ShutdownSignal;
+use tokio::time;
 
 const LOG_TARGET: &str = "wallet:contacts_service";
+/// The number of mistyped emoji a full emoji ID search query may contain and still match a contact
+pub const MAX_SEARCH_EMOJI_DISTANCE: usize = 2;
 
 pub struct ContactsService<T>
 where T: ContactsBackend + 'static
 {
+    config: ContactsServiceConfig,
     db: ContactsDatabase<T>,
     request_stream:
         Option<reply_channel::Receiver<ContactsServiceRequest, Result<ContactsServiceResponse, ContactsServiceError>>>,
     shutdown_signal: Option<ShutdownSignal>,
+    liveness: Option<LivenessHandle>,
+    liveness_data: HashMap<NodeId, ContactLivenessData>,
+    event_publisher: ContactsLivenessEventSender,
+    audit_log: Option<AuditLogHandle>,
 }
 
 impl<T> ContactsService<T>
 where T: ContactsBackend + 'static
 {
     pub fn new(
+        config: ContactsServiceConfig,
         request_stream: reply_channel::Receiver<
             ContactsServiceRequest,
             Result<ContactsServiceResponse, ContactsServiceError>,
@@ -52,11 +79,19 @@ where T: ContactsBackend + 'static
 
         db: ContactsDatabase<T>,
         shutdown_signal: ShutdownSignal,
+        liveness: Option<LivenessHandle>,
+        event_publisher: ContactsLivenessEventSender,
+        audit_log: Option<AuditLogHandle>,
     ) -> Self {
         Self {
+            config,
             db,
             request_stream: Some(request_stream),
             shutdown_signal: Some(shutdown_signal),
+            liveness,
+            liveness_data: HashMap::new(),
+            event_publisher,
+            audit_log,
         }
     }
 
@@ -74,6 +109,13 @@ where T: ContactsBackend + 'static
             .expect("Output Manager Service initialized without shutdown signal");
         pin_mut!(shutdown);
 
+        // Contacts are only pinged if the liveness service is running
+        let liveness_event_stream = stream::iter(self.liveness.as_ref().map(|l| l.get_event_stream()))
+            .flatten()
+            .fuse();
+        pin_mut!(liveness_event_stream);
+        let mut ping_interval = time::interval(self.config.liveness_ping_interval).fuse();
+
         info!(target: LOG_TARGET, "Contacts Service started");
         loop {
             futures::select! {
@@ -88,6 +130,17 @@ where T: ContactsBackend + 'static
                         e
                     });
                 },
+                event = liveness_event_stream.select_next_some() => {
+                    match event {
+                        Ok(event) => self.handle_liveness_event(&*event),
+                        Err(e) => debug!(target: LOG_TARGET, "Lagging read on Liveness event stream: {:?}", e),
+                    }
+                },
+                _ = ping_interval.select_next_some() => {
+                    if let Err(e) = self.ping_contacts().await {
+                        error!(target: LOG_TARGET, "Error pinging contacts: {:?}", e);
+                    }
+                },
                 _ = shutdown => {
                     info!(target: LOG_TARGET, "Contacts service shutting down because it received the shutdown signal");
                     break;
@@ -112,6 +165,7 @@ where T: ContactsBackend + 'static
             },
             ContactsServiceRequest::UpsertContact(c) => {
                 self.db.upsert_contact(c.clone()).await?;
+                self.ping_contact(c.public_key.clone()).await;
                 info!(
                     target: LOG_TARGET,
                     "Contact Saved: \nAlias: {}\nPubKey: {} ", c.alias, c.public_key
@@ -124,11 +178,184 @@ where T: ContactsBackend + 'static
                     target: LOG_TARGET,
                     "Contact Removed: \nAlias: {}\nPubKey: {} ", result.alias, result.public_key
                 );
+                if let Some(audit_log) = self.audit_log.as_ref() {
+                    audit_log.record(
+                        AuditAction::ContactRemoved,
+                        format!("Removed contact {} ({})", result.alias, result.public_key),
+                    );
+                }
                 Ok(ContactsServiceResponse::ContactRemoved(result))
             },
             ContactsServiceRequest::GetContacts => {
                 Ok(self.db.get_contacts().await.map(ContactsServiceResponse::Contacts)?)
             },
+            ContactsServiceRequest::SearchContacts(query) => {
+                let contacts = self.db.get_contacts().await?;
+                Ok(ContactsServiceResponse::Contacts(search_contacts(contacts, &query)))
+            },
+            ContactsServiceRequest::GetContactLiveness(pk) => {
+                let contact = self.db.get_contact(pk).await?;
+                Ok(ContactsServiceResponse::ContactLiveness(Box::new(
+                    self.get_liveness_data(contact.public_key),
+                )))
+            },
+            ContactsServiceRequest::GetContactsLiveness => {
+                let contacts = self.db.get_contacts().await?;
+                Ok(ContactsServiceResponse::ContactsLiveness(
+                    contacts
+                        .into_iter()
+                        .map(|c| self.get_liveness_data(c.public_key))
+                        .collect(),
+                ))
+            },
+            ContactsServiceRequest::ExportContacts => {
+                let contacts = self.db.get_contacts().await?;
+                Ok(ContactsServiceResponse::ContactBundle(Box::new(ContactBundle::new(
+                    &contacts,
+                ))))
+            },
+            ContactsServiceRequest::ImportContacts(bundle) => {
+                let imported = bundle.to_contacts()?;
+                let existing = self.db.get_contacts().await?;
+                let (to_save, summary) = merge_contacts(&existing, imported);
+                for contact in to_save {
+                    let public_key = contact.public_key.clone();
+                    self.db.upsert_contact(contact).await?;
+                    self.ping_contact(public_key).await;
+                }
+                info!(
+                    target: LOG_TARGET,
+                    "Contacts imported: {} added, {} updated, {} unchanged",
+                    summary.added,
+                    summary.updated,
+                    summary.unchanged
+                );
+                Ok(ContactsServiceResponse::ContactsImported(summary))
+            },
+        }
+    }
+
+    fn get_liveness_data(&self, public_key: CommsPublicKey) -> ContactLivenessData {
+        self.liveness_data
+            .get(&NodeId::from_public_key(&public_key))
+            .cloned()
+            .unwrap_or_else(|| ContactLivenessData::new(public_key))
+    }
+
+    /// Pings every contact and marks contacts that have not been seen for `liveness_offline_timeout` as offline
+    async fn ping_contacts(&mut self) -> Result<(), ContactsServiceError> {
+        if self.liveness.is_none() {
+            return Ok(());
+        }
+        let contacts = self.db.get_contacts().await?;
+        let node_ids = contacts
+            .iter()
+            .map(|c| NodeId::from_public_key(&c.public_key))
+            .collect::<Vec<_>>();
+        // Forget contacts that have been removed
+        self.liveness_data.retain(|node_id, _| node_ids.contains(node_id));
+
+        let offline_timeout = chrono::Duration::from_std(self.config.liveness_offline_timeout)
+            .unwrap_or_else(|_| chrono::Duration::max_value());
+        let now = Utc::now().naive_utc();
+        let mut went_offline = Vec::new();
+        for data in self.liveness_data.values_mut() {
+            if data.online_status == ContactOnlineStatus::Online &&
+                data.last_seen.map(|t| now - t > offline_timeout).unwrap_or(true)
+            {
+                data.online_status = ContactOnlineStatus::Offline;
+                went_offline.push(data.clone());
+            }
+        }
+        for data in went_offline {
+            debug!(target: LOG_TARGET, "Contact '{}' is offline", data.node_id.short_str());
+            self.publish_event(ContactsLivenessEvent::StatusUpdated(Box::new(data)));
+        }
+
+        for contact in contacts {
+            self.ping_contact(contact.public_key).await;
+        }
+        Ok(())
+    }
+
+    async fn ping_contact(&mut self, public_key: CommsPublicKey) {
+        if let Some(liveness) = self.liveness.as_mut() {
+            let node_id = NodeId::from_public_key(&public_key);
+            self.liveness_data
+                .entry(node_id.clone())
+                .or_insert_with(|| ContactLivenessData::new(public_key));
+            if let Err(e) = liveness.send_ping(node_id.clone()).await {
+                debug!(
+                    target: LOG_TARGET,
+                    "Failed to ping contact '{}': {}",
+                    node_id.short_str(),
+                    e
+                );
+            }
         }
     }
+
+    fn handle_liveness_event(&mut self, event: &LivenessEvent) {
+        // A ping from a contact shows that it is online just as well as a pong does
+        let event = match event {
+            LivenessEvent::ReceivedPing(event) | LivenessEvent::ReceivedPong(event) => event,
+            _ => return,
+        };
+        let data = match self.liveness_data.get_mut(&event.node_id) {
+            Some(data) => data,
+            None => return,
+        };
+        data.last_seen = Some(Utc::now().naive_utc());
+        data.latency = event.latency.or(data.latency);
+        data.online_status = ContactOnlineStatus::Online;
+        let data = data.clone();
+        self.publish_event(ContactsLivenessEvent::StatusUpdated(Box::new(data)));
+    }
+
+    fn publish_event(&self, event: ContactsLivenessEvent) {
+        let _ = self.event_publisher.send(Arc::new(event)).map_err(|e| {
+            trace!(
+                target: LOG_TARGET,
+                "Error sending event because there are no subscribers: {:?}",
+                e
+            );
+            e
+        });
+    }
+}
+
+/// Ranks the contacts that match `query`. Contacts whose alias, emoji ID or hex public key contains the query match
+/// exactly. A query that is about as long as an emoji ID also matches contacts whose emoji ID is a few typos away,
+/// ranked by the number of typos.
+fn search_contacts(contacts: Vec<Contact>, query: &str) -> Vec<Contact> {
+    let query = query.trim();
+    if query.is_empty() {
+        return contacts;
+    }
+    let lowercase_query = query.to_lowercase();
+    let is_emoji_id_length = query.chars().count() + MAX_SEARCH_EMOJI_DISTANCE >= EMOJI_ID_LENGTH;
+
+    let mut matches = contacts
+        .into_iter()
+        .filter_map(|contact| {
+            let emoji_id = EmojiId::from_pubkey(&contact.public_key);
+            if contact.alias.to_lowercase().contains(&lowercase_query) ||
+                emoji_id.as_str().contains(query) ||
+                contact.public_key.to_hex().contains(&lowercase_query)
+            {
+                return Some((0, contact));
+            }
+            if !is_emoji_id_length {
+                return None;
+            }
+            let distance = emoji_distance(query, emoji_id.as_str());
+            if distance <= MAX_SEARCH_EMOJI_DISTANCE {
+                Some((distance, contact))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by(|(a_distance, a), (b_distance, b)| a_distance.cmp(b_distance).then_with(|| a.alias.cmp(&b.alias)));
+    matches.into_iter().map(|(_, contact)| contact).collect()
 }
@@ -34,6 +34,8 @@ const LOG_TARGET: &str = "wallet::contacts_service::database";
 pub struct Contact {
     pub alias: String,
     pub public_key: CommsPublicKey,
+    /// Free-form notes about the contact
+    pub notes: Option<String>,
 }
 
 /// This trait defines the functionality that a database backend need to provide for the Contacts Service
@@ -72,7 +72,13 @@ impl ContactsBackend for ContactsServiceSqliteDatabase {
             WriteOperation::Upsert(kvp) => match kvp {
                 DbKeyValuePair::Contact(k, c) => match ContactSql::find(&k.to_vec(), &(*conn)) {
                     Ok(found_c) => {
-                        let _ = found_c.update(UpdateContact { alias: Some(c.alias) }, &(*conn))?;
+                        let _ = found_c.update(
+                            UpdateContact {
+                                alias: Some(c.alias),
+                                notes: Some(c.notes),
+                            },
+                            &(*conn),
+                        )?;
                     },
                     Err(_) => {
                         ContactSql::from(c).commit(&conn)?;
@@ -102,6 +108,7 @@ impl ContactsBackend for ContactsServiceSqliteDatabase {
 struct ContactSql {
     public_key: Vec<u8>,
     alias: String,
+    notes: Option<String>,
 }
 
 impl ContactSql {
@@ -163,6 +170,7 @@ impl TryFrom<ContactSql> for Contact {
         Ok(Self {
             public_key: PublicKey::from_vec(&o.public_key).map_err(|_| ContactsServiceStorageError::ConversionError)?,
             alias: o.alias,
+            notes: o.notes,
         })
     }
 }
@@ -173,6 +181,7 @@ impl From<Contact> for ContactSql {
         Self {
             public_key: o.public_key.to_vec(),
             alias: o.alias,
+            notes: o.notes,
         }
     }
 }
@@ -181,6 +190,7 @@ impl From<Contact> for ContactSql {
 #[table_name = "contacts"]
 pub struct UpdateContact {
     alias: Option<String>,
+    notes: Option<Option<String>>,
 }
 
 #[cfg(test)]
@@ -221,6 +231,7 @@ mod test {
                 contacts.push(Contact {
                     alias: names[i].clone(),
                     public_key: pub_key,
+                    notes: None,
                 });
                 ContactSql::from(contacts[i].clone()).commit(&conn).unwrap();
             }
@@ -249,6 +260,7 @@ mod test {
             c.update(
                 UpdateContact {
                     alias: Some("Fred".to_string()),
+                    notes: Some(Some("Met at the meetup".to_string())),
                 },
                 &conn,
             )
@@ -256,6 +268,7 @@ mod test {
 
             let c_updated = ContactSql::find(&contacts[1].public_key.to_vec(), &conn).unwrap();
             assert_eq!(c_updated.alias, "Fred".to_string());
+            assert_eq!(c_updated.notes, Some("Met at the meetup".to_string()));
         });
     }
 }
@@ -21,9 +21,12 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    base_node_selection_service::error::BaseNodeSelectionServiceError,
     base_node_service::error::BaseNodeServiceError,
     contacts_service::error::ContactsServiceError,
-    output_manager_service::error::OutputManagerError,
+    history_sync_service::error::HistorySyncServiceError,
+    output_manager_service::{error::OutputManagerError, utxo_bundle::UtxoBundleError},
+    recurring_payment_service::error::RecurringPaymentServiceError,
     storage::database::DbKey,
     transaction_service::error::TransactionServiceError,
     utxo_scanner_service::error::UtxoScannerError,
@@ -83,6 +86,14 @@ pub enum WalletError {
     ByteArrayError(#[from] tari_crypto::tari_utilities::ByteArrayError),
     #[error("Utxo Scanner Error: {0}")]
     UtxoScannerError(#[from] UtxoScannerError),
+    #[error("Recurring payment service error: `{0}`")]
+    RecurringPaymentServiceError(#[from] RecurringPaymentServiceError),
+    #[error("Base node selection service error: `{0}`")]
+    BaseNodeSelectionServiceError(#[from] BaseNodeSelectionServiceError),
+    #[error("History sync service error: `{0}`")]
+    HistorySyncServiceError(#[from] HistorySyncServiceError),
+    #[error("UTXO bundle error: `{0}`")]
+    UtxoBundleError(#[from] UtxoBundleError),
 }
 
 #[derive(Debug, Error)]
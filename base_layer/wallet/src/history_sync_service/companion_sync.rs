@@ -0,0 +1,160 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    history_sync_service::{
+        config::HistorySyncServiceConfig,
+        error::HistorySyncServiceError,
+        handle::{HistorySyncEvent, HistorySyncEventSender},
+        rpc::WalletHistorySyncRpcClient,
+        storage::database::{HistorySyncBackend, HistorySyncDatabase, SyncUpdate, SyncedTransaction},
+    },
+    proto::history_sync as proto,
+};
+use chrono::Utc;
+use futures::{FutureExt, StreamExt};
+use log::*;
+use std::{convert::TryFrom, sync::Arc};
+use tari_comms::{connectivity::ConnectivityRequester, peer_manager::NodeId, types::CommsPublicKey};
+use tari_shutdown::ShutdownSignal;
+use tokio::time;
+
+const LOG_TARGET: &str = "wallet::history_sync_service::companion_sync";
+
+/// Keeps a companion wallet subscribed to the history of its primary wallet, resubscribing whenever the subscription
+/// ends, and applies the updates it receives to storage
+pub struct CompanionSyncTask<T>
+where T: HistorySyncBackend + 'static
+{
+    config: HistorySyncServiceConfig,
+    db: HistorySyncDatabase<T>,
+    connectivity: ConnectivityRequester,
+    primary_public_key: CommsPublicKey,
+    event_publisher: HistorySyncEventSender,
+}
+
+impl<T> CompanionSyncTask<T>
+where T: HistorySyncBackend + 'static
+{
+    pub fn new(
+        config: HistorySyncServiceConfig,
+        db: HistorySyncDatabase<T>,
+        connectivity: ConnectivityRequester,
+        primary_public_key: CommsPublicKey,
+        event_publisher: HistorySyncEventSender,
+    ) -> Self {
+        Self {
+            config,
+            db,
+            connectivity,
+            primary_public_key,
+            event_publisher,
+        }
+    }
+
+    pub async fn run(mut self, mut shutdown_signal: ShutdownSignal) {
+        loop {
+            let result = futures::select! {
+                result = self.sync().fuse() => result,
+                _ = shutdown_signal => break,
+            };
+            let reason = match result {
+                Ok(()) => "The primary wallet ended the subscription".to_string(),
+                Err(err) => err.to_string(),
+            };
+            debug!(
+                target: LOG_TARGET,
+                "History subscription to primary wallet `{}` ended: {}", self.primary_public_key, reason
+            );
+            self.publish_event(HistorySyncEvent::PrimaryWalletDisconnected(reason));
+
+            futures::select! {
+                _ = time::delay_for(self.config.reconnect_interval).fuse() => {},
+                _ = shutdown_signal => break,
+            }
+        }
+        debug!(target: LOG_TARGET, "Companion sync task ended");
+    }
+
+    async fn sync(&mut self) -> Result<(), HistorySyncServiceError> {
+        let state = self
+            .db
+            .get_sync_state()
+            .await?
+            .filter(|state| state.primary_public_key == self.primary_public_key)
+            .ok_or(HistorySyncServiceError::NoPrimaryWallet)?;
+
+        let node_id = NodeId::from_public_key(&self.primary_public_key);
+        let mut conn = self.connectivity.dial_peer(node_id).await?;
+        let mut client = conn.connect_rpc::<WalletHistorySyncRpcClient>().await?;
+        self.publish_event(HistorySyncEvent::PrimaryWalletConnected(
+            self.primary_public_key.clone(),
+        ));
+
+        let mut updates = client
+            .sync_history(proto::SyncHistoryRequest {
+                cursor: state.cursor.map(Into::into),
+            })
+            .await?;
+        while let Some(update) = updates.next().await {
+            let update = convert_update(update?)?;
+            let num_transactions = update.transactions.len();
+            self.db.apply_sync_update(update).await?;
+            trace!(
+                target: LOG_TARGET,
+                "Applied history update with {} transaction(s) from primary wallet",
+                num_transactions
+            );
+            self.publish_event(HistorySyncEvent::HistorySynced(num_transactions));
+        }
+
+        Ok(())
+    }
+
+    fn publish_event(&self, event: HistorySyncEvent) {
+        let _ = self.event_publisher.send(Arc::new(event)).map_err(|e| {
+            trace!(
+                target: LOG_TARGET,
+                "Error sending event, usually because there are no subscribers: {:?}",
+                e
+            );
+            e
+        });
+    }
+}
+
+fn convert_update(update: proto::HistoryUpdate) -> Result<SyncUpdate, HistorySyncServiceError> {
+    let transactions = update
+        .transactions
+        .into_iter()
+        .map(SyncedTransaction::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(HistorySyncServiceError::InvalidUpdate)?;
+
+    Ok(SyncUpdate {
+        is_snapshot: update.is_snapshot,
+        transactions,
+        cursor: update.cursor.map(Into::into),
+        balance: update.balance.map(Into::into),
+        timestamp: Utc::now().naive_utc(),
+    })
+}
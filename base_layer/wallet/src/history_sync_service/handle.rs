@@ -0,0 +1,214 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    history_sync_service::{
+        error::HistorySyncServiceError,
+        journal::HistoryCursor,
+        storage::database::{HistorySyncState, SyncedTransaction},
+    },
+    output_manager_service::TxId,
+};
+use futures::{stream::Fuse, StreamExt};
+use std::sync::Arc;
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
+use tari_service_framework::reply_channel::SenderService;
+use tokio::sync::broadcast;
+use tower::Service;
+
+#[derive(Debug)]
+pub enum HistorySyncServiceRequest {
+    AuthorizeCompanion(CommsPublicKey),
+    RevokeCompanion(CommsPublicKey),
+    GetCompanions,
+    IsCompanionAuthorized(NodeId),
+    GetHistoryChanges(Option<HistoryCursor>),
+    SetPrimaryWallet(CommsPublicKey),
+    ClearPrimaryWallet,
+    GetSyncState,
+    GetSyncedTransactions,
+}
+
+#[derive(Debug)]
+pub enum HistorySyncServiceResponse {
+    CompanionAuthorized,
+    CompanionRevoked(bool),
+    Companions(Vec<CommsPublicKey>),
+    IsCompanionAuthorized(bool),
+    HistoryChanges(HistoryChanges),
+    PrimaryWalletSet,
+    PrimaryWalletCleared,
+    SyncState(Option<Box<HistorySyncState>>),
+    SyncedTransactions(Vec<SyncedTransaction>),
+}
+
+/// The transactions of this wallet that changed since a cursor
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryChanges {
+    /// The cursor that includes the changes
+    pub cursor: HistoryCursor,
+    /// The ids of the transactions that changed, or None if the changes are not known and the full history must be
+    /// sent instead
+    pub tx_ids: Option<Vec<TxId>>,
+}
+
+/// Events that can be published on the History Sync Service Event Stream
+#[derive(Clone, Debug, PartialEq)]
+pub enum HistorySyncEvent {
+    /// A transaction of this wallet changed. Contains the new journal cursor.
+    HistoryChanged(HistoryCursor),
+    CompanionAuthorized(CommsPublicKey),
+    CompanionRevoked(CommsPublicKey),
+    /// An update from the primary wallet was applied. Contains the number of transactions that it changed.
+    HistorySynced(usize),
+    PrimaryWalletConnected(CommsPublicKey),
+    /// The subscription to the primary wallet ended, with the reason
+    PrimaryWalletDisconnected(String),
+}
+
+pub type HistorySyncEventSender = broadcast::Sender<Arc<HistorySyncEvent>>;
+pub type HistorySyncEventReceiver = broadcast::Receiver<Arc<HistorySyncEvent>>;
+
+#[derive(Clone)]
+pub struct HistorySyncHandle {
+    handle: SenderService<HistorySyncServiceRequest, Result<HistorySyncServiceResponse, HistorySyncServiceError>>,
+    event_stream_sender: HistorySyncEventSender,
+}
+
+impl HistorySyncHandle {
+    pub fn new(
+        handle: SenderService<HistorySyncServiceRequest, Result<HistorySyncServiceResponse, HistorySyncServiceError>>,
+        event_stream_sender: HistorySyncEventSender,
+    ) -> Self {
+        Self {
+            handle,
+            event_stream_sender,
+        }
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<HistorySyncEventReceiver> {
+        self.event_stream_sender.subscribe().fuse()
+    }
+
+    /// Allows the wallet with the given public key to subscribe to the history of this wallet
+    pub async fn authorize_companion(&mut self, public_key: CommsPublicKey) -> Result<(), HistorySyncServiceError> {
+        match self
+            .handle
+            .call(HistorySyncServiceRequest::AuthorizeCompanion(public_key))
+            .await??
+        {
+            HistorySyncServiceResponse::CompanionAuthorized => Ok(()),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Revokes a companion's access to the history of this wallet and ends its subscription. Returns false if the
+    /// companion was not authorized.
+    pub async fn revoke_companion(&mut self, public_key: CommsPublicKey) -> Result<bool, HistorySyncServiceError> {
+        match self
+            .handle
+            .call(HistorySyncServiceRequest::RevokeCompanion(public_key))
+            .await??
+        {
+            HistorySyncServiceResponse::CompanionRevoked(revoked) => Ok(revoked),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_companions(&mut self) -> Result<Vec<CommsPublicKey>, HistorySyncServiceError> {
+        match self.handle.call(HistorySyncServiceRequest::GetCompanions).await?? {
+            HistorySyncServiceResponse::Companions(c) => Ok(c),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn is_companion_authorized(&mut self, node_id: NodeId) -> Result<bool, HistorySyncServiceError> {
+        match self
+            .handle
+            .call(HistorySyncServiceRequest::IsCompanionAuthorized(node_id))
+            .await??
+        {
+            HistorySyncServiceResponse::IsCompanionAuthorized(authorized) => Ok(authorized),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the transactions of this wallet that changed since `cursor`
+    pub async fn get_history_changes(
+        &mut self,
+        cursor: Option<HistoryCursor>,
+    ) -> Result<HistoryChanges, HistorySyncServiceError> {
+        match self
+            .handle
+            .call(HistorySyncServiceRequest::GetHistoryChanges(cursor))
+            .await??
+        {
+            HistorySyncServiceResponse::HistoryChanges(c) => Ok(c),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Makes this wallet a companion of the wallet with the given public key, and subscribes to its history. The
+    /// primary wallet must have authorized this wallet as a companion.
+    pub async fn set_primary_wallet(&mut self, public_key: CommsPublicKey) -> Result<(), HistorySyncServiceError> {
+        match self
+            .handle
+            .call(HistorySyncServiceRequest::SetPrimaryWallet(public_key))
+            .await??
+        {
+            HistorySyncServiceResponse::PrimaryWalletSet => Ok(()),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Stops syncing from the primary wallet and discards the synced history
+    pub async fn clear_primary_wallet(&mut self) -> Result<(), HistorySyncServiceError> {
+        match self
+            .handle
+            .call(HistorySyncServiceRequest::ClearPrimaryWallet)
+            .await??
+        {
+            HistorySyncServiceResponse::PrimaryWalletCleared => Ok(()),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the primary wallet, cursor and balance of the current subscription, if any
+    pub async fn get_sync_state(&mut self) -> Result<Option<HistorySyncState>, HistorySyncServiceError> {
+        match self.handle.call(HistorySyncServiceRequest::GetSyncState).await?? {
+            HistorySyncServiceResponse::SyncState(s) => Ok(s.map(|s| *s)),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the transactions received from the primary wallet, newest first
+    pub async fn get_synced_transactions(&mut self) -> Result<Vec<SyncedTransaction>, HistorySyncServiceError> {
+        match self
+            .handle
+            .call(HistorySyncServiceRequest::GetSyncedTransactions)
+            .await??
+        {
+            HistorySyncServiceResponse::SyncedTransactions(t) => Ok(t),
+            _ => Err(HistorySyncServiceError::UnexpectedApiResponse),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::output_manager_service::TxId;
+use rand::{rngs::OsRng, RngCore};
+use std::{collections::VecDeque, fmt};
+
+/// A position in the change journal of the primary wallet. A companion resumes its subscription from the cursor of the
+/// last update that it applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryCursor {
+    /// Identifies the run of the wallet that issued the cursor. The journal is not persisted, so a cursor from a
+    /// previous run cannot be resumed from.
+    pub epoch: u64,
+    /// The number of changes that have been recorded up to this cursor
+    pub sequence: u64,
+}
+
+impl fmt::Display for HistoryCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}:{}", self.epoch, self.sequence)
+    }
+}
+
+/// A bounded, in-memory record of which transactions have changed, in the order that they changed. The journal allows
+/// the primary wallet to send a companion only the transactions that changed since its cursor, rather than the full
+/// history on every update.
+#[derive(Debug)]
+pub struct HistoryJournal {
+    epoch: u64,
+    next_sequence: u64,
+    entries: VecDeque<(u64, TxId)>,
+    max_entries: usize,
+}
+
+impl HistoryJournal {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            epoch: OsRng.next_u64(),
+            next_sequence: 0,
+            entries: VecDeque::new(),
+            max_entries: max_entries.max(1),
+        }
+    }
+
+    /// The cursor that includes every change recorded so far
+    pub fn cursor(&self) -> HistoryCursor {
+        HistoryCursor {
+            epoch: self.epoch,
+            sequence: self.next_sequence,
+        }
+    }
+
+    /// Records that a transaction was added or changed and returns the new cursor
+    pub fn record(&mut self, tx_id: TxId) -> HistoryCursor {
+        self.entries.push_back((self.next_sequence, tx_id));
+        self.next_sequence += 1;
+        while self.entries.len() > self.max_entries {
+            self.entries.pop_front();
+        }
+        self.cursor()
+    }
+
+    /// Returns the ids of the transactions that changed after `cursor`, oldest change first and without duplicates.
+    /// Returns None if the changes since the cursor are not known, either because it was issued by a different run
+    /// or because the entries it needs have been evicted, in which case the full history must be sent instead.
+    pub fn changes_since(&self, cursor: &HistoryCursor) -> Option<Vec<TxId>> {
+        let is_from_this_run = cursor.epoch == self.epoch;
+        if !is_from_this_run || cursor.sequence > self.next_sequence {
+            return None;
+        }
+        let oldest = self.entries.front().map(|(seq, _)| *seq).unwrap_or(self.next_sequence);
+        if cursor.sequence < oldest {
+            return None;
+        }
+
+        let mut tx_ids = Vec::new();
+        for (_, tx_id) in self.entries.iter().filter(|(seq, _)| *seq >= cursor.sequence) {
+            if !tx_ids.contains(tx_id) {
+                tx_ids.push(*tx_id);
+            }
+        }
+        Some(tx_ids)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_returns_changes_since_cursor() {
+        let mut journal = HistoryJournal::new(10);
+        let start = journal.cursor();
+        assert_eq!(journal.changes_since(&start), Some(vec![]));
+
+        journal.record(1);
+        let cursor = journal.record(2);
+        journal.record(1);
+        journal.record(3);
+
+        assert_eq!(journal.changes_since(&start), Some(vec![1, 2, 3]));
+        assert_eq!(journal.changes_since(&cursor), Some(vec![1, 3]));
+        assert_eq!(journal.changes_since(&journal.cursor()), Some(vec![]));
+    }
+
+    #[test]
+    fn it_rejects_unknown_cursors() {
+        let mut journal = HistoryJournal::new(2);
+        let start = journal.cursor();
+        journal.record(1);
+        journal.record(2);
+        journal.record(3);
+
+        // The first change has been evicted
+        assert_eq!(journal.changes_since(&start), None);
+        let cursor = HistoryCursor {
+            epoch: start.epoch,
+            sequence: 1,
+        };
+        assert_eq!(journal.changes_since(&cursor), Some(vec![2, 3]));
+
+        let other_run = HistoryCursor {
+            epoch: start.epoch.wrapping_add(1),
+            sequence: 1,
+        };
+        assert_eq!(journal.changes_since(&other_run), None);
+        let ahead = HistoryCursor {
+            epoch: start.epoch,
+            sequence: 4,
+        };
+        assert_eq!(journal.changes_since(&ahead), None);
+    }
+}
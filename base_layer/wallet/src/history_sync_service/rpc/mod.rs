@@ -0,0 +1,56 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+mod service;
+pub use service::HistorySyncRpcService;
+
+use crate::{
+    history_sync_service::{config::HistorySyncServiceConfig, handle::HistorySyncHandle},
+    output_manager_service::handle::OutputManagerHandle,
+    proto::history_sync::{HistoryUpdate, SyncHistoryRequest},
+    transaction_service::handle::TransactionServiceHandle,
+};
+use tari_comms::protocol::rpc::{Request, Response, RpcStatus, Streaming};
+use tari_comms_rpc_macros::tari_rpc;
+
+#[tari_rpc(protocol_name = b"t/wallethistory/1", server_struct = WalletHistorySyncRpcServer, client_struct = WalletHistorySyncRpcClient)]
+pub trait WalletHistorySyncService: Send + Sync + 'static {
+    /// Subscribes to the transaction history and balance of this wallet. The stream starts with the changes since the
+    /// requested cursor, or a snapshot of the full history, and stays open to deliver further changes as they happen.
+    /// Only companions that this wallet has authorized may subscribe.
+    #[rpc(method = 1)]
+    async fn sync_history(&self, request: Request<SyncHistoryRequest>) -> Result<Streaming<HistoryUpdate>, RpcStatus>;
+}
+
+pub fn create_history_sync_rpc_service(
+    config: HistorySyncServiceConfig,
+    history_sync: HistorySyncHandle,
+    transaction_service: TransactionServiceHandle,
+    output_manager_service: OutputManagerHandle,
+) -> WalletHistorySyncRpcServer<HistorySyncRpcService> {
+    WalletHistorySyncRpcServer::new(HistorySyncRpcService::new(
+        config,
+        history_sync,
+        transaction_service,
+        output_manager_service,
+    ))
+}
@@ -0,0 +1,221 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    history_sync_service::{
+        config::HistorySyncServiceConfig,
+        error::HistorySyncServiceError,
+        handle::{HistorySyncEvent, HistorySyncHandle},
+        journal::HistoryCursor,
+        rpc::WalletHistorySyncService,
+        service::fetch_full_history,
+        storage::database::SyncedTransaction,
+    },
+    output_manager_service::handle::OutputManagerHandle,
+    proto::history_sync as proto,
+    transaction_service::{handle::TransactionServiceHandle, storage::models::CompletedTransaction},
+};
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use log::*;
+use tari_comms::{
+    peer_manager::NodeId,
+    protocol::rpc::{Request, RpcStatus, Streaming},
+};
+use tokio::{sync::broadcast::RecvError, task, time};
+
+const LOG_TARGET: &str = "wallet::history_sync_service::rpc";
+
+/// Serves the history of this wallet to authorized companion wallets
+pub struct HistorySyncRpcService {
+    config: HistorySyncServiceConfig,
+    history_sync: HistorySyncHandle,
+    transaction_service: TransactionServiceHandle,
+    output_manager_service: OutputManagerHandle,
+}
+
+impl HistorySyncRpcService {
+    pub fn new(
+        config: HistorySyncServiceConfig,
+        history_sync: HistorySyncHandle,
+        transaction_service: TransactionServiceHandle,
+        output_manager_service: OutputManagerHandle,
+    ) -> Self {
+        Self {
+            config,
+            history_sync,
+            transaction_service,
+            output_manager_service,
+        }
+    }
+}
+
+#[tari_comms::async_trait]
+impl WalletHistorySyncService for HistorySyncRpcService {
+    async fn sync_history(
+        &self,
+        request: Request<proto::SyncHistoryRequest>,
+    ) -> Result<Streaming<proto::HistoryUpdate>, RpcStatus> {
+        let peer_node_id = request.context().peer_node_id().clone();
+        let mut history_sync = self.history_sync.clone();
+        let is_authorized = history_sync
+            .is_companion_authorized(peer_node_id.clone())
+            .await
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+        if !is_authorized {
+            debug!(
+                target: LOG_TARGET,
+                "Peer `{}` requested history sync but is not an authorized companion", peer_node_id
+            );
+            return Err(RpcStatus::forbidden("Not an authorized companion of this wallet"));
+        }
+
+        let cursor = request.into_message().cursor.map(HistoryCursor::from);
+        debug!(
+            target: LOG_TARGET,
+            "Companion `{}` subscribed to history from cursor {:?}", peer_node_id, cursor
+        );
+
+        let (tx, rx) = mpsc::channel(1);
+        let subscription = HistorySubscription {
+            config: self.config.clone(),
+            peer_node_id,
+            history_sync,
+            transaction_service: self.transaction_service.clone(),
+            output_manager_service: self.output_manager_service.clone(),
+            tx,
+        };
+        task::spawn(subscription.run(cursor));
+
+        Ok(Streaming::new(rx))
+    }
+}
+
+/// Streams updates to a single companion until it goes away or its authorization is revoked
+struct HistorySubscription {
+    config: HistorySyncServiceConfig,
+    peer_node_id: NodeId,
+    history_sync: HistorySyncHandle,
+    transaction_service: TransactionServiceHandle,
+    output_manager_service: OutputManagerHandle,
+    tx: mpsc::Sender<Result<proto::HistoryUpdate, RpcStatus>>,
+}
+
+impl HistorySubscription {
+    async fn run(mut self, cursor: Option<HistoryCursor>) {
+        // Subscribe before the first update so that no change can be missed in between
+        let mut events = self.history_sync.get_event_stream_fused();
+        let mut heartbeat = time::interval_at(
+            time::Instant::now() + self.config.heartbeat_interval,
+            self.config.heartbeat_interval,
+        )
+        .fuse();
+
+        let mut cursor = cursor;
+        loop {
+            match self.send_changes(cursor).await {
+                Ok(Some(next_cursor)) => cursor = Some(next_cursor),
+                Ok(None) => break,
+                Err(err) => {
+                    error!(
+                        target: LOG_TARGET,
+                        "Failed to send history to companion `{}`: {}", self.peer_node_id, err
+                    );
+                    let _ = self.tx.send(Err(RpcStatus::general_default())).await;
+                    break;
+                },
+            }
+
+            // Wait for a change, or for the heartbeat which refreshes the balance and keeps the subscription alive
+            let is_revoked = futures::select! {
+                event = events.select_next_some() => match event {
+                    Ok(event) => match &*event {
+                        HistorySyncEvent::CompanionRevoked(public_key) => {
+                            NodeId::from_public_key(public_key) == self.peer_node_id
+                        },
+                        _ => false,
+                    },
+                    // Changes are found by cursor, so missed events just mean that we send an update now
+                    Err(RecvError::Lagged(_)) => false,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = heartbeat.select_next_some() => false,
+            };
+            if is_revoked {
+                debug!(
+                    target: LOG_TARGET,
+                    "Ending history subscription of companion `{}` because it was revoked", self.peer_node_id
+                );
+                let _ = self
+                    .tx
+                    .send(Err(RpcStatus::forbidden("Not an authorized companion of this wallet")))
+                    .await;
+                break;
+            }
+        }
+
+        debug!(
+            target: LOG_TARGET,
+            "History subscription of companion `{}` ended", self.peer_node_id
+        );
+    }
+
+    /// Sends the changes since `cursor`, split into batches. Returns the new cursor, or None if the companion has gone
+    /// away.
+    async fn send_changes(
+        &mut self,
+        cursor: Option<HistoryCursor>,
+    ) -> Result<Option<HistoryCursor>, HistorySyncServiceError> {
+        let changes = self.history_sync.get_history_changes(cursor).await?;
+        let balance = proto::WalletBalance::from(self.output_manager_service.get_balance().await?);
+
+        let (is_snapshot, transactions) = match changes.tx_ids {
+            Some(tx_ids) => {
+                let mut transactions = Vec::with_capacity(tx_ids.len());
+                for tx_id in tx_ids {
+                    if let Some(tx) = self.transaction_service.get_any_transaction(tx_id).await? {
+                        transactions.push(SyncedTransaction::from(CompletedTransaction::from(tx)));
+                    }
+                }
+                (false, transactions)
+            },
+            None => (true, fetch_full_history(&mut self.transaction_service).await?),
+        };
+
+        let batch_size = self.config.max_transactions_per_update.max(1);
+        let num_batches = ((transactions.len() + batch_size - 1) / batch_size).max(1);
+        let mut transactions = transactions.into_iter();
+        for i in 0..num_batches {
+            let is_last = i + 1 == num_batches;
+            let update = proto::HistoryUpdate {
+                cursor: if is_last { Some(changes.cursor.into()) } else { None },
+                is_snapshot: is_snapshot && i == 0,
+                transactions: transactions.by_ref().take(batch_size).map(Into::into).collect(),
+                balance: Some(balance.clone()),
+            };
+            if self.tx.send(Ok(update)).await.is_err() {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(changes.cursor))
+    }
+}
@@ -0,0 +1,366 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    history_sync_service::{
+        companion_sync::CompanionSyncTask,
+        config::HistorySyncServiceConfig,
+        error::HistorySyncServiceError,
+        handle::{
+            HistoryChanges,
+            HistorySyncEvent,
+            HistorySyncEventSender,
+            HistorySyncServiceRequest,
+            HistorySyncServiceResponse,
+        },
+        journal::{HistoryCursor, HistoryJournal},
+        storage::database::{HistorySyncBackend, HistorySyncDatabase, SyncedTransaction},
+    },
+    output_manager_service::TxId,
+    transaction_service::{
+        error::TransactionServiceError,
+        handle::{TransactionEvent, TransactionServiceHandle},
+        storage::models::CompletedTransaction,
+    },
+};
+use chrono::Utc;
+use futures::{pin_mut, StreamExt};
+use log::*;
+use std::{collections::HashMap, sync::Arc};
+use tari_comms::{connectivity::ConnectivityRequester, peer_manager::NodeId, types::CommsPublicKey};
+use tari_service_framework::reply_channel;
+use tari_shutdown::{Shutdown, ShutdownSignal};
+use tokio::{sync::broadcast::RecvError, task};
+
+const LOG_TARGET: &str = "wallet::history_sync_service";
+
+/// The History Sync Service lets read-only companion wallets follow the transaction history of this wallet. On the
+/// primary wallet it keeps a journal of the transactions that changed, from which the history sync RPC service streams
+/// incremental updates to authorized companions. On a companion wallet it keeps a subscription to the primary wallet
+/// and stores the updates it receives.
+pub struct HistorySyncService<T>
+where T: HistorySyncBackend + 'static
+{
+    config: HistorySyncServiceConfig,
+    db: HistorySyncDatabase<T>,
+    journal: HistoryJournal,
+    known_transactions: HashMap<TxId, SyncedTransaction>,
+    transaction_service: TransactionServiceHandle,
+    connectivity: ConnectivityRequester,
+    event_publisher: HistorySyncEventSender,
+    request_stream: Option<
+        reply_channel::Receiver<HistorySyncServiceRequest, Result<HistorySyncServiceResponse, HistorySyncServiceError>>,
+    >,
+    shutdown_signal: Option<ShutdownSignal>,
+    companion_sync_shutdown: Option<Shutdown>,
+}
+
+impl<T> HistorySyncService<T>
+where T: HistorySyncBackend + 'static
+{
+    pub fn new(
+        config: HistorySyncServiceConfig,
+        request_stream: reply_channel::Receiver<
+            HistorySyncServiceRequest,
+            Result<HistorySyncServiceResponse, HistorySyncServiceError>,
+        >,
+        db: HistorySyncDatabase<T>,
+        transaction_service: TransactionServiceHandle,
+        connectivity: ConnectivityRequester,
+        event_publisher: HistorySyncEventSender,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self {
+        Self {
+            journal: HistoryJournal::new(config.max_journal_entries),
+            config,
+            db,
+            known_transactions: HashMap::new(),
+            transaction_service,
+            connectivity,
+            event_publisher,
+            request_stream: Some(request_stream),
+            shutdown_signal: Some(shutdown_signal),
+            companion_sync_shutdown: None,
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), HistorySyncServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("History Sync Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+
+        let shutdown = self
+            .shutdown_signal
+            .take()
+            .expect("History Sync Service initialized without shutdown signal");
+        pin_mut!(shutdown);
+
+        let mut transaction_events = self.transaction_service.get_event_stream_fused();
+
+        if let Some(state) = self.db.get_sync_state().await? {
+            self.start_companion_sync(state.primary_public_key);
+        }
+        // The journal starts with a new epoch, so companions receive a snapshot and only later changes are recorded
+        match fetch_full_history(&mut self.transaction_service).await {
+            Ok(history) => self.known_transactions = history.into_iter().map(|tx| (tx.tx_id, tx)).collect(),
+            Err(e) => warn!(target: LOG_TARGET, "Could not load the transaction history: {:?}", e),
+        }
+
+        info!(target: LOG_TARGET, "History Sync Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let response = self.handle_request(request).await.map_err(|e| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", e);
+                        e
+                    });
+                    let _ = reply_tx.send(response).map_err(|e| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        e
+                    });
+                },
+                event = transaction_events.select_next_some() => {
+                    let result = match event {
+                        Ok(event) => self.handle_transaction_event(&*event).await,
+                        // Some changes were missed, so look for them in the full history
+                        Err(RecvError::Lagged(n)) => {
+                            debug!(target: LOG_TARGET, "Missed {} transaction events, reconciling history", n);
+                            self.reconcile_history().await
+                        },
+                        Err(RecvError::Closed) => Ok(()),
+                    };
+                    if let Err(e) = result {
+                        error!(target: LOG_TARGET, "Error recording transaction history change: {:?}", e);
+                    }
+                },
+                _ = shutdown => {
+                    info!(target: LOG_TARGET, "History Sync service shutting down because it received the shutdown signal");
+                    break;
+                }
+                complete => {
+                    info!(target: LOG_TARGET, "History Sync service shutting down");
+                    break;
+                }
+            }
+        }
+        self.stop_companion_sync();
+        info!(target: LOG_TARGET, "History Sync Service ended");
+        Ok(())
+    }
+
+    async fn handle_request(
+        &mut self,
+        request: HistorySyncServiceRequest,
+    ) -> Result<HistorySyncServiceResponse, HistorySyncServiceError> {
+        match request {
+            HistorySyncServiceRequest::AuthorizeCompanion(public_key) => {
+                self.db
+                    .authorize_companion(public_key.clone(), Utc::now().naive_utc())
+                    .await?;
+                info!(target: LOG_TARGET, "Authorized companion wallet `{}`", public_key);
+                self.publish_event(HistorySyncEvent::CompanionAuthorized(public_key));
+                Ok(HistorySyncServiceResponse::CompanionAuthorized)
+            },
+            HistorySyncServiceRequest::RevokeCompanion(public_key) => {
+                let revoked = self.db.revoke_companion(public_key.clone()).await?;
+                if revoked {
+                    info!(target: LOG_TARGET, "Revoked companion wallet `{}`", public_key);
+                    self.publish_event(HistorySyncEvent::CompanionRevoked(public_key));
+                }
+                Ok(HistorySyncServiceResponse::CompanionRevoked(revoked))
+            },
+            HistorySyncServiceRequest::GetCompanions => Ok(self
+                .db
+                .get_companions()
+                .await
+                .map(HistorySyncServiceResponse::Companions)?),
+            HistorySyncServiceRequest::IsCompanionAuthorized(node_id) => {
+                let is_authorized = self
+                    .db
+                    .get_companions()
+                    .await?
+                    .iter()
+                    .any(|public_key| NodeId::from_public_key(public_key) == node_id);
+                Ok(HistorySyncServiceResponse::IsCompanionAuthorized(is_authorized))
+            },
+            HistorySyncServiceRequest::GetHistoryChanges(cursor) => Ok(HistorySyncServiceResponse::HistoryChanges(
+                self.get_history_changes(cursor),
+            )),
+            HistorySyncServiceRequest::SetPrimaryWallet(public_key) => {
+                self.db.set_sync_primary(public_key.clone()).await?;
+                info!(
+                    target: LOG_TARGET,
+                    "Syncing history from primary wallet `{}`", public_key
+                );
+                self.start_companion_sync(public_key);
+                Ok(HistorySyncServiceResponse::PrimaryWalletSet)
+            },
+            HistorySyncServiceRequest::ClearPrimaryWallet => {
+                self.stop_companion_sync();
+                self.db.clear_sync_primary().await?;
+                Ok(HistorySyncServiceResponse::PrimaryWalletCleared)
+            },
+            HistorySyncServiceRequest::GetSyncState => Ok(self
+                .db
+                .get_sync_state()
+                .await
+                .map(|s| HistorySyncServiceResponse::SyncState(s.map(Box::new)))?),
+            HistorySyncServiceRequest::GetSyncedTransactions => Ok(self
+                .db
+                .get_synced_transactions()
+                .await
+                .map(HistorySyncServiceResponse::SyncedTransactions)?),
+        }
+    }
+
+    fn get_history_changes(&self, cursor: Option<HistoryCursor>) -> HistoryChanges {
+        HistoryChanges {
+            cursor: self.journal.cursor(),
+            tx_ids: cursor.and_then(|cursor| self.journal.changes_since(&cursor)),
+        }
+    }
+
+    async fn handle_transaction_event(&mut self, event: &TransactionEvent) -> Result<(), HistorySyncServiceError> {
+        use TransactionEvent::*;
+        match event {
+            ReceivedTransaction(tx_id) |
+            ReceivedTransactionReply(tx_id) |
+            ReceivedFinalizedTransaction(tx_id) |
+            TransactionDirectSendResult(tx_id, _) |
+            TransactionCompletedImmediately(tx_id) |
+            TransactionStoreForwardSendResult(tx_id, _) |
+            TransactionCancelled(tx_id) |
+            TransactionBroadcast(tx_id) |
+            TransactionImported(tx_id) |
+            TransactionMined(tx_id) |
+            TransactionMinedUnconfirmed(tx_id, _) |
+            TransactionRejectedByMempool(tx_id, _) |
+            InvoiceAccepted(_, tx_id) |
+            InvoicePaid(_, tx_id) => self.record_transaction(*tx_id).await,
+            // Validation updates mined heights and confirmations without publishing an event per transaction
+            TransactionValidationSuccess(_) => self.reconcile_history().await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn record_transaction(&mut self, tx_id: TxId) -> Result<(), HistorySyncServiceError> {
+        match self.transaction_service.get_any_transaction(tx_id).await? {
+            Some(tx) => {
+                let tx = SyncedTransaction::from(CompletedTransaction::from(tx));
+                self.known_transactions.insert(tx_id, tx);
+            },
+            None => {
+                self.known_transactions.remove(&tx_id);
+            },
+        }
+        let cursor = self.journal.record(tx_id);
+        self.publish_event(HistorySyncEvent::HistoryChanged(cursor));
+        Ok(())
+    }
+
+    /// Compares the full transaction history with the last known state of each transaction and records the ones that
+    /// changed
+    async fn reconcile_history(&mut self) -> Result<(), HistorySyncServiceError> {
+        let history = fetch_full_history(&mut self.transaction_service).await?;
+        let mut cursor = None;
+        for tx in history {
+            if self.known_transactions.get(&tx.tx_id) != Some(&tx) {
+                cursor = Some(self.journal.record(tx.tx_id));
+                self.known_transactions.insert(tx.tx_id, tx);
+            }
+        }
+        if let Some(cursor) = cursor {
+            self.publish_event(HistorySyncEvent::HistoryChanged(cursor));
+        }
+        Ok(())
+    }
+
+    fn start_companion_sync(&mut self, primary_public_key: CommsPublicKey) {
+        self.stop_companion_sync();
+        let shutdown = Shutdown::new();
+        let task = CompanionSyncTask::new(
+            self.config.clone(),
+            self.db.clone(),
+            self.connectivity.clone(),
+            primary_public_key,
+            self.event_publisher.clone(),
+        );
+        task::spawn(task.run(shutdown.to_signal()));
+        self.companion_sync_shutdown = Some(shutdown);
+    }
+
+    fn stop_companion_sync(&mut self) {
+        if let Some(mut shutdown) = self.companion_sync_shutdown.take() {
+            let _ = shutdown.trigger();
+        }
+    }
+
+    fn publish_event(&self, event: HistorySyncEvent) {
+        let _ = self.event_publisher.send(Arc::new(event)).map_err(|e| {
+            trace!(
+                target: LOG_TARGET,
+                "Error sending event, usually because there are no subscribers: {:?}",
+                e
+            );
+            e
+        });
+    }
+}
+
+/// Fetches every transaction of the wallet, including cancelled ones, oldest first
+pub(crate) async fn fetch_full_history(
+    transaction_service: &mut TransactionServiceHandle,
+) -> Result<Vec<SyncedTransaction>, TransactionServiceError> {
+    let mut history = Vec::new();
+    let inbound = transaction_service.get_pending_inbound_transactions().await?;
+    let cancelled_inbound = transaction_service.get_cancelled_pending_inbound_transactions().await?;
+    history.extend(
+        inbound
+            .into_iter()
+            .chain(cancelled_inbound)
+            .map(|(_, tx)| SyncedTransaction::from(CompletedTransaction::from(tx))),
+    );
+    let outbound = transaction_service.get_pending_outbound_transactions().await?;
+    let cancelled_outbound = transaction_service
+        .get_cancelled_pending_outbound_transactions()
+        .await?;
+    history.extend(
+        outbound
+            .into_iter()
+            .chain(cancelled_outbound)
+            .map(|(_, tx)| SyncedTransaction::from(CompletedTransaction::from(tx))),
+    );
+    let completed = transaction_service.get_completed_transactions().await?;
+    let cancelled_completed = transaction_service.get_cancelled_completed_transactions().await?;
+    history.extend(
+        completed
+            .into_iter()
+            .chain(cancelled_completed)
+            .map(|(_, tx)| SyncedTransaction::from(tx)),
+    );
+    history.sort_by_key(|tx| tx.timestamp);
+    Ok(history)
+}
@@ -0,0 +1,315 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    history_sync_service::{error::HistorySyncStorageError, journal::HistoryCursor},
+    output_manager_service::{service::Balance, TxId},
+    transaction_service::storage::models::{CompletedTransaction, TransactionDirection, TransactionStatus},
+};
+use chrono::NaiveDateTime;
+use log::*;
+use std::{
+    fmt::{Display, Error, Formatter},
+    sync::Arc,
+};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+
+const LOG_TARGET: &str = "wallet::history_sync_service::database";
+
+/// The parts of a transaction that are shown to the user. This is all that a companion wallet receives of the
+/// transactions of the primary wallet, which never shares the transaction itself or any key material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncedTransaction {
+    pub tx_id: TxId,
+    pub source_public_key: CommsPublicKey,
+    pub destination_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee: MicroTari,
+    pub status: TransactionStatus,
+    pub direction: TransactionDirection,
+    pub message: String,
+    pub timestamp: NaiveDateTime,
+    pub cancelled: bool,
+    pub mined_height: Option<u64>,
+    pub confirmations: Option<u64>,
+}
+
+impl From<CompletedTransaction> for SyncedTransaction {
+    fn from(tx: CompletedTransaction) -> Self {
+        Self {
+            tx_id: tx.tx_id,
+            source_public_key: tx.source_public_key,
+            destination_public_key: tx.destination_public_key,
+            amount: tx.amount,
+            fee: tx.fee,
+            status: tx.status,
+            direction: tx.direction,
+            message: tx.message,
+            timestamp: tx.timestamp,
+            cancelled: tx.cancelled,
+            mined_height: tx.mined_height,
+            confirmations: tx.confirmations,
+        }
+    }
+}
+
+/// The state of a companion wallet's subscription to its primary wallet
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistorySyncState {
+    pub primary_public_key: CommsPublicKey,
+    /// The cursor to resume the subscription from, None if the full history must be requested
+    pub cursor: Option<HistoryCursor>,
+    /// The balance of the primary wallet as of the last update, None if no update has been received
+    pub balance: Option<Balance>,
+    pub last_synced_at: Option<NaiveDateTime>,
+}
+
+impl HistorySyncState {
+    pub fn new(primary_public_key: CommsPublicKey) -> Self {
+        Self {
+            primary_public_key,
+            cursor: None,
+            balance: None,
+            last_synced_at: None,
+        }
+    }
+}
+
+/// An update received from the primary wallet, applied to storage as a single unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncUpdate {
+    /// True if all previously synced transactions must be discarded before this update is applied
+    pub is_snapshot: bool,
+    pub transactions: Vec<SyncedTransaction>,
+    /// The cursor to resume from once this update has been applied. None for all but the last batch of a snapshot.
+    pub cursor: Option<HistoryCursor>,
+    pub balance: Option<Balance>,
+    pub timestamp: NaiveDateTime,
+}
+
+/// This trait defines the functionality that a database backend need to provide for the History Sync Service
+pub trait HistorySyncBackend: Send + Sync + Clone {
+    /// Retrieve the record associated with the provided DbKey
+    fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, HistorySyncStorageError>;
+    /// Modify the state the of the backend with a write operation
+    fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, HistorySyncStorageError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbKey {
+    Companion(CommsPublicKey),
+    Companions,
+    SyncState,
+    SyncedTransactions,
+}
+
+pub enum DbValue {
+    Companion(Box<CommsPublicKey>),
+    Companions(Vec<CommsPublicKey>),
+    SyncState(Box<HistorySyncState>),
+    SyncedTransactions(Vec<SyncedTransaction>),
+}
+
+pub enum DbKeyValuePair {
+    Companion(CommsPublicKey, NaiveDateTime),
+    /// Sets the primary wallet to sync from. Synced history from a different primary wallet is discarded.
+    SyncPrimary(CommsPublicKey),
+}
+
+pub enum WriteOperation {
+    Upsert(DbKeyValuePair),
+    ApplySyncUpdate(Box<SyncUpdate>),
+    Remove(DbKey),
+}
+
+pub struct HistorySyncDatabase<T>
+where T: HistorySyncBackend
+{
+    db: Arc<T>,
+}
+
+impl<T> Clone for HistorySyncDatabase<T>
+where T: HistorySyncBackend
+{
+    fn clone(&self) -> Self {
+        Self { db: self.db.clone() }
+    }
+}
+
+impl<T> HistorySyncDatabase<T>
+where T: HistorySyncBackend + 'static
+{
+    pub fn new(db: T) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    pub async fn authorize_companion(
+        &self,
+        public_key: CommsPublicKey,
+        timestamp: NaiveDateTime,
+    ) -> Result<(), HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Upsert(DbKeyValuePair::Companion(public_key, timestamp)))
+        })
+        .await
+        .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    /// Removes a companion, returning true if it was authorized
+    pub async fn revoke_companion(&self, public_key: CommsPublicKey) -> Result<bool, HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        let removed =
+            tokio::task::spawn_blocking(move || db_clone.write(WriteOperation::Remove(DbKey::Companion(public_key))))
+                .await
+                .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(removed.is_some())
+    }
+
+    pub async fn get_companions(&self) -> Result<Vec<CommsPublicKey>, HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::Companions) {
+            Ok(None) => log_error(
+                DbKey::Companions,
+                HistorySyncStorageError::UnexpectedResult("Could not retrieve companions".to_string()),
+            ),
+            Ok(Some(DbValue::Companions(c))) => Ok(c),
+            Ok(Some(other)) => unexpected_result(DbKey::Companions, other),
+            Err(e) => log_error(DbKey::Companions, e),
+        })
+        .await
+        .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))
+        .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn is_companion_authorized(&self, public_key: CommsPublicKey) -> Result<bool, HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let key = DbKey::Companion(public_key);
+            match db_clone.fetch(&key) {
+                Ok(None) => Ok(false),
+                Ok(Some(DbValue::Companion(_))) => Ok(true),
+                Ok(Some(other)) => unexpected_result(key, other),
+                Err(e) => log_error(key, e),
+            }
+        })
+        .await
+        .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))
+        .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn set_sync_primary(&self, public_key: CommsPublicKey) -> Result<(), HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Upsert(DbKeyValuePair::SyncPrimary(public_key)))
+        })
+        .await
+        .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    /// Stops syncing from the primary wallet and discards the synced history
+    pub async fn clear_sync_primary(&self) -> Result<(), HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.write(WriteOperation::Remove(DbKey::SyncState)))
+            .await
+            .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_sync_state(&self) -> Result<Option<HistorySyncState>, HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::SyncState) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::SyncState(s))) => Ok(Some(*s)),
+            Ok(Some(other)) => unexpected_result(DbKey::SyncState, other),
+            Err(e) => log_error(DbKey::SyncState, e),
+        })
+        .await
+        .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))
+        .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn apply_sync_update(&self, update: SyncUpdate) -> Result<(), HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.write(WriteOperation::ApplySyncUpdate(Box::new(update))))
+            .await
+            .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_synced_transactions(&self) -> Result<Vec<SyncedTransaction>, HistorySyncStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::SyncedTransactions) {
+            Ok(None) => log_error(
+                DbKey::SyncedTransactions,
+                HistorySyncStorageError::UnexpectedResult("Could not retrieve synced transactions".to_string()),
+            ),
+            Ok(Some(DbValue::SyncedTransactions(t))) => Ok(t),
+            Ok(Some(other)) => unexpected_result(DbKey::SyncedTransactions, other),
+            Err(e) => log_error(DbKey::SyncedTransactions, e),
+        })
+        .await
+        .map_err(|err| HistorySyncStorageError::BlockingTaskSpawnError(err.to_string()))
+        .and_then(|inner_result| inner_result)
+    }
+}
+
+fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, HistorySyncStorageError> {
+    let msg = format!("Unexpected result for database query {}. Response: {}", req, res);
+    error!(target: LOG_TARGET, "{}", msg);
+    Err(HistorySyncStorageError::UnexpectedResult(msg))
+}
+
+impl Display for DbKey {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            DbKey::Companion(pk) => f.write_str(&format!("Companion: {}", pk)),
+            DbKey::Companions => f.write_str("Companions"),
+            DbKey::SyncState => f.write_str("Sync State"),
+            DbKey::SyncedTransactions => f.write_str("Synced Transactions"),
+        }
+    }
+}
+
+impl Display for DbValue {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            DbValue::Companion(_) => f.write_str("Companion"),
+            DbValue::Companions(_) => f.write_str("Companions"),
+            DbValue::SyncState(_) => f.write_str("Sync State"),
+            DbValue::SyncedTransactions(_) => f.write_str("Synced Transactions"),
+        }
+    }
+}
+
+fn log_error<T>(req: DbKey, err: HistorySyncStorageError) -> Result<T, HistorySyncStorageError> {
+    error!(
+        target: LOG_TARGET,
+        "Database access error on request: {}: {}",
+        req,
+        err.to_string()
+    );
+    Err(err)
+}
@@ -0,0 +1,567 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    history_sync_service::{
+        error::HistorySyncStorageError,
+        journal::HistoryCursor,
+        storage::database::{
+            DbKey,
+            DbKeyValuePair,
+            DbValue,
+            HistorySyncBackend,
+            HistorySyncState,
+            SyncUpdate,
+            SyncedTransaction,
+            WriteOperation,
+        },
+    },
+    output_manager_service::service::Balance,
+    schema::{history_sync_companions, history_sync_state, synced_transactions},
+    storage::sqlite_utilities::WalletDbConnection,
+    transaction_service::storage::models::{TransactionDirection, TransactionStatus},
+};
+use chrono::NaiveDateTime;
+use diesel::{prelude::*, result::Error as DieselError, SqliteConnection};
+use std::convert::TryFrom;
+use tari_core::transactions::{tari_amount::MicroTari, types::PublicKey};
+use tari_crypto::tari_utilities::ByteArray;
+
+/// A Sqlite backend for the History Sync Service. The Backend is accessed via a connection pool to the Sqlite file.
+#[derive(Clone)]
+pub struct HistorySyncSqliteDatabase {
+    database_connection: WalletDbConnection,
+}
+
+impl HistorySyncSqliteDatabase {
+    pub fn new(database_connection: WalletDbConnection) -> Self {
+        Self { database_connection }
+    }
+}
+
+impl HistorySyncBackend for HistorySyncSqliteDatabase {
+    fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, HistorySyncStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        let result = match key {
+            DbKey::Companion(pk) => match CompanionSql::find(pk, &(*conn)) {
+                Ok(c) => Some(DbValue::Companion(Box::new(PublicKey::try_from(c)?))),
+                Err(HistorySyncStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::Companions => Some(DbValue::Companions(
+                CompanionSql::index(&conn)?
+                    .into_iter()
+                    .map(PublicKey::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::SyncState => match HistorySyncStateSql::get(&conn)? {
+                Some(s) => Some(DbValue::SyncState(Box::new(HistorySyncState::try_from(s)?))),
+                None => None,
+            },
+            DbKey::SyncedTransactions => Some(DbValue::SyncedTransactions(
+                SyncedTransactionSql::index(&conn)?
+                    .into_iter()
+                    .map(SyncedTransaction::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+        };
+
+        Ok(result)
+    }
+
+    fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, HistorySyncStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        match op {
+            WriteOperation::Upsert(kvp) => match kvp {
+                DbKeyValuePair::Companion(pk, timestamp) => CompanionSql::new(&pk, timestamp).set(&conn)?,
+                DbKeyValuePair::SyncPrimary(pk) => conn.transaction::<_, HistorySyncStorageError, _>(|| {
+                    if let Some(state) = HistorySyncStateSql::get(&conn)? {
+                        if state.primary_public_key == pk.to_vec() {
+                            return Ok(());
+                        }
+                    }
+                    HistorySyncStateSql::clear(&conn)?;
+                    HistorySyncStateSql::from(HistorySyncState::new(pk)).commit(&conn)
+                })?,
+            },
+            WriteOperation::ApplySyncUpdate(update) => {
+                conn.transaction::<_, HistorySyncStorageError, _>(|| apply_sync_update(*update, &conn))?
+            },
+            WriteOperation::Remove(k) => match k {
+                DbKey::Companion(pk) => {
+                    if CompanionSql::delete(&pk, &conn)? {
+                        return Ok(Some(DbValue::Companion(Box::new(pk))));
+                    }
+                },
+                DbKey::SyncState => {
+                    conn.transaction::<_, HistorySyncStorageError, _>(|| HistorySyncStateSql::clear(&conn))?
+                },
+                DbKey::Companions | DbKey::SyncedTransactions => {
+                    return Err(HistorySyncStorageError::OperationNotSupported)
+                },
+            },
+        }
+
+        Ok(None)
+    }
+}
+
+fn apply_sync_update(update: SyncUpdate, conn: &SqliteConnection) -> Result<(), HistorySyncStorageError> {
+    let state = HistorySyncStateSql::get(conn)?.ok_or(HistorySyncStorageError::ValueNotFound(DbKey::SyncState))?;
+
+    if update.is_snapshot {
+        diesel::delete(synced_transactions::table).execute(conn)?;
+    }
+    for tx in update.transactions {
+        SyncedTransactionSql::from(tx).set(conn)?;
+    }
+
+    let mut state = HistorySyncState::try_from(state)?;
+    if update.cursor.is_some() {
+        state.cursor = update.cursor;
+    }
+    if update.balance.is_some() {
+        state.balance = update.balance;
+    }
+    state.last_synced_at = Some(update.timestamp);
+    HistorySyncStateSql::from(state).update(conn)
+}
+
+/// A Sql version of an authorized companion
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "history_sync_companions"]
+struct CompanionSql {
+    public_key: Vec<u8>,
+    timestamp: NaiveDateTime,
+}
+
+impl CompanionSql {
+    pub fn new(public_key: &PublicKey, timestamp: NaiveDateTime) -> Self {
+        Self {
+            public_key: public_key.to_vec(),
+            timestamp,
+        }
+    }
+
+    pub fn set(&self, conn: &SqliteConnection) -> Result<(), HistorySyncStorageError> {
+        diesel::replace_into(history_sync_companions::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return all companions, oldest first
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<CompanionSql>, HistorySyncStorageError> {
+        Ok(history_sync_companions::table
+            .order(history_sync_companions::timestamp.asc())
+            .load::<CompanionSql>(conn)?)
+    }
+
+    pub fn find(public_key: &PublicKey, conn: &SqliteConnection) -> Result<CompanionSql, HistorySyncStorageError> {
+        Ok(history_sync_companions::table
+            .filter(history_sync_companions::public_key.eq(public_key.to_vec()))
+            .first::<CompanionSql>(conn)?)
+    }
+
+    /// Deletes the companion, returning true if it existed
+    pub fn delete(public_key: &PublicKey, conn: &SqliteConnection) -> Result<bool, HistorySyncStorageError> {
+        let num_deleted = diesel::delete(
+            history_sync_companions::table.filter(history_sync_companions::public_key.eq(public_key.to_vec())),
+        )
+        .execute(conn)?;
+        Ok(num_deleted > 0)
+    }
+}
+
+impl TryFrom<CompanionSql> for PublicKey {
+    type Error = HistorySyncStorageError;
+
+    fn try_from(c: CompanionSql) -> Result<Self, Self::Error> {
+        PublicKey::from_vec(&c.public_key).map_err(|_| HistorySyncStorageError::ConversionError)
+    }
+}
+
+/// A Sql version of the HistorySyncState struct. The table holds at most one row.
+#[derive(Clone, Debug, Queryable, Insertable, AsChangeset, PartialEq)]
+#[table_name = "history_sync_state"]
+#[changeset_options(treat_none_as_null = "true")]
+struct HistorySyncStateSql {
+    primary_public_key: Vec<u8>,
+    cursor_epoch: Option<i64>,
+    cursor_sequence: Option<i64>,
+    available_balance: Option<i64>,
+    pending_incoming_balance: Option<i64>,
+    pending_outgoing_balance: Option<i64>,
+    time_locked_balance: Option<i64>,
+    last_synced_at: Option<NaiveDateTime>,
+}
+
+impl HistorySyncStateSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), HistorySyncStorageError> {
+        diesel::insert_into(history_sync_state::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn get(conn: &SqliteConnection) -> Result<Option<HistorySyncStateSql>, HistorySyncStorageError> {
+        match history_sync_state::table.first::<HistorySyncStateSql>(conn) {
+            Ok(s) => Ok(Some(s)),
+            Err(DieselError::NotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn update(&self, conn: &SqliteConnection) -> Result<(), HistorySyncStorageError> {
+        let num_updated = diesel::update(
+            history_sync_state::table.filter(history_sync_state::primary_public_key.eq(&self.primary_public_key)),
+        )
+        .set(self.clone())
+        .execute(conn)?;
+
+        if num_updated == 0 {
+            return Err(HistorySyncStorageError::UnexpectedResult(
+                "Database update error".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Removes the sync state and all synced transactions
+    pub fn clear(conn: &SqliteConnection) -> Result<(), HistorySyncStorageError> {
+        diesel::delete(synced_transactions::table).execute(conn)?;
+        diesel::delete(history_sync_state::table).execute(conn)?;
+        Ok(())
+    }
+}
+
+impl From<HistorySyncState> for HistorySyncStateSql {
+    fn from(s: HistorySyncState) -> Self {
+        Self {
+            primary_public_key: s.primary_public_key.to_vec(),
+            cursor_epoch: s.cursor.map(|c| c.epoch as i64),
+            cursor_sequence: s.cursor.map(|c| c.sequence as i64),
+            available_balance: s.balance.as_ref().map(|b| u64::from(b.available_balance) as i64),
+            pending_incoming_balance: s.balance.as_ref().map(|b| u64::from(b.pending_incoming_balance) as i64),
+            pending_outgoing_balance: s.balance.as_ref().map(|b| u64::from(b.pending_outgoing_balance) as i64),
+            time_locked_balance: s
+                .balance
+                .as_ref()
+                .and_then(|b| b.time_locked_balance)
+                .map(|b| u64::from(b) as i64),
+            last_synced_at: s.last_synced_at,
+        }
+    }
+}
+
+impl TryFrom<HistorySyncStateSql> for HistorySyncState {
+    type Error = HistorySyncStorageError;
+
+    fn try_from(s: HistorySyncStateSql) -> Result<Self, Self::Error> {
+        let cursor = match (s.cursor_epoch, s.cursor_sequence) {
+            (Some(epoch), Some(sequence)) => Some(HistoryCursor {
+                epoch: epoch as u64,
+                sequence: sequence as u64,
+            }),
+            _ => None,
+        };
+        let balance = match (
+            s.available_balance,
+            s.pending_incoming_balance,
+            s.pending_outgoing_balance,
+        ) {
+            (Some(available), Some(pending_incoming), Some(pending_outgoing)) => Some(Balance {
+                available_balance: MicroTari::from(available as u64),
+                time_locked_balance: s.time_locked_balance.map(|b| MicroTari::from(b as u64)),
+                pending_incoming_balance: MicroTari::from(pending_incoming as u64),
+                pending_outgoing_balance: MicroTari::from(pending_outgoing as u64),
+            }),
+            _ => None,
+        };
+
+        Ok(Self {
+            primary_public_key: PublicKey::from_vec(&s.primary_public_key)
+                .map_err(|_| HistorySyncStorageError::ConversionError)?,
+            cursor,
+            balance,
+            last_synced_at: s.last_synced_at,
+        })
+    }
+}
+
+/// A Sql version of the SyncedTransaction struct
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "synced_transactions"]
+struct SyncedTransactionSql {
+    tx_id: i64,
+    source_public_key: Vec<u8>,
+    destination_public_key: Vec<u8>,
+    amount: i64,
+    fee: i64,
+    status: i32,
+    direction: i32,
+    message: String,
+    timestamp: NaiveDateTime,
+    cancelled: i32,
+    mined_height: Option<i64>,
+    confirmations: Option<i64>,
+}
+
+impl SyncedTransactionSql {
+    /// Insert the transaction, replacing any previous version of it
+    pub fn set(&self, conn: &SqliteConnection) -> Result<(), HistorySyncStorageError> {
+        diesel::replace_into(synced_transactions::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return all synced transactions, newest first
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<SyncedTransactionSql>, HistorySyncStorageError> {
+        Ok(synced_transactions::table
+            .order(synced_transactions::timestamp.desc())
+            .load::<SyncedTransactionSql>(conn)?)
+    }
+}
+
+impl From<SyncedTransaction> for SyncedTransactionSql {
+    fn from(tx: SyncedTransaction) -> Self {
+        Self {
+            tx_id: tx.tx_id as i64,
+            source_public_key: tx.source_public_key.to_vec(),
+            destination_public_key: tx.destination_public_key.to_vec(),
+            amount: u64::from(tx.amount) as i64,
+            fee: u64::from(tx.fee) as i64,
+            status: tx.status as i32,
+            direction: tx.direction as i32,
+            message: tx.message,
+            timestamp: tx.timestamp,
+            cancelled: tx.cancelled as i32,
+            mined_height: tx.mined_height.map(|h| h as i64),
+            confirmations: tx.confirmations.map(|c| c as i64),
+        }
+    }
+}
+
+impl TryFrom<SyncedTransactionSql> for SyncedTransaction {
+    type Error = HistorySyncStorageError;
+
+    fn try_from(tx: SyncedTransactionSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tx_id: tx.tx_id as u64,
+            source_public_key: PublicKey::from_vec(&tx.source_public_key)
+                .map_err(|_| HistorySyncStorageError::ConversionError)?,
+            destination_public_key: PublicKey::from_vec(&tx.destination_public_key)
+                .map_err(|_| HistorySyncStorageError::ConversionError)?,
+            amount: MicroTari::from(tx.amount as u64),
+            fee: MicroTari::from(tx.fee as u64),
+            status: TransactionStatus::try_from(tx.status).map_err(|_| HistorySyncStorageError::ConversionError)?,
+            direction: TransactionDirection::try_from(tx.direction)
+                .map_err(|_| HistorySyncStorageError::ConversionError)?,
+            message: tx.message,
+            timestamp: tx.timestamp,
+            cancelled: tx.cancelled != 0,
+            mined_height: tx.mined_height.map(|h| h as u64),
+            confirmations: tx.confirmations.map(|c| c as u64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        history_sync_service::{
+            journal::HistoryCursor,
+            storage::{
+                database::{
+                    DbKey,
+                    DbKeyValuePair,
+                    DbValue,
+                    HistorySyncBackend,
+                    SyncUpdate,
+                    SyncedTransaction,
+                    WriteOperation,
+                },
+                sqlite_db::HistorySyncSqliteDatabase,
+            },
+        },
+        output_manager_service::service::Balance,
+        storage::sqlite_utilities::WalletDbConnection,
+        transaction_service::storage::models::{TransactionDirection, TransactionStatus},
+    };
+    use chrono::Utc;
+    use diesel::{Connection, SqliteConnection};
+    use rand::rngs::OsRng;
+    use tari_core::transactions::{
+        tari_amount::MicroTari,
+        types::{PrivateKey, PublicKey},
+    };
+    use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait};
+    use tari_test_utils::{paths::with_temp_dir, random::string};
+
+    fn random_public_key() -> PublicKey {
+        PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng))
+    }
+
+    fn synced_transaction(tx_id: u64, status: TransactionStatus) -> SyncedTransaction {
+        SyncedTransaction {
+            tx_id,
+            source_public_key: random_public_key(),
+            destination_public_key: random_public_key(),
+            amount: MicroTari::from(1000 * tx_id),
+            fee: MicroTari::from(10),
+            status,
+            direction: TransactionDirection::Outbound,
+            message: format!("Transaction {}", tx_id),
+            timestamp: Utc::now().naive_utc(),
+            cancelled: false,
+            mined_height: None,
+            confirmations: None,
+        }
+    }
+
+    fn synced_transactions(db: &HistorySyncSqliteDatabase) -> Vec<SyncedTransaction> {
+        match db.fetch(&DbKey::SyncedTransactions).unwrap() {
+            Some(DbValue::SyncedTransactions(txs)) => txs,
+            _ => panic!("Unexpected result"),
+        }
+    }
+
+    #[test]
+    fn test_apply_sync_updates() {
+        with_temp_dir(|dir_path| {
+            let db_name = format!("{}.sqlite3", string(8).as_str());
+            let db_path = format!("{}/{}", dir_path.to_str().unwrap(), db_name);
+
+            embed_migrations!("./migrations");
+            let conn =
+                SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+            embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+            let db = HistorySyncSqliteDatabase::new(WalletDbConnection::new(conn, None));
+
+            let primary = random_public_key();
+            db.write(WriteOperation::Upsert(DbKeyValuePair::SyncPrimary(primary.clone())))
+                .unwrap();
+
+            let cursor = HistoryCursor { epoch: 1, sequence: 2 };
+            let balance = Balance {
+                available_balance: MicroTari::from(5000),
+                time_locked_balance: None,
+                pending_incoming_balance: MicroTari::from(0),
+                pending_outgoing_balance: MicroTari::from(1010),
+            };
+            let update = SyncUpdate {
+                is_snapshot: true,
+                transactions: vec![
+                    synced_transaction(1, TransactionStatus::MinedConfirmed),
+                    synced_transaction(2, TransactionStatus::Broadcast),
+                ],
+                cursor: Some(cursor),
+                balance: Some(balance.clone()),
+                timestamp: Utc::now().naive_utc(),
+            };
+            db.write(WriteOperation::ApplySyncUpdate(Box::new(update))).unwrap();
+            assert_eq!(synced_transactions(&db).len(), 2);
+
+            // An incremental update replaces changed transactions
+            let mut changed = synced_transaction(2, TransactionStatus::MinedUnconfirmed);
+            changed.mined_height = Some(100);
+            let update = SyncUpdate {
+                is_snapshot: false,
+                transactions: vec![changed.clone()],
+                cursor: Some(HistoryCursor { epoch: 1, sequence: 3 }),
+                balance: Some(balance.clone()),
+                timestamp: Utc::now().naive_utc(),
+            };
+            db.write(WriteOperation::ApplySyncUpdate(Box::new(update))).unwrap();
+            let txs = synced_transactions(&db);
+            assert_eq!(txs.len(), 2);
+            assert!(txs.contains(&changed));
+
+            let state = match db.fetch(&DbKey::SyncState).unwrap() {
+                Some(DbValue::SyncState(state)) => *state,
+                _ => panic!("Unexpected result"),
+            };
+            assert_eq!(state.primary_public_key, primary);
+            assert_eq!(state.cursor, Some(HistoryCursor { epoch: 1, sequence: 3 }));
+            assert_eq!(state.balance, Some(balance));
+
+            // A snapshot discards the previous history
+            let update = SyncUpdate {
+                is_snapshot: true,
+                transactions: vec![synced_transaction(3, TransactionStatus::Completed)],
+                cursor: None,
+                balance: None,
+                timestamp: Utc::now().naive_utc(),
+            };
+            db.write(WriteOperation::ApplySyncUpdate(Box::new(update))).unwrap();
+            let txs = synced_transactions(&db);
+            assert_eq!(txs.len(), 1);
+            assert_eq!(txs[0].tx_id, 3);
+
+            // Setting the same primary keeps the history, a different primary discards it
+            db.write(WriteOperation::Upsert(DbKeyValuePair::SyncPrimary(primary)))
+                .unwrap();
+            assert_eq!(synced_transactions(&db).len(), 1);
+            db.write(WriteOperation::Upsert(DbKeyValuePair::SyncPrimary(random_public_key())))
+                .unwrap();
+            assert!(synced_transactions(&db).is_empty());
+        });
+    }
+
+    #[test]
+    fn test_companions() {
+        with_temp_dir(|dir_path| {
+            let db_name = format!("{}.sqlite3", string(8).as_str());
+            let db_path = format!("{}/{}", dir_path.to_str().unwrap(), db_name);
+
+            embed_migrations!("./migrations");
+            let conn =
+                SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+            embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+            let db = HistorySyncSqliteDatabase::new(WalletDbConnection::new(conn, None));
+
+            let companion = random_public_key();
+            db.write(WriteOperation::Upsert(DbKeyValuePair::Companion(
+                companion.clone(),
+                Utc::now().naive_utc(),
+            )))
+            .unwrap();
+            assert!(db.fetch(&DbKey::Companion(companion.clone())).unwrap().is_some());
+            assert!(db.fetch(&DbKey::Companion(random_public_key())).unwrap().is_none());
+
+            let removed = db
+                .write(WriteOperation::Remove(DbKey::Companion(companion.clone())))
+                .unwrap();
+            assert!(removed.is_some());
+            let removed = db.write(WriteOperation::Remove(DbKey::Companion(companion))).unwrap();
+            assert!(removed.is_none());
+            match db.fetch(&DbKey::Companions).unwrap() {
+                Some(DbValue::Companions(c)) => assert!(c.is_empty()),
+                _ => panic!("Unexpected result"),
+            }
+        });
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::key_manager_service::{error::KeyManagerServiceError, storage::database::KeyIndexGap};
+use tari_service_framework::reply_channel::SenderService;
+use tower::Service;
+
+#[derive(Debug)]
+pub enum KeyManagerRequest {
+    ReserveNextIndex {
+        branch: String,
+        consumer: String,
+    },
+    BackfillIndex {
+        branch: String,
+        consumer: String,
+    },
+    MarkIndexUsed {
+        branch: String,
+        index: u64,
+    },
+    ReleaseIndex {
+        branch: String,
+        index: u64,
+    },
+    RecordUsedIndex {
+        branch: String,
+        index: u64,
+        consumer: String,
+    },
+    GetCurrentIndex(String),
+    GetIndexGaps(String),
+}
+
+#[derive(Debug)]
+pub enum KeyManagerResponse {
+    Index(u64),
+    IndexUpdated,
+    IndexGaps(Vec<KeyIndexGap>),
+}
+
+/// The key manager hands out the key indexes of the wallet's key branches. Every index is reserved by exactly one
+/// consumer, which must either mark it as used once a key derived from it is stored, or release it again.
+#[derive(Clone)]
+pub struct KeyManagerHandle {
+    handle: SenderService<KeyManagerRequest, Result<KeyManagerResponse, KeyManagerServiceError>>,
+}
+
+impl KeyManagerHandle {
+    pub fn new(handle: SenderService<KeyManagerRequest, Result<KeyManagerResponse, KeyManagerServiceError>>) -> Self {
+        Self { handle }
+    }
+
+    /// Reserve the next unused index of the branch
+    pub async fn reserve_next_index(&mut self, branch: &str, consumer: &str) -> Result<u64, KeyManagerServiceError> {
+        match self
+            .handle
+            .call(KeyManagerRequest::ReserveNextIndex {
+                branch: branch.to_string(),
+                consumer: consumer.to_string(),
+            })
+            .await??
+        {
+            KeyManagerResponse::Index(index) => Ok(index),
+            _ => Err(KeyManagerServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Reserve the lowest index of the branch that was skipped or released, or the next unused index if there is no
+    /// such gap
+    pub async fn backfill_index(&mut self, branch: &str, consumer: &str) -> Result<u64, KeyManagerServiceError> {
+        match self
+            .handle
+            .call(KeyManagerRequest::BackfillIndex {
+                branch: branch.to_string(),
+                consumer: consumer.to_string(),
+            })
+            .await??
+        {
+            KeyManagerResponse::Index(index) => Ok(index),
+            _ => Err(KeyManagerServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Confirm that a key derived from a reserved index is in use
+    pub async fn mark_index_used(&mut self, branch: &str, index: u64) -> Result<(), KeyManagerServiceError> {
+        match self
+            .handle
+            .call(KeyManagerRequest::MarkIndexUsed {
+                branch: branch.to_string(),
+                index,
+            })
+            .await??
+        {
+            KeyManagerResponse::IndexUpdated => Ok(()),
+            _ => Err(KeyManagerServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Give a reserved index back without using it so that it can be backfilled
+    pub async fn release_index(&mut self, branch: &str, index: u64) -> Result<(), KeyManagerServiceError> {
+        match self
+            .handle
+            .call(KeyManagerRequest::ReleaseIndex {
+                branch: branch.to_string(),
+                index,
+            })
+            .await??
+        {
+            KeyManagerResponse::IndexUpdated => Ok(()),
+            _ => Err(KeyManagerServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Record an index that was found to be in use without being reserved, e.g. by the recovery scanner. The next
+    /// reserved index of the branch will be higher than it.
+    pub async fn record_used_index(
+        &mut self,
+        branch: &str,
+        index: u64,
+        consumer: &str,
+    ) -> Result<(), KeyManagerServiceError> {
+        match self
+            .handle
+            .call(KeyManagerRequest::RecordUsedIndex {
+                branch: branch.to_string(),
+                index,
+                consumer: consumer.to_string(),
+            })
+            .await??
+        {
+            KeyManagerResponse::IndexUpdated => Ok(()),
+            _ => Err(KeyManagerServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Return the highest index of the branch that has been handed out
+    pub async fn get_current_index(&mut self, branch: &str) -> Result<u64, KeyManagerServiceError> {
+        match self
+            .handle
+            .call(KeyManagerRequest::GetCurrentIndex(branch.to_string()))
+            .await??
+        {
+            KeyManagerResponse::Index(index) => Ok(index),
+            _ => Err(KeyManagerServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Return the tracked indexes of the branch that are not in use
+    pub async fn get_index_gaps(&mut self, branch: &str) -> Result<Vec<KeyIndexGap>, KeyManagerServiceError> {
+        match self
+            .handle
+            .call(KeyManagerRequest::GetIndexGaps(branch.to_string()))
+            .await??
+        {
+            KeyManagerResponse::IndexGaps(gaps) => Ok(gaps),
+            _ => Err(KeyManagerServiceError::UnexpectedApiResponse),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+pub mod error;
+pub mod handle;
+pub mod service;
+pub mod storage;
+
+use crate::{
+    audit_log_service::handle::AuditLogHandle,
+    key_manager_service::{
+        handle::KeyManagerHandle,
+        service::KeyManagerService,
+        storage::database::{KeyManagerBackend, KeyManagerDatabase},
+    },
+};
+use futures::future;
+use log::*;
+use tari_service_framework::{
+    async_trait,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+    ServiceInitializerContext,
+};
+
+const LOG_TARGET: &str = "wallet::key_manager_service::initializer";
+
+/// The branch of the primary spending and script keys of the wallet
+pub const PRIMARY_BRANCH: &str = "primary";
+/// The prefix of the branches of named accounts
+pub const ACCOUNT_BRANCH_PREFIX: &str = "account_";
+
+/// The branch of the spending and script keys of a named account
+pub fn account_branch(name: &str) -> String {
+    format!("{}{}", ACCOUNT_BRANCH_PREFIX, name)
+}
+
+pub struct KeyManagerServiceInitializer<T>
+where T: KeyManagerBackend
+{
+    backend: Option<T>,
+}
+
+impl<T> KeyManagerServiceInitializer<T>
+where T: KeyManagerBackend
+{
+    pub fn new(backend: T) -> Self {
+        Self { backend: Some(backend) }
+    }
+}
+
+#[async_trait]
+impl<T> ServiceInitializer for KeyManagerServiceInitializer<T>
+where T: KeyManagerBackend + 'static
+{
+    async fn initialize(&mut self, context: ServiceInitializerContext) -> Result<(), ServiceInitializationError> {
+        let (sender, receiver) = reply_channel::unbounded();
+
+        // Register handle before waiting for handles to be ready
+        context.register_handle(KeyManagerHandle::new(sender));
+
+        let backend = self
+            .backend
+            .take()
+            .expect("Cannot start Key Manager Service without setting a storage backend");
+        let shutdown_signal = context.get_shutdown_signal();
+
+        context.spawn_when_ready(move |handles| async move {
+            let service = KeyManagerService::new(
+                receiver,
+                KeyManagerDatabase::new(backend),
+                handles.get_shutdown_signal(),
+                // Key derivations are only audited if the audit log service is part of the stack
+                handles.get_handle::<AuditLogHandle>(),
+            )
+            .start();
+            futures::pin_mut!(service);
+            future::select(service, shutdown_signal).await;
+            info!(target: LOG_TARGET, "Key manager service shutdown");
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    audit_log_service::{handle::AuditLogHandle, storage::database::AuditAction},
+    key_manager_service::{
+        error::KeyManagerServiceError,
+        handle::{KeyManagerRequest, KeyManagerResponse},
+        storage::database::{KeyIndexStatus, KeyManagerBackend, KeyManagerDatabase},
+    },
+};
+use futures::{pin_mut, StreamExt};
+use log::*;
+use tari_service_framework::reply_channel;
+use tari_shutdown::ShutdownSignal;
+
+const LOG_TARGET: &str = "wallet::key_manager_service";
+
+/// Requests are handled one at a time and every storage operation is atomic, so consumers sharing a branch can never
+/// be handed the same index
+pub struct KeyManagerService<T>
+where T: KeyManagerBackend + 'static
+{
+    db: KeyManagerDatabase<T>,
+    request_stream:
+        Option<reply_channel::Receiver<KeyManagerRequest, Result<KeyManagerResponse, KeyManagerServiceError>>>,
+    shutdown_signal: Option<ShutdownSignal>,
+    audit_log: Option<AuditLogHandle>,
+}
+
+impl<T> KeyManagerService<T>
+where T: KeyManagerBackend + 'static
+{
+    pub fn new(
+        request_stream: reply_channel::Receiver<KeyManagerRequest, Result<KeyManagerResponse, KeyManagerServiceError>>,
+        db: KeyManagerDatabase<T>,
+        shutdown_signal: ShutdownSignal,
+        audit_log: Option<AuditLogHandle>,
+    ) -> Self {
+        Self {
+            db,
+            request_stream: Some(request_stream),
+            shutdown_signal: Some(shutdown_signal),
+            audit_log,
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), KeyManagerServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Key Manager Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+
+        let shutdown = self
+            .shutdown_signal
+            .take()
+            .expect("Key Manager Service initialized without shutdown signal");
+        pin_mut!(shutdown);
+
+        info!(target: LOG_TARGET, "Key Manager Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let response = self.handle_request(request).await.map_err(|e| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", e);
+                        e
+                    });
+                    let _ = reply_tx.send(response).map_err(|e| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        e
+                    });
+                },
+                _ = shutdown => {
+                    info!(target: LOG_TARGET, "Key manager service shutting down because it received the shutdown signal");
+                    break;
+                }
+                complete => {
+                    info!(target: LOG_TARGET, "Key manager service shutting down");
+                    break;
+                }
+            }
+        }
+        info!(target: LOG_TARGET, "Key Manager Service ended");
+        Ok(())
+    }
+
+    async fn handle_request(
+        &mut self,
+        request: KeyManagerRequest,
+    ) -> Result<KeyManagerResponse, KeyManagerServiceError> {
+        trace!(target: LOG_TARGET, "Handling Service Request: {:?}", request);
+        match request {
+            KeyManagerRequest::ReserveNextIndex { branch, consumer } => {
+                let index = self.db.reserve_next_index(branch.clone(), consumer.clone()).await?;
+                self.record_key_derivation(&branch, index, &consumer);
+                Ok(KeyManagerResponse::Index(index))
+            },
+            KeyManagerRequest::BackfillIndex { branch, consumer } => {
+                let index = match self.db.reserve_gap_index(branch.clone(), consumer.clone()).await? {
+                    Some(index) => {
+                        debug!(target: LOG_TARGET, "Backfilled index {} of branch `{}`", index, branch);
+                        index
+                    },
+                    None => self.db.reserve_next_index(branch.clone(), consumer.clone()).await?,
+                };
+                self.record_key_derivation(&branch, index, &consumer);
+                Ok(KeyManagerResponse::Index(index))
+            },
+            KeyManagerRequest::MarkIndexUsed { branch, index } => {
+                self.db.set_index_status(branch, index, KeyIndexStatus::Used).await?;
+                Ok(KeyManagerResponse::IndexUpdated)
+            },
+            KeyManagerRequest::ReleaseIndex { branch, index } => {
+                self.db
+                    .set_index_status(branch, index, KeyIndexStatus::Released)
+                    .await?;
+                Ok(KeyManagerResponse::IndexUpdated)
+            },
+            KeyManagerRequest::RecordUsedIndex {
+                branch,
+                index,
+                consumer,
+            } => {
+                self.db.record_used_index(branch, index, consumer).await?;
+                Ok(KeyManagerResponse::IndexUpdated)
+            },
+            KeyManagerRequest::GetCurrentIndex(branch) => Ok(KeyManagerResponse::Index(
+                self.db.get_branch(branch).await?.map(|b| b.key_index).unwrap_or(0),
+            )),
+            KeyManagerRequest::GetIndexGaps(branch) => {
+                let gaps = match self.db.get_branch(branch.clone()).await? {
+                    Some(state) => state.gaps(&self.db.get_reservations(branch).await?),
+                    None => Vec::new(),
+                };
+                Ok(KeyManagerResponse::IndexGaps(gaps))
+            },
+        }
+    }
+
+    fn record_key_derivation(&self, branch: &str, index: u64, consumer: &str) {
+        if let Some(audit_log) = self.audit_log.as_ref() {
+            audit_log.record(
+                AuditAction::KeyDerived,
+                format!("Reserved index {} of branch `{}` for {}", index, branch, consumer),
+            );
+        }
+    }
+}
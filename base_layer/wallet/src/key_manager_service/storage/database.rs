@@ -0,0 +1,217 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::key_manager_service::error::KeyManagerStorageError;
+use chrono::NaiveDateTime;
+use std::{convert::TryFrom, fmt, sync::Arc};
+
+/// This trait defines the functionality that a database backend needs to provide for the Key Manager Service. Every
+/// method must be atomic with respect to the other methods so that concurrent reservations can never hand out the
+/// same index twice.
+pub trait KeyManagerBackend: Send + Sync + Clone {
+    /// Advance the highest index of the branch and reserve the new index for the consumer. A branch that has not been
+    /// used before starts at index 0, so the first reserved index is 1.
+    fn reserve_next_index(&self, branch: &str, consumer: &str) -> Result<u64, KeyManagerStorageError>;
+    /// Reserve the lowest index of the branch that was skipped or released, if there is one
+    fn reserve_gap_index(&self, branch: &str, consumer: &str) -> Result<Option<u64>, KeyManagerStorageError>;
+    /// Move a reserved index to the `Used` or `Released` status
+    fn set_index_status(&self, branch: &str, index: u64, status: KeyIndexStatus) -> Result<(), KeyManagerStorageError>;
+    /// Record an index that was found to be in use, e.g. during wallet recovery, and advance the highest index of the
+    /// branch to it if it is higher
+    fn record_used_index(&self, branch: &str, index: u64, consumer: &str) -> Result<(), KeyManagerStorageError>;
+    /// Retrieve the state of the branch, if any index of it has been reserved
+    fn fetch_branch(&self, branch: &str) -> Result<Option<KeyManagerBranch>, KeyManagerStorageError>;
+    /// Retrieve all the tracked reservations of the branch ordered by index
+    fn fetch_reservations(&self, branch: &str) -> Result<Vec<KeyIndexReservation>, KeyManagerStorageError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyIndexStatus {
+    /// The index was handed out but the consumer has not confirmed that a key derived from it is in use yet
+    Reserved,
+    Used,
+    /// The consumer gave the index back without using it, so it may be backfilled
+    Released,
+}
+
+impl TryFrom<i32> for KeyIndexStatus {
+    type Error = KeyManagerStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(KeyIndexStatus::Reserved),
+            1 => Ok(KeyIndexStatus::Used),
+            2 => Ok(KeyIndexStatus::Released),
+            _ => Err(KeyManagerStorageError::ConversionError),
+        }
+    }
+}
+
+impl From<KeyIndexStatus> for i32 {
+    fn from(status: KeyIndexStatus) -> Self {
+        match status {
+            KeyIndexStatus::Reserved => 0,
+            KeyIndexStatus::Used => 1,
+            KeyIndexStatus::Released => 2,
+        }
+    }
+}
+
+impl fmt::Display for KeyIndexStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyIndexStatus::Reserved => write!(f, "Reserved"),
+            KeyIndexStatus::Used => write!(f, "Used"),
+            KeyIndexStatus::Released => write!(f, "Released"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyIndexReservation {
+    pub branch: String,
+    pub index: u64,
+    /// The wallet component that reserved the index, e.g. `output_manager`
+    pub consumer: String,
+    pub status: KeyIndexStatus,
+    pub timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyManagerBranch {
+    pub branch: String,
+    /// The highest index that has been handed out
+    pub key_index: u64,
+    /// Reservations are only tracked for indexes above this one; indexes up to it were handed out before tracking
+    /// started
+    pub tracked_from: u64,
+}
+
+/// A tracked index of a branch that is not known to be in use
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyIndexGap {
+    pub index: u64,
+    /// The reservation of the index, or `None` if the index was skipped
+    pub reservation: Option<KeyIndexReservation>,
+}
+
+impl KeyIndexGap {
+    /// Gaps that are still reserved may yet be used by their consumer, so only the others may be backfilled
+    pub fn can_backfill(&self) -> bool {
+        self.reservation
+            .as_ref()
+            .map(|r| r.status == KeyIndexStatus::Released)
+            .unwrap_or(true)
+    }
+}
+
+impl KeyManagerBranch {
+    /// Return every tracked index of the branch that is not used, given the branch's reservations ordered by index
+    pub fn gaps(&self, reservations: &[KeyIndexReservation]) -> Vec<KeyIndexGap> {
+        let mut reservations = reservations.iter().peekable();
+        let mut gaps = Vec::new();
+        for index in (self.tracked_from + 1)..=self.key_index {
+            while reservations.peek().map(|r| r.index < index).unwrap_or(false) {
+                reservations.next();
+            }
+            let reservation = reservations.peek().filter(|r| r.index == index).cloned();
+            match reservation {
+                Some(r) if r.status == KeyIndexStatus::Used => {},
+                _ => gaps.push(KeyIndexGap {
+                    index,
+                    reservation: reservation.cloned(),
+                }),
+            }
+        }
+        gaps
+    }
+}
+
+pub struct KeyManagerDatabase<T>
+where T: KeyManagerBackend
+{
+    db: Arc<T>,
+}
+
+impl<T> KeyManagerDatabase<T>
+where T: KeyManagerBackend + 'static
+{
+    pub fn new(db: T) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    pub async fn reserve_next_index(&self, branch: String, consumer: String) -> Result<u64, KeyManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.reserve_next_index(&branch, &consumer))
+            .await
+            .map_err(|err| KeyManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn reserve_gap_index(
+        &self,
+        branch: String,
+        consumer: String,
+    ) -> Result<Option<u64>, KeyManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.reserve_gap_index(&branch, &consumer))
+            .await
+            .map_err(|err| KeyManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn set_index_status(
+        &self,
+        branch: String,
+        index: u64,
+        status: KeyIndexStatus,
+    ) -> Result<(), KeyManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.set_index_status(&branch, index, status))
+            .await
+            .map_err(|err| KeyManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn record_used_index(
+        &self,
+        branch: String,
+        index: u64,
+        consumer: String,
+    ) -> Result<(), KeyManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.record_used_index(&branch, index, &consumer))
+            .await
+            .map_err(|err| KeyManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn get_branch(&self, branch: String) -> Result<Option<KeyManagerBranch>, KeyManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.fetch_branch(&branch))
+            .await
+            .map_err(|err| KeyManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn get_reservations(&self, branch: String) -> Result<Vec<KeyIndexReservation>, KeyManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.fetch_reservations(&branch))
+            .await
+            .map_err(|err| KeyManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+}
@@ -10,16 +10,22 @@
 
 #[macro_use]
 mod macros;
+pub mod audit_log_service;
+pub mod base_node_selection_service;
 pub mod base_node_service;
 pub mod contacts_service;
 pub mod error;
+pub mod history_sync_service;
+pub mod key_manager_service;
 pub mod output_manager_service;
+pub mod recurring_payment_service;
 pub mod storage;
 pub mod test_utils;
 pub mod transaction_service;
 pub mod types;
 pub mod util;
 pub mod wallet;
+pub mod wallet_events;
 
 #[cfg(feature = "test_harness")]
 pub mod testnet_utils;
@@ -32,6 +38,7 @@ extern crate diesel_migrations;
 extern crate lazy_static;
 
 mod config;
+mod proto;
 pub mod schema;
 pub mod utxo_scanner_service;
 
@@ -40,7 +47,9 @@ pub use wallet::Wallet;
 
 use crate::{
     contacts_service::storage::sqlite_db::ContactsServiceSqliteDatabase,
+    history_sync_service::storage::sqlite_db::HistorySyncSqliteDatabase,
     output_manager_service::storage::sqlite_db::OutputManagerSqliteDatabase,
+    recurring_payment_service::storage::sqlite_db::RecurringPaymentSqliteDatabase,
     storage::sqlite_db::WalletSqliteDatabase,
     transaction_service::storage::sqlite_db::TransactionServiceSqliteDatabase,
 };
@@ -50,4 +59,6 @@ pub type WalletSqlite = Wallet<
     TransactionServiceSqliteDatabase,
     OutputManagerSqliteDatabase,
     ContactsServiceSqliteDatabase,
+    RecurringPaymentSqliteDatabase,
+    HistorySyncSqliteDatabase,
 >;
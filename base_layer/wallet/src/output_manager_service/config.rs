@@ -30,6 +30,14 @@ pub struct OutputManagerServiceConfig {
     pub prevent_fee_gt_amount: bool,
     pub peer_dial_retry_timeout: Duration,
     pub seed_word_language: MnemonicLanguage,
+    /// How often expired output leases are checked for and released
+    pub output_lease_check_interval: Duration,
+    pub txo_validation_mode: TxoValidationMode,
+    pub unconfirmed_change_policy: UnconfirmedChangePolicy,
+    /// Derive the nonces of the partial signatures for sent and received transactions from the signing key and the
+    /// transaction instead of the OS random number generator. The offsets of sent transactions are then derived from
+    /// the master key at indexes that are never reused.
+    pub deterministic_nonces: bool,
 }
 
 impl Default for OutputManagerServiceConfig {
@@ -40,6 +48,53 @@ impl Default for OutputManagerServiceConfig {
             prevent_fee_gt_amount: true,
             peer_dial_retry_timeout: Duration::from_secs(20),
             seed_word_language: MnemonicLanguage::English,
+            output_lease_check_interval: Duration::from_secs(10),
+            txo_validation_mode: TxoValidationMode::Trusted,
+            unconfirmed_change_policy: UnconfirmedChangePolicy::Never,
+            deterministic_nonces: false,
+        }
+    }
+}
+
+/// How the base node's answers to TXO validation queries are checked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxoValidationMode {
+    /// Accept the base node's report of which outputs are unspent
+    Trusted,
+    /// Require an MMR inclusion proof for every output that the base node reports as unspent, and verify the proofs
+    /// against the output MMR root of a recent block header
+    MerkleProof,
+}
+
+/// Whether the change outputs of the wallet's own pending transactions may be selected as inputs for new transactions
+/// before they are mined
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnconfirmedChangePolicy {
+    /// Only confirmed outputs are spent
+    Never,
+    /// Unconfirmed change may be spent as long as the chain of unconfirmed transactions that created it is no longer
+    /// than the given depth
+    UpToDepth(usize),
+}
+
+impl UnconfirmedChangePolicy {
+    /// The maximum number of unconfirmed ancestors that a spendable change output may have, or `None` if
+    /// unconfirmed change may not be spent
+    pub fn max_depth(&self) -> Option<usize> {
+        match self {
+            UnconfirmedChangePolicy::Never => None,
+            UnconfirmedChangePolicy::UpToDepth(0) => None,
+            UnconfirmedChangePolicy::UpToDepth(depth) => Some(*depth),
+        }
+    }
+}
+
+impl From<usize> for UnconfirmedChangePolicy {
+    /// A depth of zero disables spending of unconfirmed change
+    fn from(max_depth: usize) -> Self {
+        match max_depth {
+            0 => UnconfirmedChangePolicy::Never,
+            depth => UnconfirmedChangePolicy::UpToDepth(depth),
         }
     }
 }
@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::base_node_service::error::BaseNodeServiceError;
+use crate::{base_node_service::error::BaseNodeServiceError, key_manager_service::error::KeyManagerServiceError};
 use diesel::result::Error as DieselError;
 use tari_comms::{peer_manager::node_id::NodeIdError, protocol::rpc::RpcError};
 use tari_comms_dht::outbound::DhtOutboundError;
@@ -53,18 +53,28 @@ pub enum OutputManagerError {
     MnemonicError(#[from] MnemonicError),
     #[error("Key manager error: `{0}`")]
     KeyManagerError(#[from] KeyManagerError),
+    #[error("Key manager service error: `{0}`")]
+    KeyManagerServiceError(#[from] KeyManagerServiceError),
     #[error("Transaction error: `{0}`")]
     TransactionError(#[from] TransactionError),
     #[error("DHT outbound error: `{0}`")]
     DhtOutboundError(#[from] DhtOutboundError),
     #[error("Conversion error: `{0}`")]
     ConversionError(String),
+    #[error("Invalid MMR proof: {0}")]
+    InvalidMmrProof(String),
     #[error("Not all the transaction inputs and outputs are present to be confirmed: {0}")]
     IncompleteTransaction(&'static str),
     #[error("Not enough funds to fulfil transaction")]
     NotEnoughFunds,
     #[error("Funds are still pending. Unable to fulfil transaction right now.")]
     FundsPending,
+    #[error("The transaction weight of {weight} exceeds the maximum of {max_weight} that fits in a block")]
+    TransactionTooLarge { weight: u64, max_weight: u64 },
+    #[error("The fee is greater than the amount that would be sent")]
+    FeeGreaterThanAmount,
+    #[error("The chain tip is not known, so the spendable outputs cannot be determined")]
+    ChainTipUnknown,
     #[error("Output already exists")]
     DuplicateOutput,
     #[error("Error sending a message to the public API")]
@@ -109,6 +119,22 @@ pub enum OutputManagerError {
     MasterSecretKeyMismatch,
     #[error("Private Key is not found in the current Key Chain")]
     KeyNotFoundInKeyChain,
+    #[error("Output lease `{0}` not found")]
+    OutputLeaseNotFound(u64),
+    #[error("Transaction `{0}` has no unconfirmed outputs to be received")]
+    NoUnconfirmedOutputsToSpend(u64),
+    #[error("Script is not standard: {0}")]
+    NonStandardScript(String),
+    #[error("An account with this name already exists")]
+    AccountAlreadyExists,
+    #[error("Account `{0}` not found")]
+    AccountNotFound(String),
+    #[error("Account names must be non-empty and at most 64 characters")]
+    InvalidAccountName,
+    #[error("Transaction `{0}` did not send a reclaimable one-sided payment")]
+    NoReclaimableOutput(u64),
+    #[error("The one-sided payment sent in transaction `{tx_id}` cannot be reclaimed before height {lock_height}")]
+    OneSidedOutputLocked { tx_id: u64, lock_height: u64 },
 }
 
 #[derive(Debug, Error, PartialEq)]
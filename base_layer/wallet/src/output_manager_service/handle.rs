@@ -24,50 +24,84 @@ use crate::{
     output_manager_service::{
         error::OutputManagerError,
         service::Balance,
-        storage::{database::PendingTransactionOutputs, models::KnownOneSidedPaymentScript},
+        storage::{
+            database::{OutputCursor, PendingTransactionOutputs, UnspentOutputFilter, UnspentOutputPage},
+            models::{KnownOneSidedPaymentScript, DEFAULT_ACCOUNT},
+        },
         tasks::TxoValidationType,
         TxId,
     },
     types::ValidationRetryStrategy,
 };
 use aes_gcm::Aes256Gcm;
+use chrono::NaiveDateTime;
 use futures::{stream::Fuse, StreamExt};
 use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
 use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
-    transaction::{Transaction, TransactionInput, TransactionOutput, UnblindedOutput},
-    transaction_protocol::sender::TransactionSenderMessage,
-    types::PublicKey,
+    transaction::{OutputFeatures, Transaction, TransactionInput, TransactionOutput, UnblindedOutput},
+    transaction_protocol::{sender::TransactionSenderMessage, RewindData},
+    types::{Commitment, PublicKey},
+    CoinbasePayout,
     ReceiverTransactionProtocol,
     SenderTransactionProtocol,
 };
-use tari_crypto::{script::TariScript, tari_utilities::hex::Hex};
+use tari_crypto::{
+    script::{ExecutionStack, TariScript},
+    tari_utilities::hex::Hex,
+};
 use tari_service_framework::reply_channel::SenderService;
 use tokio::sync::broadcast;
 use tower::Service;
 
+/// Overrides for the output that is created when receiving a transaction. Unset options use the sender's proposal,
+/// or the wallet's rewind keys for `rewind_data`.
+#[derive(Debug, Clone, Default)]
+pub struct ReceiveOutputOptions {
+    pub features: Option<OutputFeatures>,
+    /// The script that locks the received output, and the input data that will be provided to it when spending
+    pub script: Option<(TariScript, ExecutionStack)>,
+    pub rewind_data: Option<RewindData>,
+    /// The key manager account that derives the output's keys. Defaults to the default account.
+    pub account: Option<String>,
+}
+
 /// API Request enum
 pub enum OutputManagerRequest {
     GetBalance,
+    GetAccountBalance(String),
+    CreateAccount(String),
+    GetAccounts,
     AddOutput(Box<UnblindedOutput>),
     AddOutputWithTxId((TxId, Box<UnblindedOutput>)),
     UpdateOutputMetadataSignature(Box<TransactionOutput>),
-    GetRecipientTransaction(TransactionSenderMessage),
-    GetCoinbaseTransaction((u64, MicroTari, MicroTari, u64)),
+    GetRecipientTransaction(Box<(TransactionSenderMessage, ReceiveOutputOptions)>),
+    GetCoinbaseTransaction((u64, MicroTari, MicroTari, u64, Vec<CoinbasePayout>)),
     ConfirmPendingTransaction(u64),
     ConfirmTransaction((u64, Vec<TransactionInput>, Vec<TransactionOutput>)),
-    PrepareToSendTransaction((MicroTari, MicroTari, Option<u64>, String, TariScript)),
+    PrepareToSendTransaction((String, MicroTari, MicroTari, Option<u64>, String, TariScript)),
+    PrepareToSendAll((MicroTari, Option<u64>, String, TariScript)),
     CreatePayToSelfTransaction((MicroTari, MicroTari, Option<u64>, String)),
+    CreateInternalTransfer {
+        source_account: String,
+        destination_account: String,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    },
+    CreateChildPaysForParentTransaction((TxId, MicroTari, u64, MicroTari)),
     CancelTransaction(u64),
     TimeoutTransactions(Duration),
     GetPendingTransactions,
     GetSpentOutputs,
     GetUnspentOutputs,
+    GetUnspentOutputsPage((UnspentOutputFilter, Option<OutputCursor>, usize)),
     GetInvalidOutputs,
     GetSeedWords,
     SetBaseNodePublicKey(CommsPublicKey),
     ValidateUtxos(TxoValidationType, ValidationRetryStrategy),
+    RevalidateOutputs(Vec<Commitment>),
     CreateCoinSplit((MicroTari, usize, MicroTari, Option<u64>)),
     ApplyEncryption(Box<Aes256Gcm>),
     RemoveEncryption,
@@ -76,6 +110,14 @@ pub enum OutputManagerRequest {
     ScanForRecoverableOutputs(Vec<TransactionOutput>),
     ScanOutputs(Vec<TransactionOutput>),
     AddKnownOneSidedPaymentScript(KnownOneSidedPaymentScript),
+    AddSentOneSidedOutput((TxId, Box<UnblindedOutput>)),
+    ReclaimOneSidedOutput {
+        tx_id: TxId,
+        tip_height: u64,
+    },
+    ReserveOutputs((MicroTari, MicroTari, Duration)),
+    ReleaseOutputLease(LeaseId),
+    GetOutputLeases,
 }
 
 impl fmt::Display for OutputManagerRequest {
@@ -83,6 +125,9 @@ impl fmt::Display for OutputManagerRequest {
         use OutputManagerRequest::*;
         match self {
             GetBalance => write!(f, "GetBalance"),
+            GetAccountBalance(account) => write!(f, "GetAccountBalance ({})", account),
+            CreateAccount(account) => write!(f, "CreateAccount ({})", account),
+            GetAccounts => write!(f, "GetAccounts"),
             AddOutput(v) => write!(f, "AddOutput ({})", v.value),
             AddOutputWithTxId((t, v)) => write!(f, "AddOutputWithTxId ({}: {})", t, v.value),
             UpdateOutputMetadataSignature(v) => write!(
@@ -95,17 +140,36 @@ impl fmt::Display for OutputManagerRequest {
             GetRecipientTransaction(_) => write!(f, "GetRecipientTransaction"),
             ConfirmTransaction(v) => write!(f, "ConfirmTransaction ({})", v.0),
             ConfirmPendingTransaction(v) => write!(f, "ConfirmPendingTransaction ({})", v),
-            PrepareToSendTransaction((_, _, _, msg, _)) => write!(f, "PrepareToSendTransaction ({})", msg),
+            PrepareToSendTransaction((_, _, _, _, msg, _)) => write!(f, "PrepareToSendTransaction ({})", msg),
+            PrepareToSendAll((_, _, msg, _)) => write!(f, "PrepareToSendAll ({})", msg),
             CreatePayToSelfTransaction((_, _, _, msg)) => write!(f, "CreatePayToSelfTransaction ({})", msg),
+            CreateInternalTransfer {
+                source_account,
+                destination_account,
+                amount,
+                message,
+                ..
+            } => write!(
+                f,
+                "CreateInternalTransfer ({} to {}, {}, {})",
+                source_account, destination_account, amount, message
+            ),
+            CreateChildPaysForParentTransaction((tx_id, _, _, fee_per_gram)) => {
+                write!(f, "CreateChildPaysForParentTransaction ({}, {})", tx_id, fee_per_gram)
+            },
             CancelTransaction(v) => write!(f, "CancelTransaction ({})", v),
             TimeoutTransactions(d) => write!(f, "TimeoutTransactions ({}s)", d.as_secs()),
             GetPendingTransactions => write!(f, "GetPendingTransactions"),
             GetSpentOutputs => write!(f, "GetSpentOutputs"),
             GetUnspentOutputs => write!(f, "GetUnspentOutputs"),
+            GetUnspentOutputsPage((_, cursor, limit)) => {
+                write!(f, "GetUnspentOutputsPage (cursor: {:?}, limit: {})", cursor, limit)
+            },
             GetInvalidOutputs => write!(f, "GetInvalidOutputs"),
             GetSeedWords => write!(f, "GetSeedWords"),
             SetBaseNodePublicKey(k) => write!(f, "SetBaseNodePublicKey ({})", k),
             ValidateUtxos(validation_type, retry) => write!(f, "{} ({:?})", validation_type, retry),
+            RevalidateOutputs(commitments) => write!(f, "RevalidateOutputs ({} commitments)", commitments.len()),
             CreateCoinSplit(v) => write!(f, "CreateCoinSplit ({})", v.0),
             ApplyEncryption(_) => write!(f, "ApplyEncryption"),
             RemoveEncryption => write!(f, "RemoveEncryption"),
@@ -115,6 +179,15 @@ impl fmt::Display for OutputManagerRequest {
             ScanForRecoverableOutputs(_) => write!(f, "ScanForRecoverableOutputs"),
             ScanOutputs(_) => write!(f, "ScanRewindAndImportOutputs"),
             AddKnownOneSidedPaymentScript(_) => write!(f, "AddKnownOneSidedPaymentScript"),
+            AddSentOneSidedOutput((t, v)) => write!(f, "AddSentOneSidedOutput ({}: {})", t, v.value),
+            ReclaimOneSidedOutput { tx_id, tip_height } => {
+                write!(f, "ReclaimOneSidedOutput ({}, tip height: {})", tx_id, tip_height)
+            },
+            ReserveOutputs((amount, _, duration)) => {
+                write!(f, "ReserveOutputs ({}, {}s)", amount, duration.as_secs())
+            },
+            ReleaseOutputLease(v) => write!(f, "ReleaseOutputLease ({})", v),
+            GetOutputLeases => write!(f, "GetOutputLeases"),
         }
     }
 }
@@ -123,6 +196,8 @@ impl fmt::Display for OutputManagerRequest {
 #[derive(Debug, Clone)]
 pub enum OutputManagerResponse {
     Balance(Balance),
+    AccountCreated,
+    Accounts(Vec<String>),
     OutputAdded,
     OutputMetadataSignatureUpdated,
     RecipientTransactionGenerated(ReceiverTransactionProtocol),
@@ -130,25 +205,32 @@ pub enum OutputManagerResponse {
     OutputConfirmed,
     PendingTransactionConfirmed,
     PayToSelfTransaction((TxId, MicroTari, Transaction)),
+    ChildPaysForParentTransaction((TxId, MicroTari, MicroTari, Transaction)),
     TransactionConfirmed,
     TransactionToSend(SenderTransactionProtocol),
-    TransactionCancelled,
+    TransactionCancelled(Vec<TxId>),
     TransactionsTimedOut,
     PendingTransactions(HashMap<u64, PendingTransactionOutputs>),
     SpentOutputs(Vec<UnblindedOutput>),
     UnspentOutputs(Vec<UnblindedOutput>),
+    UnspentOutputsPage(UnspentOutputPage),
     InvalidOutputs(Vec<UnblindedOutput>),
     SeedWords(Vec<String>),
     BaseNodePublicKeySet,
     UtxoValidationStarted(u64),
+    OutputsRevalidating(usize),
     Transaction((u64, Transaction, MicroTari, MicroTari)),
     EncryptionApplied,
     EncryptionRemoved,
     PublicRewindKeys(Box<PublicRewindKeys>),
     FeeEstimate(MicroTari),
     RewoundOutputs(Vec<UnblindedOutput>),
-    ScanOutputs(Vec<UnblindedOutput>),
+    ScanOutputs(Vec<(UnblindedOutput, Option<String>)>),
     AddKnownOneSidedPaymentScript,
+    OneSidedOutputReclaimed(MicroTari),
+    OutputsReserved(OutputLease),
+    OutputLeaseReleased,
+    OutputLeases(Vec<OutputLease>),
 }
 
 pub type OutputManagerEventSender = broadcast::Sender<Arc<OutputManagerEvent>>;
@@ -162,9 +244,21 @@ pub enum OutputManagerEvent {
     TxoValidationFailure(u64, TxoValidationType),
     TxoValidationAborted(u64, TxoValidationType),
     TxoValidationDelayed(u64, TxoValidationType),
+    OutputLeaseExpired(LeaseId),
     Error(String),
 }
 
+pub type LeaseId = u64;
+
+/// A set of unspent outputs that have been reserved for an external party to spend. Leased outputs cannot be selected
+/// by the wallet and are released when the lease expires, is released or the wallet restarts.
+#[derive(Debug, Clone)]
+pub struct OutputLease {
+    pub lease_id: LeaseId,
+    pub outputs: Vec<UnblindedOutput>,
+    pub expires_at: NaiveDateTime,
+}
+
 #[derive(Debug, Clone)]
 pub struct PublicRewindKeys {
     pub rewind_public_key: PublicKey,
@@ -239,13 +333,52 @@ impl OutputManagerHandle {
         }
     }
 
+    pub async fn get_account_balance(&mut self, account: String) -> Result<Balance, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetAccountBalance(account))
+            .await??
+        {
+            OutputManagerResponse::Balance(b) => Ok(b),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a named account whose keys are derived from a separate branch of the wallet's master key
+    pub async fn create_account(&mut self, account: String) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::CreateAccount(account)).await?? {
+            OutputManagerResponse::AccountCreated => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the names of the named accounts. The default account is not included.
+    pub async fn get_accounts(&mut self) -> Result<Vec<String>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetAccounts).await?? {
+            OutputManagerResponse::Accounts(accounts) => Ok(accounts),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_recipient_transaction(
         &mut self,
         sender_message: TransactionSenderMessage,
+    ) -> Result<ReceiverTransactionProtocol, OutputManagerError> {
+        self.get_recipient_transaction_with_options(sender_message, ReceiveOutputOptions::default())
+            .await
+    }
+
+    pub async fn get_recipient_transaction_with_options(
+        &mut self,
+        sender_message: TransactionSenderMessage,
+        options: ReceiveOutputOptions,
     ) -> Result<ReceiverTransactionProtocol, OutputManagerError> {
         match self
             .handle
-            .call(OutputManagerRequest::GetRecipientTransaction(sender_message))
+            .call(OutputManagerRequest::GetRecipientTransaction(Box::new((
+                sender_message,
+                options,
+            ))))
             .await??
         {
             OutputManagerResponse::RecipientTransactionGenerated(rtp) => Ok(rtp),
@@ -259,6 +392,20 @@ impl OutputManagerHandle {
         reward: MicroTari,
         fees: MicroTari,
         block_height: u64,
+    ) -> Result<Transaction, OutputManagerError> {
+        self.get_split_coinbase_transaction(tx_id, reward, fees, block_height, Vec::new())
+            .await
+    }
+
+    /// Request a coinbase transaction that pays the given shares of the coinbase directly to other parties, with the
+    /// remainder going to this wallet
+    pub async fn get_split_coinbase_transaction(
+        &mut self,
+        tx_id: TxId,
+        reward: MicroTari,
+        fees: MicroTari,
+        block_height: u64,
+        payouts: Vec<CoinbasePayout>,
     ) -> Result<Transaction, OutputManagerError> {
         match self
             .handle
@@ -267,6 +414,7 @@ impl OutputManagerHandle {
                 reward,
                 fees,
                 block_height,
+                payouts,
             )))
             .await??
         {
@@ -282,10 +430,32 @@ impl OutputManagerHandle {
         lock_height: Option<u64>,
         message: String,
         recipient_script: TariScript,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError> {
+        self.prepare_transaction_to_send_from_account(
+            DEFAULT_ACCOUNT.to_string(),
+            amount,
+            fee_per_gram,
+            lock_height,
+            message,
+            recipient_script,
+        )
+        .await
+    }
+
+    /// Prepare a transaction that only spends outputs belonging to `account`. Change is returned to the same account.
+    pub async fn prepare_transaction_to_send_from_account(
+        &mut self,
+        account: String,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        recipient_script: TariScript,
     ) -> Result<SenderTransactionProtocol, OutputManagerError> {
         match self
             .handle
             .call(OutputManagerRequest::PrepareToSendTransaction((
+                account,
                 amount,
                 fee_per_gram,
                 lock_height,
@@ -299,6 +469,28 @@ impl OutputManagerHandle {
         }
     }
 
+    pub async fn prepare_transaction_to_send_all(
+        &mut self,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        recipient_script: TariScript,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::PrepareToSendAll((
+                fee_per_gram,
+                lock_height,
+                message,
+                recipient_script,
+            )))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSend(stp) => Ok(stp),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     /// Get a fee estimate for an amount of MicroTari, at a specified fee per gram and given number of kernels and
     /// outputs.
     pub async fn fee_estimate(
@@ -354,13 +546,15 @@ impl OutputManagerHandle {
         }
     }
 
-    pub async fn cancel_transaction(&mut self, tx_id: u64) -> Result<(), OutputManagerError> {
+    /// Cancel the pending outputs of a transaction. Returns the TxIds of any pending transactions that spent the
+    /// unconfirmed change of this transaction, which have been cancelled along with it.
+    pub async fn cancel_transaction(&mut self, tx_id: u64) -> Result<Vec<TxId>, OutputManagerError> {
         match self
             .handle
             .call(OutputManagerRequest::CancelTransaction(tx_id))
             .await??
         {
-            OutputManagerResponse::TransactionCancelled => Ok(()),
+            OutputManagerResponse::TransactionCancelled(descendants) => Ok(descendants),
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
@@ -400,6 +594,24 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Returns up to `limit` unspent outputs matching the filter that come after `cursor`, in the order they were
+    /// received. Pass the returned `next_cursor` to fetch the following page.
+    pub async fn get_unspent_outputs_page(
+        &mut self,
+        filter: UnspentOutputFilter,
+        cursor: Option<OutputCursor>,
+        limit: usize,
+    ) -> Result<UnspentOutputPage, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetUnspentOutputsPage((filter, cursor, limit)))
+            .await??
+        {
+            OutputManagerResponse::UnspentOutputsPage(page) => Ok(page),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_invalid_outputs(&mut self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
         match self.handle.call(OutputManagerRequest::GetInvalidOutputs).await?? {
             OutputManagerResponse::InvalidOutputs(s) => Ok(s),
@@ -447,6 +659,19 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Re-check the spent status of the outputs with these commitments, e.g. because the transactions that created or
+    /// spent them were reorged out of the chain. Returns the number of the wallet's outputs being re-checked.
+    pub async fn revalidate_outputs(&mut self, commitments: Vec<Commitment>) -> Result<usize, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::RevalidateOutputs(commitments))
+            .await??
+        {
+            OutputManagerResponse::OutputsRevalidating(n) => Ok(n),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     /// Create a coin split transaction.
     /// Returns (tx_id, tx, fee, utxos_total_value).
     pub async fn create_coin_split(
@@ -506,7 +731,7 @@ impl OutputManagerHandle {
     pub async fn scan_outputs_for_one_sided_payments(
         &mut self,
         outputs: Vec<TransactionOutput>,
-    ) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+    ) -> Result<Vec<(UnblindedOutput, Option<String>)>, OutputManagerError> {
         match self.handle.call(OutputManagerRequest::ScanOutputs(outputs)).await?? {
             OutputManagerResponse::ScanOutputs(outputs) => Ok(outputs),
             _ => Err(OutputManagerError::UnexpectedApiResponse),
@@ -524,6 +749,39 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Keep the output of a reclaimable one-sided payment sent in `tx_id`, so that it can be reclaimed later
+    pub async fn add_sent_one_sided_output(
+        &mut self,
+        tx_id: TxId,
+        output: UnblindedOutput,
+    ) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::AddSentOneSidedOutput((tx_id, Box::new(output))))
+            .await??
+        {
+            OutputManagerResponse::OutputAdded => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Reclaim the output of the one-sided payment sent in `tx_id` as an unspent output of this wallet. Fails if the
+    /// payment's lock height is after the block following `tip_height`. Returns the value of the reclaimed output.
+    pub async fn reclaim_one_sided_output(
+        &mut self,
+        tx_id: TxId,
+        tip_height: u64,
+    ) -> Result<MicroTari, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ReclaimOneSidedOutput { tx_id, tip_height })
+            .await??
+        {
+            OutputManagerResponse::OneSidedOutputReclaimed(value) => Ok(value),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn create_pay_to_self_transaction(
         &mut self,
         amount: MicroTari,
@@ -545,4 +803,92 @@ impl OutputManagerHandle {
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
+
+    /// Create and sign a transaction that moves `amount` from one of the wallet's accounts to another, or back to the
+    /// same account when consolidating. No other party is involved, so the transaction is complete when returned.
+    pub async fn create_internal_transfer(
+        &mut self,
+        source_account: String,
+        destination_account: String,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<(TxId, MicroTari, Transaction), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateInternalTransfer {
+                source_account,
+                destination_account,
+                amount,
+                fee_per_gram,
+                message,
+            })
+            .await??
+        {
+            OutputManagerResponse::PayToSelfTransaction(outputs) => Ok(outputs),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a transaction that spends the unconfirmed outputs we are receiving in the parent transaction `tx_id`
+    /// back to ourselves, paying a fee large enough for the parent and child together to reach `fee_per_gram`.
+    /// `parent_fee` and `parent_weight` are those of the parent transaction. Returns the child's tx_id, fee, amount
+    /// and transaction.
+    pub async fn create_child_pays_for_parent_transaction(
+        &mut self,
+        parent_tx_id: TxId,
+        parent_fee: MicroTari,
+        parent_weight: u64,
+        fee_per_gram: MicroTari,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateChildPaysForParentTransaction((
+                parent_tx_id,
+                parent_fee,
+                parent_weight,
+                fee_per_gram,
+            )))
+            .await??
+        {
+            OutputManagerResponse::ChildPaysForParentTransaction(v) => Ok(v),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Reserve unspent outputs that cover at least `amount` plus the fee of spending them for `duration`. The
+    /// outputs are not used by the wallet until the lease is released or expires.
+    pub async fn reserve_outputs(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        duration: Duration,
+    ) -> Result<OutputLease, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ReserveOutputs((amount, fee_per_gram, duration)))
+            .await??
+        {
+            OutputManagerResponse::OutputsReserved(lease) => Ok(lease),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn release_output_lease(&mut self, lease_id: LeaseId) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ReleaseOutputLease(lease_id))
+            .await??
+        {
+            OutputManagerResponse::OutputLeaseReleased => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_output_leases(&mut self) -> Result<Vec<OutputLease>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetOutputLeases).await?? {
+            OutputManagerResponse::OutputLeases(leases) => Ok(leases),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
 }
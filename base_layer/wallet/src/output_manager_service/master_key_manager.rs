@@ -21,15 +21,20 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    key_manager_service::{account_branch, handle::KeyManagerHandle, PRIMARY_BRANCH},
     output_manager_service::{
         error::OutputManagerError,
         handle::PublicRewindKeys,
-        storage::database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase},
+        storage::{
+            database::{KeyManagerAccount, KeyManagerState, OutputManagerBackend, OutputManagerDatabase},
+            models::DEFAULT_ACCOUNT,
+        },
     },
     types::KeyDigest,
 };
 use futures::lock::Mutex;
 use log::*;
+use std::collections::HashMap;
 use tari_core::transactions::{
     transaction_protocol::RewindData,
     types::{PrivateKey, PublicKey},
@@ -47,7 +52,27 @@ const KEY_MANAGER_COINBASE_SCRIPT_BRANCH_KEY: &str = "coinbase_script";
 const KEY_MANAGER_SCRIPT_BRANCH_KEY: &str = "script";
 const KEY_MANAGER_RECOVERY_VIEWONLY_BRANCH_KEY: &str = "recovery_viewonly";
 const KEY_MANAGER_RECOVERY_BLINDING_BRANCH_KEY: &str = "recovery_blinding";
+const KEY_MANAGER_TRANSACTION_OFFSET_BRANCH_KEY: &str = "transaction_offset";
 const KEY_MANAGER_MAX_SEARCH_DEPTH: u64 = 1_000_000;
+const MAX_ACCOUNT_NAME_LENGTH: usize = 64;
+/// The name under which the output manager reserves key indexes from the key manager service
+const KEY_INDEX_CONSUMER: &str = "output_manager";
+
+/// The spending and script key managers of a named account
+struct AccountKeyManagers {
+    spend: KeyManager<PrivateKey, KeyDigest>,
+    script: KeyManager<PrivateKey, KeyDigest>,
+}
+
+impl AccountKeyManagers {
+    fn new(master_key: PrivateKey, name: &str, key_index: u64) -> Self {
+        let branch = account_branch(name);
+        Self {
+            spend: KeyManager::from(master_key.clone(), branch.clone(), key_index),
+            script: KeyManager::from(master_key, format!("{}_script", branch), key_index),
+        }
+    }
+}
 
 pub(crate) struct MasterKeyManager<TBackend>
 where TBackend: OutputManagerBackend + 'static
@@ -56,8 +81,11 @@ where TBackend: OutputManagerBackend + 'static
     utxo_script_key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
     coinbase_key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
     coinbase_script_key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
+    account_key_managers: Mutex<HashMap<String, AccountKeyManagers>>,
+    transaction_offset_key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
     rewind_data: RewindData,
     db: OutputManagerDatabase<TBackend>,
+    key_manager: KeyManagerHandle,
 }
 
 impl<TBackend> MasterKeyManager<TBackend>
@@ -66,6 +94,7 @@ where TBackend: OutputManagerBackend + 'static
     pub async fn new(
         master_secret_key: PrivateKey,
         db: OutputManagerDatabase<TBackend>,
+        key_manager: KeyManagerHandle,
     ) -> Result<Self, OutputManagerError> {
         // Check to see if there is any persisted state. If there is confirm that the provided master secret key matches
         let key_manager_state = match db.get_key_manager_state().await? {
@@ -88,7 +117,7 @@ where TBackend: OutputManagerBackend + 'static
 
         let utxo_key_manager = KeyManager::<PrivateKey, KeyDigest>::from(
             key_manager_state.master_key.clone(),
-            key_manager_state.branch_seed,
+            key_manager_state.branch_seed.clone(),
             key_manager_state.primary_key_index,
         );
 
@@ -110,6 +139,23 @@ where TBackend: OutputManagerBackend + 'static
             0,
         );
 
+        let account_key_managers = db
+            .get_key_manager_accounts()
+            .await?
+            .into_iter()
+            .map(|account| {
+                let managers =
+                    AccountKeyManagers::new(key_manager_state.master_key.clone(), &account.name, account.key_index);
+                (account.name, managers)
+            })
+            .collect();
+
+        let transaction_offset_key_manager = KeyManager::<PrivateKey, KeyDigest>::from(
+            key_manager_state.master_key.clone(),
+            KEY_MANAGER_TRANSACTION_OFFSET_BRANCH_KEY.to_string(),
+            0,
+        );
+
         let rewind_key_manager = KeyManager::<PrivateKey, KeyDigest>::from(
             key_manager_state.master_key.clone(),
             KEY_MANAGER_RECOVERY_VIEWONLY_BRANCH_KEY.to_string(),
@@ -135,8 +181,11 @@ where TBackend: OutputManagerBackend + 'static
             utxo_script_key_manager: Mutex::new(utxo_script_key_manager),
             coinbase_key_manager: Mutex::new(coinbase_key_manager),
             coinbase_script_key_manager: Mutex::new(coinbase_script_key_manager),
+            account_key_managers: Mutex::new(account_key_managers),
+            transaction_offset_key_manager: Mutex::new(transaction_offset_key_manager),
             rewind_data,
             db,
+            key_manager,
         })
     }
 
@@ -145,18 +194,119 @@ where TBackend: OutputManagerBackend + 'static
     }
 
     /// Return the next pair of (spending_key, script_private_key) from the key managers. These will always be generated
-    /// in tandem and at corresponding increments
+    /// in tandem and at corresponding increments. The index is reserved from the key manager service so that it is
+    /// never handed out to another wallet component as well.
     pub async fn get_next_spend_and_script_key(&self) -> Result<(PrivateKey, PrivateKey), OutputManagerError> {
-        let mut km = self.utxo_key_manager.lock().await;
-        let key = km.next_key()?;
+        let mut key_manager = self.key_manager.clone();
+        let index = key_manager
+            .reserve_next_index(PRIMARY_BRANCH, KEY_INDEX_CONSUMER)
+            .await?;
 
+        let mut km = self.utxo_key_manager.lock().await;
+        let key = km.derive_key(index)?;
         let mut skm = self.utxo_script_key_manager.lock().await;
-        let script_key = skm.next_key()?;
+        let script_key = skm.derive_key(index)?;
+        if index > km.key_index() {
+            km.update_key_index(index);
+            skm.update_key_index(index);
+        }
 
-        self.db.increment_key_index().await?;
+        key_manager.mark_index_used(PRIMARY_BRANCH, index).await?;
         Ok((key.k, script_key.k))
     }
 
+    /// Return the next pair of (spending_key, script_private_key) from the key managers of the given account. The
+    /// default account uses the primary key managers.
+    pub async fn get_next_spend_and_script_key_for_account(
+        &self,
+        account: &str,
+    ) -> Result<(PrivateKey, PrivateKey), OutputManagerError> {
+        if account == DEFAULT_ACCOUNT {
+            return self.get_next_spend_and_script_key().await;
+        }
+
+        let mut accounts = self.account_key_managers.lock().await;
+        let managers = accounts
+            .get_mut(account)
+            .ok_or_else(|| OutputManagerError::AccountNotFound(account.to_string()))?;
+        let branch = account_branch(account);
+        let mut key_manager = self.key_manager.clone();
+        let index = key_manager.reserve_next_index(&branch, KEY_INDEX_CONSUMER).await?;
+
+        let key = managers.spend.derive_key(index)?;
+        let script_key = managers.script.derive_key(index)?;
+        if index > managers.spend.key_index() {
+            managers.spend.update_key_index(index);
+            managers.script.update_key_index(index);
+        }
+
+        key_manager.mark_index_used(&branch, index).await?;
+        Ok((key.k, script_key.k))
+    }
+
+    /// Return the offset for a new transaction that is being sent. The offset is derived from the master key at an
+    /// index that is reserved from the key manager service, so the same offset is never used for two transactions.
+    /// Since the sender's deterministic nonce is derived from the excess less the offset, this keeps each nonce unique
+    /// to a single transaction.
+    pub async fn get_next_transaction_offset(&self) -> Result<PrivateKey, OutputManagerError> {
+        let mut key_manager = self.key_manager.clone();
+        let index = key_manager
+            .reserve_next_index(KEY_MANAGER_TRANSACTION_OFFSET_BRANCH_KEY, KEY_INDEX_CONSUMER)
+            .await?;
+
+        let mut km = self.transaction_offset_key_manager.lock().await;
+        let offset = km.derive_key(index)?;
+        if index > km.key_index() {
+            km.update_key_index(index);
+        }
+
+        key_manager
+            .mark_index_used(KEY_MANAGER_TRANSACTION_OFFSET_BRANCH_KEY, index)
+            .await?;
+        Ok(offset.k)
+    }
+
+    /// Create a new named account that derives its keys from its own branch of the master key
+    pub async fn create_account(&self, name: String) -> Result<(), OutputManagerError> {
+        if name == DEFAULT_ACCOUNT || name.len() > MAX_ACCOUNT_NAME_LENGTH {
+            return Err(OutputManagerError::InvalidAccountName);
+        }
+
+        let mut accounts = self.account_key_managers.lock().await;
+        if accounts.contains_key(&name) {
+            return Err(OutputManagerError::AccountAlreadyExists);
+        }
+
+        self.db
+            .set_key_manager_account(KeyManagerAccount {
+                name: name.clone(),
+                key_index: 0,
+            })
+            .await?;
+        let master_key = self.utxo_key_manager.lock().await.master_key().clone();
+        accounts.insert(name.clone(), AccountKeyManagers::new(master_key, &name, 0));
+        debug!(target: LOG_TARGET, "Created key manager account '{}'", name);
+        Ok(())
+    }
+
+    /// Returns true if the account exists. The default account always exists.
+    pub async fn has_account(&self, account: &str) -> bool {
+        account == DEFAULT_ACCOUNT || self.account_key_managers.lock().await.contains_key(account)
+    }
+
+    /// Return the names of all named accounts, not including the default account
+    pub async fn account_names(&self) -> Vec<String> {
+        let mut names = self
+            .account_key_managers
+            .lock()
+            .await
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
     pub async fn get_script_key_at_index(&self, index: u64) -> Result<PrivateKey, OutputManagerError> {
         let skm = self.utxo_script_key_manager.lock().await;
         let script_key = skm.derive_key(index)?;
@@ -193,8 +343,8 @@ where TBackend: OutputManagerBackend + 'static
 
     /// Search the current key manager key chain to find the index of the specified key.
     pub async fn find_utxo_key_index(&self, key: PrivateKey) -> Result<u64, OutputManagerError> {
+        let current_index = self.key_manager.clone().get_current_index(PRIMARY_BRANCH).await?;
         let utxo_key_manager = self.utxo_key_manager.lock().await;
-        let current_index = (*utxo_key_manager).key_index();
 
         for i in 0u64..current_index + KEY_MANAGER_MAX_SEARCH_DEPTH {
             if (*utxo_key_manager).derive_key(i)?.k == key {
@@ -206,15 +356,19 @@ where TBackend: OutputManagerBackend + 'static
         Err(OutputManagerError::KeyNotFoundInKeyChain)
     }
 
-    /// If the supplied index is higher than the current UTXO key chain indices then they will be updated.
+    /// Record that the key at the supplied index is in use. If the index is higher than the current UTXO key chain
+    /// indices then they will be updated.
     pub async fn update_current_index_if_higher(&self, index: u64) -> Result<(), OutputManagerError> {
+        self.key_manager
+            .clone()
+            .record_used_index(PRIMARY_BRANCH, index, KEY_INDEX_CONSUMER)
+            .await?;
         let mut utxo_key_manager = self.utxo_key_manager.lock().await;
         let mut utxo_script_key_manager = self.utxo_script_key_manager.lock().await;
         let current_index = (*utxo_key_manager).key_index();
         if index > current_index {
             (*utxo_key_manager).update_key_index(index);
             (*utxo_script_key_manager).update_key_index(index);
-            self.db.set_key_index(index).await?;
             trace!(target: LOG_TARGET, "Updated UTXO Key Index to {}", index);
         }
         Ok(())
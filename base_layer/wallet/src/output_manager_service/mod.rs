@@ -22,6 +22,7 @@
 
 use crate::{
     base_node_service::handle::BaseNodeServiceHandle,
+    key_manager_service::handle::KeyManagerHandle,
     output_manager_service::{
         config::OutputManagerServiceConfig,
         handle::OutputManagerHandle,
@@ -56,8 +57,10 @@ pub mod resources;
 pub mod service;
 pub mod storage;
 mod tasks;
+pub mod utxo_bundle;
 
 pub(crate) use master_key_manager::MasterKeyManager;
+pub use recovery::UtxoScanner;
 pub use tasks::TxoValidationType;
 
 const LOG_TARGET: &str = "wallet::output_manager_service::initializer";
@@ -124,6 +127,7 @@ where T: OutputManagerBackend + 'static
             let transaction_service = handles.expect_handle::<TransactionServiceHandle>();
             let base_node_service_handle = handles.expect_handle::<BaseNodeServiceHandle>();
             let connectivity_manager = handles.expect_handle::<ConnectivityRequester>();
+            let key_manager = handles.expect_handle::<KeyManagerHandle>();
 
             let service = OutputManagerService::new(
                 config,
@@ -136,6 +140,7 @@ where T: OutputManagerBackend + 'static
                 handles.get_shutdown_signal(),
                 base_node_service_handle,
                 connectivity_manager,
+                key_manager,
                 master_secret_key,
             )
             .await
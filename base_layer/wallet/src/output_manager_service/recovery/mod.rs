@@ -21,5 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 mod standard_outputs_recoverer;
+mod utxo_scanner;
 
 pub(crate) use standard_outputs_recoverer::StandardUtxoRecoverer;
+pub use utxo_scanner::UtxoScanner;
@@ -22,6 +22,7 @@
 
 use crate::output_manager_service::{
     error::OutputManagerError,
+    recovery::UtxoScanner,
     storage::{
         database::{OutputManagerBackend, OutputManagerDatabase},
         models::DbUnblindedOutput,
@@ -65,41 +66,8 @@ where TBackend: OutputManagerBackend + 'static
         &mut self,
         outputs: Vec<TransactionOutput>,
     ) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
-        let mut rewound_outputs: Vec<UnblindedOutput> = outputs
-            .into_iter()
-            .filter_map(|output| {
-                output
-                    .full_rewind_range_proof(
-                        &self.factories.range_proof,
-                        &self.master_key_manager.rewind_data().rewind_key,
-                        &self.master_key_manager.rewind_data().rewind_blinding_key,
-                    )
-                    .ok()
-                    .map(|v| {
-                        (
-                            v,
-                            output.features,
-                            output.script,
-                            output.sender_offset_public_key,
-                            output.metadata_signature,
-                        )
-                    })
-            })
-            .map(
-                |(output, features, script, sender_offset_public_key, metadata_signature)| {
-                    UnblindedOutput::new(
-                        output.committed_value,
-                        output.blinding_factor.clone(),
-                        Some(features),
-                        script,
-                        inputs!(PublicKey::from_secret_key(&output.blinding_factor)),
-                        output.blinding_factor,
-                        sender_offset_public_key,
-                        metadata_signature,
-                    )
-                },
-            )
-            .collect();
+        let scanner = UtxoScanner::new(self.master_key_manager.rewind_data().clone(), self.factories.clone());
+        let mut rewound_outputs = scanner.scan(outputs);
 
         for output in rewound_outputs.iter_mut() {
             self.update_outputs_script_private_key_and_update_key_manager_index(output)
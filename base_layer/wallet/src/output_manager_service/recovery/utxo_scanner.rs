@@ -0,0 +1,212 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use futures::{future, Stream, StreamExt};
+use tari_core::transactions::{
+    transaction::{TransactionOutput, UnblindedOutput},
+    transaction_protocol::RewindData,
+    types::{CryptoFactories, PublicKey},
+};
+use tari_crypto::{inputs, keys::PublicKey as PublicKeyTrait};
+
+/// Recovers outputs that were created with a set of rewind keys by rewinding their range proofs. The scanner only needs
+/// the rewind keys, not a wallet, so that block explorers and audit tools can scan for a wallet's outputs.
+///
+/// The recovered outputs contain the value, blinding factor and features (including the maturity) of each output. The
+/// script private key cannot be derived from the rewind keys, so the blinding factor is used in its place; a wallet
+/// that wants to spend a recovered output must replace it with the correct script key.
+///
+/// The public rewind keys are derived once when the scanner is created. Every proof is first rewound with the cheaper
+/// value-only rewind using those keys, which fails for outputs created with other keys, and only the remaining proofs
+/// are fully rewound. Scanning outputs in batches with one scanner therefore amortises the setup over the batch.
+#[derive(Clone)]
+pub struct UtxoScanner {
+    rewind_data: RewindData,
+    rewind_public_key: PublicKey,
+    rewind_blinding_public_key: PublicKey,
+    factories: CryptoFactories,
+}
+
+impl UtxoScanner {
+    pub fn new(rewind_data: RewindData, factories: CryptoFactories) -> Self {
+        Self {
+            rewind_public_key: PublicKey::from_secret_key(&rewind_data.rewind_key),
+            rewind_blinding_public_key: PublicKey::from_secret_key(&rewind_data.rewind_blinding_key),
+            rewind_data,
+            factories,
+        }
+    }
+
+    pub fn rewind_data(&self) -> &RewindData {
+        &self.rewind_data
+    }
+
+    /// Attempt to rewind a single output. Returns None if the output was not created with these rewind keys.
+    pub fn try_recover(&self, output: &TransactionOutput) -> Option<UnblindedOutput> {
+        output
+            .rewind_range_proof_value_only(
+                &self.factories.range_proof,
+                &self.rewind_public_key,
+                &self.rewind_blinding_public_key,
+            )
+            .ok()?;
+
+        let rewound = output
+            .full_rewind_range_proof(
+                &self.factories.range_proof,
+                &self.rewind_data.rewind_key,
+                &self.rewind_data.rewind_blinding_key,
+            )
+            .ok()?;
+
+        Some(UnblindedOutput::new(
+            rewound.committed_value,
+            rewound.blinding_factor.clone(),
+            Some(output.features.clone()),
+            output.script.clone(),
+            inputs!(PublicKey::from_secret_key(&rewound.blinding_factor)),
+            rewound.blinding_factor,
+            output.sender_offset_public_key.clone(),
+            output.metadata_signature.clone(),
+            output.covenant.clone(),
+        ))
+    }
+
+    /// Attempt to rewind a batch of outputs. Returns the recovered outputs, in order, together with their index in the
+    /// batch.
+    pub fn try_recover_batch(&self, outputs: &[TransactionOutput]) -> Vec<(usize, UnblindedOutput)> {
+        outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, output)| self.try_recover(output).map(|recovered| (i, recovered)))
+            .collect()
+    }
+
+    /// Returns the outputs that could be recovered from the given outputs, in order
+    pub fn scan<I>(&self, outputs: I) -> Vec<UnblindedOutput>
+    where I: IntoIterator<Item = TransactionOutput> {
+        let outputs = outputs.into_iter().collect::<Vec<_>>();
+        self.try_recover_batch(&outputs)
+            .into_iter()
+            .map(|(_, recovered)| recovered)
+            .collect()
+    }
+
+    /// Returns a stream of the outputs that could be recovered from the given stream of outputs, e.g. outputs streamed
+    /// from a base node while syncing.
+    pub fn scan_stream<S>(&self, outputs: S) -> impl Stream<Item = UnblindedOutput>
+    where S: Stream<Item = TransactionOutput> {
+        let scanner = self.clone();
+        outputs.filter_map(move |output| future::ready(scanner.try_recover(&output)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::stream;
+    use rand::rngs::OsRng;
+    use tari_core::transactions::{
+        helpers::{TestParams, UtxoTestParams},
+        tari_amount::MicroTari,
+        transaction::OutputFeatures,
+        types::PrivateKey,
+    };
+    use tari_crypto::keys::SecretKey;
+
+    fn random_rewind_data() -> RewindData {
+        RewindData {
+            rewind_key: PrivateKey::random(&mut OsRng),
+            rewind_blinding_key: PrivateKey::random(&mut OsRng),
+            proof_message: [7u8; 21],
+        }
+    }
+
+    fn create_output(
+        value: u64,
+        maturity: u64,
+        rewind_data: &RewindData,
+        factories: &CryptoFactories,
+    ) -> TransactionOutput {
+        TestParams::new()
+            .create_unblinded_output(UtxoTestParams {
+                value: MicroTari::from(value),
+                output_features: OutputFeatures::with_maturity(maturity),
+                ..Default::default()
+            })
+            .as_rewindable_transaction_output(factories, rewind_data)
+            .unwrap()
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_recovers_only_outputs_created_with_its_keys() {
+        let factories = CryptoFactories::default();
+        let ours = random_rewind_data();
+        let theirs = random_rewind_data();
+        let outputs = vec![
+            create_output(1000, 0, &ours, &factories),
+            create_output(2000, 5, &theirs, &factories),
+            create_output(3000, 10, &ours, &factories),
+        ];
+
+        let scanner = UtxoScanner::new(ours, factories.clone());
+        let recovered = scanner.scan(outputs.clone());
+        assert_eq!(recovered.len(), 2);
+        assert_eq!(recovered[0].value, MicroTari::from(1000));
+        assert_eq!(recovered[1].value, MicroTari::from(3000));
+        assert_eq!(recovered[1].features.maturity, 10);
+        let commitment = recovered[1]
+            .as_transaction_input(&factories.commitment)
+            .unwrap()
+            .commitment;
+        assert_eq!(commitment, outputs[2].commitment);
+
+        let streamed = scanner
+            .scan_stream(stream::iter(outputs))
+            .map(|output| output.value)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(streamed, vec![MicroTari::from(1000), MicroTari::from(3000)]);
+    }
+
+    #[test]
+    fn it_returns_the_batch_index_of_recovered_outputs() {
+        let factories = CryptoFactories::default();
+        let ours = random_rewind_data();
+        let theirs = random_rewind_data();
+        let outputs = vec![
+            create_output(1000, 0, &theirs, &factories),
+            create_output(2000, 0, &ours, &factories),
+            create_output(3000, 0, &theirs, &factories),
+            create_output(4000, 0, &ours, &factories),
+        ];
+
+        let scanner = UtxoScanner::new(ours, factories);
+        let recovered = scanner
+            .try_recover_batch(&outputs)
+            .into_iter()
+            .map(|(i, output)| (i, output.value))
+            .collect::<Vec<_>>();
+        assert_eq!(recovered, vec![(1, MicroTari::from(2000)), (3, MicroTari::from(4000))]);
+        assert!(scanner.try_recover_batch(&[]).is_empty());
+    }
+}
@@ -22,15 +22,24 @@
 
 use crate::{
     base_node_service::handle::BaseNodeServiceHandle,
+    key_manager_service::handle::KeyManagerHandle,
     output_manager_service::{
         config::OutputManagerServiceConfig,
         error::{OutputManagerError, OutputManagerProtocolError, OutputManagerStorageError},
-        handle::{OutputManagerEventSender, OutputManagerRequest, OutputManagerResponse},
+        handle::{
+            LeaseId,
+            OutputLease,
+            OutputManagerEvent,
+            OutputManagerEventSender,
+            OutputManagerRequest,
+            OutputManagerResponse,
+            ReceiveOutputOptions,
+        },
         recovery::StandardUtxoRecoverer,
         resources::OutputManagerResources,
         storage::{
             database::{OutputManagerBackend, OutputManagerDatabase, PendingTransactionOutputs},
-            models::{DbUnblindedOutput, KnownOneSidedPaymentScript},
+            models::{DbUnblindedOutput, KnownOneSidedPaymentScript, DEFAULT_ACCOUNT},
         },
         tasks::{TxoValidationTask, TxoValidationType},
         MasterKeyManager,
@@ -40,6 +49,7 @@ use crate::{
     types::{HashDigest, ValidationRetryStrategy},
 };
 use blake2::Digest;
+use chrono::{Duration as ChronoDuration, Utc};
 use diesel::result::{DatabaseErrorKind, Error as DieselError};
 use futures::{pin_mut, StreamExt};
 use log::*;
@@ -58,7 +68,10 @@ use tari_comms::{
 use tari_core::{
     consensus::ConsensusConstants,
     transactions::{
+        covenant::Covenant,
         fee::Fee,
+        one_sided::ReclaimableOneSidedScript,
+        script_analysis::analyze_script,
         tari_amount::MicroTari,
         transaction::{
             KernelFeatures,
@@ -68,14 +81,20 @@ use tari_core::{
             TransactionOutput,
             UnblindedOutput,
         },
-        transaction_protocol::sender::TransactionSenderMessage,
-        types::{CryptoFactories, PrivateKey, PublicKey},
+        transaction_protocol::{
+            recipient::ReceiverTransactionProtocolBuilder,
+            sender::TransactionSenderMessage,
+            transaction_initializer::SenderTransactionInitializer,
+        },
+        types::{Commitment, CryptoFactories, PrivateKey, PublicKey},
         CoinbaseBuilder,
+        CoinbasePayout,
         ReceiverTransactionProtocol,
         SenderTransactionProtocol,
     },
 };
 use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
     inputs,
     keys::{DiffieHellmanSharedSecret, PublicKey as PublicKeyTrait, SecretKey},
     script,
@@ -84,7 +103,7 @@ use tari_crypto::{
 };
 use tari_service_framework::reply_channel;
 use tari_shutdown::ShutdownSignal;
-use tokio::sync::broadcast;
+use tokio::{sync::broadcast, time};
 
 const LOG_TARGET: &str = "wallet::output_manager_service";
 const LOG_TARGET_STRESS: &str = "stress_test::output_manager_service";
@@ -101,6 +120,7 @@ where TBackend: OutputManagerBackend + 'static
         Option<reply_channel::Receiver<OutputManagerRequest, Result<OutputManagerResponse, OutputManagerError>>>,
     base_node_update_publisher: broadcast::Sender<CommsPublicKey>,
     base_node_service: BaseNodeServiceHandle,
+    output_leases: HashMap<LeaseId, OutputLease>,
 }
 
 impl<TBackend> OutputManagerService<TBackend>
@@ -121,13 +141,14 @@ where TBackend: OutputManagerBackend + 'static
         shutdown_signal: ShutdownSignal,
         base_node_service: BaseNodeServiceHandle,
         connectivity_manager: ConnectivityRequester,
+        key_manager: KeyManagerHandle,
         master_secret_key: CommsSecretKey,
     ) -> Result<OutputManagerService<TBackend>, OutputManagerError> {
         // Clear any encumberances for transactions that were being negotiated but did not complete to become official
         // Pending Transactions.
         db.clear_short_term_encumberances().await?;
 
-        let master_key_manager = MasterKeyManager::new(master_secret_key, db.clone()).await?;
+        let master_key_manager = MasterKeyManager::new(master_secret_key, db.clone(), key_manager).await?;
 
         let resources = OutputManagerResources {
             config,
@@ -149,6 +170,7 @@ where TBackend: OutputManagerBackend + 'static
             request_stream: Some(request_stream),
             base_node_update_publisher,
             base_node_service,
+            output_leases: HashMap::new(),
         })
     }
 
@@ -161,6 +183,7 @@ where TBackend: OutputManagerBackend + 'static
         pin_mut!(request_stream);
 
         let mut shutdown = self.resources.shutdown_signal.clone();
+        let mut lease_check_interval = time::interval(self.resources.config.output_lease_check_interval).fuse();
 
         info!(target: LOG_TARGET, "Output Manager Service started");
         loop {
@@ -177,6 +200,11 @@ where TBackend: OutputManagerBackend + 'static
                         e
                     });
                 },
+                _ = lease_check_interval.select_next_some() => {
+                    if let Err(e) = self.release_expired_output_leases().await {
+                        warn!(target: LOG_TARGET, "Error releasing expired output leases: {:?}", e);
+                    }
+                },
                 _ = shutdown => {
                     info!(target: LOG_TARGET, "Output manager service shutting down because it received the shutdown signal");
                     break;
@@ -219,28 +247,72 @@ where TBackend: OutputManagerBackend + 'static
                     .await
                     .map(OutputManagerResponse::Balance)
             },
-            OutputManagerRequest::GetRecipientTransaction(tsm) => self
-                .get_recipient_transaction(tsm)
+            OutputManagerRequest::GetAccountBalance(account) => {
+                let current_chain_tip = match self.base_node_service.get_chain_metadata().await {
+                    Ok(metadata) => metadata.map(|m| m.height_of_longest_chain()),
+                    Err(_) => None,
+                };
+                self.get_account_balance(account, current_chain_tip)
+                    .await
+                    .map(OutputManagerResponse::Balance)
+            },
+            OutputManagerRequest::CreateAccount(account) => self
+                .resources
+                .master_key_manager
+                .create_account(account)
                 .await
-                .map(OutputManagerResponse::RecipientTransactionGenerated),
-            OutputManagerRequest::GetCoinbaseTransaction((tx_id, reward, fees, block_height)) => self
-                .get_coinbase_transaction(tx_id, reward, fees, block_height)
+                .map(|_| OutputManagerResponse::AccountCreated),
+            OutputManagerRequest::GetAccounts => Ok(OutputManagerResponse::Accounts(
+                self.resources.master_key_manager.account_names().await,
+            )),
+            OutputManagerRequest::GetRecipientTransaction(request) => {
+                let (tsm, options) = *request;
+                self.get_recipient_transaction(tsm, options)
+                    .await
+                    .map(OutputManagerResponse::RecipientTransactionGenerated)
+            },
+            OutputManagerRequest::GetCoinbaseTransaction((tx_id, reward, fees, block_height, payouts)) => self
+                .get_coinbase_transaction(tx_id, reward, fees, block_height, payouts)
                 .await
                 .map(OutputManagerResponse::CoinbaseTransaction),
             OutputManagerRequest::PrepareToSendTransaction((
+                account,
                 amount,
                 fee_per_gram,
                 lock_height,
                 message,
                 recipient_script,
             )) => self
-                .prepare_transaction_to_send(amount, fee_per_gram, lock_height, message, recipient_script)
+                .prepare_transaction_to_send(account, amount, fee_per_gram, lock_height, message, recipient_script)
+                .await
+                .map(OutputManagerResponse::TransactionToSend),
+            OutputManagerRequest::PrepareToSendAll((fee_per_gram, lock_height, message, recipient_script)) => self
+                .prepare_transaction_to_send_all(fee_per_gram, lock_height, message, recipient_script)
                 .await
                 .map(OutputManagerResponse::TransactionToSend),
             OutputManagerRequest::CreatePayToSelfTransaction((amount, fee_per_gram, lock_height, message)) => self
                 .create_pay_to_self_transaction(amount, fee_per_gram, lock_height, message)
                 .await
                 .map(OutputManagerResponse::PayToSelfTransaction),
+            OutputManagerRequest::CreateInternalTransfer {
+                source_account,
+                destination_account,
+                amount,
+                fee_per_gram,
+                message,
+            } => self
+                .create_internal_transfer(source_account, destination_account, amount, fee_per_gram, None, message)
+                .await
+                .map(OutputManagerResponse::PayToSelfTransaction),
+            OutputManagerRequest::CreateChildPaysForParentTransaction((
+                parent_tx_id,
+                parent_fee,
+                parent_weight,
+                fee_per_gram,
+            )) => self
+                .create_child_pays_for_parent_transaction(parent_tx_id, parent_fee, parent_weight, fee_per_gram)
+                .await
+                .map(OutputManagerResponse::ChildPaysForParentTransaction),
             OutputManagerRequest::FeeEstimate((amount, fee_per_gram, num_kernels, num_outputs)) => self
                 .fee_estimate(amount, fee_per_gram, num_kernels, num_outputs)
                 .await
@@ -256,7 +328,7 @@ where TBackend: OutputManagerBackend + 'static
             OutputManagerRequest::CancelTransaction(tx_id) => self
                 .cancel_transaction(tx_id)
                 .await
-                .map(|_| OutputManagerResponse::TransactionCancelled),
+                .map(OutputManagerResponse::TransactionCancelled),
             OutputManagerRequest::TimeoutTransactions(period) => self
                 .timeout_pending_transactions(period)
                 .await
@@ -283,6 +355,13 @@ where TBackend: OutputManagerBackend + 'static
                     .collect();
                 Ok(OutputManagerResponse::UnspentOutputs(outputs))
             },
+            OutputManagerRequest::GetUnspentOutputsPage((filter, cursor, limit)) => self
+                .resources
+                .db
+                .fetch_unspent_outputs_page(filter, cursor, limit)
+                .await
+                .map(OutputManagerResponse::UnspentOutputsPage)
+                .map_err(OutputManagerError::OutputManagerStorageError),
             OutputManagerRequest::GetSeedWords => self
                 .resources
                 .master_key_manager
@@ -294,8 +373,12 @@ where TBackend: OutputManagerBackend + 'static
                 .await
                 .map(|_| OutputManagerResponse::BaseNodePublicKeySet),
             OutputManagerRequest::ValidateUtxos(validation_type, retries) => self
-                .validate_outputs(validation_type, retries)
+                .validate_outputs(validation_type, retries, None)
                 .map(OutputManagerResponse::UtxoValidationStarted),
+            OutputManagerRequest::RevalidateOutputs(commitments) => self
+                .revalidate_outputs(commitments)
+                .await
+                .map(OutputManagerResponse::OutputsRevalidating),
             OutputManagerRequest::GetInvalidOutputs => {
                 let outputs = self
                     .fetch_invalid_outputs()
@@ -343,6 +426,25 @@ where TBackend: OutputManagerBackend + 'static
                 .add_known_script(known_script)
                 .await
                 .map(|_| OutputManagerResponse::AddKnownOneSidedPaymentScript),
+            OutputManagerRequest::AddSentOneSidedOutput((tx_id, uo)) => self
+                .add_sent_one_sided_output(tx_id, *uo)
+                .await
+                .map(|_| OutputManagerResponse::OutputAdded),
+            OutputManagerRequest::ReclaimOneSidedOutput { tx_id, tip_height } => self
+                .reclaim_one_sided_output(tx_id, tip_height)
+                .await
+                .map(OutputManagerResponse::OneSidedOutputReclaimed),
+            OutputManagerRequest::ReserveOutputs((amount, fee_per_gram, duration)) => self
+                .reserve_outputs(amount, fee_per_gram, duration)
+                .await
+                .map(OutputManagerResponse::OutputsReserved),
+            OutputManagerRequest::ReleaseOutputLease(lease_id) => self
+                .release_output_lease(lease_id)
+                .await
+                .map(|_| OutputManagerResponse::OutputLeaseReleased),
+            OutputManagerRequest::GetOutputLeases => Ok(OutputManagerResponse::OutputLeases(
+                self.output_leases.values().cloned().collect(),
+            )),
         }
     }
 
@@ -350,13 +452,14 @@ where TBackend: OutputManagerBackend + 'static
         &mut self,
         validation_type: TxoValidationType,
         retry_strategy: ValidationRetryStrategy,
+        output_hashes: Option<Vec<Vec<u8>>>,
     ) -> Result<u64, OutputManagerError> {
         match self.resources.base_node_public_key.as_ref() {
             None => Err(OutputManagerError::NoBaseNodeKeysProvided),
             Some(pk) => {
                 let id = OsRng.next_u64();
 
-                let utxo_validation_task = TxoValidationTask::new(
+                let mut utxo_validation_task = TxoValidationTask::new(
                     id,
                     validation_type,
                     retry_strategy,
@@ -364,6 +467,9 @@ where TBackend: OutputManagerBackend + 'static
                     pk.clone(),
                     self.base_node_update_publisher.subscribe(),
                 );
+                if let Some(output_hashes) = output_hashes {
+                    utxo_validation_task = utxo_validation_task.restricted_to(output_hashes);
+                }
 
                 tokio::spawn(async move {
                     match utxo_validation_task.execute().await {
@@ -387,6 +493,49 @@ where TBackend: OutputManagerBackend + 'static
         }
     }
 
+    /// Validate only the spent and unspent outputs with the given commitments, so that spent outputs that are back in
+    /// the UTXO set are restored to unspent and unspent outputs that no longer exist are invalidated
+    async fn revalidate_outputs(&mut self, commitments: Vec<Commitment>) -> Result<usize, OutputManagerError> {
+        let spent = self
+            .resources
+            .db
+            .get_spent_outputs()
+            .await?
+            .into_iter()
+            .filter(|o| commitments.contains(&o.commitment))
+            .map(|o| o.hash)
+            .collect::<Vec<_>>();
+        let unspent = self
+            .resources
+            .db
+            .get_unspent_outputs()
+            .await?
+            .into_iter()
+            .filter(|o| commitments.contains(&o.commitment))
+            .map(|o| o.hash)
+            .collect::<Vec<_>>();
+        let num_outputs = spent.len() + unspent.len();
+
+        for (validation_type, output_hashes) in
+            vec![(TxoValidationType::Spent, spent), (TxoValidationType::Unspent, unspent)]
+        {
+            if output_hashes.is_empty() {
+                continue;
+            }
+            let id = self.validate_outputs(
+                validation_type,
+                ValidationRetryStrategy::UntilSuccess,
+                Some(output_hashes),
+            )?;
+            debug!(
+                target: LOG_TARGET,
+                "Revalidating outputs after reorg with {} Validation Protocol (Id: {})", validation_type, id
+            );
+        }
+
+        Ok(num_outputs)
+    }
+
     /// Add an unblinded output to the unspent outputs list
     pub async fn add_output(&mut self, tx_id: Option<TxId>, output: UnblindedOutput) -> Result<(), OutputManagerError> {
         debug!(
@@ -401,6 +550,49 @@ where TBackend: OutputManagerBackend + 'static
         Ok(())
     }
 
+    async fn add_sent_one_sided_output(
+        &mut self,
+        tx_id: TxId,
+        output: UnblindedOutput,
+    ) -> Result<(), OutputManagerError> {
+        let output = DbUnblindedOutput::from_unblinded_output(output, &self.resources.factories)?;
+        self.resources.db.add_sent_one_sided_output(tx_id, output).await?;
+        Ok(())
+    }
+
+    /// Turns the output of a one-sided payment sent in `tx_id` back into an unspent output of this wallet once its lock
+    /// height has been reached. The recipient may have spent the output before then, so it is re-validated straight
+    /// away and invalidated if it is no longer in the UTXO set.
+    async fn reclaim_one_sided_output(
+        &mut self,
+        tx_id: TxId,
+        tip_height: u64,
+    ) -> Result<MicroTari, OutputManagerError> {
+        let output = self
+            .resources
+            .db
+            .fetch_sent_one_sided_output(tx_id)
+            .await?
+            .ok_or(OutputManagerError::NoReclaimableOutput(tx_id))?;
+        let script = ReclaimableOneSidedScript::from_script(&output.unblinded_output.script)
+            .ok_or(OutputManagerError::NoReclaimableOutput(tx_id))?;
+        // The earliest the reclaiming transaction can be mined is in the next block
+        if !script.is_reclaimable_at(tip_height + 1) {
+            return Err(OutputManagerError::OneSidedOutputLocked {
+                tx_id,
+                lock_height: script.lock_height,
+            });
+        }
+
+        let output = self.resources.db.reclaim_one_sided_output(tx_id).await?;
+        info!(
+            target: LOG_TARGET,
+            "Reclaimed the one-sided payment output of value {} sent in TxId: {}", output.unblinded_output.value, tx_id
+        );
+        self.revalidate_outputs(vec![output.commitment.clone()]).await?;
+        Ok(output.unblinded_output.value)
+    }
+
     /// Update an output's metadata signature, akin to 'finalize output'
     pub async fn update_output_metadata_signature(
         &mut self,
@@ -416,49 +608,84 @@ where TBackend: OutputManagerBackend + 'static
         Ok(balance)
     }
 
+    async fn get_account_balance(
+        &self,
+        account: String,
+        current_chain_tip: Option<u64>,
+    ) -> Result<Balance, OutputManagerError> {
+        if !self.resources.master_key_manager.has_account(&account).await {
+            return Err(OutputManagerError::AccountNotFound(account));
+        }
+        let balance = self
+            .resources
+            .db
+            .get_account_balance(account, current_chain_tip)
+            .await?;
+        Ok(balance)
+    }
+
     /// Request a receiver transaction be generated from the supplied Sender Message
     async fn get_recipient_transaction(
         &mut self,
         sender_message: TransactionSenderMessage,
+        options: ReceiveOutputOptions,
     ) -> Result<ReceiverTransactionProtocol, OutputManagerError> {
         let single_round_sender_data = match sender_message.single() {
             Some(data) => data,
             _ => return Err(OutputManagerError::InvalidSenderMessage),
         };
 
-        // Confirm script hash is for the expected script, at the moment assuming Nop
-        if single_round_sender_data.script != script!(Nop) {
-            return Err(OutputManagerError::InvalidScriptHash);
-        }
-
+        let account = options.account.unwrap_or_else(|| DEFAULT_ACCOUNT.to_string());
         let (spending_key, script_private_key) = self
             .resources
             .master_key_manager
-            .get_next_spend_and_script_key()
+            .get_next_spend_and_script_key_for_account(&account)
             .await?;
 
+        let features = options
+            .features
+            .unwrap_or_else(|| single_round_sender_data.features.clone());
+        let (script, input_data) = match options.script {
+            Some(script_and_input_data) => {
+                check_script_is_standard(&script_and_input_data.0)?;
+                script_and_input_data
+            },
+            None => {
+                // Confirm script hash is for the expected script, at the moment assuming Nop
+                if single_round_sender_data.script != script!(Nop) {
+                    return Err(OutputManagerError::InvalidScriptHash);
+                }
+                (
+                    single_round_sender_data.script.clone(),
+                    inputs!(PublicKey::from_secret_key(&script_private_key)),
+                )
+            },
+        };
+
         let output = DbUnblindedOutput::from_unblinded_output(
             UnblindedOutput::new(
                 single_round_sender_data.amount,
                 spending_key.clone(),
-                Some(single_round_sender_data.features.clone()),
-                single_round_sender_data.script.clone(),
-                // TODO: The input data should be variable; this will only work for a Nop script
-                inputs!(PublicKey::from_secret_key(&script_private_key)),
+                Some(features.clone()),
+                script.clone(),
+                input_data,
                 script_private_key,
                 single_round_sender_data.sender_offset_public_key.clone(),
                 // Note: The commitment signature at this time is only partially built
                 TransactionOutput::create_partial_metadata_signature(
                     &single_round_sender_data.amount,
                     &spending_key.clone(),
-                    &single_round_sender_data.script.clone(),
-                    &single_round_sender_data.features.clone(),
+                    &script,
+                    &features,
+                    &single_round_sender_data.covenant,
                     &single_round_sender_data.sender_offset_public_key.clone(),
                     &single_round_sender_data.public_commitment_nonce.clone(),
                 )?,
+                single_round_sender_data.covenant.clone(),
             ),
             &self.resources.factories,
-        )?;
+        )?
+        .with_account(account);
 
         self.resources
             .db
@@ -467,18 +694,19 @@ where TBackend: OutputManagerBackend + 'static
 
         self.confirm_encumberance(single_round_sender_data.tx_id).await?;
 
-        let nonce = PrivateKey::random(&mut OsRng);
-
-        let rtp = ReceiverTransactionProtocol::new_with_rewindable_output(
-            sender_message.clone(),
-            nonce,
-            spending_key,
-            single_round_sender_data.features.clone(),
-            &self.resources.factories,
-            self.resources.master_key_manager.rewind_data(),
-        );
+        let rewind_data = options
+            .rewind_data
+            .unwrap_or_else(|| self.resources.master_key_manager.rewind_data().clone());
+        let mut builder = ReceiverTransactionProtocolBuilder::new(sender_message.clone(), spending_key);
+        builder
+            .with_output_features(features)
+            .with_script(script)
+            .with_rewind_data(rewind_data);
+        if self.resources.config.deterministic_nonces {
+            builder.with_deterministic_nonce();
+        }
 
-        Ok(rtp)
+        Ok(builder.build(&self.resources.factories))
     }
 
     /// Confirm the reception of an expected transaction output. This will be called by the Transaction Service when it
@@ -540,7 +768,7 @@ where TBackend: OutputManagerBackend + 'static
         );
 
         let (utxos, _, _) = self
-            .select_utxos(amount, fee_per_gram, num_outputs as usize, None)
+            .select_utxos(DEFAULT_ACCOUNT, amount, fee_per_gram, num_outputs as usize, None)
             .await?;
         debug!(target: LOG_TARGET, "{} utxos selected.", utxos.len());
 
@@ -550,10 +778,11 @@ where TBackend: OutputManagerBackend + 'static
         Ok(fee)
     }
 
-    /// Prepare a Sender Transaction Protocol for the amount and fee_per_gram specified. If required a change output
-    /// will be produced.
+    /// Prepare a Sender Transaction Protocol for the amount and fee_per_gram specified, spending outputs from the given
+    /// account. If required a change output will be produced for the same account.
     pub async fn prepare_transaction_to_send(
         &mut self,
+        account: String,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         lock_height: Option<u64>,
@@ -564,17 +793,21 @@ where TBackend: OutputManagerBackend + 'static
             target: LOG_TARGET,
             "Preparing to send transaction. Amount: {}. Fee per gram: {}. ", amount, fee_per_gram,
         );
-        let (outputs, _, total) = self.select_utxos(amount, fee_per_gram, 1, None).await?;
+        check_script_is_standard(&recipient_script)?;
+        if !self.resources.master_key_manager.has_account(&account).await {
+            return Err(OutputManagerError::AccountNotFound(account));
+        }
+        let (outputs, _, total) = self.select_utxos(&account, amount, fee_per_gram, 1, None).await?;
 
-        let offset = PrivateKey::random(&mut OsRng);
-        let nonce = PrivateKey::random(&mut OsRng);
+        let offset = self.next_transaction_offset().await?;
 
         let mut builder = SenderTransactionProtocol::builder(1);
+        self.with_sender_nonce(&mut builder);
         builder
             .with_lock_height(lock_height.unwrap_or(0))
             .with_fee_per_gram(fee_per_gram)
+            .with_transaction_weight(*self.resources.consensus_constants.transaction_weight())
             .with_offset(offset.clone())
-            .with_private_nonce(nonce.clone())
             .with_amount(0, amount)
             .with_recipient_data(
                 0,
@@ -584,7 +817,8 @@ where TBackend: OutputManagerBackend + 'static
                 PrivateKey::random(&mut OsRng),
             )
             .with_message(message)
-            .with_prevent_fee_gt_amount(self.resources.config.prevent_fee_gt_amount);
+            .with_prevent_fee_gt_amount(self.resources.config.prevent_fee_gt_amount)
+            .with_input_script_validation(true);
 
         for uo in outputs.iter() {
             builder.with_input(
@@ -606,7 +840,7 @@ where TBackend: OutputManagerBackend + 'static
             let (spending_key, script_private_key) = self
                 .resources
                 .master_key_manager
-                .get_next_spend_and_script_key()
+                .get_next_spend_and_script_key_for_account(&account)
                 .await?;
             builder.with_change_secret(spending_key);
             builder.with_rewindable_outputs(self.resources.master_key_manager.rewind_data().clone());
@@ -629,10 +863,10 @@ where TBackend: OutputManagerBackend + 'static
                     "There should be a change output metadata signature available".to_string(),
                 )
             })?;
-            change_output.push(DbUnblindedOutput::from_unblinded_output(
-                unblinded_output,
-                &self.resources.factories,
-            )?);
+            change_output.push(
+                DbUnblindedOutput::from_unblinded_output(unblinded_output, &self.resources.factories)?
+                    .with_account(account),
+            );
         }
 
         let tx_id = stp.get_tx_id()?;
@@ -652,16 +886,112 @@ where TBackend: OutputManagerBackend + 'static
         Ok(stp)
     }
 
+    /// Prepare a transaction that spends every spendable output to a single recipient without a change output. The
+    /// amount sent is the total value of the outputs less the fee. Outputs that have not matured at the current chain
+    /// tip are excluded, so the chain tip must be known.
+    pub async fn prepare_transaction_to_send_all(
+        &mut self,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        recipient_script: TariScript,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError> {
+        check_script_is_standard(&recipient_script)?;
+        let tip_height = self
+            .base_node_service
+            .get_chain_metadata()
+            .await?
+            .map(|metadata| metadata.height_of_longest_chain())
+            .ok_or(OutputManagerError::ChainTipUnknown)?;
+        let outputs = self
+            .resources
+            .db
+            .fetch_sorted_unspent_outputs()
+            .await?
+            .into_iter()
+            .filter(|uo| uo.account == DEFAULT_ACCOUNT && uo.unblinded_output.features.maturity <= tip_height)
+            .collect::<Vec<_>>();
+        if outputs.is_empty() {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
+        let total = outputs
+            .iter()
+            .fold(MicroTari::from(0), |acc, uo| acc + uo.unblinded_output.value);
+
+        let weighting = *self.resources.consensus_constants.transaction_weight();
+        let weight = weighting.calculate(
+            1,
+            outputs.len(),
+            1,
+            weighting.output_metadata_weight_of(&OutputFeatures::default(), &recipient_script, &Covenant::default()),
+        );
+        self.check_transaction_weight(weight)?;
+        let fee = Fee::calculate_for_weight(fee_per_gram, weight);
+        if total <= fee {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
+        let amount = total - fee;
+        if self.resources.config.prevent_fee_gt_amount && fee > amount {
+            return Err(OutputManagerError::FeeGreaterThanAmount);
+        }
+        debug!(
+            target: LOG_TARGET,
+            "Preparing to send all {} spendable outputs. Amount: {}. Fee: {}.",
+            outputs.len(),
+            amount,
+            fee
+        );
+
+        let offset = self.next_transaction_offset().await?;
+        let mut builder = SenderTransactionProtocol::builder(1);
+        self.with_sender_nonce(&mut builder);
+        builder
+            .with_lock_height(lock_height.unwrap_or(0))
+            .with_fee_per_gram(fee_per_gram)
+            .with_transaction_weight(weighting)
+            .with_offset(offset)
+            .with_amount(0, amount)
+            .with_recipient_data(
+                0,
+                recipient_script,
+                PrivateKey::random(&mut OsRng),
+                Default::default(),
+                PrivateKey::random(&mut OsRng),
+            )
+            .with_message(message)
+            .with_prevent_fee_gt_amount(self.resources.config.prevent_fee_gt_amount)
+            .with_input_script_validation(true);
+        for uo in outputs.iter() {
+            builder.with_input(
+                uo.unblinded_output
+                    .as_transaction_input(&self.resources.factories.commitment)?,
+                uo.unblinded_output.clone(),
+            );
+        }
+
+        let stp = builder
+            .build::<HashDigest>(&self.resources.factories)
+            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+
+        let tx_id = stp.get_tx_id()?;
+        self.resources.db.encumber_outputs(tx_id, outputs, Vec::new()).await?;
+        debug!(target: LOG_TARGET, "Prepared send all transaction (TxId: {})", tx_id);
+
+        Ok(stp)
+    }
+
     /// Request a Coinbase transaction for a specific block height. All existing pending transactions with
     /// this blockheight will be cancelled.
     /// The key will be derived from the coinbase specific keychain using the blockheight as an index. The coinbase
-    /// keychain is based on the wallets master_key and the "coinbase" branch.
+    /// keychain is based on the wallets master_key and the "coinbase" branch. Any `payouts` are paid directly to
+    /// their destinations as part of the coinbase and only the remainder is received by this wallet.
     async fn get_coinbase_transaction(
         &mut self,
         tx_id: TxId,
         reward: MicroTari,
         fees: MicroTari,
         block_height: u64,
+        payouts: Vec<CoinbasePayout>,
     ) -> Result<Transaction, OutputManagerError> {
         debug!(
             target: LOG_TARGET,
@@ -683,6 +1013,7 @@ where TBackend: OutputManagerBackend + 'static
             .with_script(script!(Nop))
             .with_nonce(nonce)
             .with_rewind_data(self.resources.master_key_manager.rewind_data().clone())
+            .with_payouts(payouts)
             .build_with_reward(&self.resources.consensus_constants, reward)?;
 
         let output = DbUnblindedOutput::from_unblinded_output(unblinded_output, &self.resources.factories)?;
@@ -723,21 +1054,51 @@ where TBackend: OutputManagerBackend + 'static
         lock_height: Option<u64>,
         message: String,
     ) -> Result<(TxId, MicroTari, Transaction), OutputManagerError> {
-        let (inputs, _, total) = self.select_utxos(amount, fee_per_gram, 1, None).await?;
+        self.create_internal_transfer(
+            DEFAULT_ACCOUNT.to_string(),
+            DEFAULT_ACCOUNT.to_string(),
+            amount,
+            fee_per_gram,
+            lock_height,
+            message,
+        )
+        .await
+    }
 
-        let offset = PrivateKey::random(&mut OsRng);
-        let nonce = PrivateKey::random(&mut OsRng);
+    /// Build and sign a transaction that spends outputs of `source_account` to a new output of `destination_account`.
+    /// Change is returned to `source_account`.
+    async fn create_internal_transfer(
+        &mut self,
+        source_account: String,
+        destination_account: String,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<(TxId, MicroTari, Transaction), OutputManagerError> {
+        for account in &[&source_account, &destination_account] {
+            if !self.resources.master_key_manager.has_account(account).await {
+                return Err(OutputManagerError::AccountNotFound(account.to_string()));
+            }
+        }
+        let (inputs, _, total) = self
+            .select_utxos(&source_account, amount, fee_per_gram, 1, None)
+            .await?;
+
+        let offset = self.next_transaction_offset().await?;
         let sender_offset_private_key = PrivateKey::random(&mut OsRng);
 
         // Create builder with no recipients (other than ourselves)
         let mut builder = SenderTransactionProtocol::builder(0);
+        self.with_sender_nonce(&mut builder);
         builder
             .with_lock_height(lock_height.unwrap_or(0))
             .with_fee_per_gram(fee_per_gram)
-            .with_offset(offset.clone())
-            .with_private_nonce(nonce.clone())
+            .with_transaction_weight(*self.resources.consensus_constants.transaction_weight())
+            .with_offset(offset)
             .with_message(message)
-            .with_prevent_fee_gt_amount(self.resources.config.prevent_fee_gt_amount);
+            .with_prevent_fee_gt_amount(self.resources.config.prevent_fee_gt_amount)
+            .with_input_script_validation(true);
 
         for uo in &inputs {
             builder.with_input(
@@ -752,13 +1113,14 @@ where TBackend: OutputManagerBackend + 'static
         let (spending_key, script_private_key) = self
             .resources
             .master_key_manager
-            .get_next_spend_and_script_key()
+            .get_next_spend_and_script_key_for_account(&destination_account)
             .await?;
         let metadata_signature = TransactionOutput::create_final_metadata_signature(
             &amount,
             &spending_key.clone(),
             &script,
             &output_features,
+            &Covenant::default(),
             &&sender_offset_private_key,
         )?;
         let utxo = DbUnblindedOutput::from_unblinded_output(
@@ -771,9 +1133,11 @@ where TBackend: OutputManagerBackend + 'static
                 script_private_key,
                 PublicKey::from_secret_key(&sender_offset_private_key),
                 metadata_signature,
+                Covenant::default(),
             ),
             &self.resources.factories,
-        )?;
+        )?
+        .with_account(destination_account);
         builder
             .with_output(utxo.unblinded_output.clone(), sender_offset_private_key.clone())
             .map_err(|e| OutputManagerError::BuildError(e.message))?;
@@ -786,7 +1150,7 @@ where TBackend: OutputManagerBackend + 'static
             let (spending_key, script_private_key) = self
                 .resources
                 .master_key_manager
-                .get_next_spend_and_script_key()
+                .get_next_spend_and_script_key_for_account(&source_account)
                 .await?;
             builder.with_change_secret(spending_key);
             builder.with_rewindable_outputs(self.resources.master_key_manager.rewind_data().clone());
@@ -808,7 +1172,8 @@ where TBackend: OutputManagerBackend + 'static
                     "There should be a change output metadata signature available".to_string(),
                 )
             })?;
-            let change_output = DbUnblindedOutput::from_unblinded_output(unblinded_output, &self.resources.factories)?;
+            let change_output = DbUnblindedOutput::from_unblinded_output(unblinded_output, &self.resources.factories)?
+                .with_account(source_account);
 
             outputs.push(change_output);
         }
@@ -829,6 +1194,123 @@ where TBackend: OutputManagerBackend + 'static
         Ok((tx_id, fee, tx))
     }
 
+    /// Spend the outputs that are still to be received from the unconfirmed parent transaction back to ourselves. The
+    /// child's fee is chosen so that the parent and child together pay at least `fee_per_gram`, which lets a base node
+    /// mempool prioritise the pair as a package.
+    async fn create_child_pays_for_parent_transaction(
+        &mut self,
+        parent_tx_id: TxId,
+        parent_fee: MicroTari,
+        parent_weight: u64,
+        fee_per_gram: MicroTari,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError> {
+        let inputs = self
+            .resources
+            .db
+            .fetch_pending_transaction_outputs(parent_tx_id)
+            .await?
+            .outputs_to_be_received;
+        if inputs.is_empty() {
+            return Err(OutputManagerError::NoUnconfirmedOutputsToSpend(parent_tx_id));
+        }
+        let total = inputs
+            .iter()
+            .fold(MicroTari::from(0), |acc, uo| acc + uo.unblinded_output.value);
+
+        // The child pays whatever the parent is short of, but never less than the requested rate for its own weight
+        let script = script!(Nop);
+        let output_features = OutputFeatures::default();
+        let weighting = *self.resources.consensus_constants.transaction_weight();
+        let child_weight = weighting.calculate(
+            1,
+            inputs.len(),
+            1,
+            weighting.output_metadata_weight_of(&output_features, &script, &Covenant::default()),
+        );
+        let package_fee = Fee::calculate_for_weight(fee_per_gram, parent_weight + child_weight);
+        let required_fee = std::cmp::max(
+            package_fee.saturating_sub(parent_fee),
+            Fee::calculate_for_weight(fee_per_gram, child_weight),
+        );
+        let child_fee_per_gram = MicroTari::from((required_fee.as_u64() + child_weight - 1) / child_weight);
+        let fee = Fee::calculate_for_weight(child_fee_per_gram, child_weight);
+        if total <= fee {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
+        let amount = total - fee;
+
+        let offset = self.next_transaction_offset().await?;
+        let sender_offset_private_key = PrivateKey::random(&mut OsRng);
+
+        let mut builder = SenderTransactionProtocol::builder(0);
+        self.with_sender_nonce(&mut builder);
+        builder
+            .with_lock_height(0)
+            .with_fee_per_gram(child_fee_per_gram)
+            .with_transaction_weight(weighting)
+            .with_offset(offset)
+            .with_message(format!("Child pays for parent transaction {}", parent_tx_id));
+
+        for uo in &inputs {
+            builder.with_input(
+                uo.unblinded_output
+                    .as_transaction_input(&self.resources.factories.commitment)?,
+                uo.unblinded_output.clone(),
+            );
+        }
+
+        let (spending_key, script_private_key) = self
+            .resources
+            .master_key_manager
+            .get_next_spend_and_script_key()
+            .await?;
+        let metadata_signature = TransactionOutput::create_final_metadata_signature(
+            &amount,
+            &spending_key,
+            &script,
+            &output_features,
+            &Covenant::default(),
+            &sender_offset_private_key,
+        )?;
+        let utxo = DbUnblindedOutput::from_unblinded_output(
+            UnblindedOutput::new(
+                amount,
+                spending_key,
+                Some(output_features),
+                script,
+                inputs!(PublicKey::from_secret_key(&script_private_key)),
+                script_private_key,
+                PublicKey::from_secret_key(&sender_offset_private_key),
+                metadata_signature,
+                Covenant::default(),
+            ),
+            &self.resources.factories,
+        )?;
+        builder
+            .with_output(utxo.unblinded_output.clone(), sender_offset_private_key)
+            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+
+        let mut stp = builder
+            .build::<HashDigest>(&self.resources.factories)
+            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+
+        let tx_id = stp.get_tx_id()?;
+        debug!(
+            target: LOG_TARGET,
+            "Encumber child-pays-for-parent transaction ({}) spending outputs of parent transaction ({}).",
+            tx_id,
+            parent_tx_id
+        );
+        // The parent's outputs are re-encumbered to be spent by the child, so they become spent once the child is mined
+        self.resources.db.encumber_outputs(tx_id, inputs, vec![utxo]).await?;
+        self.confirm_encumberance(tx_id).await?;
+        let fee = stp.get_fee_amount()?;
+        stp.finalize(KernelFeatures::empty(), &self.resources.factories)?;
+        let tx = stp.take_transaction()?;
+
+        Ok((tx_id, fee, amount, tx))
+    }
+
     /// Confirm that a transaction has finished being negotiated between parties so the short-term encumberance can be
     /// made official
     async fn confirm_encumberance(&mut self, tx_id: u64) -> Result<(), OutputManagerError> {
@@ -888,12 +1370,91 @@ where TBackend: OutputManagerBackend + 'static
     }
 
     /// Cancel a pending transaction and place the encumbered outputs back into the unspent pool
-    pub async fn cancel_transaction(&mut self, tx_id: u64) -> Result<(), OutputManagerError> {
+    pub async fn cancel_transaction(&mut self, tx_id: u64) -> Result<Vec<TxId>, OutputManagerError> {
         debug!(
             target: LOG_TARGET,
             "Cancelling pending transaction outputs for TxId: {}", tx_id
         );
-        Ok(self.resources.db.cancel_pending_transaction_outputs(tx_id).await?)
+        let descendants = self.resources.db.cancel_pending_transaction_outputs(tx_id).await?;
+        for descendant in descendants.iter() {
+            debug!(
+                target: LOG_TARGET,
+                "Cancelled pending transaction outputs for TxId: {} which spent unconfirmed change from TxId: {}",
+                descendant,
+                tx_id
+            );
+            let _ = self.output_leases.remove(descendant);
+        }
+        Ok(descendants)
+    }
+
+    /// Reserve unspent outputs for an external party. The outputs are short term encumbered under the lease id so
+    /// that they cannot be selected for any other transaction, and so that the lease does not survive a restart.
+    async fn reserve_outputs(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        duration: Duration,
+    ) -> Result<OutputLease, OutputManagerError> {
+        // Make the outputs of any expired lease available for selection first
+        self.release_expired_output_leases().await?;
+
+        let expires_at = Utc::now().naive_utc() +
+            ChronoDuration::from_std(duration).map_err(|e| OutputManagerError::ConversionError(e.to_string()))?;
+        let (outputs, _, _) = self
+            .select_utxos(DEFAULT_ACCOUNT, amount, fee_per_gram, 1, None)
+            .await?;
+
+        let lease_id = OsRng.next_u64();
+        self.resources
+            .db
+            .encumber_outputs(lease_id, outputs.clone(), Vec::new())
+            .await?;
+
+        let lease = OutputLease {
+            lease_id,
+            outputs: outputs.into_iter().map(|o| o.unblinded_output).collect(),
+            expires_at,
+        };
+        debug!(
+            target: LOG_TARGET,
+            "Reserved {} outputs under lease {} until {}",
+            lease.outputs.len(),
+            lease_id,
+            expires_at
+        );
+        self.output_leases.insert(lease_id, lease.clone());
+
+        Ok(lease)
+    }
+
+    async fn release_output_lease(&mut self, lease_id: LeaseId) -> Result<(), OutputManagerError> {
+        if self.output_leases.remove(&lease_id).is_none() {
+            return Err(OutputManagerError::OutputLeaseNotFound(lease_id));
+        }
+        debug!(target: LOG_TARGET, "Releasing output lease {}", lease_id);
+        self.resources.db.cancel_pending_transaction_outputs(lease_id).await?;
+        Ok(())
+    }
+
+    async fn release_expired_output_leases(&mut self) -> Result<(), OutputManagerError> {
+        let now = Utc::now().naive_utc();
+        let expired = self
+            .output_leases
+            .values()
+            .filter(|lease| lease.expires_at <= now)
+            .map(|lease| lease.lease_id)
+            .collect::<Vec<_>>();
+
+        for lease_id in expired {
+            self.release_output_lease(lease_id).await?;
+            let _ = self
+                .resources
+                .event_publisher
+                .send(Arc::new(OutputManagerEvent::OutputLeaseExpired(lease_id)));
+        }
+
+        Ok(())
     }
 
     /// Go through the pending transaction and if any have existed longer than the specified duration, cancel them
@@ -901,10 +1462,54 @@ where TBackend: OutputManagerBackend + 'static
         Ok(self.resources.db.timeout_pending_transaction_outputs(period).await?)
     }
 
-    /// Select which unspent transaction outputs to use to send a transaction of the specified amount. Use the specified
-    /// selection strategy to choose the outputs. It also determines if a change output is required.
+    /// Select which of the given account's unspent transaction outputs to use to send a transaction of the specified
+    /// amount. Use the specified selection strategy to choose the outputs. It also determines if a change output is
+    /// required.
+    /// The change outputs of the wallet's own pending transactions that the configured `UnconfirmedChangePolicy`
+    /// allows to be spent, ordered by the depth of their chain of unconfirmed ancestors and then by value
+    async fn fetch_spendable_unconfirmed_change(
+        &self,
+        account: &str,
+    ) -> Result<Vec<DbUnblindedOutput>, OutputManagerError> {
+        let max_depth = match self.resources.config.unconfirmed_change_policy.max_depth() {
+            Some(max_depth) => max_depth,
+            None => return Ok(Vec::new()),
+        };
+        let pending_txs = self.resources.db.fetch_all_pending_transaction_outputs().await?;
+        let parents = self.resources.db.fetch_unconfirmed_parents().await?;
+
+        let mut depths = HashMap::new();
+        let mut change = Vec::new();
+        for (tx_id, pending_tx) in pending_txs.iter() {
+            // Only outputs of transactions we are spending from are our change, never those of incoming payments or
+            // coinbases
+            if pending_tx.coinbase_block_height.is_some() || pending_tx.outputs_to_be_spent.is_empty() {
+                continue;
+            }
+            let depth = unconfirmed_depth(*tx_id, &parents, &mut depths);
+            if depth > max_depth {
+                continue;
+            }
+            change.extend(
+                pending_tx
+                    .outputs_to_be_received
+                    .iter()
+                    .filter(|o| o.account == account)
+                    .map(|o| (depth, o.clone())),
+            );
+        }
+        change.sort_by(|(depth_a, a), (depth_b, b)| {
+            depth_a
+                .cmp(depth_b)
+                .then(a.unblinded_output.value.cmp(&b.unblinded_output.value))
+        });
+
+        Ok(change.into_iter().map(|(_, o)| o).collect())
+    }
+
     async fn select_utxos(
         &mut self,
+        account: &str,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         output_count: usize,
@@ -923,7 +1528,14 @@ where TBackend: OutputManagerBackend + 'static
         let mut fee_without_change = MicroTari::from(0);
         let mut fee_with_change = MicroTari::from(0);
 
-        let uo = self.resources.db.fetch_sorted_unspent_outputs().await?;
+        let uo = self
+            .resources
+            .db
+            .fetch_sorted_unspent_outputs()
+            .await?
+            .into_iter()
+            .filter(|u| u.account == account)
+            .collect::<Vec<_>>();
 
         // Attempt to get the chain tip height
         let chain_metadata = self.base_node_service.get_chain_metadata().await?;
@@ -995,7 +1607,21 @@ where TBackend: OutputManagerBackend + 'static
             },
             UTXOSelectionStrategy::Largest => uo.into_iter().rev().collect(),
         };
-        trace!(target: LOG_TARGET, "We found {} UTXOs to select from", uo.len());
+
+        // Unconfirmed change is only selected once all the confirmed outputs have been used
+        let num_confirmed_utxos = uo.len();
+        let unconfirmed_change = self
+            .fetch_spendable_unconfirmed_change(account)
+            .await?
+            .into_iter()
+            .filter(|u| !connected || u.unblinded_output.features.maturity <= tip_height);
+        let uo = uo.into_iter().chain(unconfirmed_change).collect::<Vec<_>>();
+        trace!(
+            target: LOG_TARGET,
+            "We found {} UTXOs to select from, {} of which are unconfirmed change",
+            uo.len(),
+            uo.len() - num_confirmed_utxos
+        );
 
         let mut require_change_output = false;
         for o in uo.iter() {
@@ -1020,16 +1646,70 @@ where TBackend: OutputManagerBackend + 'static
             let current_chain_tip = chain_metadata.map(|cm| cm.height_of_longest_chain());
             let balance = self.get_balance(current_chain_tip).await?;
             let pending_incoming = balance.pending_incoming_balance;
-            if utxos_total_value + pending_incoming >= amount + fee_with_change {
+            // Any unconfirmed change that was selected is already part of the pending incoming balance
+            let confirmed_total_value = utxos
+                .iter()
+                .take(num_confirmed_utxos)
+                .map(|o| o.unblinded_output.value)
+                .sum::<MicroTari>();
+            if confirmed_total_value + pending_incoming >= amount + fee_with_change {
                 return Err(OutputManagerError::FundsPending);
             } else {
                 return Err(OutputManagerError::NotEnoughFunds);
             }
         }
 
+        let num_outputs = if require_change_output {
+            output_count + 1
+        } else {
+            output_count
+        };
+        self.check_transaction_weight(self.resources.consensus_constants.transaction_weight().calculate(
+            1,
+            utxos.len(),
+            num_outputs,
+            0,
+        ))?;
+
         Ok((utxos, require_change_output, utxos_total_value))
     }
 
+    /// The offset for a transaction that is being sent. With deterministic nonces the offset comes from the key manager
+    /// instead of the OS RNG, because the sender's nonce is derived from it and must not repeat across transactions.
+    async fn next_transaction_offset(&self) -> Result<PrivateKey, OutputManagerError> {
+        if self.resources.config.deterministic_nonces {
+            self.resources.master_key_manager.get_next_transaction_offset().await
+        } else {
+            Ok(PrivateKey::random(&mut OsRng))
+        }
+    }
+
+    /// Chooses the sender's nonce according to the `deterministic_nonces` setting
+    fn with_sender_nonce(&self, builder: &mut SenderTransactionInitializer) {
+        if self.resources.config.deterministic_nonces {
+            builder.with_deterministic_nonce();
+        } else {
+            builder.with_private_nonce(PrivateKey::random(&mut OsRng));
+        }
+    }
+
+    /// Refuses transactions that would be too heavy to fit in a block alongside the coinbase. Base nodes reject these
+    /// transactions, so they would otherwise only fail after they have been broadcast.
+    fn check_transaction_weight(&self, weight: u64) -> Result<(), OutputManagerError> {
+        let max_weight = self
+            .resources
+            .consensus_constants
+            .get_max_block_weight_excluding_coinbase();
+        if weight > max_weight {
+            warn!(
+                target: LOG_TARGET,
+                "Refusing to create a transaction with weight {} (max: {})", weight, max_weight
+            );
+            return Err(OutputManagerError::TransactionTooLarge { weight, max_weight });
+        }
+        Ok(())
+    }
+
     /// Set the base node public key to the list that will be used to check the status of UTXO's on the base chain. If
     /// this is the first time the base node public key is set do the UTXO queries.
     async fn set_base_node_public_key(
@@ -1087,6 +1767,7 @@ where TBackend: OutputManagerBackend + 'static
         let total_split_amount = amount_per_split * split_count as u64;
         let (inputs, require_change_output, utxos_total_value) = self
             .select_utxos(
+                DEFAULT_ACCOUNT,
                 total_split_amount,
                 fee_per_gram,
                 output_count,
@@ -1100,15 +1781,15 @@ where TBackend: OutputManagerBackend + 'static
         let fee = Fee::calculate(fee_per_gram, 1, input_count, output_count);
 
         trace!(target: LOG_TARGET, "Construct coin split transaction.");
-        let offset = PrivateKey::random(&mut OsRng);
-        let nonce = PrivateKey::random(&mut OsRng);
+        let offset = self.next_transaction_offset().await?;
 
         let mut builder = SenderTransactionProtocol::builder(0);
+        self.with_sender_nonce(&mut builder);
         builder
             .with_lock_height(lock_height.unwrap_or(0))
             .with_fee_per_gram(fee_per_gram)
-            .with_offset(offset.clone())
-            .with_private_nonce(nonce.clone())
+            .with_transaction_weight(*self.resources.consensus_constants.transaction_weight())
+            .with_offset(offset)
             .with_rewindable_outputs(self.resources.master_key_manager.rewind_data().clone());
 
         trace!(target: LOG_TARGET, "Add inputs to coin split transaction.");
@@ -1148,6 +1829,7 @@ where TBackend: OutputManagerBackend + 'static
                 &spending_key.clone(),
                 &script,
                 &output_features,
+                &Covenant::default(),
                 &sender_offset_private_key,
             )?;
             let utxo = DbUnblindedOutput::from_unblinded_output(
@@ -1160,6 +1842,7 @@ where TBackend: OutputManagerBackend + 'static
                     script_private_key,
                     sender_offset_public_key,
                     metadata_signature,
+                    Covenant::default(),
                 ),
                 &self.resources.factories,
             )?;
@@ -1206,42 +1889,72 @@ where TBackend: OutputManagerBackend + 'static
     }
 
     /// Attempt to scan and then rewind all of the given transaction outputs into unblinded outputs based on known
-    /// pubkeys
+    /// pubkeys. Each output is returned with the sender's message if one was encrypted with its value.
     async fn scan_outputs_for_one_sided_payments(
         &mut self,
         outputs: Vec<TransactionOutput>,
-    ) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+    ) -> Result<Vec<(UnblindedOutput, Option<String>)>, OutputManagerError> {
         let known_one_sided_payment_scripts: Vec<KnownOneSidedPaymentScript> =
             self.resources.db.get_all_known_one_sided_payment_scripts().await?;
 
-        let mut rewound_outputs: Vec<UnblindedOutput> = Vec::new();
+        let mut rewound_outputs = Vec::new();
         for output in outputs {
+            // A reclaimable payment is locked with a script that pays to the same key as a plain one-sided payment
+            // until its lock height
+            let recipient_script = ReclaimableOneSidedScript::from_script(&output.script)
+                .map(|s| s.recipient_script())
+                .unwrap_or_else(|| output.script.clone());
             let position = known_one_sided_payment_scripts
                 .iter()
-                .position(|known_one_sided_script| known_one_sided_script.script == output.script);
+                .position(|known_one_sided_script| known_one_sided_script.script == recipient_script);
             if let Some(i) = position {
-                let spending_key = PrivateKey::from_bytes(
-                    CommsPublicKey::shared_secret(
-                        &known_one_sided_payment_scripts[i].private_key,
-                        &output.sender_offset_public_key,
-                    )
-                    .as_bytes(),
-                )?;
-                let rewind_key = PrivateKey::from_bytes(&hash_secret_key(&spending_key))?;
-                let blinding_key = PrivateKey::from_bytes(&hash_secret_key(&rewind_key))?;
-                let rewound =
-                    output.full_rewind_range_proof(&self.resources.factories.range_proof, &rewind_key, &blinding_key);
-
-                if let Ok(rewound_result) = rewound {
+                let shared_secret = CommsPublicKey::shared_secret(
+                    &known_one_sided_payment_scripts[i].private_key,
+                    &output.sender_offset_public_key,
+                );
+                let spending_key = PrivateKey::from_bytes(shared_secret.as_bytes())?;
+
+                // Outputs that carry encrypted data are recovered from it, older outputs by rewinding the range proof
+                let recovered = match output
+                    .features
+                    .encrypted_data
+                    .decrypt(&shared_secret, &output.commitment)
+                {
+                    Ok((value, message)) => {
+                        let commitment = self
+                            .resources
+                            .factories
+                            .commitment
+                            .commit_value(&spending_key, value.into());
+                        if commitment == output.commitment {
+                            let message =
+                                Some(String::from_utf8_lossy(&message).into_owned()).filter(|m| !m.is_empty());
+                            Some((value, spending_key, message))
+                        } else {
+                            None
+                        }
+                    },
+                    Err(_) => {
+                        let rewind_key = PrivateKey::from_bytes(&hash_secret_key(&spending_key))?;
+                        let blinding_key = PrivateKey::from_bytes(&hash_secret_key(&rewind_key))?;
+                        output
+                            .full_rewind_range_proof(&self.resources.factories.range_proof, &rewind_key, &blinding_key)
+                            .ok()
+                            .map(|rewound| (rewound.committed_value, rewound.blinding_factor, None))
+                    },
+                };
+
+                if let Some((value, blinding_factor, message)) = recovered {
                     let rewound_output = UnblindedOutput::new(
-                        rewound_result.committed_value,
-                        rewound_result.blinding_factor.clone(),
+                        value,
+                        blinding_factor,
                         Some(output.features),
-                        known_one_sided_payment_scripts[i].script.clone(),
+                        output.script,
                         known_one_sided_payment_scripts[i].input.clone(),
                         known_one_sided_payment_scripts[i].private_key.clone(),
                         output.sender_offset_public_key,
                         output.metadata_signature,
+                        output.covenant,
                     );
                     let db_output =
                         DbUnblindedOutput::from_unblinded_output(rewound_output.clone(), &self.resources.factories)?;
@@ -1249,7 +1962,7 @@ where TBackend: OutputManagerBackend + 'static
                     let output_hex = output.commitment.to_hex();
                     match self.resources.db.add_unspent_output(db_output).await {
                         Ok(_) => {
-                            rewound_outputs.push(rewound_output);
+                            rewound_outputs.push((rewound_output, message));
                         },
                         Err(OutputManagerStorageError::DuplicateOutput) => {
                             warn!(
@@ -1266,7 +1979,7 @@ where TBackend: OutputManagerBackend + 'static
                         target: LOG_TARGET,
                         "One-sided payment Output {} with value {} recovered",
                         output_hex,
-                        rewound_result.committed_value,
+                        value,
                     );
                 }
             }
@@ -1276,6 +1989,30 @@ where TBackend: OutputManagerBackend + 'static
     }
 }
 
+/// Refuse to create outputs with scripts that base nodes would not relay or that could never be spent
+fn check_script_is_standard(script: &TariScript) -> Result<(), OutputManagerError> {
+    analyze_script(script)
+        .map_err(|e| OutputManagerError::NonStandardScript(e.to_string()))?
+        .check_standard()
+        .map_err(|e| OutputManagerError::NonStandardScript(e.to_string()))
+}
+
+/// The number of unconfirmed transactions in the longest chain of pending transactions ending in `tx_id`, where each
+/// transaction spends the unconfirmed change of its parent. A transaction that only spends confirmed outputs has a
+/// depth of 1.
+fn unconfirmed_depth(tx_id: TxId, parents: &HashMap<TxId, Vec<TxId>>, depths: &mut HashMap<TxId, usize>) -> usize {
+    if let Some(depth) = depths.get(&tx_id) {
+        return *depth;
+    }
+    let mut max_parent_depth = 0;
+    for parent in parents.get(&tx_id).into_iter().flatten() {
+        max_parent_depth = max_parent_depth.max(unconfirmed_depth(*parent, parents, depths));
+    }
+    let depth = max_parent_depth + 1;
+    depths.insert(tx_id, depth);
+    depth
+}
+
 /// Different UTXO selection strategies for choosing which UTXO's are used to fulfill a transaction
 /// TODO Investigate and implement more optimal strategies
 #[derive(Debug)]
@@ -20,11 +20,14 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{
-    error::OutputManagerStorageError,
-    service::Balance,
-    storage::models::{DbUnblindedOutput, KnownOneSidedPaymentScript},
-    TxId,
+use crate::{
+    key_manager_service::storage::database::KeyManagerBackend,
+    output_manager_service::{
+        error::OutputManagerStorageError,
+        service::Balance,
+        storage::models::{DbUnblindedOutput, KnownOneSidedPaymentScript, DEFAULT_ACCOUNT},
+        TxId,
+    },
 };
 use aes_gcm::Aes256Gcm;
 use chrono::{NaiveDateTime, Utc};
@@ -32,6 +35,7 @@ use log::*;
 use std::{
     collections::HashMap,
     fmt::{Display, Error, Formatter},
+    str::FromStr,
     sync::Arc,
     time::Duration,
 };
@@ -46,8 +50,9 @@ const LOG_TARGET: &str = "wallet::output_manager_service::database";
 /// This trait defines the required behaviour that a storage backend must provide for the Output Manager service.
 /// Data is passed to and from the backend via the [DbKey], [DbValue], and [DbValueKey] enums. If new data types are
 /// required to be supported by the backends then these enums can be updated to reflect this requirement and the trait
-/// will remain the same
-pub trait OutputManagerBackend: Send + Sync + Clone {
+/// will remain the same. The key indexes are handed out through the [KeyManagerBackend] which shares the key manager
+/// state with this backend.
+pub trait OutputManagerBackend: KeyManagerBackend + Send + Sync + Clone {
     /// Retrieve the record associated with the provided DbKey
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, OutputManagerStorageError>;
     /// Modify the state the of the backend with a write operation
@@ -73,8 +78,12 @@ pub trait OutputManagerBackend: Send + Sync + Clone {
     fn clear_short_term_encumberances(&self) -> Result<(), OutputManagerStorageError>;
     /// This method must take all the `outputs_to_be_spent` from the specified transaction and move them back into the
     /// `UnspentOutputs` pool. The `outputs_to_be_received`'` will be marked as cancelled inbound outputs in case they
-    /// need to be recovered.
-    fn cancel_pending_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
+    /// need to be recovered. Any pending transactions spending the unconfirmed change of this transaction must be
+    /// cancelled first, and their TxIds returned.
+    fn cancel_pending_transaction(&self, tx_id: TxId) -> Result<Vec<TxId>, OutputManagerStorageError>;
+    /// Fetch, for every pending transaction that spends unconfirmed change, the pending transactions that the change
+    /// is received in
+    fn fetch_unconfirmed_parents(&self) -> Result<HashMap<TxId, Vec<TxId>>, OutputManagerStorageError>;
     /// This method must run through all the `PendingTransactionOutputs` and test if any have existed for longer that
     /// the specified duration. If they have they should be cancelled.
     fn timeout_pending_transactions(&self, period: Duration) -> Result<(), OutputManagerStorageError>;
@@ -102,6 +111,52 @@ pub trait OutputManagerBackend: Send + Sync + Clone {
         &self,
         commitment: &Commitment,
     ) -> Result<DbUnblindedOutput, OutputManagerStorageError>;
+    /// Turn the sent output of a reclaimable one-sided payment back into an unspent output of this wallet
+    fn reclaim_one_sided_output(&self, tx_id: TxId) -> Result<DbUnblindedOutput, OutputManagerStorageError>;
+    /// Fetch up to `limit` unspent outputs matching `filter`, in the order they were added, starting after `cursor`
+    fn fetch_unspent_outputs_page(
+        &self,
+        filter: &UnspentOutputFilter,
+        cursor: Option<OutputCursor>,
+        limit: usize,
+    ) -> Result<UnspentOutputPage, OutputManagerStorageError>;
+}
+
+/// Restricts the unspent outputs returned in a page
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnspentOutputFilter {
+    pub min_value: Option<MicroTari>,
+    pub max_value: Option<MicroTari>,
+}
+
+/// The position after the last output of a page. Outputs are ordered by when they were added to the wallet, so a
+/// cursor remains valid as new outputs are added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputCursor(pub u64);
+
+impl Display for OutputCursor {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for OutputCursor {
+    type Err = OutputManagerStorageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u64>()
+            .map(OutputCursor)
+            .map_err(|_| OutputManagerStorageError::ConversionError)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnspentOutputPage {
+    pub outputs: Vec<DbUnblindedOutput>,
+    /// The number of unspent outputs matching the filter across all pages
+    pub total_count: u64,
+    /// The cursor to fetch the next page with, or `None` if this is the last page
+    pub next_cursor: Option<OutputCursor>,
 }
 
 /// Holds the outputs that have been selected for a given pending transaction waiting for confirmation
@@ -122,6 +177,13 @@ pub struct KeyManagerState {
     pub primary_key_index: u64,
 }
 
+/// A named key manager account. Each account derives its keys from its own branch of the master key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyManagerAccount {
+    pub name: String,
+    pub key_index: u64,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DbKey {
     SpentOutput(BlindingFactor),
@@ -133,8 +195,10 @@ pub enum DbKey {
     SpentOutputs,
     AllPendingTransactionOutputs,
     KeyManagerState,
+    KeyManagerAccounts,
     InvalidOutputs,
     KnownOneSidedPaymentScripts,
+    SentOneSidedOutput(TxId),
 }
 
 #[derive(Debug)]
@@ -147,16 +211,22 @@ pub enum DbValue {
     InvalidOutputs(Vec<DbUnblindedOutput>),
     AllPendingTransactionOutputs(HashMap<TxId, PendingTransactionOutputs>),
     KeyManagerState(KeyManagerState),
+    KeyManagerAccounts(Vec<KeyManagerAccount>),
     KnownOneSidedPaymentScripts(Vec<KnownOneSidedPaymentScript>),
     AnyOutput(Box<DbUnblindedOutput>),
+    SentOneSidedOutput(Box<DbUnblindedOutput>),
 }
 
 pub enum DbKeyValuePair {
     SpentOutput(Commitment, Box<DbUnblindedOutput>),
     UnspentOutput(Commitment, Box<DbUnblindedOutput>),
     UnspentOutputWithTxId(Commitment, (TxId, Box<DbUnblindedOutput>)),
+    /// The output of a reclaimable one-sided payment sent in the given transaction
+    SentOneSidedOutput(Commitment, (TxId, Box<DbUnblindedOutput>)),
     PendingTransactionOutputs(TxId, Box<PendingTransactionOutputs>),
     KeyManagerState(KeyManagerState),
+    /// Inserts the account, or updates its key index if it already exists
+    KeyManagerAccount(KeyManagerAccount),
     KnownOneSidedPaymentScripts(KnownOneSidedPaymentScript),
 }
 
@@ -218,6 +288,30 @@ where T: OutputManagerBackend + 'static
         Ok(())
     }
 
+    pub async fn get_key_manager_accounts(&self) -> Result<Vec<KeyManagerAccount>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::KeyManagerAccounts) {
+            Ok(None) => Ok(Vec::new()),
+            Ok(Some(DbValue::KeyManagerAccounts(accounts))) => Ok(accounts),
+            Ok(Some(other)) => unexpected_result(DbKey::KeyManagerAccounts, other),
+            Err(e) => log_error(DbKey::KeyManagerAccounts, e),
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))
+        .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn set_key_manager_account(&self, account: KeyManagerAccount) -> Result<(), OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::KeyManagerAccount(account)))
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+
+        Ok(())
+    }
+
     pub async fn increment_key_index(&self) -> Result<(), OutputManagerStorageError> {
         let db_clone = self.db.clone();
         tokio::task::spawn_blocking(move || db_clone.increment_key_index())
@@ -266,7 +360,54 @@ where T: OutputManagerBackend + 'static
         Ok(())
     }
 
+    /// Records the output of a reclaimable one-sided payment sent in `tx_id`, which the sender can spend once the
+    /// payment's lock height has passed
+    pub async fn add_sent_one_sided_output(
+        &self,
+        tx_id: TxId,
+        output: DbUnblindedOutput,
+    ) -> Result<(), OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::SentOneSidedOutput(
+                output.commitment.clone(),
+                (tx_id, Box::new(output)),
+            )))
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+
+        Ok(())
+    }
+
+    pub async fn fetch_sent_one_sided_output(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<DbUnblindedOutput>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::SentOneSidedOutput(tx_id)) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::SentOneSidedOutput(o))) => Ok(Some(*o)),
+            Ok(Some(other)) => unexpected_result(DbKey::SentOneSidedOutput(tx_id), other),
+            Err(e) => log_error(DbKey::SentOneSidedOutput(tx_id), e),
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    /// Returns the balance of the default account
     pub async fn get_balance(&self, current_chain_tip: Option<u64>) -> Result<Balance, OutputManagerStorageError> {
+        self.get_account_balance(DEFAULT_ACCOUNT.to_string(), current_chain_tip)
+            .await
+    }
+
+    /// Returns the balance of the outputs that belong to the given key manager account
+    pub async fn get_account_balance(
+        &self,
+        account: String,
+        current_chain_tip: Option<u64>,
+    ) -> Result<Balance, OutputManagerStorageError> {
+        let in_account = |o: &&DbUnblindedOutput| o.account == account;
         let db_clone = self.db.clone();
         let db_clone2 = self.db.clone();
         let db_clone3 = self.db.clone();
@@ -293,6 +434,7 @@ where T: OutputManagerBackend + 'static
             if let DbValue::AllPendingTransactionOutputs(pto) = pending_txs {
                 let available_balance = uo
                     .iter()
+                    .filter(in_account)
                     .fold(MicroTari::from(0), |acc, x| acc + x.unblinded_output.value);
                 let time_locked_balance = if let Some(tip) = current_chain_tip {
                     let time_locked_outputs = tokio::task::spawn_blocking(move || {
@@ -308,6 +450,7 @@ where T: OutputManagerBackend + 'static
                         Some(
                             time_locked_uo
                                 .iter()
+                                .filter(in_account)
                                 .fold(MicroTari::from(0), |acc, x| acc + x.unblinded_output.value),
                         )
                     } else {
@@ -323,10 +466,12 @@ where T: OutputManagerBackend + 'static
                     pending_incoming += v
                         .outputs_to_be_received
                         .iter()
+                        .filter(in_account)
                         .fold(MicroTari::from(0), |acc, x| acc + x.unblinded_output.value);
                     pending_outgoing += v
                         .outputs_to_be_spent
                         .iter()
+                        .filter(in_account)
                         .fold(MicroTari::from(0), |acc, x| acc + x.unblinded_output.value);
                 }
 
@@ -448,8 +593,12 @@ where T: OutputManagerBackend + 'static
     }
 
     /// When a pending transaction is cancelled the encumbered outputs are moved back to the `unspent_outputs`
-    /// collection.
-    pub async fn cancel_pending_transaction_outputs(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
+    /// collection. Pending transactions that spent its unconfirmed change are cancelled along with it, and their TxIds
+    /// are returned.
+    pub async fn cancel_pending_transaction_outputs(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Vec<TxId>, OutputManagerStorageError> {
         let db_clone = self.db.clone();
         tokio::task::spawn_blocking(move || db_clone.cancel_pending_transaction(tx_id))
             .await
@@ -634,6 +783,35 @@ where T: OutputManagerBackend + 'static
             .and_then(|inner_result| inner_result)
     }
 
+    pub async fn reclaim_one_sided_output(&self, tx_id: TxId) -> Result<DbUnblindedOutput, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.reclaim_one_sided_output(tx_id))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn fetch_unspent_outputs_page(
+        &self,
+        filter: UnspentOutputFilter,
+        cursor: Option<OutputCursor>,
+        limit: usize,
+    ) -> Result<UnspentOutputPage, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.fetch_unspent_outputs_page(&filter, cursor, limit))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn fetch_unconfirmed_parents(&self) -> Result<HashMap<TxId, Vec<TxId>>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.fetch_unconfirmed_parents())
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))
+            .and_then(|inner_result| inner_result)
+    }
+
     pub async fn cancel_pending_transaction_at_block_height(
         &self,
         block_height: u64,
@@ -733,10 +911,12 @@ impl Display for DbKey {
             DbKey::SpentOutputs => f.write_str(&"Spent Outputs Key".to_string()),
             DbKey::AllPendingTransactionOutputs => f.write_str(&"All Pending Transaction Outputs".to_string()),
             DbKey::KeyManagerState => f.write_str(&"Key Manager State".to_string()),
+            DbKey::KeyManagerAccounts => f.write_str(&"Key Manager Accounts".to_string()),
             DbKey::InvalidOutputs => f.write_str(&"Invalid Outputs Key"),
             DbKey::TimeLockedUnspentOutputs(_t) => f.write_str(&"Timelocked Outputs"),
             DbKey::KnownOneSidedPaymentScripts => f.write_str(&"Known claiming scripts"),
             DbKey::AnyOutputByCommitment(_) => f.write_str(&"AnyOutputByCommitment"),
+            DbKey::SentOneSidedOutput(tx_id) => f.write_str(&format!("Sent One-sided Output TX_ID: {}", tx_id)),
         }
     }
 }
@@ -751,9 +931,11 @@ impl Display for DbValue {
             DbValue::SpentOutputs(_) => f.write_str("Spent Outputs"),
             DbValue::AllPendingTransactionOutputs(_) => f.write_str("All Pending Transaction Outputs"),
             DbValue::KeyManagerState(_) => f.write_str("Key Manager State"),
+            DbValue::KeyManagerAccounts(_) => f.write_str("Key Manager Accounts"),
             DbValue::InvalidOutputs(_) => f.write_str("Invalid Outputs"),
             DbValue::KnownOneSidedPaymentScripts(_) => f.write_str(&"Known claiming scripts"),
             DbValue::AnyOutput(_) => f.write_str(&"Any Output"),
+            DbValue::SentOneSidedOutput(_) => f.write_str(&"Sent One-sided Output"),
         }
     }
 }
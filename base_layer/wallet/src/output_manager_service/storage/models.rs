@@ -32,11 +32,16 @@ use tari_core::{
 };
 use tari_crypto::script::{ExecutionStack, TariScript};
 
+/// The name of the account that outputs belong to unless another account is specified
+pub const DEFAULT_ACCOUNT: &str = "";
+
 #[derive(Debug, Clone)]
 pub struct DbUnblindedOutput {
     pub commitment: Commitment,
     pub unblinded_output: UnblindedOutput,
     pub hash: HashOutput,
+    /// The key manager account that the output's keys were derived from
+    pub account: String,
 }
 
 impl DbUnblindedOutput {
@@ -49,6 +54,7 @@ impl DbUnblindedOutput {
             hash: tx_out.hash(),
             commitment: tx_out.commitment,
             unblinded_output: output,
+            account: DEFAULT_ACCOUNT.to_string(),
         })
     }
 
@@ -62,8 +68,14 @@ impl DbUnblindedOutput {
             hash: tx_out.hash(),
             commitment: tx_out.commitment,
             unblinded_output: output,
+            account: DEFAULT_ACCOUNT.to_string(),
         })
     }
+
+    pub fn with_account(mut self, account: String) -> Self {
+        self.account = account;
+        self
+    }
 }
 
 impl From<DbUnblindedOutput> for UnblindedOutput {
@@ -21,6 +21,12 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    key_manager_service::{
+        error::KeyManagerStorageError,
+        storage::database::{KeyIndexReservation, KeyIndexStatus, KeyManagerBackend, KeyManagerBranch},
+        ACCOUNT_BRANCH_PREFIX,
+        PRIMARY_BRANCH,
+    },
     output_manager_service::{
         error::OutputManagerStorageError,
         storage::{
@@ -28,22 +34,34 @@ use crate::{
                 DbKey,
                 DbKeyValuePair,
                 DbValue,
+                KeyManagerAccount,
                 KeyManagerState,
+                OutputCursor,
                 OutputManagerBackend,
                 PendingTransactionOutputs,
+                UnspentOutputFilter,
+                UnspentOutputPage,
                 WriteOperation,
             },
             models::{DbUnblindedOutput, KnownOneSidedPaymentScript},
         },
         TxId,
     },
-    schema::{key_manager_states, known_one_sided_payment_scripts, outputs, pending_transaction_outputs},
+    schema::{
+        key_index_reservations,
+        key_manager_accounts,
+        key_manager_branches,
+        key_manager_states,
+        known_one_sided_payment_scripts,
+        outputs,
+        pending_transaction_outputs,
+    },
     storage::sqlite_utilities::WalletDbConnection,
     util::encryption::{decrypt_bytes_integral_nonce, encrypt_bytes_integral_nonce, Encryptable},
 };
 use aes_gcm::{aead::Error as AeadError, Aes256Gcm, Error};
 use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
-use diesel::{prelude::*, result::Error as DieselError, SqliteConnection};
+use diesel::{prelude::*, result::Error as DieselError, sqlite::Sqlite, SqliteConnection};
 use log::*;
 use std::{
     collections::HashMap,
@@ -55,6 +73,8 @@ use std::{
 use tari_core::{
     tari_utilities::hash::Hashable,
     transactions::{
+        covenant::Covenant,
+        encrypted_data::EncryptedData,
         tari_amount::MicroTari,
         transaction::{OutputFeatures, OutputFlags, TransactionOutput, UnblindedOutput},
         types::{ComSignature, Commitment, CryptoFactories, PrivateKey, PublicKey},
@@ -95,6 +115,14 @@ impl OutputManagerSqliteDatabase {
         Ok(())
     }
 
+    /// Cancel a pending transaction that may already have been cancelled along with one of its ancestors
+    fn cancel_pending_transaction_if_exists(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
+        match self.cancel_pending_transaction(tx_id) {
+            Ok(_) | Err(OutputManagerStorageError::ValueNotFound) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
     fn encrypt_if_necessary<T: Encryptable<Aes256Gcm>>(&self, o: &mut T) -> Result<(), OutputManagerStorageError> {
         let cipher = acquire_read_lock!(self.cipher);
         if let Some(cipher) = cipher.as_ref() {
@@ -242,6 +270,12 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     Some(DbValue::KeyManagerState(KeyManagerState::try_from(km)?))
                 },
             },
+            DbKey::KeyManagerAccounts => Some(DbValue::KeyManagerAccounts(
+                KeyManagerAccountSql::index(&(*conn))?
+                    .into_iter()
+                    .map(KeyManagerAccount::from)
+                    .collect(),
+            )),
             DbKey::InvalidOutputs => {
                 let mut outputs = OutputSql::index_status(OutputStatus::Invalid, &(*conn))?;
                 for o in outputs.iter_mut() {
@@ -255,6 +289,21 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                         .collect::<Result<Vec<_>, _>>()?,
                 ))
             },
+            DbKey::SentOneSidedOutput(tx_id) => {
+                match OutputSql::find_by_tx_id_and_status(*tx_id, OutputStatus::SentOneSided, &(*conn)) {
+                    Ok(mut o) => {
+                        self.decrypt_if_necessary(&mut o)?;
+                        Some(DbValue::SentOneSidedOutput(Box::new(DbUnblindedOutput::try_from(o)?)))
+                    },
+                    Err(e) => {
+                        match e {
+                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                            e => return Err(e),
+                        };
+                        None
+                    },
+                }
+            },
             DbKey::KnownOneSidedPaymentScripts => {
                 let mut known_one_sided_payment_scripts = KnownOneSidedPaymentScriptSql::index(&(*conn))?;
                 for script in known_one_sided_payment_scripts.iter_mut() {
@@ -304,6 +353,14 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     self.encrypt_if_necessary(&mut new_output)?;
                     new_output.commit(&(*conn))?
                 },
+                DbKeyValuePair::SentOneSidedOutput(c, (tx_id, o)) => {
+                    if OutputSql::find_by_commitment_and_cancelled(&c.to_vec(), false, &(*conn)).is_ok() {
+                        return Err(OutputManagerStorageError::DuplicateOutput);
+                    }
+                    let mut new_output = NewOutputSql::new(*o, OutputStatus::SentOneSided, Some(tx_id))?;
+                    self.encrypt_if_necessary(&mut new_output)?;
+                    new_output.commit(&(*conn))?
+                },
                 DbKeyValuePair::PendingTransactionOutputs(tx_id, p) => {
                     if PendingTransactionOutputSql::find(tx_id, &(*conn)).is_ok() {
                         return Err(OutputManagerStorageError::DuplicateTransaction);
@@ -332,6 +389,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     self.encrypt_if_necessary(&mut km_sql)?;
                     km_sql.set_state(&(*conn))?
                 },
+                DbKeyValuePair::KeyManagerAccount(account) => KeyManagerAccountSql::from(account).upsert(&(*conn))?,
                 DbKeyValuePair::KnownOneSidedPaymentScripts(script) => {
                     let mut script_sql = KnownOneSidedPaymentScriptSql::from(script);
                     self.encrypt_if_necessary(&mut script_sql)?;
@@ -406,9 +464,11 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 DbKey::SpentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::AllPendingTransactionOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::KeyManagerState => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::KeyManagerAccounts => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::InvalidOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::TimeLockedUnspentOutputs(_) => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::KnownOneSidedPaymentScripts => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::SentOneSidedOutput(_) => return Err(OutputManagerStorageError::OperationNotSupported),
             },
         }
 
@@ -450,6 +510,11 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     }
                 }
 
+                // Change from this transaction that is already being spent by another transaction is now confirmed
+                for o in OutputSql::find_by_received_in_tx_id(tx_id, &(*conn))? {
+                    o.update_received_in_tx_id(None, &(*conn))?;
+                }
+
                 p.delete(&(*conn))?;
             },
             Err(e) => {
@@ -483,7 +548,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         PendingTransactionOutputSql::new(tx_id, true, Utc::now().naive_utc(), None).commit(&(*conn))?;
 
         for o in outputs_to_be_spent {
-            o.update(
+            let updated = o.update(
                 UpdateOutput {
                     status: Some(OutputStatus::EncumberedToBeSpent),
                     tx_id: Some(tx_id),
@@ -494,6 +559,11 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 },
                 &(*conn),
             )?;
+            // Unconfirmed change keeps a link to the pending transaction it is received in, so that this transaction
+            // can be cancelled along with it
+            if o.status == (OutputStatus::EncumberedToBeReceived as i32) {
+                updated.update_received_in_tx_id(o.tx_id.map(|t| t as u64), &(*conn))?;
+            }
         }
 
         for co in outputs_to_receive {
@@ -532,61 +602,15 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         drop(conn);
 
         for pto in pending_transaction_outputs.iter() {
-            self.cancel_pending_transaction(pto.tx_id as u64)?;
+            self.cancel_pending_transaction_if_exists(pto.tx_id as u64)?;
         }
 
         Ok(())
     }
 
-    fn cancel_pending_transaction(&self, tx_id: u64) -> Result<(), OutputManagerStorageError> {
+    fn cancel_pending_transaction(&self, tx_id: u64) -> Result<Vec<TxId>, OutputManagerStorageError> {
         let conn = self.database_connection.acquire_lock();
-
-        match PendingTransactionOutputSql::find(tx_id, &(*conn)) {
-            Ok(p) => {
-                let outputs = OutputSql::find_by_tx_id_and_encumbered(tx_id, &(*conn))?;
-
-                for o in outputs {
-                    if o.status == (OutputStatus::EncumberedToBeReceived as i32) {
-                        o.update(
-                            UpdateOutput {
-                                status: Some(OutputStatus::CancelledInbound),
-                                tx_id: None,
-                                spending_key: None,
-                                script_private_key: None,
-                                metadata_signature_nonce: None,
-                                metadata_signature_u_key: None,
-                            },
-                            &(*conn),
-                        )?;
-                    } else if o.status == (OutputStatus::EncumberedToBeSpent as i32) {
-                        o.update(
-                            UpdateOutput {
-                                status: Some(OutputStatus::Unspent),
-                                tx_id: None,
-                                spending_key: None,
-                                script_private_key: None,
-                                metadata_signature_nonce: None,
-                                metadata_signature_u_key: None,
-                            },
-                            &(*conn),
-                        )?;
-                        o.update_null(NullOutputSql { tx_id: None }, &(*conn))?;
-                    }
-                }
-
-                p.delete(&(*conn))?;
-            },
-            Err(e) => {
-                match e {
-                    OutputManagerStorageError::DieselError(DieselError::NotFound) => {
-                        return Err(OutputManagerStorageError::ValueNotFound)
-                    },
-                    e => return Err(e),
-                };
-            },
-        }
-
-        Ok(())
+        cancel_pending_transaction_and_descendants(tx_id, &(*conn))
     }
 
     fn timeout_pending_transactions(&self, period: Duration) -> Result<(), OutputManagerStorageError> {
@@ -598,7 +622,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         )?;
         drop(conn);
         for ptx in older_pending_txs {
-            self.cancel_pending_transaction(ptx.tx_id as u64)?;
+            self.cancel_pending_transaction_if_exists(ptx.tx_id as u64)?;
         }
         Ok(())
     }
@@ -704,6 +728,79 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         DbUnblindedOutput::try_from(o)
     }
 
+    fn reclaim_one_sided_output(&self, tx_id: TxId) -> Result<DbUnblindedOutput, OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        let output = match OutputSql::find_by_tx_id_and_status(tx_id, OutputStatus::SentOneSided, &conn) {
+            Ok(o) => o,
+            Err(OutputManagerStorageError::DieselError(DieselError::NotFound)) => {
+                return Err(OutputManagerStorageError::ValueNotFound)
+            },
+            Err(e) => return Err(e),
+        };
+
+        let mut o = output.update(
+            UpdateOutput {
+                status: Some(OutputStatus::Unspent),
+                tx_id: None,
+                spending_key: None,
+                script_private_key: None,
+                metadata_signature_nonce: None,
+                metadata_signature_u_key: None,
+            },
+            &(*conn),
+        )?;
+        self.decrypt_if_necessary(&mut o)?;
+
+        DbUnblindedOutput::try_from(o)
+    }
+
+    fn fetch_unspent_outputs_page(
+        &self,
+        filter: &UnspentOutputFilter,
+        cursor: Option<OutputCursor>,
+        limit: usize,
+    ) -> Result<UnspentOutputPage, OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        let total_count = OutputSql::count_unspent_filtered(filter, &(*conn))?;
+        // Fetch one extra output to find out if there is another page
+        let mut page = OutputSql::index_unspent_page(filter, cursor, limit + 1, &(*conn))?;
+        let has_more = page.len() > limit;
+        page.truncate(limit);
+        let next_cursor = if has_more {
+            page.last().map(|o| OutputCursor(o.id as u64))
+        } else {
+            None
+        };
+
+        let mut outputs = Vec::with_capacity(page.len());
+        for mut o in page {
+            self.decrypt_if_necessary(&mut o)?;
+            outputs.push(DbUnblindedOutput::try_from(o)?);
+        }
+
+        Ok(UnspentOutputPage {
+            outputs,
+            total_count,
+            next_cursor,
+        })
+    }
+
+    fn fetch_unconfirmed_parents(&self) -> Result<HashMap<TxId, Vec<TxId>>, OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        let mut parents = HashMap::<TxId, Vec<TxId>>::new();
+        for o in OutputSql::index_unconfirmed_change_spends(&(*conn))? {
+            if let (Some(tx_id), Some(parent_tx_id)) = (o.tx_id, o.received_in_tx_id) {
+                let tx_parents = parents.entry(tx_id as u64).or_default();
+                if !tx_parents.contains(&(parent_tx_id as u64)) {
+                    tx_parents.push(parent_tx_id as u64);
+                }
+            }
+        }
+
+        Ok(parents)
+    }
+
     fn cancel_pending_transaction_at_block_height(&self, block_height: u64) -> Result<(), OutputManagerStorageError> {
         let pending_txs;
         {
@@ -711,7 +808,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             pending_txs = PendingTransactionOutputSql::index_block_height(block_height as i64, &conn)?;
         }
         for p in pending_txs {
-            self.cancel_pending_transaction(p.tx_id as u64)?;
+            self.cancel_pending_transaction_if_exists(p.tx_id as u64)?;
         }
         Ok(())
     }
@@ -814,6 +911,178 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
     }
 }
 
+impl KeyManagerBackend for OutputManagerSqliteDatabase {
+    fn reserve_next_index(&self, branch: &str, consumer: &str) -> Result<u64, KeyManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        conn.transaction::<_, KeyManagerStorageError, _>(|| {
+            let index = KeyManagerBranchSql::find_or_create(branch, &conn)?.key_index as u64 + 1;
+            KeyManagerBranchSql::set_index(branch, index, &conn)?;
+            KeyIndexReservationSql::new(branch, index, consumer, KeyIndexStatus::Reserved).upsert(&conn)?;
+            Ok(index)
+        })
+    }
+
+    fn reserve_gap_index(&self, branch: &str, consumer: &str) -> Result<Option<u64>, KeyManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        conn.transaction::<_, KeyManagerStorageError, _>(|| {
+            let state = match KeyManagerBranchSql::find(branch, &conn)? {
+                Some(state) => KeyManagerBranch::from(state),
+                None => return Ok(None),
+            };
+            let reservations = KeyIndexReservationSql::index_by_branch(branch, &conn)?
+                .into_iter()
+                .map(KeyIndexReservation::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            match state.gaps(&reservations).into_iter().find(|gap| gap.can_backfill()) {
+                Some(gap) => {
+                    KeyIndexReservationSql::new(branch, gap.index, consumer, KeyIndexStatus::Reserved).upsert(&conn)?;
+                    Ok(Some(gap.index))
+                },
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn set_index_status(&self, branch: &str, index: u64, status: KeyIndexStatus) -> Result<(), KeyManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        let not_reserved = || KeyManagerStorageError::IndexNotReserved {
+            branch: branch.to_string(),
+            index,
+        };
+        let mut reservation = KeyIndexReservationSql::find(branch, index, &(*conn))?.ok_or_else(not_reserved)?;
+        match KeyIndexStatus::try_from(reservation.status)? {
+            KeyIndexStatus::Reserved => {},
+            KeyIndexStatus::Used => {
+                return Err(KeyManagerStorageError::IndexAlreadyUsed {
+                    branch: branch.to_string(),
+                    index,
+                })
+            },
+            KeyIndexStatus::Released => return Err(not_reserved()),
+        }
+        reservation.status = i32::from(status);
+        reservation.timestamp = Utc::now().naive_utc();
+        reservation.upsert(&(*conn))
+    }
+
+    fn record_used_index(&self, branch: &str, index: u64, consumer: &str) -> Result<(), KeyManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        conn.transaction::<_, KeyManagerStorageError, _>(|| {
+            if index > KeyManagerBranchSql::find_or_create(branch, &conn)?.key_index as u64 {
+                KeyManagerBranchSql::set_index(branch, index, &conn)?;
+            }
+            KeyIndexReservationSql::new(branch, index, consumer, KeyIndexStatus::Used).upsert(&conn)
+        })
+    }
+
+    fn fetch_branch(&self, branch: &str) -> Result<Option<KeyManagerBranch>, KeyManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        Ok(KeyManagerBranchSql::find(branch, &(*conn))?.map(KeyManagerBranch::from))
+    }
+
+    fn fetch_reservations(&self, branch: &str) -> Result<Vec<KeyIndexReservation>, KeyManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        KeyIndexReservationSql::index_by_branch(branch, &(*conn))?
+            .into_iter()
+            .map(KeyIndexReservation::try_from)
+            .collect()
+    }
+}
+
+/// Cancel a pending transaction, and before it every pending transaction that spends its unconfirmed change, as those
+/// can never be mined without it. The spent outputs of each cancelled transaction are returned to the unspent pool, or
+/// to their still pending parent if they are unconfirmed change, and the outputs to be received are marked as cancelled
+/// inbound outputs. Returns the TxIds of the descendant transactions that were cancelled.
+fn cancel_pending_transaction_and_descendants(
+    tx_id: TxId,
+    conn: &SqliteConnection,
+) -> Result<Vec<TxId>, OutputManagerStorageError> {
+    let p = match PendingTransactionOutputSql::find(tx_id, conn) {
+        Ok(p) => p,
+        Err(OutputManagerStorageError::DieselError(DieselError::NotFound)) => {
+            return Err(OutputManagerStorageError::ValueNotFound)
+        },
+        Err(e) => return Err(e),
+    };
+
+    let mut children = OutputSql::find_by_received_in_tx_id(tx_id, conn)?
+        .into_iter()
+        .filter_map(|o| o.tx_id.map(|t| t as u64))
+        .collect::<Vec<_>>();
+    children.sort_unstable();
+    children.dedup();
+
+    let mut cancelled_descendants = Vec::new();
+    for child in children {
+        match cancel_pending_transaction_and_descendants(child, conn) {
+            Ok(descendants) => {
+                cancelled_descendants.push(child);
+                cancelled_descendants.extend(descendants);
+            },
+            Err(OutputManagerStorageError::ValueNotFound) => (),
+            Err(e) => return Err(e),
+        }
+    }
+
+    let outputs = OutputSql::find_by_tx_id_and_encumbered(tx_id, conn)?;
+    for o in outputs {
+        if o.status == (OutputStatus::EncumberedToBeReceived as i32) {
+            o.update(
+                UpdateOutput {
+                    status: Some(OutputStatus::CancelledInbound),
+                    tx_id: None,
+                    spending_key: None,
+                    script_private_key: None,
+                    metadata_signature_nonce: None,
+                    metadata_signature_u_key: None,
+                },
+                conn,
+            )?;
+        } else if o.status == (OutputStatus::EncumberedToBeSpent as i32) {
+            match o.received_in_tx_id {
+                Some(parent_tx_id) => {
+                    let updated = o.update(
+                        UpdateOutput {
+                            status: Some(OutputStatus::EncumberedToBeReceived),
+                            tx_id: Some(parent_tx_id as u64),
+                            spending_key: None,
+                            script_private_key: None,
+                            metadata_signature_nonce: None,
+                            metadata_signature_u_key: None,
+                        },
+                        conn,
+                    )?;
+                    updated.update_received_in_tx_id(None, conn)?;
+                },
+                None => {
+                    o.update(
+                        UpdateOutput {
+                            status: Some(OutputStatus::Unspent),
+                            tx_id: None,
+                            spending_key: None,
+                            script_private_key: None,
+                            metadata_signature_nonce: None,
+                            metadata_signature_u_key: None,
+                        },
+                        conn,
+                    )?;
+                    o.update_null(NullOutputSql { tx_id: None }, conn)?;
+                },
+            }
+        }
+    }
+
+    p.delete(conn)?;
+
+    Ok(cancelled_descendants)
+}
+
 /// A utility function to construct a PendingTransactionOutputs structure for a TxId, set of Outputs and a Timestamp
 fn pending_transaction_outputs_from_sql_outputs(
     tx_id: TxId,
@@ -849,6 +1118,9 @@ enum OutputStatus {
     EncumberedToBeSpent,
     Invalid,
     CancelledInbound,
+    /// The output of a reclaimable one-sided payment sent by this wallet, which belongs to the recipient unless it is
+    /// reclaimed
+    SentOneSided,
 }
 
 impl TryFrom<i32> for OutputStatus {
@@ -862,6 +1134,7 @@ impl TryFrom<i32> for OutputStatus {
             3 => Ok(OutputStatus::EncumberedToBeSpent),
             4 => Ok(OutputStatus::Invalid),
             5 => Ok(OutputStatus::CancelledInbound),
+            6 => Ok(OutputStatus::SentOneSided),
             _ => Err(OutputManagerStorageError::ConversionError),
         }
     }
@@ -887,6 +1160,11 @@ struct NewOutputSql {
     metadata_signature_nonce: Vec<u8>,
     metadata_signature_u_key: Vec<u8>,
     metadata_signature_v_key: Vec<u8>,
+    covenant: Vec<u8>,
+    account: String,
+    features_encrypted_data: Vec<u8>,
+    features_parent_public_key: Option<Vec<u8>>,
+    features_unique_id: Option<Vec<u8>>,
 }
 
 impl NewOutputSql {
@@ -911,6 +1189,16 @@ impl NewOutputSql {
             metadata_signature_nonce: output.unblinded_output.metadata_signature.public_nonce().to_vec(),
             metadata_signature_u_key: output.unblinded_output.metadata_signature.u().to_vec(),
             metadata_signature_v_key: output.unblinded_output.metadata_signature.v().to_vec(),
+            covenant: output.unblinded_output.covenant.as_bytes(),
+            account: output.account,
+            features_encrypted_data: output.unblinded_output.features.encrypted_data.as_bytes().to_vec(),
+            features_parent_public_key: output
+                .unblinded_output
+                .features
+                .parent_public_key
+                .as_ref()
+                .map(|pk| pk.to_vec()),
+            features_unique_id: output.unblinded_output.features.unique_id.clone(),
         })
     }
 
@@ -954,6 +1242,12 @@ struct OutputSql {
     metadata_signature_nonce: Vec<u8>,
     metadata_signature_u_key: Vec<u8>,
     metadata_signature_v_key: Vec<u8>,
+    covenant: Vec<u8>,
+    account: String,
+    received_in_tx_id: Option<i64>,
+    features_encrypted_data: Vec<u8>,
+    features_parent_public_key: Option<Vec<u8>>,
+    features_unique_id: Option<Vec<u8>>,
 }
 
 impl OutputSql {
@@ -970,6 +1264,42 @@ impl OutputSql {
         Ok(outputs::table.filter(outputs::status.eq(status as i32)).load(conn)?)
     }
 
+    fn unspent_filtered(filter: &UnspentOutputFilter) -> outputs::BoxedQuery<'_, Sqlite> {
+        let mut query = outputs::table
+            .filter(outputs::status.eq(OutputStatus::Unspent as i32))
+            .into_boxed();
+        if let Some(min_value) = filter.min_value {
+            query = query.filter(outputs::value.ge(min_value.as_u64() as i64));
+        }
+        if let Some(max_value) = filter.max_value {
+            query = query.filter(outputs::value.le(max_value.as_u64() as i64));
+        }
+        query
+    }
+
+    pub fn count_unspent_filtered(
+        filter: &UnspentOutputFilter,
+        conn: &SqliteConnection,
+    ) -> Result<u64, OutputManagerStorageError> {
+        let count = Self::unspent_filtered(filter).count().get_result::<i64>(conn)?;
+        Ok(count as u64)
+    }
+
+    /// Return up to `limit` unspent outputs matching the filter, in the order they were added, that come after the
+    /// cursor
+    pub fn index_unspent_page(
+        filter: &UnspentOutputFilter,
+        cursor: Option<OutputCursor>,
+        limit: usize,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<OutputSql>, OutputManagerStorageError> {
+        let mut query = Self::unspent_filtered(filter);
+        if let Some(cursor) = cursor {
+            query = query.filter(outputs::id.gt(cursor.0 as i32));
+        }
+        Ok(query.order(outputs::id.asc()).limit(limit as i64).load(conn)?)
+    }
+
     /// Return all unspent outputs that have a maturity above the provided chain tip
     pub fn index_time_locked(tip: u64, conn: &SqliteConnection) -> Result<Vec<OutputSql>, OutputManagerStorageError> {
         Ok(outputs::table
@@ -1026,6 +1356,38 @@ impl OutputSql {
             .load(conn)?)
     }
 
+    pub fn find_by_tx_id_and_status(
+        tx_id: TxId,
+        status: OutputStatus,
+        conn: &SqliteConnection,
+    ) -> Result<OutputSql, OutputManagerStorageError> {
+        Ok(outputs::table
+            .filter(outputs::tx_id.eq(Some(tx_id as i64)))
+            .filter(outputs::status.eq(status as i32))
+            .first::<OutputSql>(conn)?)
+    }
+
+    /// Find the unconfirmed change outputs of a pending transaction that are being spent by other pending transactions
+    pub fn find_by_received_in_tx_id(
+        tx_id: TxId,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<OutputSql>, OutputManagerStorageError> {
+        Ok(outputs::table
+            .filter(outputs::received_in_tx_id.eq(Some(tx_id as i64)))
+            .filter(outputs::status.eq(OutputStatus::EncumberedToBeSpent as i32))
+            .load(conn)?)
+    }
+
+    /// Return all outputs that are unconfirmed change being spent by another pending transaction
+    pub fn index_unconfirmed_change_spends(
+        conn: &SqliteConnection,
+    ) -> Result<Vec<OutputSql>, OutputManagerStorageError> {
+        Ok(outputs::table
+            .filter(outputs::received_in_tx_id.is_not_null())
+            .filter(outputs::status.eq(OutputStatus::EncumberedToBeSpent as i32))
+            .load(conn)?)
+    }
+
     /// Find a particular Output, if it exists and is in the specified Spent state
     pub fn find_status(
         spending_key: &[u8],
@@ -1086,6 +1448,27 @@ impl OutputSql {
         OutputSql::find(&self.spending_key, conn)
     }
 
+    /// Set or clear the pending transaction that this output is still to be received in
+    pub fn update_received_in_tx_id(
+        &self,
+        received_in_tx_id: Option<TxId>,
+        conn: &SqliteConnection,
+    ) -> Result<OutputSql, OutputManagerStorageError> {
+        let num_updated = diesel::update(outputs::table.filter(outputs::id.eq(&self.id)))
+            .set(ReceivedInTxIdSql {
+                received_in_tx_id: received_in_tx_id.map(|t| t as i64),
+            })
+            .execute(conn)?;
+
+        if num_updated == 0 {
+            return Err(OutputManagerStorageError::UnexpectedResult(
+                "Database update error".to_string(),
+            ));
+        }
+
+        OutputSql::find(&self.spending_key, conn)
+    }
+
     /// Update the changed fields of this record after encryption/decryption is performed
     pub fn update_encryption(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
         let _ = self.update(
@@ -1120,6 +1503,25 @@ impl TryFrom<OutputSql> for DbUnblindedOutput {
             Some(OutputFeatures {
                 flags: OutputFlags::from_bits(o.flags as u8).ok_or(OutputManagerStorageError::ConversionError)?,
                 maturity: o.maturity as u64,
+                encrypted_data: EncryptedData::from_bytes(o.features_encrypted_data).map_err(|e| {
+                    error!(
+                        target: LOG_TARGET,
+                        "Could not create EncryptedData from stored bytes: {}", e
+                    );
+                    OutputManagerStorageError::ConversionError
+                })?,
+                parent_public_key: o
+                    .features_parent_public_key
+                    .map(|bytes| PublicKey::from_vec(&bytes))
+                    .transpose()
+                    .map_err(|_| {
+                        error!(
+                            target: LOG_TARGET,
+                            "Could not create parent PublicKey from stored bytes"
+                        );
+                        OutputManagerStorageError::ConversionError
+                    })?,
+                unique_id: o.features_unique_id,
             }),
             TariScript::from_bytes(o.script.as_slice())?,
             ExecutionStack::from_bytes(o.input_data.as_slice())?,
@@ -1160,6 +1562,10 @@ impl TryFrom<OutputSql> for DbUnblindedOutput {
                     OutputManagerStorageError::ConversionError
                 })?,
             ),
+            Covenant::from_bytes(&o.covenant).map_err(|e| {
+                error!(target: LOG_TARGET, "Could not create Covenant from stored bytes: {}", e);
+                OutputManagerStorageError::ConversionError
+            })?,
         );
 
         let hash = match o.hash {
@@ -1183,6 +1589,7 @@ impl TryFrom<OutputSql> for DbUnblindedOutput {
             commitment,
             unblinded_output,
             hash,
+            account: o.account,
         })
     }
 }
@@ -1219,6 +1626,11 @@ impl From<OutputSql> for NewOutputSql {
             metadata_signature_nonce: o.metadata_signature_nonce,
             metadata_signature_u_key: o.metadata_signature_u_key,
             metadata_signature_v_key: o.metadata_signature_v_key,
+            covenant: o.covenant,
+            account: o.account,
+            features_encrypted_data: o.features_encrypted_data,
+            features_parent_public_key: o.features_parent_public_key,
+            features_unique_id: o.features_unique_id,
         }
     }
 }
@@ -1258,6 +1670,14 @@ pub struct NullOutputSql {
     tx_id: Option<i64>,
 }
 
+#[derive(AsChangeset)]
+#[table_name = "outputs"]
+#[changeset_options(treat_none_as_null = "true")]
+/// This struct is used to set or clear the pending transaction that an unconfirmed output is received in
+pub struct ReceivedInTxIdSql {
+    received_in_tx_id: Option<i64>,
+}
+
 /// Map a Rust friendly UpdateOutput to the Sql data type form
 impl From<UpdateOutput> for UpdateOutputSql {
     fn from(u: UpdateOutput) -> Self {
@@ -1531,6 +1951,193 @@ impl Encryptable<Aes256Gcm> for KeyManagerStateSql {
     }
 }
 
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "key_manager_accounts"]
+struct KeyManagerAccountSql {
+    name: String,
+    key_index: i64,
+}
+
+impl KeyManagerAccountSql {
+    /// Insert this account, or replace the stored key index if the account already exists
+    pub fn upsert(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::replace_into(key_manager_accounts::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return all accounts
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<KeyManagerAccountSql>, OutputManagerStorageError> {
+        Ok(key_manager_accounts::table.load::<KeyManagerAccountSql>(conn)?)
+    }
+}
+
+impl From<KeyManagerAccount> for KeyManagerAccountSql {
+    fn from(account: KeyManagerAccount) -> Self {
+        Self {
+            name: account.name,
+            key_index: account.key_index as i64,
+        }
+    }
+}
+
+impl From<KeyManagerAccountSql> for KeyManagerAccount {
+    fn from(account: KeyManagerAccountSql) -> Self {
+        Self {
+            name: account.name,
+            key_index: account.key_index as u64,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "key_manager_branches"]
+struct KeyManagerBranchSql {
+    branch: String,
+    key_index: i64,
+    tracked_from: i64,
+}
+
+impl KeyManagerBranchSql {
+    pub fn find(branch: &str, conn: &SqliteConnection) -> Result<Option<KeyManagerBranchSql>, KeyManagerStorageError> {
+        Ok(key_manager_branches::table
+            .filter(key_manager_branches::branch.eq(branch))
+            .first::<KeyManagerBranchSql>(conn)
+            .optional()?)
+    }
+
+    /// A branch that is not stored yet starts at the index kept in `key_manager_states` or `key_manager_accounts`, if
+    /// there is one, and reservations are tracked from there
+    pub fn find_or_create(
+        branch: &str,
+        conn: &SqliteConnection,
+    ) -> Result<KeyManagerBranchSql, KeyManagerStorageError> {
+        if let Some(state) = KeyManagerBranchSql::find(branch, conn)? {
+            return Ok(state);
+        }
+        let key_index = if branch == PRIMARY_BRANCH {
+            key_manager_states::table
+                .select(key_manager_states::primary_key_index)
+                .first::<i64>(conn)
+                .optional()?
+        } else if let Some(name) = branch.strip_prefix(ACCOUNT_BRANCH_PREFIX) {
+            key_manager_accounts::table
+                .filter(key_manager_accounts::name.eq(name))
+                .select(key_manager_accounts::key_index)
+                .first::<i64>(conn)
+                .optional()?
+        } else {
+            None
+        }
+        .unwrap_or(0);
+        let state = KeyManagerBranchSql {
+            branch: branch.to_string(),
+            key_index,
+            tracked_from: key_index,
+        };
+        diesel::insert_into(key_manager_branches::table)
+            .values(state.clone())
+            .execute(conn)?;
+        Ok(state)
+    }
+
+    /// Set the highest index of the branch. The primary and account indexes are also kept in `key_manager_states` and
+    /// `key_manager_accounts`, which are updated in step so that they never fall behind the reservations.
+    pub fn set_index(branch: &str, index: u64, conn: &SqliteConnection) -> Result<(), KeyManagerStorageError> {
+        diesel::update(key_manager_branches::table.filter(key_manager_branches::branch.eq(branch)))
+            .set(key_manager_branches::key_index.eq(index as i64))
+            .execute(conn)?;
+        if branch == PRIMARY_BRANCH {
+            diesel::update(key_manager_states::table)
+                .set(key_manager_states::primary_key_index.eq(index as i64))
+                .execute(conn)?;
+        } else if let Some(name) = branch.strip_prefix(ACCOUNT_BRANCH_PREFIX) {
+            diesel::update(key_manager_accounts::table.filter(key_manager_accounts::name.eq(name)))
+                .set(key_manager_accounts::key_index.eq(index as i64))
+                .execute(conn)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<KeyManagerBranchSql> for KeyManagerBranch {
+    fn from(state: KeyManagerBranchSql) -> Self {
+        Self {
+            branch: state.branch,
+            key_index: state.key_index as u64,
+            tracked_from: state.tracked_from as u64,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "key_index_reservations"]
+struct KeyIndexReservationSql {
+    branch: String,
+    key_index: i64,
+    consumer: String,
+    status: i32,
+    timestamp: NaiveDateTime,
+}
+
+impl KeyIndexReservationSql {
+    pub fn new(branch: &str, index: u64, consumer: &str, status: KeyIndexStatus) -> Self {
+        Self {
+            branch: branch.to_string(),
+            key_index: index as i64,
+            consumer: consumer.to_string(),
+            status: i32::from(status),
+            timestamp: Utc::now().naive_utc(),
+        }
+    }
+
+    /// Insert this reservation, or replace the stored reservation of the same index
+    pub fn upsert(&self, conn: &SqliteConnection) -> Result<(), KeyManagerStorageError> {
+        diesel::replace_into(key_index_reservations::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find(
+        branch: &str,
+        index: u64,
+        conn: &SqliteConnection,
+    ) -> Result<Option<KeyIndexReservationSql>, KeyManagerStorageError> {
+        Ok(key_index_reservations::table
+            .filter(key_index_reservations::branch.eq(branch))
+            .filter(key_index_reservations::key_index.eq(index as i64))
+            .first::<KeyIndexReservationSql>(conn)
+            .optional()?)
+    }
+
+    /// Return all reservations of the branch ordered by index
+    pub fn index_by_branch(
+        branch: &str,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<KeyIndexReservationSql>, KeyManagerStorageError> {
+        Ok(key_index_reservations::table
+            .filter(key_index_reservations::branch.eq(branch))
+            .order(key_index_reservations::key_index.asc())
+            .load::<KeyIndexReservationSql>(conn)?)
+    }
+}
+
+impl TryFrom<KeyIndexReservationSql> for KeyIndexReservation {
+    type Error = KeyManagerStorageError;
+
+    fn try_from(reservation: KeyIndexReservationSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            branch: reservation.branch,
+            index: reservation.key_index as u64,
+            consumer: reservation.consumer,
+            status: KeyIndexStatus::try_from(reservation.status)?,
+            timestamp: reservation.timestamp,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Queryable, Insertable, Identifiable, PartialEq, AsChangeset)]
 #[table_name = "known_one_sided_payment_scripts"]
 #[primary_key(script_hash)]
@@ -1685,8 +2292,13 @@ impl Encryptable<Aes256Gcm> for KnownOneSidedPaymentScriptSql {
 #[cfg(test)]
 mod test {
     use crate::{
+        key_manager_service::{
+            error::KeyManagerStorageError,
+            storage::database::{KeyIndexStatus, KeyManagerBackend},
+            PRIMARY_BRANCH,
+        },
         output_manager_service::storage::{
-            database::{DbKey, KeyManagerState, OutputManagerBackend},
+            database::{DbKey, DbValue, KeyManagerState, OutputManagerBackend},
             models::DbUnblindedOutput,
             sqlite_db::{
                 KeyManagerStateSql,
@@ -2075,4 +2687,68 @@ mod test {
 
         assert!(db3.fetch(&DbKey::UnspentOutputs).is_ok());
     }
+
+    #[test]
+    fn test_key_index_reservations() {
+        let db_name = format!("{}.sqlite3", random::string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        embed_migrations!("./migrations");
+        let conn = SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+
+        embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+
+        let starting_state = KeyManagerState {
+            master_key: PrivateKey::random(&mut OsRng),
+            branch_seed: "".to_string(),
+            primary_key_index: 2,
+        };
+        KeyManagerStateSql::from(starting_state).set_state(&conn).unwrap();
+
+        let db = OutputManagerSqliteDatabase::new(WalletDbConnection::new(conn, None), None);
+
+        assert_eq!(db.reserve_next_index(PRIMARY_BRANCH, "a").unwrap(), 3);
+        assert_eq!(db.reserve_next_index(PRIMARY_BRANCH, "b").unwrap(), 4);
+        assert_eq!(db.reserve_next_index(PRIMARY_BRANCH, "a").unwrap(), 5);
+        assert_eq!(db.reserve_next_index("other", "a").unwrap(), 1);
+
+        match db.fetch(&DbKey::KeyManagerState).unwrap() {
+            Some(DbValue::KeyManagerState(state)) => assert_eq!(state.primary_key_index, 5),
+            _ => panic!("Key manager state should be stored"),
+        }
+
+        db.set_index_status(PRIMARY_BRANCH, 3, KeyIndexStatus::Used).unwrap();
+        db.set_index_status(PRIMARY_BRANCH, 4, KeyIndexStatus::Released)
+            .unwrap();
+        assert!(matches!(
+            db.set_index_status(PRIMARY_BRANCH, 3, KeyIndexStatus::Released),
+            Err(KeyManagerStorageError::IndexAlreadyUsed { index: 3, .. })
+        ));
+        assert!(matches!(
+            db.set_index_status(PRIMARY_BRANCH, 6, KeyIndexStatus::Used),
+            Err(KeyManagerStorageError::IndexNotReserved { index: 6, .. })
+        ));
+
+        let branch = db.fetch_branch(PRIMARY_BRANCH).unwrap().unwrap();
+        assert_eq!(branch.tracked_from, 2);
+        let gaps = branch.gaps(&db.fetch_reservations(PRIMARY_BRANCH).unwrap());
+        assert_eq!(gaps.iter().map(|g| g.index).collect::<Vec<_>>(), vec![4, 5]);
+        assert!(gaps[0].can_backfill());
+        assert!(!gaps[1].can_backfill());
+
+        assert_eq!(db.reserve_gap_index(PRIMARY_BRANCH, "c").unwrap(), Some(4));
+        assert_eq!(db.reserve_gap_index(PRIMARY_BRANCH, "c").unwrap(), None);
+
+        // Indexes skipped by a recovered index are gaps without a reservation
+        db.record_used_index(PRIMARY_BRANCH, 8, "recovery").unwrap();
+        let branch = db.fetch_branch(PRIMARY_BRANCH).unwrap().unwrap();
+        assert_eq!(branch.key_index, 8);
+        let gaps = branch.gaps(&db.fetch_reservations(PRIMARY_BRANCH).unwrap());
+        assert_eq!(gaps.iter().map(|g| g.index).collect::<Vec<_>>(), vec![4, 5, 6, 7]);
+        assert_eq!(gaps[2].reservation, None);
+        assert_eq!(db.reserve_gap_index(PRIMARY_BRANCH, "c").unwrap(), Some(6));
+        assert_eq!(db.reserve_next_index(PRIMARY_BRANCH, "a").unwrap(), 9);
+    }
 }
@@ -22,6 +22,7 @@
 
 use crate::{
     output_manager_service::{
+        config::TxoValidationMode,
         error::{OutputManagerError, OutputManagerProtocolError},
         handle::OutputManagerEvent,
         resources::OutputManagerResources,
@@ -36,10 +37,12 @@ use std::{cmp, collections::HashMap, convert::TryFrom, fmt, sync::Arc, time::Dur
 use tari_comms::{peer_manager::NodeId, types::CommsPublicKey, PeerConnection};
 use tari_core::{
     base_node::rpc::BaseNodeWalletRpcClient,
-    proto::base_node::FetchMatchingUtxos,
+    blocks::{verify_output_mmr_commitment, verify_utxo_proof, BlockHeader},
+    proto::base_node::{FetchMatchingUtxos, FetchMmrProof, FetchMmrProofResponse},
     transactions::{transaction::TransactionOutput, types::Signature},
 };
 use tari_crypto::tari_utilities::{hash::Hashable, hex::Hex};
+use tari_mmr::MerkleProof;
 use tokio::{sync::broadcast, time::delay_for};
 
 const LOG_TARGET: &str = "wallet::output_manager_service::utxo_validation_task";
@@ -57,6 +60,7 @@ where TBackend: OutputManagerBackend + 'static
     retry_delay: Duration,
     base_node_update_receiver: Option<broadcast::Receiver<CommsPublicKey>>,
     base_node_synced: bool,
+    output_hashes: Option<Vec<Vec<u8>>>,
 }
 
 /// This protocol defines the process of submitting our current UTXO set to the Base Node to validate it.
@@ -82,9 +86,16 @@ where TBackend: OutputManagerBackend + 'static
             retry_delay,
             base_node_update_receiver: Some(base_node_update_receiver),
             base_node_synced: true,
+            output_hashes: None,
         }
     }
 
+    /// Only validate the outputs with these hashes rather than every output of the validation type
+    pub(crate) fn restricted_to(mut self, output_hashes: Vec<Vec<u8>>) -> Self {
+        self.output_hashes = Some(output_hashes);
+        self
+    }
+
     /// The task that defines the execution of the protocol.
     pub async fn execute(mut self) -> Result<u64, OutputManagerProtocolError> {
         let mut base_node_update_receiver = self
@@ -403,30 +414,15 @@ where TBackend: OutputManagerBackend + 'static
         batch: Vec<Vec<u8>>,
         client: &mut BaseNodeWalletRpcClient,
     ) -> Result<bool, OutputManagerProtocolError> {
-        let request = FetchMatchingUtxos {
-            output_hashes: batch.clone(),
+        let (returned_outputs, synced) = match self.resources.config.txo_validation_mode {
+            TxoValidationMode::Trusted => self.fetch_matching_utxos(batch.clone(), client).await?,
+            TxoValidationMode::MerkleProof => self.fetch_proven_utxos(batch.clone(), client).await?,
         };
 
-        let batch_response = client
-            .fetch_matching_utxos(request)
-            .await
-            .map_err(|e| OutputManagerProtocolError::new(self.id, OutputManagerError::from(e)))?;
-
-        if !batch_response.is_synced {
+        if !synced {
             return Ok(false);
         }
 
-        let mut returned_outputs = Vec::new();
-        for output_proto in batch_response.outputs.iter() {
-            let output = TransactionOutput::try_from(output_proto.clone()).map_err(|_| {
-                OutputManagerProtocolError::new(
-                    self.id,
-                    OutputManagerError::ConversionError("Could not convert protobuf TransactionOutput".to_string()),
-                )
-            })?;
-            returned_outputs.push(output);
-        }
-
         // complete validation
         match self.validation_type {
             TxoValidationType::Unspent => {
@@ -567,6 +563,79 @@ where TBackend: OutputManagerBackend + 'static
         Ok(true)
     }
 
+    /// Returns the outputs in the batch that the base node reports as unspent, and whether the base node is synced
+    async fn fetch_matching_utxos(
+        &self,
+        batch: Vec<Vec<u8>>,
+        client: &mut BaseNodeWalletRpcClient,
+    ) -> Result<(Vec<TransactionOutput>, bool), OutputManagerProtocolError> {
+        let request = FetchMatchingUtxos { output_hashes: batch };
+
+        let batch_response = client
+            .fetch_matching_utxos(request)
+            .await
+            .map_err(|e| OutputManagerProtocolError::new(self.id, OutputManagerError::from(e)))?;
+
+        if !batch_response.is_synced {
+            return Ok((Vec::new(), false));
+        }
+
+        let mut returned_outputs = Vec::new();
+        for output_proto in batch_response.outputs.iter() {
+            let output = TransactionOutput::try_from(output_proto.clone()).map_err(|_| {
+                OutputManagerProtocolError::new(
+                    self.id,
+                    OutputManagerError::ConversionError("Could not convert protobuf TransactionOutput".to_string()),
+                )
+            })?;
+            returned_outputs.push(output);
+        }
+
+        Ok((returned_outputs, true))
+    }
+
+    /// Returns the outputs in the batch that the base node proves are unspent, and whether the base node is synced.
+    /// The proofs must be made against a header that is at least as high as the tip the base node reported before
+    /// the proofs were requested, so that the base node cannot answer using a stale header.
+    async fn fetch_proven_utxos(
+        &self,
+        batch: Vec<Vec<u8>>,
+        client: &mut BaseNodeWalletRpcClient,
+    ) -> Result<(Vec<TransactionOutput>, bool), OutputManagerProtocolError> {
+        let tip_info = client
+            .get_tip_info()
+            .await
+            .map_err(|e| OutputManagerProtocolError::new(self.id, OutputManagerError::from(e)))?;
+        if !tip_info.is_synced {
+            return Ok((Vec::new(), false));
+        }
+        let tip_height = tip_info
+            .metadata
+            .and_then(|metadata| metadata.height_of_longest_chain)
+            .unwrap_or(0);
+
+        let response = client
+            .fetch_mmr_proof(FetchMmrProof {
+                output_hashes: batch.clone(),
+            })
+            .await
+            .map_err(|e| OutputManagerProtocolError::new(self.id, OutputManagerError::from(e)))?;
+        if !response.is_synced {
+            return Ok((Vec::new(), false));
+        }
+
+        let outputs =
+            verify_mmr_proofs(response, &batch, tip_height).map_err(|e| OutputManagerProtocolError::new(self.id, e))?;
+        debug!(
+            target: LOG_TARGET,
+            "Verified MMR proofs for {} of {} outputs in batch",
+            outputs.len(),
+            batch.len()
+        );
+
+        Ok((outputs, true))
+    }
+
     async fn get_output_batches(&self) -> Result<Vec<Vec<Vec<u8>>>, OutputManagerProtocolError> {
         let mut outputs: Vec<Vec<u8>> = match self.validation_type {
             TxoValidationType::Unspent => self
@@ -603,6 +672,9 @@ where TBackend: OutputManagerBackend + 'static
                 .map(|uo| uo.hash)
                 .collect(),
         };
+        if let Some(output_hashes) = self.output_hashes.as_ref() {
+            outputs.retain(|hash| output_hashes.contains(hash));
+        }
 
         // Determine how many rounds of base node request we need to query all the transactions in batches of
         // max_tx_query_batch_size
@@ -635,6 +707,56 @@ where TBackend: OutputManagerBackend + 'static
     }
 }
 
+/// Verifies the MMR proofs in the response against the header they were made for, and returns the proven outputs.
+/// Any invalid proof fails the whole response, because it means the base node cannot be trusted.
+fn verify_mmr_proofs(
+    response: FetchMmrProofResponse,
+    requested_hashes: &[Vec<u8>],
+    min_height: u64,
+) -> Result<Vec<TransactionOutput>, OutputManagerError> {
+    let header = response
+        .header
+        .ok_or_else(|| OutputManagerError::InvalidMmrProof("Base node did not provide a header".to_string()))
+        .and_then(|header| BlockHeader::try_from(header).map_err(OutputManagerError::InvalidMmrProof))?;
+    if header.height < min_height {
+        return Err(OutputManagerError::InvalidMmrProof(format!(
+            "Proofs were made against header #{} which is behind the reported tip #{}",
+            header.height, min_height
+        )));
+    }
+
+    let deleted = verify_output_mmr_commitment(&header, &response.output_mmr_root, &response.deleted_bitmap)
+        .map_err(|e| OutputManagerError::InvalidMmrProof(e.to_string()))?;
+
+    let mut outputs = Vec::with_capacity(response.proofs.len());
+    for proof in response.proofs {
+        let output = proof
+            .output
+            .ok_or_else(|| OutputManagerError::InvalidMmrProof("Proof does not include the output".to_string()))
+            .and_then(|output| TransactionOutput::try_from(output).map_err(OutputManagerError::ConversionError))?;
+        let hash = output.hash();
+        if !requested_hashes.contains(&hash) {
+            return Err(OutputManagerError::InvalidMmrProof(format!(
+                "Proof provided for output {} that was not requested",
+                hash.to_hex()
+            )));
+        }
+        let merkle_proof: MerkleProof = bincode::deserialize(&proof.merkle_proof)
+            .map_err(|e| OutputManagerError::InvalidMmrProof(format!("Could not deserialize proof: {}", e)))?;
+        verify_utxo_proof(
+            &response.output_mmr_root,
+            &deleted,
+            &hash,
+            proof.mmr_position,
+            &merkle_proof,
+        )
+        .map_err(|e| OutputManagerError::InvalidMmrProof(e.to_string()))?;
+        outputs.push(output);
+    }
+
+    Ok(outputs)
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TxoValidationType {
     Unspent,
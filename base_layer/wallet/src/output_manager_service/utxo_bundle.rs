@@ -0,0 +1,159 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # UTXO bundles
+//!
+//! A [UtxoBundle] moves a spendable output from one Tari wallet to another. It contains everything needed to spend
+//! the output: the value, the blinding factor, the script and script key, and the metadata signature. The bundle is
+//! encrypted to the recipient's public key with a key derived from an ephemeral Diffie-Hellman exchange, so that it
+//! can be sent over an untrusted channel.
+//!
+//! Knowing the blinding factor and value of a commitment that is unspent on the blockchain is the proof of ownership
+//! that the importing wallet checks before it adds the output.
+
+use crate::util::encryption::{decrypt_bytes_integral_nonce, encrypt_bytes_integral_nonce};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, NewAead},
+    Aes256Gcm,
+};
+use digest::Digest;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::{
+    transaction::UnblindedOutput,
+    types::{PrivateKey, PublicKey},
+};
+use tari_crypto::{
+    common::Blake256,
+    keys::{DiffieHellmanSharedSecret, PublicKey as PublicKeyTrait},
+    tari_utilities::ByteArray,
+};
+use thiserror::Error;
+
+const UTXO_BUNDLE_KEY_DOMAIN: &[u8] = b"com.tari.wallet.utxo_bundle";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum UtxoBundleError {
+    #[error("The bundle could not be decrypted. It may have been encrypted for a different wallet.")]
+    DecryptionFailed,
+    #[error("The bundle could not be encrypted")]
+    EncryptionFailed,
+    #[error("Malformed bundle: {0}")]
+    Malformed(String),
+    #[error("The wallet does not have an unspent output with commitment {0}")]
+    OutputNotFound(String),
+    #[error("The output is already in the wallet")]
+    AlreadyImported,
+    #[error("The output is not unspent on the blockchain")]
+    NotUnspentOnChain,
+    #[error("No base node is set to verify the output against the blockchain")]
+    NoBaseNode,
+    #[error("The base node is not synced and cannot verify the output")]
+    BaseNodeNotSynced,
+    #[error("Base node query failed: {0}")]
+    BaseNodeQueryFailed(String),
+}
+
+/// A spendable output exported by a wallet, encrypted to the recipient's public key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UtxoBundle {
+    /// The public key of the ephemeral key used to derive the encryption key
+    pub ephemeral_public_key: PublicKey,
+    /// The encrypted output, prefixed with the AES-GCM nonce
+    pub ciphertext: Vec<u8>,
+}
+
+impl UtxoBundle {
+    /// Encrypts the output to the recipient's public key
+    pub fn encrypt(output: &UnblindedOutput, recipient: &PublicKey) -> Result<Self, UtxoBundleError> {
+        let (ephemeral_secret_key, ephemeral_public_key) = PublicKey::random_keypair(&mut OsRng);
+        let cipher = bundle_cipher(&ephemeral_secret_key, recipient);
+        let plaintext = bincode::serialize(output).map_err(|e| UtxoBundleError::Malformed(e.to_string()))?;
+        let ciphertext =
+            encrypt_bytes_integral_nonce(&cipher, plaintext).map_err(|_| UtxoBundleError::EncryptionFailed)?;
+        Ok(Self {
+            ephemeral_public_key,
+            ciphertext,
+        })
+    }
+
+    /// Decrypts the output with the recipient's secret key
+    pub fn decrypt(&self, recipient_secret_key: &PrivateKey) -> Result<UnblindedOutput, UtxoBundleError> {
+        let cipher = bundle_cipher(recipient_secret_key, &self.ephemeral_public_key);
+        let plaintext = decrypt_bytes_integral_nonce(&cipher, self.ciphertext.clone())
+            .map_err(|_| UtxoBundleError::DecryptionFailed)?;
+        bincode::deserialize(&plaintext).map_err(|e| UtxoBundleError::Malformed(e.to_string()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Serializing a public key and a byte vector cannot fail
+        bincode::serialize(self).expect("UtxoBundle serialization failed")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, UtxoBundleError> {
+        bincode::deserialize(bytes).map_err(|e| UtxoBundleError::Malformed(e.to_string()))
+    }
+}
+
+fn bundle_cipher(secret_key: &PrivateKey, public_key: &PublicKey) -> Aes256Gcm {
+    let shared_secret = PublicKey::shared_secret(secret_key, public_key);
+    let key = Blake256::new()
+        .chain(UTXO_BUNDLE_KEY_DOMAIN)
+        .chain(shared_secret.as_bytes())
+        .finalize();
+    Aes256Gcm::new(GenericArray::from_slice(key.as_slice()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_core::transactions::{
+        helpers::{create_unblinded_output, TestParams},
+        tari_amount::MicroTari,
+        transaction::OutputFeatures,
+    };
+    use tari_crypto::{keys::SecretKey, script};
+
+    #[test]
+    fn it_round_trips_for_the_recipient_only() {
+        let output = create_unblinded_output(
+            script!(Nop),
+            OutputFeatures::default(),
+            TestParams::new(),
+            MicroTari::from(5000),
+        );
+        let (recipient_secret_key, recipient_public_key) = PublicKey::random_keypair(&mut OsRng);
+        let bundle = UtxoBundle::encrypt(&output, &recipient_public_key).unwrap();
+        let bundle = UtxoBundle::from_bytes(&bundle.to_bytes()).unwrap();
+
+        let decrypted = bundle.decrypt(&recipient_secret_key).unwrap();
+        assert_eq!(decrypted.value, output.value);
+        assert_eq!(decrypted.spending_key, output.spending_key);
+        assert_eq!(decrypted.script_private_key, output.script_private_key);
+
+        let other_secret_key = PrivateKey::random(&mut OsRng);
+        assert_eq!(
+            bundle.decrypt(&other_secret_key).unwrap_err(),
+            UtxoBundleError::DecryptionFailed
+        );
+    }
+}
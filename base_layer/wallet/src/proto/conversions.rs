@@ -0,0 +1,118 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    history_sync_service::{journal::HistoryCursor, storage::database::SyncedTransaction},
+    output_manager_service::service::Balance,
+    proto::history_sync as proto,
+    transaction_service::storage::models::{TransactionDirection, TransactionStatus},
+};
+use chrono::NaiveDateTime;
+use std::convert::TryFrom;
+use tari_core::transactions::{tari_amount::MicroTari, types::PublicKey};
+use tari_crypto::tari_utilities::ByteArray;
+
+impl From<HistoryCursor> for proto::HistoryCursor {
+    fn from(cursor: HistoryCursor) -> Self {
+        Self {
+            epoch: cursor.epoch,
+            sequence: cursor.sequence,
+        }
+    }
+}
+
+impl From<proto::HistoryCursor> for HistoryCursor {
+    fn from(cursor: proto::HistoryCursor) -> Self {
+        Self {
+            epoch: cursor.epoch,
+            sequence: cursor.sequence,
+        }
+    }
+}
+
+impl From<Balance> for proto::WalletBalance {
+    fn from(balance: Balance) -> Self {
+        Self {
+            available_balance: balance.available_balance.into(),
+            pending_incoming_balance: balance.pending_incoming_balance.into(),
+            pending_outgoing_balance: balance.pending_outgoing_balance.into(),
+            time_locked_balance: balance.time_locked_balance.map(Into::into),
+        }
+    }
+}
+
+impl From<proto::WalletBalance> for Balance {
+    fn from(balance: proto::WalletBalance) -> Self {
+        Self {
+            available_balance: balance.available_balance.into(),
+            time_locked_balance: balance.time_locked_balance.map(MicroTari::from),
+            pending_incoming_balance: balance.pending_incoming_balance.into(),
+            pending_outgoing_balance: balance.pending_outgoing_balance.into(),
+        }
+    }
+}
+
+impl From<SyncedTransaction> for proto::HistoryTransaction {
+    fn from(tx: SyncedTransaction) -> Self {
+        Self {
+            tx_id: tx.tx_id,
+            source_public_key: tx.source_public_key.to_vec(),
+            destination_public_key: tx.destination_public_key.to_vec(),
+            amount: tx.amount.into(),
+            fee: tx.fee.into(),
+            status: tx.status as i32,
+            direction: tx.direction as i32,
+            message: tx.message,
+            timestamp: tx.timestamp.timestamp(),
+            cancelled: tx.cancelled,
+            mined_height: tx.mined_height.unwrap_or(0),
+            confirmations: tx.confirmations.unwrap_or(0),
+        }
+    }
+}
+
+impl TryFrom<proto::HistoryTransaction> for SyncedTransaction {
+    type Error = String;
+
+    fn try_from(tx: proto::HistoryTransaction) -> Result<Self, Self::Error> {
+        let status = TransactionStatus::try_from(tx.status).map_err(|err| err.to_string())?;
+        let mined_height = Some(tx.mined_height).filter(|h| *h > 0);
+        Ok(Self {
+            tx_id: tx.tx_id,
+            source_public_key: PublicKey::from_bytes(&tx.source_public_key)
+                .map_err(|err| format!("Invalid source public key: {}", err))?,
+            destination_public_key: PublicKey::from_bytes(&tx.destination_public_key)
+                .map_err(|err| format!("Invalid destination public key: {}", err))?,
+            amount: tx.amount.into(),
+            fee: tx.fee.into(),
+            direction: TransactionDirection::try_from(tx.direction).map_err(|err| err.to_string())?,
+            message: tx.message,
+            timestamp: NaiveDateTime::from_timestamp_opt(tx.timestamp, 0)
+                .ok_or_else(|| "Invalid timestamp".to_string())?,
+            cancelled: tx.cancelled,
+            // Confirmations are only meaningful once the transaction has been mined
+            confirmations: mined_height.and(Some(tx.confirmations)),
+            mined_height,
+            status,
+        })
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    recurring_payment_service::storage::database::{DbKey, RecurringPaymentId},
+    transaction_service::error::TransactionServiceError,
+};
+use diesel::result::Error as DieselError;
+use tari_service_framework::reply_channel::TransportChannelError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RecurringPaymentServiceError {
+    #[error("Recurring payment `{0}` is not active")]
+    RecurringPaymentNotActive(RecurringPaymentId),
+    #[error("Invalid recurring payment schedule: `{0}`")]
+    InvalidSchedule(String),
+    #[error("Received incorrect response from service request")]
+    UnexpectedApiResponse,
+    #[error("Recurring payment service storage error: `{0}`")]
+    RecurringPaymentStorageError(#[from] RecurringPaymentStorageError),
+    #[error("Transaction service error: `{0}`")]
+    TransactionServiceError(#[from] TransactionServiceError),
+    #[error("Transport channel error: `{0}`")]
+    TransportChannelError(#[from] TransportChannelError),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RecurringPaymentStorageError {
+    #[error("This write operation is not supported for provided DbKey")]
+    OperationNotSupported,
+    #[error("Error converting a type")]
+    ConversionError,
+    #[error("Value not found error: `{0}`")]
+    ValueNotFound(DbKey),
+    #[error("Unexpected result error: `{0}`")]
+    UnexpectedResult(String),
+    #[error("Diesel error: `{0}`")]
+    DieselError(#[from] DieselError),
+    #[error("Blocking task spawn error: `{0}`")]
+    BlockingTaskSpawnError(String),
+}
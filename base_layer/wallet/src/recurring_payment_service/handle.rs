@@ -0,0 +1,168 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::TxId,
+    recurring_payment_service::{
+        error::RecurringPaymentServiceError,
+        storage::database::{RecurringPayment, RecurringPaymentId},
+    },
+};
+use futures::{stream::Fuse, StreamExt};
+use std::{sync::Arc, time::Duration};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_service_framework::reply_channel::SenderService;
+use tokio::sync::broadcast;
+use tower::Service;
+
+/// The end conditions and timing of a new recurring payment schedule
+#[derive(Debug, Clone, Default)]
+pub struct RecurringPaymentSchedule {
+    pub interval: Duration,
+    /// Delay before the first payment is made. The first payment is made immediately if this is zero.
+    pub first_payment_delay: Duration,
+    pub max_payments: Option<u32>,
+    pub end_after: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub enum RecurringPaymentServiceRequest {
+    CreateRecurringPayment(CommsPublicKey, MicroTari, MicroTari, String, RecurringPaymentSchedule),
+    CancelRecurringPayment(RecurringPaymentId),
+    GetRecurringPayment(RecurringPaymentId),
+    GetRecurringPayments,
+}
+
+#[derive(Debug)]
+pub enum RecurringPaymentServiceResponse {
+    RecurringPaymentCreated(RecurringPaymentId),
+    RecurringPaymentCancelled,
+    RecurringPayment(Box<RecurringPayment>),
+    RecurringPayments(Vec<RecurringPayment>),
+}
+
+/// Events that can be published on the Recurring Payment Service Event Stream
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecurringPaymentEvent {
+    PaymentSent(RecurringPaymentId, TxId),
+    /// A payment failed and will be retried. Contains the number of failed attempts so far.
+    PaymentFailed(RecurringPaymentId, u32),
+    Completed(RecurringPaymentId),
+    Cancelled(RecurringPaymentId),
+    /// A payment failed after exhausting all retries and the schedule has been stopped
+    Failed(RecurringPaymentId),
+}
+
+pub type RecurringPaymentEventSender = broadcast::Sender<Arc<RecurringPaymentEvent>>;
+pub type RecurringPaymentEventReceiver = broadcast::Receiver<Arc<RecurringPaymentEvent>>;
+
+#[derive(Clone)]
+pub struct RecurringPaymentServiceHandle {
+    handle: SenderService<
+        RecurringPaymentServiceRequest,
+        Result<RecurringPaymentServiceResponse, RecurringPaymentServiceError>,
+    >,
+    event_stream_sender: RecurringPaymentEventSender,
+}
+
+impl RecurringPaymentServiceHandle {
+    pub fn new(
+        handle: SenderService<
+            RecurringPaymentServiceRequest,
+            Result<RecurringPaymentServiceResponse, RecurringPaymentServiceError>,
+        >,
+        event_stream_sender: RecurringPaymentEventSender,
+    ) -> Self {
+        Self {
+            handle,
+            event_stream_sender,
+        }
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<RecurringPaymentEventReceiver> {
+        self.event_stream_sender.subscribe().fuse()
+    }
+
+    pub async fn create_recurring_payment(
+        &mut self,
+        destination: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        schedule: RecurringPaymentSchedule,
+    ) -> Result<RecurringPaymentId, RecurringPaymentServiceError> {
+        match self
+            .handle
+            .call(RecurringPaymentServiceRequest::CreateRecurringPayment(
+                destination,
+                amount,
+                fee_per_gram,
+                message,
+                schedule,
+            ))
+            .await??
+        {
+            RecurringPaymentServiceResponse::RecurringPaymentCreated(id) => Ok(id),
+            _ => Err(RecurringPaymentServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn cancel_recurring_payment(
+        &mut self,
+        id: RecurringPaymentId,
+    ) -> Result<(), RecurringPaymentServiceError> {
+        match self
+            .handle
+            .call(RecurringPaymentServiceRequest::CancelRecurringPayment(id))
+            .await??
+        {
+            RecurringPaymentServiceResponse::RecurringPaymentCancelled => Ok(()),
+            _ => Err(RecurringPaymentServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_recurring_payment(
+        &mut self,
+        id: RecurringPaymentId,
+    ) -> Result<RecurringPayment, RecurringPaymentServiceError> {
+        match self
+            .handle
+            .call(RecurringPaymentServiceRequest::GetRecurringPayment(id))
+            .await??
+        {
+            RecurringPaymentServiceResponse::RecurringPayment(p) => Ok(*p),
+            _ => Err(RecurringPaymentServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_recurring_payments(&mut self) -> Result<Vec<RecurringPayment>, RecurringPaymentServiceError> {
+        match self
+            .handle
+            .call(RecurringPaymentServiceRequest::GetRecurringPayments)
+            .await??
+        {
+            RecurringPaymentServiceResponse::RecurringPayments(p) => Ok(p),
+            _ => Err(RecurringPaymentServiceError::UnexpectedApiResponse),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+pub mod config;
+pub mod error;
+pub mod handle;
+pub mod service;
+pub mod storage;
+
+use crate::{
+    recurring_payment_service::{
+        config::RecurringPaymentServiceConfig,
+        handle::RecurringPaymentServiceHandle,
+        service::RecurringPaymentService,
+        storage::database::{RecurringPaymentBackend, RecurringPaymentDatabase},
+    },
+    transaction_service::handle::TransactionServiceHandle,
+};
+use futures::future;
+use log::*;
+use tari_service_framework::{
+    async_trait,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+    ServiceInitializerContext,
+};
+use tokio::sync::broadcast;
+
+const LOG_TARGET: &str = "wallet::recurring_payment_service::initializer";
+
+pub struct RecurringPaymentServiceInitializer<T>
+where T: RecurringPaymentBackend
+{
+    config: RecurringPaymentServiceConfig,
+    backend: Option<T>,
+}
+
+impl<T> RecurringPaymentServiceInitializer<T>
+where T: RecurringPaymentBackend
+{
+    pub fn new(config: RecurringPaymentServiceConfig, backend: T) -> Self {
+        Self {
+            config,
+            backend: Some(backend),
+        }
+    }
+}
+
+#[async_trait]
+impl<T> ServiceInitializer for RecurringPaymentServiceInitializer<T>
+where T: RecurringPaymentBackend + 'static
+{
+    async fn initialize(&mut self, context: ServiceInitializerContext) -> Result<(), ServiceInitializationError> {
+        let (sender, receiver) = reply_channel::unbounded();
+        let (publisher, _) = broadcast::channel(200);
+
+        let handle = RecurringPaymentServiceHandle::new(sender, publisher.clone());
+
+        // Register handle before waiting for handles to be ready
+        context.register_handle(handle);
+
+        let backend = self
+            .backend
+            .take()
+            .expect("Cannot start Recurring Payment Service without setting a storage backend");
+        let config = self.config.clone();
+
+        context.spawn_when_ready(move |handles| async move {
+            let transaction_service = handles.expect_handle::<TransactionServiceHandle>();
+
+            let service = RecurringPaymentService::new(
+                config,
+                receiver,
+                RecurringPaymentDatabase::new(backend),
+                transaction_service,
+                publisher,
+                handles.get_shutdown_signal(),
+            )
+            .start();
+            futures::pin_mut!(service);
+            future::select(service, handles.get_shutdown_signal()).await;
+            info!(target: LOG_TARGET, "Recurring Payment service shutdown");
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    recurring_payment_service::{
+        config::RecurringPaymentServiceConfig,
+        error::RecurringPaymentServiceError,
+        handle::{
+            RecurringPaymentEvent,
+            RecurringPaymentEventSender,
+            RecurringPaymentSchedule,
+            RecurringPaymentServiceRequest,
+            RecurringPaymentServiceResponse,
+        },
+        storage::database::{
+            RecurringPayment,
+            RecurringPaymentBackend,
+            RecurringPaymentDatabase,
+            RecurringPaymentId,
+            RecurringPaymentStatus,
+        },
+    },
+    transaction_service::handle::TransactionServiceHandle,
+};
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use futures::{pin_mut, StreamExt};
+use log::*;
+use rand::{rngs::OsRng, RngCore};
+use std::sync::Arc;
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_service_framework::reply_channel;
+use tari_shutdown::ShutdownSignal;
+use tokio::time;
+
+const LOG_TARGET: &str = "wallet::recurring_payment_service";
+
+/// The Recurring Payment Service makes scheduled payments using the Transaction Service. Schedules are checked every
+/// `check_interval` and a payment is sent for each schedule that is due. A failed payment is retried after
+/// `retry_interval` up to `max_retries` times before the schedule is marked as failed.
+pub struct RecurringPaymentService<T>
+where T: RecurringPaymentBackend + 'static
+{
+    config: RecurringPaymentServiceConfig,
+    db: RecurringPaymentDatabase<T>,
+    transaction_service: TransactionServiceHandle,
+    event_publisher: RecurringPaymentEventSender,
+    request_stream: Option<
+        reply_channel::Receiver<
+            RecurringPaymentServiceRequest,
+            Result<RecurringPaymentServiceResponse, RecurringPaymentServiceError>,
+        >,
+    >,
+    shutdown_signal: Option<ShutdownSignal>,
+}
+
+impl<T> RecurringPaymentService<T>
+where T: RecurringPaymentBackend + 'static
+{
+    pub fn new(
+        config: RecurringPaymentServiceConfig,
+        request_stream: reply_channel::Receiver<
+            RecurringPaymentServiceRequest,
+            Result<RecurringPaymentServiceResponse, RecurringPaymentServiceError>,
+        >,
+        db: RecurringPaymentDatabase<T>,
+        transaction_service: TransactionServiceHandle,
+        event_publisher: RecurringPaymentEventSender,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self {
+        Self {
+            config,
+            db,
+            transaction_service,
+            event_publisher,
+            request_stream: Some(request_stream),
+            shutdown_signal: Some(shutdown_signal),
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), RecurringPaymentServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Recurring Payment Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+
+        let shutdown = self
+            .shutdown_signal
+            .take()
+            .expect("Recurring Payment Service initialized without shutdown signal");
+        pin_mut!(shutdown);
+
+        let mut check_interval = time::interval(self.config.check_interval).fuse();
+
+        info!(target: LOG_TARGET, "Recurring Payment Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let response = self.handle_request(request).await.map_err(|e| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", e);
+                        e
+                    });
+                    let _ = reply_tx.send(response).map_err(|e| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        e
+                    });
+                },
+                _ = check_interval.select_next_some() => {
+                    if let Err(e) = self.make_due_payments().await {
+                        error!(target: LOG_TARGET, "Error making recurring payments: {:?}", e);
+                    }
+                },
+                _ = shutdown => {
+                    info!(target: LOG_TARGET, "Recurring Payment service shutting down because it received the shutdown signal");
+                    break;
+                }
+                complete => {
+                    info!(target: LOG_TARGET, "Recurring Payment service shutting down");
+                    break;
+                }
+            }
+        }
+        info!(target: LOG_TARGET, "Recurring Payment Service ended");
+        Ok(())
+    }
+
+    async fn handle_request(
+        &mut self,
+        request: RecurringPaymentServiceRequest,
+    ) -> Result<RecurringPaymentServiceResponse, RecurringPaymentServiceError> {
+        match request {
+            RecurringPaymentServiceRequest::CreateRecurringPayment(
+                destination,
+                amount,
+                fee_per_gram,
+                message,
+                schedule,
+            ) => self
+                .create_recurring_payment(destination, amount, fee_per_gram, message, schedule)
+                .await
+                .map(RecurringPaymentServiceResponse::RecurringPaymentCreated),
+            RecurringPaymentServiceRequest::CancelRecurringPayment(id) => self
+                .cancel_recurring_payment(id)
+                .await
+                .map(|_| RecurringPaymentServiceResponse::RecurringPaymentCancelled),
+            RecurringPaymentServiceRequest::GetRecurringPayment(id) => Ok(self
+                .db
+                .get_recurring_payment(id)
+                .await
+                .map(|p| RecurringPaymentServiceResponse::RecurringPayment(Box::new(p)))?),
+            RecurringPaymentServiceRequest::GetRecurringPayments => Ok(self
+                .db
+                .get_recurring_payments()
+                .await
+                .map(RecurringPaymentServiceResponse::RecurringPayments)?),
+        }
+    }
+
+    async fn create_recurring_payment(
+        &mut self,
+        destination: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        schedule: RecurringPaymentSchedule,
+    ) -> Result<RecurringPaymentId, RecurringPaymentServiceError> {
+        if schedule.interval.as_secs() == 0 {
+            return Err(RecurringPaymentServiceError::InvalidSchedule(
+                "The payment interval must be at least one second".to_string(),
+            ));
+        }
+        if amount == MicroTari::from(0) {
+            return Err(RecurringPaymentServiceError::InvalidSchedule(
+                "The payment amount must be greater than zero".to_string(),
+            ));
+        }
+        if schedule.max_payments == Some(0) {
+            return Err(RecurringPaymentServiceError::InvalidSchedule(
+                "The maximum number of payments must be greater than zero".to_string(),
+            ));
+        }
+
+        let now = Utc::now().naive_utc();
+        let first_payment_at = now + to_chrono_duration(schedule.first_payment_delay)?;
+        let end_at = match schedule.end_after {
+            Some(end_after) => Some(now + to_chrono_duration(end_after)?),
+            None => None,
+        };
+        let id = OsRng.next_u64();
+        let payment = RecurringPayment::new(
+            id,
+            destination,
+            amount,
+            fee_per_gram,
+            message,
+            schedule.interval,
+            first_payment_at,
+            schedule.max_payments,
+            end_at,
+            now,
+        );
+        self.db.upsert_recurring_payment(payment).await?;
+        info!(
+            target: LOG_TARGET,
+            "Recurring payment {} of {} every {:?} created", id, amount, schedule.interval
+        );
+
+        Ok(id)
+    }
+
+    async fn cancel_recurring_payment(&mut self, id: RecurringPaymentId) -> Result<(), RecurringPaymentServiceError> {
+        let mut payment = self.db.get_recurring_payment(id).await?;
+        if payment.status != RecurringPaymentStatus::Active {
+            return Err(RecurringPaymentServiceError::RecurringPaymentNotActive(id));
+        }
+        payment.status = RecurringPaymentStatus::Cancelled;
+        self.db.upsert_recurring_payment(payment).await?;
+        info!(target: LOG_TARGET, "Recurring payment {} cancelled", id);
+        self.publish_event(RecurringPaymentEvent::Cancelled(id));
+
+        Ok(())
+    }
+
+    /// Send a payment for every active schedule that is due
+    async fn make_due_payments(&mut self) -> Result<(), RecurringPaymentServiceError> {
+        let now = Utc::now().naive_utc();
+        let due = self
+            .db
+            .get_recurring_payments()
+            .await?
+            .into_iter()
+            .filter(|p| p.is_due(now))
+            .collect::<Vec<_>>();
+
+        for payment in due {
+            let id = payment.id;
+            if let Err(e) = self.make_payment(payment, now).await {
+                error!(target: LOG_TARGET, "Error making recurring payment {}: {:?}", id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn make_payment(
+        &mut self,
+        mut payment: RecurringPayment,
+        now: NaiveDateTime,
+    ) -> Result<(), RecurringPaymentServiceError> {
+        if payment.is_finished(now) {
+            self.complete(payment).await?;
+            return Ok(());
+        }
+
+        // The payment is recorded as made before it is sent. If the schedule could only be persisted after sending, a
+        // failed write or a restart in between would send the same payment again on the next check.
+        let scheduled = payment.clone();
+        payment.payments_made += 1;
+        payment.advance(now);
+        self.db.upsert_recurring_payment(payment.clone()).await?;
+
+        match self
+            .transaction_service
+            .send_transaction(
+                payment.destination.clone(),
+                payment.amount,
+                payment.fee_per_gram,
+                payment.message.clone(),
+            )
+            .await
+        {
+            Ok(tx_id) => {
+                payment.failed_attempts = 0;
+                payment.last_tx_id = Some(tx_id);
+                info!(
+                    target: LOG_TARGET,
+                    "Recurring payment {} sent (TxId: {}), next payment at {}",
+                    payment.id,
+                    tx_id,
+                    payment.next_payment_at
+                );
+                self.publish_event(RecurringPaymentEvent::PaymentSent(payment.id, tx_id));
+                if payment.is_finished(now) {
+                    self.complete(payment).await?;
+                } else {
+                    self.db.upsert_recurring_payment(payment).await?;
+                }
+            },
+            Err(e) => {
+                // Nothing was sent, so the schedule is restored before the failure is recorded
+                let mut payment = scheduled;
+                payment.failed_attempts += 1;
+                warn!(
+                    target: LOG_TARGET,
+                    "Recurring payment {} failed (attempt {}): {}", payment.id, payment.failed_attempts, e
+                );
+                if payment.failed_attempts > self.config.max_retries {
+                    payment.status = RecurringPaymentStatus::Failed;
+                    let id = payment.id;
+                    self.db.upsert_recurring_payment(payment).await?;
+                    self.publish_event(RecurringPaymentEvent::Failed(id));
+                } else {
+                    // Retrying shifts the rest of the schedule by the time it took for the payment to succeed
+                    payment.next_payment_at = now + to_chrono_duration(self.config.retry_interval)?;
+                    let (id, attempts) = (payment.id, payment.failed_attempts);
+                    self.db.upsert_recurring_payment(payment).await?;
+                    self.publish_event(RecurringPaymentEvent::PaymentFailed(id, attempts));
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    async fn complete(&mut self, mut payment: RecurringPayment) -> Result<(), RecurringPaymentServiceError> {
+        payment.status = RecurringPaymentStatus::Completed;
+        let id = payment.id;
+        self.db.upsert_recurring_payment(payment).await?;
+        info!(target: LOG_TARGET, "Recurring payment {} completed", id);
+        self.publish_event(RecurringPaymentEvent::Completed(id));
+        Ok(())
+    }
+
+    fn publish_event(&self, event: RecurringPaymentEvent) {
+        let _ = self.event_publisher.send(Arc::new(event)).map_err(|e| {
+            trace!(
+                target: LOG_TARGET,
+                "Error sending event, usually because there are no subscribers: {:?}",
+                e
+            );
+            e
+        });
+    }
+}
+
+fn to_chrono_duration(duration: std::time::Duration) -> Result<ChronoDuration, RecurringPaymentServiceError> {
+    ChronoDuration::from_std(duration)
+        .map_err(|_| RecurringPaymentServiceError::InvalidSchedule(format!("Duration {:?} is too large", duration)))
+}
@@ -0,0 +1,337 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{output_manager_service::TxId, recurring_payment_service::error::RecurringPaymentStorageError};
+use chrono::{Duration as ChronoDuration, NaiveDateTime};
+use log::*;
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Error, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+
+const LOG_TARGET: &str = "wallet::recurring_payment_service::database";
+
+pub type RecurringPaymentId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecurringPaymentStatus {
+    /// Payments are being made according to the schedule
+    Active,
+    /// An end condition of the schedule was reached
+    Completed,
+    /// The schedule was cancelled by the user
+    Cancelled,
+    /// A payment could not be made after exhausting all retries
+    Failed,
+}
+
+impl TryFrom<i32> for RecurringPaymentStatus {
+    type Error = RecurringPaymentStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RecurringPaymentStatus::Active),
+            1 => Ok(RecurringPaymentStatus::Completed),
+            2 => Ok(RecurringPaymentStatus::Cancelled),
+            3 => Ok(RecurringPaymentStatus::Failed),
+            _ => Err(RecurringPaymentStorageError::ConversionError),
+        }
+    }
+}
+
+impl Display for RecurringPaymentStatus {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            RecurringPaymentStatus::Active => write!(f, "Active"),
+            RecurringPaymentStatus::Completed => write!(f, "Completed"),
+            RecurringPaymentStatus::Cancelled => write!(f, "Cancelled"),
+            RecurringPaymentStatus::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+/// A schedule of payments of a fixed amount to a single destination. The schedule ends when `max_payments` payments
+/// have been made or when the next payment would fall after `end_at`, whichever comes first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurringPayment {
+    pub id: RecurringPaymentId,
+    pub destination: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee_per_gram: MicroTari,
+    pub message: String,
+    pub interval: Duration,
+    pub next_payment_at: NaiveDateTime,
+    pub max_payments: Option<u32>,
+    pub end_at: Option<NaiveDateTime>,
+    pub payments_made: u32,
+    pub failed_attempts: u32,
+    pub status: RecurringPaymentStatus,
+    pub last_tx_id: Option<TxId>,
+    pub timestamp: NaiveDateTime,
+}
+
+impl RecurringPayment {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: RecurringPaymentId,
+        destination: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        interval: Duration,
+        first_payment_at: NaiveDateTime,
+        max_payments: Option<u32>,
+        end_at: Option<NaiveDateTime>,
+        timestamp: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id,
+            destination,
+            amount,
+            fee_per_gram,
+            message,
+            interval,
+            next_payment_at: first_payment_at,
+            max_payments,
+            end_at,
+            payments_made: 0,
+            failed_attempts: 0,
+            status: RecurringPaymentStatus::Active,
+            last_tx_id: None,
+            timestamp,
+        }
+    }
+
+    /// Returns true if the schedule is active and a payment is due at `now`
+    pub fn is_due(&self, now: NaiveDateTime) -> bool {
+        self.status == RecurringPaymentStatus::Active && self.next_payment_at <= now
+    }
+
+    /// Returns true if an end condition of the schedule has been reached
+    pub fn is_finished(&self, now: NaiveDateTime) -> bool {
+        self.max_payments.map_or(false, |max| self.payments_made >= max) ||
+            self.end_at
+                .map_or(false, |end_at| self.next_payment_at.max(now) > end_at)
+    }
+
+    /// Moves the next payment to the first interval after `now`. Payments missed while the wallet was offline are
+    /// skipped rather than being made all at once.
+    pub fn advance(&mut self, now: NaiveDateTime) {
+        let interval_secs = self.interval.as_secs().max(1) as i64;
+        if self.next_payment_at <= now {
+            let elapsed = (now - self.next_payment_at).num_seconds();
+            let intervals = elapsed / interval_secs + 1;
+            self.next_payment_at += ChronoDuration::seconds(intervals * interval_secs);
+        }
+    }
+}
+
+/// This trait defines the functionality that a database backend need to provide for the Recurring Payment Service
+pub trait RecurringPaymentBackend: Send + Sync + Clone {
+    /// Retrieve the record associated with the provided DbKey
+    fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, RecurringPaymentStorageError>;
+    /// Modify the state the of the backend with a write operation
+    fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, RecurringPaymentStorageError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbKey {
+    RecurringPayment(RecurringPaymentId),
+    RecurringPayments,
+}
+
+pub enum DbValue {
+    RecurringPayment(Box<RecurringPayment>),
+    RecurringPayments(Vec<RecurringPayment>),
+}
+
+pub enum DbKeyValuePair {
+    RecurringPayment(RecurringPaymentId, Box<RecurringPayment>),
+}
+
+pub enum WriteOperation {
+    Upsert(DbKeyValuePair),
+    Remove(DbKey),
+}
+
+pub struct RecurringPaymentDatabase<T>
+where T: RecurringPaymentBackend
+{
+    db: Arc<T>,
+}
+
+impl<T> Clone for RecurringPaymentDatabase<T>
+where T: RecurringPaymentBackend
+{
+    fn clone(&self) -> Self {
+        Self { db: self.db.clone() }
+    }
+}
+
+impl<T> RecurringPaymentDatabase<T>
+where T: RecurringPaymentBackend + 'static
+{
+    pub fn new(db: T) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    pub async fn get_recurring_payment(
+        &self,
+        id: RecurringPaymentId,
+    ) -> Result<RecurringPayment, RecurringPaymentStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let key = DbKey::RecurringPayment(id);
+            match db_clone.fetch(&key) {
+                Ok(None) => Err(RecurringPaymentStorageError::ValueNotFound(key)),
+                Ok(Some(DbValue::RecurringPayment(p))) => Ok(*p),
+                Ok(Some(other)) => unexpected_result(key, other),
+                Err(e) => log_error(key, e),
+            }
+        })
+        .await
+        .map_err(|err| RecurringPaymentStorageError::BlockingTaskSpawnError(err.to_string()))
+        .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn get_recurring_payments(&self) -> Result<Vec<RecurringPayment>, RecurringPaymentStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::RecurringPayments) {
+            Ok(None) => log_error(
+                DbKey::RecurringPayments,
+                RecurringPaymentStorageError::UnexpectedResult("Could not retrieve recurring payments".to_string()),
+            ),
+            Ok(Some(DbValue::RecurringPayments(p))) => Ok(p),
+            Ok(Some(other)) => unexpected_result(DbKey::RecurringPayments, other),
+            Err(e) => log_error(DbKey::RecurringPayments, e),
+        })
+        .await
+        .map_err(|err| RecurringPaymentStorageError::BlockingTaskSpawnError(err.to_string()))
+        .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn upsert_recurring_payment(
+        &self,
+        payment: RecurringPayment,
+    ) -> Result<(), RecurringPaymentStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Upsert(DbKeyValuePair::RecurringPayment(
+                payment.id,
+                Box::new(payment),
+            )))
+        })
+        .await
+        .map_err(|err| RecurringPaymentStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+}
+
+fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, RecurringPaymentStorageError> {
+    let msg = format!("Unexpected result for database query {}. Response: {}", req, res);
+    error!(target: LOG_TARGET, "{}", msg);
+    Err(RecurringPaymentStorageError::UnexpectedResult(msg))
+}
+
+impl Display for DbKey {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            DbKey::RecurringPayment(id) => f.write_str(&format!("Recurring Payment: {}", id)),
+            DbKey::RecurringPayments => f.write_str("Recurring Payments"),
+        }
+    }
+}
+
+impl Display for DbValue {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            DbValue::RecurringPayment(_) => f.write_str("Recurring Payment"),
+            DbValue::RecurringPayments(_) => f.write_str("Recurring Payments"),
+        }
+    }
+}
+
+fn log_error<T>(req: DbKey, err: RecurringPaymentStorageError) -> Result<T, RecurringPaymentStorageError> {
+    error!(
+        target: LOG_TARGET,
+        "Database access error on request: {}: {}",
+        req,
+        err.to_string()
+    );
+    Err(err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+    use tari_core::transactions::types::PublicKey;
+
+    #[test]
+    fn it_advances_past_missed_payments() {
+        let start = Utc::now().naive_utc();
+        let mut payment = RecurringPayment::new(
+            1,
+            PublicKey::default(),
+            MicroTari::from(100),
+            MicroTari::from(5),
+            "Rent".to_string(),
+            Duration::from_secs(60),
+            start,
+            None,
+            None,
+            start,
+        );
+        payment.advance(start + ChronoDuration::seconds(150));
+        assert_eq!(payment.next_payment_at, start + ChronoDuration::seconds(180));
+        payment.advance(start);
+        assert_eq!(payment.next_payment_at, start + ChronoDuration::seconds(180));
+    }
+
+    #[test]
+    fn it_checks_end_conditions() {
+        let start = Utc::now().naive_utc();
+        let mut payment = RecurringPayment::new(
+            1,
+            PublicKey::default(),
+            MicroTari::from(100),
+            MicroTari::from(5),
+            "Rent".to_string(),
+            Duration::from_secs(60),
+            start,
+            Some(2),
+            Some(start + ChronoDuration::seconds(90)),
+            start,
+        );
+        assert!(!payment.is_finished(start));
+        payment.payments_made = 1;
+        payment.advance(start);
+        assert!(!payment.is_finished(start));
+        assert!(payment.is_finished(start + ChronoDuration::seconds(91)));
+        payment.payments_made = 2;
+        assert!(payment.is_finished(start));
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    recurring_payment_service::{
+        error::RecurringPaymentStorageError,
+        storage::database::{
+            DbKey,
+            DbKeyValuePair,
+            DbValue,
+            RecurringPayment,
+            RecurringPaymentBackend,
+            RecurringPaymentId,
+            RecurringPaymentStatus,
+            WriteOperation,
+        },
+    },
+    schema::recurring_payments,
+    storage::sqlite_utilities::WalletDbConnection,
+};
+use chrono::NaiveDateTime;
+use diesel::{prelude::*, result::Error as DieselError, SqliteConnection};
+use std::{convert::TryFrom, time::Duration};
+use tari_core::transactions::{tari_amount::MicroTari, types::PublicKey};
+use tari_crypto::tari_utilities::ByteArray;
+
+/// A Sqlite backend for the Recurring Payment Service. The Backend is accessed via a connection pool to the Sqlite
+/// file.
+#[derive(Clone)]
+pub struct RecurringPaymentSqliteDatabase {
+    database_connection: WalletDbConnection,
+}
+
+impl RecurringPaymentSqliteDatabase {
+    pub fn new(database_connection: WalletDbConnection) -> Self {
+        Self { database_connection }
+    }
+}
+
+impl RecurringPaymentBackend for RecurringPaymentSqliteDatabase {
+    fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, RecurringPaymentStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        let result = match key {
+            DbKey::RecurringPayment(id) => match RecurringPaymentSql::find(*id, &(*conn)) {
+                Ok(p) => Some(DbValue::RecurringPayment(Box::new(RecurringPayment::try_from(p)?))),
+                Err(RecurringPaymentStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::RecurringPayments => Some(DbValue::RecurringPayments(
+                RecurringPaymentSql::index(&conn)?
+                    .into_iter()
+                    .map(RecurringPayment::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+        };
+
+        Ok(result)
+    }
+
+    fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, RecurringPaymentStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        match op {
+            WriteOperation::Upsert(kvp) => match kvp {
+                DbKeyValuePair::RecurringPayment(id, p) => match RecurringPaymentSql::find(id, &(*conn)) {
+                    Ok(found) => {
+                        found.update(UpdateRecurringPaymentSql::from(*p), &(*conn))?;
+                    },
+                    Err(RecurringPaymentStorageError::DieselError(DieselError::NotFound)) => {
+                        RecurringPaymentSql::from(*p).commit(&conn)?;
+                    },
+                    Err(e) => return Err(e),
+                },
+            },
+            WriteOperation::Remove(k) => match k {
+                DbKey::RecurringPayment(id) => match RecurringPaymentSql::find(id, &(*conn)) {
+                    Ok(p) => {
+                        p.delete(&conn)?;
+                        return Ok(Some(DbValue::RecurringPayment(Box::new(RecurringPayment::try_from(
+                            p,
+                        )?))));
+                    },
+                    Err(RecurringPaymentStorageError::DieselError(DieselError::NotFound)) => (),
+                    Err(e) => return Err(e),
+                },
+                DbKey::RecurringPayments => return Err(RecurringPaymentStorageError::OperationNotSupported),
+            },
+        }
+
+        Ok(None)
+    }
+}
+
+/// A Sql version of the RecurringPayment struct
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "recurring_payments"]
+struct RecurringPaymentSql {
+    id: i64,
+    destination_public_key: Vec<u8>,
+    amount: i64,
+    fee_per_gram: i64,
+    message: String,
+    interval_secs: i64,
+    next_payment_at: NaiveDateTime,
+    max_payments: Option<i32>,
+    end_at: Option<NaiveDateTime>,
+    payments_made: i32,
+    failed_attempts: i32,
+    status: i32,
+    last_tx_id: Option<i64>,
+    timestamp: NaiveDateTime,
+}
+
+impl RecurringPaymentSql {
+    /// Write this struct to the database
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), RecurringPaymentStorageError> {
+        diesel::insert_into(recurring_payments::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return all recurring payments, oldest first
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<RecurringPaymentSql>, RecurringPaymentStorageError> {
+        Ok(recurring_payments::table
+            .order(recurring_payments::timestamp.asc())
+            .load::<RecurringPaymentSql>(conn)?)
+    }
+
+    /// Find a particular recurring payment, if it exists
+    pub fn find(
+        id: RecurringPaymentId,
+        conn: &SqliteConnection,
+    ) -> Result<RecurringPaymentSql, RecurringPaymentStorageError> {
+        Ok(recurring_payments::table
+            .filter(recurring_payments::id.eq(id as i64))
+            .first::<RecurringPaymentSql>(conn)?)
+    }
+
+    pub fn delete(&self, conn: &SqliteConnection) -> Result<(), RecurringPaymentStorageError> {
+        let num_deleted =
+            diesel::delete(recurring_payments::table.filter(recurring_payments::id.eq(&self.id))).execute(conn)?;
+
+        if num_deleted == 0 {
+            return Err(RecurringPaymentStorageError::ValueNotFound(DbKey::RecurringPayment(
+                self.id as u64,
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn update(
+        &self,
+        update: UpdateRecurringPaymentSql,
+        conn: &SqliteConnection,
+    ) -> Result<(), RecurringPaymentStorageError> {
+        let num_updated = diesel::update(recurring_payments::table.filter(recurring_payments::id.eq(&self.id)))
+            .set(update)
+            .execute(conn)?;
+
+        if num_updated == 0 {
+            return Err(RecurringPaymentStorageError::UnexpectedResult(
+                "Database update error".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl From<RecurringPayment> for RecurringPaymentSql {
+    fn from(p: RecurringPayment) -> Self {
+        Self {
+            id: p.id as i64,
+            destination_public_key: p.destination.to_vec(),
+            amount: u64::from(p.amount) as i64,
+            fee_per_gram: u64::from(p.fee_per_gram) as i64,
+            message: p.message,
+            interval_secs: p.interval.as_secs() as i64,
+            next_payment_at: p.next_payment_at,
+            max_payments: p.max_payments.map(|m| m as i32),
+            end_at: p.end_at,
+            payments_made: p.payments_made as i32,
+            failed_attempts: p.failed_attempts as i32,
+            status: p.status as i32,
+            last_tx_id: p.last_tx_id.map(|id| id as i64),
+            timestamp: p.timestamp,
+        }
+    }
+}
+
+impl TryFrom<RecurringPaymentSql> for RecurringPayment {
+    type Error = RecurringPaymentStorageError;
+
+    fn try_from(p: RecurringPaymentSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: p.id as u64,
+            destination: PublicKey::from_vec(&p.destination_public_key)
+                .map_err(|_| RecurringPaymentStorageError::ConversionError)?,
+            amount: MicroTari::from(p.amount as u64),
+            fee_per_gram: MicroTari::from(p.fee_per_gram as u64),
+            message: p.message,
+            interval: Duration::from_secs(p.interval_secs as u64),
+            next_payment_at: p.next_payment_at,
+            max_payments: p.max_payments.map(|m| m as u32),
+            end_at: p.end_at,
+            payments_made: p.payments_made as u32,
+            failed_attempts: p.failed_attempts as u32,
+            status: RecurringPaymentStatus::try_from(p.status)?,
+            last_tx_id: p.last_tx_id.map(|id| id as u64),
+            timestamp: p.timestamp,
+        })
+    }
+}
+
+/// The fields of a recurring payment that change as payments are made
+#[derive(AsChangeset)]
+#[table_name = "recurring_payments"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct UpdateRecurringPaymentSql {
+    next_payment_at: NaiveDateTime,
+    payments_made: i32,
+    failed_attempts: i32,
+    status: i32,
+    last_tx_id: Option<i64>,
+}
+
+impl From<RecurringPayment> for UpdateRecurringPaymentSql {
+    fn from(p: RecurringPayment) -> Self {
+        Self {
+            next_payment_at: p.next_payment_at,
+            payments_made: p.payments_made as i32,
+            failed_attempts: p.failed_attempts as i32,
+            status: p.status as i32,
+            last_tx_id: p.last_tx_id.map(|id| id as i64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::recurring_payment_service::storage::{
+        database::{RecurringPayment, RecurringPaymentStatus},
+        sqlite_db::{RecurringPaymentSql, UpdateRecurringPaymentSql},
+    };
+    use chrono::Utc;
+    use diesel::{Connection, SqliteConnection};
+    use rand::rngs::OsRng;
+    use std::{convert::TryFrom, time::Duration};
+    use tari_core::transactions::{
+        tari_amount::MicroTari,
+        types::{PrivateKey, PublicKey},
+    };
+    use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait};
+    use tari_test_utils::{paths::with_temp_dir, random::string};
+
+    #[test]
+    fn test_crud() {
+        with_temp_dir(|dir_path| {
+            let db_name = format!("{}.sqlite3", string(8).as_str());
+            let db_path = format!("{}/{}", dir_path.to_str().unwrap(), db_name);
+
+            embed_migrations!("./migrations");
+            let conn =
+                SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+
+            embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+
+            let now = Utc::now().naive_utc();
+            let mut payments = Vec::new();
+            for i in 0..3 {
+                let payment = RecurringPayment::new(
+                    i,
+                    PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+                    MicroTari::from(1000 * (i + 1)),
+                    MicroTari::from(5),
+                    format!("Payment {}", i),
+                    Duration::from_secs(3600),
+                    now,
+                    Some(12),
+                    None,
+                    now,
+                );
+                RecurringPaymentSql::from(payment.clone()).commit(&conn).unwrap();
+                payments.push(payment);
+            }
+
+            let retrieved = RecurringPaymentSql::index(&conn).unwrap();
+            assert_eq!(retrieved.len(), 3);
+            assert_eq!(
+                payments[1],
+                RecurringPayment::try_from(RecurringPaymentSql::find(1, &conn).unwrap()).unwrap()
+            );
+
+            let mut updated = payments[2].clone();
+            updated.payments_made = 1;
+            updated.last_tx_id = Some(123);
+            updated.status = RecurringPaymentStatus::Completed;
+            RecurringPaymentSql::find(2, &conn)
+                .unwrap()
+                .update(UpdateRecurringPaymentSql::from(updated.clone()), &conn)
+                .unwrap();
+            assert_eq!(
+                updated,
+                RecurringPayment::try_from(RecurringPaymentSql::find(2, &conn).unwrap()).unwrap()
+            );
+
+            RecurringPaymentSql::from(payments[0].clone()).delete(&conn).unwrap();
+            assert_eq!(RecurringPaymentSql::index(&conn).unwrap().len(), 2);
+            assert!(RecurringPaymentSql::find(0, &conn).is_err());
+        });
+    }
+}
@@ -1,3 +1,14 @@
+table! {
+    audit_log (sequence) {
+        sequence -> BigInt,
+        timestamp -> Timestamp,
+        action -> Integer,
+        details -> Text,
+        prev_hash -> Binary,
+        hash -> Binary,
+    }
+}
+
 table! {
     client_key_values (key) {
         key -> Text,
@@ -31,6 +42,27 @@ table! {
     contacts (public_key) {
         public_key -> Binary,
         alias -> Text,
+        notes -> Nullable<Text>,
+    }
+}
+
+table! {
+    history_sync_companions (public_key) {
+        public_key -> Binary,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    history_sync_state (primary_public_key) {
+        primary_public_key -> Binary,
+        cursor_epoch -> Nullable<BigInt>,
+        cursor_sequence -> Nullable<BigInt>,
+        available_balance -> Nullable<BigInt>,
+        pending_incoming_balance -> Nullable<BigInt>,
+        pending_outgoing_balance -> Nullable<BigInt>,
+        time_locked_balance -> Nullable<BigInt>,
+        last_synced_at -> Nullable<Timestamp>,
     }
 }
 
@@ -49,6 +81,45 @@ table! {
     }
 }
 
+table! {
+    invoices (invoice_id) {
+        invoice_id -> BigInt,
+        counterparty_public_key -> Binary,
+        amount -> BigInt,
+        memo -> Text,
+        expires_at -> Timestamp,
+        direction -> Integer,
+        status -> Integer,
+        tx_id -> Nullable<BigInt>,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    key_index_reservations (branch, key_index) {
+        branch -> Text,
+        key_index -> BigInt,
+        consumer -> Text,
+        status -> Integer,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    key_manager_accounts (name) {
+        name -> Text,
+        key_index -> BigInt,
+    }
+}
+
+table! {
+    key_manager_branches (branch) {
+        branch -> Text,
+        key_index -> BigInt,
+        tracked_from -> BigInt,
+    }
+}
+
 table! {
     key_manager_states (id) {
         id -> Nullable<BigInt>,
@@ -102,6 +173,12 @@ table! {
         metadata_signature_nonce -> Binary,
         metadata_signature_u_key -> Binary,
         metadata_signature_v_key -> Binary,
+        covenant -> Binary,
+        account -> Text,
+        received_in_tx_id -> Nullable<BigInt>,
+        features_encrypted_data -> Binary,
+        features_parent_public_key -> Nullable<Binary>,
+        features_unique_id -> Nullable<Binary>,
     }
 }
 
@@ -114,6 +191,50 @@ table! {
     }
 }
 
+table! {
+    recurring_payments (id) {
+        id -> BigInt,
+        destination_public_key -> Binary,
+        amount -> BigInt,
+        fee_per_gram -> BigInt,
+        message -> Text,
+        interval_secs -> BigInt,
+        next_payment_at -> Timestamp,
+        max_payments -> Nullable<Integer>,
+        end_at -> Nullable<Timestamp>,
+        payments_made -> Integer,
+        failed_attempts -> Integer,
+        status -> Integer,
+        last_tx_id -> Nullable<BigInt>,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    synced_transactions (tx_id) {
+        tx_id -> BigInt,
+        source_public_key -> Binary,
+        destination_public_key -> Binary,
+        amount -> BigInt,
+        fee -> BigInt,
+        status -> Integer,
+        direction -> Integer,
+        message -> Text,
+        timestamp -> Timestamp,
+        cancelled -> Integer,
+        mined_height -> Nullable<BigInt>,
+        confirmations -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    transaction_idempotency_keys (idempotency_key) {
+        idempotency_key -> Text,
+        tx_id -> BigInt,
+        timestamp -> Timestamp,
+    }
+}
+
 table! {
     wallet_settings (key) {
         key -> Text,
@@ -122,14 +243,24 @@ table! {
 }
 
 allow_tables_to_appear_in_same_query!(
+    audit_log,
     client_key_values,
     completed_transactions,
     contacts,
+    history_sync_companions,
+    history_sync_state,
     inbound_transactions,
+    invoices,
+    key_index_reservations,
+    key_manager_accounts,
+    key_manager_branches,
     key_manager_states,
     known_one_sided_payment_scripts,
     outbound_transactions,
     outputs,
     pending_transaction_outputs,
+    recurring_payments,
+    synced_transactions,
+    transaction_idempotency_keys,
     wallet_settings,
 );
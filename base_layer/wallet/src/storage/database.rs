@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::error::WalletStorageError;
+use crate::{audit_log_service::storage::database::AuditLogBackend, error::WalletStorageError};
 use aes_gcm::Aes256Gcm;
 use log::*;
 use std::{
@@ -37,8 +37,9 @@ use tari_comms::{
 
 const LOG_TARGET: &str = "wallet::database";
 
-/// This trait defines the functionality that a database backend need to provide for the Contacts Service
-pub trait WalletBackend: Send + Sync + Clone {
+/// This trait defines the functionality that a database backend need to provide for the Contacts Service. The audit log
+/// is kept in the wallet database, so the backend also provides its storage.
+pub trait WalletBackend: AuditLogBackend + Send + Sync + Clone {
     /// Retrieve the record associated with the provided DbKey
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, WalletStorageError>;
     /// Modify the state the of the backend with a write operation
@@ -58,6 +59,7 @@ pub enum DbKey {
     ClientKey(String),
     MasterSecretKey,
     MasterPublicKey,
+    PassphraseSalt,
 }
 
 pub enum DbValue {
@@ -69,6 +71,7 @@ pub enum DbValue {
     BaseNodeChainMetadata(ChainMetadata),
     MasterSecretKey(CommsSecretKey),
     MasterPublicKey(CommsPublicKey),
+    PassphraseSalt(Vec<u8>),
 }
 
 #[derive(Clone)]
@@ -79,6 +82,9 @@ pub enum DbKeyValuePair {
     MasterSecretKey(CommsSecretKey),
     CommsAddress(Multiaddr),
     CommsFeatures(PeerFeatures),
+    /// The salt used to derive the encryption key from the wallet passphrase. It can only be set while the database
+    /// is unencrypted.
+    PassphraseSalt(Vec<u8>),
 }
 
 pub enum WriteOperation {
@@ -98,6 +104,12 @@ where T: WalletBackend + 'static
         Self { db: Arc::new(db) }
     }
 
+    /// Returns a clone of the underlying backend so that services sharing the wallet database can be given their own
+    /// handle to it
+    pub fn backend(&self) -> T {
+        (*self.db).clone()
+    }
+
     pub async fn get_master_secret_key(&self) -> Result<Option<CommsSecretKey>, WalletStorageError> {
         let db_clone = self.db.clone();
 
@@ -131,6 +143,31 @@ where T: WalletBackend + 'static
         Ok(())
     }
 
+    pub async fn get_passphrase_salt(&self) -> Result<Option<Vec<u8>>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let c = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::PassphraseSalt) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::PassphraseSalt(salt))) => Ok(Some(salt)),
+            Ok(Some(other)) => unexpected_result(DbKey::PassphraseSalt, other),
+            Err(e) => log_error(DbKey::PassphraseSalt, e),
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(c)
+    }
+
+    pub async fn set_passphrase_salt(&self, salt: Vec<u8>) -> Result<(), WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PassphraseSalt(salt)))
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
     pub async fn get_tor_id(&self) -> Result<Option<TorIdentity>, WalletStorageError> {
         let db_clone = self.db.clone();
 
@@ -317,6 +354,7 @@ impl Display for DbKey {
         match self {
             DbKey::MasterSecretKey => f.write_str(&"MasterSecretKey".to_string()),
             DbKey::MasterPublicKey => f.write_str(&"MasterPublicKey".to_string()),
+            DbKey::PassphraseSalt => f.write_str(&"PassphraseSalt".to_string()),
             DbKey::CommsAddress => f.write_str(&"CommsAddress".to_string()),
             DbKey::CommsFeatures => f.write_str(&"Node features".to_string()),
             DbKey::TorId => f.write_str(&"TorId".to_string()),
@@ -331,6 +369,7 @@ impl Display for DbValue {
         match self {
             DbValue::MasterSecretKey(k) => f.write_str(&format!("MasterSecretKey: {:?}", k)),
             DbValue::MasterPublicKey(k) => f.write_str(&format!("MasterPublicKey: {:?}", k)),
+            DbValue::PassphraseSalt(_) => f.write_str(&"PassphraseSalt".to_string()),
             DbValue::ClientValue(v) => f.write_str(&format!("ClientValue: {:?}", v)),
             DbValue::ValueCleared => f.write_str(&"ValueCleared".to_string()),
             DbValue::CommsFeatures(_) => f.write_str(&"Node features".to_string()),
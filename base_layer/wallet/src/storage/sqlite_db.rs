@@ -21,8 +21,12 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    audit_log_service::{
+        error::AuditLogStorageError,
+        storage::database::{AuditAction, AuditLogBackend, AuditLogEntry},
+    },
     error::WalletStorageError,
-    schema::{client_key_values, wallet_settings},
+    schema::{audit_log, client_key_values, wallet_settings},
     storage::{
         database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
         sqlite_utilities::WalletDbConnection,
@@ -34,9 +38,11 @@ use aes_gcm::{
     Aes256Gcm,
     Error as AeadError,
 };
+use chrono::NaiveDateTime;
 use diesel::{prelude::*, SqliteConnection};
 use log::*;
 use std::{
+    convert::TryFrom,
     str::{from_utf8, FromStr},
     sync::{Arc, RwLock},
 };
@@ -74,6 +80,18 @@ impl WalletSqliteDatabase {
         })
     }
 
+    /// Returns the salt used to derive the encryption key from the wallet passphrase. Wallets that were encrypted
+    /// before the salt was introduced do not have one.
+    pub fn get_passphrase_salt(
+        database_connection: &WalletDbConnection,
+    ) -> Result<Option<Vec<u8>>, WalletStorageError> {
+        let conn = database_connection.acquire_lock();
+        match WalletSettingSql::get(DbKey::PassphraseSalt.to_string(), &conn)? {
+            Some(salt) => Ok(Some(from_hex(&salt)?)),
+            None => Ok(None),
+        }
+    }
+
     fn set_master_secret_key(
         &self,
         secret_key: &CommsSecretKey,
@@ -247,6 +265,13 @@ impl WalletSqliteDatabase {
             DbKeyValuePair::CommsFeatures(cf) => {
                 WalletSettingSql::new(DbKey::CommsFeatures.to_string(), cf.bits().to_string()).set(&conn)?;
             },
+            DbKeyValuePair::PassphraseSalt(salt) => {
+                // Replacing the salt of an encrypted database would make its key impossible to derive
+                if acquire_read_lock!(self.cipher).is_some() {
+                    return Err(WalletStorageError::AlreadyEncrypted);
+                }
+                WalletSettingSql::new(DbKey::PassphraseSalt.to_string(), salt.to_hex()).set(&conn)?;
+            },
         }
         Ok(None)
     }
@@ -258,6 +283,7 @@ impl WalletSqliteDatabase {
                 let _ = WalletSettingSql::clear(DbKey::MasterSecretKey.to_string(), &conn)?;
             },
             DbKey::MasterPublicKey => return Err(WalletStorageError::OperationNotSupported),
+            DbKey::PassphraseSalt => return Err(WalletStorageError::OperationNotSupported),
             DbKey::ClientKey(k) => {
                 if ClientKeyValueSql::clear(&k, &conn)? {
                     return Ok(Some(DbValue::ValueCleared));
@@ -293,6 +319,10 @@ impl WalletBackend for WalletSqliteDatabase {
                     None
                 }
             },
+            DbKey::PassphraseSalt => match WalletSettingSql::get(key.to_string(), &conn)? {
+                Some(salt) => Some(DbValue::PassphraseSalt(from_hex(&salt)?)),
+                None => None,
+            },
             DbKey::ClientKey(k) => match ClientKeyValueSql::get(k, &conn)? {
                 None => None,
                 Some(mut v) => {
@@ -395,6 +425,9 @@ impl WalletBackend for WalletSqliteDatabase {
             WalletSettingSql::new(DbKey::TorId.to_string(), tor_string).set(&conn)?;
         }
 
+        // A new salt is generated if encryption is applied again
+        let _ = WalletSettingSql::clear(DbKey::PassphraseSalt.to_string(), &conn)?;
+
         // Now that all the decryption has been completed we can safely remove the cipher fully
         let _ = (*current_cipher).take();
 
@@ -604,17 +637,124 @@ impl Encryptable<Aes256Gcm> for ClientKeyValueSql {
     }
 }
 
+impl AuditLogBackend for WalletSqliteDatabase {
+    fn append_audit_entry(
+        &self,
+        action: AuditAction,
+        details: &str,
+        timestamp: NaiveDateTime,
+    ) -> Result<AuditLogEntry, AuditLogStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        conn.transaction::<_, AuditLogStorageError, _>(|| {
+            let last_entry = AuditLogEntrySql::last(&conn)?
+                .map(AuditLogEntry::try_from)
+                .transpose()?;
+            let entry = AuditLogEntry::new(last_entry.as_ref(), action, details.to_string(), timestamp);
+            AuditLogEntrySql::from(entry.clone()).commit(&conn)?;
+            Ok(entry)
+        })
+    }
+
+    fn fetch_audit_entries(
+        &self,
+        from_sequence: u64,
+        limit: usize,
+    ) -> Result<Vec<AuditLogEntry>, AuditLogStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        AuditLogEntrySql::index_from(from_sequence, limit, &conn)?
+            .into_iter()
+            .map(AuditLogEntry::try_from)
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "audit_log"]
+struct AuditLogEntrySql {
+    sequence: i64,
+    timestamp: NaiveDateTime,
+    action: i32,
+    details: String,
+    prev_hash: Vec<u8>,
+    hash: Vec<u8>,
+}
+
+impl AuditLogEntrySql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), AuditLogStorageError> {
+        diesel::insert_into(audit_log::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn last(conn: &SqliteConnection) -> Result<Option<AuditLogEntrySql>, AuditLogStorageError> {
+        Ok(audit_log::table
+            .order(audit_log::sequence.desc())
+            .first::<AuditLogEntrySql>(conn)
+            .optional()?)
+    }
+
+    /// Return up to `limit` entries ordered by sequence number, starting at `from_sequence`
+    pub fn index_from(
+        from_sequence: u64,
+        limit: usize,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<AuditLogEntrySql>, AuditLogStorageError> {
+        Ok(audit_log::table
+            .filter(audit_log::sequence.ge(from_sequence as i64))
+            .order(audit_log::sequence.asc())
+            .limit(limit as i64)
+            .load::<AuditLogEntrySql>(conn)?)
+    }
+}
+
+impl From<AuditLogEntry> for AuditLogEntrySql {
+    fn from(entry: AuditLogEntry) -> Self {
+        Self {
+            sequence: entry.sequence as i64,
+            timestamp: entry.timestamp,
+            action: i32::from(entry.action),
+            details: entry.details,
+            prev_hash: entry.prev_hash,
+            hash: entry.hash,
+        }
+    }
+}
+
+impl TryFrom<AuditLogEntrySql> for AuditLogEntry {
+    type Error = AuditLogStorageError;
+
+    fn try_from(entry: AuditLogEntrySql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sequence: entry.sequence as u64,
+            timestamp: entry.timestamp,
+            action: AuditAction::try_from(entry.action)?,
+            details: entry.details,
+            prev_hash: entry.prev_hash,
+            hash: entry.hash,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::storage::{
-        database::{DbKey, DbValue, WalletBackend},
-        sqlite_db::{ClientKeyValueSql, WalletSettingSql, WalletSqliteDatabase},
-        sqlite_utilities::run_migration_and_create_sqlite_connection,
+    use crate::{
+        audit_log_service::storage::database::{AuditAction, AuditLogBackend},
+        schema::audit_log,
+        storage::{
+            database::{DbKey, DbValue, WalletBackend},
+            sqlite_db::{ClientKeyValueSql, WalletSettingSql, WalletSqliteDatabase},
+            sqlite_utilities::run_migration_and_create_sqlite_connection,
+        },
     };
     use aes_gcm::{
         aead::{generic_array::GenericArray, Aead, NewAead},
         Aes256Gcm,
     };
+    use chrono::Utc;
+    use diesel::prelude::*;
     use rand::{rngs::OsRng, RngCore};
     use tari_comms::types::{CommsPublicKey, CommsSecretKey};
     use tari_crypto::{
@@ -865,4 +1005,43 @@ mod test {
             panic!("Should find value2");
         }
     }
+
+    #[test]
+    fn test_audit_log_is_hash_chained_and_append_only() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let tempdir = tempdir().unwrap();
+        let db_folder = tempdir.path().to_str().unwrap().to_string();
+        let connection = run_migration_and_create_sqlite_connection(&format!("{}{}", db_folder, db_name)).unwrap();
+        let db = WalletSqliteDatabase::new(connection.clone(), None).unwrap();
+
+        let actions = [
+            AuditAction::KeyDerived,
+            AuditAction::TransactionSigned,
+            AuditAction::TransactionCancelled,
+        ];
+        for (i, action) in actions.iter().enumerate() {
+            let entry = db
+                .append_audit_entry(*action, &format!("operation {}", i), Utc::now().naive_utc())
+                .unwrap();
+            assert_eq!(entry.sequence, i as u64 + 1);
+        }
+
+        let entries = db.fetch_audit_entries(1, 10).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].is_valid_successor_of(None));
+        assert!(entries[1].is_valid_successor_of(Some(&entries[0])));
+        assert!(entries[2].is_valid_successor_of(Some(&entries[1])));
+        assert_eq!(entries[2].action, AuditAction::TransactionCancelled);
+
+        let entries = db.fetch_audit_entries(2, 1).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].sequence, 2);
+
+        let conn = connection.acquire_lock();
+        assert!(diesel::delete(audit_log::table).execute(&*conn).is_err());
+        assert!(diesel::update(audit_log::table)
+            .set(audit_log::details.eq("tampered"))
+            .execute(&*conn)
+            .is_err());
+    }
 }
@@ -23,16 +23,14 @@
 use crate::{
     contacts_service::storage::sqlite_db::ContactsServiceSqliteDatabase,
     error::WalletStorageError,
+    history_sync_service::storage::sqlite_db::HistorySyncSqliteDatabase,
     output_manager_service::storage::sqlite_db::OutputManagerSqliteDatabase,
+    recurring_payment_service::storage::sqlite_db::RecurringPaymentSqliteDatabase,
     storage::{database::WalletDatabase, sqlite_db::WalletSqliteDatabase},
     transaction_service::storage::sqlite_db::TransactionServiceSqliteDatabase,
-};
-use aes_gcm::{
-    aead::{generic_array::GenericArray, NewAead},
-    Aes256Gcm,
+    util::encryption::{cipher_from_passphrase, legacy_cipher_from_passphrase},
 };
 use diesel::{Connection, SqliteConnection};
-use digest::Digest;
 use fs2::FileExt;
 use log::*;
 use std::{
@@ -40,7 +38,6 @@ use std::{
     path::{Path, PathBuf},
     sync::{Arc, Mutex, MutexGuard},
 };
-use tari_crypto::common::Blake256;
 
 const LOG_TARGET: &str = "wallet::storage:sqlite_utilities";
 
@@ -146,15 +143,11 @@ pub fn initialize_sqlite_database_backends(
         TransactionServiceSqliteDatabase,
         OutputManagerSqliteDatabase,
         ContactsServiceSqliteDatabase,
+        RecurringPaymentSqliteDatabase,
+        HistorySyncSqliteDatabase,
     ),
     WalletStorageError,
 > {
-    let cipher = passphrase.map(|passphrase_str| {
-        let passphrase_hash = Blake256::new().chain(passphrase_str.as_bytes()).finalize();
-        let key = GenericArray::from_slice(passphrase_hash.as_slice());
-        Aes256Gcm::new(key)
-    });
-
     let connection = run_migration_and_create_sqlite_connection(&db_path).map_err(|e| {
         error!(
             target: LOG_TARGET,
@@ -163,15 +156,30 @@ pub fn initialize_sqlite_database_backends(
         e
     })?;
 
+    let cipher = match passphrase {
+        None => None,
+        Some(passphrase) => match WalletSqliteDatabase::get_passphrase_salt(&connection)? {
+            Some(salt) => Some(
+                cipher_from_passphrase(&passphrase, &salt)
+                    .map_err(|e| WalletStorageError::AeadError(format!("Key derivation error: {}", e)))?,
+            ),
+            None => Some(legacy_cipher_from_passphrase(&passphrase)),
+        },
+    };
+
     let wallet_backend = WalletSqliteDatabase::new(connection.clone(), cipher.clone())?;
     let transaction_backend = TransactionServiceSqliteDatabase::new(connection.clone(), cipher.clone());
     let output_manager_backend = OutputManagerSqliteDatabase::new(connection.clone(), cipher);
-    let contacts_backend = ContactsServiceSqliteDatabase::new(connection);
+    let contacts_backend = ContactsServiceSqliteDatabase::new(connection.clone());
+    let recurring_payment_backend = RecurringPaymentSqliteDatabase::new(connection.clone());
+    let history_sync_backend = HistorySyncSqliteDatabase::new(connection);
 
     Ok((
         wallet_backend,
         transaction_backend,
         output_manager_backend,
         contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
     ))
 }
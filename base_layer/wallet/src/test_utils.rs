@@ -22,14 +22,24 @@
 
 use crate::{
     contacts_service::storage::sqlite_db::ContactsServiceSqliteDatabase,
+    history_sync_service::storage::sqlite_db::HistorySyncSqliteDatabase,
+    key_manager_service::{
+        handle::KeyManagerHandle,
+        service::KeyManagerService,
+        storage::database::{KeyManagerBackend, KeyManagerDatabase},
+    },
     output_manager_service::storage::sqlite_db::OutputManagerSqliteDatabase,
+    recurring_payment_service::storage::sqlite_db::RecurringPaymentSqliteDatabase,
     storage::{sqlite_db::WalletSqliteDatabase, sqlite_utilities::run_migration_and_create_sqlite_connection},
     transaction_service::storage::sqlite_db::TransactionServiceSqliteDatabase,
 };
 use core::iter;
 use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
 use std::path::Path;
+use tari_service_framework::reply_channel;
+use tari_shutdown::ShutdownSignal;
 use tempfile::{tempdir, TempDir};
+use tokio::runtime;
 
 pub fn random_string(len: usize) -> String {
     iter::repeat(())
@@ -46,6 +56,8 @@ pub fn make_wallet_databases(
     TransactionServiceSqliteDatabase,
     OutputManagerSqliteDatabase,
     ContactsServiceSqliteDatabase,
+    RecurringPaymentSqliteDatabase,
+    HistorySyncSqliteDatabase,
     Option<TempDir>,
 ) {
     let (path_string, temp_dir): (String, Option<TempDir>) = if let Some(p) = path {
@@ -65,7 +77,24 @@ pub fn make_wallet_databases(
         WalletSqliteDatabase::new(connection.clone(), None).expect("Should be able to create wallet database"),
         TransactionServiceSqliteDatabase::new(connection.clone(), None),
         OutputManagerSqliteDatabase::new(connection.clone(), None),
-        ContactsServiceSqliteDatabase::new(connection),
+        ContactsServiceSqliteDatabase::new(connection.clone()),
+        RecurringPaymentSqliteDatabase::new(connection.clone()),
+        HistorySyncSqliteDatabase::new(connection),
         temp_dir,
     )
 }
+
+/// Spawn a Key Manager Service on the given runtime for services that are constructed outside of a service stack
+pub fn spawn_key_manager_service<T>(
+    handle: &runtime::Handle,
+    backend: T,
+    shutdown_signal: ShutdownSignal,
+) -> KeyManagerHandle
+where
+    T: KeyManagerBackend + 'static,
+{
+    let (sender, receiver) = reply_channel::unbounded();
+    let service = KeyManagerService::new(receiver, KeyManagerDatabase::new(backend), shutdown_signal, None);
+    handle.spawn(service.start());
+    KeyManagerHandle::new(sender)
+}
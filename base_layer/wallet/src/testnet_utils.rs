@@ -25,10 +25,15 @@ use crate::{
         sqlite_db::ContactsServiceSqliteDatabase,
     },
     error::{WalletError, WalletStorageError},
+    history_sync_service::storage::{database::HistorySyncBackend, sqlite_db::HistorySyncSqliteDatabase},
     output_manager_service::{
         storage::{database::OutputManagerBackend, sqlite_db::OutputManagerSqliteDatabase},
         TxId,
     },
+    recurring_payment_service::storage::{
+        database::RecurringPaymentBackend,
+        sqlite_db::RecurringPaymentSqliteDatabase,
+    },
     storage::{
         database::{DbKeyValuePair, WalletBackend, WalletDatabase, WriteOperation},
         sqlite_db::WalletSqliteDatabase,
@@ -121,6 +126,8 @@ pub async fn create_wallet(
     TransactionServiceSqliteDatabase,
     OutputManagerSqliteDatabase,
     ContactsServiceSqliteDatabase,
+    RecurringPaymentSqliteDatabase,
+    HistorySyncSqliteDatabase,
 > {
     let factories = CryptoFactories::default();
 
@@ -152,6 +159,7 @@ pub async fn create_wallet(
         dns_seeds: Default::default(),
         dns_seeds_name_server: "1.1.1.1:53".parse().unwrap(),
         dns_seeds_use_dnssec: false,
+        min_peer_minor_version: 0,
         peer_seeds: Default::default(),
     };
 
@@ -167,7 +175,7 @@ pub async fn create_wallet(
         None,
     );
 
-    let (db, backend, oms_backend, contacts_backend, _) =
+    let (db, backend, oms_backend, contacts_backend, recurring_payment_backend, history_sync_backend, _) =
         make_wallet_databases(Some(datastore_path.to_str().unwrap().to_string()));
 
     let metadata = ChainMetadata::new(std::u64::MAX, Vec::new(), 0, 0, 0);
@@ -180,6 +188,8 @@ pub async fn create_wallet(
         backend,
         oms_backend,
         contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
         shutdown_signal,
         None,
     )
@@ -198,9 +208,11 @@ pub async fn generate_wallet_test_data<
     U: TransactionBackend,
     V: OutputManagerBackend,
     W: ContactsBackend,
+    X: RecurringPaymentBackend,
+    Y: HistorySyncBackend,
     P: AsRef<Path>,
 >(
-    wallet: &mut Wallet<T, U, V, W>,
+    wallet: &mut Wallet<T, U, V, W, X, Y>,
     data_path: P,
     transaction_service_backend: U,
 ) -> Result<(), WalletError> {
@@ -247,6 +259,7 @@ pub async fn generate_wallet_test_data<
             .upsert_contact(Contact {
                 alias: names[i].to_string(),
                 public_key: public_key.clone(),
+                notes: None,
             })
             .await?;
 
@@ -736,8 +749,10 @@ pub async fn complete_sent_transaction<
     U: TransactionBackend,
     V: OutputManagerBackend,
     W: ContactsBackend,
+    X: RecurringPaymentBackend,
+    Y: HistorySyncBackend,
 >(
-    wallet: &mut Wallet<T, U, V, W>,
+    wallet: &mut Wallet<T, U, V, W, X, Y>,
     tx_id: TxId,
 ) -> Result<(), WalletError> {
     let pending_outbound_tx = wallet.transaction_service.get_pending_outbound_transactions().await?;
@@ -785,8 +800,10 @@ pub async fn receive_test_transaction<
     U: TransactionBackend,
     V: OutputManagerBackend,
     W: ContactsBackend,
+    X: RecurringPaymentBackend,
+    Y: HistorySyncBackend,
 >(
-    wallet: &mut Wallet<T, U, V, W>,
+    wallet: &mut Wallet<T, U, V, W, X, Y>,
     handle: &Handle,
 ) -> Result<(), WalletError> {
     let contacts = wallet.contacts_service.get_contacts().await.unwrap();
@@ -818,8 +835,10 @@ pub async fn finalize_received_transaction<
     U: TransactionBackend,
     V: OutputManagerBackend,
     W: ContactsBackend,
+    X: RecurringPaymentBackend,
+    Y: HistorySyncBackend,
 >(
-    wallet: &mut Wallet<T, U, V, W>,
+    wallet: &mut Wallet<T, U, V, W, X, Y>,
     tx_id: TxId,
 ) -> Result<(), WalletError> {
     wallet.transaction_service.test_finalize_transaction(tx_id).await?;
@@ -836,8 +855,10 @@ pub async fn broadcast_transaction<
     U: TransactionBackend,
     V: OutputManagerBackend,
     W: ContactsBackend,
+    X: RecurringPaymentBackend,
+    Y: HistorySyncBackend,
 >(
-    wallet: &mut Wallet<T, U, V, W>,
+    wallet: &mut Wallet<T, U, V, W, X, Y>,
     tx_id: TxId,
 ) -> Result<(), WalletError> {
     wallet.transaction_service.test_broadcast_transaction(tx_id).await?;
@@ -849,8 +870,15 @@ pub async fn broadcast_transaction<
 /// the event when a CompletedTransaction that is in the Broadcast status, is in a mempool but not mined, beocmes
 /// mined/confirmed. After this function is called the status of the CompletedTransaction becomes `Mined` and the funds
 /// that were pending become spent and available respectively.
-pub async fn mine_transaction<T: WalletBackend, U: TransactionBackend, V: OutputManagerBackend, W: ContactsBackend>(
-    wallet: &mut Wallet<T, U, V, W>,
+pub async fn mine_transaction<
+    T: WalletBackend,
+    U: TransactionBackend,
+    V: OutputManagerBackend,
+    W: ContactsBackend,
+    X: RecurringPaymentBackend,
+    Y: HistorySyncBackend,
+>(
+    wallet: &mut Wallet<T, U, V, W, X, Y>,
     tx_id: TxId,
 ) -> Result<(), WalletError> {
     wallet.transaction_service.test_mine_transaction(tx_id).await?;
@@ -20,8 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::output_manager_service::handle::ReceiveOutputOptions;
 use log::*;
 use std::{fmt, time::Duration};
+#[cfg(feature = "chaos")]
+use tari_comms::pipeline::fault_injection::FaultInjector;
 
 const LOG_TARGET: &str = "wallet::transaction_service::config";
 
@@ -38,6 +41,14 @@ pub struct TransactionServiceConfig {
     pub num_confirmations_required: u64,
     pub max_tx_query_batch_size: usize,
     pub transaction_routing_mechanism: TransactionRoutingMechanism,
+    /// Customises the outputs created for received transactions, e.g. to receive funds into a time-locked output
+    pub receive_output_options: ReceiveOutputOptions,
+    /// If set, one-sided payments can be reclaimed by the sender once this many blocks have been mined after they were
+    /// sent, as long as the recipient has not spent them by then
+    pub one_sided_reclaim_lock_blocks: Option<u64>,
+    /// Drops and delays the RPC requests made to the base node by the transaction protocols, for soak testing
+    #[cfg(feature = "chaos")]
+    pub fault_injector: Option<FaultInjector>,
 }
 
 impl Default for TransactionServiceConfig {
@@ -54,6 +65,10 @@ impl Default for TransactionServiceConfig {
             num_confirmations_required: 3,
             max_tx_query_batch_size: 5000,
             transaction_routing_mechanism: TransactionRoutingMechanism::default(),
+            receive_output_options: ReceiveOutputOptions::default(),
+            one_sided_reclaim_lock_blocks: None,
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
         }
     }
 }
@@ -21,8 +21,9 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    base_node_service::error::BaseNodeServiceError,
     output_manager_service::{error::OutputManagerError, TxId},
-    transaction_service::storage::database::DbKey,
+    transaction_service::{payment_proof::PaymentProofError, storage::database::DbKey},
 };
 use diesel::result::Error as DieselError;
 use futures::channel::oneshot::Canceled;
@@ -67,6 +68,8 @@ pub enum TransactionServiceError {
     InvalidSourcePublicKey,
     #[error("The transaction does not contain the receivers output")]
     ReceiverOutputNotFound,
+    #[error("Prepared transaction (Id: {0}) not found")]
+    PreparedTransactionNotFound(TxId),
     #[error("Outbound Service send failed")]
     OutboundSendFailure,
     #[error(
@@ -78,6 +81,8 @@ pub enum TransactionServiceError {
     DiscoveryProcessFailed(TxId),
     #[error("Invalid Completed Transaction provided")]
     InvalidCompletedTransaction,
+    #[error("Transaction `{0}` is not an unconfirmed inbound transaction that can be sped up")]
+    ChildPaysForParentNotPossible(TxId),
     #[error("No Base Node public keys are provided for Base chain broadcast and monitoring")]
     NoBaseNodeKeysProvided,
     #[error("Error sending data to Protocol via register channels")]
@@ -131,8 +136,30 @@ pub enum TransactionServiceError {
     MempoolRejectionDoubleSpend,
     #[error("Transaction detected as rejected by mempool due to invalid transaction")]
     MempoolRejectionInvalidTransaction,
+    #[error("Transaction rejected by the base node relay policy: fee per gram is too low, try a higher fee")]
+    MempoolRejectionFeeTooLow,
+    #[error("Transaction rejected by the base node relay policy: an output script is too large")]
+    MempoolRejectionScriptTooLarge,
+    #[error("Transaction rejected by the base node relay policy: too many outputs")]
+    MempoolRejectionTooManyOutputs,
+    #[error("Transaction rejected by the base node relay policy: fee is too low for the number of outputs")]
+    MempoolRejectionDustOutput,
+    #[error(
+        "Transaction rejected by the base node mempool: too many unconfirmed transactions carry the same unique id"
+    )]
+    MempoolRejectionTooManyUnconfirmedForUniqueId,
+    #[error("Transaction rejected by the base node mempool: the unconfirmed weight limit for the sender was exceeded")]
+    MempoolRejectionSenderWeightLimitExceeded,
+    #[error("Send all to self transactions are not supported")]
+    SendAllToSelf,
+    #[error("The operation is not supported")]
+    OperationNotSupported,
     #[error("Transaction is malformed")]
     InvalidTransaction,
+    #[error("Invoice (Id: {0}) has expired")]
+    InvoiceExpired(u64),
+    #[error("Invoice (Id: {0}) cannot be paid: `{1}`")]
+    InvoiceNotPayable(u64, String),
     #[error("RpcError: `{0}`")]
     RpcError(#[from] RpcError),
     #[error("Protobuf Conversion Error: `{0}`")]
@@ -141,6 +168,14 @@ pub enum TransactionServiceError {
     MaximumAttemptsExceeded,
     #[error("Byte array error")]
     ByteArrayError(#[from] tari_crypto::tari_utilities::ByteArrayError),
+    #[error("Base node service error: `{0}`")]
+    BaseNodeServiceError(#[from] BaseNodeServiceError),
+    #[error("The chain tip height is not known yet")]
+    ChainTipUnknown,
+    #[error("Transaction `{0}` is not a mined outbound one-sided payment")]
+    OneSidedPaymentNotReclaimable(TxId),
+    #[error("Payment proof error: `{0}`")]
+    PaymentProofError(#[from] PaymentProofError),
 }
 
 #[derive(Debug, Error)]
@@ -179,6 +214,8 @@ pub enum TransactionStorageError {
     AeadError(String),
     #[error("Transaction (TxId: '{0}') is not mined")]
     TransactionNotMined(TxId),
+    #[error("Idempotency key has already been used")]
+    IdempotencyKeyAlreadyExists,
 }
 
 /// This error type is used to return TransactionServiceErrors from inside a Transaction Service protocol but also
@@ -24,14 +24,30 @@ use crate::{
     output_manager_service::TxId,
     transaction_service::{
         error::TransactionServiceError,
-        storage::models::{CompletedTransaction, InboundTransaction, OutboundTransaction, WalletTransaction},
+        payment_proof::PaymentProof,
+        storage::{
+            database::{
+                CompletedTransactionFilter,
+                CompletedTransactionPage,
+                TransactionCursor,
+                TransactionSearchPage,
+            },
+            models::{
+                CompletedTransaction,
+                InboundTransaction,
+                Invoice,
+                InvoiceId,
+                OutboundTransaction,
+                WalletTransaction,
+            },
+        },
     },
 };
 use aes_gcm::Aes256Gcm;
 use futures::{stream::Fuse, StreamExt};
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
 use tari_comms::types::CommsPublicKey;
-use tari_core::transactions::{tari_amount::MicroTari, transaction::Transaction};
+use tari_core::transactions::{tari_amount::MicroTari, transaction::Transaction, types::Commitment, CoinbasePayout};
 use tari_service_framework::reply_channel::SenderService;
 use tokio::sync::broadcast;
 use tower::Service;
@@ -46,28 +62,69 @@ pub enum TransactionServiceRequest {
     GetPendingInboundTransactions,
     GetPendingOutboundTransactions,
     GetCompletedTransactions,
+    GetCompletedTransactionsPage(CompletedTransactionFilter, Option<TransactionCursor>, usize),
+    SearchTransactions(String, Option<TransactionCursor>, usize),
     GetCancelledPendingInboundTransactions,
     GetCancelledPendingOutboundTransactions,
     GetCancelledCompletedTransactions,
     GetCompletedTransaction(TxId),
     GetAnyTransaction(TxId),
     SetBaseNodePublicKey(CommsPublicKey),
-    SendTransaction(CommsPublicKey, MicroTari, MicroTari, String),
-    SendOneSidedTransaction(CommsPublicKey, MicroTari, MicroTari, String),
+    /// Destination, amount, fee per gram, message and an optional idempotency key
+    SendTransaction(CommsPublicKey, MicroTari, MicroTari, String, Option<String>),
+    /// Destination, amount, fee per gram, message and an optional idempotency key
+    SendOneSidedTransaction(CommsPublicKey, MicroTari, MicroTari, String, Option<String>),
+    SendTransactionFromAccount {
+        account: String,
+        destination: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    },
+    SendInternalTransfer {
+        source_account: String,
+        destination_account: String,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    },
+    SendAll {
+        destination: CommsPublicKey,
+        fee_per_gram: MicroTari,
+        message: String,
+    },
     CancelTransaction(TxId),
+    /// Reclaims the funds of a reclaimable one-sided payment that the recipient did not spend before its lock height
+    ReclaimOneSided(TxId),
+    /// Selects the inputs and computes the fee of a send without sending it. The outputs are encumbered until the
+    /// send is confirmed with `ConfirmSend` or aborted with `AbortPrepared`.
+    PrepareTransaction {
+        destination: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    },
+    ConfirmSend(TxId),
+    AbortPrepared(TxId),
     ImportUtxo(MicroTari, CommsPublicKey, String, Option<u64>),
     SubmitCoinSplitTransaction(TxId, Transaction, MicroTari, MicroTari, String),
+    CreateChildPaysForParentTransaction(TxId, MicroTari),
     SetLowPowerMode,
     SetNormalPowerMode,
     ApplyEncryption(Box<Aes256Gcm>),
     RemoveEncryption,
-    GenerateCoinbaseTransaction(MicroTari, MicroTari, u64),
+    GenerateCoinbaseTransaction(MicroTari, MicroTari, u64, Vec<CoinbasePayout>),
     RestartTransactionProtocols,
     RestartBroadcastProtocols,
     GetNumConfirmationsRequired,
     SetNumConfirmationsRequired(u64),
     SetCompletedTransactionValidity(u64, bool),
     ValidateTransactions(ValidationRetryStrategy),
+    CreateInvoice(CommsPublicKey, MicroTari, Duration, String),
+    GetInvoices,
+    PayInvoice(InvoiceId, MicroTari),
+    GeneratePaymentProof(TxId),
+    VerifyPaymentProof(Box<PaymentProof>),
     #[cfg(feature = "test_harness")]
     CompletePendingOutboundTransaction(CompletedTransaction),
     #[cfg(feature = "test_harness")]
@@ -86,16 +143,61 @@ impl fmt::Display for TransactionServiceRequest {
             Self::GetPendingInboundTransactions => f.write_str("GetPendingInboundTransactions"),
             Self::GetPendingOutboundTransactions => f.write_str("GetPendingOutboundTransactions"),
             Self::GetCompletedTransactions => f.write_str("GetCompletedTransactions"),
+            Self::GetCompletedTransactionsPage(_, cursor, limit) => f.write_str(&format!(
+                "GetCompletedTransactionsPage (cursor: {:?}, limit: {})",
+                cursor, limit
+            )),
+            Self::SearchTransactions(query, cursor, limit) => f.write_str(&format!(
+                "SearchTransactions ({}, cursor: {:?}, limit: {})",
+                query, cursor, limit
+            )),
             Self::GetCancelledPendingInboundTransactions => f.write_str("GetCancelledPendingInboundTransactions"),
             Self::GetCancelledPendingOutboundTransactions => f.write_str("GetCancelledPendingOutboundTransactions"),
             Self::GetCancelledCompletedTransactions => f.write_str("GetCancelledCompletedTransactions"),
             Self::GetCompletedTransaction(t) => f.write_str(&format!("GetCompletedTransaction({})", t)),
             Self::SetBaseNodePublicKey(k) => f.write_str(&format!("SetBaseNodePublicKey ({})", k)),
-            Self::SendTransaction(k, v, _, msg) => f.write_str(&format!("SendTransaction (to {}, {}, {})", k, v, msg)),
-            Self::SendOneSidedTransaction(k, v, _, msg) => {
+            Self::SendTransaction(k, v, _, msg, _) => {
+                f.write_str(&format!("SendTransaction (to {}, {}, {})", k, v, msg))
+            },
+            Self::SendOneSidedTransaction(k, v, _, msg, _) => {
                 f.write_str(&format!("SendOneSidedTransaction (to {}, {}, {})", k, v, msg))
             },
+            Self::SendTransactionFromAccount {
+                account,
+                destination,
+                amount,
+                message,
+                ..
+            } => f.write_str(&format!(
+                "SendTransactionFromAccount ({}, to {}, {}, {})",
+                account, destination, amount, message
+            )),
+            Self::SendInternalTransfer {
+                source_account,
+                destination_account,
+                amount,
+                message,
+                ..
+            } => f.write_str(&format!(
+                "SendInternalTransfer ({} to {}, {}, {})",
+                source_account, destination_account, amount, message
+            )),
+            Self::SendAll {
+                destination, message, ..
+            } => f.write_str(&format!("SendAll (to {}, {})", destination, message)),
             Self::CancelTransaction(t) => f.write_str(&format!("CancelTransaction ({})", t)),
+            Self::ReclaimOneSided(t) => f.write_str(&format!("ReclaimOneSided ({})", t)),
+            Self::PrepareTransaction {
+                destination,
+                amount,
+                message,
+                ..
+            } => f.write_str(&format!(
+                "PrepareTransaction (to {}, {}, {})",
+                destination, amount, message
+            )),
+            Self::ConfirmSend(prep_id) => f.write_str(&format!("ConfirmSend ({})", prep_id)),
+            Self::AbortPrepared(prep_id) => f.write_str(&format!("AbortPrepared ({})", prep_id)),
             Self::ImportUtxo(v, k, msg, maturity) => f.write_str(&format!(
                 "ImportUtxo (from {}, {}, {} with maturity: {})",
                 k,
@@ -106,11 +208,15 @@ impl fmt::Display for TransactionServiceRequest {
             Self::SubmitCoinSplitTransaction(tx_id, _, _, _, _) => {
                 f.write_str(&format!("SubmitTransaction ({})", tx_id))
             },
+            Self::CreateChildPaysForParentTransaction(tx_id, fee_per_gram) => f.write_str(&format!(
+                "CreateChildPaysForParentTransaction ({}, {})",
+                tx_id, fee_per_gram
+            )),
             Self::SetLowPowerMode => f.write_str("SetLowPowerMode "),
             Self::SetNormalPowerMode => f.write_str("SetNormalPowerMode"),
             Self::ApplyEncryption(_) => f.write_str("ApplyEncryption"),
             Self::RemoveEncryption => f.write_str("RemoveEncryption"),
-            Self::GenerateCoinbaseTransaction(_, _, bh) => {
+            Self::GenerateCoinbaseTransaction(_, _, bh, _) => {
                 f.write_str(&format!("GenerateCoinbaseTransaction (Blockheight {})", bh))
             },
             Self::RestartTransactionProtocols => f.write_str("RestartTransactionProtocols"),
@@ -137,18 +243,49 @@ impl fmt::Display for TransactionServiceRequest {
                 "SetCompletedTransactionValidity(TxId: {}, Validity: {:?})",
                 tx_id, s
             )),
+            Self::CreateInvoice(k, v, expires_in, memo) => f.write_str(&format!(
+                "CreateInvoice (to {}, {}, expires in {:?}, {})",
+                k, v, expires_in, memo
+            )),
+            Self::GetInvoices => f.write_str("GetInvoices"),
+            Self::PayInvoice(invoice_id, _) => f.write_str(&format!("PayInvoice ({})", invoice_id)),
+            Self::GeneratePaymentProof(tx_id) => f.write_str(&format!("GeneratePaymentProof ({})", tx_id)),
+            Self::VerifyPaymentProof(proof) => f.write_str(&format!("VerifyPaymentProof ({})", proof.tx_id)),
         }
     }
 }
 
+/// The details of a send that has been prepared but not yet sent, for the user to review before confirming it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedTransaction {
+    /// Identifies the prepared send to `ConfirmSend` and `AbortPrepared`. It becomes the TxId once confirmed.
+    pub prep_id: TxId,
+    pub destination: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee: MicroTari,
+    /// The commitment and value of each output that will be spent
+    pub inputs: Vec<(Commitment, MicroTari)>,
+    pub change: MicroTari,
+}
+
+impl PreparedTransaction {
+    /// The amount by which the wallet balance will decrease, being the amount sent and the fee
+    pub fn balance_impact(&self) -> MicroTari {
+        self.amount + self.fee
+    }
+}
+
 /// API Response enum
 #[derive(Debug)]
 pub enum TransactionServiceResponse {
     TransactionSent(TxId),
     TransactionCancelled,
+    OneSidedReclaimed(MicroTari),
     PendingInboundTransactions(HashMap<u64, InboundTransaction>),
     PendingOutboundTransactions(HashMap<u64, OutboundTransaction>),
     CompletedTransactions(HashMap<u64, CompletedTransaction>),
+    CompletedTransactionsPage(CompletedTransactionPage),
+    TransactionSearchResults(TransactionSearchPage),
     CompletedTransaction(Box<CompletedTransaction>),
     BaseNodePublicKeySet,
     UtxoImported(TxId),
@@ -164,6 +301,12 @@ pub enum TransactionServiceResponse {
     NumConfirmationsSet,
     ValidationStarted(u64),
     CompletedTransactionValidityChanged,
+    InvoiceCreated(InvoiceId),
+    Invoices(Vec<Invoice>),
+    PaymentProof(Box<PaymentProof>),
+    PaymentProofVerified,
+    TransactionPrepared(Box<PreparedTransaction>),
+    PreparedTransactionAborted,
     #[cfg(feature = "test_harness")]
     CompletedPendingTransaction,
     #[cfg(feature = "test_harness")]
@@ -193,15 +336,35 @@ pub enum TransactionEvent {
     TransactionMined(TxId),
     TransactionMinedRequestTimedOut(TxId),
     TransactionMinedUnconfirmed(TxId, u64),
+    /// The base node reported that the transaction was rejected by or removed from its mempool, with the reason
+    TransactionRejectedByMempool(TxId, String),
     TransactionValidationTimedOut(u64),
     TransactionValidationSuccess(u64),
     TransactionValidationFailure(u64),
     TransactionValidationAborted(u64),
     TransactionValidationDelayed(u64),
     TransactionBaseNodeConnectionProblem(u64),
+    ReceivedInvoice(InvoiceId),
+    InvoiceAccepted(InvoiceId, TxId),
+    InvoicePaid(InvoiceId, TxId),
+    /// The base node's chain reorged and the wallet state that depended on the replaced blocks is being re-checked
+    ChainReorg(ReorgImpact),
+    /// The output of a one-sided payment sent in this transaction was reclaimed by the sender
+    OneSidedReclaimed(TxId),
     Error(String),
 }
 
+/// The effect of a base node chain reorg on the wallet
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ReorgImpact {
+    /// Every block above this height may have been replaced
+    pub fork_height: u64,
+    /// The transactions that were mined above the fork height and have been moved back to Broadcast (or Coinbase)
+    pub reverted_transactions: Vec<TxId>,
+    /// The number of the wallet's outputs touched by the reverted transactions whose spent status is being re-checked
+    pub rechecked_outputs: usize,
+}
+
 pub type TransactionEventSender = broadcast::Sender<Arc<TransactionEvent>>;
 pub type TransactionEventReceiver = broadcast::Receiver<Arc<TransactionEvent>>;
 /// The Transaction Service Handle is a struct that contains the interfaces used to communicate with a running
@@ -233,6 +396,20 @@ impl TransactionServiceHandle {
         amount: MicroTari,
         fee_per_gram: MicroTari,
         message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        self.send_transaction_with_idempotency_key(dest_pubkey, amount, fee_per_gram, message, None)
+            .await
+    }
+
+    /// Send a transaction. If a transaction has already been sent with the same `idempotency_key`, no new
+    /// transaction is created and the TxId of the original transaction is returned.
+    pub async fn send_transaction_with_idempotency_key(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        idempotency_key: Option<String>,
     ) -> Result<TxId, TransactionServiceError> {
         match self
             .handle
@@ -241,6 +418,7 @@ impl TransactionServiceHandle {
                 amount,
                 fee_per_gram,
                 message,
+                idempotency_key,
             ))
             .await??
         {
@@ -249,12 +427,77 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Send a transaction that only spends outputs belonging to the given output manager account
+    pub async fn send_transaction_from_account(
+        &mut self,
+        account: String,
+        destination: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendTransactionFromAccount {
+                account,
+                destination,
+                amount,
+                fee_per_gram,
+                message,
+            })
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Move funds between the wallet's own accounts, or back to the same account to consolidate its outputs. The
+    /// transaction is built and signed locally and broadcast straight away.
+    pub async fn send_internal_transfer(
+        &mut self,
+        source_account: String,
+        destination_account: String,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendInternalTransfer {
+                source_account,
+                destination_account,
+                amount,
+                fee_per_gram,
+                message,
+            })
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn send_one_sided_transaction(
         &mut self,
         dest_pubkey: CommsPublicKey,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        self.send_one_sided_transaction_with_idempotency_key(dest_pubkey, amount, fee_per_gram, message, None)
+            .await
+    }
+
+    /// Send a one-sided transaction. If a transaction has already been sent with the same `idempotency_key`, no new
+    /// transaction is created and the TxId of the original transaction is returned.
+    pub async fn send_one_sided_transaction_with_idempotency_key(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        idempotency_key: Option<String>,
     ) -> Result<TxId, TransactionServiceError> {
         match self
             .handle
@@ -263,6 +506,50 @@ impl TransactionServiceHandle {
                 amount,
                 fee_per_gram,
                 message,
+                idempotency_key,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send every spendable output to `destination` in a single transaction with no change output. The amount sent is
+    /// the wallet's spendable balance less the fee.
+    pub async fn send_all(
+        &mut self,
+        destination: CommsPublicKey,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendAll {
+                destination,
+                fee_per_gram,
+                message,
+            })
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Speed up an inbound transaction that is stuck in the mempool by spending its outputs back to ourselves with a
+    /// fee that brings the combined fee per gram of both transactions up to `fee_per_gram`. Returns the tx_id of the
+    /// child transaction.
+    pub async fn create_child_pays_for_parent_transaction(
+        &mut self,
+        parent_tx_id: TxId,
+        fee_per_gram: MicroTari,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreateChildPaysForParentTransaction(
+                parent_tx_id,
+                fee_per_gram,
             ))
             .await??
         {
@@ -271,6 +558,128 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Create an invoice asking `payer_pubkey` to pay `amount`, and send it to them. The invoice should not be paid
+    /// after `expires_in` has elapsed.
+    pub async fn create_invoice(
+        &mut self,
+        payer_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        expires_in: Duration,
+        memo: String,
+    ) -> Result<InvoiceId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreateInvoice(
+                payer_pubkey,
+                amount,
+                expires_in,
+                memo,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::InvoiceCreated(invoice_id) => Ok(invoice_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns all invoices that were issued or received by this wallet
+    pub async fn get_invoices(&mut self) -> Result<Vec<Invoice>, TransactionServiceError> {
+        match self.handle.call(TransactionServiceRequest::GetInvoices).await?? {
+            TransactionServiceResponse::Invoices(invoices) => Ok(invoices),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Pay a received invoice by sending a standard transaction for the invoiced amount to the payee
+    pub async fn pay_invoice(
+        &mut self,
+        invoice_id: InvoiceId,
+        fee_per_gram: MicroTari,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::PayInvoice(invoice_id, fee_per_gram))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Creates a proof that this wallet paid the receiver of a completed outbound transaction
+    pub async fn generate_payment_proof(&mut self, tx_id: TxId) -> Result<PaymentProof, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GeneratePaymentProof(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::PaymentProof(proof) => Ok(*proof),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Verifies a payment proof. If this wallet is the receiver, this also checks that the wallet owns the paid output
+    /// and that it is worth the claimed amount.
+    pub async fn verify_payment_proof(&mut self, proof: PaymentProof) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::VerifyPaymentProof(Box::new(proof)))
+            .await??
+        {
+            TransactionServiceResponse::PaymentProofVerified => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Prepares a send without sending it, returning the selected inputs and fee for review. The send must then be
+    /// confirmed with [confirm_send](Self::confirm_send) or aborted with [abort_prepared](Self::abort_prepared).
+    /// Prepared sends that are neither are aborted after a timeout, and do not survive a restart.
+    pub async fn prepare_transaction(
+        &mut self,
+        destination: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<PreparedTransaction, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::PrepareTransaction {
+                destination,
+                amount,
+                fee_per_gram,
+                message,
+            })
+            .await??
+        {
+            TransactionServiceResponse::TransactionPrepared(prepared) => Ok(*prepared),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Sends a prepared transaction, returning its TxId
+    pub async fn confirm_send(&mut self, prep_id: TxId) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ConfirmSend(prep_id))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Discards a prepared transaction, making its inputs available to other transactions
+    pub async fn abort_prepared(&mut self, prep_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::AbortPrepared(prep_id))
+            .await??
+        {
+            TransactionServiceResponse::PreparedTransactionAborted => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn cancel_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
         match self
             .handle
@@ -282,6 +691,19 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Reclaims the funds of a one-sided payment sent with a reclaim lock, if the recipient has not spent the payment
+    /// before its lock height. Returns the value that was reclaimed.
+    pub async fn reclaim_one_sided(&mut self, tx_id: TxId) -> Result<MicroTari, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ReclaimOneSided(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::OneSidedReclaimed(value) => Ok(value),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_pending_inbound_transactions(
         &mut self,
     ) -> Result<HashMap<u64, InboundTransaction>, TransactionServiceError> {
@@ -347,6 +769,45 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Returns up to `limit` completed transactions matching the filter that come after `cursor`, newest first. Pass
+    /// the returned `next_cursor` to fetch the following page.
+    pub async fn get_completed_transactions_page(
+        &mut self,
+        filter: CompletedTransactionFilter,
+        cursor: Option<TransactionCursor>,
+        limit: usize,
+    ) -> Result<CompletedTransactionPage, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetCompletedTransactionsPage(
+                filter, cursor, limit,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::CompletedTransactionsPage(p) => Ok(p),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns up to `limit` pending and completed transactions that come after `cursor`, newest first, whose message,
+    /// invoice memo or counterparty contact alias contains words starting with every word of `query`. Pass the
+    /// returned `next_cursor` to fetch the following page.
+    pub async fn search_transactions(
+        &mut self,
+        query: String,
+        cursor: Option<TransactionCursor>,
+        limit: usize,
+    ) -> Result<TransactionSearchPage, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SearchTransactions(query, cursor, limit))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSearchResults(p) => Ok(p),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_cancelled_completed_transactions(
         &mut self,
     ) -> Result<HashMap<u64, CompletedTransaction>, TransactionServiceError> {
@@ -507,6 +968,19 @@ impl TransactionServiceHandle {
         rewards: MicroTari,
         fees: MicroTari,
         block_height: u64,
+    ) -> Result<Transaction, TransactionServiceError> {
+        self.generate_split_coinbase_transaction(rewards, fees, block_height, Vec::new())
+            .await
+    }
+
+    /// Generate a coinbase transaction that pays the given shares of the coinbase directly to other parties, such as
+    /// a mining pool or a development fund, with the remainder going to this wallet
+    pub async fn generate_split_coinbase_transaction(
+        &mut self,
+        rewards: MicroTari,
+        fees: MicroTari,
+        block_height: u64,
+        payouts: Vec<CoinbasePayout>,
     ) -> Result<Transaction, TransactionServiceError> {
         match self
             .handle
@@ -514,6 +988,7 @@ impl TransactionServiceHandle {
                 rewards,
                 fees,
                 block_height,
+                payouts,
             ))
             .await??
         {
@@ -23,12 +23,16 @@
 pub mod config;
 pub mod error;
 pub mod handle;
+pub mod payment_proof;
 pub mod protocols;
 pub mod service;
 pub mod storage;
 pub mod tasks;
+pub mod uri;
 
 use crate::{
+    audit_log_service::handle::AuditLogHandle,
+    base_node_service::handle::BaseNodeServiceHandle,
     output_manager_service::handle::OutputManagerHandle,
     transaction_service::{
         config::TransactionServiceConfig,
@@ -158,6 +162,19 @@ where T: TransactionBackend
             .map(map_decode::<proto::TransactionCancelledMessage>)
             .filter_map(ok_or_skip_result)
     }
+
+    fn payment_request_stream(&self) -> impl Stream<Item = DomainMessage<proto::PaymentRequestMessage>> {
+        trace!(
+            target: LOG_TARGET,
+            "Subscription '{}' for topic '{:?}' created.",
+            SUBSCRIPTION_LABEL,
+            TariMessageType::PaymentRequest
+        );
+        self.subscription_factory
+            .get_subscription(TariMessageType::PaymentRequest, SUBSCRIPTION_LABEL)
+            .map(map_decode::<proto::PaymentRequestMessage>)
+            .filter_map(ok_or_skip_result)
+    }
 }
 
 #[async_trait]
@@ -171,6 +188,7 @@ where T: TransactionBackend + 'static
         let transaction_finalized_stream = self.transaction_finalized_stream();
         let base_node_response_stream = self.base_node_response_stream();
         let transaction_cancelled_stream = self.transaction_cancelled_stream();
+        let payment_request_stream = self.payment_request_stream();
 
         let (publisher, _) = broadcast::channel(200);
 
@@ -191,7 +209,10 @@ where T: TransactionBackend + 'static
         context.spawn_when_ready(move |handles| async move {
             let outbound_message_service = handles.expect_handle::<Dht>().outbound_requester();
             let output_manager_service = handles.expect_handle::<OutputManagerHandle>();
+            let base_node_service = handles.expect_handle::<BaseNodeServiceHandle>();
             let connectivity_manager = handles.expect_handle::<ConnectivityRequester>();
+            // Signing operations and cancellations are only audited if the wallet runs the audit log service
+            let audit_log = handles.get_handle::<AuditLogHandle>();
 
             let result = TransactionService::new(
                 config,
@@ -202,12 +223,15 @@ where T: TransactionBackend + 'static
                 transaction_finalized_stream,
                 base_node_response_stream,
                 transaction_cancelled_stream,
+                payment_request_stream,
                 output_manager_service,
+                base_node_service,
                 outbound_message_service,
                 connectivity_manager,
                 publisher,
                 node_identity,
                 factories,
+                audit_log,
                 handles.get_shutdown_signal(),
             )
             .start()
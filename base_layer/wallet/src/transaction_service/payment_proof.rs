@@ -0,0 +1,433 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Proof of payment
+//!
+//! A [PaymentProof] lets the sender of a completed transaction show that it paid a specific output to the receiver.
+//! It contains the transaction kernel and offset, the input and output commitments of the transaction, the range proof
+//! (the "rewind blob") of the receiver's output and a signature by the sender's node identity over all of these.
+//!
+//! Anyone can check that the proof was signed by the sender, that the kernel and range proof are valid and that the
+//! receiver's output is part of the transaction the kernel belongs to with [PaymentProof::verify]. The outputs less the
+//! inputs of the transaction must balance against the kernel excess, offset and fee, so the kernel cannot be paired
+//! with an output from another transaction. Looking the kernel and output up on the blockchain confirms that the
+//! payment was mined. The receiver's wallet can go further and rewind the range proof with its own rewind keys to
+//! confirm that it owns the output and that the output is worth the claimed amount.
+
+use crate::{
+    output_manager_service::{handle::PublicRewindKeys, TxId},
+    transaction_service::storage::models::{CompletedTransaction, TransactionDirection},
+    types::HashDigest,
+};
+use digest::Digest;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tari_comms::types::{CommsPublicKey, CommsSecretKey};
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::{RewindResult, TransactionKernel, TransactionOutput},
+    types::{BlindingFactor, Commitment, CryptoFactories, PrivateKey, PublicKey, Signature},
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::SecretKey,
+    range_proof::RangeProofService,
+    tari_utilities::{ByteArray, Hashable},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PaymentProofError {
+    #[error("Only outbound transactions can be proven by the sender")]
+    NotOutbound,
+    #[error("The transaction does not have a kernel")]
+    KernelNotFound,
+    #[error("The transaction does not contain the receiver's output")]
+    ReceiverOutputNotFound,
+    #[error("The sender's signature is invalid")]
+    InvalidSenderSignature,
+    #[error("The kernel signature is invalid")]
+    InvalidKernelSignature,
+    #[error("The inputs and outputs of the transaction do not balance against the kernel")]
+    UnbalancedTransaction,
+    #[error("The range proof of the output is invalid")]
+    InvalidRangeProof,
+    #[error("The output does not belong to this wallet")]
+    OutputNotOwned,
+    #[error("The output is worth {actual}, not {expected}")]
+    AmountMismatch { expected: MicroTari, actual: MicroTari },
+    #[error("Failed to sign proof: {0}")]
+    SigningFailed(String),
+}
+
+/// Proof that the sender of a transaction paid a specific output to the receiver
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentProof {
+    pub tx_id: TxId,
+    pub sender_public_key: CommsPublicKey,
+    pub receiver_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub kernel: TransactionKernel,
+    /// The offset of the transaction, which the kernel excess is balanced against
+    pub offset: BlindingFactor,
+    pub input_commitments: Vec<Commitment>,
+    /// The commitments of all of the outputs of the transaction, including the receiver's output
+    pub output_commitments: Vec<Commitment>,
+    pub output_commitment: Commitment,
+    /// The range proof of the output, which the receiver can rewind to reveal its value
+    pub rewind_blob: Vec<u8>,
+    /// The sender's signature over all of the fields above
+    pub signature: Signature,
+}
+
+impl PaymentProof {
+    /// Creates a proof for an outbound transaction. The paid output is the one that cannot be rewound with the
+    /// sender's own rewind keys, i.e. the output that is not change.
+    pub fn create(
+        transaction: &CompletedTransaction,
+        sender_rewind_keys: &PublicRewindKeys,
+        sender_secret_key: &CommsSecretKey,
+        factories: &CryptoFactories,
+    ) -> Result<Self, PaymentProofError> {
+        if transaction.direction != TransactionDirection::Outbound {
+            return Err(PaymentProofError::NotOutbound);
+        }
+        let kernel = transaction
+            .transaction
+            .body
+            .kernels()
+            .first()
+            .cloned()
+            .ok_or(PaymentProofError::KernelNotFound)?;
+        let body = &transaction.transaction.body;
+        let output = body
+            .outputs()
+            .iter()
+            .find(|o| !is_rewindable(o, sender_rewind_keys, factories))
+            .ok_or(PaymentProofError::ReceiverOutputNotFound)?;
+
+        let mut proof = Self {
+            tx_id: transaction.tx_id,
+            sender_public_key: transaction.source_public_key.clone(),
+            receiver_public_key: transaction.destination_public_key.clone(),
+            amount: transaction.amount,
+            kernel,
+            offset: transaction.transaction.offset.clone(),
+            input_commitments: body.inputs().iter().map(|i| i.commitment.clone()).collect(),
+            output_commitments: body.outputs().iter().map(|o| o.commitment.clone()).collect(),
+            output_commitment: output.commitment.clone(),
+            rewind_blob: output.proof.0.clone(),
+            // Replaced below, the challenge does not include the signature
+            signature: Signature::new(PublicKey::default(), PrivateKey::default()),
+        };
+        let nonce = PrivateKey::random(&mut OsRng);
+        proof.signature = Signature::sign(sender_secret_key.clone(), nonce, &proof.challenge())
+            .map_err(|err| PaymentProofError::SigningFailed(err.to_string()))?;
+        Ok(proof)
+    }
+
+    /// Checks the sender's signature, the kernel signature, that the output is part of the transaction that the kernel
+    /// balances and the range proof of the output. This does not check that the transaction was mined or that the
+    /// output belongs to the receiver.
+    pub fn verify(&self, factories: &CryptoFactories) -> Result<(), PaymentProofError> {
+        if !self
+            .signature
+            .verify_challenge(&self.sender_public_key, &self.challenge())
+        {
+            return Err(PaymentProofError::InvalidSenderSignature);
+        }
+        self.kernel
+            .verify_signature()
+            .map_err(|_| PaymentProofError::InvalidKernelSignature)?;
+        if !self.output_commitments.contains(&self.output_commitment) {
+            return Err(PaymentProofError::ReceiverOutputNotFound);
+        }
+        self.verify_balance(factories)?;
+        if !factories.range_proof.verify(&self.rewind_blob, &self.output_commitment) {
+            return Err(PaymentProofError::InvalidRangeProof);
+        }
+        Ok(())
+    }
+
+    /// Checks that the output was created for the owner of the rewind keys and is worth the claimed amount. Only the
+    /// receiver can do this.
+    pub fn verify_receiver_output(
+        &self,
+        receiver_rewind_keys: &PublicRewindKeys,
+        factories: &CryptoFactories,
+    ) -> Result<(), PaymentProofError> {
+        let rewound: RewindResult = factories
+            .range_proof
+            .rewind_proof_value_only(
+                &self.rewind_blob,
+                &self.output_commitment,
+                &receiver_rewind_keys.rewind_public_key,
+                &receiver_rewind_keys.rewind_blinding_public_key,
+            )
+            .map_err(|_| PaymentProofError::OutputNotOwned)?
+            .into();
+        if rewound.committed_value != self.amount {
+            return Err(PaymentProofError::AmountMismatch {
+                expected: self.amount,
+                actual: rewound.committed_value,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the outputs less the inputs of the transaction equal the kernel excess and offset, less the fee
+    fn verify_balance(&self, factories: &CryptoFactories) -> Result<(), PaymentProofError> {
+        let factory = &factories.commitment;
+        let sum_inputs = self.input_commitments.iter().sum::<Commitment>();
+        let sum_outputs = self.output_commitments.iter().sum::<Commitment>();
+        let fee = factory.commit_value(&PrivateKey::default(), self.kernel.fee.into());
+        let excess = &self.kernel.excess + &factory.commit_value(&self.offset, 0);
+        if excess != &(&sum_outputs - &sum_inputs) + &fee {
+            return Err(PaymentProofError::UnbalancedTransaction);
+        }
+        Ok(())
+    }
+
+    fn challenge(&self) -> Vec<u8> {
+        let mut hasher = HashDigest::new()
+            .chain(self.tx_id.to_le_bytes())
+            .chain(self.sender_public_key.as_bytes())
+            .chain(self.receiver_public_key.as_bytes())
+            .chain(u64::from(self.amount).to_le_bytes())
+            .chain(self.kernel.hash())
+            .chain(self.offset.as_bytes());
+        for commitment in self.input_commitments.iter().chain(&self.output_commitments) {
+            hasher = hasher.chain(commitment.as_bytes());
+        }
+        hasher
+            .chain(self.output_commitment.as_bytes())
+            .chain(&self.rewind_blob)
+            .finalize()
+            .to_vec()
+    }
+}
+
+fn is_rewindable(output: &TransactionOutput, rewind_keys: &PublicRewindKeys, factories: &CryptoFactories) -> bool {
+    output
+        .rewind_range_proof_value_only(
+            &factories.range_proof,
+            &rewind_keys.rewind_public_key,
+            &rewind_keys.rewind_blinding_public_key,
+        )
+        .is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transaction_service::storage::models::TransactionStatus;
+    use chrono::Utc;
+    use tari_core::transactions::{
+        helpers::{create_signature, create_tx, TestParams, UtxoTestParams},
+        transaction::{KernelBuilder, Transaction},
+        transaction_protocol::RewindData,
+    };
+    use tari_crypto::keys::PublicKey as PublicKeyTrait;
+
+    fn random_rewind_data() -> RewindData {
+        RewindData {
+            rewind_key: PrivateKey::random(&mut OsRng),
+            rewind_blinding_key: PrivateKey::random(&mut OsRng),
+            proof_message: [0u8; 21],
+        }
+    }
+
+    fn public_rewind_keys(rewind_data: &RewindData) -> PublicRewindKeys {
+        PublicRewindKeys {
+            rewind_public_key: PublicKey::from_secret_key(&rewind_data.rewind_key),
+            rewind_blinding_public_key: PublicKey::from_secret_key(&rewind_data.rewind_blinding_key),
+        }
+    }
+
+    /// Returns the output and its spending key
+    fn create_output(
+        value: MicroTari,
+        rewind_data: &RewindData,
+        factories: &CryptoFactories,
+    ) -> (TransactionOutput, PrivateKey) {
+        let unblinded = TestParams::new().create_unblinded_output(UtxoTestParams {
+            value,
+            ..Default::default()
+        });
+        let output = unblinded
+            .as_rewindable_transaction_output(factories, rewind_data)
+            .unwrap();
+        (output, unblinded.spending_key)
+    }
+
+    /// Creates a balanced transaction that spends one input to a change output for the sender and an output for the
+    /// receiver
+    fn create_payment(
+        amount: MicroTari,
+        sender_rewind_data: &RewindData,
+        receiver_rewind_data: &RewindData,
+        factories: &CryptoFactories,
+    ) -> Transaction {
+        let fee = MicroTari::from(100);
+        let change_amount = MicroTari::from(1000);
+        let (input, unblinded_input) = TestParams::new().create_input(UtxoTestParams {
+            value: amount + change_amount + fee,
+            ..Default::default()
+        });
+        let (change, change_key) = create_output(change_amount, sender_rewind_data, factories);
+        let (paid, paid_key) = create_output(amount, receiver_rewind_data, factories);
+        let offset = PrivateKey::random(&mut OsRng);
+        let excess_key = &(&(&change_key + &paid_key) - &unblinded_input.spending_key) - &offset;
+        let kernel = KernelBuilder::new()
+            .with_fee(fee)
+            .with_excess(&factories.commitment.commit_value(&excess_key, 0))
+            .with_signature(&create_signature(excess_key, fee, 0))
+            .build()
+            .unwrap();
+        Transaction::new(
+            vec![input],
+            vec![change, paid],
+            vec![kernel],
+            offset,
+            PrivateKey::default(),
+        )
+    }
+
+    fn completed_outbound(
+        transaction: Transaction,
+        amount: MicroTari,
+        sender_public_key: CommsPublicKey,
+    ) -> CompletedTransaction {
+        let (_, receiver_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        CompletedTransaction::new(
+            1,
+            sender_public_key,
+            receiver_public_key,
+            amount,
+            MicroTari::from(100),
+            transaction,
+            TransactionStatus::Completed,
+            "Yo!".to_string(),
+            Utc::now().naive_utc(),
+            TransactionDirection::Outbound,
+            None,
+        )
+    }
+
+    #[test]
+    fn it_proves_payment_to_the_receiver() {
+        let factories = CryptoFactories::default();
+        let sender_rewind_data = random_rewind_data();
+        let receiver_rewind_data = random_rewind_data();
+        let (sender_secret_key, sender_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let amount = MicroTari::from(5000);
+        let transaction = create_payment(amount, &sender_rewind_data, &receiver_rewind_data, &factories);
+        let completed = completed_outbound(transaction, amount, sender_public_key);
+
+        let proof = PaymentProof::create(
+            &completed,
+            &public_rewind_keys(&sender_rewind_data),
+            &sender_secret_key,
+            &factories,
+        )
+        .unwrap();
+        let paid_output = completed
+            .transaction
+            .body
+            .outputs()
+            .iter()
+            .find(|o| o.commitment == proof.output_commitment)
+            .unwrap();
+        assert_eq!(paid_output.proof.0, proof.rewind_blob);
+
+        proof.verify(&factories).unwrap();
+        proof
+            .verify_receiver_output(&public_rewind_keys(&receiver_rewind_data), &factories)
+            .unwrap();
+        assert_eq!(
+            proof.verify_receiver_output(&public_rewind_keys(&sender_rewind_data), &factories),
+            Err(PaymentProofError::OutputNotOwned)
+        );
+
+        let mut tampered = proof.clone();
+        tampered.amount = MicroTari::from(6000);
+        assert_eq!(
+            tampered.verify(&factories),
+            Err(PaymentProofError::InvalidSenderSignature)
+        );
+    }
+
+    #[test]
+    fn it_rejects_an_output_that_is_not_balanced_by_the_kernel() {
+        let factories = CryptoFactories::default();
+        let sender_rewind_data = random_rewind_data();
+        let receiver_rewind_data = random_rewind_data();
+        let (sender_secret_key, sender_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let amount = MicroTari::from(5000);
+
+        // The kernel of an unrelated transaction, paired with an output paying the receiver
+        let (tx, _, _) = create_tx(MicroTari::from(10000), MicroTari::from(25), 0, 1, 0, 1);
+        let transaction = Transaction::new(
+            vec![],
+            vec![create_output(amount, &receiver_rewind_data, &factories).0],
+            tx.body.kernels().clone(),
+            tx.offset.clone(),
+            tx.script_offset,
+        );
+        let proof = PaymentProof::create(
+            &completed_outbound(transaction, amount, sender_public_key),
+            &public_rewind_keys(&sender_rewind_data),
+            &sender_secret_key,
+            &factories,
+        )
+        .unwrap();
+        assert_eq!(proof.verify(&factories), Err(PaymentProofError::UnbalancedTransaction));
+    }
+
+    #[test]
+    fn it_only_proves_outbound_transactions() {
+        let factories = CryptoFactories::default();
+        let (tx, _, _) = create_tx(MicroTari::from(10000), MicroTari::from(25), 0, 1, 0, 1);
+        let (secret_key, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let completed = CompletedTransaction::new(
+            1,
+            public_key.clone(),
+            public_key,
+            MicroTari::from(5000),
+            MicroTari::from(100),
+            tx,
+            TransactionStatus::Completed,
+            "".to_string(),
+            Utc::now().naive_utc(),
+            TransactionDirection::Inbound,
+            None,
+        );
+        let err = PaymentProof::create(
+            &completed,
+            &public_rewind_keys(&random_rewind_data()),
+            &secret_key,
+            &factories,
+        )
+        .unwrap_err();
+        assert_eq!(err, PaymentProofError::NotOutbound);
+    }
+}
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    audit_log_service::storage::database::AuditAction,
     output_manager_service::TxId,
     transaction_service::{
         error::{TransactionServiceError, TransactionServiceProtocolError},
@@ -229,8 +230,8 @@ where TBackend: TransactionBackend + 'static
 
             let mut client = match base_node_connection
                 .connect_rpc_using_builder(
-                    BaseNodeWalletRpcClient::builder()
-                        .with_deadline(self.resources.config.broadcast_monitoring_timeout),
+                    self.resources
+                        .base_node_rpc_client_builder(self.resources.config.broadcast_monitoring_timeout),
                 )
                 .await
             {
@@ -381,6 +382,7 @@ where TBackend: TransactionBackend + 'static
                 "Transaction (TxId: {}) rejected by Base Node for reason: {}", self.tx_id, response.rejection_reason
             );
 
+            self.publish_mempool_rejection(response.rejection_reason.clone());
             self.cancel_transaction().await;
 
             let _ = self
@@ -403,6 +405,16 @@ where TBackend: TransactionBackend + 'static
                 TxSubmissionRejectionReason::DoubleSpend => TransactionServiceError::MempoolRejectionDoubleSpend,
                 TxSubmissionRejectionReason::Orphan => TransactionServiceError::MempoolRejectionOrphan,
                 TxSubmissionRejectionReason::TimeLocked => TransactionServiceError::MempoolRejectionTimeLocked,
+                TxSubmissionRejectionReason::FeePerGramTooLow => TransactionServiceError::MempoolRejectionFeeTooLow,
+                TxSubmissionRejectionReason::ScriptTooLarge => TransactionServiceError::MempoolRejectionScriptTooLarge,
+                TxSubmissionRejectionReason::TooManyOutputs => TransactionServiceError::MempoolRejectionTooManyOutputs,
+                TxSubmissionRejectionReason::DustOutput => TransactionServiceError::MempoolRejectionDustOutput,
+                TxSubmissionRejectionReason::TooManyUnconfirmedForUniqueId => {
+                    TransactionServiceError::MempoolRejectionTooManyUnconfirmedForUniqueId
+                },
+                TxSubmissionRejectionReason::SenderWeightLimitExceeded => {
+                    TransactionServiceError::MempoolRejectionSenderWeightLimitExceeded
+                },
                 _ => TransactionServiceError::UnexpectedBaseNodeResponse,
             };
             return Err(TransactionServiceProtocolError::new(self.tx_id, reason));
@@ -547,6 +559,15 @@ where TBackend: TransactionBackend + 'static
                     e
                 });
         } else if response.location != TxLocation::InMempool {
+            if response.rejection_reason != TxSubmissionRejectionReason::None {
+                warn!(
+                    target: LOG_TARGET,
+                    "Transaction (TxId: {}) was removed from the mempool for reason: {}",
+                    self.tx_id,
+                    response.rejection_reason
+                );
+                self.publish_mempool_rejection(response.rejection_reason.clone());
+            }
             if !self.first_rejection {
                 info!(
                     target: LOG_TARGET,
@@ -590,6 +611,24 @@ where TBackend: TransactionBackend + 'static
         Ok(false)
     }
 
+    fn publish_mempool_rejection(&self, reason: TxSubmissionRejectionReason) {
+        let _ = self
+            .resources
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionRejectedByMempool(
+                self.tx_id,
+                reason.to_string(),
+            )))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+    }
+
     async fn query_or_submit_transaction(
         &mut self,
         completed_transaction: CompletedTransaction,
@@ -619,22 +658,60 @@ where TBackend: TransactionBackend + 'static
     }
 
     async fn cancel_transaction(&mut self) {
-        if let Err(e) = self
+        let descendant_tx_ids = match self
             .resources
             .output_manager_service
             .cancel_transaction(self.tx_id)
             .await
         {
-            warn!(
-                target: LOG_TARGET,
-                "Failed to Cancel outputs for TxId: {} after failed sending attempt with error {:?}", self.tx_id, e
-            );
-        }
-        if let Err(e) = self.resources.db.cancel_completed_transaction(self.tx_id).await {
-            warn!(
+            Ok(descendant_tx_ids) => descendant_tx_ids,
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to Cancel outputs for TxId: {} after failed sending attempt with error {:?}", self.tx_id, e
+                );
+                Vec::new()
+            },
+        };
+        match self.resources.db.cancel_completed_transaction(self.tx_id).await {
+            Ok(()) => self.resources.record_audit(
+                AuditAction::TransactionCancelled,
+                format!("Cancelled transaction {}", self.tx_id),
+            ),
+            Err(e) => warn!(
                 target: LOG_TARGET,
                 "Failed to Cancel TxId: {} after failed sending attempt with error {:?}", self.tx_id, e
-            );
+            ),
+        }
+
+        // Transactions that spent the change of this transaction can never be mined without it
+        for tx_id in descendant_tx_ids {
+            let result = match self.resources.db.cancel_pending_transaction(tx_id).await {
+                Ok(()) => Ok(()),
+                Err(_) => self.resources.db.cancel_completed_transaction(tx_id).await,
+            };
+            match result {
+                Ok(()) => {
+                    self.resources.record_audit(
+                        AuditAction::TransactionCancelled,
+                        format!(
+                            "Cancelled transaction {}, which spent the change of cancelled transaction {}",
+                            tx_id, self.tx_id
+                        ),
+                    );
+                    let _ = self
+                        .resources
+                        .event_publisher
+                        .send(Arc::new(TransactionEvent::TransactionCancelled(tx_id)));
+                },
+                Err(e) => warn!(
+                    target: LOG_TARGET,
+                    "Failed to Cancel TxId: {} which spent the change of cancelled TxId: {} with error {:?}",
+                    tx_id,
+                    self.tx_id,
+                    e
+                ),
+            }
         }
     }
 }
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    audit_log_service::storage::database::AuditAction,
     output_manager_service::TxId,
     transaction_service::{
         error::{TransactionServiceError, TransactionServiceProtocolError},
@@ -284,7 +285,8 @@ where TBackend: TransactionBackend + 'static
             };
             let mut client = match base_node_connection
                 .connect_rpc_using_builder(
-                    BaseNodeWalletRpcClient::builder().with_deadline(self.resources.config.chain_monitoring_timeout),
+                    self.resources
+                        .base_node_rpc_client_builder(self.resources.config.chain_monitoring_timeout),
                 )
                 .await
             {
@@ -591,11 +593,15 @@ where TBackend: TransactionBackend + 'static
                 "Failed to Cancel outputs for Coinbase transaction (TxId: {}) with error: {:?}", self.tx_id, e
             );
         }
-        if let Err(e) = self.resources.db.cancel_completed_transaction(self.tx_id).await {
-            warn!(
+        match self.resources.db.cancel_completed_transaction(self.tx_id).await {
+            Ok(()) => self.resources.record_audit(
+                AuditAction::TransactionCancelled,
+                format!("Cancelled coinbase transaction {}", self.tx_id),
+            ),
+            Err(e) => warn!(
                 target: LOG_TARGET,
                 "Failed to Cancel Coinbase transaction (TxId: {}) with error: {:?}", self.tx_id, e
-            );
+            ),
         }
     }
 }
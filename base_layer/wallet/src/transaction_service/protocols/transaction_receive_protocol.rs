@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    audit_log_service::storage::database::AuditAction,
     output_manager_service::TxId,
     transaction_service::{
         error::{TransactionServiceError, TransactionServiceProtocolError},
@@ -141,7 +142,10 @@ where TBackend: TransactionBackend + 'static
             let rtp = self
                 .resources
                 .output_manager_service
-                .get_recipient_transaction(self.sender_message.clone())
+                .get_recipient_transaction_with_options(
+                    self.sender_message.clone(),
+                    self.resources.config.receive_output_options.clone(),
+                )
                 .await
                 .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
 
@@ -198,6 +202,11 @@ where TBackend: TransactionBackend + 'static
                 data.message,
             );
 
+            self.resources.record_audit(
+                AuditAction::TransactionSigned,
+                format!("Signed the recipient part of inbound transaction {}", data.tx_id),
+            );
+
             let _ = self
                 .resources
                 .event_publisher
@@ -507,6 +516,10 @@ where TBackend: TransactionBackend + 'static
             .cancel_transaction(self.id)
             .await
             .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
+        self.resources.record_audit(
+            AuditAction::TransactionCancelled,
+            format!("Cancelled transaction {}", self.id),
+        );
 
         let _ = self
             .resources
@@ -26,19 +26,22 @@ use chrono::Utc;
 use futures::{channel::mpsc::Receiver, FutureExt, StreamExt};
 use log::*;
 
-use crate::transaction_service::{
-    config::TransactionRoutingMechanism,
-    error::{TransactionServiceError, TransactionServiceProtocolError},
-    handle::TransactionEvent,
-    service::TransactionServiceResources,
-    storage::{
-        database::TransactionBackend,
-        models::{CompletedTransaction, OutboundTransaction, TransactionDirection, TransactionStatus},
-    },
-    tasks::{
-        send_finalized_transaction::send_finalized_transaction_message,
-        send_transaction_cancelled::send_transaction_cancelled_message,
-        wait_on_dial::wait_on_dial,
+use crate::{
+    audit_log_service::storage::database::AuditAction,
+    transaction_service::{
+        config::TransactionRoutingMechanism,
+        error::{TransactionServiceError, TransactionServiceProtocolError},
+        handle::TransactionEvent,
+        service::TransactionServiceResources,
+        storage::{
+            database::TransactionBackend,
+            models::{CompletedTransaction, OutboundTransaction, TransactionDirection, TransactionStatus},
+        },
+        tasks::{
+            send_finalized_transaction::send_finalized_transaction_message,
+            send_transaction_cancelled::send_transaction_cancelled_message,
+            wait_on_dial::wait_on_dial,
+        },
     },
 };
 use futures::channel::oneshot;
@@ -399,20 +402,26 @@ where TBackend: TransactionBackend + 'static
             .add_single_recipient_info(recipient_reply, &self.resources.factories.range_proof)
             .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
 
-        outbound_tx
+        if let Err(e) = outbound_tx
             .sender_protocol
             .finalize(KernelFeatures::empty(), &self.resources.factories)
-            .map_err(|e| {
-                error!(
-                    target: LOG_TARGET,
-                    "Transaction (TxId: {}) could not be finalized. Failure error: {:?}", self.id, e,
-                );
-                debug!(
-                    target: LOG_TARGET_STRESS,
-                    "Transaction (TxId: {}) could not be finalized. Failure error: {:?}", self.id, e,
-                );
-                TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e))
-            })?;
+        {
+            error!(
+                target: LOG_TARGET,
+                "Transaction (TxId: {}) could not be finalized. Failure error: {:?}", self.id, e,
+            );
+            debug!(
+                target: LOG_TARGET_STRESS,
+                "Transaction (TxId: {}) could not be finalized. Failure error: {:?}", self.id, e,
+            );
+            // Our partial signature has been made with this transaction's nonce. The transaction is cancelled rather
+            // than left pending, where a different reply could get it signed a second time with the same nonce.
+            self.cancel_pending_transaction().await?;
+            return Err(TransactionServiceProtocolError::new(
+                self.id,
+                TransactionServiceError::from(e),
+            ));
+        }
 
         let tx = outbound_tx
             .sender_protocol
@@ -464,6 +473,11 @@ where TBackend: TransactionBackend + 'static
             .await
             .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
 
+        self.resources.record_audit(
+            AuditAction::TransactionSigned,
+            format!("Signed and finalized outbound transaction {}", tx_id),
+        );
+
         let _ = self
             .resources
             .event_publisher
@@ -720,6 +734,21 @@ where TBackend: TransactionBackend + 'static
             target: LOG_TARGET,
             "Cancelling Transaction Send Protocol (TxId: {}) due to timeout after no counterparty response", self.id
         );
+        self.cancel_pending_transaction().await?;
+
+        info!(
+            target: LOG_TARGET,
+            "Pending Transaction (TxId: {}) timed out after no response from counterparty", self.id
+        );
+
+        Err(TransactionServiceProtocolError::new(
+            self.id,
+            TransactionServiceError::Timeout,
+        ))
+    }
+
+    /// Cancel the pending transaction, release its outputs and let the recipient know
+    async fn cancel_pending_transaction(&mut self) -> Result<(), TransactionServiceProtocolError> {
         let _ = send_transaction_cancelled_message(
             self.id,
             self.dest_pubkey.clone(),
@@ -755,6 +784,10 @@ where TBackend: TransactionBackend + 'static
             .cancel_transaction(self.id)
             .await
             .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
+        self.resources.record_audit(
+            AuditAction::TransactionCancelled,
+            format!("Cancelled transaction {}", self.id),
+        );
 
         let _ = self
             .resources
@@ -772,15 +805,7 @@ where TBackend: TransactionBackend + 'static
                 )
             });
 
-        info!(
-            target: LOG_TARGET,
-            "Pending Transaction (TxId: {}) timed out after no response from counterparty", self.id
-        );
-
-        Err(TransactionServiceProtocolError::new(
-            self.id,
-            TransactionServiceError::Timeout,
-        ))
+        Ok(())
     }
 }
 
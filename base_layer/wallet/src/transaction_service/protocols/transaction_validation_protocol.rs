@@ -60,11 +60,14 @@ where TBackend: TransactionBackend + 'static
     base_node_synced: bool,
 }
 
-/// This protocol will check all of the mined transactions (both valid and invalid) in the db to see if they are present
-/// on the current base node. # Behaviour
+/// This protocol will check all of the mined transactions (both valid and invalid) and broadcast transactions in the db
+/// to see if they are present on the current base node. Transactions are queried in batches of
+/// `max_tx_query_batch_size` so that a single request to the base node covers many transactions. # Behaviour
 /// - If a valid transaction is not present the protocol will mark the transaction as invalid
 /// - If an invalid transaction is present on th ebase node it will be marked as valid
 /// - If a Confirmed mined transaction is present but no longer confirmed its status will change to MinedUnconfirmed
+/// - If a Broadcast transaction is present it will be marked as mined (and confirmed once it has enough confirmations)
+/// - The confirmations and mined height of every mined transaction are updated
 impl<TBackend> TransactionValidationProtocol<TBackend>
 where TBackend: TransactionBackend + 'static
 {
@@ -258,7 +261,7 @@ where TBackend: TransactionBackend + 'static
             };
 
             let mut client = match base_node_connection
-                .connect_rpc_using_builder(BaseNodeWalletRpcClient::builder().with_deadline(self.timeout))
+                .connect_rpc_using_builder(self.resources.base_node_rpc_client_builder(self.timeout))
                 .await
             {
                 Ok(c) => c,
@@ -452,6 +455,32 @@ where TBackend: TransactionBackend + 'static
             }) {
                 // Mined?
                 if response.location == TxLocation::Mined {
+                    if queried_tx.confirmations != Some(response.confirmations) {
+                        if let Err(e) = self
+                            .resources
+                            .db
+                            .set_transaction_confirmations(queried_tx.tx_id, response.confirmations)
+                            .await
+                        {
+                            warn!(
+                                target: LOG_TARGET,
+                                "Error setting transaction (TxId: {}) confirmations: {}", queried_tx.tx_id, e
+                            );
+                        }
+                    }
+                    if queried_tx.mined_height != Some(response.block_height) {
+                        if let Err(e) = self
+                            .resources
+                            .db
+                            .set_transaction_mined_height(queried_tx.tx_id, response.block_height)
+                            .await
+                        {
+                            warn!(
+                                target: LOG_TARGET,
+                                "Error setting transaction (TxId: {}) mined height: {}", queried_tx.tx_id, e
+                            );
+                        }
+                    }
                     if !queried_tx.valid {
                         info!(
                             target: LOG_TARGET,
@@ -471,7 +500,9 @@ where TBackend: TransactionBackend + 'static
                         }
                     }
                     if response.confirmations >= self.resources.config.num_confirmations_required as u64 {
-                        if queried_tx.status == TransactionStatus::MinedUnconfirmed {
+                        if queried_tx.status == TransactionStatus::MinedUnconfirmed ||
+                            queried_tx.status == TransactionStatus::Broadcast
+                        {
                             info!(
                                 target: LOG_TARGET,
                                 "Transaction (TxId: {}) is MINED and CONFIRMED according to base node, status will be \
@@ -521,7 +552,46 @@ where TBackend: TransactionBackend + 'static
                                 "Error unconfirming mined transaction (TxId: {}): {}", queried_tx.tx_id, e
                             );
                         }
+                    } else if queried_tx.status == TransactionStatus::Broadcast {
+                        info!(
+                            target: LOG_TARGET,
+                            "Transaction (TxId: {}) is MINED but UNCONFIRMED with {} confirmations according to base \
+                             node, status will be updated",
+                            queried_tx.tx_id,
+                            response.confirmations
+                        );
+                        if let Err(e) = self.resources.db.mine_completed_transaction(queried_tx.tx_id).await {
+                            warn!(
+                                target: LOG_TARGET,
+                                "Error marking transaction (TxId: {}) as mined: {}", queried_tx.tx_id, e
+                            );
+                        } else {
+                            let _ = self
+                                .resources
+                                .event_publisher
+                                .send(Arc::new(TransactionEvent::TransactionMinedUnconfirmed(
+                                    queried_tx.tx_id,
+                                    response.confirmations,
+                                )))
+                                .map_err(|e| {
+                                    trace!(
+                                        target: LOG_TARGET,
+                                        "Error sending event because there are no subscribers: {:?}",
+                                        e
+                                    );
+                                    e
+                                });
+                        }
                     }
+                } else if queried_tx.status == TransactionStatus::Broadcast {
+                    // Resubmitting broadcast transactions that are not in the mempool is left to the broadcast
+                    // protocol
+                    trace!(
+                        target: LOG_TARGET,
+                        "Broadcast transaction (TxId: {}) is not mined yet ({})",
+                        queried_tx.tx_id,
+                        response.location
+                    );
                 } else if queried_tx.valid {
                     info!(
                         target: LOG_TARGET,
@@ -550,7 +620,7 @@ where TBackend: TransactionBackend + 'static
         Ok(true)
     }
 
-    /// Get completed transactions from db and sort the mined transactions into batches
+    /// Get completed transactions from db and sort the mined and broadcast transactions into batches
     async fn get_transaction_batches(&self) -> Result<Vec<Vec<CompletedTransaction>>, TransactionServiceError> {
         let mut completed_txs: Vec<CompletedTransaction> = self
             .resources
@@ -559,7 +629,9 @@ where TBackend: TransactionBackend + 'static
             .await?
             .values()
             .filter(|tx| {
-                tx.status == TransactionStatus::MinedUnconfirmed || tx.status == TransactionStatus::MinedConfirmed
+                tx.status == TransactionStatus::MinedUnconfirmed ||
+                    tx.status == TransactionStatus::MinedConfirmed ||
+                    tx.status == TransactionStatus::Broadcast
             })
             .cloned()
             .collect();
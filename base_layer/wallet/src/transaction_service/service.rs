@@ -21,11 +21,21 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    output_manager_service::{handle::OutputManagerHandle, TxId},
+    audit_log_service::{handle::AuditLogHandle, storage::database::AuditAction},
+    base_node_service::handle::{BaseNodeEvent, BaseNodeServiceHandle, ChainReorg},
+    output_manager_service::{handle::OutputManagerHandle, storage::models::DEFAULT_ACCOUNT, TxId},
     transaction_service::{
         config::TransactionServiceConfig,
         error::{TransactionServiceError, TransactionServiceProtocolError},
-        handle::{TransactionEvent, TransactionEventSender, TransactionServiceRequest, TransactionServiceResponse},
+        handle::{
+            PreparedTransaction,
+            ReorgImpact,
+            TransactionEvent,
+            TransactionEventSender,
+            TransactionServiceRequest,
+            TransactionServiceResponse,
+        },
+        payment_proof::PaymentProof,
         protocols::{
             transaction_broadcast_protocol::TransactionBroadcastProtocol,
             transaction_coinbase_monitoring_protocol::TransactionCoinbaseMonitoringProtocol,
@@ -35,10 +45,19 @@ use crate::{
         },
         storage::{
             database::{TransactionBackend, TransactionDatabase},
-            models::{CompletedTransaction, TransactionDirection, TransactionStatus},
+            models::{
+                CompletedTransaction,
+                Invoice,
+                InvoiceDirection,
+                InvoiceId,
+                InvoiceStatus,
+                TransactionDirection,
+                TransactionStatus,
+            },
         },
         tasks::{
             send_finalized_transaction::send_finalized_transaction_message,
+            send_payment_request::send_payment_request_message,
             send_transaction_cancelled::send_transaction_cancelled_message,
             send_transaction_reply::send_transaction_reply,
         },
@@ -58,38 +77,69 @@ use futures::{
 use log::*;
 use rand::{rngs::OsRng, RngCore};
 use std::{
+    cmp::min,
     collections::{HashMap, HashSet},
-    convert::TryInto,
+    convert::{TryFrom, TryInto},
     sync::Arc,
     time::{Duration, Instant},
 };
-use tari_comms::{connectivity::ConnectivityRequester, peer_manager::NodeIdentity, types::CommsPublicKey};
+use tari_comms::{
+    connectivity::ConnectivityRequester,
+    peer_manager::NodeIdentity,
+    protocol::rpc::RpcClientBuilder,
+    types::CommsPublicKey,
+    utils::log_fields::LogFieldExt,
+};
 use tari_comms_dht::outbound::OutboundMessageRequester;
 #[cfg(feature = "test_harness")]
 use tari_core::transactions::{tari_amount::uT, types::BlindingFactor};
 use tari_core::{
+    base_node::rpc::BaseNodeWalletRpcClient,
     crypto::keys::SecretKey,
     proto::base_node as base_node_proto,
     transactions::{
+        encrypted_data::{EncryptedData, MAX_ENCRYPTED_MESSAGE_BYTES},
+        miner_coinbase_value,
+        one_sided::ReclaimableOneSidedScript,
         tari_amount::MicroTari,
-        transaction::{KernelFeatures, OutputFeatures, Transaction},
+        transaction::{KernelFeatures, OutputFeatures, Transaction, TransactionError, UnblindedOutput},
         transaction_protocol::{
             proto,
             recipient::RecipientSignedMessage,
             sender::TransactionSenderMessage,
             RewindData,
         },
-        types::{CryptoFactories, PrivateKey},
+        types::{CryptoFactories, PrivateKey, PublicKey},
+        weight::TransactionWeight,
+        CoinbasePayout,
         ReceiverTransactionProtocol,
+        SenderTransactionProtocol,
     },
 };
-use tari_crypto::{keys::DiffieHellmanSharedSecret, script, tari_utilities::ByteArray};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{DiffieHellmanSharedSecret, PublicKey as PublicKeyTrait},
+    script,
+    script::ExecutionStack,
+    tari_utilities::ByteArray,
+};
 use tari_p2p::domain_message::DomainMessage;
 use tari_service_framework::{reply_channel, reply_channel::Receiver};
 use tari_shutdown::ShutdownSignal;
 use tokio::{sync::broadcast, task::JoinHandle};
 
 const LOG_TARGET: &str = "wallet::transaction_service::service";
+/// How long a prepared send may wait for confirmation before its inputs are released
+const PREPARED_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// A send that has selected its inputs but is waiting for the user to confirm it
+struct PreparedSend {
+    dest_pubkey: CommsPublicKey,
+    amount: MicroTari,
+    message: String,
+    sender_protocol: SenderTransactionProtocol,
+    prepared_at: Instant,
+}
 
 /// TransactionService allows for the management of multiple inbound and outbound transaction protocols
 /// which are uniquely identified by a tx_id. The TransactionService generates and accepts the various protocol
@@ -111,16 +161,19 @@ pub struct TransactionService<
     BNResponseStream,
     TBackend,
     TTxCancelledStream,
+    TPaymentRequestStream,
 > where TBackend: TransactionBackend + 'static
 {
     config: TransactionServiceConfig,
     db: TransactionDatabase<TBackend>,
     output_manager_service: OutputManagerHandle,
+    base_node_service: BaseNodeServiceHandle,
     transaction_stream: Option<TTxStream>,
     transaction_reply_stream: Option<TTxReplyStream>,
     transaction_finalized_stream: Option<TTxFinalizedStream>,
     base_node_response_stream: Option<BNResponseStream>,
     transaction_cancelled_stream: Option<TTxCancelledStream>,
+    payment_request_stream: Option<TPaymentRequestStream>,
     request_stream: Option<
         reply_channel::Receiver<TransactionServiceRequest, Result<TransactionServiceResponse, TransactionServiceError>>,
     >,
@@ -135,20 +188,38 @@ pub struct TransactionService<
     receiver_transaction_cancellation_senders: HashMap<u64, oneshot::Sender<()>>,
     active_transaction_broadcast_protocols: HashSet<u64>,
     active_coinbase_monitoring_protocols: HashSet<u64>,
+    prepared_transactions: HashMap<TxId, PreparedSend>,
     timeout_update_publisher: broadcast::Sender<Duration>,
     base_node_update_publisher: broadcast::Sender<CommsPublicKey>,
     power_mode: PowerMode,
 }
 
 #[allow(clippy::too_many_arguments)]
-impl<TTxStream, TTxReplyStream, TTxFinalizedStream, BNResponseStream, TBackend, TTxCancelledStream>
-    TransactionService<TTxStream, TTxReplyStream, TTxFinalizedStream, BNResponseStream, TBackend, TTxCancelledStream>
+impl<
+        TTxStream,
+        TTxReplyStream,
+        TTxFinalizedStream,
+        BNResponseStream,
+        TBackend,
+        TTxCancelledStream,
+        TPaymentRequestStream,
+    >
+    TransactionService<
+        TTxStream,
+        TTxReplyStream,
+        TTxFinalizedStream,
+        BNResponseStream,
+        TBackend,
+        TTxCancelledStream,
+        TPaymentRequestStream,
+    >
 where
     TTxStream: Stream<Item = DomainMessage<proto::TransactionSenderMessage>>,
     TTxReplyStream: Stream<Item = DomainMessage<proto::RecipientSignedMessage>>,
     TTxFinalizedStream: Stream<Item = DomainMessage<proto::TransactionFinalizedMessage>>,
     BNResponseStream: Stream<Item = DomainMessage<base_node_proto::BaseNodeServiceResponse>>,
     TTxCancelledStream: Stream<Item = DomainMessage<proto::TransactionCancelledMessage>>,
+    TPaymentRequestStream: Stream<Item = DomainMessage<proto::PaymentRequestMessage>>,
     TBackend: TransactionBackend + 'static,
 {
     pub fn new(
@@ -163,12 +234,15 @@ where
         transaction_finalized_stream: TTxFinalizedStream,
         base_node_response_stream: BNResponseStream,
         transaction_cancelled_stream: TTxCancelledStream,
+        payment_request_stream: TPaymentRequestStream,
         output_manager_service: OutputManagerHandle,
+        base_node_service: BaseNodeServiceHandle,
         outbound_message_service: OutboundMessageRequester,
         connectivity_manager: ConnectivityRequester,
         event_publisher: TransactionEventSender,
         node_identity: Arc<NodeIdentity>,
         factories: CryptoFactories,
+        audit_log: Option<AuditLogHandle>,
         shutdown_signal: ShutdownSignal,
     ) -> Self {
         // Collect the resources that all protocols will need so that they can be neatly cloned as the protocols are
@@ -182,7 +256,7 @@ where
             node_identity: node_identity.clone(),
             factories,
             config: config.clone(),
-
+            audit_log,
             shutdown_signal,
         };
         let (timeout_update_publisher, _) = broadcast::channel(20);
@@ -192,11 +266,13 @@ where
             config,
             db,
             output_manager_service,
+            base_node_service,
             transaction_stream: Some(transaction_stream),
             transaction_reply_stream: Some(transaction_reply_stream),
             transaction_finalized_stream: Some(transaction_finalized_stream),
             base_node_response_stream: Some(base_node_response_stream),
             transaction_cancelled_stream: Some(transaction_cancelled_stream),
+            payment_request_stream: Some(payment_request_stream),
             request_stream: Some(request_stream),
             event_publisher,
             node_identity,
@@ -209,6 +285,7 @@ where
             receiver_transaction_cancellation_senders: HashMap::new(),
             active_transaction_broadcast_protocols: HashSet::new(),
             active_coinbase_monitoring_protocols: HashSet::new(),
+            prepared_transactions: HashMap::new(),
             timeout_update_publisher,
             base_node_update_publisher,
             power_mode: PowerMode::Normal,
@@ -253,6 +330,13 @@ where
             .expect("Transaction Service initialized without transaction_cancelled_stream")
             .fuse();
         pin_mut!(transaction_cancelled_stream);
+        let payment_request_stream = self
+            .payment_request_stream
+            .take()
+            .expect("Transaction Service initialized without payment_request_stream")
+            .fuse();
+        pin_mut!(payment_request_stream);
+        let mut base_node_events = self.base_node_service.get_event_stream_fused();
 
         let mut shutdown = self.resources.shutdown_signal.clone();
 
@@ -437,6 +521,39 @@ where
                         finish.duration_since(start).as_millis(),
                     );
                 }
+                // Incoming Payment Request messages from the Comms layer
+                msg = payment_request_stream.select_next_some() => {
+                    let (origin_public_key, inner_msg) = msg.clone().into_origin_and_inner();
+                    trace!(target: LOG_TARGET, "Handling Payment Request message, Trace: {}", msg.dht_header.message_tag);
+                    match self.handle_payment_request_message(origin_public_key, inner_msg).await {
+                        Err(TransactionServiceError::RepeatedMessageError) => {
+                            trace!(target: LOG_TARGET, "A repeated Payment Request message was received, Trace: {}",
+                            msg.dht_header.message_tag);
+                        },
+                        Err(e) => {
+                            warn!(target: LOG_TARGET, "Error handling Payment Request message: {:?}, Trace: {}", e,
+                            msg.dht_header.message_tag);
+                        },
+                        Ok(_) => (),
+                    }
+                }
+                // Chain reorgs observed by the Base Node Service
+                event = base_node_events.select_next_some() => {
+                    match event {
+                        Ok(event) => {
+                            if let BaseNodeEvent::ChainReorg(reorg) = &*event {
+                                if let Err(e) = self.handle_chain_reorg(
+                                    reorg.clone(),
+                                    &mut transaction_broadcast_protocol_handles,
+                                    &mut coinbase_transaction_monitoring_protocol_handles,
+                                ).await {
+                                    warn!(target: LOG_TARGET, "Error handling base node chain reorg: {:?}", e);
+                                }
+                            }
+                        },
+                        Err(_) => debug!(target: LOG_TARGET, "Lagging read on Base Node Service event broadcast channel"),
+                    }
+                }
                 join_result = send_transaction_protocol_handles.select_next_some() => {
                     trace!(target: LOG_TARGET, "Send Protocol for Transaction has ended with result {:?}", join_result);
                     match join_result {
@@ -513,9 +630,34 @@ where
     ) -> Result<TransactionServiceResponse, TransactionServiceError> {
         trace!(target: LOG_TARGET, "Handling Service Request: {}", request);
         match request {
-            TransactionServiceRequest::SendTransaction(dest_pubkey, amount, fee_per_gram, message) => self
+            TransactionServiceRequest::SendTransaction(dest_pubkey, amount, fee_per_gram, message, idempotency_key) => {
+                if let Some(tx_id) = self.find_idempotent_send(idempotency_key.as_deref()).await? {
+                    return Ok(TransactionServiceResponse::TransactionSent(tx_id));
+                }
+                let tx_id = self
+                    .send_transaction(
+                        DEFAULT_ACCOUNT.to_string(),
+                        dest_pubkey,
+                        amount,
+                        fee_per_gram,
+                        message,
+                        send_transaction_join_handles,
+                        transaction_broadcast_join_handles,
+                    )
+                    .await?;
+                self.record_idempotency_key(idempotency_key, tx_id).await;
+                Ok(TransactionServiceResponse::TransactionSent(tx_id))
+            },
+            TransactionServiceRequest::SendTransactionFromAccount {
+                account,
+                destination,
+                amount,
+                fee_per_gram,
+                message,
+            } => self
                 .send_transaction(
-                    dest_pubkey,
+                    account,
+                    destination,
                     amount,
                     fee_per_gram,
                     message,
@@ -524,9 +666,16 @@ where
                 )
                 .await
                 .map(TransactionServiceResponse::TransactionSent),
-            TransactionServiceRequest::SendOneSidedTransaction(dest_pubkey, amount, fee_per_gram, message) => self
-                .send_one_sided_transaction(
-                    dest_pubkey,
+            TransactionServiceRequest::SendInternalTransfer {
+                source_account,
+                destination_account,
+                amount,
+                fee_per_gram,
+                message,
+            } => self
+                .send_internal_transfer(
+                    source_account,
+                    destination_account,
                     amount,
                     fee_per_gram,
                     message,
@@ -534,10 +683,61 @@ where
                 )
                 .await
                 .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::SendAll {
+                destination,
+                fee_per_gram,
+                message,
+            } => self
+                .send_all(destination, fee_per_gram, message, send_transaction_join_handles)
+                .await
+                .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::SendOneSidedTransaction(
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                message,
+                idempotency_key,
+            ) => {
+                if let Some(tx_id) = self.find_idempotent_send(idempotency_key.as_deref()).await? {
+                    return Ok(TransactionServiceResponse::TransactionSent(tx_id));
+                }
+                let tx_id = self
+                    .send_one_sided_transaction(
+                        dest_pubkey,
+                        amount,
+                        fee_per_gram,
+                        message,
+                        transaction_broadcast_join_handles,
+                    )
+                    .await?;
+                self.record_idempotency_key(idempotency_key, tx_id).await;
+                Ok(TransactionServiceResponse::TransactionSent(tx_id))
+            },
             TransactionServiceRequest::CancelTransaction(tx_id) => self
                 .cancel_pending_transaction(tx_id)
                 .await
                 .map(|_| TransactionServiceResponse::TransactionCancelled),
+            TransactionServiceRequest::ReclaimOneSided(tx_id) => self
+                .reclaim_one_sided(tx_id)
+                .await
+                .map(TransactionServiceResponse::OneSidedReclaimed),
+            TransactionServiceRequest::PrepareTransaction {
+                destination,
+                amount,
+                fee_per_gram,
+                message,
+            } => self
+                .prepare_transaction(destination, amount, fee_per_gram, message)
+                .await
+                .map(|prepared| TransactionServiceResponse::TransactionPrepared(Box::new(prepared))),
+            TransactionServiceRequest::ConfirmSend(prep_id) => self
+                .confirm_send(prep_id, send_transaction_join_handles)
+                .await
+                .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::AbortPrepared(prep_id) => self
+                .abort_prepared(prep_id)
+                .await
+                .map(|_| TransactionServiceResponse::PreparedTransactionAborted),
             TransactionServiceRequest::GetPendingInboundTransactions => {
                 Ok(TransactionServiceResponse::PendingInboundTransactions(
                     self.db.get_pending_inbound_transactions().await?,
@@ -552,6 +752,16 @@ where
             TransactionServiceRequest::GetCompletedTransactions => Ok(
                 TransactionServiceResponse::CompletedTransactions(self.db.get_completed_transactions().await?),
             ),
+            TransactionServiceRequest::GetCompletedTransactionsPage(filter, cursor, limit) => {
+                Ok(TransactionServiceResponse::CompletedTransactionsPage(
+                    self.db.get_completed_transactions_page(filter, cursor, limit).await?,
+                ))
+            },
+            TransactionServiceRequest::SearchTransactions(query, cursor, limit) => {
+                Ok(TransactionServiceResponse::TransactionSearchResults(
+                    self.db.search_transactions(query, cursor, limit).await?,
+                ))
+            },
             TransactionServiceRequest::GetCancelledPendingInboundTransactions => {
                 Ok(TransactionServiceResponse::PendingInboundTransactions(
                     self.db.get_cancelled_pending_inbound_transactions().await?,
@@ -587,8 +797,16 @@ where
                 .submit_coin_split_transaction(transaction_broadcast_join_handles, tx_id, tx, fee, amount, message)
                 .await
                 .map(|_| TransactionServiceResponse::TransactionSubmitted),
-            TransactionServiceRequest::GenerateCoinbaseTransaction(reward, fees, block_height) => self
-                .generate_coinbase_transaction(reward, fees, block_height, coinbase_monitoring_join_handles)
+            TransactionServiceRequest::CreateChildPaysForParentTransaction(parent_tx_id, fee_per_gram) => self
+                .create_child_pays_for_parent_transaction(
+                    parent_tx_id,
+                    fee_per_gram,
+                    transaction_broadcast_join_handles,
+                )
+                .await
+                .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::GenerateCoinbaseTransaction(reward, fees, block_height, payouts) => self
+                .generate_coinbase_transaction(reward, fees, block_height, payouts, coinbase_monitoring_join_handles)
                 .await
                 .map(|tx| TransactionServiceResponse::CoinbaseTransactionGenerated(Box::new(tx))),
             #[cfg(feature = "test_harness")]
@@ -664,16 +882,42 @@ where
                 .set_completed_transaction_validity(tx_id, validity)
                 .await
                 .map(|_| TransactionServiceResponse::CompletedTransactionValidityChanged),
+            TransactionServiceRequest::CreateInvoice(payer_pubkey, amount, expires_in, memo) => self
+                .create_invoice(payer_pubkey, amount, expires_in, memo)
+                .await
+                .map(TransactionServiceResponse::InvoiceCreated),
+            TransactionServiceRequest::GetInvoices => {
+                Ok(TransactionServiceResponse::Invoices(self.db.get_invoices().await?))
+            },
+            TransactionServiceRequest::PayInvoice(invoice_id, fee_per_gram) => self
+                .pay_invoice(
+                    invoice_id,
+                    fee_per_gram,
+                    send_transaction_join_handles,
+                    transaction_broadcast_join_handles,
+                )
+                .await
+                .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::GeneratePaymentProof(tx_id) => self
+                .generate_payment_proof(tx_id)
+                .await
+                .map(|proof| TransactionServiceResponse::PaymentProof(Box::new(proof))),
+            TransactionServiceRequest::VerifyPaymentProof(proof) => self
+                .verify_payment_proof(*proof)
+                .await
+                .map(|_| TransactionServiceResponse::PaymentProofVerified),
         }
     }
 
     /// Sends a new transaction to a recipient
     /// # Arguments
+    /// 'account': The output manager account that the transaction spends from
     /// 'dest_pubkey': The Comms pubkey of the recipient node
     /// 'amount': The amount of Tari to send to the recipient
     /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
     pub async fn send_transaction(
         &mut self,
+        account: String,
         dest_pubkey: CommsPublicKey,
         amount: MicroTari,
         fee_per_gram: MicroTari,
@@ -689,43 +933,252 @@ where
                 target: LOG_TARGET,
                 "Received transaction with spend-to-self transaction"
             );
+            return self
+                .send_internal_transfer(
+                    account.clone(),
+                    account,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    transaction_broadcast_join_handles,
+                )
+                .await;
+        }
 
-            let (tx_id, fee, transaction) = self
-                .output_manager_service
-                .create_pay_to_self_transaction(amount, fee_per_gram, None, message.clone())
-                .await?;
+        let sender_protocol = self
+            .output_manager_service
+            .prepare_transaction_to_send_from_account(
+                account,
+                amount,
+                fee_per_gram,
+                None,
+                message.clone(),
+                script!(Nop),
+            )
+            .await?;
 
-            // Notify that the transaction was successfully resolved.
-            let _ = self
-                .event_publisher
-                .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(tx_id)));
+        self.start_transaction_send_protocol(dest_pubkey, amount, message, sender_protocol, join_handles)
+    }
 
-            self.submit_transaction(
-                transaction_broadcast_join_handles,
-                CompletedTransaction::new(
-                    tx_id,
-                    self.node_identity.public_key().clone(),
-                    self.node_identity.public_key().clone(),
-                    amount,
-                    fee,
-                    transaction,
-                    TransactionStatus::Completed,
-                    message,
-                    Utc::now().naive_utc(),
-                    TransactionDirection::Inbound,
-                    None,
-                ),
+    /// Moves funds between the wallet's own accounts, or back to the same account to consolidate outputs. There is no
+    /// counterparty, so the two-party protocol is skipped: the output manager builds and signs the transaction and it
+    /// is submitted for broadcast immediately.
+    /// # Arguments
+    /// 'source_account': The output manager account that the transaction spends from
+    /// 'destination_account': The output manager account that receives the amount
+    /// 'amount': The amount of Tari to transfer
+    /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
+    pub async fn send_internal_transfer(
+        &mut self,
+        source_account: String,
+        destination_account: String,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<TxId, TransactionServiceError> {
+        let (tx_id, fee, transaction) = self
+            .output_manager_service
+            .create_internal_transfer(
+                source_account,
+                destination_account,
+                amount,
+                fee_per_gram,
+                message.clone(),
+            )
+            .await?;
+
+        // Notify that the transaction was successfully resolved.
+        self.resources.record_audit(
+            AuditAction::TransactionSigned,
+            format!("Signed transaction {}, which was completed immediately", tx_id),
+        );
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(tx_id)));
+
+        self.submit_transaction(
+            transaction_broadcast_join_handles,
+            CompletedTransaction::new(
+                tx_id,
+                self.node_identity.public_key().clone(),
+                self.node_identity.public_key().clone(),
+                amount,
+                fee,
+                transaction,
+                TransactionStatus::Completed,
+                message,
+                Utc::now().naive_utc(),
+                TransactionDirection::Internal,
+                None,
+            ),
+        )
+        .await?;
+
+        Ok(tx_id)
+    }
+
+    /// Selects the inputs and computes the fee of a send to a recipient, holding the result until the send is
+    /// confirmed or aborted. The selected outputs stay encumbered only short-term, so they are released on restart.
+    /// # Arguments
+    /// 'dest_pubkey': The Comms pubkey of the recipient node
+    /// 'amount': The amount of Tari to send to the recipient
+    /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
+    pub async fn prepare_transaction(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<PreparedTransaction, TransactionServiceError> {
+        self.expire_prepared_transactions().await;
+
+        // Spend-to-self transactions are completed immediately, so there is nothing to confirm
+        if self.node_identity.public_key() == &dest_pubkey {
+            return Err(TransactionServiceError::OperationNotSupported);
+        }
+
+        let sender_protocol = self
+            .output_manager_service
+            .prepare_transaction_to_send_from_account(
+                DEFAULT_ACCOUNT.to_string(),
+                amount,
+                fee_per_gram,
+                None,
+                message.clone(),
+                script!(Nop),
             )
             .await?;
+        let prep_id = sender_protocol.get_tx_id()?;
+        let fee = sender_protocol.get_fee_amount()?;
+        let change = sender_protocol.get_change_amount()?;
+
+        let inputs = match self.output_manager_service.get_pending_transactions().await {
+            Ok(mut pending) => pending
+                .remove(&prep_id)
+                .map(|p| {
+                    p.outputs_to_be_spent
+                        .into_iter()
+                        .map(|o| (o.commitment, o.unblinded_output.value))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Err(e) => {
+                // Release the encumbered outputs rather than leave a preparation the caller cannot review
+                let _ = self.output_manager_service.cancel_transaction(prep_id).await;
+                return Err(e.into());
+            },
+        };
 
-            return Ok(tx_id);
+        self.prepared_transactions.insert(prep_id, PreparedSend {
+            dest_pubkey: dest_pubkey.clone(),
+            amount,
+            message,
+            sender_protocol,
+            prepared_at: Instant::now(),
+        });
+        debug!(
+            target: LOG_TARGET,
+            "Prepared transaction (TxId: {}) of {} with fee {} awaiting confirmation", prep_id, amount, fee
+        );
+
+        Ok(PreparedTransaction {
+            prep_id,
+            destination: dest_pubkey,
+            amount,
+            fee,
+            inputs,
+            change,
+        })
+    }
+
+    /// Starts the send protocol for a prepared transaction
+    pub async fn confirm_send(
+        &mut self,
+        prep_id: TxId,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<TxId, TransactionServiceError> {
+        self.expire_prepared_transactions().await;
+        let prepared = self
+            .prepared_transactions
+            .remove(&prep_id)
+            .ok_or(TransactionServiceError::PreparedTransactionNotFound(prep_id))?;
+
+        self.start_transaction_send_protocol(
+            prepared.dest_pubkey,
+            prepared.amount,
+            prepared.message,
+            prepared.sender_protocol,
+            join_handles,
+        )
+    }
+
+    /// Discards a prepared transaction and releases its encumbered outputs
+    pub async fn abort_prepared(&mut self, prep_id: TxId) -> Result<(), TransactionServiceError> {
+        if self.prepared_transactions.remove(&prep_id).is_none() {
+            return Err(TransactionServiceError::PreparedTransactionNotFound(prep_id));
+        }
+        self.output_manager_service.cancel_transaction(prep_id).await?;
+        debug!(target: LOG_TARGET, "Aborted prepared transaction (TxId: {})", prep_id);
+        Ok(())
+    }
+
+    /// Aborts any prepared transactions that have waited longer than `PREPARED_TRANSACTION_TIMEOUT` for confirmation
+    async fn expire_prepared_transactions(&mut self) {
+        let expired = self
+            .prepared_transactions
+            .iter()
+            .filter(|(_, p)| p.prepared_at.elapsed() > PREPARED_TRANSACTION_TIMEOUT)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for prep_id in expired {
+            self.prepared_transactions.remove(&prep_id);
+            if let Err(e) = self.output_manager_service.cancel_transaction(prep_id).await {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not release outputs of expired prepared transaction (TxId: {}): {}", prep_id, e
+                );
+            } else {
+                debug!(target: LOG_TARGET, "Expired prepared transaction (TxId: {})", prep_id);
+            }
+        }
+    }
+
+    /// Sends every spendable output in the wallet to a recipient in a single transaction without a change output
+    /// # Arguments
+    /// 'dest_pubkey': The Comms pubkey of the recipient node
+    /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
+    pub async fn send_all(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        fee_per_gram: MicroTari,
+        message: String,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<TxId, TransactionServiceError> {
+        if self.node_identity.public_key() == &dest_pubkey {
+            warn!(target: LOG_TARGET, "Send all to self transactions not supported");
+            return Err(TransactionServiceError::SendAllToSelf);
         }
 
         let sender_protocol = self
             .output_manager_service
-            .prepare_transaction_to_send(amount, fee_per_gram, None, message.clone(), script!(Nop))
+            .prepare_transaction_to_send_all(fee_per_gram, None, message.clone(), script!(Nop))
             .await?;
+        let amount = sender_protocol.get_total_amount()?;
+
+        self.start_transaction_send_protocol(dest_pubkey, amount, message, sender_protocol, join_handles)
+    }
 
+    fn start_transaction_send_protocol(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        message: String,
+        sender_protocol: SenderTransactionProtocol,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<TxId, TransactionServiceError> {
         let tx_id = sender_protocol.get_tx_id()?;
 
         let (tx_reply_sender, tx_reply_receiver) = mpsc::channel(100);
@@ -746,12 +1199,44 @@ where
             TransactionSendProtocolStage::Initial,
         );
 
-        let join_handle = tokio::spawn(protocol.execute());
+        let join_handle = tokio::spawn(protocol.execute().with_log_field("tx_id", tx_id));
         join_handles.push(join_handle);
 
         Ok(tx_id)
     }
 
+    /// Returns the TxId of the transaction that was previously sent with this idempotency key, if there is one
+    async fn find_idempotent_send(
+        &self,
+        idempotency_key: Option<&str>,
+    ) -> Result<Option<TxId>, TransactionServiceError> {
+        let key = match idempotency_key {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let tx_id = self.db.find_tx_id_by_idempotency_key(key.to_string()).await?;
+        if let Some(tx_id) = tx_id {
+            debug!(
+                target: LOG_TARGET,
+                "Send request with idempotency key `{}` was already processed as TxId: {}", key, tx_id
+            );
+        }
+        Ok(tx_id)
+    }
+
+    /// Records the transaction created for an idempotency key. The transaction has already been created at this point
+    /// so a failure is logged rather than returned, otherwise the client would retry and send a second payment.
+    async fn record_idempotency_key(&self, idempotency_key: Option<String>, tx_id: TxId) {
+        if let Some(key) = idempotency_key {
+            if let Err(e) = self.db.add_idempotency_key(key.clone(), tx_id).await {
+                error!(
+                    target: LOG_TARGET,
+                    "Could not record idempotency key `{}` for TxId: {}: {}", key, tx_id, e
+                );
+            }
+        }
+    }
+
     /// Sends a one side payment transaction to a recipient
     /// # Arguments
     /// 'dest_pubkey': The Comms pubkey of the recipient node
@@ -774,17 +1259,30 @@ where
             ));
         }
 
+        // A reclaimable payment can be spent by the sender with the refund key from the lock height onwards
+        let refund = match self.resources.config.one_sided_reclaim_lock_blocks {
+            Some(lock_blocks) => {
+                let lock_height = self.chain_tip_height().await? + lock_blocks;
+                let refund_key = PrivateKey::random(&mut OsRng);
+                let script = ReclaimableOneSidedScript::new(
+                    dest_pubkey.clone(),
+                    PublicKey::from_secret_key(&refund_key),
+                    lock_height,
+                );
+                Some((script, refund_key))
+            },
+            None => None,
+        };
+        let script = match &refund {
+            Some((script, _)) => script.to_script(),
+            None => script!(PushPubKey(Box::new(dest_pubkey.clone()))),
+        };
+
         // Prepare sender part of the transaction
 
         let mut stp = self
             .output_manager_service
-            .prepare_transaction_to_send(
-                amount,
-                fee_per_gram,
-                None,
-                message.clone(),
-                script!(PushPubKey(Box::new(dest_pubkey.clone()))),
-            )
+            .prepare_transaction_to_send(amount, fee_per_gram, None, message.clone(), script)
             .await?;
         let tx_id = stp.get_tx_id()?;
 
@@ -808,10 +1306,25 @@ where
             .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
         // TODO: Add a standardized Diffie-Hellman method to the tari_crypto library that will return a private key,
         // TODO: then come back and use it here.
-        let spending_key = PrivateKey::from_bytes(
-            CommsPublicKey::shared_secret(&sender_offset_private_key.clone(), &dest_pubkey.clone()).as_bytes(),
+        let shared_secret = CommsPublicKey::shared_secret(&sender_offset_private_key, &dest_pubkey);
+        let spending_key = PrivateKey::from_bytes(shared_secret.as_bytes())
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+
+        // The value and message are encrypted to the recipient so that the output can be recovered without rewinding
+        // the range proof
+        let commitment = self
+            .resources
+            .factories
+            .commitment
+            .commit_value(&spending_key, amount.into());
+        let message_bytes = message.as_bytes();
+        let encrypted_data = EncryptedData::encrypt(
+            &shared_secret,
+            &commitment,
+            amount,
+            &message_bytes[..min(message_bytes.len(), MAX_ENCRYPTED_MESSAGE_BYTES)],
         )
-        .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        .map_err(|e| TransactionServiceProtocolError::new(tx_id, TransactionError::from(e).into()))?;
 
         let sender_message = TransactionSenderMessage::new_single_round_message(stp.get_single_round_message()?);
         let rewind_key = PrivateKey::from_bytes(&hash_secret_key(&spending_key))?;
@@ -825,8 +1338,8 @@ where
         let rtp = ReceiverTransactionProtocol::new_with_rewindable_output(
             sender_message,
             PrivateKey::random(&mut OsRng),
-            spending_key,
-            OutputFeatures::default(),
+            spending_key.clone(),
+            OutputFeatures::with_encrypted_data(encrypted_data),
             &self.resources.factories,
             &rewind_data,
         );
@@ -852,6 +1365,10 @@ where
 
         // This event being sent is important, but not critical to the protocol being successful. Send only fails if
         // there are no subscribers.
+        self.resources.record_audit(
+            AuditAction::TransactionSigned,
+            format!("Signed transaction {}, which was completed immediately", tx_id),
+        );
         let _ = self
             .event_publisher
             .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(tx_id)));
@@ -864,6 +1381,10 @@ where
         let fee = stp
             .get_fee_amount()
             .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        if let Some((_, refund_key)) = refund {
+            self.record_sent_one_sided_output(tx_id, &tx, amount, spending_key, refund_key)
+                .await;
+        }
         self.submit_transaction(
             transaction_broadcast_join_handles,
             CompletedTransaction::new(
@@ -885,6 +1406,99 @@ where
         Ok(tx_id)
     }
 
+    /// Keeps the output of a reclaimable one-sided payment in the output manager, so that it can be reclaimed if the
+    /// recipient does not spend it before the lock height. The payment has already been finalized at this point, so a
+    /// failure is logged rather than returned.
+    async fn record_sent_one_sided_output(
+        &mut self,
+        tx_id: TxId,
+        tx: &Transaction,
+        amount: MicroTari,
+        spending_key: PrivateKey,
+        refund_key: PrivateKey,
+    ) {
+        let commitment = self
+            .resources
+            .factories
+            .commitment
+            .commit_value(&spending_key, u64::from(amount));
+        let output = match tx.body.outputs().iter().find(|o| o.commitment == commitment) {
+            Some(output) => output.clone(),
+            None => {
+                error!(
+                    target: LOG_TARGET,
+                    "The recipient output of one-sided transaction TxId: {} was not found, it cannot be reclaimed",
+                    tx_id
+                );
+                return;
+            },
+        };
+        let output = UnblindedOutput::new(
+            amount,
+            spending_key,
+            Some(output.features),
+            output.script,
+            ExecutionStack::new(vec![]),
+            refund_key,
+            output.sender_offset_public_key,
+            output.metadata_signature,
+            output.covenant,
+        );
+        if let Err(e) = self
+            .output_manager_service
+            .add_sent_one_sided_output(tx_id, output)
+            .await
+        {
+            error!(
+                target: LOG_TARGET,
+                "Could not record the reclaimable output of one-sided transaction TxId: {}: {}", tx_id, e
+            );
+        }
+    }
+
+    /// Reclaims the output of a reclaimable one-sided payment that the recipient has not spent before its lock
+    /// height. The output becomes an unspent output of this wallet and is re-validated straight away, in case the
+    /// recipient did spend it after all.
+    async fn reclaim_one_sided(&mut self, tx_id: TxId) -> Result<MicroTari, TransactionServiceError> {
+        let tx = self.db.get_completed_transaction(tx_id).await?;
+        let is_mined = matches!(
+            tx.status,
+            TransactionStatus::MinedUnconfirmed | TransactionStatus::MinedConfirmed
+        );
+        if tx.direction != TransactionDirection::Outbound || !is_mined {
+            return Err(TransactionServiceError::OneSidedPaymentNotReclaimable(tx_id));
+        }
+
+        let tip_height = self.chain_tip_height().await?;
+        let value = self
+            .output_manager_service
+            .reclaim_one_sided_output(tx_id, tip_height)
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Reclaimed {} from one-sided transaction TxId: {}", value, tx_id
+        );
+
+        // This event being sent is important, but not critical to the protocol being successful. Send only fails if
+        // there are no subscribers.
+        self.resources.record_audit(
+            AuditAction::OneSidedReclaimed,
+            format!("Reclaimed the one-sided payment of transaction {}", tx_id),
+        );
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::OneSidedReclaimed(tx_id)));
+        Ok(value)
+    }
+
+    async fn chain_tip_height(&mut self) -> Result<u64, TransactionServiceError> {
+        self.base_node_service
+            .get_chain_metadata()
+            .await?
+            .map(|metadata| metadata.height_of_longest_chain())
+            .ok_or(TransactionServiceError::ChainTipUnknown)
+    }
+
     /// Accept the public reply from a recipient and apply the reply to the relevant transaction protocol
     /// # Arguments
     /// 'recipient_reply' - The public response from a recipient with data required to complete the transaction
@@ -1026,6 +1640,12 @@ where
             Ok(id) => {
                 let _ = self.pending_transaction_reply_senders.remove(&id);
                 let _ = self.send_transaction_cancellation_senders.remove(&id);
+                if let Err(e) = self.complete_invoice_payment(id).await {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Error completing invoice payment for TxId: {}: {:?}", id, e
+                    );
+                }
                 let completed_tx = match self.db.get_completed_transaction(id).await {
                     Ok(v) => v,
                     Err(e) => {
@@ -1079,7 +1699,7 @@ where
             e
         })?;
 
-        self.output_manager_service.cancel_transaction(tx_id).await?;
+        let descendant_tx_ids = self.output_manager_service.cancel_transaction(tx_id).await?;
 
         if let Some(cancellation_sender) = self.send_transaction_cancellation_senders.remove(&tx_id) {
             let _ = cancellation_sender.send(());
@@ -1091,6 +1711,10 @@ where
         }
         let _ = self.finalized_transaction_senders.remove(&tx_id);
 
+        self.resources.record_audit(
+            AuditAction::TransactionCancelled,
+            format!("Cancelled transaction {}", tx_id),
+        );
         let _ = self
             .event_publisher
             .send(Arc::new(TransactionEvent::TransactionCancelled(tx_id)))
@@ -1105,9 +1729,49 @@ where
 
         info!(target: LOG_TARGET, "Pending Transaction (TxId: {}) cancelled", tx_id);
 
+        self.cancel_descendant_transactions(descendant_tx_ids).await;
+
         Ok(())
     }
 
+    /// Cancel the transactions that spent the unconfirmed change of a cancelled transaction. The Output Manager has
+    /// already released their outputs, and without their ancestor they can never be mined.
+    async fn cancel_descendant_transactions(&mut self, tx_ids: Vec<TxId>) {
+        for tx_id in tx_ids {
+            let result = match self.db.cancel_pending_transaction(tx_id).await {
+                Ok(()) => Ok(()),
+                Err(_) => self.db.cancel_completed_transaction(tx_id).await,
+            };
+            if let Err(e) = result {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not cancel Transaction (TxId: {}) that spent the change of a cancelled transaction: {:?}",
+                    tx_id,
+                    e
+                );
+                continue;
+            }
+
+            if let Some(cancellation_sender) = self.send_transaction_cancellation_senders.remove(&tx_id) {
+                let _ = cancellation_sender.send(());
+            }
+            let _ = self.pending_transaction_reply_senders.remove(&tx_id);
+
+            self.resources.record_audit(
+                AuditAction::TransactionCancelled,
+                format!("Cancelled transaction {}", tx_id),
+            );
+            let _ = self
+                .event_publisher
+                .send(Arc::new(TransactionEvent::TransactionCancelled(tx_id)));
+
+            info!(
+                target: LOG_TARGET,
+                "Transaction (TxId: {}) cancelled along with its unconfirmed ancestor", tx_id
+            );
+        }
+    }
+
     async fn set_completed_transaction_validity(
         &mut self,
         tx_id: TxId,
@@ -1146,6 +1810,249 @@ where
         Ok(())
     }
 
+    /// Creates an invoice asking the payer to pay `amount` and sends it to them
+    pub async fn create_invoice(
+        &mut self,
+        payer_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        expires_in: Duration,
+        memo: String,
+    ) -> Result<InvoiceId, TransactionServiceError> {
+        let invoice_id = OsRng.next_u64();
+        let now = Utc::now().naive_utc();
+        let expires_at = now +
+            chrono::Duration::from_std(expires_in)
+                .map_err(|e| TransactionServiceError::ConversionError(e.to_string()))?;
+
+        let message = proto::PaymentRequestMessage {
+            message: Some(proto::payment_request_message::Message::Invoice(proto::Invoice {
+                invoice_id,
+                amount: u64::from(amount),
+                expires_at: expires_at.timestamp() as u64,
+                memo: memo.clone(),
+            })),
+        };
+        self.db
+            .add_invoice(Invoice::new(
+                invoice_id,
+                payer_pubkey.clone(),
+                amount,
+                memo,
+                expires_at,
+                InvoiceDirection::Issued,
+                now,
+            ))
+            .await?;
+
+        self.spawn_send_payment_request_message(invoice_id, message, payer_pubkey);
+        Ok(invoice_id)
+    }
+
+    /// Pays a received invoice by starting a standard send transaction protocol for the invoiced amount. The payee
+    /// is told which transaction pays the invoice so that it can mark the invoice as paid once the transaction
+    /// completes.
+    pub async fn pay_invoice(
+        &mut self,
+        invoice_id: InvoiceId,
+        fee_per_gram: MicroTari,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<TxId, TransactionServiceError> {
+        let invoice = self.db.get_invoice(invoice_id).await?;
+        if invoice.direction != InvoiceDirection::Received {
+            return Err(TransactionServiceError::InvoiceNotPayable(
+                invoice_id,
+                "the invoice was issued by this wallet".to_string(),
+            ));
+        }
+        if invoice.status != InvoiceStatus::Pending {
+            return Err(TransactionServiceError::InvoiceNotPayable(
+                invoice_id,
+                format!("the invoice is {}", invoice.status),
+            ));
+        }
+        if invoice.is_expired(Utc::now().naive_utc()) {
+            return Err(TransactionServiceError::InvoiceExpired(invoice_id));
+        }
+
+        let tx_id = self
+            .send_transaction(
+                DEFAULT_ACCOUNT.to_string(),
+                invoice.counterparty_public_key.clone(),
+                invoice.amount,
+                fee_per_gram,
+                invoice.memo,
+                join_handles,
+                transaction_broadcast_join_handles,
+            )
+            .await?;
+        self.db
+            .update_invoice_status(invoice_id, InvoiceStatus::Accepted, Some(tx_id))
+            .await?;
+
+        let message = proto::PaymentRequestMessage {
+            message: Some(proto::payment_request_message::Message::Payment(
+                proto::InvoicePayment { invoice_id, tx_id },
+            )),
+        };
+        self.spawn_send_payment_request_message(invoice_id, message, invoice.counterparty_public_key);
+        Ok(tx_id)
+    }
+
+    /// Creates a proof that this wallet paid the receiver of a completed outbound transaction
+    pub async fn generate_payment_proof(&mut self, tx_id: TxId) -> Result<PaymentProof, TransactionServiceError> {
+        let transaction = self.db.get_completed_transaction(tx_id).await?;
+        let rewind_keys = self.output_manager_service.get_rewind_public_keys().await?;
+        let proof = PaymentProof::create(
+            &transaction,
+            &rewind_keys,
+            self.resources.node_identity.secret_key(),
+            &self.resources.factories,
+        )?;
+        Ok(proof)
+    }
+
+    /// Verifies a payment proof. If this wallet is the receiver, this also checks that the wallet owns the paid output.
+    pub async fn verify_payment_proof(&mut self, proof: PaymentProof) -> Result<(), TransactionServiceError> {
+        proof.verify(&self.resources.factories)?;
+        if &proof.receiver_public_key == self.resources.node_identity.public_key() {
+            let rewind_keys = self.output_manager_service.get_rewind_public_keys().await?;
+            proof.verify_receiver_output(&rewind_keys, &self.resources.factories)?;
+        }
+        Ok(())
+    }
+
+    fn spawn_send_payment_request_message(
+        &self,
+        invoice_id: InvoiceId,
+        message: proto::PaymentRequestMessage,
+        destination_public_key: CommsPublicKey,
+    ) {
+        let outbound_message_service = self.resources.outbound_message_service.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                send_payment_request_message(message, destination_public_key, outbound_message_service).await
+            {
+                warn!(
+                    target: LOG_TARGET,
+                    "Error sending Payment Request message for Invoice {}: {:?}", invoice_id, e
+                );
+            }
+        });
+    }
+
+    pub async fn handle_payment_request_message(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        payment_request: proto::PaymentRequestMessage,
+    ) -> Result<(), TransactionServiceError> {
+        match payment_request.message {
+            Some(proto::payment_request_message::Message::Invoice(invoice)) => {
+                self.accept_invoice(source_pubkey, invoice).await
+            },
+            Some(proto::payment_request_message::Message::Payment(payment)) => {
+                self.accept_invoice_payment(source_pubkey, payment).await
+            },
+            None => Err(TransactionServiceError::InvalidMessageError(
+                "Payment Request message is empty".to_string(),
+            )),
+        }
+    }
+
+    async fn accept_invoice(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        invoice: proto::Invoice,
+    ) -> Result<(), TransactionServiceError> {
+        if self.db.get_invoice(invoice.invoice_id).await.is_ok() {
+            return Err(TransactionServiceError::RepeatedMessageError);
+        }
+        let expires_at = i64::try_from(invoice.expires_at)
+            .ok()
+            .and_then(|t| NaiveDateTime::from_timestamp_opt(t, 0))
+            .ok_or_else(|| TransactionServiceError::InvalidMessageError("Invalid invoice expiry".to_string()))?;
+
+        self.db
+            .add_invoice(Invoice::new(
+                invoice.invoice_id,
+                source_pubkey,
+                MicroTari::from(invoice.amount),
+                invoice.memo,
+                expires_at,
+                InvoiceDirection::Received,
+                Utc::now().naive_utc(),
+            ))
+            .await?;
+
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::ReceivedInvoice(invoice.invoice_id)));
+        Ok(())
+    }
+
+    async fn accept_invoice_payment(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        payment: proto::InvoicePayment,
+    ) -> Result<(), TransactionServiceError> {
+        let invoice = self.db.get_invoice(payment.invoice_id).await?;
+        if invoice.direction != InvoiceDirection::Issued || invoice.counterparty_public_key != source_pubkey {
+            return Err(TransactionServiceError::InvalidMessageError(format!(
+                "Invoice payment for Invoice {} received from an unknown source",
+                payment.invoice_id
+            )));
+        }
+        if invoice.status != InvoiceStatus::Pending {
+            return Err(TransactionServiceError::RepeatedMessageError);
+        }
+
+        self.db
+            .update_invoice_status(invoice.invoice_id, InvoiceStatus::Accepted, Some(payment.tx_id))
+            .await?;
+        let _ = self.event_publisher.send(Arc::new(TransactionEvent::InvoiceAccepted(
+            invoice.invoice_id,
+            payment.tx_id,
+        )));
+
+        // The transaction may have been completed before this message arrived
+        if self.db.get_completed_transaction(payment.tx_id).await.is_ok() {
+            self.complete_invoice_payment(payment.tx_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Marks the invoice that is paid by the completed transaction, if there is one, as paid
+    async fn complete_invoice_payment(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        let invoice = match self.db.find_invoice_by_tx_id(tx_id).await? {
+            Some(invoice) if invoice.status == InvoiceStatus::Accepted => invoice,
+            _ => return Ok(()),
+        };
+
+        if invoice.direction == InvoiceDirection::Issued {
+            // The link between the transaction and the invoice was made by the payer, so check that the transaction
+            // is from the payer and for the amount that was asked for
+            let completed_tx = self.db.get_completed_transaction(tx_id).await?;
+            let is_from_payer = completed_tx.source_public_key == invoice.counterparty_public_key;
+            if !is_from_payer || completed_tx.amount < invoice.amount {
+                warn!(
+                    target: LOG_TARGET,
+                    "Transaction (TxId: {}) does not pay Invoice {}", tx_id, invoice.invoice_id
+                );
+                return Ok(());
+            }
+        }
+
+        self.db
+            .update_invoice_status(invoice.invoice_id, InvoiceStatus::Paid, None)
+            .await?;
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::InvoicePaid(invoice.invoice_id, tx_id)));
+        Ok(())
+    }
+
     #[allow(clippy::map_entry)]
     async fn restart_all_send_transaction_protocols(
         &mut self,
@@ -1175,7 +2082,7 @@ where
                     TransactionSendProtocolStage::WaitForReply,
                 );
 
-                let join_handle = tokio::spawn(protocol.execute());
+                let join_handle = tokio::spawn(protocol.execute().with_log_field("tx_id", tx_id));
                 join_handles.push(join_handle);
             }
         }
@@ -1285,7 +2192,7 @@ where
                 cancellation_receiver,
             );
 
-            let join_handle = tokio::spawn(protocol.execute());
+            let join_handle = tokio::spawn(protocol.execute().with_log_field("tx_id", data.tx_id));
             join_handles.push(join_handle);
             Ok(())
         } else {
@@ -1341,6 +2248,12 @@ where
             Ok(id) => {
                 let _ = self.finalized_transaction_senders.remove(&id);
                 let _ = self.receiver_transaction_cancellation_senders.remove(&id);
+                if let Err(e) = self.complete_invoice_payment(id).await {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Error completing invoice payment for TxId: {}: {:?}", id, e
+                    );
+                }
 
                 let completed_tx = match self.db.get_completed_transaction(id).await {
                     Ok(v) => v,
@@ -1421,7 +2334,7 @@ where
                     cancellation_receiver,
                 );
 
-                let join_handle = tokio::spawn(protocol.execute());
+                let join_handle = tokio::spawn(protocol.execute().with_log_field("tx_id", tx_id));
                 join_handles.push(join_handle);
             }
         }
@@ -1575,6 +2488,90 @@ where
         Ok(())
     }
 
+    /// Revert the transactions that were mined in blocks replaced by a base node reorg, re-check the spent status of
+    /// the outputs they touched and start monitoring the transactions again.
+    async fn handle_chain_reorg(
+        &mut self,
+        reorg: ChainReorg,
+        broadcast_join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+        coinbase_monitoring_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<(), TransactionServiceError> {
+        let reverted = self.db.revert_transactions_mined_above(reorg.fork_height).await?;
+        if reverted.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "Chain reorg above height {} did not affect any mined transactions", reorg.fork_height
+            );
+            return Ok(());
+        }
+
+        let commitments = reverted
+            .iter()
+            .flat_map(|tx| {
+                let body = &tx.transaction.body;
+                body.inputs()
+                    .iter()
+                    .map(|i| i.commitment.clone())
+                    .chain(body.outputs().iter().map(|o| o.commitment.clone()))
+            })
+            .collect::<Vec<_>>();
+        let rechecked_outputs = match self.output_manager_service.revalidate_outputs(commitments).await {
+            Ok(n) => n,
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not re-check outputs affected by chain reorg: {:?}", e
+                );
+                0
+            },
+        };
+
+        let mut reverted_transactions = Vec::with_capacity(reverted.len());
+        for tx in reverted {
+            let tx_id = tx.tx_id;
+            reverted_transactions.push(tx_id);
+            let result = if tx.status == TransactionStatus::Coinbase {
+                self.start_coinbase_transaction_monitoring_protocol(tx_id, coinbase_monitoring_join_handles)
+                    .await
+            } else {
+                self.broadcast_completed_transaction(tx, broadcast_join_handles).await
+            };
+            if let Err(e) = result {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not restart monitoring of reorged transaction (TxId: {}): {:?}", tx_id, e
+                );
+            }
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "Chain reorg above height {} reverted {} mined transaction(s) and {} output(s) are being re-checked",
+            reorg.fork_height,
+            reverted_transactions.len(),
+            rechecked_outputs
+        );
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::ChainReorg(ReorgImpact {
+                fork_height: reorg.fork_height,
+                reverted_transactions,
+                rechecked_outputs,
+            })))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+
+        Ok(())
+    }
+
     /// Start to protocol to Broadcast the specified Completed Transaction to the Base Node.
     async fn broadcast_completed_transaction(
         &mut self,
@@ -1606,7 +2603,7 @@ where
                         self.timeout_update_publisher.subscribe(),
                         self.base_node_update_publisher.subscribe(),
                     );
-                    let join_handle = tokio::spawn(protocol.execute());
+                    let join_handle = tokio::spawn(protocol.execute().with_log_field("tx_id", tx_id));
                     join_handles.push(join_handle);
                 } else {
                     debug!(
@@ -1801,22 +2798,83 @@ where
         Ok(())
     }
 
+    /// Create and broadcast a transaction that spends the outputs of an unconfirmed inbound transaction back to
+    /// ourselves, paying enough fee for the pair to be mined at `fee_per_gram`.
+    async fn create_child_pays_for_parent_transaction(
+        &mut self,
+        parent_tx_id: TxId,
+        fee_per_gram: MicroTari,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<TxId, TransactionServiceError> {
+        let parent = self.db.get_completed_transaction(parent_tx_id).await?;
+        if parent.direction != TransactionDirection::Inbound ||
+            !matches!(
+                parent.status,
+                TransactionStatus::Completed | TransactionStatus::Broadcast
+            )
+        {
+            return Err(TransactionServiceError::ChildPaysForParentNotPossible(parent_tx_id));
+        }
+        let parent_fee = parent.transaction.body.get_total_fee();
+        let parent_weight = parent.transaction.calculate_weight(&TransactionWeight::latest());
+
+        let (tx_id, fee, amount, tx) = self
+            .output_manager_service
+            .create_child_pays_for_parent_transaction(parent_tx_id, parent_fee, parent_weight, fee_per_gram)
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Created child-pays-for-parent transaction ({}) for parent transaction ({}) with fee {}",
+            tx_id,
+            parent_tx_id,
+            fee
+        );
+
+        self.submit_transaction(
+            transaction_broadcast_join_handles,
+            CompletedTransaction::new(
+                tx_id,
+                self.node_identity.public_key().clone(),
+                self.node_identity.public_key().clone(),
+                amount,
+                fee,
+                tx,
+                TransactionStatus::Completed,
+                format!("Child pays for parent transaction {}", parent_tx_id),
+                Utc::now().naive_utc(),
+                TransactionDirection::Inbound,
+                None,
+            ),
+        )
+        .await?;
+
+        Ok(tx_id)
+    }
+
     async fn generate_coinbase_transaction(
         &mut self,
         reward: MicroTari,
         fees: MicroTari,
         block_height: u64,
+        payouts: Vec<CoinbasePayout>,
         coinbase_monitoring_protocol_join_handles: &mut FuturesUnordered<
             JoinHandle<Result<u64, TransactionServiceProtocolError>>,
         >,
     ) -> Result<Transaction, TransactionServiceError> {
-        let amount = reward + fees;
-
-        // first check if we already have a coinbase tx for this height and amount
-        let find_result = self
-            .db
-            .find_coinbase_transaction_at_block_height(block_height, amount)
-            .await?;
+        // Only the miner's share of a split coinbase is received by this wallet
+        let amount = miner_coinbase_value(reward + fees, &payouts);
+
+        // first check if we already have a coinbase tx for this height and amount. A split coinbase is always rebuilt
+        // as the payout destinations may have changed.
+        let find_result = if payouts.is_empty() {
+            self.db
+                .find_coinbase_transaction_at_block_height(block_height, amount)
+                .await?
+        } else {
+            None
+        };
 
         let (tx_id, completed_transaction) = match find_result {
             Some(completed_tx) => {
@@ -1835,7 +2893,7 @@ where
                 let tx_id = OsRng.next_u64();
                 let tx = self
                     .output_manager_service
-                    .get_coinbase_transaction(tx_id, reward, fees, block_height)
+                    .get_split_coinbase_transaction(tx_id, reward, fees, block_height, payouts)
                     .await?;
 
                 // Cancel existing unmined coinbase transactions for this blockheight
@@ -1932,7 +2990,7 @@ where
                         self.base_node_update_publisher.subscribe(),
                         self.timeout_update_publisher.subscribe(),
                     );
-                    let join_handle = tokio::spawn(protocol.execute());
+                    let join_handle = tokio::spawn(protocol.execute().with_log_field("tx_id", tx_id));
                     join_handles.push(join_handle);
                 } else {
                     debug!(
@@ -2106,7 +3164,7 @@ where
         handle: tokio::runtime::Handle,
     ) -> Result<(), TransactionServiceError> {
         use crate::{
-            base_node_service::{handle::BaseNodeServiceHandle, mock_base_node_service::MockBaseNodeService},
+            base_node_service::mock_base_node_service::MockBaseNodeService,
             output_manager_service::{
                 config::OutputManagerServiceConfig,
                 error::OutputManagerError,
@@ -2114,6 +3172,7 @@ where
                 storage::{database::OutputManagerDatabase, sqlite_db::OutputManagerSqliteDatabase},
             },
             storage::sqlite_utilities::run_migration_and_create_sqlite_connection,
+            test_utils::spawn_key_manager_service,
             transaction_service::{handle::TransactionServiceHandle, storage::models::InboundTransaction},
         };
         use tari_comms::types::CommsSecretKey;
@@ -2148,6 +3207,7 @@ where
         let backend = OutputManagerSqliteDatabase::new(connection, None);
 
         handle.spawn(mock_base_node_service.run());
+        let key_manager = spawn_key_manager_service(&handle, backend.clone(), shutdown_signal.clone());
         let mut fake_oms = OutputManagerService::new(
             OutputManagerServiceConfig::default(),
             ts_handle,
@@ -2159,6 +3219,7 @@ where
             shutdown_signal,
             basenode_service_handle,
             connectivity_manager,
+            key_manager,
             CommsSecretKey::default(),
         )
         .await?;
@@ -2169,7 +3230,14 @@ where
         fake_oms.add_output(None, uo).await?;
 
         let mut stp = fake_oms
-            .prepare_transaction_to_send(amount, MicroTari::from(25), None, "".to_string(), script!(Nop))
+            .prepare_transaction_to_send(
+                DEFAULT_ACCOUNT.to_string(),
+                amount,
+                MicroTari::from(25),
+                None,
+                "".to_string(),
+                script!(Nop),
+            )
             .await?;
 
         let msg = stp.build_single_round_message()?;
@@ -2206,6 +3274,10 @@ where
             .add_pending_inbound_transaction(tx_id, inbound_transaction.clone())
             .await?;
 
+        self.resources.record_audit(
+            AuditAction::TransactionSigned,
+            format!("Signed the recipient part of inbound transaction {}", tx_id),
+        );
         let _ = self
             .event_publisher
             .send(Arc::new(TransactionEvent::ReceivedTransaction(tx_id)))
@@ -2226,7 +3298,6 @@ where
     #[cfg(feature = "test_harness")]
     pub async fn finalize_received_test_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
         use tari_core::transactions::{transaction::KernelBuilder, types::Signature};
-        use tari_crypto::commitment::HomomorphicCommitmentFactory;
 
         let factories = CryptoFactories::default();
 
@@ -2293,9 +3364,35 @@ where TBackend: TransactionBackend + 'static
     pub node_identity: Arc<NodeIdentity>,
     pub factories: CryptoFactories,
     pub config: TransactionServiceConfig,
+    /// Signing operations and cancellations are recorded here, if the wallet keeps an audit log
+    pub audit_log: Option<AuditLogHandle>,
     pub shutdown_signal: ShutdownSignal,
 }
 
+impl<TBackend> TransactionServiceResources<TBackend>
+where TBackend: TransactionBackend + 'static
+{
+    /// Returns a builder for RPC clients to the base node that time out after `deadline`
+    pub fn base_node_rpc_client_builder(&self, deadline: Duration) -> RpcClientBuilder<BaseNodeWalletRpcClient> {
+        #[cfg(feature = "chaos")]
+        {
+            if let Some(injector) = self.config.fault_injector.clone() {
+                return BaseNodeWalletRpcClient::builder()
+                    .with_deadline(deadline)
+                    .with_fault_injection(injector);
+            }
+        }
+        BaseNodeWalletRpcClient::builder().with_deadline(deadline)
+    }
+
+    /// Record a signing operation or destructive action in the audit log, if the wallet keeps one
+    pub fn record_audit<S: Into<String>>(&self, action: AuditAction, details: S) {
+        if let Some(audit_log) = self.audit_log.as_ref() {
+            audit_log.record(action, details);
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 enum PowerMode {
     Low,
@@ -27,6 +27,9 @@ use crate::{
         storage::models::{
             CompletedTransaction,
             InboundTransaction,
+            Invoice,
+            InvoiceId,
+            InvoiceStatus,
             OutboundTransaction,
             TransactionDirection,
             TransactionStatus,
@@ -34,15 +37,14 @@ use crate::{
     },
 };
 use aes_gcm::Aes256Gcm;
-#[cfg(feature = "test_harness")]
-use chrono::NaiveDateTime;
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use log::*;
 
 use crate::transaction_service::storage::models::WalletTransaction;
 use std::{
     collections::HashMap,
     fmt::{Display, Error, Formatter},
+    str::FromStr,
     sync::Arc,
 };
 use tari_comms::types::CommsPublicKey;
@@ -124,6 +126,40 @@ pub trait TransactionBackend: Send + Sync + Clone {
     fn update_confirmations(&self, tx_id: TxId, confirmations: u64) -> Result<(), TransactionStorageError>;
     /// Update a transactions mined height
     fn update_mined_height(&self, tx_id: TxId, mined_height: u64) -> Result<(), TransactionStorageError>;
+    /// Move the transactions mined above the given height back to Broadcast (or Coinbase for coinbase transactions),
+    /// clearing their mined height and confirmations, and return the reverted transactions
+    fn revert_transactions_mined_above(
+        &self,
+        height: u64,
+    ) -> Result<Vec<CompletedTransaction>, TransactionStorageError>;
+    /// Update the status of an invoice and, once known, the transaction that pays it
+    fn update_invoice_status(
+        &self,
+        invoice_id: InvoiceId,
+        status: InvoiceStatus,
+        tx_id: Option<TxId>,
+    ) -> Result<(), TransactionStorageError>;
+    /// Find the invoice that is paid by the transaction with the provided tx_id, if there is one
+    fn find_invoice_by_tx_id(&self, tx_id: TxId) -> Result<Option<Invoice>, TransactionStorageError>;
+    /// Fetch up to `limit` completed transactions matching `filter`, newest first, starting after `cursor`
+    fn fetch_completed_transactions_page(
+        &self,
+        filter: &CompletedTransactionFilter,
+        cursor: Option<&TransactionCursor>,
+        limit: usize,
+    ) -> Result<CompletedTransactionPage, TransactionStorageError>;
+    /// Fetch up to `limit` pending and completed transactions, newest first and starting after `cursor`, whose
+    /// message, invoice memo or counterparty contact alias contains words starting with every word of `query`
+    fn search_transactions(
+        &self,
+        query: &str,
+        cursor: Option<&TransactionCursor>,
+        limit: usize,
+    ) -> Result<TransactionSearchPage, TransactionStorageError>;
+    /// Record the transaction that was created for a client-supplied idempotency key
+    fn insert_idempotency_key(&self, idempotency_key: String, tx_id: TxId) -> Result<(), TransactionStorageError>;
+    /// Find the transaction that was created for a client-supplied idempotency key, if there is one
+    fn find_tx_id_by_idempotency_key(&self, idempotency_key: &str) -> Result<Option<TxId>, TransactionStorageError>;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -140,6 +176,8 @@ pub enum DbKey {
     CancelledPendingOutboundTransaction(TxId),
     CancelledPendingInboundTransaction(TxId),
     AnyTransaction(TxId),
+    Invoice(InvoiceId),
+    Invoices,
 }
 
 #[derive(Debug)]
@@ -151,12 +189,15 @@ pub enum DbValue {
     PendingInboundTransactions(HashMap<TxId, InboundTransaction>),
     CompletedTransactions(HashMap<TxId, CompletedTransaction>),
     WalletTransaction(Box<WalletTransaction>),
+    Invoice(Box<Invoice>),
+    Invoices(Vec<Invoice>),
 }
 
 pub enum DbKeyValuePair {
     PendingOutboundTransaction(TxId, Box<OutboundTransaction>),
     PendingInboundTransaction(TxId, Box<InboundTransaction>),
     CompletedTransaction(TxId, Box<CompletedTransaction>),
+    Invoice(InvoiceId, Box<Invoice>),
 }
 
 pub enum WriteOperation {
@@ -164,6 +205,62 @@ pub enum WriteOperation {
     Remove(DbKey),
 }
 
+/// Restricts the completed transactions returned in a page
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompletedTransactionFilter {
+    pub cancelled: bool,
+    pub direction: Option<TransactionDirection>,
+    /// Transactions with these statuses are left out
+    pub excluded_statuses: Vec<TransactionStatus>,
+    /// Leaves out transfers between the wallet's own accounts
+    pub exclude_internal: bool,
+}
+
+/// The position after the last transaction of a page. Pages are ordered newest first, so a cursor remains valid as
+/// new transactions are added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionCursor {
+    pub timestamp: NaiveDateTime,
+    pub tx_id: TxId,
+}
+
+impl Display for TransactionCursor {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}:{}", self.timestamp.timestamp_nanos(), self.tx_id)
+    }
+}
+
+impl FromStr for TransactionCursor {
+    type Err = TransactionStorageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TransactionStorageError::ConversionError(format!("Invalid transaction cursor '{}'", s));
+        let (nanos, tx_id) = s.split_once(':').ok_or_else(invalid)?;
+        let nanos = nanos.parse::<i64>().map_err(|_| invalid())?;
+        let timestamp =
+            NaiveDateTime::from_timestamp_opt(nanos.div_euclid(1_000_000_000), nanos.rem_euclid(1_000_000_000) as u32)
+                .ok_or_else(invalid)?;
+        let tx_id = tx_id.parse::<TxId>().map_err(|_| invalid())?;
+        Ok(Self { timestamp, tx_id })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompletedTransactionPage {
+    pub transactions: Vec<CompletedTransaction>,
+    /// The number of transactions matching the filter across all pages
+    pub total_count: u64,
+    /// The cursor to fetch the next page with, or `None` if this is the last page
+    pub next_cursor: Option<TransactionCursor>,
+}
+
+#[derive(Debug)]
+pub struct TransactionSearchPage {
+    pub transactions: Vec<WalletTransaction>,
+    /// The cursor to fetch the next page of results with, or `None` if this is the last page
+    pub next_cursor: Option<TransactionCursor>,
+}
+
 /// This structure holds an inner type that implements the `TransactionBackend` trait and contains the more complex
 /// data access logic required by the module built onto the functionality defined by the trait
 #[derive(Clone)]
@@ -738,6 +835,129 @@ where T: TransactionBackend + 'static
             .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
         Ok(())
     }
+
+    pub async fn revert_transactions_mined_above(
+        &self,
+        height: u64,
+    ) -> Result<Vec<CompletedTransaction>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.revert_transactions_mined_above(height))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn add_invoice(&self, invoice: Invoice) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::Invoice(
+                invoice.invoice_id,
+                Box::new(invoice),
+            )))
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_invoice(&self, invoice_id: InvoiceId) -> Result<Invoice, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let key = DbKey::Invoice(invoice_id);
+        let invoice = tokio::task::spawn_blocking(move || match db_clone.fetch(&key) {
+            Ok(None) => Err(TransactionStorageError::ValueNotFound(key)),
+            Ok(Some(DbValue::Invoice(i))) => Ok(i),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(*invoice)
+    }
+
+    pub async fn get_invoices(&self) -> Result<Vec<Invoice>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let key = DbKey::Invoices;
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&key) {
+            Ok(None) => log_error(
+                key,
+                TransactionStorageError::UnexpectedResult("Could not retrieve invoices".to_string()),
+            ),
+            Ok(Some(DbValue::Invoices(i))) => Ok(i),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn update_invoice_status(
+        &self,
+        invoice_id: InvoiceId,
+        status: InvoiceStatus,
+        tx_id: Option<TxId>,
+    ) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.update_invoice_status(invoice_id, status, tx_id))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn find_invoice_by_tx_id(&self, tx_id: TxId) -> Result<Option<Invoice>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.find_invoice_by_tx_id(tx_id))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn add_idempotency_key(
+        &self,
+        idempotency_key: String,
+        tx_id: TxId,
+    ) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.insert_idempotency_key(idempotency_key, tx_id))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn find_tx_id_by_idempotency_key(
+        &self,
+        idempotency_key: String,
+    ) -> Result<Option<TxId>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.find_tx_id_by_idempotency_key(&idempotency_key))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn get_completed_transactions_page(
+        &self,
+        filter: CompletedTransactionFilter,
+        cursor: Option<TransactionCursor>,
+        limit: usize,
+    ) -> Result<CompletedTransactionPage, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.fetch_completed_transactions_page(&filter, cursor.as_ref(), limit))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn search_transactions(
+        &self,
+        query: String,
+        cursor: Option<TransactionCursor>,
+        limit: usize,
+    ) -> Result<TransactionSearchPage, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.search_transactions(&query, cursor.as_ref(), limit))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))
+            .and_then(|inner_result| inner_result)
+    }
 }
 
 impl Display for DbKey {
@@ -764,6 +984,8 @@ impl Display for DbKey {
                 f.write_str(&"Cancelled Pending Inbound Transaction".to_string())
             },
             DbKey::AnyTransaction(_) => f.write_str(&"Any Transaction".to_string()),
+            DbKey::Invoice(_) => f.write_str(&"Invoice".to_string()),
+            DbKey::Invoices => f.write_str(&"All Invoices".to_string()),
         }
     }
 }
@@ -778,6 +1000,8 @@ impl Display for DbValue {
             DbValue::PendingInboundTransactions(_) => f.write_str(&"All Pending Inbound Transactions".to_string()),
             DbValue::CompletedTransactions(_) => f.write_str(&"All Complete Transactions".to_string()),
             DbValue::WalletTransaction(_) => f.write_str(&"Any Wallet Transaction".to_string()),
+            DbValue::Invoice(_) => f.write_str(&"Invoice".to_string()),
+            DbValue::Invoices(_) => f.write_str(&"All Invoices".to_string()),
         }
     }
 }
@@ -245,6 +245,8 @@ pub enum TransactionDirection {
     Inbound,
     Outbound,
     Unknown,
+    /// A transfer between the wallet's own accounts, or back to the same account when consolidating outputs
+    Internal,
 }
 
 impl TryFrom<i32> for TransactionDirection {
@@ -255,6 +257,7 @@ impl TryFrom<i32> for TransactionDirection {
             0 => Ok(TransactionDirection::Inbound),
             1 => Ok(TransactionDirection::Outbound),
             2 => Ok(TransactionDirection::Unknown),
+            3 => Ok(TransactionDirection::Internal),
             _ => Err(TransactionStorageError::ConversionError(
                 "Invalid TransactionDirection".to_string(),
             )),
@@ -269,6 +272,7 @@ impl Display for TransactionDirection {
             TransactionDirection::Inbound => write!(f, "Inbound"),
             TransactionDirection::Outbound => write!(f, "Outbound"),
             TransactionDirection::Unknown => write!(f, "Unknown"),
+            TransactionDirection::Internal => write!(f, "Internal"),
         }
     }
 }
@@ -375,3 +379,115 @@ impl From<WalletTransaction> for CompletedTransaction {
         }
     }
 }
+
+pub type InvoiceId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InvoiceDirection {
+    /// This wallet issued the invoice and is waiting to be paid
+    Issued,
+    /// This wallet received the invoice and is being asked to pay it
+    Received,
+}
+
+impl TryFrom<i32> for InvoiceDirection {
+    type Error = TransactionStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(InvoiceDirection::Issued),
+            1 => Ok(InvoiceDirection::Received),
+            _ => Err(TransactionStorageError::ConversionError(
+                "Invalid InvoiceDirection".to_string(),
+            )),
+        }
+    }
+}
+
+impl Display for InvoiceDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            InvoiceDirection::Issued => write!(f, "Issued"),
+            InvoiceDirection::Received => write!(f, "Received"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InvoiceStatus {
+    /// The invoice has not been paid yet
+    Pending,
+    /// The payer has started a transaction to pay the invoice
+    Accepted,
+    /// The transaction paying the invoice has been completed
+    Paid,
+}
+
+impl TryFrom<i32> for InvoiceStatus {
+    type Error = TransactionStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(InvoiceStatus::Pending),
+            1 => Ok(InvoiceStatus::Accepted),
+            2 => Ok(InvoiceStatus::Paid),
+            _ => Err(TransactionStorageError::ConversionError(
+                "Invalid InvoiceStatus".to_string(),
+            )),
+        }
+    }
+}
+
+impl Display for InvoiceStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            InvoiceStatus::Pending => write!(f, "Pending"),
+            InvoiceStatus::Accepted => write!(f, "Accepted"),
+            InvoiceStatus::Paid => write!(f, "Paid"),
+        }
+    }
+}
+
+/// A request for payment that was either issued by this wallet or received from another wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Invoice {
+    pub invoice_id: InvoiceId,
+    /// The payer of an issued invoice, or the payee of a received invoice
+    pub counterparty_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub memo: String,
+    pub expires_at: NaiveDateTime,
+    pub direction: InvoiceDirection,
+    pub status: InvoiceStatus,
+    /// The transaction that pays this invoice, once the payer has started it
+    pub tx_id: Option<TxId>,
+    pub timestamp: NaiveDateTime,
+}
+
+impl Invoice {
+    pub fn new(
+        invoice_id: InvoiceId,
+        counterparty_public_key: CommsPublicKey,
+        amount: MicroTari,
+        memo: String,
+        expires_at: NaiveDateTime,
+        direction: InvoiceDirection,
+        timestamp: NaiveDateTime,
+    ) -> Self {
+        Self {
+            invoice_id,
+            counterparty_public_key,
+            amount,
+            memo,
+            expires_at,
+            direction,
+            status: InvoiceStatus::Pending,
+            tx_id: None,
+            timestamp,
+        }
+    }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        now > self.expires_at
+    }
+}
@@ -22,15 +22,35 @@
 
 use crate::{
     output_manager_service::TxId,
-    schema::{completed_transactions, inbound_transactions, outbound_transactions},
+    schema::{
+        completed_transactions,
+        inbound_transactions,
+        invoices,
+        outbound_transactions,
+        transaction_idempotency_keys,
+    },
     storage::sqlite_utilities::WalletDbConnection,
     transaction_service::{
         error::TransactionStorageError,
         storage::{
-            database::{DbKey, DbKeyValuePair, DbValue, TransactionBackend, WriteOperation},
+            database::{
+                CompletedTransactionFilter,
+                CompletedTransactionPage,
+                DbKey,
+                DbKeyValuePair,
+                DbValue,
+                TransactionBackend,
+                TransactionCursor,
+                TransactionSearchPage,
+                WriteOperation,
+            },
             models::{
                 CompletedTransaction,
                 InboundTransaction,
+                Invoice,
+                InvoiceDirection,
+                InvoiceId,
+                InvoiceStatus,
                 OutboundTransaction,
                 TransactionDirection,
                 TransactionStatus,
@@ -42,7 +62,14 @@ use crate::{
 };
 use aes_gcm::{self, aead::Error as AeadError, Aes256Gcm};
 use chrono::{NaiveDateTime, Utc};
-use diesel::{prelude::*, result::Error as DieselError, SqliteConnection};
+use diesel::{
+    prelude::*,
+    result::Error as DieselError,
+    sql_query,
+    sql_types::{BigInt, Text, Timestamp},
+    sqlite::Sqlite,
+    SqliteConnection,
+};
 use log::*;
 use std::{
     collections::HashMap,
@@ -74,6 +101,34 @@ impl TransactionServiceSqliteDatabase {
         }
     }
 
+    /// Find a transaction by tx_id, preferring the completed transaction over a pending one that has not been removed
+    /// yet
+    fn find_wallet_transaction(
+        &self,
+        tx_id: TxId,
+        conn: &SqliteConnection,
+    ) -> Result<WalletTransaction, TransactionStorageError> {
+        match CompletedTransactionSql::find(tx_id, conn) {
+            Ok(mut c) => {
+                self.decrypt_if_necessary(&mut c)?;
+                return Ok(WalletTransaction::Completed(CompletedTransaction::try_from(c)?));
+            },
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => (),
+            Err(e) => return Err(e),
+        }
+        match OutboundTransactionSql::find(tx_id, conn) {
+            Ok(mut o) => {
+                self.decrypt_if_necessary(&mut o)?;
+                return Ok(WalletTransaction::PendingOutbound(OutboundTransaction::try_from(o)?));
+            },
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => (),
+            Err(e) => return Err(e),
+        }
+        let mut i = InboundTransactionSql::find(tx_id, conn)?;
+        self.decrypt_if_necessary(&mut i)?;
+        Ok(WalletTransaction::PendingInbound(InboundTransaction::try_from(i)?))
+    }
+
     fn insert(&self, kvp: DbKeyValuePair, conn: MutexGuard<SqliteConnection>) -> Result<(), TransactionStorageError> {
         match kvp {
             DbKeyValuePair::PendingOutboundTransaction(k, v) => {
@@ -102,6 +157,12 @@ impl TransactionServiceSqliteDatabase {
 
                 c.commit(&(*conn))?;
             },
+            DbKeyValuePair::Invoice(k, v) => {
+                if InvoiceSql::find(k, &(*conn)).is_ok() {
+                    return Err(TransactionStorageError::DuplicateOutput);
+                }
+                InvoiceSql::from(*v).commit(&(*conn))?;
+            },
         }
         Ok(())
     }
@@ -189,6 +250,17 @@ impl TransactionServiceSqliteDatabase {
                 }
             },
             DbKey::AnyTransaction(_) => Err(TransactionStorageError::OperationNotSupported),
+            DbKey::Invoice(k) => match InvoiceSql::find(k, &(*conn)) {
+                Ok(v) => {
+                    v.delete(&(*conn))?;
+                    Ok(Some(DbValue::Invoice(Box::new(Invoice::try_from(v)?))))
+                },
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                    Err(TransactionStorageError::ValueNotFound(DbKey::Invoice(k)))
+                },
+                Err(e) => Err(e),
+            },
+            DbKey::Invoices => Err(TransactionStorageError::OperationNotSupported),
         }
     }
 
@@ -365,6 +437,17 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     Err(e) => return Err(e),
                 }
             },
+            DbKey::Invoice(k) => match InvoiceSql::find(*k, &(*conn)) {
+                Ok(i) => Some(DbValue::Invoice(Box::new(Invoice::try_from(i)?))),
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::Invoices => Some(DbValue::Invoices(
+                InvoiceSql::index(&(*conn))?
+                    .into_iter()
+                    .map(Invoice::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
         };
 
         Ok(result)
@@ -398,6 +481,8 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     InboundTransactionSql::find(*k, &(*conn)).is_ok() ||
                     OutboundTransactionSql::find(*k, &(*conn)).is_ok()
             },
+            DbKey::Invoice(k) => InvoiceSql::find(*k, &(*conn)).is_ok(),
+            DbKey::Invoices => false,
         };
 
         Ok(result)
@@ -933,6 +1018,229 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         };
         Ok(())
     }
+
+    fn revert_transactions_mined_above(
+        &self,
+        height: u64,
+    ) -> Result<Vec<CompletedTransaction>, TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        let reverted = conn.transaction::<_, TransactionStorageError, _>(|| {
+            let mut reverted = Vec::new();
+            for tx in CompletedTransactionSql::index_mined_above(height as i64, &(*conn))? {
+                tx.revert_to_unmined(&(*conn))?;
+                reverted.push(CompletedTransactionSql::find(tx.tx_id as TxId, &(*conn))?);
+            }
+            Ok(reverted)
+        })?;
+
+        reverted
+            .into_iter()
+            .map(|mut v| {
+                self.decrypt_if_necessary(&mut v)?;
+                CompletedTransaction::try_from(v)
+            })
+            .collect()
+    }
+
+    fn update_invoice_status(
+        &self,
+        invoice_id: InvoiceId,
+        status: InvoiceStatus,
+        tx_id: Option<TxId>,
+    ) -> Result<(), TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        match InvoiceSql::find(invoice_id, &(*conn)) {
+            Ok(v) => {
+                v.update(
+                    UpdateInvoiceSql {
+                        status: Some(status as i32),
+                        tx_id: tx_id.map(|id| Some(id as i64)),
+                    },
+                    &(*conn),
+                )?;
+            },
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                return Err(TransactionStorageError::ValueNotFound(DbKey::Invoice(invoice_id)));
+            },
+            Err(e) => return Err(e),
+        };
+        Ok(())
+    }
+
+    fn find_invoice_by_tx_id(&self, tx_id: TxId) -> Result<Option<Invoice>, TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        match InvoiceSql::find_by_tx_id(tx_id, &(*conn)) {
+            Ok(v) => Ok(Some(Invoice::try_from(v)?)),
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn fetch_completed_transactions_page(
+        &self,
+        filter: &CompletedTransactionFilter,
+        cursor: Option<&TransactionCursor>,
+        limit: usize,
+    ) -> Result<CompletedTransactionPage, TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        let total_count = CompletedTransactionSql::count_filtered(filter, &(*conn))?;
+        // Fetch one extra transaction to find out if there is another page
+        let mut page = CompletedTransactionSql::index_page(filter, cursor, limit + 1, &(*conn))?;
+        let has_more = page.len() > limit;
+        page.truncate(limit);
+
+        let mut transactions = Vec::with_capacity(page.len());
+        for mut c in page {
+            self.decrypt_if_necessary(&mut c)?;
+            transactions.push(CompletedTransaction::try_from(c)?);
+        }
+        let next_cursor = if has_more {
+            transactions.last().map(|t| TransactionCursor {
+                timestamp: t.timestamp,
+                tx_id: t.tx_id,
+            })
+        } else {
+            None
+        };
+
+        Ok(CompletedTransactionPage {
+            transactions,
+            total_count,
+            next_cursor,
+        })
+    }
+
+    fn search_transactions(
+        &self,
+        query: &str,
+        cursor: Option<&TransactionCursor>,
+        limit: usize,
+    ) -> Result<TransactionSearchPage, TransactionStorageError> {
+        let query = match fts_query(query) {
+            Some(q) => q,
+            None => {
+                return Ok(TransactionSearchPage {
+                    transactions: Vec::new(),
+                    next_cursor: None,
+                })
+            },
+        };
+        let conn = self.database_connection.acquire_lock();
+        // Fetch one extra result to find out if there is another page
+        let mut results = TransactionSearchResultSql::search(&query, cursor, limit + 1, &(*conn))?;
+        let has_more = results.len() > limit;
+        results.truncate(limit);
+
+        let next_cursor = if has_more {
+            results.last().map(|r| TransactionCursor {
+                timestamp: r.timestamp,
+                tx_id: r.tx_id as TxId,
+            })
+        } else {
+            None
+        };
+        let mut transactions = Vec::with_capacity(results.len());
+        for result in results {
+            transactions.push(self.find_wallet_transaction(result.tx_id as TxId, &(*conn))?);
+        }
+
+        Ok(TransactionSearchPage {
+            transactions,
+            next_cursor,
+        })
+    }
+
+    fn insert_idempotency_key(&self, idempotency_key: String, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        if IdempotencyKeySql::find(&idempotency_key, &(*conn)).is_ok() {
+            return Err(TransactionStorageError::IdempotencyKeyAlreadyExists);
+        }
+        IdempotencyKeySql {
+            idempotency_key,
+            tx_id: tx_id as i64,
+            timestamp: Utc::now().naive_utc(),
+        }
+        .commit(&(*conn))
+    }
+
+    fn find_tx_id_by_idempotency_key(&self, idempotency_key: &str) -> Result<Option<TxId>, TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        match IdempotencyKeySql::find(idempotency_key, &(*conn)) {
+            Ok(v) => Ok(Some(v.tx_id as TxId)),
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Turns the words typed by a user into an FTS5 query that matches text containing words starting with each of them.
+/// Every word is quoted so that characters with a meaning in the FTS5 query syntax are searched for literally.
+fn fts_query(query: &str) -> Option<String> {
+    let terms = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// The pending and completed transactions in a single list. A pending transaction is left out once its completed
+/// transaction has been written.
+const SEARCHABLE_TRANSACTIONS: &str =
+    "SELECT tx_id, timestamp, source_public_key AS first_party, destination_public_key AS second_party FROM \
+     completed_transactions UNION ALL SELECT tx_id, timestamp, source_public_key, source_public_key FROM \
+     inbound_transactions WHERE tx_id NOT IN (SELECT tx_id FROM completed_transactions) UNION ALL SELECT tx_id, \
+     timestamp, destination_public_key, destination_public_key FROM outbound_transactions WHERE tx_id NOT IN (SELECT \
+     tx_id FROM completed_transactions)";
+
+/// Matches transactions whose message, invoice memo or counterparty contact alias matches the FTS5 query bound to ?1
+const SEARCH_MATCHES: &str =
+    "(tx_id IN (SELECT rowid FROM transaction_message_search WHERE transaction_message_search MATCH ?1) OR tx_id IN \
+     (SELECT tx_id FROM invoices WHERE invoice_id IN (SELECT rowid FROM invoice_memo_search WHERE invoice_memo_search \
+     MATCH ?1)) OR first_party IN (SELECT public_key FROM contacts WHERE rowid IN (SELECT rowid FROM \
+     contact_alias_search WHERE contact_alias_search MATCH ?1)) OR second_party IN (SELECT public_key FROM contacts \
+     WHERE rowid IN (SELECT rowid FROM contact_alias_search WHERE contact_alias_search MATCH ?1)))";
+
+#[derive(Debug, QueryableByName)]
+struct TransactionSearchResultSql {
+    #[sql_type = "BigInt"]
+    tx_id: i64,
+    #[sql_type = "Timestamp"]
+    timestamp: NaiveDateTime,
+}
+
+impl TransactionSearchResultSql {
+    /// Return up to `limit` transactions matching the FTS5 query, ordered newest first, that come after the cursor
+    pub fn search(
+        match_query: &str,
+        cursor: Option<&TransactionCursor>,
+        limit: usize,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<TransactionSearchResultSql>, TransactionStorageError> {
+        let results = match cursor {
+            Some(cursor) => sql_query(format!(
+                "SELECT tx_id, timestamp FROM ({}) WHERE {} AND (timestamp < ?2 OR (timestamp = ?2 AND tx_id < ?3)) \
+                 ORDER BY timestamp DESC, tx_id DESC LIMIT ?4",
+                SEARCHABLE_TRANSACTIONS, SEARCH_MATCHES
+            ))
+            .bind::<Text, _>(match_query)
+            .bind::<Timestamp, _>(cursor.timestamp)
+            .bind::<BigInt, _>(cursor.tx_id as i64)
+            .bind::<BigInt, _>(limit as i64)
+            .load::<TransactionSearchResultSql>(conn)?,
+            None => sql_query(format!(
+                "SELECT tx_id, timestamp FROM ({}) WHERE {} ORDER BY timestamp DESC, tx_id DESC LIMIT ?2",
+                SEARCHABLE_TRANSACTIONS, SEARCH_MATCHES
+            ))
+            .bind::<Text, _>(match_query)
+            .bind::<BigInt, _>(limit as i64)
+            .load::<TransactionSearchResultSql>(conn)?,
+        };
+        Ok(results)
+    }
 }
 
 #[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
@@ -1345,6 +1653,65 @@ impl CompletedTransactionSql {
             .load::<CompletedTransactionSql>(conn)?)
     }
 
+    fn filtered(filter: &CompletedTransactionFilter) -> completed_transactions::BoxedQuery<'_, Sqlite> {
+        let mut query = completed_transactions::table
+            .filter(completed_transactions::cancelled.eq(filter.cancelled as i32))
+            .into_boxed();
+        if let Some(direction) = filter.direction.as_ref() {
+            query = query.filter(completed_transactions::direction.eq(direction.clone() as i32));
+        }
+        if filter.exclude_internal {
+            query = query.filter(
+                completed_transactions::direction
+                    .is_null()
+                    .or(completed_transactions::direction.ne(TransactionDirection::Internal as i32)),
+            );
+        }
+        if !filter.excluded_statuses.is_empty() {
+            let excluded = filter
+                .excluded_statuses
+                .iter()
+                .map(|s| s.clone() as i32)
+                .collect::<Vec<_>>();
+            query = query.filter(completed_transactions::status.ne_all(excluded));
+        }
+        query
+    }
+
+    pub fn count_filtered(
+        filter: &CompletedTransactionFilter,
+        conn: &SqliteConnection,
+    ) -> Result<u64, TransactionStorageError> {
+        let count = Self::filtered(filter).count().get_result::<i64>(conn)?;
+        Ok(count as u64)
+    }
+
+    /// Return up to `limit` transactions matching the filter, ordered newest first, that come after the cursor
+    pub fn index_page(
+        filter: &CompletedTransactionFilter,
+        cursor: Option<&TransactionCursor>,
+        limit: usize,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<CompletedTransactionSql>, TransactionStorageError> {
+        let mut query = Self::filtered(filter);
+        if let Some(cursor) = cursor {
+            query = query.filter(
+                completed_transactions::timestamp
+                    .lt(cursor.timestamp)
+                    .or(completed_transactions::timestamp
+                        .eq(cursor.timestamp)
+                        .and(completed_transactions::tx_id.lt(cursor.tx_id as i64))),
+            );
+        }
+        Ok(query
+            .order((
+                completed_transactions::timestamp.desc(),
+                completed_transactions::tx_id.desc(),
+            ))
+            .limit(limit as i64)
+            .load::<CompletedTransactionSql>(conn)?)
+    }
+
     pub fn index_coinbase_at_block_height(
         block_height: i64,
         conn: &SqliteConnection,
@@ -1355,6 +1722,20 @@ impl CompletedTransactionSql {
             .load::<CompletedTransactionSql>(conn)?)
     }
 
+    pub fn index_mined_above(
+        height: i64,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<CompletedTransactionSql>, TransactionStorageError> {
+        Ok(completed_transactions::table
+            .filter(completed_transactions::cancelled.eq(false as i32))
+            .filter(completed_transactions::status.eq_any(vec![
+                TransactionStatus::MinedUnconfirmed as i32,
+                TransactionStatus::MinedConfirmed as i32,
+            ]))
+            .filter(completed_transactions::mined_height.gt(height))
+            .load::<CompletedTransactionSql>(conn)?)
+    }
+
     pub fn find(tx_id: TxId, conn: &SqliteConnection) -> Result<CompletedTransactionSql, TransactionStorageError> {
         Ok(completed_transactions::table
             .filter(completed_transactions::tx_id.eq(tx_id as i64))
@@ -1463,6 +1844,32 @@ impl CompletedTransactionSql {
         Ok(())
     }
 
+    /// Undo a mined status, e.g. because the block the transaction was mined in was reorged out
+    pub fn revert_to_unmined(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        let status = if self.coinbase_block_height.is_some() {
+            TransactionStatus::Coinbase
+        } else {
+            TransactionStatus::Broadcast
+        };
+        self.update(
+            UpdateCompletedTransactionSql {
+                status: Some(status as i32),
+                timestamp: None,
+                cancelled: None,
+                direction: None,
+                transaction_protocol: None,
+                send_count: None,
+                last_send_timestamp: None,
+                valid: None,
+                confirmations: Some(None),
+                mined_height: Some(None),
+            },
+            conn,
+        )?;
+
+        Ok(())
+    }
+
     pub fn set_validity(&self, valid: bool, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
         self.update(
             UpdateCompletedTransactionSql {
@@ -1672,17 +2079,168 @@ impl From<UpdateCompletedTransaction> for UpdateCompletedTransactionSql {
     }
 }
 
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "invoices"]
+struct InvoiceSql {
+    invoice_id: i64,
+    counterparty_public_key: Vec<u8>,
+    amount: i64,
+    memo: String,
+    expires_at: NaiveDateTime,
+    direction: i32,
+    status: i32,
+    tx_id: Option<i64>,
+    timestamp: NaiveDateTime,
+}
+
+impl InvoiceSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(invoices::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<InvoiceSql>, TransactionStorageError> {
+        Ok(invoices::table
+            .order(invoices::timestamp.asc())
+            .load::<InvoiceSql>(conn)?)
+    }
+
+    pub fn find(invoice_id: InvoiceId, conn: &SqliteConnection) -> Result<InvoiceSql, TransactionStorageError> {
+        Ok(invoices::table
+            .filter(invoices::invoice_id.eq(invoice_id as i64))
+            .first::<InvoiceSql>(conn)?)
+    }
+
+    pub fn find_by_tx_id(tx_id: TxId, conn: &SqliteConnection) -> Result<InvoiceSql, TransactionStorageError> {
+        Ok(invoices::table
+            .filter(invoices::tx_id.eq(tx_id as i64))
+            .first::<InvoiceSql>(conn)?)
+    }
+
+    pub fn delete(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        let num_deleted =
+            diesel::delete(invoices::table.filter(invoices::invoice_id.eq(&self.invoice_id))).execute(conn)?;
+
+        if num_deleted == 0 {
+            return Err(TransactionStorageError::ValuesNotFound);
+        }
+
+        Ok(())
+    }
+
+    pub fn update(&self, update: UpdateInvoiceSql, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        let num_updated = diesel::update(invoices::table.filter(invoices::invoice_id.eq(&self.invoice_id)))
+            .set(update)
+            .execute(conn)?;
+
+        if num_updated == 0 {
+            return Err(TransactionStorageError::UnexpectedResult(
+                "Database update error".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Invoice> for InvoiceSql {
+    fn from(i: Invoice) -> Self {
+        Self {
+            invoice_id: i.invoice_id as i64,
+            counterparty_public_key: i.counterparty_public_key.to_vec(),
+            amount: u64::from(i.amount) as i64,
+            memo: i.memo,
+            expires_at: i.expires_at,
+            direction: i.direction as i32,
+            status: i.status as i32,
+            tx_id: i.tx_id.map(|id| id as i64),
+            timestamp: i.timestamp,
+        }
+    }
+}
+
+impl TryFrom<InvoiceSql> for Invoice {
+    type Error = TransactionStorageError;
+
+    fn try_from(i: InvoiceSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            invoice_id: i.invoice_id as u64,
+            counterparty_public_key: PublicKey::from_vec(&i.counterparty_public_key)
+                .map_err(|_| TransactionStorageError::ConversionError("Invalid Counterparty Publickey".to_string()))?,
+            amount: MicroTari::from(i.amount as u64),
+            memo: i.memo,
+            expires_at: i.expires_at,
+            direction: InvoiceDirection::try_from(i.direction)?,
+            status: InvoiceStatus::try_from(i.status)?,
+            tx_id: i.tx_id.map(|id| id as u64),
+            timestamp: i.timestamp,
+        })
+    }
+}
+
+#[derive(AsChangeset)]
+#[table_name = "invoices"]
+pub struct UpdateInvoiceSql {
+    status: Option<i32>,
+    tx_id: Option<Option<i64>>,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "transaction_idempotency_keys"]
+struct IdempotencyKeySql {
+    idempotency_key: String,
+    tx_id: i64,
+    timestamp: NaiveDateTime,
+}
+
+impl IdempotencyKeySql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(transaction_idempotency_keys::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find(idempotency_key: &str, conn: &SqliteConnection) -> Result<IdempotencyKeySql, TransactionStorageError> {
+        Ok(transaction_idempotency_keys::table
+            .filter(transaction_idempotency_keys::idempotency_key.eq(idempotency_key))
+            .first::<IdempotencyKeySql>(conn)?)
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[cfg(feature = "test_harness")]
     use crate::transaction_service::storage::sqlite_db::UpdateCompletedTransactionSql;
     use crate::{
+        contacts_service::storage::{
+            database::{
+                Contact,
+                ContactsBackend,
+                DbKeyValuePair as ContactsDbKeyValuePair,
+                WriteOperation as ContactsWriteOperation,
+            },
+            sqlite_db::ContactsServiceSqliteDatabase,
+        },
         storage::sqlite_utilities::WalletDbConnection,
         transaction_service::storage::{
-            database::{DbKey, TransactionBackend},
+            database::{
+                CompletedTransactionFilter,
+                DbKey,
+                DbKeyValuePair,
+                DbValue,
+                TransactionBackend,
+                TransactionCursor,
+                WriteOperation,
+            },
             models::{
                 CompletedTransaction,
                 InboundTransaction,
+                Invoice,
+                InvoiceDirection,
+                InvoiceStatus,
                 OutboundTransaction,
                 TransactionDirection,
                 TransactionStatus,
@@ -2316,4 +2874,333 @@ mod test {
         assert!(db3.fetch(&DbKey::PendingOutboundTransactions).is_ok());
         assert!(db3.fetch(&DbKey::CompletedTransactions).is_ok());
     }
+
+    #[test]
+    fn test_completed_transactions_page() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        embed_migrations!("./migrations");
+        let conn = SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(conn, None), None);
+
+        let now = Utc::now().naive_utc();
+        for i in 0..7u64 {
+            let completed_tx = CompletedTransaction {
+                tx_id: 100 + i,
+                source_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+                destination_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+                amount: MicroTari::from(100 * i),
+                fee: MicroTari::from(10),
+                transaction: Transaction::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    PrivateKey::random(&mut OsRng),
+                    PrivateKey::random(&mut OsRng),
+                ),
+                status: if i == 6 {
+                    TransactionStatus::Broadcast
+                } else {
+                    TransactionStatus::MinedConfirmed
+                },
+                message: "Yo!".to_string(),
+                // The last two transactions share a timestamp so that the tx_id breaks the tie
+                timestamp: now + chrono::Duration::seconds(i.min(5) as i64),
+                cancelled: false,
+                direction: if i % 2 == 0 {
+                    TransactionDirection::Inbound
+                } else {
+                    TransactionDirection::Outbound
+                },
+                coinbase_block_height: None,
+                send_count: 0,
+                last_send_timestamp: None,
+                valid: true,
+                confirmations: None,
+                mined_height: None,
+            };
+            db.write(WriteOperation::Insert(DbKeyValuePair::CompletedTransaction(
+                completed_tx.tx_id,
+                Box::new(completed_tx),
+            )))
+            .unwrap();
+        }
+
+        let filter = CompletedTransactionFilter::default();
+        let mut cursor = None;
+        let mut tx_ids = Vec::new();
+        loop {
+            let page = db
+                .fetch_completed_transactions_page(&filter, cursor.as_ref(), 3)
+                .unwrap();
+            assert_eq!(page.total_count, 7);
+            assert!(page.transactions.len() <= 3);
+            tx_ids.extend(page.transactions.iter().map(|t| t.tx_id));
+            match page.next_cursor {
+                // Cursors survive being passed around as strings
+                Some(c) => cursor = Some(c.to_string().parse::<TransactionCursor>().unwrap()),
+                None => break,
+            }
+        }
+        assert_eq!(tx_ids, vec![106, 105, 104, 103, 102, 101, 100]);
+
+        let filter = CompletedTransactionFilter {
+            direction: Some(TransactionDirection::Inbound),
+            excluded_statuses: vec![TransactionStatus::Broadcast],
+            ..Default::default()
+        };
+        let page = db.fetch_completed_transactions_page(&filter, None, 10).unwrap();
+        assert_eq!(page.total_count, 3);
+        assert!(page.next_cursor.is_none());
+        assert_eq!(page.transactions.iter().map(|t| t.tx_id).collect::<Vec<_>>(), vec![
+            104, 102, 100
+        ]);
+
+        assert!("not a cursor".parse::<TransactionCursor>().is_err());
+    }
+
+    #[test]
+    fn test_revert_transactions_mined_above() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        embed_migrations!("./migrations");
+        let conn = SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(conn, None), None);
+
+        // (tx_id, status, mined height, coinbase block height)
+        let transactions = vec![
+            (200u64, TransactionStatus::MinedConfirmed, Some(10u64), None),
+            (201, TransactionStatus::MinedUnconfirmed, Some(12), None),
+            (202, TransactionStatus::MinedConfirmed, Some(15), Some(15u64)),
+            (203, TransactionStatus::Broadcast, None, None),
+        ];
+        for (tx_id, status, mined_height, coinbase_block_height) in transactions {
+            let completed_tx = CompletedTransaction {
+                tx_id,
+                source_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+                destination_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+                amount: MicroTari::from(100),
+                fee: MicroTari::from(10),
+                transaction: Transaction::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    PrivateKey::random(&mut OsRng),
+                    PrivateKey::random(&mut OsRng),
+                ),
+                status,
+                message: "Yo!".to_string(),
+                timestamp: Utc::now().naive_utc(),
+                cancelled: false,
+                direction: TransactionDirection::Inbound,
+                coinbase_block_height,
+                send_count: 0,
+                last_send_timestamp: None,
+                valid: true,
+                confirmations: mined_height.map(|_| 3),
+                mined_height,
+            };
+            db.write(WriteOperation::Insert(DbKeyValuePair::CompletedTransaction(
+                completed_tx.tx_id,
+                Box::new(completed_tx),
+            )))
+            .unwrap();
+        }
+
+        let mut reverted = db.revert_transactions_mined_above(11).unwrap();
+        reverted.sort_by_key(|tx| tx.tx_id);
+        assert_eq!(reverted.iter().map(|tx| tx.tx_id).collect::<Vec<_>>(), vec![201, 202]);
+        assert_eq!(reverted[0].status, TransactionStatus::Broadcast);
+        assert_eq!(reverted[1].status, TransactionStatus::Coinbase);
+        assert!(reverted
+            .iter()
+            .all(|tx| tx.mined_height.is_none() && tx.confirmations.is_none()));
+
+        match db.fetch(&DbKey::CompletedTransaction(200)).unwrap() {
+            Some(DbValue::CompletedTransaction(tx)) => {
+                assert_eq!(tx.status, TransactionStatus::MinedConfirmed);
+                assert_eq!(tx.mined_height, Some(10));
+            },
+            _ => panic!("Completed transaction 200 should still exist"),
+        }
+
+        assert!(db.revert_transactions_mined_above(11).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_transactions() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        embed_migrations!("./migrations");
+        let conn = SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+
+        let connection = WalletDbConnection::new(conn, None);
+        let db = TransactionServiceSqliteDatabase::new(connection.clone(), None);
+        let contacts_db = ContactsServiceSqliteDatabase::new(connection);
+
+        let alice = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+        let now = Utc::now().naive_utc();
+        let messages = ["Coffee beans", "Groceries", "", "Café \"au lait\"", "Rent"];
+        for (i, message) in messages.iter().enumerate() {
+            let completed_tx = CompletedTransaction {
+                tx_id: 200 + i as u64,
+                source_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+                destination_public_key: if i == 2 {
+                    alice.clone()
+                } else {
+                    PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng))
+                },
+                amount: MicroTari::from(100),
+                fee: MicroTari::from(10),
+                transaction: Transaction::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    PrivateKey::random(&mut OsRng),
+                    PrivateKey::random(&mut OsRng),
+                ),
+                status: TransactionStatus::MinedConfirmed,
+                message: message.to_string(),
+                timestamp: now + chrono::Duration::seconds(i as i64),
+                cancelled: false,
+                direction: TransactionDirection::Outbound,
+                coinbase_block_height: None,
+                send_count: 0,
+                last_send_timestamp: None,
+                valid: true,
+                confirmations: None,
+                mined_height: None,
+            };
+            db.write(WriteOperation::Insert(DbKeyValuePair::CompletedTransaction(
+                completed_tx.tx_id,
+                Box::new(completed_tx),
+            )))
+            .unwrap();
+        }
+        contacts_db
+            .write(ContactsWriteOperation::Upsert(ContactsDbKeyValuePair::Contact(
+                alice.clone(),
+                Contact {
+                    alias: "Alice Smith".to_string(),
+                    public_key: alice,
+                    notes: None,
+                },
+            )))
+            .unwrap();
+        let invoice = Invoice::new(
+            1,
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            MicroTari::from(100),
+            "Consulting for March".to_string(),
+            now + chrono::Duration::hours(1),
+            InvoiceDirection::Received,
+            now,
+        );
+        db.write(WriteOperation::Insert(DbKeyValuePair::Invoice(1, Box::new(invoice))))
+            .unwrap();
+        db.update_invoice_status(1, InvoiceStatus::Paid, Some(204)).unwrap();
+
+        let search = |query: &str| {
+            db.search_transactions(query, None, 10)
+                .unwrap()
+                .transactions
+                .into_iter()
+                .map(|t| CompletedTransaction::from(t).tx_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search("coff"), vec![200]);
+        assert_eq!(search("BEANS coffee"), vec![200]);
+        assert_eq!(search("coffee rent"), Vec::<u64>::new());
+        assert_eq!(search("smi"), vec![202]);
+        assert_eq!(search("march"), vec![204]);
+        assert_eq!(search("cafe"), vec![203]);
+        assert_eq!(search("\"au"), vec![203]);
+        assert_eq!(search("c"), vec![204, 203, 200]);
+        assert!(search("   ").is_empty());
+        assert!(search("AND OR NOT * ( ^").is_empty());
+
+        let first_page = db.search_transactions("c", None, 2).unwrap();
+        assert_eq!(first_page.transactions.len(), 2);
+        let cursor = first_page.next_cursor.unwrap();
+        let second_page = db.search_transactions("c", Some(&cursor), 2).unwrap();
+        assert_eq!(second_page.transactions.len(), 1);
+        assert!(second_page.next_cursor.is_none());
+        assert_eq!(
+            CompletedTransaction::from(second_page.transactions.into_iter().next().unwrap()).tx_id,
+            200
+        );
+
+        db.write(WriteOperation::Remove(DbKey::CompletedTransaction(200)))
+            .unwrap();
+        assert!(search("coffee").is_empty());
+    }
+
+    #[test]
+    fn test_invoice_crud() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        embed_migrations!("./migrations");
+        let conn = SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(conn, None), None);
+
+        let now = Utc::now().naive_utc();
+        let invoice = Invoice::new(
+            1,
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            MicroTari::from(5000),
+            "Coffee".to_string(),
+            now + chrono::Duration::hours(1),
+            InvoiceDirection::Issued,
+            now,
+        );
+        db.write(WriteOperation::Insert(DbKeyValuePair::Invoice(
+            1,
+            Box::new(invoice.clone()),
+        )))
+        .unwrap();
+        assert!(db
+            .write(WriteOperation::Insert(DbKeyValuePair::Invoice(
+                1,
+                Box::new(invoice.clone())
+            )))
+            .is_err());
+        assert!(db.contains(&DbKey::Invoice(1)).unwrap());
+        assert!(db.find_invoice_by_tx_id(99).unwrap().is_none());
+
+        db.update_invoice_status(1, InvoiceStatus::Accepted, Some(99)).unwrap();
+        let found = db.find_invoice_by_tx_id(99).unwrap().unwrap();
+        assert_eq!(found.status, InvoiceStatus::Accepted);
+        assert_eq!(found.memo, invoice.memo);
+        assert_eq!(found.amount, invoice.amount);
+
+        db.update_invoice_status(1, InvoiceStatus::Paid, None).unwrap();
+        match db.fetch(&DbKey::Invoices).unwrap() {
+            Some(DbValue::Invoices(invoices)) => {
+                assert_eq!(invoices.len(), 1);
+                assert_eq!(invoices[0].status, InvoiceStatus::Paid);
+                assert_eq!(invoices[0].tx_id, Some(99));
+            },
+            _ => panic!("Should have found invoices"),
+        }
+        assert!(db.update_invoice_status(2, InvoiceStatus::Paid, None).is_err());
+    }
 }
@@ -0,0 +1,400 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Tari URIs
+//!
+//! A versioned URI scheme for exchanging payment details between wallets, e.g. as QR codes:
+//! - `tari://<network>/v1/pay/<public key hex>?amount=<µT>&message=<text>` asks for a standard transaction
+//! - `tari://<network>/v1/pay_one_sided/<public key hex>?amount=<µT>&message=<text>` asks for a one-sided transaction
+//! - `tari://<network>/v1/pst/<base58>` carries a partially signed transaction for offline signing
+//!
+//! The amount and message are optional. The unversioned `tari://<network>/pubkey/<public key hex>` links shown by
+//! older wallets are parsed as payment requests without an amount.
+
+use crate::{
+    output_manager_service::TxId,
+    transaction_service::{error::TransactionServiceError, handle::TransactionServiceHandle},
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction_protocol::interchange::{InterchangeError, PartiallySignedTransaction},
+};
+use tari_crypto::tari_utilities::hex::Hex;
+use thiserror::Error;
+
+pub const TARI_URI_SCHEME: &str = "tari";
+/// The current version of the URI path layout
+pub const TARI_URI_VERSION: u8 = 1;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TariUriError {
+    #[error("Not a tari:// URI")]
+    InvalidScheme,
+    #[error("Unsupported URI version `{0}`")]
+    UnsupportedVersion(String),
+    #[error("Unknown URI action `{0}`")]
+    UnknownAction(String),
+    #[error("Malformed URI: {0}")]
+    Malformed(String),
+    #[error("Invalid public key in URI")]
+    InvalidPublicKey,
+    #[error("Invalid amount in URI: {0}")]
+    InvalidAmount(String),
+    #[error("Invalid partially signed transaction in URI: {0}")]
+    InvalidTransaction(#[from] InterchangeError),
+    #[error("The URI does not specify an amount")]
+    MissingAmount,
+    #[error("The URI does not describe a payment")]
+    NotAPayment,
+    #[error("Transaction service error: {0}")]
+    TransactionServiceError(String),
+}
+
+impl From<TransactionServiceError> for TariUriError {
+    fn from(err: TransactionServiceError) -> Self {
+        TariUriError::TransactionServiceError(err.to_string())
+    }
+}
+
+/// The recipient and optional amount and message of a requested payment
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentDetails {
+    pub public_key: CommsPublicKey,
+    pub amount: Option<MicroTari>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TariUriPayload {
+    /// A request to be paid with a standard interactive transaction
+    Payment(PaymentDetails),
+    /// A request to be paid with a one-sided transaction
+    OneSidedPayment(PaymentDetails),
+    /// A transaction to be signed or finalized by another (e.g. offline) wallet
+    PartiallySignedTransaction(Box<PartiallySignedTransaction>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TariUri {
+    /// The network the URI is intended for, e.g. `stibbons`
+    pub network: String,
+    pub payload: TariUriPayload,
+}
+
+impl TariUri {
+    pub fn payment(network: String, public_key: CommsPublicKey, amount: Option<MicroTari>, message: String) -> Self {
+        Self {
+            network,
+            payload: TariUriPayload::Payment(PaymentDetails {
+                public_key,
+                amount,
+                message,
+            }),
+        }
+    }
+
+    pub fn one_sided_payment(
+        network: String,
+        public_key: CommsPublicKey,
+        amount: Option<MicroTari>,
+        message: String,
+    ) -> Self {
+        Self {
+            network,
+            payload: TariUriPayload::OneSidedPayment(PaymentDetails {
+                public_key,
+                amount,
+                message,
+            }),
+        }
+    }
+
+    pub fn partially_signed_transaction(network: String, transaction: PartiallySignedTransaction) -> Self {
+        Self {
+            network,
+            payload: TariUriPayload::PartiallySignedTransaction(Box::new(transaction)),
+        }
+    }
+
+    /// Sends the payment requested by this URI. `amount` is used if the URI does not specify one. Returns
+    /// `NotAPayment` for partially signed transactions, which must be handled by the caller.
+    pub async fn pay(
+        &self,
+        transaction_service: &mut TransactionServiceHandle,
+        amount: Option<MicroTari>,
+        fee_per_gram: MicroTari,
+    ) -> Result<TxId, TariUriError> {
+        let (details, one_sided) = match &self.payload {
+            TariUriPayload::Payment(details) => (details, false),
+            TariUriPayload::OneSidedPayment(details) => (details, true),
+            TariUriPayload::PartiallySignedTransaction(_) => return Err(TariUriError::NotAPayment),
+        };
+        let amount = details.amount.or(amount).ok_or(TariUriError::MissingAmount)?;
+        let public_key = details.public_key.clone();
+        let message = details.message.clone();
+
+        let tx_id = if one_sided {
+            transaction_service
+                .send_one_sided_transaction(public_key, amount, fee_per_gram, message)
+                .await?
+        } else {
+            transaction_service
+                .send_transaction(public_key, amount, fee_per_gram, message)
+                .await?
+        };
+        Ok(tx_id)
+    }
+}
+
+impl Display for TariUri {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}/v{}/", TARI_URI_SCHEME, self.network, TARI_URI_VERSION)?;
+        let (action, details) = match &self.payload {
+            TariUriPayload::Payment(details) => ("pay", details),
+            TariUriPayload::OneSidedPayment(details) => ("pay_one_sided", details),
+            TariUriPayload::PartiallySignedTransaction(tx) => return write!(f, "pst/{}", tx.to_base58()),
+        };
+
+        write!(f, "{}/{}", action, details.public_key.to_hex())?;
+        let mut params = Vec::new();
+        if let Some(amount) = details.amount {
+            params.push(format!("amount={}", amount.as_u64()));
+        }
+        if !details.message.is_empty() {
+            params.push(format!("message={}", percent_encode(&details.message)));
+        }
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TariUri {
+    type Err = TariUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .trim()
+            .strip_prefix(TARI_URI_SCHEME)
+            .and_then(|r| r.strip_prefix("://"))
+            .ok_or(TariUriError::InvalidScheme)?;
+        let (path, query) = match rest.find('?') {
+            Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+            None => (rest, None),
+        };
+        let segments = path.trim_end_matches('/').split('/').collect::<Vec<_>>();
+        let malformed = || TariUriError::Malformed("expected tari://<network>/<version>/<action>/<data>".to_string());
+        if segments.len() < 3 || segments[0].is_empty() {
+            return Err(malformed());
+        }
+        let network = segments[0].to_string();
+
+        // Links from before the scheme was versioned only contained a public key
+        if segments[1] == "pubkey" && segments.len() == 3 {
+            let public_key = parse_public_key(segments[2])?;
+            return Ok(Self::payment(network, public_key, None, String::new()));
+        }
+
+        if segments[1].strip_prefix('v').and_then(|v| v.parse::<u8>().ok()) != Some(TARI_URI_VERSION) {
+            return Err(TariUriError::UnsupportedVersion(segments[1].to_string()));
+        }
+        if segments.len() != 4 {
+            return Err(malformed());
+        }
+
+        let (action, data) = (segments[2], segments[3]);
+        if action == "pst" {
+            let tx = PartiallySignedTransaction::from_base58(data)?;
+            return Ok(Self::partially_signed_transaction(network, tx));
+        }
+
+        let public_key = parse_public_key(data)?;
+        let mut amount = None;
+        let mut message = String::new();
+        for param in query.unwrap_or("").split('&').filter(|p| !p.is_empty()) {
+            let mut parts = param.splitn(2, '=');
+            let key = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("");
+            match key {
+                "amount" => {
+                    let v = value
+                        .parse::<u64>()
+                        .map_err(|e| TariUriError::InvalidAmount(e.to_string()))?;
+                    amount = Some(MicroTari::from(v));
+                },
+                "message" => message = percent_decode(value)?,
+                // Unknown parameters are ignored so that newer wallets can add optional fields
+                _ => {},
+            }
+        }
+
+        match action {
+            "pay" => Ok(Self::payment(network, public_key, amount, message)),
+            "pay_one_sided" => Ok(Self::one_sided_payment(network, public_key, amount, message)),
+            _ => Err(TariUriError::UnknownAction(action.to_string())),
+        }
+    }
+}
+
+fn parse_public_key(s: &str) -> Result<CommsPublicKey, TariUriError> {
+    CommsPublicKey::from_hex(s).map_err(|_| TariUriError::InvalidPublicKey)
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(b as char),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str) -> Result<String, TariUriError> {
+    let invalid = || TariUriError::Malformed("invalid percent encoding".to_string());
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = s.get(i + 1..i + 3).ok_or_else(invalid)?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                i += 3;
+            },
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            },
+            b => {
+                decoded.push(b);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_core::transactions::{helpers::create_tx, tari_amount::T};
+    use tari_crypto::keys::PublicKey;
+
+    fn random_public_key() -> CommsPublicKey {
+        CommsPublicKey::random_keypair(&mut OsRng).1
+    }
+
+    #[test]
+    fn it_round_trips_payment_requests() {
+        let public_key = random_public_key();
+        let uri = TariUri::payment(
+            "stibbons".to_string(),
+            public_key.clone(),
+            Some(5 * T),
+            "Coffee & cake, 100% 🍰".to_string(),
+        );
+        let s = uri.to_string();
+        assert!(s.starts_with(&format!(
+            "tari://stibbons/v1/pay/{}?amount=5000000&message=",
+            public_key.to_hex()
+        )));
+        assert!(!s.contains(' '));
+        assert_eq!(TariUri::from_str(&s).unwrap(), uri);
+
+        let uri = TariUri::one_sided_payment("stibbons".to_string(), public_key, None, String::new());
+        let s = uri.to_string();
+        assert!(!s.contains('?'));
+        assert_eq!(TariUri::from_str(&s).unwrap(), uri);
+    }
+
+    #[test]
+    fn it_round_trips_partially_signed_transactions() {
+        let (tx, _, _) = create_tx(5000.into(), 15.into(), 1, 2, 1, 3);
+        let uri = TariUri::partially_signed_transaction(
+            "stibbons".to_string(),
+            PartiallySignedTransaction::Finalized(Box::new(tx)),
+        );
+        assert_eq!(TariUri::from_str(&uri.to_string()).unwrap(), uri);
+    }
+
+    #[test]
+    fn it_parses_legacy_and_rejects_invalid_uris() {
+        let public_key = random_public_key();
+        let legacy = format!("tari://ridcully/pubkey/{}", public_key.to_hex());
+        assert_eq!(
+            TariUri::from_str(&legacy).unwrap(),
+            TariUri::payment("ridcully".to_string(), public_key.clone(), None, String::new())
+        );
+
+        let uri = format!("tari://ridcully/v1/pay/{}?amount=12&unknown=1", public_key.to_hex());
+        assert_eq!(
+            TariUri::from_str(&uri).unwrap(),
+            TariUri::payment(
+                "ridcully".to_string(),
+                public_key.clone(),
+                Some(12.into()),
+                String::new()
+            )
+        );
+
+        let hex = public_key.to_hex();
+        let cases = vec![
+            ("bitcoin:abc".to_string(), TariUriError::InvalidScheme),
+            (
+                format!("tari://ridcully/v2/pay/{}", hex),
+                TariUriError::UnsupportedVersion("v2".to_string()),
+            ),
+            (
+                format!("tari://ridcully/v1/steal/{}", hex),
+                TariUriError::UnknownAction("steal".to_string()),
+            ),
+            (
+                "tari://ridcully/v1/pay/abcd".to_string(),
+                TariUriError::InvalidPublicKey,
+            ),
+        ];
+        for (uri, err) in cases {
+            assert_eq!(TariUri::from_str(&uri).unwrap_err(), err);
+        }
+        assert!(matches!(
+            TariUri::from_str(&format!("tari://ridcully/v1/pay/{}?amount=1.5", hex)),
+            Err(TariUriError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            TariUri::from_str(&format!("tari://ridcully/v1/pay/{}?message=%F", hex)),
+            Err(TariUriError::Malformed(_))
+        ));
+        assert!(matches!(
+            TariUri::from_str("tari://ridcully/v1/pst/notbase58!"),
+            Err(TariUriError::InvalidTransaction(_))
+        ));
+    }
+}
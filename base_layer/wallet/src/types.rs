@@ -27,6 +27,10 @@ use tari_crypto::common::Blake256;
 /// TODO discuss what the default fee value should actually be
 pub const DEFAULT_FEE_PER_GRAM: MicroTari = MicroTari(25);
 
+/// The number of blocks within which the wallet aims to have its transactions mined when asking the base node for a
+/// fee estimate
+pub const DEFAULT_FEE_ESTIMATE_TARGET_BLOCKS: u64 = 3;
+
 /// Specify the Hash function used by the key manager
 pub type KeyDigest = Blake256;
 
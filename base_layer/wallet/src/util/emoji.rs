@@ -22,14 +22,20 @@
 
 use crate::util::luhn::{checksum, is_valid};
 use std::{
+    cmp,
     collections::HashMap,
     fmt::{Display, Error, Formatter},
+    str::FromStr,
 };
 use tari_core::transactions::types::PublicKey;
 use tari_crypto::tari_utilities::{
     hex::{Hex, HexError},
     ByteArray,
 };
+use thiserror::Error;
+
+/// The number of emoji in an emoji ID: one for each byte of the public key, followed by the checksum
+pub const EMOJI_ID_LENGTH: usize = 33;
 
 const EMOJI: [char; 256] = [
     '🌀', '🌂', '🌈', '🌊', '🌋', '🌍', '🌙', '🌝', '🌞', '🌟', '🌠', '🌰', '🌴', '🌵', '🌷', '🌸', '🌹', '🌻', '🌽',
@@ -111,19 +117,76 @@ impl EmojiId {
     }
 
     pub fn str_to_pubkey(s: &str) -> Result<PublicKey, EmojiIdError> {
-        let mut indices = Vec::with_capacity(33);
-        for c in s.chars() {
-            if let Some(i) = REVERSE_EMOJI.get(&c) {
-                indices.push(*i);
-            } else {
-                return Err(EmojiIdError);
+        let mut indices = Vec::with_capacity(EMOJI_ID_LENGTH);
+        for (position, c) in s.chars().enumerate() {
+            match REVERSE_EMOJI.get(&c) {
+                Some(i) => indices.push(*i),
+                None => return Err(EmojiIdError::InvalidEmoji { position, character: c }),
             }
         }
+        if indices.len() != EMOJI_ID_LENGTH {
+            return Err(EmojiIdError::InvalidLength(indices.len()));
+        }
         if !is_valid(&indices, 256) {
-            return Err(EmojiIdError);
+            return Err(EmojiIdError::InvalidChecksum);
         }
         let bytes = EmojiId::byte_vec(s)?;
-        PublicKey::from_bytes(&bytes).map_err(|_| EmojiIdError)
+        PublicKey::from_bytes(&bytes).map_err(|_| EmojiIdError::InvalidPublicKey)
+    }
+
+    /// Returns the valid emoji IDs that are a single substituted, missing, extra or swapped emoji away from `s`, which
+    /// are the most likely typos when an emoji ID is copied by hand. The list is empty if `s` is already a valid emoji
+    /// ID or no emoji ID is a single edit away. The checksum rules out all but a few candidates, but when more than
+    /// one is returned the user has to choose, as the intended emoji ID cannot be known.
+    pub fn suggest_corrections(s: &str) -> Vec<EmojiId> {
+        if EmojiId::is_valid(s) {
+            return Vec::new();
+        }
+        let chars = s.chars().collect::<Vec<_>>();
+        let mut candidates = Vec::<Vec<char>>::new();
+        match chars.len() {
+            EMOJI_ID_LENGTH => {
+                for i in 0..chars.len() {
+                    for emoji in EMOJI.iter().filter(|e| **e != chars[i]) {
+                        let mut candidate = chars.clone();
+                        candidate[i] = *emoji;
+                        candidates.push(candidate);
+                    }
+                }
+                for i in 0..chars.len() - 1 {
+                    if chars[i] != chars[i + 1] {
+                        let mut candidate = chars.clone();
+                        candidate.swap(i, i + 1);
+                        candidates.push(candidate);
+                    }
+                }
+            },
+            len if len + 1 == EMOJI_ID_LENGTH => {
+                for i in 0..=chars.len() {
+                    for emoji in EMOJI.iter() {
+                        let mut candidate = chars.clone();
+                        candidate.insert(i, *emoji);
+                        candidates.push(candidate);
+                    }
+                }
+            },
+            len if len == EMOJI_ID_LENGTH + 1 => {
+                for i in 0..chars.len() {
+                    let mut candidate = chars.clone();
+                    candidate.remove(i);
+                    candidates.push(candidate);
+                }
+            },
+            _ => {},
+        }
+
+        let mut suggestions = candidates
+            .into_iter()
+            .filter_map(|candidate| candidate.into_iter().collect::<String>().parse::<EmojiId>().ok())
+            .collect::<Vec<_>>();
+        suggestions.sort();
+        suggestions.dedup();
+        suggestions
     }
 
     /// Return the 33 character emoji string for this emoji ID
@@ -148,29 +211,62 @@ impl EmojiId {
 
     fn byte_vec(s: &str) -> Result<Vec<u8>, EmojiIdError> {
         let mut v = Vec::with_capacity(32);
-        for c in s.chars().take(32) {
+        for (position, c) in s.chars().take(32).enumerate() {
             if let Some(index) = REVERSE_EMOJI.get(&c) {
                 v.push(*index as u8);
             } else {
-                return Err(EmojiIdError);
+                return Err(EmojiIdError::InvalidEmoji { position, character: c });
             }
         }
         Ok(v)
     }
 }
 
+impl FromStr for EmojiId {
+    type Err = EmojiIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EmojiId::str_to_pubkey(s).map(|key| EmojiId::from_pubkey(&key))
+    }
+}
+
 impl Display for EmojiId {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         fmt.write_str(self.as_str())
     }
 }
 
-#[derive(Debug)]
-pub struct EmojiIdError;
+/// Returns the number of emoji that must be inserted, removed or substituted to turn `a` into `b`
+pub fn emoji_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == *cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = cmp::min(substitution, cmp::min(row[j], row[j + 1]) + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EmojiIdError {
+    #[error("Emoji IDs are {} emoji long, but {0} were given", EMOJI_ID_LENGTH)]
+    InvalidLength(usize),
+    #[error("'{character}' at position {position} is not in the emoji set")]
+    InvalidEmoji { position: usize, character: char },
+    #[error("The checksum emoji does not match, the emoji ID may contain a typo")]
+    InvalidChecksum,
+    #[error("The emoji ID does not represent a valid public key")]
+    InvalidPublicKey,
+}
 
 #[cfg(test)]
 mod test {
-    use crate::util::emoji::EmojiId;
+    use crate::util::emoji::{emoji_distance, emoji_set, EmojiId, EmojiIdError};
     use tari_core::transactions::types::PublicKey;
     use tari_crypto::tari_utilities::hex::Hex;
 
@@ -217,4 +313,75 @@ mod test {
             "Wrong checksum"
         );
     }
+
+    #[test]
+    fn validation_errors() {
+        let eid = EmojiId::from_hex("70350e09c474809209824c6e6888707b7dd09959aa227343b5106382b856f73a").unwrap();
+        let chars = eid.as_str().chars().collect::<Vec<_>>();
+
+        let short = chars[..32].iter().collect::<String>();
+        assert_eq!(EmojiId::str_to_pubkey(&short), Err(EmojiIdError::InvalidLength(32)));
+        let not_emoji = format!(
+            "{}a{}",
+            chars[..3].iter().collect::<String>(),
+            chars[4..].iter().collect::<String>()
+        );
+        assert_eq!(
+            EmojiId::str_to_pubkey(&not_emoji),
+            Err(EmojiIdError::InvalidEmoji {
+                position: 3,
+                character: 'a'
+            })
+        );
+        let mut wrong_checksum = chars;
+        wrong_checksum[32] = '📝';
+        assert_eq!(
+            EmojiId::str_to_pubkey(&wrong_checksum.iter().collect::<String>()),
+            Err(EmojiIdError::InvalidChecksum)
+        );
+        assert_eq!(eid.as_str().parse::<EmojiId>().unwrap(), eid);
+    }
+
+    #[test]
+    fn suggest_corrections() {
+        let eid = EmojiId::from_hex("70350e09c474809209824c6e6888707b7dd09959aa227343b5106382b856f73a").unwrap();
+        let chars = eid.as_str().chars().collect::<Vec<_>>();
+        assert!(EmojiId::suggest_corrections(eid.as_str()).is_empty());
+
+        let mut substituted = chars.clone();
+        substituted[5] = if chars[5] == emoji_set()[0] {
+            emoji_set()[1]
+        } else {
+            emoji_set()[0]
+        };
+        let mut swapped = chars.clone();
+        swapped.swap(10, 11);
+        let mut missing = chars.clone();
+        missing.remove(20);
+        let mut extra = chars.clone();
+        extra.insert(7, emoji_set()[42]);
+
+        for typo in &[substituted, swapped, missing, extra] {
+            let typo = typo.iter().collect::<String>();
+            assert!(!EmojiId::is_valid(&typo));
+            let suggestions = EmojiId::suggest_corrections(&typo);
+            assert!(suggestions.contains(&eid), "No suggestion for {}", typo);
+            assert!(suggestions.iter().all(|s| EmojiId::is_valid(s.as_str())));
+        }
+
+        // More than one edit away
+        let mut typo = chars;
+        typo.truncate(30);
+        assert!(EmojiId::suggest_corrections(&typo.iter().collect::<String>()).is_empty());
+    }
+
+    #[test]
+    fn distance() {
+        assert_eq!(emoji_distance("", ""), 0);
+        assert_eq!(emoji_distance("🌀🌂🌈", "🌀🌂🌈"), 0);
+        assert_eq!(emoji_distance("🌀🌂🌈", "🌀🌊🌈"), 1);
+        assert_eq!(emoji_distance("🌀🌂🌈", "🌀🌈"), 1);
+        assert_eq!(emoji_distance("🌀🌂", "🌂🌀"), 2);
+        assert_eq!(emoji_distance("", "🌀🌂"), 2);
+    }
 }
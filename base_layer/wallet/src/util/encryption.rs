@@ -21,13 +21,17 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use aes_gcm::{
-    aead::{generic_array::GenericArray, Aead, Error as AeadError},
+    aead::{generic_array::GenericArray, Aead, Error as AeadError, NewAead},
     Aes256Gcm,
 };
+use argon2::{Algorithm, Argon2};
+use digest::Digest;
 use rand::{rngs::OsRng, RngCore};
+use tari_crypto::common::Blake256;
 
 pub const AES_NONCE_BYTES: usize = 12;
 pub const AES_KEY_BYTES: usize = 32;
+pub const PASSPHRASE_SALT_BYTES: usize = 16;
 
 pub trait Encryptable<C> {
     fn encrypt(&mut self, cipher: &C) -> Result<(), AeadError>;
@@ -53,9 +57,37 @@ pub fn encrypt_bytes_integral_nonce(cipher: &Aes256Gcm, plaintext: Vec<u8>) -> R
     Ok(ciphertext_integral_nonce)
 }
 
+/// Generates a random salt for `cipher_from_passphrase`
+pub fn generate_passphrase_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; PASSPHRASE_SALT_BYTES];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Derives a cipher from a passphrase and salt using Argon2id, so that brute forcing the passphrase is expensive
+pub fn cipher_from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, AeadError> {
+    let mut key = [0u8; AES_KEY_BYTES];
+    Argon2::default()
+        .hash_password_into(Algorithm::Argon2id, passphrase.as_bytes(), salt, &[], &mut key)
+        .map_err(|_| AeadError)?;
+    Ok(Aes256Gcm::new(GenericArray::from_slice(&key)))
+}
+
+/// Derives a cipher from the unsalted hash of a passphrase. This is only used for wallets that were encrypted before
+/// `cipher_from_passphrase` was introduced.
+pub fn legacy_cipher_from_passphrase(passphrase: &str) -> Aes256Gcm {
+    let passphrase_hash = Blake256::new().chain(passphrase.as_bytes()).finalize();
+    Aes256Gcm::new(GenericArray::from_slice(passphrase_hash.as_slice()))
+}
+
 #[cfg(test)]
 mod test {
-    use crate::util::encryption::{decrypt_bytes_integral_nonce, encrypt_bytes_integral_nonce};
+    use crate::util::encryption::{
+        cipher_from_passphrase,
+        decrypt_bytes_integral_nonce,
+        encrypt_bytes_integral_nonce,
+        generate_passphrase_salt,
+    };
     use aes_gcm::{
         aead::{generic_array::GenericArray, NewAead},
         Aes256Gcm,
@@ -70,4 +102,21 @@ mod test {
         let decrypted_text = decrypt_bytes_integral_nonce(&cipher, cipher_text).unwrap();
         assert_eq!(decrypted_text, plaintext);
     }
+
+    #[test]
+    fn test_cipher_from_passphrase() {
+        let plaintext = b"The quick brown fox was annoying".to_vec();
+        let salt = generate_passphrase_salt();
+        let cipher = cipher_from_passphrase("correct horse", &salt).unwrap();
+        let cipher_text = encrypt_bytes_integral_nonce(&cipher, plaintext.clone()).unwrap();
+
+        let cipher = cipher_from_passphrase("correct horse", &salt).unwrap();
+        let decrypted_text = decrypt_bytes_integral_nonce(&cipher, cipher_text.clone()).unwrap();
+        assert_eq!(decrypted_text, plaintext);
+
+        let cipher = cipher_from_passphrase("wrong horse", &salt).unwrap();
+        assert!(decrypt_bytes_integral_nonce(&cipher, cipher_text.clone()).is_err());
+        let cipher = cipher_from_passphrase("correct horse", &generate_passphrase_salt()).unwrap();
+        assert!(decrypt_bytes_integral_nonce(&cipher, cipher_text).is_err());
+    }
 }
@@ -50,10 +50,12 @@ pub enum UtxoScannerEvent {
         num_retries: usize,
         retry_limit: usize,
     },
-    /// Progress of the recovery process (current_block, current_chain_height)
+    /// Progress of the recovery process (current_block, current_chain_height, and the scanning rate of the current
+    /// scanning round)
     Progress {
         current_block: u64,
         current_chain_height: u64,
+        outputs_per_second: u64,
     },
     /// Completed Recovery (Number scanned, Num of Recovered outputs, Value of recovered outputs, Time taken)
     Completed {
@@ -39,6 +39,7 @@ use futures::{pin_mut, StreamExt};
 use log::*;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{HashSet, VecDeque},
     convert::TryFrom,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -76,6 +77,13 @@ pub const LOG_TARGET: &str = "wallet::utxo_scanning";
 pub const RECOVERY_KEY: &str = "recovery_data";
 const SCANNING_KEY: &str = "scanning_data";
 
+/// The default number of outputs that are sent to the output manager to be rewound in one request
+pub const DEFAULT_SCANNING_BATCH_SIZE: usize = 100;
+/// Scanning progress is persisted after this many outputs have been scanned
+const COMMIT_EVERY_N_OUTPUTS: usize = 1000;
+/// The number of recently scanned range proofs that are remembered so that they are not rewound again
+const ATTEMPTED_PROOF_CACHE_SIZE: usize = 10 * COMMIT_EVERY_N_OUTPUTS;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum UtxoScannerMode {
     Recovery,
@@ -94,6 +102,7 @@ pub struct UtxoScannerServiceBuilder {
     peers: Vec<CommsPublicKey>,
     mode: Option<UtxoScannerMode>,
     scanning_interval: Option<Duration>,
+    batch_size: Option<usize>,
 }
 
 #[derive(Clone)]
@@ -129,6 +138,13 @@ impl UtxoScannerServiceBuilder {
         self
     }
 
+    /// Set the number of outputs that are rewound per output manager request. Defaults to
+    /// [DEFAULT_SCANNING_BATCH_SIZE].
+    pub fn with_batch_size(&mut self, batch_size: usize) -> &mut Self {
+        self.batch_size = Some(batch_size.max(1));
+        self
+    }
+
     pub fn build_with_wallet(
         &mut self,
         wallet: &WalletSqlite,
@@ -155,6 +171,7 @@ impl UtxoScannerServiceBuilder {
             self.peers.drain(..).collect(),
             self.retry_limit,
             self.mode.clone().unwrap_or_default(),
+            self.batch_size.unwrap_or(DEFAULT_SCANNING_BATCH_SIZE),
             resources,
             interval,
             shutdown_signal,
@@ -192,6 +209,7 @@ impl UtxoScannerServiceBuilder {
             self.peers.drain(..).collect(),
             self.retry_limit,
             self.mode.clone().unwrap_or_default(),
+            self.batch_size.unwrap_or(DEFAULT_SCANNING_BATCH_SIZE),
             resources,
             interval,
             shutdown_signal,
@@ -212,6 +230,8 @@ where TBackend: WalletBackend + 'static
     peer_seeds: Vec<CommsPublicKey>,
     peer_index: usize,
     mode: UtxoScannerMode,
+    batch_size: usize,
+    attempted_proofs: AttemptedProofCache,
     run_flag: Arc<AtomicBool>,
 }
 impl<TBackend> UtxoScannerTask<TBackend>
@@ -227,6 +247,7 @@ where TBackend: WalletBackend + 'static
         self.publish_event(UtxoScannerEvent::Progress {
             current_block: final_utxo_pos,
             current_chain_height: final_utxo_pos,
+            outputs_per_second: outputs_per_second(total_scanned, elapsed),
         });
         self.publish_event(UtxoScannerEvent::Completed {
             number_scanned: total_scanned,
@@ -373,10 +394,13 @@ where TBackend: WalletBackend + 'static
         let mut num_recovered = 0u64;
         let mut total_amount = MicroTari::from(0);
         let mut total_scanned = 0;
+        let mut scanned_since_commit = 0;
+        let timer = Instant::now();
 
         self.publish_event(UtxoScannerEvent::Progress {
             current_block: start_mmr_leaf_index,
             current_chain_height: (end_header_size - 1),
+            outputs_per_second: 0,
         });
         let request = SyncUtxosRequest {
             start: start_mmr_leaf_index,
@@ -386,11 +410,10 @@ where TBackend: WalletBackend + 'static
         };
 
         let utxo_stream = client.sync_utxos(request).await?;
-        // We download in chunks just because rewind_outputs works with multiple outputs (and could parallelized
-        // rewinding)
-        let mut utxo_stream = utxo_stream.chunks(10);
+        // Outputs are rewound in batches so that the cost of each output manager request and of setting up the rewind
+        // is shared by the whole batch
+        let mut utxo_stream = utxo_stream.chunks(self.batch_size);
         let mut last_utxo_index = 0u64;
-        let mut iteration_count = 0u64;
         while let Some(response) = utxo_stream.next().await {
             if !self.run_flag.load(Ordering::Relaxed) {
                 // if running is set to false, we know its been canceled upstream so lets exit the loop
@@ -399,15 +422,27 @@ where TBackend: WalletBackend + 'static
             let (outputs, utxo_index) = convert_response_to_transaction_outputs(response, last_utxo_index)?;
             last_utxo_index = utxo_index;
             total_scanned += outputs.len();
-            iteration_count += 1;
+            scanned_since_commit += outputs.len();
+
+            // Outputs after the last persisted progress are streamed again when a scanning round is retried, there is
+            // no need to rewind them a second time
+            let (outputs, proof_hashes): (Vec<_>, Vec<_>) = outputs
+                .into_iter()
+                .map(|output| {
+                    let proof_hash = output.proof.hash();
+                    (output, proof_hash)
+                })
+                .filter(|(_, proof_hash)| !self.attempted_proofs.contains(proof_hash))
+                .unzip();
             let found_outputs = self.scan_for_outputs(outputs).await?;
 
-            // Reduce the number of db hits by only persisting progress every N iterations
-            const COMMIT_EVERY_N: u64 = 100;
-            if iteration_count % COMMIT_EVERY_N == 0 || last_utxo_index >= end_header_size - 1 {
+            // Reduce the number of db hits by only persisting progress every N outputs
+            if scanned_since_commit >= COMMIT_EVERY_N_OUTPUTS || last_utxo_index >= end_header_size - 1 {
+                scanned_since_commit = 0;
                 self.publish_event(UtxoScannerEvent::Progress {
                     current_block: last_utxo_index,
                     current_chain_height: (end_header_size - 1),
+                    outputs_per_second: outputs_per_second(total_scanned as u64, timer.elapsed()),
                 });
                 self.update_scanning_progress_in_db(
                     last_utxo_index,
@@ -420,12 +455,17 @@ where TBackend: WalletBackend + 'static
             let (count, amount) = self.import_utxos_to_transaction_service(found_outputs).await?;
             num_recovered = num_recovered.saturating_add(count);
             total_amount += amount;
+            // Only remember the proofs once the outputs found in them have been imported
+            for proof_hash in proof_hashes {
+                self.attempted_proofs.insert(proof_hash);
+            }
         }
         self.update_scanning_progress_in_db(last_utxo_index, total_amount, num_recovered, end_header_hash)
             .await?;
         self.publish_event(UtxoScannerEvent::Progress {
             current_block: (end_header_size - 1),
             current_chain_height: (end_header_size - 1),
+            outputs_per_second: outputs_per_second(total_scanned as u64, timer.elapsed()),
         });
         Ok(total_scanned as u64)
     }
@@ -452,6 +492,9 @@ where TBackend: WalletBackend + 'static
         outputs: Vec<TransactionOutput>,
     ) -> Result<Vec<(UnblindedOutput, String)>, UtxoScannerError> {
         let mut found_outputs: Vec<(UnblindedOutput, String)> = Vec::new();
+        if outputs.is_empty() {
+            return Ok(found_outputs);
+        }
         if self.mode == UtxoScannerMode::Recovery {
             found_outputs.append(
                 &mut self
@@ -471,11 +514,10 @@ where TBackend: WalletBackend + 'static
                 .scan_outputs_for_one_sided_payments(outputs.clone())
                 .await?
                 .into_iter()
-                .map(|v| {
-                    (
-                        v,
-                        format!("Detected one-sided transaction on {}.", Utc::now().naive_utc()),
-                    )
+                .map(|(v, message)| {
+                    let message = message
+                        .unwrap_or_else(|| format!("Detected one-sided transaction on {}.", Utc::now().naive_utc()));
+                    (v, message)
                 })
                 .collect(),
         );
@@ -632,6 +674,7 @@ where TBackend: WalletBackend + 'static
     retry_limit: usize,
     peer_seeds: Vec<CommsPublicKey>,
     mode: UtxoScannerMode,
+    batch_size: usize,
     is_running: Arc<AtomicBool>,
     scan_for_utxo_interval: Duration,
     shutdown_signal: ShutdownSignal,
@@ -649,6 +692,7 @@ where TBackend: WalletBackend + 'static
         peer_seeds: Vec<CommsPublicKey>,
         retry_limit: usize,
         mode: UtxoScannerMode,
+        batch_size: usize,
         resources: UtxoScannerResources<TBackend>,
         scan_for_utxo_interval: Duration,
         shutdown_signal: ShutdownSignal,
@@ -663,6 +707,7 @@ where TBackend: WalletBackend + 'static
             peer_seeds,
             retry_limit,
             mode,
+            batch_size,
             is_running: Arc::new(AtomicBool::new(false)),
             scan_for_utxo_interval,
             shutdown_signal,
@@ -681,6 +726,8 @@ where TBackend: WalletBackend + 'static
             peer_index: 0,
             num_retries: 0,
             mode: self.mode.clone(),
+            batch_size: self.batch_size,
+            attempted_proofs: AttemptedProofCache::new(ATTEMPTED_PROOF_CACHE_SIZE),
             run_flag: self.is_running.clone(),
         }
     }
@@ -763,6 +810,48 @@ where TBackend: WalletBackend + 'static
     }
 }
 
+fn outputs_per_second(num_outputs: u64, elapsed: Duration) -> u64 {
+    let elapsed_ms = elapsed.as_millis() as u64;
+    if elapsed_ms == 0 {
+        return 0;
+    }
+    num_outputs.saturating_mul(1000) / elapsed_ms
+}
+
+/// A bounded set of the hashes of the range proofs that have most recently been scanned. The oldest hash is forgotten
+/// once the capacity is reached.
+struct AttemptedProofCache {
+    capacity: usize,
+    hashes: HashSet<Vec<u8>>,
+    insertion_order: VecDeque<Vec<u8>>,
+}
+
+impl AttemptedProofCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            hashes: HashSet::with_capacity(capacity),
+            insertion_order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn contains(&self, proof_hash: &[u8]) -> bool {
+        self.hashes.contains(proof_hash)
+    }
+
+    fn insert(&mut self, proof_hash: Vec<u8>) {
+        if self.capacity == 0 || !self.hashes.insert(proof_hash.clone()) {
+            return;
+        }
+        self.insertion_order.push_back(proof_hash);
+        if self.insertion_order.len() > self.capacity {
+            if let Some(oldest) = self.insertion_order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+    }
+}
+
 fn convert_response_to_transaction_outputs(
     response: Vec<Result<proto::base_node::SyncUtxosResponse, RpcStatus>>,
     last_utxo_index: u64,
@@ -804,3 +893,34 @@ struct ScanningMetadata {
     pub utxo_index: u64,
     pub height_hash: HashOutput,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn attempted_proof_cache_forgets_the_oldest_proofs() {
+        let mut cache = AttemptedProofCache::new(2);
+        cache.insert(vec![1]);
+        cache.insert(vec![2]);
+        cache.insert(vec![2]);
+        assert!(cache.contains(&[1]));
+        assert!(cache.contains(&[2]));
+
+        cache.insert(vec![3]);
+        assert!(!cache.contains(&[1]));
+        assert!(cache.contains(&[2]));
+        assert!(cache.contains(&[3]));
+
+        let mut cache = AttemptedProofCache::new(0);
+        cache.insert(vec![1]);
+        assert!(!cache.contains(&[1]));
+    }
+
+    #[test]
+    fn it_calculates_outputs_per_second() {
+        assert_eq!(outputs_per_second(1000, Duration::from_millis(500)), 2000);
+        assert_eq!(outputs_per_second(10, Duration::from_secs(4)), 2);
+        assert_eq!(outputs_per_second(10, Duration::from_secs(0)), 0);
+    }
+}
@@ -21,17 +21,32 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    audit_log_service::{handle::AuditLogHandle, storage::database::AuditAction, AuditLogServiceInitializer},
+    base_node_selection_service::{handle::BaseNodeSelectionServiceHandle, BaseNodeSelectionServiceInitializer},
     base_node_service::{handle::BaseNodeServiceHandle, BaseNodeServiceInitializer},
     config::{WalletConfig, KEY_MANAGER_COMMS_SECRET_KEY_BRANCH_KEY},
     contacts_service::{handle::ContactsServiceHandle, storage::database::ContactsBackend, ContactsServiceInitializer},
-    error::WalletError,
+    error::{WalletError, WalletStorageError},
+    history_sync_service::{
+        handle::HistorySyncHandle,
+        rpc::create_history_sync_rpc_service,
+        storage::database::HistorySyncBackend,
+        HistorySyncServiceInitializer,
+    },
+    key_manager_service::{handle::KeyManagerHandle, KeyManagerServiceInitializer},
     output_manager_service::{
         error::OutputManagerError,
         handle::OutputManagerHandle,
         storage::{database::OutputManagerBackend, models::KnownOneSidedPaymentScript},
+        utxo_bundle::{UtxoBundle, UtxoBundleError},
         OutputManagerServiceInitializer,
         TxId,
     },
+    recurring_payment_service::{
+        handle::RecurringPaymentServiceHandle,
+        storage::database::RecurringPaymentBackend,
+        RecurringPaymentServiceInitializer,
+    },
     storage::database::{WalletBackend, WalletDatabase},
     transaction_service::{
         handle::TransactionServiceHandle,
@@ -39,29 +54,32 @@ use crate::{
         TransactionServiceInitializer,
     },
     types::KeyDigest,
+    util::encryption::{cipher_from_passphrase, generate_passphrase_salt},
     utxo_scanner_service::{handle::UtxoScannerHandle, UtxoScannerServiceInitializer},
 };
-use aes_gcm::{
-    aead::{generic_array::GenericArray, NewAead},
-    Aes256Gcm,
-};
 use digest::Digest;
 use log::*;
 use rand::rngs::OsRng;
-use std::{marker::PhantomData, sync::Arc};
+use std::{convert::TryFrom, marker::PhantomData, sync::Arc, time::Duration};
 use tari_comms::{
     multiaddr::Multiaddr,
     peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
+    protocol::rpc::RpcServer,
     types::{CommsPublicKey, CommsSecretKey},
     CommsNode,
     NodeIdentity,
     UnspawnedCommsNode,
 };
 use tari_comms_dht::{store_forward::StoreAndForwardRequester, Dht};
-use tari_core::transactions::{
-    tari_amount::MicroTari,
-    transaction::{OutputFeatures, UnblindedOutput},
-    types::{ComSignature, CryptoFactories, PrivateKey, PublicKey},
+use tari_core::{
+    base_node::rpc::BaseNodeWalletRpcClient,
+    proto::base_node::FetchMatchingUtxos,
+    transactions::{
+        covenant::Covenant,
+        tari_amount::MicroTari,
+        transaction::{OutputFeatures, TransactionOutput, UnblindedOutput},
+        types::{ComSignature, Commitment, CryptoFactories, PrivateKey, PublicKey},
+    },
 };
 use tari_crypto::{
     common::Blake256,
@@ -70,34 +88,51 @@ use tari_crypto::{
     script,
     script::{ExecutionStack, TariScript},
     signatures::{SchnorrSignature, SchnorrSignatureError},
-    tari_utilities::hex::Hex,
+    tari_utilities::{
+        hex::{to_hex, Hex},
+        Hashable,
+    },
 };
 use tari_key_manager::key_manager::KeyManager;
-use tari_p2p::{comms_connector::pubsub_connector, initialization, initialization::P2pInitializer};
+use tari_p2p::{
+    comms_connector::pubsub_connector,
+    initialization,
+    initialization::P2pInitializer,
+    services::liveness::{LivenessConfig, LivenessInitializer},
+};
 use tari_service_framework::StackBuilder;
 use tari_shutdown::ShutdownSignal;
 use tokio::runtime;
 
 const LOG_TARGET: &str = "wallet";
+/// The time allowed for the base node to confirm that an imported output is unspent
+const UTXO_IMPORT_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// A structure containing the config and services that a Wallet application will require. This struct will start up all
 /// the services and provide the APIs that applications will use to interact with the services
 #[derive(Clone)]
-pub struct Wallet<T, U, V, W>
+pub struct Wallet<T, U, V, W, X, Y>
 where
     T: WalletBackend + 'static,
     U: TransactionBackend + 'static,
     V: OutputManagerBackend + 'static,
     W: ContactsBackend + 'static,
+    X: RecurringPaymentBackend + 'static,
+    Y: HistorySyncBackend + 'static,
 {
     pub comms: CommsNode,
     pub dht_service: Dht,
     pub store_and_forward_requester: StoreAndForwardRequester,
+    pub key_manager_service: KeyManagerHandle,
     pub output_manager_service: OutputManagerHandle,
     pub transaction_service: TransactionServiceHandle,
     pub contacts_service: ContactsServiceHandle,
+    pub recurring_payment_service: RecurringPaymentServiceHandle,
+    pub history_sync_service: HistorySyncHandle,
     pub base_node_service: BaseNodeServiceHandle,
+    pub base_node_selection_service: BaseNodeSelectionServiceHandle,
     pub utxo_scanner_service: UtxoScannerHandle,
+    pub audit_log_service: AuditLogHandle,
     pub db: WalletDatabase<T>,
     pub factories: CryptoFactories,
     #[cfg(feature = "test_harness")]
@@ -105,24 +140,31 @@ where
     _u: PhantomData<U>,
     _v: PhantomData<V>,
     _w: PhantomData<W>,
+    _x: PhantomData<X>,
+    _y: PhantomData<Y>,
 }
 
-impl<T, U, V, W> Wallet<T, U, V, W>
+impl<T, U, V, W, X, Y> Wallet<T, U, V, W, X, Y>
 where
     T: WalletBackend + 'static,
     U: TransactionBackend + 'static,
     V: OutputManagerBackend + 'static,
     W: ContactsBackend + 'static,
+    X: RecurringPaymentBackend + 'static,
+    Y: HistorySyncBackend + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub async fn start(
         config: WalletConfig,
         wallet_database: WalletDatabase<T>,
         transaction_backend: U,
         output_manager_backend: V,
         contacts_backend: W,
+        recurring_payment_backend: X,
+        history_sync_backend: Y,
         shutdown_signal: ShutdownSignal,
         recovery_master_key: Option<CommsSecretKey>,
-    ) -> Result<Wallet<T, U, V, W>, WalletError> {
+    ) -> Result<Wallet<T, U, V, W, X, Y>, WalletError> {
         let master_secret_key =
             read_or_create_master_secret_key(recovery_master_key, &mut wallet_database.clone()).await?;
         let comms_secret_key = derive_comms_secret_key(&master_secret_key)?;
@@ -167,6 +209,8 @@ where
         );
         let stack = StackBuilder::new(shutdown_signal)
             .add_initializer(P2pInitializer::new(comms_config, publisher))
+            .add_initializer(AuditLogServiceInitializer::new(wallet_database.backend()))
+            .add_initializer(KeyManagerServiceInitializer::new(output_manager_backend.clone()))
             .add_initializer(OutputManagerServiceInitializer::new(
                 config.output_manager_service_config.unwrap_or_default(),
                 output_manager_backend,
@@ -176,12 +220,30 @@ where
             ))
             .add_initializer(TransactionServiceInitializer::new(
                 config.transaction_service_config.unwrap_or_default(),
-                peer_message_subscription_factory,
+                peer_message_subscription_factory.clone(),
                 transaction_backend,
                 node_identity.clone(),
                 factories.clone(),
             ))
-            .add_initializer(ContactsServiceInitializer::new(contacts_backend))
+            .add_initializer(LivenessInitializer::new(
+                LivenessConfig {
+                    auto_ping_interval: None,
+                    ..Default::default()
+                },
+                peer_message_subscription_factory,
+            ))
+            .add_initializer(ContactsServiceInitializer::new(
+                config.contacts_service_config,
+                contacts_backend,
+            ))
+            .add_initializer(RecurringPaymentServiceInitializer::new(
+                config.recurring_payment_service_config,
+                recurring_payment_backend,
+            ))
+            .add_initializer(HistorySyncServiceInitializer::new(
+                config.history_sync_service_config.clone(),
+                history_sync_backend,
+            ))
             .add_initializer(BaseNodeServiceInitializer::new(
                 config.base_node_service_config,
                 bn_service_db,
@@ -191,23 +253,40 @@ where
                 wallet_database.clone(),
                 factories.clone(),
                 node_identity.clone(),
+            ))
+            .add_initializer(BaseNodeSelectionServiceInitializer::new(
+                config.base_node_selection_service_config,
             ));
 
         let mut handles = stack.build().await?;
 
+        let key_manager_handle = handles.expect_handle::<KeyManagerHandle>();
+        let mut output_manager_handle = handles.expect_handle::<OutputManagerHandle>();
+        let transaction_service_handle = handles.expect_handle::<TransactionServiceHandle>();
+        let contacts_handle = handles.expect_handle::<ContactsServiceHandle>();
+        let recurring_payment_handle = handles.expect_handle::<RecurringPaymentServiceHandle>();
+        let history_sync_handle = handles.expect_handle::<HistorySyncHandle>();
+        let audit_log_handle = handles.expect_handle::<AuditLogHandle>();
+
         let comms = handles
             .take_handle::<UnspawnedCommsNode>()
             .expect("P2pInitializer was not added to the stack");
+        // Companion wallets subscribe to the history of this wallet over RPC
+        let rpc_server = RpcServer::new().add_service(create_history_sync_rpc_service(
+            config.history_sync_service_config,
+            history_sync_handle.clone(),
+            transaction_service_handle.clone(),
+            output_manager_handle.clone(),
+        ));
+        let comms = comms.add_protocol_extension(rpc_server);
         let comms = initialization::spawn_comms_using_transport(comms, transport_type).await?;
 
-        let mut output_manager_handle = handles.expect_handle::<OutputManagerHandle>();
-        let transaction_service_handle = handles.expect_handle::<TransactionServiceHandle>();
-        let contacts_handle = handles.expect_handle::<ContactsServiceHandle>();
         let dht = handles.expect_handle::<Dht>();
         let store_and_forward_requester = dht.store_and_forward_requester();
 
         let base_node_service_handle = handles.expect_handle::<BaseNodeServiceHandle>();
         let utxo_scanner_service_handle = handles.expect_handle::<UtxoScannerHandle>();
+        let base_node_selection_service_handle = handles.expect_handle::<BaseNodeSelectionServiceHandle>();
 
         persist_one_sided_payment_script_for_node_identity(&mut output_manager_handle, comms.node_identity())
             .await
@@ -229,11 +308,16 @@ where
             comms,
             dht_service: dht,
             store_and_forward_requester,
+            key_manager_service: key_manager_handle,
             output_manager_service: output_manager_handle,
             transaction_service: transaction_service_handle,
             contacts_service: contacts_handle,
+            recurring_payment_service: recurring_payment_handle,
+            history_sync_service: history_sync_handle,
             base_node_service: base_node_service_handle,
+            base_node_selection_service: base_node_selection_service_handle,
             utxo_scanner_service: utxo_scanner_service_handle,
+            audit_log_service: audit_log_handle,
             db: wallet_database,
             factories,
             #[cfg(feature = "test_harness")]
@@ -241,6 +325,8 @@ where
             _u: PhantomData,
             _v: PhantomData,
             _w: PhantomData,
+            _x: PhantomData,
+            _y: PhantomData,
         })
     }
 
@@ -262,17 +348,7 @@ where
             public_key, net_address
         );
 
-        let address = net_address.parse::<Multiaddr>()?;
-        let peer = Peer::new(
-            public_key.clone(),
-            NodeId::from_key(&public_key),
-            vec![address].into(),
-            PeerFlags::empty(),
-            PeerFeatures::COMMUNICATION_NODE,
-            Default::default(),
-            String::new(),
-        );
-
+        let peer = base_node_peer(public_key, &net_address)?;
         self.comms.peer_manager().add_peer(peer.clone()).await?;
         self.comms
             .connectivity()
@@ -296,6 +372,44 @@ where
         Ok(())
     }
 
+    /// Makes this wallet a read-only companion of the wallet at the given address. The transaction history and balance
+    /// of that wallet are synced to this one, which must have been authorized as a companion by the primary wallet.
+    pub async fn set_history_sync_primary(
+        &mut self,
+        public_key: CommsPublicKey,
+        net_address: String,
+    ) -> Result<(), WalletError> {
+        info!(
+            target: LOG_TARGET,
+            "Wallet setting history sync primary, public key: {}, net address: {}.", public_key, net_address
+        );
+
+        let peer = base_node_peer(public_key, &net_address)?;
+        self.comms.peer_manager().add_peer(peer.clone()).await?;
+        self.history_sync_service.set_primary_wallet(peer.public_key).await?;
+
+        Ok(())
+    }
+
+    /// Adds a base node that the wallet may automatically switch to if it is better than the current base node. See
+    /// [BaseNodeSelectionService](crate::base_node_selection_service::service::BaseNodeSelectionService).
+    pub async fn add_base_node_candidate(
+        &mut self,
+        public_key: CommsPublicKey,
+        net_address: String,
+    ) -> Result<(), WalletError> {
+        info!(
+            "Wallet adding base node candidate, public key: {}, net address: {}.",
+            public_key, net_address
+        );
+
+        let peer = base_node_peer(public_key, &net_address)?;
+        self.comms.peer_manager().add_peer(peer.clone()).await?;
+        self.base_node_selection_service.add_candidate(peer).await?;
+
+        Ok(())
+    }
+
     pub async fn get_base_node_peer(&mut self) -> Result<Option<Peer>, WalletError> {
         self.base_node_service
             .get_base_node_peer()
@@ -329,6 +443,7 @@ where
             script_private_key.clone(),
             sender_offset_public_key.clone(),
             metadata_signature,
+            Covenant::default(),
         );
 
         let tx_id = self
@@ -387,6 +502,85 @@ where
         Ok(tx_id)
     }
 
+    /// Exports the unspent output with the given commitment as a bundle encrypted to the recipient's public key, so
+    /// that it can be imported by another wallet with [import_utxo_bundle](Self::import_utxo_bundle). The output
+    /// remains in this wallet; once the recipient has imported it, either wallet can spend it.
+    pub async fn export_utxo(
+        &mut self,
+        commitment: &Commitment,
+        recipient_public_key: &CommsPublicKey,
+    ) -> Result<UtxoBundle, WalletError> {
+        let mut found = None;
+        for output in self.output_manager_service.get_unspent_outputs().await? {
+            if &output.as_transaction_input(&self.factories.commitment)?.commitment == commitment {
+                found = Some(output);
+                break;
+            }
+        }
+        let output = found.ok_or_else(|| UtxoBundleError::OutputNotFound(commitment.to_hex()))?;
+        Ok(UtxoBundle::encrypt(&output, recipient_public_key)?)
+    }
+
+    /// Imports an output exported by another wallet with [export_utxo](Self::export_utxo). The bundle must be
+    /// encrypted to this wallet's public key. The output is only imported if the base node confirms that it is
+    /// unspent, in which case it is added as an unspent output along with a faux incoming transaction. The TxId of
+    /// the generated transaction is returned.
+    pub async fn import_utxo_bundle(
+        &mut self,
+        bundle: &UtxoBundle,
+        source_public_key: &CommsPublicKey,
+        message: String,
+    ) -> Result<TxId, WalletError> {
+        let unblinded_output = bundle.decrypt(self.comms.node_identity().secret_key())?;
+        let output = unblinded_output.as_transaction_output(&self.factories)?;
+
+        // Checked before anything is written, so that a failed import leaves no trace in the wallet
+        for existing in self.output_manager_service.get_unspent_outputs().await? {
+            if existing.as_transaction_input(&self.factories.commitment)?.commitment == output.commitment {
+                return Err(UtxoBundleError::AlreadyImported.into());
+            }
+        }
+        self.check_unspent_on_chain(&output).await?;
+
+        self.import_unblinded_utxo(unblinded_output, source_public_key, message)
+            .await
+    }
+
+    /// Asks the base node whether the output is in the unspent set. The output hash commits to the commitment, so a
+    /// match proves that the bundle opens an output on the blockchain.
+    async fn check_unspent_on_chain(&mut self, output: &TransactionOutput) -> Result<(), WalletError> {
+        let peer = self
+            .base_node_service
+            .get_base_node_peer()
+            .await?
+            .ok_or(UtxoBundleError::NoBaseNode)?;
+        let mut connection = self.comms.connectivity().dial_peer(peer.node_id).await?;
+        let mut client = connection
+            .connect_rpc_using_builder(BaseNodeWalletRpcClient::builder().with_deadline(UTXO_IMPORT_QUERY_TIMEOUT))
+            .await
+            .map_err(|e| UtxoBundleError::BaseNodeQueryFailed(e.to_string()))?;
+
+        let output_hash = output.hash();
+        let response = client
+            .fetch_matching_utxos(FetchMatchingUtxos {
+                output_hashes: vec![output_hash.clone()],
+            })
+            .await
+            .map_err(|e| UtxoBundleError::BaseNodeQueryFailed(e.to_string()))?;
+        if !response.is_synced {
+            return Err(UtxoBundleError::BaseNodeNotSynced.into());
+        }
+        let is_unspent = response
+            .outputs
+            .into_iter()
+            .filter_map(|o| TransactionOutput::try_from(o).ok())
+            .any(|o| o.hash() == output_hash);
+        if !is_unspent {
+            return Err(UtxoBundleError::NotUnspentOnChain.into());
+        }
+        Ok(())
+    }
+
     pub fn sign_message(
         &mut self,
         secret: RistrettoSecretKey,
@@ -394,7 +588,12 @@ where
         message: &str,
     ) -> Result<SchnorrSignature<RistrettoPublicKey, RistrettoSecretKey>, SchnorrSignatureError> {
         let challenge = Blake256::digest(message.as_bytes());
-        RistrettoSchnorr::sign(secret, nonce, &challenge)
+        let signature = RistrettoSchnorr::sign(secret, nonce, &challenge)?;
+        self.audit_log_service.record(
+            AuditAction::MessageSigned,
+            format!("Signed message with challenge {}", to_hex(&challenge)),
+        );
+        Ok(signature)
     }
 
     pub fn verify_message_signature(
@@ -439,16 +638,21 @@ where
     }
 
     /// Apply encryption to all the Wallet db backends. The Wallet backend will test if the db's are already encrypted
-    /// in which case this will fail.
+    /// in which case this will fail. The key is derived from the passphrase with a new random salt, which is stored
+    /// unencrypted so that the key can be derived again when the wallet is opened.
     pub async fn apply_encryption(&mut self, passphrase: String) -> Result<(), WalletError> {
         debug!(target: LOG_TARGET, "Applying wallet encryption.");
-        let passphrase_hash = Blake256::new().chain(passphrase.as_bytes()).finalize();
-        let key = GenericArray::from_slice(passphrase_hash.as_slice());
-        let cipher = Aes256Gcm::new(key);
+        let salt = generate_passphrase_salt();
+        let cipher = cipher_from_passphrase(&passphrase, &salt)
+            .map_err(|e| WalletStorageError::AeadError(format!("Key derivation error: {}", e)))?;
+        // Fails if the database is already encrypted, before any data is changed
+        self.db.set_passphrase_salt(salt).await?;
 
         self.db.apply_encryption(cipher.clone()).await?;
         self.output_manager_service.apply_encryption(cipher.clone()).await?;
         self.transaction_service.apply_encryption(cipher).await?;
+        self.audit_log_service
+            .record(AuditAction::EncryptionApplied, "Wallet encryption applied");
         Ok(())
     }
 
@@ -458,6 +662,8 @@ where
         self.db.remove_encryption().await?;
         self.output_manager_service.remove_encryption().await?;
         self.transaction_service.remove_encryption().await?;
+        self.audit_log_service
+            .record(AuditAction::EncryptionRemoved, "Wallet encryption removed");
         Ok(())
     }
 
@@ -512,6 +718,19 @@ fn derive_comms_secret_key(master_secret_key: &CommsSecretKey) -> Result<CommsSe
     Ok(comms_key_manager.derive_key(0)?.k)
 }
 
+fn base_node_peer(public_key: CommsPublicKey, net_address: &str) -> Result<Peer, WalletError> {
+    let address = net_address.parse::<Multiaddr>()?;
+    Ok(Peer::new(
+        public_key.clone(),
+        NodeId::from_key(&public_key),
+        vec![address].into(),
+        PeerFlags::empty(),
+        PeerFeatures::COMMUNICATION_NODE,
+        Default::default(),
+        String::new(),
+    ))
+}
+
 /// Persist the one-sided payment script for the current wallet NodeIdentity for use during scanning for One-sided
 /// payment outputs. This is peristed so that if the Node Identity changes the wallet will still scan for outputs
 /// using old node identities.
@@ -0,0 +1,250 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Wallet events
+//! The wallet services each publish their own fine-grained events. Clients that only want to know when the balance
+//! or base node connectivity of the wallet has changed would have to listen to all of them and query the balance after
+//! each one. The [WalletEventMonitor] does this once and publishes a [WalletEvent] only when something the client can
+//! display has changed.
+
+use crate::{
+    base_node_service::{
+        handle::{BaseNodeEvent, BaseNodeEventReceiver},
+        service::OnlineState,
+    },
+    output_manager_service::{
+        handle::{OutputManagerEvent, OutputManagerEventReceiver, OutputManagerHandle},
+        service::Balance,
+    },
+    transaction_service::handle::{TransactionEvent, TransactionEventReceiver},
+    utxo_scanner_service::handle::UtxoScannerEvent,
+};
+use futures::{stream::Fuse, StreamExt};
+use log::*;
+use std::sync::Arc;
+use tari_comms::connectivity::{ConnectivityEvent, ConnectivityEventRx, ConnectivityStatus};
+use tari_shutdown::ShutdownSignal;
+use tokio::sync::broadcast;
+
+const LOG_TARGET: &str = "wallet::wallet_events";
+
+/// The wallet activity that caused the balance to change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceChangeReason {
+    TransactionReceived = 0,
+    TransactionSent = 1,
+    TransactionMined = 2,
+    TransactionCancelled = 3,
+    OutputsValidated = 4,
+    OutputsRecovered = 5,
+    ChainReorg = 6,
+    OneSidedReclaimed = 7,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WalletEvent {
+    /// The balance has changed. `delta` is the change in MicroTari of the available plus pending incoming balance,
+    /// which is zero when funds only move from pending to available.
+    BalanceChanged {
+        balance: Balance,
+        delta: i64,
+        reason: BalanceChangeReason,
+    },
+    BaseNodeConnectivityChanged(OnlineState),
+    /// The wallet's connectivity to the network has changed, e.g. it has fewer connections than the configured minimum
+    NetworkConnectivityChanged(ConnectivityStatus),
+    /// Progress of the UTXO scanner (current_block, chain_height)
+    SyncProgress {
+        current_block: u64,
+        chain_height: u64,
+    },
+}
+
+pub type WalletEventSender = broadcast::Sender<Arc<WalletEvent>>;
+pub type WalletEventReceiver = broadcast::Receiver<Arc<WalletEvent>>;
+
+/// Aggregates the events of the wallet services into [WalletEvent]s
+pub struct WalletEventMonitor {
+    output_manager_service: OutputManagerHandle,
+    transaction_event_stream: Fuse<TransactionEventReceiver>,
+    output_manager_event_stream: Fuse<OutputManagerEventReceiver>,
+    base_node_event_stream: Fuse<BaseNodeEventReceiver>,
+    utxo_scanner_event_stream: Fuse<broadcast::Receiver<UtxoScannerEvent>>,
+    connectivity_event_stream: Fuse<ConnectivityEventRx>,
+    event_publisher: WalletEventSender,
+    last_balance: Option<Balance>,
+    last_online_state: Option<OnlineState>,
+}
+
+impl WalletEventMonitor {
+    pub fn new(
+        output_manager_service: OutputManagerHandle,
+        transaction_event_stream: Fuse<TransactionEventReceiver>,
+        output_manager_event_stream: Fuse<OutputManagerEventReceiver>,
+        base_node_event_stream: Fuse<BaseNodeEventReceiver>,
+        utxo_scanner_event_stream: broadcast::Receiver<UtxoScannerEvent>,
+        connectivity_event_stream: ConnectivityEventRx,
+    ) -> Self {
+        let (event_publisher, _) = broadcast::channel(100);
+        Self {
+            output_manager_service,
+            transaction_event_stream,
+            output_manager_event_stream,
+            base_node_event_stream,
+            utxo_scanner_event_stream: utxo_scanner_event_stream.fuse(),
+            connectivity_event_stream: connectivity_event_stream.fuse(),
+            event_publisher,
+            last_balance: None,
+            last_online_state: None,
+        }
+    }
+
+    /// Subscribe to the aggregated events. Subscribe before calling `run` to receive every event.
+    pub fn subscribe(&self) -> WalletEventReceiver {
+        self.event_publisher.subscribe()
+    }
+
+    pub async fn run(mut self, mut shutdown_signal: ShutdownSignal) {
+        match self.output_manager_service.get_balance().await {
+            Ok(balance) => self.last_balance = Some(balance),
+            Err(e) => warn!(target: LOG_TARGET, "Could not fetch initial balance: {}", e),
+        }
+
+        loop {
+            futures::select! {
+                event = self.transaction_event_stream.select_next_some() => {
+                    if let Ok(event) = event {
+                        if let Some(reason) = transaction_event_reason(&event) {
+                            self.check_balance(reason).await;
+                        }
+                    }
+                },
+                event = self.output_manager_event_stream.select_next_some() => {
+                    if let Ok(event) = event {
+                        if let OutputManagerEvent::TxoValidationSuccess(_, _) = *event {
+                            self.check_balance(BalanceChangeReason::OutputsValidated).await;
+                        }
+                    }
+                },
+                event = self.base_node_event_stream.select_next_some() => {
+                    if let Ok(event) = event {
+                        if let BaseNodeEvent::BaseNodeStateChanged(state) = &*event {
+                            self.check_online_state(state.online.clone());
+                        }
+                    }
+                },
+                event = self.utxo_scanner_event_stream.select_next_some() => {
+                    match event {
+                        Ok(UtxoScannerEvent::Progress { current_block, current_chain_height, .. }) => {
+                            self.publish(WalletEvent::SyncProgress { current_block, chain_height: current_chain_height });
+                        },
+                        Ok(UtxoScannerEvent::Completed { .. }) => {
+                            self.check_balance(BalanceChangeReason::OutputsRecovered).await;
+                        },
+                        _ => (),
+                    }
+                },
+                event = self.connectivity_event_stream.select_next_some() => {
+                    if let Ok(event) = event {
+                        if let Some(status) = connectivity_status(&event) {
+                            self.publish(WalletEvent::NetworkConnectivityChanged(status));
+                        }
+                    }
+                },
+                _ = shutdown_signal => {
+                    info!(target: LOG_TARGET, "Wallet event monitor shutting down because the shutdown signal was received");
+                    break;
+                },
+                complete => {
+                    info!(target: LOG_TARGET, "Wallet event monitor is exiting because all event streams have ended");
+                    break;
+                },
+            }
+        }
+    }
+
+    async fn check_balance(&mut self, reason: BalanceChangeReason) {
+        let balance = match self.output_manager_service.get_balance().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Could not fetch balance: {}", e);
+                return;
+            },
+        };
+        if self.last_balance.as_ref() == Some(&balance) {
+            return;
+        }
+
+        let delta = self
+            .last_balance
+            .as_ref()
+            .map(|last| spendable_total(&balance) - spendable_total(last))
+            .unwrap_or_else(|| spendable_total(&balance));
+        self.last_balance = Some(balance.clone());
+        self.publish(WalletEvent::BalanceChanged { balance, delta, reason });
+    }
+
+    fn check_online_state(&mut self, online: OnlineState) {
+        if self.last_online_state.as_ref() == Some(&online) {
+            return;
+        }
+        self.last_online_state = Some(online.clone());
+        self.publish(WalletEvent::BaseNodeConnectivityChanged(online));
+    }
+
+    fn publish(&self, event: WalletEvent) {
+        trace!(target: LOG_TARGET, "Publishing wallet event {:?}", event);
+        // An error only means that there are no subscribers
+        let _ = self.event_publisher.send(Arc::new(event));
+    }
+}
+
+fn spendable_total(balance: &Balance) -> i64 {
+    (balance.available_balance.as_u64() + balance.pending_incoming_balance.as_u64()) as i64
+}
+
+fn connectivity_status(event: &ConnectivityEvent) -> Option<ConnectivityStatus> {
+    use ConnectivityEvent::*;
+    match event {
+        ConnectivityStateOnline(n) => Some(ConnectivityStatus::Online(*n)),
+        ConnectivityStateDegraded(n) => Some(ConnectivityStatus::Degraded(*n)),
+        ConnectivityStateOffline => Some(ConnectivityStatus::Offline),
+        _ => None,
+    }
+}
+
+fn transaction_event_reason(event: &TransactionEvent) -> Option<BalanceChangeReason> {
+    use TransactionEvent::*;
+    match event {
+        ReceivedTransaction(_) | ReceivedFinalizedTransaction(_) | TransactionImported(_) => {
+            Some(BalanceChangeReason::TransactionReceived)
+        },
+        TransactionCompletedImmediately(_) |
+        TransactionDirectSendResult(_, _) |
+        TransactionStoreForwardSendResult(_, _) => Some(BalanceChangeReason::TransactionSent),
+        TransactionMined(_) | TransactionMinedUnconfirmed(_, _) => Some(BalanceChangeReason::TransactionMined),
+        TransactionCancelled(_) => Some(BalanceChangeReason::TransactionCancelled),
+        ChainReorg(_) => Some(BalanceChangeReason::ChainReorg),
+        OneSidedReclaimed(_) => Some(BalanceChangeReason::OneSidedReclaimed),
+        _ => None,
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::support::rpc::{BaseNodeWalletRpcMockService, BaseNodeWalletRpcMockState};
+use futures::{channel::mpsc, StreamExt};
+use std::{sync::Arc, time::Duration};
+use tari_comms::{
+    peer_manager::PeerFeatures,
+    protocol::rpc::{mock::MockRpcServer, NamedProtocolService},
+    test_utils::{mocks::create_connectivity_mock, node_identity::build_node_identity},
+    types::CommsPublicKey,
+    NodeIdentity,
+    Substream,
+};
+use tari_core::{
+    base_node::rpc::BaseNodeWalletRpcServer,
+    proto::base_node::{ChainMetadata, TipInfoResponse},
+};
+use tari_service_framework::reply_channel;
+use tari_shutdown::Shutdown;
+use tari_wallet::{
+    base_node_selection_service::{
+        config::BaseNodeSelectionServiceConfig,
+        handle::{BaseNodeSelectionEvent, BaseNodeSelectionServiceHandle, SwitchReason},
+        service::BaseNodeSelectionService,
+    },
+    base_node_service::{handle::BaseNodeServiceHandle, mock_base_node_service::MockBaseNodeService},
+    output_manager_service::handle::{OutputManagerHandle, OutputManagerRequest, OutputManagerResponse},
+    transaction_service::handle::{TransactionServiceHandle, TransactionServiceRequest, TransactionServiceResponse},
+    utxo_scanner_service::handle::{UtxoScannerHandle, UtxoScannerResponse},
+};
+use tokio::{sync::broadcast, task, time::timeout};
+
+struct MockBaseNode {
+    node_identity: Arc<NodeIdentity>,
+    state: BaseNodeWalletRpcMockState,
+    _server: MockRpcServer<BaseNodeWalletRpcServer<BaseNodeWalletRpcMockService>, Substream>,
+}
+
+fn tip_info(height: u64, is_synced: bool) -> TipInfoResponse {
+    TipInfoResponse {
+        metadata: Some(ChainMetadata {
+            height_of_longest_chain: Some(height),
+            best_block: Some(Vec::new()),
+            accumulated_difficulty: 1u128.to_be_bytes().to_vec(),
+            pruned_height: 0,
+            accumulated_monero_difficulty: 0,
+            accumulated_sha_difficulty: 0,
+        }),
+        is_synced,
+    }
+}
+
+async fn setup_base_node_selection_service(
+    shutdown: &Shutdown,
+) -> (
+    BaseNodeSelectionServiceHandle,
+    BaseNodeServiceHandle,
+    mpsc::UnboundedReceiver<CommsPublicKey>,
+    Vec<MockBaseNode>,
+) {
+    let (connectivity_manager, connectivity_mock) = create_connectivity_mock();
+    let connectivity_mock_state = connectivity_mock.get_shared_state();
+    connectivity_mock.spawn();
+
+    let mut base_nodes = Vec::new();
+    for _ in 0..2 {
+        let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+        let service = BaseNodeWalletRpcMockService::new();
+        let state = service.get_state();
+        let server = BaseNodeWalletRpcServer::new(service);
+        let protocol_name = server.as_protocol_name();
+        let mut mock_server = MockRpcServer::new(server, node_identity.clone());
+        mock_server.serve();
+        let connection = mock_server
+            .create_connection(node_identity.to_peer(), protocol_name.into())
+            .await;
+        connectivity_mock_state.add_active_connection(connection).await;
+        base_nodes.push(MockBaseNode {
+            node_identity,
+            state,
+            _server: mock_server,
+        });
+    }
+
+    // The transaction service reports which base node it was switched to
+    let (ts_sender, mut ts_receiver) = reply_channel::unbounded();
+    let (ts_event_publisher, _) = broadcast::channel(20);
+    let (switched_tx, switched_rx) = mpsc::unbounded();
+    task::spawn(async move {
+        while let Some(request_context) = ts_receiver.next().await {
+            let (request, reply_tx) = request_context.split();
+            if let TransactionServiceRequest::SetBaseNodePublicKey(public_key) = request {
+                let _ = switched_tx.unbounded_send(public_key);
+            }
+            let _ = reply_tx.send(Ok(TransactionServiceResponse::BaseNodePublicKeySet));
+        }
+    });
+
+    let (oms_sender, mut oms_receiver) = reply_channel::unbounded();
+    let (oms_event_publisher, _) = broadcast::channel(20);
+    task::spawn(async move {
+        while let Some(request_context) = oms_receiver.next().await {
+            let (request, reply_tx) = request_context.split();
+            assert!(matches!(request, OutputManagerRequest::SetBaseNodePublicKey(_)));
+            let _ = reply_tx.send(Ok(OutputManagerResponse::BaseNodePublicKeySet));
+        }
+    });
+
+    let (utxo_sender, mut utxo_receiver) = reply_channel::unbounded();
+    let (utxo_event_publisher, _) = broadcast::channel(20);
+    task::spawn(async move {
+        while let Some(request_context) = utxo_receiver.next().await {
+            let (_, reply_tx) = request_context.split();
+            let _ = reply_tx.send(Ok(UtxoScannerResponse::BaseNodePublicKeySet));
+        }
+    });
+
+    let (bns_sender, bns_receiver) = reply_channel::unbounded();
+    let (bns_event_publisher, _) = broadcast::channel(20);
+    let base_node_service = BaseNodeServiceHandle::new(bns_sender, bns_event_publisher);
+    task::spawn(MockBaseNodeService::new(bns_receiver, shutdown.to_signal()).run());
+
+    let (sender, receiver) = reply_channel::unbounded();
+    let (event_publisher, _) = broadcast::channel(20);
+    let handle = BaseNodeSelectionServiceHandle::new(sender, event_publisher.clone());
+
+    let config = BaseNodeSelectionServiceConfig {
+        // Candidates are probed explicitly by the tests
+        probe_interval: Duration::from_secs(3600),
+        probe_timeout: Duration::from_secs(10),
+        ..Default::default()
+    };
+    let service = BaseNodeSelectionService::new(
+        config,
+        receiver,
+        connectivity_manager,
+        base_node_service.clone(),
+        TransactionServiceHandle::new(ts_sender, ts_event_publisher),
+        OutputManagerHandle::new(oms_sender, oms_event_publisher),
+        UtxoScannerHandle::new(utxo_sender, utxo_event_publisher),
+        event_publisher,
+        shutdown.to_signal(),
+    );
+    task::spawn(service.start());
+
+    (handle, base_node_service, switched_rx, base_nodes)
+}
+
+#[tokio_macros::test]
+async fn test_base_node_selection() {
+    let shutdown = Shutdown::new();
+    let (mut handle, mut base_node_service, mut switched, base_nodes) =
+        setup_base_node_selection_service(&shutdown).await;
+    let mut events = handle.get_event_stream_fused();
+    let node_a = base_nodes[0].node_identity.public_key().clone();
+    let node_b = base_nodes[1].node_identity.public_key().clone();
+
+    // Node B is too far behind to be selected
+    base_nodes[0].state.set_tip_info_response(tip_info(100, true));
+    base_nodes[1].state.set_tip_info_response(tip_info(90, true));
+    for base_node in &base_nodes {
+        handle.add_candidate(base_node.node_identity.to_peer()).await.unwrap();
+    }
+
+    let selected = handle.probe_candidates().await.unwrap();
+    assert_eq!(selected, Some(node_a.clone()));
+    assert_eq!(
+        timeout(Duration::from_secs(10), switched.next()).await.unwrap(),
+        Some(node_a.clone())
+    );
+    assert_eq!(
+        base_node_service
+            .get_base_node_peer()
+            .await
+            .unwrap()
+            .unwrap()
+            .public_key,
+        node_a
+    );
+    let event = timeout(Duration::from_secs(10), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(*event, BaseNodeSelectionEvent::BaseNodeSwitched {
+        previous: None,
+        selected: node_a.clone(),
+        reason: SwitchReason::NoBaseNode,
+    });
+
+    let candidates = handle.get_candidates().await.unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert!(candidates.iter().all(|c| c.is_reachable()));
+
+    // Nothing changes while node A is still the best
+    assert_eq!(handle.probe_candidates().await.unwrap(), None);
+
+    // Node A stops being synced so node B is selected
+    base_nodes[0].state.set_tip_info_response(tip_info(100, false));
+    base_nodes[1].state.set_tip_info_response(tip_info(100, true));
+    let selected = handle.probe_candidates().await.unwrap();
+    assert_eq!(selected, Some(node_b.clone()));
+    let event = timeout(Duration::from_secs(10), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(*event, BaseNodeSelectionEvent::BaseNodeSwitched {
+        previous: Some(node_a.clone()),
+        selected: node_b.clone(),
+        reason: SwitchReason::Behind,
+    });
+
+    // Once node B is removed it is no longer probed, and the wallet stays on it because it is no longer a candidate
+    handle.remove_candidate(node_b.clone()).await.unwrap();
+    assert!(handle.remove_candidate(node_b).await.is_err());
+    base_nodes[0].state.set_tip_info_response(tip_info(101, true));
+    assert_eq!(handle.probe_candidates().await.unwrap(), None);
+}
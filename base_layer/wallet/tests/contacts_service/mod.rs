@@ -21,22 +21,37 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::support::data::get_temp_sqlite_database_connection;
+use futures::StreamExt;
 use rand::rngs::OsRng;
+use std::time::Duration;
+use tari_comms::peer_manager::NodeId;
 use tari_core::transactions::types::PublicKey;
-use tari_crypto::keys::PublicKey as PublicKeyTrait;
-use tari_service_framework::StackBuilder;
+use tari_crypto::{keys::PublicKey as PublicKeyTrait, tari_utilities::hex::Hex};
+use tari_p2p::services::liveness::{
+    mock::create_p2p_liveness_mock,
+    LivenessEvent,
+    LivenessRequest,
+    Metadata,
+    PingPongEvent,
+};
+use tari_service_framework::{RegisterHandle, StackBuilder};
 use tari_shutdown::Shutdown;
 use tari_test_utils::random;
-use tari_wallet::contacts_service::{
-    error::{ContactsServiceError, ContactsServiceStorageError},
-    handle::ContactsServiceHandle,
-    storage::{
-        database::{Contact, ContactsBackend, DbKey},
-        sqlite_db::ContactsServiceSqliteDatabase,
+use tari_wallet::{
+    contacts_service::{
+        bundle::{ContactBundle, ContactsImportSummary},
+        config::ContactsServiceConfig,
+        error::{ContactsServiceError, ContactsServiceStorageError},
+        handle::{ContactOnlineStatus, ContactsLivenessEvent, ContactsServiceHandle},
+        storage::{
+            database::{Contact, ContactsBackend, DbKey},
+            sqlite_db::ContactsServiceSqliteDatabase,
+        },
+        ContactsServiceInitializer,
     },
-    ContactsServiceInitializer,
+    util::emoji::{emoji_set, EmojiId},
 };
-use tokio::runtime::Runtime;
+use tokio::{runtime::Runtime, time::timeout};
 
 pub fn setup_contacts_service<T: ContactsBackend + 'static>(
     runtime: &mut Runtime,
@@ -44,7 +59,7 @@ pub fn setup_contacts_service<T: ContactsBackend + 'static>(
 ) -> (ContactsServiceHandle, Shutdown) {
     let shutdown = Shutdown::new();
     let fut = StackBuilder::new(shutdown.to_signal())
-        .add_initializer(ContactsServiceInitializer::new(backend))
+        .add_initializer(ContactsServiceInitializer::new(Default::default(), backend))
         .build();
 
     let handles = runtime.block_on(fut).expect("Service initialization failed");
@@ -69,6 +84,7 @@ pub fn test_contacts_service() {
         contacts.push(Contact {
             alias: random::string(8),
             public_key,
+            notes: None,
         });
 
         runtime
@@ -120,3 +136,197 @@ pub fn test_contacts_service() {
 
     assert_eq!(new_contact.alias, updated_contact.alias);
 }
+
+#[test]
+pub fn test_export_import_contacts() {
+    let mut runtime = Runtime::new().unwrap();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let (mut exporting_service, _shutdown) =
+        setup_contacts_service(&mut runtime, ContactsServiceSqliteDatabase::new(connection));
+    let (connection, _tempdir2) = get_temp_sqlite_database_connection();
+    let (mut importing_service, _shutdown2) =
+        setup_contacts_service(&mut runtime, ContactsServiceSqliteDatabase::new(connection));
+
+    let contacts = (0..3)
+        .map(|i| Contact {
+            alias: random::string(8),
+            public_key: PublicKey::random_keypair(&mut OsRng).1,
+            notes: Some(format!("Notes {}", i)),
+        })
+        .collect::<Vec<_>>();
+    for contact in &contacts {
+        runtime
+            .block_on(exporting_service.upsert_contact(contact.clone()))
+            .unwrap();
+    }
+    // The importing wallet already knows the first contact under a different alias
+    let existing = Contact {
+        alias: "Local alias".to_string(),
+        notes: None,
+        ..contacts[0].clone()
+    };
+    runtime
+        .block_on(importing_service.upsert_contact(existing.clone()))
+        .unwrap();
+
+    let bundle = runtime.block_on(exporting_service.export_contacts()).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("contacts.json");
+    bundle.write_to_file(&path, Some("correct horse")).unwrap();
+    assert_eq!(
+        ContactBundle::read_from_file(&path, Some("wrong horse")).unwrap_err(),
+        ContactsServiceError::ContactBundleDecryptionFailed
+    );
+    let bundle = ContactBundle::read_from_file(&path, Some("correct horse")).unwrap();
+
+    let summary = runtime.block_on(importing_service.import_contacts(bundle)).unwrap();
+    assert_eq!(summary, ContactsImportSummary {
+        added: 2,
+        updated: 1,
+        unchanged: 0,
+    });
+
+    let imported = runtime
+        .block_on(importing_service.get_contact(contacts[0].public_key.clone()))
+        .unwrap();
+    assert_eq!(imported.alias, existing.alias);
+    assert_eq!(imported.notes, contacts[0].notes);
+    for contact in &contacts[1..] {
+        let imported = runtime
+            .block_on(importing_service.get_contact(contact.public_key.clone()))
+            .unwrap();
+        assert_eq!(&imported, contact);
+    }
+}
+
+#[test]
+pub fn test_search_contacts() {
+    let mut runtime = Runtime::new().unwrap();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = ContactsServiceSqliteDatabase::new(connection);
+
+    let (mut contacts_service, _shutdown) = setup_contacts_service(&mut runtime, backend);
+
+    let contacts = ["Alicia", "Bob", "Alice"]
+        .iter()
+        .map(|alias| Contact {
+            alias: alias.to_string(),
+            public_key: PublicKey::random_keypair(&mut OsRng).1,
+            notes: None,
+        })
+        .collect::<Vec<_>>();
+    for contact in &contacts {
+        runtime
+            .block_on(contacts_service.upsert_contact(contact.clone()))
+            .unwrap();
+    }
+    let search = |contacts_service: &mut ContactsServiceHandle, runtime: &mut Runtime, query: String| {
+        runtime
+            .block_on(contacts_service.search_contacts(query))
+            .unwrap()
+            .into_iter()
+            .map(|c| c.alias)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(search(&mut contacts_service, &mut runtime, "ali".to_string()), vec![
+        "Alice", "Alicia"
+    ]);
+    assert_eq!(
+        search(
+            &mut contacts_service,
+            &mut runtime,
+            contacts[1].public_key.to_hex()[..16].to_string()
+        ),
+        vec!["Bob"]
+    );
+
+    // An emoji ID with a typo still finds the contact
+    let mut emoji_id = EmojiId::from_pubkey(&contacts[1].public_key)
+        .as_str()
+        .chars()
+        .collect::<Vec<_>>();
+    emoji_id[3] = if emoji_id[3] == emoji_set()[0] {
+        emoji_set()[1]
+    } else {
+        emoji_set()[0]
+    };
+    let typo = emoji_id.iter().collect::<String>();
+    assert!(!EmojiId::is_valid(&typo));
+    assert_eq!(search(&mut contacts_service, &mut runtime, typo), vec!["Bob"]);
+
+    assert!(search(&mut contacts_service, &mut runtime, "Carol".to_string()).is_empty());
+}
+
+#[test]
+pub fn test_contacts_liveness() {
+    let mut runtime = Runtime::new().unwrap();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = ContactsServiceSqliteDatabase::new(connection);
+
+    let (liveness_handle, liveness_mock, _) = create_p2p_liveness_mock(10);
+    let liveness_mock_state = liveness_mock.get_mock_state();
+    runtime.spawn(liveness_mock.run());
+
+    let shutdown = Shutdown::new();
+    let fut = StackBuilder::new(shutdown.to_signal())
+        .add_initializer(RegisterHandle::new(liveness_handle))
+        .add_initializer(ContactsServiceInitializer::new(
+            ContactsServiceConfig {
+                liveness_ping_interval: Duration::from_secs(60),
+                liveness_offline_timeout: Duration::from_secs(60),
+            },
+            backend,
+        ))
+        .build();
+    let handles = runtime.block_on(fut).expect("Service initialization failed");
+    let mut contacts_service = handles.expect_handle::<ContactsServiceHandle>();
+    let mut event_stream = contacts_service.get_contacts_liveness_event_stream_fused();
+
+    let (_secret_key, public_key) = PublicKey::random_keypair(&mut OsRng);
+    let contact = Contact {
+        alias: random::string(8),
+        public_key: public_key.clone(),
+        notes: None,
+    };
+    runtime.block_on(contacts_service.upsert_contact(contact)).unwrap();
+
+    // The new contact is pinged straight away
+    let node_id = NodeId::from_public_key(&public_key);
+    let calls = liveness_mock_state.take_calls();
+    assert!(calls
+        .iter()
+        .any(|c| matches!(c, LivenessRequest::SendPing(n) if *n == node_id)));
+
+    let liveness = runtime
+        .block_on(contacts_service.get_contact_liveness(public_key.clone()))
+        .unwrap();
+    assert_eq!(liveness.online_status, ContactOnlineStatus::NeverSeen);
+
+    runtime
+        .block_on(
+            liveness_mock_state.publish_event(LivenessEvent::ReceivedPong(Box::new(PingPongEvent::new(
+                node_id,
+                Some(123),
+                Metadata::new(),
+            )))),
+        )
+        .unwrap();
+
+    let event = runtime
+        .block_on(async { timeout(Duration::from_secs(10), event_stream.select_next_some()).await })
+        .unwrap()
+        .unwrap();
+    match &*event {
+        ContactsLivenessEvent::StatusUpdated(data) => {
+            assert_eq!(data.public_key, public_key);
+            assert_eq!(data.online_status, ContactOnlineStatus::Online);
+            assert_eq!(data.latency, Some(123));
+            assert!(data.last_seen.is_some());
+        },
+    }
+
+    let liveness = runtime.block_on(contacts_service.get_contacts_liveness()).unwrap();
+    assert_eq!(liveness.len(), 1);
+    assert_eq!(liveness[0].online_status, ContactOnlineStatus::Online);
+}
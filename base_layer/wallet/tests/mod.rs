@@ -20,8 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+pub mod base_node_selection_service;
 pub mod contacts_service;
 pub mod output_manager_service;
+pub mod recurring_payment_service;
 pub mod support;
 pub mod transaction_service;
 pub mod wallet;
@@ -62,26 +62,28 @@ use tari_crypto::{
     script,
     script::TariScript,
 };
+use tari_key_manager::key_manager::KeyManager;
 use tari_p2p::Network;
 use tari_service_framework::reply_channel;
 use tari_shutdown::Shutdown;
 use tari_wallet::{
     base_node_service::{handle::BaseNodeServiceHandle, mock_base_node_service::MockBaseNodeService},
     output_manager_service::{
-        config::OutputManagerServiceConfig,
+        config::{OutputManagerServiceConfig, UnconfirmedChangePolicy},
         error::{OutputManagerError, OutputManagerStorageError},
-        handle::{OutputManagerEvent, OutputManagerHandle},
+        handle::{OutputManagerEvent, OutputManagerHandle, ReceiveOutputOptions},
         service::OutputManagerService,
         storage::{
             database::{DbKey, DbKeyValuePair, DbValue, OutputManagerBackend, OutputManagerDatabase, WriteOperation},
-            models::DbUnblindedOutput,
+            models::{DbUnblindedOutput, DEFAULT_ACCOUNT},
             sqlite_db::OutputManagerSqliteDatabase,
         },
         TxId,
         TxoValidationType,
     },
+    test_utils::spawn_key_manager_service,
     transaction_service::handle::TransactionServiceHandle,
-    types::ValidationRetryStrategy,
+    types::{KeyDigest, ValidationRetryStrategy},
 };
 
 use tokio::{
@@ -103,6 +105,29 @@ pub fn setup_output_manager_service<T: OutputManagerBackend + 'static>(
     Arc<NodeIdentity>,
     BaseNodeWalletRpcMockState,
     ConnectivityManagerMockState,
+) {
+    setup_output_manager_service_with_config(runtime, backend, with_connection, OutputManagerServiceConfig {
+        base_node_query_timeout: Duration::from_secs(10),
+        max_utxo_query_size: 2,
+        peer_dial_retry_timeout: Duration::from_secs(5),
+        ..Default::default()
+    })
+}
+
+#[allow(clippy::type_complexity)]
+pub fn setup_output_manager_service_with_config<T: OutputManagerBackend + 'static>(
+    runtime: &mut Runtime,
+    backend: T,
+    with_connection: bool,
+    config: OutputManagerServiceConfig,
+) -> (
+    OutputManagerHandle,
+    Shutdown,
+    TransactionServiceHandle,
+    MockRpcServer<BaseNodeWalletRpcServer<BaseNodeWalletRpcMockService>, Substream>,
+    Arc<NodeIdentity>,
+    BaseNodeWalletRpcMockState,
+    ConnectivityManagerMockState,
 ) {
     let shutdown = Shutdown::new();
     let factories = CryptoFactories::default();
@@ -149,14 +174,10 @@ pub fn setup_output_manager_service<T: OutputManagerBackend + 'static>(
         });
         runtime.block_on(connectivity_mock_state.add_active_connection(connection));
     }
+    let key_manager = spawn_key_manager_service(runtime.handle(), backend.clone(), shutdown.to_signal());
     let output_manager_service = runtime
         .block_on(OutputManagerService::new(
-            OutputManagerServiceConfig {
-                base_node_query_timeout: Duration::from_secs(10),
-                max_utxo_query_size: 2,
-                peer_dial_retry_timeout: Duration::from_secs(5),
-                ..Default::default()
-            },
+            config,
             ts_handle.clone(),
             oms_request_receiver,
             OutputManagerDatabase::new(backend),
@@ -166,6 +187,7 @@ pub fn setup_output_manager_service<T: OutputManagerBackend + 'static>(
             shutdown.to_signal(),
             basenode_service_handle,
             connectivity_manager,
+            key_manager,
             CommsSecretKey::default(),
         ))
         .unwrap();
@@ -250,6 +272,7 @@ pub fn setup_oms_with_bn_state<T: OutputManagerBackend + 'static>(
     let _connectivity_mock_state = connectivity_mock.get_shared_state();
     runtime.spawn(connectivity_mock.run());
 
+    let key_manager = spawn_key_manager_service(runtime.handle(), backend.clone(), shutdown.to_signal());
     let output_manager_service = runtime
         .block_on(OutputManagerService::new(
             OutputManagerServiceConfig {
@@ -267,6 +290,7 @@ pub fn setup_oms_with_bn_state<T: OutputManagerBackend + 'static>(
             shutdown.to_signal(),
             base_node_service_handle.clone(),
             connectivity_manager,
+            key_manager,
             CommsSecretKey::default(),
         ))
         .unwrap();
@@ -529,6 +553,67 @@ fn test_utxo_selection_with_chain_metadata() {
     }
 }
 
+#[test]
+fn send_all_spendable_outputs() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let (mut oms, _shutdown, _, _) = setup_oms_with_bn_state(
+        &mut runtime,
+        OutputManagerSqliteDatabase::new(connection, None),
+        Some(6),
+    );
+
+    let fee_per_gram = MicroTari::from(10);
+    let err = runtime
+        .block_on(oms.prepare_transaction_to_send_all(fee_per_gram, None, "".to_string(), script!(Nop)))
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerError::NotEnoughFunds));
+
+    // create 10 utxos with maturity at heights from 1 to 10
+    let amount = MicroTari::from(1000);
+    for i in 1..=10 {
+        let (_, uo) = make_input_with_features(
+            &mut OsRng.clone(),
+            i * amount,
+            &factories.commitment,
+            Some(OutputFeatures::with_maturity(i)),
+        );
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_all(fee_per_gram, None, "".to_string(), script!(Nop)))
+        .unwrap();
+    // Only the outputs that have matured at height 6 are spent, and nothing is left over as change
+    let spendable_amount = (1..=6).sum::<u64>() * amount;
+    assert_eq!(
+        stp.get_total_amount().unwrap() + stp.get_fee_amount().unwrap(),
+        spendable_amount
+    );
+    assert_eq!(stp.get_change_amount().unwrap(), MicroTari::from(0));
+    let utxos = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(utxos.len(), 4);
+    assert!(utxos.iter().all(|u| u.features.maturity > 6));
+}
+
+#[test]
+fn send_all_requires_chain_tip() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let (mut oms, _shutdown, _, _) =
+        setup_oms_with_bn_state(&mut runtime, OutputManagerSqliteDatabase::new(connection, None), None);
+
+    let (_, uo) = make_input(&mut OsRng.clone(), MicroTari::from(1000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    let err = runtime
+        .block_on(oms.prepare_transaction_to_send_all(MicroTari::from(10), None, "".to_string(), script!(Nop)))
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerError::ChainTipUnknown));
+}
+
 #[test]
 fn sending_transaction_and_confirmation() {
     let factories = CryptoFactories::default();
@@ -813,6 +898,174 @@ fn receiving_and_confirmation() {
     assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 1);
 }
 
+#[test]
+fn receiving_with_custom_output_options() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+
+    let (mut oms, _shutdown, _, _, _, _, _) = setup_output_manager_service(&mut runtime, backend, true);
+
+    let (_, sender_message) = generate_sender_transaction_message(MicroTari::from(5000));
+    let options = ReceiveOutputOptions {
+        features: Some(OutputFeatures::with_maturity(10)),
+        script: Some((script!(Drop Nop), inputs!(PublicKey::default()))),
+        ..Default::default()
+    };
+    let rtp = runtime
+        .block_on(oms.get_recipient_transaction_with_options(sender_message, options))
+        .unwrap();
+
+    let output = match rtp.state {
+        RecipientState::Finalized(s) => s.output,
+        RecipientState::Failed(_) => panic!("Should not be in Failed state"),
+    };
+    assert_eq!(output.script, script!(Drop Nop));
+    assert_eq!(output.features.maturity, 10);
+
+    let (_, sender_message) = generate_sender_transaction_message(MicroTari::from(5000));
+    let options = ReceiveOutputOptions {
+        script: Some((script!(Return), inputs!(PublicKey::default()))),
+        ..Default::default()
+    };
+    let err = runtime
+        .block_on(oms.get_recipient_transaction_with_options(sender_message, options))
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerError::NonStandardScript(_)));
+}
+
+#[test]
+fn account_outputs_are_kept_separate() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+
+    let (mut oms, _shutdown, _, _, _, _, _) = setup_output_manager_service(&mut runtime, backend, true);
+
+    let account = "savings".to_string();
+    runtime.block_on(oms.create_account(account.clone())).unwrap();
+    let err = runtime.block_on(oms.create_account(account.clone())).unwrap_err();
+    assert!(matches!(err, OutputManagerError::AccountAlreadyExists));
+    assert_eq!(runtime.block_on(oms.get_accounts()).unwrap(), vec![account.clone()]);
+
+    let default_value = MicroTari::from(2000);
+    let (_ti, uo) = make_input(&mut OsRng.clone(), default_value, &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    let account_value = MicroTari::from(5000);
+    let (tx_id, sender_message) = generate_sender_transaction_message(account_value);
+    let options = ReceiveOutputOptions {
+        account: Some(account.clone()),
+        ..Default::default()
+    };
+    let rtp = runtime
+        .block_on(oms.get_recipient_transaction_with_options(sender_message, options))
+        .unwrap();
+    let output = match rtp.state {
+        RecipientState::Finalized(s) => s.output,
+        RecipientState::Failed(_) => panic!("Should not be in Failed state"),
+    };
+    runtime
+        .block_on(oms.confirm_transaction(tx_id, vec![], vec![output]))
+        .unwrap();
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, default_value);
+    let balance = runtime.block_on(oms.get_account_balance(account.clone())).unwrap();
+    assert_eq!(balance.available_balance, account_value);
+
+    // The default account cannot spend the account's outputs
+    let err = runtime
+        .block_on(oms.prepare_transaction_to_send(
+            MicroTari::from(3000),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            script!(Nop),
+        ))
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerError::NotEnoughFunds));
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_from_account(
+            account.clone(),
+            MicroTari::from(3000),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            script!(Nop),
+        ))
+        .unwrap();
+    let change = stp.get_change_amount().unwrap();
+    let balance = runtime.block_on(oms.get_account_balance(account)).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(0));
+    assert_eq!(balance.pending_outgoing_balance, account_value);
+    assert_eq!(balance.pending_incoming_balance, change);
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, default_value);
+
+    let err = runtime
+        .block_on(oms.get_account_balance("unknown".to_string()))
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerError::AccountNotFound(_)));
+}
+
+#[test]
+fn internal_transfer_between_accounts() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+
+    let (mut oms, _shutdown, _, _, _, _, _) = setup_output_manager_service(&mut runtime, backend, true);
+
+    let account = "savings".to_string();
+    runtime.block_on(oms.create_account(account.clone())).unwrap();
+
+    let initial_value = MicroTari::from(5000);
+    let (input, uo) = make_input(&mut OsRng.clone(), initial_value, &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    let amount = MicroTari::from(2000);
+    let (tx_id, fee, tx) = runtime
+        .block_on(oms.create_internal_transfer(
+            DEFAULT_ACCOUNT.to_string(),
+            account.clone(),
+            amount,
+            MicroTari::from(20),
+            "Move to savings".to_string(),
+        ))
+        .unwrap();
+    let balance = runtime.block_on(oms.get_account_balance(account.clone())).unwrap();
+    assert_eq!(balance.pending_incoming_balance, amount);
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.pending_outgoing_balance, initial_value);
+    assert_eq!(balance.pending_incoming_balance, initial_value - amount - fee);
+
+    runtime
+        .block_on(oms.confirm_transaction(tx_id, vec![input], tx.body.outputs().clone()))
+        .unwrap();
+    let balance = runtime.block_on(oms.get_account_balance(account.clone())).unwrap();
+    assert_eq!(balance.available_balance, amount);
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, initial_value - amount - fee);
+
+    let err = runtime
+        .block_on(oms.create_internal_transfer(
+            account,
+            "unknown".to_string(),
+            MicroTari::from(500),
+            MicroTari::from(20),
+            "".to_string(),
+        ))
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerError::AccountNotFound(_)));
+}
+
 #[test]
 fn cancel_transaction() {
     let factories = CryptoFactories::default();
@@ -855,6 +1108,72 @@ fn cancel_transaction() {
     assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), num_outputs);
 }
 
+#[test]
+fn spend_unconfirmed_change() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+
+    let (mut oms, _shutdown, _, _, _, _, _) =
+        setup_output_manager_service_with_config(&mut runtime, backend, true, OutputManagerServiceConfig {
+            unconfirmed_change_policy: UnconfirmedChangePolicy::UpToDepth(1),
+            ..Default::default()
+        });
+
+    let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(10_000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    let parent = runtime
+        .block_on(oms.prepare_transaction_to_send(
+            MicroTari::from(2000),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            script!(Nop),
+        ))
+        .unwrap();
+    let parent_tx_id = parent.get_tx_id().unwrap();
+    runtime.block_on(oms.confirm_pending_transaction(parent_tx_id)).unwrap();
+
+    // The parent's change is spendable while the parent is still pending
+    let child = runtime
+        .block_on(oms.prepare_transaction_to_send(
+            MicroTari::from(2000),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            script!(Nop),
+        ))
+        .unwrap();
+    let child_tx_id = child.get_tx_id().unwrap();
+    runtime.block_on(oms.confirm_pending_transaction(child_tx_id)).unwrap();
+
+    // The child's change is two unconfirmed transactions deep, which the policy does not allow
+    let err = runtime
+        .block_on(oms.prepare_transaction_to_send(
+            MicroTari::from(2000),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            script!(Nop),
+        ))
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerError::FundsPending));
+
+    // Cancelling the parent cancels the child that spent its change
+    let cancelled = runtime.block_on(oms.cancel_transaction(parent_tx_id)).unwrap();
+    assert_eq!(cancelled, vec![child_tx_id]);
+    assert!(runtime.block_on(oms.get_pending_transactions()).unwrap().is_empty());
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(10_000));
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(0));
+    assert_eq!(balance.pending_outgoing_balance, MicroTari::from(0));
+}
+
 #[test]
 fn timeout_transaction() {
     let factories = CryptoFactories::default();
@@ -904,6 +1223,54 @@ fn timeout_transaction() {
     assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), num_outputs);
 }
 
+#[test]
+fn reserve_and_release_outputs() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+
+    let (mut oms, _shutdown, _, _, _, _, _) = setup_output_manager_service(&mut runtime, backend, true);
+
+    for _i in 0..2 {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(1000), &factories.commitment);
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+
+    let lease = runtime
+        .block_on(oms.reserve_outputs(MicroTari::from(500), MicroTari::from(1), Duration::from_secs(3600)))
+        .unwrap();
+    assert_eq!(lease.outputs.len(), 1);
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 1);
+
+    // Leased outputs cannot be selected for another lease or transaction
+    match runtime.block_on(oms.reserve_outputs(MicroTari::from(1500), MicroTari::from(1), Duration::from_secs(3600))) {
+        Err(OutputManagerError::NotEnoughFunds) => {},
+        _ => panic!("Leased outputs should not be selected"),
+    }
+
+    let short_lease = runtime
+        .block_on(oms.reserve_outputs(MicroTari::from(500), MicroTari::from(1), Duration::from_millis(1)))
+        .unwrap();
+    assert!(runtime.block_on(oms.get_unspent_outputs()).unwrap().is_empty());
+    assert_eq!(runtime.block_on(oms.get_output_leases()).unwrap().len(), 2);
+
+    // Reserving again releases the expired lease first
+    thread::sleep(Duration::from_millis(2));
+    let next_lease = runtime
+        .block_on(oms.reserve_outputs(MicroTari::from(500), MicroTari::from(1), Duration::from_secs(3600)))
+        .unwrap();
+    assert_eq!(next_lease.outputs[0].spending_key, short_lease.outputs[0].spending_key);
+
+    runtime.block_on(oms.release_output_lease(lease.lease_id)).unwrap();
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 1);
+    match runtime.block_on(oms.release_output_lease(lease.lease_id)) {
+        Err(OutputManagerError::OutputLeaseNotFound(_)) => {},
+        _ => panic!("Lease should not exist"),
+    }
+}
+
 #[test]
 fn test_get_balance() {
     let factories = CryptoFactories::default();
@@ -982,6 +1349,43 @@ fn test_confirming_received_output() {
     assert_eq!(rewind_result.committed_value, value);
 }
 
+#[test]
+fn test_child_pays_for_parent() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+
+    let (mut oms, _shutdown, _, _, _, _, _) = setup_output_manager_service(&mut runtime, backend, true);
+
+    let value = MicroTari::from(100_000);
+    let (parent_tx_id, sender_message) = generate_sender_transaction_message(value);
+    runtime.block_on(oms.get_recipient_transaction(sender_message)).unwrap();
+
+    let parent_fee = MicroTari::from(10);
+    let parent_weight = 50;
+    let fee_per_gram = MicroTari::from(25);
+    let (tx_id, fee, amount, tx) = runtime
+        .block_on(oms.create_child_pays_for_parent_transaction(parent_tx_id, parent_fee, parent_weight, fee_per_gram))
+        .unwrap();
+    assert_eq!(amount + fee, value);
+    assert!(fee + parent_fee > MicroTari::from(parent_weight * 25));
+    assert_eq!(tx.body.inputs().len(), 1);
+    assert_eq!(tx.body.outputs().len(), 1);
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 2);
+
+    // The parent's outputs are now spent by the child
+    let err = runtime
+        .block_on(oms.create_child_pays_for_parent_transaction(parent_tx_id, parent_fee, parent_weight, fee_per_gram))
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerError::NoUnconfirmedOutputsToSpend(id) if id == parent_tx_id));
+
+    runtime
+        .block_on(oms.confirm_transaction(tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+    assert_eq!(runtime.block_on(oms.get_balance()).unwrap().available_balance, amount);
+}
+
 #[test]
 fn sending_transaction_with_short_term_clear() {
     let factories = CryptoFactories::default();
@@ -1124,6 +1528,86 @@ fn coin_split_no_change() {
     assert_eq!(amount, val1 + val2 + val3);
 }
 
+#[test]
+fn deterministic_nonces_derive_unique_offsets_for_every_send_path() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+    let (mut oms, _shutdown, _, _, _, _, _) =
+        setup_output_manager_service_with_config(&mut runtime, backend, true, OutputManagerServiceConfig {
+            deterministic_nonces: true,
+            ..Default::default()
+        });
+
+    for _ in 0..4 {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(10_000), &factories.commitment);
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+
+    let fee_per_gram = MicroTari::from(25);
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(
+            MicroTari::from(2_000),
+            fee_per_gram,
+            None,
+            "".to_string(),
+            script!(Nop),
+        ))
+        .unwrap();
+    let sent = runtime.block_on(complete_transaction(stp, oms.clone()));
+    let (_, _, to_self) = runtime
+        .block_on(oms.create_pay_to_self_transaction(MicroTari::from(2_000), fee_per_gram, None, "".to_string()))
+        .unwrap();
+    let (_, coin_split, _, _) = runtime
+        .block_on(oms.create_coin_split(MicroTari::from(1_000), 2, fee_per_gram, None))
+        .unwrap();
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_all(fee_per_gram, None, "".to_string(), script!(Nop)))
+        .unwrap();
+    let sent_all = runtime.block_on(complete_transaction(stp, oms));
+
+    let offset_key_manager =
+        KeyManager::<PrivateKey, KeyDigest>::from(CommsSecretKey::default(), "transaction_offset".to_string(), 0);
+    let offsets = vec![sent.offset, to_self.offset, coin_split.offset, sent_all.offset];
+    for offset in &offsets {
+        assert!(
+            (0..10).any(|i| offset_key_manager.derive_key(i).unwrap().k == *offset),
+            "Every send path should take its offset from the key manager"
+        );
+    }
+    for (i, offset) in offsets.iter().enumerate() {
+        assert!(
+            !offsets[i + 1..].contains(offset),
+            "An offset should never be used for two transactions"
+        );
+    }
+}
+
+#[test]
+fn coin_split_exceeding_block_weight_is_refused() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+    let (mut oms, _shutdown, _, _, _, _, _) = setup_output_manager_service(&mut runtime, backend, true);
+
+    let (_ti, uo) = make_input(&mut OsRng.clone(), 10_000_000 * uT, &factories.commitment);
+    assert!(runtime.block_on(oms.add_output(uo)).is_ok());
+
+    // 2,000 outputs alone weigh more than a block can hold
+    let err = runtime
+        .block_on(oms.create_coin_split(100.into(), 2_000, MicroTari::from(1), None))
+        .unwrap_err();
+    match err {
+        OutputManagerError::TransactionTooLarge { weight, max_weight } => assert!(weight > max_weight),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, 10_000_000 * uT);
+}
+
 #[test]
 fn handle_coinbase() {
     let mut runtime = Runtime::new().unwrap();
@@ -1848,7 +2332,9 @@ fn test_oms_key_manager_discrepancy() {
     let (connectivity_manager, _connectivity_mock) = create_connectivity_mock();
 
     let (connection, _tempdir) = get_temp_sqlite_database_connection();
-    let db = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(connection, None));
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+    let db = OutputManagerDatabase::new(backend.clone());
+    let key_manager = spawn_key_manager_service(runtime.handle(), backend, shutdown.to_signal());
 
     let master_key1 = CommsSecretKey::random(&mut OsRng);
 
@@ -1864,6 +2350,7 @@ fn test_oms_key_manager_discrepancy() {
             shutdown.to_signal(),
             basenode_service_handle.clone(),
             connectivity_manager.clone(),
+            key_manager.clone(),
             master_key1.clone(),
         ))
         .unwrap();
@@ -1883,6 +2370,7 @@ fn test_oms_key_manager_discrepancy() {
             shutdown.to_signal(),
             basenode_service_handle.clone(),
             connectivity_manager.clone(),
+            key_manager.clone(),
             master_key1,
         ))
         .expect("Should be able to make a new OMS with same master key");
@@ -1901,6 +2389,7 @@ fn test_oms_key_manager_discrepancy() {
         shutdown.to_signal(),
         basenode_service_handle,
         connectivity_manager,
+        key_manager,
         master_key2,
     ));
 
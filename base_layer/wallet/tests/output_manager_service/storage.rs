@@ -569,3 +569,71 @@ pub async fn test_no_duplicate_outputs() {
     let outputs = db.get_unspent_outputs().await.unwrap();
     assert_eq!(outputs.len(), 1);
 }
+
+#[tokio_macros::test]
+pub async fn test_spend_unconfirmed_change() {
+    let factories = CryptoFactories::default();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+    let db = OutputManagerDatabase::new(backend);
+
+    let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(1000), &factories.commitment);
+    let uo = DbUnblindedOutput::from_unblinded_output(uo, &factories).unwrap();
+    db.add_unspent_output(uo.clone()).await.unwrap();
+
+    // The parent spends the confirmed output and the child spends the parent's unconfirmed change
+    let (_ti, change) = make_input(&mut OsRng, MicroTari::from(500), &factories.commitment);
+    let change = DbUnblindedOutput::from_unblinded_output(change, &factories).unwrap();
+    let parent_tx_id = OsRng.next_u64();
+    db.encumber_outputs(parent_tx_id, vec![uo.clone()], vec![change.clone()])
+        .await
+        .unwrap();
+    db.confirm_encumbered_outputs(parent_tx_id).await.unwrap();
+
+    let (_ti, child_change) = make_input(&mut OsRng, MicroTari::from(200), &factories.commitment);
+    let child_change = DbUnblindedOutput::from_unblinded_output(child_change, &factories).unwrap();
+    let child_tx_id = OsRng.next_u64();
+    db.encumber_outputs(child_tx_id, vec![change.clone()], vec![child_change.clone()])
+        .await
+        .unwrap();
+    db.confirm_encumbered_outputs(child_tx_id).await.unwrap();
+
+    let parents = db.fetch_unconfirmed_parents().await.unwrap();
+    assert_eq!(parents.len(), 1);
+    assert_eq!(parents.get(&child_tx_id), Some(&vec![parent_tx_id]));
+
+    // Cancelling the parent cancels the child first, and the change is not returned to the unspent pool
+    let cancelled = db.cancel_pending_transaction_outputs(parent_tx_id).await.unwrap();
+    assert_eq!(cancelled, vec![child_tx_id]);
+    assert!(db.fetch_all_pending_transaction_outputs().await.unwrap().is_empty());
+    assert!(db.fetch_unconfirmed_parents().await.unwrap().is_empty());
+    let balance = db.get_balance(None).await.unwrap();
+    assert_eq!(balance.available_balance, uo.unblinded_output.value);
+    assert_eq!(balance.pending_incoming_balance, MicroTari(0));
+    assert_eq!(balance.pending_outgoing_balance, MicroTari(0));
+    let err = db.cancel_pending_transaction_outputs(child_tx_id).await.unwrap_err();
+    assert!(matches!(err, OutputManagerStorageError::ValueNotFound));
+
+    // Once the parent is confirmed, its change is an ordinary output being spent by the child
+    let (_ti, change) = make_input(&mut OsRng, MicroTari::from(600), &factories.commitment);
+    let change = DbUnblindedOutput::from_unblinded_output(change, &factories).unwrap();
+    let parent_tx_id = OsRng.next_u64();
+    db.encumber_outputs(parent_tx_id, vec![uo.clone()], vec![change.clone()])
+        .await
+        .unwrap();
+    db.confirm_encumbered_outputs(parent_tx_id).await.unwrap();
+
+    let child_tx_id = OsRng.next_u64();
+    db.encumber_outputs(child_tx_id, vec![change.clone()], vec![])
+        .await
+        .unwrap();
+    db.confirm_encumbered_outputs(child_tx_id).await.unwrap();
+
+    db.confirm_pending_transaction_outputs(parent_tx_id).await.unwrap();
+    assert!(db.fetch_unconfirmed_parents().await.unwrap().is_empty());
+
+    let cancelled = db.cancel_pending_transaction_outputs(child_tx_id).await.unwrap();
+    assert!(cancelled.is_empty());
+    let balance = db.get_balance(None).await.unwrap();
+    assert_eq!(balance.available_balance, change.unblinded_output.value);
+}
@@ -0,0 +1,231 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::support::data::get_temp_sqlite_database_connection;
+use futures::StreamExt;
+use rand::rngs::OsRng;
+use std::time::Duration;
+use tari_core::transactions::{tari_amount::MicroTari, types::PublicKey};
+use tari_crypto::keys::PublicKey as PublicKeyTrait;
+use tari_service_framework::reply_channel;
+use tari_shutdown::Shutdown;
+use tari_wallet::{
+    recurring_payment_service::{
+        config::RecurringPaymentServiceConfig,
+        error::RecurringPaymentServiceError,
+        handle::{RecurringPaymentEvent, RecurringPaymentSchedule, RecurringPaymentServiceHandle},
+        service::RecurringPaymentService,
+        storage::{
+            database::{RecurringPaymentDatabase, RecurringPaymentStatus},
+            sqlite_db::RecurringPaymentSqliteDatabase,
+        },
+    },
+    transaction_service::{
+        error::TransactionServiceError,
+        handle::{TransactionServiceHandle, TransactionServiceRequest, TransactionServiceResponse},
+    },
+};
+use tempfile::TempDir;
+use tokio::{sync::broadcast, time::timeout};
+
+type TransactionServiceRequestStream =
+    reply_channel::Receiver<TransactionServiceRequest, Result<TransactionServiceResponse, TransactionServiceError>>;
+
+fn setup_recurring_payment_service(
+    shutdown: &Shutdown,
+) -> (
+    RecurringPaymentServiceHandle,
+    TransactionServiceRequestStream,
+    RecurringPaymentDatabase<RecurringPaymentSqliteDatabase>,
+    TempDir,
+) {
+    let (connection, tempdir) = get_temp_sqlite_database_connection();
+    let db = RecurringPaymentDatabase::new(RecurringPaymentSqliteDatabase::new(connection));
+
+    let (ts_sender, ts_receiver) = reply_channel::unbounded();
+    let (ts_event_publisher, _) = broadcast::channel(20);
+    let transaction_service = TransactionServiceHandle::new(ts_sender, ts_event_publisher);
+
+    let (sender, receiver) = reply_channel::unbounded();
+    let (event_publisher, _) = broadcast::channel(20);
+    let handle = RecurringPaymentServiceHandle::new(sender, event_publisher.clone());
+
+    let config = RecurringPaymentServiceConfig {
+        check_interval: Duration::from_millis(50),
+        max_retries: 1,
+        retry_interval: Duration::from_secs(0),
+    };
+    let service = RecurringPaymentService::new(
+        config,
+        receiver,
+        db.clone(),
+        transaction_service,
+        event_publisher,
+        shutdown.to_signal(),
+    );
+    tokio::spawn(service.start());
+
+    (handle, ts_receiver, db, tempdir)
+}
+
+#[tokio_macros::test]
+async fn test_recurring_payment_lifecycle() {
+    let shutdown = Shutdown::new();
+    let (mut handle, mut ts_requests, db, _tempdir) = setup_recurring_payment_service(&shutdown);
+    let mut events = handle.get_event_stream_fused();
+    let (_, destination) = PublicKey::random_keypair(&mut OsRng);
+
+    let id = handle
+        .create_recurring_payment(
+            destination.clone(),
+            MicroTari::from(5000),
+            MicroTari::from(25),
+            "Subscription".to_string(),
+            RecurringPaymentSchedule {
+                interval: Duration::from_secs(3600),
+                max_payments: Some(1),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let (request, reply_tx) = timeout(Duration::from_secs(10), ts_requests.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .split();
+    match request {
+        TransactionServiceRequest::SendTransaction(pk, amount, _, _, _) => {
+            assert_eq!(pk, destination);
+            assert_eq!(amount, MicroTari::from(5000));
+        },
+        _ => panic!("Unexpected request"),
+    }
+    // The payment is persisted as made while it is in flight, so it cannot be sent twice
+    let in_flight = db.get_recurring_payment(id).await.unwrap();
+    assert_eq!(in_flight.payments_made, 1);
+    assert_eq!(in_flight.last_tx_id, None);
+    reply_tx
+        .send(Ok(TransactionServiceResponse::TransactionSent(99)))
+        .unwrap();
+
+    let event = timeout(Duration::from_secs(10), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(*event, RecurringPaymentEvent::PaymentSent(id, 99));
+    let event = timeout(Duration::from_secs(10), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(*event, RecurringPaymentEvent::Completed(id));
+
+    let payment = handle.get_recurring_payment(id).await.unwrap();
+    assert_eq!(payment.status, RecurringPaymentStatus::Completed);
+    assert_eq!(payment.payments_made, 1);
+    assert_eq!(payment.last_tx_id, Some(99));
+
+    let id = handle
+        .create_recurring_payment(
+            destination.clone(),
+            MicroTari::from(5000),
+            MicroTari::from(25),
+            "Subscription".to_string(),
+            RecurringPaymentSchedule {
+                interval: Duration::from_secs(3600),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    for attempt in 1..=2 {
+        let (_, reply_tx) = timeout(Duration::from_secs(10), ts_requests.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .split();
+        reply_tx
+            .send(Err(TransactionServiceError::UnexpectedApiResponse))
+            .unwrap();
+        let event = timeout(Duration::from_secs(10), events.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        if attempt == 1 {
+            assert_eq!(*event, RecurringPaymentEvent::PaymentFailed(id, 1));
+        } else {
+            assert_eq!(*event, RecurringPaymentEvent::Failed(id));
+        }
+    }
+    let payment = handle.get_recurring_payment(id).await.unwrap();
+    assert_eq!(payment.status, RecurringPaymentStatus::Failed);
+    assert_eq!(payment.payments_made, 0);
+}
+
+#[tokio_macros::test]
+async fn test_cancel_recurring_payment() {
+    let shutdown = Shutdown::new();
+    let (mut handle, _ts_requests, _db, _tempdir) = setup_recurring_payment_service(&shutdown);
+    let (_, destination) = PublicKey::random_keypair(&mut OsRng);
+
+    let result = handle
+        .create_recurring_payment(
+            destination.clone(),
+            MicroTari::from(5000),
+            MicroTari::from(25),
+            "Subscription".to_string(),
+            RecurringPaymentSchedule::default(),
+        )
+        .await;
+    assert!(matches!(result, Err(RecurringPaymentServiceError::InvalidSchedule(_))));
+
+    let id = handle
+        .create_recurring_payment(
+            destination,
+            MicroTari::from(5000),
+            MicroTari::from(25),
+            "Subscription".to_string(),
+            RecurringPaymentSchedule {
+                interval: Duration::from_secs(3600),
+                first_payment_delay: Duration::from_secs(3600),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(handle.get_recurring_payments().await.unwrap().len(), 1);
+
+    handle.cancel_recurring_payment(id).await.unwrap();
+    let payment = handle.get_recurring_payment(id).await.unwrap();
+    assert_eq!(payment.status, RecurringPaymentStatus::Cancelled);
+
+    let result = handle.cancel_recurring_payment(id).await;
+    assert!(matches!(
+        result,
+        Err(RecurringPaymentServiceError::RecurringPaymentNotActive(_))
+    ));
+}
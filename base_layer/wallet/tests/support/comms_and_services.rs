@@ -22,6 +22,8 @@
 
 use futures::Sink;
 use std::{error::Error, sync::Arc, time::Duration};
+#[cfg(feature = "chaos")]
+use tari_comms::pipeline::fault_injection::FaultInjector;
 use tari_comms::{
     message::MessageTag,
     multiaddr::Multiaddr,
@@ -30,7 +32,13 @@ use tari_comms::{
     types::CommsPublicKey,
     CommsNode,
 };
+#[cfg(feature = "simulation")]
+use tari_comms::{protocol::ProtocolExtensions, transports::SimulatedTransport};
 use tari_comms_dht::{envelope::DhtMessageHeader, Dht};
+#[cfg(feature = "chaos")]
+use tari_p2p::initialization::initialize_local_test_comms_with_fault_injection;
+#[cfg(feature = "simulation")]
+use tari_p2p::initialization::initialize_local_test_comms_with_transport;
 use tari_p2p::{
     comms_connector::{InboundDomainConnector, PeerMessage},
     domain_message::DomainMessage,
@@ -70,6 +78,66 @@ where
     (comms, dht)
 }
 
+#[cfg(feature = "chaos")]
+pub async fn setup_comms_services_with_fault_injection<TSink>(
+    node_identity: Arc<NodeIdentity>,
+    peers: Vec<Arc<NodeIdentity>>,
+    publisher: InboundDomainConnector<TSink>,
+    database_path: String,
+    fault_injector: FaultInjector,
+    shutdown_signal: ShutdownSignal,
+) -> (CommsNode, Dht)
+where
+    TSink: Sink<Arc<PeerMessage>> + Clone + Unpin + Send + Sync + 'static,
+    TSink::Error: Error + Send + Sync,
+{
+    let peers = peers.into_iter().map(|ni| ni.to_peer()).collect();
+    let (comms, dht, _) = initialize_local_test_comms_with_fault_injection(
+        node_identity,
+        publisher,
+        &database_path,
+        Duration::from_secs(0),
+        peers,
+        fault_injector,
+        shutdown_signal,
+    )
+    .await
+    .unwrap();
+
+    (comms, dht)
+}
+
+#[cfg(feature = "simulation")]
+pub async fn setup_comms_services_with_transport<TSink>(
+    node_identity: Arc<NodeIdentity>,
+    peers: Vec<Arc<NodeIdentity>>,
+    publisher: InboundDomainConnector<TSink>,
+    database_path: String,
+    transport: SimulatedTransport,
+    protocol_extensions: ProtocolExtensions,
+    shutdown_signal: ShutdownSignal,
+) -> (CommsNode, Dht)
+where
+    TSink: Sink<Arc<PeerMessage>> + Clone + Unpin + Send + Sync + 'static,
+    TSink::Error: Error + Send + Sync,
+{
+    let peers = peers.into_iter().map(|ni| ni.to_peer()).collect();
+    let (comms, dht, _) = initialize_local_test_comms_with_transport(
+        node_identity,
+        publisher,
+        &database_path,
+        Duration::from_secs(0),
+        peers,
+        transport,
+        protocol_extensions,
+        shutdown_signal,
+    )
+    .await
+    .unwrap();
+
+    (comms, dht)
+}
+
 pub fn create_dummy_message<T>(inner: T, public_key: &CommsPublicKey) -> DomainMessage<T> {
     let peer_source = Peer::new(
         public_key.clone(),
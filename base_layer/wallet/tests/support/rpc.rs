@@ -34,8 +34,16 @@ use tari_core::{
     proto::{
         base_node::{
             ChainMetadata,
+            FeePerGramEstimate,
             FetchMatchingUtxos,
+            FetchMmrProof,
+            FetchMmrProofResponse,
             FetchUtxosResponse,
+            QueryOutputMaturity,
+            QueryOutputMaturityResponse,
+            QueryUtxosByFeatures,
+            QueryUtxosByScriptHash,
+            QueryUtxosResponse,
             Signatures as SignaturesProto,
             TipInfoResponse,
             TxQueryBatchResponse as TxQueryBatchResponseProto,
@@ -88,6 +96,7 @@ pub struct BaseNodeWalletRpcMockState {
     rpc_status_error: Arc<Mutex<Option<RpcStatus>>>,
     synced: Arc<Mutex<bool>>,
     utxos: Arc<Mutex<Vec<TransactionOutput>>>,
+    fee_per_gram_estimate: Arc<Mutex<u64>>,
 }
 
 #[allow(clippy::mutex_atomic)]
@@ -108,6 +117,7 @@ impl BaseNodeWalletRpcMockState {
                 confirmations: 0,
                 is_synced: true,
                 height_of_longest_chain: 0,
+                rejection_reason: TxSubmissionRejectionReason::None,
             })),
             tip_info_response: Arc::new(Mutex::new(TipInfoResponse {
                 metadata: Some(ChainMetadata {
@@ -115,6 +125,8 @@ impl BaseNodeWalletRpcMockState {
                     best_block: Some(Vec::new()),
                     accumulated_difficulty: Vec::new(),
                     pruned_height: 0,
+                    accumulated_monero_difficulty: 0,
+                    accumulated_sha_difficulty: 0,
                 }),
                 is_synced: true,
             })),
@@ -123,6 +135,7 @@ impl BaseNodeWalletRpcMockState {
             rpc_status_error: Arc::new(Mutex::new(None)),
             synced: Arc::new(Mutex::new(true)),
             utxos: Arc::new(Mutex::new(Vec::new())),
+            fee_per_gram_estimate: Arc::new(Mutex::new(25)),
         }
     }
 
@@ -156,6 +169,11 @@ impl BaseNodeWalletRpcMockState {
         *lock = synced;
     }
 
+    pub fn set_fee_per_gram_estimate(&self, fee_per_gram: u64) {
+        let mut lock = acquire_lock!(self.fee_per_gram_estimate);
+        *lock = fee_per_gram;
+    }
+
     /// This method sets the contents of the UTXO set against which the queries will be made
     pub fn set_utxos(&self, utxos: Vec<TransactionOutput>) {
         let mut lock = acquire_lock!(self.utxos);
@@ -395,6 +413,10 @@ impl BaseNodeWalletService for BaseNodeWalletRpcMockService {
                 location: transaction_query_response.location,
                 block_hash: transaction_query_response.block_hash.clone(),
                 confirmations: transaction_query_response.confirmations,
+                block_height: transaction_query_response
+                    .height_of_longest_chain
+                    .saturating_sub(transaction_query_response.confirmations),
+                rejection_reason: transaction_query_response.rejection_reason,
             };
             responses.push(response);
         }
@@ -402,6 +424,7 @@ impl BaseNodeWalletService for BaseNodeWalletRpcMockService {
         Ok(Response::new(TxQueryBatchResponsesProto {
             responses,
             is_synced: *sync_lock,
+            height_of_longest_chain: transaction_query_response.height_of_longest_chain,
         }))
     }
 
@@ -458,6 +481,69 @@ impl BaseNodeWalletService for BaseNodeWalletRpcMockService {
 
         Ok(Response::new(tip_info_response_lock.clone()))
     }
+
+    async fn query_utxos_by_script_hash(
+        &self,
+        _request: Request<QueryUtxosByScriptHash>,
+    ) -> Result<Response<QueryUtxosResponse>, RpcStatus> {
+        Ok(Response::new(QueryUtxosResponse {
+            outputs: vec![],
+            is_synced: true,
+        }))
+    }
+
+    async fn query_utxos_by_features(
+        &self,
+        _request: Request<QueryUtxosByFeatures>,
+    ) -> Result<Response<QueryUtxosResponse>, RpcStatus> {
+        Ok(Response::new(QueryUtxosResponse {
+            outputs: vec![],
+            is_synced: true,
+        }))
+    }
+
+    async fn fetch_mmr_proof(
+        &self,
+        _request: Request<FetchMmrProof>,
+    ) -> Result<Response<FetchMmrProofResponse>, RpcStatus> {
+        Ok(Response::new(FetchMmrProofResponse {
+            header: None,
+            output_mmr_root: vec![],
+            deleted_bitmap: vec![],
+            proofs: vec![],
+            is_synced: true,
+        }))
+    }
+
+    async fn query_output_maturity(
+        &self,
+        _request: Request<QueryOutputMaturity>,
+    ) -> Result<Response<QueryOutputMaturityResponse>, RpcStatus> {
+        Ok(Response::new(QueryOutputMaturityResponse {
+            outputs: vec![],
+            tip_height: 0,
+            is_synced: true,
+        }))
+    }
+
+    async fn estimate_fee_per_gram(&self, request: Request<u64>) -> Result<Response<FeePerGramEstimate>, RpcStatus> {
+        let target_blocks = request.into_message();
+        log::info!("Estimate fee per gram call received: {} block(s)", target_blocks);
+
+        let status_lock = acquire_lock!(self.state.rpc_status_error);
+        if let Some(status) = (*status_lock).clone() {
+            return Err(status);
+        }
+
+        let fee_per_gram = *acquire_lock!(self.state.fee_per_gram_estimate);
+        Ok(Response::new(FeePerGramEstimate {
+            fee_per_gram,
+            target_blocks,
+            mempool_weight: 0,
+            num_blocks_analysed: 0,
+            is_synced: *acquire_lock!(self.state.synced),
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -537,6 +623,8 @@ mod test {
             best_block: Some(Vec::new()),
             accumulated_difficulty: Vec::new(),
             pruned_height: 0,
+            accumulated_monero_difficulty: 0,
+            accumulated_sha_difficulty: 0,
         };
         service_state.set_tip_info_response(TipInfoResponse {
             metadata: Some(chain_metadata),
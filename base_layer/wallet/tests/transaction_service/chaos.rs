@@ -0,0 +1,298 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Soak tests that check the transaction protocols still complete when messages and base node RPC requests are
+//! dropped, duplicated and delayed. Faults are decided by a seeded RNG. The seed can be set with the
+//! `TARI_CHAOS_SEED` environment variable and is printed by each test so that a failing run can be reproduced.
+
+use crate::{
+    support::{
+        comms_and_services::{get_next_memory_address, setup_comms_services_with_fault_injection},
+        utils::make_input,
+    },
+    transaction_service::transaction_protocols::{add_transaction_to_database, setup, TxProtocolTestConfig},
+};
+use futures::{FutureExt, StreamExt};
+use rand::rngs::OsRng;
+use std::{collections::HashSet, env, sync::Arc, time::Duration};
+use tari_common_types::chain_metadata::ChainMetadata;
+use tari_comms::{
+    peer_manager::{NodeIdentity, PeerFeatures},
+    pipeline::fault_injection::{FaultConfig, FaultInjector},
+    types::CommsSecretKey,
+    CommsNode,
+};
+use tari_core::{
+    base_node::proto::wallet_rpc::{TxLocation, TxQueryResponse, TxSubmissionRejectionReason, TxSubmissionResponse},
+    transactions::{tari_amount::*, types::CryptoFactories},
+};
+use tari_p2p::{comms_connector::pubsub_connector, Network};
+use tari_service_framework::{RegisterHandle, StackBuilder};
+use tari_shutdown::{Shutdown, ShutdownSignal};
+use tari_wallet::{
+    base_node_service::{config::BaseNodeServiceConfig, BaseNodeServiceInitializer},
+    key_manager_service::KeyManagerServiceInitializer,
+    output_manager_service::{
+        config::OutputManagerServiceConfig,
+        handle::OutputManagerHandle,
+        OutputManagerServiceInitializer,
+    },
+    storage::database::WalletDatabase,
+    test_utils::make_wallet_databases,
+    transaction_service::{
+        config::TransactionServiceConfig,
+        handle::{TransactionEvent, TransactionServiceHandle},
+        protocols::transaction_broadcast_protocol::TransactionBroadcastProtocol,
+        storage::models::TransactionStatus,
+        TransactionServiceInitializer,
+    },
+};
+use tempfile::{tempdir, TempDir};
+use tokio::{
+    runtime::Handle,
+    sync::broadcast,
+    task,
+    time::{delay_for, timeout},
+};
+
+const DEFAULT_CHAOS_SEED: u64 = 0x7a71_c4a0_5eed;
+
+fn chaos_seed(test_name: &str) -> u64 {
+    let seed = env::var("TARI_CHAOS_SEED")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_CHAOS_SEED);
+    println!("{}: TARI_CHAOS_SEED={}", test_name, seed);
+    seed
+}
+
+async fn setup_wallet(
+    node_identity: Arc<NodeIdentity>,
+    peers: Vec<Arc<NodeIdentity>>,
+    fault_injector: FaultInjector,
+    shutdown_signal: ShutdownSignal,
+) -> (TransactionServiceHandle, OutputManagerHandle, CommsNode, TempDir) {
+    let factories = CryptoFactories::default();
+    let temp_dir = tempdir().unwrap();
+    let database_path = temp_dir.path().to_str().unwrap().to_string();
+    let (wallet_backend, tx_backend, oms_backend, _, _, _, _) = make_wallet_databases(Some(database_path.clone()));
+
+    let (publisher, subscription_factory) = pubsub_connector(Handle::current(), 100, 20);
+    let subscription_factory = Arc::new(subscription_factory);
+    let (comms, dht) = setup_comms_services_with_fault_injection(
+        node_identity,
+        peers,
+        publisher,
+        database_path,
+        fault_injector,
+        shutdown_signal.clone(),
+    )
+    .await;
+
+    let db = WalletDatabase::new(wallet_backend);
+    db.set_chain_metadata(ChainMetadata::new(std::u64::MAX, Vec::new(), 0, 0, 0))
+        .await
+        .unwrap();
+
+    let handles = StackBuilder::new(shutdown_signal)
+        .add_initializer(RegisterHandle::new(dht))
+        .add_initializer(RegisterHandle::new(comms.connectivity()))
+        .add_initializer(KeyManagerServiceInitializer::new(oms_backend.clone()))
+        .add_initializer(OutputManagerServiceInitializer::new(
+            OutputManagerServiceConfig::default(),
+            oms_backend,
+            factories.clone(),
+            Network::Weatherwax.into(),
+            CommsSecretKey::default(),
+        ))
+        .add_initializer(TransactionServiceInitializer::new(
+            TransactionServiceConfig {
+                transaction_resend_period: Duration::from_secs(5),
+                resend_response_cooldown: Duration::from_secs(1),
+                direct_send_timeout: Duration::from_secs(5),
+                num_confirmations_required: 0,
+                ..Default::default()
+            },
+            subscription_factory,
+            tx_backend,
+            comms.node_identity(),
+            factories,
+        ))
+        .add_initializer(BaseNodeServiceInitializer::new(BaseNodeServiceConfig::default(), db))
+        .build()
+        .await
+        .expect("Service initialization failed");
+
+    (
+        handles.expect_handle::<TransactionServiceHandle>(),
+        handles.expect_handle::<OutputManagerHandle>(),
+        comms,
+        temp_dir,
+    )
+}
+
+#[tokio_macros::test]
+async fn transactions_complete_under_message_faults() {
+    const NUM_TRANSACTIONS: usize = 5;
+
+    let seed = chaos_seed("transactions_complete_under_message_faults");
+    let fault_injector = FaultInjector::new(
+        FaultConfig::none(seed)
+            .with_drop_probability(0.1)
+            .with_duplicate_probability(0.1)
+            .with_delay(0.3, Duration::from_millis(10), Duration::from_millis(500)),
+    );
+    let factories = CryptoFactories::default();
+    let shutdown = Shutdown::new();
+
+    let alice_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+    let bob_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+
+    let (mut alice_ts, mut alice_oms, _alice_comms, _alice_temp_dir) = setup_wallet(
+        alice_node_identity.clone(),
+        vec![],
+        fault_injector.derive("alice"),
+        shutdown.to_signal(),
+    )
+    .await;
+    let (bob_ts, _bob_oms, bob_comms, _bob_temp_dir) = setup_wallet(
+        bob_node_identity.clone(),
+        vec![alice_node_identity.clone()],
+        fault_injector.derive("bob"),
+        shutdown.to_signal(),
+    )
+    .await;
+    let mut bob_event_stream = bob_ts.get_event_stream_fused();
+    let _ = bob_comms
+        .connectivity()
+        .dial_peer(alice_node_identity.node_id().clone())
+        .await;
+
+    let mut tx_ids = HashSet::new();
+    for _ in 0..NUM_TRANSACTIONS {
+        let (_utxo, uo) = make_input(&mut OsRng, MicroTari(2500), &factories.commitment);
+        alice_oms.add_output(uo).await.unwrap();
+        let tx_id = alice_ts
+            .send_transaction(
+                bob_node_identity.public_key().clone(),
+                MicroTari::from(1000),
+                MicroTari::from(20),
+                "chaos".to_string(),
+            )
+            .await
+            .unwrap();
+        tx_ids.insert(tx_id);
+    }
+
+    let mut finalized = HashSet::new();
+    let mut delay = delay_for(Duration::from_secs(120)).fuse();
+    loop {
+        futures::select! {
+            event = bob_event_stream.select_next_some() => {
+                if let TransactionEvent::ReceivedFinalizedTransaction(tx_id) = &*event.unwrap() {
+                    finalized.insert(*tx_id);
+                    if finalized == tx_ids {
+                        break;
+                    }
+                }
+            },
+            () = delay => {
+                break;
+            },
+        }
+    }
+
+    let stats = fault_injector.stats();
+    println!("Faults injected: {:?}", stats);
+    assert_eq!(finalized, tx_ids, "Not all transactions were finalized (seed {})", seed);
+    for tx_id in tx_ids {
+        let tx = alice_ts.get_completed_transaction(tx_id).await.unwrap();
+        assert_eq!(tx.status, TransactionStatus::Completed);
+    }
+}
+
+#[tokio_macros::test]
+#[allow(clippy::identity_op)]
+async fn broadcast_completes_under_rpc_faults() {
+    let seed = chaos_seed("broadcast_completes_under_rpc_faults");
+    let (
+        mut resources,
+        _connectivity_mock_state,
+        _outbound_mock_state,
+        _mock_rpc_server,
+        server_node_identity,
+        rpc_service_state,
+        timeout_update_publisher,
+        _shutdown,
+        _temp_dir,
+        _transaction_event_receiver,
+    ) = setup(TxProtocolTestConfig::WithConnection).await;
+    let fault_injector = FaultInjector::new(FaultConfig::none(seed).with_drop_probability(0.3).with_delay(
+        0.3,
+        Duration::from_millis(10),
+        Duration::from_millis(500),
+    ));
+    resources.config.fault_injector = Some(fault_injector.clone());
+    let (base_node_update_publisher, _) = broadcast::channel(20);
+
+    add_transaction_to_database(1, 1 * T, true, None, resources.db.clone()).await;
+
+    rpc_service_state.set_submit_transaction_response(TxSubmissionResponse {
+        accepted: true,
+        rejection_reason: TxSubmissionRejectionReason::None,
+        is_synced: true,
+    });
+    rpc_service_state.set_transaction_query_response(TxQueryResponse {
+        location: TxLocation::Mined,
+        block_hash: None,
+        confirmations: resources.config.num_confirmations_required,
+        is_synced: true,
+        height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
+    });
+
+    let protocol = TransactionBroadcastProtocol::new(
+        1,
+        resources.clone(),
+        Duration::from_secs(1),
+        server_node_identity.public_key().clone(),
+        timeout_update_publisher.subscribe(),
+        base_node_update_publisher.subscribe(),
+    );
+    let result = timeout(Duration::from_secs(60), task::spawn(protocol.execute()))
+        .await
+        .unwrap_or_else(|_| panic!("Broadcast protocol did not complete (seed {})", seed))
+        .unwrap();
+    assert_eq!(result.unwrap(), 1);
+
+    println!("Faults injected: {:?}", fault_injector.stats());
+    let db_completed_tx = resources.db.get_completed_transaction(1).await.unwrap();
+    assert_eq!(db_completed_tx.status, TransactionStatus::MinedConfirmed);
+}
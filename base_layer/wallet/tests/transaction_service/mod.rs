@@ -20,6 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod service;
+#[cfg(feature = "simulation")]
+pub mod simulation;
 pub mod storage;
 pub mod transaction_protocols;
@@ -99,6 +99,7 @@ use tari_wallet::{
         mock_base_node_service::MockBaseNodeService,
         BaseNodeServiceInitializer,
     },
+    key_manager_service::KeyManagerServiceInitializer,
     output_manager_service::{
         config::OutputManagerServiceConfig,
         handle::OutputManagerHandle,
@@ -114,7 +115,7 @@ use tari_wallet::{
         database::{WalletBackend, WalletDatabase},
         sqlite_utilities::run_migration_and_create_sqlite_connection,
     },
-    test_utils::make_wallet_databases,
+    test_utils::{make_wallet_databases, spawn_key_manager_service},
     transaction_service::{
         config::TransactionServiceConfig,
         error::TransactionServiceError,
@@ -125,9 +126,12 @@ use tari_wallet::{
             models::{
                 CompletedTransaction,
                 InboundTransaction,
+                InvoiceDirection,
+                InvoiceStatus,
                 OutboundTransaction,
                 TransactionDirection,
                 TransactionStatus,
+                WalletTransaction,
             },
             sqlite_db::TransactionServiceSqliteDatabase,
         },
@@ -190,6 +194,7 @@ pub fn setup_transaction_service<
     let fut = StackBuilder::new(shutdown_signal)
         .add_initializer(RegisterHandle::new(dht))
         .add_initializer(RegisterHandle::new(comms.connectivity()))
+        .add_initializer(KeyManagerServiceInitializer::new(oms_backend.clone()))
         .add_initializer(OutputManagerServiceInitializer::new(
             OutputManagerServiceConfig::default(),
             oms_backend,
@@ -286,6 +291,7 @@ pub fn setup_transaction_service_no_comms_and_oms_backend<
     let (tx_finalized_sender, tx_finalized_receiver) = mpsc::channel(20);
     let (base_node_response_sender, base_node_response_receiver) = mpsc::channel(20);
     let (tx_cancelled_sender, tx_cancelled_receiver) = mpsc::channel(20);
+    let (_, payment_request_receiver) = mpsc::channel(20);
 
     let outbound_mock_state = mock_outbound_service.get_state();
     runtime.spawn(mock_outbound_service.run());
@@ -326,6 +332,7 @@ pub fn setup_transaction_service_no_comms_and_oms_backend<
     mock_base_node_service.set_default_base_node_state();
     runtime.spawn(mock_base_node_service.run());
 
+    let key_manager = spawn_key_manager_service(runtime.handle(), oms_backend.clone(), shutdown.to_signal());
     let output_manager_service = runtime
         .block_on(OutputManagerService::new(
             OutputManagerServiceConfig::default(),
@@ -336,8 +343,9 @@ pub fn setup_transaction_service_no_comms_and_oms_backend<
             factories.clone(),
             constants,
             shutdown.to_signal(),
-            basenode_service_handle,
+            basenode_service_handle.clone(),
             connectivity_manager.clone(),
+            key_manager,
             CommsSecretKey::default(),
         ))
         .unwrap();
@@ -366,7 +374,9 @@ pub fn setup_transaction_service_no_comms_and_oms_backend<
         tx_finalized_receiver,
         base_node_response_receiver,
         tx_cancelled_receiver,
+        payment_request_receiver,
         output_manager_service_handle.clone(),
+        basenode_service_handle,
         outbound_message_requester,
         connectivity_manager,
         event_publisher,
@@ -376,6 +386,7 @@ pub fn setup_transaction_service_no_comms_and_oms_backend<
             PeerFeatures::COMMUNICATION_NODE,
         )),
         factories,
+        None,
         shutdown.to_signal(),
     );
     runtime.spawn(async move { output_manager_service.start().await.unwrap() });
@@ -480,9 +491,9 @@ fn manage_single_transaction() {
     );
     let temp_dir = tempdir().unwrap();
     let database_path = temp_dir.path().to_str().unwrap().to_string();
-    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _tempdir) =
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
-    let (bob_wallet_backend, bob_backend, bob_oms_backend, _, _tempdir) =
+    let (bob_wallet_backend, bob_backend, bob_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
 
     let shutdown = Shutdown::new();
@@ -609,6 +620,164 @@ fn manage_single_transaction() {
     );
 }
 
+#[test]
+fn pay_invoice() {
+    let mut runtime = create_runtime();
+
+    let factories = CryptoFactories::default();
+    let alice_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+    let bob_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+    let base_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+
+    let temp_dir = tempdir().unwrap();
+    let database_path = temp_dir.path().to_str().unwrap().to_string();
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
+        make_wallet_databases(Some(database_path.clone()));
+    let (bob_wallet_backend, bob_backend, bob_oms_backend, _, _, _, _tempdir) =
+        make_wallet_databases(Some(database_path.clone()));
+
+    let shutdown = Shutdown::new();
+    let (mut alice_ts, mut alice_oms, _alice_comms) = setup_transaction_service(
+        &mut runtime,
+        alice_node_identity.clone(),
+        vec![],
+        factories.clone(),
+        alice_wallet_backend,
+        alice_backend,
+        alice_oms_backend,
+        database_path.clone(),
+        Duration::from_secs(0),
+        shutdown.to_signal(),
+    );
+    runtime
+        .block_on(alice_ts.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    let mut alice_event_stream = alice_ts.get_event_stream_fused();
+
+    runtime.block_on(async { delay_for(Duration::from_secs(2)).await });
+
+    let (mut bob_ts, _bob_oms, bob_comms) = setup_transaction_service(
+        &mut runtime,
+        bob_node_identity.clone(),
+        vec![alice_node_identity.clone()],
+        factories.clone(),
+        bob_wallet_backend,
+        bob_backend,
+        bob_oms_backend,
+        database_path,
+        Duration::from_secs(0),
+        shutdown.to_signal(),
+    );
+    runtime
+        .block_on(bob_ts.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    let mut bob_event_stream = bob_ts.get_event_stream_fused();
+
+    let _ = runtime.block_on(
+        bob_comms
+            .connectivity()
+            .dial_peer(alice_node_identity.node_id().clone()),
+    );
+
+    let (_utxo, uo1) = make_input(&mut OsRng, MicroTari(2500), &factories.commitment);
+    runtime.block_on(alice_oms.add_output(uo1)).unwrap();
+
+    // Bob asks Alice to pay him
+    let value = MicroTari::from(1000);
+    let invoice_id = runtime
+        .block_on(bob_ts.create_invoice(
+            alice_node_identity.public_key().clone(),
+            value,
+            Duration::from_secs(3600),
+            "Coffee".to_string(),
+        ))
+        .unwrap();
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(60)).fuse();
+        let mut received = false;
+        loop {
+            futures::select! {
+                event = alice_event_stream.select_next_some() => {
+                    if let TransactionEvent::ReceivedInvoice(id) = &*event.unwrap() {
+                        assert_eq!(*id, invoice_id);
+                        received = true;
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert!(received, "Alice did not receive the invoice");
+    });
+
+    let alice_invoices = runtime.block_on(alice_ts.get_invoices()).unwrap();
+    assert_eq!(alice_invoices.len(), 1);
+    assert_eq!(alice_invoices[0].direction, InvoiceDirection::Received);
+    assert_eq!(alice_invoices[0].amount, value);
+    assert_eq!(
+        &alice_invoices[0].counterparty_public_key,
+        bob_node_identity.public_key()
+    );
+
+    // Bob cannot pay his own invoice
+    assert!(runtime
+        .block_on(bob_ts.pay_invoice(invoice_id, MicroTari::from(20)))
+        .is_err());
+
+    let tx_id = runtime
+        .block_on(alice_ts.pay_invoice(invoice_id, MicroTari::from(20)))
+        .unwrap();
+    // An invoice can only be paid once
+    assert!(runtime
+        .block_on(alice_ts.pay_invoice(invoice_id, MicroTari::from(20)))
+        .is_err());
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(90)).fuse();
+        let mut paid = false;
+        loop {
+            futures::select! {
+                event = bob_event_stream.select_next_some() => {
+                    if let TransactionEvent::InvoicePaid(id, paid_tx_id) = &*event.unwrap() {
+                        assert_eq!(*id, invoice_id);
+                        assert_eq!(*paid_tx_id, tx_id);
+                        paid = true;
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert!(paid, "Bob's invoice was not paid");
+    });
+
+    let bob_invoices = runtime.block_on(bob_ts.get_invoices()).unwrap();
+    assert_eq!(bob_invoices[0].direction, InvoiceDirection::Issued);
+    assert_eq!(bob_invoices[0].status, InvoiceStatus::Paid);
+    assert_eq!(bob_invoices[0].tx_id, Some(tx_id));
+
+    let bob_completed_tx = runtime.block_on(bob_ts.get_completed_transaction(tx_id)).unwrap();
+    assert_eq!(bob_completed_tx.amount, value);
+    assert_eq!(bob_completed_tx.message, "Coffee");
+}
+
 #[test]
 fn single_transaction_to_self() {
     let mut runtime = create_runtime();
@@ -636,7 +805,7 @@ fn single_transaction_to_self() {
     let temp_dir = tempdir().unwrap();
     let database_path = temp_dir.path().to_str().unwrap().to_string();
 
-    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _tempdir) =
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
 
     let shutdown = Shutdown::new();
@@ -679,6 +848,7 @@ fn single_transaction_to_self() {
             .get_completed_transaction(tx_id)
             .await
             .expect("Could not find tx");
+        assert_eq!(completed_tx.direction, TransactionDirection::Internal);
 
         alice_oms
             .confirm_transaction(tx_id, vec![utxo], completed_tx.transaction.body.outputs().clone())
@@ -728,7 +898,7 @@ fn send_one_sided_transaction_to_other() {
     let temp_dir = tempdir().unwrap();
     let database_path = temp_dir.path().to_str().unwrap().to_string();
 
-    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _tempdir) =
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
 
     let shutdown = Shutdown::new();
@@ -811,6 +981,213 @@ fn send_one_sided_transaction_to_other() {
     });
 }
 
+#[test]
+fn send_one_sided_transaction_with_idempotency_key() {
+    let mut runtime = create_runtime();
+
+    let factories = CryptoFactories::default();
+    let alice_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+    let bob_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+    let base_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+
+    let temp_dir = tempdir().unwrap();
+    let database_path = temp_dir.path().to_str().unwrap().to_string();
+
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
+        make_wallet_databases(Some(database_path.clone()));
+
+    let shutdown = Shutdown::new();
+    let (mut alice_ts, mut alice_oms, _alice_comms) = setup_transaction_service(
+        &mut runtime,
+        alice_node_identity,
+        vec![],
+        factories.clone(),
+        alice_wallet_backend,
+        alice_backend,
+        alice_oms_backend,
+        database_path,
+        Duration::from_secs(0),
+        shutdown.to_signal(),
+    );
+
+    runtime.block_on(async move {
+        alice_ts
+            .set_base_node_public_key(base_node_identity.public_key().clone())
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            let (_utxo, uo) = make_input(&mut OsRng, 2500.into(), &factories.commitment);
+            alice_oms.add_output(uo).await.unwrap();
+        }
+
+        let ts = alice_ts.clone();
+        let send = move |key: &str| {
+            let mut ts = ts.clone();
+            let destination = bob_node_identity.public_key().clone();
+            let key = key.to_string();
+            async move {
+                ts.send_one_sided_transaction_with_idempotency_key(
+                    destination,
+                    1000.into(),
+                    20.into(),
+                    "Payout".to_string(),
+                    Some(key),
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let tx_id = send("payout-1").await;
+        let retried_tx_id = send("payout-1").await;
+        assert_eq!(tx_id, retried_tx_id);
+        assert_eq!(alice_ts.get_completed_transactions().await.unwrap().len(), 1);
+
+        let other_tx_id = send("payout-2").await;
+        assert_ne!(tx_id, other_tx_id);
+        assert_eq!(alice_ts.get_completed_transactions().await.unwrap().len(), 2);
+    });
+}
+
+#[test]
+fn prepare_confirm_and_abort_transaction() {
+    let mut runtime = create_runtime();
+
+    let factories = CryptoFactories::default();
+    let alice_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+    let bob_node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        get_next_memory_address(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+
+    let temp_dir = tempdir().unwrap();
+    let database_path = temp_dir.path().to_str().unwrap().to_string();
+
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
+        make_wallet_databases(Some(database_path.clone()));
+    let (bob_wallet_backend, bob_backend, bob_oms_backend, _, _, _, _tempdir) =
+        make_wallet_databases(Some(database_path.clone()));
+
+    let shutdown = Shutdown::new();
+    let (mut alice_ts, mut alice_oms, _alice_comms) = setup_transaction_service(
+        &mut runtime,
+        alice_node_identity.clone(),
+        vec![],
+        factories.clone(),
+        alice_wallet_backend,
+        alice_backend,
+        alice_oms_backend,
+        database_path.clone(),
+        Duration::from_secs(0),
+        shutdown.to_signal(),
+    );
+    let mut alice_event_stream = alice_ts.get_event_stream_fused();
+
+    // Bob has to be reachable for the confirmed transaction to be sent and stored as pending
+    let (_bob_ts, _bob_oms, bob_comms) = setup_transaction_service(
+        &mut runtime,
+        bob_node_identity.clone(),
+        vec![alice_node_identity.clone()],
+        factories.clone(),
+        bob_wallet_backend,
+        bob_backend,
+        bob_oms_backend,
+        database_path,
+        Duration::from_secs(0),
+        shutdown.to_signal(),
+    );
+    let _ = runtime.block_on(
+        bob_comms
+            .connectivity()
+            .dial_peer(alice_node_identity.node_id().clone()),
+    );
+
+    runtime.block_on(async move {
+        let (_utxo, uo) = make_input(&mut OsRng, 2500.into(), &factories.commitment);
+        alice_oms.add_output(uo.clone()).await.unwrap();
+        let bob_pk = bob_node_identity.public_key().clone();
+
+        let prepared = alice_ts
+            .prepare_transaction(bob_pk.clone(), 1000.into(), 20.into(), "Review".to_string())
+            .await
+            .unwrap();
+        assert_eq!(prepared.amount, MicroTari::from(1000));
+        assert_eq!(prepared.inputs.len(), 1);
+        assert_eq!(prepared.inputs[0].1, uo.value);
+        assert_eq!(prepared.change, uo.value - prepared.balance_impact());
+        assert_eq!(
+            alice_oms.get_balance().await.unwrap().available_balance,
+            MicroTari::from(0)
+        );
+        assert!(alice_ts.get_pending_outbound_transactions().await.unwrap().is_empty());
+
+        alice_ts.abort_prepared(prepared.prep_id).await.unwrap();
+        assert_eq!(alice_oms.get_balance().await.unwrap().available_balance, uo.value);
+        assert!(matches!(
+            alice_ts.confirm_send(prepared.prep_id).await,
+            Err(TransactionServiceError::PreparedTransactionNotFound(_))
+        ));
+
+        let prepared = alice_ts
+            .prepare_transaction(bob_pk, 1000.into(), 20.into(), "Review".to_string())
+            .await
+            .unwrap();
+        let tx_id = alice_ts.confirm_send(prepared.prep_id).await.unwrap();
+        assert_eq!(tx_id, prepared.prep_id);
+
+        let mut delay = delay_for(Duration::from_secs(60)).fuse();
+        loop {
+            futures::select! {
+                event = alice_event_stream.select_next_some() => {
+                    if let TransactionEvent::TransactionDirectSendResult(id, result) = &*event.unwrap() {
+                        assert_eq!(*id, tx_id);
+                        assert!(*result);
+                        break;
+                    }
+                },
+                () = delay => panic!("Timed out waiting for the confirmed transaction to be sent"),
+            }
+        }
+        // Bob may already have replied, in which case the transaction has moved on from pending
+        let fee = match alice_ts.get_any_transaction(tx_id).await.unwrap().unwrap() {
+            WalletTransaction::PendingOutbound(tx) => tx.fee,
+            WalletTransaction::Completed(tx) => tx.fee,
+            WalletTransaction::PendingInbound(_) => panic!("The confirmed transaction should be outbound"),
+        };
+        assert_eq!(fee, prepared.fee);
+
+        assert!(matches!(
+            alice_ts
+                .prepare_transaction(
+                    alice_node_identity.public_key().clone(),
+                    1000.into(),
+                    20.into(),
+                    "Self".to_string()
+                )
+                .await,
+            Err(TransactionServiceError::OperationNotSupported)
+        ));
+    });
+}
+
 #[test]
 fn recover_one_sided_transaction() {
     let mut runtime = create_runtime();
@@ -848,9 +1225,9 @@ fn recover_one_sided_transaction() {
     let database_path = temp_dir.path().to_str().unwrap().to_string();
     let database_path2 = temp_dir2.path().to_str().unwrap().to_string();
 
-    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _tempdir) =
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
-    let (bob_wallet_backend, bob_backend, bob_oms_backend, _, _tempdir) =
+    let (bob_wallet_backend, bob_backend, bob_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path2.clone()));
 
     let shutdown = Shutdown::new();
@@ -900,7 +1277,7 @@ fn recover_one_sided_transaction() {
     let mut alice_oms_clone = alice_oms;
     runtime.block_on(async move { alice_oms_clone.add_output(uo1).await.unwrap() });
 
-    let message = "".to_string();
+    let message = "for the coffee".to_string();
     let value = 1000.into();
     let mut alice_ts_clone = alice_ts.clone();
     let tx_id = runtime.block_on(async move {
@@ -928,7 +1305,10 @@ fn recover_one_sided_transaction() {
             .unwrap();
         // Bob should be able to claim 1 output.
         assert_eq!(1, unblinded.len());
-        assert_eq!(value, unblinded[0].value);
+        assert_eq!(value, unblinded[0].0.value);
+        // The value is encrypted to Bob, so the output is recovered without rewinding the range proof
+        assert!(!unblinded[0].0.features.encrypted_data.is_empty());
+        assert_eq!(unblinded[0].1.as_deref(), Some("for the coffee"));
 
         // Should ignore already existing outputs
         let unblinded = bob_oms.scan_outputs_for_one_sided_payments(outputs).await.unwrap();
@@ -963,7 +1343,7 @@ fn send_one_sided_transaction_to_self() {
     let temp_dir = tempdir().unwrap();
     let database_path = temp_dir.path().to_str().unwrap().to_string();
 
-    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _tempdir) =
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
 
     let shutdown = Shutdown::new();
@@ -1048,11 +1428,11 @@ fn manage_multiple_transactions() {
 
     let database_path = temp_dir.path().to_str().unwrap().to_string();
 
-    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _tempdir) =
+    let (alice_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
-    let (bob_wallet_backend, bob_backend, bob_oms_backend, _, _tempdir) =
+    let (bob_wallet_backend, bob_backend, bob_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
-    let (carol_wallet_backend, carol_backend, carol_oms_backend, _, _tempdir) =
+    let (carol_wallet_backend, carol_backend, carol_oms_backend, _, _, _, _tempdir) =
         make_wallet_databases(Some(database_path.clone()));
 
     let mut shutdown = Shutdown::new();
@@ -1681,7 +2061,7 @@ fn discovery_async_return_test() {
     );
     let mut shutdown = Shutdown::new();
 
-    let (carol_wallet_backend, carol_db, carol_oms_db, _, _temp_dir1) = make_wallet_databases(None);
+    let (carol_wallet_backend, carol_db, carol_oms_db, _, _, _, _temp_dir1) = make_wallet_databases(None);
 
     let (_carol_ts, _carol_oms, carol_comms) = setup_transaction_service(
         &mut runtime,
@@ -1696,7 +2076,7 @@ fn discovery_async_return_test() {
         shutdown.to_signal(),
     );
 
-    let (alice_wallet_backend, alice_db, alice_oms_db, _, _temp_dir2) = make_wallet_databases(None);
+    let (alice_wallet_backend, alice_db, alice_oms_db, _, _, _, _temp_dir2) = make_wallet_databases(None);
 
     let (mut alice_ts, mut alice_oms, alice_comms) = setup_transaction_service(
         &mut runtime,
@@ -1818,7 +2198,7 @@ fn discovery_async_return_test() {
 fn test_power_mode_updates() {
     let factories = CryptoFactories::default();
     let mut runtime = Runtime::new().unwrap();
-    let (_wallet_backend, tx_backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_wallet_backend, tx_backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let kernel = KernelBuilder::new()
         .with_excess(&factories.commitment.zero())
@@ -2287,7 +2667,7 @@ fn test_direct_vs_saf_send_of_tx_reply_and_finalize() {
 
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
-    let (_wallet_backend, tx_backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_wallet_backend, tx_backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -2341,7 +2721,7 @@ fn test_direct_vs_saf_send_of_tx_reply_and_finalize() {
         },
     };
     assert_eq!(tx_id, msg_tx_id);
-    let (_wallet_backend, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_wallet_backend, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     // Test sending the Reply to a receiver with Direct and then with SAF and never both
     let (_bob_ts, _, bob_outbound_service, _, mut bob_tx_sender, _, _, _, _, _shutdown, _, _, _) =
@@ -2384,7 +2764,7 @@ fn test_direct_vs_saf_send_of_tx_reply_and_finalize() {
 
     runtime.block_on(async { delay_for(Duration::from_secs(5)).await });
     assert_eq!(bob_outbound_service.call_count(), 0, "Should be no more calls");
-    let (_wallet_backend, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_wallet_backend, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (_bob2_ts, _, bob2_outbound_service, _, mut bob2_tx_sender, _, _, _, _, _shutdown, _, _, _) =
         setup_transaction_service_no_comms(
@@ -2527,7 +2907,7 @@ fn test_tx_direct_send_behaviour() {
 
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
-    let (_wallet_backend, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_wallet_backend, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -2729,8 +3109,8 @@ fn test_tx_direct_send_behaviour() {
 fn test_restarting_transaction_protocols() {
     let mut runtime = Runtime::new().unwrap();
     let factories = CryptoFactories::default();
-    let (_wallet_backend, alice_backend, alice_oms_backend, _, _temp_dir) = make_wallet_databases(None);
-    let (_, bob_backend, bob_oms_backend, _, _temp_dir2) = make_wallet_databases(None);
+    let (_wallet_backend, alice_backend, alice_oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
+    let (_, bob_backend, bob_oms_backend, _, _, _, _temp_dir2) = make_wallet_databases(None);
 
     let base_node_identity = Arc::new(NodeIdentity::random(
         &mut OsRng,
@@ -2927,7 +3307,7 @@ fn test_coinbase_transactions_rejection_same_height() {
     let factories = CryptoFactories::default();
     let mut runtime = Runtime::new().unwrap();
 
-    let (_, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -3025,7 +3405,7 @@ fn test_coinbase_monitoring_stuck_in_mempool() {
     let factories = CryptoFactories::default();
     let mut runtime = Runtime::new().unwrap();
 
-    let (_, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -3107,6 +3487,7 @@ fn test_coinbase_monitoring_stuck_in_mempool() {
         confirmations,
         is_synced: true,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     assert!(runtime.block_on(alice_ts.restart_broadcast_protocols()).is_ok());
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(4, Duration::from_secs(30))) {
@@ -3122,6 +3503,7 @@ fn test_coinbase_monitoring_stuck_in_mempool() {
         confirmations,
         is_synced: true,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(4, Duration::from_secs(30))) {
         println!("  {}", e)
@@ -3161,6 +3543,7 @@ fn test_coinbase_monitoring_stuck_in_mempool() {
         confirmations,
         is_synced: true,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(2, Duration::from_secs(30))) {
         println!("  {}", e)
@@ -3194,7 +3577,7 @@ fn test_coinbase_monitoring_with_base_node_change_and_mined() {
     let factories = CryptoFactories::default();
     let mut runtime = Runtime::new().unwrap();
 
-    let (_, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -3277,6 +3660,7 @@ fn test_coinbase_monitoring_with_base_node_change_and_mined() {
         confirmations,
         is_synced: true,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     assert!(runtime.block_on(alice_ts.restart_broadcast_protocols()).is_ok());
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(4, Duration::from_secs(30))) {
@@ -3292,6 +3676,7 @@ fn test_coinbase_monitoring_with_base_node_change_and_mined() {
         confirmations,
         is_synced: true,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(2, Duration::from_secs(30))) {
         println!("  {}", e)
@@ -3342,6 +3727,7 @@ fn test_coinbase_monitoring_with_base_node_change_and_mined() {
         confirmations,
         is_synced: true,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     runtime
         .block_on(alice_ts.set_base_node_public_key(new_server_node_identity.public_key().clone()))
@@ -3359,6 +3745,7 @@ fn test_coinbase_monitoring_with_base_node_change_and_mined() {
         confirmations,
         is_synced: true,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(2, Duration::from_secs(30))) {
         println!("  {}", e)
@@ -3392,7 +3779,7 @@ fn test_coinbase_monitoring_mined_not_synced() {
     let factories = CryptoFactories::default();
     let mut runtime = Runtime::new().unwrap();
 
-    let (_, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -3475,6 +3862,7 @@ fn test_coinbase_monitoring_mined_not_synced() {
         confirmations,
         is_synced: false,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     assert!(runtime.block_on(alice_ts.restart_broadcast_protocols()).is_ok());
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(1, Duration::from_secs(30))) {
@@ -3490,6 +3878,7 @@ fn test_coinbase_monitoring_mined_not_synced() {
         confirmations,
         is_synced: false,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(1, Duration::from_secs(30))) {
         println!("  {}", e)
@@ -3529,6 +3918,7 @@ fn test_coinbase_monitoring_mined_not_synced() {
         confirmations,
         is_synced: false,
         height_of_longest_chain,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     if let Err(e) = runtime.block_on(rpc_service_state.wait_pop_transaction_query_calls(4, Duration::from_secs(30))) {
         println!("  {}", e)
@@ -3561,7 +3951,7 @@ fn test_coinbase_monitoring_mined_not_synced() {
 fn test_coinbase_transaction_reused_for_same_height() {
     let factories = CryptoFactories::default();
     let mut runtime = Runtime::new().unwrap();
-    let (_, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (mut tx_service, mut output_service, _, _, _, _, _, _, _, _shutdown, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories, backend, oms_backend, None);
@@ -3647,7 +4037,7 @@ fn test_transaction_resending() {
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
     // Setup Alice wallet with no comms stack
-    let (_, alice_backend, alice_oms_backend, _, _tempdir) = make_wallet_databases(None);
+    let (_, alice_backend, alice_oms_backend, _, _, _, _tempdir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -3708,7 +4098,7 @@ fn test_transaction_resending() {
     }
 
     // Setup Bob's wallet with no comms stack
-    let (_, bob_backend, bob_oms_backend, _, _tempdir) = make_wallet_databases(None);
+    let (_, bob_backend, bob_oms_backend, _, _, _, _tempdir) = make_wallet_databases(None);
 
     let (
         _bob_ts,
@@ -3833,6 +4223,102 @@ fn test_transaction_resending() {
     assert_eq!(alice_finalize_message.tx_id, tx_id);
 }
 
+#[test]
+fn test_reply_that_fails_to_finalize_cancels_transaction() {
+    // Once Alice has signed with the transaction's nonce, the pending transaction must not be signed again for a
+    // different reply
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let bob_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+    let (_, alice_backend, alice_oms_backend, _, _, _, _tempdir) = make_wallet_databases(None);
+
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        _,
+        _alice_tx_sender,
+        mut alice_tx_reply_sender,
+        _,
+        _,
+        _,
+        _shutdown,
+        _,
+        _,
+        _,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), alice_backend, alice_oms_backend, None);
+    let mut alice_event_stream = alice_ts.get_event_stream_fused();
+
+    let (_utxo, uo) = make_input(&mut OsRng, 250000 * uT, &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+    let tx_id = runtime
+        .block_on(alice_ts.send_transaction(
+            bob_node_identity.public_key().clone(),
+            10000 * uT,
+            100 * uT,
+            "Testing Message".to_string(),
+        ))
+        .unwrap();
+
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(30))
+        .expect("Alice call wait 1");
+    let call = alice_outbound_service.pop_call().unwrap();
+    let sender_message = try_decode_sender_message(call.1.to_vec()).unwrap();
+
+    // Bob's partial signature is swapped for one that was made for a different reply
+    let reply = |params: TestParams| {
+        ReceiverTransactionProtocol::new(
+            sender_message.clone(),
+            params.nonce,
+            params.spend_key,
+            OutputFeatures::default(),
+            &factories,
+        )
+        .get_signed_data()
+        .unwrap()
+        .clone()
+    };
+    let mut bad_reply = reply(TestParams::new(&mut OsRng));
+    bad_reply.partial_signature = reply(TestParams::new(&mut OsRng)).partial_signature;
+
+    runtime
+        .block_on(alice_tx_reply_sender.send(create_dummy_message(bad_reply.into(), bob_node_identity.public_key())))
+        .unwrap();
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut cancelled = false;
+        loop {
+            futures::select! {
+                event = alice_event_stream.select_next_some() => {
+                    if let TransactionEvent::TransactionCancelled(id) = &*event.unwrap() {
+                        assert_eq!(*id, tx_id);
+                        cancelled = true;
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert!(
+            cancelled,
+            "The transaction should be cancelled when it fails to finalize"
+        );
+    });
+
+    let pending = runtime.block_on(alice_ts.get_pending_outbound_transactions()).unwrap();
+    assert!(!pending.contains_key(&tx_id));
+    let cancelled = runtime
+        .block_on(alice_ts.get_cancelled_pending_outbound_transactions())
+        .unwrap();
+    assert!(cancelled.contains_key(&tx_id));
+}
+
 #[test]
 fn test_resend_on_startup() {
     // Test that messages are resent on startup if enough time has passed
@@ -3893,7 +4379,7 @@ fn test_resend_on_startup() {
         send_count: 1,
         last_send_timestamp: Some(Utc::now().naive_utc()),
     };
-    let (_, alice_backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, alice_backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
     alice_backend
         .write(WriteOperation::Insert(DbKeyValuePair::PendingOutboundTransaction(
             tx_id,
@@ -3932,7 +4418,7 @@ fn test_resend_on_startup() {
     outbound_tx.send_count = 1;
     outbound_tx.last_send_timestamp = Utc::now().naive_utc().checked_sub_signed(ChronoDuration::seconds(20));
 
-    let (_, alice_backend2, oms_backend2, _, _temp_dir2) = make_wallet_databases(None);
+    let (_, alice_backend2, oms_backend2, _, _, _, _temp_dir2) = make_wallet_databases(None);
 
     alice_backend2
         .write(WriteOperation::Insert(DbKeyValuePair::PendingOutboundTransaction(
@@ -3997,7 +4483,7 @@ fn test_resend_on_startup() {
         send_count: 0,
         last_send_timestamp: Some(Utc::now().naive_utc()),
     };
-    let (_, bob_backend, bob_oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, bob_backend, bob_oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     bob_backend
         .write(WriteOperation::Insert(DbKeyValuePair::PendingInboundTransaction(
@@ -4034,7 +4520,7 @@ fn test_resend_on_startup() {
     // Now we do it again with the timestamp prior to the cooldown and see that a message is sent
     inbound_tx.send_count = 1;
     inbound_tx.last_send_timestamp = Utc::now().naive_utc().checked_sub_signed(ChronoDuration::seconds(20));
-    let (_, bob_backend2, bob_oms_backend2, _, _temp_dir2) = make_wallet_databases(None);
+    let (_, bob_backend2, bob_oms_backend2, _, _, _, _temp_dir2) = make_wallet_databases(None);
 
     bob_backend2
         .write(WriteOperation::Insert(DbKeyValuePair::PendingInboundTransaction(
@@ -4085,7 +4571,7 @@ fn test_replying_to_cancelled_tx() {
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
     // Testing if a Tx Reply is received for a Cancelled Outbound Tx that a Cancelled message is sent back:
-    let (_, alice_backend, alice_oms_backend, _, _tempdir) = make_wallet_databases(None);
+    let (_, alice_backend, alice_oms_backend, _, _, _, _tempdir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -4144,7 +4630,7 @@ fn test_replying_to_cancelled_tx() {
     runtime.block_on(alice_ts.cancel_transaction(tx_id)).unwrap();
 
     // Setup Bob's wallet with no comms stack
-    let (_, bob_backend, bob_oms_backend, _, _tempdir) = make_wallet_databases(None);
+    let (_, bob_backend, bob_oms_backend, _, _, _, _tempdir) = make_wallet_databases(None);
 
     let (
         _bob_ts,
@@ -4217,7 +4703,7 @@ fn test_transaction_timeout_cancellation() {
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
     // Testing if a Tx Reply is received for a Cancelled Outbound Tx that a Cancelled message is sent back:
-    let (_, alice_backend, alice_oms_backend, _, _tempdir) = make_wallet_databases(None);
+    let (_, alice_backend, alice_oms_backend, _, _, _, _tempdir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -4345,7 +4831,7 @@ fn test_transaction_timeout_cancellation() {
         send_count: 1,
         last_send_timestamp: Some(Utc::now().naive_utc()),
     };
-    let (_, bob_backend, bob_oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, bob_backend, bob_oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     bob_backend
         .write(WriteOperation::Insert(DbKeyValuePair::PendingOutboundTransaction(
@@ -4386,7 +4872,7 @@ fn test_transaction_timeout_cancellation() {
     let call = bob_outbound_service.pop_call().unwrap();
     let bob_cancelled_message = try_decode_transaction_cancelled_message(call.1.to_vec()).unwrap();
     assert_eq!(bob_cancelled_message.tx_id, tx_id);
-    let (_, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     // Now to do this for the Receiver
     let (carol_ts, _, carol_outbound_service, _, mut carol_tx_sender, _, _, _, _, _shutdown, _, _, _) =
@@ -4460,7 +4946,7 @@ fn transaction_service_tx_broadcast() {
 
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
-    let (_, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -4483,7 +4969,7 @@ fn transaction_service_tx_broadcast() {
         .block_on(alice_ts.set_base_node_public_key(server_node_identity.public_key().clone()))
         .unwrap();
 
-    let (_, backend2, oms_backend2, _, _temp_dir2) = make_wallet_databases(None);
+    let (_, backend2, oms_backend2, _, _, _, _temp_dir2) = make_wallet_databases(None);
     let (_bob_ts, _bob_output_manager, bob_outbound_service, _, mut bob_tx_sender, _, _, _, _, _shutdown, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories.clone(), backend2, oms_backend2, None);
 
@@ -4648,6 +5134,7 @@ fn transaction_service_tx_broadcast() {
         confirmations: TransactionServiceConfig::default().num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     runtime.block_on(async {
@@ -4726,6 +5213,7 @@ fn transaction_service_tx_broadcast() {
         confirmations: TransactionServiceConfig::default().num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     runtime.block_on(async {
@@ -4762,7 +5250,7 @@ fn transaction_service_tx_broadcast() {
 fn broadcast_all_completed_transactions_on_startup() {
     let mut runtime = Runtime::new().unwrap();
     let factories = CryptoFactories::default();
-    let (_, db, oms_db, _, _temp_dir) = make_wallet_databases(None);
+    let (_, db, oms_db, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let kernel = KernelBuilder::new()
         .with_excess(&factories.commitment.zero())
@@ -4837,6 +5325,7 @@ fn broadcast_all_completed_transactions_on_startup() {
         confirmations: TransactionServiceConfig::default().num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     assert!(runtime.block_on(alice_ts.restart_broadcast_protocols()).is_err());
@@ -4895,7 +5384,7 @@ fn transaction_service_tx_broadcast_with_base_node_change() {
 
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
-    let (_, backend, oms_backend, _, _temp_dir) = make_wallet_databases(None);
+    let (_, backend, oms_backend, _, _, _, _temp_dir) = make_wallet_databases(None);
 
     let (
         mut alice_ts,
@@ -4917,7 +5406,7 @@ fn transaction_service_tx_broadcast_with_base_node_change() {
     runtime
         .block_on(alice_ts.set_base_node_public_key(server_node_identity.public_key().clone()))
         .unwrap();
-    let (_, backend2, oms_backend2, _, _temp_dir2) = make_wallet_databases(None);
+    let (_, backend2, oms_backend2, _, _, _, _temp_dir2) = make_wallet_databases(None);
 
     let (_bob_ts, _bob_output_manager, bob_outbound_service, _, mut bob_tx_sender, _, _, _, _, _shutdown, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories.clone(), backend2, oms_backend2, None);
@@ -5051,6 +5540,7 @@ fn transaction_service_tx_broadcast_with_base_node_change() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     runtime
@@ -5068,6 +5558,7 @@ fn transaction_service_tx_broadcast_with_base_node_change() {
         confirmations: TransactionServiceConfig::default().num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     runtime.block_on(async {
@@ -5307,6 +5798,7 @@ fn start_validation_protocol_then_broadcast_protocol_change_base_node() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     rpc_service_state.set_response_delay(Some(Duration::from_secs(2)));
 
@@ -5363,6 +5855,7 @@ fn start_validation_protocol_then_broadcast_protocol_change_base_node() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     new_rpc_service_state.set_response_delay(Some(Duration::from_secs(2)));
@@ -5384,6 +5877,7 @@ fn start_validation_protocol_then_broadcast_protocol_change_base_node() {
         confirmations: TransactionServiceConfig::default().num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     runtime
@@ -0,0 +1,277 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Cluster tests that run a base node and wallets on a `SimulatedNetwork`. Latency, partitions and all tokio timers
+//! use the virtual clock of the network, so long timeouts and partitions complete quickly and in the same order on
+//! every run.
+
+use crate::support::{
+    comms_and_services::setup_comms_services_with_transport,
+    rpc::{BaseNodeWalletRpcMockService, BaseNodeWalletRpcMockState},
+    utils::make_input,
+};
+use futures::{FutureExt, StreamExt};
+use rand::rngs::OsRng;
+use std::{sync::Arc, time::Duration};
+use tari_common_types::chain_metadata::ChainMetadata;
+use tari_comms::{
+    multiaddr::Multiaddr,
+    peer_manager::{NodeIdentity, PeerFeatures},
+    protocol::{rpc::RpcServer, ProtocolExtensions},
+    test_utils::transport::spawn_simulated_clock,
+    transports::{NetworkEvent, SimulatedNetwork},
+    types::CommsSecretKey,
+    CommsNode,
+};
+use tari_core::{
+    base_node::{
+        proto::wallet_rpc::{TxLocation, TxQueryResponse, TxSubmissionRejectionReason, TxSubmissionResponse},
+        rpc::BaseNodeWalletRpcServer,
+    },
+    transactions::{tari_amount::*, types::CryptoFactories},
+};
+use tari_p2p::{comms_connector::pubsub_connector, Network};
+use tari_service_framework::{RegisterHandle, StackBuilder};
+use tari_shutdown::{Shutdown, ShutdownSignal};
+use tari_wallet::{
+    base_node_service::{config::BaseNodeServiceConfig, BaseNodeServiceInitializer},
+    key_manager_service::KeyManagerServiceInitializer,
+    output_manager_service::{
+        config::OutputManagerServiceConfig,
+        handle::OutputManagerHandle,
+        OutputManagerServiceInitializer,
+    },
+    storage::database::WalletDatabase,
+    test_utils::make_wallet_databases,
+    transaction_service::{
+        config::TransactionServiceConfig,
+        handle::{TransactionEvent, TransactionServiceHandle},
+        storage::models::TransactionStatus,
+        TransactionServiceInitializer,
+    },
+};
+use tempfile::{tempdir, TempDir};
+use tokio::{runtime::Handle, time::delay_for};
+
+const BASE_NODE: &str = "base_node";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+
+fn node_identity(port: u64) -> Arc<NodeIdentity> {
+    let address: Multiaddr = format!("/memory/{}", port).parse().unwrap();
+    Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        address,
+        PeerFeatures::COMMUNICATION_NODE,
+    ))
+}
+
+async fn setup_base_node(
+    network: &SimulatedNetwork,
+    node_identity: Arc<NodeIdentity>,
+    shutdown_signal: ShutdownSignal,
+) -> (BaseNodeWalletRpcMockState, CommsNode, TempDir) {
+    let temp_dir = tempdir().unwrap();
+    let database_path = temp_dir.path().to_str().unwrap().to_string();
+    let service = BaseNodeWalletRpcMockService::new();
+    let state = service.get_state();
+    let mut protocols = ProtocolExtensions::new();
+    protocols.add(RpcServer::new().add_service(BaseNodeWalletRpcServer::new(service)));
+
+    let (publisher, _) = pubsub_connector(Handle::current(), 100, 20);
+    let (comms, _) = setup_comms_services_with_transport(
+        node_identity,
+        vec![],
+        publisher,
+        database_path,
+        network.transport(BASE_NODE),
+        protocols,
+        shutdown_signal,
+    )
+    .await;
+
+    (state, comms, temp_dir)
+}
+
+async fn setup_wallet(
+    network: &SimulatedNetwork,
+    label: &str,
+    node_identity: Arc<NodeIdentity>,
+    peers: Vec<Arc<NodeIdentity>>,
+    shutdown_signal: ShutdownSignal,
+) -> (TransactionServiceHandle, OutputManagerHandle, CommsNode, TempDir) {
+    let factories = CryptoFactories::default();
+    let temp_dir = tempdir().unwrap();
+    let database_path = temp_dir.path().to_str().unwrap().to_string();
+    let (wallet_backend, tx_backend, oms_backend, _, _, _, _) = make_wallet_databases(Some(database_path.clone()));
+
+    let (publisher, subscription_factory) = pubsub_connector(Handle::current(), 100, 20);
+    let subscription_factory = Arc::new(subscription_factory);
+    let (comms, dht) = setup_comms_services_with_transport(
+        node_identity,
+        peers,
+        publisher,
+        database_path,
+        network.transport(label),
+        ProtocolExtensions::new(),
+        shutdown_signal.clone(),
+    )
+    .await;
+
+    let db = WalletDatabase::new(wallet_backend);
+    db.set_chain_metadata(ChainMetadata::new(std::u64::MAX, Vec::new(), 0, 0, 0))
+        .await
+        .unwrap();
+
+    let handles = StackBuilder::new(shutdown_signal)
+        .add_initializer(RegisterHandle::new(dht))
+        .add_initializer(RegisterHandle::new(comms.connectivity()))
+        .add_initializer(KeyManagerServiceInitializer::new(oms_backend.clone()))
+        .add_initializer(OutputManagerServiceInitializer::new(
+            OutputManagerServiceConfig::default(),
+            oms_backend,
+            factories.clone(),
+            Network::Weatherwax.into(),
+            CommsSecretKey::default(),
+        ))
+        .add_initializer(TransactionServiceInitializer::new(
+            TransactionServiceConfig {
+                broadcast_monitoring_timeout: Duration::from_secs(5),
+                direct_send_timeout: Duration::from_secs(5),
+                num_confirmations_required: 1,
+                ..Default::default()
+            },
+            subscription_factory,
+            tx_backend,
+            comms.node_identity(),
+            factories,
+        ))
+        .add_initializer(BaseNodeServiceInitializer::new(BaseNodeServiceConfig::default(), db))
+        .build()
+        .await
+        .expect("Service initialization failed");
+
+    (
+        handles.expect_handle::<TransactionServiceHandle>(),
+        handles.expect_handle::<OutputManagerHandle>(),
+        comms,
+        temp_dir,
+    )
+}
+
+#[tokio_macros::test_basic]
+async fn transaction_is_mined_after_base_node_partition_heals() {
+    const PARTITION_HEALED_AT: Duration = Duration::from_secs(60);
+
+    let factories = CryptoFactories::default();
+    let network = SimulatedNetwork::new().with_default_latency(Duration::from_millis(100));
+    let shutdown = Shutdown::new();
+
+    let base_node_identity = node_identity(1);
+    let alice_node_identity = node_identity(2);
+    let bob_node_identity = node_identity(3);
+
+    let (rpc_service_state, _base_node_comms, _base_node_temp_dir) =
+        setup_base_node(&network, base_node_identity.clone(), shutdown.to_signal()).await;
+    let (mut alice_ts, mut alice_oms, _alice_comms, _alice_temp_dir) = setup_wallet(
+        &network,
+        ALICE,
+        alice_node_identity.clone(),
+        vec![base_node_identity.clone(), bob_node_identity.clone()],
+        shutdown.to_signal(),
+    )
+    .await;
+    let (_bob_ts, _bob_oms, _bob_comms, _bob_temp_dir) = setup_wallet(
+        &network,
+        BOB,
+        bob_node_identity.clone(),
+        vec![alice_node_identity.clone()],
+        shutdown.to_signal(),
+    )
+    .await;
+
+    rpc_service_state.set_submit_transaction_response(TxSubmissionResponse {
+        accepted: true,
+        rejection_reason: TxSubmissionRejectionReason::None,
+        is_synced: true,
+    });
+    rpc_service_state.set_transaction_query_response(TxQueryResponse {
+        location: TxLocation::Mined,
+        block_hash: None,
+        confirmations: 1,
+        is_synced: true,
+        height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
+    });
+
+    // Alice cannot reach the base node until the partition heals, but can still transact with Bob
+    network.apply(NetworkEvent::Partition {
+        a: vec![ALICE.to_string()],
+        b: vec![BASE_NODE.to_string()],
+    });
+    network.schedule(PARTITION_HEALED_AT, NetworkEvent::Heal);
+    spawn_simulated_clock(network.clone(), Duration::from_millis(10));
+
+    alice_ts
+        .set_base_node_public_key(base_node_identity.public_key().clone())
+        .await
+        .unwrap();
+    let mut alice_event_stream = alice_ts.get_event_stream_fused();
+
+    let (_utxo, uo) = make_input(&mut OsRng, MicroTari(2500), &factories.commitment);
+    alice_oms.add_output(uo).await.unwrap();
+    let tx_id = alice_ts
+        .send_transaction(
+            bob_node_identity.public_key().clone(),
+            MicroTari::from(1000),
+            MicroTari::from(20),
+            "simulated".to_string(),
+        )
+        .await
+        .unwrap();
+
+    let mut mined = false;
+    let mut delay = delay_for(Duration::from_secs(300)).fuse();
+    loop {
+        futures::select! {
+            event = alice_event_stream.select_next_some() => {
+                if let TransactionEvent::TransactionMined(id) = &*event.unwrap() {
+                    if *id == tx_id {
+                        mined = true;
+                        break;
+                    }
+                }
+            },
+            () = delay => {
+                break;
+            },
+        }
+    }
+
+    assert!(mined, "Transaction was not mined");
+    assert!(network.now() >= PARTITION_HEALED_AT);
+    let submitted = rpc_service_state.take_submit_transaction_calls();
+    assert!(!submitted.is_empty());
+    let tx = alice_ts.get_completed_transaction(tx_id).await.unwrap();
+    assert_eq!(tx.status, TransactionStatus::MinedConfirmed);
+    assert_eq!(submitted[0].body, tx.transaction.body);
+}
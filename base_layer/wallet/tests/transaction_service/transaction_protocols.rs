@@ -25,7 +25,7 @@ use crate::support::{
     utils::make_input,
 };
 use chrono::Utc;
-use futures::{FutureExt, StreamExt};
+use futures::{channel::mpsc, FutureExt, StreamExt};
 use rand::rngs::OsRng;
 use std::{sync::Arc, thread::sleep, time::Duration};
 use tari_comms::{
@@ -56,6 +56,7 @@ use tari_service_framework::{reply_channel, reply_channel::Receiver};
 use tari_shutdown::Shutdown;
 use tari_test_utils::random;
 use tari_wallet::{
+    audit_log_service::{handle::AuditLogHandle, storage::database::AuditAction},
     output_manager_service::{
         error::OutputManagerError,
         handle::{OutputManagerHandle, OutputManagerRequest, OutputManagerResponse},
@@ -163,6 +164,7 @@ pub async fn setup(
             max_tx_query_batch_size: 2,
             ..TransactionServiceConfig::default()
         },
+        audit_log: None,
         shutdown_signal: shutdown.to_signal(),
     };
 
@@ -219,7 +221,7 @@ pub async fn oms_reply_channel_task(
         let (request, reply_tx) = request_context.split();
         let response = match request {
             OutputManagerRequest::ConfirmTransaction(_) => Ok(OutputManagerResponse::TransactionConfirmed),
-            OutputManagerRequest::CancelTransaction(_) => Ok(OutputManagerResponse::TransactionCancelled),
+            OutputManagerRequest::CancelTransaction(_) => Ok(OutputManagerResponse::TransactionCancelled(Vec::new())),
             _ => Err(OutputManagerError::InvalidResponseError(
                 "Unhandled request type".to_string(),
             )),
@@ -308,6 +310,7 @@ async fn tx_broadcast_protocol_submit_success_i() {
         confirmations: 1,
         is_synced: false,
         height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     // Wait for 1 query
     let _ = rpc_service_state
@@ -326,6 +329,7 @@ async fn tx_broadcast_protocol_submit_success_i() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     // Wait for 1 query
     let _ = rpc_service_state
@@ -345,6 +349,7 @@ async fn tx_broadcast_protocol_submit_success_i() {
         confirmations: resources.config.num_confirmations_required,
         is_synced: false,
         height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     let _ = rpc_service_state
@@ -373,6 +378,7 @@ async fn tx_broadcast_protocol_submit_success_i() {
         confirmations: resources.config.num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Check that the protocol ends with success
@@ -430,7 +436,7 @@ async fn tx_broadcast_protocol_submit_success_i() {
 #[allow(clippy::identity_op)]
 async fn tx_broadcast_protocol_submit_rejection() {
     let (
-        resources,
+        mut resources,
         _connectivity_mock_state,
         _outbound_mock_state,
         _mock_rpc_server,
@@ -441,6 +447,9 @@ async fn tx_broadcast_protocol_submit_rejection() {
         _temp_dir,
         _transaction_event_receiver,
     ) = setup(TxProtocolTestConfig::WithConnection).await;
+    let (audit_request_sender, _) = reply_channel::unbounded();
+    let (audit_record_sender, mut audit_record_receiver) = mpsc::unbounded();
+    resources.audit_log = Some(AuditLogHandle::new(audit_request_sender, audit_record_sender));
     let mut event_stream = resources.event_publisher.subscribe().fuse();
     let (base_node_update_publisher, _) = broadcast::channel(20);
 
@@ -494,6 +503,10 @@ async fn tx_broadcast_protocol_submit_rejection() {
     }
 
     assert!(cancelled, "Should have cancelled transaction");
+
+    // The cancellation is recorded in the audit log by the protocol itself
+    let record = audit_record_receiver.try_next().unwrap().unwrap();
+    assert_eq!(record.action, AuditAction::TransactionCancelled);
 }
 
 /// Test restarting a protocol which means the first step is a query not a submission, detecting the Tx is not in the
@@ -524,6 +537,7 @@ async fn tx_broadcast_protocol_restart_protocol_as_query() {
         confirmations: 0,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     let protocol = TransactionBroadcastProtocol::new(
@@ -551,6 +565,7 @@ async fn tx_broadcast_protocol_restart_protocol_as_query() {
         confirmations: 0,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Should receive a resummission call
@@ -572,6 +587,7 @@ async fn tx_broadcast_protocol_restart_protocol_as_query() {
         confirmations: resources.config.num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Check that the protocol ends with success
@@ -630,6 +646,7 @@ async fn tx_broadcast_protocol_submit_success_followed_by_rejection() {
         confirmations: 0,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Set Base Node to reject resubmission
@@ -684,6 +701,96 @@ async fn tx_broadcast_protocol_submit_success_followed_by_rejection() {
     assert!(cancelled, "Should have cancelled transaction");
 }
 
+/// This test will submit a tx that is accepted by the mempool and then expires from it, which must be reported to the
+/// wallet before the transaction is resubmitted
+#[tokio_macros::test]
+#[allow(clippy::identity_op)]
+async fn tx_broadcast_protocol_submit_success_followed_by_expiry() {
+    let (
+        resources,
+        _connectivity_mock_state,
+        _outbound_mock_state,
+        _mock_rpc_server,
+        server_node_identity,
+        rpc_service_state,
+        timeout_update_publisher,
+        _shutdown,
+        _temp_dir,
+        _transaction_event_receiver,
+    ) = setup(TxProtocolTestConfig::WithConnection).await;
+    let mut event_stream = resources.event_publisher.subscribe().fuse();
+    let (base_node_update_publisher, _) = broadcast::channel(20);
+
+    add_transaction_to_database(1, 1 * T, true, None, resources.db.clone()).await;
+
+    let protocol = TransactionBroadcastProtocol::new(
+        1,
+        resources.clone(),
+        Duration::from_secs(1),
+        server_node_identity.public_key().clone(),
+        timeout_update_publisher.subscribe(),
+        base_node_update_publisher.subscribe(),
+    );
+
+    let join_handle = task::spawn(protocol.execute());
+
+    let _ = rpc_service_state
+        .wait_pop_transaction_query_calls(1, Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    // Set Base Node response to be expired from the mempool
+    rpc_service_state.set_transaction_query_response(TxQueryResponse {
+        location: TxLocation::NotStored,
+        block_hash: None,
+        confirmations: 0,
+        is_synced: true,
+        height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::Expired,
+    });
+
+    // Set Base Node to reject resubmission
+    rpc_service_state.set_submit_transaction_response(TxSubmissionResponse {
+        accepted: false,
+        rejection_reason: TxSubmissionRejectionReason::DoubleSpend,
+        is_synced: true,
+    });
+
+    let _ = rpc_service_state
+        .wait_pop_submit_transaction_calls(1, Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    if let Err(e) = join_handle.await.unwrap() {
+        if let TransactionServiceError::MempoolRejectionDoubleSpend = e.error {
+        } else {
+            panic!("Tx broadcast Should have failed with mempool rejection for being a double spend");
+        }
+    } else {
+        panic!("Tx broadcast Should have failed");
+    }
+
+    let mut delay = delay_for(Duration::from_secs(1)).fuse();
+    let mut rejection_reasons = Vec::new();
+    loop {
+        futures::select! {
+            event = event_stream.select_next_some() => {
+                if let TransactionEvent::TransactionRejectedByMempool(_, reason) = &*event.unwrap() {
+                    rejection_reasons.push(reason.clone());
+                }
+            },
+            () = delay => {
+                break;
+            },
+        }
+    }
+
+    assert_eq!(rejection_reasons, vec![
+        TxSubmissionRejectionReason::Expired.to_string(),
+        TxSubmissionRejectionReason::DoubleSpend.to_string()
+    ]);
+}
+
 /// This test will submit a tx which is accepted and mined but unconfirmed, then the next query it will not exist
 /// resulting in a resubmission which we will let run to being mined with success
 #[tokio_macros::test]
@@ -740,6 +847,7 @@ async fn tx_broadcast_protocol_submit_mined_then_not_mined_resubmit_success() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     // Wait for the correct amount of queries
     if let Err(e) = rpc_service_state
@@ -779,6 +887,7 @@ async fn tx_broadcast_protocol_submit_mined_then_not_mined_resubmit_success() {
         confirmations: 0,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Should receive a resubmission call
@@ -794,6 +903,7 @@ async fn tx_broadcast_protocol_submit_mined_then_not_mined_resubmit_success() {
         confirmations: resources.config.num_confirmations_required as u64 + 1u64,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Check that the protocol ends with success
@@ -872,6 +982,7 @@ async fn tx_broadcast_protocol_connection_problem() {
         confirmations: resources.config.num_confirmations_required as u64 + 1u64,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     let result = join_handle.await.unwrap();
     assert_eq!(result.unwrap(), 1);
@@ -932,6 +1043,7 @@ async fn tx_broadcast_protocol_submit_already_mined() {
         confirmations: resources.config.num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Check that the protocol ends with success
@@ -1007,6 +1119,7 @@ async fn tx_broadcast_protocol_submit_and_base_node_gets_changed() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Change Base Node
@@ -1022,6 +1135,7 @@ async fn tx_broadcast_protocol_submit_and_base_node_gets_changed() {
         confirmations: 0,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Wait for 1 query
@@ -1037,6 +1151,7 @@ async fn tx_broadcast_protocol_submit_and_base_node_gets_changed() {
         confirmations: resources.config.num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     // Check that the protocol ends with success
@@ -1107,6 +1222,7 @@ async fn tx_validation_protocol_tx_becomes_valid() {
         confirmations: resources.config.num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     rpc_service_state.set_is_synced(false);
@@ -1181,6 +1297,7 @@ async fn tx_validation_protocol_tx_becomes_invalid() {
         confirmations: resources.config.num_confirmations_required,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     let protocol = TransactionValidationProtocol::new(
@@ -1247,6 +1364,7 @@ async fn tx_validation_protocol_tx_becomes_unconfirmed() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     let protocol = TransactionValidationProtocol::new(
@@ -1283,6 +1401,108 @@ async fn tx_validation_protocol_tx_becomes_unconfirmed() {
     }
 }
 
+/// Validate broadcast transactions alongside mined transactions in a single batch, the broadcast transactions should
+/// become mined and all mined heights and confirmations should be recorded
+#[tokio_macros::test]
+#[allow(clippy::identity_op)]
+async fn tx_validation_protocol_broadcast_tx_becomes_mined() {
+    let (
+        resources,
+        _connectivity_mock_state,
+        _outbound_mock_state,
+        _mock_rpc_server,
+        server_node_identity,
+        rpc_service_state,
+        _timeout_update_publisher,
+        _shutdown,
+        _temp_dir,
+        _transaction_event_receiver,
+    ) = setup(TxProtocolTestConfig::WithConnection).await;
+    let (base_node_update_publisher, _) = broadcast::channel(20);
+    let (_timeout_update_publisher, _) = broadcast::channel(20);
+
+    add_transaction_to_database(1, 1 * T, true, Some(TransactionStatus::Broadcast), resources.db.clone()).await;
+    add_transaction_to_database(
+        2,
+        2 * T,
+        true,
+        Some(TransactionStatus::MinedUnconfirmed),
+        resources.db.clone(),
+    )
+    .await;
+    add_transaction_to_database(3, 3 * T, true, Some(TransactionStatus::Completed), resources.db.clone()).await;
+
+    rpc_service_state.set_transaction_query_response(TxQueryResponse {
+        location: TxLocation::Mined,
+        block_hash: None,
+        confirmations: 1,
+        is_synced: true,
+        height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
+    });
+
+    let protocol = TransactionValidationProtocol::new(
+        1,
+        resources.clone(),
+        server_node_identity.public_key().clone(),
+        Duration::from_secs(1),
+        base_node_update_publisher.subscribe(),
+        _timeout_update_publisher.subscribe(),
+        ValidationRetryStrategy::UntilSuccess,
+    );
+
+    let join_handle = task::spawn(protocol.execute());
+
+    // Both the broadcast and the mined transaction are queried in the same call
+    let calls = rpc_service_state
+        .wait_pop_transaction_batch_query_calls(1, Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert_eq!(calls[0].len(), 2);
+
+    let result = join_handle.await.unwrap();
+    assert!(result.is_ok());
+
+    let db_completed_txs = resources.db.get_completed_transactions().await.unwrap();
+    for tx_id in &[1, 2] {
+        let tx = db_completed_txs.get(tx_id).unwrap();
+        assert_eq!(tx.status, TransactionStatus::MinedUnconfirmed);
+        assert_eq!(tx.confirmations, Some(1));
+        assert_eq!(tx.mined_height, Some(9));
+    }
+    // Completed transactions have not been submitted yet so are left to the broadcast protocol
+    let tx = db_completed_txs.get(&3).unwrap();
+    assert_eq!(tx.status, TransactionStatus::Completed);
+    assert_eq!(tx.mined_height, None);
+
+    // A broadcast transaction that is not mined yet is still valid
+    add_transaction_to_database(4, 4 * T, true, Some(TransactionStatus::Broadcast), resources.db.clone()).await;
+    rpc_service_state.set_transaction_query_response(TxQueryResponse {
+        location: TxLocation::NotStored,
+        block_hash: None,
+        confirmations: 0,
+        is_synced: true,
+        height_of_longest_chain: 10,
+        rejection_reason: TxSubmissionRejectionReason::None,
+    });
+
+    let protocol = TransactionValidationProtocol::new(
+        2,
+        resources.clone(),
+        server_node_identity.public_key().clone(),
+        Duration::from_secs(1),
+        base_node_update_publisher.subscribe(),
+        _timeout_update_publisher.subscribe(),
+        ValidationRetryStrategy::UntilSuccess,
+    );
+    let result = task::spawn(protocol.execute()).await.unwrap();
+    assert!(result.is_ok());
+
+    let tx = resources.db.get_completed_transaction(4).await.unwrap();
+    assert_eq!(tx.status, TransactionStatus::Broadcast);
+    assert!(tx.valid);
+}
+
 /// Test the validation protocol reacts correctly to a change in base node and redoes the full validation based on the
 /// new base node
 #[tokio_macros::test]
@@ -1365,6 +1585,7 @@ async fn tx_validation_protocol_tx_ends_on_base_node_end() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     rpc_service_state.set_response_delay(Some(Duration::from_secs(5)));
@@ -1495,6 +1716,7 @@ async fn tx_validation_protocol_rpc_client_broken_between_calls() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     rpc_service_state.set_response_delay(Some(Duration::from_secs(5)));
@@ -1583,6 +1805,7 @@ async fn tx_validation_protocol_rpc_client_broken_finite_retries() {
         confirmations: 1,
         is_synced: true,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
     rpc_service_state.set_rpc_status_error(Some(RpcStatus::bad_request("blah".to_string())));
 
@@ -1691,6 +1914,7 @@ async fn tx_validation_protocol_base_node_not_synced() {
         confirmations: resources.config.num_confirmations_required,
         is_synced: false,
         height_of_longest_chain: 0,
+        rejection_reason: TxSubmissionRejectionReason::None,
     });
 
     rpc_service_state.set_is_synced(false);
@@ -23,11 +23,6 @@
 use crate::support::{comms_and_services::get_next_memory_address, utils::make_input};
 use tari_core::transactions::transaction::OutputFeatures;
 
-use aes_gcm::{
-    aead::{generic_array::GenericArray, NewAead},
-    Aes256Gcm,
-};
-use digest::Digest;
 use futures::{FutureExt, StreamExt};
 use rand::rngs::OsRng;
 use std::{panic, path::Path, sync::Arc, time::Duration};
@@ -44,7 +39,6 @@ use tari_core::transactions::{
     types::{CryptoFactories, PrivateKey, PublicKey},
 };
 use tari_crypto::{
-    common::Blake256,
     inputs,
     keys::{PublicKey as PublicKeyTrait, SecretKey},
     script,
@@ -66,6 +60,7 @@ use tari_wallet::{
     },
     test_utils::make_wallet_databases,
     transaction_service::{config::TransactionServiceConfig, handle::TransactionEvent},
+    util::encryption::cipher_from_passphrase,
     Wallet,
     WalletConfig,
     WalletSqlite,
@@ -120,6 +115,7 @@ async fn create_wallet(
         peer_seeds: Default::default(),
         dns_seeds: Default::default(),
         dns_seeds_use_dnssec: false,
+        min_peer_minor_version: 0,
     };
 
     let sql_database_path = comms_config
@@ -128,8 +124,14 @@ async fn create_wallet(
         .join(database_name)
         .with_extension("sqlite3");
 
-    let (wallet_backend, transaction_backend, output_manager_backend, contacts_backend) =
-        initialize_sqlite_database_backends(sql_database_path, passphrase).unwrap();
+    let (
+        wallet_backend,
+        transaction_backend,
+        output_manager_backend,
+        contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
+    ) = initialize_sqlite_database_backends(sql_database_path, passphrase).unwrap();
 
     let transaction_service_config = TransactionServiceConfig {
         resend_response_cooldown: Duration::from_secs(1),
@@ -157,6 +159,8 @@ async fn create_wallet(
         transaction_backend,
         output_manager_backend,
         contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
         shutdown_signal,
         recovery_master_key,
     )
@@ -267,6 +271,7 @@ async fn test_wallet() {
         contacts.push(Contact {
             alias: random::string(8),
             public_key,
+            notes: None,
         });
 
         alice_wallet
@@ -308,11 +313,10 @@ async fn test_wallet() {
         panic!("Should not be able to instantiate encrypted wallet without cipher");
     }
 
-    let passphrase_hash = Blake256::new()
-        .chain("wrong passphrase".to_string().as_bytes())
-        .finalize();
-    let key = GenericArray::from_slice(passphrase_hash.as_slice());
-    let cipher = Aes256Gcm::new(key);
+    let salt = WalletSqliteDatabase::get_passphrase_salt(&connection)
+        .unwrap()
+        .expect("Encrypted wallet should have a passphrase salt");
+    let cipher = cipher_from_passphrase("wrong passphrase", &salt).unwrap();
     let result = WalletSqliteDatabase::new(connection.clone(), Some(cipher));
 
     if let Err(err) = result {
@@ -321,11 +325,7 @@ async fn test_wallet() {
         panic!("Should not be able to instantiate encrypted wallet without cipher");
     }
 
-    let passphrase_hash = Blake256::new()
-        .chain("It's turtles all the way down".to_string().as_bytes())
-        .finalize();
-    let key = GenericArray::from_slice(passphrase_hash.as_slice());
-    let cipher = Aes256Gcm::new(key);
+    let cipher = cipher_from_passphrase("It's turtles all the way down", &salt).unwrap();
     let db = WalletSqliteDatabase::new(connection, Some(cipher)).expect("Should be able to instantiate db with cipher");
     drop(db);
 
@@ -670,13 +670,22 @@ async fn test_import_utxo() {
         PeerFeatures::COMMUNICATION_NODE,
     );
     let temp_dir = tempdir().unwrap();
-    let (wallet_backend, tx_backend, oms_backend, contacts_backend, _temp_dir) = make_wallet_databases(None);
+    let (
+        wallet_backend,
+        tx_backend,
+        oms_backend,
+        contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
+        _temp_dir,
+    ) = make_wallet_databases(None);
     let comms_config = CommsConfig {
         network: Network::Weatherwax,
         node_identity: Arc::new(alice_identity.clone()),
         transport_type: TransportType::Tcp {
             listener_address: "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
             tor_socks_config: None,
+            outbound_socks_config: None,
         },
         auxilary_tcp_listener_address: None,
         datastore_path: temp_dir.path().to_path_buf(),
@@ -692,6 +701,7 @@ async fn test_import_utxo() {
         peer_seeds: Default::default(),
         dns_seeds: Default::default(),
         dns_seeds_use_dnssec: false,
+        min_peer_minor_version: 0,
     };
     let config = WalletConfig::new(
         comms_config,
@@ -710,6 +720,8 @@ async fn test_import_utxo() {
         tx_backend,
         oms_backend,
         contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
         shutdown.to_signal(),
         None,
     )
@@ -789,6 +801,7 @@ async fn test_data_generation() {
         peer_seeds: Default::default(),
         dns_seeds: Default::default(),
         dns_seeds_use_dnssec: false,
+        min_peer_minor_version: 0,
     };
 
     let config = WalletConfig::new(
@@ -803,7 +816,15 @@ async fn test_data_generation() {
         None,
     );
 
-    let (db, transaction_backend, oms_backend, contacts_backend, _temp_dir) = make_wallet_databases(None);
+    let (
+        db,
+        transaction_backend,
+        oms_backend,
+        contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
+        _temp_dir,
+    ) = make_wallet_databases(None);
 
     let metadata = ChainMetadata::new(std::u64::MAX, Vec::new(), 0, 0, 0);
 
@@ -816,6 +837,8 @@ async fn test_data_generation() {
         transaction_backend.clone(),
         oms_backend,
         contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
         shutdown.to_signal(),
         None,
     )
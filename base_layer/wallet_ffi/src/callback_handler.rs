@@ -47,6 +47,14 @@
 //! `callback_base_node_sync_complete` - This is called when a Base Node Sync process is completed or times out. The
 //! request_key is used to identify which request this callback references and a result of true means it was successful
 //! and false that the process timed out and new one will be started
+//!
+//! `callback_balance_updated` - This is called when the balance of the wallet changes, with the new balance, the
+//! change in the available plus pending incoming balance and the reason for the change
+//!
+//! `callback_connectivity_status` - This is called when the connection state of the wallet's base node changes
+//!
+//! `callback_sync_progress` - This is called with the current block and chain height while the wallet is scanning
+//! the blockchain for its outputs
 
 use futures::{stream::Fuse, StreamExt};
 use log::*;
@@ -54,6 +62,7 @@ use tari_comms::types::CommsPublicKey;
 use tari_comms_dht::event::{DhtEvent, DhtEventReceiver};
 use tari_shutdown::ShutdownSignal;
 use tari_wallet::{
+    base_node_service::service::OnlineState,
     output_manager_service::{
         handle::{OutputManagerEvent, OutputManagerEventReceiver},
         TxId,
@@ -66,10 +75,18 @@ use tari_wallet::{
             models::{CompletedTransaction, InboundTransaction},
         },
     },
+    wallet_events::{WalletEvent, WalletEventReceiver},
 };
 
 const LOG_TARGET: &str = "wallet::transaction_service::callback_handler";
 
+#[derive(Clone, Copy)]
+enum CallbackConnectivityStatus {
+    Connecting, // 0
+    Online,     // 1
+    Offline,    // 2
+}
+
 #[derive(Clone, Copy)]
 enum CallbackValidationResults {
     Success,           // 0
@@ -95,10 +112,14 @@ where TBackend: TransactionBackend + 'static
     callback_invalid_txo_validation_complete: unsafe extern "C" fn(u64, u8),
     callback_transaction_validation_complete: unsafe extern "C" fn(u64, u8),
     callback_saf_messages_received: unsafe extern "C" fn(),
+    callback_balance_updated: unsafe extern "C" fn(u64, u64, u64, i64, u8),
+    callback_connectivity_status: unsafe extern "C" fn(u8),
+    callback_sync_progress: unsafe extern "C" fn(u64, u64),
     db: TransactionDatabase<TBackend>,
     transaction_service_event_stream: Fuse<TransactionEventReceiver>,
     output_manager_service_event_stream: Fuse<OutputManagerEventReceiver>,
     dht_event_stream: Fuse<DhtEventReceiver>,
+    wallet_event_stream: Fuse<WalletEventReceiver>,
     shutdown_signal: Option<ShutdownSignal>,
     comms_public_key: CommsPublicKey,
 }
@@ -112,6 +133,7 @@ where TBackend: TransactionBackend + 'static
         transaction_service_event_stream: Fuse<TransactionEventReceiver>,
         output_manager_service_event_stream: Fuse<OutputManagerEventReceiver>,
         dht_event_stream: Fuse<DhtEventReceiver>,
+        wallet_event_stream: Fuse<WalletEventReceiver>,
         shutdown_signal: ShutdownSignal,
         comms_public_key: CommsPublicKey,
         callback_received_transaction: unsafe extern "C" fn(*mut InboundTransaction),
@@ -128,6 +150,9 @@ where TBackend: TransactionBackend + 'static
         callback_invalid_txo_validation_complete: unsafe extern "C" fn(TxId, u8),
         callback_transaction_validation_complete: unsafe extern "C" fn(TxId, u8),
         callback_saf_messages_received: unsafe extern "C" fn(),
+        callback_balance_updated: unsafe extern "C" fn(u64, u64, u64, i64, u8),
+        callback_connectivity_status: unsafe extern "C" fn(u8),
+        callback_sync_progress: unsafe extern "C" fn(u64, u64),
     ) -> Self {
         info!(
             target: LOG_TARGET,
@@ -185,6 +210,18 @@ where TBackend: TransactionBackend + 'static
             target: LOG_TARGET,
             "SafMessagesReceivedCallback -> Assigning Fn:  {:?}", callback_saf_messages_received
         );
+        info!(
+            target: LOG_TARGET,
+            "BalanceUpdatedCallback -> Assigning Fn:  {:?}", callback_balance_updated
+        );
+        info!(
+            target: LOG_TARGET,
+            "ConnectivityStatusCallback -> Assigning Fn:  {:?}", callback_connectivity_status
+        );
+        info!(
+            target: LOG_TARGET,
+            "SyncProgressCallback -> Assigning Fn:  {:?}", callback_sync_progress
+        );
 
         Self {
             callback_received_transaction,
@@ -201,10 +238,14 @@ where TBackend: TransactionBackend + 'static
             callback_invalid_txo_validation_complete,
             callback_transaction_validation_complete,
             callback_saf_messages_received,
+            callback_balance_updated,
+            callback_connectivity_status,
+            callback_sync_progress,
             db,
             transaction_service_event_stream,
             output_manager_service_event_stream,
             dht_event_stream,
+            wallet_event_stream,
             shutdown_signal: Some(shutdown_signal),
             comms_public_key,
         }
@@ -305,7 +346,16 @@ where TBackend: TransactionBackend + 'static
                         },
                         Err(_e) => error!(target: LOG_TARGET, "Error reading from DHT event broadcast channel"),
                     }
-                }
+                },
+                result = self.wallet_event_stream.select_next_some() => {
+                    match result {
+                        Ok(msg) => {
+                            trace!(target: LOG_TARGET, "Wallet Callback Handler event {:?}", msg);
+                            self.wallet_event(&msg);
+                        },
+                        Err(_e) => error!(target: LOG_TARGET, "Error reading from Wallet event broadcast channel"),
+                    }
+                },
                 complete => {
                     info!(target: LOG_TARGET, "Callback Handler is exiting because all tasks have completed");
                     break;
@@ -579,6 +629,48 @@ where TBackend: TransactionBackend + 'static
             (self.callback_saf_messages_received)();
         }
     }
+
+    fn wallet_event(&mut self, event: &WalletEvent) {
+        match event {
+            WalletEvent::BalanceChanged { balance, delta, reason } => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Calling Balance Updated callback function with delta {} ({:?})", delta, reason
+                );
+                unsafe {
+                    (self.callback_balance_updated)(
+                        u64::from(balance.available_balance),
+                        u64::from(balance.pending_incoming_balance),
+                        u64::from(balance.pending_outgoing_balance),
+                        *delta,
+                        *reason as u8,
+                    );
+                }
+            },
+            WalletEvent::BaseNodeConnectivityChanged(online) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Calling Connectivity Status callback function with {:?}", online
+                );
+                let status = match online {
+                    OnlineState::Connecting => CallbackConnectivityStatus::Connecting,
+                    OnlineState::Online => CallbackConnectivityStatus::Online,
+                    OnlineState::Offline => CallbackConnectivityStatus::Offline,
+                };
+                unsafe {
+                    (self.callback_connectivity_status)(status as u8);
+                }
+            },
+            WalletEvent::SyncProgress {
+                current_block,
+                chain_height,
+            } => unsafe {
+                (self.callback_sync_progress)(*current_block, *chain_height);
+            },
+            // Clients are notified of the connectivity to their base node, which is what they can act upon
+            WalletEvent::NetworkConnectivityChanged(_) => {},
+        }
+    }
 }
 
 #[cfg(test)]
@@ -603,7 +695,8 @@ mod test {
     use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
     use tari_shutdown::Shutdown;
     use tari_wallet::{
-        output_manager_service::{handle::OutputManagerEvent, TxoValidationType},
+        base_node_service::service::OnlineState,
+        output_manager_service::{handle::OutputManagerEvent, service::Balance, TxoValidationType},
         test_utils::make_wallet_databases,
         transaction_service::{
             handle::TransactionEvent,
@@ -618,6 +711,7 @@ mod test {
                 },
             },
         },
+        wallet_events::{BalanceChangeReason, WalletEvent},
     };
     use tokio::{runtime::Runtime, sync::broadcast};
 
@@ -638,6 +732,9 @@ mod test {
         pub callback_invalid_txo_validation_complete: u32,
         pub callback_transaction_validation_complete: u32,
         pub saf_messages_received: bool,
+        pub balance_updated: Option<(u64, i64, u8)>,
+        pub connectivity_status: Option<u8>,
+        pub sync_progress: Option<(u64, u64)>,
     }
 
     impl CallbackState {
@@ -659,6 +756,9 @@ mod test {
                 tx_cancellation_callback_called_inbound: false,
                 tx_cancellation_callback_called_outbound: false,
                 saf_messages_received: false,
+                balance_updated: None,
+                connectivity_status: None,
+                sync_progress: None,
             }
         }
     }
@@ -727,6 +827,30 @@ mod test {
         drop(lock);
     }
 
+    unsafe extern "C" fn balance_updated_callback(
+        available: u64,
+        _pending_incoming: u64,
+        _pending_outgoing: u64,
+        delta: i64,
+        reason: u8,
+    ) {
+        let mut lock = CALLBACK_STATE.lock().unwrap();
+        lock.balance_updated = Some((available, delta, reason));
+        drop(lock);
+    }
+
+    unsafe extern "C" fn connectivity_status_callback(status: u8) {
+        let mut lock = CALLBACK_STATE.lock().unwrap();
+        lock.connectivity_status = Some(status);
+        drop(lock);
+    }
+
+    unsafe extern "C" fn sync_progress_callback(current: u64, total: u64) {
+        let mut lock = CALLBACK_STATE.lock().unwrap();
+        lock.sync_progress = Some((current, total));
+        drop(lock);
+    }
+
     unsafe extern "C" fn tx_cancellation_callback(tx: *mut CompletedTransaction) {
         let mut lock = CALLBACK_STATE.lock().unwrap();
         match (*tx).tx_id {
@@ -776,7 +900,7 @@ mod test {
     fn test_callback_handler() {
         let mut runtime = Runtime::new().unwrap();
 
-        let (_wallet_backend, backend, _oms_backend, _, _tempdir) = make_wallet_databases(None);
+        let (_wallet_backend, backend, _oms_backend, _, _, _, _tempdir) = make_wallet_databases(None);
         let db = TransactionDatabase::new(backend);
         let rtp = ReceiverTransactionProtocol::new_placeholder();
         let inbound_tx = InboundTransaction::new(
@@ -850,6 +974,7 @@ mod test {
         let (tx_sender, tx_receiver) = broadcast::channel(20);
         let (oms_sender, oms_receiver) = broadcast::channel(20);
         let (dht_sender, dht_receiver) = broadcast::channel(20);
+        let (wallet_event_sender, wallet_event_receiver) = broadcast::channel(20);
 
         let shutdown_signal = Shutdown::new();
         let callback_handler = CallbackHandler::new(
@@ -857,6 +982,7 @@ mod test {
             tx_receiver.fuse(),
             oms_receiver.fuse(),
             dht_receiver.fuse(),
+            wallet_event_receiver.fuse(),
             shutdown_signal.to_signal(),
             PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
             received_tx_callback,
@@ -873,6 +999,9 @@ mod test {
             invalid_txo_validation_complete_callback,
             transaction_validation_complete_callback,
             saf_messages_received_callback,
+            balance_updated_callback,
+            connectivity_status_callback,
+            sync_progress_callback,
         );
 
         runtime.spawn(callback_handler.start());
@@ -1019,6 +1148,28 @@ mod test {
             .send(Arc::new(DhtEvent::StoreAndForwardMessagesReceived))
             .unwrap();
 
+        wallet_event_sender
+            .send(Arc::new(WalletEvent::BalanceChanged {
+                balance: Balance {
+                    available_balance: MicroTari::from(1000),
+                    time_locked_balance: None,
+                    pending_incoming_balance: MicroTari::from(500),
+                    pending_outgoing_balance: MicroTari::from(0),
+                },
+                delta: -250,
+                reason: BalanceChangeReason::TransactionSent,
+            }))
+            .unwrap();
+        wallet_event_sender
+            .send(Arc::new(WalletEvent::BaseNodeConnectivityChanged(OnlineState::Online)))
+            .unwrap();
+        wallet_event_sender
+            .send(Arc::new(WalletEvent::SyncProgress {
+                current_block: 10,
+                chain_height: 100,
+            }))
+            .unwrap();
+
         thread::sleep(Duration::from_secs(10));
 
         let lock = CALLBACK_STATE.lock().unwrap();
@@ -1034,6 +1185,12 @@ mod test {
         assert!(lock.tx_cancellation_callback_called_completed);
         assert!(lock.tx_cancellation_callback_called_outbound);
         assert!(lock.saf_messages_received);
+        assert_eq!(
+            lock.balance_updated,
+            Some((1000, -250, BalanceChangeReason::TransactionSent as u8))
+        );
+        assert_eq!(lock.connectivity_status, Some(1));
+        assert_eq!(lock.sync_progress, Some((10, 100)));
 
         assert_eq!(lock.callback_utxo_validation_complete, 6);
         assert_eq!(lock.callback_stxo_validation_complete, 6);
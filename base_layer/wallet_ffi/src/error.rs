@@ -20,6 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 use log::*;
+use std::{cell::RefCell, fmt};
 use tari_comms::multiaddr;
 use tari_comms_dht::store_forward::StoreAndForwardError;
 use tari_crypto::{
@@ -31,6 +32,7 @@ use tari_wallet::{
     contacts_service::error::{ContactsServiceError, ContactsServiceStorageError},
     error::{WalletError, WalletStorageError},
     output_manager_service::error::{OutputManagerError, OutputManagerStorageError},
+    recurring_payment_service::error::{RecurringPaymentServiceError, RecurringPaymentStorageError},
     transaction_service::error::{TransactionServiceError, TransactionStorageError},
 };
 use thiserror::Error;
@@ -49,41 +51,102 @@ pub enum InterfaceError {
     TokioError(String),
     #[error("Emoji ID is invalid")]
     InvalidEmojiId,
+    #[error("The supplied page cursor is invalid")]
+    InvalidCursor,
+    #[error("The supplied amount or amount format is invalid: `{0}`")]
+    InvalidAmount(String),
+    #[error("The supplied payment proof could not be decoded: `{0}`")]
+    InvalidPaymentProof(String),
 }
 
-/// This struct is meant to hold an error for use by FFI client applications. The error has an integer code and string
-/// message
+/// The component of the wallet that an error originated from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorDomain {
+    Interface,
+    Wallet,
+    OutputManager,
+    TransactionService,
+    Contacts,
+    RecurringPayment,
+    Comms,
+    Encoding,
+    Crypto,
+    Transaction,
+    KeyManager,
+}
+
+impl ErrorDomain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorDomain::Interface => "interface",
+            ErrorDomain::Wallet => "wallet",
+            ErrorDomain::OutputManager => "output_manager",
+            ErrorDomain::TransactionService => "transaction_service",
+            ErrorDomain::Contacts => "contacts",
+            ErrorDomain::RecurringPayment => "recurring_payment",
+            ErrorDomain::Comms => "comms",
+            ErrorDomain::Encoding => "encoding",
+            ErrorDomain::Crypto => "crypto",
+            ErrorDomain::Transaction => "transaction",
+            ErrorDomain::KeyManager => "key_manager",
+        }
+    }
+}
+
+impl fmt::Display for ErrorDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<LibWalletError>> = RefCell::new(None);
+}
+
+/// This struct is meant to hold an error for use by FFI client applications. The error has an integer code, the
+/// domain it originated from, a human readable message and whether the failed operation may succeed if retried.
 #[derive(Debug, Clone)]
 pub struct LibWalletError {
     pub code: i32,
+    pub domain: ErrorDomain,
     pub message: String,
+    pub is_retryable: bool,
+}
+
+impl LibWalletError {
+    /// Creates the error and records it as the last error of the calling thread, so that FFI clients can fetch the
+    /// details behind a code returned through an `error_out` parameter
+    fn new(code: i32, domain: ErrorDomain, message: String, is_retryable: bool) -> Self {
+        let err = Self {
+            code,
+            domain,
+            message,
+            is_retryable,
+        };
+        LAST_ERROR.with(|last| *last.borrow_mut() = Some(err.clone()));
+        err
+    }
+
+    /// Returns the most recent error created on the calling thread
+    pub fn last_error() -> Option<Self> {
+        LAST_ERROR.with(|last| last.borrow().clone())
+    }
 }
 
 impl From<InterfaceError> for LibWalletError {
     fn from(v: InterfaceError) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", v));
-        match v {
-            InterfaceError::NullError(_) => Self {
-                code: 1,
-                message: format!("{:?}", v),
-            },
-            InterfaceError::AllocationError => Self {
-                code: 2,
-                message: format!("{:?}", v),
-            },
-            InterfaceError::PositionInvalidError => Self {
-                code: 3,
-                message: format!("{:?}", v),
-            },
-            InterfaceError::TokioError(_) => Self {
-                code: 4,
-                message: format!("{:?}", v),
-            },
-            InterfaceError::InvalidEmojiId => Self {
-                code: 6,
-                message: format!("{:?}", v),
-            },
-        }
+        let code = match v {
+            InterfaceError::NullError(_) => 1,
+            InterfaceError::AllocationError => 2,
+            InterfaceError::PositionInvalidError => 3,
+            InterfaceError::TokioError(_) => 4,
+            InterfaceError::InvalidEmojiId => 6,
+            InterfaceError::InvalidCursor => 7,
+            InterfaceError::InvalidAmount(_) => 8,
+            InterfaceError::InvalidPaymentProof(_) => 9,
+        };
+        Self::new(code, ErrorDomain::Interface, v.to_string(), false)
     }
 }
 
@@ -92,209 +155,152 @@ impl From<InterfaceError> for LibWalletError {
 impl From<WalletError> for LibWalletError {
     fn from(w: WalletError) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", w));
-        match w {
+        let code = match w {
             // Output Manager Service Errors
-            WalletError::OutputManagerError(OutputManagerError::NotEnoughFunds) => Self {
-                code: 101,
-                message: format!("{:?}", w),
-            },
-            WalletError::OutputManagerError(OutputManagerError::FundsPending) => Self {
-                code: 115,
-                message: format!("{:?}", w),
-            },
-            WalletError::OutputManagerError(OutputManagerError::IncompleteTransaction(_)) => Self {
-                code: 102,
-                message: format!("{:?}", w),
-            },
-            WalletError::OutputManagerError(OutputManagerError::DuplicateOutput) => Self {
-                code: 103,
-                message: format!("{:?}", w),
-            },
+            WalletError::OutputManagerError(OutputManagerError::NotEnoughFunds) => 101,
+            WalletError::OutputManagerError(OutputManagerError::FundsPending) => 115,
+            WalletError::OutputManagerError(OutputManagerError::IncompleteTransaction(_)) => 102,
+            WalletError::OutputManagerError(OutputManagerError::DuplicateOutput) => 103,
             WalletError::TransactionServiceError(TransactionServiceError::TransactionStorageError(
                 TransactionStorageError::DuplicateOutput,
-            )) => Self {
-                code: 103,
-                message: format!("{:?}", w),
-            },
+            )) => 103,
             WalletError::OutputManagerError(OutputManagerError::OutputManagerStorageError(
                 OutputManagerStorageError::ValuesNotFound,
-            )) => Self {
-                code: 104,
-                message: format!("{:?}", w),
-            },
+            )) => 104,
             WalletError::OutputManagerError(OutputManagerError::OutputManagerStorageError(
                 OutputManagerStorageError::OutputAlreadySpent,
-            )) => Self {
-                code: 105,
-                message: format!("{:?}", w),
-            },
+            )) => 105,
             WalletError::OutputManagerError(OutputManagerError::OutputManagerStorageError(
                 OutputManagerStorageError::PendingTransactionNotFound,
-            )) => Self {
-                code: 106,
-                message: format!("{:?}", w),
-            },
+            )) => 106,
             WalletError::OutputManagerError(OutputManagerError::OutputManagerStorageError(
                 OutputManagerStorageError::ValueNotFound,
-            )) => Self {
-                code: 108,
-                message: format!("{:?}", w),
-            },
-            WalletError::OutputManagerError(OutputManagerError::NoBaseNodeKeysProvided) => Self {
-                code: 109,
-                message: format!("{:?}", w),
-            },
+            )) => 108,
+            WalletError::OutputManagerError(OutputManagerError::NoBaseNodeKeysProvided) => 109,
             WalletError::ContactsServiceError(ContactsServiceError::ContactsServiceStorageError(
                 ContactsServiceStorageError::ValuesNotFound,
-            )) => Self {
-                code: 110,
-                message: format!("{:?}", w),
-            },
+            )) => 110,
             WalletError::TransactionServiceError(TransactionServiceError::TransactionStorageError(
                 TransactionStorageError::ValueNotFound(_),
-            )) => Self {
-                code: 111,
-                message: format!("{:?}", w),
-            },
+            )) => 111,
             WalletError::OutputManagerError(OutputManagerError::OutputManagerStorageError(
                 OutputManagerStorageError::DuplicateOutput,
-            )) => Self {
-                code: 112,
-                message: format!("{:?}", w),
-            },
+            )) => 112,
             WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(
                 OutputManagerError::NotEnoughFunds,
-            )) => Self {
-                code: 113,
-                message: format!("{:?}", w),
-            },
-            WalletError::OutputManagerError(_) => Self {
-                code: 114,
-                message: format!("{:?}", w),
-            },
+            )) => 113,
+            WalletError::OutputManagerError(OutputManagerError::AccountAlreadyExists) => 116,
+            WalletError::OutputManagerError(OutputManagerError::AccountNotFound(_)) |
+            WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(
+                OutputManagerError::AccountNotFound(_),
+            )) => 117,
+            WalletError::OutputManagerError(OutputManagerError::InvalidAccountName) => 118,
+            WalletError::OutputManagerError(OutputManagerError::TransactionTooLarge { .. }) |
+            WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(
+                OutputManagerError::TransactionTooLarge { .. },
+            )) => 119,
+            WalletError::OutputManagerError(_) => 114,
             // Transaction Service Errors
-            WalletError::TransactionServiceError(TransactionServiceError::InvalidStateError) => Self {
-                code: 201,
-                message: format!("{:?}", w),
-            },
-            WalletError::TransactionServiceError(TransactionServiceError::TransactionProtocolError(_)) => Self {
-                code: 202,
-                message: format!("{:?}", w),
-            },
-            WalletError::TransactionServiceError(TransactionServiceError::RepeatedMessageError) => Self {
-                code: 203,
-                message: format!("{:?}", w),
-            },
-            WalletError::TransactionServiceError(TransactionServiceError::TransactionDoesNotExistError) => Self {
-                code: 204,
-                message: format!("{:?}", w),
-            },
-            WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(_)) => Self {
-                code: 206,
-                message: format!("{:?}", w),
-            },
-            WalletError::TransactionServiceError(TransactionServiceError::TransactionError(_)) => Self {
-                code: 207,
-                message: format!("{:?}", w),
-            },
-            WalletError::TransactionServiceError(TransactionServiceError::OutboundSendDiscoveryInProgress(_)) => Self {
-                code: 210,
-                message: format!("{:?}", w),
-            },
-            WalletError::TransactionServiceError(_) => Self {
-                code: 211,
-                message: format!("{:?}", w),
-            },
+            WalletError::TransactionServiceError(TransactionServiceError::InvalidStateError) => 201,
+            WalletError::TransactionServiceError(TransactionServiceError::TransactionProtocolError(_)) => 202,
+            WalletError::TransactionServiceError(TransactionServiceError::RepeatedMessageError) => 203,
+            WalletError::TransactionServiceError(TransactionServiceError::TransactionDoesNotExistError) => 204,
+            WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(_)) => 206,
+            WalletError::TransactionServiceError(TransactionServiceError::TransactionError(_)) => 207,
+            WalletError::TransactionServiceError(TransactionServiceError::OutboundSendDiscoveryInProgress(_)) => 210,
+            WalletError::TransactionServiceError(TransactionServiceError::PaymentProofError(_)) => 212,
+            WalletError::TransactionServiceError(_) => 211,
             // Comms Stack errors
-            WalletError::MultiaddrError(_) => Self {
-                code: 301,
-                message: format!("{:?}", w),
-            },
-            WalletError::StoreAndForwardError(_) => Self {
-                code: 302,
-                message: format!("{:?}", w),
-            },
-            WalletError::ContactsServiceError(ContactsServiceError::ContactNotFound) => Self {
-                code: 401,
-                message: format!("{:?}", w),
-            },
+            WalletError::MultiaddrError(_) => 301,
+            WalletError::StoreAndForwardError(_) => 302,
+            WalletError::ContactsServiceError(ContactsServiceError::ContactNotFound) => 401,
             WalletError::ContactsServiceError(ContactsServiceError::ContactsServiceStorageError(
                 ContactsServiceStorageError::OperationNotSupported,
-            )) => Self {
-                code: 403,
-                message: format!("{:?}", w),
-            },
+            )) => 403,
             WalletError::ContactsServiceError(ContactsServiceError::ContactsServiceStorageError(
                 ContactsServiceStorageError::ConversionError,
-            )) => Self {
-                code: 404,
-                message: format!("{:?}", w),
-            },
+            )) => 404,
+            WalletError::ContactsServiceError(ContactsServiceError::InvalidContactBundle(_)) => 405,
+            WalletError::ContactsServiceError(ContactsServiceError::ContactBundleEncryptionFailed) => 406,
+            WalletError::ContactsServiceError(ContactsServiceError::ContactBundleDecryptionFailed) => 407,
+            WalletError::ContactsServiceError(ContactsServiceError::ContactBundleIoError(_)) => 408,
             // Wallet Encryption Errors
-            WalletError::WalletStorageError(WalletStorageError::InvalidEncryptionCipher) => Self {
-                code: 420,
-                message: format!("{:?}", w),
-            },
-            WalletError::WalletStorageError(WalletStorageError::MissingNonce) => Self {
-                code: 421,
-                message: format!("{:?}", w),
-            },
-            WalletError::WalletStorageError(WalletStorageError::AlreadyEncrypted) => Self {
-                code: 422,
-                message: format!("{:?}", w),
-            },
-            WalletError::WalletStorageError(WalletStorageError::AeadError(_)) => Self {
-                code: 423,
-                message: format!("{:?}", w),
-            },
-            WalletError::WalletStorageError(WalletStorageError::ValuesNotFound) => Self {
-                code: 424,
-                message: format!("{:?}", w),
-            },
-            WalletError::WalletStorageError(WalletStorageError::CannotAcquireFileLock) => Self {
-                code: 425,
-                message: format!("{:?}", w),
-            },
-            WalletError::WalletStorageError(WalletStorageError::NoPasswordError) => Self {
-                code: 426,
-                message: format!("{:?}", w),
-            },
-            WalletError::UtxoScannerError(_) => Self {
-                code: 427,
-                message: format!("{:?}", w),
-            },
-            WalletError::WalletStorageError(WalletStorageError::IncorrectPassword) => Self {
-                code: 428,
-                message: format!("{:?}", w),
-            },
+            WalletError::WalletStorageError(WalletStorageError::InvalidEncryptionCipher) => 420,
+            WalletError::WalletStorageError(WalletStorageError::MissingNonce) => 421,
+            WalletError::WalletStorageError(WalletStorageError::AlreadyEncrypted) => 422,
+            WalletError::WalletStorageError(WalletStorageError::AeadError(_)) => 423,
+            WalletError::WalletStorageError(WalletStorageError::ValuesNotFound) => 424,
+            WalletError::WalletStorageError(WalletStorageError::CannotAcquireFileLock) => 425,
+            WalletError::WalletStorageError(WalletStorageError::NoPasswordError) => 426,
+            WalletError::UtxoScannerError(_) => 427,
+            WalletError::WalletStorageError(WalletStorageError::IncorrectPassword) => 428,
+            // Recurring Payment Service Errors
+            WalletError::RecurringPaymentServiceError(RecurringPaymentServiceError::RecurringPaymentNotActive(_)) => {
+                430
+            },
+            WalletError::RecurringPaymentServiceError(RecurringPaymentServiceError::InvalidSchedule(_)) => 431,
+            WalletError::RecurringPaymentServiceError(RecurringPaymentServiceError::RecurringPaymentStorageError(
+                RecurringPaymentStorageError::ValueNotFound(_),
+            )) => 432,
             // This is the catch all error code. Any error that is not explicitly mapped above will be given this code
-            _ => Self {
-                code: 999,
-                message: format!("{:?}", w),
-            },
-        }
+            _ => 999,
+        };
+        Self::new(code, wallet_error_domain(&w), w.to_string(), is_retryable(&w))
     }
 }
 
+/// The domain of a WalletError is the service it originated from, which for output manager errors raised through the
+/// transaction service is the output manager
+fn wallet_error_domain(w: &WalletError) -> ErrorDomain {
+    match w {
+        WalletError::OutputManagerError(_) |
+        WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(_)) => {
+            ErrorDomain::OutputManager
+        },
+        WalletError::TransactionServiceError(_) => ErrorDomain::TransactionService,
+        WalletError::ContactsServiceError(_) => ErrorDomain::Contacts,
+        WalletError::RecurringPaymentServiceError(_) => ErrorDomain::RecurringPayment,
+        WalletError::MultiaddrError(_) |
+        WalletError::StoreAndForwardError(_) |
+        WalletError::CommsInitializationError(_) |
+        WalletError::PeerManagerError(_) => ErrorDomain::Comms,
+        _ => ErrorDomain::Wallet,
+    }
+}
+
+/// Errors caused by a transient condition, such as pending funds, an unreachable peer or a base node that is still
+/// syncing, after which the same request may succeed
+fn is_retryable(w: &WalletError) -> bool {
+    use OutputManagerError as Oms;
+    use TransactionServiceError as Tx;
+    matches!(
+        w,
+        WalletError::OutputManagerError(Oms::FundsPending) |
+            WalletError::OutputManagerError(Oms::BaseNodeNotSynced) |
+            WalletError::OutputManagerError(Oms::RpcError(_)) |
+            WalletError::TransactionServiceError(Tx::OutputManagerError(Oms::FundsPending)) |
+            WalletError::TransactionServiceError(Tx::OutputManagerError(Oms::BaseNodeNotSynced)) |
+            WalletError::TransactionServiceError(Tx::OutboundSendFailure) |
+            WalletError::TransactionServiceError(Tx::OutboundSendDiscoveryInProgress(_)) |
+            WalletError::TransactionServiceError(Tx::DiscoveryProcessFailed(_)) |
+            WalletError::TransactionServiceError(Tx::Timeout) |
+            WalletError::TransactionServiceError(Tx::RpcError(_)) |
+            WalletError::WalletStorageError(WalletStorageError::CannotAcquireFileLock) |
+            WalletError::StoreAndForwardError(_)
+    )
+}
+
 /// This implementation maps the internal HexError to a set of LibWalletErrors.
 /// The mapping is explicitly managed here.
 impl From<HexError> for LibWalletError {
     fn from(h: HexError) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", h));
-        match h {
-            HexError::HexConversionError => Self {
-                code: 404,
-                message: format!("{:?}", h),
-            },
-            HexError::LengthError => Self {
-                code: 501,
-                message: format!("{:?}", h),
-            },
-            HexError::InvalidCharacter(_) => Self {
-                code: 503,
-                message: format!("{:?}", h),
-            },
-        }
+        let code = match h {
+            HexError::HexConversionError => 404,
+            HexError::LengthError => 501,
+            HexError::InvalidCharacter(_) => 503,
+        };
+        Self::new(code, ErrorDomain::Encoding, h.to_string(), false)
     }
 }
 
@@ -303,76 +309,47 @@ impl From<HexError> for LibWalletError {
 impl From<ByteArrayError> for LibWalletError {
     fn from(b: ByteArrayError) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", b));
-        match b {
-            ByteArrayError::ConversionError(_) => Self {
-                code: 404,
-                message: format!("{:?}", b),
-            },
-            ByteArrayError::IncorrectLength => Self {
-                code: 601,
-                message: format!("{:?}", b),
-            },
-        }
+        let code = match b {
+            ByteArrayError::ConversionError(_) => 404,
+            ByteArrayError::IncorrectLength => 601,
+        };
+        Self::new(code, ErrorDomain::Encoding, b.to_string(), false)
     }
 }
 
 impl From<multiaddr::Error> for LibWalletError {
     fn from(err: multiaddr::Error) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", err));
-        match err {
-            multiaddr::Error::ParsingError(_) => Self {
-                code: 801,
-                message: format!("{:?}", err),
-            },
-            multiaddr::Error::InvalidMultiaddr => Self {
-                code: 802,
-                message: format!("{:?}", err),
-            },
-            multiaddr::Error::DataLessThanLen => Self {
-                code: 803,
-                message: format!("{:?}", err),
-            },
-            multiaddr::Error::InvalidProtocolString => Self {
-                code: 804,
-                message: format!("{:?}", err),
-            },
-            multiaddr::Error::UnknownProtocolString(_) => Self {
-                code: 805,
-                message: format!("{:?}", err),
-            },
-            multiaddr::Error::InvalidUvar(_) => Self {
-                code: 806,
-                message: format!("{:?}", err),
-            },
-            err => Self {
-                code: 810,
-                message: format!("Multiaddr error: {:?}", err),
-            },
-        }
+        let code = match err {
+            multiaddr::Error::ParsingError(_) => 801,
+            multiaddr::Error::InvalidMultiaddr => 802,
+            multiaddr::Error::DataLessThanLen => 803,
+            multiaddr::Error::InvalidProtocolString => 804,
+            multiaddr::Error::UnknownProtocolString(_) => 805,
+            multiaddr::Error::InvalidUvar(_) => 806,
+            _ => 810,
+        };
+        Self::new(code, ErrorDomain::Comms, format!("Multiaddr error: {}", err), false)
     }
 }
 
 impl From<SchnorrSignatureError> for LibWalletError {
     fn from(err: SchnorrSignatureError) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", err));
-        match err {
-            SchnorrSignatureError::InvalidChallenge => Self {
-                code: 901,
-                message: format!("{:?}", err),
-            },
-        }
+        let code = match err {
+            SchnorrSignatureError::InvalidChallenge => 901,
+        };
+        Self::new(code, ErrorDomain::Crypto, err.to_string(), false)
     }
 }
 
 impl From<StoreAndForwardError> for LibWalletError {
     fn from(err: StoreAndForwardError) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", err));
-        Self {
-            code: 902,
-            message: format!("{:?}", err),
-        }
+        Self::new(902, ErrorDomain::Comms, err.to_string(), true)
     }
 }
+
 #[derive(Debug, Error, PartialEq)]
 pub enum TransactionError {
     #[error("The transaction has an incorrect status: `{0}`")]
@@ -386,25 +363,17 @@ pub enum TransactionError {
 impl From<TransactionError> for LibWalletError {
     fn from(v: TransactionError) -> Self {
         error!(target: LOG_TARGET, "{}", v);
-        match v {
-            TransactionError::StatusError(_) => Self {
-                code: 640,
-                message: v.to_string(),
-            },
-            TransactionError::KernelError(_) => Self {
-                code: 650,
-                message: format!("{:?}", v),
-            },
-        }
+        let code = match v {
+            TransactionError::StatusError(_) => 640,
+            TransactionError::KernelError(_) => 650,
+        };
+        Self::new(code, ErrorDomain::Transaction, v.to_string(), false)
     }
 }
 
 impl From<MnemonicError> for LibWalletError {
     fn from(err: MnemonicError) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", err));
-        Self {
-            code: 910,
-            message: format!("{:?}", err),
-        }
+        Self::new(910, ErrorDomain::KeyManager, err.to_string(), false)
     }
 }
@@ -152,6 +152,7 @@ use std::{
     ffi::{CStr, CString},
     path::PathBuf,
     slice,
+    str::FromStr,
     sync::Arc,
     time::Duration,
 };
@@ -164,7 +165,7 @@ use tari_comms::{
 };
 use tari_comms_dht::{DbConnectionUrl, DhtConfig};
 use tari_core::transactions::{
-    tari_amount::MicroTari,
+    tari_amount::{AmountFormat, AmountUnit, MicroTari},
     transaction::OutputFeatures,
     types::{ComSignature, CryptoFactories, PublicKey},
 };
@@ -172,7 +173,7 @@ use tari_crypto::{
     inputs,
     keys::{PublicKey as PublicKeyTrait, SecretKey},
     script,
-    tari_utilities::ByteArray,
+    tari_utilities::{message_format::MessageFormat, ByteArray},
 };
 use tari_p2p::{
     transport::{TorConfig, TransportType, TransportType::Tor},
@@ -181,9 +182,14 @@ use tari_p2p::{
 use tari_shutdown::Shutdown;
 use tari_utilities::{hex, hex::Hex};
 use tari_wallet::{
-    contacts_service::storage::database::Contact,
+    contacts_service::{bundle::ContactBundle, storage::database::Contact},
     error::{WalletError, WalletStorageError},
-    output_manager_service::TxoValidationType,
+    output_manager_service::{
+        error::{OutputManagerError, OutputManagerStorageError},
+        storage::database::{OutputCursor, UnspentOutputFilter},
+        TxoValidationType,
+    },
+    recurring_payment_service::handle::RecurringPaymentSchedule,
     storage::{
         database::WalletDatabase,
         sqlite_db::WalletSqliteDatabase,
@@ -201,8 +207,9 @@ use tari_wallet::{
     transaction_service::{
         config::TransactionServiceConfig,
         error::TransactionServiceError,
+        payment_proof::PaymentProof,
         storage::{
-            database::TransactionDatabase,
+            database::{CompletedTransactionFilter, TransactionDatabase},
             models::{
                 CompletedTransaction,
                 InboundTransaction,
@@ -213,8 +220,9 @@ use tari_wallet::{
         },
     },
     types::ValidationRetryStrategy,
-    util::emoji::{emoji_set, EmojiId, EmojiIdError},
+    util::emoji::{emoji_set, EmojiId},
     utxo_scanner_service::utxo_scanning::UtxoScannerService,
+    wallet_events::WalletEventMonitor,
     Wallet,
     WalletConfig,
     WalletSqlite,
@@ -238,6 +246,16 @@ pub type TariCompletedTransaction = tari_wallet::transaction_service::storage::m
 
 pub struct TariCompletedTransactions(Vec<TariCompletedTransaction>);
 
+pub type TariCompletedTransactionsPage = tari_wallet::transaction_service::storage::database::CompletedTransactionPage;
+
+pub type TariUnblindedOutput = tari_core::transactions::transaction::UnblindedOutput;
+
+pub struct TariUnblindedOutputsPage {
+    outputs: Vec<TariUnblindedOutput>,
+    total_count: u64,
+    next_cursor: Option<OutputCursor>,
+}
+
 pub type TariPendingInboundTransaction = tari_wallet::transaction_service::storage::models::InboundTransaction;
 pub type TariPendingOutboundTransaction = tari_wallet::transaction_service::storage::models::OutboundTransaction;
 
@@ -251,9 +269,13 @@ pub struct ByteVector(Vec<c_uchar>); // declared like this so that it can be exp
 #[derive(Debug, PartialEq)]
 pub struct EmojiSet(Vec<ByteVector>);
 
+pub struct TariEmojiIdSuggestions(Vec<EmojiId>);
+
 #[derive(Debug, PartialEq)]
 pub struct TariSeedWords(Vec<String>);
 
+pub type TariError = LibWalletError;
+
 pub struct TariWallet {
     wallet: WalletSqlite,
     runtime: Runtime,
@@ -281,6 +303,297 @@ pub unsafe extern "C" fn string_destroy(ptr: *mut c_char) {
 
 /// -------------------------------------------------------------------------------------------- ///
 
+/// -------------------------------- Errors ---------------------------------------------------- ///
+
+/// Gets the details of the most recent error raised by a function called on this thread. Functions report failure by
+/// setting their `error_out` parameter to a non-zero code; this returns the error behind that code. The last error is
+/// not cleared by functions that succeed, so it should only be fetched after a failure.
+///
+/// ## Arguments
+/// `()` - Does not take any arguments
+///
+/// ## Returns
+/// `*mut TariError` - Returns a pointer to a TariError, note that it returns ptr::null_mut() if no error has been
+/// raised on this thread
+///
+/// # Safety
+/// The ```error_destroy``` method must be called when finished with a TariError to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_last_error() -> *mut TariError {
+    match LibWalletError::last_error() {
+        Some(err) => Box::into_raw(Box::new(err)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Gets the code of a TariError, which is the same code that was returned through the `error_out` parameter
+///
+/// ## Arguments
+/// `err` - The pointer to a TariError
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_int` - Returns the error code, note that it will be zero if err is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn error_get_code(err: *mut TariError, error_out: *mut c_int) -> c_int {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if err.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("err".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    (*err).code as c_int
+}
+
+/// Gets the domain of a TariError, i.e. the part of the wallet it originated from. One of "interface", "wallet",
+/// "output_manager", "transaction_service", "contacts", "recurring_payment", "comms", "encoding", "crypto",
+/// "transaction" or "key_manager".
+///
+/// ## Arguments
+/// `err` - The pointer to a TariError
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns the pointer to the char array, note that it will return a pointer to an empty char array if
+/// err is null
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with string coming from rust to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn error_get_domain(err: *mut TariError, error_out: *mut c_int) -> *mut c_char {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut result = CString::new("").unwrap();
+    if err.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("err".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return result.into_raw();
+    }
+    result = CString::new((*err).domain.as_str()).unwrap();
+    result.into_raw()
+}
+
+/// Gets the human readable message of a TariError
+///
+/// ## Arguments
+/// `err` - The pointer to a TariError
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns the pointer to the char array, note that it will return a pointer to an empty char array if
+/// err is null
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with string coming from rust to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn error_get_message(err: *mut TariError, error_out: *mut c_int) -> *mut c_char {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut result = CString::new("").unwrap();
+    if err.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("err".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return result.into_raw();
+    }
+    // Messages may quote user input, which can contain interior null bytes
+    match CString::new((*err).message.replace('\0', "")) {
+        Ok(message) => result = message,
+        Err(_) => {
+            error = LibWalletError::from(InterfaceError::AllocationError).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+        },
+    }
+    result.into_raw()
+}
+
+/// Checks whether the operation that raised a TariError may succeed if it is retried later, e.g. because funds are
+/// pending or a peer could not be reached
+///
+/// ## Arguments
+/// `err` - The pointer to a TariError
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns true if the operation may be retried, note that it will be false if err is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn error_is_retryable(err: *mut TariError, error_out: *mut c_int) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if err.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("err".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    (*err).is_retryable
+}
+
+/// Frees memory for a TariError
+///
+/// ## Arguments
+/// `err` - The pointer to a TariError
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn error_destroy(err: *mut TariError) {
+    if !err.is_null() {
+        Box::from_raw(err);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
+/// -------------------------------- Amounts --------------------------------------------------- ///
+
+/// Reads an optional single character separator. A null pointer or empty string is `None`.
+unsafe fn amount_separator_from_ptr(ptr: *const c_char, name: &str) -> Result<Option<char>, InterfaceError> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    let s = CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| InterfaceError::InvalidAmount(format!("{} is not valid UTF-8", name)))?;
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (c, None) => Ok(c),
+        _ => Err(InterfaceError::InvalidAmount(format!(
+            "{} must be a single character",
+            name
+        ))),
+    }
+}
+
+unsafe fn amount_format_from_ptrs(
+    thousands_separator: *const c_char,
+    decimal_separator: *const c_char,
+) -> Result<AmountFormat, InterfaceError> {
+    let thousands_separator = amount_separator_from_ptr(thousands_separator, "thousands_separator")?;
+    let decimal_separator = amount_separator_from_ptr(decimal_separator, "decimal_separator")?.unwrap_or('.');
+    if thousands_separator == Some(decimal_separator) {
+        return Err(InterfaceError::InvalidAmount(
+            "thousands and decimal separators must differ".to_string(),
+        ));
+    }
+    Ok(AmountFormat::default().with_separators(thousands_separator, decimal_separator))
+}
+
+/// Formats an amount in MicroTari for display
+///
+/// ## Arguments
+/// `amount` - The amount in MicroTari
+/// `unit` - The unit to display the amount in: 0 for Tari, 1 for MicroTari, or 2 to use Tari for amounts of at least
+/// 1 Tari and MicroTari otherwise
+/// `precision` - The number of decimal places shown for Tari amounts (at most 6). Amounts are rounded half up.
+/// `thousands_separator` - A single character used to group thousands, or null/empty for no grouping
+/// `decimal_separator` - A single character used as the decimal point, or null/empty for "."
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns a pointer to a char array, e.g. "1,234.56 T". Note that it returns an empty char array if
+/// an error occurred
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with a string from rust to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn amount_format(
+    amount: c_ulonglong,
+    unit: c_uint,
+    precision: c_uint,
+    thousands_separator: *const c_char,
+    decimal_separator: *const c_char,
+    error_out: *mut c_int,
+) -> *mut c_char {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+
+    let unit = match unit {
+        0 => AmountUnit::Tari,
+        1 => AmountUnit::MicroTari,
+        2 => AmountUnit::Auto,
+        _ => {
+            error = LibWalletError::from(InterfaceError::InvalidAmount(format!("invalid unit {}", unit))).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return CString::into_raw(CString::new("").unwrap());
+        },
+    };
+
+    match amount_format_from_ptrs(thousands_separator, decimal_separator) {
+        Ok(format) => {
+            let format = format.with_unit(unit).with_precision(precision as usize);
+            let formatted = MicroTari::from(amount).format_with(&format);
+            CString::into_raw(CString::new(formatted).unwrap())
+        },
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            CString::into_raw(CString::new("").unwrap())
+        },
+    }
+}
+
+/// Parses a user-entered amount, e.g. "1,234.56 T", "1234560 uT" or "1234560". Amounts without a unit are taken to be
+/// in MicroTari. Amounts that cannot be represented exactly in MicroTari are rejected.
+///
+/// ## Arguments
+/// `amount` - The amount as a char array
+/// `thousands_separator` - A single character used to group thousands, or null/empty for no grouping
+/// `decimal_separator` - A single character used as the decimal point, or null/empty for "."
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the amount in MicroTari. Note that it returns 0 if an error occurred
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn amount_parse(
+    amount: *const c_char,
+    thousands_separator: *const c_char,
+    decimal_separator: *const c_char,
+    error_out: *mut c_int,
+) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if amount.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("amount".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    let result = amount_format_from_ptrs(thousands_separator, decimal_separator).and_then(|format| {
+        let amount = CStr::from_ptr(amount)
+            .to_str()
+            .map_err(|_| InterfaceError::InvalidAmount("amount is not valid UTF-8".to_string()))?;
+        MicroTari::parse_with(amount, &format).map_err(|e| InterfaceError::InvalidAmount(e.to_string()))
+    });
+    match result {
+        Ok(v) => v.as_u64(),
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
 /// -------------------------------- ByteVector ------------------------------------------------ ///
 
 /// Creates a ByteVector
@@ -651,11 +964,11 @@ pub unsafe extern "C" fn emoji_id_to_public_key(emoji: *const c_char, error_out:
 
     match CStr::from_ptr(emoji)
         .to_str()
-        .map_err(|_| EmojiIdError)
-        .and_then(EmojiId::str_to_pubkey)
+        .ok()
+        .and_then(|s| EmojiId::str_to_pubkey(s).ok())
     {
-        Ok(pk) => Box::into_raw(Box::new(pk)),
-        Err(_) => {
+        Some(pk) => Box::into_raw(Box::new(pk)),
+        None => {
             error = LibWalletError::from(InterfaceError::InvalidEmojiId).code;
             ptr::swap(error_out, &mut error as *mut c_int);
             ptr::null_mut()
@@ -663,110 +976,230 @@ pub unsafe extern "C" fn emoji_id_to_public_key(emoji: *const c_char, error_out:
     }
 }
 
-/// -------------------------------------------------------------------------------------------- ///
-
-/// -------------------------------- Private Key ----------------------------------------------- ///
-
-/// Creates a TariPrivateKey from a ByteVector
+/// Gets the valid emoji IDs that are a single substituted, missing, extra or swapped emoji away from a mistyped emoji
+/// ID, so that a typo can be caught before the emoji ID is used
 ///
 /// ## Arguments
-/// `bytes` - The pointer to a ByteVector
+/// `emoji` - The pointer to a char array in emoji format
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPrivateKey` - Returns a pointer to a TariPublicKey. Note that it returns ptr::null_mut()
-/// if bytes is null or if there was an error creating the TariPrivateKey from bytes
+/// `*mut TariEmojiIdSuggestions` - Returns a pointer to the suggestions, which are empty if the emoji ID is valid or no
+/// correction was found. Note that it returns null if emoji is null or not valid UTF-8.
 ///
 /// # Safety
-/// The ```private_key_destroy``` method must be called when finished with a TariPrivateKey to prevent a memory leak
+/// The ```emoji_id_suggestions_destroy``` method must be called when finished with a TariEmojiIdSuggestions to prevent
+/// a memory leak
 #[no_mangle]
-pub unsafe extern "C" fn private_key_create(bytes: *mut ByteVector, error_out: *mut c_int) -> *mut TariPrivateKey {
+pub unsafe extern "C" fn emoji_id_get_suggestions(
+    emoji: *const c_char,
+    error_out: *mut c_int,
+) -> *mut TariEmojiIdSuggestions {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let v;
-    if bytes.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("bytes".to_string())).code;
+    if emoji.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("emoji".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
         return ptr::null_mut();
-    } else {
-        v = (*bytes).0.clone();
     }
-    let pk = TariPrivateKey::from_bytes(&v);
-    match pk {
-        Ok(pk) => Box::into_raw(Box::new(pk)),
-        Err(e) => {
-            error = LibWalletError::from(e).code;
+
+    match CStr::from_ptr(emoji).to_str() {
+        Ok(s) => Box::into_raw(Box::new(TariEmojiIdSuggestions(EmojiId::suggest_corrections(s)))),
+        Err(_) => {
+            error = LibWalletError::from(InterfaceError::InvalidEmojiId).code;
             ptr::swap(error_out, &mut error as *mut c_int);
             ptr::null_mut()
         },
     }
 }
 
-/// Frees memory for a TariPrivateKey
+/// Gets the number of suggestions in a TariEmojiIdSuggestions
 ///
 /// ## Arguments
-/// `pk` - The pointer to a TariPrivateKey
+/// `suggestions` - The pointer to a TariEmojiIdSuggestions
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
 ///
 /// ## Returns
-/// `()` - Does not return a value, equivalent to void in C
+/// `c_uint` - Returns the number of suggestions, zero if suggestions is null
 ///
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn private_key_destroy(pk: *mut TariPrivateKey) {
-    if !pk.is_null() {
-        Box::from_raw(pk);
+pub unsafe extern "C" fn emoji_id_suggestions_get_length(
+    suggestions: *const TariEmojiIdSuggestions,
+    error_out: *mut c_int,
+) -> c_uint {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if suggestions.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("suggestions".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
     }
+    (*suggestions).0.len() as c_uint
 }
 
-/// Gets a ByteVector from a TariPrivateKey
+/// Gets the suggested emoji ID at position in a TariEmojiIdSuggestions
 ///
 /// ## Arguments
-/// `pk` - The pointer to a TariPrivateKey
+/// `suggestions` - The pointer to a TariEmojiIdSuggestions
+/// `position` - The integer position
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut ByteVectror` - Returns a pointer to a ByteVector. Note that it returns ptr::null_mut()
-/// if pk is null
+/// `*mut c_char` - Returns a pointer to a char array. Note that it returns an empty char array if suggestions is null
+/// or the position is invalid
 ///
 /// # Safety
-/// The ```byte_vector_destroy``` must be called when finished with a ByteVector to prevent a memory leak
+/// The ```string_destroy``` method must be called when finished with a string from rust to prevent a memory leak
 #[no_mangle]
-pub unsafe extern "C" fn private_key_get_bytes(pk: *mut TariPrivateKey, error_out: *mut c_int) -> *mut ByteVector {
+pub unsafe extern "C" fn emoji_id_suggestions_get_at(
+    suggestions: *const TariEmojiIdSuggestions,
+    position: c_uint,
+    error_out: *mut c_int,
+) -> *mut c_char {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let mut bytes = ByteVector(Vec::new());
-    if pk.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("pk".to_string())).code;
+    let mut result = CString::new("").unwrap();
+    if suggestions.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("suggestions".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
     } else {
-        bytes.0 = (*pk).to_vec();
+        match (*suggestions).0.get(position as usize) {
+            Some(emoji_id) => result = CString::new(emoji_id.as_str()).unwrap(),
+            None => {
+                error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+                ptr::swap(error_out, &mut error as *mut c_int);
+            },
+        }
     }
-    Box::into_raw(Box::new(bytes))
+    CString::into_raw(result)
 }
 
-/// Generates a TariPrivateKey
+/// Frees memory for a TariEmojiIdSuggestions
 ///
 /// ## Arguments
-/// `()` - Does  not take any arguments
+/// `suggestions` - The pointer to a TariEmojiIdSuggestions
 ///
 /// ## Returns
-/// `*mut TariPrivateKey` - Returns a pointer to a TariPrivateKey
+/// `()` - Does not return a value, equivalent to void in C
 ///
 /// # Safety
-/// The ```private_key_destroy``` method must be called when finished with a TariPrivateKey to prevent a memory leak.
+/// None
 #[no_mangle]
-pub unsafe extern "C" fn private_key_generate() -> *mut TariPrivateKey {
-    let secret_key = TariPrivateKey::random(&mut OsRng);
-    Box::into_raw(Box::new(secret_key))
+pub unsafe extern "C" fn emoji_id_suggestions_destroy(suggestions: *mut TariEmojiIdSuggestions) {
+    if !suggestions.is_null() {
+        Box::from_raw(suggestions);
+    }
 }
 
-/// Creates a TariPrivateKey from a char array
-///
-/// ## Arguments
+/// -------------------------------------------------------------------------------------------- ///
+
+/// -------------------------------- Private Key ----------------------------------------------- ///
+
+/// Creates a TariPrivateKey from a ByteVector
+///
+/// ## Arguments
+/// `bytes` - The pointer to a ByteVector
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPrivateKey` - Returns a pointer to a TariPublicKey. Note that it returns ptr::null_mut()
+/// if bytes is null or if there was an error creating the TariPrivateKey from bytes
+///
+/// # Safety
+/// The ```private_key_destroy``` method must be called when finished with a TariPrivateKey to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn private_key_create(bytes: *mut ByteVector, error_out: *mut c_int) -> *mut TariPrivateKey {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let v;
+    if bytes.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("bytes".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    } else {
+        v = (*bytes).0.clone();
+    }
+    let pk = TariPrivateKey::from_bytes(&v);
+    match pk {
+        Ok(pk) => Box::into_raw(Box::new(pk)),
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Frees memory for a TariPrivateKey
+///
+/// ## Arguments
+/// `pk` - The pointer to a TariPrivateKey
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn private_key_destroy(pk: *mut TariPrivateKey) {
+    if !pk.is_null() {
+        Box::from_raw(pk);
+    }
+}
+
+/// Gets a ByteVector from a TariPrivateKey
+///
+/// ## Arguments
+/// `pk` - The pointer to a TariPrivateKey
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut ByteVectror` - Returns a pointer to a ByteVector. Note that it returns ptr::null_mut()
+/// if pk is null
+///
+/// # Safety
+/// The ```byte_vector_destroy``` must be called when finished with a ByteVector to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn private_key_get_bytes(pk: *mut TariPrivateKey, error_out: *mut c_int) -> *mut ByteVector {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut bytes = ByteVector(Vec::new());
+    if pk.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("pk".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    } else {
+        bytes.0 = (*pk).to_vec();
+    }
+    Box::into_raw(Box::new(bytes))
+}
+
+/// Generates a TariPrivateKey
+///
+/// ## Arguments
+/// `()` - Does  not take any arguments
+///
+/// ## Returns
+/// `*mut TariPrivateKey` - Returns a pointer to a TariPrivateKey
+///
+/// # Safety
+/// The ```private_key_destroy``` method must be called when finished with a TariPrivateKey to prevent a memory leak.
+#[no_mangle]
+pub unsafe extern "C" fn private_key_generate() -> *mut TariPrivateKey {
+    let secret_key = TariPrivateKey::random(&mut OsRng);
+    Box::into_raw(Box::new(secret_key))
+}
+
+/// Creates a TariPrivateKey from a char array
+///
+/// ## Arguments
 /// `key` - The pointer to a char array which is hex encoded
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
@@ -1007,6 +1440,7 @@ pub unsafe extern "C" fn contact_create(
     let contact = Contact {
         alias: alias_string,
         public_key: (*public_key).clone(),
+        notes: None,
     };
     Box::into_raw(Box::new(contact))
 }
@@ -1254,168 +1688,138 @@ pub unsafe extern "C" fn completed_transactions_destroy(transactions: *mut TariC
 
 /// -------------------------------------------------------------------------------------------- ///
 
-/// ----------------------------------- OutboundTransactions ------------------------------------ ///
+/// ------------------------------- CompletedTransactionsPage ----------------------------------- ///
 
-/// Gets the length of a TariPendingOutboundTransactions
+/// Gets the length of a TariCompletedTransactionsPage
 ///
 /// ## Arguments
-/// `transactions` - The pointer to a TariPendingOutboundTransactions
+/// `page` - The pointer to a TariCompletedTransactionsPage
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_uint` - Returns the number of elements in a TariPendingOutboundTransactions, note that it will be
-/// zero if transactions is null
+/// `c_uint` - Returns the number of transactions in the page, note that it will be zero if page is null
 ///
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn pending_outbound_transactions_get_length(
-    transactions: *mut TariPendingOutboundTransactions,
+pub unsafe extern "C" fn completed_transactions_page_get_length(
+    page: *mut TariCompletedTransactionsPage,
     error_out: *mut c_int,
 ) -> c_uint {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     let mut len = 0;
-    if transactions.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+    if page.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("page".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
     } else {
-        len = (*transactions).0.len();
+        len = (*page).transactions.len();
     }
-
     len as c_uint
 }
 
-/// Gets a TariPendingOutboundTransaction of a TariPendingOutboundTransactions
+/// Gets a TariCompletedTransaction from a TariCompletedTransactionsPage at position
 ///
 /// ## Arguments
-/// `transactions` - The pointer to a TariPendingOutboundTransactions
+/// `page` - The pointer to a TariCompletedTransactionsPage
 /// `position` - The integer position
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPendingOutboundTransaction` - Returns a pointer to a TariPendingOutboundTransaction,
-/// note that ptr::null_mut() is returned if transactions is null or position is invalid
+/// `*mut TariCompletedTransaction` - Returns a pointer to a TariCompletedTransaction,
+/// note that ptr::null_mut() is returned if page is null or position is invalid
 ///
 /// # Safety
-/// The ```pending_outbound_transaction_destroy``` method must be called when finished with a
-/// TariPendingOutboundTransaction to prevent a memory leak
+/// The ```completed_transaction_destroy``` method must be called when finished with a TariCompletedTransaction to
+/// prevent a memory leak
 #[no_mangle]
-pub unsafe extern "C" fn pending_outbound_transactions_get_at(
-    transactions: *mut TariPendingOutboundTransactions,
+pub unsafe extern "C" fn completed_transactions_page_get_at(
+    page: *mut TariCompletedTransactionsPage,
     position: c_uint,
     error_out: *mut c_int,
-) -> *mut TariPendingOutboundTransaction {
+) -> *mut TariCompletedTransaction {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    if transactions.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
-    }
-    let len = pending_outbound_transactions_get_length(transactions, error_out) as c_int - 1;
-    if len < 0 || position > len as c_uint {
-        error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+    if page.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("page".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
         return ptr::null_mut();
     }
-    Box::into_raw(Box::new((*transactions).0[position as usize].clone()))
-}
-
-/// Frees memory for a TariPendingOutboundTransactions
-///
-/// ## Arguments
-/// `transactions` - The pointer to a TariPendingOutboundTransactions
-///
-/// ## Returns
-/// `()` - Does not return a value, equivalent to void in C
-///
-/// # Safety
-/// None
-#[no_mangle]
-pub unsafe extern "C" fn pending_outbound_transactions_destroy(transactions: *mut TariPendingOutboundTransactions) {
-    if !transactions.is_null() {
-        Box::from_raw(transactions);
+    match (*page).transactions.get(position as usize) {
+        Some(tx) => Box::into_raw(Box::new(tx.clone())),
+        None => {
+            error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
     }
 }
 
-/// -------------------------------------------------------------------------------------------- ///
-
-/// ----------------------------------- InboundTransactions ------------------------------------- ///
-
-/// Gets the length of a TariPendingInboundTransactions
+/// Gets the total number of transactions matching the query that produced a TariCompletedTransactionsPage, across
+/// all pages
 ///
 /// ## Arguments
-/// `transactions` - The pointer to a TariPendingInboundTransactions
+/// `page` - The pointer to a TariCompletedTransactionsPage
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_uint` - Returns the number of elements in a TariPendingInboundTransactions, note that
-/// it will be zero if transactions is null
+/// `c_ulonglong` - Returns the total count, note that it will be zero if page is null
 ///
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn pending_inbound_transactions_get_length(
-    transactions: *mut TariPendingInboundTransactions,
+pub unsafe extern "C" fn completed_transactions_page_get_total_count(
+    page: *mut TariCompletedTransactionsPage,
     error_out: *mut c_int,
-) -> c_uint {
+) -> c_ulonglong {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let mut len = 0;
-    if transactions.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+    if page.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("page".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-    } else {
-        len = (*transactions).0.len();
+        return 0;
     }
-    len as c_uint
+    (*page).total_count as c_ulonglong
 }
 
-/// Gets a TariPendingInboundTransaction of a TariPendingInboundTransactions
+/// Gets the cursor that fetches the page following a TariCompletedTransactionsPage
 ///
 /// ## Arguments
-/// `transactions` - The pointer to a TariPendingInboundTransactions
-/// `position` - The integer position
+/// `page` - The pointer to a TariCompletedTransactionsPage
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPendingOutboundTransaction` - Returns a pointer to a TariPendingInboundTransaction,
-/// note that ptr::null_mut() is returned if transactions is null or position is invalid
+/// `*mut c_char` - Returns the cursor to pass to `wallet_get_transactions_page`, note that it returns ptr::null_mut()
+/// if this is the last page or page is null
 ///
 /// # Safety
-/// The ```pending_inbound_transaction_destroy``` method must be called when finished with a
-/// TariPendingOutboundTransaction to prevent a memory leak
+/// The ```string_destroy``` method must be called when finished with a string from rust to prevent a memory leak
 #[no_mangle]
-pub unsafe extern "C" fn pending_inbound_transactions_get_at(
-    transactions: *mut TariPendingInboundTransactions,
-    position: c_uint,
+pub unsafe extern "C" fn completed_transactions_page_get_next_cursor(
+    page: *mut TariCompletedTransactionsPage,
     error_out: *mut c_int,
-) -> *mut TariPendingInboundTransaction {
+) -> *mut c_char {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    if transactions.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+    if page.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("page".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
         return ptr::null_mut();
     }
-    let len = pending_inbound_transactions_get_length(transactions, error_out) as c_int - 1;
-    if len < 0 || position > len as c_uint {
-        error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+    match &(*page).next_cursor {
+        Some(cursor) => CString::into_raw(CString::new(cursor.to_string()).unwrap()),
+        None => ptr::null_mut(),
     }
-    Box::into_raw(Box::new((*transactions).0[position as usize].clone()))
 }
 
-/// Frees memory for a TariPendingInboundTransactions
+/// Frees memory for a TariCompletedTransactionsPage
 ///
 /// ## Arguments
-/// `transactions` - The pointer to a TariPendingInboundTransactions
+/// `page` - The pointer to a TariCompletedTransactionsPage
 ///
 /// ## Returns
 /// `()` - Does not return a value, equivalent to void in C
@@ -1423,95 +1827,494 @@ pub unsafe extern "C" fn pending_inbound_transactions_get_at(
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn pending_inbound_transactions_destroy(transactions: *mut TariPendingInboundTransactions) {
-    if !transactions.is_null() {
-        Box::from_raw(transactions);
+pub unsafe extern "C" fn completed_transactions_page_destroy(page: *mut TariCompletedTransactionsPage) {
+    if !page.is_null() {
+        Box::from_raw(page);
     }
 }
 
 /// -------------------------------------------------------------------------------------------- ///
 
-/// ----------------------------------- CompletedTransaction ------------------------------------- ///
+/// ----------------------------------- UnblindedOutput ----------------------------------------- ///
 
-/// Gets the TransactionID of a TariCompletedTransaction
+/// Gets the value of a TariUnblindedOutput
 ///
 /// ## Arguments
-/// `transaction` - The pointer to a TariCompletedTransaction
+/// `output` - The pointer to a TariUnblindedOutput
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - Returns the TransactionID, note that it will be zero if transaction is null
+/// `c_ulonglong` - Returns the value in MicroTari, note that it will be zero if output is null
 ///
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn completed_transaction_get_transaction_id(
-    transaction: *mut TariCompletedTransaction,
+pub unsafe extern "C" fn unblinded_output_get_value(
+    output: *mut TariUnblindedOutput,
     error_out: *mut c_int,
 ) -> c_ulonglong {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    if transaction.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+    if output.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("output".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
         return 0;
     }
-    (*transaction).tx_id as c_ulonglong
+    c_ulonglong::from((*output).value)
 }
 
-/// Gets the destination TariPublicKey of a TariCompletedTransaction
+/// Gets the maturity of a TariUnblindedOutput, the block height from which it may be spent
 ///
 /// ## Arguments
-/// `transaction` - The pointer to a TariCompletedTransaction
+/// `output` - The pointer to a TariUnblindedOutput
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPublicKey` - Returns the destination TariPublicKey, note that it will be
-/// ptr::null_mut() if transaction is null
+/// `c_ulonglong` - Returns the maturity, note that it will be zero if output is null
 ///
 /// # Safety
-/// The ```public_key_destroy``` method must be called when finished with a TariPublicKey to prevent a memory leak
+/// None
 #[no_mangle]
-pub unsafe extern "C" fn completed_transaction_get_destination_public_key(
-    transaction: *mut TariCompletedTransaction,
+pub unsafe extern "C" fn unblinded_output_get_maturity(
+    output: *mut TariUnblindedOutput,
     error_out: *mut c_int,
-) -> *mut TariPublicKey {
+) -> c_ulonglong {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    if transaction.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+    if output.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("output".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return 0;
     }
-    let m = (*transaction).destination_public_key.clone();
-    Box::into_raw(Box::new(m))
+    (*output).features.maturity as c_ulonglong
 }
 
-/// Gets the TariExcess of a TariCompletedTransaction
+/// Frees memory for a TariUnblindedOutput
 ///
 /// ## Arguments
-/// `transaction` - The pointer to a TariCompletedTransaction
+/// `output` - The pointer to a TariUnblindedOutput
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_output_destroy(output: *mut TariUnblindedOutput) {
+    if !output.is_null() {
+        Box::from_raw(output);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
+/// ---------------------------------- UnblindedOutputsPage ------------------------------------- ///
+
+/// Gets the length of a TariUnblindedOutputsPage
+///
+/// ## Arguments
+/// `page` - The pointer to a TariUnblindedOutputsPage
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariExcess` - Returns the transaction excess, note that it will be
-/// ptr::null_mut() if transaction is null, if the transaction status is Pending, or if the number of kernels is not
-/// exactly one.
+/// `c_uint` - Returns the number of outputs in the page, note that it will be zero if page is null
 ///
 /// # Safety
-/// The ```excess_destroy``` method must be called when finished with a TariExcess to prevent a memory leak
+/// None
 #[no_mangle]
-pub unsafe extern "C" fn completed_transaction_get_excess(
-    transaction: *mut TariCompletedTransaction,
+pub unsafe extern "C" fn unblinded_outputs_page_get_length(
+    page: *mut TariUnblindedOutputsPage,
     error_out: *mut c_int,
-) -> *mut TariExcess {
+) -> c_uint {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    if transaction.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+    let mut len = 0;
+    if page.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("page".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+    } else {
+        len = (*page).outputs.len();
+    }
+    len as c_uint
+}
+
+/// Gets a TariUnblindedOutput from a TariUnblindedOutputsPage at position
+///
+/// ## Arguments
+/// `page` - The pointer to a TariUnblindedOutputsPage
+/// `position` - The integer position
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariUnblindedOutput` - Returns a pointer to a TariUnblindedOutput, note that ptr::null_mut() is returned if
+/// page is null or position is invalid
+///
+/// # Safety
+/// The ```unblinded_output_destroy``` method must be called when finished with a TariUnblindedOutput to prevent a
+/// memory leak
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_outputs_page_get_at(
+    page: *mut TariUnblindedOutputsPage,
+    position: c_uint,
+    error_out: *mut c_int,
+) -> *mut TariUnblindedOutput {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if page.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("page".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    match (*page).outputs.get(position as usize) {
+        Some(output) => Box::into_raw(Box::new(output.clone())),
+        None => {
+            error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Gets the total number of unspent outputs matching the query that produced a TariUnblindedOutputsPage, across all
+/// pages
+///
+/// ## Arguments
+/// `page` - The pointer to a TariUnblindedOutputsPage
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the total count, note that it will be zero if page is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_outputs_page_get_total_count(
+    page: *mut TariUnblindedOutputsPage,
+    error_out: *mut c_int,
+) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if page.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("page".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    (*page).total_count as c_ulonglong
+}
+
+/// Gets the cursor that fetches the page following a TariUnblindedOutputsPage
+///
+/// ## Arguments
+/// `page` - The pointer to a TariUnblindedOutputsPage
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns the cursor to pass to `wallet_get_utxos_page`, note that it returns ptr::null_mut() if this
+/// is the last page or page is null
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with a string from rust to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_outputs_page_get_next_cursor(
+    page: *mut TariUnblindedOutputsPage,
+    error_out: *mut c_int,
+) -> *mut c_char {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if page.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("page".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    match &(*page).next_cursor {
+        Some(cursor) => CString::into_raw(CString::new(cursor.to_string()).unwrap()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Frees memory for a TariUnblindedOutputsPage
+///
+/// ## Arguments
+/// `page` - The pointer to a TariUnblindedOutputsPage
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_outputs_page_destroy(page: *mut TariUnblindedOutputsPage) {
+    if !page.is_null() {
+        Box::from_raw(page);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
+/// ----------------------------------- OutboundTransactions ------------------------------------ ///
+
+/// Gets the length of a TariPendingOutboundTransactions
+///
+/// ## Arguments
+/// `transactions` - The pointer to a TariPendingOutboundTransactions
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_uint` - Returns the number of elements in a TariPendingOutboundTransactions, note that it will be
+/// zero if transactions is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn pending_outbound_transactions_get_length(
+    transactions: *mut TariPendingOutboundTransactions,
+    error_out: *mut c_int,
+) -> c_uint {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut len = 0;
+    if transactions.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+    } else {
+        len = (*transactions).0.len();
+    }
+
+    len as c_uint
+}
+
+/// Gets a TariPendingOutboundTransaction of a TariPendingOutboundTransactions
+///
+/// ## Arguments
+/// `transactions` - The pointer to a TariPendingOutboundTransactions
+/// `position` - The integer position
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPendingOutboundTransaction` - Returns a pointer to a TariPendingOutboundTransaction,
+/// note that ptr::null_mut() is returned if transactions is null or position is invalid
+///
+/// # Safety
+/// The ```pending_outbound_transaction_destroy``` method must be called when finished with a
+/// TariPendingOutboundTransaction to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn pending_outbound_transactions_get_at(
+    transactions: *mut TariPendingOutboundTransactions,
+    position: c_uint,
+    error_out: *mut c_int,
+) -> *mut TariPendingOutboundTransaction {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if transactions.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    let len = pending_outbound_transactions_get_length(transactions, error_out) as c_int - 1;
+    if len < 0 || position > len as c_uint {
+        error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new((*transactions).0[position as usize].clone()))
+}
+
+/// Frees memory for a TariPendingOutboundTransactions
+///
+/// ## Arguments
+/// `transactions` - The pointer to a TariPendingOutboundTransactions
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn pending_outbound_transactions_destroy(transactions: *mut TariPendingOutboundTransactions) {
+    if !transactions.is_null() {
+        Box::from_raw(transactions);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
+/// ----------------------------------- InboundTransactions ------------------------------------- ///
+
+/// Gets the length of a TariPendingInboundTransactions
+///
+/// ## Arguments
+/// `transactions` - The pointer to a TariPendingInboundTransactions
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_uint` - Returns the number of elements in a TariPendingInboundTransactions, note that
+/// it will be zero if transactions is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn pending_inbound_transactions_get_length(
+    transactions: *mut TariPendingInboundTransactions,
+    error_out: *mut c_int,
+) -> c_uint {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut len = 0;
+    if transactions.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+    } else {
+        len = (*transactions).0.len();
+    }
+    len as c_uint
+}
+
+/// Gets a TariPendingInboundTransaction of a TariPendingInboundTransactions
+///
+/// ## Arguments
+/// `transactions` - The pointer to a TariPendingInboundTransactions
+/// `position` - The integer position
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPendingOutboundTransaction` - Returns a pointer to a TariPendingInboundTransaction,
+/// note that ptr::null_mut() is returned if transactions is null or position is invalid
+///
+/// # Safety
+/// The ```pending_inbound_transaction_destroy``` method must be called when finished with a
+/// TariPendingOutboundTransaction to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn pending_inbound_transactions_get_at(
+    transactions: *mut TariPendingInboundTransactions,
+    position: c_uint,
+    error_out: *mut c_int,
+) -> *mut TariPendingInboundTransaction {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if transactions.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    let len = pending_inbound_transactions_get_length(transactions, error_out) as c_int - 1;
+    if len < 0 || position > len as c_uint {
+        error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new((*transactions).0[position as usize].clone()))
+}
+
+/// Frees memory for a TariPendingInboundTransactions
+///
+/// ## Arguments
+/// `transactions` - The pointer to a TariPendingInboundTransactions
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn pending_inbound_transactions_destroy(transactions: *mut TariPendingInboundTransactions) {
+    if !transactions.is_null() {
+        Box::from_raw(transactions);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
+/// ----------------------------------- CompletedTransaction ------------------------------------- ///
+
+/// Gets the TransactionID of a TariCompletedTransaction
+///
+/// ## Arguments
+/// `transaction` - The pointer to a TariCompletedTransaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the TransactionID, note that it will be zero if transaction is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn completed_transaction_get_transaction_id(
+    transaction: *mut TariCompletedTransaction,
+    error_out: *mut c_int,
+) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if transaction.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    (*transaction).tx_id as c_ulonglong
+}
+
+/// Gets the destination TariPublicKey of a TariCompletedTransaction
+///
+/// ## Arguments
+/// `transaction` - The pointer to a TariCompletedTransaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPublicKey` - Returns the destination TariPublicKey, note that it will be
+/// ptr::null_mut() if transaction is null
+///
+/// # Safety
+/// The ```public_key_destroy``` method must be called when finished with a TariPublicKey to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn completed_transaction_get_destination_public_key(
+    transaction: *mut TariCompletedTransaction,
+    error_out: *mut c_int,
+) -> *mut TariPublicKey {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if transaction.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    let m = (*transaction).destination_public_key.clone();
+    Box::into_raw(Box::new(m))
+}
+
+/// Gets the TariExcess of a TariCompletedTransaction
+///
+/// ## Arguments
+/// `transaction` - The pointer to a TariCompletedTransaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariExcess` - Returns the transaction excess, note that it will be
+/// ptr::null_mut() if transaction is null, if the transaction status is Pending, or if the number of kernels is not
+/// exactly one.
+///
+/// # Safety
+/// The ```excess_destroy``` method must be called when finished with a TariExcess to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn completed_transaction_get_excess(
+    transaction: *mut TariCompletedTransaction,
+    error_out: *mut c_int,
+) -> *mut TariExcess {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if transaction.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
         return ptr::null_mut();
     }
@@ -1890,6 +2693,36 @@ pub unsafe extern "C" fn completed_transaction_is_outbound(
     false
 }
 
+/// This function checks to determine if a TariCompletedTransaction is a transfer between the wallet's own accounts,
+/// such as a send to self or an output consolidation
+///
+/// ## Arguments
+/// `tx` - The TariCompletedTransaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if the transaction moved funds within the wallet rather than paying or being paid by another party
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn completed_transaction_is_internal(
+    tx: *mut TariCompletedTransaction,
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+
+    if tx.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("tx".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    (*tx).direction == TransactionDirection::Internal
+}
+
 /// Gets the number of confirmations of a TariCompletedTransaction
 ///
 /// ## Arguments
@@ -2418,6 +3251,7 @@ pub unsafe extern "C" fn transport_tcp_create(
     let transport = TariTransportType::Tcp {
         listener_address: listener_address_str.parse::<Multiaddr>().unwrap(),
         tor_socks_config: None,
+        outbound_socks_config: None,
     };
     Box::into_raw(Box::new(transport))
 }
@@ -2651,6 +3485,7 @@ pub unsafe extern "C" fn comms_config_create(
                 peer_seeds: Default::default(),
                 dns_seeds: Default::default(),
                 dns_seeds_use_dnssec: true,
+                min_peer_minor_version: 0,
             };
 
             Box::into_raw(Box::new(config))
@@ -2779,6 +3614,15 @@ unsafe fn init_logging(log_path: *const c_char, num_rolling_log_files: c_uint, s
 /// `callback_saf_message_received` - The callback function pointer that will be called when the Dht has determined that
 /// is has connected to enough of its neighbours to be confident that it has received any SAF messages that were waiting
 /// for it.
+/// `callback_balance_updated` - The callback function pointer matching the function signature. This is called when
+/// the balance of the wallet changes, with the available, pending incoming and pending outgoing balances, the change in
+/// the available plus pending incoming balance and a u8 that represents the BalanceChangeReason enum:
+/// 0 - TransactionReceived, 1 - TransactionSent, 2 - TransactionMined, 3 - TransactionCancelled, 4 - OutputsValidated,
+/// 5 - OutputsRecovered, 6 - ChainReorg, 7 - OneSidedReclaimed
+/// `callback_connectivity_status` - The callback function pointer matching the function signature. This is called when
+/// the connection state of the base node changes: 0 - Connecting, 1 - Online, 2 - Offline
+/// `callback_sync_progress` - The callback function pointer matching the function signature. This is called with the
+/// current block and the chain height while the wallet scans the blockchain for its outputs
 /// `error_out` - Pointer to an int which will be modified
 /// to an error code should one occur, may not be null. Functions as an out parameter.
 /// ## Returns
@@ -2810,6 +3654,9 @@ pub unsafe extern "C" fn wallet_create(
     callback_invalid_txo_validation_complete: unsafe extern "C" fn(u64, u8),
     callback_transaction_validation_complete: unsafe extern "C" fn(u64, u8),
     callback_saf_messages_received: unsafe extern "C" fn(),
+    callback_balance_updated: unsafe extern "C" fn(c_ulonglong, c_ulonglong, c_ulonglong, c_longlong, u8),
+    callback_connectivity_status: unsafe extern "C" fn(u8),
+    callback_sync_progress: unsafe extern "C" fn(c_ulonglong, c_ulonglong),
     error_out: *mut c_int,
 ) -> *mut TariWallet {
     use tari_key_manager::mnemonic::Mnemonic;
@@ -2867,15 +3714,21 @@ pub unsafe extern "C" fn wallet_create(
         .with_extension("sqlite3");
 
     debug!(target: LOG_TARGET, "Running Wallet database migrations");
-    let (wallet_backend, transaction_backend, output_manager_backend, contacts_backend) =
-        match initialize_sqlite_database_backends(sql_database_path, passphrase_option) {
-            Ok((w, t, o, c)) => (w, t, o, c),
-            Err(e) => {
-                error = LibWalletError::from(WalletError::WalletStorageError(e)).code;
-                ptr::swap(error_out, &mut error as *mut c_int);
-                return ptr::null_mut();
-            },
-        };
+    let (
+        wallet_backend,
+        transaction_backend,
+        output_manager_backend,
+        contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
+    ) = match initialize_sqlite_database_backends(sql_database_path, passphrase_option) {
+        Ok(backends) => backends,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::WalletStorageError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return ptr::null_mut();
+        },
+    };
     let wallet_database = WalletDatabase::new(wallet_backend);
 
     debug!(target: LOG_TARGET, "Databases Initialized");
@@ -2915,6 +3768,8 @@ pub unsafe extern "C" fn wallet_create(
         transaction_backend.clone(),
         output_manager_backend,
         contacts_backend,
+        recurring_payment_backend,
+        history_sync_backend,
         shutdown.to_signal(),
         recovery_master_key,
     ));
@@ -2927,12 +3782,25 @@ pub unsafe extern "C" fn wallet_create(
                     warn!(target: LOG_TARGET, "Could not save tor identity to db: {}", e);
                 }
             }
+            // Start the wallet event monitor that drives the balance, connectivity and sync progress callbacks
+            let wallet_event_monitor = WalletEventMonitor::new(
+                w.output_manager_service.clone(),
+                w.transaction_service.get_event_stream_fused(),
+                w.output_manager_service.get_event_stream_fused(),
+                w.base_node_service.get_event_stream_fused(),
+                w.utxo_scanner_service.get_event_receiver(),
+                w.comms.connectivity().get_event_subscription(),
+            );
+            let wallet_event_stream = wallet_event_monitor.subscribe();
+            runtime.spawn(wallet_event_monitor.run(w.comms.shutdown_signal()));
+
             // Start Callback Handler
             let callback_handler = CallbackHandler::new(
                 TransactionDatabase::new(transaction_backend),
                 w.transaction_service.get_event_stream_fused(),
                 w.output_manager_service.get_event_stream_fused(),
                 w.dht_service.subscribe_dht_events().fuse(),
+                wallet_event_stream.fuse(),
                 w.comms.shutdown_signal(),
                 w.comms.node_identity().public_key().clone(),
                 callback_received_transaction,
@@ -2949,6 +3817,9 @@ pub unsafe extern "C" fn wallet_create(
                 callback_invalid_txo_validation_complete,
                 callback_transaction_validation_complete,
                 callback_saf_messages_received,
+                callback_balance_updated,
+                callback_connectivity_status,
+                callback_sync_progress,
             );
 
             runtime.spawn(callback_handler.start());
@@ -3071,45 +3942,156 @@ pub unsafe extern "C" fn wallet_verify_message_signature(
     if hex_sig_nonce.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("signature".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return result;
+        return result;
+    }
+    if msg.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return result;
+    }
+
+    let message = CStr::from_ptr(msg).to_str().unwrap().to_owned();
+    let hex = CStr::from_ptr(hex_sig_nonce).to_str().unwrap().to_owned();
+    let hex_keys: Vec<&str> = hex.split('|').collect();
+    if hex_keys.len() != 2 {
+        error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return result;
+    }
+    let secret = TariPrivateKey::from_hex(hex_keys.get(0).unwrap());
+    match secret {
+        Ok(p) => {
+            let public_nonce = TariPublicKey::from_hex(hex_keys.get(1).unwrap());
+            match public_nonce {
+                Ok(pn) => {
+                    result = (*wallet)
+                        .wallet
+                        .verify_message_signature((*public_key).clone(), pn, p, message)
+                },
+                Err(e) => {
+                    error = LibWalletError::from(e).code;
+                    ptr::swap(error_out, &mut error as *mut c_int);
+                },
+            }
+        },
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+        },
+    }
+
+    result
+}
+
+/// Generates a proof that this wallet paid the receiver of a completed outbound transaction. The proof can be given to
+/// the receiver or a third party, who can check it with `wallet_verify_payment_proof`.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer.
+/// `transaction_id` - The id of the completed outbound transaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+/// ## Returns
+/// `*mut c_char` - Returns the pointer to the base64 encoded proof. Empty if an error occured.
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with a string coming from rust to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_generate_payment_proof(
+    wallet: *mut TariWallet,
+    transaction_id: c_ulonglong,
+    error_out: *mut c_int,
+) -> *mut c_char {
+    let mut error = 0;
+    let mut result = CString::new("").unwrap();
+
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return result.into_raw();
+    }
+
+    match (*wallet).runtime.block_on(
+        (*wallet)
+            .wallet
+            .transaction_service
+            .generate_payment_proof(transaction_id),
+    ) {
+        Ok(proof) => match proof.to_base64() {
+            Ok(encoded) => result = CString::new(encoded).unwrap(),
+            Err(e) => {
+                error = LibWalletError::from(InterfaceError::InvalidPaymentProof(e.to_string())).code;
+                ptr::swap(error_out, &mut error as *mut c_int);
+            },
+        },
+        Err(e) => {
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+        },
+    }
+
+    result.into_raw()
+}
+
+/// Verifies a proof of payment generated by `wallet_generate_payment_proof`. The signatures in the proof are always
+/// checked. If this wallet is the receiver, this also checks that the wallet owns the paid output and that it is worth
+/// the claimed amount.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer.
+/// `proof` - The pointer to the base64 encoded proof
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+/// ## Returns
+/// `bool` - Returns if the proof is valid, will be false if an error occurs.
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_verify_payment_proof(
+    wallet: *mut TariWallet,
+    proof: *const c_char,
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
     }
-    if msg.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
+    if proof.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("proof".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return result;
+        return false;
     }
 
-    let message = CStr::from_ptr(msg).to_str().unwrap().to_owned();
-    let hex = CStr::from_ptr(hex_sig_nonce).to_str().unwrap().to_owned();
-    let hex_keys: Vec<&str> = hex.split('|').collect();
-    if hex_keys.len() != 2 {
-        error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return result;
-    }
-    let secret = TariPrivateKey::from_hex(hex_keys.get(0).unwrap());
-    match secret {
-        Ok(p) => {
-            let public_nonce = TariPublicKey::from_hex(hex_keys.get(1).unwrap());
-            match public_nonce {
-                Ok(pn) => {
-                    result = (*wallet)
-                        .wallet
-                        .verify_message_signature((*public_key).clone(), pn, p, message)
-                },
-                Err(e) => {
-                    error = LibWalletError::from(e).code;
-                    ptr::swap(error_out, &mut error as *mut c_int);
-                },
-            }
+    let proof = match CStr::from_ptr(proof)
+        .to_str()
+        .map_err(|e| e.to_string())
+        .and_then(|s| PaymentProof::from_base64(s).map_err(|e| e.to_string()))
+    {
+        Ok(proof) => proof,
+        Err(e) => {
+            error = LibWalletError::from(InterfaceError::InvalidPaymentProof(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return false;
         },
+    };
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.transaction_service.verify_payment_proof(proof))
+    {
+        Ok(()) => true,
+        Err(TransactionServiceError::PaymentProofError(_)) => false,
         Err(e) => {
-            error = LibWalletError::from(e).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
+            false
         },
     }
-
-    result
 }
 
 /// This function will generate some test data in the wallet. The data generated will be
@@ -3475,67 +4457,319 @@ pub unsafe extern "C" fn wallet_upsert_contact(
     }
 }
 
-/// Removes a TariContact from the TariWallet
+/// Removes a TariContact from the TariWallet
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `tx` - The TariPendingInboundTransaction pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_remove_contact(
+    wallet: *mut TariWallet,
+    contact: *mut TariContact,
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    if contact.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("contact".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet).runtime.block_on(
+        (*wallet)
+            .wallet
+            .contacts_service
+            .remove_contact((*contact).public_key.clone()),
+    ) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Exports all contacts of the TariWallet to a contact bundle file
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `file_path` - The path of the file to write the contact bundle to
+/// `passphrase` - The passphrase used to encrypt the bundle. If this is null the bundle is written as plain JSON
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_export_contacts(
+    wallet: *mut TariWallet,
+    file_path: *const c_char,
+    passphrase: *const c_char,
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    if file_path.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("file_path".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let file_path = CStr::from_ptr(file_path)
+        .to_str()
+        .expect("A non-null file_path should be able to be converted to string")
+        .to_owned();
+    let passphrase = if passphrase.is_null() {
+        None
+    } else {
+        Some(
+            CStr::from_ptr(passphrase)
+                .to_str()
+                .expect("A non-null passphrase should be able to be converted to string")
+                .to_owned(),
+        )
+    };
+
+    let result = (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.contacts_service.export_contacts())
+        .and_then(|bundle| bundle.write_to_file(file_path, passphrase.as_deref()));
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Imports the contacts in a contact bundle file into the TariWallet. Contacts that are not in the address book are
+/// added. Existing contacts keep their alias and only receive the imported notes if they have none.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `file_path` - The path of the contact bundle file
+/// `passphrase` - The passphrase of an encrypted bundle. May be null if the bundle is not encrypted
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_uint` - Returns the number of contacts that were added or updated, 0 if an error occurred
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_import_contacts(
+    wallet: *mut TariWallet,
+    file_path: *const c_char,
+    passphrase: *const c_char,
+    error_out: *mut c_int,
+) -> c_uint {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    if file_path.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("file_path".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    let file_path = CStr::from_ptr(file_path)
+        .to_str()
+        .expect("A non-null file_path should be able to be converted to string")
+        .to_owned();
+    let passphrase = if passphrase.is_null() {
+        None
+    } else {
+        Some(
+            CStr::from_ptr(passphrase)
+                .to_str()
+                .expect("A non-null passphrase should be able to be converted to string")
+                .to_owned(),
+        )
+    };
+
+    let result = ContactBundle::read_from_file(file_path, passphrase.as_deref()).and_then(|bundle| {
+        (*wallet)
+            .runtime
+            .block_on((*wallet).wallet.contacts_service.import_contacts(bundle))
+    });
+    match result {
+        Ok(summary) => (summary.added + summary.updated) as c_uint,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Gets the available balance from a TariWallet. This is the balance the user can spend.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - The available balance, 0 if wallet is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_available_balance(wallet: *mut TariWallet, error_out: *mut c_int) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.output_manager_service.get_balance())
+    {
+        Ok(b) => c_ulonglong::from(b.available_balance),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Gets the incoming balance from a `TariWallet`. This is the uncleared balance of Tari that is
+/// expected to come into the `TariWallet` but is not yet spendable.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - The incoming balance, 0 if wallet is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_pending_incoming_balance(
+    wallet: *mut TariWallet,
+    error_out: *mut c_int,
+) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.output_manager_service.get_balance())
+    {
+        Ok(b) => c_ulonglong::from(b.pending_incoming_balance),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Gets the outgoing balance from a `TariWallet`. This is the uncleared balance of Tari that has
+/// been spent
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `tx` - The TariPendingInboundTransaction pointer
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `c_ulonglong` - The outgoing balance, 0 if wallet is null
 ///
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn wallet_remove_contact(
+pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
     wallet: *mut TariWallet,
-    contact: *mut TariContact,
     error_out: *mut c_int,
-) -> bool {
+) -> c_ulonglong {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
-    if contact.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("contact".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return 0;
     }
 
-    match (*wallet).runtime.block_on(
-        (*wallet)
-            .wallet
-            .contacts_service
-            .remove_contact((*contact).public_key.clone()),
-    ) {
-        Ok(_) => true,
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.output_manager_service.get_balance())
+    {
+        Ok(b) => c_ulonglong::from(b.pending_outgoing_balance),
         Err(e) => {
-            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            0
         },
     }
 }
 
-/// Gets the available balance from a TariWallet. This is the balance the user can spend.
+/// Sends a TariPendingOutboundTransaction
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `dest_public_key` - The TariPublicKey pointer of the peer
+/// `amount` - The amount
+/// `fee_per_gram` - The transaction fee
+/// `message` - The pointer to a char array
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The available balance, 0 if wallet is null
+/// `unsigned long long` - Returns 0 if unsuccessful or the TxId of the sent transaction if successful
 ///
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_available_balance(wallet: *mut TariWallet, error_out: *mut c_int) -> c_ulonglong {
+pub unsafe extern "C" fn wallet_send_transaction(
+    wallet: *mut TariWallet,
+    dest_public_key: *mut TariPublicKey,
+    amount: c_ulonglong,
+    fee_per_gram: c_ulonglong,
+    message: *const c_char,
+    error_out: *mut c_int,
+) -> c_ulonglong {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
@@ -3544,74 +4778,102 @@ pub unsafe extern "C" fn wallet_get_available_balance(wallet: *mut TariWallet, e
         return 0;
     }
 
+    if dest_public_key.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("dest_public_key".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    let message_string = if !message.is_null() {
+        CStr::from_ptr(message).to_str().unwrap().to_owned()
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        CString::new("").unwrap().to_str().unwrap().to_owned()
+    };
+
     match (*wallet)
         .runtime
-        .block_on((*wallet).wallet.output_manager_service.get_balance())
-    {
-        Ok(b) => c_ulonglong::from(b.available_balance),
+        .block_on((*wallet).wallet.transaction_service.send_transaction(
+            (*dest_public_key).clone(),
+            MicroTari::from(amount),
+            MicroTari::from(fee_per_gram),
+            message_string,
+        )) {
+        Ok(tx_id) => tx_id,
         Err(e) => {
-            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
             0
         },
     }
 }
 
-/// Gets the incoming balance from a `TariWallet`. This is the uncleared balance of Tari that is
-/// expected to come into the `TariWallet` but is not yet spendable.
+/// Creates a named account in the wallet. Each account derives its keys from its own branch of the wallet's master
+/// key and its outputs are only spent by transactions sent from that account.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `account` - The pointer to a char array containing the account name
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The incoming balance, 0 if wallet is null
+/// `bool` - Returns true if the account was created, false if an error occurred
 ///
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_pending_incoming_balance(
+pub unsafe extern "C" fn wallet_create_account(
     wallet: *mut TariWallet,
+    account: *const c_char,
     error_out: *mut c_int,
-) -> c_ulonglong {
+) -> bool {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return 0;
+        return false;
+    }
+
+    if account.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("account".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
     }
+    let account_string = CStr::from_ptr(account).to_str().unwrap().to_owned();
 
     match (*wallet)
         .runtime
-        .block_on((*wallet).wallet.output_manager_service.get_balance())
+        .block_on((*wallet).wallet.output_manager_service.create_account(account_string))
     {
-        Ok(b) => c_ulonglong::from(b.pending_incoming_balance),
+        Ok(_) => true,
         Err(e) => {
             error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            0
+            false
         },
     }
 }
 
-/// Gets the outgoing balance from a `TariWallet`. This is the uncleared balance of Tari that has
-/// been spent
+/// Gets the available balance of a named account
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `account` - The pointer to a char array containing the account name
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The outgoing balance, 0 if wallet is null
+/// `c_ulonglong` - The available balance of the account, 0 if an error occurred
 ///
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
+pub unsafe extern "C" fn wallet_get_account_available_balance(
     wallet: *mut TariWallet,
+    account: *const c_char,
     error_out: *mut c_int,
 ) -> c_ulonglong {
     let mut error = 0;
@@ -3622,11 +4884,20 @@ pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
         return 0;
     }
 
-    match (*wallet)
-        .runtime
-        .block_on((*wallet).wallet.output_manager_service.get_balance())
-    {
-        Ok(b) => c_ulonglong::from(b.pending_outgoing_balance),
+    if account.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("account".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    let account_string = CStr::from_ptr(account).to_str().unwrap().to_owned();
+
+    match (*wallet).runtime.block_on(
+        (*wallet)
+            .wallet
+            .output_manager_service
+            .get_account_balance(account_string),
+    ) {
+        Ok(b) => c_ulonglong::from(b.available_balance),
         Err(e) => {
             error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
@@ -3635,10 +4906,12 @@ pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
     }
 }
 
-/// Sends a TariPendingOutboundTransaction
+/// Sends a TariPendingOutboundTransaction that only spends outputs belonging to the named account. Any change is
+/// returned to the same account.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `account` - The pointer to a char array containing the account name
 /// `dest_public_key` - The TariPublicKey pointer of the peer
 /// `amount` - The amount
 /// `fee_per_gram` - The transaction fee
@@ -3652,8 +4925,9 @@ pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
 /// # Safety
 /// None
 #[no_mangle]
-pub unsafe extern "C" fn wallet_send_transaction(
+pub unsafe extern "C" fn wallet_send_transaction_from_account(
     wallet: *mut TariWallet,
+    account: *const c_char,
     dest_public_key: *mut TariPublicKey,
     amount: c_ulonglong,
     fee_per_gram: c_ulonglong,
@@ -3668,6 +4942,13 @@ pub unsafe extern "C" fn wallet_send_transaction(
         return 0;
     }
 
+    if account.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("account".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    let account_string = CStr::from_ptr(account).to_str().unwrap().to_owned();
+
     if dest_public_key.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("dest_public_key".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
@@ -3684,7 +4965,8 @@ pub unsafe extern "C" fn wallet_send_transaction(
 
     match (*wallet)
         .runtime
-        .block_on((*wallet).wallet.transaction_service.send_transaction(
+        .block_on((*wallet).wallet.transaction_service.send_transaction_from_account(
+            account_string,
             (*dest_public_key).clone(),
             MicroTari::from(amount),
             MicroTari::from(fee_per_gram),
@@ -3833,86 +5115,283 @@ pub unsafe extern "C" fn wallet_set_num_confirmations_required(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariContacts` - returns the contacts, note that it returns ptr::null_mut() if
-/// wallet is null
+/// `*mut TariContacts` - returns the contacts, note that it returns ptr::null_mut() if
+/// wallet is null
+///
+/// # Safety
+/// The ```contacts_destroy``` method must be called when finished with a TariContacts to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_contacts(wallet: *mut TariWallet, error_out: *mut c_int) -> *mut TariContacts {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut contacts = Vec::new();
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    let retrieved_contacts = (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.contacts_service.get_contacts());
+    match retrieved_contacts {
+        Ok(mut retrieved_contacts) => {
+            contacts.append(&mut retrieved_contacts);
+            Box::into_raw(Box::new(TariContacts(contacts)))
+        },
+        Err(e) => {
+            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Search the contacts of a TariWallet by alias, emoji ID or hex public key. A full emoji ID that contains a few typos
+/// still matches its contact. The best matches are first.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `query` - The pointer to a char array containing the search query
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariContacts` - returns the matching contacts, note that it returns ptr::null_mut() if
+/// wallet or query is null or an error is encountered
+///
+/// # Safety
+/// The ```contacts_destroy``` method must be called when finished with a TariContacts to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_search_contacts(
+    wallet: *mut TariWallet,
+    query: *const c_char,
+    error_out: *mut c_int,
+) -> *mut TariContacts {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    let query = match Some(query)
+        .filter(|q| !q.is_null())
+        .and_then(|q| CStr::from_ptr(q).to_str().ok())
+    {
+        Some(q) => q.to_owned(),
+        None => {
+            error = LibWalletError::from(InterfaceError::NullError("query".to_string())).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return ptr::null_mut();
+        },
+    };
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.contacts_service.search_contacts(query))
+    {
+        Ok(contacts) => Box::into_raw(Box::new(TariContacts(contacts))),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Get the TariCompletedTransactions from a TariWallet
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariCompletedTransactions` - returns the transactions, note that it returns ptr::null_mut() if
+/// wallet is null or an error is encountered
+///
+/// # Safety
+/// The ```completed_transactions_destroy``` method must be called when finished with a TariCompletedTransactions to
+/// prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_completed_transactions(
+    wallet: *mut TariWallet,
+    error_out: *mut c_int,
+) -> *mut TariCompletedTransactions {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut completed = Vec::new();
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    let completed_transactions = (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.transaction_service.get_completed_transactions());
+    match completed_transactions {
+        Ok(completed_transactions) => {
+            // The frontend specification calls for completed transactions that have not yet been mined to be
+            // classified as Pending Transactions. In order to support this logic without impacting the practical
+            // definitions and storage of a MimbleWimble CompletedTransaction we will remove CompletedTransactions with
+            // the Completed and Broadcast states from the list returned by this FFI function
+            for tx in completed_transactions
+                .values()
+                .filter(|ct| ct.status != TransactionStatus::Completed)
+                .filter(|ct| ct.status != TransactionStatus::Broadcast)
+            {
+                completed.push(tx.clone());
+            }
+            Box::into_raw(Box::new(TariCompletedTransactions(completed)))
+        },
+        Err(e) => {
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Parses an optional cursor passed in from the client, where null means the first page
+unsafe fn parse_cursor<T: FromStr>(cursor: *const c_char) -> Result<Option<T>, InterfaceError> {
+    if cursor.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(cursor)
+        .to_str()
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Some)
+        .ok_or(InterfaceError::InvalidCursor)
+}
+
+/// Get a page of completed transactions from a TariWallet, newest first. Unlike `wallet_get_completed_transactions`
+/// this does not load every transaction, so it should be preferred by wallets with a long history.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `cursor` - The cursor returned by `completed_transactions_page_get_next_cursor` for the previous page, or null to
+/// fetch the first page
+/// `page_size` - The maximum number of transactions to return
+/// `include_pending` - If false, transactions with the Completed and Broadcast statuses are excluded, as they are by
+/// `wallet_get_completed_transactions`
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariCompletedTransactionsPage` - returns the page, note that it returns ptr::null_mut() if wallet is null,
+/// the cursor is invalid or an error is encountered
 ///
 /// # Safety
-/// The ```contacts_destroy``` method must be called when finished with a TariContacts to prevent a memory leak
+/// The ```completed_transactions_page_destroy``` method must be called when finished with a
+/// TariCompletedTransactionsPage to prevent a memory leak
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_contacts(wallet: *mut TariWallet, error_out: *mut c_int) -> *mut TariContacts {
+pub unsafe extern "C" fn wallet_get_transactions_page(
+    wallet: *mut TariWallet,
+    cursor: *const c_char,
+    page_size: c_uint,
+    include_pending: bool,
+    error_out: *mut c_int,
+) -> *mut TariCompletedTransactionsPage {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let mut contacts = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
         return ptr::null_mut();
     }
 
-    let retrieved_contacts = (*wallet)
-        .runtime
-        .block_on((*wallet).wallet.contacts_service.get_contacts());
-    match retrieved_contacts {
-        Ok(mut retrieved_contacts) => {
-            contacts.append(&mut retrieved_contacts);
-            Box::into_raw(Box::new(TariContacts(contacts)))
+    let cursor = match parse_cursor(cursor) {
+        Ok(c) => c,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return ptr::null_mut();
+        },
+    };
+
+    let filter = CompletedTransactionFilter {
+        excluded_statuses: if include_pending {
+            Vec::new()
+        } else {
+            vec![TransactionStatus::Completed, TransactionStatus::Broadcast]
         },
+        ..Default::default()
+    };
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.transaction_service.get_completed_transactions_page(
+            filter,
+            cursor,
+            page_size as usize,
+        )) {
+        Ok(page) => Box::into_raw(Box::new(page)),
         Err(e) => {
-            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
             ptr::null_mut()
         },
     }
 }
 
-/// Get the TariCompletedTransactions from a TariWallet
+/// Get a page of unspent outputs from a TariWallet, in the order they were received
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `cursor` - The cursor returned by `unblinded_outputs_page_get_next_cursor` for the previous page, or null to fetch
+/// the first page
+/// `page_size` - The maximum number of outputs to return
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariCompletedTransactions` - returns the transactions, note that it returns ptr::null_mut() if
-/// wallet is null or an error is encountered
+/// `*mut TariUnblindedOutputsPage` - returns the page, note that it returns ptr::null_mut() if wallet is null, the
+/// cursor is invalid or an error is encountered
 ///
 /// # Safety
-/// The ```completed_transactions_destroy``` method must be called when finished with a TariCompletedTransactions to
+/// The ```unblinded_outputs_page_destroy``` method must be called when finished with a TariUnblindedOutputsPage to
 /// prevent a memory leak
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_completed_transactions(
+pub unsafe extern "C" fn wallet_get_utxos_page(
     wallet: *mut TariWallet,
+    cursor: *const c_char,
+    page_size: c_uint,
     error_out: *mut c_int,
-) -> *mut TariCompletedTransactions {
+) -> *mut TariUnblindedOutputsPage {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let mut completed = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
         return ptr::null_mut();
     }
 
-    let completed_transactions = (*wallet)
-        .runtime
-        .block_on((*wallet).wallet.transaction_service.get_completed_transactions());
-    match completed_transactions {
-        Ok(completed_transactions) => {
-            // The frontend specification calls for completed transactions that have not yet been mined to be
-            // classified as Pending Transactions. In order to support this logic without impacting the practical
-            // definitions and storage of a MimbleWimble CompletedTransaction we will remove CompletedTransactions with
-            // the Completed and Broadcast states from the list returned by this FFI function
-            for tx in completed_transactions
-                .values()
-                .filter(|ct| ct.status != TransactionStatus::Completed)
-                .filter(|ct| ct.status != TransactionStatus::Broadcast)
-            {
-                completed.push(tx.clone());
-            }
-            Box::into_raw(Box::new(TariCompletedTransactions(completed)))
+    let cursor = match parse_cursor(cursor) {
+        Ok(c) => c,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return ptr::null_mut();
         },
+    };
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.output_manager_service.get_unspent_outputs_page(
+            UnspentOutputFilter::default(),
+            cursor,
+            page_size as usize,
+        )) {
+        Ok(page) => Box::into_raw(Box::new(TariUnblindedOutputsPage {
+            outputs: page.outputs.into_iter().map(|o| o.unblinded_output).collect(),
+            total_count: page.total_count,
+            next_cursor: page.next_cursor,
+        })),
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
             ptr::null_mut()
         },
@@ -4178,7 +5657,10 @@ pub unsafe extern "C" fn wallet_get_completed_transaction_by_id(
                     return Box::into_raw(Box::new(completed));
                 }
             }
-            error = 108;
+            error = LibWalletError::from(WalletError::OutputManagerError(
+                OutputManagerError::OutputManagerStorageError(OutputManagerStorageError::ValueNotFound),
+            ))
+            .code;
             ptr::swap(error_out, &mut error as *mut c_int);
         },
         Err(e) => {
@@ -4251,7 +5733,10 @@ pub unsafe extern "C" fn wallet_get_pending_inbound_transaction_by_id(
                 let pending = tx.clone();
                 return Box::into_raw(Box::new(pending));
             }
-            error = 108;
+            error = LibWalletError::from(WalletError::OutputManagerError(
+                OutputManagerError::OutputManagerStorageError(OutputManagerStorageError::ValueNotFound),
+            ))
+            .code;
             ptr::swap(error_out, &mut error as *mut c_int);
         },
         Err(e) => {
@@ -4324,7 +5809,10 @@ pub unsafe extern "C" fn wallet_get_pending_outbound_transaction_by_id(
                 let pending = tx.clone();
                 return Box::into_raw(Box::new(pending));
             }
-            error = 108;
+            error = LibWalletError::from(WalletError::OutputManagerError(
+                OutputManagerError::OutputManagerStorageError(OutputManagerStorageError::ValueNotFound),
+            ))
+            .code;
             ptr::swap(error_out, &mut error as *mut c_int);
         },
         Err(e) => {
@@ -4586,6 +6074,183 @@ pub unsafe extern "C" fn wallet_cancel_pending_transaction(
     }
 }
 
+/// Creates a schedule of recurring payments to a destination. Payments are made by the wallet while it is running;
+/// payments missed while the wallet was not running are skipped.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `dest_public_key` - The TariPublicKey pointer of the peer
+/// `amount` - The amount of each payment
+/// `fee_per_gram` - The transaction fee
+/// `message` - The pointer to a char array
+/// `interval_secs` - The number of seconds between payments, must be greater than 0
+/// `first_payment_delay_secs` - The number of seconds until the first payment, 0 to make it immediately
+/// `max_payments` - The number of payments after which the schedule ends, 0 for no limit
+/// `end_after_secs` - The number of seconds after which the schedule ends, 0 for no limit
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `unsigned long long` - Returns 0 if unsuccessful or the id of the recurring payment if successful
+///
+/// # Safety
+/// None
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn wallet_create_recurring_payment(
+    wallet: *mut TariWallet,
+    dest_public_key: *mut TariPublicKey,
+    amount: c_ulonglong,
+    fee_per_gram: c_ulonglong,
+    message: *const c_char,
+    interval_secs: c_ulonglong,
+    first_payment_delay_secs: c_ulonglong,
+    max_payments: c_uint,
+    end_after_secs: c_ulonglong,
+    error_out: *mut c_int,
+) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    if dest_public_key.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("dest_public_key".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    let message_string = if !message.is_null() {
+        CStr::from_ptr(message).to_str().unwrap().to_owned()
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        CString::new("").unwrap().to_str().unwrap().to_owned()
+    };
+
+    let schedule = RecurringPaymentSchedule {
+        interval: Duration::from_secs(interval_secs),
+        first_payment_delay: Duration::from_secs(first_payment_delay_secs),
+        max_payments: if max_payments == 0 { None } else { Some(max_payments) },
+        end_after: if end_after_secs == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(end_after_secs))
+        },
+    };
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.recurring_payment_service.create_recurring_payment(
+            (*dest_public_key).clone(),
+            MicroTari::from(amount),
+            MicroTari::from(fee_per_gram),
+            message_string,
+            schedule,
+        )) {
+        Ok(id) => id,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::RecurringPaymentServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Cancel a recurring payment so that no further payments are made
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `recurring_payment_id` - The id of the recurring payment
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - returns whether the recurring payment could be cancelled
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_cancel_recurring_payment(
+    wallet: *mut TariWallet,
+    recurring_payment_id: c_ulonglong,
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet).runtime.block_on(
+        (*wallet)
+            .wallet
+            .recurring_payment_service
+            .cancel_recurring_payment(recurring_payment_id),
+    ) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::RecurringPaymentServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Gets the status of a recurring payment
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `recurring_payment_id` - The id of the recurring payment
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_int` - Returns the status which corresponds to:
+/// | Value | Interpretation |
+/// |---|---|
+/// |  -1 | Error |
+/// |   0 | Active |
+/// |   1 | Completed |
+/// |   2 | Cancelled |
+/// |   3 | Failed |
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_recurring_payment_status(
+    wallet: *mut TariWallet,
+    recurring_payment_id: c_ulonglong,
+    error_out: *mut c_int,
+) -> c_int {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return -1;
+    }
+
+    match (*wallet).runtime.block_on(
+        (*wallet)
+            .wallet
+            .recurring_payment_service
+            .get_recurring_payment(recurring_payment_id),
+    ) {
+        Ok(p) => p.status as c_int,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::RecurringPaymentServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            -1
+        },
+    }
+}
+
 /// This function will tell the wallet to query the set base node to confirm the status of unspent transaction outputs
 /// (UTXOs).
 ///
@@ -5738,6 +7403,24 @@ mod test {
         // assert!(true); //optimized out by compiler
     }
 
+    unsafe extern "C" fn balance_updated_callback(
+        _available: c_ulonglong,
+        _pending_incoming: c_ulonglong,
+        _pending_outgoing: c_ulonglong,
+        _delta: c_longlong,
+        _reason: u8,
+    ) {
+        // assert!(true); //optimized out by compiler
+    }
+
+    unsafe extern "C" fn connectivity_status_callback(_status: u8) {
+        // assert!(true); //optimized out by compiler
+    }
+
+    unsafe extern "C" fn sync_progress_callback(_current: c_ulonglong, _total: c_ulonglong) {
+        // assert!(true); //optimized out by compiler
+    }
+
     unsafe extern "C" fn received_tx_callback_bob(tx: *mut TariPendingInboundTransaction) {
         assert!(!tx.is_null());
         assert_eq!(
@@ -5874,6 +7557,94 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_last_error_details() {
+        unsafe {
+            let mut error = 0;
+            let error_ptr = &mut error as *mut c_int;
+            let bytes_ptr = byte_vector_create(ptr::null_mut(), 20u32, error_ptr);
+            assert!(bytes_ptr.is_null());
+            let code = error;
+            assert_ne!(code, 0);
+
+            let mut accessor_error = 0;
+            let accessor_error_ptr = &mut accessor_error as *mut c_int;
+            let err = wallet_get_last_error();
+            assert!(!err.is_null());
+            assert_eq!(error_get_code(err, accessor_error_ptr), code);
+            let domain = error_get_domain(err, accessor_error_ptr);
+            assert_eq!(CStr::from_ptr(domain).to_str().unwrap(), "interface");
+            let message = error_get_message(err, accessor_error_ptr);
+            assert!(CStr::from_ptr(message).to_str().unwrap().contains("byte_array"));
+            assert!(!error_is_retryable(err, accessor_error_ptr));
+            assert_eq!(accessor_error, 0);
+            string_destroy(domain);
+            string_destroy(message);
+            error_destroy(err);
+
+            let code = LibWalletError::from(WalletError::TransactionServiceError(
+                TransactionServiceError::OutputManagerError(OutputManagerError::FundsPending),
+            ))
+            .code;
+            let err = wallet_get_last_error();
+            assert_eq!(error_get_code(err, accessor_error_ptr), code);
+            let domain = error_get_domain(err, accessor_error_ptr);
+            assert_eq!(CStr::from_ptr(domain).to_str().unwrap(), "output_manager");
+            assert!(error_is_retryable(err, accessor_error_ptr));
+            string_destroy(domain);
+            error_destroy(err);
+
+            assert_eq!(error_get_code(ptr::null_mut(), accessor_error_ptr), 0);
+            assert_eq!(
+                accessor_error,
+                LibWalletError::from(InterfaceError::NullError("err".to_string())).code
+            );
+        }
+    }
+
+    #[test]
+    fn test_amount_format_and_parse() {
+        unsafe {
+            let mut error = 0;
+            let error_ptr = &mut error as *mut c_int;
+            let comma = CString::new(",").unwrap();
+            let dot = CString::new(".").unwrap();
+
+            let formatted = amount_format(1_234_565_000, 0, 2, comma.as_ptr(), dot.as_ptr(), error_ptr);
+            assert_eq!(error, 0);
+            assert_eq!(CStr::from_ptr(formatted).to_str().unwrap(), "1,234.57 T");
+            string_destroy(formatted);
+
+            let formatted = amount_format(1_234_565_000, 0, 6, dot.as_ptr(), comma.as_ptr(), error_ptr);
+            assert_eq!(error, 0);
+            assert_eq!(CStr::from_ptr(formatted).to_str().unwrap(), "1.234,565000 T");
+            let parsed = amount_parse(formatted, dot.as_ptr(), comma.as_ptr(), error_ptr);
+            assert_eq!(error, 0);
+            assert_eq!(parsed, 1_234_565_000);
+            string_destroy(formatted);
+
+            let micro = CString::new("1234560 uT").unwrap();
+            let parsed = amount_parse(micro.as_ptr(), ptr::null(), ptr::null(), error_ptr);
+            assert_eq!(error, 0);
+            assert_eq!(parsed, 1_234_560);
+
+            let garbage = CString::new("1.0000001 T").unwrap();
+            let parsed = amount_parse(garbage.as_ptr(), comma.as_ptr(), dot.as_ptr(), error_ptr);
+            assert_eq!(parsed, 0);
+            assert_eq!(
+                error,
+                LibWalletError::from(InterfaceError::InvalidAmount(String::new())).code
+            );
+
+            let formatted = amount_format(1, 3, 2, comma.as_ptr(), comma.as_ptr(), error_ptr);
+            assert_eq!(
+                error,
+                LibWalletError::from(InterfaceError::InvalidAmount(String::new())).code
+            );
+            string_destroy(formatted);
+        }
+    }
+
     #[test]
     fn test_emoji_set() {
         unsafe {
@@ -5903,6 +7674,36 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_emoji_id_suggestions() {
+        unsafe {
+            let mut error = 0;
+            let error_ptr = &mut error as *mut c_int;
+            let public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+            let emoji_id = EmojiId::from_pubkey(&public_key);
+            let mut chars = emoji_id.as_str().chars().collect::<Vec<_>>();
+            chars.remove(5);
+            let typo = CString::new(chars.iter().collect::<String>()).unwrap();
+
+            let suggestions = emoji_id_get_suggestions(typo.as_ptr(), error_ptr);
+            assert_eq!(error, 0);
+            let len = emoji_id_suggestions_get_length(suggestions, error_ptr);
+            assert!(len > 0);
+            let mut found = false;
+            for i in 0..len {
+                let suggestion = emoji_id_suggestions_get_at(suggestions, i, error_ptr);
+                assert_eq!(error, 0);
+                found |= CStr::from_ptr(suggestion).to_str().unwrap() == emoji_id.as_str();
+                string_destroy(suggestion);
+            }
+            assert!(found);
+            let suggestion = emoji_id_suggestions_get_at(suggestions, len, error_ptr);
+            assert_eq!(error, LibWalletError::from(InterfaceError::PositionInvalidError).code);
+            string_destroy(suggestion);
+            emoji_id_suggestions_destroy(suggestions);
+        }
+    }
+
     #[test]
     fn test_transport_type_memory() {
         unsafe {
@@ -6152,6 +7953,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
             let secret_key_bob = private_key_generate();
@@ -6200,6 +8004,9 @@ mod test {
                 invalid_txo_validation_complete_callback_bob,
                 transaction_validation_complete_callback_bob,
                 saf_messages_received_callback_bob,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
 
@@ -6724,6 +8531,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
 
@@ -6763,6 +8573,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
 
@@ -6861,6 +8674,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
             let generated = wallet_test_generate_data(alice_wallet, db_path_alice_str, error_ptr);
@@ -6909,6 +8725,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
 
@@ -6939,6 +8758,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
             assert_eq!(error, 428);
@@ -6964,6 +8786,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
 
@@ -7009,6 +8834,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
 
@@ -7077,6 +8905,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
 
@@ -7220,6 +9051,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
 
@@ -7270,6 +9104,9 @@ mod test {
                 invalid_txo_validation_complete_callback,
                 transaction_validation_complete_callback,
                 saf_messages_received_callback,
+                balance_updated_callback,
+                connectivity_status_callback,
+                sync_progress_callback,
                 error_ptr,
             );
             assert_eq!(error, 0);
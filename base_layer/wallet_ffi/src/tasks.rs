@@ -90,11 +90,15 @@ pub async fn recovery_event_monitoring(
             Ok(UtxoScannerEvent::Progress {
                 current_block: current,
                 current_chain_height: total,
+                outputs_per_second,
             }) => {
                 unsafe {
                     (recovery_progress_callback)(RecoveryEvent::Progress as u8, current, total);
                 }
-                info!(target: LOG_TARGET, "Recovery progress: {}/{}", current, total);
+                info!(
+                    target: LOG_TARGET,
+                    "Recovery progress: {}/{} ({} outputs/s)", current, total, outputs_per_second
+                );
             },
             Ok(UtxoScannerEvent::Completed {
                 number_scanned: num_scanned,
@@ -59,6 +59,12 @@ struct TariContact;
 
 struct TariCompletedTransactions;
 
+struct TariCompletedTransactionsPage;
+
+struct TariUnblindedOutput;
+
+struct TariUnblindedOutputsPage;
+
 struct TariCompletedTransaction;
 
 struct TariPendingOutboundTransactions;
@@ -75,12 +81,16 @@ struct TariSeedWords;
 
 struct EmojiSet;
 
+struct TariEmojiIdSuggestions;
+
 struct TariExcess;
 
 struct TariExcessPublicNonce;
 
 struct TariExcessSignature;
 
+struct TariError;
+
 /// -------------------------------- Transport Types ----------------------------------------------- ///
 
 // Creates a memory transport type
@@ -109,6 +119,36 @@ void transport_type_destroy(struct TariTransportType *transport);
 // Frees memory for a string pointer
 void string_destroy(char *s);
 
+/// -------------------------------- Errors ----------------------------------------------- ///
+
+// Gets the details of the most recent error raised on this thread, or null if there has been none. Functions report
+// failure by setting error_out to a non-zero code; this returns the error behind that code.
+struct TariError *wallet_get_last_error();
+
+// Gets the code of an error, which is the same code that was returned through error_out
+int error_get_code(struct TariError *err, int* error_out);
+
+// Gets the part of the wallet an error originated from, e.g. "output_manager" or "transaction_service"
+char *error_get_domain(struct TariError *err, int* error_out);
+
+// Gets the human readable message of an error
+char *error_get_message(struct TariError *err, int* error_out);
+
+// Checks whether the operation that raised an error may succeed if it is retried later
+bool error_is_retryable(struct TariError *err, int* error_out);
+
+// Frees memory for an error
+void error_destroy(struct TariError *err);
+
+/// -------------------------------- Amounts ----------------------------------------------- ///
+
+// Formats an amount in MicroTari for display. unit is 0 for Tari, 1 for MicroTari or 2 to choose automatically.
+// Separators are single characters, a null or empty thousands_separator disables grouping.
+char *amount_format(unsigned long long amount, unsigned int unit, unsigned int precision, const char *thousands_separator, const char *decimal_separator, int* error_out);
+
+// Parses a user-entered amount such as "1,234.56 T" or "1234560 uT" and returns it in MicroTari
+unsigned long long amount_parse(const char *amount, const char *thousands_separator, const char *decimal_separator, int* error_out);
+
 /// -------------------------------- ByteVector ----------------------------------------------- ///
 
 // Creates a ByteVector
@@ -146,6 +186,18 @@ char *public_key_to_emoji_id(struct TariPublicKey *pk, int* error_out);
 // Converts a char array in emoji format to a public key
 struct TariPublicKey *emoji_id_to_public_key(const char *emoji,  int* error_out);
 
+// Gets the valid emoji IDs that are a single substituted, missing, extra or swapped emoji away from a mistyped emoji ID
+struct TariEmojiIdSuggestions *emoji_id_get_suggestions(const char *emoji, int* error_out);
+
+// Gets the number of suggestions in a TariEmojiIdSuggestions
+unsigned int emoji_id_suggestions_get_length(struct TariEmojiIdSuggestions *suggestions, int* error_out);
+
+// Gets the suggested emoji ID at position in a TariEmojiIdSuggestions
+char *emoji_id_suggestions_get_at(struct TariEmojiIdSuggestions *suggestions, unsigned int position, int* error_out);
+
+// Frees memory for a TariEmojiIdSuggestions
+void emoji_id_suggestions_destroy(struct TariEmojiIdSuggestions *suggestions);
+
 /// -------------------------------- TariPrivateKey ----------------------------------------------- ///
 
 // Creates a TariPrivateKey from a ByteVector
@@ -261,6 +313,9 @@ bool completed_transaction_is_valid(struct TariCompletedTransaction *tx,int* err
 // i.e the transaction was originally sent from the wallet
 bool completed_transaction_is_outbound(struct TariCompletedTransaction *tx,int* error_out);
 
+// Checks if a TariCompletedTransaction is a transfer between the wallet's own accounts, such as a send to self
+bool completed_transaction_is_internal(struct TariCompletedTransaction *tx,int* error_out);
+
 /// Gets the number of confirmations of a TariCompletedTransaction
 unsigned long long completed_transaction_get_confirmations(struct TariCompletedTransaction *transaction,int* error_out);
 
@@ -296,6 +351,51 @@ struct TariCompletedTransaction *completed_transactions_get_at(struct TariComple
 // Frees memory for a TariCompletedTransactions
 void completed_transactions_destroy(struct TariCompletedTransactions *transactions);
 
+/// -------------------------------- CompletedTransactionsPage ------------------------------------------------ ///
+
+// Gets number of transactions in a TariCompletedTransactionsPage
+unsigned int completed_transactions_page_get_length(struct TariCompletedTransactionsPage *page,int* error_out);
+
+// Gets a TariCompletedTransaction from a TariCompletedTransactionsPage at position
+struct TariCompletedTransaction *completed_transactions_page_get_at(struct TariCompletedTransactionsPage *page, unsigned int position,int* error_out);
+
+// Gets the total number of transactions across all pages
+unsigned long long completed_transactions_page_get_total_count(struct TariCompletedTransactionsPage *page,int* error_out);
+
+// Gets the cursor for the next page, or null if this is the last page
+char *completed_transactions_page_get_next_cursor(struct TariCompletedTransactionsPage *page,int* error_out);
+
+// Frees memory for a TariCompletedTransactionsPage
+void completed_transactions_page_destroy(struct TariCompletedTransactionsPage *page);
+
+/// -------------------------------- UnblindedOutput ---------------------------------------------------------- ///
+
+// Gets the value of a TariUnblindedOutput
+unsigned long long unblinded_output_get_value(struct TariUnblindedOutput *output,int* error_out);
+
+// Gets the maturity of a TariUnblindedOutput
+unsigned long long unblinded_output_get_maturity(struct TariUnblindedOutput *output,int* error_out);
+
+// Frees memory for a TariUnblindedOutput
+void unblinded_output_destroy(struct TariUnblindedOutput *output);
+
+/// -------------------------------- UnblindedOutputsPage ----------------------------------------------------- ///
+
+// Gets number of outputs in a TariUnblindedOutputsPage
+unsigned int unblinded_outputs_page_get_length(struct TariUnblindedOutputsPage *page,int* error_out);
+
+// Gets a TariUnblindedOutput from a TariUnblindedOutputsPage at position
+struct TariUnblindedOutput *unblinded_outputs_page_get_at(struct TariUnblindedOutputsPage *page, unsigned int position,int* error_out);
+
+// Gets the total number of unspent outputs across all pages
+unsigned long long unblinded_outputs_page_get_total_count(struct TariUnblindedOutputsPage *page,int* error_out);
+
+// Gets the cursor for the next page, or null if this is the last page
+char *unblinded_outputs_page_get_next_cursor(struct TariUnblindedOutputsPage *page,int* error_out);
+
+// Frees memory for a TariUnblindedOutputsPage
+void unblinded_outputs_page_destroy(struct TariUnblindedOutputsPage *page);
+
 /// -------------------------------- OutboundTransaction ------------------------------------------------------ ///
 
 // Gets the TransactionId of a TariPendingOutboundTransaction
@@ -441,6 +541,13 @@ void comms_config_destroy(struct TariCommsConfig *wc);
 /// `callback_saf_message_received` - The callback function pointer that will be called when the Dht has determined that
 /// is has connected to enough of its neighbours to be confident that it has received any SAF messages that were waiting
 /// for it.
+/// `callback_balance_updated` - The callback function pointer matching the function signature. This is called when
+/// the balance of the wallet changes, with the available, pending incoming and pending outgoing balances, the change in
+/// the available plus pending incoming balance and a u8 that represents the BalanceChangeReason enum.
+/// `callback_connectivity_status` - The callback function pointer matching the function signature. This is called when
+/// the connection state of the base node changes, with a u8 that represents the ConnectivityStatus enum.
+/// `callback_sync_progress` - The callback function pointer matching the function signature. This is called with the
+/// current block and the chain height while the wallet scans the blockchain for its outputs
 /// `error_out` - Pointer to an int which will be modified
 /// to an error code should one occur, may not be null. Functions as an out parameter.
 /// ## Returns
@@ -457,6 +564,25 @@ void comms_config_destroy(struct TariCommsConfig *wc);
 ///        Failure,           // 2
 ///        BaseNodeNotInSync, // 3
 ///    }
+///
+/// The BalanceChangeReason enum can return the following values:
+/// enum BalanceChangeReason {
+///        TransactionReceived,  // 0
+///        TransactionSent,      // 1
+///        TransactionMined,     // 2
+///        TransactionCancelled, // 3
+///        OutputsValidated,     // 4
+///        OutputsRecovered,     // 5
+///        ChainReorg,           // 6
+///        OneSidedReclaimed,    // 7
+///    }
+///
+/// The ConnectivityStatus enum can return the following values:
+/// enum ConnectivityStatus {
+///        Connecting, // 0
+///        Online,     // 1
+///        Offline,    // 2
+///    }
 struct TariWallet *wallet_create(struct TariWalletConfig *config,
                                     const char *log_path,
                                     unsigned int num_rolling_log_files,
@@ -477,6 +603,9 @@ struct TariWallet *wallet_create(struct TariWalletConfig *config,
                                     void (*callback_invalid_txo_validation_complete)(unsigned long long, unsigned char),
                                     void (*callback_transaction_validation_complete)(unsigned long long, unsigned char),
                                     void (*callback_saf_message_received)(),
+                                    void (*callback_balance_updated)(unsigned long long, unsigned long long, unsigned long long, long long, unsigned char),
+                                    void (*callback_connectivity_status)(unsigned char),
+                                    void (*callback_sync_progress)(unsigned long long, unsigned long long),
                                     int* error_out);
 
 // Signs a message
@@ -485,6 +614,12 @@ char* wallet_sign_message(struct TariWallet *wallet, const char* msg, int* error
 // Verifies signature for a signed message
 bool wallet_verify_message_signature(struct TariWallet *wallet, struct TariPublicKey *public_key, const char* hex_sig_nonce, const char* msg, int* error_out);
 
+// Generates a base64 encoded proof that this wallet paid the receiver of a completed outbound transaction
+char* wallet_generate_payment_proof(struct TariWallet *wallet, unsigned long long transaction_id, int* error_out);
+
+// Verifies a payment proof, and if this wallet is the receiver, that it owns the paid output
+bool wallet_verify_payment_proof(struct TariWallet *wallet, const char* proof, int* error_out);
+
 /// Generates test data
 bool wallet_test_generate_data(struct TariWallet *wallet, const char *datastore_path,int* error_out);
 
@@ -497,6 +632,13 @@ bool wallet_upsert_contact(struct TariWallet *wallet, struct TariContact *contac
 // Removes a TariContact form the TariWallet
 bool wallet_remove_contact(struct TariWallet *wallet, struct TariContact *contact,int* error_out);
 
+// Exports all contacts of the TariWallet to a contact bundle file. The bundle is encrypted if passphrase is not null
+bool wallet_export_contacts(struct TariWallet *wallet, const char *file_path, const char *passphrase, int* error_out);
+
+// Imports the contacts in a contact bundle file into the TariWallet, returning the number of contacts added or updated.
+// The passphrase may be null if the bundle is not encrypted
+unsigned int wallet_import_contacts(struct TariWallet *wallet, const char *file_path, const char *passphrase, int* error_out);
+
 // Gets the available balance from a TariWallet
 unsigned long long wallet_get_available_balance(struct TariWallet *wallet,int* error_out);
 
@@ -519,12 +661,30 @@ void wallet_set_num_confirmations_required(struct TariWallet *wallet, unsigned l
 // Sends a TariPendingOutboundTransaction
 unsigned long long wallet_send_transaction(struct TariWallet *wallet, struct TariPublicKey *destination, unsigned long long amount, unsigned long long fee_per_gram,const char *message,int* error_out);
 
+// Creates a named account in a TariWallet whose keys are derived from a separate branch of the master key
+bool wallet_create_account(struct TariWallet *wallet, const char *account, int* error_out);
+
+// Gets the available balance of a named account in a TariWallet
+unsigned long long wallet_get_account_available_balance(struct TariWallet *wallet, const char *account, int* error_out);
+
+// Sends a TariPendingOutboundTransaction that only spends outputs belonging to the named account
+unsigned long long wallet_send_transaction_from_account(struct TariWallet *wallet, const char *account, struct TariPublicKey *destination, unsigned long long amount, unsigned long long fee_per_gram, const char *message, int* error_out);
+
 // Get the TariContacts from a TariWallet
 struct TariContacts *wallet_get_contacts(struct TariWallet *wallet,int* error_out);
 
+// Search the contacts of a TariWallet by alias, emoji ID or hex public key. Emoji IDs with a few typos still match.
+struct TariContacts *wallet_search_contacts(struct TariWallet *wallet, const char *query, int* error_out);
+
 // Get the TariCompletedTransactions from a TariWallet
 struct TariCompletedTransactions *wallet_get_completed_transactions(struct TariWallet *wallet,int* error_out);
 
+// Get a page of completed transactions from a TariWallet, pass a null cursor for the first page
+struct TariCompletedTransactionsPage *wallet_get_transactions_page(struct TariWallet *wallet, const char *cursor, unsigned int page_size, bool include_pending, int* error_out);
+
+// Get a page of unspent outputs from a TariWallet, pass a null cursor for the first page
+struct TariUnblindedOutputsPage *wallet_get_utxos_page(struct TariWallet *wallet, const char *cursor, unsigned int page_size, int* error_out);
+
 // Get the TariPendingOutboundTransactions from a TariWallet
 struct TariPendingOutboundTransactions *wallet_get_pending_outbound_transactions(struct TariWallet *wallet,int* error_out);
 
@@ -593,6 +753,15 @@ bool wallet_test_receive_transaction(struct TariWallet *wallet,int* error_out);
 /// Cancel a Pending Outbound Transaction
 bool wallet_cancel_pending_transaction(struct TariWallet *wallet, unsigned long long transaction_id, int* error_out);
 
+/// Create a schedule of recurring payments, returns the id of the schedule
+unsigned long long wallet_create_recurring_payment(struct TariWallet *wallet, struct TariPublicKey *dest_public_key, unsigned long long amount, unsigned long long fee_per_gram, const char *message, unsigned long long interval_secs, unsigned long long first_payment_delay_secs, unsigned int max_payments, unsigned long long end_after_secs, int* error_out);
+
+/// Cancel a recurring payment
+bool wallet_cancel_recurring_payment(struct TariWallet *wallet, unsigned long long recurring_payment_id, int* error_out);
+
+/// Get the status of a recurring payment
+int wallet_get_recurring_payment_status(struct TariWallet *wallet, unsigned long long recurring_payment_id, int* error_out);
+
 /// Perform a coin split
 unsigned long long wallet_coin_split(struct TariWallet *wallet, unsigned long long amount, unsigned long long count, unsigned long long fee, const char* msg, unsigned long long lock_height, int* error_out);
 
@@ -146,6 +146,18 @@ pub struct ConfigBootstrap {
     pub miner_min_diff: Option<u64>,
     #[structopt(long, alias = "max-difficulty")]
     pub miner_max_diff: Option<u64>,
+    /// Export the node identity and peer database to the given file and exit
+    #[structopt(long, alias = "export_identity", parse(from_os_str))]
+    pub export_identity: Option<PathBuf>,
+    /// Import the node identity and peer database from a file created with --export-identity and exit
+    #[structopt(long, alias = "import_identity", parse(from_os_str))]
+    pub import_identity: Option<PathBuf>,
+    /// Change (or set) the passphrase that the node identity file is encrypted with and exit
+    #[structopt(long, alias = "change_identity_passphrase")]
+    pub change_identity_passphrase: bool,
+    /// Report the blockchain database migrations that would be run at startup, without running them, and exit
+    #[structopt(long, alias = "migrate_db_dry_run")]
+    pub migrate_db_dry_run: bool,
 }
 
 fn normalize_path(path: PathBuf) -> PathBuf {
@@ -180,6 +192,10 @@ impl Default for ConfigBootstrap {
             miner_max_blocks: None,
             miner_min_diff: None,
             miner_max_diff: None,
+            export_identity: None,
+            import_identity: None,
+            change_identity_passphrase: false,
+            migrate_db_dry_run: false,
         }
     }
 }
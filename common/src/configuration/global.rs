@@ -63,6 +63,10 @@ pub struct GlobalConfig {
     pub listnener_liveness_max_sessions: usize,
     pub listener_liveness_allowlist_cidrs: Vec<String>,
     pub rpc_max_simultaneous_sessions: Option<usize>,
+    pub rpc_max_in_flight_messages: usize,
+    pub rpc_slow_consumer_timeout: Option<Duration>,
+    pub rpc_max_concurrent_requests: Option<usize>,
+    pub rpc_max_concurrent_requests_per_peer: Option<usize>,
     pub data_dir: PathBuf,
     pub db_type: DatabaseType,
     pub db_config: LMDBConfig,
@@ -70,6 +74,18 @@ pub struct GlobalConfig {
     pub orphan_db_clean_out_threshold: usize,
     pub pruning_horizon: u64,
     pub pruned_mode_cleanup_interval: u64,
+    pub db_group_commit_max_operations: usize,
+    pub db_group_commit_interval: Duration,
+    pub mempool_relay_max_script_size: Option<usize>,
+    pub mempool_relay_min_fee_per_gram: Option<u64>,
+    pub mempool_relay_max_outputs: Option<usize>,
+    pub mempool_relay_min_fee_per_output: Option<u64>,
+    pub mempool_max_txs_per_unique_id: Option<usize>,
+    pub mempool_max_weight_per_sender: Option<u64>,
+    pub watchdog_stale_tip_warning: Duration,
+    pub watchdog_stale_tip_critical: Duration,
+    pub watchdog_max_tip_divergence: u64,
+    pub watchdog_webhook_url: Option<String>,
     pub core_threads: Option<usize>,
     pub max_threads: Option<usize>,
     pub base_node_identity_file: PathBuf,
@@ -77,10 +93,19 @@ pub struct GlobalConfig {
     pub grpc_enabled: bool,
     pub grpc_base_node_address: SocketAddr,
     pub grpc_console_wallet_address: SocketAddr,
+    pub grpc_tls_cert_file: Option<PathBuf>,
+    pub grpc_tls_key_file: Option<PathBuf>,
+    pub grpc_read_only_token: Option<String>,
+    pub grpc_spend_token: Option<String>,
+    pub grpc_admin_token: Option<String>,
+    pub grpc_allow_unauthenticated: bool,
+    pub metrics_server_address: Option<SocketAddr>,
+    pub http_api_address: Option<SocketAddr>,
     pub peer_seeds: Vec<String>,
     pub dns_seeds: Vec<String>,
     pub dns_seeds_name_server: SocketAddr,
     pub dns_seeds_use_dnssec: bool,
+    pub min_peer_minor_version: u32,
     pub peer_db_path: PathBuf,
     pub enable_wallet: bool,
     pub num_mining_threads: usize,
@@ -96,6 +121,7 @@ pub struct GlobalConfig {
     pub buffer_rate_limit_base_node: usize,
     pub buffer_rate_limit_base_node_wallet: usize,
     pub dedup_cache_capacity: usize,
+    pub dedup_bloom_retention: Duration,
     pub fetch_blocks_timeout: Duration,
     pub fetch_utxos_timeout: Duration,
     pub service_request_timeout: Duration,
@@ -115,6 +141,9 @@ pub struct GlobalConfig {
     pub wallet_base_node_service_refresh_interval: u64,
     pub wallet_base_node_service_request_max_age: u64,
     pub prevent_fee_gt_amount: bool,
+    pub wallet_verify_utxo_proofs: bool,
+    pub wallet_max_unconfirmed_change_depth: usize,
+    pub wallet_one_sided_reclaim_lock_blocks: Option<u64>,
     pub monerod_url: String,
     pub monerod_username: String,
     pub monerod_password: String,
@@ -261,6 +290,74 @@ fn convert_node_config(
         .get_int(&key)
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as u64;
 
+    let key = config_string("base_node", &net_str, "db_group_commit_max_operations");
+    let db_group_commit_max_operations = optional(cfg.get_int(&key))
+        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
+        .unwrap_or(1000) as usize;
+
+    let key = config_string("base_node", &net_str, "db_group_commit_interval_ms");
+    let db_group_commit_interval = Duration::from_millis(
+        optional(cfg.get_int(&key))
+            .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
+            .unwrap_or(500) as u64,
+    );
+
+    // Mempool relay policy
+    let key = config_string("mempool", &net_str, "relay_max_script_size");
+    let mempool_relay_max_script_size =
+        optional(cfg.get_int(&key).map(|n| n as usize)).map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+
+    let key = config_string("mempool", &net_str, "relay_min_fee_per_gram");
+    let mempool_relay_min_fee_per_gram =
+        optional(cfg.get_int(&key).map(|n| n as u64)).map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+
+    let key = config_string("mempool", &net_str, "relay_max_outputs");
+    let mempool_relay_max_outputs =
+        optional(cfg.get_int(&key).map(|n| n as usize)).map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+
+    let key = config_string("mempool", &net_str, "relay_min_fee_per_output");
+    let mempool_relay_min_fee_per_output =
+        optional(cfg.get_int(&key).map(|n| n as u64)).map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+
+    // Mempool admission limits
+    let key = config_string("mempool", &net_str, "max_txs_per_unique_id");
+    let mempool_max_txs_per_unique_id =
+        optional(cfg.get_int(&key).map(|n| n as usize)).map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+
+    let key = config_string("mempool", &net_str, "max_weight_per_sender");
+    let mempool_max_weight_per_sender =
+        optional(cfg.get_int(&key).map(|n| n as u64)).map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+
+    // Stale tip watchdog
+    let key = config_string("base_node", &net_str, "watchdog_stale_tip_warning");
+    let watchdog_stale_tip_warning = Duration::from_secs(
+        optional(cfg.get_int(&key))
+            .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
+            .unwrap_or(1200) as u64,
+    );
+
+    let key = config_string("base_node", &net_str, "watchdog_stale_tip_critical");
+    let watchdog_stale_tip_critical = Duration::from_secs(
+        optional(cfg.get_int(&key))
+            .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
+            .unwrap_or(3600) as u64,
+    );
+    if watchdog_stale_tip_critical < watchdog_stale_tip_warning {
+        return Err(ConfigurationError::new(
+            &key,
+            "The critical stale tip threshold cannot be shorter than the warning threshold",
+        ));
+    }
+
+    let key = config_string("base_node", &net_str, "watchdog_max_tip_divergence");
+    let watchdog_max_tip_divergence = optional(cfg.get_int(&key))
+        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
+        .unwrap_or(10) as u64;
+
+    let key = config_string("base_node", &net_str, "watchdog_webhook_url");
+    let watchdog_webhook_url =
+        optional(cfg.get_str(&key)).map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+
     // Thread counts
     let key = config_string("base_node", &net_str, "core_threads");
     let core_threads =
@@ -353,6 +450,43 @@ fn convert_node_config(
                 .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
         })?;
 
+    // gRPC TLS and authentication
+    let key = config_string("base_node", &net_str, "grpc_tls_cert_file");
+    let grpc_tls_cert_file = optional(cfg.get_str(&key))?.map(PathBuf::from);
+    let key = config_string("base_node", &net_str, "grpc_tls_key_file");
+    let grpc_tls_key_file = optional(cfg.get_str(&key))?.map(PathBuf::from);
+    if grpc_tls_cert_file.is_some() != grpc_tls_key_file.is_some() {
+        return Err(ConfigurationError::new(
+            &key,
+            "grpc_tls_cert_file and grpc_tls_key_file must be set together",
+        ));
+    }
+
+    let key = config_string("base_node", &net_str, "grpc_read_only_token");
+    let grpc_read_only_token = optional(cfg.get_str(&key))?;
+    let key = config_string("base_node", &net_str, "grpc_spend_token");
+    let grpc_spend_token = optional(cfg.get_str(&key))?;
+    let key = config_string("base_node", &net_str, "grpc_admin_token");
+    let grpc_admin_token = optional(cfg.get_str(&key))?;
+    let key = config_string("base_node", &net_str, "grpc_allow_unauthenticated");
+    let grpc_allow_unauthenticated = optional(cfg.get_bool(&key))?.unwrap_or(false);
+
+    let key = config_string("base_node", &net_str, "metrics_server_address");
+    let metrics_server_address = optional(cfg.get_str(&key))?
+        .map(|addr| {
+            addr.parse::<SocketAddr>()
+                .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
+        })
+        .transpose()?;
+
+    let key = config_string("base_node", &net_str, "http_api_address");
+    let http_api_address = optional(cfg.get_str(&key))?
+        .map(|addr| {
+            addr.parse::<SocketAddr>()
+                .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
+        })
+        .transpose()?;
+
     // Peer and DNS seeds
     let key = config_string("base_node", &net_str, "peer_seeds");
     // Peer seeds can be an array or a comma separated list (e.g. in an ENVVAR)
@@ -378,6 +512,11 @@ fn convert_node_config(
         .get_bool(&key)
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
 
+    let key = config_string("base_node", &net_str, "min_peer_minor_version");
+    let min_peer_minor_version = optional(cfg.get_int(&key))
+        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
+        .unwrap_or(0) as u32;
+
     let key = config_string("base_node", &net_str, "dns_seeds");
     let dns_seeds = optional(cfg.get_array(&key))?
         .unwrap_or_default()
@@ -481,6 +620,15 @@ fn convert_node_config(
         .get_bool(&key)
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
 
+    let key = "wallet.verify_utxo_proofs";
+    let wallet_verify_utxo_proofs = optional(cfg.get_bool(key))?.unwrap_or(false);
+
+    let key = "wallet.max_unconfirmed_change_depth";
+    let wallet_max_unconfirmed_change_depth = optional(cfg.get_int(key))?.unwrap_or(0).max(0) as usize;
+
+    let key = "wallet.one_sided_reclaim_lock_blocks";
+    let wallet_one_sided_reclaim_lock_blocks = optional(cfg.get_int(key))?.filter(|n| *n > 0).map(|n| n as u64);
+
     let key = "wallet.transaction_routing_mechanism";
     let transaction_routing_mechanism =
         optional(cfg.get_str(key))?.unwrap_or_else(|| "DirectAndStoreAndForward".to_string());
@@ -547,6 +695,57 @@ fn convert_node_config(
             )),
         })?;
 
+    let key = "common.rpc_max_in_flight_messages";
+    let rpc_max_in_flight_messages = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            n if n.is_positive() => Ok(n as usize),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for rpc_max_in_flight_messages", v),
+            )),
+        })?;
+
+    let key = "common.rpc_slow_consumer_timeout";
+    let rpc_slow_consumer_timeout = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            0 => Ok(None),
+            n if n.is_positive() => Ok(Some(Duration::from_secs(n as u64))),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for rpc_slow_consumer_timeout", v),
+            )),
+        })?;
+
+    let key = "common.rpc_max_concurrent_requests";
+    let rpc_max_concurrent_requests = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            -1 => Ok(None),
+            n if n.is_positive() => Ok(Some(n as usize)),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for rpc_max_concurrent_requests", v),
+            )),
+        })?;
+
+    let key = "common.rpc_max_concurrent_requests_per_peer";
+    let rpc_max_concurrent_requests_per_peer = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            -1 => Ok(None),
+            n if n.is_positive() => Ok(Some(n as usize)),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for rpc_max_concurrent_requests_per_peer", v),
+            )),
+        })?;
+
     let key = "common.buffer_size_base_node";
     let buffer_size_base_node = cfg
         .get_int(&key)
@@ -572,6 +771,12 @@ fn convert_node_config(
         .get_int(&key)
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as usize;
 
+    let key = "common.dedup_bloom_retention";
+    let dedup_bloom_retention = Duration::from_secs(
+        cfg.get_int(&key)
+            .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as u64,
+    );
+
     let key = "common.fetch_blocks_timeout";
     let fetch_blocks_timeout = Duration::from_secs(
         cfg.get_int(&key)
@@ -687,6 +892,10 @@ fn convert_node_config(
         listnener_liveness_max_sessions: liveness_max_sessions,
         listener_liveness_allowlist_cidrs: liveness_allowlist_cidrs,
         rpc_max_simultaneous_sessions,
+        rpc_max_in_flight_messages,
+        rpc_slow_consumer_timeout,
+        rpc_max_concurrent_requests,
+        rpc_max_concurrent_requests_per_peer,
         data_dir,
         db_type,
         db_config,
@@ -694,6 +903,18 @@ fn convert_node_config(
         orphan_db_clean_out_threshold,
         pruning_horizon,
         pruned_mode_cleanup_interval,
+        db_group_commit_max_operations,
+        db_group_commit_interval,
+        mempool_relay_max_script_size,
+        mempool_relay_min_fee_per_gram,
+        mempool_relay_max_outputs,
+        mempool_relay_min_fee_per_output,
+        mempool_max_txs_per_unique_id,
+        mempool_max_weight_per_sender,
+        watchdog_stale_tip_warning,
+        watchdog_stale_tip_critical,
+        watchdog_max_tip_divergence,
+        watchdog_webhook_url,
         core_threads,
         max_threads,
         base_node_identity_file,
@@ -701,10 +922,19 @@ fn convert_node_config(
         grpc_enabled,
         grpc_base_node_address,
         grpc_console_wallet_address,
+        grpc_tls_cert_file,
+        grpc_tls_key_file,
+        grpc_read_only_token,
+        grpc_spend_token,
+        grpc_admin_token,
+        grpc_allow_unauthenticated,
+        metrics_server_address,
+        http_api_address,
         peer_seeds,
         dns_seeds,
         dns_seeds_name_server,
         dns_seeds_use_dnssec,
+        min_peer_minor_version,
         peer_db_path,
         enable_wallet,
         num_mining_threads,
@@ -720,6 +950,7 @@ fn convert_node_config(
         buffer_rate_limit_base_node,
         buffer_rate_limit_base_node_wallet,
         dedup_cache_capacity,
+        dedup_bloom_retention,
         fetch_blocks_timeout,
         fetch_utxos_timeout,
         service_request_timeout,
@@ -739,6 +970,9 @@ fn convert_node_config(
         wallet_base_node_service_refresh_interval,
         wallet_base_node_service_request_max_age,
         prevent_fee_gt_amount,
+        wallet_verify_utxo_proofs,
+        wallet_max_unconfirmed_change_depth,
+        wallet_one_sided_reclaim_lock_blocks,
         proxy_host_address,
         transcoder_host_address,
         proxy_submit_to_origin,
@@ -824,11 +1058,17 @@ fn network_transport_config(
             let tor_socks_address = get_conf_multiaddr(&key).ok();
             let key = config_string(app_str, network, "tcp_tor_socks_auth");
             let tor_socks_auth = get_conf_str(&key).ok().and_then(|auth_str| auth_str.parse().ok());
+            let key = config_string(app_str, network, "tcp_outbound_socks_address");
+            let outbound_socks_address = get_conf_multiaddr(&key).ok();
+            let key = config_string(app_str, network, "tcp_outbound_socks_auth");
+            let outbound_socks_auth = get_conf_str(&key).ok().and_then(|auth_str| auth_str.parse().ok());
 
             Ok(CommsTransport::Tcp {
                 listener_address,
                 tor_socks_auth,
                 tor_socks_address,
+                outbound_socks_address,
+                outbound_socks_auth,
             })
         },
         "tor" => {
@@ -1005,6 +1245,9 @@ pub enum CommsTransport {
         listener_address: Multiaddr,
         tor_socks_address: Option<Multiaddr>,
         tor_socks_auth: Option<SocksAuthentication>,
+        /// If set, all outbound connections are dialed through this SOCKS5 proxy unless overridden for a peer
+        outbound_socks_address: Option<Multiaddr>,
+        outbound_socks_auth: Option<SocksAuthentication>,
     },
     /// Configures the node to run over a tor hidden service using the Tor proxy. This transport recognises ip/tcp,
     /// onion v2, onion v3 and DNS addresses.
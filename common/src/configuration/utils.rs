@@ -58,6 +58,11 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
     cfg.set_default("common.message_cache_ttl", 1440).unwrap();
     cfg.set_default("common.peer_allowlist", Vec::<String>::new()).unwrap();
     cfg.set_default("common.rpc_max_simultaneous_sessions", 1000).unwrap();
+    cfg.set_default("common.rpc_max_in_flight_messages", 32).unwrap();
+    cfg.set_default("common.rpc_slow_consumer_timeout", 30).unwrap();
+    cfg.set_default("common.rpc_max_concurrent_requests", 200).unwrap();
+    cfg.set_default("common.rpc_max_concurrent_requests_per_peer", 4)
+        .unwrap();
     cfg.set_default("common.liveness_max_sessions", 0).unwrap();
     cfg.set_default("common.denylist_ban_period", 1440).unwrap();
     cfg.set_default("common.buffer_size_base_node", 1_500).unwrap();
@@ -66,6 +71,8 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
     cfg.set_default("common.buffer_rate_limit_base_node_wallet", 1_000)
         .unwrap();
     cfg.set_default("common.dedup_cache_capacity", 2_500).unwrap();
+    cfg.set_default("common.dedup_bloom_retention", 3 * 24 * 60 * 60)
+        .unwrap();
     cfg.set_default("common.fetch_blocks_timeout", 150).unwrap();
     cfg.set_default("common.fetch_utxos_timeout", 600).unwrap();
     cfg.set_default("common.service_request_timeout", 180).unwrap();
@@ -76,6 +76,9 @@
 //! # std::fs::remove_dir_all(temp_dir).unwrap();
 //! ```
 
+#[macro_use]
+extern crate lazy_static;
+
 #[cfg(any(feature = "build", feature = "static-application-info"))]
 pub mod build;
 #[macro_use]
@@ -92,7 +95,16 @@ pub use configuration::{
 
 pub mod dir_utils;
 
-pub use logging::initialize_logging;
+pub use logging::{
+    initialize_logging,
+    log_level_overrides,
+    reload_logging_config,
+    reset_log_levels,
+    set_global_log_field,
+    set_log_level,
+    LoggingError,
+    ROOT_LOG_TARGET,
+};
 
 pub const DEFAULT_CONFIG: &str = "config/config.toml";
 pub const DEFAULT_BASE_NODE_LOG_CONFIG: &str = "config/log4rs_base_node.yml";
@@ -21,7 +21,75 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //
 
-use std::{fs, fs::File, io::Write, path::Path};
+mod json_encoder;
+pub use json_encoder::{set_global_log_field, JsonLinesEncoder, JSON_LINES_ENCODER_KIND};
+
+use json_encoder::JsonLinesEncoderDeserializer;
+use log::LevelFilter;
+use log4rs::{
+    config::{Config, Logger, Root},
+    file::{Deserializers, RawConfig},
+    Handle,
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::SystemTime,
+};
+
+/// The target name used to change the level of the root logger
+pub const ROOT_LOG_TARGET: &str = "root";
+
+lazy_static! {
+    static ref LOG_CONTROL: Mutex<Option<LogControl>> = Mutex::new(None);
+}
+
+/// Holds what is needed to rebuild the logging configuration when log levels are changed at runtime
+struct LogControl {
+    handle: Handle,
+    config_file: PathBuf,
+    raw_config: RawConfig,
+    overrides: BTreeMap<String, LevelFilter>,
+}
+
+impl LogControl {
+    fn apply_raw_config(&mut self, raw_config: RawConfig) -> Result<(), LoggingError> {
+        self.handle.set_config(build_config(&raw_config, &self.overrides)?);
+        self.raw_config = raw_config;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum LoggingError {
+    NotInitialized,
+    InvalidConfigFile(String),
+    InvalidConfig(String),
+    InvalidLevel(String),
+}
+
+impl std::error::Error for LoggingError {}
+
+impl fmt::Display for LoggingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoggingError::NotInitialized => write!(f, "Logging has not been initialized"),
+            LoggingError::InvalidConfigFile(e) => write!(f, "Could not read the logging configuration file: {}", e),
+            LoggingError::InvalidConfig(e) => write!(f, "Invalid logging configuration: {}", e),
+            LoggingError::InvalidLevel(level) => write!(
+                f,
+                "Invalid log level '{}'. Expected one of off, error, warn, info, debug or trace",
+                level
+            ),
+        }
+    }
+}
 
 /// Set up application-level logging using the Log4rs configuration file specified in
 pub fn initialize_logging(config_file: &Path) -> bool {
@@ -29,13 +97,172 @@ pub fn initialize_logging(config_file: &Path) -> bool {
         "Initializing logging according to {:?}",
         config_file.to_str().unwrap_or("[??]")
     );
-    if let Err(e) = log4rs::init_file(config_file, Default::default()) {
-        println!("We couldn't load a logging configuration file. {}", e.to_string());
-        return false;
+    let is_yaml = config_file
+        .extension()
+        .map(|ext| ext == "yml" || ext == "yaml")
+        .unwrap_or(false);
+    if !is_yaml {
+        // Runtime log level changes and the json_lines encoder are only supported for YAML configuration files
+        if let Err(e) = log4rs::init_file(config_file, Default::default()) {
+            println!("We couldn't load a logging configuration file. {}", e.to_string());
+            return false;
+        }
+        return true;
+    }
+    let raw_config = match read_raw_config(config_file) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("We couldn't load a logging configuration file. {}", e);
+            return false;
+        },
+    };
+    let config = match build_config(&raw_config, &BTreeMap::new()) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("We couldn't load a logging configuration file. {}", e);
+            return false;
+        },
+    };
+    let handle = match log4rs::init_config(config) {
+        Ok(h) => h,
+        Err(e) => {
+            println!("We couldn't initialize logging. {}", e);
+            return false;
+        },
+    };
+    if let Some(refresh_rate) = raw_config.refresh_rate() {
+        spawn_config_reloader(config_file.to_path_buf(), refresh_rate);
     }
+    *LOG_CONTROL.lock().expect("log control lock poisoned") = Some(LogControl {
+        handle,
+        config_file: config_file.to_path_buf(),
+        raw_config,
+        overrides: BTreeMap::new(),
+    });
     true
 }
 
+/// Changes the level of the given log target (or `root`) at runtime. The change is kept when the logging
+/// configuration file is reloaded, until it is removed with [reset_log_levels].
+pub fn set_log_level(target: &str, level: &str) -> Result<(), LoggingError> {
+    let level = level
+        .parse::<LevelFilter>()
+        .map_err(|_| LoggingError::InvalidLevel(level.to_string()))?;
+    let mut lock = LOG_CONTROL.lock().expect("log control lock poisoned");
+    let control = lock.as_mut().ok_or(LoggingError::NotInitialized)?;
+    let mut overrides = control.overrides.clone();
+    overrides.insert(target.to_string(), level);
+    control
+        .handle
+        .set_config(build_config(&control.raw_config, &overrides)?);
+    control.overrides = overrides;
+    Ok(())
+}
+
+/// Removes all runtime log level changes, restoring the levels in the logging configuration file
+pub fn reset_log_levels() -> Result<(), LoggingError> {
+    let mut lock = LOG_CONTROL.lock().expect("log control lock poisoned");
+    let control = lock.as_mut().ok_or(LoggingError::NotInitialized)?;
+    control
+        .handle
+        .set_config(build_config(&control.raw_config, &BTreeMap::new())?);
+    control.overrides.clear();
+    Ok(())
+}
+
+/// Re-reads the logging configuration file, keeping runtime log level changes
+pub fn reload_logging_config() -> Result<(), LoggingError> {
+    let mut lock = LOG_CONTROL.lock().expect("log control lock poisoned");
+    let control = lock.as_mut().ok_or(LoggingError::NotInitialized)?;
+    let raw_config = read_raw_config(&control.config_file)?;
+    control.apply_raw_config(raw_config)
+}
+
+/// Returns the log levels that have been changed at runtime
+pub fn log_level_overrides() -> Vec<(String, LevelFilter)> {
+    LOG_CONTROL
+        .lock()
+        .expect("log control lock poisoned")
+        .as_ref()
+        .map(|c| c.overrides.iter().map(|(t, l)| (t.clone(), *l)).collect())
+        .unwrap_or_default()
+}
+
+fn read_raw_config(config_file: &Path) -> Result<RawConfig, LoggingError> {
+    let source = fs::read_to_string(config_file).map_err(|e| LoggingError::InvalidConfigFile(e.to_string()))?;
+    serde_yaml::from_str(&source).map_err(|e| LoggingError::InvalidConfigFile(e.to_string()))
+}
+
+fn deserializers() -> Deserializers {
+    let mut deserializers = Deserializers::default();
+    deserializers.insert(JSON_LINES_ENCODER_KIND, JsonLinesEncoderDeserializer);
+    deserializers
+}
+
+fn build_config(raw_config: &RawConfig, overrides: &BTreeMap<String, LevelFilter>) -> Result<Config, LoggingError> {
+    let (appenders, errors) = raw_config.appenders_lossy(&deserializers());
+    for err in errors {
+        println!("Error in logging configuration: {}", err);
+    }
+
+    let root = raw_config.root();
+    let root = match overrides.get(ROOT_LOG_TARGET) {
+        Some(level) => Root::builder()
+            .appenders(root.appenders().iter().cloned())
+            .build(*level),
+        None => root,
+    };
+
+    let mut loggers = raw_config.loggers();
+    for logger in loggers.iter_mut() {
+        if let Some(level) = overrides.get(logger.name()) {
+            *logger = Logger::builder()
+                .appenders(logger.appenders().iter().cloned())
+                .additive(logger.additive())
+                .build(logger.name(), *level);
+        }
+    }
+    // Targets that are not configured in the file inherit the appenders of their parent
+    for (target, level) in overrides {
+        if target != ROOT_LOG_TARGET && !loggers.iter().any(|l| l.name() == target) {
+            loggers.push(Logger::builder().build(target, *level));
+        }
+    }
+
+    Config::builder()
+        .appenders(appenders)
+        .loggers(loggers)
+        .build(root)
+        .map_err(|e| LoggingError::InvalidConfig(e.to_string()))
+}
+
+/// Periodically reloads the logging configuration file if it has changed, keeping runtime log level changes
+fn spawn_config_reloader(config_file: PathBuf, refresh_rate: std::time::Duration) {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last_modified: Option<SystemTime> = modified(&config_file);
+    thread::spawn(move || loop {
+        thread::sleep(refresh_rate);
+        let current = modified(&config_file);
+        if current == last_modified {
+            continue;
+        }
+        last_modified = current;
+        let raw_config = match read_raw_config(&config_file) {
+            Ok(c) => c,
+            Err(e) => {
+                println!("Could not reload logging configuration. {}", e);
+                continue;
+            },
+        };
+        let mut lock = LOG_CONTROL.lock().expect("log control lock poisoned");
+        if let Some(control) = lock.as_mut() {
+            if let Err(e) = control.apply_raw_config(raw_config) {
+                println!("Could not reload logging configuration. {}", e);
+            }
+        }
+    });
+}
+
 /// Installs a new default logfile configuration, copied from `log4rs_sample_base_node.yml` to the given path.
 pub fn install_default_base_node_logfile_config(path: &Path) -> Result<(), std::io::Error> {
     let source = include_str!("../logging/log4rs_sample_base_node.yml");
@@ -0,0 +1,144 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A log4rs encoder that writes each record as a single line of JSON, for consumption by log aggregation tools.
+//!
+//! Each line contains the `timestamp`, `level`, `target`, `thread` and `message` of the record, followed by:
+//! - global fields set with [set_global_log_field](../fn.set_global_log_field.html), e.g. `node_id`
+//! - mapped diagnostic context (MDC) fields, e.g. the `tx_id` of transaction protocols and the `peer_id` of peer
+//!   connection and messaging tasks, which are set with `tari_comms::utils::log_fields::LogFieldExt`
+//!
+//! Enable it in a log4rs config file with:
+//! ```yaml
+//! encoder:
+//!   kind: json_lines
+//! ```
+
+use chrono::Local;
+use log::Record;
+use log4rs::{
+    encode::{Encode, Write},
+    file::{Deserialize, Deserializers},
+};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    sync::RwLock,
+    thread,
+};
+
+pub const JSON_LINES_ENCODER_KIND: &str = "json_lines";
+
+lazy_static! {
+    static ref GLOBAL_FIELDS: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+}
+
+/// Sets a field that is added to every JSON log line, or removes it if `value` is `None`
+pub fn set_global_log_field(key: &str, value: Option<String>) {
+    let mut fields = GLOBAL_FIELDS.write().expect("log field lock poisoned");
+    match value {
+        Some(value) => {
+            fields.insert(key.to_string(), value);
+        },
+        None => {
+            fields.remove(key);
+        },
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct JsonLinesEncoder;
+
+impl JsonLinesEncoder {
+    fn to_json(&self, record: &Record) -> Map<String, Value> {
+        let mut line = Map::new();
+        line.insert("timestamp".to_string(), Value::String(Local::now().to_rfc3339()));
+        line.insert("level".to_string(), Value::String(record.level().to_string()));
+        line.insert("target".to_string(), Value::String(record.target().to_string()));
+        if let Some(name) = thread::current().name() {
+            line.insert("thread".to_string(), Value::String(name.to_string()));
+        }
+        line.insert("message".to_string(), Value::String(record.args().to_string()));
+
+        if let Ok(fields) = GLOBAL_FIELDS.read() {
+            for (key, value) in fields.iter() {
+                line.insert(key.clone(), Value::String(value.clone()));
+            }
+        }
+        log_mdc::iter(|key, value| {
+            line.insert(key.to_string(), Value::String(value.to_string()));
+        });
+        line
+    }
+}
+
+impl Encode for JsonLinesEncoder {
+    fn encode(&self, w: &mut dyn Write, record: &Record) -> Result<(), Box<dyn Error + Sync + Send>> {
+        serde_json::to_writer(&mut *w, &self.to_json(record))?;
+        w.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Allows the `json_lines` encoder to be used in log4rs config files. The encoder takes no options.
+pub struct JsonLinesEncoderDeserializer;
+
+impl Deserialize for JsonLinesEncoderDeserializer {
+    type Config = HashMap<String, String>;
+    type Trait = dyn Encode;
+
+    fn deserialize(
+        &self,
+        _config: HashMap<String, String>,
+        _: &Deserializers,
+    ) -> Result<Box<dyn Encode>, Box<dyn Error + Sync + Send>> {
+        Ok(Box::new(JsonLinesEncoder))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn it_includes_record_and_context_fields() {
+        set_global_log_field("node_id", Some("abcd".to_string()));
+        log_mdc::insert("tx_id", "123");
+        let line = JsonLinesEncoder.to_json(
+            &Record::builder()
+                .args(format_args!("hello {}", "world"))
+                .level(Level::Info)
+                .target("c::test")
+                .build(),
+        );
+        log_mdc::remove("tx_id");
+        set_global_log_field("node_id", None);
+
+        assert_eq!(line["message"], "hello world");
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "c::test");
+        assert_eq!(line["node_id"], "abcd");
+        assert_eq!(line["tx_id"], "123");
+    }
+}
@@ -29,7 +29,7 @@
 
 use crate::{
     broadcast_strategy::BroadcastStrategy,
-    dedup::DedupCacheDatabase,
+    dedup::{DedupCacheDatabase, DedupCacheStats, RotatingBloomFilter},
     discovery::DhtDiscoveryError,
     outbound::{DhtOutboundError, OutboundMessageRequester, SendMessageParams},
     proto::{dht::JoinMessage, envelope::DhtMessageType},
@@ -45,7 +45,12 @@ use futures::{
     StreamExt,
 };
 use log::*;
-use std::{cmp, fmt, fmt::Display, sync::Arc};
+use std::{
+    cmp,
+    fmt,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
 use tari_comms::{
     connectivity::{ConnectivityError, ConnectivityRequester, ConnectivitySelection},
     peer_manager::{NodeId, NodeIdentity, PeerFeatures, PeerManager, PeerManagerError, PeerQuery, PeerQuerySortBy},
@@ -108,6 +113,8 @@ pub enum DhtRequest {
     SelectPeers(BroadcastStrategy, oneshot::Sender<Vec<NodeId>>),
     GetMetadata(DhtMetadataKey, oneshot::Sender<Result<Option<Vec<u8>>, DhtActorError>>),
     SetMetadata(DhtMetadataKey, Vec<u8>, oneshot::Sender<Result<(), DhtActorError>>),
+    /// Fetch the hit-rate counters of the message hash cache
+    GetDedupCacheStats(oneshot::Sender<DedupCacheStats>),
 }
 
 impl Display for DhtRequest {
@@ -121,6 +128,7 @@ impl Display for DhtRequest {
             SetMetadata(key, value, _) => {
                 f.write_str(&format!("SetMetadata (key={}, value={} bytes)", key, value.len()))
             },
+            GetDedupCacheStats(_) => f.write_str("GetDedupCacheStats"),
         }
     }
 }
@@ -177,6 +185,12 @@ impl DhtRequester {
         self.sender.send(DhtRequest::SetMetadata(key, bytes, reply_tx)).await?;
         reply_rx.await.map_err(|_| DhtActorError::ReplyCanceled)?
     }
+
+    pub async fn get_dedup_cache_stats(&mut self) -> Result<DedupCacheStats, DhtActorError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender.send(DhtRequest::GetDedupCacheStats(reply_tx)).await?;
+        reply_rx.await.map_err(|_| DhtActorError::ReplyCanceled)
+    }
 }
 
 pub struct DhtActor {
@@ -189,6 +203,8 @@ pub struct DhtActor {
     shutdown_signal: Option<ShutdownSignal>,
     request_rx: Fuse<mpsc::Receiver<DhtRequest>>,
     msg_hash_dedup_cache: DedupCacheDatabase,
+    dedup_bloom: Option<Arc<Mutex<RotatingBloomFilter>>>,
+    dedup_stats: Arc<Mutex<DedupCacheStats>>,
 }
 
 impl DhtActor {
@@ -209,8 +225,11 @@ impl DhtActor {
             config.dedup_cache_trim_interval.as_secs() as f64 +
                 config.dedup_cache_trim_interval.subsec_nanos() as f64 * 1e-9
         );
+        let dedup_bloom = Self::new_dedup_bloom(&config).map(|bloom| Arc::new(Mutex::new(bloom)));
         Self {
             msg_hash_dedup_cache: DedupCacheDatabase::new(conn.clone(), config.dedup_cache_capacity),
+            dedup_bloom,
+            dedup_stats: Default::default(),
             config,
             database: DhtDatabase::new(conn),
             outbound_requester,
@@ -245,6 +264,8 @@ impl DhtActor {
                 .unwrap_or_else(String::new)
         );
 
+        self.load_dedup_bloom().await;
+
         let mut pending_jobs = FuturesUnordered::new();
 
         let mut dedup_cache_trim_ticker = time::interval(self.config.dedup_cache_trim_interval).fuse();
@@ -271,11 +292,14 @@ impl DhtActor {
                     if let Err(err) = self.msg_hash_dedup_cache.truncate().await {
                         error!(target: LOG_TARGET, "Error when trimming message dedup cache: {:?}", err);
                     }
+                    self.save_dedup_bloom().await;
+                    debug!(target: LOG_TARGET, "Message dedup cache stats: {}", *acquire_lock!(self.dedup_stats));
                 },
 
                 _ = shutdown_signal => {
                     info!(target: LOG_TARGET, "DhtActor is shutting down because it received a shutdown signal.");
                     self.mark_shutdown_time().await;
+                    self.save_dedup_bloom().await;
                     break Ok(());
                 },
             }
@@ -292,6 +316,79 @@ impl DhtActor {
         }
     }
 
+    fn new_dedup_bloom(config: &DhtConfig) -> Option<RotatingBloomFilter> {
+        if config.dedup_bloom_retention.as_secs() == 0 {
+            return None;
+        }
+        Some(RotatingBloomFilter::new(
+            config.dedup_bloom_capacity,
+            config.dedup_bloom_false_positive_rate,
+            config.dedup_bloom_retention,
+            config.dedup_bloom_generations,
+            Utc::now().timestamp(),
+        ))
+    }
+
+    /// Restores the message hash bloom filter persisted at the last shutdown, so that messages seen before the
+    /// restart are not processed and propagated again
+    async fn load_dedup_bloom(&self) {
+        let bloom = match self.dedup_bloom.as_ref() {
+            Some(bloom) => bloom,
+            None => return,
+        };
+        match self
+            .database
+            .get_metadata_value::<RotatingBloomFilter>(DhtMetadataKey::DedupBloomFilter)
+            .await
+        {
+            Ok(Some(mut stored)) => {
+                let mut bloom = acquire_lock!(bloom);
+                if stored.is_compatible_with(&bloom) {
+                    stored.rotate_if_due(Utc::now().timestamp());
+                    debug!(
+                        target: LOG_TARGET,
+                        "Restored message dedup bloom filter ({} bytes)",
+                        stored.size_in_bytes()
+                    );
+                    *bloom = stored;
+                } else {
+                    info!(
+                        target: LOG_TARGET,
+                        "Discarding persisted message dedup bloom filter because the configuration has changed"
+                    );
+                }
+            },
+            Ok(None) => {},
+            Err(err) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to load message dedup bloom filter: {:?}", err
+                );
+            },
+        }
+    }
+
+    async fn save_dedup_bloom(&self) {
+        let bloom = match self.dedup_bloom.as_ref() {
+            Some(bloom) => {
+                let mut bloom = acquire_lock!(bloom);
+                bloom.rotate_if_due(Utc::now().timestamp());
+                bloom.clone()
+            },
+            None => return,
+        };
+        if let Err(err) = self
+            .database
+            .set_metadata_value(DhtMetadataKey::DedupBloomFilter, bloom)
+            .await
+        {
+            warn!(
+                target: LOG_TARGET,
+                "Failed to save message dedup bloom filter: {:?}", err
+            );
+        }
+    }
+
     fn request_handler(&mut self, request: DhtRequest) -> BoxFuture<'static, Result<(), DhtActorError>> {
         use DhtRequest::*;
         match request {
@@ -302,22 +399,38 @@ impl DhtActor {
             },
             MsgHashCacheInsert(hash, public_key, reply_tx) => {
                 let msg_hash_cache = self.msg_hash_dedup_cache.clone();
+                let dedup_bloom = self.dedup_bloom.clone();
+                let dedup_stats = self.dedup_stats.clone();
                 Box::pin(async move {
-                    match msg_hash_cache.insert_body_hash_if_unique(hash, public_key).await {
-                        Ok(already_exists) => {
-                            let _ = reply_tx.send(already_exists).map_err(|_| DhtActorError::ReplyCanceled);
-                        },
+                    let in_bloom = dedup_bloom
+                        .map(|bloom| {
+                            let mut bloom = acquire_lock!(bloom);
+                            let in_bloom = bloom.contains(&hash);
+                            bloom.insert(&hash);
+                            in_bloom
+                        })
+                        .unwrap_or(false);
+                    let already_exists = match msg_hash_cache.insert_body_hash_if_unique(hash, public_key).await {
+                        Ok(already_exists) => already_exists,
                         Err(err) => {
                             warn!(
                                 target: LOG_TARGET,
                                 "Unable to update message dedup cache because {:?}", err
                             );
-                            let _ = reply_tx.send(false).map_err(|_| DhtActorError::ReplyCanceled);
+                            false
                         },
-                    }
+                    };
+                    acquire_lock!(dedup_stats).record(already_exists, in_bloom);
+                    let _ = reply_tx
+                        .send(already_exists || in_bloom)
+                        .map_err(|_| DhtActorError::ReplyCanceled);
                     Ok(())
                 })
             },
+            GetDedupCacheStats(reply_tx) => {
+                let stats = *acquire_lock!(self.dedup_stats);
+                Box::pin(async move { reply_tx.send(stats).map_err(|_| DhtActorError::ReplyCanceled) })
+            },
             SelectPeers(broadcast_strategy, reply_tx) => {
                 let peer_manager = Arc::clone(&self.peer_manager);
                 let node_identity = Arc::clone(&self.node_identity);
@@ -465,7 +578,8 @@ impl DhtActor {
             },
             Broadcast(exclude) => {
                 let connections = connectivity
-                    .select_connections(ConnectivitySelection::random_nodes(
+                    .select_connections(ConnectivitySelection::diverse_nodes(
+                        node_identity.node_id().clone(),
                         config.broadcast_factor,
                         exclude.clone(),
                     ))
@@ -544,10 +658,13 @@ impl DhtActor {
                     None => {
                         debug!(
                             target: LOG_TARGET,
-                            "No destination for propagation, sending to {} random peers", config.propagation_factor
+                            "No destination for propagation, sending to {} diverse random peers",
+                            config.propagation_factor
                         );
+                        // Spread the message across network regions so that a single region cannot withhold it
                         connectivity
-                            .select_connections(ConnectivitySelection::random_nodes(
+                            .select_connections(ConnectivitySelection::diverse_nodes(
+                                node_identity.node_id().clone(),
                                 config.propagation_factor,
                                 exclude.clone(),
                             ))
@@ -771,6 +888,8 @@ mod test {
             DhtConfig {
                 dedup_cache_capacity: capacity,
                 dedup_cache_trim_interval: Duration::from_millis(trim_interval_ms),
+                // Only the message hash database is under test
+                dedup_bloom_retention: Duration::from_secs(0),
                 ..Default::default()
             },
             db_connection().await,
@@ -842,6 +961,66 @@ mod test {
         shutdown.trigger().unwrap();
     }
 
+    #[tokio_macros::test_basic]
+    async fn dedup_bloom_persists_across_restarts() {
+        let conn = db_connection().await;
+        let signature = vec![1u8, 2, 3];
+
+        let spawn_actor = |shutdown_signal| {
+            let (connectivity_manager, mock) = create_connectivity_mock();
+            mock.spawn();
+            let (out_tx, _) = mpsc::channel(1);
+            let (actor_tx, actor_rx) = mpsc::channel(1);
+            let actor = DhtActor::new(
+                DhtConfig {
+                    dedup_bloom_capacity: 100,
+                    ..Default::default()
+                },
+                conn.clone(),
+                make_node_identity(),
+                build_peer_manager(),
+                connectivity_manager,
+                OutboundMessageRequester::new(out_tx),
+                actor_rx,
+                shutdown_signal,
+            );
+            actor.spawn();
+            DhtRequester::new(actor_tx)
+        };
+
+        let mut shutdown = Shutdown::new();
+        let mut requester = spawn_actor(shutdown.to_signal());
+        let is_dup = requester
+            .insert_message_hash(signature.clone(), CommsPublicKey::default())
+            .await
+            .unwrap();
+        assert!(!is_dup);
+        shutdown.trigger().unwrap();
+        delay_for(Duration::from_millis(100)).await;
+
+        // Clear the message hash database so that only the persisted bloom filter remembers the message
+        DedupCacheDatabase::new(conn.clone(), 0).truncate().await.unwrap();
+
+        let shutdown = Shutdown::new();
+        let mut requester = spawn_actor(shutdown.to_signal());
+        let is_dup = requester
+            .insert_message_hash(signature, CommsPublicKey::default())
+            .await
+            .unwrap();
+        assert!(is_dup);
+        let is_dup = requester
+            .insert_message_hash(vec![4u8, 5, 6], CommsPublicKey::default())
+            .await
+            .unwrap();
+        assert!(!is_dup);
+
+        let stats = requester.get_dedup_cache_stats().await.unwrap();
+        assert_eq!(stats.num_lookups, 2);
+        assert_eq!(stats.num_db_hits, 0);
+        assert_eq!(stats.num_bloom_hits, 1);
+        assert!((stats.hit_rate() - 0.5).abs() < f64::EPSILON);
+    }
+
     #[tokio_macros::test_basic]
     async fn select_peers() {
         let node_identity = make_node_identity();
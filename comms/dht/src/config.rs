@@ -77,6 +77,22 @@ pub struct DhtConfig {
     /// The periodic trim interval for items in the message hash cache
     /// Default: 300s (5 mins)
     pub dedup_cache_trim_interval: Duration,
+    /// The period for which message hashes are remembered by the persistent bloom filter, which lets messages seen
+    /// before a restart or trimmed from the message hash cache still be recognised as duplicates. Set to zero to
+    /// disable the bloom filter.
+    /// Default: 3 days
+    pub dedup_bloom_retention: Duration,
+    /// The number of generations the bloom filter retention is split into. The oldest generation is discarded each
+    /// time `dedup_bloom_retention / dedup_bloom_generations` elapses.
+    /// Default: 3
+    pub dedup_bloom_generations: usize,
+    /// The number of message hashes each bloom filter generation is sized for
+    /// Default: 100,000
+    pub dedup_bloom_capacity: usize,
+    /// The false positive rate of each bloom filter generation when filled to capacity. A false positive causes a
+    /// new message to be discarded as a duplicate.
+    /// Default: 0.000001
+    pub dedup_bloom_false_positive_rate: f64,
     /// The duration to wait for a peer discovery to complete before giving up.
     /// Default: 2 minutes
     pub discovery_request_timeout: Duration,
@@ -112,6 +128,19 @@ pub struct DhtConfig {
     /// peers that were previously tried.
     /// Default: 24 hours
     pub offline_peer_cooldown: Duration,
+    /// The minimum number of outbound node connections to routable IP addresses required before checking that the
+    /// connections span more than one network group (/16 IPv4 or /32 IPv6 prefix). If every such connection shares the
+    /// same network group, this node may be the target of an eclipse attack and peers from other network groups are
+    /// dialed. Inbound and onion connections are not counted. Set to zero to disable the check.
+    /// Default: 4
+    pub eclipse_detection_min_connections: usize,
+    /// The time to wait after the first attempt to restore connectivity when the connectivity status is DEGRADED or
+    /// OFFLINE. The wait doubles after each further attempt, up to `connectivity_heal_max_backoff`.
+    /// Default: 10 seconds
+    pub connectivity_heal_min_backoff: Duration,
+    /// The maximum time to wait between attempts to restore connectivity.
+    /// Default: 10 minutes
+    pub connectivity_heal_max_backoff: Duration,
 }
 
 impl DhtConfig {
@@ -134,6 +163,8 @@ impl DhtConfig {
                 ..Default::default()
             },
             allow_test_addresses: true,
+            // Local test networks always share a network group
+            eclipse_detection_min_connections: 0,
             ..Default::default()
         }
     }
@@ -157,6 +188,10 @@ impl Default for DhtConfig {
             saf_minimum_request_period: Duration::from_secs(3 * 24 * 60 * 60), // 3 days
             dedup_cache_capacity: 2_500,
             dedup_cache_trim_interval: Duration::from_secs(5 * 60),
+            dedup_bloom_retention: Duration::from_secs(3 * 24 * 60 * 60), // 3 days
+            dedup_bloom_generations: 3,
+            dedup_bloom_capacity: 100_000,
+            dedup_bloom_false_positive_rate: 0.000_001,
             database_url: DbConnectionUrl::Memory,
             discovery_request_timeout: Duration::from_secs(2 * 60),
             connectivity_update_interval: Duration::from_secs(2 * 60),
@@ -170,6 +205,9 @@ impl Default for DhtConfig {
             flood_ban_timespan: Duration::from_secs(100),
             offline_peer_cooldown: Duration::from_secs(2 * 60 * 60),
             saf_msg_validity: Duration::from_secs(10800),
+            eclipse_detection_min_connections: 4,
+            connectivity_heal_min_backoff: Duration::from_secs(10),
+            connectivity_heal_max_backoff: Duration::from_secs(10 * 60),
         }
     }
 }
@@ -26,13 +26,25 @@ mod test;
 mod metrics;
 pub use metrics::{MetricsCollector, MetricsCollectorHandle};
 
-use crate::{connectivity::metrics::MetricsError, event::DhtEvent, DhtActorError, DhtConfig, DhtRequester};
-use futures::{stream::Fuse, StreamExt};
+use crate::{
+    connectivity::metrics::MetricsError,
+    event::{DhtEvent, DhtEventSender},
+    DhtActorError,
+    DhtConfig,
+    DhtRequester,
+};
+use futures::{future, future::FusedFuture, stream::Fuse, FutureExt, StreamExt};
 use log::*;
-use std::{sync::Arc, time::Instant};
+use rand::{rngs::OsRng, seq::SliceRandom};
+use std::{
+    cmp,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tari_comms::{
     connectivity::{ConnectivityError, ConnectivityEvent, ConnectivityEventRx, ConnectivityRequester},
     peer_manager::{node_id::NodeDistance, NodeId, PeerManagerError, PeerQuery, PeerQuerySortBy},
+    utils::multiaddr::{network_group, routable_network_group},
     NodeIdentity,
     PeerConnection,
     PeerManager,
@@ -63,7 +75,9 @@ pub enum DhtConnectivityError {
 /// percentage of these peers is online, the node is established on the DHT network.
 ///
 /// The DHT connectivity actor monitors the connectivity state (using `ConnectivityEvent`s) and attempts
-/// to maintain connectivity to the network as peers come and go.
+/// to maintain connectivity to the network as peers come and go. While the connectivity status is DEGRADED or OFFLINE,
+/// it periodically dials peers that this node has previously connected to and requests a network discovery round,
+/// backing off exponentially between attempts until the node is ONLINE again.
 pub struct DhtConnectivity {
     config: DhtConfig,
     peer_manager: Arc<PeerManager>,
@@ -77,8 +91,13 @@ pub struct DhtConnectivity {
     random_pool: Vec<NodeId>,
     /// Used to track when the random peer pool was last refreshed
     random_pool_last_refresh: Option<Instant>,
+    /// Used to track when the random peer pool was last replaced to improve connection diversity
+    diversity_last_corrected: Option<Instant>,
     stats: Stats,
+    /// The number of attempts made to restore connectivity since the node was last ONLINE
+    num_heal_attempts: usize,
     dht_events: Fuse<broadcast::Receiver<Arc<DhtEvent>>>,
+    event_publisher: DhtEventSender,
 
     metrics_collector: MetricsCollectorHandle,
 
@@ -93,7 +112,7 @@ impl DhtConnectivity {
         node_identity: Arc<NodeIdentity>,
         connectivity: ConnectivityRequester,
         dht_requester: DhtRequester,
-        dht_events: broadcast::Receiver<Arc<DhtEvent>>,
+        event_publisher: DhtEventSender,
         metrics_collector: MetricsCollectorHandle,
         shutdown_signal: ShutdownSignal,
     ) -> Self {
@@ -107,8 +126,11 @@ impl DhtConnectivity {
             dht_requester,
             metrics_collector,
             random_pool_last_refresh: None,
+            diversity_last_corrected: None,
             stats: Stats::new(),
-            dht_events: dht_events.fuse(),
+            num_heal_attempts: 0,
+            dht_events: event_publisher.subscribe().fuse(),
+            event_publisher,
             shutdown_signal: Some(shutdown_signal),
         }
     }
@@ -142,6 +164,7 @@ impl DhtConnectivity {
         self.refresh_neighbour_pool().await?;
 
         let mut ticker = time::interval(self.config.connectivity_update_interval).fuse();
+        let mut heal_timer = future::Fuse::terminated();
 
         loop {
             futures::select! {
@@ -150,7 +173,31 @@ impl DhtConnectivity {
                         if let Err(err) = self.handle_connectivity_event(&event).await {
                             debug!(target: LOG_TARGET, "Error handling connectivity event: {:?}", err);
                         }
+
+                        match &*event {
+                            ConnectivityEvent::ConnectivityStateDegraded(_) | ConnectivityEvent::ConnectivityStateOffline => {
+                                if heal_timer.is_terminated() {
+                                    // Start healing straight away, subsequent attempts are backed off
+                                    heal_timer = time::delay_for(Duration::from_secs(0)).fuse();
+                                }
+                            },
+                            ConnectivityEvent::ConnectivityStateOnline(_) => {
+                                if self.num_heal_attempts > 0 {
+                                    info!(target: LOG_TARGET, "Connectivity restored after {} attempt(s)", self.num_heal_attempts);
+                                    self.num_heal_attempts = 0;
+                                }
+                                heal_timer = future::Fuse::terminated();
+                            },
+                            _ => {},
+                        }
+                    }
+               },
+
+               _ = heal_timer => {
+                    if let Err(err) = self.heal_connectivity().await {
+                        debug!(target: LOG_TARGET, "Error attempting to restore connectivity: {:?}", err);
                     }
+                    heal_timer = time::delay_for(self.heal_backoff()).fuse();
                },
 
                event = self.dht_events.select_next_some() => {
@@ -168,6 +215,9 @@ impl DhtConnectivity {
                     if let Err(err) = self.check_and_ban_flooding_peers().await {
                         debug!(target: LOG_TARGET, "Error checking for peer flooding: {:?}", err);
                     }
+                    if let Err(err) = self.check_connection_diversity().await {
+                        debug!(target: LOG_TARGET, "Error checking connection diversity: {:?}", err);
+                    }
                },
 
                _ = shutdown_signal => {
@@ -221,6 +271,84 @@ impl DhtConnectivity {
         Ok(())
     }
 
+    /// Checks that this node's connections are not all within the same network group. If they are, the node may be
+    /// the target of an eclipse attack, so the random pool is replaced with peers from other network groups.
+    ///
+    /// Only outbound connections to routable IP addresses are measured. The address of an inbound connection is that
+    /// of the connecting socket (e.g. 127.0.0.1 for connections through a Tor proxy) and onion addresses have no
+    /// network group. Once the random pool has been replaced, the check waits for `connectivity_random_pool_refresh`
+    /// so that the new peers have time to connect.
+    async fn check_connection_diversity(&mut self) -> Result<(), DhtConnectivityError> {
+        let min_connections = self.config.eclipse_detection_min_connections;
+        if min_connections == 0 {
+            return Ok(());
+        }
+        let recently_corrected = self
+            .diversity_last_corrected
+            .map(|instant| instant.elapsed() < self.config.connectivity_random_pool_refresh)
+            .unwrap_or(false);
+        if recently_corrected {
+            return Ok(());
+        }
+
+        let connections = self.connectivity.get_active_connections().await?;
+        let groups = connections
+            .iter()
+            .filter(|conn| conn.peer_features().is_node() && conn.direction().is_outbound())
+            .filter_map(|conn| routable_network_group(conn.address()))
+            .collect::<Vec<_>>();
+
+        // At least two measured connections are needed for them to share a network group
+        if groups.len() < cmp::max(min_connections, 2) {
+            return Ok(());
+        }
+
+        let group = &groups[0];
+        if groups.iter().any(|g| g != group) {
+            return Ok(());
+        }
+
+        warn!(
+            target: LOG_TARGET,
+            "All {} node connection(s) share the same network group. This node may be the target of an eclipse \
+             attack. Dialing peers from other network groups.",
+            groups.len()
+        );
+
+        let excluded = self
+            .get_managed_peers()
+            .into_iter()
+            .chain(connections.iter().map(|conn| conn.peer_node_id().clone()))
+            .collect::<Vec<_>>();
+        let diverse_peers = self
+            .fetch_random_peers_outside_network_group(self.config.num_random_nodes, &excluded, group)
+            .await?;
+        if diverse_peers.is_empty() {
+            warn!(
+                target: LOG_TARGET,
+                "Unable to improve connection diversity because no peers are known outside of the current network \
+                 group"
+            );
+            return Ok(());
+        }
+
+        debug!(
+            target: LOG_TARGET,
+            "Replacing random peer pool (len={}) with {} peer(s) from other network groups",
+            self.random_pool.len(),
+            diverse_peers.len()
+        );
+        for node_id in self.random_pool.drain(..).collect::<Vec<_>>() {
+            self.connectivity.remove_peer(node_id).await?;
+        }
+        self.connectivity.add_managed_peers(diverse_peers.clone()).await?;
+        self.random_pool = diverse_peers;
+        self.random_pool_last_refresh = Some(Instant::now());
+        self.diversity_last_corrected = Some(Instant::now());
+
+        Ok(())
+    }
+
     async fn refresh_peer_pools(&mut self) -> Result<(), DhtConnectivityError> {
         info!(
             target: LOG_TARGET,
@@ -418,6 +546,40 @@ impl DhtConnectivity {
         Ok(())
     }
 
+    /// Attempts to restore connectivity by connecting to peers that this node has successfully connected to before and
+    /// requesting a network discovery round to find more peers.
+    async fn heal_connectivity(&mut self) -> Result<(), DhtConnectivityError> {
+        self.num_heal_attempts += 1;
+        let excluded = self.get_managed_peers();
+        let peers = self
+            .fetch_previously_connected_peers(self.config.num_random_nodes, &excluded)
+            .await?;
+        debug!(
+            target: LOG_TARGET,
+            "Connectivity heal attempt #{}: connecting to {} previously connected peer(s)",
+            self.num_heal_attempts,
+            peers.len()
+        );
+        if !peers.is_empty() {
+            // These peers are replaced when the random pool is next refreshed
+            self.random_pool.extend(peers.clone());
+            self.connectivity.add_managed_peers(peers).await?;
+        }
+
+        let _ = self.event_publisher.send(Arc::new(DhtEvent::NetworkDiscoveryRequested));
+
+        Ok(())
+    }
+
+    /// The time to wait before the next attempt to restore connectivity
+    fn heal_backoff(&self) -> Duration {
+        let exponent = self.num_heal_attempts.saturating_sub(1) as u32;
+        2u32.checked_pow(exponent)
+            .and_then(|factor| self.config.connectivity_heal_min_backoff.checked_mul(factor))
+            .map(|backoff| backoff.min(self.config.connectivity_heal_max_backoff))
+            .unwrap_or(self.config.connectivity_heal_max_backoff)
+    }
+
     async fn replace_managed_peer(&mut self, current_peer: &NodeId) -> Result<(), DhtConnectivityError> {
         if !self.is_managed(current_peer) {
             debug!(target: LOG_TARGET, "{} is not managed. Ignoring", current_peer);
@@ -601,11 +763,57 @@ impl DhtConnectivity {
         Ok(peers.into_iter().map(|p| p.node_id).collect())
     }
 
+    /// Fetch up to `n` communication nodes that have been successfully connected to before, most recently seen first
+    async fn fetch_previously_connected_peers(
+        &self,
+        n: usize,
+        excluded: &[NodeId],
+    ) -> Result<Vec<NodeId>, DhtConnectivityError> {
+        let query = PeerQuery::new()
+            .select_where(|peer| {
+                !peer.is_banned() &&
+                    !peer.features.is_client() &&
+                    peer.last_seen().is_some() &&
+                    !peer
+                        .offline_since()
+                        .map(|since| since <= self.config.offline_peer_cooldown)
+                        .unwrap_or(false) &&
+                    !excluded.contains(&peer.node_id)
+            })
+            .sort_by(PeerQuerySortBy::LastSeen)
+            .limit(n);
+
+        let peers = self.peer_manager.perform_query(query).await?;
+        Ok(peers.into_iter().map(|p| p.node_id).collect())
+    }
+
     async fn fetch_random_peers(&self, n: usize, excluded: &[NodeId]) -> Result<Vec<NodeId>, DhtConnectivityError> {
         let peers = self.peer_manager.random_peers(n, excluded).await?;
         Ok(peers.into_iter().map(|p| p.node_id).collect())
     }
 
+    async fn fetch_random_peers_outside_network_group(
+        &self,
+        n: usize,
+        excluded: &[NodeId],
+        group: &[u8],
+    ) -> Result<Vec<NodeId>, DhtConnectivityError> {
+        let query = PeerQuery::new().select_where(|peer| {
+            !peer.is_banned() &&
+                !peer.is_offline() &&
+                !peer.features.is_client() &&
+                !excluded.contains(&peer.node_id) &&
+                peer.addresses
+                    .iter()
+                    .any(|addr| network_group(addr).map(|g| g != group).unwrap_or(true))
+        });
+
+        let mut peers = self.peer_manager.perform_query(query).await?;
+        peers.shuffle(&mut OsRng);
+        peers.truncate(n);
+        Ok(peers.into_iter().map(|p| p.node_id).collect())
+    }
+
     fn should_send_join(&self) -> bool {
         let cooldown = self.config.join_cooldown_interval;
         self.stats
@@ -22,25 +22,33 @@
 
 use crate::{
     connectivity::{DhtConnectivity, MetricsCollector},
+    event::DhtEvent,
     test_utils::{build_peer_manager, create_dht_actor_mock, make_node_identity, DhtMockState},
     DhtConfig,
 };
 use rand::{rngs::OsRng, seq::SliceRandom};
 use std::{iter::repeat_with, sync::Arc, time::Duration};
 use tari_comms::{
+    connection_manager::ConnectionDirection,
     connectivity::ConnectivityEvent,
+    multiaddr::Multiaddr,
     peer_manager::{Peer, PeerFeatures},
     test_utils::{
         count_string_occurrences,
-        mocks::{create_connectivity_mock, create_dummy_peer_connection, ConnectivityManagerMockState},
+        mocks::{
+            create_connectivity_mock,
+            create_dummy_peer_connection,
+            create_dummy_peer_connection_with_address,
+            ConnectivityManagerMockState,
+        },
         node_identity::ordered_node_identities_by_distance,
     },
     NodeIdentity,
     PeerManager,
 };
 use tari_shutdown::Shutdown;
-use tari_test_utils::async_assert;
-use tokio::sync::broadcast;
+use tari_test_utils::{async_assert, unpack_enum};
+use tokio::{sync::broadcast, time};
 
 async fn setup(
     config: DhtConfig,
@@ -74,7 +82,7 @@ async fn setup(
         node_identity.clone(),
         connectivity,
         dht_requester,
-        event_publisher.subscribe(),
+        event_publisher,
         MetricsCollector::spawn(),
         shutdown.to_signal(),
     );
@@ -215,6 +223,106 @@ async fn reinitialize_pools_when_offline() {
     assert_eq!(managed.len(), 5);
 }
 
+#[tokio_macros::test_basic]
+async fn heal_connectivity_when_degraded() {
+    let node_identity = make_node_identity();
+    let mut peers = repeat_with(|| make_node_identity().to_peer())
+        .take(3)
+        .collect::<Vec<_>>();
+    // Only the first peer has been connected to before
+    let address = peers[0].addresses.first().unwrap().address.clone();
+    peers[0].addresses.mark_successful_connection_attempt(&address);
+    let previously_connected = peers[0].node_id.clone();
+
+    let config = DhtConfig {
+        num_neighbouring_nodes: 0,
+        num_random_nodes: 1,
+        ..Default::default()
+    };
+    let (dht_connectivity, _, connectivity, _, _, _shutdown) = setup(config, node_identity, peers).await;
+    let mut dht_events = dht_connectivity.event_publisher.subscribe();
+    dht_connectivity.spawn();
+
+    connectivity.publish_event(ConnectivityEvent::ConnectivityStateDegraded(0));
+
+    let event = time::timeout(Duration::from_secs(5), dht_events.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    unpack_enum!(DhtEvent::NetworkDiscoveryRequested = &*event);
+    let managed = connectivity.get_managed_peers().await;
+    assert!(managed.contains(&previously_connected));
+}
+
+#[tokio_macros::test_basic]
+async fn connection_diversity() {
+    let config = DhtConfig {
+        num_neighbouring_nodes: 0,
+        num_random_nodes: 2,
+        eclipse_detection_min_connections: 2,
+        ..Default::default()
+    };
+    let peers = repeat_with(|| make_node_identity().to_peer()).take(5).collect();
+    let (mut dht_connectivity, _, connectivity, _, _, _shutdown) = setup(config, make_node_identity(), peers).await;
+    let random_pool = vec![make_node_identity().node_id().clone()];
+    dht_connectivity.random_pool = random_pool.clone();
+
+    let add_connection = |address: &str, direction| {
+        let (conn, _) = create_dummy_peer_connection_with_address(
+            make_node_identity().node_id().clone(),
+            address.parse::<Multiaddr>().unwrap(),
+            direction,
+        );
+        connectivity.add_active_connection(conn)
+    };
+
+    // Inbound connections through a Tor proxy and outbound onion connections cannot be measured
+    for _ in 0..3 {
+        add_connection("/ip4/127.0.0.1/tcp/1234", ConnectionDirection::Inbound).await;
+        add_connection(
+            "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234",
+            ConnectionDirection::Outbound,
+        )
+        .await;
+    }
+    // A single measured connection cannot be compared with anything
+    add_connection("/ip4/8.8.1.1/tcp/1234", ConnectionDirection::Outbound).await;
+    dht_connectivity.check_connection_diversity().await.unwrap();
+    assert_eq!(dht_connectivity.random_pool, random_pool);
+
+    // Two outbound connections in the same network group
+    add_connection("/ip4/8.8.2.2/tcp/1234", ConnectionDirection::Outbound).await;
+    dht_connectivity.check_connection_diversity().await.unwrap();
+    assert_eq!(dht_connectivity.random_pool.len(), 2);
+    assert!(!dht_connectivity.random_pool.contains(&random_pool[0]));
+
+    // The new random pool is given time to connect before the check runs again
+    let diverse_pool = dht_connectivity.random_pool.clone();
+    dht_connectivity.check_connection_diversity().await.unwrap();
+    assert_eq!(dht_connectivity.random_pool, diverse_pool);
+}
+
+#[tokio_macros::test_basic]
+async fn heal_backoff() {
+    let config = DhtConfig {
+        connectivity_heal_min_backoff: Duration::from_secs(10),
+        connectivity_heal_max_backoff: Duration::from_secs(60),
+        ..Default::default()
+    };
+    let (mut dht_connectivity, _, _, _, _, _) = setup(config, make_node_identity(), vec![]).await;
+
+    let backoffs = (1..=5)
+        .map(|n| {
+            dht_connectivity.num_heal_attempts = n;
+            dht_connectivity.heal_backoff().as_secs()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(backoffs, vec![10, 20, 40, 60, 60]);
+
+    dht_connectivity.num_heal_attempts = 100;
+    assert_eq!(dht_connectivity.heal_backoff(), Duration::from_secs(60));
+}
+
 #[tokio_macros::test_basic]
 async fn insert_neighbour() {
     let node_identity = make_node_identity();
@@ -0,0 +1,214 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use digest::Digest;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, time::Duration};
+use tari_comms::types::Challenge;
+
+/// A bloom filter made up of a number of generations, each covering an equal slice of the retention window. New
+/// hashes are inserted into the newest generation and a hash is considered seen if any generation contains it. When
+/// the newest generation is older than its slice, a fresh generation is started and the oldest one is discarded, so a
+/// hash is remembered for at least `retention * (generations - 1) / generations` and at most `retention`.
+///
+/// The filter is compact enough to be persisted, allowing messages seen before a restart to be recognised afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotatingBloomFilter {
+    num_bits: u64,
+    num_hashes: u32,
+    rotation_interval_secs: u64,
+    max_generations: usize,
+    generations: VecDeque<BloomGeneration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BloomGeneration {
+    /// Unix timestamp (seconds) at which this generation started accepting hashes
+    started_at: i64,
+    bits: Vec<u64>,
+}
+
+impl RotatingBloomFilter {
+    /// Creates a filter sized so that each generation holds `capacity` hashes at the given false positive rate.
+    pub fn new(capacity: usize, false_positive_rate: f64, retention: Duration, generations: usize, now: i64) -> Self {
+        let (num_bits, num_hashes) = optimal_parameters(capacity, false_positive_rate);
+        let max_generations = generations.max(1);
+        let mut filter = Self {
+            num_bits,
+            num_hashes,
+            rotation_interval_secs: (retention.as_secs() / max_generations as u64).max(1),
+            max_generations,
+            generations: VecDeque::with_capacity(max_generations),
+        };
+        filter.push_generation(now);
+        filter
+    }
+
+    /// Returns true if this filter was created with the same parameters as `other`, and so can replace it
+    pub fn is_compatible_with(&self, other: &Self) -> bool {
+        self.num_bits == other.num_bits &&
+            self.num_hashes == other.num_hashes &&
+            self.rotation_interval_secs == other.rotation_interval_secs &&
+            self.max_generations == other.max_generations
+    }
+
+    /// Returns true if the hash has (probably) been inserted within the retention window
+    pub fn contains(&self, hash: &[u8]) -> bool {
+        let indexes = self.bit_indexes(hash);
+        self.generations
+            .iter()
+            .any(|gen| indexes.iter().all(|&i| gen.bits[i / 64] & (1 << (i % 64)) != 0))
+    }
+
+    /// Inserts the hash into the newest generation
+    pub fn insert(&mut self, hash: &[u8]) {
+        let indexes = self.bit_indexes(hash);
+        let gen = self
+            .generations
+            .front_mut()
+            .expect("RotatingBloomFilter always has at least one generation");
+        for i in indexes {
+            gen.bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    /// Starts a new generation if the newest one has been accepting hashes for longer than its slice of the
+    /// retention window, discarding any generations that have fallen out of the window. Returns true if the filter
+    /// rotated.
+    pub fn rotate_if_due(&mut self, now: i64) -> bool {
+        let interval = self.rotation_interval_secs as i64;
+        let newest_started_at = self.generations.front().map(|g| g.started_at).unwrap_or(i64::MIN);
+        if now.saturating_sub(newest_started_at) < interval {
+            return false;
+        }
+        let retention = interval * self.max_generations as i64;
+        self.generations
+            .retain(|g| now.saturating_sub(g.started_at) < retention);
+        self.push_generation(now);
+        true
+    }
+
+    /// The size of the filter bits in bytes
+    pub fn size_in_bytes(&self) -> usize {
+        self.generations.len() * self.words_per_generation() * 8
+    }
+
+    fn push_generation(&mut self, now: i64) {
+        let words = self.words_per_generation();
+        self.generations.push_front(BloomGeneration {
+            started_at: now,
+            bits: vec![0; words],
+        });
+        self.generations.truncate(self.max_generations);
+    }
+
+    fn words_per_generation(&self) -> usize {
+        ((self.num_bits + 63) / 64) as usize
+    }
+
+    /// Derives the bit indexes for a hash using double hashing over a digest of the hash, so arbitrary length input
+    /// is spread evenly over the filter
+    fn bit_indexes(&self, hash: &[u8]) -> Vec<usize> {
+        let digest = Challenge::new().chain(hash).finalize();
+        let mut h1 = [0u8; 8];
+        let mut h2 = [0u8; 8];
+        h1.copy_from_slice(&digest[0..8]);
+        h2.copy_from_slice(&digest[8..16]);
+        let h1 = u64::from_le_bytes(h1);
+        // An odd step guarantees that the probe sequence does not collapse onto a single bit
+        let h2 = u64::from_le_bytes(h2) | 1;
+        (0..u64::from(self.num_hashes))
+            .map(|i| (h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits) as usize)
+            .collect()
+    }
+}
+
+/// Calculates the number of bits and hash functions needed to hold `capacity` items at the given false positive rate
+fn optimal_parameters(capacity: usize, false_positive_rate: f64) -> (u64, u32) {
+    let capacity = capacity.max(1) as f64;
+    let false_positive_rate = false_positive_rate.max(f64::MIN_POSITIVE).min(0.5);
+    let ln2 = std::f64::consts::LN_2;
+    let num_bits = (-capacity * false_positive_rate.ln() / (ln2 * ln2)).ceil().max(64.0);
+    let num_hashes = (num_bits / capacity * ln2).round().max(1.0);
+    (num_bits as u64, num_hashes as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_utilities::message_format::MessageFormat;
+
+    const HOUR: i64 = 60 * 60;
+
+    fn make_filter(now: i64) -> RotatingBloomFilter {
+        RotatingBloomFilter::new(1000, 0.0001, Duration::from_secs(3 * HOUR as u64), 3, now)
+    }
+
+    #[test]
+    fn insert_and_contains() {
+        let mut filter = make_filter(0);
+        for i in 0..1000u32 {
+            filter.insert(&i.to_le_bytes());
+        }
+        assert!((0..1000u32).all(|i| filter.contains(&i.to_le_bytes())));
+        let false_positives = (1000..11000u32).filter(|i| filter.contains(&i.to_le_bytes())).count();
+        assert!(false_positives < 10, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn it_forgets_hashes_after_retention() {
+        let mut filter = make_filter(0);
+        filter.insert(b"first");
+
+        assert!(!filter.rotate_if_due(HOUR - 1));
+        assert!(filter.rotate_if_due(HOUR));
+        filter.insert(b"second");
+        assert!(filter.rotate_if_due(2 * HOUR));
+        assert!(filter.contains(b"first"));
+        assert!(filter.contains(b"second"));
+
+        assert!(filter.rotate_if_due(3 * HOUR));
+        assert!(!filter.contains(b"first"));
+        assert!(filter.contains(b"second"));
+    }
+
+    #[test]
+    fn it_drops_expired_generations_after_downtime() {
+        let mut filter = make_filter(0);
+        filter.insert(b"old");
+        assert!(filter.rotate_if_due(10 * HOUR));
+        assert!(!filter.contains(b"old"));
+        assert_eq!(filter.generations.len(), 1);
+    }
+
+    #[test]
+    fn it_survives_serialization() {
+        let mut filter = make_filter(0);
+        filter.insert(b"persisted");
+        let bytes = filter.to_binary().unwrap();
+        let restored = RotatingBloomFilter::from_binary(&bytes).unwrap();
+        assert!(restored.contains(b"persisted"));
+        assert!(restored.is_compatible_with(&filter));
+        let resized = RotatingBloomFilter::new(10, 0.0001, Duration::from_secs(3 * HOUR as u64), 3, 0);
+        assert!(!restored.is_compatible_with(&resized));
+    }
+}
@@ -20,15 +20,17 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+mod bloom;
 mod dedup_cache;
 
+pub use bloom::RotatingBloomFilter;
 pub use dedup_cache::DedupCacheDatabase;
 
 use crate::{actor::DhtRequester, inbound::DhtInboundMessage};
 use digest::Digest;
 use futures::{future::BoxFuture, task::Context};
 use log::*;
-use std::task::Poll;
+use std::{fmt, task::Poll};
 use tari_comms::{pipeline::PipelineError, types::Challenge};
 use tari_utilities::hex::Hex;
 use tower::{layer::Layer, Service, ServiceExt};
@@ -39,6 +41,50 @@ fn hash_inbound_message(message: &DhtInboundMessage) -> Vec<u8> {
     Challenge::new().chain(&message.body).finalize().to_vec()
 }
 
+/// Hit-rate counters for the message dedup cache since the DHT started
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DedupCacheStats {
+    /// The number of message hashes checked against the cache
+    pub num_lookups: u64,
+    /// The number of duplicates found in the message hash database
+    pub num_db_hits: u64,
+    /// The number of duplicates found only in the persistent bloom filter, typically messages seen before a restart
+    /// or since trimmed from the database
+    pub num_bloom_hits: u64,
+}
+
+impl DedupCacheStats {
+    pub(crate) fn record(&mut self, db_hit: bool, bloom_hit: bool) {
+        self.num_lookups += 1;
+        if db_hit {
+            self.num_db_hits += 1;
+        } else if bloom_hit {
+            self.num_bloom_hits += 1;
+        }
+    }
+
+    /// The proportion of checked messages that were duplicates
+    pub fn hit_rate(&self) -> f64 {
+        if self.num_lookups == 0 {
+            return 0.0;
+        }
+        (self.num_db_hits + self.num_bloom_hits) as f64 / self.num_lookups as f64
+    }
+}
+
+impl fmt::Display for DedupCacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "lookups: {}, database hits: {}, bloom filter hits: {}, hit rate: {:.2}%",
+            self.num_lookups,
+            self.num_db_hits,
+            self.num_bloom_hits,
+            self.hit_rate() * 100.0
+        )
+    }
+}
+
 /// # DHT Deduplication middleware
 ///
 /// Takes in a `DhtInboundMessage` and checks the message signature cache for duplicates.
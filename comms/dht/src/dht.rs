@@ -200,7 +200,7 @@ impl Dht {
             self.node_identity.clone(),
             self.connectivity.clone(),
             self.dht_requester(),
-            self.event_publisher.subscribe(),
+            self.event_publisher.clone(),
             self.metrics_collector.clone(),
             shutdown_signal,
         )
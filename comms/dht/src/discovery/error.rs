@@ -47,6 +47,8 @@ pub enum DhtDiscoveryError {
     PeerManagerError(#[from] PeerManagerError),
     #[error("InvalidPeerMultiaddr: {0}")]
     InvalidPeerMultiaddr(String),
+    #[error("The discovered peer's identity signature was missing or invalid")]
+    InvalidIdentitySignature,
 }
 
 impl DhtDiscoveryError {
@@ -36,12 +36,13 @@ use log::*;
 use rand::{rngs::OsRng, RngCore};
 use std::{
     collections::HashMap,
+    convert::TryFrom,
     sync::Arc,
     time::{Duration, Instant},
 };
 use tari_comms::{
     log_if_error,
-    peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerManager},
+    peer_manager::{IdentitySignature, NodeId, NodeIdentity, Peer, PeerFeatures, PeerManager, PeerManagerError},
     types::CommsPublicKey,
     validate_peer_addresses,
 };
@@ -250,17 +251,29 @@ impl DhtDiscoveryService {
         validate_peer_addresses(&addresses, self.config.allow_test_addresses)
             .map_err(|err| DhtDiscoveryError::InvalidPeerMultiaddr(err.to_string()))?;
 
-        let peer = self
+        let identity_signature = discovery_msg
+            .identity_signature
+            .map(IdentitySignature::try_from)
+            .transpose()
+            .map_err(|_| DhtDiscoveryError::InvalidIdentitySignature)?
+            .ok_or(DhtDiscoveryError::InvalidIdentitySignature)?;
+
+        // TODO: Misbehaviour #banheuristic
+        match self
             .peer_manager
-            .add_or_update_online_peer(
+            .add_or_update_signed_peer(
                 &public_key,
                 node_id,
                 addresses,
                 PeerFeatures::from_bits_truncate(discovery_msg.peer_features),
+                identity_signature,
             )
-            .await?;
-
-        Ok(peer)
+            .await
+        {
+            Ok(peer) => Ok(peer),
+            Err(PeerManagerError::InvalidIdentitySignature(_)) => Err(DhtDiscoveryError::InvalidIdentitySignature),
+            Err(err) => Err(err.into()),
+        }
     }
 
     fn validate_raw_node_id(
@@ -332,6 +345,7 @@ impl DhtDiscoveryService {
             addresses: vec![self.node_identity.public_address().to_string()],
             peer_features: self.node_identity.features().bits(),
             nonce,
+            identity_signature: Some((&self.node_identity.identity_signature()).into()),
         };
         debug!(
             target: LOG_TARGET,
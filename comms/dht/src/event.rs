@@ -35,4 +35,8 @@ pub enum DhtEvent {
 
     /// Emitted by the NetworkDiscovery actor once a round of peer syncing has completed.
     NetworkDiscoveryPeersAdded(DhtNetworkDiscoveryRoundInfo),
+
+    /// Emitted by the DhtConnectivity actor while it is attempting to restore connectivity. If idle, the
+    /// NetworkDiscovery actor starts a new round of peer syncing.
+    NetworkDiscoveryRequested,
 }
@@ -28,13 +28,15 @@ use crate::{
     proto::{
         dht::{DiscoveryMessage, DiscoveryResponseMessage, JoinMessage},
         envelope::DhtMessageType,
+        IdentitySignature as IdentitySignatureProto,
     },
 };
 use log::*;
-use std::sync::Arc;
+use std::{convert::TryFrom, sync::Arc};
 use tari_comms::{
     message::MessageExt,
-    peer_manager::{NodeId, NodeIdentity, PeerFeatures, PeerManager},
+    multiaddr::Multiaddr,
+    peer_manager::{IdentitySignature, NodeId, NodeIdentity, Peer, PeerFeatures, PeerManager, PeerManagerError},
     pipeline::PipelineError,
     types::CommsPublicKey,
 };
@@ -129,6 +131,40 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         }
     }
 
+    /// Adds or updates the peer from a signed address record. Unsigned records, or records with an invalid signature
+    /// are rejected, so that peers cannot spoof the addresses of other peers.
+    async fn add_or_update_signed_peer(
+        &mut self,
+        authenticated_pk: &CommsPublicKey,
+        node_id: NodeId,
+        addresses: Vec<Multiaddr>,
+        peer_features: u64,
+        identity_signature: Option<IdentitySignatureProto>,
+    ) -> Result<Peer, DhtInboundError> {
+        let identity_signature = identity_signature
+            .map(IdentitySignature::try_from)
+            .transpose()
+            .map_err(|_| DhtInboundError::InvalidIdentitySignature)?
+            .ok_or(DhtInboundError::InvalidIdentitySignature)?;
+
+        // TODO: Misbehaviour?
+        match self
+            .peer_manager
+            .add_or_update_signed_peer(
+                authenticated_pk,
+                node_id,
+                addresses,
+                PeerFeatures::from_bits_truncate(peer_features),
+                identity_signature,
+            )
+            .await
+        {
+            Ok(peer) => Ok(peer),
+            Err(PeerManagerError::InvalidIdentitySignature(_)) => Err(DhtInboundError::InvalidIdentitySignature),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     async fn handle_join(&mut self, message: DecryptedDhtMessage) -> Result<(), DhtInboundError> {
         let DecryptedDhtMessage {
             decryption_result,
@@ -171,12 +207,12 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         let node_id = self.validate_raw_node_id(&authenticated_pk, &join_msg.node_id)?;
 
         let origin_peer = self
-            .peer_manager
-            .add_or_update_online_peer(
+            .add_or_update_signed_peer(
                 &authenticated_pk,
                 node_id,
                 addresses,
-                PeerFeatures::from_bits_truncate(join_msg.peer_features),
+                join_msg.peer_features,
+                join_msg.identity_signature,
             )
             .await?;
 
@@ -282,12 +318,12 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
 
         let node_id = self.validate_raw_node_id(&authenticated_pk, &discover_msg.node_id)?;
         let origin_peer = self
-            .peer_manager
-            .add_or_update_online_peer(
+            .add_or_update_signed_peer(
                 &authenticated_pk,
                 node_id,
                 addresses,
-                PeerFeatures::from_bits_truncate(discover_msg.peer_features),
+                discover_msg.peer_features,
+                discover_msg.identity_signature,
             )
             .await?;
 
@@ -319,6 +355,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
             addresses: vec![self.node_identity.public_address().to_string()],
             peer_features: self.node_identity.features().bits(),
             nonce,
+            identity_signature: Some((&self.node_identity.identity_signature()).into()),
         };
 
         trace!(target: LOG_TARGET, "Sending discovery response to {}", dest_public_key);
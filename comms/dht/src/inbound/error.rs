@@ -38,6 +38,8 @@ pub enum DhtInboundError {
     InvalidNodeId,
     #[error("All given addresses were invalid")]
     InvalidAddresses,
+    #[error("The peer's identity signature was missing or invalid")]
+    InvalidIdentitySignature,
     #[error("DhtDiscoveryError: {0}")]
     DhtDiscoveryError(#[from] DhtDiscoveryError),
     #[error("OriginRequired: {0}")]
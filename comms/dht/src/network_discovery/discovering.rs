@@ -199,6 +199,17 @@ impl Discovering {
             return Ok(());
         }
 
+        if !peer.is_valid_identity_signature() {
+            // TODO: #banheuristic
+            debug!(
+                target: LOG_TARGET,
+                "Peer `{}` received from `{}` does not have a valid identity signature. Peer not added.",
+                peer.node_id,
+                sync_peer
+            );
+            return Ok(());
+        }
+
         let peer_dist = peer.node_id.distance(self.context.node_identity.node_id());
         let is_neighbour = peer_dist <= self.neighbourhood_threshold;
 
@@ -169,6 +169,17 @@ impl OnConnect {
             return Ok(false);
         }
 
+        if !peer.is_valid_identity_signature() {
+            // TODO: #banheuristic
+            debug!(
+                target: LOG_TARGET,
+                "Peer `{}` received from `{}` does not have a valid identity signature. Peer not added.",
+                peer.node_id,
+                sync_peer
+            );
+            return Ok(false);
+        }
+
         let addresses = peer.addresses.iter();
         match validate_peer_addresses(addresses, self.config().allow_test_addresses) {
             Ok(_) => {
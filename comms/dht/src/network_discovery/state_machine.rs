@@ -21,7 +21,7 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    event::DhtEvent,
+    event::{DhtEvent, DhtEventReceiver},
     network_discovery::{
         discovering::Discovering,
         initializing::Initializing,
@@ -80,6 +80,11 @@ impl State {
     pub fn is_shutdown(&self) -> bool {
         matches!(self, State::Shutdown)
     }
+
+    /// Returns true if the state is waiting for something to happen, rather than performing a discovery round
+    pub fn is_idle(&self) -> bool {
+        matches!(self, State::Waiting(_) | State::OnConnect(_))
+    }
 }
 
 #[derive(Debug)]
@@ -90,6 +95,7 @@ pub enum StateEvent {
     Idle,
     OnConnectMode,
     DiscoveryComplete(DhtNetworkDiscoveryRoundInfo),
+    DiscoveryRequested,
     Errored(NetworkDiscoveryError),
     Shutdown,
 }
@@ -103,6 +109,7 @@ impl Display for StateEvent {
             Ready => write!(f, "Ready"),
             Idle => write!(f, "Idle"),
             DiscoveryComplete(stats) => write!(f, "DiscoveryComplete({})", stats),
+            DiscoveryRequested => write!(f, "DiscoveryRequested"),
             Errored(err) => write!(f, "Errored({})", err),
             OnConnectMode => write!(f, "OnConnectMode"),
             Shutdown => write!(f, "Shutdown"),
@@ -159,6 +166,13 @@ impl NetworkDiscoveryContext {
     pub async fn last_round(&self) -> Option<DhtNetworkDiscoveryRoundInfo> {
         self.last_round.read().await.as_ref().cloned()
     }
+
+    /// Forget previous rounds so that the next round may select any peer
+    pub(super) async fn reset_rounds(&self) {
+        self.reset_num_rounds();
+        self.all_attempted_peers.write().await.clear();
+        *self.last_round.write().await = None;
+    }
 }
 
 pub struct DhtNetworkDiscovery {
@@ -229,6 +243,11 @@ impl DhtNetworkDiscovery {
                 State::Ready(DiscoveryReady::new(self.context.clone()))
             },
             (State::Ready(_), StateEvent::Idle) => State::Waiting(config.idle_period.into()),
+            (state, StateEvent::DiscoveryRequested) if state.is_idle() => {
+                // Start afresh so that peers that were already attempted can be synced from again
+                self.context.reset_rounds().await;
+                State::Ready(DiscoveryReady::new(self.context.clone()))
+            },
             (_, StateEvent::Shutdown) => State::Shutdown,
             (_, StateEvent::Errored(err)) => {
                 error!(
@@ -266,12 +285,24 @@ impl DhtNetworkDiscovery {
             return;
         }
         let mut state = State::Initializing;
+        let mut dht_events = self.context.event_tx.subscribe();
         loop {
             let shutdown_signal = self.shutdown_signal.clone();
+            let is_idle = state.is_idle();
+            if is_idle {
+                // Discard requests made while a round was in progress
+                while dht_events.try_recv().is_ok() {}
+            }
             let next_event = {
                 let fut = self.get_next_event(&mut state);
                 futures::pin_mut!(fut);
-                or_shutdown(shutdown_signal, fut).await
+                if is_idle {
+                    let fut = or_discovery_requested(&mut dht_events, fut);
+                    futures::pin_mut!(fut);
+                    or_shutdown(shutdown_signal, fut).await
+                } else {
+                    or_shutdown(shutdown_signal, fut).await
+                }
             };
             state = self.transition(state, next_event).await;
             if state.is_shutdown() {
@@ -289,6 +320,30 @@ where Fut: Future<Output = StateEvent> + Unpin {
     }
 }
 
+/// Polls the given future until it resolves or a `NetworkDiscoveryRequested` event is received
+async fn or_discovery_requested<Fut>(dht_events: &mut DhtEventReceiver, fut: Fut) -> StateEvent
+where Fut: Future<Output = StateEvent> + Unpin {
+    let requested = async {
+        loop {
+            match dht_events.recv().await {
+                Ok(event) => {
+                    if let DhtEvent::NetworkDiscoveryRequested = &*event {
+                        break;
+                    }
+                },
+                Err(broadcast::RecvError::Lagged(_)) => {},
+                // No more requests can be made
+                Err(broadcast::RecvError::Closed) => future::pending::<()>().await,
+            }
+        }
+    };
+    futures::pin_mut!(requested);
+    match future::select(fut, requested).await {
+        Either::Left((event, _)) => event,
+        Either::Right(_) => StateEvent::DiscoveryRequested,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DiscoveryParams {
     pub peers: Vec<NodeId>,
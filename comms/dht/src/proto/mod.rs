@@ -21,14 +21,20 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::proto::dht::JoinMessage;
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use rand::{rngs::OsRng, RngCore};
-use std::{convert::TryInto, fmt};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt,
+};
 use tari_comms::{
     multiaddr::Multiaddr,
-    peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
-    types::CommsPublicKey,
+    peer_manager::{self, NodeId, Peer, PeerFeatures, PeerFlags},
+    types::{CommsPublicKey, CommsSecretKey},
     NodeIdentity,
 };
+use tari_crypto::ristretto::RistrettoSchnorr;
 use tari_utilities::{hex::Hex, ByteArray};
 
 pub mod envelope {
@@ -38,6 +44,8 @@ pub mod envelope {
 pub mod dht {
     tari_comms::outdir_include!("tari.dht.rs");
 }
+// The generated rpc module refers to the dht IdentitySignature message as `super::IdentitySignature`
+pub use dht::IdentitySignature;
 
 pub mod rpc {
     tari_comms::outdir_include!("tari.dht.rpc.rs");
@@ -61,6 +69,7 @@ impl<T: AsRef<NodeIdentity>> From<T> for JoinMessage {
             addresses: vec![node_identity.public_address().to_string()],
             peer_features: node_identity.features().bits(),
             nonce: OsRng.next_u64(),
+            identity_signature: Some((&node_identity.identity_signature()).into()),
         }
     }
 }
@@ -82,6 +91,7 @@ impl fmt::Display for dht::JoinMessage {
 impl From<Peer> for rpc::Peer {
     fn from(peer: Peer) -> Self {
         rpc::Peer {
+            identity_signature: peer.identity_signature.as_ref().map(Into::into),
             public_key: peer.public_key.to_vec(),
             addresses: peer
                 .addresses
@@ -106,7 +116,7 @@ impl TryInto<Peer> for rpc::Peer {
             .filter_map(|addr| addr.parse::<Multiaddr>().ok())
             .collect::<Vec<_>>();
 
-        Ok(Peer::new(
+        let mut peer = Peer::new(
             pk,
             node_id,
             addresses.into(),
@@ -114,6 +124,39 @@ impl TryInto<Peer> for rpc::Peer {
             PeerFeatures::from_bits_truncate(self.peer_features),
             Default::default(),
             "".to_string(),
+        );
+        peer.identity_signature = self.identity_signature.map(TryInto::try_into).transpose()?;
+        Ok(peer)
+    }
+}
+
+//---------------------------------- IdentitySignature --------------------------------------------//
+
+impl TryFrom<IdentitySignature> for peer_manager::IdentitySignature {
+    type Error = anyhow::Error;
+
+    fn try_from(value: IdentitySignature) -> Result<Self, Self::Error> {
+        let version = u8::try_from(value.version).map_err(|_| anyhow!("Invalid identity signature version"))?;
+        let public_nonce = CommsPublicKey::from_bytes(&value.public_nonce)?;
+        let signature = CommsSecretKey::from_bytes(&value.signature)?;
+        let updated_at = NaiveDateTime::from_timestamp_opt(value.updated_at, 0)
+            .ok_or_else(|| anyhow!("Invalid identity signature timestamp"))?;
+
+        Ok(Self::new(
+            version,
+            RistrettoSchnorr::new(public_nonce, signature),
+            DateTime::from_utc(updated_at, Utc),
         ))
     }
 }
+
+impl From<&peer_manager::IdentitySignature> for IdentitySignature {
+    fn from(identity_signature: &peer_manager::IdentitySignature) -> Self {
+        IdentitySignature {
+            version: u32::from(identity_signature.version()),
+            signature: identity_signature.signature().get_signature().to_vec(),
+            public_nonce: identity_signature.signature().get_public_nonce().to_vec(),
+            updated_at: identity_signature.updated_at().timestamp(),
+        }
+    }
+}
@@ -27,6 +27,8 @@ use std::fmt;
 pub enum DhtMetadataKey {
     /// Timestamp each time the DHT is shut down
     OfflineTimestamp,
+    /// The rotating bloom filter of recently seen message hashes
+    DedupBloomFilter,
 }
 
 impl fmt::Display for DhtMetadataKey {
@@ -134,6 +134,9 @@ impl DhtActorMock {
                 self.state.settings.write().unwrap().insert(key.to_string(), value);
                 reply_tx.send(Ok(())).unwrap();
             },
+            GetDedupCacheStats(reply_tx) => {
+                let _ = reply_tx.send(Default::default());
+            },
         }
     }
 }
@@ -46,13 +46,14 @@ use crate::{
     connection_manager::{ConnectionManagerConfig, ConnectionManagerRequester},
     connectivity::{ConnectivityConfig, ConnectivityRequester},
     multiaddr::Multiaddr,
+    multiplexing::SubstreamSettings,
     peer_manager::{NodeIdentity, PeerManager},
-    protocol::{NodeNetworkInfo, ProtocolExtensions},
+    protocol::{NodeNetworkInfo, ProtocolExtensions, ProtocolId},
     tor,
     types::CommsDatabase,
 };
 use futures::channel::mpsc;
-use std::{fs::File, sync::Arc};
+use std::{fs::File, sync::Arc, time::Duration};
 use tari_shutdown::ShutdownSignal;
 use tokio::sync::broadcast;
 
@@ -129,6 +130,12 @@ impl CommsBuilder {
         self
     }
 
+    /// Refuse connections from and to peers that advertise a minor protocol version below `min_minor_version`
+    pub fn with_min_peer_minor_version(mut self, min_minor_version: u32) -> Self {
+        self.connection_manager_config.min_peer_minor_version = min_minor_version;
+        self
+    }
+
     /// Allow test addresses (memory addresses, local loopback etc). This should only be activated for tests.
     pub fn allow_test_addresses(mut self) -> Self {
         #[cfg(not(debug_assertions))]
@@ -155,6 +162,15 @@ impl CommsBuilder {
         self
     }
 
+    /// Set the write scheduling settings for the substreams of the given protocol, e.g. to give way to
+    /// latency-sensitive protocols by marking a sync protocol as `SubstreamPriority::Bulk`.
+    pub fn with_substream_settings(mut self, protocol: ProtocolId, settings: SubstreamSettings) -> Self {
+        self.connection_manager_config
+            .substream_settings
+            .insert(protocol, settings);
+        self
+    }
+
     pub fn with_listener_liveness_allowlist_cidrs(mut self, cidrs: Vec<cidr::AnyIpCidr>) -> Self {
         self.connection_manager_config.liveness_cidr_allowlist = cidrs;
         self
@@ -173,12 +189,30 @@ impl CommsBuilder {
         self
     }
 
+    /// The maximum number of a peer's addresses to dial at the same time.
+    pub fn with_max_parallel_dials_per_peer(mut self, max_parallel_dials_per_peer: usize) -> Self {
+        self.connection_manager_config.max_parallel_dials_per_peer = max_parallel_dials_per_peer;
+        self
+    }
+
+    /// The time to wait after starting a dial to one of a peer's addresses before also dialing the next address.
+    pub fn with_dial_stagger_delay(mut self, dial_stagger_delay: Duration) -> Self {
+        self.connection_manager_config.dial_stagger_delay = dial_stagger_delay;
+        self
+    }
+
     /// Sets the minimum required connectivity as a percentage of peers added to the connectivity manager peer set.
     pub fn with_min_connectivity(mut self, min_connectivity: f32) -> Self {
         self.connectivity_config.min_connectivity = min_connectivity;
         self
     }
 
+    /// Sets the minimum number of connected nodes required for connectivity to be considered ONLINE.
+    pub fn with_min_connected_nodes(mut self, min_connected_nodes: usize) -> Self {
+        self.connectivity_config.min_connected_nodes = min_connected_nodes;
+        self
+    }
+
     /// Call to disable connection reaping. Usually you would want to have this enabled, however there are some test
     /// cases where disabling this is desirable.
     pub fn disable_connection_reaping(mut self) -> Self {
@@ -38,14 +38,16 @@ use crate::{
     },
     runtime,
     test_utils::node_identity::build_node_identity,
-    transports::MemoryTransport,
+    transports::{MemoryTransport, Transport},
     CommsNode,
 };
 use bytes::Bytes;
 use futures::{
     channel::{mpsc, oneshot},
     stream::FuturesUnordered,
+    AsyncRead,
     AsyncReadExt,
+    AsyncWrite,
     AsyncWriteExt,
     SinkExt,
     StreamExt,
@@ -68,6 +70,24 @@ async fn spawn_node(
     let addr = format!("/memory/{}", memsocket::acquire_next_memsocket_port())
         .parse::<Multiaddr>()
         .unwrap();
+    spawn_node_with_transport(protocols, shutdown_sig, addr, MemoryTransport).await
+}
+
+async fn spawn_node_with_transport<TTransport>(
+    protocols: Protocols<Substream>,
+    shutdown_sig: ShutdownSignal,
+    addr: Multiaddr,
+    transport: TTransport,
+) -> (
+    CommsNode,
+    mpsc::Receiver<InboundMessage>,
+    mpsc::Sender<OutboundMessage>,
+    MessagingEventSender,
+)
+where
+    TTransport: Transport + Unpin + Send + Sync + Clone + 'static,
+    TTransport::Output: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+{
     let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
     node_identity.set_public_address(addr.clone());
 
@@ -100,7 +120,7 @@ async fn spawn_node(
                 .with_inbound_pipeline(SinkService::new(inbound_tx))
                 .build(),
         ))
-        .spawn_with_transport(MemoryTransport)
+        .spawn_with_transport(transport)
         .await
         .unwrap();
 
@@ -279,6 +299,67 @@ async fn peer_to_peer_messaging() {
     comms_node2.wait_until_shutdown().await;
 }
 
+#[cfg(feature = "simulation")]
+#[runtime::test_basic]
+async fn peer_to_peer_messaging_over_simulated_network() {
+    use crate::{test_utils::transport::spawn_simulated_clock, transports::SimulatedNetwork};
+
+    const LATENCY: Duration = Duration::from_millis(200);
+    let network = SimulatedNetwork::new().with_default_latency(LATENCY);
+    let shutdown = Shutdown::new();
+    let (comms_node1, _, mut outbound_tx1, _) = spawn_node_with_transport(
+        Protocols::new(),
+        shutdown.to_signal(),
+        "/memory/1".parse().unwrap(),
+        network.transport("node1"),
+    )
+    .await;
+    let (comms_node2, mut inbound_rx2, _, _) = spawn_node_with_transport(
+        Protocols::new(),
+        shutdown.to_signal(),
+        "/memory/2".parse().unwrap(),
+        network.transport("node2"),
+    )
+    .await;
+    spawn_simulated_clock(network.clone(), Duration::from_millis(10));
+
+    let node_identity2 = comms_node2.node_identity();
+    comms_node1
+        .peer_manager()
+        .add_peer(Peer::new(
+            node_identity2.public_key().clone(),
+            node_identity2.node_id().clone(),
+            node_identity2.public_address().clone().into(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        ))
+        .await
+        .unwrap();
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    outbound_tx1
+        .send(OutboundMessage::with_reply(
+            node_identity2.node_id().clone(),
+            Bytes::from_static(b"Hello over the simulated network"),
+            reply_tx.into(),
+        ))
+        .await
+        .unwrap();
+
+    let messages = collect_stream!(inbound_rx2, take = 1, timeout = Duration::from_secs(10));
+    assert_eq!(&messages[0].body[..], b"Hello over the simulated network");
+    reply_rx.await.unwrap().unwrap();
+    assert_eq!(&messages[0].source_peer, comms_node1.node_identity().node_id());
+    // Connecting and sending the message takes several round trips on the network
+    assert!(network.now() >= LATENCY * 2);
+
+    drop(shutdown);
+    comms_node1.wait_until_shutdown().await;
+    comms_node2.wait_until_shutdown().await;
+}
+
 #[runtime::test_basic]
 async fn peer_to_peer_messaging_simultaneous() {
     const NUM_MSGS: usize = 10;
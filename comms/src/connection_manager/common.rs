@@ -25,7 +25,7 @@ use crate::{
     connection_manager::error::ConnectionManagerError,
     multiaddr::{Multiaddr, Protocol},
     multiplexing::Yamux,
-    peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerFlags},
+    peer_manager::{IdentitySignature, NodeId, NodeIdentity, Peer, PeerFeatures, PeerFlags},
     proto::identity::PeerIdentityMsg,
     protocol,
     protocol::{NodeNetworkInfo, ProtocolId},
@@ -75,6 +75,7 @@ pub async fn perform_identity_exchange<'p, P: IntoIterator<Item = &'p ProtocolId
 /// 1. Check the offered node identity is a valid base node identity (TODO: This won't work for DAN nodes)
 /// 1. Check if we know the peer, if so, is the peer banned, if so, return an error
 /// 1. Check that the offered addresses are valid
+/// 1. Check that the addresses and features are signed by the peer's identity key
 /// 1. Update or add the peer, returning it's NodeId
 ///
 /// If the `allow_test_addrs` parameter is true, loopback, local link and other addresses normally not considered valid
@@ -101,6 +102,19 @@ pub async fn validate_and_add_peer_from_peer_identity(
         return Err(ConnectionManagerError::PeerIdentityNoValidAddresses);
     }
 
+    let features = PeerFeatures::from_bits_truncate(peer_identity.features);
+    let identity_signature = peer_identity
+        .identity_signature
+        .map(IdentitySignature::try_from)
+        .transpose()
+        .map_err(|_| ConnectionManagerError::PeerIdentityInvalidSignature)?
+        .ok_or(ConnectionManagerError::PeerIdentityInvalidSignature)?;
+
+    // TODO: #banheuristic
+    if !identity_signature.is_valid(&authenticated_public_key, features, &addresses) {
+        return Err(ConnectionManagerError::PeerIdentityInvalidSignature);
+    }
+
     let supported_protocols = peer_identity
         .supported_protocols
         .into_iter()
@@ -118,14 +132,16 @@ pub async fn validate_and_add_peer_from_peer_identity(
                 peer.node_id.short_str()
             );
             peer.connection_stats.set_connection_success();
-            peer.addresses = addresses.into();
+            // Keep the stats of addresses the peer still advertises
+            peer.addresses.update_net_addresses(addresses);
             peer.set_offline(false);
             if let Some(addr) = dialed_addr {
                 peer.addresses.mark_successful_connection_attempt(addr);
             }
-            peer.features = PeerFeatures::from_bits_truncate(peer_identity.features);
+            peer.features = features;
             peer.supported_protocols = supported_protocols.clone();
             peer.user_agent = peer_identity.user_agent;
+            peer.identity_signature = Some(identity_signature);
             peer
         },
         None => {
@@ -139,10 +155,11 @@ pub async fn validate_and_add_peer_from_peer_identity(
                 peer_node_id.clone(),
                 addresses.into(),
                 PeerFlags::empty(),
-                PeerFeatures::from_bits_truncate(peer_identity.features),
+                features,
                 supported_protocols.clone(),
                 peer_identity.user_agent,
             );
+            new_peer.identity_signature = Some(identity_signature);
             new_peer.connection_stats.set_connection_success();
             if let Some(addr) = dialed_addr {
                 new_peer.addresses.mark_successful_connection_attempt(addr);
@@ -156,6 +173,39 @@ pub async fn validate_and_add_peer_from_peer_identity(
     Ok((peer_node_id, supported_protocols))
 }
 
+/// Refuses peers that advertise a minor protocol version below `min_minor_version`. A known peer that is refused is
+/// marked as offline, so that it is not dialed and is eventually culled from the peer list unless it upgrades.
+pub async fn check_peer_version(
+    peer_manager: &PeerManager,
+    known_peer: Option<&Peer>,
+    peer_identity: &PeerIdentityMsg,
+    min_minor_version: u32,
+) -> Result<(), ConnectionManagerError> {
+    if peer_identity.minor >= min_minor_version {
+        return Ok(());
+    }
+
+    warn!(
+        target: LOG_TARGET,
+        "Refusing connection to peer with user agent '{}' because it advertised minor protocol version {} which is \
+         below the minimum of {}",
+        peer_identity.user_agent,
+        peer_identity.minor,
+        min_minor_version
+    );
+    #[cfg(feature = "metrics")]
+    super::metrics::outdated_peers_rejected().inc();
+
+    if let Some(peer) = known_peer.filter(|p| !p.is_offline()) {
+        peer_manager.set_offline(&peer.node_id, true).await?;
+    }
+
+    Err(ConnectionManagerError::PeerVersionTooOld {
+        version: peer_identity.minor,
+        minimum: min_minor_version,
+    })
+}
+
 pub async fn find_unbanned_peer(
     peer_manager: &PeerManager,
     authenticated_public_key: &CommsPublicKey,
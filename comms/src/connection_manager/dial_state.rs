@@ -25,6 +25,7 @@ use crate::{
     peer_manager::Peer,
 };
 use futures::channel::oneshot;
+use multiaddr::Multiaddr;
 use tari_shutdown::ShutdownSignal;
 
 /// The state of the dial request
@@ -37,6 +38,8 @@ pub struct DialState {
     cancel_signal: ShutdownSignal,
     /// Reply channel for a connection result
     pub reply_tx: oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>,
+    /// Addresses that could not be connected to
+    failed_addresses: Vec<Multiaddr>,
 }
 
 impl DialState {
@@ -51,6 +54,7 @@ impl DialState {
             attempts: 0,
             reply_tx,
             cancel_signal,
+            failed_addresses: Vec::new(),
         }
     }
 
@@ -68,4 +72,15 @@ impl DialState {
     pub fn num_attempts(&self) -> usize {
         self.attempts
     }
+
+    /// Record that a connection could not be established on the given address
+    pub fn add_failed_address(&mut self, address: Multiaddr) -> &mut Self {
+        self.failed_addresses.push(address);
+        self
+    }
+
+    /// Take the addresses that failed to connect
+    pub fn take_failed_addresses(&mut self) -> Vec<Multiaddr> {
+        std::mem::take(&mut self.failed_addresses)
+    }
 }
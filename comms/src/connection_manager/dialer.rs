@@ -28,6 +28,7 @@ use crate::{
         dial_state::DialState,
         manager::{ConnectionManagerConfig, ConnectionManagerEvent},
         peer_connection,
+        stream_guard,
     },
     multiaddr::Multiaddr,
     multiplexing::Yamux,
@@ -35,14 +36,12 @@ use crate::{
     peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerManager},
     protocol::ProtocolId,
     runtime,
-    transports::Transport,
+    transports::{DialProxy, Transport},
     types::CommsPublicKey,
 };
 use futures::{
     channel::{mpsc, oneshot},
-    future,
-    future::{BoxFuture, Either, FusedFuture},
-    pin_mut,
+    future::{self, BoxFuture, FusedFuture},
     stream::{Fuse, FuturesUnordered},
     AsyncRead,
     AsyncWrite,
@@ -52,7 +51,7 @@ use futures::{
     StreamExt,
 };
 use log::*;
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{cmp, collections::HashMap, sync::Arc, time::Duration};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::{task::JoinHandle, time};
 
@@ -189,9 +188,10 @@ where
 
     async fn handle_dial_result(
         &mut self,
-        dial_state: DialState,
+        mut dial_state: DialState,
         dial_result: Result<PeerConnection, ConnectionManagerError>,
     ) {
+        let failed_addresses = dial_state.take_failed_addresses();
         let DialState { peer, reply_tx, .. } = dial_state;
 
         let node_id = peer.node_id.clone();
@@ -219,6 +219,10 @@ where
             },
         }
 
+        if !failed_addresses.is_empty() {
+            self.record_failed_addresses(&node_id, &failed_addresses).await;
+        }
+
         if self.pending_dial_requests.contains_key(&node_id) {
             self.reply_to_pending_requests(&node_id, dial_result.clone());
         }
@@ -226,6 +230,30 @@ where
         let _ = reply_tx.send(dial_result);
     }
 
+    /// Updates the stats of addresses that failed to connect so that they are tried later in subsequent dials
+    async fn record_failed_addresses(&self, node_id: &NodeId, addresses: &[Multiaddr]) {
+        let mut peer = match self.peer_manager.find_by_node_id(node_id).await {
+            Ok(peer) => peer,
+            Err(err) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Unable to record failed addresses for peer '{}' because '{}'",
+                    node_id.short_str(),
+                    err
+                );
+                return;
+            },
+        };
+        for address in addresses {
+            peer.addresses.mark_failed_connection_attempt(address);
+        }
+        log_if_error!(
+            target: LOG_TARGET,
+            self.peer_manager.add_peer(peer).await,
+            "Failed to update peer addresses because '{error}'",
+        );
+    }
+
     pub async fn notify_connection_manager(&mut self, event: ConnectionManagerEvent) {
         log_if_error!(
             target: LOG_TARGET,
@@ -297,7 +325,7 @@ where
                             },
                         };
 
-                    let result = Self::perform_socket_upgrade_procedure(
+                    let upgrade_fut = Self::perform_socket_upgrade_procedure(
                         peer_manager,
                         node_identity,
                         socket,
@@ -307,8 +335,14 @@ where
                         supported_protocols,
                         &config,
                         cancel_signal,
-                    )
-                    .await;
+                    );
+                    let result = time::timeout(config.handshake_timeout, upgrade_fut)
+                        .await
+                        .unwrap_or(Err(ConnectionManagerError::HandshakeTimeout));
+
+                    if let Some(kind) = result.as_ref().err().and_then(|err| err.stream_failure_kind()) {
+                        stream_guard::record_stream_failure(kind);
+                    }
 
                     (dial_state, result)
                 },
@@ -385,6 +419,14 @@ where
         // Check if we know the peer and if it is banned
         let known_peer = common::find_unbanned_peer(&peer_manager, &authenticated_public_key).await?;
 
+        common::check_peer_version(
+            &peer_manager,
+            known_peer.as_ref(),
+            &peer_identity,
+            config.min_peer_minor_version,
+        )
+        .await?;
+
         let (peer_node_id, their_supported_protocols) = common::validate_and_add_peer_from_peer_identity(
             &peer_manager,
             known_peer,
@@ -416,6 +458,7 @@ where
             conn_man_notifier,
             our_supported_protocols,
             their_supported_protocols,
+            config.substream_settings.clone(),
         )
     }
 
@@ -447,7 +490,7 @@ where
             futures::select! {
                 _ = delay => {
                     debug!(target: LOG_TARGET, "[Attempt {}] Connecting to peer '{}'", current_state.num_attempts(), current_state.peer.node_id.short_str());
-                    match Self::dial_peer(current_state, &noise_config, &current_transport, config.network_info.network_byte).await {
+                    match Self::dial_peer(current_state, &noise_config, &current_transport, config).await {
                         (state, Ok((socket, addr))) => {
                             debug!(target: LOG_TARGET, "Dial succeeded for peer '{}' after {} attempt(s)", state.peer.node_id.short_str(), state.num_attempts());
                             break (state, Ok((socket, addr)));
@@ -474,93 +517,138 @@ where
         }
     }
 
-    /// Attempts to dial a peer sequentially on all addresses.
+    /// Attempts to dial a peer on all of its addresses, highest scoring first. Each dial is started
+    /// `dial_stagger_delay` after the previous one (or as soon as the previous one fails), up to
+    /// `max_parallel_dials_per_peer` dials at the same time, and the first connection to succeed is used.
     /// Returns ownership of the given `DialState` and a success or failure result for the dial,
     /// or None if the dial was cancelled inflight
     async fn dial_peer(
-        dial_state: DialState,
+        mut dial_state: DialState,
         noise_config: &NoiseConfig,
         transport: &TTransport,
-        network_byte: u8,
+        config: &ConnectionManagerConfig,
     ) -> (
         DialState,
         Result<(NoiseSocket<TTransport::Output>, Multiaddr), ConnectionManagerError>,
     ) {
-        let mut addr_iter = dial_state.peer.addresses.iter();
-        let cancel_signal = dial_state.get_cancel_signal();
-        loop {
-            let result = match addr_iter.next() {
-                Some(address) => {
+        let mut addr_iter = dial_state
+            .peer
+            .addresses
+            .iter_by_score()
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter();
+        let dial_proxy = dial_state.peer.dial_proxy.clone();
+        let max_parallel_dials = cmp::max(config.max_parallel_dials_per_peer, 1);
+        let mut cancel_signal = dial_state.get_cancel_signal();
+        let mut pending_dials = FuturesUnordered::new();
+        let mut stagger_delay = future::Fuse::terminated();
+        let result = loop {
+            // Each iteration follows a failed dial or the stagger delay, either of which starts the next dial
+            if pending_dials.len() < max_parallel_dials {
+                if let Some(address) = addr_iter.next() {
                     debug!(
                         target: LOG_TARGET,
                         "Attempting address '{}' for peer '{}'",
                         address,
                         dial_state.peer.node_id.short_str()
                     );
+                    pending_dials.push(Self::dial_address(
+                        address,
+                        &dial_proxy,
+                        noise_config,
+                        transport,
+                        config.network_info.network_byte,
+                        config.handshake_timeout,
+                    ));
+                    stagger_delay = time::delay_for(config.dial_stagger_delay).fuse();
+                }
+            }
+
+            // No more addresses to try - returning failure
+            if pending_dials.is_empty() {
+                break Err(ConnectionManagerError::DialConnectFailedAllAddresses);
+            }
 
-                    let dial_fut = async move {
-                        let mut socket = transport
-                            .dial(address.clone())
-                            .await
-                            .map_err(|err| ConnectionManagerError::TransportError(err.to_string()))?;
+            futures::select! {
+                (address, result) = pending_dials.select_next_some() => match result {
+                    Ok(noise_socket) => break Ok((noise_socket, address)),
+                    Err(err) => {
                         debug!(
                             target: LOG_TARGET,
-                            "Socket established on '{}'. Performing noise upgrade protocol", address
+                            "(Attempt {}) Dial failed on address '{}' for peer '{}' because '{}'",
+                            dial_state.num_attempts(),
+                            address,
+                            dial_state.peer.node_id.short_str(),
+                            err,
                         );
+                        // Try the next address
+                        dial_state.add_failed_address(address);
+                    },
+                },
+                _ = stagger_delay => {},
+                cancel_result = cancel_signal => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Dial for peer '{}' cancelled",
+                        dial_state.peer.node_id.short_str()
+                    );
+                    log_if_error!(
+                        level: warn,
+                        target: LOG_TARGET,
+                        cancel_result,
+                        "Cancel channel error during dial: {}",
+                    );
+                    break Err(ConnectionManagerError::DialCancelled);
+                },
+            }
+        };
 
-                        socket
-                            .write(&[network_byte])
-                            .await
-                            .map_err(|_| ConnectionManagerError::WireFormatSendFailed)?;
+        // Drop any dials that lost the race
+        drop(pending_dials);
 
-                        let noise_socket = time::timeout(
-                            Duration::from_secs(30),
-                            noise_config.upgrade_socket(socket, ConnectionDirection::Outbound),
-                        )
-                        .await
-                        .map_err(|_| ConnectionManagerError::NoiseProtocolTimeout)??;
-                        Result::<_, ConnectionManagerError>::Ok(noise_socket)
-                    };
-
-                    pin_mut!(dial_fut);
-                    let either = future::select(dial_fut, cancel_signal.clone()).await;
-                    match either {
-                        Either::Left((Ok(noise_socket), _)) => Ok((noise_socket, address.clone())),
-                        Either::Left((Err(err), _)) => {
-                            debug!(
-                                target: LOG_TARGET,
-                                "(Attempt {}) Dial failed on address '{}' for peer '{}' because '{}'",
-                                dial_state.num_attempts(),
-                                address,
-                                dial_state.peer.node_id.short_str(),
-                                err,
-                            );
-                            // Try the next address
-                            continue;
-                        },
-                        Either::Right((cancel_result, _)) => {
-                            debug!(
-                                target: LOG_TARGET,
-                                "Dial for peer '{}' cancelled",
-                                dial_state.peer.node_id.short_str()
-                            );
-                            log_if_error!(
-                                level: warn,
-                                target: LOG_TARGET,
-                                cancel_result,
-                                "Cancel channel error during dial: {}",
-                            );
-                            Err(ConnectionManagerError::DialCancelled)
-                        },
-                    }
-                },
-                // No more addresses to try - returning failure
-                None => Err(ConnectionManagerError::DialConnectFailedAllAddresses),
-            };
+        (dial_state, result)
+    }
+
+    async fn dial_address(
+        address: Multiaddr,
+        dial_proxy: &DialProxy,
+        noise_config: &NoiseConfig,
+        transport: &TTransport,
+        network_byte: u8,
+        handshake_timeout: Duration,
+    ) -> (
+        Multiaddr,
+        Result<NoiseSocket<TTransport::Output>, ConnectionManagerError>,
+    ) {
+        let dial_fut = async {
+            let mut socket = transport
+                .dial_with_proxy(address.clone(), dial_proxy)
+                .await
+                .map_err(|err| ConnectionManagerError::TransportError(err.to_string()))?;
+            debug!(
+                target: LOG_TARGET,
+                "Socket established on '{}'. Performing noise upgrade protocol", address
+            );
 
-            drop(addr_iter);
+            socket
+                .write(&[network_byte])
+                .await
+                .map_err(|_| ConnectionManagerError::WireFormatSendFailed)?;
+
+            let noise_socket = time::timeout(
+                handshake_timeout,
+                noise_config.upgrade_socket(socket, ConnectionDirection::Outbound),
+            )
+            .await
+            .map_err(|_| ConnectionManagerError::NoiseProtocolTimeout)??;
+            Result::<_, ConnectionManagerError>::Ok(noise_socket)
+        };
 
-            break (dial_state, result);
+        let result = dial_fut.await;
+        if let Some(kind) = result.as_ref().err().and_then(|err| err.stream_failure_kind()) {
+            stream_guard::record_stream_failure(kind);
         }
+        (address, result)
     }
 }
@@ -20,6 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use super::StreamFailureKind;
 use crate::{
     noise,
     peer_manager::PeerManagerError,
@@ -62,12 +63,18 @@ pub enum ConnectionManagerError {
     // send the same response to multiple requesters
     #[error("Noise error: {0}")]
     NoiseError(String),
+    #[error("Peer sent a malformed noise handshake: {0}")]
+    NoiseHandshakeMalformed(String),
     #[error("Incoming listener stream unexpectedly closed")]
     IncomingListenerStreamClosed,
     #[error("Peer is banned, denying connection")]
     PeerBanned,
     #[error("Unable to parse any of the network addresses offered by the connecting peer")]
     PeerIdentityNoValidAddresses,
+    #[error("The peer did not provide a valid signature for its identity")]
+    PeerIdentityInvalidSignature,
+    #[error("Peer advertised minor protocol version {version}, which is below the minimum of {minimum}")]
+    PeerVersionTooOld { version: u32, minimum: u32 },
     #[error("Identity protocol failed: {0}")]
     IdentityProtocolError(#[from] IdentityProtocolError),
     #[error("The dial was cancelled")]
@@ -78,10 +85,35 @@ pub enum ConnectionManagerError {
     WireFormatSendFailed,
     #[error("Noise protocol handshake timed out")]
     NoiseProtocolTimeout,
+    #[error("Connection handshake timed out")]
+    HandshakeTimeout,
+    #[error("Connections from this peer are refused because it has sent too many malformed streams")]
+    StreamCircuitBreakerTripped,
     #[error("Listener oneshot cancelled")]
     ListenerOneshotCancelled,
 }
 
+impl ConnectionManagerError {
+    /// Returns the kind of stream misbehaviour that caused this error, if it was caused by the peer sending a
+    /// malformed, oversized or stalled stream
+    pub fn stream_failure_kind(&self) -> Option<StreamFailureKind> {
+        match self {
+            ConnectionManagerError::NoiseProtocolTimeout | ConnectionManagerError::HandshakeTimeout => {
+                Some(StreamFailureKind::HandshakeTimeout)
+            },
+            ConnectionManagerError::NoiseHandshakeMalformed(_) => Some(StreamFailureKind::MalformedNoiseFrame),
+            ConnectionManagerError::IdentityProtocolError(err) => match err {
+                IdentityProtocolError::Timeout => Some(StreamFailureKind::HandshakeTimeout),
+                IdentityProtocolError::FrameTooLarge(_) => Some(StreamFailureKind::OversizedFrame),
+                IdentityProtocolError::ProtobufDecodeError(_) => Some(StreamFailureKind::MalformedFrame),
+                IdentityProtocolError::NegotiationFailed(_) => Some(StreamFailureKind::InvalidNegotiation),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
 impl From<yamux::ConnectionError> for ConnectionManagerError {
     fn from(err: yamux::ConnectionError) -> Self {
         ConnectionManagerError::YamuxConnectionError(err.to_string())
@@ -90,7 +122,11 @@ impl From<yamux::ConnectionError> for ConnectionManagerError {
 
 impl From<noise::NoiseError> for ConnectionManagerError {
     fn from(err: noise::NoiseError) -> Self {
-        ConnectionManagerError::NoiseError(err.to_string())
+        if err.is_malformed() {
+            ConnectionManagerError::NoiseHandshakeMalformed(err.to_string())
+        } else {
+            ConnectionManagerError::NoiseError(err.to_string())
+        }
     }
 }
 
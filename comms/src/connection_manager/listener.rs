@@ -24,6 +24,7 @@ use super::{
     common,
     error::ConnectionManagerError,
     peer_connection::{self, PeerConnection},
+    stream_guard::{self, FailureSource, StreamFailureCircuitBreaker, StreamFailureKind},
     types::ConnectionDirection,
     ConnectionManagerConfig,
     ConnectionManagerEvent,
@@ -33,11 +34,12 @@ use crate::{
     connection_manager::{liveness::LivenessSession, types::OneshotTrigger, wire_mode::WireMode},
     multiaddr::Multiaddr,
     multiplexing::Yamux,
-    noise::NoiseConfig,
+    noise::{NoiseConfig, NoiseSocket},
     peer_manager::{NodeIdentity, PeerFeatures},
     protocol::ProtocolId,
     runtime,
     transports::Transport,
+    types::CommsPublicKey,
     utils::multiaddr::multiaddr_to_socketaddr,
     PeerManager,
 };
@@ -80,6 +82,7 @@ pub struct PeerListener<TTransport> {
     our_supported_protocols: Vec<ProtocolId>,
     liveness_session_count: Arc<AtomicUsize>,
     on_listening: OneshotTrigger<Result<Multiaddr, ConnectionManagerError>>,
+    stream_guard: StreamFailureCircuitBreaker,
 }
 
 impl<TTransport> PeerListener<TTransport>
@@ -109,6 +112,7 @@ where
             our_supported_protocols: Vec::new(),
             bounded_executor: BoundedExecutor::from_current(config.max_simultaneous_inbound_connects),
             liveness_session_count: Arc::new(AtomicUsize::new(config.liveness_max_sessions)),
+            stream_guard: StreamFailureCircuitBreaker::from_config(&config),
             config,
             on_listening: OneshotTrigger::new(),
         }
@@ -216,6 +220,18 @@ where
     }
 
     async fn spawn_listen_task(&self, mut socket: TTransport::Output, peer_addr: Multiaddr) {
+        let failure_source = FailureSource::from_address(&peer_addr);
+        if let Some(ref source) = failure_source {
+            if self.stream_guard.is_blocked(source) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Refusing connection from '{}' because its stream circuit breaker has tripped", peer_addr
+                );
+                let _ = socket.close().await;
+                return;
+            }
+        }
+
         let node_identity = self.node_identity.clone();
         let peer_manager = self.peer_manager.clone();
         let mut conn_man_notifier = self.conn_man_notifier.clone();
@@ -224,12 +240,13 @@ where
         let our_supported_protocols = self.our_supported_protocols.clone();
         let liveness_session_count = self.liveness_session_count.clone();
         let shutdown_signal = self.shutdown_signal.clone();
+        let stream_guard = self.stream_guard.clone();
 
         let inbound_fut = async move {
             match Self::read_wire_format(&mut socket, config.time_to_first_byte).await {
                 Some(WireMode::Comms(byte)) if byte == config.network_info.network_byte => {
                     let this_node_id_str = node_identity.node_id().short_str();
-                    let result = Self::perform_socket_upgrade_procedure(
+                    let upgrade_fut = Self::perform_socket_upgrade_procedure(
                         node_identity,
                         peer_manager,
                         noise_config,
@@ -238,8 +255,18 @@ where
                         peer_addr,
                         our_supported_protocols,
                         &config,
-                    )
-                    .await;
+                        &stream_guard,
+                    );
+                    let result = time::timeout(config.handshake_timeout, upgrade_fut)
+                        .await
+                        .unwrap_or(Err(ConnectionManagerError::HandshakeTimeout));
+
+                    if let Some(kind) = result.as_ref().err().and_then(|err| err.stream_failure_kind()) {
+                        stream_guard::record_stream_failure(kind);
+                        if let Some(source) = failure_source {
+                            stream_guard.record_failure(source, kind);
+                        }
+                    }
 
                     match result {
                         Ok(peer_conn) => {
@@ -319,6 +346,7 @@ where
         peer_addr: Multiaddr,
         our_supported_protocols: Vec<ProtocolId>,
         config: &ConnectionManagerConfig,
+        stream_guard: &StreamFailureCircuitBreaker,
     ) -> Result<PeerConnection, ConnectionManagerError> {
         static CONNECTION_DIRECTION: ConnectionDirection = ConnectionDirection::Inbound;
         debug!(
@@ -326,19 +354,78 @@ where
             "Starting noise protocol upgrade for peer at address '{}'", peer_addr
         );
 
-        let noise_socket = time::timeout(
-            Duration::from_secs(30),
-            noise_config.upgrade_socket(socket, CONNECTION_DIRECTION),
-        )
-        .await
-        .map_err(|_| ConnectionManagerError::NoiseProtocolTimeout)??;
+        let noise_socket = noise_config.upgrade_socket(socket, CONNECTION_DIRECTION).await?;
 
         let authenticated_public_key = noise_socket
             .get_remote_public_key()
             .ok_or(ConnectionManagerError::InvalidStaticPublicKey)?;
 
+        let failure_source = FailureSource::PublicKey(authenticated_public_key.clone());
+        if stream_guard.is_blocked(&failure_source) {
+            return Err(ConnectionManagerError::StreamCircuitBreakerTripped);
+        }
+
+        let result = Self::perform_authenticated_upgrade(
+            node_identity,
+            &peer_manager,
+            noise_socket,
+            authenticated_public_key.clone(),
+            conn_man_notifier,
+            peer_addr,
+            our_supported_protocols,
+            config,
+        )
+        .await;
+
+        if let Some(kind) = result.as_ref().err().and_then(|err| err.stream_failure_kind()) {
+            if stream_guard.record_failure(failure_source, kind) {
+                Self::ban_misbehaving_peer(&peer_manager, &authenticated_public_key, stream_guard, kind).await;
+            }
+        }
+
+        result
+    }
+
+    async fn ban_misbehaving_peer(
+        peer_manager: &PeerManager,
+        public_key: &CommsPublicKey,
+        stream_guard: &StreamFailureCircuitBreaker,
+        kind: StreamFailureKind,
+    ) {
+        // Peers that never completed the identity exchange may not be in the peer manager, in which case the circuit
+        // breaker alone refuses their connections
+        match peer_manager
+            .ban_peer(
+                public_key,
+                stream_guard.block_duration(),
+                format!("Repeated malformed connection streams ({})", kind),
+            )
+            .await
+        {
+            Ok(node_id) => debug!(
+                target: LOG_TARGET,
+                "Banned peer '{}' for repeated malformed connection streams",
+                node_id.short_str()
+            ),
+            Err(err) => debug!(target: LOG_TARGET, "Unable to ban peer '{}': {}", public_key, err),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn perform_authenticated_upgrade(
+        node_identity: Arc<NodeIdentity>,
+        peer_manager: &PeerManager,
+        noise_socket: NoiseSocket<TTransport::Output>,
+        authenticated_public_key: CommsPublicKey,
+        conn_man_notifier: mpsc::Sender<ConnectionManagerEvent>,
+        peer_addr: Multiaddr,
+        our_supported_protocols: Vec<ProtocolId>,
+        config: &ConnectionManagerConfig,
+    ) -> Result<PeerConnection, ConnectionManagerError> {
+        static CONNECTION_DIRECTION: ConnectionDirection = ConnectionDirection::Inbound;
+
         // Check if we know the peer and if it is banned
-        let known_peer = common::find_unbanned_peer(&peer_manager, &authenticated_public_key).await?;
+        let known_peer = common::find_unbanned_peer(peer_manager, &authenticated_public_key).await?;
 
         let mut muxer = Yamux::upgrade_connection(noise_socket, CONNECTION_DIRECTION)
             .await
@@ -368,8 +455,16 @@ where
         );
         trace!(target: LOG_TARGET, "{:?}", peer_identity);
 
+        common::check_peer_version(
+            peer_manager,
+            known_peer.as_ref(),
+            &peer_identity,
+            config.min_peer_minor_version,
+        )
+        .await?;
+
         let (peer_node_id, their_supported_protocols) = common::validate_and_add_peer_from_peer_identity(
-            &peer_manager,
+            peer_manager,
             known_peer,
             authenticated_public_key,
             peer_identity,
@@ -394,6 +489,7 @@ where
             conn_man_notifier,
             our_supported_protocols,
             their_supported_protocols,
+            config.substream_settings.clone(),
         )
     }
 
@@ -29,10 +29,10 @@ use super::{
 };
 use crate::{
     backoff::Backoff,
-    multiplexing::Substream,
+    multiplexing::{Substream, SubstreamPriority, SubstreamSettings},
     noise::NoiseConfig,
     peer_manager::{NodeId, NodeIdentity},
-    protocol::{NodeNetworkInfo, ProtocolEvent, ProtocolId, Protocols},
+    protocol::{messaging::MESSAGING_PROTOCOL, NodeNetworkInfo, ProtocolEvent, ProtocolId, Protocols},
     transports::{TcpTransport, Transport},
     PeerManager,
 };
@@ -46,7 +46,7 @@ use futures::{
 };
 use log::*;
 use multiaddr::Multiaddr;
-use std::{fmt, sync::Arc};
+use std::{collections::HashMap, fmt, sync::Arc};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use time::Duration;
 use tokio::{sync::broadcast, task, time};
@@ -93,6 +93,13 @@ pub struct ConnectionManagerConfig {
     pub listener_address: Multiaddr,
     /// The number of dial attempts to make before giving up. Default: 3
     pub max_dial_attempts: usize,
+    /// The maximum number of a peer's addresses to dial at the same time. The first successful connection is used and
+    /// the others are dropped. Racing dials helps peers with addresses on more than one transport (e.g. Tor and TCP)
+    /// where some addresses may be slow or unreachable. Default: 3
+    pub max_parallel_dials_per_peer: usize,
+    /// The time to wait after starting a dial to one of a peer's addresses before also dialing the next address. A
+    /// failed dial starts the next address immediately. Default: 250ms
+    pub dial_stagger_delay: Duration,
     /// The maximum number of connection tasks that will be spawned at the same time. Once this limit is reached, peers
     /// attempting to connect will have to wait for another connection attempt to complete. Default: 20
     pub max_simultaneous_inbound_connects: usize,
@@ -101,8 +108,22 @@ pub struct ConnectionManagerConfig {
     pub allow_test_addresses: bool,
     /// Version information for this node
     pub network_info: NodeNetworkInfo,
+    /// Connections from and to peers that advertise a minor protocol version below this are refused. This allows old
+    /// wire formats to be retired once the network has upgraded. Default: 0 (all minor versions are accepted)
+    pub min_peer_minor_version: u32,
     /// The maximum time to wait for the first byte before closing the connection. Default: 7s
     pub time_to_first_byte: Duration,
+    /// The maximum time allowed for a connection to complete the noise handshake, multiplexer upgrade and identity
+    /// exchange. Default: 30s
+    pub handshake_timeout: Duration,
+    /// The number of malformed, oversized or timed out connection handshakes from an address or peer within
+    /// `stream_failure_window` that trips its circuit breaker. Zero disables the circuit breaker. Default: 5
+    pub max_stream_failures: usize,
+    /// The period over which stream failures are counted. Default: 10 minutes
+    pub stream_failure_window: Duration,
+    /// The length of time that connections from an address or peer are refused once its circuit breaker has tripped.
+    /// Peers known to the peer manager are also banned for this long. Default: 30 minutes
+    pub stream_failure_ban_duration: Duration,
     /// The number of liveness check sessions to allow. Default: 0
     pub liveness_max_sessions: usize,
     /// CIDR blocks that allowlist liveness checks. Default: Localhost only (127.0.0.1/32)
@@ -110,6 +131,9 @@ pub struct ConnectionManagerConfig {
     /// If set, an additional TCP-only p2p listener will be started. This is useful for local wallet connections.
     /// Default: None (disabled)
     pub auxilary_tcp_listener_address: Option<Multiaddr>,
+    /// Write scheduling settings for the substreams of each protocol. Protocols that are not listed use
+    /// `SubstreamPriority::Normal` with no send window. Default: messaging is `SubstreamPriority::High`
+    pub substream_settings: HashMap<ProtocolId, SubstreamSettings>,
 }
 
 impl Default for ConnectionManagerConfig {
@@ -122,8 +146,11 @@ impl Default for ConnectionManagerConfig {
             #[cfg(test)]
             listener_address: "/memory/0".parse().unwrap(),
             max_dial_attempts: 3,
+            max_parallel_dials_per_peer: 3,
+            dial_stagger_delay: Duration::from_millis(250),
             max_simultaneous_inbound_connects: 20,
             network_info: Default::default(),
+            min_peer_minor_version: 0,
             #[cfg(not(test))]
             allow_test_addresses: false,
             // This must always be true for internal crate tests
@@ -131,8 +158,18 @@ impl Default for ConnectionManagerConfig {
             allow_test_addresses: true,
             liveness_max_sessions: 0,
             time_to_first_byte: Duration::from_secs(7),
+            handshake_timeout: Duration::from_secs(30),
+            max_stream_failures: 5,
+            stream_failure_window: Duration::from_secs(10 * 60),
+            stream_failure_ban_duration: Duration::from_secs(30 * 60),
             liveness_cidr_allowlist: vec![cidr::AnyIpCidr::V4("127.0.0.1/32".parse().unwrap())],
             auxilary_tcp_listener_address: None,
+            substream_settings: vec![(
+                MESSAGING_PROTOCOL.clone(),
+                SubstreamSettings::new(SubstreamPriority::High),
+            )]
+            .into_iter()
+            .collect(),
         }
     }
 }
@@ -0,0 +1,58 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::StreamFailureKind;
+use crate::protocol::ProtocolId;
+use lazy_static::lazy_static;
+use tari_metrics::{IntCounter, IntCounterVec};
+
+lazy_static! {
+    static ref LEGACY_PROTOCOL_NEGOTIATED: IntCounterVec = tari_metrics::register_int_counter_vec(
+        "comms_legacy_protocol_negotiated",
+        "The number of outbound substreams that fell back to an older version of a protocol",
+        &["protocol"],
+    )
+    .unwrap();
+    static ref OUTDATED_PEERS_REJECTED: IntCounter = tari_metrics::register_int_counter(
+        "comms_connection_outdated_peers_rejected",
+        "The number of connections refused because the peer advertised a protocol version below the minimum",
+    )
+    .unwrap();
+    static ref STREAM_FAILURES: IntCounterVec = tari_metrics::register_int_counter_vec(
+        "comms_connection_stream_failures",
+        "The number of connections that failed because the peer sent a malformed, oversized or stalled stream",
+        &["kind"],
+    )
+    .unwrap();
+}
+
+pub fn legacy_protocol_negotiated(protocol: &ProtocolId) -> IntCounter {
+    LEGACY_PROTOCOL_NEGOTIATED.with_label_values(&[&String::from_utf8_lossy(protocol)])
+}
+
+pub fn stream_failures(kind: StreamFailureKind) -> IntCounter {
+    STREAM_FAILURES.with_label_values(&[kind.as_str()])
+}
+
+pub fn outdated_peers_rejected() -> IntCounter {
+    OUTDATED_PEERS_REJECTED.clone()
+}
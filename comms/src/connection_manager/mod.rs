@@ -45,5 +45,12 @@ pub use peer_connection::{ConnectionId, NegotiatedSubstream, PeerConnection, Pee
 mod liveness;
 mod wire_mode;
 
+mod stream_guard;
+pub(crate) use stream_guard::record_stream_failure;
+pub use stream_guard::StreamFailureKind;
+
+#[cfg(feature = "metrics")]
+mod metrics;
+
 #[cfg(test)]
 mod tests;
@@ -31,10 +31,19 @@ use super::{
 use crate::{
     framing,
     framing::CanonicalFraming,
-    multiplexing::{Control, IncomingSubstreams, Substream, SubstreamCounter, Yamux},
+    multiplexing::{
+        Control,
+        IncomingSubstreams,
+        Substream,
+        SubstreamCounter,
+        SubstreamPriority,
+        SubstreamSettings,
+        Yamux,
+    },
     peer_manager::{NodeId, PeerFeatures},
     protocol::{ProtocolId, ProtocolNegotiation},
     runtime,
+    utils::log_fields::LogFieldExt,
 };
 use futures::{
     channel::{mpsc, oneshot},
@@ -45,6 +54,7 @@ use futures::{
 use log::*;
 use multiaddr::Multiaddr;
 use std::{
+    collections::HashMap,
     fmt,
     sync::atomic::{AtomicUsize, Ordering},
     time::{Duration, Instant},
@@ -58,6 +68,7 @@ const PEER_REQUEST_BUFFER_SIZE: usize = 64;
 static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 #[allow(clippy::too_many_arguments)]
+#[allow(clippy::mutable_key_type)] // Note: Clippy Breaks with Interior Mutability Error
 pub fn create(
     connection: Yamux,
     peer_addr: Multiaddr,
@@ -67,6 +78,7 @@ pub fn create(
     event_notifier: mpsc::Sender<ConnectionManagerEvent>,
     our_supported_protocols: Vec<ProtocolId>,
     their_supported_protocols: Vec<ProtocolId>,
+    substream_settings: HashMap<ProtocolId, SubstreamSettings>,
 ) -> Result<PeerConnection, ConnectionManagerError> {
     trace!(
         target: LOG_TARGET,
@@ -85,6 +97,7 @@ pub fn create(
         direction,
         substream_counter,
     );
+    let peer_id = peer_node_id.to_string();
     let peer_actor = PeerConnectionActor::new(
         id,
         peer_node_id,
@@ -94,17 +107,20 @@ pub fn create(
         event_notifier,
         our_supported_protocols,
         their_supported_protocols,
+        substream_settings,
     );
-    runtime::current().spawn(peer_actor.run());
+    runtime::current().spawn(peer_actor.run().with_log_field("peer_id", peer_id));
 
     Ok(peer_conn)
 }
 
 #[derive(Debug)]
 pub enum PeerConnectionRequest {
-    /// Open a new substream and negotiate the given protocol
+    /// Open a new substream and negotiate one of the given protocols, in order of preference. The priority, if given,
+    /// overrides the one configured for the negotiated protocol.
     OpenSubstream(
-        ProtocolId,
+        Vec<ProtocolId>,
+        Option<SubstreamPriority>,
         oneshot::Sender<Result<NegotiatedSubstream<Substream>, PeerConnectionError>>,
     ),
     /// Disconnect all substreams and close the transport connection
@@ -183,10 +199,42 @@ impl PeerConnection {
     pub async fn open_substream(
         &mut self,
         protocol_id: &ProtocolId,
+    ) -> Result<NegotiatedSubstream<Substream>, PeerConnectionError> {
+        self.open_substream_versioned(&[protocol_id.clone()]).await
+    }
+
+    /// Open a substream using the first protocol in `protocol_ids` that the peer supports. Protocol ids should be
+    /// given in order of preference, typically the versions of the same protocol from newest to oldest. The
+    /// `protocol` of the returned substream is the version that was selected.
+    pub async fn open_substream_versioned(
+        &mut self,
+        protocol_ids: &[ProtocolId],
+    ) -> Result<NegotiatedSubstream<Substream>, PeerConnectionError> {
+        self.request_substream(protocol_ids, None).await
+    }
+
+    /// Open a substream whose writes are scheduled with the given priority instead of the one configured for the
+    /// protocol
+    pub async fn open_substream_with_priority(
+        &mut self,
+        protocol_id: &ProtocolId,
+        priority: SubstreamPriority,
+    ) -> Result<NegotiatedSubstream<Substream>, PeerConnectionError> {
+        self.request_substream(&[protocol_id.clone()], Some(priority)).await
+    }
+
+    async fn request_substream(
+        &mut self,
+        protocol_ids: &[ProtocolId],
+        priority: Option<SubstreamPriority>,
     ) -> Result<NegotiatedSubstream<Substream>, PeerConnectionError> {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.request_tx
-            .send(PeerConnectionRequest::OpenSubstream(protocol_id.clone(), reply_tx))
+            .send(PeerConnectionRequest::OpenSubstream(
+                protocol_ids.to_vec(),
+                priority,
+                reply_tx,
+            ))
             .await?;
         reply_rx
             .await
@@ -277,11 +325,13 @@ struct PeerConnectionActor {
     event_notifier: mpsc::Sender<ConnectionManagerEvent>,
     our_supported_protocols: Vec<ProtocolId>,
     their_supported_protocols: Vec<ProtocolId>,
+    substream_settings: HashMap<ProtocolId, SubstreamSettings>,
     shutdown: bool,
 }
 
 impl PeerConnectionActor {
     #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::mutable_key_type)] // Note: Clippy Breaks with Interior Mutability Error
     fn new(
         id: ConnectionId,
         peer_node_id: NodeId,
@@ -291,6 +341,7 @@ impl PeerConnectionActor {
         event_notifier: mpsc::Sender<ConnectionManagerEvent>,
         our_supported_protocols: Vec<ProtocolId>,
         their_supported_protocols: Vec<ProtocolId>,
+        substream_settings: HashMap<ProtocolId, SubstreamSettings>,
     ) -> Self {
         Self {
             id,
@@ -304,6 +355,7 @@ impl PeerConnectionActor {
             shutdown: false,
             our_supported_protocols,
             their_supported_protocols,
+            substream_settings,
         }
     }
 
@@ -342,8 +394,8 @@ impl PeerConnectionActor {
     async fn handle_request(&mut self, request: PeerConnectionRequest) {
         use PeerConnectionRequest::*;
         match request {
-            OpenSubstream(proto, reply_tx) => {
-                let result = self.open_negotiated_protocol_stream(proto).await;
+            OpenSubstream(protocols, priority, reply_tx) => {
+                let result = self.open_negotiated_protocol_stream(protocols, priority).await;
                 log_if_error_fmt!(
                     target: LOG_TARGET,
                     reply_tx.send(result),
@@ -368,6 +420,7 @@ impl PeerConnectionActor {
         let selected_protocol = ProtocolNegotiation::new(&mut stream)
             .negotiate_protocol_inbound(&self.our_supported_protocols)
             .await?;
+        stream.set_settings(self.settings_for(&selected_protocol, None));
 
         self.notify_event(ConnectionManagerEvent::NewInboundSubstream(
             Box::new(self.peer_node_id.clone()),
@@ -379,27 +432,58 @@ impl PeerConnectionActor {
         Ok(())
     }
 
+    /// The write scheduling settings for a substream of the given protocol
+    fn settings_for(&self, protocol: &ProtocolId, priority: Option<SubstreamPriority>) -> SubstreamSettings {
+        match priority {
+            Some(priority) => SubstreamSettings::new(priority),
+            None => self.substream_settings.get(protocol).copied().unwrap_or_default(),
+        }
+    }
+
     async fn open_negotiated_protocol_stream(
         &mut self,
-        protocol: ProtocolId,
+        protocols: Vec<ProtocolId>,
+        priority: Option<SubstreamPriority>,
     ) -> Result<NegotiatedSubstream<Substream>, PeerConnectionError> {
         debug!(
             target: LOG_TARGET,
             "[{}] Negotiating protocol '{}' on new substream for peer '{}'",
             self,
-            String::from_utf8_lossy(&protocol),
+            protocols
+                .iter()
+                .map(|p| String::from_utf8_lossy(p).to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
             self.peer_node_id.short_str()
         );
         let mut stream = self.control.open_stream().await?;
 
         let mut negotiation = ProtocolNegotiation::new(&mut stream);
 
-        let selected_protocol = if self.their_supported_protocols.contains(&protocol) {
-            negotiation.negotiate_protocol_outbound_optimistic(&protocol).await?
-        } else {
-            negotiation.negotiate_protocol_outbound(&[protocol]).await?
+        // The peer advertised its supported protocols in the identity exchange, so if it supports any of the given
+        // protocols, the most preferred one can be selected without a round trip
+        let selected_protocol = match protocols.iter().find(|p| self.their_supported_protocols.contains(p)) {
+            Some(protocol) => negotiation.negotiate_protocol_outbound_optimistic(protocol).await?,
+            None => negotiation.negotiate_protocol_outbound(&protocols).await?,
         };
 
+        if protocols.first() != Some(&selected_protocol) {
+            info!(
+                target: LOG_TARGET,
+                "[{}] Peer '{}' does not support '{}', falling back to '{}'",
+                self,
+                self.peer_node_id.short_str(),
+                protocols
+                    .first()
+                    .map(|p| String::from_utf8_lossy(p))
+                    .unwrap_or_default(),
+                String::from_utf8_lossy(&selected_protocol)
+            );
+            #[cfg(feature = "metrics")]
+            super::metrics::legacy_protocol_negotiated(&selected_protocol).inc();
+        }
+
+        stream.set_settings(self.settings_for(&selected_protocol, priority));
         Ok(NegotiatedSubstream::new(selected_protocol, stream))
     }
 
@@ -0,0 +1,271 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Guards the connection manager against peers that send malformed, oversized or stalled streams while a connection is
+//! being established. Each such failure is counted against the peer's address and, once the noise handshake has
+//! authenticated it, the peer's public key. When a source accumulates too many failures its circuit breaker trips and
+//! new connections from it are refused before they can occupy an inbound connect slot.
+
+use super::ConnectionManagerConfig;
+use crate::{multiaddr::Multiaddr, types::CommsPublicKey, utils::multiaddr::multiaddr_to_socketaddr};
+use log::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const LOG_TARGET: &str = "comms::connection_manager::stream_guard";
+
+/// The number of tracked sources above which expired records are pruned
+const PRUNE_THRESHOLD: usize = 1000;
+
+/// The kinds of stream misbehaviour that count towards a peer's circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamFailureKind {
+    /// The connection handshake did not complete within the handshake timeout
+    HandshakeTimeout,
+    /// A noise frame could not be decrypted
+    MalformedNoiseFrame,
+    /// A frame was larger than the maximum size permitted for it
+    OversizedFrame,
+    /// A frame could not be decoded
+    MalformedFrame,
+    /// The peer did not negotiate the expected protocol
+    InvalidNegotiation,
+}
+
+impl StreamFailureKind {
+    pub fn as_str(&self) -> &'static str {
+        use StreamFailureKind::*;
+        match self {
+            HandshakeTimeout => "handshake_timeout",
+            MalformedNoiseFrame => "malformed_noise_frame",
+            OversizedFrame => "oversized_frame",
+            MalformedFrame => "malformed_frame",
+            InvalidNegotiation => "invalid_negotiation",
+        }
+    }
+}
+
+impl fmt::Display for StreamFailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Count a stream failure in the metrics registry
+pub(crate) fn record_stream_failure(kind: StreamFailureKind) {
+    debug!(target: LOG_TARGET, "Stream failure: {}", kind);
+    #[cfg(feature = "metrics")]
+    super::metrics::stream_failures(kind).inc();
+}
+
+/// Where a stream failure is attributed to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum FailureSource {
+    Address(IpAddr),
+    PublicKey(CommsPublicKey),
+}
+
+impl FailureSource {
+    /// Returns the source for a peer's address, or None if failures from the address cannot be attributed to a single
+    /// peer. Loopback addresses are never tracked because every inbound Tor connection arrives from localhost.
+    pub fn from_address(addr: &Multiaddr) -> Option<Self> {
+        let ip = multiaddr_to_socketaddr(addr).ok()?.ip();
+        if ip.is_loopback() {
+            return None;
+        }
+        Some(FailureSource::Address(ip))
+    }
+}
+
+impl fmt::Display for FailureSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureSource::Address(ip) => write!(f, "address {}", ip),
+            FailureSource::PublicKey(pk) => write!(f, "public key {}", pk),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct FailureRecord {
+    failures: VecDeque<Instant>,
+    blocked_until: Option<Instant>,
+}
+
+impl FailureRecord {
+    fn is_blocked(&self, now: Instant) -> bool {
+        self.blocked_until.map(|until| until > now).unwrap_or(false)
+    }
+
+    fn is_expired(&self, now: Instant, window: Duration) -> bool {
+        !self.is_blocked(now) && self.failures.back().map(|t| now - *t > window).unwrap_or(true)
+    }
+}
+
+/// Tracks stream failures per source and refuses sources that have exceeded the failure threshold
+#[derive(Debug, Clone)]
+pub(crate) struct StreamFailureCircuitBreaker {
+    max_failures: usize,
+    window: Duration,
+    block_duration: Duration,
+    records: Arc<Mutex<HashMap<FailureSource, FailureRecord>>>,
+}
+
+impl StreamFailureCircuitBreaker {
+    pub fn new(max_failures: usize, window: Duration, block_duration: Duration) -> Self {
+        Self {
+            max_failures,
+            window,
+            block_duration,
+            records: Default::default(),
+        }
+    }
+
+    pub fn from_config(config: &ConnectionManagerConfig) -> Self {
+        Self::new(
+            config.max_stream_failures,
+            config.stream_failure_window,
+            config.stream_failure_ban_duration,
+        )
+    }
+
+    /// The length of time that a tripped source is refused for
+    pub fn block_duration(&self) -> Duration {
+        self.block_duration
+    }
+
+    /// Returns true if the circuit breaker for this source has tripped and has not yet reset
+    pub fn is_blocked(&self, source: &FailureSource) -> bool {
+        let records = acquire_lock!(self.records);
+        records
+            .get(source)
+            .map(|r| r.is_blocked(Instant::now()))
+            .unwrap_or(false)
+    }
+
+    /// Record a failure for the source. Returns true if this failure tripped the source's circuit breaker.
+    pub fn record_failure(&self, source: FailureSource, kind: StreamFailureKind) -> bool {
+        if self.max_failures == 0 {
+            return false;
+        }
+        let now = Instant::now();
+        let mut records = acquire_lock!(self.records);
+        if records.len() > PRUNE_THRESHOLD {
+            let window = self.window;
+            records.retain(|_, r| !r.is_expired(now, window));
+        }
+
+        let record = records.entry(source.clone()).or_default();
+        if record.is_blocked(now) {
+            return false;
+        }
+        while record.failures.front().map(|t| now - *t > self.window).unwrap_or(false) {
+            record.failures.pop_front();
+        }
+        record.failures.push_back(now);
+        debug!(
+            target: LOG_TARGET,
+            "{} failure for {} ({}/{} within {:.0?})",
+            kind,
+            source,
+            record.failures.len(),
+            self.max_failures,
+            self.window
+        );
+
+        if record.failures.len() < self.max_failures {
+            return false;
+        }
+
+        record.failures.clear();
+        record.blocked_until = Some(now + self.block_duration);
+        warn!(
+            target: LOG_TARGET,
+            "Circuit breaker tripped for {} after repeated stream failures. Refusing connections for {:.0?}",
+            source,
+            self.block_duration
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{peer_manager::PeerFeatures, test_utils::node_identity::build_node_identity};
+
+    #[test]
+    fn it_trips_after_max_failures() {
+        let breaker = StreamFailureCircuitBreaker::new(3, Duration::from_secs(60), Duration::from_secs(60));
+        let source = FailureSource::from_address(&"/ip4/8.8.8.8/tcp/1234".parse().unwrap()).unwrap();
+        assert!(!breaker.record_failure(source.clone(), StreamFailureKind::MalformedFrame));
+        assert!(!breaker.record_failure(source.clone(), StreamFailureKind::OversizedFrame));
+        assert!(!breaker.is_blocked(&source));
+        assert!(breaker.record_failure(source.clone(), StreamFailureKind::HandshakeTimeout));
+        assert!(breaker.is_blocked(&source));
+
+        // Other ports on the same host are blocked too
+        let other_port = FailureSource::from_address(&"/ip4/8.8.8.8/tcp/4321".parse().unwrap()).unwrap();
+        assert!(breaker.is_blocked(&other_port));
+
+        let other = FailureSource::PublicKey(
+            build_node_identity(PeerFeatures::COMMUNICATION_NODE)
+                .public_key()
+                .clone(),
+        );
+        assert!(!breaker.is_blocked(&other));
+    }
+
+    #[test]
+    fn it_resets_after_the_block_duration() {
+        let breaker = StreamFailureCircuitBreaker::new(1, Duration::from_secs(60), Duration::from_millis(1));
+        let source = FailureSource::PublicKey(
+            build_node_identity(PeerFeatures::COMMUNICATION_NODE)
+                .public_key()
+                .clone(),
+        );
+        assert!(breaker.record_failure(source.clone(), StreamFailureKind::MalformedNoiseFrame));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(!breaker.is_blocked(&source));
+    }
+
+    #[test]
+    fn it_forgets_failures_outside_the_window() {
+        let breaker = StreamFailureCircuitBreaker::new(2, Duration::from_millis(1), Duration::from_secs(60));
+        let source = FailureSource::Address("8.8.8.8".parse().unwrap());
+        assert!(!breaker.record_failure(source.clone(), StreamFailureKind::MalformedFrame));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(!breaker.record_failure(source.clone(), StreamFailureKind::MalformedFrame));
+        assert!(!breaker.is_blocked(&source));
+    }
+
+    #[test]
+    fn it_does_not_track_loopback_or_memory_addresses() {
+        assert!(FailureSource::from_address(&"/ip4/127.0.0.1/tcp/1234".parse().unwrap()).is_none());
+        assert!(FailureSource::from_address(&"/memory/1234".parse().unwrap()).is_none());
+    }
+}
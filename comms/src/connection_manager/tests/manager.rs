@@ -187,6 +187,15 @@ async fn dial_success() {
     let mut buf = [0u8; MSG.len()];
     substream_in.read_exact(&mut buf).await.unwrap();
     assert_eq!(buf, MSG);
+
+    // Node 2 does not support the newer version of the protocol, so the older version is selected
+    let substream_out = conn_out
+        .open_substream_versioned(&[ProtocolId::from_static(b"/test/valid/2"), TEST_PROTO.clone()])
+        .await
+        .unwrap();
+    assert_eq!(substream_out.protocol, TEST_PROTO);
+    let protocol_in = proto_rx2.next().await.unwrap();
+    assert_eq!(protocol_in.protocol, &TEST_PROTO);
 }
 
 #[runtime::test_basic]
@@ -28,6 +28,10 @@ pub struct ConnectivityConfig {
     /// To change the status to ONLINE, this must be true: `num_connected >= num_peers * min_connectivity`
     /// Default: 30%
     pub min_connectivity: f32,
+    /// The minimum number of connected nodes required to be ONLINE, regardless of the number of managed peers. With
+    /// fewer connections the connectivity status is DEGRADED, which prompts the DHT to look for more peers.
+    /// Default: 1
+    pub min_connected_nodes: usize,
     /// Interval to check the connection pool, including reaping inactive connections and retrying failed managed peer
     /// connections. Default: 30s
     pub connection_pool_refresh_interval: Duration,
@@ -42,17 +46,27 @@ pub struct ConnectivityConfig {
     /// The length of time to wait before disconnecting a connection that failed tie breaking.
     /// Default: 1s
     pub connection_tie_break_linger: Duration,
+    /// The length of time a peer must have been offline before it is removed from the peer database.
+    /// Default: 30 days
+    pub peer_cull_offline_age: Duration,
+    /// The maximum number of stale peers removed from the peer database on each connection pool refresh. Culling
+    /// is gradual so that a temporary network outage does not wipe the peer database. Set to 0 to disable culling.
+    /// Default: 10
+    pub max_peers_culled_per_refresh: usize,
 }
 
 impl Default for ConnectivityConfig {
     fn default() -> Self {
         Self {
             min_connectivity: 0.3,
+            min_connected_nodes: 1,
             connection_pool_refresh_interval: Duration::from_secs(30),
             reaper_min_inactive_age: Duration::from_secs(60),
             is_connection_reaping_enabled: true,
             max_failures_mark_offline: 2,
             connection_tie_break_linger: Duration::from_secs(2),
+            peer_cull_offline_age: Duration::from_secs(30 * 24 * 60 * 60),
+            max_peers_culled_per_refresh: 10,
         }
     }
 }
@@ -324,6 +324,30 @@ impl ConnectivityManagerActor {
         // Remove disconnected/failed peers from the connection pool
         self.clean_connection_pool();
         self.update_connectivity_status();
+        self.cull_stale_peers().await?;
+        Ok(())
+    }
+
+    async fn cull_stale_peers(&mut self) -> Result<(), ConnectivityError> {
+        if self.config.max_peers_culled_per_refresh == 0 {
+            return Ok(());
+        }
+        let num_culled = self
+            .peer_manager
+            .cull_offline_peers(
+                self.config.peer_cull_offline_age,
+                self.config.max_peers_culled_per_refresh,
+                &self.managed_peers,
+            )
+            .await?;
+        if num_culled > 0 {
+            debug!(
+                target: LOG_TARGET,
+                "Removed {} peer(s) that have been offline for longer than {:.0?}",
+                num_culled,
+                self.config.peer_cull_offline_age
+            );
+        }
         Ok(())
     }
 
@@ -723,7 +747,7 @@ impl ConnectivityManagerActor {
         // - Clients MUST NOT assume that all managed peers are connected when ONLINE
         let min_peers = cmp::max(
             (self.managed_peers.len() as f32 * self.config.min_connectivity).ceil() as usize,
-            1,
+            cmp::max(self.config.min_connected_nodes, 1),
         );
         let num_connected_nodes = self.pool.count_connected_nodes();
         let num_connected_clients = self.pool.count_connected_clients();
@@ -21,7 +21,12 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::connection_pool::ConnectionPool;
-use crate::{connectivity::connection_pool::ConnectionStatus, peer_manager::NodeId, PeerConnection};
+use crate::{
+    connectivity::connection_pool::ConnectionStatus,
+    peer_manager::NodeId,
+    utils::multiaddr::network_group,
+    PeerConnection,
+};
 use rand::{rngs::OsRng, seq::SliceRandom};
 use std::{fmt, fmt::Display};
 
@@ -36,6 +41,7 @@ enum SelectionMode {
     AllNodes,
     RandomNodes(usize),
     ClosestTo(Box<NodeId>, usize),
+    DiverseNodes(Box<NodeId>, usize),
 }
 
 impl ConnectivitySelection {
@@ -61,6 +67,15 @@ impl ConnectivitySelection {
         }
     }
 
+    /// Select `n` random peer connections, preferring connections that are spread across different k-buckets (relative
+    /// to `node_id`) and network groups.
+    pub fn diverse_nodes(node_id: NodeId, n: usize, exclude: Vec<NodeId>) -> Self {
+        Self {
+            selection_mode: SelectionMode::DiverseNodes(Box::new(node_id), n),
+            excluded_peers: exclude,
+        }
+    }
+
     /// Select peers from the pool according to the ConnectivitySelection
     pub fn select<'a>(&self, pool: &'a ConnectionPool) -> Vec<&'a PeerConnection> {
         use SelectionMode::*;
@@ -72,6 +87,7 @@ impl ConnectivitySelection {
                 connections.truncate(*n);
                 connections.to_vec()
             },
+            DiverseNodes(node_id, n) => select_diverse_nodes(pool, node_id, *n, &self.excluded_peers),
         }
     }
 }
@@ -105,6 +121,59 @@ pub fn select_random_nodes<'a>(pool: &'a ConnectionPool, n: usize, exclude: &[No
     nodes.choose_multiple(&mut OsRng, n).cloned().collect()
 }
 
+/// Select up to `n` random nodes, preferring nodes that are in a different k-bucket (relative to `node_id`) and a
+/// different network group to the nodes already selected. Nodes that share a bucket or network group with an already
+/// selected node are only chosen if there are not enough diverse nodes to select from.
+pub fn select_diverse_nodes<'a>(
+    pool: &'a ConnectionPool,
+    node_id: &NodeId,
+    n: usize,
+    exclude: &[NodeId],
+) -> Vec<&'a PeerConnection> {
+    let mut candidates = select_connected_nodes(pool, exclude)
+        .into_iter()
+        .map(|conn| {
+            let bucket = conn.peer_node_id().distance(node_id).bucket_index();
+            let group = network_group(conn.address());
+            (conn, bucket, group)
+        })
+        .collect::<Vec<_>>();
+    candidates.shuffle(&mut OsRng);
+
+    let mut selected = Vec::with_capacity(n);
+    let mut used_buckets = Vec::new();
+    let mut used_groups = Vec::new();
+    // First pass selects nodes in a new bucket _and_ network group, the second pass in a new bucket _or_ network group
+    // and the last pass fills any remaining slots
+    for pass in 0..3 {
+        let mut i = 0;
+        while i < candidates.len() && selected.len() < n {
+            let (_, bucket, group) = &candidates[i];
+            let is_new_bucket = !used_buckets.contains(bucket);
+            // Addresses without a network group (e.g. onion addresses) are always considered diverse
+            let is_new_group = group.as_ref().map(|g| !used_groups.contains(g)).unwrap_or(true);
+            let is_eligible = match pass {
+                0 => is_new_bucket && is_new_group,
+                1 => is_new_bucket || is_new_group,
+                _ => true,
+            };
+
+            if is_eligible {
+                let (conn, bucket, group) = candidates.remove(i);
+                used_buckets.push(bucket);
+                if let Some(group) = group {
+                    used_groups.push(group);
+                }
+                selected.push(conn);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    selected
+}
+
 impl Display for ConnectivitySelection {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -123,6 +192,7 @@ impl Display for SelectionMode {
             AllNodes => write!(f, "AllNodes"),
             RandomNodes(n) => write!(f, "RandomNodes({})", n),
             ClosestTo(node_id, n) => write!(f, "ClosestTo({}, {})", node_id, n),
+            DiverseNodes(node_id, n) => write!(f, "DiverseNodes({}, {})", node_id, n),
         }
     }
 }
@@ -131,12 +201,17 @@ impl Display for SelectionMode {
 mod test {
     use super::*;
     use crate::{
-        connection_manager::PeerConnectionRequest,
+        connection_manager::{ConnectionDirection, PeerConnectionRequest},
         peer_manager::node_id::NodeDistance,
-        test_utils::{mocks::create_dummy_peer_connection, node_id, node_identity::build_node_identity},
+        test_utils::{
+            mocks::{create_dummy_peer_connection, create_dummy_peer_connection_with_address},
+            node_id,
+            node_identity::build_node_identity,
+        },
     };
     use futures::channel::mpsc;
     use std::iter::repeat_with;
+    use tari_crypto::tari_utilities::ByteArray;
 
     fn create_pool_with_connections(n: usize) -> (ConnectionPool, Vec<mpsc::Receiver<PeerConnectionRequest>>) {
         let mut pool = ConnectionPool::new();
@@ -178,6 +253,65 @@ mod test {
         }
     }
 
+    #[test]
+    fn select_diverse() {
+        let (pool, _receivers) = create_pool_with_connections(10);
+        let node_identity = build_node_identity(Default::default());
+        let conns = select_diverse_nodes(&pool, node_identity.node_id(), 500, &[]);
+        assert_eq!(conns.len(), 10);
+
+        let conns = select_diverse_nodes(&pool, node_identity.node_id(), 4, &[]);
+        assert_eq!(conns.len(), 4);
+
+        let first_node = conns.first().unwrap().peer_node_id().clone();
+        let conns = select_diverse_nodes(&pool, node_identity.node_id(), 10, &[first_node.clone()]);
+        assert_eq!(conns.len(), 9);
+        assert!(conns.iter().all(|c| c.peer_node_id() != &first_node));
+    }
+
+    #[test]
+    fn select_diverse_prefers_other_network_groups() {
+        // Every peer is in the same bucket relative to the origin, so only the network group sets them apart
+        let mut peers = repeat_with(node_id::random).filter(|node_id| node_id.as_bytes()[0] & 0x80 != 0);
+        let mut pool = ConnectionPool::new();
+        let mut receivers = Vec::new();
+        let mut add_connection = |address: &str| {
+            let node_id = peers.next().unwrap();
+            let (conn, rx) = create_dummy_peer_connection_with_address(
+                node_id.clone(),
+                address.parse().unwrap(),
+                ConnectionDirection::Outbound,
+            );
+            receivers.push(rx);
+            pool.insert_connection(conn);
+            node_id
+        };
+        for i in 0..6 {
+            add_connection(&format!("/ip4/8.8.{}.1/tcp/1234", i));
+        }
+        let diverse = vec![
+            add_connection("/ip4/1.1.1.1/tcp/1234"),
+            add_connection("/ip4/2.2.2.2/tcp/1234"),
+            add_connection("/ip4/3.3.3.3/tcp/1234"),
+        ];
+
+        for _ in 0..20 {
+            let conns = select_diverse_nodes(&pool, &NodeId::new(), 4, &[]);
+            assert_eq!(conns.len(), 4);
+            for node_id in &diverse {
+                assert!(
+                    conns.iter().any(|c| c.peer_node_id() == node_id),
+                    "Peers from other network groups should be selected before peers from the same network group"
+                );
+            }
+            let same_group = conns
+                .iter()
+                .filter(|c| network_group(c.address()) == Some(vec![4, 8, 8]))
+                .count();
+            assert_eq!(same_group, 1);
+        }
+    }
+
     #[test]
     fn select_closest_empty() {
         let pool = ConnectionPool::new();
@@ -303,6 +303,53 @@ async fn online_then_offline() {
     assert!(is_offline);
 }
 
+#[runtime::test_basic]
+async fn degraded_below_min_connected_nodes() {
+    let (_, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connected_nodes: 3,
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 3).await;
+    let connections = future::join_all(
+        peers
+            .iter()
+            .cloned()
+            .map(|peer| create_peer_connection_mock_pair(1, node_identity.to_peer(), peer)),
+    )
+    .await
+    .into_iter()
+    .map(|(conn, _, _, _)| conn)
+    .collect::<Vec<_>>();
+
+    let mut events = collect_stream!(event_stream, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateInitialized = &*events.remove(0).unwrap());
+
+    for conn in connections.iter().take(2) {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    streams::assert_in_stream(
+        &mut event_stream,
+        |item| match &*item.unwrap() {
+            ConnectivityEvent::ConnectivityStateDegraded(2) => Some(()),
+            _ => None,
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(connections[2].clone()));
+    streams::assert_in_stream(
+        &mut event_stream,
+        |item| match &*item.unwrap() {
+            ConnectivityEvent::ConnectivityStateOnline(3) => Some(()),
+            _ => None,
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+}
+
 #[runtime::test_basic]
 async fn ban_peer() {
     let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
@@ -21,4 +21,14 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 mod yamux;
-pub use self::yamux::{ConnectionError, Control, IncomingSubstreams, Substream, SubstreamCounter, Yamux};
+pub use self::yamux::{
+    ConnectionError,
+    Control,
+    IncomingSubstreams,
+    Substream,
+    SubstreamCounter,
+    SubstreamPriority,
+    SubstreamSettings,
+    Yamux,
+    DEFAULT_BULK_SEND_WINDOW,
+};
@@ -20,7 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{connection_manager::ConnectionDirection, runtime};
+use crate::{
+    connection_manager::{record_stream_failure, ConnectionDirection, StreamFailureKind},
+    runtime,
+};
 use futures::{
     channel::mpsc,
     future,
@@ -33,7 +36,19 @@ use futures::{
     StreamExt,
 };
 use log::*;
-use std::{future::Future, io, pin::Pin, sync::Arc, task::Poll};
+use std::{
+    cmp,
+    future::Future,
+    io,
+    mem,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+        Mutex,
+    },
+    task::{Poll, Waker},
+};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use yamux::Mode;
 
@@ -53,6 +68,53 @@ pub struct Yamux {
 
 const MAX_BUFFER_SIZE: u32 = 8 * 1024 * 1024; // 8MiB
 const RECEIVE_WINDOW: u32 = 5 * 1024 * 1024; // 5MiB
+/// The send window used by bulk substreams when none is configured for their protocol
+pub const DEFAULT_BULK_SEND_WINDOW: usize = 256 * 1024; // 256KiB
+
+/// A hint for how a substream's writes are scheduled relative to the other substreams on the same connection. While a
+/// write on a substream is blocked on the connection, substreams of a lower priority hold back their writes so that
+/// latency-sensitive protocols are not starved behind bulk transfers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SubstreamPriority {
+    /// Large transfers, such as block and UTXO sync, that should give way to everything else
+    Bulk = 0,
+    Normal = 1,
+    /// Latency-sensitive protocols, such as messaging
+    High = 2,
+}
+
+impl Default for SubstreamPriority {
+    fn default() -> Self {
+        SubstreamPriority::Normal
+    }
+}
+
+/// The write scheduling settings for a substream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SubstreamSettings {
+    pub priority: SubstreamPriority,
+    /// The maximum number of bytes the substream writes before flushing them to the connection, which interleaves its
+    /// frames with those of other substreams. Yamux 0.9 only supports a receive window for the whole connection, so
+    /// this is enforced per substream on the sending side. `None` leaves writes unbounded.
+    pub send_window: Option<usize>,
+}
+
+impl SubstreamSettings {
+    pub fn new(priority: SubstreamPriority) -> Self {
+        Self {
+            priority,
+            send_window: match priority {
+                SubstreamPriority::Bulk => Some(DEFAULT_BULK_SEND_WINDOW),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn with_send_window(mut self, send_window: usize) -> Self {
+        self.send_window = Some(send_window);
+        self
+    }
+}
 
 impl Yamux {
     /// Upgrade the underlying socket to use yamux
@@ -75,9 +137,10 @@ impl Yamux {
         config.set_receive_window(RECEIVE_WINDOW);
 
         let substream_counter = SubstreamCounter::new();
+        let scheduler = WriteScheduler::default();
         let connection = yamux::Connection::new(socket, config, mode);
-        let control = Control::new(connection.control(), substream_counter.clone());
-        let incoming = Self::spawn_incoming_stream_worker(connection, substream_counter.clone());
+        let control = Control::new(connection.control(), substream_counter.clone(), scheduler.clone());
+        let incoming = Self::spawn_incoming_stream_worker(connection, substream_counter.clone(), scheduler);
 
         Ok(Self {
             control,
@@ -91,6 +154,7 @@ impl Yamux {
     fn spawn_incoming_stream_worker<TSocket>(
         connection: yamux::Connection<TSocket>,
         counter: SubstreamCounter,
+        scheduler: WriteScheduler,
     ) -> IncomingSubstreams
     where
         TSocket: AsyncRead + AsyncWrite + Unpin + Send + 'static,
@@ -100,7 +164,7 @@ impl Yamux {
         let stream = yamux::into_stream(connection).boxed();
         let incoming = IncomingWorker::new(stream, incoming_tx, shutdown.to_signal());
         runtime::current().spawn(incoming.run());
-        IncomingSubstreams::new(incoming_rx, counter, shutdown)
+        IncomingSubstreams::new(incoming_rx, counter, scheduler, shutdown)
     }
 
     /// Get the yamux control struct
@@ -137,23 +201,40 @@ impl Yamux {
 pub struct Control {
     inner: yamux::Control,
     substream_counter: SubstreamCounter,
+    scheduler: WriteScheduler,
 }
 
 impl Control {
-    pub fn new(inner: yamux::Control, substream_counter: SubstreamCounter) -> Self {
+    pub(crate) fn new(inner: yamux::Control, substream_counter: SubstreamCounter, scheduler: WriteScheduler) -> Self {
         Self {
             inner,
             substream_counter,
+            scheduler,
         }
     }
 
     /// Open a new stream to the remote.
     pub async fn open_stream(&mut self) -> Result<Substream, ConnectionError> {
+        self.open_stream_with_settings(Default::default()).await
+    }
+
+    /// Open a new stream to the remote with the given write scheduling priority.
+    pub async fn open_stream_with_priority(
+        &mut self,
+        priority: SubstreamPriority,
+    ) -> Result<Substream, ConnectionError> {
+        self.open_stream_with_settings(SubstreamSettings::new(priority)).await
+    }
+
+    /// Open a new stream to the remote with the given write scheduling settings.
+    pub async fn open_stream_with_settings(
+        &mut self,
+        settings: SubstreamSettings,
+    ) -> Result<Substream, ConnectionError> {
         let stream = self.inner.open_stream().await?;
-        Ok(Substream {
-            stream,
-            counter_guard: self.substream_counter.new_guard(),
-        })
+        let mut substream = Substream::new(stream, self.substream_counter.new_guard(), self.scheduler.clone());
+        substream.set_settings(settings);
+        Ok(substream)
     }
 
     /// Close the connection.
@@ -173,14 +254,21 @@ impl Control {
 pub struct IncomingSubstreams {
     inner: IncomingRx,
     substream_counter: SubstreamCounter,
+    scheduler: WriteScheduler,
     shutdown: Shutdown,
 }
 
 impl IncomingSubstreams {
-    pub fn new(inner: IncomingRx, substream_counter: SubstreamCounter, shutdown: Shutdown) -> Self {
+    pub(crate) fn new(
+        inner: IncomingRx,
+        substream_counter: SubstreamCounter,
+        scheduler: WriteScheduler,
+        shutdown: Shutdown,
+    ) -> Self {
         Self {
             inner,
             substream_counter,
+            scheduler,
             shutdown,
         }
     }
@@ -201,10 +289,11 @@ impl Stream for IncomingSubstreams {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         match futures::ready!(Pin::new(&mut self.inner).poll_next(cx)) {
-            Some(stream) => Poll::Ready(Some(Substream {
+            Some(stream) => Poll::Ready(Some(Substream::new(
                 stream,
-                counter_guard: self.substream_counter.new_guard(),
-            })),
+                self.substream_counter.new_guard(),
+                self.scheduler.clone(),
+            ))),
             None => Poll::Ready(None),
         }
     }
@@ -220,6 +309,59 @@ impl Drop for IncomingSubstreams {
 pub struct Substream {
     stream: yamux::Stream,
     counter_guard: CounterGuard,
+    settings: SubstreamSettings,
+    scheduler: WriteScheduler,
+    /// Bytes written since the last flush
+    unflushed: usize,
+    /// True while this substream is counted as a blocked writer in the scheduler
+    is_blocked: bool,
+}
+
+impl Substream {
+    fn new(stream: yamux::Stream, counter_guard: CounterGuard, scheduler: WriteScheduler) -> Self {
+        Self {
+            stream,
+            counter_guard,
+            settings: Default::default(),
+            scheduler,
+            unflushed: 0,
+            is_blocked: false,
+        }
+    }
+
+    pub fn priority(&self) -> SubstreamPriority {
+        self.settings.priority
+    }
+
+    /// Set the write scheduling settings for this substream, typically once the protocol it carries is known
+    pub fn set_settings(&mut self, settings: SubstreamSettings) {
+        self.set_blocked(false);
+        self.settings = settings;
+    }
+
+    fn set_blocked(&mut self, is_blocked: bool) {
+        if self.is_blocked == is_blocked || self.settings.priority == SubstreamPriority::Bulk {
+            return;
+        }
+        self.is_blocked = is_blocked;
+        if is_blocked {
+            self.scheduler.block(self.settings.priority);
+        } else {
+            self.scheduler.unblock(self.settings.priority);
+        }
+    }
+
+    /// Track whether this substream's write is blocked on the connection so that lower priority substreams give way
+    fn track_blocked<T>(&mut self, poll: Poll<T>) -> Poll<T> {
+        self.set_blocked(poll.is_pending());
+        poll
+    }
+}
+
+impl Drop for Substream {
+    fn drop(&mut self) {
+        self.set_blocked(false);
+    }
 }
 
 impl AsyncRead for Substream {
@@ -230,11 +372,30 @@ impl AsyncRead for Substream {
 
 impl AsyncWrite for Substream {
     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.stream).poll_write(cx, buf)
+        futures::ready!(self.scheduler.poll_write_ready(self.settings.priority, cx));
+
+        let mut buf = buf;
+        if let Some(send_window) = self.settings.send_window {
+            if self.unflushed >= send_window {
+                futures::ready!(self.as_mut().poll_flush(cx))?;
+            }
+            let remaining = send_window.saturating_sub(self.unflushed).max(1);
+            buf = &buf[..cmp::min(buf.len(), remaining)];
+        }
+
+        let poll = Pin::new(&mut self.stream).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            self.unflushed += n;
+        }
+        self.track_blocked(poll)
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.stream).poll_flush(cx)
+        let poll = Pin::new(&mut self.stream).poll_flush(cx);
+        if let Poll::Ready(Ok(_)) = poll {
+            self.unflushed = 0;
+        }
+        self.track_blocked(poll)
     }
 
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
@@ -242,6 +403,73 @@ impl AsyncWrite for Substream {
     }
 }
 
+/// Shared by the substreams of a connection to hold back lower priority writes while a higher priority write is
+/// blocked on the connection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WriteScheduler {
+    inner: Arc<WriteSchedulerState>,
+}
+
+#[derive(Debug, Default)]
+struct WriteSchedulerState {
+    /// The number of blocked writers for each `SubstreamPriority`
+    blocked: [AtomicUsize; 3],
+    waiting: Mutex<Vec<Waker>>,
+}
+
+impl WriteScheduler {
+    fn block(&self, priority: SubstreamPriority) {
+        self.inner.blocked[priority as usize].fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn unblock(&self, priority: SubstreamPriority) {
+        if self.inner.blocked[priority as usize].fetch_sub(1, Ordering::SeqCst) == 1 {
+            // Take the wakers so that the lock is not held while waking them
+            let waiting = mem::take(&mut *self.inner.waiting.lock().unwrap());
+            waiting.into_iter().for_each(Waker::wake);
+        }
+    }
+
+    fn has_blocked_above(&self, priority: SubstreamPriority) -> bool {
+        self.inner.blocked[priority as usize + 1..]
+            .iter()
+            .any(|n| n.load(Ordering::SeqCst) > 0)
+    }
+
+    /// Returns `Pending` while a write of a higher priority than `priority` is blocked
+    fn poll_write_ready(&self, priority: SubstreamPriority, cx: &mut Context<'_>) -> Poll<()> {
+        if !self.has_blocked_above(priority) {
+            return Poll::Ready(());
+        }
+        {
+            let mut waiting = self.inner.waiting.lock().unwrap();
+            if !waiting.iter().any(|w| w.will_wake(cx.waker())) {
+                waiting.push(cx.waker().clone());
+            }
+        }
+        // The blocked writes may have completed while the waker was being registered
+        if self.has_blocked_above(priority) {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    }
+}
+
+/// Classifies yamux connection errors caused by the peer sending frames that could not be decoded
+fn stream_failure_kind(err: &ConnectionError) -> Option<StreamFailureKind> {
+    match err {
+        ConnectionError::Decode(yamux::FrameDecodeError::FrameTooLarge(_)) => Some(StreamFailureKind::OversizedFrame),
+        ConnectionError::Decode(yamux::FrameDecodeError::Header(_)) => Some(StreamFailureKind::MalformedFrame),
+        ConnectionError::Io(err) | ConnectionError::Decode(yamux::FrameDecodeError::Io(err))
+            if err.kind() == io::ErrorKind::InvalidData =>
+        {
+            Some(StreamFailureKind::MalformedNoiseFrame)
+        },
+        _ => None,
+    }
+}
+
 struct IncomingWorker<S> {
     inner: S,
     sender: mpsc::Sender<yamux::Stream>,
@@ -269,6 +497,9 @@ where S: Stream<Item = Result<yamux::Stream, yamux::ConnectionError>> + Unpin
                         target: LOG_TARGET,
                         "Incoming peer substream task received an error because '{}'", err
                     );
+                    if let Some(kind) = stream_failure_kind(&err) {
+                        record_stream_failure(kind);
+                    }
                     break;
                 },
                 // Received a substream result
@@ -335,16 +566,24 @@ mod test {
     use crate::{
         connection_manager::ConnectionDirection,
         memsocket::MemorySocket,
-        multiplexing::yamux::Yamux,
+        multiplexing::yamux::{SubstreamPriority, WriteScheduler, Yamux, DEFAULT_BULK_SEND_WINDOW},
         runtime,
         runtime::task,
     };
     use futures::{
         future,
         io::{AsyncReadExt, AsyncWriteExt},
+        task::{waker, ArcWake, Context},
         StreamExt,
     };
-    use std::{io, time::Duration};
+    use std::{
+        io,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
     use tari_test_utils::collect_stream;
 
     #[runtime::test_basic]
@@ -501,4 +740,158 @@ mod test {
 
         Ok(())
     }
+
+    struct WakeFlag(AtomicBool);
+
+    impl ArcWake for WakeFlag {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn lower_priority_writes_wait_for_blocked_writes() {
+        let scheduler = WriteScheduler::default();
+        let flag = Arc::new(WakeFlag(AtomicBool::new(false)));
+        let waker = waker(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        scheduler.block(SubstreamPriority::High);
+        assert!(scheduler
+            .poll_write_ready(SubstreamPriority::Bulk, &mut cx)
+            .is_pending());
+        assert!(scheduler
+            .poll_write_ready(SubstreamPriority::Normal, &mut cx)
+            .is_pending());
+        assert!(scheduler.poll_write_ready(SubstreamPriority::High, &mut cx).is_ready());
+
+        scheduler.unblock(SubstreamPriority::High);
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert!(scheduler.poll_write_ready(SubstreamPriority::Bulk, &mut cx).is_ready());
+
+        scheduler.block(SubstreamPriority::Normal);
+        assert!(scheduler
+            .poll_write_ready(SubstreamPriority::Bulk, &mut cx)
+            .is_pending());
+        assert!(scheduler
+            .poll_write_ready(SubstreamPriority::Normal, &mut cx)
+            .is_ready());
+        scheduler.unblock(SubstreamPriority::Normal);
+        assert!(scheduler.poll_write_ready(SubstreamPriority::Bulk, &mut cx).is_ready());
+    }
+
+    #[runtime::test_basic]
+    async fn bulk_substream_send_window() -> io::Result<()> {
+        const MSG_LEN: usize = 4 * DEFAULT_BULK_SEND_WINDOW;
+        let (dialer, listener) = MemorySocket::new_pair();
+
+        let dialer = Yamux::upgrade_connection(dialer, ConnectionDirection::Outbound).await?;
+        let mut dialer_control = dialer.get_yamux_control();
+
+        task::spawn(async move {
+            let mut substream = dialer_control
+                .open_stream_with_priority(SubstreamPriority::Bulk)
+                .await
+                .unwrap();
+            assert_eq!(substream.priority(), SubstreamPriority::Bulk);
+
+            let msg = vec![0x55u8; MSG_LEN];
+            // A single write never exceeds the send window
+            let n = substream.write(msg.as_slice()).await.unwrap();
+            assert!(n <= DEFAULT_BULK_SEND_WINDOW);
+            substream.write_all(&msg[n..]).await.unwrap();
+            substream.close().await.unwrap();
+        });
+
+        let mut incoming = Yamux::upgrade_connection(listener, ConnectionDirection::Inbound)
+            .await?
+            .incoming();
+        let mut substream = incoming.next().await.unwrap();
+
+        let mut buf = vec![0u8; MSG_LEN];
+        substream.read_exact(&mut buf).await?;
+        assert_eq!(buf, vec![0x55u8; MSG_LEN]);
+
+        Ok(())
+    }
+
+    #[runtime::test]
+    async fn high_priority_latency_under_bulk_load() -> io::Result<()> {
+        #[allow(non_upper_case_globals)]
+        static MiB: usize = 1 << 20;
+        const NUM_PINGS: usize = 20;
+        const MAX_PING_LATENCY: Duration = Duration::from_secs(1);
+
+        let (dialer, listener) = MemorySocket::new_pair();
+        let dialer = Yamux::upgrade_connection(dialer, ConnectionDirection::Outbound).await?;
+        let mut dialer_control = dialer.get_yamux_control();
+        let mut incoming = Yamux::upgrade_connection(listener, ConnectionDirection::Inbound)
+            .await?
+            .incoming();
+
+        // Announce both substreams to the listener before the load starts so that they are received in order
+        let mut bulk = dialer_control
+            .open_stream_with_priority(SubstreamPriority::Bulk)
+            .await
+            .unwrap();
+        bulk.write_all(b"b").await?;
+        let mut bulk_in = incoming.next().await.unwrap();
+        let mut ping = dialer_control
+            .open_stream_with_priority(SubstreamPriority::High)
+            .await
+            .unwrap();
+        ping.write_all(b"p").await?;
+        let mut ping_in = incoming.next().await.unwrap();
+
+        // The listener sinks the bulk transfer and echoes pings
+        task::spawn(async move {
+            let mut buf = Vec::new();
+            bulk_in.read_to_end(&mut buf).await.unwrap();
+        });
+        task::spawn(async move {
+            let mut buf = [0u8; 1];
+            ping_in.read_exact(&mut buf).await.unwrap();
+            while ping_in.read_exact(&mut buf).await.is_ok() {
+                ping_in.write_all(&buf).await.unwrap();
+                ping_in.flush().await.unwrap();
+            }
+        });
+
+        // Keep the connection loaded with bulk writes until all of the pings have completed
+        let pings_done = Arc::new(AtomicBool::new(false));
+        let pings_done_clone = pings_done.clone();
+        let bulk_task = task::spawn(async move {
+            let chunk = vec![0x55u8; MiB];
+            let mut written = 0;
+            while !pings_done_clone.load(Ordering::SeqCst) {
+                bulk.write_all(&chunk).await.unwrap();
+                written += chunk.len();
+            }
+            bulk.close().await.unwrap();
+            written
+        });
+
+        let mut latencies = Vec::with_capacity(NUM_PINGS);
+        let mut buf = [0u8; 1];
+        for _ in 0..NUM_PINGS {
+            let timer = Instant::now();
+            ping.write_all(b"p").await?;
+            ping.flush().await?;
+            ping.read_exact(&mut buf).await?;
+            latencies.push(timer.elapsed());
+        }
+
+        pings_done.store(true, Ordering::SeqCst);
+        let bulk_written = bulk_task.await.unwrap();
+        assert!(bulk_written >= MiB, "Expected the bulk transfer to load the connection");
+
+        let max_latency = latencies.iter().max().unwrap();
+        assert!(
+            *max_latency < MAX_PING_LATENCY,
+            "Expected pings to complete promptly while the bulk transfer was in progress (max latency {:.2?})",
+            max_latency
+        );
+
+        Ok(())
+    }
 }
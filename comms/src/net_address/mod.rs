@@ -21,7 +21,7 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 mod multiaddr_with_stats;
-pub use multiaddr_with_stats::MutliaddrWithStats;
+pub use multiaddr_with_stats::{AddressKind, MutliaddrWithStats};
 
 mod mutliaddresses_with_stats;
 pub use mutliaddresses_with_stats::MultiaddressesWithStats;
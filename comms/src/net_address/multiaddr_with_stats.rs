@@ -1,7 +1,8 @@
 use chrono::{DateTime, Utc};
-use multiaddr::Multiaddr;
+use multiaddr::{Multiaddr, Protocol};
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp,
     cmp::{Ord, Ordering},
     fmt,
     hash::{Hash, Hasher},
@@ -10,10 +11,59 @@ use std::{
 
 const MAX_LATENCY_SAMPLE_COUNT: u32 = 100;
 
+/// Score given to an address that has been successfully connected to
+const SCORE_SUCCESS_BONUS: i64 = 1000;
+/// Score deducted per hour since the last successful connection, up to the success bonus
+const SCORE_PENALTY_PER_HOUR_SINCE_SUCCESS: i64 = 10;
+/// Score deducted for each consecutive failed connection attempt
+const SCORE_PENALTY_PER_FAILED_ATTEMPT: i64 = 200;
+/// Maximum score deducted for a high average latency (1 point per 10ms)
+const SCORE_MAX_LATENCY_PENALTY: i64 = 500;
+
+/// The transport used to dial an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressKind {
+    Tcp,
+    Tor,
+    Quic,
+    Memory,
+    Other,
+}
+
+impl AddressKind {
+    pub fn from_multiaddr(address: &Multiaddr) -> Self {
+        let mut kind = AddressKind::Other;
+        for protocol in address.iter() {
+            match protocol {
+                Protocol::Onion(_, _) | Protocol::Onion3(_) => return AddressKind::Tor,
+                Protocol::Quic => return AddressKind::Quic,
+                Protocol::Memory(_) => return AddressKind::Memory,
+                Protocol::Tcp(_) => kind = AddressKind::Tcp,
+                _ => {},
+            }
+        }
+        kind
+    }
+}
+
+impl fmt::Display for AddressKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressKind::Tcp => write!(f, "tcp"),
+            AddressKind::Tor => write!(f, "tor"),
+            AddressKind::Quic => write!(f, "quic"),
+            AddressKind::Memory => write!(f, "memory"),
+            AddressKind::Other => write!(f, "other"),
+        }
+    }
+}
+
 #[derive(Debug, Eq, Clone, Deserialize, Serialize)]
 pub struct MutliaddrWithStats {
     pub address: Multiaddr,
     pub last_seen: Option<DateTime<Utc>>,
+    /// The last time a connection was successfully established with this address
+    pub last_success: Option<DateTime<Utc>>,
     pub connection_attempts: u32,
     pub rejected_message_count: u32,
     pub avg_latency: Duration,
@@ -26,6 +76,7 @@ impl MutliaddrWithStats {
         Self {
             address,
             last_seen: None,
+            last_success: None,
             connection_attempts: 0,
             rejected_message_count: 0,
             avg_latency: Duration::from_millis(0),
@@ -37,6 +88,7 @@ impl MutliaddrWithStats {
     pub fn new_with_stats(
         address: Multiaddr,
         last_seen: Option<DateTime<Utc>>,
+        last_success: Option<DateTime<Utc>>,
         connection_attempts: u32,
         rejected_message_count: u32,
         avg_latency: Duration,
@@ -45,6 +97,7 @@ impl MutliaddrWithStats {
         Self {
             address,
             last_seen,
+            last_success,
             connection_attempts,
             rejected_message_count,
             avg_latency,
@@ -83,7 +136,9 @@ impl MutliaddrWithStats {
 
     /// Mark that a successful connection was established with this net address
     pub fn mark_successful_connection_attempt(&mut self) {
-        self.last_seen = Some(Utc::now());
+        let now = Utc::now();
+        self.last_seen = Some(now);
+        self.last_success = Some(now);
         self.connection_attempts = 0;
     }
 
@@ -101,6 +156,30 @@ impl MutliaddrWithStats {
     pub fn as_net_address(&self) -> Multiaddr {
         self.clone().address
     }
+
+    /// The transport used to dial this address
+    pub fn kind(&self) -> AddressKind {
+        AddressKind::from_multiaddr(&self.address)
+    }
+
+    /// A measure of how likely a dial to this address is to succeed quickly, higher is better. Addresses that have
+    /// recently been connected to score highest, and each consecutive failed attempt and high latency reduce the score.
+    pub fn quality_score(&self) -> i64 {
+        let mut score = 0;
+        if let Some(last_success) = self.last_success {
+            let hours_since_success = Utc::now().signed_duration_since(last_success).num_hours().max(0);
+            score += SCORE_SUCCESS_BONUS -
+                cmp::min(
+                    hours_since_success.saturating_mul(SCORE_PENALTY_PER_HOUR_SINCE_SUCCESS),
+                    SCORE_SUCCESS_BONUS,
+                );
+        }
+        score -= i64::from(self.connection_attempts) * SCORE_PENALTY_PER_FAILED_ATTEMPT;
+        if self.latency_sample_count > 0 {
+            score -= cmp::min(self.avg_latency.as_millis() as i64 / 10, SCORE_MAX_LATENCY_PENALTY);
+        }
+        score
+    }
 }
 
 impl From<Multiaddr> for MutliaddrWithStats {
@@ -109,6 +188,7 @@ impl From<Multiaddr> for MutliaddrWithStats {
         Self {
             address: net_address,
             last_seen: None,
+            last_success: None,
             connection_attempts: 0,
             rejected_message_count: 0,
             avg_latency: Duration::new(0, 0),
@@ -245,6 +325,36 @@ mod test {
         assert_eq!(net_address_with_stats.connection_attempts, 0);
     }
 
+    #[test]
+    fn test_address_kind() {
+        let kind = |s: &str| AddressKind::from_multiaddr(&s.parse().unwrap());
+        assert_eq!(kind("/ip4/123.0.0.123/tcp/8000"), AddressKind::Tcp);
+        assert_eq!(kind("/dns4/tari.com/tcp/8000"), AddressKind::Tcp);
+        assert_eq!(
+            kind("/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"),
+            AddressKind::Tor
+        );
+        assert_eq!(kind("/ip4/123.0.0.123/udp/8000/quic"), AddressKind::Quic);
+        assert_eq!(kind("/memory/1234"), AddressKind::Memory);
+        assert_eq!(kind("/ip4/123.0.0.123/udp/8000"), AddressKind::Other);
+    }
+
+    #[test]
+    fn test_quality_score() {
+        let net_address = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
+        let mut na = MutliaddrWithStats::from(net_address);
+        assert_eq!(na.quality_score(), 0);
+        na.mark_failed_connection_attempt();
+        assert_eq!(na.quality_score(), -SCORE_PENALTY_PER_FAILED_ATTEMPT);
+        na.mark_successful_connection_attempt();
+        assert!(na.last_success.is_some());
+        assert_eq!(na.quality_score(), SCORE_SUCCESS_BONUS);
+        na.update_latency(Duration::from_millis(100));
+        assert_eq!(na.quality_score(), SCORE_SUCCESS_BONUS - 10);
+        na.update_latency(Duration::from_secs(60));
+        assert_eq!(na.quality_score(), SCORE_SUCCESS_BONUS - SCORE_MAX_LATENCY_PENALTY);
+    }
+
     #[test]
     fn test_net_address_reliability_ordering() {
         let net_address = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
@@ -1,8 +1,8 @@
-use super::multiaddr_with_stats::MutliaddrWithStats;
+use super::multiaddr_with_stats::{AddressKind, MutliaddrWithStats};
 use chrono::{DateTime, Utc};
 use multiaddr::Multiaddr;
 use serde::{Deserialize, Serialize};
-use std::{ops::Index, time::Duration};
+use std::{cmp, ops::Index, time::Duration};
 
 /// This struct is used to store a set of different net addresses such as IPv4, IPv6, Tor or I2P for a single peer.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default, Eq)]
@@ -48,6 +48,10 @@ impl MultiaddressesWithStats {
         self.last_attempted
     }
 
+    pub(crate) fn set_last_attempted(&mut self, last_attempted: Option<DateTime<Utc>>) {
+        self.last_attempted = last_attempted;
+    }
+
     /// Adds a new net address to the peer. This function will not add a duplicate if the address
     /// already exists.
     pub fn add_net_address(&mut self, net_address: &Multiaddr) {
@@ -92,6 +96,23 @@ impl MultiaddressesWithStats {
         self.addresses.iter().map(|addr| &addr.address)
     }
 
+    /// Returns the addresses in the order they should be dialed, highest [quality
+    /// score](MutliaddrWithStats::quality_score) first
+    pub fn iter_by_score(&self) -> impl Iterator<Item = &Multiaddr> {
+        let mut addresses = self.addresses.iter().collect::<Vec<_>>();
+        // Stable sort, addresses with equal scores remain in reliability order
+        addresses.sort_by_key(|a| cmp::Reverse(a.quality_score()));
+        addresses.into_iter().map(|a| &a.address)
+    }
+
+    /// Returns the addresses that are dialed using the given transport
+    pub fn iter_kind(&self, kind: AddressKind) -> impl Iterator<Item = &Multiaddr> {
+        self.addresses
+            .iter()
+            .filter(move |a| a.kind() == kind)
+            .map(|a| &a.address)
+    }
+
     /// Finds the specified address in the set and allow updating of its variables such as its usage stats
     fn find_address_mut(&mut self, address: &Multiaddr) -> Option<&mut MutliaddrWithStats> {
         self.addresses.iter_mut().find(|a| &a.address == address)
@@ -348,6 +369,27 @@ mod test {
     //        assert_eq!(net_addresses.addresses[2].connection_attempts, 2);
     //    }
 
+    #[test]
+    fn test_iter_by_score_and_kind() {
+        let tcp_address = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
+        let tor_address = "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"
+            .parse::<Multiaddr>()
+            .unwrap();
+        let mut net_addresses: MultiaddressesWithStats = vec![tcp_address.clone(), tor_address.clone()].into();
+        assert_eq!(net_addresses.iter_kind(AddressKind::Tor).collect::<Vec<_>>(), vec![
+            &tor_address
+        ]);
+        assert_eq!(net_addresses.iter_kind(AddressKind::Tcp).collect::<Vec<_>>(), vec![
+            &tcp_address
+        ]);
+
+        assert_eq!(net_addresses.iter_by_score().next().unwrap(), &tcp_address);
+        assert!(net_addresses.mark_failed_connection_attempt(&tcp_address));
+        assert_eq!(net_addresses.iter_by_score().next().unwrap(), &tor_address);
+        assert!(net_addresses.mark_successful_connection_attempt(&tcp_address));
+        assert_eq!(net_addresses.iter_by_score().next().unwrap(), &tcp_address);
+    }
+
     #[test]
     fn test_resetting_all_connection_attempts() {
         let net_address1 = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
@@ -30,3 +30,10 @@ pub enum NoiseError {
     #[error("Handshake Failed: {0}")]
     HandshakeFailed(io::Error),
 }
+
+impl NoiseError {
+    /// Returns true if the handshake failed because the peer sent a message that could not be decrypted
+    pub fn is_malformed(&self) -> bool {
+        matches!(self, NoiseError::HandshakeFailed(err) if err.kind() == io::ErrorKind::InvalidData)
+    }
+}
@@ -0,0 +1,224 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    peer_manager::{PeerFeatures, PeerManagerError},
+    proto::identity as proto,
+    types::{Challenge, CommsPublicKey, CommsSecretKey},
+};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use digest::Digest;
+use multiaddr::Multiaddr;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use tari_crypto::{keys::PublicKey, ristretto::RistrettoSchnorr, tari_utilities::ByteArray};
+
+/// How far in the future an identity signature timestamp may be before the signature is rejected. This allows for
+/// some clock drift between nodes.
+const MAX_FUTURE_DRIFT_MINS: i64 = 10;
+
+/// A signed record of a peer's addresses and features. The record is signed by the peer's identity key, so that
+/// addresses received from other nodes (e.g. propagated via the DHT) can be verified as having come from the peer
+/// itself. The `updated_at` timestamp is part of the signature and is used to discard records that are older than the
+/// one that is already known.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentitySignature {
+    version: u8,
+    signature: RistrettoSchnorr,
+    updated_at: DateTime<Utc>,
+}
+
+impl IdentitySignature {
+    /// The latest version of the identity signature challenge
+    pub const LATEST_VERSION: u8 = 0;
+
+    pub fn new(version: u8, signature: RistrettoSchnorr, updated_at: DateTime<Utc>) -> Self {
+        Self {
+            version,
+            signature,
+            updated_at,
+        }
+    }
+
+    /// Signs the given features and addresses with the secret key, using the current time as the timestamp
+    pub fn sign_new<'a, I: IntoIterator<Item = &'a Multiaddr>>(
+        secret_key: &CommsSecretKey,
+        features: PeerFeatures,
+        addresses: I,
+    ) -> Self {
+        // The timestamp is transmitted in seconds, so the sub-second part is dropped before signing
+        let updated_at = DateTime::from_utc(NaiveDateTime::from_timestamp(Utc::now().timestamp(), 0), Utc);
+        let public_key = CommsPublicKey::from_secret_key(secret_key);
+        let (nonce_secret, public_nonce) = CommsPublicKey::random_keypair(&mut OsRng);
+        let challenge = Self::construct_challenge(
+            &public_key,
+            &public_nonce,
+            Self::LATEST_VERSION,
+            features,
+            addresses,
+            updated_at,
+        );
+        let signature = RistrettoSchnorr::sign(secret_key.clone(), nonce_secret, &challenge)
+            .expect("unreachable panic: a Blake256 challenge is always a valid scalar");
+        Self {
+            version: Self::LATEST_VERSION,
+            signature,
+            updated_at,
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn signature(&self) -> &RistrettoSchnorr {
+        &self.signature
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    /// Returns true if this is a valid signature of the features and addresses by the given public key. The order of
+    /// the addresses does not matter.
+    pub fn is_valid<'a, I: IntoIterator<Item = &'a Multiaddr>>(
+        &self,
+        public_key: &CommsPublicKey,
+        features: PeerFeatures,
+        addresses: I,
+    ) -> bool {
+        if self.version != Self::LATEST_VERSION {
+            return false;
+        }
+        if self.updated_at > Utc::now() + Duration::minutes(MAX_FUTURE_DRIFT_MINS) {
+            return false;
+        }
+        let challenge = Self::construct_challenge(
+            public_key,
+            self.signature.get_public_nonce(),
+            self.version,
+            features,
+            addresses,
+            self.updated_at,
+        );
+        self.signature.verify_challenge(public_key, &challenge)
+    }
+
+    fn construct_challenge<'a, I: IntoIterator<Item = &'a Multiaddr>>(
+        public_key: &CommsPublicKey,
+        public_nonce: &CommsPublicKey,
+        version: u8,
+        features: PeerFeatures,
+        addresses: I,
+        updated_at: DateTime<Utc>,
+    ) -> Vec<u8> {
+        let mut addresses = addresses.into_iter().map(|addr| addr.to_vec()).collect::<Vec<_>>();
+        addresses.sort();
+        addresses.dedup();
+        let challenge = addresses.iter().fold(
+            Challenge::new()
+                .chain(b"comms.peer_manager.identity_signature")
+                .chain(&[version])
+                .chain(public_key.as_bytes())
+                .chain(public_nonce.as_bytes())
+                .chain(updated_at.timestamp().to_le_bytes())
+                .chain(features.bits().to_le_bytes()),
+            |challenge, addr| challenge.chain((addr.len() as u64).to_le_bytes()).chain(addr),
+        );
+        challenge.finalize().to_vec()
+    }
+}
+
+impl TryFrom<proto::IdentitySignature> for IdentitySignature {
+    type Error = PeerManagerError;
+
+    fn try_from(value: proto::IdentitySignature) -> Result<Self, Self::Error> {
+        let version = u8::try_from(value.version)
+            .map_err(|_| PeerManagerError::InvalidIdentitySignature("Invalid version".to_string()))?;
+        let public_nonce = CommsPublicKey::from_bytes(&value.public_nonce)
+            .map_err(|_| PeerManagerError::InvalidIdentitySignature("Invalid public nonce".to_string()))?;
+        let signature = CommsSecretKey::from_bytes(&value.signature)
+            .map_err(|_| PeerManagerError::InvalidIdentitySignature("Invalid signature".to_string()))?;
+        let updated_at = NaiveDateTime::from_timestamp_opt(value.updated_at, 0)
+            .ok_or_else(|| PeerManagerError::InvalidIdentitySignature("Invalid timestamp".to_string()))?;
+
+        Ok(Self::new(
+            version,
+            RistrettoSchnorr::new(public_nonce, signature),
+            DateTime::from_utc(updated_at, Utc),
+        ))
+    }
+}
+
+impl From<&IdentitySignature> for proto::IdentitySignature {
+    fn from(identity_signature: &IdentitySignature) -> Self {
+        proto::IdentitySignature {
+            version: u32::from(identity_signature.version),
+            signature: identity_signature.signature.get_signature().to_vec(),
+            public_nonce: identity_signature.signature.get_public_nonce().to_vec(),
+            updated_at: identity_signature.updated_at.timestamp(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn addresses() -> Vec<Multiaddr> {
+        vec![
+            "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+            "/ip4/10.0.0.1/tcp/18141".parse().unwrap(),
+        ]
+    }
+
+    #[test]
+    fn it_is_valid_for_the_signed_record() {
+        let (secret_key, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let addresses = addresses();
+        let signature = IdentitySignature::sign_new(&secret_key, PeerFeatures::COMMUNICATION_NODE, &addresses);
+        assert!(signature.is_valid(&public_key, PeerFeatures::COMMUNICATION_NODE, &addresses));
+        // Address order does not matter
+        assert!(signature.is_valid(&public_key, PeerFeatures::COMMUNICATION_NODE, addresses.iter().rev()));
+
+        let signature = IdentitySignature::try_from(proto::IdentitySignature::from(&signature)).unwrap();
+        assert!(signature.is_valid(&public_key, PeerFeatures::COMMUNICATION_NODE, &addresses));
+    }
+
+    #[test]
+    fn it_is_invalid_if_the_record_is_changed() {
+        let (secret_key, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let addresses = addresses();
+        let signature = IdentitySignature::sign_new(&secret_key, PeerFeatures::COMMUNICATION_NODE, &addresses);
+
+        let spoofed = vec!["/ip4/1.2.3.4/tcp/9000".parse().unwrap()];
+        assert!(!signature.is_valid(&public_key, PeerFeatures::COMMUNICATION_NODE, &spoofed));
+        assert!(!signature.is_valid(&public_key, PeerFeatures::COMMUNICATION_CLIENT, &addresses));
+        let (_, other_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        assert!(!signature.is_valid(&other_public_key, PeerFeatures::COMMUNICATION_NODE, &addresses));
+
+        let mut future = signature;
+        future.updated_at = future.updated_at + Duration::minutes(MAX_FUTURE_DRIFT_MINS + 1);
+        assert!(!future.is_valid(&public_key, PeerFeatures::COMMUNICATION_NODE, &addresses));
+    }
+}
@@ -28,10 +28,12 @@ use crate::{
         peer_id::PeerId,
         peer_storage::PeerStorage,
         wrapper::KeyValueWrapper,
+        IdentitySignature,
         PeerFeatures,
         PeerManagerError,
         PeerQuery,
     },
+    transports::DialProxy,
     types::{CommsDatabase, CommsPublicKey},
 };
 use multiaddr::Multiaddr;
@@ -76,6 +78,36 @@ impl PeerManager {
         self.peer_storage.write().await.delete_peer(node_id)
     }
 
+    /// Deletes up to `max_peers` peers that have been offline for longer than `min_offline_age`, starting with the
+    /// peers that have been offline the longest. Peers in `excluded` are never deleted. Returns the number of
+    /// deleted peers.
+    pub async fn cull_offline_peers(
+        &self,
+        min_offline_age: Duration,
+        max_peers: usize,
+        excluded: &[NodeId],
+    ) -> Result<usize, PeerManagerError> {
+        let mut storage = self.peer_storage.write().await;
+        let mut stale_peers = storage
+            .all()?
+            .into_iter()
+            .filter(|peer| !excluded.contains(&peer.node_id))
+            .filter(|peer| {
+                peer.offline_since()
+                    .map(|since| since > min_offline_age)
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        stale_peers.sort_by_key(|peer| peer.offline_at);
+
+        let mut num_deleted = 0;
+        for peer in stale_peers.into_iter().take(max_peers) {
+            storage.delete_peer(&peer.node_id)?;
+            num_deleted += 1;
+        }
+        Ok(num_deleted)
+    }
+
     /// Performs the given [PeerQuery].
     ///
     /// [PeerQuery]: crate::peer_manager::peer_query::PeerQuery
@@ -144,6 +176,60 @@ impl PeerManager {
         }
     }
 
+    /// Adds or updates a peer using a signed address record and sets the last connection as successful. An error is
+    /// returned if the identity signature is not valid for the given addresses and features. The addresses and
+    /// features of a known peer are only updated if the signature is newer than the one that is stored, so that an
+    /// older record cannot be replayed to overwrite the peer's current addresses.
+    pub async fn add_or_update_signed_peer(
+        &self,
+        pubkey: &CommsPublicKey,
+        node_id: NodeId,
+        addresses: Vec<Multiaddr>,
+        peer_features: PeerFeatures,
+        identity_signature: IdentitySignature,
+    ) -> Result<Peer, PeerManagerError> {
+        if !identity_signature.is_valid(pubkey, peer_features, &addresses) {
+            return Err(PeerManagerError::InvalidIdentitySignature(
+                "Signature is not valid for the given addresses and features".to_string(),
+            ));
+        }
+
+        match self.find_by_public_key(&pubkey).await {
+            Ok(mut peer) => {
+                peer.connection_stats.set_connection_success();
+                peer.set_offline(false);
+                let is_newer = peer
+                    .identity_signature
+                    .as_ref()
+                    .map(|current| identity_signature.updated_at() > current.updated_at())
+                    .unwrap_or(true);
+                if is_newer {
+                    peer.addresses.update_net_addresses(addresses);
+                    peer.features = peer_features;
+                    peer.identity_signature = Some(identity_signature);
+                }
+                self.add_peer(peer.clone()).await?;
+                Ok(peer)
+            },
+            Err(PeerManagerError::PeerNotFoundError) => {
+                let mut peer = Peer::new(
+                    pubkey.clone(),
+                    node_id,
+                    addresses.into(),
+                    PeerFlags::default(),
+                    peer_features,
+                    Default::default(),
+                    Default::default(),
+                );
+                peer.identity_signature = Some(identity_signature);
+                self.add_peer(peer).await?;
+
+                self.find_by_public_key(&pubkey).await
+            },
+            Err(err) => Err(err),
+        }
+    }
+
     /// Get a peer matching the given node ID
     pub async fn direct_identity_node_id(&self, node_id: &NodeId) -> Result<Option<Peer>, PeerManagerError> {
         match self.peer_storage.read().await.direct_identity_node_id(&node_id) {
@@ -284,6 +370,11 @@ impl PeerManager {
         Ok(peer.features)
     }
 
+    /// Sets the proxy to use when dialing the peer with the given NodeId, overriding the transport's outbound proxy
+    pub async fn set_peer_dial_proxy(&self, node_id: &NodeId, dial_proxy: DialProxy) -> Result<(), PeerManagerError> {
+        self.peer_storage.write().await.set_peer_dial_proxy(node_id, dial_proxy)
+    }
+
     /// This will store metadata inside of the metadata field in the peer provided by the nodeID.
     /// It will return None if the value was empty and the old value if the value was updated
     pub async fn set_peer_metadata(
@@ -314,6 +405,7 @@ mod test {
         },
         runtime,
     };
+    use chrono::Utc;
     use rand::rngs::OsRng;
     use tari_crypto::{keys::PublicKey, ristretto::RistrettoPublicKey};
     use tari_storage::HashmapDatabase;
@@ -565,4 +657,97 @@ mod test {
         assert!(!peer.is_offline());
         assert_eq!(peer.connection_stats.failed_attempts(), 0);
     }
+
+    #[runtime::test_basic]
+    async fn set_peer_dial_proxy() {
+        let peer_manager = PeerManager::new(HashmapDatabase::new(), None).unwrap();
+        let peer = create_test_peer(false, PeerFeatures::COMMUNICATION_NODE);
+        peer_manager.add_peer(peer.clone()).await.unwrap();
+        assert_eq!(peer.dial_proxy, DialProxy::Global);
+
+        let dial_proxy = DialProxy::Socks5 {
+            proxy_address: "/ip4/127.0.0.1/tcp/9050".parse().unwrap(),
+            authentication: Default::default(),
+        };
+        peer_manager
+            .set_peer_dial_proxy(&peer.node_id, dial_proxy.clone())
+            .await
+            .unwrap();
+        let peer = peer_manager.find_by_node_id(&peer.node_id).await.unwrap();
+        assert_eq!(peer.dial_proxy, dial_proxy);
+    }
+
+    #[runtime::test_basic]
+    async fn add_or_update_signed_peer() {
+        let peer_manager = PeerManager::new(HashmapDatabase::new(), None).unwrap();
+        let (sk, pk) = RistrettoPublicKey::random_keypair(&mut OsRng);
+        let node_id = NodeId::from_key(&pk);
+        let addresses = vec!["/ip4/1.2.3.4/tcp/8000".parse::<Multiaddr>().unwrap()];
+        let identity_signature = IdentitySignature::sign_new(&sk, PeerFeatures::COMMUNICATION_NODE, &addresses);
+
+        let spoofed = vec!["/ip4/6.6.6.6/tcp/8000".parse::<Multiaddr>().unwrap()];
+        let err = peer_manager
+            .add_or_update_signed_peer(
+                &pk,
+                node_id.clone(),
+                spoofed,
+                PeerFeatures::COMMUNICATION_NODE,
+                identity_signature.clone(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PeerManagerError::InvalidIdentitySignature(_)));
+        assert!(!peer_manager.exists(&pk).await);
+
+        let peer = peer_manager
+            .add_or_update_signed_peer(
+                &pk,
+                node_id,
+                addresses.clone(),
+                PeerFeatures::COMMUNICATION_NODE,
+                identity_signature,
+            )
+            .await
+            .unwrap();
+        assert!(peer.is_valid_identity_signature());
+        assert_eq!(
+            peer.addresses.iter().collect::<Vec<_>>(),
+            addresses.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[runtime::test_basic]
+    async fn cull_offline_peers() {
+        let peer_manager = PeerManager::new(HashmapDatabase::new(), None).unwrap();
+        let mut stale_peers = Vec::new();
+        for i in 0..4 {
+            let mut peer = create_test_peer(false, PeerFeatures::COMMUNICATION_NODE);
+            peer.offline_at = Some((Utc::now() - chrono::Duration::days(10 + i)).naive_utc());
+            peer_manager.add_peer(peer.clone()).await.unwrap();
+            stale_peers.push(peer);
+        }
+        let mut recently_offline = create_test_peer(false, PeerFeatures::COMMUNICATION_NODE);
+        recently_offline.set_offline(true);
+        peer_manager.add_peer(recently_offline.clone()).await.unwrap();
+        let online = create_test_peer(false, PeerFeatures::COMMUNICATION_NODE);
+        peer_manager.add_peer(online.clone()).await.unwrap();
+
+        let min_age = Duration::from_secs(7 * 24 * 60 * 60);
+        // The peers that have been offline the longest are culled first
+        let num_deleted = peer_manager
+            .cull_offline_peers(min_age, 2, &[stale_peers[3].node_id.clone()])
+            .await
+            .unwrap();
+        assert_eq!(num_deleted, 2);
+        assert!(peer_manager.exists(&stale_peers[3].public_key).await);
+        assert!(!peer_manager.exists(&stale_peers[2].public_key).await);
+        assert!(!peer_manager.exists(&stale_peers[1].public_key).await);
+        assert!(peer_manager.exists(&stale_peers[0].public_key).await);
+
+        let num_deleted = peer_manager.cull_offline_peers(min_age, 10, &[]).await.unwrap();
+        assert_eq!(num_deleted, 2);
+        assert_eq!(peer_manager.count().await, 2);
+        assert!(peer_manager.exists(&recently_offline.public_key).await);
+        assert!(peer_manager.exists(&online.public_key).await);
+    }
 }
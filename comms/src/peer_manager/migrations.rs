@@ -23,6 +23,9 @@
 mod v1;
 mod v2;
 mod v3;
+mod v4;
+mod v5;
+mod v6;
 
 use log::*;
 use tari_storage::lmdb_store::{LMDBDatabase, LMDBError};
@@ -36,6 +39,9 @@ pub fn migrate(database: &LMDBDatabase) -> Result<(), LMDBError> {
         v1::MigrationV1.boxed(),
         v2::MigrationV2.boxed(),
         v3::MigrationV3.boxed(),
+        v4::MigrationV4.boxed(),
+        v5::MigrationV5.boxed(),
+        v6::MigrationV6.boxed(),
     ];
 
     // If the database is empty there is nothing to migrate, so set it to the latest version
@@ -102,3 +108,63 @@ trait MigrationExt<T>: Migration<T> {
 }
 
 impl<T, U> MigrationExt<T> for U where U: Migration<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags, PeerId},
+        types::CommsPublicKey,
+    };
+    use chrono::Utc;
+    use multiaddr::Multiaddr;
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::PublicKey;
+    use tari_storage::lmdb_store::{LMDBBuilder, LMDBConfig};
+
+    #[test]
+    fn it_migrates_an_old_format_peer_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let datastore = LMDBBuilder::new()
+            .set_path(dir.path())
+            .set_env_config(LMDBConfig::default())
+            .set_max_number_of_databases(1)
+            .add_database("peers", lmdb_zero::db::CREATE)
+            .build()
+            .unwrap();
+        let database = datastore.get_handle("peers").unwrap();
+
+        // A peer as stored before the identity_signature field was added
+        let (_, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let banned_until = Utc::now().naive_utc();
+        let old_peer = v6::PeerV6 {
+            id: Some(1),
+            node_id: NodeId::from_key(&public_key),
+            public_key,
+            addresses: "/ip4/127.0.0.1/tcp/18189".parse::<Multiaddr>().unwrap().into(),
+            flags: PeerFlags::NONE,
+            banned_until: Some(banned_until),
+            banned_reason: "Misbehaved".to_string(),
+            offline_at: None,
+            features: PeerFeatures::COMMUNICATION_NODE,
+            connection_stats: Default::default(),
+            supported_protocols: vec![],
+            added_at: banned_until,
+            user_agent: "old".to_string(),
+            metadata: Default::default(),
+            dial_proxy: Default::default(),
+        };
+        database.insert(&1u64, &old_peer).unwrap();
+        database.insert(&MIGRATION_VERSION_KEY, &5u32).unwrap();
+
+        migrate(&database).unwrap();
+
+        assert_eq!(database.get::<_, u32>(&MIGRATION_VERSION_KEY).unwrap(), Some(6));
+        let peer = database.get::<PeerId, Peer>(&1).unwrap().unwrap();
+        assert_eq!(peer.public_key, old_peer.public_key);
+        assert_eq!(peer.banned_until, Some(banned_until));
+        assert_eq!(peer.banned_reason, "Misbehaved");
+        assert_eq!(peer.connection_stats, old_peer.connection_stats);
+        assert!(peer.identity_signature.is_none());
+    }
+}
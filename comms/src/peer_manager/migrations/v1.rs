@@ -21,10 +21,9 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    net_address::MultiaddressesWithStats,
     peer_manager::{
         connection_stats::PeerConnectionStats,
-        migrations::{v2::PeerV2, Migration},
+        migrations::{v2::PeerV2, v4::MultiaddressesWithStatsV4, Migration},
         node_id::deserialize_node_id_from_hex,
         NodeId,
         PeerFeatures,
@@ -52,7 +51,7 @@ pub struct PeerV1 {
     #[serde(serialize_with = "serialize_to_hex")]
     #[serde(deserialize_with = "deserialize_node_id_from_hex")]
     node_id: NodeId,
-    addresses: MultiaddressesWithStats,
+    addresses: MultiaddressesWithStatsV4,
     flags: PeerFlags,
     banned_until: Option<NaiveDateTime>,
     offline_at: Option<NaiveDateTime>,
@@ -21,10 +21,9 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    net_address::MultiaddressesWithStats,
     peer_manager::{
         connection_stats::PeerConnectionStats,
-        migrations::{v3::PeerV3, Migration},
+        migrations::{v3::PeerV3, v4::MultiaddressesWithStatsV4, Migration},
         node_id::deserialize_node_id_from_hex,
         NodeId,
         PeerFeatures,
@@ -52,7 +51,7 @@ pub struct PeerV2 {
     #[serde(serialize_with = "serialize_to_hex")]
     #[serde(deserialize_with = "deserialize_node_id_from_hex")]
     pub node_id: NodeId,
-    pub addresses: MultiaddressesWithStats,
+    pub addresses: MultiaddressesWithStatsV4,
     pub flags: PeerFlags,
     pub banned_until: Option<NaiveDateTime>,
     pub offline_at: Option<NaiveDateTime>,
@@ -21,13 +21,14 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    net_address::MultiaddressesWithStats,
     peer_manager::{
         connection_stats::PeerConnectionStats,
-        migrations::Migration,
+        migrations::{
+            v4::{MultiaddressesWithStatsV4, PeerV4},
+            Migration,
+        },
         node_id::deserialize_node_id_from_hex,
         NodeId,
-        Peer,
         PeerFeatures,
         PeerFlags,
         PeerId,
@@ -54,7 +55,7 @@ pub struct PeerV3 {
     #[serde(serialize_with = "serialize_to_hex")]
     #[serde(deserialize_with = "deserialize_node_id_from_hex")]
     pub node_id: NodeId,
-    pub addresses: MultiaddressesWithStats,
+    pub addresses: MultiaddressesWithStatsV4,
     pub flags: PeerFlags,
     pub banned_until: Option<NaiveDateTime>,
     pub banned_reason: String,
@@ -76,7 +77,7 @@ impl Migration<LMDBDatabase> for MigrationV3 {
             match old_peer {
                 Ok((key, peer)) => {
                     debug!(target: LOG_TARGET, "Migrating peer `{}`", peer.node_id.short_str());
-                    let result = db.insert(&key, &Peer {
+                    let result = db.insert(&key, &PeerV4 {
                         id: peer.id,
                         public_key: peer.public_key,
                         node_id: peer.node_id,
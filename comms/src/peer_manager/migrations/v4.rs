@@ -0,0 +1,156 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    net_address::{MultiaddressesWithStats, MutliaddrWithStats},
+    peer_manager::{
+        connection_stats::PeerConnectionStats,
+        migrations::{v5::PeerV5, Migration},
+        node_id::deserialize_node_id_from_hex,
+        NodeId,
+        PeerFeatures,
+        PeerFlags,
+        PeerId,
+    },
+    protocol::ProtocolId,
+    types::CommsPublicKey,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::*;
+use multiaddr::Multiaddr;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+use tari_crypto::tari_utilities::hex::serialize_to_hex;
+use tari_storage::{
+    lmdb_store::{LMDBDatabase, LMDBError},
+    IterationResult,
+};
+
+const LOG_TARGET: &str = "comms::peer_manager::migrations::v4";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MutliaddrWithStatsV4 {
+    pub address: Multiaddr,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub connection_attempts: u32,
+    pub rejected_message_count: u32,
+    pub avg_latency: Duration,
+    pub latency_sample_count: u32,
+}
+
+impl From<MutliaddrWithStatsV4> for MutliaddrWithStats {
+    fn from(addr: MutliaddrWithStatsV4) -> Self {
+        MutliaddrWithStats::new_with_stats(
+            addr.address,
+            addr.last_seen,
+            // Previously the last successful connection was not recorded
+            None,
+            addr.connection_attempts,
+            addr.rejected_message_count,
+            addr.avg_latency,
+            addr.latency_sample_count,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MultiaddressesWithStatsV4 {
+    pub addresses: Vec<MutliaddrWithStatsV4>,
+    pub last_attempted: Option<DateTime<Utc>>,
+}
+
+impl From<MultiaddressesWithStatsV4> for MultiaddressesWithStats {
+    fn from(addresses: MultiaddressesWithStatsV4) -> Self {
+        let mut new_addresses = MultiaddressesWithStats::new(addresses.addresses.into_iter().map(Into::into).collect());
+        new_addresses.set_last_attempted(addresses.last_attempted);
+        new_addresses
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PeerV4 {
+    pub id: Option<PeerId>,
+    pub public_key: CommsPublicKey,
+    #[serde(serialize_with = "serialize_to_hex")]
+    #[serde(deserialize_with = "deserialize_node_id_from_hex")]
+    pub node_id: NodeId,
+    pub addresses: MultiaddressesWithStatsV4,
+    pub flags: PeerFlags,
+    pub banned_until: Option<NaiveDateTime>,
+    pub banned_reason: String,
+    pub offline_at: Option<NaiveDateTime>,
+    pub features: PeerFeatures,
+    pub connection_stats: PeerConnectionStats,
+    pub supported_protocols: Vec<ProtocolId>,
+    pub added_at: NaiveDateTime,
+    pub user_agent: String,
+    pub metadata: HashMap<u8, Vec<u8>>,
+}
+
+/// This migration is to add the last_success field to the address stats
+pub struct MigrationV4;
+
+impl Migration<LMDBDatabase> for MigrationV4 {
+    type Error = LMDBError;
+
+    fn migrate(&self, db: &LMDBDatabase) -> Result<(), Self::Error> {
+        db.for_each::<PeerId, PeerV4, _>(|old_peer| {
+            match old_peer {
+                Ok((key, peer)) => {
+                    debug!(target: LOG_TARGET, "Migrating peer `{}`", peer.node_id.short_str());
+                    let result = db.insert(&key, &PeerV5 {
+                        id: peer.id,
+                        public_key: peer.public_key,
+                        node_id: peer.node_id,
+                        addresses: peer.addresses.into(),
+                        flags: peer.flags,
+                        banned_until: peer.banned_until,
+                        banned_reason: peer.banned_reason,
+                        offline_at: peer.offline_at,
+                        features: peer.features,
+                        connection_stats: peer.connection_stats,
+                        supported_protocols: peer.supported_protocols,
+                        added_at: peer.added_at,
+                        user_agent: peer.user_agent,
+                        metadata: peer.metadata,
+                    });
+
+                    if let Err(err) = result {
+                        error!(
+                            target: LOG_TARGET,
+                            "Failed to insert peer: {}. ** Database may be corrupt **", err
+                        );
+                    }
+                },
+                Err(err) => {
+                    error!(
+                        target: LOG_TARGET,
+                        "Failed to deserialize peer: {} ** Database may be corrupt **", err
+                    );
+                },
+            }
+            IterationResult::Continue
+        })?;
+
+        Ok(())
+    }
+}
@@ -78,6 +78,9 @@ pub use error::PeerManagerError;
 pub mod node_id;
 pub use node_id::NodeId;
 
+mod identity_signature;
+pub use identity_signature::IdentitySignature;
+
 mod node_identity;
 pub use node_identity::NodeIdentity;
 
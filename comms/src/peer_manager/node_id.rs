@@ -86,6 +86,21 @@ impl XorDistance {
     pub const fn byte_length() -> usize {
         NODE_XOR_DISTANCE_ARRAY_SIZE
     }
+
+    /// Returns the index of the k-bucket that this distance falls into. This is the number of leading zero bits in
+    /// the distance, so a larger index means the node ids share a longer common prefix (i.e. are closer).
+    pub fn bucket_index(&self) -> usize {
+        let mut zeros = 0;
+        for b in &self.0 {
+            if *b == 0 {
+                zeros += 8;
+            } else {
+                zeros += b.leading_zeros() as usize;
+                break;
+            }
+        }
+        zeros
+    }
 }
 
 impl PartialEq for XorDistance {
@@ -619,4 +634,13 @@ mod test {
         assert_eq!(n12_distance, 56114865924689668092413877285545836544);
         assert_eq!(n13_distance, 228941924089749863963604860508980641792);
     }
+
+    #[test]
+    fn bucket_index() {
+        assert_eq!(XorDistance::zero().bucket_index(), NODE_XOR_DISTANCE_ARRAY_SIZE * 8);
+        assert_eq!(XorDistance::max_distance().bucket_index(), 0);
+        let mut bytes = [0u8; NODE_XOR_DISTANCE_ARRAY_SIZE];
+        bytes[1] = 0b0010_0000;
+        assert_eq!(XorDistance::try_from(&bytes[..]).unwrap().bucket_index(), 10);
+    }
 }
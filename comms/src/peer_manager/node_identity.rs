@@ -22,7 +22,7 @@
 
 use super::node_id::deserialize_node_id_from_hex;
 use crate::{
-    peer_manager::{node_id::NodeId, Peer, PeerFeatures, PeerFlags},
+    peer_manager::{node_id::NodeId, IdentitySignature, Peer, PeerFeatures, PeerFlags},
     types::{CommsPublicKey, CommsSecretKey},
 };
 use multiaddr::Multiaddr;
@@ -44,6 +44,9 @@ pub struct NodeIdentity {
     features: PeerFeatures,
     secret_key: CommsSecretKey,
     public_address: RwLock<Multiaddr>,
+    /// Signature of the public address and features. This is not persisted and is re-signed on load.
+    #[serde(skip)]
+    identity_signature: RwLock<Option<IdentitySignature>>,
 }
 
 impl NodeIdentity {
@@ -52,13 +55,16 @@ impl NodeIdentity {
         let public_key = CommsPublicKey::from_secret_key(&secret_key);
         let node_id = NodeId::from_key(&public_key);
 
-        NodeIdentity {
+        let node_identity = NodeIdentity {
             node_id,
             public_key,
             features,
             secret_key,
             public_address: RwLock::new(public_address),
-        }
+            identity_signature: RwLock::new(None),
+        };
+        node_identity.sign();
+        node_identity
     }
 
     /// Generates a new random NodeIdentity for CommsPublicKey
@@ -68,13 +74,16 @@ impl NodeIdentity {
         let public_key = CommsPublicKey::from_secret_key(&secret_key);
         let node_id = NodeId::from_key(&public_key);
 
-        NodeIdentity {
+        let node_identity = NodeIdentity {
             node_id,
             public_key,
             features,
             secret_key,
             public_address: RwLock::new(public_address),
-        }
+            identity_signature: RwLock::new(None),
+        };
+        node_identity.sign();
+        node_identity
     }
 
     /// Retrieve the publicly accessible address that peers must connect to establish a connection
@@ -85,6 +94,23 @@ impl NodeIdentity {
     /// Modify the control_service_address
     pub fn set_public_address(&self, address: Multiaddr) {
         *acquire_write_lock!(self.public_address) = address;
+        self.sign();
+    }
+
+    /// Returns the signature of this node's public address and features. If the identity has not been signed yet (e.g.
+    /// it was loaded from disk), it is signed now.
+    pub fn identity_signature(&self) -> IdentitySignature {
+        if let Some(signature) = acquire_read_lock!(self.identity_signature).as_ref() {
+            return signature.clone();
+        }
+        self.sign()
+    }
+
+    fn sign(&self) -> IdentitySignature {
+        let public_address = self.public_address();
+        let signature = IdentitySignature::sign_new(&self.secret_key, self.features, Some(&public_address));
+        *acquire_write_lock!(self.identity_signature) = Some(signature.clone());
+        signature
     }
 
     /// This returns a random NodeIdentity for testing purposes. This function can panic. If public_address
@@ -128,7 +154,7 @@ impl NodeIdentity {
     /// Returns a Peer with the same public key, node id, public address and features as represented in this
     /// NodeIdentity. _NOTE: PeerFlags, supported_protocols and user agent are empty._
     pub fn to_peer(&self) -> Peer {
-        Peer::new(
+        let mut peer = Peer::new(
             self.public_key().clone(),
             self.node_id().clone(),
             self.public_address().into(),
@@ -136,7 +162,9 @@ impl NodeIdentity {
             self.features(),
             Default::default(),
             Default::default(),
-        )
+        );
+        peer.identity_signature = Some(self.identity_signature());
+        peer
     }
 }
 
@@ -148,6 +176,7 @@ impl Clone for NodeIdentity {
             features: self.features,
             secret_key: self.secret_key.clone(),
             public_address: RwLock::new(self.public_address()),
+            identity_signature: RwLock::new(acquire_read_lock!(self.identity_signature).clone()),
         }
     }
 }
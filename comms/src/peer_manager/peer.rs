@@ -24,11 +24,13 @@ use super::{
     connection_stats::PeerConnectionStats,
     node_id::{deserialize_node_id_from_hex, NodeId},
     peer_id::PeerId,
+    IdentitySignature,
     PeerFeatures,
 };
 use crate::{
     net_address::MultiaddressesWithStats,
     protocol::ProtocolId,
+    transports::DialProxy,
     types::CommsPublicKey,
     utils::datetime::safe_future_datetime_from_duration,
 };
@@ -91,6 +93,11 @@ pub struct Peer {
     /// Metadata field. This field is for use by upstream clients to record extra info about a peer.
     /// We use a hashmap here so that we can use more than one "info set"
     pub metadata: HashMap<u8, Vec<u8>>,
+    /// The proxy to use when dialing this peer
+    pub dial_proxy: DialProxy,
+    /// Signature of the peer's addresses and features, signed by the peer's identity key. If this is None, the
+    /// addresses have not been verified as having come from the peer.
+    pub identity_signature: Option<IdentitySignature>,
 }
 
 impl Peer {
@@ -119,6 +126,8 @@ impl Peer {
             supported_protocols,
             user_agent,
             metadata: HashMap::new(),
+            dial_proxy: Default::default(),
+            identity_signature: None,
         }
     }
 
@@ -154,6 +163,14 @@ impl Peer {
             .map(|since| Duration::from_millis(since.num_milliseconds() as u64))
     }
 
+    /// Returns true if the peer has an identity signature that is valid for its current addresses and features
+    pub fn is_valid_identity_signature(&self) -> bool {
+        self.identity_signature
+            .as_ref()
+            .map(|sig| sig.is_valid(&self.public_key, self.features, self.addresses.iter()))
+            .unwrap_or(false)
+    }
+
     /// TODO: Remove once we don't have to sync wallet and base node db
     pub fn unset_id(&mut self) {
         self.id = None;
@@ -251,6 +268,11 @@ impl Peer {
         }
     }
 
+    /// Sets the proxy to use when dialing this peer
+    pub fn set_dial_proxy(&mut self, dial_proxy: DialProxy) {
+        self.dial_proxy = dial_proxy;
+    }
+
     /// This will store metadata inside of the metadata field in the peer.
     /// It will return None if the value was empty and the old value if the value was updated
     pub fn set_metadata(&mut self, key: u8, data: Vec<u8>) -> Option<Vec<u8>> {
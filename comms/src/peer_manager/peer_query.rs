@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::peer_manager::{peer_id::PeerId, NodeId, Peer, PeerManagerError};
-use std::cmp::min;
+use std::cmp::{min, Reverse};
 use tari_storage::{IterationResult, KeyValueStore};
 
 type Predicate<'a, A> = Box<dyn FnMut(&A) -> bool + Send + 'a>;
@@ -33,6 +33,8 @@ pub enum PeerQuerySortBy<'a> {
     None,
     /// Sort by distance from a given node id
     DistanceFrom(&'a NodeId),
+    /// Sort by the time the peer was last seen, most recent first. Peers that have never been seen are last.
+    LastSeen,
 }
 
 impl Default for PeerQuerySortBy<'_> {
@@ -129,9 +131,30 @@ where DS: KeyValueStore<PeerId, Peer>
         match self.query.sort_by {
             PeerQuerySortBy::None => self.get_query_results(),
             PeerQuerySortBy::DistanceFrom(node_id) => self.get_distance_sorted_results(node_id),
+            PeerQuerySortBy::LastSeen => self.get_last_seen_sorted_results(),
         }
     }
 
+    pub fn get_last_seen_sorted_results(&mut self) -> Result<Vec<Peer>, PeerManagerError> {
+        let mut selected_peers = Vec::new();
+        self.store
+            .for_each_ok(|(_, peer)| {
+                if self.query.is_selected(&peer) {
+                    selected_peers.push(peer);
+                }
+
+                IterationResult::Continue
+            })
+            .map_err(PeerManagerError::DatabaseError)?;
+
+        selected_peers.sort_by_key(|peer| Reverse(peer.last_seen()));
+        if let Some(limit) = self.query.limit {
+            selected_peers.truncate(limit);
+        }
+
+        Ok(selected_peers)
+    }
+
     pub fn get_distance_sorted_results(&mut self, node_id: &NodeId) -> Result<Vec<Peer>, PeerManagerError> {
         let mut peer_keys = Vec::new();
         let mut distances = Vec::new();
@@ -410,4 +433,32 @@ mod test {
         })
         .unwrap();
     }
+
+    #[test]
+    fn sort_by_last_seen_query() {
+        let db = HashmapDatabase::new();
+        let mut seen_peers = Vec::new();
+        for id in 0..5 {
+            let mut peer = create_test_peer(false);
+            if id % 2 == 0 {
+                let address = peer.addresses.first().unwrap().address.clone();
+                peer.addresses.mark_successful_connection_attempt(&address);
+                seen_peers.push(peer.node_id.clone());
+            }
+            db.insert(id, peer).unwrap();
+        }
+
+        let peers = PeerQuery::new()
+            .sort_by(PeerQuerySortBy::LastSeen)
+            .limit(4)
+            .executor(&db)
+            .get_results()
+            .unwrap();
+
+        assert_eq!(peers.len(), 4);
+        assert!(peers[..3].iter().all(|p| seen_peers.contains(&p.node_id)));
+        assert!(peers[0].last_seen() >= peers[1].last_seen());
+        assert!(peers[1].last_seen() >= peers[2].last_seen());
+        assert!(peers[3].last_seen().is_none());
+    }
 }
@@ -30,6 +30,7 @@ use crate::{
         PeerQuery,
     },
     protocol::ProtocolId,
+    transports::DialProxy,
     types::{CommsDatabase, CommsPublicKey},
 };
 use log::*;
@@ -505,6 +506,24 @@ where DS: KeyValueStore<PeerId, Peer>
             .map_err(PeerManagerError::DatabaseError)
     }
 
+    /// Sets the proxy to use when dialing the peer with the given NodeId
+    pub fn set_peer_dial_proxy(&self, node_id: &NodeId, dial_proxy: DialProxy) -> Result<(), PeerManagerError> {
+        let peer_key = *self
+            .node_id_index
+            .get(&node_id)
+            .ok_or(PeerManagerError::PeerNotFoundError)?;
+        let mut peer: Peer = self
+            .peer_db
+            .get(&peer_key)
+            .map_err(PeerManagerError::DatabaseError)?
+            .expect("node_id_index is out of sync with peer db");
+        peer.set_dial_proxy(dial_proxy);
+        self.peer_db
+            .insert(peer_key, peer)
+            .map_err(PeerManagerError::DatabaseError)?;
+        Ok(())
+    }
+
     /// This will store metadata inside of the metadata field in the peer provided by the nodeID.
     /// It will return None if the value was empty and the old value if the value was updated
     pub fn set_peer_metadata(
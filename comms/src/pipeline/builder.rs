@@ -20,6 +20,8 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[cfg(feature = "chaos")]
+use crate::pipeline::fault_injection::FaultInjector;
 use crate::{
     message::{InboundMessage, OutboundMessage},
     pipeline::SinkService,
@@ -40,6 +42,8 @@ pub struct Builder<TInSvc, TOutSvc, TOutReq> {
     inbound: Option<TInSvc>,
     outbound_rx: Option<mpsc::Receiver<TOutReq>>,
     outbound_pipeline_factory: Option<Box<dyn FnOnce(OutboundMessageSinkService) -> TOutSvc>>,
+    #[cfg(feature = "chaos")]
+    fault_injector: Option<FaultInjector>,
 }
 
 impl Builder<(), (), ()> {
@@ -50,6 +54,8 @@ impl Builder<(), (), ()> {
             inbound: None,
             outbound_rx: None,
             outbound_pipeline_factory: None,
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
         }
     }
 }
@@ -65,6 +71,13 @@ impl<TInSvc, TOutSvc, TOutReq> Builder<TInSvc, TOutSvc, TOutReq> {
         self
     }
 
+    /// Inject faults into all messages sent and received by the messaging protocol
+    #[cfg(feature = "chaos")]
+    pub fn with_fault_injection(mut self, injector: FaultInjector) -> Self {
+        self.fault_injector = Some(injector);
+        self
+    }
+
     pub fn with_outbound_pipeline<F, S, R>(self, receiver: mpsc::Receiver<R>, factory: F) -> Builder<TInSvc, S, R>
     where
         // Factory function takes in a SinkService and returns a new composed service
@@ -78,6 +91,8 @@ impl<TInSvc, TOutSvc, TOutReq> Builder<TInSvc, TOutSvc, TOutReq> {
             max_concurrent_inbound_tasks: self.max_concurrent_inbound_tasks,
             inbound: self.inbound,
             outbound_buffer_size: self.outbound_buffer_size,
+            #[cfg(feature = "chaos")]
+            fault_injector: self.fault_injector,
         }
     }
 
@@ -90,6 +105,8 @@ impl<TInSvc, TOutSvc, TOutReq> Builder<TInSvc, TOutSvc, TOutReq> {
             outbound_rx: self.outbound_rx,
             outbound_pipeline_factory: self.outbound_pipeline_factory,
             outbound_buffer_size: self.outbound_buffer_size,
+            #[cfg(feature = "chaos")]
+            fault_injector: self.fault_injector,
         }
     }
 }
@@ -129,6 +146,8 @@ where
             max_concurrent_inbound_tasks: self.max_concurrent_inbound_tasks,
             inbound,
             outbound,
+            #[cfg(feature = "chaos")]
+            fault_injector: self.fault_injector,
         })
     }
 
@@ -150,6 +169,8 @@ pub struct Config<TInSvc, TOutSvc, TOutReq> {
     pub max_concurrent_inbound_tasks: usize,
     pub inbound: TInSvc,
     pub outbound: OutboundPipelineConfig<mpsc::Receiver<TOutReq>, TOutSvc>,
+    #[cfg(feature = "chaos")]
+    pub fault_injector: Option<FaultInjector>,
 }
 
 #[derive(Debug, Error)]
@@ -0,0 +1,466 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Fault injection
+//!
+//! Test support for soak-testing protocols under adverse network conditions. Only available with the `chaos`
+//! feature.
+//!
+//! [FaultInjectionLayer] can be added to a pipeline (typically the inbound pipeline, where messages are `Clone`) to
+//! drop, duplicate and delay messages. Delaying a random subset of messages by a random amount reorders them.
+//! [FaultInjector::inject] applies the same faults to any future. RPC clients built using
+//! `RpcClientBuilder::with_fault_injection` apply it to every request, e.g. the wallet's RPC calls to its base node.
+//!
+//! Passing a [FaultInjector] to `pipeline::Builder::with_fault_injection` injects faults into all messages sent and
+//! received by the messaging protocol of that node.
+//!
+//! All decisions are made from a seeded RNG so that a failing run can be reproduced using the same seed. Each stream
+//! of messages or RPC session draws from its own RNG (see [FaultInjector::derive]) so that the faults applied to one
+//! stream do not depend on how it is scheduled relative to the others.
+
+use super::{PipelineError, SinkService};
+use futures::{channel::mpsc, future::BoxFuture, task::Context, Future, FutureExt, SinkExt, StreamExt};
+use log::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+        Mutex,
+    },
+    task::Poll,
+    time::Duration,
+};
+use tokio::{task, time};
+use tower::{layer::Layer, Service, ServiceExt};
+
+const LOG_TARGET: &str = "comms::pipeline::fault_injection";
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Describes which faults to inject and how often
+#[derive(Debug, Clone)]
+pub struct FaultConfig {
+    /// Seed for the RNG that decides which faults are injected
+    pub seed: u64,
+    /// Probability (0.0 to 1.0) that a message is dropped
+    pub drop_probability: f64,
+    /// Probability (0.0 to 1.0) that a message is delivered twice
+    pub duplicate_probability: f64,
+    /// Probability (0.0 to 1.0) that a message is delayed
+    pub delay_probability: f64,
+    /// The range that a delayed message's delay is chosen from
+    pub delay_range: (Duration, Duration),
+}
+
+impl FaultConfig {
+    /// A config that injects no faults
+    pub fn none(seed: u64) -> Self {
+        Self {
+            seed,
+            drop_probability: 0.0,
+            duplicate_probability: 0.0,
+            delay_probability: 0.0,
+            delay_range: (Duration::from_millis(0), Duration::from_millis(0)),
+        }
+    }
+
+    pub fn with_drop_probability(mut self, probability: f64) -> Self {
+        self.drop_probability = probability;
+        self
+    }
+
+    pub fn with_duplicate_probability(mut self, probability: f64) -> Self {
+        self.duplicate_probability = probability;
+        self
+    }
+
+    pub fn with_delay(mut self, probability: f64, min: Duration, max: Duration) -> Self {
+        self.delay_probability = probability;
+        self.delay_range = (min, max);
+        self
+    }
+}
+
+/// What should happen to a single message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Drop,
+    Deliver { copies: usize, delay: Option<Duration> },
+}
+
+/// Counts of the faults that have been injected
+#[derive(Debug, Default)]
+pub struct FaultStats {
+    pub dropped: AtomicUsize,
+    pub duplicated: AtomicUsize,
+    pub delayed: AtomicUsize,
+    pub delivered: AtomicUsize,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Fault injected: request dropped")]
+pub struct InjectedFault;
+
+/// Decides which faults to apply. Clones share the same RNG and stats.
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    config: Arc<FaultConfig>,
+    rng: Arc<Mutex<StdRng>>,
+    stats: Arc<FaultStats>,
+    derivations: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl FaultInjector {
+    pub fn new(config: FaultConfig) -> Self {
+        Self {
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(config.seed))),
+            config: Arc::new(config),
+            stats: Default::default(),
+            derivations: Default::default(),
+        }
+    }
+
+    pub fn stats(&self) -> &FaultStats {
+        &self.stats
+    }
+
+    /// Returns an injector with its own RNG for the stream named `label`. The RNG is seeded from the configured seed,
+    /// the label and the number of times that label has been derived before, so the n-th stream with a given label
+    /// always sees the same faults. The returned injector shares the stats of this injector.
+    pub fn derive(&self, label: &str) -> Self {
+        let n = {
+            let mut derivations = self.derivations.lock().expect("fault injector lock poisoned");
+            let n = derivations.entry(label.to_string()).or_insert(0);
+            *n += 1;
+            *n
+        };
+        let seed = label
+            .as_bytes()
+            .iter()
+            .chain(n.to_le_bytes().iter())
+            .fold(self.config.seed ^ FNV_OFFSET_BASIS, |hash, b| {
+                (hash ^ u64::from(*b)).wrapping_mul(FNV_PRIME)
+            });
+        Self {
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))),
+            config: self.config.clone(),
+            stats: self.stats.clone(),
+            derivations: self.derivations.clone(),
+        }
+    }
+
+    /// Decide the fault for the next message
+    pub fn next_fault(&self) -> Fault {
+        let mut rng = self.rng.lock().expect("fault injector lock poisoned");
+        if rng.gen_bool(self.config.drop_probability) {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return Fault::Drop;
+        }
+
+        let mut copies = 1;
+        if rng.gen_bool(self.config.duplicate_probability) {
+            self.stats.duplicated.fetch_add(1, Ordering::Relaxed);
+            copies = 2;
+        }
+
+        let mut delay = None;
+        if rng.gen_bool(self.config.delay_probability) {
+            let (min, max) = self.config.delay_range;
+            let millis = if max > min {
+                rng.gen_range(min.as_millis() as u64..=max.as_millis() as u64)
+            } else {
+                min.as_millis() as u64
+            };
+            self.stats.delayed.fetch_add(1, Ordering::Relaxed);
+            delay = Some(Duration::from_millis(millis));
+        }
+
+        self.stats.delivered.fetch_add(1, Ordering::Relaxed);
+        Fault::Deliver { copies, delay }
+    }
+
+    /// Applies drop and delay faults to a future, e.g. an RPC request. Duplication is not applicable to futures and
+    /// is ignored.
+    pub async fn inject<F: Future>(&self, fut: F) -> Result<F::Output, InjectedFault> {
+        match self.next_fault() {
+            Fault::Drop => Err(InjectedFault),
+            Fault::Deliver { delay, .. } => {
+                if let Some(delay) = delay {
+                    time::delay_for(delay).await;
+                }
+                Ok(fut.await)
+            },
+        }
+    }
+}
+
+/// Layer that injects faults into the messages passed to the inner service
+#[derive(Clone)]
+pub struct FaultInjectionLayer {
+    injector: FaultInjector,
+}
+
+impl FaultInjectionLayer {
+    pub fn new(injector: FaultInjector) -> Self {
+        Self { injector }
+    }
+}
+
+impl<S> Layer<S> for FaultInjectionLayer {
+    type Service = FaultInjectionService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        FaultInjectionService {
+            inner: service,
+            injector: self.injector.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct FaultInjectionService<S> {
+    inner: S,
+    injector: FaultInjector,
+}
+
+impl<S, T> Service<T> for FaultInjectionService<S>
+where
+    S: Service<T, Response = (), Error = PipelineError> + Clone + Send + 'static,
+    S::Future: Send,
+    T: Clone + Send + 'static,
+{
+    type Error = PipelineError;
+    type Future = BoxFuture<'static, Result<(), PipelineError>>;
+    type Response = ();
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Readiness of the inner service is checked when each (possibly delayed) message is sent
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, msg: T) -> Self::Future {
+        let (copies, delay) = match self.injector.next_fault() {
+            Fault::Drop => {
+                debug!(target: LOG_TARGET, "Dropping message");
+                return futures::future::ready(Ok(())).boxed();
+            },
+            Fault::Deliver { copies, delay } => (copies, delay),
+        };
+
+        let inner = self.inner.clone();
+        let deliver = async move {
+            if let Some(delay) = delay {
+                debug!(target: LOG_TARGET, "Delaying message by {:.2?}", delay);
+                time::delay_for(delay).await;
+            }
+            for _ in 0..copies {
+                let service = inner.clone();
+                let msg = msg.clone();
+                service.oneshot(msg).await?;
+            }
+            Ok(())
+        };
+
+        if delay.is_some() {
+            // Delayed messages must not hold up the messages behind them, otherwise they could not be reordered
+            task::spawn(deliver.map(|result: Result<(), PipelineError>| {
+                if let Err(err) = result {
+                    error!(target: LOG_TARGET, "Failed to deliver delayed message: {}", err);
+                }
+            }));
+            futures::future::ready(Ok(())).boxed()
+        } else {
+            deliver.boxed()
+        }
+    }
+}
+
+/// Forwards the messages received on `rx` through a [FaultInjectionLayer] and returns the receiver of the messages
+/// that were not dropped.
+pub(crate) fn inject_into_stream<T>(
+    injector: FaultInjector,
+    mut rx: mpsc::Receiver<T>,
+    buf_size: usize,
+) -> mpsc::Receiver<T>
+where
+    T: Clone + Send + 'static,
+{
+    let (tx, out_rx) = mpsc::channel(buf_size);
+    let mut service = FaultInjectionLayer::new(injector).layer(SinkService::new(tx));
+    task::spawn(async move {
+        while let Some(msg) = rx.next().await {
+            if let Err(err) = service.call(msg).await {
+                debug!(target: LOG_TARGET, "Fault injection stream closed: {}", err);
+                break;
+            }
+        }
+    });
+    out_rx
+}
+
+/// Like [inject_into_stream](fn.inject_into_stream.html) for messages that cannot be cloned, such as outbound
+/// messaging requests. These messages are dropped and delayed, but never duplicated. `on_drop` is called with each
+/// message that is dropped, e.g. to report it as sent as would happen if it were lost in transit.
+pub(crate) fn inject_into_unclonable_stream<T, F>(
+    injector: FaultInjector,
+    mut rx: mpsc::Receiver<T>,
+    buf_size: usize,
+    mut on_drop: F,
+) -> mpsc::Receiver<T>
+where
+    T: Send + 'static,
+    F: FnMut(T) + Send + 'static,
+{
+    let (mut tx, out_rx) = mpsc::channel(buf_size);
+    task::spawn(async move {
+        while let Some(msg) = rx.next().await {
+            match injector.next_fault() {
+                Fault::Drop => {
+                    debug!(target: LOG_TARGET, "Dropping message");
+                    on_drop(msg);
+                },
+                Fault::Deliver { delay: Some(delay), .. } => {
+                    debug!(target: LOG_TARGET, "Delaying message by {:.2?}", delay);
+                    let mut tx = tx.clone();
+                    task::spawn(async move {
+                        time::delay_for(delay).await;
+                        let _ = tx.send(msg).await;
+                    });
+                },
+                Fault::Deliver { delay: None, .. } => {
+                    if tx.send(msg).await.is_err() {
+                        break;
+                    }
+                },
+            }
+        }
+    });
+    out_rx
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn collecting_service() -> (SinkService<mpsc::UnboundedSender<u32>>, mpsc::UnboundedReceiver<u32>) {
+        let (tx, rx) = mpsc::unbounded();
+        (SinkService::new(tx), rx)
+    }
+
+    #[test]
+    fn it_is_deterministic_for_a_seed() {
+        let config = FaultConfig::none(123)
+            .with_drop_probability(0.3)
+            .with_duplicate_probability(0.3)
+            .with_delay(0.3, Duration::from_millis(1), Duration::from_millis(100));
+        let a = FaultInjector::new(config.clone());
+        let b = FaultInjector::new(config);
+        let faults_a = (0..100).map(|_| a.next_fault()).collect::<Vec<_>>();
+        let faults_b = (0..100).map(|_| b.next_fault()).collect::<Vec<_>>();
+        assert_eq!(faults_a, faults_b);
+    }
+
+    #[test]
+    fn it_derives_independent_deterministic_streams() {
+        let config = FaultConfig::none(123).with_delay(0.5, Duration::from_millis(1), Duration::from_millis(1000));
+        let a = FaultInjector::new(config.clone());
+        let b = FaultInjector::new(config);
+
+        // Draws from the parent and other streams do not affect a derived stream
+        let a1 = a.derive("stream");
+        let _ = b.next_fault();
+        let _ = b.derive("other").next_fault();
+        let b1 = b.derive("stream");
+        let faults_a1 = (0..100).map(|_| a1.next_fault()).collect::<Vec<_>>();
+        let faults_b1 = (0..100).map(|_| b1.next_fault()).collect::<Vec<_>>();
+        assert_eq!(faults_a1, faults_b1);
+
+        // The next stream with the same label sees different faults
+        let a2 = a.derive("stream");
+        let faults_a2 = (0..100).map(|_| a2.next_fault()).collect::<Vec<_>>();
+        assert_ne!(faults_a1, faults_a2);
+        assert_eq!(
+            a.stats().delayed.load(Ordering::Relaxed),
+            a1.stats().delayed.load(Ordering::Relaxed)
+        );
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_drops_and_duplicates_messages() {
+        let (service, mut rx) = collecting_service();
+        let injector = FaultInjector::new(FaultConfig::none(1).with_drop_probability(1.0));
+        let mut service = FaultInjectionLayer::new(injector.clone()).layer(service);
+        service.call(1).await.unwrap();
+        assert_eq!(injector.stats().dropped.load(Ordering::Relaxed), 1);
+        assert!(rx.try_next().is_err());
+
+        let (service, mut rx) = collecting_service();
+        let injector = FaultInjector::new(FaultConfig::none(1).with_duplicate_probability(1.0));
+        let mut service = FaultInjectionLayer::new(injector).layer(service);
+        service.call(2).await.unwrap();
+        assert_eq!(rx.next().await.unwrap(), 2);
+        assert_eq!(rx.next().await.unwrap(), 2);
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_delays_messages() {
+        let (service, mut rx) = collecting_service();
+        let injector = FaultInjector::new(FaultConfig::none(1).with_delay(
+            1.0,
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+        ));
+        let mut service = FaultInjectionLayer::new(injector.clone()).layer(service);
+        service.call(3).await.unwrap();
+        assert!(rx.try_next().is_err());
+        assert_eq!(rx.next().await.unwrap(), 3);
+        assert_eq!(injector.stats().delayed.load(Ordering::Relaxed), 1);
+
+        let result = injector.inject(async { 4 }).await.unwrap();
+        assert_eq!(result, 4);
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_injects_faults_into_streams() {
+        let (mut tx, rx) = mpsc::channel(10);
+        let injector = FaultInjector::new(FaultConfig::none(1).with_duplicate_probability(1.0));
+        let mut rx = inject_into_stream(injector, rx, 10);
+        tx.send(5u32).await.unwrap();
+        assert_eq!(rx.next().await.unwrap(), 5);
+        assert_eq!(rx.next().await.unwrap(), 5);
+
+        let (mut tx, rx) = mpsc::channel(10);
+        let injector = FaultInjector::new(FaultConfig::none(1).with_drop_probability(1.0));
+        let (dropped_tx, mut dropped_rx) = mpsc::unbounded();
+        let mut rx = inject_into_unclonable_stream(injector.clone(), rx, 10, move |msg| {
+            dropped_tx.unbounded_send(msg).unwrap();
+        });
+        tx.send(6u32).await.unwrap();
+        drop(tx);
+        assert!(rx.next().await.is_none());
+        assert_eq!(dropped_rx.next().await.unwrap(), 6);
+        assert_eq!(injector.stats().dropped.load(Ordering::Relaxed), 1);
+    }
+}
@@ -47,4 +47,7 @@ pub(crate) use outbound::Outbound;
 mod translate_sink;
 pub use translate_sink::TranslateSink;
 
+#[cfg(feature = "chaos")]
+pub mod fault_injection;
+
 pub type PipelineError = anyhow::Error;
@@ -38,6 +38,9 @@ use tokio_util::codec::{Framed, LengthDelimitedCodec};
 pub static IDENTITY_PROTOCOL: ProtocolId = ProtocolId::from_static(b"t/identity/1.0");
 const LOG_TARGET: &str = "comms::protocol::identity";
 
+/// The maximum size of a peer identity message frame
+pub const MAX_IDENTITY_PROTOCOL_MSG_SIZE: usize = 4 * 1024;
+
 pub async fn identity_exchange<'p, TSocket, P>(
     node_identity: &NodeIdentity,
     direction: ConnectionDirection,
@@ -77,7 +80,12 @@ where
     debug_assert_eq!(proto, IDENTITY_PROTOCOL);
 
     // Create length-delimited frame codec
-    let framed = Framed::new(IoCompat::new(socket), LengthDelimitedCodec::new());
+    let framed = Framed::new(
+        IoCompat::new(socket),
+        LengthDelimitedCodec::builder()
+            .max_frame_length(MAX_IDENTITY_PROTOCOL_MSG_SIZE)
+            .new_codec(),
+    );
     let (mut sink, mut stream) = framed.split();
 
     let supported_protocols = our_supported_protocols.into_iter().map(|p| p.to_vec()).collect();
@@ -90,6 +98,7 @@ where
         major: network_info.major_version,
         minor: network_info.minor_version,
         user_agent: network_info.user_agent,
+        identity_signature: Some((&node_identity.identity_signature()).into()),
     }
     .to_encoded_bytes();
 
@@ -97,9 +106,15 @@ where
     sink.close().await?;
 
     // Receive the connecting nodes identity
-    let msg_bytes = time::timeout(Duration::from_secs(10), stream.next())
-        .await?
-        .ok_or(IdentityProtocolError::PeerUnexpectedCloseConnection)??;
+    let msg_bytes = match time::timeout(Duration::from_secs(10), stream.next()).await? {
+        Some(Ok(msg_bytes)) => msg_bytes,
+        // The length-delimited codec reports frames exceeding the maximum frame length as invalid data
+        Some(Err(err)) if err.kind() == io::ErrorKind::InvalidData => {
+            return Err(IdentityProtocolError::FrameTooLarge(MAX_IDENTITY_PROTOCOL_MSG_SIZE))
+        },
+        Some(Err(err)) => return Err(err.into()),
+        None => return Err(IdentityProtocolError::PeerUnexpectedCloseConnection),
+    };
     let identity_msg = PeerIdentityMsg::decode(msg_bytes)?;
 
     if identity_msg.major != network_info.major_version {
@@ -122,6 +137,10 @@ pub enum IdentityProtocolError {
     IoError(String),
     #[error("ProtocolError: {0}")]
     ProtocolError(String),
+    #[error("Peer did not negotiate the identity protocol: {0}")]
+    NegotiationFailed(String),
+    #[error("Peer sent an identity message larger than the maximum of {0} bytes")]
+    FrameTooLarge(usize),
     #[error("ProtobufDecodeError: {0}")]
     ProtobufDecodeError(String),
     #[error("Failed to encode protobuf message")]
@@ -142,7 +161,14 @@ impl From<time::Elapsed> for IdentityProtocolError {
 
 impl From<ProtocolError> for IdentityProtocolError {
     fn from(err: ProtocolError) -> Self {
-        IdentityProtocolError::ProtocolError(err.to_string())
+        match err {
+            ProtocolError::ProtocolInboundNegotiationFailed |
+            ProtocolError::ProtocolOptimisticNegotiationFailed |
+            ProtocolError::ProtocolOutboundNegotiationFailed(_) => {
+                IdentityProtocolError::NegotiationFailed(err.to_string())
+            },
+            err => IdentityProtocolError::ProtocolError(err.to_string()),
+        }
     }
 }
 
@@ -162,13 +188,14 @@ impl From<prost::DecodeError> for IdentityProtocolError {
 mod test {
     use crate::{
         connection_manager::ConnectionDirection,
-        peer_manager::PeerFeatures,
+        peer_manager::{IdentitySignature, PeerFeatures},
         protocol::{IdentityProtocolError, NodeNetworkInfo},
         runtime,
         test_utils::node_identity::build_node_identity,
         transports::{MemoryTransport, Transport},
     };
     use futures::{future, StreamExt};
+    use std::convert::TryFrom;
 
     #[runtime::test_basic]
     async fn identity_exchange() {
@@ -217,6 +244,19 @@ mod test {
 
         assert_eq!(identity2.features, node_identity2.features().bits());
         assert_eq!(identity2.addresses, vec![node_identity2.public_address().to_vec()]);
+
+        let signature1 = IdentitySignature::try_from(identity1.identity_signature.unwrap()).unwrap();
+        assert!(
+            signature1.is_valid(node_identity1.public_key(), node_identity1.features(), &[
+                node_identity1.public_address()
+            ])
+        );
+        let signature2 = IdentitySignature::try_from(identity2.identity_signature.unwrap()).unwrap();
+        assert!(
+            signature2.is_valid(node_identity2.public_key(), node_identity2.features(), &[
+                node_identity2.public_address()
+            ])
+        );
     }
 
     #[runtime::test_basic]
@@ -34,6 +34,8 @@ use crate::{
     runtime,
     runtime::task,
 };
+#[cfg(feature = "chaos")]
+use crate::{pipeline::fault_injection, protocol::messaging::MessagingRequest};
 use futures::channel::mpsc;
 use std::fmt;
 use tower::Service;
@@ -77,6 +79,24 @@ where
 
         let (messaging_request_tx, messaging_request_rx) = mpsc::channel(MESSAGING_REQUEST_BUFFER_SIZE);
         let (inbound_message_tx, inbound_message_rx) = mpsc::channel(INBOUND_MESSAGE_BUFFER_SIZE);
+        #[cfg(feature = "chaos")]
+        let (messaging_request_rx, inbound_message_rx) = match self.pipeline.fault_injector.clone() {
+            Some(injector) => (
+                fault_injection::inject_into_unclonable_stream(
+                    injector.derive("messaging-outbound"),
+                    messaging_request_rx,
+                    MESSAGING_REQUEST_BUFFER_SIZE,
+                    // A message lost in transit is reported as sent
+                    |MessagingRequest::SendMessage(mut msg)| msg.reply_success(),
+                ),
+                fault_injection::inject_into_stream(
+                    injector.derive("messaging-inbound"),
+                    inbound_message_rx,
+                    INBOUND_MESSAGE_BUFFER_SIZE,
+                ),
+            ),
+            None => (messaging_request_rx, inbound_message_rx),
+        };
 
         let messaging = MessagingProtocol::new(
             Default::default(),
@@ -31,6 +31,7 @@ mod inbound;
 mod outbound;
 
 mod protocol;
+pub(crate) use protocol::MESSAGING_PROTOCOL;
 pub use protocol::{
     MessagingEvent,
     MessagingEventReceiver,
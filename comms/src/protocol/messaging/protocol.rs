@@ -34,6 +34,7 @@ use crate::{
         ProtocolNotification,
     },
     runtime::task,
+    utils::log_fields::LogFieldExt,
 };
 use bytes::Bytes;
 use futures::{channel::mpsc, stream::Fuse, AsyncRead, AsyncWrite, SinkExt, StreamExt};
@@ -50,7 +51,7 @@ use tokio::sync::broadcast;
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
 const LOG_TARGET: &str = "comms::protocol::messaging";
-pub(super) static MESSAGING_PROTOCOL: Bytes = Bytes::from_static(b"t/msg/0.1");
+pub(crate) static MESSAGING_PROTOCOL: Bytes = Bytes::from_static(b"t/msg/0.1");
 const INTERNAL_MESSAGING_EVENT_CHANNEL_SIZE: usize = 150;
 
 /// The maximum amount of inbound messages to accept within the `RATE_LIMIT_RESTOCK_INTERVAL` window
@@ -294,15 +295,17 @@ impl MessagingProtocol {
         inactivity_timeout: Option<Duration>,
     ) -> mpsc::UnboundedSender<OutboundMessage> {
         let (msg_tx, msg_rx) = mpsc::unbounded();
+        let peer_id = peer_node_id.to_string();
         let outbound_messaging =
             OutboundMessaging::new(connectivity, events_tx, msg_rx, peer_node_id, inactivity_timeout);
-        task::spawn(outbound_messaging.run());
+        task::spawn(outbound_messaging.run().with_log_field("peer_id", peer_id));
         msg_tx
     }
 
     fn spawn_inbound_handler(&mut self, peer: NodeId, substream: Substream) {
         let messaging_events_tx = self.messaging_events_tx.clone();
         let inbound_message_tx = self.inbound_message_tx.clone();
+        let peer_id = peer.to_string();
         let inbound_messaging = InboundMessaging::new(
             peer,
             inbound_message_tx,
@@ -311,7 +314,7 @@ impl MessagingProtocol {
             RATE_LIMIT_RESTOCK_INTERVAL,
             self.config.inactivity_timeout,
         );
-        task::spawn(inbound_messaging.run(substream));
+        task::spawn(inbound_messaging.run(substream).with_log_field("peer_id", peer_id));
     }
 
     async fn handle_protocol_notification(&mut self, notification: ProtocolNotification<Substream>) {
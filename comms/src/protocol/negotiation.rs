@@ -61,7 +61,8 @@ where TSocket: AsyncRead + AsyncWrite + Unpin
     }
 
     /// Negotiate a protocol to speak. Since this node is initiating this interation, send each protocol this node
-    /// wishes to speak until the destination node agrees.
+    /// wishes to speak until the destination node agrees. Protocols are tried in the given order, so when negotiating
+    /// between versions of a protocol, the newest version should come first.
     pub async fn negotiate_protocol_outbound(
         &mut self,
         selected_protocols: &[ProtocolId],
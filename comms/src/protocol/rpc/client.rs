@@ -21,13 +21,16 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::message::RpcMethod;
+#[cfg(feature = "chaos")]
+use crate::pipeline::fault_injection::FaultInjector;
 use crate::{
     framing::CanonicalFraming,
     message::MessageExt,
     proto,
     protocol::rpc::{
         body::ClientStreaming,
-        message::BaseRequest,
+        handshake::RPC_FLOW_CONTROL_VERSION,
+        message::{BaseRequest, RpcMessageFlags},
         Handshake,
         NamedProtocolService,
         Response,
@@ -63,6 +66,8 @@ const LOG_TARGET: &str = "comms::rpc::client";
 #[derive(Clone)]
 pub struct RpcClient {
     connector: ClientConnector,
+    #[cfg(feature = "chaos")]
+    fault_injector: Option<FaultInjector>,
 }
 
 impl RpcClient {
@@ -76,12 +81,18 @@ impl RpcClient {
     {
         let (request_tx, request_rx) = mpsc::channel(1);
         let connector = ClientConnector { inner: request_tx };
+        #[cfg(feature = "chaos")]
+        let fault_injector = config.fault_injector.clone();
         let (ready_tx, ready_rx) = oneshot::channel();
         task::spawn(RpcClientWorker::new(config, request_rx, framed, ready_tx).run());
         ready_rx
             .await
             .expect("ready_rx oneshot is never dropped without a reply")?;
-        Ok(Self { connector })
+        Ok(Self {
+            connector,
+            #[cfg(feature = "chaos")]
+            fault_injector,
+        })
     }
 
     /// Perform a single request and single response
@@ -129,6 +140,24 @@ impl RpcClient {
     async fn call_inner(
         &mut self,
         request: BaseRequest<Bytes>,
+    ) -> Result<mpsc::Receiver<Result<Response<Bytes>, RpcStatus>>, RpcError> {
+        #[cfg(feature = "chaos")]
+        {
+            if let Some(injector) = self.fault_injector.clone() {
+                // A dropped request looks like a request that the server never responded to
+                return injector
+                    .inject(self.call_connector(request))
+                    .await
+                    .map_err(|err| RpcError::RequestFailed(RpcStatus::timed_out(err)))?;
+            }
+        }
+
+        self.call_connector(request).await
+    }
+
+    async fn call_connector(
+        &mut self,
+        request: BaseRequest<Bytes>,
     ) -> Result<mpsc::Receiver<Result<Response<Bytes>, RpcStatus>>, RpcError> {
         let svc = self.connector.ready_and().await?;
         let resp = svc.call(request).await?;
@@ -193,6 +222,15 @@ where TClient: From<RpcClient> + NamedProtocolService
         self
     }
 
+    /// Drop and delay the requests made by the client. Each client session draws its faults from an injector derived
+    /// from `injector` for the protocol of the client.
+    #[cfg(feature = "chaos")]
+    pub fn with_fault_injection(mut self, injector: FaultInjector) -> Self {
+        let label = String::from_utf8_lossy(TClient::PROTOCOL_NAME);
+        self.config.fault_injector = Some(injector.derive(&label));
+        self
+    }
+
     /// Negotiates and establishes a session to the peer's RPC service
     pub async fn connect<TSubstream>(self, framed: CanonicalFraming<TSubstream>) -> Result<TClient, RpcError>
     where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + 'static {
@@ -205,6 +243,8 @@ pub struct RpcClientConfig {
     pub deadline: Option<Duration>,
     pub deadline_grace_period: Duration,
     pub handshake_timeout: Duration,
+    #[cfg(feature = "chaos")]
+    pub fault_injector: Option<FaultInjector>,
 }
 
 impl RpcClientConfig {
@@ -225,6 +265,8 @@ impl Default for RpcClientConfig {
             deadline: Some(Duration::from_secs(30)),
             deadline_grace_period: Duration::from_secs(10),
             handshake_timeout: Duration::from_secs(30),
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
         }
     }
 }
@@ -289,6 +331,7 @@ pub struct RpcClientWorker<TSubstream> {
     request_id: u16,
     ready_tx: Option<oneshot::Sender<Result<(), RpcError>>>,
     latency: Option<Duration>,
+    protocol_version: u32,
 }
 
 impl<TSubstream> RpcClientWorker<TSubstream>
@@ -307,6 +350,7 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send
             request_id: 0,
             ready_tx: Some(ready_tx),
             latency: None,
+            protocol_version: 0,
         }
     }
 
@@ -315,13 +359,14 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send
         let start = Instant::now();
         let mut handshake = Handshake::new(&mut self.framed).with_timeout(self.config.handshake_timeout());
         match handshake.perform_client_handshake().await {
-            Ok(_) => {
+            Ok(version) => {
                 let latency = start.elapsed();
                 debug!(
                     target: LOG_TARGET,
-                    "RPC Session negotiation completed. Latency: {:.0?}", latency
+                    "RPC Session negotiation completed (v{}). Latency: {:.0?}", version, latency
                 );
                 self.latency = Some(latency);
+                self.protocol_version = version;
                 if let Some(r) = self.ready_tx.take() {
                     let _ = r.send(Ok(()));
                 }
@@ -438,6 +483,9 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send
                         response_tx.close_channel();
                         break;
                     }
+                    // The message has been handed to the consumer (or the consumer has gone away), so let the server
+                    // know that it may send another
+                    self.send_ack(request_id).await?;
                 },
                 Err(err) => {
                     debug!(target: LOG_TARGET, "Remote service returned error: {}", err);
@@ -453,6 +501,19 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send
         Ok(())
     }
 
+    async fn send_ack(&mut self, request_id: u16) -> Result<(), RpcError> {
+        if self.protocol_version < RPC_FLOW_CONTROL_VERSION {
+            return Ok(());
+        }
+        let ack = proto::rpc::RpcRequest {
+            request_id: request_id as u32,
+            flags: RpcMessageFlags::ACK.bits().into(),
+            ..Default::default()
+        };
+        self.framed.send(ack.to_encoded_bytes().into()).await?;
+        Ok(())
+    }
+
     fn next_request_id(&mut self) -> u16 {
         let next_id = self.request_id;
         // request_id is allowed to wrap around back to 0
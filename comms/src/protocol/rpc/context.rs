@@ -28,7 +28,7 @@ use crate::{
     PeerManager,
 };
 use async_trait::async_trait;
-use std::{fmt, sync::Arc};
+use std::{fmt, sync::Arc, time::Duration};
 
 /// Abstraction of the comms backend calls provided to RPC services.
 #[async_trait]
@@ -36,6 +36,7 @@ pub trait RpcCommsProvider: Send + Sync {
     async fn fetch_peer(&self, node_id: &NodeId) -> Result<Peer, RpcError>;
     async fn dial_peer(&mut self, node_id: &NodeId) -> Result<PeerConnection, RpcError>;
     async fn select_connections(&mut self, selection: ConnectivitySelection) -> Result<Vec<PeerConnection>, RpcError>;
+    async fn ban_peer(&mut self, node_id: &NodeId, duration: Duration, reason: String) -> Result<(), RpcError>;
 }
 
 /// Provides access to the `PeerManager` and connectivity manager.
@@ -74,6 +75,13 @@ impl RpcCommsProvider for RpcCommsBackend {
             .await
             .map_err(Into::into)
     }
+
+    async fn ban_peer(&mut self, node_id: &NodeId, duration: Duration, reason: String) -> Result<(), RpcError> {
+        self.connectivity
+            .ban_peer_until(node_id.clone(), duration, reason)
+            .await
+            .map_err(Into::into)
+    }
 }
 
 pub struct RequestContext {
@@ -30,9 +30,12 @@ use tokio::time;
 
 const LOG_TARGET: &str = "comms::rpc::handshake";
 
-/// Supported RPC protocol versions.
-/// Currently only v0 is supported
-pub(super) const SUPPORTED_RPC_VERSIONS: &[u32] = &[0];
+/// Supported RPC protocol versions, in order of preference.
+/// v1 adds acknowledgement-based flow control for streaming responses.
+pub(super) const SUPPORTED_RPC_VERSIONS: &[u32] = &[1, 0];
+
+/// The first RPC protocol version in which clients acknowledge streamed response messages
+pub(super) const RPC_FLOW_CONTROL_VERSION: u32 = 1;
 
 #[derive(Debug, thiserror::Error)]
 pub enum RpcHandshakeError {
@@ -111,8 +114,8 @@ where T: AsyncRead + AsyncWrite + Unpin
         Ok(())
     }
 
-    /// Client-side handshake protocol
-    pub async fn perform_client_handshake(&mut self) -> Result<(), RpcHandshakeError> {
+    /// Client-side handshake protocol. Returns the protocol version accepted by the server.
+    pub async fn perform_client_handshake(&mut self) -> Result<u32, RpcHandshakeError> {
         let msg = proto::rpc::RpcSession {
             supported_versions: SUPPORTED_RPC_VERSIONS.to_vec(),
         };
@@ -130,7 +133,7 @@ where T: AsyncRead + AsyncWrite + Unpin
                 let msg = proto::rpc::RpcSessionReply::decode(&mut msg.freeze())?;
                 let version = msg.result()?;
                 debug!(target: LOG_TARGET, "Server accepted version {}", version);
-                Ok(())
+                Ok(version)
             },
             Ok(Some(Err(err))) => Err(err.into()),
             Ok(None) => Err(RpcHandshakeError::ServerClosedRequest),
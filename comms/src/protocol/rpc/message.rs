@@ -173,7 +173,7 @@ impl<T> Response<T> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RpcMethod(u32);
 
 impl RpcMethod {
@@ -198,12 +198,18 @@ impl Into<u32> for RpcMethod {
 bitflags! {
     pub struct RpcMessageFlags: u8 {
         const FIN = 0x01;
+        /// Sent by the client to acknowledge a streamed response message (RPC v1 and above)
+        const ACK = 0x02;
     }
 }
 impl RpcMessageFlags {
     pub fn is_fin(&self) -> bool {
         self.contains(Self::FIN)
     }
+
+    pub fn is_ack(&self) -> bool {
+        self.contains(Self::ACK)
+    }
 }
 
 impl Default for RpcMessageFlags {
@@ -32,7 +32,16 @@ pub use body::{Body, ClientStreaming, IntoBody, Streaming};
 mod context;
 
 mod server;
-pub use server::{mock, NamedProtocolService, RpcServer, RpcServerError, RpcServerHandle};
+pub use server::{
+    mock,
+    NamedProtocolService,
+    RpcMethodStats,
+    RpcQosConfig,
+    RpcServer,
+    RpcServerError,
+    RpcServerHandle,
+    SlowConsumerPolicy,
+};
 
 mod client;
 pub use client::{RpcClient, RpcClientBuilder, RpcClientConfig};
@@ -40,7 +49,7 @@ pub use client::{RpcClient, RpcClientBuilder, RpcClientConfig};
 mod either;
 
 mod message;
-pub use message::{Request, Response};
+pub use message::{Request, Response, RpcMethod};
 
 mod error;
 pub use error::RpcError;
@@ -23,7 +23,7 @@
 use crate::protocol::rpc::handshake::RpcHandshakeError;
 use futures::channel::oneshot;
 use prost::DecodeError;
-use std::io;
+use std::{io, time::Duration};
 
 #[derive(Debug, thiserror::Error)]
 pub enum RpcServerError {
@@ -39,6 +39,14 @@ pub enum RpcServerError {
     HandshakeError(#[from] RpcHandshakeError),
     #[error("Service not found for protocol `{0}`")]
     ProtocolServiceNotFound(String),
+    #[error("Client did not acknowledge streamed messages within {0:.0?}")]
+    SlowConsumer(Duration),
+    #[error("Client sent an unexpected message while a streaming response was in progress")]
+    UnexpectedIncomingMessage,
+    #[error("The session was closed while waiting for the client")]
+    SessionClosed,
+    #[error("Peer was banned for exceeding its request quota")]
+    PeerBanned,
 }
 
 impl From<oneshot::Canceled> for RpcServerError {
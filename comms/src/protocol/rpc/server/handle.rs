@@ -20,15 +20,18 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::RpcServerError;
+use super::{RpcMethodStats, RpcServerError};
+use crate::protocol::{rpc::message::RpcMethod, ProtocolId};
 use futures::{
     channel::{mpsc, oneshot},
     SinkExt,
 };
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub enum RpcServerRequest {
     GetNumActiveSessions(oneshot::Sender<usize>),
+    GetMethodStats(oneshot::Sender<HashMap<(ProtocolId, RpcMethod), RpcMethodStats>>),
 }
 
 #[derive(Debug, Clone)]
@@ -49,4 +52,16 @@ impl RpcServerHandle {
             .map_err(|_| RpcServerError::RequestCanceled)?;
         resp.await.map_err(Into::into)
     }
+
+    /// Returns the statistics for each RPC method that has been called, keyed by protocol and method
+    pub async fn get_method_stats(
+        &mut self,
+    ) -> Result<HashMap<(ProtocolId, RpcMethod), RpcMethodStats>, RpcServerError> {
+        let (req, resp) = oneshot::channel();
+        self.sender
+            .send(RpcServerRequest::GetMethodStats(req))
+            .await
+            .map_err(|_| RpcServerError::RequestCanceled)?;
+        resp.await.map_err(Into::into)
+    }
 }
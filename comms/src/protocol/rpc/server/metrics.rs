@@ -0,0 +1,79 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::protocol::{rpc::RpcMethod, ProtocolId};
+use lazy_static::lazy_static;
+use tari_metrics::{Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec};
+
+lazy_static! {
+    static ref REQUEST_SECONDS: HistogramVec = tari_metrics::register_histogram_vec(
+        "comms_rpc_request_seconds",
+        "The time taken to handle an RPC request, including streaming the response",
+        &["protocol", "method"],
+        None,
+    )
+    .unwrap();
+    static ref BYTES_RECEIVED: IntCounterVec = tari_metrics::register_int_counter_vec(
+        "comms_rpc_bytes_received",
+        "The number of request bytes received by the RPC server",
+        &["protocol"],
+    )
+    .unwrap();
+    static ref BYTES_SENT: IntCounterVec = tari_metrics::register_int_counter_vec(
+        "comms_rpc_bytes_sent",
+        "The number of response bytes sent by the RPC server",
+        &["protocol"],
+    )
+    .unwrap();
+    static ref QOS_QUEUE_DEPTH: IntGaugeVec = tari_metrics::register_int_gauge_vec(
+        "comms_rpc_qos_queue_depth",
+        "The number of RPC requests waiting for the server to have capacity",
+        &["priority"],
+    )
+    .unwrap();
+    static ref QOS_REJECTIONS: IntCounterVec = tari_metrics::register_int_counter_vec(
+        "comms_rpc_qos_rejections",
+        "The number of RPC requests rejected because the server was busy or the peer exceeded its quota",
+        &["protocol", "reason"],
+    )
+    .unwrap();
+}
+
+pub fn request_seconds(protocol: &ProtocolId, method: RpcMethod) -> Histogram {
+    REQUEST_SECONDS.with_label_values(&[&String::from_utf8_lossy(protocol), &method.id().to_string()])
+}
+
+pub fn bytes_received(protocol: &ProtocolId) -> IntCounter {
+    BYTES_RECEIVED.with_label_values(&[&String::from_utf8_lossy(protocol)])
+}
+
+pub fn bytes_sent(protocol: &ProtocolId) -> IntCounter {
+    BYTES_SENT.with_label_values(&[&String::from_utf8_lossy(protocol)])
+}
+
+pub fn qos_queue_depth(priority: &str) -> IntGauge {
+    QOS_QUEUE_DEPTH.with_label_values(&[priority])
+}
+
+pub fn qos_rejections(protocol: &ProtocolId, reason: &str) -> IntCounter {
+    QOS_REJECTIONS.with_label_values(&[&String::from_utf8_lossy(protocol), reason])
+}
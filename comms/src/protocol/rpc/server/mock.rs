@@ -49,7 +49,7 @@ use crate::{
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::{channel::mpsc, stream, SinkExt};
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tokio::{sync::RwLock, task};
 use tower::Service;
 use tower_make::MakeService;
@@ -178,6 +178,10 @@ impl RpcCommsProvider for MockCommsProvider {
     async fn select_connections(&mut self, _: ConnectivitySelection) -> Result<Vec<PeerConnection>, RpcError> {
         unimplemented!()
     }
+
+    async fn ban_peer(&mut self, _: &NodeId, _: Duration, _: String) -> Result<(), RpcError> {
+        unimplemented!()
+    }
 }
 
 pub struct MockRpcServer<TSvc, TSubstream> {
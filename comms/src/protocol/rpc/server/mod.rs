@@ -24,19 +24,30 @@ mod error;
 pub use error::RpcServerError;
 
 mod handle;
+#[cfg(feature = "metrics")]
+mod metrics;
 pub use handle::RpcServerHandle;
 use handle::RpcServerRequest;
 
 pub mod mock;
 
+mod qos;
+pub use qos::RpcQosConfig;
+use qos::{RpcQos, RpcQosRejection};
+
 mod router;
 use router::Router;
 
+mod stats;
+pub use stats::RpcMethodStats;
+use stats::RpcServerStats;
+
 use super::{
     body::Body,
     context::{RequestContext, RpcCommsProvider},
     error::HandshakeRejectReason,
-    message::{Request, Response, RpcMessageFlags},
+    handshake::RPC_FLOW_CONTROL_VERSION,
+    message::{Request, Response, RpcMessageFlags, RpcMethod},
     not_found::ProtocolServiceNotFound,
     status::RpcStatus,
     Handshake,
@@ -53,7 +64,8 @@ use crate::{
     protocol::{ProtocolEvent, ProtocolId, ProtocolNotification, ProtocolNotificationRx},
     Bytes,
 };
-use futures::{channel::mpsc, AsyncRead, AsyncWrite, Sink, SinkExt, StreamExt};
+use bytes::BytesMut;
+use futures::{channel::mpsc, AsyncRead, AsyncWrite, Sink, SinkExt, Stream, StreamExt};
 use log::*;
 use prost::Message;
 use std::{
@@ -145,11 +157,25 @@ impl Default for RpcServer {
     }
 }
 
+/// What the server does when a client does not keep up with a streaming response. A response message that cannot be
+/// written to the substream in time always closes the session, because a partially written frame cannot be recovered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowConsumerPolicy {
+    /// Keep waiting for the client until the request deadline expires, then abort the response. The session remains
+    /// open.
+    AbortAfterDeadline,
+    /// Close the session if the client has not made progress within the given time
+    DisconnectAfter(Duration),
+}
+
 #[derive(Clone)]
 pub struct RpcServerBuilder {
     maximum_simultaneous_sessions: Option<usize>,
     minimum_client_deadline: Duration,
     handshake_timeout: Duration,
+    maximum_in_flight_messages: usize,
+    slow_consumer_policy: SlowConsumerPolicy,
+    qos_config: RpcQosConfig,
     shutdown_signal: OptionalShutdownSignal,
 }
 
@@ -173,6 +199,25 @@ impl RpcServerBuilder {
         self
     }
 
+    /// The maximum number of streamed response messages that may be sent to a client before the client acknowledges
+    /// them. Only applies to clients that support RPC flow control (v1 and above).
+    pub fn with_maximum_in_flight_messages(mut self, limit: usize) -> Self {
+        self.maximum_in_flight_messages = limit.max(1);
+        self
+    }
+
+    /// Sets what happens when a client does not keep up with a streaming response
+    pub fn with_slow_consumer_policy(mut self, policy: SlowConsumerPolicy) -> Self {
+        self.slow_consumer_policy = policy;
+        self
+    }
+
+    /// Sets the request prioritisation, quota and throttling settings. By default requests are not limited.
+    pub fn with_qos_config(mut self, config: RpcQosConfig) -> Self {
+        self.qos_config = config;
+        self
+    }
+
     pub fn with_shutdown_signal(mut self, shutdown_signal: ShutdownSignal) -> Self {
         self.shutdown_signal = Some(shutdown_signal).into();
         self
@@ -194,6 +239,9 @@ impl Default for RpcServerBuilder {
             maximum_simultaneous_sessions: Some(1000),
             minimum_client_deadline: Duration::from_secs(1),
             handshake_timeout: Duration::from_secs(15),
+            maximum_in_flight_messages: 32,
+            slow_consumer_policy: SlowConsumerPolicy::DisconnectAfter(Duration::from_secs(30)),
+            qos_config: Default::default(),
             shutdown_signal: Default::default(),
         }
     }
@@ -206,6 +254,8 @@ pub(super) struct PeerRpcServer<TSvc, TSubstream, TCommsProvider> {
     protocol_notifications: Option<ProtocolNotificationRx<TSubstream>>,
     comms_provider: TCommsProvider,
     request_rx: Option<mpsc::Receiver<RpcServerRequest>>,
+    stats: RpcServerStats,
+    qos: RpcQos,
 }
 
 impl<TSvc, TSubstream, TCommsProvider> PeerRpcServer<TSvc, TSubstream, TCommsProvider>
@@ -236,11 +286,13 @@ where
                 Some(num) => BoundedExecutor::from_current(num),
                 None => BoundedExecutor::allow_maximum(),
             },
+            qos: RpcQos::new(config.qos_config.clone()),
             config,
             service,
             protocol_notifications: Some(protocol_notifications),
             comms_provider,
             request_rx: Some(request_rx),
+            stats: Default::default(),
         }
     }
 
@@ -292,6 +344,9 @@ where
                 let num_active = max_sessions.saturating_sub(self.executor.num_available());
                 let _ = reply.send(num_active);
             },
+            GetMethodStats(reply) => {
+                let _ = reply.send(self.stats.snapshot());
+            },
         }
     }
 
@@ -366,11 +421,15 @@ where
 
         let service = ActivePeerRpcService {
             config: self.config.clone(),
+            protocol,
+            protocol_version: version,
             node_id: node_id.clone(),
             framed: Some(framed),
             service,
             comms_provider: self.comms_provider.clone(),
             shutdown_signal: self.config.shutdown_signal.clone(),
+            stats: self.stats.clone(),
+            qos: self.qos.clone(),
         };
 
         self.executor
@@ -383,11 +442,15 @@ where
 
 struct ActivePeerRpcService<TSvc, TSubstream, TCommsProvider> {
     config: RpcServerBuilder,
+    protocol: ProtocolId,
+    protocol_version: u32,
     node_id: NodeId,
     service: TSvc,
     framed: Option<CanonicalFraming<TSubstream>>,
     comms_provider: TCommsProvider,
     shutdown_signal: OptionalShutdownSignal,
+    stats: RpcServerStats,
+    qos: RpcQos,
 }
 
 impl<TSvc, TSubstream, TCommsProvider> ActivePeerRpcService<TSvc, TSubstream, TCommsProvider>
@@ -413,7 +476,7 @@ where
 
         while let Some(result) = stream.next().await {
             let start = Instant::now();
-            if let Err(err) = self.handle(&mut sink, result?.freeze()).await {
+            if let Err(err) = self.handle(&mut sink, &mut stream, result?.freeze()).await {
                 sink.close().await?;
                 return Err(err);
             }
@@ -424,16 +487,67 @@ where
         Ok(())
     }
 
+    #[cfg(feature = "metrics")]
+    fn record_bytes_received(&self, num_bytes: usize) {
+        metrics::bytes_received(&self.protocol).inc_by(num_bytes as u64);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn record_bytes_received(&self, _num_bytes: usize) {}
+
+    #[cfg(feature = "metrics")]
+    fn record_bytes_sent(&self, num_bytes: u64) {
+        metrics::bytes_sent(&self.protocol).inc_by(num_bytes);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn record_bytes_sent(&self, _num_bytes: u64) {}
+
+    #[cfg(feature = "metrics")]
+    fn record_rejection(&self, rejection: RpcQosRejection) {
+        metrics::qos_rejections(&self.protocol, rejection.as_str()).inc();
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn record_rejection(&self, _rejection: RpcQosRejection) {}
+
+    /// Returns a timer that records the request latency when dropped
+    #[cfg(feature = "metrics")]
+    fn start_request_timer(&self, method: RpcMethod) -> Option<tari_metrics::HistogramTimer> {
+        Some(metrics::request_seconds(&self.protocol, method).start_timer())
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn start_request_timer(&self, _method: RpcMethod) -> Option<()> {
+        None
+    }
+
     fn create_request_context(&self) -> RequestContext {
         RequestContext::new(self.node_id.clone(), Box::new(self.comms_provider.clone()))
     }
 
-    async fn handle<W>(&mut self, sink: &mut W, mut request: Bytes) -> Result<(), RpcServerError>
-    where W: Sink<Bytes, Error = io::Error> + Unpin {
+    async fn handle<W, R>(&mut self, sink: &mut W, stream: &mut R, mut request: Bytes) -> Result<(), RpcServerError>
+    where
+        W: Sink<Bytes, Error = io::Error> + Unpin,
+        R: Stream<Item = Result<BytesMut, io::Error>> + Unpin,
+    {
+        self.record_bytes_received(request.len());
         let decoded_msg = proto::rpc::RpcRequest::decode(&mut request)?;
 
         let request_id = decoded_msg.request_id;
+        if decoded_msg.flags().is_ack() {
+            // The final acknowledgements of a streaming response arrive after the response has completed
+            trace!(
+                target: LOG_TARGET,
+                "[Peer=`{}`] Ignoring late ACK for request {}",
+                self.node_id,
+                request_id
+            );
+            return Ok(());
+        }
         let method = decoded_msg.method.into();
+        self.stats.update(&self.protocol, method, |s| s.num_requests += 1);
+        let _timer = self.start_request_timer(method);
         let deadline = Duration::from_secs(decoded_msg.deadline);
 
         // The client side deadline MUST be greater or equal to the minimum_client_deadline
@@ -462,6 +576,16 @@ where
             "[Peer=`{}`] Got request {}", self.node_id, decoded_msg
         );
 
+        // Held until the response is complete
+        let permit = match self.qos.acquire(&self.node_id, &self.protocol, method).await {
+            Ok(permit) => permit,
+            Err(rejection) => return self.reject_request(sink, request_id, method, rejection).await,
+        };
+        if permit.is_throttled() {
+            self.stats
+                .update(&self.protocol, method, |s| s.num_throttled_requests += 1);
+        }
+
         let req = Request::with_context(self.create_request_context(), method, decoded_msg.message.into());
 
         let service_result = time::timeout(deadline, self.service.call(req)).await;
@@ -478,6 +602,9 @@ where
 
         match service_result {
             Ok(body) => {
+                let flow_control_enabled = self.protocol_version >= RPC_FLOW_CONTROL_VERSION;
+                let slow_consumer_timeout = self.slow_consumer_timeout(deadline);
+                let mut num_unacked = 0;
                 let mut message = body.into_message();
                 loop {
                     match time::timeout(deadline, message.next()).await {
@@ -507,8 +634,43 @@ where
                                 },
                             };
 
-                            if !send_response_checked(sink, request_id, resp).await? {
-                                break;
+                            let is_finished = resp.flags().is_fin();
+                            if flow_control_enabled && num_unacked >= self.config.maximum_in_flight_messages {
+                                self.stats
+                                    .update(&self.protocol, method, |s| s.num_flow_control_stalls += 1);
+                                if !wait_for_ack(stream, request_id, slow_consumer_timeout).await? {
+                                    self.on_slow_consumer(method, slow_consumer_timeout)?;
+                                    break;
+                                }
+                                num_unacked -= 1;
+                            }
+
+                            let num_bytes = resp.encoded_len() as u64;
+                            let send_result =
+                                time::timeout(slow_consumer_timeout, send_response_checked(sink, request_id, resp))
+                                    .await;
+                            match send_result {
+                                Ok(is_sent) => {
+                                    let is_sent = is_sent?;
+                                    self.stats.update(&self.protocol, method, |s| {
+                                        s.num_messages_sent += 1;
+                                        s.bytes_sent += num_bytes;
+                                    });
+                                    self.record_bytes_sent(num_bytes);
+                                    if !is_sent {
+                                        break;
+                                    }
+                                },
+                                Err(_) => {
+                                    // A partially written frame cannot be recovered from, so the session is always
+                                    // closed
+                                    self.stats
+                                        .update(&self.protocol, method, |s| s.num_slow_consumer_aborts += 1);
+                                    return Err(RpcServerError::SlowConsumer(slow_consumer_timeout));
+                                },
+                            }
+                            if !is_finished {
+                                num_unacked += 1;
                             }
                         },
                         Ok(None) => break,
@@ -538,6 +700,112 @@ where
 
         Ok(())
     }
+
+    /// Responds with a `Busy` status. If the peer should be banned, the peer is banned and an error is returned to
+    /// close the session.
+    async fn reject_request<W>(
+        &mut self,
+        sink: &mut W,
+        request_id: u32,
+        method: RpcMethod,
+        rejection: RpcQosRejection,
+    ) -> Result<(), RpcServerError>
+    where
+        W: Sink<Bytes, Error = io::Error> + Unpin,
+    {
+        debug!(
+            target: LOG_TARGET,
+            "[Peer=`{}`] Rejecting request {} because {}", self.node_id, request_id, rejection
+        );
+        self.stats
+            .update(&self.protocol, method, |s| s.num_rejected_requests += 1);
+        self.record_rejection(rejection);
+
+        let status = RpcStatus::busy(format!("Request rejected because {}", rejection));
+        let resp = proto::rpc::RpcResponse {
+            request_id,
+            status: status.as_code(),
+            flags: RpcMessageFlags::FIN.bits().into(),
+            message: status.details_bytes(),
+        };
+        sink.send(resp.to_encoded_bytes().into()).await?;
+
+        if rejection == RpcQosRejection::Banned {
+            let ban_duration = self.qos.config().ban_duration;
+            if let Err(err) = self
+                .comms_provider
+                .ban_peer(&self.node_id, ban_duration, rejection.to_string())
+                .await
+            {
+                warn!(
+                    target: LOG_TARGET,
+                    "[Peer=`{}`] Failed to ban peer: {}", self.node_id, err
+                );
+            }
+            return Err(RpcServerError::PeerBanned);
+        }
+
+        Ok(())
+    }
+}
+
+impl<TSvc, TSubstream, TCommsProvider> ActivePeerRpcService<TSvc, TSubstream, TCommsProvider> {
+    fn slow_consumer_timeout(&self, deadline: Duration) -> Duration {
+        match self.config.slow_consumer_policy {
+            SlowConsumerPolicy::AbortAfterDeadline => deadline,
+            SlowConsumerPolicy::DisconnectAfter(timeout) => timeout,
+        }
+    }
+
+    /// Applies the slow consumer policy. Returns an error if the session should be closed, otherwise the caller aborts
+    /// the response.
+    fn on_slow_consumer(&self, method: RpcMethod, timeout: Duration) -> Result<(), RpcServerError> {
+        self.stats
+            .update(&self.protocol, method, |s| s.num_slow_consumer_aborts += 1);
+        match self.config.slow_consumer_policy {
+            SlowConsumerPolicy::AbortAfterDeadline => {
+                debug!(
+                    target: LOG_TARGET,
+                    "[Peer=`{}`] Client did not keep up with the response within the deadline ({:.0?}). Response \
+                     aborted.",
+                    self.node_id,
+                    timeout
+                );
+                Ok(())
+            },
+            SlowConsumerPolicy::DisconnectAfter(_) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "[Peer=`{}`] Client did not keep up with the response within {:.0?}. Closing session.",
+                    self.node_id,
+                    timeout
+                );
+                Err(RpcServerError::SlowConsumer(timeout))
+            },
+        }
+    }
+}
+
+/// Waits for the client to acknowledge a message of the current streaming response. Returns false if no
+/// acknowledgement was received within the timeout.
+async fn wait_for_ack<R>(stream: &mut R, request_id: u32, timeout: Duration) -> Result<bool, RpcServerError>
+where R: Stream<Item = Result<BytesMut, io::Error>> + Unpin {
+    loop {
+        match time::timeout(timeout, stream.next()).await {
+            Ok(Some(frame)) => {
+                let msg = proto::rpc::RpcRequest::decode(frame?.freeze())?;
+                if !msg.flags().is_ack() {
+                    return Err(RpcServerError::UnexpectedIncomingMessage);
+                }
+                if msg.request_id == request_id {
+                    return Ok(true);
+                }
+                // Late ACK for a previous request
+            },
+            Ok(None) => return Err(RpcServerError::SessionClosed),
+            Err(_) => return Ok(false),
+        }
+    }
 }
 
 /// Sends an RpcResponse on the given Sink. If the size of the message exceeds the RPC_MAX_FRAME_SIZE, an error is
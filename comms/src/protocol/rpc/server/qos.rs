@@ -0,0 +1,518 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    peer_manager::NodeId,
+    protocol::{rpc::RpcMethod, ProtocolId},
+};
+use futures::channel::oneshot;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::time;
+
+#[cfg(feature = "metrics")]
+use super::metrics;
+
+/// Quality of service settings for the RPC server. The default configuration does not limit requests.
+#[derive(Debug, Clone)]
+pub struct RpcQosConfig {
+    /// The maximum number of requests handled at the same time over all sessions. Requests that arrive while the
+    /// server is at capacity are queued by priority. None for unlimited.
+    pub max_concurrent_requests: Option<usize>,
+    /// The maximum number of requests, including queued requests, that a single peer may have in progress. Requests
+    /// over this quota are rejected with a `Busy` status. None for unlimited.
+    pub max_concurrent_requests_per_peer: Option<usize>,
+    /// The maximum time a request is queued before it is rejected with a `Busy` status
+    pub max_queue_wait: Duration,
+    /// Requests for these methods from a peer that is actively syncing are handled before other queued requests
+    pub sync_critical_methods: HashSet<(ProtocolId, RpcMethod)>,
+    /// A peer is actively syncing if it has requested a sync-critical method within this time
+    pub active_sync_timeout: Duration,
+    /// Each request rejected for exceeding the per-peer quota adds one to the peer's misbehaviour score. Requests from
+    /// a peer with a score of at least this threshold are delayed by `throttle_delay`.
+    pub throttle_threshold: u32,
+    pub throttle_delay: Duration,
+    /// A peer with a misbehaviour score of at least this threshold is banned for `ban_duration`
+    pub ban_threshold: u32,
+    pub ban_duration: Duration,
+    /// The misbehaviour score of a peer decreases by one every `score_decay_interval`
+    pub score_decay_interval: Duration,
+}
+
+impl RpcQosConfig {
+    /// Marks the given methods of the protocol as sync-critical
+    pub fn with_sync_critical_methods<I>(mut self, protocol: ProtocolId, methods: I) -> Self
+    where I: IntoIterator<Item = u32> {
+        self.sync_critical_methods
+            .extend(methods.into_iter().map(|m| (protocol.clone(), RpcMethod::from(m))));
+        self
+    }
+}
+
+impl Default for RpcQosConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: None,
+            max_concurrent_requests_per_peer: None,
+            max_queue_wait: Duration::from_secs(10),
+            sync_critical_methods: HashSet::new(),
+            active_sync_timeout: Duration::from_secs(60),
+            throttle_threshold: 5,
+            throttle_delay: Duration::from_secs(1),
+            ban_threshold: 20,
+            ban_duration: Duration::from_secs(60 * 60),
+            score_decay_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// The reason a request was rejected by the QoS layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RpcQosRejection {
+    /// The peer has too many requests in progress
+    PeerQuotaExceeded,
+    /// The server did not have capacity for the request within the maximum queue wait
+    QueueTimeout,
+    /// The peer has exceeded its quota too many times and should be banned
+    Banned,
+}
+
+impl RpcQosRejection {
+    pub fn as_str(self) -> &'static str {
+        use RpcQosRejection::*;
+        match self {
+            PeerQuotaExceeded => "peer_quota_exceeded",
+            QueueTimeout => "queue_timeout",
+            Banned => "banned",
+        }
+    }
+}
+
+impl fmt::Display for RpcQosRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use RpcQosRejection::*;
+        match self {
+            PeerQuotaExceeded => write!(f, "too many concurrent requests from this peer"),
+            QueueTimeout => write!(f, "the server is busy"),
+            Banned => write!(f, "the peer repeatedly exceeded its request quota"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Priority {
+    High,
+    Normal,
+}
+
+/// Admission control shared by all sessions of an RPC server. Clones refer to the same state.
+#[derive(Clone)]
+pub(super) struct RpcQos {
+    config: Arc<RpcQosConfig>,
+    state: Arc<Mutex<QosState>>,
+}
+
+impl RpcQos {
+    pub fn new(config: RpcQosConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            state: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> &RpcQosConfig {
+        &self.config
+    }
+
+    /// Waits until the request may be handled. The returned permit must be held until the response is complete.
+    pub async fn acquire(
+        &self,
+        node_id: &NodeId,
+        protocol: &ProtocolId,
+        method: RpcMethod,
+    ) -> Result<RpcQosPermit, RpcQosRejection> {
+        let (priority, is_throttled) = self.reserve_peer_request(node_id, protocol, method)?;
+        // From here the permit releases the peer's reservation, even if this future is dropped
+        let mut permit = RpcQosPermit {
+            qos: self.clone(),
+            node_id: node_id.clone(),
+            has_slot: false,
+            is_throttled,
+        };
+
+        if is_throttled {
+            time::delay_for(self.config.throttle_delay).await;
+        }
+
+        let (waiter_id, mut grant_rx) = match self.try_acquire_slot(priority) {
+            Some(waiter) => waiter,
+            None => {
+                permit.has_slot = true;
+                return Ok(permit);
+            },
+        };
+
+        match time::timeout(self.config.max_queue_wait, &mut grant_rx).await {
+            Ok(Ok(_)) => {},
+            Ok(Err(_)) => return Err(RpcQosRejection::QueueTimeout),
+            Err(_) => {
+                // The slot may have been granted after the timeout expired but before the waiter was removed
+                if self.remove_waiter(waiter_id) {
+                    return Err(RpcQosRejection::QueueTimeout);
+                }
+            },
+        }
+        permit.has_slot = true;
+        Ok(permit)
+    }
+
+    fn reserve_peer_request(
+        &self,
+        node_id: &NodeId,
+        protocol: &ProtocolId,
+        method: RpcMethod,
+    ) -> Result<(Priority, bool), RpcQosRejection> {
+        let now = Instant::now();
+        let config = &self.config;
+        let mut state = self.state.lock().expect("RPC QoS lock poisoned");
+        let peer = state
+            .peers
+            .entry(node_id.clone())
+            .or_insert_with(|| PeerState::new(now));
+        peer.decay_score(config.score_decay_interval, now);
+
+        if let Some(limit) = config.max_concurrent_requests_per_peer {
+            if peer.num_requests >= limit {
+                peer.score = peer.score.saturating_add(1);
+                if peer.score >= config.ban_threshold {
+                    return Err(RpcQosRejection::Banned);
+                }
+                return Err(RpcQosRejection::PeerQuotaExceeded);
+            }
+        }
+
+        let is_sync_critical = config.sync_critical_methods.contains(&(protocol.clone(), method));
+        let priority = if is_sync_critical && peer.is_syncing(config.active_sync_timeout, now) {
+            Priority::High
+        } else {
+            Priority::Normal
+        };
+        if is_sync_critical {
+            peer.last_sync_request = Some(now);
+        }
+        peer.num_requests += 1;
+
+        Ok((priority, peer.score >= config.throttle_threshold))
+    }
+
+    /// Takes a slot if one is available, otherwise queues a waiter and returns its id and the receiver that is
+    /// notified when a slot is handed over to it
+    fn try_acquire_slot(&self, priority: Priority) -> Option<(u64, oneshot::Receiver<()>)> {
+        let mut state = self.state.lock().expect("RPC QoS lock poisoned");
+        let has_capacity = self
+            .config
+            .max_concurrent_requests
+            .map(|limit| state.num_in_progress < limit)
+            .unwrap_or(true);
+        let is_first_in_line = match priority {
+            Priority::High => state.high_priority.is_empty(),
+            Priority::Normal => state.high_priority.is_empty() && state.normal_priority.is_empty(),
+        };
+        if has_capacity && is_first_in_line {
+            state.num_in_progress += 1;
+            return None;
+        }
+
+        let id = state.next_waiter_id;
+        state.next_waiter_id = state.next_waiter_id.wrapping_add(1);
+        let (grant_tx, grant_rx) = oneshot::channel();
+        let waiter = Waiter { id, grant_tx };
+        match priority {
+            Priority::High => state.high_priority.push_back(waiter),
+            Priority::Normal => state.normal_priority.push_back(waiter),
+        }
+        state.record_queue_depths();
+        Some((id, grant_rx))
+    }
+
+    /// Removes a queued waiter. Returns false if the waiter was already granted a slot.
+    fn remove_waiter(&self, id: u64) -> bool {
+        let mut state = self.state.lock().expect("RPC QoS lock poisoned");
+        let state = &mut *state;
+        let removed = [&mut state.high_priority, &mut state.normal_priority]
+            .iter_mut()
+            .any(|queue| match queue.iter().position(|w| w.id == id) {
+                Some(pos) => {
+                    queue.remove(pos);
+                    true
+                },
+                None => false,
+            });
+        state.record_queue_depths();
+        removed
+    }
+
+    fn release(&self, node_id: &NodeId, has_slot: bool) {
+        let now = Instant::now();
+        let config = &self.config;
+        let mut state = self.state.lock().expect("RPC QoS lock poisoned");
+        if let Some(peer) = state.peers.get_mut(node_id) {
+            peer.num_requests = peer.num_requests.saturating_sub(1);
+        }
+        state.peers.retain(|_, peer| {
+            peer.decay_score(config.score_decay_interval, now);
+            peer.num_requests > 0 || peer.score > 0 || peer.is_syncing(config.active_sync_timeout, now)
+        });
+        if has_slot {
+            state.release_slot();
+        }
+    }
+
+    #[cfg(test)]
+    fn num_queued(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.high_priority.len() + state.normal_priority.len()
+    }
+}
+
+/// Holds a request slot until dropped
+pub(super) struct RpcQosPermit {
+    qos: RpcQos,
+    node_id: NodeId,
+    has_slot: bool,
+    is_throttled: bool,
+}
+
+impl RpcQosPermit {
+    /// True if the request was delayed because the peer is being throttled
+    pub fn is_throttled(&self) -> bool {
+        self.is_throttled
+    }
+}
+
+impl Drop for RpcQosPermit {
+    fn drop(&mut self) {
+        self.qos.release(&self.node_id, self.has_slot);
+    }
+}
+
+#[derive(Default)]
+struct QosState {
+    num_in_progress: usize,
+    peers: HashMap<NodeId, PeerState>,
+    high_priority: VecDeque<Waiter>,
+    normal_priority: VecDeque<Waiter>,
+    next_waiter_id: u64,
+}
+
+impl QosState {
+    /// Hands the slot over to the next waiter, high priority first. If no-one is waiting the slot is freed.
+    fn release_slot(&mut self) {
+        loop {
+            let next = self
+                .high_priority
+                .pop_front()
+                .or_else(|| self.normal_priority.pop_front());
+            match next {
+                // The send fails if the waiting request was dropped, in which case try the next one
+                Some(waiter) => {
+                    if waiter.grant_tx.send(()).is_ok() {
+                        break;
+                    }
+                },
+                None => {
+                    self.num_in_progress = self.num_in_progress.saturating_sub(1);
+                    break;
+                },
+            }
+        }
+        self.record_queue_depths();
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_queue_depths(&self) {
+        metrics::qos_queue_depth("high").set(self.high_priority.len() as i64);
+        metrics::qos_queue_depth("normal").set(self.normal_priority.len() as i64);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn record_queue_depths(&self) {}
+}
+
+struct PeerState {
+    num_requests: usize,
+    score: u32,
+    score_updated_at: Instant,
+    last_sync_request: Option<Instant>,
+}
+
+impl PeerState {
+    fn new(now: Instant) -> Self {
+        Self {
+            num_requests: 0,
+            score: 0,
+            score_updated_at: now,
+            last_sync_request: None,
+        }
+    }
+
+    fn decay_score(&mut self, interval: Duration, now: Instant) {
+        if self.score == 0 || interval.as_millis() == 0 {
+            self.score_updated_at = now;
+            return;
+        }
+        let elapsed = now.duration_since(self.score_updated_at);
+        let num_intervals = (elapsed.as_millis() / interval.as_millis()).min(u128::from(u32::MAX)) as u32;
+        if num_intervals > 0 {
+            self.score = self.score.saturating_sub(num_intervals);
+            self.score_updated_at += interval * num_intervals;
+        }
+    }
+
+    fn is_syncing(&self, timeout: Duration, now: Instant) -> bool {
+        self.last_sync_request
+            .map(|t| now.duration_since(t) < timeout)
+            .unwrap_or(false)
+    }
+}
+
+struct Waiter {
+    id: u64,
+    grant_tx: oneshot::Sender<()>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{runtime, test_utils::node_identity::build_node_identity};
+    use futures::{pin_mut, poll, task::Poll};
+
+    const PROTOCOL: &[u8] = b"/test/qos/1";
+    const SYNC_METHOD: u32 = 1;
+    const OTHER_METHOD: u32 = 2;
+
+    fn node_id() -> NodeId {
+        build_node_identity(Default::default()).node_id().clone()
+    }
+
+    fn acquire<'a>(
+        qos: &'a RpcQos,
+        node_id: &'a NodeId,
+        method: u32,
+    ) -> impl std::future::Future<Output = Result<RpcQosPermit, RpcQosRejection>> + 'a {
+        let protocol = ProtocolId::from_static(PROTOCOL);
+        async move { qos.acquire(node_id, &protocol, method.into()).await }
+    }
+
+    #[runtime::test_basic]
+    async fn it_rejects_then_bans_peers_over_quota() {
+        let qos = RpcQos::new(RpcQosConfig {
+            max_concurrent_requests_per_peer: Some(1),
+            ban_threshold: 2,
+            ..Default::default()
+        });
+        let peer = node_id();
+        let _permit = acquire(&qos, &peer, OTHER_METHOD).await.unwrap();
+        let err = acquire(&qos, &peer, OTHER_METHOD).await.err().unwrap();
+        assert_eq!(err, RpcQosRejection::PeerQuotaExceeded);
+        let err = acquire(&qos, &peer, OTHER_METHOD).await.err().unwrap();
+        assert_eq!(err, RpcQosRejection::Banned);
+
+        // Other peers are unaffected
+        let other = node_id();
+        acquire(&qos, &other, OTHER_METHOD).await.unwrap();
+    }
+
+    #[runtime::test_basic]
+    async fn it_releases_the_peer_quota_when_the_permit_is_dropped() {
+        let qos = RpcQos::new(RpcQosConfig {
+            max_concurrent_requests_per_peer: Some(1),
+            ..Default::default()
+        });
+        let peer = node_id();
+        let permit = acquire(&qos, &peer, OTHER_METHOD).await.unwrap();
+        drop(permit);
+        acquire(&qos, &peer, OTHER_METHOD).await.unwrap();
+    }
+
+    #[runtime::test_basic]
+    async fn it_rejects_requests_that_wait_too_long() {
+        let qos = RpcQos::new(RpcQosConfig {
+            max_concurrent_requests: Some(1),
+            max_queue_wait: Duration::from_millis(10),
+            ..Default::default()
+        });
+        let (peer1, peer2) = (node_id(), node_id());
+        let _permit = acquire(&qos, &peer1, OTHER_METHOD).await.unwrap();
+        let err = acquire(&qos, &peer2, OTHER_METHOD).await.err().unwrap();
+        assert_eq!(err, RpcQosRejection::QueueTimeout);
+        assert_eq!(qos.num_queued(), 0);
+    }
+
+    #[runtime::test_basic]
+    async fn it_prioritises_sync_requests_from_syncing_peers() {
+        let qos = RpcQos::new(
+            RpcQosConfig {
+                max_concurrent_requests: Some(1),
+                ..Default::default()
+            }
+            .with_sync_critical_methods(ProtocolId::from_static(PROTOCOL), vec![SYNC_METHOD]),
+        );
+        let (busy_peer, other_peer, syncing_peer) = (node_id(), node_id(), node_id());
+        // The first sync request marks the peer as syncing
+        drop(acquire(&qos, &syncing_peer, SYNC_METHOD).await.unwrap());
+
+        let permit = acquire(&qos, &busy_peer, OTHER_METHOD).await.unwrap();
+        let other_req = acquire(&qos, &other_peer, OTHER_METHOD);
+        pin_mut!(other_req);
+        assert!(poll!(&mut other_req).is_pending());
+        let sync_req = acquire(&qos, &syncing_peer, SYNC_METHOD);
+        pin_mut!(sync_req);
+        assert!(poll!(&mut sync_req).is_pending());
+        assert_eq!(qos.num_queued(), 2);
+
+        drop(permit);
+        let sync_permit = match poll!(&mut sync_req) {
+            Poll::Ready(result) => result.unwrap(),
+            Poll::Pending => panic!("sync request was not prioritised"),
+        };
+        assert!(poll!(&mut other_req).is_pending());
+
+        drop(sync_permit);
+        assert!(matches!(poll!(&mut other_req), Poll::Ready(Ok(_))));
+    }
+
+    #[test]
+    fn it_decays_the_misbehaviour_score() {
+        let start = Instant::now();
+        let mut peer = PeerState::new(start);
+        peer.score = 3;
+        peer.decay_score(Duration::from_secs(10), start + Duration::from_secs(25));
+        assert_eq!(peer.score, 1);
+        peer.decay_score(Duration::from_secs(10), start + Duration::from_secs(30));
+        assert_eq!(peer.score, 0);
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::protocol::{rpc::message::RpcMethod, ProtocolId};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Statistics for a single RPC method, accumulated over all sessions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RpcMethodStats {
+    /// Number of requests received for this method
+    pub num_requests: u64,
+    /// Number of response messages sent, including the final message of a stream
+    pub num_messages_sent: u64,
+    /// Total encoded size of the response messages sent
+    pub bytes_sent: u64,
+    /// Number of times a streaming response had to wait because the client had not acknowledged enough messages
+    pub num_flow_control_stalls: u64,
+    /// Number of streaming responses that were aborted because the client did not keep up
+    pub num_slow_consumer_aborts: u64,
+    /// Number of requests rejected because the server was busy or the peer exceeded its request quota
+    pub num_rejected_requests: u64,
+    /// Number of requests that were delayed because the peer is being throttled
+    pub num_throttled_requests: u64,
+}
+
+/// Shared per-method statistics for an RPC server. Clones refer to the same statistics.
+#[derive(Debug, Clone, Default)]
+pub(super) struct RpcServerStats {
+    inner: Arc<Mutex<HashMap<(ProtocolId, RpcMethod), RpcMethodStats>>>,
+}
+
+impl RpcServerStats {
+    pub fn update<F>(&self, protocol: &ProtocolId, method: RpcMethod, f: F)
+    where F: FnOnce(&mut RpcMethodStats) {
+        let mut inner = self.inner.lock().expect("RPC stats lock poisoned");
+        f(inner.entry((protocol.clone(), method)).or_default());
+    }
+
+    #[allow(clippy::mutable_key_type)] // Note: Clippy Breaks with Interior Mutability Error
+    pub fn snapshot(&self) -> HashMap<(ProtocolId, RpcMethod), RpcMethodStats> {
+        self.inner.lock().expect("RPC stats lock poisoned").clone()
+    }
+}
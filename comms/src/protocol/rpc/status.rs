@@ -90,6 +90,23 @@ impl RpcStatus {
         }
     }
 
+    /// The server did not handle the request because it is at capacity or the peer exceeded its request quota. The
+    /// request may be retried later.
+    pub fn busy<T: ToString>(details: T) -> Self {
+        Self {
+            code: RpcStatusCode::Busy,
+            details: details.to_string(),
+        }
+    }
+
+    /// The peer is not permitted to make the request
+    pub fn forbidden<T: ToString>(details: T) -> Self {
+        Self {
+            code: RpcStatusCode::Forbidden,
+            details: details.to_string(),
+        }
+    }
+
     /// Returns a closure that logs the given error and returns a generic general error that does not leak any
     /// potentially sensitive error information. Use this function with map_err to catch "miscellaneous" errors.
     pub fn log_internal_error<'a, E: std::error::Error + 'a>(target: &'a str) -> impl Fn(E) -> Self + 'a {
@@ -177,6 +194,10 @@ pub enum RpcStatusCode {
     General = 6,
     /// Entity not found
     NotFound = 7,
+    /// The server is at capacity or the peer exceeded its request quota
+    Busy = 8,
+    /// The peer is not permitted to make the request
+    Forbidden = 9,
     // The following status represents anything that is not recognised (i.e not one of the above codes).
     /// Unrecognised RPC status code
     InvalidRpcStatusCode,
@@ -194,6 +215,14 @@ impl RpcStatusCode {
     pub fn is_timeout(self) -> bool {
         self == Self::Timeout
     }
+
+    pub fn is_busy(self) -> bool {
+        self == Self::Busy
+    }
+
+    pub fn is_forbidden(self) -> bool {
+        self == Self::Forbidden
+    }
 }
 
 impl From<u32> for RpcStatusCode {
@@ -208,6 +237,8 @@ impl From<u32> for RpcStatusCode {
             5 => MalformedResponse,
             6 => General,
             7 => NotFound,
+            8 => Busy,
+            9 => Forbidden,
             _ => InvalidRpcStatusCode,
         }
     }
@@ -228,6 +259,8 @@ mod test {
         assert_eq!(RpcStatusCode::from(MalformedResponse as u32), MalformedResponse);
         assert_eq!(RpcStatusCode::from(Timeout as u32), Timeout);
         assert_eq!(RpcStatusCode::from(NotFound as u32), NotFound);
+        assert_eq!(RpcStatusCode::from(Busy as u32), Busy);
+        assert_eq!(RpcStatusCode::from(Forbidden as u32), Forbidden);
         assert_eq!(RpcStatusCode::from(InvalidRpcStatusCode as u32), InvalidRpcStatusCode);
         assert_eq!(RpcStatusCode::from(123), InvalidRpcStatusCode);
     }
@@ -46,9 +46,10 @@ async fn it_performs_the_handshake() {
     let mut client_framed = framing::canonical(client, 1024);
     let mut handshake_client = Handshake::new(&mut client_framed);
 
-    handshake_client.perform_client_handshake().await.unwrap();
+    let client_version = handshake_client.perform_client_handshake().await.unwrap();
     let v = handshake_result.await.unwrap().unwrap();
     assert!(SUPPORTED_RPC_VERSIONS.contains(&v));
+    assert_eq!(client_version, v);
 }
 
 #[runtime::test_basic]
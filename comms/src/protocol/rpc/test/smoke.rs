@@ -30,13 +30,16 @@ use crate::{
             error::HandshakeRejectReason,
             handshake::RpcHandshakeError,
             message::Request,
-            server::RpcServerError,
+            server::{RpcServerBuilder, RpcServerError},
             test::mock::create_mocked_rpc_context,
             Response,
             RpcError,
+            RpcMethod,
             RpcServer,
+            RpcServerHandle,
             RpcStatus,
             RpcStatusCode,
+            SlowConsumerPolicy,
             RPC_MAX_FRAME_SIZE,
         },
         ProtocolEvent,
@@ -125,6 +128,38 @@ async fn setup<T: GreetingRpc>(
     (outbound, server_hnd, node_identity, shutdown)
 }
 
+async fn setup_with_builder<T: GreetingRpc>(
+    service: T,
+    builder: RpcServerBuilder,
+) -> (MemorySocket, RpcServerHandle, Shutdown) {
+    let (mut notif_tx, notif_rx) = mpsc::channel(1);
+    let shutdown = Shutdown::new();
+    let (context, _) = create_mocked_rpc_context();
+    let server = builder
+        .with_minimum_client_deadline(Duration::from_secs(0))
+        .with_shutdown_signal(shutdown.to_signal())
+        .finish();
+    let server_handle = server.get_handle();
+    task::spawn(
+        server
+            .add_service(GreetingServer::new(service))
+            .serve(notif_rx, context.clone()),
+    );
+
+    let (inbound, outbound) = MemorySocket::new_pair();
+    let node_identity = build_node_identity(Default::default());
+    context.peer_manager().add_peer(node_identity.to_peer()).await.unwrap();
+    notif_tx
+        .send(ProtocolNotification::new(
+            ProtocolId::from_static(b"/test/greeting/1.0"),
+            ProtocolEvent::NewInboundSubstream(node_identity.node_id().clone(), inbound),
+        ))
+        .await
+        .unwrap();
+
+    (outbound, server_handle, shutdown)
+}
+
 #[runtime::test_basic]
 async fn request_reponse_errors_and_streaming() // a.k.a  smoke test
 {
@@ -200,6 +235,64 @@ async fn request_reponse_errors_and_streaming() // a.k.a  smoke test
     server_hnd.await.unwrap().unwrap();
 }
 
+#[runtime::test_basic]
+#[allow(clippy::mutable_key_type)] // Note: Clippy Breaks with Interior Mutability Error
+async fn streaming_flow_control() {
+    let greetings = &["Sawubona", "Jambo", "Bonjour", "Hello", "Molo", "Olá"];
+    let builder = RpcServer::builder().with_maximum_in_flight_messages(1);
+    let (socket, mut server_handle, _shutdown) = setup_with_builder(GreetingService::new(greetings), builder).await;
+
+    let framed = framing::canonical(socket, 1024);
+    let mut client = GreetingClient::connect(framed).await.unwrap();
+
+    let resp = client.get_greetings(6).await.unwrap();
+    let greetings = resp.map(|r| r.unwrap()).collect::<Vec<_>>().await;
+    assert_eq!(greetings, ["Sawubona", "Jambo", "Bonjour", "Hello", "Molo", "Olá"]);
+
+    // The session is still usable after the stream completes and late ACKs are ignored
+    let resp = client.get_greetings(1).await.unwrap();
+    let greetings = resp.map(|r| r.unwrap()).collect::<Vec<_>>().await;
+    assert_eq!(greetings, ["Sawubona"]);
+
+    let stats = server_handle.get_method_stats().await.unwrap();
+    let stats = stats
+        .get(&(ProtocolId::from_static(b"/test/greeting/1.0"), RpcMethod::from(3)))
+        .unwrap();
+    assert_eq!(stats.num_requests, 2);
+    // 7 greetings + 2 FIN messages
+    assert_eq!(stats.num_messages_sent, 9);
+    assert!(stats.num_flow_control_stalls > 0);
+    assert_eq!(stats.num_slow_consumer_aborts, 0);
+}
+
+#[runtime::test_basic]
+#[allow(clippy::mutable_key_type)] // Note: Clippy Breaks with Interior Mutability Error
+async fn slow_consumer_disconnected() {
+    let greetings = &["Sawubona", "Jambo", "Bonjour", "Hello", "Molo", "Olá"];
+    let builder = RpcServer::builder()
+        .with_maximum_in_flight_messages(1)
+        .with_slow_consumer_policy(SlowConsumerPolicy::DisconnectAfter(Duration::from_millis(100)));
+    let (socket, mut server_handle, _shutdown) = setup_with_builder(GreetingService::new(greetings), builder).await;
+
+    let framed = framing::canonical(socket, 1024);
+    let mut client = GreetingClient::connect(framed).await.unwrap();
+
+    // Do not read from the stream until the server has given up on us
+    let resp = client.get_greetings(6).await.unwrap();
+    time::delay_for(Duration::from_millis(500)).await;
+
+    let stats = server_handle.get_method_stats().await.unwrap();
+    let stats = stats
+        .get(&(ProtocolId::from_static(b"/test/greeting/1.0"), RpcMethod::from(3)))
+        .unwrap();
+    assert_eq!(stats.num_slow_consumer_aborts, 1);
+    assert!(stats.num_messages_sent < 7);
+
+    let greetings = resp.collect::<Vec<_>>().await;
+    assert!(greetings.len() < 6);
+    assert_eq!(server_handle.get_num_active_sessions().await.unwrap(), 0);
+}
+
 #[runtime::test_basic]
 async fn response_too_big() {
     let (socket, _, _, _shutdown) = setup(GreetingService::new(&[]), 1).await;
@@ -269,6 +362,27 @@ async fn timeout() {
     assert_eq!(resp.greeting, "took a while to load");
 }
 
+#[cfg(feature = "chaos")]
+#[runtime::test_basic]
+async fn fault_injection() {
+    use crate::pipeline::fault_injection::{FaultConfig, FaultInjector};
+    use std::sync::atomic::Ordering;
+
+    let (socket, _, _, _shutdown) = setup(GreetingService::new(&["Sawubona"]), 1).await;
+    let framed = framing::canonical(socket, 1024);
+    let injector = FaultInjector::new(FaultConfig::none(1).with_drop_probability(1.0));
+    let mut client = GreetingClient::builder()
+        .with_fault_injection(injector.clone())
+        .connect(framed)
+        .await
+        .unwrap();
+
+    let err = client.say_hello(Default::default()).await.unwrap_err();
+    unpack_enum!(RpcError::RequestFailed(status) = err);
+    assert_eq!(status.status_code(), RpcStatusCode::Timeout);
+    assert_eq!(injector.stats().dropped.load(Ordering::Relaxed), 1);
+}
+
 #[runtime::test_basic]
 async fn unknown_protocol() {
     let (mut notif_tx, _, _, _shutdown) = setup_service(GreetingService::new(&[]), 1).await;
@@ -25,6 +25,7 @@ use super::error::SocksError;
 use data_encoding::BASE32;
 use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use multiaddr::{Multiaddr, Protocol};
+use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
     fmt,
@@ -35,7 +36,7 @@ use std::{
 pub type Result<T> = std::result::Result<T, SocksError>;
 
 /// Authentication methods
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Authentication {
     /// No auth
     None,
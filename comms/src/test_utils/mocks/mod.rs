@@ -29,6 +29,7 @@ pub use connectivity_manager::{create_connectivity_mock, ConnectivityManagerMock
 mod peer_connection;
 pub use peer_connection::{
     create_dummy_peer_connection,
+    create_dummy_peer_connection_with_address,
     create_peer_connection_mock_pair,
     PeerConnectionMock,
     PeerConnectionMockState,
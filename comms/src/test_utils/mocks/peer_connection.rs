@@ -30,7 +30,7 @@ use crate::{
     },
     multiaddr::Multiaddr,
     multiplexing,
-    multiplexing::{IncomingSubstreams, Substream, SubstreamCounter, Yamux},
+    multiplexing::{IncomingSubstreams, Substream, SubstreamCounter, SubstreamSettings, Yamux},
     peer_manager::{NodeId, Peer, PeerFeatures},
     test_utils::transport,
 };
@@ -42,6 +42,14 @@ use std::sync::{
 use tokio::runtime::Handle;
 
 pub fn create_dummy_peer_connection(node_id: NodeId) -> (PeerConnection, mpsc::Receiver<PeerConnectionRequest>) {
+    create_dummy_peer_connection_with_address(node_id, Multiaddr::empty(), ConnectionDirection::Inbound)
+}
+
+pub fn create_dummy_peer_connection_with_address(
+    node_id: NodeId,
+    address: Multiaddr,
+    direction: ConnectionDirection,
+) -> (PeerConnection, mpsc::Receiver<PeerConnectionRequest>) {
     let (tx, rx) = mpsc::channel(0);
     (
         PeerConnection::new(
@@ -49,8 +57,8 @@ pub fn create_dummy_peer_connection(node_id: NodeId) -> (PeerConnection, mpsc::R
             tx,
             node_id,
             PeerFeatures::COMMUNICATION_NODE,
-            Multiaddr::empty(),
-            ConnectionDirection::Inbound,
+            address,
+            direction,
             SubstreamCounter::new(),
         ),
         rx,
@@ -176,9 +184,15 @@ impl PeerConnectionMock {
         use PeerConnectionRequest::*;
         self.state.inc_call_count();
         match req {
-            OpenSubstream(protocol, reply_tx) => match self.state.open_substream().await {
-                Ok(stream) => {
-                    let negotiated_substream = NegotiatedSubstream { protocol, stream };
+            OpenSubstream(mut protocols, priority, reply_tx) => match self.state.open_substream().await {
+                Ok(mut stream) => {
+                    if let Some(priority) = priority {
+                        stream.set_settings(SubstreamSettings::new(priority));
+                    }
+                    let negotiated_substream = NegotiatedSubstream {
+                        protocol: protocols.swap_remove(0),
+                        stream,
+                    };
                     reply_tx.send(Ok(negotiated_substream)).unwrap();
                 },
                 Err(err) => {
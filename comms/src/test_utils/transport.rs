@@ -20,6 +20,8 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[cfg(feature = "simulation")]
+use crate::transports::{SimulatedNetwork, SimulatedSocket};
 use crate::{
     connection_manager::ConnectionDirection,
     memsocket::MemorySocket,
@@ -28,6 +30,8 @@ use crate::{
     transports::{MemoryTransport, Transport},
 };
 use futures::{future, StreamExt};
+#[cfg(feature = "simulation")]
+use std::time::{Duration, Instant};
 
 pub async fn build_connected_sockets() -> (Multiaddr, MemorySocket, MemorySocket) {
     let (mut listener, addr) = MemoryTransport.listen("/memory/0".parse().unwrap()).await.unwrap();
@@ -36,6 +40,45 @@ pub async fn build_connected_sockets() -> (Multiaddr, MemorySocket, MemorySocket
     (addr, dial_sock.unwrap(), listen_sock)
 }
 
+/// Returns the (listener, dialer) sockets of a connection between the nodes with the given labels
+#[cfg(feature = "simulation")]
+pub async fn build_simulated_connected_sockets(
+    network: &SimulatedNetwork,
+    listener_label: &str,
+    dialer_label: &str,
+) -> (SimulatedSocket, SimulatedSocket) {
+    let (mut listener, addr) = network
+        .transport(listener_label)
+        .listen("/memory/0".parse().unwrap())
+        .await
+        .unwrap();
+    let outbound = network.transport(dialer_label).dial(addr).await.unwrap();
+    let (inbound, _) = listener.next().await.unwrap().unwrap();
+    (inbound, outbound)
+}
+
+/// Pauses the tokio clock and spawns a task that advances both it and the virtual clock of the network by `step` every
+/// millisecond of real time. Tokio timers (delays, timeouts and intervals) used by the comms nodes and services
+/// therefore run on the same virtual time as the network, and the test does not need to drive the clock itself.
+///
+/// The task never lets the runtime go idle, which stops tokio from skipping ahead to the next timer on its own. This
+/// must be called from within a basic scheduler runtime, because idle worker threads of the threaded scheduler would
+/// still do so.
+#[cfg(feature = "simulation")]
+pub fn spawn_simulated_clock(network: SimulatedNetwork, step: Duration) {
+    tokio::time::pause();
+    tokio::spawn(async move {
+        loop {
+            network.advance(step);
+            tokio::time::advance(step).await;
+            let tick = Instant::now();
+            while tick.elapsed() < Duration::from_millis(1) {
+                tokio::task::yield_now().await;
+            }
+        }
+    });
+}
+
 pub async fn build_multiplexed_connections() -> (Multiaddr, Yamux, Yamux) {
     let (addr, socket_out, socket_in) = build_connected_sockets().await;
 
@@ -33,6 +33,14 @@ mod helpers;
 mod memory;
 pub use memory::MemoryTransport;
 
+#[cfg(feature = "simulation")]
+mod simulated;
+#[cfg(feature = "simulation")]
+pub use simulated::{NetworkEvent, SimulatedListener, SimulatedNetwork, SimulatedSocket, SimulatedTransport, Sleep};
+
+mod proxy;
+pub use proxy::{DialProxy, OutboundProxyTransport};
+
 mod socks;
 pub use socks::{SocksConfig, SocksTransport};
 
@@ -56,4 +64,10 @@ pub trait Transport {
 
     /// Connect (dial) to the given multiaddr
     async fn dial(&self, addr: Multiaddr) -> Result<Self::Output, Self::Error>;
+
+    /// Connect (dial) to the given multiaddr using the given proxy. Transports that do not support selecting a proxy
+    /// at dial time ignore it and dial as normal.
+    async fn dial_with_proxy(&self, addr: Multiaddr, _proxy: &DialProxy) -> Result<Self::Output, Self::Error> {
+        self.dial(addr).await
+    }
 }
@@ -0,0 +1,163 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    multiaddr::Multiaddr,
+    socks,
+    transports::{SocksConfig, SocksTransport, TcpSocket, Transport},
+};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// The proxy to use when dialing a peer. This is stored per-peer in the peer database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DialProxy {
+    /// Use the outbound proxy configured for the transport, if any
+    Global,
+    /// Dial using the underlying transport, bypassing the configured outbound proxy
+    Bypass,
+    /// Dial through the given SOCKS5 proxy
+    Socks5 {
+        proxy_address: Multiaddr,
+        authentication: socks::Authentication,
+    },
+}
+
+impl Default for DialProxy {
+    fn default() -> Self {
+        DialProxy::Global
+    }
+}
+
+/// Transport that dials outbound connections through a SOCKS5 proxy independently of the wrapped transport, which is
+/// still used to listen. For example, a node can listen on clearnet TCP and dial some or all peers via Tor. The proxy
+/// used for a particular dial is selected using [DialProxy].
+#[derive(Clone)]
+pub struct OutboundProxyTransport<T> {
+    inner: T,
+    outbound_proxy: Option<SocksTransport>,
+}
+
+impl<T> OutboundProxyTransport<T> {
+    /// Create a new OutboundProxyTransport without a global outbound proxy. Dials use the wrapped transport unless a
+    /// peer overrides the proxy.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            outbound_proxy: None,
+        }
+    }
+
+    /// Sets the SOCKS5 proxy used to dial all peers that do not override it
+    pub fn set_outbound_proxy(&mut self, socks_config: SocksConfig) -> &mut Self {
+        self.outbound_proxy = Some(SocksTransport::new(socks_config));
+        self
+    }
+
+    /// Create a new OutboundProxyTransport that dials all peers through the given SOCKS5 proxy by default
+    pub fn with_outbound_proxy(inner: T, socks_config: SocksConfig) -> Self {
+        let mut transport = Self::new(inner);
+        transport.set_outbound_proxy(socks_config);
+        transport
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[crate::async_trait]
+impl<T> Transport for OutboundProxyTransport<T>
+where T: Transport<Output = TcpSocket, Error = io::Error> + Send + Sync
+{
+    type Error = io::Error;
+    type Listener = T::Listener;
+    type Output = TcpSocket;
+
+    async fn listen(&self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), Self::Error> {
+        self.inner.listen(addr).await
+    }
+
+    async fn dial(&self, addr: Multiaddr) -> Result<Self::Output, Self::Error> {
+        self.dial_with_proxy(addr, &DialProxy::Global).await
+    }
+
+    async fn dial_with_proxy(&self, addr: Multiaddr, proxy: &DialProxy) -> Result<Self::Output, Self::Error> {
+        match proxy {
+            DialProxy::Global => match self.outbound_proxy {
+                Some(ref socks_transport) => socks_transport.dial(addr).await,
+                None => self.inner.dial(addr).await,
+            },
+            DialProxy::Bypass => self.inner.dial(addr).await,
+            DialProxy::Socks5 {
+                proxy_address,
+                authentication,
+            } => {
+                let socks_transport = SocksTransport::new(SocksConfig {
+                    proxy_address: proxy_address.clone(),
+                    authentication: authentication.clone(),
+                    proxy_bypass_addresses: vec![],
+                });
+                socks_transport.dial(addr).await
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transports::TcpTransport;
+    use futures::StreamExt;
+
+    #[test]
+    fn dial_proxy_default() {
+        assert_eq!(DialProxy::default(), DialProxy::Global);
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_dials_directly_without_an_outbound_proxy() {
+        let transport = OutboundProxyTransport::new(TcpTransport::new());
+        let (mut listener, addr) = transport.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let (dial_result, listen_result) = futures::join!(transport.dial(addr), listener.next());
+        dial_result.unwrap();
+        listen_result.unwrap().unwrap();
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_uses_the_outbound_proxy_unless_bypassed() {
+        let inner = TcpTransport::new();
+        let (mut listener, addr) = inner.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        // Nothing is listening on the proxy address, so dials through the proxy fail
+        let transport = OutboundProxyTransport::with_outbound_proxy(inner, SocksConfig {
+            proxy_address: "/ip4/127.0.0.1/tcp/1".parse().unwrap(),
+            authentication: Default::default(),
+            proxy_bypass_addresses: vec![],
+        });
+        assert!(transport.dial(addr.clone()).await.is_err());
+
+        let (dial_result, listen_result) =
+            futures::join!(transport.dial_with_proxy(addr, &DialProxy::Bypass), listener.next());
+        dial_result.unwrap();
+        listen_result.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,617 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Simulated network
+//!
+//! An in-process network with a virtual clock for running multi-node tests deterministically. Only available with
+//! the `simulation` feature.
+//!
+//! Each node gets its own [SimulatedTransport] from a shared [SimulatedNetwork]. Bytes written to a connection are
+//! only readable once the virtual clock has been advanced past their delivery time, which is determined by the latency
+//! configured between the two nodes. Partitions refuse new dials between the nodes and reset existing connections.
+//! Latency and partition changes can be applied immediately or scheduled for a point in virtual time, so a test
+//! drives the whole network by calling [SimulatedNetwork::advance].
+//!
+//! Nothing in this module uses real timers or randomness, so the same test always observes the same ordering of
+//! events.
+//!
+//! A [SimulatedTransport] can be passed to `UnspawnedCommsNode::spawn_with_transport` to run full comms nodes on the
+//! network. `test_utils::transport::spawn_simulated_clock` advances the clock in the background for such tests and
+//! pauses the tokio clock, advancing it in step with the network so that timers in the comms stack also run on virtual
+//! time.
+
+use crate::transports::Transport;
+use bytes::{Buf, Bytes};
+use futures::{
+    channel::mpsc,
+    io::{AsyncRead, AsyncWrite},
+    stream::Stream,
+    task::{Context, Poll, Waker},
+    Future,
+};
+use multiaddr::{Multiaddr, Protocol};
+use std::{
+    cmp,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, Weak},
+    time::Duration,
+};
+
+/// A change to the network conditions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkEvent {
+    /// Set the one-way latency between two nodes, in both directions
+    SetLatency { a: String, b: String, latency: Duration },
+    /// Prevent every node in `a` from communicating with every node in `b`
+    Partition { a: Vec<String>, b: Vec<String> },
+    /// Remove all partitions
+    Heal,
+}
+
+/// A network shared by all simulated transports. Clones refer to the same network.
+#[derive(Clone, Default)]
+pub struct SimulatedNetwork {
+    state: Arc<Mutex<NetworkState>>,
+}
+
+impl SimulatedNetwork {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the latency used between nodes that do not have a specific latency set
+    pub fn with_default_latency(self, latency: Duration) -> Self {
+        self.lock().default_latency = latency;
+        self
+    }
+
+    /// Returns a transport for the node with the given label. The label is used to configure latency and partitions.
+    pub fn transport<T: Into<String>>(&self, label: T) -> SimulatedTransport {
+        SimulatedTransport {
+            label: label.into(),
+            network: self.clone(),
+        }
+    }
+
+    /// The current virtual time, measured from the creation of the network
+    pub fn now(&self) -> Duration {
+        self.lock().now
+    }
+
+    /// Returns a future that resolves once the virtual clock reaches `now + duration`
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep {
+            deadline: self.now() + duration,
+            network: self.clone(),
+        }
+    }
+
+    /// Applies an event immediately
+    pub fn apply(&self, event: NetworkEvent) {
+        let wakers = {
+            let mut state = self.lock();
+            state.apply(event);
+            state.take_wakers()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Schedules an event to be applied when the virtual clock reaches `at`
+    pub fn schedule(&self, at: Duration, event: NetworkEvent) {
+        let mut state = self.lock();
+        // Insert after any events scheduled for the same time so that events are applied in the order given
+        let pos = state
+            .schedule
+            .iter()
+            .position(|(t, _)| *t > at)
+            .unwrap_or_else(|| state.schedule.len());
+        state.schedule.insert(pos, (at, event));
+    }
+
+    /// Advances the virtual clock by `duration`, applying scheduled events in order and waking any sockets and sleeps
+    /// that became ready.
+    pub fn advance(&self, duration: Duration) {
+        let wakers = {
+            let mut state = self.lock();
+            let target = state.now + duration;
+            while state.schedule.front().filter(|(at, _)| *at <= target).is_some() {
+                let (at, event) = state.schedule.pop_front().expect("checked above");
+                state.now = cmp::max(state.now, at);
+                state.apply(event);
+            }
+            state.now = target;
+            state.take_wakers()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, NetworkState> {
+        self.state.lock().expect("simulated network lock poisoned")
+    }
+}
+
+struct NetworkState {
+    now: Duration,
+    clock_wakers: Vec<Waker>,
+    schedule: VecDeque<(Duration, NetworkEvent)>,
+    default_latency: Duration,
+    latencies: HashMap<(String, String), Duration>,
+    partitions: HashSet<(String, String)>,
+    next_port: u64,
+    listeners: HashMap<u64, (String, mpsc::UnboundedSender<SimulatedSocket>)>,
+    pipes: Vec<(String, String, Weak<Mutex<Pipe>>)>,
+}
+
+impl Default for NetworkState {
+    fn default() -> Self {
+        Self {
+            now: Duration::from_secs(0),
+            clock_wakers: Vec::new(),
+            schedule: VecDeque::new(),
+            default_latency: Duration::from_millis(0),
+            latencies: HashMap::new(),
+            partitions: HashSet::new(),
+            next_port: 1,
+            listeners: HashMap::new(),
+            pipes: Vec::new(),
+        }
+    }
+}
+
+impl NetworkState {
+    fn apply(&mut self, event: NetworkEvent) {
+        match event {
+            NetworkEvent::SetLatency { a, b, latency } => {
+                self.latencies.insert(link(&a, &b), latency);
+            },
+            NetworkEvent::Partition { a, b } => {
+                for x in &a {
+                    for y in &b {
+                        self.partitions.insert(link(x, y));
+                    }
+                }
+                let partitions = &self.partitions;
+                self.pipes.retain(|(from, to, pipe)| match pipe.upgrade() {
+                    Some(pipe) => {
+                        if partitions.contains(&link(from, to)) {
+                            lock_pipe(&pipe).sever();
+                        }
+                        true
+                    },
+                    None => false,
+                });
+            },
+            NetworkEvent::Heal => {
+                self.partitions.clear();
+            },
+        }
+    }
+
+    fn latency(&self, a: &str, b: &str) -> Duration {
+        self.latencies.get(&link(a, b)).copied().unwrap_or(self.default_latency)
+    }
+
+    fn is_partitioned(&self, a: &str, b: &str) -> bool {
+        self.partitions.contains(&link(a, b))
+    }
+
+    fn register_clock_waker(&mut self, waker: &Waker) {
+        if !self.clock_wakers.iter().any(|w| w.will_wake(waker)) {
+            self.clock_wakers.push(waker.clone());
+        }
+    }
+
+    fn take_wakers(&mut self) -> Vec<Waker> {
+        self.clock_wakers.drain(..).collect()
+    }
+}
+
+/// Links are undirected, so the key is ordered
+fn link(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+/// Future returned by [SimulatedNetwork::sleep]
+#[must_use = "futures do nothing unless polled"]
+pub struct Sleep {
+    deadline: Duration,
+    network: SimulatedNetwork,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.network.lock();
+        if state.now >= self.deadline {
+            return Poll::Ready(());
+        }
+        state.register_clock_waker(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// Transport for a single node on a [SimulatedNetwork]. Addresses have the form `/memory/<port>` but are private to
+/// the simulated network.
+#[derive(Clone)]
+pub struct SimulatedTransport {
+    label: String,
+    network: SimulatedNetwork,
+}
+
+impl SimulatedTransport {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+#[crate::async_trait]
+impl Transport for SimulatedTransport {
+    type Error = io::Error;
+    type Listener = SimulatedListener;
+    type Output = SimulatedSocket;
+
+    async fn listen(&self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), Self::Error> {
+        let port = parse_addr(&addr)?;
+        let mut state = self.network.lock();
+        let port = if port == 0 {
+            while state.listeners.contains_key(&state.next_port) {
+                state.next_port += 1;
+            }
+            state.next_port
+        } else {
+            if state.listeners.contains_key(&port) {
+                return Err(io::ErrorKind::AddrInUse.into());
+            }
+            port
+        };
+        let (tx, rx) = mpsc::unbounded();
+        state.listeners.insert(port, (self.label.clone(), tx));
+        let listener = SimulatedListener {
+            port,
+            network: self.network.clone(),
+            incoming: rx,
+        };
+        Ok((listener, Protocol::Memory(port).into()))
+    }
+
+    async fn dial(&self, addr: Multiaddr) -> Result<Self::Output, Self::Error> {
+        let port = parse_addr(&addr)?;
+        let mut state = self.network.lock();
+        let (remote, sender) = state
+            .listeners
+            .get(&port)
+            .map(|(label, sender)| (label.clone(), sender.clone()))
+            .ok_or(io::ErrorKind::AddrNotAvailable)?;
+        if state.is_partitioned(&self.label, &remote) {
+            return Err(io::ErrorKind::ConnectionRefused.into());
+        }
+
+        let outbound = Arc::new(Mutex::new(Pipe::default()));
+        let inbound = Arc::new(Mutex::new(Pipe::default()));
+        state
+            .pipes
+            .push((self.label.clone(), remote.clone(), Arc::downgrade(&outbound)));
+        state
+            .pipes
+            .push((remote.clone(), self.label.clone(), Arc::downgrade(&inbound)));
+
+        let listener_socket = SimulatedSocket {
+            local: remote.clone(),
+            remote: self.label.clone(),
+            network: self.network.clone(),
+            read_pipe: outbound.clone(),
+            write_pipe: inbound.clone(),
+        };
+        sender
+            .unbounded_send(listener_socket)
+            .map_err(|_| io::Error::from(io::ErrorKind::ConnectionRefused))?;
+
+        Ok(SimulatedSocket {
+            local: self.label.clone(),
+            remote,
+            network: self.network.clone(),
+            read_pipe: inbound,
+            write_pipe: outbound,
+        })
+    }
+}
+
+fn parse_addr(addr: &Multiaddr) -> io::Result<u64> {
+    let mut iter = addr.iter();
+    match (iter.next(), iter.next()) {
+        (Some(Protocol::Memory(port)), None) => Ok(port),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid Multiaddr '{:?}'", addr),
+        )),
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct SimulatedListener {
+    port: u64,
+    network: SimulatedNetwork,
+    incoming: mpsc::UnboundedReceiver<SimulatedSocket>,
+}
+
+impl Stream for SimulatedListener {
+    type Item = io::Result<(SimulatedSocket, Multiaddr)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.incoming).poll_next(cx) {
+            // As with the MemoryTransport, the dialer address is not dialable
+            Poll::Ready(Some(socket)) => Poll::Ready(Some(Ok((socket, Protocol::Memory(0).into())))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for SimulatedListener {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.network.state.lock() {
+            state.listeners.remove(&self.port);
+        }
+    }
+}
+
+/// One direction of a simulated connection
+#[derive(Default)]
+struct Pipe {
+    chunks: VecDeque<(Duration, Bytes)>,
+    last_delivery: Duration,
+    writer_closed: bool,
+    reader_closed: bool,
+    severed: bool,
+    reader_waker: Option<Waker>,
+}
+
+impl Pipe {
+    fn sever(&mut self) {
+        self.severed = true;
+        self.chunks.clear();
+        self.wake_reader();
+    }
+
+    fn wake_reader(&mut self) {
+        if let Some(waker) = self.reader_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+fn lock_pipe(pipe: &Mutex<Pipe>) -> MutexGuard<'_, Pipe> {
+    pipe.lock().expect("simulated pipe lock poisoned")
+}
+
+/// A connection between two nodes on a [SimulatedNetwork]
+pub struct SimulatedSocket {
+    local: String,
+    remote: String,
+    network: SimulatedNetwork,
+    read_pipe: Arc<Mutex<Pipe>>,
+    write_pipe: Arc<Mutex<Pipe>>,
+}
+
+impl fmt::Debug for SimulatedSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimulatedSocket")
+            .field("local", &self.local)
+            .field("remote", &self.remote)
+            .finish()
+    }
+}
+
+impl SimulatedSocket {
+    /// The label of the node on the other end of this connection
+    pub fn remote_label(&self) -> &str {
+        &self.remote
+    }
+}
+
+impl AsyncRead for SimulatedSocket {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        // Lock order is always network, then pipe
+        let mut state = self.network.lock();
+        let mut pipe = lock_pipe(&self.read_pipe);
+        if pipe.severed {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+
+        let mut bytes_read = 0;
+        while bytes_read < buf.len() {
+            match pipe.chunks.front_mut() {
+                Some((deliver_at, chunk)) if *deliver_at <= state.now => {
+                    let n = cmp::min(buf.len() - bytes_read, chunk.len());
+                    buf[bytes_read..bytes_read + n].copy_from_slice(&chunk[..n]);
+                    chunk.advance(n);
+                    bytes_read += n;
+                    if chunk.is_empty() {
+                        pipe.chunks.pop_front();
+                    }
+                },
+                _ => break,
+            }
+        }
+
+        if bytes_read > 0 || buf.is_empty() {
+            return Poll::Ready(Ok(bytes_read));
+        }
+        if pipe.chunks.is_empty() {
+            if pipe.writer_closed {
+                return Poll::Ready(Ok(0));
+            }
+        } else {
+            // Data is in flight, wait for the clock to reach its delivery time
+            state.register_clock_waker(cx.waker());
+        }
+        pipe.reader_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl AsyncWrite for SimulatedSocket {
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let mut state = self.network.lock();
+        let mut pipe = lock_pipe(&self.write_pipe);
+        if pipe.severed {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        if pipe.writer_closed || pipe.reader_closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        // Bytes on a connection are never reordered, even if the latency is lowered
+        let deliver_at = cmp::max(state.now + state.latency(&self.local, &self.remote), pipe.last_delivery);
+        pipe.last_delivery = deliver_at;
+        pipe.chunks.push_back((deliver_at, Bytes::copy_from_slice(buf)));
+        if deliver_at <= state.now {
+            pipe.wake_reader();
+        } else if let Some(waker) = pipe.reader_waker.as_ref() {
+            // A reader waiting on an empty pipe must be woken by the clock once these bytes are delivered
+            state.register_clock_waker(waker);
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut pipe = lock_pipe(&self.write_pipe);
+        pipe.writer_closed = true;
+        pipe.wake_reader();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for SimulatedSocket {
+    fn drop(&mut self) {
+        if let Ok(mut pipe) = self.write_pipe.lock() {
+            pipe.writer_closed = true;
+            pipe.wake_reader();
+        }
+        if let Ok(mut pipe) = self.read_pipe.lock() {
+            pipe.reader_closed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::transport::{build_simulated_connected_sockets, spawn_simulated_clock};
+    use futures::{AsyncReadExt, AsyncWriteExt, FutureExt};
+    use tokio::task;
+
+    async fn connect(network: &SimulatedNetwork) -> (SimulatedSocket, SimulatedSocket) {
+        build_simulated_connected_sockets(network, "alice", "bob").await
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_delivers_after_latency() {
+        let network = SimulatedNetwork::new().with_default_latency(Duration::from_millis(100));
+        let (mut inbound, mut outbound) = connect(&network).await;
+        assert_eq!(inbound.remote_label(), "bob");
+
+        let mut buf = [0u8; 5];
+        let read = task::spawn(async move {
+            let mut buf = [0u8; 2];
+            outbound.read_exact(&mut buf).await.unwrap();
+            (outbound, buf)
+        });
+        task::yield_now().await;
+        inbound.write_all(b"hi").await.unwrap();
+        network.advance(Duration::from_millis(100));
+        let (mut outbound, reply) = read.await.unwrap();
+        assert_eq!(&reply, b"hi");
+
+        outbound.write_all(b"hello").await.unwrap();
+        assert!(inbound.read(&mut buf).now_or_never().is_none());
+
+        network.advance(Duration::from_millis(99));
+        assert!(inbound.read(&mut buf).now_or_never().is_none());
+
+        network.advance(Duration::from_millis(1));
+        inbound.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+
+        let sleep = network.sleep(Duration::from_secs(1));
+        futures::pin_mut!(sleep);
+        assert!(sleep.as_mut().now_or_never().is_none());
+        network.advance(Duration::from_secs(1));
+        sleep.await;
+        assert_eq!(network.now(), Duration::from_millis(1200));
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_applies_scheduled_partitions() {
+        let network = SimulatedNetwork::new();
+        let (mut inbound, mut outbound) = connect(&network).await;
+        let (_listener, addr) = network
+            .transport("alice")
+            .listen("/memory/0".parse().unwrap())
+            .await
+            .unwrap();
+
+        network.schedule(Duration::from_secs(10), NetworkEvent::Partition {
+            a: vec!["alice".to_string()],
+            b: vec!["bob".to_string()],
+        });
+        network.schedule(Duration::from_secs(20), NetworkEvent::Heal);
+
+        network.advance(Duration::from_secs(10));
+        let err = outbound.write_all(b"hello").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+        let err = inbound.read(&mut [0u8; 1]).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+        let err = network.transport("bob").dial(addr.clone()).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        // Nodes on the same side of the partition are unaffected
+        network.transport("alice").dial(addr.clone()).await.unwrap();
+
+        network.advance(Duration::from_secs(10));
+        network.transport("bob").dial(addr).await.unwrap();
+    }
+
+    #[tokio_macros::test_basic]
+    async fn it_drives_tokio_timers_with_the_virtual_clock() {
+        let network = SimulatedNetwork::new();
+        spawn_simulated_clock(network.clone(), Duration::from_millis(100));
+        let start = tokio::time::Instant::now();
+        let real_start = std::time::Instant::now();
+
+        tokio::time::delay_for(Duration::from_secs(60)).await;
+        assert!(network.now() >= Duration::from_secs(60));
+        assert_eq!(start.elapsed(), network.now());
+        assert!(real_start.elapsed() < Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Mapped diagnostic context (MDC) fields for futures. The MDC is thread-local, so a field inserted inside an async
+//! task would leak into other tasks polled on the same thread. `with_log_field` instead sets the field only while the
+//! wrapped future is being polled, so that every log record it emits carries the field (e.g. `peer_id` or `tx_id`).
+
+use futures::Future;
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+pub trait LogFieldExt: Future + Sized {
+    /// Sets the MDC field `key` to `value` whenever this future is polled
+    fn with_log_field<V: ToString>(self, key: &'static str, value: V) -> WithLogField<Self> {
+        WithLogField {
+            inner: self,
+            key,
+            value: value.to_string(),
+        }
+    }
+}
+
+impl<F: Future> LogFieldExt for F {}
+
+#[pin_project]
+pub struct WithLogField<F> {
+    #[pin]
+    inner: F,
+    key: &'static str,
+    value: String,
+}
+
+impl<F: Future> Future for WithLogField<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        // Restores the previous value of the field when dropped
+        let _guard = log_mdc::insert_scoped(*this.key, this.value.as_str());
+        this.inner.poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::future;
+
+    fn get_field(key: &str) -> Option<String> {
+        log_mdc::get(key, |v| v.map(ToString::to_string))
+    }
+
+    #[test]
+    fn it_sets_the_field_only_while_polling() {
+        let fut = future::lazy(|_| get_field("tx_id")).with_log_field("tx_id", 123);
+        assert_eq!(futures::executor::block_on(fut), Some("123".to_string()));
+        assert!(get_field("tx_id").is_none());
+    }
+
+    #[test]
+    fn it_restores_an_outer_field() {
+        let inner = future::lazy(|_| get_field("peer_id")).with_log_field("peer_id", "inner");
+        let outer = async move {
+            let inner_value = inner.await;
+            (inner_value, get_field("peer_id"))
+        }
+        .with_log_field("peer_id", "outer");
+        let (inner_value, outer_value) = futures::executor::block_on(outer);
+        assert_eq!(inner_value.unwrap(), "inner");
+        assert_eq!(outer_value.unwrap(), "outer");
+    }
+}
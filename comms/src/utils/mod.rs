@@ -22,5 +22,6 @@
 
 pub mod cidr;
 pub mod datetime;
+pub mod log_fields;
 pub mod multiaddr;
 pub mod signature;
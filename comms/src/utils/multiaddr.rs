@@ -77,6 +77,40 @@ pub fn socketaddr_to_multiaddr(socket_addr: &SocketAddr) -> Multiaddr {
     addr
 }
 
+/// Returns the network group of the IP address in the given multiaddr. The network group is the /16 prefix of an IPv4
+/// address or the /32 prefix of an IPv6 address, and is used to determine whether peers are likely to be operated from
+/// the same network. `None` is returned for addresses that do not contain an IP address (e.g. onion or DNS addresses).
+pub fn network_group(addr: &Multiaddr) -> Option<Vec<u8>> {
+    match addr.iter().next()? {
+        Protocol::Ip4(ip) => {
+            let octets = ip.octets();
+            Some(vec![4, octets[0], octets[1]])
+        },
+        Protocol::Ip6(ip) => {
+            let octets = ip.octets();
+            Some(vec![6, octets[0], octets[1], octets[2], octets[3]])
+        },
+        _ => None,
+    }
+}
+
+/// Returns the network group of the given multiaddr if it is a publicly routable IP address. Loopback, private,
+/// link-local and unspecified addresses say nothing about where a peer is operated from (e.g. inbound connections
+/// through a Tor proxy come from 127.0.0.1), so `None` is returned for these as well as for non-IP addresses.
+pub fn routable_network_group(addr: &Multiaddr) -> Option<Vec<u8>> {
+    /// Returns [true] if the address is a unique local (fc00::/7) or unicast link-local (fe80::/10) address.
+    #[inline]
+    const fn is_local_ipv6(segments: [u16; 8]) -> bool {
+        (segments[0] & 0xfe00) == 0xfc00 || (segments[0] & 0xffc0) == 0xfe80
+    }
+
+    match addr.iter().next()? {
+        Protocol::Ip4(ip) if ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() => None,
+        Protocol::Ip6(ip) if ip.is_loopback() || ip.is_unspecified() || is_local_ipv6(ip.segments()) => None,
+        _ => network_group(addr),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -117,6 +151,47 @@ mod test {
         expect_fail("/dns4/doesntexist.theresnotldlikethis/tcp/1234")
     }
 
+    #[test]
+    fn network_group() {
+        fn group_of(addr: &str) -> Option<Vec<u8>> {
+            super::network_group(&Multiaddr::from_str(addr).unwrap())
+        }
+
+        assert_eq!(group_of("/ip4/10.1.2.3/tcp/1234"), group_of("/ip4/10.1.200.1/tcp/4321"));
+        assert_ne!(group_of("/ip4/10.1.2.3/tcp/1234"), group_of("/ip4/10.2.2.3/tcp/1234"));
+        assert_eq!(
+            group_of("/ip6/2001:db8::1/tcp/1234"),
+            group_of("/ip6/2001:db8:ff::2/tcp/1234")
+        );
+        assert_ne!(
+            group_of("/ip6/2001:db8::1/tcp/1234"),
+            group_of("/ip6/2001:db9::1/tcp/1234")
+        );
+        assert!(group_of("/dns4/localhost/tcp/1234").is_none());
+    }
+
+    #[test]
+    fn routable_network_group() {
+        fn group_of(addr: &str) -> Option<Vec<u8>> {
+            super::routable_network_group(&Multiaddr::from_str(addr).unwrap())
+        }
+
+        assert_eq!(group_of("/ip4/8.8.8.8/tcp/1234"), Some(vec![4, 8, 8]));
+        assert_eq!(
+            group_of("/ip6/2001:db8::1/tcp/1234"),
+            Some(vec![6, 0x20, 0x01, 0x0d, 0xb8])
+        );
+        assert!(group_of("/ip4/127.0.0.1/tcp/1234").is_none());
+        assert!(group_of("/ip4/10.1.2.3/tcp/1234").is_none());
+        assert!(group_of("/ip4/192.168.1.1/tcp/1234").is_none());
+        assert!(group_of("/ip4/169.254.1.1/tcp/1234").is_none());
+        assert!(group_of("/ip4/0.0.0.0/tcp/1234").is_none());
+        assert!(group_of("/ip6/::1/tcp/1234").is_none());
+        assert!(group_of("/ip6/fe80::1/tcp/1234").is_none());
+        assert!(group_of("/ip6/fd00::1/tcp/1234").is_none());
+        assert!(group_of("/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234").is_none());
+    }
+
     #[test]
     fn multiaddr_from_components() {
         let ip: Ipv4Addr = "127.0.0.1".parse().unwrap();
@@ -0,0 +1,129 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Tari metrics
+//!
+//! Registers metrics in a single default registry so that every part of an application reports to the same place.
+//! Enable the `server` feature to expose the registry to Prometheus over HTTP.
+
+mod error;
+pub use error::MetricsError;
+
+#[cfg(feature = "server")]
+pub mod server;
+
+pub use prometheus::{
+    Histogram,
+    HistogramTimer,
+    HistogramVec,
+    IntCounter,
+    IntCounterVec,
+    IntGauge,
+    IntGaugeVec,
+    Registry,
+};
+
+use lazy_static::lazy_static;
+use prometheus::{HistogramOpts, Opts};
+
+lazy_static! {
+    static ref DEFAULT_REGISTRY: Registry =
+        Registry::new_custom(Some("tari".to_string()), None).expect("the 'tari' metric prefix is valid");
+}
+
+/// Returns the registry that all metrics are registered in
+pub fn get_default_registry() -> &'static Registry {
+    &DEFAULT_REGISTRY
+}
+
+pub fn register_int_gauge(name: &str, help: &str) -> Result<IntGauge, MetricsError> {
+    let gauge = IntGauge::new(name, help)?;
+    DEFAULT_REGISTRY.register(Box::new(gauge.clone()))?;
+    Ok(gauge)
+}
+
+pub fn register_int_gauge_vec(name: &str, help: &str, label_names: &[&str]) -> Result<IntGaugeVec, MetricsError> {
+    let gauge = IntGaugeVec::new(Opts::new(name, help), label_names)?;
+    DEFAULT_REGISTRY.register(Box::new(gauge.clone()))?;
+    Ok(gauge)
+}
+
+pub fn register_int_counter(name: &str, help: &str) -> Result<IntCounter, MetricsError> {
+    let counter = IntCounter::new(name, help)?;
+    DEFAULT_REGISTRY.register(Box::new(counter.clone()))?;
+    Ok(counter)
+}
+
+pub fn register_int_counter_vec(name: &str, help: &str, label_names: &[&str]) -> Result<IntCounterVec, MetricsError> {
+    let counter = IntCounterVec::new(Opts::new(name, help), label_names)?;
+    DEFAULT_REGISTRY.register(Box::new(counter.clone()))?;
+    Ok(counter)
+}
+
+/// Registers a histogram. If `buckets` is `None`, the prometheus default buckets, which suit durations in seconds,
+/// are used.
+pub fn register_histogram(name: &str, help: &str, buckets: Option<Vec<f64>>) -> Result<Histogram, MetricsError> {
+    let histogram = Histogram::with_opts(histogram_opts(name, help, buckets))?;
+    DEFAULT_REGISTRY.register(Box::new(histogram.clone()))?;
+    Ok(histogram)
+}
+
+pub fn register_histogram_vec(
+    name: &str,
+    help: &str,
+    label_names: &[&str],
+    buckets: Option<Vec<f64>>,
+) -> Result<HistogramVec, MetricsError> {
+    let histogram = HistogramVec::new(histogram_opts(name, help, buckets), label_names)?;
+    DEFAULT_REGISTRY.register(Box::new(histogram.clone()))?;
+    Ok(histogram)
+}
+
+fn histogram_opts(name: &str, help: &str, buckets: Option<Vec<f64>>) -> HistogramOpts {
+    let opts = HistogramOpts::new(name, help);
+    match buckets {
+        Some(buckets) => opts.buckets(buckets),
+        None => opts,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_registers_metrics_in_the_default_registry() {
+        let gauge = register_int_gauge("test_gauge", "A test gauge").unwrap();
+        gauge.set(5);
+        let counter = register_int_counter_vec("test_counter", "A test counter", &["label"]).unwrap();
+        counter.with_label_values(&["a"]).inc();
+
+        let families = get_default_registry().gather();
+        let gauge_family = families.iter().find(|f| f.get_name() == "tari_test_gauge").unwrap();
+        assert_eq!(gauge_family.get_metric()[0].get_gauge().get_value() as i64, 5);
+        assert!(families.iter().any(|f| f.get_name() == "tari_test_counter"));
+
+        // The same name cannot be registered twice
+        let err = register_int_gauge("test_gauge", "A test gauge").unwrap_err();
+        assert!(matches!(err, MetricsError::Prometheus(_)));
+    }
+}
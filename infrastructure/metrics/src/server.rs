@@ -0,0 +1,76 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A minimal HTTP server that serves the default registry in the Prometheus text format at `/metrics`
+
+use crate::{get_default_registry, MetricsError};
+use futures::FutureExt;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body,
+    Request,
+    Response,
+    Server,
+    StatusCode,
+};
+use log::*;
+use prometheus::{Encoder, TextEncoder};
+use std::{convert::Infallible, net::SocketAddr};
+use tari_shutdown::ShutdownSignal;
+
+const LOG_TARGET: &str = "metrics::server";
+
+/// Serves metrics on `address` until the shutdown signal is triggered
+pub async fn start(address: SocketAddr, shutdown_signal: ShutdownSignal) -> Result<(), MetricsError> {
+    let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle_request)) });
+    let server = Server::try_bind(&address)?.serve(make_service);
+    info!(target: LOG_TARGET, "Metrics server listening on {}", address);
+    server.with_graceful_shutdown(shutdown_signal.map(|_| ())).await?;
+    info!(target: LOG_TARGET, "Metrics server has shut down");
+    Ok(())
+}
+
+async fn handle_request(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() != "/metrics" {
+        return Ok(status_response(StatusCode::NOT_FOUND));
+    }
+
+    let encoder = TextEncoder::new();
+    let mut buf = Vec::new();
+    if let Err(err) = encoder.encode(&get_default_registry().gather(), &mut buf) {
+        error!(target: LOG_TARGET, "Failed to encode metrics: {}", err);
+        return Ok(status_response(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+
+    let response = Response::builder()
+        .header(CONTENT_TYPE, encoder.format_type())
+        .body(Body::from(buf))
+        .unwrap_or_else(|_| status_response(StatusCode::INTERNAL_SERVER_ERROR));
+    Ok(response)
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
@@ -26,6 +26,16 @@
 #![deny(unused_must_use)]
 #![deny(unreachable_patterns)]
 #![deny(unknown_lints)]
+
+mod orchestrator;
+pub use orchestrator::{
+    ServiceShutdownOutcome,
+    ServiceShutdownResult,
+    ShutdownOrchestrator,
+    ShutdownReport,
+    ShutdownStage,
+};
+
 use futures::{
     channel::{oneshot, oneshot::Canceled},
     future::{Fuse, FusedFuture, Shared},
@@ -0,0 +1,288 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Stops an application's services in dependency order, e.g. RPC servers before the protocols they call into, and
+//! protocols before the connection manager and storage they depend on. Each service is given a timeout to stop in,
+//! and the outcome for every service is collected into a [ShutdownReport].
+
+use crate::{Shutdown, ShutdownSignal};
+use futures::future::{self, BoxFuture, FutureExt};
+use std::{
+    fmt,
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::time;
+
+/// The stages of a shutdown, in the order they are stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShutdownStage {
+    /// Servers that accept requests from outside of the node, e.g. gRPC
+    RpcServers,
+    /// Protocols and services that act on messages from peers
+    Protocols,
+    /// The connection manager and the rest of the comms stack
+    ConnectionManager,
+    /// Flushing pending writes to storage
+    Storage,
+}
+
+impl ShutdownStage {
+    /// All stages in shutdown order
+    pub const ALL: [ShutdownStage; 4] = [
+        ShutdownStage::RpcServers,
+        ShutdownStage::Protocols,
+        ShutdownStage::ConnectionManager,
+        ShutdownStage::Storage,
+    ];
+}
+
+impl fmt::Display for ShutdownStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownStage::RpcServers => write!(f, "RPC servers"),
+            ShutdownStage::Protocols => write!(f, "protocols"),
+            ShutdownStage::ConnectionManager => write!(f, "connection manager"),
+            ShutdownStage::Storage => write!(f, "storage"),
+        }
+    }
+}
+
+enum Stopper {
+    /// The service is signalled to stop and reports that it has stopped by triggering (or dropping) its `Shutdown`
+    Signalled { trigger: Shutdown, stopped: ShutdownSignal },
+    /// A task that is run to completion, e.g. flushing writes to disk
+    Task(Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>),
+}
+
+struct RegisteredService {
+    name: String,
+    stage: ShutdownStage,
+    timeout: Duration,
+    stopper: Stopper,
+}
+
+/// Stops registered services stage by stage, see [ShutdownStage]. Services in the same stage are stopped
+/// concurrently, and the next stage begins once they have all stopped or timed out.
+///
+/// _Note_: All registered services are signalled to stop if the orchestrator is dropped without calling `shutdown`.
+#[derive(Default)]
+pub struct ShutdownOrchestrator {
+    services: Vec<RegisteredService>,
+}
+
+impl ShutdownOrchestrator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a service that is stopped in the given stage. The service should stop once the returned signal
+    /// resolves, and trigger or drop the returned `Shutdown` once it has stopped.
+    pub fn register<S: Into<String>>(
+        &mut self,
+        name: S,
+        stage: ShutdownStage,
+        timeout: Duration,
+    ) -> (ShutdownSignal, Shutdown) {
+        let trigger = Shutdown::new();
+        let signal = trigger.to_signal();
+        let stopped = Shutdown::new();
+        self.services.push(RegisteredService {
+            name: name.into(),
+            stage,
+            timeout,
+            stopper: Stopper::Signalled {
+                trigger,
+                stopped: stopped.to_signal(),
+            },
+        });
+        (signal, stopped)
+    }
+
+    /// Registers a task that is run in the given stage, e.g. flushing pending writes to storage
+    pub fn register_task<S, F, Fut>(&mut self, name: S, stage: ShutdownStage, timeout: Duration, task: F)
+    where
+        S: Into<String>,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.services.push(RegisteredService {
+            name: name.into(),
+            stage,
+            timeout,
+            stopper: Stopper::Task(Box::new(move || task().boxed())),
+        });
+    }
+
+    /// Stops all registered services in stage order and returns a report of the outcome for each service
+    pub async fn shutdown(self) -> ShutdownReport {
+        let mut remaining = self.services;
+        let mut results = Vec::with_capacity(remaining.len());
+        for stage in ShutdownStage::ALL.iter() {
+            let (services, rest) = remaining.into_iter().partition::<Vec<_>, _>(|s| s.stage == *stage);
+            remaining = rest;
+            results.extend(future::join_all(services.into_iter().map(Self::stop_service)).await);
+        }
+        ShutdownReport { results }
+    }
+
+    async fn stop_service(service: RegisteredService) -> ServiceShutdownResult {
+        let timer = Instant::now();
+        let is_stopped = match service.stopper {
+            Stopper::Signalled { mut trigger, stopped } => {
+                let _ = trigger.trigger();
+                time::timeout(service.timeout, stopped).await.is_ok()
+            },
+            Stopper::Task(task) => time::timeout(service.timeout, task()).await.is_ok(),
+        };
+
+        ServiceShutdownResult {
+            name: service.name,
+            stage: service.stage,
+            elapsed: timer.elapsed(),
+            outcome: if is_stopped {
+                ServiceShutdownOutcome::Stopped
+            } else {
+                ServiceShutdownOutcome::TimedOut
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceShutdownOutcome {
+    Stopped,
+    TimedOut,
+}
+
+/// The outcome of stopping a single service
+#[derive(Debug, Clone)]
+pub struct ServiceShutdownResult {
+    pub name: String,
+    pub stage: ShutdownStage,
+    pub elapsed: Duration,
+    pub outcome: ServiceShutdownOutcome,
+}
+
+/// The outcome of stopping every registered service, in the order they were stopped
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    pub results: Vec<ServiceShutdownResult>,
+}
+
+impl ShutdownReport {
+    /// Returns true if every service stopped within its timeout
+    pub fn is_clean(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    /// Returns the services that did not stop within their timeout
+    pub fn failed(&self) -> impl Iterator<Item = &ServiceShutdownResult> {
+        self.results
+            .iter()
+            .filter(|r| r.outcome != ServiceShutdownOutcome::Stopped)
+    }
+}
+
+impl fmt::Display for ShutdownReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "All {} service(s) stopped cleanly", self.results.len());
+        }
+        write!(
+            f,
+            "{} of {} service(s) failed to stop cleanly:",
+            self.failed().count(),
+            self.results.len()
+        )?;
+        for result in self.failed() {
+            write!(
+                f,
+                " '{}' ({}) timed out after {:.2?};",
+                result.name, result.stage, result.elapsed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn it_stops_services_in_stage_order() {
+        let mut rt = Runtime::new().unwrap();
+        let stopped = Arc::new(Mutex::new(Vec::new()));
+        let mut orchestrator = ShutdownOrchestrator::new();
+
+        let stopped_clone = stopped.clone();
+        orchestrator.register_task(
+            "storage",
+            ShutdownStage::Storage,
+            Duration::from_secs(1),
+            move || async move {
+                stopped_clone.lock().unwrap().push("storage");
+            },
+        );
+        for (name, stage) in &[
+            ("comms", ShutdownStage::ConnectionManager),
+            ("grpc", ShutdownStage::RpcServers),
+            ("protocols", ShutdownStage::Protocols),
+        ] {
+            let (signal, stopped_trigger) = orchestrator.register(*name, *stage, Duration::from_secs(1));
+            let stopped = stopped.clone();
+            let name = *name;
+            rt.spawn(async move {
+                signal.await.unwrap();
+                stopped.lock().unwrap().push(name);
+                drop(stopped_trigger);
+            });
+        }
+
+        let report = rt.block_on(orchestrator.shutdown());
+        assert!(report.is_clean());
+        assert_eq!(*stopped.lock().unwrap(), vec!["grpc", "protocols", "comms", "storage"]);
+        let names = report.results.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["grpc", "protocols", "comms", "storage"]);
+    }
+
+    #[test]
+    fn it_reports_services_that_time_out() {
+        let mut rt = Runtime::new().unwrap();
+        let mut orchestrator = ShutdownOrchestrator::new();
+        // The stopped trigger is held so the service never reports that it stopped
+        let (_signal, _stopped) = orchestrator.register("stuck", ShutdownStage::Protocols, Duration::from_millis(10));
+        orchestrator.register_task("flush", ShutdownStage::Storage, Duration::from_secs(1), || async {});
+
+        let report = rt.block_on(orchestrator.shutdown());
+        assert!(!report.is_clean());
+        let failed = report.failed().collect::<Vec<_>>();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].name, "stuck");
+        assert_eq!(failed[0].outcome, ServiceShutdownOutcome::TimedOut);
+        // Later stages still run after a timeout
+        assert_eq!(report.results[1].outcome, ServiceShutdownOutcome::Stopped);
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
     lmdb_store::error::LMDBError,
 };
 use lmdb_zero::{
+    copy,
     db,
     error::{self, LmdbResultExt},
     open,
@@ -39,6 +40,71 @@ const BYTES_PER_MB: usize = 1024 * 1024;
 /// An atomic pointer to an LMDB database instance
 type DatabaseRef = Arc<Database<'static>>;
 
+/// Size information for a single database within an LMDB environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LMDBDatabaseStats {
+    pub name: String,
+    pub entries: usize,
+    pub depth: u32,
+    pub branch_pages: usize,
+    pub leaf_pages: usize,
+    pub overflow_pages: usize,
+    /// The number of bytes in use by the database's pages
+    pub size_bytes: usize,
+}
+
+impl LMDBDatabaseStats {
+    fn from_stat(name: &str, stat: &Stat) -> Self {
+        Self {
+            name: name.to_string(),
+            entries: stat.entries,
+            depth: stat.depth,
+            branch_pages: stat.branch_pages,
+            leaf_pages: stat.leaf_pages,
+            overflow_pages: stat.overflow_pages,
+            size_bytes: stat.psize as usize * Self::total_pages(stat),
+        }
+    }
+
+    fn total_pages(stat: &Stat) -> usize {
+        stat.branch_pages + stat.leaf_pages + stat.overflow_pages
+    }
+}
+
+/// Size and map usage information for an LMDB environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LMDBStoreStats {
+    /// The size of the memory map, i.e. the maximum size the data file can currently grow to
+    pub map_size_bytes: usize,
+    pub page_size: u32,
+    /// The number of pages in the data file that have been used
+    pub used_pages: usize,
+    /// The number of used pages that are not referenced by any database. These are pages on the free list, which are
+    /// reused by later writes but are only returned to the file system by compaction.
+    pub free_pages: usize,
+    pub databases: Vec<LMDBDatabaseStats>,
+}
+
+impl LMDBStoreStats {
+    /// The number of bytes of the data file in use
+    pub fn used_bytes(&self) -> usize {
+        self.used_pages * self.page_size as usize
+    }
+
+    /// The number of bytes that compaction could reclaim
+    pub fn free_bytes(&self) -> usize {
+        self.free_pages * self.page_size as usize
+    }
+
+    /// The percentage of the memory map that is in use
+    pub fn map_usage_percent(&self) -> f64 {
+        if self.map_size_bytes == 0 {
+            return 0.0;
+        }
+        self.used_bytes() as f64 * 100.0 / self.map_size_bytes as f64
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LMDBConfig {
     init_size_bytes: usize,
@@ -393,6 +459,54 @@ impl LMDBStore {
         self.env.clone()
     }
 
+    /// Returns the map usage of the environment and the size of each database in it.
+    pub fn get_stats(&self) -> Result<LMDBStoreStats, LMDBError> {
+        let mut databases = self.databases.values().collect::<Vec<_>>();
+        databases.sort_by(|a, b| a.name.cmp(&b.name));
+        Self::get_env_stats(&self.env, databases.iter().map(|db| (db.name.as_str(), &*db.db)))
+    }
+
+    /// Returns the map usage of the environment and the size of each of the given databases. Pages that are not
+    /// referenced by the given databases, the main database or the two meta pages are counted as free pages, so all
+    /// databases in the environment must be given.
+    pub fn get_env_stats<'a, I>(env: &Environment, databases: I) -> Result<LMDBStoreStats, LMDBError>
+    where I: IntoIterator<Item = (&'a str, &'a Database<'static>)> {
+        let env_info = env.info()?;
+        let main_stat = env.stat()?;
+        let txn = ReadTransaction::new(env)?;
+        let databases = databases
+            .into_iter()
+            .map(|(name, db)| txn.db_stat(db).map(|stat| LMDBDatabaseStats::from_stat(name, &stat)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Page numbers start at zero and pages 0 and 1 are the meta pages
+        let used_pages = env_info.last_pgno + 1;
+        let referenced_pages = 2 +
+            LMDBDatabaseStats::total_pages(&main_stat) +
+            databases
+                .iter()
+                .map(|db| db.size_bytes / main_stat.psize as usize)
+                .sum::<usize>();
+
+        Ok(LMDBStoreStats {
+            map_size_bytes: env_info.mapsize,
+            page_size: main_stat.psize,
+            used_pages,
+            free_pages: used_pages.saturating_sub(referenced_pages),
+            databases,
+        })
+    }
+
+    /// Writes a compacted copy of the environment to `dest`, which must be an existing, empty directory. Free pages are
+    /// omitted and the pages of each database are written sequentially. The copy is made within a read transaction,
+    /// so the environment can continue to be read from and written to while it runs, but writes made after it has
+    /// started are not included in the copy.
+    pub fn copy_compacted<P: AsRef<Path>>(env: &Environment, dest: P) -> Result<(), LMDBError> {
+        let dest = dest.as_ref().to_str().ok_or(LMDBError::InvalidPath)?;
+        env.copy(dest, copy::COMPACT)?;
+        Ok(())
+    }
+
     /// Resize the LMDB environment if the resize threshold is breached.
     pub fn resize_if_required(env: &Environment, config: &LMDBConfig) -> Result<(), LMDBError> {
         let env_info = env.info()?;
@@ -702,9 +816,9 @@ impl<'txn, 'db: 'txn> LMDBWriteTransaction<'txn, 'db> {
 
 #[cfg(test)]
 mod test {
-    use crate::lmdb_store::{LMDBBuilder, LMDBConfig};
+    use crate::lmdb_store::{LMDBBuilder, LMDBConfig, LMDBStore};
     use lmdb_zero::db;
-    use std::env;
+    use std::{env, fs};
 
     #[test]
     fn test_lmdb_builder() {
@@ -718,4 +832,52 @@ mod test {
             .unwrap();
         assert_eq!(store.databases.len(), 2);
     }
+
+    #[test]
+    fn test_stats_and_compaction() {
+        let path = env::temp_dir().join("lmdb_store_test_stats_and_compaction");
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        let store = LMDBBuilder::new()
+            .set_path(&path)
+            .set_env_config(LMDBConfig::default())
+            .add_database("db1", db::CREATE)
+            .add_database("db2", db::CREATE)
+            .build()
+            .unwrap();
+        let db1 = store.get_handle("db1").unwrap();
+        for i in 0u64..1000 {
+            db1.insert(&i.to_le_bytes(), &vec![0u8; 512]).unwrap();
+        }
+        for i in 0u64..900 {
+            db1.remove(&i.to_le_bytes()).unwrap();
+        }
+
+        let stats = store.get_stats().unwrap();
+        assert_eq!(stats.databases.len(), 2);
+        assert_eq!(stats.databases[0].name, "db1");
+        assert_eq!(stats.databases[0].entries, 100);
+        assert_eq!(stats.databases[1].entries, 0);
+        assert!(stats.free_pages > 0);
+        assert!(stats.map_usage_percent() > 0.0);
+
+        let compacted_path = path.join("compacted");
+        fs::create_dir_all(&compacted_path).unwrap();
+        LMDBStore::copy_compacted(&store.env(), &compacted_path).unwrap();
+        let original_size = fs::metadata(path.join("data.mdb")).unwrap().len();
+        let compacted_size = fs::metadata(compacted_path.join("data.mdb")).unwrap().len();
+        assert!(compacted_size < original_size);
+
+        let compacted = LMDBBuilder::new()
+            .set_path(&compacted_path)
+            .set_env_config(LMDBConfig::default())
+            .add_database("db1", db::CREATE)
+            .add_database("db2", db::CREATE)
+            .build()
+            .unwrap();
+        let compacted_stats = compacted.get_stats().unwrap();
+        assert_eq!(compacted_stats.databases[0].entries, 100);
+        assert!(compacted_stats.free_pages < stats.free_pages);
+        let _ = fs::remove_dir_all(&path);
+    }
 }
@@ -79,6 +79,7 @@ function baseEnvs(peerSeeds = []) {
     TARI_BASE_NODE__LOCALNET__CONSOLE_WALLET_TOR_IDENTITY_FILE: "none.json",
     TARI_BASE_NODE__LOCALNET__ALLOW_TEST_ADDRESSES: true,
     TARI_BASE_NODE__LOCALNET__GRPC_ENABLED: true,
+    TARI_BASE_NODE__LOCALNET__GRPC_ALLOW_UNAUTHENTICATED: true,
     TARI_BASE_NODE__LOCALNET__ENABLE_WALLET: false,
     TARI_BASE_NODE__LOCALNET__DNS_SEEDS_NAME_SERVER: "1.1.1.1:53",
     TARI_BASE_NODE__LOCALNET__DNS_SEEDS_USE_DNSSEC: "false",